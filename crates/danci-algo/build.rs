@@ -0,0 +1,29 @@
+//! 把构建时的 git 提交号写入 `DANCI_ALGO_GIT_HASH`，供 `build_info` 使用。
+//!
+//! 优先取同名环境变量（容器构建时没有 .git 目录），其次 `git rev-parse`，都拿不到时为 "unknown"。
+
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DANCI_ALGO_GIT_HASH");
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        println!("cargo:rerun-if-changed={git_dir}/refs");
+    }
+
+    let hash = std::env::var("DANCI_ALGO_GIT_HASH")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| git(&["rev-parse", "--short=12", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=DANCI_ALGO_GIT_HASH={hash}");
+}
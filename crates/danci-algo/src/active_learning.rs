@@ -0,0 +1,343 @@
+//! 主动学习选词：按能力后验方差的期望减少量挑词
+//!
+//! 新用户最初几次会话的目标是尽快摸清能力，而不是立即收益最大。作答模型与
+//! 分级测试一致，为 Rasch（1PL）：P(正确 | θ, β) = σ(θ − β)。能力 θ 的后验
+//! 放在 [-4, 4] 网格上，对每个候选词计算
+//!
+//! ```text
+//! gain(β) = Var(θ) − [P(正确)·Var(θ | 正确) + P(错误)·Var(θ | 错误)]
+//! ```
+//!
+//! 即作答后后验方差的期望减少量。会话编排配置中的 `selection` 决定前 N 次
+//! 会话是否先按该得分重排候选，再交给约束编排器；超出 N 次后回到原优先级。
+
+use serde::{Deserialize, Serialize};
+
+use danci_native::{
+    ComposedSession, PlacementResponse, PolicyRule, PolicyRuleSet, PolicyValidation, SessionItem,
+};
+
+const GRID_MIN: f64 = -4.0;
+const GRID_MAX: f64 = 4.0;
+const GRID_POINTS: usize = 161;
+
+/// 难度 [0, 1] 换算 β 时的截断，避免 logit 发散
+const DIFFICULTY_EPS: f64 = 0.01;
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn grid_point(i: usize) -> f64 {
+    GRID_MIN + (GRID_MAX - GRID_MIN) * i as f64 / (GRID_POINTS - 1) as f64
+}
+
+/// 词难度 [0, 1] 换算为 IRT 难度 β
+///
+/// 与难度标定 `difficulty = σ(β)` 互逆，即 θ = 0 的学习者答错的概率。
+pub fn difficulty_to_beta(difficulty: f64) -> f64 {
+    let d = difficulty.clamp(DIFFICULTY_EPS, 1.0 - DIFFICULTY_EPS);
+    (d / (1.0 - d)).ln()
+}
+
+/// 网格上的能力后验
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbilityPosterior {
+    weights: Vec<f64>,
+}
+
+impl AbilityPosterior {
+    /// 正态先验 N(mean, sd²)；sd 非正时取 1
+    pub fn new(mean: f64, sd: f64) -> Self {
+        let sd = if sd > 0.0 && sd.is_finite() { sd } else { 1.0 };
+        let mean = if mean.is_finite() { mean } else { 0.0 };
+        let weights = (0..GRID_POINTS)
+            .map(|i| {
+                let z = (grid_point(i) - mean) / sd;
+                (-0.5 * z * z).exp()
+            })
+            .collect();
+        let mut posterior = Self { weights };
+        posterior.normalize();
+        posterior
+    }
+
+    /// 先验加上已有作答（例如分级测试的记录）
+    pub fn from_responses(mean: f64, sd: f64, responses: &[PlacementResponse]) -> Self {
+        let mut posterior = Self::new(mean, sd);
+        for r in responses {
+            posterior.update(r.beta, r.correct);
+        }
+        posterior
+    }
+
+    /// 记录一次作答；β 非有限时忽略
+    pub fn update(&mut self, beta: f64, correct: bool) {
+        if !beta.is_finite() {
+            return;
+        }
+        self.weights = self.conditioned(beta, correct);
+    }
+
+    pub fn mean(&self) -> f64 {
+        Self::mean_of(&self.weights)
+    }
+
+    pub fn variance(&self) -> f64 {
+        Self::variance_of(&self.weights)
+    }
+
+    /// 作答正确的后验预测概率
+    pub fn p_correct(&self, beta: f64) -> f64 {
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| w * sigmoid(grid_point(i) - beta))
+            .sum()
+    }
+
+    /// 作答 β 词后后验方差的期望减少量（非负）
+    pub fn expected_variance_reduction(&self, beta: f64) -> f64 {
+        if !beta.is_finite() {
+            return 0.0;
+        }
+        let p = self.p_correct(beta);
+        let after_correct = Self::variance_of(&self.conditioned(beta, true));
+        let after_wrong = Self::variance_of(&self.conditioned(beta, false));
+        (self.variance() - (p * after_correct + (1.0 - p) * after_wrong)).max(0.0)
+    }
+
+    fn conditioned(&self, beta: f64, correct: bool) -> Vec<f64> {
+        let mut weights: Vec<f64> = self
+            .weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let p = sigmoid(grid_point(i) - beta);
+                w * if correct { p } else { 1.0 - p }
+            })
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 && total.is_finite() {
+            weights.iter_mut().for_each(|w| *w /= total);
+            weights
+        } else {
+            self.weights.clone()
+        }
+    }
+
+    fn normalize(&mut self) {
+        let total: f64 = self.weights.iter().sum();
+        if total > 0.0 {
+            self.weights.iter_mut().for_each(|w| *w /= total);
+        }
+    }
+
+    fn mean_of(weights: &[f64]) -> f64 {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| grid_point(i) * w)
+            .sum()
+    }
+
+    fn variance_of(weights: &[f64]) -> f64 {
+        let mean = Self::mean_of(weights);
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (grid_point(i) - mean).powi(2) * w)
+            .sum::<f64>()
+            .max(0.0)
+    }
+}
+
+impl Default for AbilityPosterior {
+    fn default() -> Self {
+        Self::new(0.0, 1.0)
+    }
+}
+
+/// 按信息增益从高到低重排候选
+///
+/// 没有难度的条目无法评分，保持原相对顺序排在最后；得分相同时保持原顺序。
+pub fn rank_by_information_gain(
+    candidates: &[SessionItem],
+    posterior: &AbilityPosterior,
+) -> Vec<SessionItem> {
+    let mut scored: Vec<(f64, &SessionItem)> = candidates
+        .iter()
+        .map(|item| {
+            let gain = item
+                .difficulty
+                .filter(|d| d.is_finite())
+                .map(|d| posterior.expected_variance_reduction(difficulty_to_beta(d)))
+                .unwrap_or(f64::NEG_INFINITY);
+            (gain, item)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+/// 选词方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase", deny_unknown_fields)]
+pub enum SelectionMode {
+    /// 保持候选的原优先级
+    #[default]
+    Priority,
+    /// 前 `sessions` 次会话按信息增益选词
+    #[serde(rename_all = "camelCase")]
+    InformationGain { sessions: u32 },
+}
+
+/// 会话编排配置：选词方式 + 教学约束
+///
+/// ```json
+/// {
+///   "selection": { "mode": "informationGain", "sessions": 3 },
+///   "rules": [{ "type": "maxNewPerSession", "limit": 10 }]
+/// }
+/// ```
+///
+/// 选词只改变候选的先后；配合 `maxNewPerSession` 时，得分低的新词会被移出会话。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct SessionComposerConfig {
+    pub selection: SelectionMode,
+    pub rules: Vec<PolicyRule>,
+}
+
+impl SessionComposerConfig {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("编排配置解析失败: {e}"))
+    }
+
+    pub fn rule_set(&self) -> PolicyRuleSet {
+        PolicyRuleSet {
+            rules: self.rules.clone(),
+        }
+    }
+
+    /// 校验约束，并拒绝 `sessions` 为 0 的信息增益模式
+    pub fn validate(&self) -> PolicyValidation {
+        let mut validation = self.rule_set().validate();
+        if let SelectionMode::InformationGain { sessions: 0 } = self.selection {
+            validation.valid = false;
+            validation
+                .errors
+                .push("informationGain: sessions 必须大于 0".to_string());
+        }
+        validation
+    }
+
+    /// 已完成 `sessions_completed` 次会话的用户是否使用信息增益选词
+    pub fn information_gain_active(&self, sessions_completed: u32) -> bool {
+        match self.selection {
+            SelectionMode::Priority => false,
+            SelectionMode::InformationGain { sessions } => sessions_completed < sessions,
+        }
+    }
+
+    /// 先按选词方式排序候选，再按约束编排
+    pub fn compose(
+        &self,
+        candidates: &[SessionItem],
+        sessions_completed: u32,
+        posterior: &AbilityPosterior,
+    ) -> ComposedSession {
+        let rules = self.rule_set();
+        if self.information_gain_active(sessions_completed) {
+            rules.compose(&rank_by_information_gain(candidates, posterior))
+        } else {
+            rules.compose(candidates)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rated(id: &str, difficulty: f64) -> SessionItem {
+        SessionItem {
+            word_id: id.to_string(),
+            is_new: true,
+            failed: false,
+            difficulty: Some(difficulty),
+            confusable_with: None,
+            recall_probability: None,
+        }
+    }
+
+    fn ids(items: &[SessionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.word_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_difficulty_beta_roundtrip() {
+        assert!(difficulty_to_beta(0.5).abs() < 1e-12);
+        for d in [0.1, 0.3, 0.7, 0.9] {
+            assert!((sigmoid(difficulty_to_beta(d)) - d).abs() < 1e-9);
+        }
+        assert!(difficulty_to_beta(1.0).is_finite());
+    }
+
+    #[test]
+    fn test_gain_peaks_near_ability_and_answers_shrink_variance() {
+        let posterior = AbilityPosterior::new(1.0, 1.0);
+        let near = posterior.expected_variance_reduction(1.0);
+        let far = posterior.expected_variance_reduction(-3.0);
+        assert!(near > far);
+        assert!(far >= 0.0);
+
+        let mut updated = posterior.clone();
+        updated.update(1.0, true);
+        assert!(updated.variance() < posterior.variance());
+        assert!(updated.mean() > posterior.mean());
+    }
+
+    #[test]
+    fn test_information_gain_ranking_prefers_informative_words() {
+        let candidates = vec![
+            rated("easy", 0.05),
+            SessionItem {
+                difficulty: None,
+                ..rated("unrated", 0.5)
+            },
+            rated("hard", 0.95),
+            rated("matched", 0.5),
+        ];
+        let ranked = rank_by_information_gain(&candidates, &AbilityPosterior::default());
+        assert_eq!(ranked[0].word_id, "matched");
+        assert_eq!(ranked.last().unwrap().word_id, "unrated");
+    }
+
+    #[test]
+    fn test_composer_applies_selection_for_first_sessions_only() {
+        let config = SessionComposerConfig::from_json(
+            r#"{"selection":{"mode":"informationGain","sessions":2},
+                "rules":[{"type":"maxNewPerSession","limit":1}]}"#,
+        )
+        .unwrap();
+        assert!(config.validate().valid);
+        let candidates = vec![rated("easy", 0.02), rated("matched", 0.5)];
+        let posterior = AbilityPosterior::default();
+
+        let early = config.compose(&candidates, 0, &posterior);
+        assert_eq!(ids(&early.items), vec!["matched"]);
+        assert_eq!(early.dropped_word_ids, vec!["easy"]);
+
+        let later = config.compose(&candidates, 2, &posterior);
+        assert_eq!(ids(&later.items), vec!["easy"]);
+
+        let default = SessionComposerConfig::from_json("{}").unwrap();
+        assert_eq!(default.selection, SelectionMode::Priority);
+        let zero = SessionComposerConfig {
+            selection: SelectionMode::InformationGain { sessions: 0 },
+            rules: Vec::new(),
+        };
+        assert!(!zero.validate().valid);
+    }
+}
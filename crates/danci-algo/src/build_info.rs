@@ -0,0 +1,97 @@
+//! 算法构建信息：crate 版本 + git 提交号 + 配置指纹
+//!
+//! 写入模型快照与决策记录，排查行为变化时可以按算法版本拆分。配置指纹是
+//! 配置序列化为 JSON、对象键排序后的 FNV-1a 哈希，与字段插入顺序无关。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use danci_native::hash::fnv1a_64;
+
+/// danci-algo 的 crate 版本
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 构建时的 git 提交号（12 位），无法获取时为 "unknown"
+pub const GIT_HASH: &str = env!("DANCI_ALGO_GIT_HASH");
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmBuild {
+    pub crate_version: String,
+    pub git_hash: String,
+    pub config_fingerprint: String,
+}
+
+impl AlgorithmBuild {
+    pub fn new(config_fingerprint: impl Into<String>) -> Self {
+        Self {
+            crate_version: CRATE_VERSION.to_string(),
+            git_hash: GIT_HASH.to_string(),
+            config_fingerprint: config_fingerprint.into(),
+        }
+    }
+
+    /// 当前构建 + 给定配置
+    pub fn for_config<T: Serialize + ?Sized>(config: &T) -> Self {
+        Self::new(config_fingerprint(config))
+    }
+
+    /// 形如 `0.1.0+1a2b3c4d5e6f`，用于分组统计
+    pub fn version_label(&self) -> String {
+        format!("{}+{}", self.crate_version, self.git_hash)
+    }
+}
+
+/// 配置指纹（16 位十六进制）；无法序列化时为 "unknown"
+pub fn config_fingerprint<T: Serialize + ?Sized>(config: &T) -> String {
+    match serde_json::to_value(config).and_then(|v| serde_json::to_vec(&canonical(v))) {
+        Ok(bytes) => format!("{:016x}", fnv1a_64(&[&bytes])),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+/// 递归按键排序（即使 serde_json 启用了 preserve_order 也保持稳定）
+fn canonical(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, canonical(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_fingerprint_ignores_key_order() {
+        let a: HashMap<&str, f64> = [("alpha", 1.0), ("beta", 0.5)].into_iter().collect();
+        let b: HashMap<&str, f64> = [("beta", 0.5), ("alpha", 1.0)].into_iter().collect();
+        assert_eq!(config_fingerprint(&a), config_fingerprint(&b));
+
+        let c: HashMap<&str, f64> = [("alpha", 1.0), ("beta", 0.6)].into_iter().collect();
+        assert_ne!(config_fingerprint(&a), config_fingerprint(&c));
+        assert_eq!(config_fingerprint(&a).len(), 16);
+    }
+
+    #[test]
+    fn test_build_label() {
+        let build = AlgorithmBuild::for_config(&serde_json::json!({ "alpha": 1.0 }));
+        assert_eq!(build.crate_version, CRATE_VERSION);
+        assert!(!build.git_hash.is_empty());
+        assert_eq!(
+            build.version_label(),
+            format!("{CRATE_VERSION}+{}", build.git_hash)
+        );
+    }
+}
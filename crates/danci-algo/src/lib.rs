@@ -1 +1,4 @@
+pub mod active_learning;
+pub mod build_info;
+
 pub use danci_native::*;
@@ -0,0 +1,11 @@
+-- Migration: Two-part reward attribution on the delayed reward queue
+-- IMMEDIATE rows keep the existing behaviour; RETENTION rows carry the
+-- immediate half of a bandit reward until the follow-up review outcome
+-- arrives and the delayed correction can be applied.
+
+ALTER TABLE "reward_queue"
+    ADD COLUMN IF NOT EXISTS "kind" TEXT NOT NULL DEFAULT 'IMMEDIATE',
+    ADD COLUMN IF NOT EXISTS "decisionId" TEXT,
+    ADD COLUMN IF NOT EXISTS "attribution" JSONB;
+
+CREATE INDEX IF NOT EXISTS "idx_rq_decision" ON "reward_queue"("decisionId");
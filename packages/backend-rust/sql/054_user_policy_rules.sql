@@ -0,0 +1,7 @@
+-- 用户级教学策略约束（JSON 规则数组，由 danci-algo 会话编排器执行）
+
+CREATE TABLE IF NOT EXISTS "user_policy_rules" (
+    "userId" TEXT PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "rules" JSONB NOT NULL DEFAULT '[]'::jsonb,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
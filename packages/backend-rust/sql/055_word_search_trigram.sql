@@ -0,0 +1,24 @@
+-- Fuzzy word search: pg_trgm indexes on spelling and meanings.
+-- The extension is optional; without it search falls back to LIKE matching.
+
+CREATE OR REPLACE FUNCTION words_meanings_text(meanings TEXT[])
+RETURNS TEXT
+LANGUAGE SQL
+IMMUTABLE PARALLEL SAFE
+AS $$ SELECT array_to_string(meanings, ' ') $$;
+
+DO $$
+BEGIN
+    BEGIN
+        CREATE EXTENSION IF NOT EXISTS pg_trgm;
+    EXCEPTION WHEN OTHERS THEN
+        RAISE NOTICE 'pg_trgm unavailable, fuzzy word search disabled: %', SQLERRM;
+        RETURN;
+    END;
+
+    EXECUTE 'CREATE INDEX IF NOT EXISTS "idx_words_spelling_trgm"
+        ON "words" USING GIN (lower("spelling") gin_trgm_ops)';
+    EXECUTE 'CREATE INDEX IF NOT EXISTS "idx_words_meanings_trgm"
+        ON "words" USING GIN (lower(words_meanings_text("meanings")) gin_trgm_ops)';
+END
+$$;
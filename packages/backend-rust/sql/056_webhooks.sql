@@ -0,0 +1,41 @@
+-- 外部集成 Webhook：按组织管理端点，投递记录由 webhook worker 异步发送
+
+CREATE TABLE IF NOT EXISTS "webhook_organization_members" (
+    "organizationId" TEXT NOT NULL,
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("organizationId", "userId")
+);
+CREATE INDEX IF NOT EXISTS "idx_webhook_org_members_user" ON "webhook_organization_members"("userId");
+
+CREATE TABLE IF NOT EXISTS "webhook_endpoints" (
+    "id" TEXT PRIMARY KEY,
+    "organizationId" TEXT NOT NULL,
+    "url" TEXT NOT NULL,
+    "secret" TEXT NOT NULL,
+    "eventTypes" TEXT[] NOT NULL DEFAULT '{}',
+    "description" TEXT,
+    "isActive" BOOLEAN NOT NULL DEFAULT TRUE,
+    "createdBy" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS "idx_webhook_endpoints_org" ON "webhook_endpoints"("organizationId");
+
+CREATE TABLE IF NOT EXISTS "webhook_deliveries" (
+    "id" TEXT PRIMARY KEY,
+    "endpointId" TEXT NOT NULL REFERENCES "webhook_endpoints"("id") ON DELETE CASCADE,
+    "eventType" TEXT NOT NULL,
+    "userId" TEXT,
+    "payload" JSONB NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'PENDING',
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "nextAttemptAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "lastStatusCode" INTEGER,
+    "lastError" TEXT,
+    "deliveredAt" TIMESTAMP,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS "idx_webhook_deliveries_due" ON "webhook_deliveries"("status", "nextAttemptAt");
+CREATE INDEX IF NOT EXISTS "idx_webhook_deliveries_endpoint" ON "webhook_deliveries"("endpointId", "createdAt" DESC);
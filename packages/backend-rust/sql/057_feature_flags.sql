@@ -0,0 +1,11 @@
+-- 共享功能开关定义（评估逻辑在 danci-algo::flags，后端与桌面端一致）
+
+CREATE TABLE IF NOT EXISTS "feature_flags" (
+    "key" TEXT PRIMARY KEY,
+    "description" TEXT,
+    "enabled" BOOLEAN NOT NULL DEFAULT FALSE,
+    "rolloutPercent" DOUBLE PRECISION NOT NULL DEFAULT 100,
+    "overrides" JSONB NOT NULL DEFAULT '{}'::jsonb,
+    "updatedBy" TEXT,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
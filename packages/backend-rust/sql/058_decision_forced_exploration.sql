@@ -0,0 +1,9 @@
+-- Migration: Flag decisions made by IGE forced exploration
+-- Forced picks exist for arm monitoring and should be excluded from
+-- strategy comparisons, like simulation records.
+
+ALTER TABLE "decision_records"
+    ADD COLUMN IF NOT EXISTS "isForcedExploration" BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS "idx_dr_forced_exploration"
+    ON "decision_records"("isForcedExploration") WHERE "isForcedExploration";
@@ -0,0 +1,20 @@
+-- Migration: Idempotency keys for mutation endpoints
+-- One row per (auth session, client key). The request hash guards against a
+-- key being reused for a different payload; the stored response is replayed
+-- to retries until "expiresAt" (epoch ms).
+
+CREATE TABLE IF NOT EXISTS "idempotency_keys" (
+    "scope" TEXT NOT NULL,
+    "key" TEXT NOT NULL,
+    "requestHash" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'IN_PROGRESS',
+    "responseStatus" INTEGER,
+    "contentType" TEXT,
+    "responseBody" TEXT,
+    "responseHash" TEXT,
+    "createdAt" BIGINT NOT NULL,
+    "expiresAt" BIGINT NOT NULL,
+    PRIMARY KEY ("scope", "key")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_idempotency_keys_expires" ON "idempotency_keys"("expiresAt");
@@ -0,0 +1,14 @@
+-- 假期模式：冻结区间内的日期不打断连续学习天数；返回后积压的复习按
+-- "rampUpDays" 分摊到若干天
+
+CREATE TABLE IF NOT EXISTS "vacation_periods" (
+    "id" TEXT PRIMARY KEY,
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "startDate" DATE NOT NULL,
+    "endDate" DATE NOT NULL,
+    "rampUpDays" INTEGER NOT NULL DEFAULT 3,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ("endDate" >= "startDate")
+);
+CREATE INDEX IF NOT EXISTS "idx_vacation_periods_user_end" ON "vacation_periods"("userId", "endDate");
@@ -0,0 +1,17 @@
+-- 管理后台模拟沙箱：用 danci-algo sim 在合成队列上比较候选算法配置与当前配置，
+-- 任务在后台执行，前端按 "completedUnits"/"totalUnits" 轮询进度
+
+CREATE TABLE IF NOT EXISTS "simulation_jobs" (
+    "id" TEXT PRIMARY KEY,
+    "status" TEXT NOT NULL DEFAULT 'queued',
+    "completedUnits" INTEGER NOT NULL DEFAULT 0,
+    "totalUnits" INTEGER NOT NULL,
+    "request" JSONB NOT NULL,
+    "result" JSONB,
+    "error" TEXT,
+    "createdBy" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "completedAt" TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS "idx_simulation_jobs_created" ON "simulation_jobs"("createdAt" DESC);
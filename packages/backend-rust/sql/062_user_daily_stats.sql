@@ -0,0 +1,24 @@
+-- 统计物化汇总：按用户、按日（UTC）聚合答题记录，由 stats_rollup worker 增量刷新。
+-- "ingestedAt" 记录写入时间，worker 以它为水位线只重算新写入记录涉及的 (用户, 日期)
+
+ALTER TABLE "answer_records" ADD COLUMN IF NOT EXISTS "ingestedAt" TIMESTAMP NOT NULL DEFAULT NOW();
+CREATE INDEX IF NOT EXISTS "idx_answer_records_ingested" ON "answer_records"("ingestedAt");
+
+CREATE TABLE IF NOT EXISTS "user_daily_stats" (
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "date" DATE NOT NULL,
+    "answerCount" INTEGER NOT NULL DEFAULT 0,
+    "correctCount" INTEGER NOT NULL DEFAULT 0,
+    "totalResponseTimeMs" BIGINT NOT NULL DEFAULT 0,
+    "totalDwellTimeMs" BIGINT NOT NULL DEFAULT 0,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("userId", "date")
+);
+CREATE INDEX IF NOT EXISTS "idx_user_daily_stats_date" ON "user_daily_stats"("date");
+
+CREATE TABLE IF NOT EXISTS "stats_refresh_watermarks" (
+    "name" TEXT PRIMARY KEY,
+    "watermark" TIMESTAMP NOT NULL,
+    "lastRunAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "rowsProcessed" BIGINT NOT NULL DEFAULT 0
+);
@@ -0,0 +1,31 @@
+-- 语言对支持：词书记录学习语言与释义语言，单词携带自身的学习语言。
+-- 单词未显式指定时插入时继承所属词书的 "languageCode"，调度与特征构建据此选取语言参数
+
+ALTER TABLE "word_books" ADD COLUMN IF NOT EXISTS "languageCode" TEXT NOT NULL DEFAULT 'en';
+ALTER TABLE "word_books" ADD COLUMN IF NOT EXISTS "glossLanguage" TEXT NOT NULL DEFAULT 'zh';
+ALTER TABLE "words" ADD COLUMN IF NOT EXISTS "languageCode" TEXT;
+
+UPDATE "words" w
+SET "languageCode" = wb."languageCode"
+FROM "word_books" wb
+WHERE w."wordBookId" = wb."id" AND w."languageCode" IS NULL;
+
+CREATE OR REPLACE FUNCTION words_inherit_language_code()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    IF NEW."languageCode" IS NULL THEN
+        SELECT "languageCode" INTO NEW."languageCode"
+        FROM "word_books" WHERE "id" = NEW."wordBookId";
+    END IF;
+    RETURN NEW;
+END
+$$;
+
+DROP TRIGGER IF EXISTS "trg_words_inherit_language_code" ON "words";
+CREATE TRIGGER "trg_words_inherit_language_code"
+    BEFORE INSERT ON "words"
+    FOR EACH ROW EXECUTE FUNCTION words_inherit_language_code();
+
+CREATE INDEX IF NOT EXISTS "idx_word_books_language" ON "word_books"("languageCode", "glossLanguage");
@@ -0,0 +1,15 @@
+-- 模型快照静态加密：每个用户一把数据密钥，由主密钥包裹后存放于此。
+-- "amas_user_models"."parameters" 中的密文信封记录数据密钥版本 "kv"，
+-- 轮换主密钥只需重新包裹本表，轮换数据密钥则需重新加密该用户的快照。
+-- 删除本行即可使该用户的快照密文不可恢复。
+
+CREATE TABLE IF NOT EXISTS "user_data_keys" (
+    "userId" TEXT PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "wrappedKey" TEXT NOT NULL,
+    "masterKeyId" TEXT NOT NULL,
+    "keyVersion" INTEGER NOT NULL DEFAULT 1,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "rotatedAt" TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "user_data_keys_masterKeyId_idx" ON "user_data_keys"("masterKeyId");
@@ -0,0 +1,17 @@
+-- 算法配置草稿：离线调优等自动流程给出的候选参数，需人工审核后才会应用。
+-- "parameters" 为建议值，"metrics" 记录留出集回放评估的提升估计。
+
+CREATE TABLE IF NOT EXISTS "algorithm_config_drafts" (
+    "id" TEXT PRIMARY KEY,
+    "cohort" TEXT NOT NULL,
+    "source" TEXT NOT NULL DEFAULT 'auto_tuning',
+    "baseConfigId" TEXT REFERENCES "algorithm_configs"("id") ON DELETE SET NULL,
+    "parameters" JSONB NOT NULL,
+    "metrics" JSONB NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'PENDING',
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "reviewedAt" TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "algorithm_config_drafts_cohort_createdAt_idx"
+    ON "algorithm_config_drafts"("cohort", "createdAt" DESC);
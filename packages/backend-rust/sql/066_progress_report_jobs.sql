@@ -0,0 +1,19 @@
+-- 学习进度报告：按学生或按名单（班级）生成 CSV / PDF，任务在后台执行，
+-- 生成的文件直接存于 "content"，"expiresAt" 之后不再提供下载并在新建任务时清理
+
+CREATE TABLE IF NOT EXISTS "progress_report_jobs" (
+    "id" TEXT PRIMARY KEY,
+    "scope" TEXT NOT NULL,
+    "format" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'queued',
+    "request" JSONB NOT NULL,
+    "fileName" TEXT,
+    "contentType" TEXT,
+    "content" BYTEA,
+    "error" TEXT,
+    "createdBy" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "completedAt" TIMESTAMP,
+    "expiresAt" TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS "idx_progress_report_jobs_created" ON "progress_report_jobs"("createdAt" DESC);
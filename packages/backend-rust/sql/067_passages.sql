@@ -0,0 +1,19 @@
+-- 阅读短文：一篇短文包含多个目标单词，按短文整体安排复习，
+-- 记忆仍按单词分别追踪（见 amas::memory::passage）
+
+CREATE TABLE IF NOT EXISTS "passages" (
+    "id" TEXT PRIMARY KEY,
+    "wordBookId" TEXT REFERENCES "word_books"("id") ON DELETE CASCADE,
+    "title" TEXT NOT NULL,
+    "content" TEXT NOT NULL,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS "idx_passages_word_book" ON "passages"("wordBookId");
+
+CREATE TABLE IF NOT EXISTS "passage_words" (
+    "passageId" TEXT NOT NULL REFERENCES "passages"("id") ON DELETE CASCADE,
+    "wordId" TEXT NOT NULL REFERENCES "words"("id") ON DELETE CASCADE,
+    "position" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY ("passageId", "wordId")
+);
+CREATE INDEX IF NOT EXISTS "idx_passage_words_word" ON "passage_words"("wordId");
@@ -0,0 +1,20 @@
+-- 单词知识图谱：同义、反义、同词根、易混淆关系，无向边（"sourceWordId" < "targetWordId"）
+-- "userId" 为空表示全局边；用户自己添加的边只出现在该用户的图中
+-- same_root / confused 全局边由词素表和混淆对缓存派生，可整体重建
+
+CREATE TABLE IF NOT EXISTS "word_relations" (
+    "id" TEXT PRIMARY KEY,
+    "sourceWordId" TEXT NOT NULL REFERENCES "words"("id") ON DELETE CASCADE,
+    "targetWordId" TEXT NOT NULL REFERENCES "words"("id") ON DELETE CASCADE,
+    "relationType" TEXT NOT NULL CHECK ("relationType" IN ('synonym', 'antonym', 'same_root', 'confused')),
+    "weight" DOUBLE PRECISION NOT NULL DEFAULT 1.0,
+    "userId" TEXT REFERENCES "users"("id") ON DELETE CASCADE,
+    "source" TEXT NOT NULL DEFAULT 'manual',
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ("sourceWordId" < "targetWordId")
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uq_word_relations_edge"
+    ON "word_relations" ("sourceWordId", "targetWordId", "relationType", (COALESCE("userId", '')));
+CREATE INDEX IF NOT EXISTS "idx_word_relations_target" ON "word_relations"("targetWordId");
+CREATE INDEX IF NOT EXISTS "idx_word_relations_user" ON "word_relations"("userId") WHERE "userId" IS NOT NULL;
@@ -0,0 +1,11 @@
+-- 单词难度自动校准：由全体学习者的答题数据（按 IRT 能力值 airTheta 校正）估计的难度
+-- "calibratedBeta" 为 IRT 难度参数（与 airBeta 同一尺度，[-3, 3]）
+-- "calibratedDifficulty" 为映射到 [0, 1] 的难度，供新词选择使用；为空表示样本不足、沿用静态难度
+
+ALTER TABLE "words"
+ADD COLUMN IF NOT EXISTS "calibratedBeta" DOUBLE PRECISION,
+ADD COLUMN IF NOT EXISTS "calibratedDifficulty" DOUBLE PRECISION,
+ADD COLUMN IF NOT EXISTS "calibrationAttempts" INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN IF NOT EXISTS "calibratedAt" TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS "idx_answer_records_wordId_userId" ON "answer_records"("wordId", "userId");
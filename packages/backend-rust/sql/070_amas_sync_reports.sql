@@ -0,0 +1,12 @@
+-- 多设备模型同步的对账报告：记录每次同步时服务端与设备端模型的分歧度、
+-- 是否触发全量合并，以及最终采用的结果，便于排查离线后模型漂移
+CREATE TABLE IF NOT EXISTS "amas_sync_reports" (
+    "id" TEXT PRIMARY KEY,
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "deviceId" TEXT NOT NULL,
+    "mode" TEXT NOT NULL,
+    "report" JSONB NOT NULL,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_amas_sync_reports_user_created" ON "amas_sync_reports"("userId", "createdAt" DESC);
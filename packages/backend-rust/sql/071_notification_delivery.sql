@@ -0,0 +1,55 @@
+-- 通知的站外投递（邮件 / 推送）：模板、用户渠道偏好与投递队列
+-- 通知创建时按用户启用的渠道渲染模板并写入 "notification_deliveries"，由投递 worker 消费并重试
+-- "notification_templates"."key" 为通知类型，找不到时回退到 'DEFAULT'
+
+CREATE TABLE IF NOT EXISTS "notification_templates" (
+    "key" TEXT NOT NULL,
+    "channel" TEXT NOT NULL,
+    "subject" TEXT NOT NULL,
+    "body" TEXT NOT NULL,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("key", "channel")
+);
+
+INSERT INTO "notification_templates" ("key", "channel", "subject", "body") VALUES
+    ('DEFAULT', 'email', '{{title}}',
+     '<p>{{user.username}}，您好：</p><p>{{content}}</p>'),
+    ('DEFAULT', 'push', '{{title}}', '{{content}}'),
+    ('REMINDER', 'email', '{{title}}',
+     '<p>{{user.username}}，您好：</p><p>{{content}}</p><p>趁记忆还在，现在复习一下吧。</p>'),
+    ('REMINDER', 'push', '{{title}}', '{{content}}，现在复习一下吧')
+ON CONFLICT ("key", "channel") DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS "notification_channel_preferences" (
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "channel" TEXT NOT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT false,
+    -- 邮件：覆盖账号邮箱；推送：设备 token
+    "address" TEXT,
+    "platform" TEXT,
+    -- 投递的通知类型，空数组表示全部
+    "types" TEXT[] NOT NULL DEFAULT '{}',
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("userId", "channel")
+);
+
+CREATE TABLE IF NOT EXISTS "notification_deliveries" (
+    "id" TEXT PRIMARY KEY,
+    "notificationId" TEXT NOT NULL REFERENCES "notifications"("id") ON DELETE CASCADE,
+    "userId" TEXT NOT NULL,
+    "channel" TEXT NOT NULL,
+    "platform" TEXT,
+    "recipient" TEXT NOT NULL,
+    "subject" TEXT NOT NULL,
+    "body" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'PENDING',
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "nextAttemptAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "lastError" TEXT,
+    "deliveredAt" TIMESTAMP,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE ("notificationId", "channel")
+);
+CREATE INDEX IF NOT EXISTS "idx_notification_deliveries_due" ON "notification_deliveries"("status", "nextAttemptAt");
+CREATE INDEX IF NOT EXISTS "idx_notification_deliveries_user" ON "notification_deliveries"("userId", "createdAt" DESC);
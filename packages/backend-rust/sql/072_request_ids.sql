@@ -0,0 +1,4 @@
+-- 请求关联 ID：由请求创建的后台任务记录发起请求的 X-Request-Id，便于跨日志追踪
+
+ALTER TABLE "simulation_jobs" ADD COLUMN IF NOT EXISTS "requestId" TEXT;
+ALTER TABLE "progress_report_jobs" ADD COLUMN IF NOT EXISTS "requestId" TEXT;
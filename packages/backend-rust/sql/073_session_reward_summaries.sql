@@ -0,0 +1,18 @@
+-- 会话结束时的反事实奖励摘要：本次自适应排程与均匀随机排程的平均奖励对比
+-- 由 danci_algo::uniform_baseline_summary 在会话的决策日志上做双稳健估计
+
+CREATE TABLE IF NOT EXISTS "session_reward_summaries" (
+    "sessionId" TEXT PRIMARY KEY REFERENCES "learning_sessions"("id") ON DELETE CASCADE,
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "decisions" INTEGER NOT NULL,
+    "executedValue" DOUBLE PRECISION NOT NULL,
+    "baselineValue" DOUBLE PRECISION NOT NULL,
+    "baselineStdError" DOUBLE PRECISION NOT NULL,
+    "uplift" DOUBLE PRECISION NOT NULL,
+    "upliftStdError" DOUBLE PRECISION NOT NULL,
+    "relativeUplift" DOUBLE PRECISION,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_session_reward_summaries_user"
+    ON "session_reward_summaries"("userId", "createdAt");
@@ -0,0 +1,42 @@
+-- 词书的组织授权：标记为 "requiresLicense" 的词书只对持有有效授权的组织成员可见
+-- 组织成员沿用 "webhook_organization_members"；成员首次打开词书时占用授权的一个席位
+-- 授权的发放、调整与撤销记录在 "wordbook_license_events"
+
+ALTER TABLE "word_books" ADD COLUMN IF NOT EXISTS "requiresLicense" BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE IF NOT EXISTS "wordbook_licenses" (
+    "id" TEXT PRIMARY KEY,
+    "wordBookId" TEXT NOT NULL REFERENCES "word_books"("id") ON DELETE CASCADE,
+    "organizationId" TEXT NOT NULL,
+    -- NULL 表示不限席位
+    "seats" INTEGER,
+    "expiresAt" TIMESTAMP,
+    "revokedAt" TIMESTAMP,
+    "grantedBy" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+-- 同一词书对同一组织最多一份未撤销的授权
+CREATE UNIQUE INDEX IF NOT EXISTS "uq_wordbook_licenses_active"
+    ON "wordbook_licenses"("wordBookId", "organizationId") WHERE "revokedAt" IS NULL;
+CREATE INDEX IF NOT EXISTS "idx_wordbook_licenses_org" ON "wordbook_licenses"("organizationId");
+
+CREATE TABLE IF NOT EXISTS "wordbook_license_seats" (
+    "licenseId" TEXT NOT NULL REFERENCES "wordbook_licenses"("id") ON DELETE CASCADE,
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "firstUsedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "lastUsedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("licenseId", "userId")
+);
+
+CREATE TABLE IF NOT EXISTS "wordbook_license_events" (
+    "id" TEXT PRIMARY KEY,
+    "licenseId" TEXT NOT NULL REFERENCES "wordbook_licenses"("id") ON DELETE CASCADE,
+    "adminId" TEXT NOT NULL,
+    -- GRANT / UPDATE / REVOKE
+    "action" TEXT NOT NULL,
+    "details" JSONB,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS "idx_wordbook_license_events_license"
+    ON "wordbook_license_events"("licenseId", "createdAt");
@@ -0,0 +1,18 @@
+-- 按用户、天、路径类别、状态码聚合的 API 调用计数，用于排查同步问题
+-- 由 usage 中间件在内存中累加后定期合并写入，只保留最近 30 天
+-- 不引用 users：批量写入不应因已删除用户的残留计数整体失败，过期后自然清理
+
+CREATE TABLE IF NOT EXISTS "api_usage_daily" (
+    "userId" TEXT NOT NULL,
+    "day" DATE NOT NULL,
+    "pathClass" TEXT NOT NULL,
+    "status" INTEGER NOT NULL,
+    "requests" BIGINT NOT NULL DEFAULT 0,
+    "requestBytes" BIGINT NOT NULL DEFAULT 0,
+    "responseBytes" BIGINT NOT NULL DEFAULT 0,
+    "totalLatencyMs" BIGINT NOT NULL DEFAULT 0,
+    "lastSeenAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("userId", "day", "pathClass", "status")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_api_usage_daily_day" ON "api_usage_daily"("day");
@@ -0,0 +1,8 @@
+-- 逐题提示策略：每个用户一份 Thompson 采样状态（按难度档 × 回忆概率桶、提示等级的 Beta 后验）
+-- 由提示作答反馈更新，出题时为每个词选择提示等级
+
+CREATE TABLE IF NOT EXISTS "user_hint_policies" (
+    "userId" TEXT PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "policy" JSONB NOT NULL,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,27 @@
+-- SCIM v2 用户开通：企业身份提供方用组织级令牌在 /scim/v2 下创建、更新、停用学员账号
+-- 令牌只保存 SHA-256 摘要；"scim_users" 记录账号归属的组织与 IdP 侧的 externalId
+-- 停用的账号不能登录，停用时清除其全部会话
+
+CREATE TABLE IF NOT EXISTS "scim_tokens" (
+    "id" TEXT PRIMARY KEY,
+    "organizationId" TEXT NOT NULL,
+    "tokenHash" TEXT NOT NULL UNIQUE,
+    "description" TEXT,
+    "createdBy" TEXT,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "lastUsedAt" TIMESTAMP,
+    "revokedAt" TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS "idx_scim_tokens_org" ON "scim_tokens"("organizationId");
+
+CREATE TABLE IF NOT EXISTS "scim_users" (
+    "userId" TEXT PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "organizationId" TEXT NOT NULL,
+    "externalId" TEXT,
+    "active" BOOLEAN NOT NULL DEFAULT TRUE,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS "idx_scim_users_org" ON "scim_users"("organizationId");
+CREATE UNIQUE INDEX IF NOT EXISTS "uq_scim_users_external"
+    ON "scim_users"("organizationId", "externalId") WHERE "externalId" IS NOT NULL;
@@ -0,0 +1,16 @@
+-- 新用户分级测试：在标定词集上自适应出题，按标准误停止，结果用于初始化掌握度与 ACT-R 参数先验
+-- "session" 为 danci-algo PlacementSession（作答记录与当前 θ/SE），"items" 为本次测试的标定词集
+-- 完成后 "result" 保存能力估计、分段掌握度与 ACT-R 先验，并写回 users."airTheta"
+
+CREATE TABLE IF NOT EXISTS "placement_tests" (
+    "id" TEXT PRIMARY KEY,
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "status" TEXT NOT NULL DEFAULT 'active',
+    "session" JSONB NOT NULL,
+    "items" JSONB NOT NULL,
+    "result" JSONB,
+    "source" TEXT NOT NULL DEFAULT 'online',
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    "completedAt" TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS "idx_placement_tests_user" ON "placement_tests"("userId", "createdAt" DESC);
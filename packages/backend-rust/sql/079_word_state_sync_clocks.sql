@@ -0,0 +1,7 @@
+-- 学习状态多设备同步的向量时钟：设备 id（及 "server"）→ 该副本在此行上的写次数
+-- 客户端带时钟推送状态，服务端按时钟判断先后，真正的并发写入交给合并函数，而不是按时间戳覆盖
+-- "clockedAt" 为时钟最后一次与行内容对齐的时间；之后的服务端写入在下次同步时计为一次 server 写
+
+ALTER TABLE "word_learning_states"
+ADD COLUMN IF NOT EXISTS "syncClock" JSONB,
+ADD COLUMN IF NOT EXISTS "clockedAt" TIMESTAMP;
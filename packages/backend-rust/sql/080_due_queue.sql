@@ -0,0 +1,19 @@
+-- 服务端物化的“今日到期”队列：每个用户今天（UTC）到期的单词及复习优先级
+-- 作答和学习状态变更时按单词增量刷新，夜间任务整体重建；"horizon" 为队列覆盖到的时刻（次日零点），
+-- 过期的队列在下次读取时重建
+
+CREATE TABLE IF NOT EXISTS "due_queue" (
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "wordId" TEXT NOT NULL,
+    "state" TEXT NOT NULL,
+    "dueAt" TIMESTAMP,
+    "priority" DOUBLE PRECISION NOT NULL DEFAULT 0,
+    PRIMARY KEY ("userId", "wordId")
+);
+CREATE INDEX IF NOT EXISTS "idx_due_queue_order" ON "due_queue"("userId", "priority" DESC, "dueAt");
+
+CREATE TABLE IF NOT EXISTS "due_queue_users" (
+    "userId" TEXT PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "horizon" TIMESTAMP NOT NULL,
+    "refreshedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,17 @@
+-- Migration: Record the algorithm build behind each decision and model snapshot
+-- algorithmVersion is the danci-algo crate version, gitHash the commit it was
+-- built from, configFingerprint a hash of the active AMAS config. Rows written
+-- before this migration keep NULLs.
+
+ALTER TABLE "decision_records"
+    ADD COLUMN IF NOT EXISTS "algorithmVersion" TEXT,
+    ADD COLUMN IF NOT EXISTS "gitHash" TEXT,
+    ADD COLUMN IF NOT EXISTS "configFingerprint" TEXT;
+
+ALTER TABLE "amas_user_models"
+    ADD COLUMN IF NOT EXISTS "algorithmVersion" TEXT,
+    ADD COLUMN IF NOT EXISTS "gitHash" TEXT,
+    ADD COLUMN IF NOT EXISTS "configFingerprint" TEXT;
+
+CREATE INDEX IF NOT EXISTS "idx_dr_algorithm_version"
+    ON "decision_records"("algorithmVersion", "gitHash", "configFingerprint");
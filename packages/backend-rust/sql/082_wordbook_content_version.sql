@@ -0,0 +1,95 @@
+-- 词书内容版本：每次单词内容变化（新增、修改、软删除、移出词书、物理删除）都让所属词书的
+-- "contentVersion" 单调递增，并把新版本号记到该单词上；物理删除与移出词书写入 "word_tombstones"。
+-- 客户端凭上次同步到的版本号拉取增量，无需重新下载整本词书。
+-- 仅 Elo/难度校准等非内容字段的更新不推进版本。
+
+ALTER TABLE "word_books" ADD COLUMN IF NOT EXISTS "contentVersion" BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE "words" ADD COLUMN IF NOT EXISTS "contentVersion" BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "idx_words_book_content_version"
+    ON "words"("wordBookId", "contentVersion");
+
+CREATE TABLE IF NOT EXISTS "word_tombstones" (
+    "wordBookId" TEXT NOT NULL REFERENCES "word_books"("id") ON DELETE CASCADE,
+    "wordId" TEXT NOT NULL,
+    "contentVersion" BIGINT NOT NULL,
+    "deletedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("wordBookId", "wordId")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_word_tombstones_version"
+    ON "word_tombstones"("wordBookId", "contentVersion");
+
+CREATE OR REPLACE FUNCTION words_bump_content_version()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    old_version BIGINT;
+BEGIN
+    IF TG_OP = 'UPDATE' AND
+        ROW(NEW."spelling", NEW."phonetic", NEW."meanings", NEW."examples",
+            NEW."audioUrl", NEW."deletedAt", NEW."wordBookId")
+        IS NOT DISTINCT FROM
+        ROW(OLD."spelling", OLD."phonetic", OLD."meanings", OLD."examples",
+            OLD."audioUrl", OLD."deletedAt", OLD."wordBookId")
+    THEN
+        RETURN NEW;
+    END IF;
+
+    IF TG_OP = 'UPDATE' AND NEW."wordBookId" IS DISTINCT FROM OLD."wordBookId" THEN
+        UPDATE "word_books" SET "contentVersion" = "contentVersion" + 1
+        WHERE "id" = OLD."wordBookId"
+        RETURNING "contentVersion" INTO old_version;
+        IF old_version IS NOT NULL THEN
+            INSERT INTO "word_tombstones" ("wordBookId", "wordId", "contentVersion", "deletedAt")
+            VALUES (OLD."wordBookId", OLD."id", old_version, NOW())
+            ON CONFLICT ("wordBookId", "wordId") DO UPDATE
+            SET "contentVersion" = EXCLUDED."contentVersion", "deletedAt" = EXCLUDED."deletedAt";
+        END IF;
+    END IF;
+
+    UPDATE "word_books" SET "contentVersion" = "contentVersion" + 1
+    WHERE "id" = NEW."wordBookId"
+    RETURNING "contentVersion" INTO NEW."contentVersion";
+    NEW."contentVersion" := COALESCE(NEW."contentVersion", 0);
+    IF TG_OP = 'UPDATE' THEN
+        NEW."updatedAt" := NOW();
+    END IF;
+
+    DELETE FROM "word_tombstones"
+    WHERE "wordBookId" = NEW."wordBookId" AND "wordId" = NEW."id";
+    RETURN NEW;
+END
+$$;
+
+DROP TRIGGER IF EXISTS "trg_words_bump_content_version" ON "words";
+CREATE TRIGGER "trg_words_bump_content_version"
+    BEFORE INSERT OR UPDATE ON "words"
+    FOR EACH ROW EXECUTE FUNCTION words_bump_content_version();
+
+CREATE OR REPLACE FUNCTION words_tombstone_on_delete()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    version BIGINT;
+BEGIN
+    UPDATE "word_books" SET "contentVersion" = "contentVersion" + 1
+    WHERE "id" = OLD."wordBookId"
+    RETURNING "contentVersion" INTO version;
+    -- 词书本身被删除时级联删除单词，此时无需墓碑
+    IF version IS NOT NULL THEN
+        INSERT INTO "word_tombstones" ("wordBookId", "wordId", "contentVersion", "deletedAt")
+        VALUES (OLD."wordBookId", OLD."id", version, NOW())
+        ON CONFLICT ("wordBookId", "wordId") DO UPDATE
+        SET "contentVersion" = EXCLUDED."contentVersion", "deletedAt" = EXCLUDED."deletedAt";
+    END IF;
+    RETURN OLD;
+END
+$$;
+
+DROP TRIGGER IF EXISTS "trg_words_tombstone_on_delete" ON "words";
+CREATE TRIGGER "trg_words_tombstone_on_delete"
+    AFTER DELETE ON "words"
+    FOR EACH ROW EXECUTE FUNCTION words_tombstone_on_delete();
@@ -0,0 +1,5 @@
+-- 会话多目标评分：记忆收益、时间成本、挫败风险的权重与帕累托权衡参数 tradeoff。
+-- 空对象表示全部取默认值（见 danci_algo::SessionObjective）。
+
+ALTER TABLE "algorithm_configs"
+    ADD COLUMN IF NOT EXISTS "sessionObjective" JSONB NOT NULL DEFAULT '{}'::jsonb;
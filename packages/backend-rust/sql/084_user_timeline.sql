@@ -0,0 +1,26 @@
+-- 学习历程时间线：每个用户按其本地日期（时区感知）汇总一天的学习情况，
+-- 由 timeline worker 在用户本地零点之后写入，可通过管理接口回填历史。
+-- "rolledThrough" 为已汇总到的最后一个本地日期，"timezone" 为 IANA 时区名
+
+CREATE TABLE IF NOT EXISTS "user_timeline_cursors" (
+    "userId" TEXT PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "timezone" TEXT NOT NULL DEFAULT 'UTC',
+    "rolledThrough" DATE,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS "user_timeline_entries" (
+    "userId" TEXT NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "date" DATE NOT NULL,
+    "timezone" TEXT NOT NULL,
+    "answerCount" INTEGER NOT NULL DEFAULT 0,
+    "correctCount" INTEGER NOT NULL DEFAULT 0,
+    "wordsLearned" INTEGER NOT NULL DEFAULT 0,
+    "wordsReviewed" INTEGER NOT NULL DEFAULT 0,
+    "studyMinutes" INTEGER NOT NULL DEFAULT 0,
+    "bestCorrectStreak" INTEGER NOT NULL DEFAULT 0,
+    "dayStreak" INTEGER NOT NULL DEFAULT 1,
+    "events" JSONB NOT NULL DEFAULT '[]'::jsonb,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("userId", "date")
+);
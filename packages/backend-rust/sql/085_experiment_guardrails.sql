@@ -0,0 +1,35 @@
+-- 实验护栏：每个变体相对对照组的滚动窗口指标（正确率、会话时长）由
+-- experiment_guardrail worker 做序贯检验，显著劣化的变体被自动停用。
+-- 停用后新用户不再分配到该变体，已分配用户改回对照组，
+-- "guardrailFromVariantId" 记录其原变体，使对照组指标不受污染
+
+ALTER TABLE "ab_variants" ADD COLUMN IF NOT EXISTS "disabledAt" TIMESTAMP;
+ALTER TABLE "ab_variants" ADD COLUMN IF NOT EXISTS "disabledReason" TEXT;
+
+ALTER TABLE "ab_user_assignments" ADD COLUMN IF NOT EXISTS "guardrailFromVariantId" TEXT;
+
+-- 停用决策审计记录：触发的指标、两组的窗口统计量与检验结果
+CREATE TABLE IF NOT EXISTS "ab_guardrail_decisions" (
+    "id" TEXT PRIMARY KEY,
+    "experimentId" TEXT NOT NULL,
+    "experimentName" TEXT NOT NULL,
+    "variantId" TEXT NOT NULL,
+    "variantName" TEXT NOT NULL,
+    "controlVariantId" TEXT NOT NULL,
+    "metric" TEXT NOT NULL,
+    "windowDays" INTEGER NOT NULL,
+    "variantUsers" INTEGER NOT NULL,
+    "controlUsers" INTEGER NOT NULL,
+    "variantMean" DOUBLE PRECISION NOT NULL,
+    "controlMean" DOUBLE PRECISION NOT NULL,
+    "relativeChange" DOUBLE PRECISION NOT NULL,
+    "logLikelihoodRatio" DOUBLE PRECISION NOT NULL,
+    "threshold" DOUBLE PRECISION NOT NULL,
+    "reassignedUsers" INTEGER NOT NULL DEFAULT 0,
+    "reason" TEXT NOT NULL,
+    "metrics" JSONB NOT NULL DEFAULT '[]'::jsonb,
+    "createdAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_ab_guardrail_decisions_experiment"
+    ON "ab_guardrail_decisions" ("experimentId", "createdAt" DESC);
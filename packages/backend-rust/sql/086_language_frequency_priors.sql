@@ -0,0 +1,28 @@
+-- 新语言冷启动难度：管理员导入的词频表按语言生成每个词的初始难度先验，
+-- 在还没有作答数据（未校准、Elo 未对局）时供新词调度、IRT 与分级测试使用。
+-- "word" 为规范化词形（去首尾空白、小写），与 LOWER(TRIM(words."spelling")) 匹配；
+-- "difficulty" 与新词难度同一尺度 [0, 1]，"beta" 为 IRT 难度先验 [-3, 3]，
+-- "selectionPrior" 为该词在词频表中的频率占比
+
+CREATE TABLE IF NOT EXISTS "language_frequency_lists" (
+    "languageCode" TEXT PRIMARY KEY,
+    "source" TEXT,
+    "entries" INTEGER NOT NULL,
+    "fromCounts" BOOLEAN NOT NULL,
+    "config" JSONB NOT NULL DEFAULT '{}'::jsonb,
+    "bandCounts" JSONB NOT NULL DEFAULT '[]'::jsonb,
+    "importedBy" TEXT,
+    "importedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS "language_frequency_priors" (
+    "languageCode" TEXT NOT NULL REFERENCES "language_frequency_lists"("languageCode") ON DELETE CASCADE,
+    "word" TEXT NOT NULL,
+    "rank" INTEGER NOT NULL,
+    "zipf" DOUBLE PRECISION NOT NULL,
+    "difficulty" DOUBLE PRECISION NOT NULL,
+    "band" INTEGER NOT NULL,
+    "beta" DOUBLE PRECISION NOT NULL,
+    "selectionPrior" DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY ("languageCode", "word")
+);
@@ -0,0 +1,10 @@
+-- Migration: Record the session exploration budget behind each decision
+-- explorationScale is the budget scale applied to IGE forced exploration
+-- (1 = unscaled), intendedExplorationShare the share of selections the
+-- scaled policy hands to exploration. Comparing it with isForcedExploration
+-- shows whether the realised exploration matches the intent. Decisions made
+-- with exploration off keep NULLs.
+
+ALTER TABLE "decision_records"
+    ADD COLUMN IF NOT EXISTS "explorationScale" DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS "intendedExplorationShare" DOUBLE PRECISION;
@@ -0,0 +1,29 @@
+-- Migration: Backfill per-book progress counters
+-- Answers now adjust learnedCount/masteredCount incrementally for every copy
+-- of a word across the user's books. Recompute the starting point from the
+-- learning states so existing users are not credited from stale counters.
+-- learned = any state past NEW, mastered = MASTERED, matching book_dedup.
+
+INSERT INTO "user_word_book_progress" (
+    "id", "userId", "wordBookId", "learnedCount", "masteredCount",
+    "totalWords", "lastStudyAt", "createdAt", "updatedAt"
+)
+SELECT
+    gen_random_uuid()::text,
+    s."userId",
+    w."wordBookId",
+    COUNT(*) FILTER (WHERE s."state"::text <> 'NEW'),
+    COUNT(*) FILTER (WHERE s."state"::text = 'MASTERED'),
+    (SELECT COUNT(*) FROM "words" bw WHERE bw."wordBookId" = w."wordBookId"),
+    MAX(s."lastReviewDate"),
+    NOW(),
+    NOW()
+FROM "word_learning_states" s
+JOIN "words" w ON w."id" = s."wordId"
+GROUP BY s."userId", w."wordBookId"
+ON CONFLICT ("userId", "wordBookId") DO UPDATE SET
+    "learnedCount" = EXCLUDED."learnedCount",
+    "masteredCount" = EXCLUDED."masteredCount",
+    "totalWords" = EXCLUDED."totalWords",
+    "lastStudyAt" = COALESCE("user_word_book_progress"."lastStudyAt", EXCLUDED."lastStudyAt"),
+    "updatedAt" = NOW();
@@ -0,0 +1,7 @@
+-- Migration: Partial credit on review traces
+-- Passage answers split one reward across several words. Each credited word
+-- now records its share as a credit in [0, 1] next to isCorrect, and MSMT
+-- uses it instead of the binary outcome. Single-word reviews keep NULL.
+
+ALTER TABLE "word_review_traces"
+    ADD COLUMN IF NOT EXISTS "credit" DOUBLE PRECISION;
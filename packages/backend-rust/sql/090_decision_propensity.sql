@@ -0,0 +1,8 @@
+-- Migration: Log the propensity of randomized decisions
+-- propensity is the probability that IGE's uniform exploration draw picked
+-- the logged difficulty. It is only set for decisions the draw actually
+-- randomized; greedy, round-robin and post-filtered picks keep NULL. The
+-- offline tuner and the session reward summary use these rows only.
+
+ALTER TABLE "decision_records"
+    ADD COLUMN IF NOT EXISTS "propensity" DOUBLE PRECISION;
@@ -0,0 +1,28 @@
+-- 功能开关版本号：单行计数器，"feature_flags" 的任何写入（含删除）都让它递增。
+-- 之前取 max("updatedAt")，删除开关不会改变版本，设备因此拿不到删除。
+-- 初值沿用旧的毫秒时间戳版本，保证设备上缓存的版本号不会比新版本大。
+
+CREATE TABLE IF NOT EXISTS "feature_flag_version" (
+    "id" BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK ("id"),
+    "version" BIGINT NOT NULL
+);
+
+INSERT INTO "feature_flag_version" ("id", "version")
+SELECT TRUE, COALESCE((EXTRACT(EPOCH FROM MAX("updatedAt")) * 1000)::BIGINT, 0)
+FROM "feature_flags"
+ON CONFLICT ("id") DO NOTHING;
+
+CREATE OR REPLACE FUNCTION feature_flags_bump_version()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    UPDATE "feature_flag_version" SET "version" = "version" + 1;
+    RETURN NULL;
+END
+$$;
+
+DROP TRIGGER IF EXISTS "trg_feature_flags_bump_version" ON "feature_flags";
+CREATE TRIGGER "trg_feature_flags_bump_version"
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON "feature_flags"
+    FOR EACH STATEMENT EXECUTE FUNCTION feature_flags_bump_version();
@@ -0,0 +1,12 @@
+-- 维护模式状态：单行，由管理端开关写入，各实例定时轮询，
+-- 使任一实例上的切换对所有实例（及其在线客户端）生效。
+
+CREATE TABLE IF NOT EXISTS "maintenance_state" (
+    "id" BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK ("id"),
+    "enabled" BOOLEAN NOT NULL DEFAULT FALSE,
+    "message" TEXT NOT NULL,
+    "retryAfterSecs" BIGINT NOT NULL,
+    "since" TIMESTAMP,
+    "changedBy" TEXT,
+    "updatedAt" TIMESTAMP NOT NULL DEFAULT NOW()
+);
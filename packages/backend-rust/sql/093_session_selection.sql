@@ -0,0 +1,5 @@
+-- 会话选词方式（danci_algo::SelectionMode）：{"mode":"priority"} 保持原优先级，
+-- {"mode":"informationGain","sessions":N} 让新用户前 N 次会话按能力信息增益选词。
+
+ALTER TABLE "algorithm_configs"
+    ADD COLUMN IF NOT EXISTS "sessionSelection" JSONB NOT NULL DEFAULT '{"mode":"priority"}'::jsonb;
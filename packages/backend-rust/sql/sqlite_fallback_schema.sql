@@ -16,6 +16,7 @@ CREATE TABLE IF NOT EXISTS "_db_metadata" (
 CREATE TABLE IF NOT EXISTS "_pending_writes" (
   "operation_id" TEXT PRIMARY KEY,
   "operation_data" TEXT NOT NULL,
+  "priority" TEXT NOT NULL DEFAULT 'normal',
   "created_at" TEXT DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -1188,3 +1189,27 @@ CREATE TABLE IF NOT EXISTS "amas_shadow_results" (
 
 CREATE INDEX IF NOT EXISTS "idx_amas_shadow_user_word" ON "amas_shadow_results" ("userId", "wordId");
 CREATE INDEX IF NOT EXISTS "idx_amas_shadow_created" ON "amas_shadow_results" ("createdAt");
+
+-- Full-text index over words for fallback search (rebuilt on demand by the search service)
+CREATE VIRTUAL TABLE IF NOT EXISTS "words_fts" USING fts5(
+  "spelling", "meanings",
+  content='words', content_rowid='rowid',
+  tokenize='unicode61 remove_diacritics 2'
+);
+
+-- Idempotency keys for mutation endpoints (also created on first use by the idempotency service)
+CREATE TABLE IF NOT EXISTS "idempotency_keys" (
+  "scope" TEXT NOT NULL,
+  "key" TEXT NOT NULL,
+  "requestHash" TEXT NOT NULL,
+  "status" TEXT NOT NULL DEFAULT 'IN_PROGRESS',
+  "responseStatus" INTEGER,
+  "contentType" TEXT,
+  "responseBody" TEXT,
+  "responseHash" TEXT,
+  "createdAt" INTEGER NOT NULL,
+  "expiresAt" INTEGER NOT NULL,
+  PRIMARY KEY ("scope", "key")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_idempotency_keys_expires" ON "idempotency_keys" ("expiresAt");
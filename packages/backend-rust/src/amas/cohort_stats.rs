@@ -0,0 +1,372 @@
+//! Population-level arm statistics.
+//!
+//! Each user's IGE model keeps per-arm success and trial counts, which are the
+//! observation counts of a Beta posterior with the prior taken out. Summing
+//! them over a cohort and adding the prior back once gives a population
+//! posterior per arm. Its mean and equal-tailed credible interval feed the
+//! strategy dashboards.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::amas::decision::{IgeModel, StrategyStats};
+use crate::amas::types::BanditModel;
+use crate::db::operations::amas::{list_amas_user_models_page, UserModelScanFilter};
+use crate::db::DatabaseProxy;
+
+const PAGE_SIZE: i64 = 500;
+const QUANTILE_ITERATIONS: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct CohortQuery {
+    pub filter: UserModelScanFilter,
+    /// Aggregate one context's statistics instead of the global ones.
+    pub context_key: Option<String>,
+    /// Probability mass inside the credible interval.
+    pub credible_level: f64,
+    /// Scales down users with more trials than this on an arm (keeping their
+    /// success rate) so a few heavy users cannot dominate the population.
+    pub per_user_trial_cap: Option<f64>,
+    pub prior_alpha: f64,
+    pub prior_beta: f64,
+}
+
+impl Default for CohortQuery {
+    fn default() -> Self {
+        Self {
+            filter: UserModelScanFilter::default(),
+            context_key: None,
+            credible_level: 0.95,
+            per_user_trial_cap: None,
+            prior_alpha: 1.0,
+            prior_beta: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmPosterior {
+    pub action: String,
+    pub alpha: f64,
+    pub beta: f64,
+    pub successes: f64,
+    pub trials: f64,
+    /// Users with at least one trial on this arm.
+    pub users: u64,
+    pub mean: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortArmStats {
+    pub users: u64,
+    pub skipped_users: u64,
+    pub context_key: Option<String>,
+    pub credible_level: f64,
+    pub arms: Vec<ArmPosterior>,
+    pub generated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Default, Clone)]
+struct ArmTotals {
+    successes: f64,
+    trials: f64,
+    users: u64,
+}
+
+/// Running sums of per-user observation counts.
+#[derive(Debug, Default)]
+pub struct CohortAccumulator {
+    arms: BTreeMap<String, ArmTotals>,
+    users: u64,
+}
+
+impl CohortAccumulator {
+    pub fn add_user(
+        &mut self,
+        model: &IgeModel,
+        context_key: Option<&str>,
+        per_user_trial_cap: Option<f64>,
+    ) {
+        let stats = match context_key {
+            Some(key) => model.context_stats(key),
+            None => Some(model.global_stats()),
+        };
+        self.users += 1;
+        for (action, stats) in stats.into_iter().flatten() {
+            let Some((successes, trials)) = observation_counts(stats, per_user_trial_cap) else {
+                continue;
+            };
+            let totals = self.arms.entry(action.clone()).or_default();
+            totals.successes += successes;
+            totals.trials += trials;
+            totals.users += 1;
+        }
+    }
+
+    pub fn finish(self, query: &CohortQuery) -> Vec<ArmPosterior> {
+        let level = query.credible_level.clamp(0.5, 0.999);
+        let tail = (1.0 - level) / 2.0;
+        let mut arms: Vec<ArmPosterior> = self
+            .arms
+            .into_iter()
+            .map(|(action, totals)| {
+                let alpha = query.prior_alpha + totals.successes;
+                let beta = query.prior_beta + (totals.trials - totals.successes);
+                ArmPosterior {
+                    action,
+                    alpha,
+                    beta,
+                    successes: totals.successes,
+                    trials: totals.trials,
+                    users: totals.users,
+                    mean: alpha / (alpha + beta),
+                    lower: beta_quantile(tail, alpha, beta),
+                    upper: beta_quantile(1.0 - tail, alpha, beta),
+                }
+            })
+            .collect();
+        arms.sort_by(|a, b| {
+            b.mean
+                .total_cmp(&a.mean)
+                .then_with(|| a.action.cmp(&b.action))
+        });
+        arms
+    }
+}
+
+/// A user's (successes, trials) for one arm, or `None` when there is nothing
+/// usable to add.
+fn observation_counts(stats: &StrategyStats, cap: Option<f64>) -> Option<(f64, f64)> {
+    if !stats.trials.is_finite() || !stats.successes.is_finite() || stats.trials <= 0.0 {
+        return None;
+    }
+    let trials = stats.trials;
+    let successes = stats.successes.clamp(0.0, trials);
+    match cap {
+        Some(cap) if cap > 0.0 && trials > cap => Some((successes * cap / trials, cap)),
+        _ => Some((successes, trials)),
+    }
+}
+
+/// Scans every bandit model in the cohort and merges its arm statistics.
+pub async fn aggregate_cohort(
+    proxy: &DatabaseProxy,
+    query: &CohortQuery,
+) -> Result<CohortArmStats, sqlx::Error> {
+    let mut accumulator = CohortAccumulator::default();
+    let mut skipped_users = 0;
+    let mut after: Option<String> = None;
+    loop {
+        let page =
+            list_amas_user_models_page(proxy, "bandit", &query.filter, after.as_deref(), PAGE_SIZE)
+                .await?;
+        let Some(last) = page.last() else { break };
+        after = Some(last.user_id.clone());
+
+        for row in &page {
+            let model = serde_json::from_value::<BanditModel>(row.parameters.clone())
+                .ok()
+                .and_then(|bandit| bandit.thompson_params)
+                .and_then(|params| serde_json::from_value::<IgeModel>(params).ok());
+            match model {
+                Some(model) => accumulator.add_user(
+                    &model,
+                    query.context_key.as_deref(),
+                    query.per_user_trial_cap,
+                ),
+                None => skipped_users += 1,
+            }
+        }
+        if (page.len() as i64) < PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(CohortArmStats {
+        users: accumulator.users,
+        skipped_users,
+        context_key: query.context_key.clone(),
+        credible_level: query.credible_level.clamp(0.5, 0.999),
+        arms: accumulator.finish(query),
+        generated_at: chrono::Utc::now().naive_utc(),
+    })
+}
+
+/// Inverse CDF of Beta(a, b) by bisection on the regularized incomplete beta.
+pub fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    if p <= 0.0 {
+        return 0.0;
+    }
+    if p >= 1.0 {
+        return 1.0;
+    }
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..QUANTILE_ITERATIONS {
+        let mid = 0.5 * (lo + hi);
+        if regularized_incomplete_beta(mid, a, b) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// I_x(a, b) via the continued fraction (modified Lentz).
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The fraction converges fastest below the mean; use the symmetry otherwise.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const MAX_TERMS: usize = 300;
+    const TOLERANCE: f64 = 1e-14;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=MAX_TERMS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for numerator in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < TOLERANCE {
+            break;
+        }
+    }
+    h
+}
+
+/// Lanczos approximation (g = 7, n = 9).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |acc, (i, c)| {
+            acc + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(updates: &[(&str, f64, Option<&str>)]) -> IgeModel {
+        let mut model = IgeModel::new();
+        for (arm, reward, context) in updates {
+            model.update(arm, *reward, *context);
+        }
+        model
+    }
+
+    #[test]
+    fn test_beta_quantile_known_values() {
+        // Beta(1, 1) is uniform.
+        assert!((beta_quantile(0.025, 1.0, 1.0) - 0.025).abs() < 1e-9);
+        // Beta(2, 1) has CDF x^2.
+        assert!((beta_quantile(0.25, 2.0, 1.0) - 0.5).abs() < 1e-9);
+        // Symmetric posterior has its median at 0.5.
+        assert!((beta_quantile(0.5, 30.0, 30.0) - 0.5).abs() < 1e-9);
+        let lower = beta_quantile(0.025, 30.0, 70.0);
+        let upper = beta_quantile(0.975, 30.0, 70.0);
+        assert!(lower < 0.3 && upper > 0.3);
+        assert!((lower - 0.2145).abs() < 5e-3, "{lower}");
+        assert!((upper - 0.3932).abs() < 5e-3, "{upper}");
+    }
+
+    #[test]
+    fn test_merges_counts_and_adds_prior_once() {
+        let mut acc = CohortAccumulator::default();
+        acc.add_user(
+            &model(&[("easy", 1.0, None), ("easy", 0.0, None)]),
+            None,
+            None,
+        );
+        acc.add_user(
+            &model(&[("easy", 1.0, None), ("hard", 0.0, None)]),
+            None,
+            None,
+        );
+        acc.add_user(&IgeModel::new(), None, None);
+
+        let arms = acc.finish(&CohortQuery::default());
+        let easy = arms.iter().find(|a| a.action == "easy").unwrap();
+        assert_eq!((easy.alpha, easy.beta), (3.0, 2.0));
+        assert_eq!(easy.users, 2);
+        assert!((easy.mean - 0.6).abs() < 1e-12);
+        assert!(easy.lower < easy.mean && easy.mean < easy.upper);
+        let hard = arms.iter().find(|a| a.action == "hard").unwrap();
+        assert_eq!((hard.alpha, hard.beta), (1.0, 2.0));
+        assert_eq!(arms[0].action, "easy");
+    }
+
+    #[test]
+    fn test_context_and_trial_cap() {
+        let heavy: Vec<_> = (0..100).map(|_| ("a", 1.0, Some("night"))).collect();
+        let mut acc = CohortAccumulator::default();
+        acc.add_user(&model(&heavy), Some("night"), Some(10.0));
+        acc.add_user(
+            &model(&[("a", 0.0, Some("day"))]),
+            Some("night"),
+            Some(10.0),
+        );
+        let arms = acc.finish(&CohortQuery::default());
+        assert_eq!(arms.len(), 1);
+        assert_eq!(arms[0].trials, 10.0);
+        assert_eq!(arms[0].successes, 10.0);
+        assert_eq!(arms[0].users, 1);
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::amas::decision::ForcedExploration;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NormalizationStat {
     pub mean: f64,
@@ -304,6 +306,8 @@ pub struct FeatureFlags {
         alias = "umm_ab_test_percentage"
     )]
     pub amas_ab_test_percentage: u8,
+    #[serde(default)]
+    pub amas_delayed_reward_enabled: bool,
 }
 
 fn default_true() -> bool {
@@ -330,6 +334,7 @@ impl Default for FeatureFlags {
             amas_evm_enabled: true,
             amas_ab_test_enabled: false,
             amas_ab_test_percentage: 10,
+            amas_delayed_reward_enabled: false,
         }
     }
 }
@@ -456,6 +461,11 @@ pub struct AMASConfig {
     pub reward: RewardConfig,
     pub feature_flags: FeatureFlags,
     pub ensemble: EnsembleConfig,
+    #[serde(default)]
+    pub ige_exploration: ForcedExploration,
+    /// Master seed for replayable randomness; `None` draws from the OS RNG.
+    #[serde(default)]
+    pub rng_seed: Option<u64>,
     pub attention_smoothing: f64,
     pub confidence_decay: f64,
     pub min_confidence: f64,
@@ -475,6 +485,8 @@ impl Default for AMASConfig {
             reward: RewardConfig::default(),
             feature_flags: FeatureFlags::default(),
             ensemble: EnsembleConfig::default(),
+            ige_exploration: ForcedExploration::Off,
+            rng_seed: None,
             attention_smoothing: 0.3,
             confidence_decay: 0.99,
             min_confidence: 0.1,
@@ -489,6 +501,17 @@ impl AMASConfig {
         if let Ok(val) = std::env::var("AMAS_ENSEMBLE_ENABLED") {
             config.feature_flags.ensemble_enabled = val.parse().unwrap_or(true);
         }
+        if let Ok(val) = std::env::var("AMAS_DELAYED_REWARD_ENABLED") {
+            config.feature_flags.amas_delayed_reward_enabled = val.parse().unwrap_or(false);
+        }
+        if let Ok(val) = std::env::var("AMAS_IGE_FORCED_EXPLORATION") {
+            match ForcedExploration::parse(&val) {
+                Some(policy) => config.ige_exploration = policy,
+                None => {
+                    tracing::warn!(value = %val, "Invalid AMAS_IGE_FORCED_EXPLORATION, ignoring")
+                }
+            }
+        }
 
         config
     }
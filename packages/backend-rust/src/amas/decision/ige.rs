@@ -9,7 +9,12 @@
 //! - ess_k = 5 (effective sample size factor)
 //! - min_confidence = 0.4
 //! - max_confidence = 0.98
+//!
+//! Context statistics that have gone unused and never moved away from the
+//! prior are pruned by [`IgeModel::gc`] to keep long-lived snapshots small.
 
+use danci_algo::footprint::hashmap_table_bytes;
+use danci_algo::{BetaPrior, ExplorationBudget, MemoryFootprint};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -19,11 +24,19 @@ const ESS_K: f64 = 5.0;
 const MIN_CONFIDENCE: f64 = 0.4;
 const MAX_CONFIDENCE: f64 = 0.98;
 const EPSILON: f64 = 1e-6;
+/// Share of a two-part reward decided by the delayed (next-day retention) outcome.
+pub const RETENTION_REWARD_WEIGHT: f64 = 0.7;
+/// Mean and variance an arm falls back to without context statistics.
+const PRIOR_MEAN: f64 = 0.5;
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StrategyStats {
     pub successes: f64,
     pub trials: f64,
+    /// Unix ms of the last update; 0 for statistics saved before it was tracked.
+    #[serde(default)]
+    pub updated_at: i64,
 }
 
 impl StrategyStats {
@@ -48,12 +61,201 @@ impl StrategyStats {
         self.trials += 1.0;
         self.successes += reward;
     }
+
+    /// Whether dropping these statistics would change little: few trials and
+    /// a mean still close to the prior.
+    fn is_near_prior(&self, config: &IgeGcConfig) -> bool {
+        self.trials <= config.max_trials
+            && (self.mean() - PRIOR_MEAN).abs() <= config.mean_tolerance
+    }
+
+    /// Shift an already counted reward without adding a trial.
+    fn correct(&mut self, delta: f64) {
+        self.successes = (self.successes + delta).clamp(0.0, self.trials);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdateItem {
+    pub strategy: String,
+    pub success: bool,
+    #[serde(default)]
+    pub reward: Option<f64>,
+    #[serde(default)]
+    pub context_key: Option<String>,
+}
+
+impl BatchUpdateItem {
+    fn effective_reward(&self) -> Option<f64> {
+        match self.reward {
+            Some(r) if r.is_finite() => Some(r.clamp(0.0, 1.0)),
+            Some(_) => None,
+            None => Some(if self.success { 1.0 } else { 0.0 }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdateResult {
+    pub strategy: String,
+    pub context_key: Option<String>,
+    pub applied: bool,
+    pub reward: f64,
+    pub mean: f64,
+    pub confidence: f64,
+}
+
+/// Forced exploration on top of the deterministic IGE choice, so every arm
+/// keeps getting traffic for monitoring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ForcedExploration {
+    #[default]
+    Off,
+    /// Each candidate is picked with at least this probability.
+    ProbabilityFloor { min_probability: f64 },
+    /// Every `every_n`-th selection goes to the least recently selected arm.
+    RoundRobin { every_n: u32 },
+}
+
+impl ForcedExploration {
+    /// Parses `off`, `floor:<p>` or `round_robin:<n>`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("off") {
+            return Some(Self::Off);
+        }
+        let (mode, arg) = value.split_once(':')?;
+        match mode.trim() {
+            "floor" => {
+                let min_probability: f64 = arg.trim().parse().ok()?;
+                (min_probability > 0.0 && min_probability <= 1.0)
+                    .then_some(Self::ProbabilityFloor { min_probability })
+            }
+            "round_robin" => {
+                let every_n: u32 = arg.trim().parse().ok()?;
+                (every_n > 0).then_some(Self::RoundRobin { every_n })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn mode(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::ProbabilityFloor { .. } => "probability_floor",
+            Self::RoundRobin { .. } => "round_robin",
+        }
+    }
+
+    /// The policy shrunk by a session's exploration budget: the floor is
+    /// multiplied by the budget scale and round-robin turns come less often.
+    /// A zero budget turns exploration off.
+    pub fn scaled(&self, budget: &ExplorationBudget) -> Self {
+        match *self {
+            Self::Off => Self::Off,
+            Self::ProbabilityFloor { min_probability } => {
+                let min_probability = budget.scale_probability(min_probability);
+                if min_probability > 0.0 {
+                    Self::ProbabilityFloor { min_probability }
+                } else {
+                    Self::Off
+                }
+            }
+            Self::RoundRobin { every_n } => match budget.scale_interval(every_n) {
+                Some(every_n) => Self::RoundRobin { every_n },
+                None => Self::Off,
+            },
+        }
+    }
+
+    /// Share of selections the policy hands to exploration over `arms`
+    /// candidates. This is an upper bound on the forced rate, because an
+    /// exploratory pick that lands on the greedy arm is not counted as forced.
+    pub fn intended_share(&self, arms: usize) -> f64 {
+        match *self {
+            Self::Off => 0.0,
+            _ if arms < 2 => 0.0,
+            Self::ProbabilityFloor { min_probability } => {
+                (min_probability.clamp(0.0, 1.0) * arms as f64).min(1.0)
+            }
+            Self::RoundRobin { every_n } => {
+                if every_n == 0 {
+                    0.0
+                } else {
+                    1.0 / f64::from(every_n)
+                }
+            }
+        }
+    }
+}
+
+/// Garbage collection of per-context statistics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IgeGcConfig {
+    /// Context entries not updated for this many days are candidates.
+    pub max_age_days: i64,
+    /// Candidates with more trials than this are kept.
+    pub max_trials: f64,
+    /// Candidates whose mean is further than this from the prior are kept.
+    pub mean_tolerance: f64,
+    /// Minimum days between automatic runs.
+    pub interval_days: i64,
+}
+
+impl Default for IgeGcConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 30,
+            max_trials: 10.0,
+            mean_tolerance: 0.1,
+            interval_days: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgeGcReport {
+    /// Context x arm entries removed.
+    pub pruned_entries: usize,
+    /// Context keys left without any arm and removed.
+    pub pruned_contexts: usize,
+    /// Trials of the pruned entries, now represented by the global arms only.
+    pub merged_trials: f64,
+    pub remaining_entries: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgeSelection {
+    pub strategy: String,
+    /// The arm IGE would have picked on its own.
+    pub greedy: String,
+    /// True when the exploration policy overrode the greedy choice.
+    pub forced: bool,
+    /// True when the arm came from the probability floor's uniform draw,
+    /// even if it matches the greedy arm. Only these picks have a known
+    /// propensity (1 / candidates) for off-policy estimates.
+    pub randomized: bool,
+    /// Per-model selection counter at the time of this pick.
+    pub selection_index: u64,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IgeModel {
     global: HashMap<String, StrategyStats>,
     context: HashMap<String, HashMap<String, StrategyStats>>,
+    #[serde(default)]
+    selections: u64,
+    /// Arm -> value of `selections` when it was last picked.
+    #[serde(default)]
+    last_selected: HashMap<String, u64>,
+    /// Unix ms of the last garbage collection.
+    #[serde(default)]
+    last_gc: i64,
 }
 
 impl IgeModel {
@@ -103,20 +305,242 @@ impl IgeModel {
         scored.first().map(|(s, _)| s.clone())
     }
 
+    /// Select with a forced-exploration policy applied on top of
+    /// [`select_action`](Self::select_action). `draw` is a uniform sample in
+    /// `[0, 1)`; it is only used by the probability floor.
+    pub fn select_with_exploration(
+        &mut self,
+        candidates: &[String],
+        context_key: Option<&str>,
+        policy: &ForcedExploration,
+        draw: f64,
+    ) -> Option<IgeSelection> {
+        let greedy = self.select_action(candidates, context_key)?;
+        self.selections += 1;
+
+        let explored = match *policy {
+            ForcedExploration::Off => None,
+            ForcedExploration::ProbabilityFloor { min_probability } => {
+                let k = candidates.len() as f64;
+                let explore = (min_probability.clamp(0.0, 1.0) * k).min(1.0);
+                (draw < explore).then(|| {
+                    let idx = ((draw / explore) * k) as usize;
+                    candidates[idx.min(candidates.len() - 1)].clone()
+                })
+            }
+            ForcedExploration::RoundRobin { every_n } => (every_n > 0
+                && self.selections.is_multiple_of(u64::from(every_n)))
+            .then(|| self.least_recently_selected(candidates))
+            .flatten(),
+        };
+
+        let randomized =
+            explored.is_some() && matches!(policy, ForcedExploration::ProbabilityFloor { .. });
+        let strategy = explored.unwrap_or_else(|| greedy.clone());
+        self.last_selected.insert(strategy.clone(), self.selections);
+        Some(IgeSelection {
+            forced: strategy != greedy,
+            randomized,
+            strategy,
+            greedy,
+            selection_index: self.selections,
+        })
+    }
+
+    /// Selections made so far; the next one gets index `selection_count() + 1`.
+    pub fn selection_count(&self) -> u64 {
+        self.selections
+    }
+
+    /// Per-arm statistics across all contexts.
+    pub fn global_stats(&self) -> &HashMap<String, StrategyStats> {
+        &self.global
+    }
+
+    /// Per-arm statistics recorded under one context key.
+    pub fn context_stats(&self, context_key: &str) -> Option<&HashMap<String, StrategyStats>> {
+        self.context.get(context_key)
+    }
+
+    fn least_recently_selected(&self, candidates: &[String]) -> Option<String> {
+        candidates
+            .iter()
+            .min_by(|a, b| {
+                let la = self.last_selected.get(*a).copied().unwrap_or(0);
+                let lb = self.last_selected.get(*b).copied().unwrap_or(0);
+                la.cmp(&lb).then_with(|| a.cmp(b))
+            })
+            .cloned()
+    }
+
     pub fn update(&mut self, strategy: &str, reward: f64, context_key: Option<&str>) {
-        self.global
-            .entry(strategy.to_string())
-            .or_default()
-            .update(reward);
+        let now = chrono::Utc::now().timestamp_millis();
+        let global = self.global.entry(strategy.to_string()).or_default();
+        global.update(reward);
+        global.updated_at = now;
 
         if let Some(ck) = context_key {
-            self.context
+            let stats = self
+                .context
                 .entry(ck.to_string())
                 .or_default()
                 .entry(strategy.to_string())
-                .or_default()
-                .update(reward);
+                .or_default();
+            stats.update(reward);
+            stats.updated_at = now;
+        }
+    }
+
+    /// Prunes context statistics that were not updated for
+    /// `config.max_age_days` and are still near the prior.
+    ///
+    /// Every contextual update is also counted in the global arm, so the
+    /// pruned counts are already merged there; selection for that context
+    /// falls back to the global arm plus the prior. Entries saved before
+    /// update times were tracked are stamped with `now_ms` and get a full
+    /// `max_age_days` grace period.
+    pub fn gc(&mut self, config: &IgeGcConfig, now_ms: i64) -> IgeGcReport {
+        let cutoff = now_ms - config.max_age_days.max(0) * MS_PER_DAY;
+        let mut report = IgeGcReport::default();
+        self.context.retain(|_, arms| {
+            arms.retain(|_, stats| {
+                if stats.updated_at == 0 {
+                    stats.updated_at = now_ms;
+                }
+                let prune = stats.updated_at < cutoff && stats.is_near_prior(config);
+                if prune {
+                    report.pruned_entries += 1;
+                    report.merged_trials += stats.trials;
+                }
+                !prune
+            });
+            if arms.is_empty() {
+                report.pruned_contexts += 1;
+            }
+            !arms.is_empty()
+        });
+        report.remaining_entries = self.context.values().map(HashMap::len).sum();
+        self.last_gc = now_ms;
+        report
+    }
+
+    /// Runs [`gc`](Self::gc) when `config.interval_days` have passed since
+    /// the last run.
+    pub fn maybe_gc(&mut self, config: &IgeGcConfig, now_ms: i64) -> Option<IgeGcReport> {
+        (now_ms - self.last_gc >= config.interval_days.max(0) * MS_PER_DAY)
+            .then(|| self.gc(config, now_ms))
+    }
+
+    /// Number of context x arm entries currently stored.
+    pub fn context_entry_count(&self) -> usize {
+        self.context.values().map(HashMap::len).sum()
+    }
+
+    /// Global arm statistics as Beta(successes, failures) posteriors, whose
+    /// means match [`StrategyStats::mean`].
+    pub fn arm_posteriors(&self) -> HashMap<String, BetaPrior> {
+        self.global
+            .iter()
+            .map(|(arm, stats)| {
+                let posterior = BetaPrior {
+                    alpha: stats.successes.max(0.0),
+                    beta: (stats.trials - stats.successes).max(0.0),
+                };
+                (arm.clone(), posterior)
+            })
+            .collect()
+    }
+
+    /// Symmetric KL divergence between the global arm posteriors of two
+    /// models, averaged over arms, using the normal approximation given by
+    /// [`StrategyStats::mean`] and [`StrategyStats::variance`]. Arms missing
+    /// on one side compare against the prior.
+    pub fn posterior_divergence(&self, other: &IgeModel) -> f64 {
+        let arms: std::collections::HashSet<&String> =
+            self.global.keys().chain(other.global.keys()).collect();
+        if arms.is_empty() {
+            return 0.0;
+        }
+        let prior = StrategyStats::default();
+        let total: f64 = arms
+            .iter()
+            .map(|arm| {
+                let a = self.global.get(*arm).unwrap_or(&prior);
+                let b = other.global.get(*arm).unwrap_or(&prior);
+                0.5 * (normal_kl(a, b) + normal_kl(b, a))
+            })
+            .sum();
+        total / arms.len() as f64
+    }
+
+    /// Merge another copy of the same user's model. Both copies start from a
+    /// shared history, so statistics are not summed: each entry keeps the
+    /// side with more trials (the newer one on ties).
+    pub fn merge_from(&mut self, other: &IgeModel) {
+        merge_stats(&mut self.global, &other.global);
+        for (key, stats) in &other.context {
+            merge_stats(self.context.entry(key.clone()).or_default(), stats);
+        }
+        self.selections = self.selections.max(other.selections);
+        for (arm, &at) in &other.last_selected {
+            let entry = self.last_selected.entry(arm.clone()).or_insert(at);
+            *entry = (*entry).max(at);
+        }
+    }
+
+    pub fn batch_update(&mut self, items: &[BatchUpdateItem]) -> Vec<BatchUpdateResult> {
+        items
+            .iter()
+            .map(|item| {
+                let context_key = item.context_key.as_deref();
+                let reward = item.effective_reward();
+                if let Some(r) = reward {
+                    self.update(&item.strategy, r, context_key);
+                }
+                BatchUpdateResult {
+                    strategy: item.strategy.clone(),
+                    context_key: item.context_key.clone(),
+                    applied: reward.is_some(),
+                    reward: reward.unwrap_or(0.0),
+                    mean: self
+                        .global
+                        .get(&item.strategy)
+                        .map(|s| s.mean())
+                        .unwrap_or(0.5),
+                    confidence: self.get_confidence(&item.strategy, context_key),
+                }
+            })
+            .collect()
+    }
+
+    /// Second half of a two-part reward: the immediate reward counted at
+    /// decision time is moved towards the delayed outcome, so the final
+    /// contribution is `(1 - w) * immediate + w * delayed`. Returns false when
+    /// the strategy has no statistics to correct.
+    pub fn apply_delayed_correction(
+        &mut self,
+        strategy: &str,
+        context_key: Option<&str>,
+        immediate: f64,
+        delayed: f64,
+    ) -> bool {
+        if !immediate.is_finite() || !delayed.is_finite() {
+            return false;
+        }
+        let delta = RETENTION_REWARD_WEIGHT * (delayed.clamp(0.0, 1.0) - immediate.clamp(0.0, 1.0));
+
+        let Some(global) = self.global.get_mut(strategy) else {
+            return false;
+        };
+        global.correct(delta);
+
+        if let Some(stats) = context_key
+            .and_then(|ck| self.context.get_mut(ck))
+            .and_then(|m| m.get_mut(strategy))
+        {
+            stats.correct(delta);
         }
+        true
     }
 
     pub fn get_confidence(&self, strategy: &str, context_key: Option<&str>) -> f64 {
@@ -133,10 +557,72 @@ impl IgeModel {
     }
 }
 
+fn normal_kl(p: &StrategyStats, q: &StrategyStats) -> f64 {
+    let vp = p.variance().max(EPSILON);
+    let vq = q.variance().max(EPSILON);
+    let diff = p.mean() - q.mean();
+    0.5 * ((vq / vp).ln() + (vp + diff * diff) / vq - 1.0)
+}
+
+fn merge_stats(into: &mut HashMap<String, StrategyStats>, from: &HashMap<String, StrategyStats>) {
+    for (arm, theirs) in from {
+        match into.get(arm) {
+            Some(ours)
+                if ours.trials > theirs.trials
+                    || (ours.trials == theirs.trials && ours.updated_at >= theirs.updated_at) => {}
+            _ => {
+                into.insert(arm.clone(), theirs.clone());
+            }
+        }
+    }
+}
+
+impl MemoryFootprint for IgeModel {
+    fn memory_footprint(&self) -> usize {
+        let keys = |map: &HashMap<String, StrategyStats>| -> usize {
+            map.keys().map(|k| k.capacity()).sum()
+        };
+        std::mem::size_of::<Self>()
+            + hashmap_table_bytes(&self.global)
+            + keys(&self.global)
+            + hashmap_table_bytes(&self.context)
+            + self
+                .context
+                .iter()
+                .map(|(ctx, stats)| ctx.capacity() + hashmap_table_bytes(stats) + keys(stats))
+                .sum::<usize>()
+            + hashmap_table_bytes(&self.last_selected)
+            + self
+                .last_selected
+                .keys()
+                .map(|k| k.capacity())
+                .sum::<usize>()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_posterior_divergence_and_merge() {
+        let mut phone = IgeModel::new();
+        let mut desktop = IgeModel::new();
+        for _ in 0..20 {
+            phone.update("a", 1.0, Some("ctx"));
+            desktop.update("a", 0.0, None);
+        }
+        assert_eq!(phone.posterior_divergence(&phone.clone()), 0.0);
+        let before = phone.posterior_divergence(&desktop);
+        assert!(before > 1.0);
+
+        desktop.update("a", 0.0, None);
+        phone.merge_from(&desktop);
+        assert_eq!(phone.global["a"].trials, 21.0);
+        assert_eq!(phone.context["ctx"]["a"].trials, 20.0);
+        assert!(phone.posterior_divergence(&desktop) < EPSILON);
+    }
+
     #[test]
     fn test_cold_start() {
         let model = IgeModel::new();
@@ -166,10 +652,270 @@ mod tests {
         assert!(c1 > c0);
     }
 
+    #[test]
+    fn test_batch_update_matches_sequential_updates() {
+        let items = vec![
+            BatchUpdateItem {
+                strategy: "a".into(),
+                success: true,
+                reward: None,
+                context_key: Some("morning".into()),
+            },
+            BatchUpdateItem {
+                strategy: "a".into(),
+                success: false,
+                reward: Some(0.4),
+                context_key: None,
+            },
+            BatchUpdateItem {
+                strategy: "b".into(),
+                success: false,
+                reward: None,
+                context_key: Some("morning".into()),
+            },
+        ];
+
+        let mut batched = IgeModel::new();
+        let results = batched.batch_update(&items);
+
+        let mut sequential = IgeModel::new();
+        sequential.update("a", 1.0, Some("morning"));
+        sequential.update("a", 0.4, None);
+        sequential.update("b", 0.0, Some("morning"));
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.applied));
+        assert!((results[1].mean - 0.7).abs() < 1e-9);
+        assert!(
+            (batched.get_confidence("a", Some("morning"))
+                - sequential.get_confidence("a", Some("morning")))
+            .abs()
+                < 1e-12
+        );
+    }
+
+    #[test]
+    fn test_batch_update_skips_non_finite_reward() {
+        let mut model = IgeModel::new();
+        let results = model.batch_update(&[BatchUpdateItem {
+            strategy: "a".into(),
+            success: true,
+            reward: Some(f64::NAN),
+            context_key: None,
+        }]);
+        assert!(!results[0].applied);
+        assert!(!model.global.contains_key("a"));
+    }
+
     #[test]
     fn test_tie_break_by_key() {
         let model = IgeModel::new();
         let result = model.select_action(&["b".into(), "a".into()], None);
         assert_eq!(result, Some("a".into()));
     }
+
+    #[test]
+    fn test_delayed_correction_blends_rewards() {
+        let mut model = IgeModel::new();
+        model.update("a", 1.0, Some("ctx"));
+        assert!(model.apply_delayed_correction("a", Some("ctx"), 1.0, 0.0));
+
+        let expected = 1.0 - RETENTION_REWARD_WEIGHT;
+        assert!((model.global["a"].successes - expected).abs() < 1e-9);
+        assert!((model.context["ctx"]["a"].successes - expected).abs() < 1e-9);
+        assert_eq!(model.global["a"].trials, 1.0);
+
+        assert!(!model.apply_delayed_correction("missing", None, 1.0, 0.0));
+        assert!(!model.apply_delayed_correction("a", None, f64::NAN, 0.0));
+    }
+
+    #[test]
+    fn test_delayed_correction_stays_in_bounds() {
+        let mut model = IgeModel::new();
+        model.update("a", 0.0, None);
+        model.apply_delayed_correction("a", None, 1.0, 0.0);
+        assert_eq!(model.global["a"].successes, 0.0);
+    }
+
+    fn arms() -> Vec<String> {
+        vec!["a".into(), "b".into(), "c".into()]
+    }
+
+    #[test]
+    fn test_exploration_off_matches_greedy() {
+        let mut model = IgeModel::new();
+        let sel = model
+            .select_with_exploration(&arms(), None, &ForcedExploration::Off, 0.0)
+            .unwrap();
+        assert_eq!(sel.strategy, "a");
+        assert!(!sel.forced);
+        assert_eq!(sel.selection_index, 1);
+    }
+
+    #[test]
+    fn test_probability_floor_reaches_every_arm() {
+        let mut model = IgeModel::new();
+        for _ in 0..20 {
+            model.update("a", 1.0, None);
+        }
+        let policy = ForcedExploration::ProbabilityFloor {
+            min_probability: 0.1,
+        };
+        // explore region is [0, 0.3): one tenth per arm
+        let pick = |m: &mut IgeModel, draw| {
+            m.select_with_exploration(&arms(), None, &policy, draw)
+                .unwrap()
+        };
+        assert_eq!(pick(&mut model, 0.15).strategy, "b");
+        let c = pick(&mut model, 0.25);
+        assert_eq!(c.strategy, "c");
+        assert!(c.forced);
+        let a = pick(&mut model, 0.05);
+        assert_eq!(a.strategy, "a");
+        assert!(!a.forced);
+        assert!(a.randomized);
+        let greedy = pick(&mut model, 0.5);
+        assert!(!greedy.forced);
+        assert!(!greedy.randomized);
+    }
+
+    #[test]
+    fn test_round_robin_injects_stalest_arm() {
+        let mut model = IgeModel::new();
+        for _ in 0..20 {
+            model.update("a", 1.0, None);
+        }
+        let policy = ForcedExploration::RoundRobin { every_n: 3 };
+        let picks: Vec<IgeSelection> = (0..9)
+            .map(|_| {
+                model
+                    .select_with_exploration(&arms(), None, &policy, 0.0)
+                    .unwrap()
+            })
+            .collect();
+        let forced: Vec<&str> = picks
+            .iter()
+            .filter(|s| s.forced)
+            .map(|s| s.strategy.as_str())
+            .collect();
+        assert_eq!(forced, vec!["b", "c", "b"]);
+        assert_eq!(picks[2].strategy, "b");
+        assert_eq!(picks[5].strategy, "c");
+        assert!(!picks[6].forced);
+        assert!(picks.iter().all(|s| !s.randomized));
+    }
+
+    #[test]
+    fn test_parse_exploration_policy() {
+        assert_eq!(
+            ForcedExploration::parse("off"),
+            Some(ForcedExploration::Off)
+        );
+        assert_eq!(
+            ForcedExploration::parse("floor:0.05"),
+            Some(ForcedExploration::ProbabilityFloor {
+                min_probability: 0.05
+            })
+        );
+        assert_eq!(
+            ForcedExploration::parse("round_robin:20"),
+            Some(ForcedExploration::RoundRobin { every_n: 20 })
+        );
+        assert_eq!(ForcedExploration::parse("round_robin:0"), None);
+        assert_eq!(ForcedExploration::parse("floor:2"), None);
+        assert_eq!(ForcedExploration::parse("sometimes"), None);
+    }
+
+    #[test]
+    fn test_budget_scales_exploration_policy() {
+        let budget = danci_algo::exploration_budget(None, Some(11.5), 30);
+        let floor = ForcedExploration::ProbabilityFloor {
+            min_probability: 0.1,
+        };
+        match floor.scaled(&budget) {
+            ForcedExploration::ProbabilityFloor { min_probability } => {
+                assert!((min_probability - 0.045).abs() < 1e-12)
+            }
+            other => panic!("unexpected policy {other:?}"),
+        }
+        assert!((floor.intended_share(3) - 0.3).abs() < 1e-12);
+        assert_eq!(floor.intended_share(1), 0.0);
+
+        let round_robin = ForcedExploration::RoundRobin { every_n: 10 };
+        assert_eq!(
+            round_robin.scaled(&budget),
+            ForcedExploration::RoundRobin { every_n: 23 }
+        );
+        assert!((round_robin.intended_share(4) - 0.1).abs() < 1e-12);
+
+        let zero = ExplorationBudget {
+            scale: 0.0,
+            ..ExplorationBudget::full()
+        };
+        assert_eq!(floor.scaled(&zero), ForcedExploration::Off);
+        assert_eq!(round_robin.scaled(&zero), ForcedExploration::Off);
+    }
+
+    #[test]
+    fn test_gc_prunes_stale_near_prior_contexts_only() {
+        let day = MS_PER_DAY;
+        let now = 100 * day;
+        let mut model = IgeModel::new();
+        // Stale and near the prior: pruned.
+        model.update("a", 1.0, Some("stale"));
+        model.update("a", 0.0, Some("stale"));
+        // Stale but informative: kept.
+        for _ in 0..20 {
+            model.update("a", 1.0, Some("learned"));
+        }
+        // Recent: kept.
+        model.update("a", 1.0, Some("recent"));
+        for arms in model.context.values_mut() {
+            for stats in arms.values_mut() {
+                stats.updated_at = now - 60 * day;
+            }
+        }
+        model
+            .context
+            .get_mut("recent")
+            .unwrap()
+            .get_mut("a")
+            .unwrap()
+            .updated_at = now - day;
+        let global_trials = model.global["a"].trials;
+
+        let report = model.gc(&IgeGcConfig::default(), now);
+        assert_eq!(report.pruned_entries, 1);
+        assert_eq!(report.pruned_contexts, 1);
+        assert!((report.merged_trials - 2.0).abs() < 1e-9);
+        assert_eq!(report.remaining_entries, 2);
+        assert!(model.context_stats("stale").is_none());
+        assert!(model.context_stats("learned").is_some());
+        // Pruned evidence is still in the global arm.
+        assert!((model.global["a"].trials - global_trials).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_gc_grants_grace_period_to_legacy_entries() {
+        let mut model: IgeModel = serde_json::from_str(
+            r#"{"global":{"a":{"successes":1.0,"trials":2.0}},
+                "context":{"old":{"a":{"successes":1.0,"trials":2.0}}}}"#,
+        )
+        .unwrap();
+        let now = 400 * MS_PER_DAY;
+        let config = IgeGcConfig::default();
+        assert_eq!(model.gc(&config, now).pruned_entries, 0);
+        assert!(model.maybe_gc(&config, now + MS_PER_DAY / 2).is_none());
+        let later = model
+            .maybe_gc(&config, now + (config.max_age_days + 1) * MS_PER_DAY)
+            .expect("interval elapsed");
+        assert_eq!(later.pruned_entries, 1);
+        assert_eq!(model.context_entry_count(), 0);
+    }
+
+    #[test]
+    fn test_legacy_model_without_selection_state_deserializes() {
+        let model: IgeModel = serde_json::from_str(r#"{"global":{},"context":{}}"#).unwrap();
+        assert_eq!(model.selections, 0);
+    }
 }
@@ -10,5 +10,8 @@ pub use coldstart::ColdStartManager;
 pub use ensemble::EnsembleDecision;
 #[allow(unused_imports)]
 pub use heuristic::HeuristicLearner;
-pub use ige::IgeModel;
+pub use ige::{
+    BatchUpdateItem as IgeBatchUpdateItem, BatchUpdateResult as IgeBatchUpdateResult,
+    ForcedExploration, IgeGcConfig, IgeGcReport, IgeModel, IgeSelection, StrategyStats,
+};
 pub use swd::SwdModel;
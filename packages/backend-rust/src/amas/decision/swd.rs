@@ -8,8 +8,10 @@
 //! - max_history = 200
 //! - ε = 1e-6
 
+use danci_algo::footprint::vec_heap_bytes;
+use danci_algo::MemoryFootprint;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::amas::types::SwdRecommendation;
 
@@ -25,6 +27,18 @@ pub struct HistoryEntry {
     pub context: Vec<f64>,
     pub strategy: String,
     pub reward: f64,
+    /// Unix ms of the observation; 0 for entries stored before it was kept.
+    #[serde(default)]
+    pub observed_at: i64,
+}
+
+impl HistoryEntry {
+    fn same_observation(&self, other: &HistoryEntry) -> bool {
+        self.observed_at == other.observed_at
+            && self.strategy == other.strategy
+            && self.reward == other.reward
+            && self.context == other.context
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -95,16 +109,82 @@ impl SwdModel {
     }
 
     pub fn update(&mut self, context: Vec<f64>, strategy: String, reward: f64) {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        self.update_at(context, strategy, reward, now_ms);
+    }
+
+    pub fn update_at(
+        &mut self,
+        context: Vec<f64>,
+        strategy: String,
+        reward: f64,
+        observed_at: i64,
+    ) {
         self.history.push_back(HistoryEntry {
             context,
             strategy,
             reward,
+            observed_at,
         });
         while self.history.len() > MAX_HISTORY {
             self.history.pop_front();
         }
     }
 
+    /// Mean observed reward per strategy.
+    pub fn strategy_means(&self) -> HashMap<String, f64> {
+        let mut sums: HashMap<String, (f64, f64)> = HashMap::new();
+        for entry in &self.history {
+            let (sum, count) = sums.entry(entry.strategy.clone()).or_default();
+            *sum += entry.reward;
+            *count += 1.0;
+        }
+        sums.into_iter()
+            .map(|(strategy, (sum, count))| (strategy, sum / count))
+            .collect()
+    }
+
+    /// Root-mean-square difference of the per-strategy mean rewards of two
+    /// models. Strategies seen by only one side count with their full mean.
+    pub fn value_distance(&self, other: &SwdModel) -> f64 {
+        let ours = self.strategy_means();
+        let theirs = other.strategy_means();
+        let strategies: HashSet<&String> = ours.keys().chain(theirs.keys()).collect();
+        if strategies.is_empty() {
+            return 0.0;
+        }
+        let sum_sq: f64 = strategies
+            .iter()
+            .map(|s| {
+                let d =
+                    ours.get(*s).copied().unwrap_or(0.0) - theirs.get(*s).copied().unwrap_or(0.0);
+                d * d
+            })
+            .sum();
+        (sum_sq / strategies.len() as f64).sqrt()
+    }
+
+    /// Merge another copy of the same user's model. Entries are matched one
+    /// to one, so an observation both copies hold is kept once while equal
+    /// but distinct observations in one copy all survive. The union is put
+    /// back in observation order (legacy entries first, ties keeping this
+    /// copy's order) and trimmed to the newest [`MAX_HISTORY`].
+    pub fn merge_from(&mut self, other: &SwdModel) {
+        let mut merged: Vec<HistoryEntry> = self.history.iter().cloned().collect();
+        let mut unmatched: Vec<&HistoryEntry> = self.history.iter().collect();
+        for entry in &other.history {
+            match unmatched.iter().position(|e| e.same_observation(entry)) {
+                Some(i) => {
+                    unmatched.swap_remove(i);
+                }
+                None => merged.push(entry.clone()),
+            }
+        }
+        merged.sort_by_key(|e| e.observed_at);
+        let excess = merged.len().saturating_sub(MAX_HISTORY);
+        self.history = merged.into_iter().skip(excess).collect();
+    }
+
     pub fn get_confidence(&self, strategy: &str) -> f64 {
         let count = self
             .history
@@ -155,6 +235,18 @@ impl SwdModel {
     }
 }
 
+impl MemoryFootprint for SwdModel {
+    fn memory_footprint(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.history.capacity() * std::mem::size_of::<HistoryEntry>()
+            + self
+                .history
+                .iter()
+                .map(|e| vec_heap_bytes(&e.context) + e.strategy.capacity())
+                .sum::<usize>()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,6 +278,60 @@ mod tests {
         assert_eq!(model.history.len(), MAX_HISTORY);
     }
 
+    #[test]
+    fn test_merge_keeps_observation_order() {
+        let mut shared = SwdModel::new();
+        shared.update_at(vec![1.0], "a".into(), 1.0, 10);
+        let mut older = shared.clone();
+        let mut newer = shared;
+        for t in 0..MAX_HISTORY as i64 {
+            older.update_at(vec![0.0], "old".into(), 0.0, 100 + t);
+        }
+        newer.update_at(vec![1.0], "recent".into(), 1.0, 1_000);
+
+        newer.merge_from(&older);
+        assert_eq!(newer.history.len(), MAX_HISTORY);
+        // The oldest entries are the ones trimmed, not the newer copy's.
+        assert_eq!(newer.history.back().unwrap().strategy, "recent");
+        assert!(newer.history.iter().all(|e| e.strategy != "a"));
+        assert!(newer
+            .history
+            .iter()
+            .zip(newer.history.iter().skip(1))
+            .all(|(a, b)| a.observed_at <= b.observed_at));
+    }
+
+    #[test]
+    fn test_merge_keeps_equal_distinct_observations() {
+        let mut shared = SwdModel::new();
+        shared.update_at(vec![1.0], "a".into(), 1.0, 10);
+        let mut ours = shared.clone();
+        let mut theirs = shared;
+        ours.update_at(vec![1.0], "a".into(), 1.0, 20);
+        theirs.update_at(vec![1.0], "a".into(), 1.0, 30);
+        theirs.update_at(vec![1.0], "a".into(), 1.0, 30);
+
+        ours.merge_from(&theirs);
+        let times: Vec<i64> = ours.history.iter().map(|e| e.observed_at).collect();
+        assert_eq!(times, vec![10, 20, 30, 30]);
+
+        // Legacy entries without a time still match one to one.
+        let mut legacy = SwdModel::new();
+        legacy.update_at(vec![1.0], "a".into(), 1.0, 0);
+        legacy.update_at(vec![1.0], "a".into(), 1.0, 0);
+        let mut copy = legacy.clone();
+        copy.merge_from(&legacy);
+        assert_eq!(copy.history.len(), 2);
+    }
+
+    #[test]
+    fn test_legacy_history_deserializes() {
+        let model: SwdModel =
+            serde_json::from_str(r#"{"history":[{"context":[1.0],"strategy":"a","reward":0.5}]}"#)
+                .unwrap();
+        assert_eq!(model.history[0].observed_at, 0);
+    }
+
     #[test]
     fn test_zero_vector() {
         let model = SwdModel::new();
@@ -0,0 +1,446 @@
+//! Cross-device model divergence and reconciliation.
+//!
+//! A device that studied offline uploads its copy of the user's AMAS state.
+//! The copies are compared component by component:
+//! - IGE arm posteriors: symmetric KL divergence (normal approximation)
+//! - SWD value estimates: RMS difference of per-strategy mean reward
+//! - AIR ability: absolute difference of theta
+//! - Cognitive profile: largest difference among mem / speed / stability
+//!
+//! When both copies carry a vector clock, the clocks decide: a copy that has
+//! seen every write of the other wins as a whole, and copies with writes the
+//! other has not seen are a true conflict and are fully merged component by
+//! component. Copies from clients without clocks fall back to the divergence
+//! thresholds: below every threshold the sync stays incremental and the newer
+//! copy wins as a whole; past any threshold the copies are fully merged,
+//! since letting either copy win would discard real learning.
+//!
+//! Each report also records how the kept state differs from the server copy
+//! it replaces (arm posterior changes), so a bad overwrite can be spotted and
+//! rolled back.
+
+use danci_algo::snapshot_diff::{diff_thompson, SnapshotDiff};
+use danci_algo::{Causality, VectorClock};
+use serde::{Deserialize, Serialize};
+
+use crate::amas::decision::ige::IgeModel;
+use crate::amas::decision::swd::SwdModel;
+use crate::amas::modeling::air::AirUserState;
+use crate::amas::types::{BanditModel, PersistedAMASState};
+
+const AIR_STATE_KEY: &str = "air_user";
+/// Clock entry for writes made by the server itself.
+pub const SERVER_NODE: &str = "server";
+/// Most-changed arms recorded per diff.
+const DIFF_TOP_ARMS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DivergenceThresholds {
+    pub ige_kl: f64,
+    pub swd_distance: f64,
+    pub ability_delta: f64,
+    pub cognitive_delta: f64,
+    /// Difference in interaction counts.
+    pub interaction_gap: i32,
+}
+
+impl Default for DivergenceThresholds {
+    fn default() -> Self {
+        Self {
+            ige_kl: 0.5,
+            swd_distance: 0.2,
+            ability_delta: 0.5,
+            cognitive_delta: 0.2,
+            interaction_gap: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDivergence {
+    pub ige_kl: f64,
+    pub swd_distance: f64,
+    pub ability_delta: f64,
+    pub cognitive_delta: f64,
+    pub interaction_gap: i32,
+}
+
+impl ModelDivergence {
+    /// Names of the metrics past their threshold.
+    pub fn exceeded(&self, thresholds: &DivergenceThresholds) -> Vec<String> {
+        [
+            ("igeKl", self.ige_kl > thresholds.ige_kl),
+            ("swdDistance", self.swd_distance > thresholds.swd_distance),
+            (
+                "abilityDelta",
+                self.ability_delta > thresholds.ability_delta,
+            ),
+            (
+                "cognitiveDelta",
+                self.cognitive_delta > thresholds.cognitive_delta,
+            ),
+            (
+                "interactionGap",
+                self.interaction_gap > thresholds.interaction_gap,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, over)| *over)
+        .map(|(name, _)| name.to_string())
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncMode {
+    Incremental,
+    FullMerge,
+}
+
+impl SyncMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incremental => "incremental",
+            Self::FullMerge => "full_merge",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationReport {
+    pub device_id: String,
+    pub mode: SyncMode,
+    pub divergence: ModelDivergence,
+    pub thresholds: DivergenceThresholds,
+    pub exceeded: Vec<String>,
+    /// "server" or "device" for incremental syncs, "merged" otherwise.
+    pub resolution: String,
+    pub server_interactions: i32,
+    pub device_interactions: i32,
+    pub result_interactions: i32,
+    pub created_at: i64,
+    /// Server copy against the kept state; empty when nothing changed.
+    #[serde(default)]
+    pub diffs: Vec<SnapshotDiff>,
+    /// Device copy relative to the server copy; absent when either copy has
+    /// no clock and the thresholds decided.
+    #[serde(default)]
+    pub causality: Option<Causality>,
+}
+
+struct Components {
+    ige: IgeModel,
+    swd: SwdModel,
+    air: AirUserState,
+}
+
+impl Components {
+    fn of(state: &PersistedAMASState) -> Self {
+        let bandit = state.bandit_model.as_ref();
+        Self {
+            ige: bandit
+                .and_then(|b| b.thompson_params.clone())
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+            swd: bandit
+                .and_then(|b| b.linucb_state.clone())
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+            air: state
+                .algorithm_states
+                .as_ref()
+                .and_then(|s| s.get(AIR_STATE_KEY).cloned())
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+pub fn measure(server: &PersistedAMASState, device: &PersistedAMASState) -> ModelDivergence {
+    let ours = Components::of(server);
+    let theirs = Components::of(device);
+    let a = &server.user_state.cognitive;
+    let b = &device.user_state.cognitive;
+
+    ModelDivergence {
+        ige_kl: ours.ige.posterior_divergence(&theirs.ige),
+        swd_distance: ours.swd.value_distance(&theirs.swd),
+        ability_delta: (ours.air.theta - theirs.air.theta).abs(),
+        cognitive_delta: (a.mem - b.mem)
+            .abs()
+            .max((a.speed - b.speed).abs())
+            .max((a.stability - b.stability).abs()),
+        interaction_gap: (server.interaction_count - device.interaction_count).abs(),
+    }
+}
+
+/// Record a write made on the server, so a device still holding the previous
+/// copy is seen as behind (or concurrent) rather than newer.
+pub fn stamp_server_update(state: &mut PersistedAMASState) {
+    state
+        .sync_clock
+        .get_or_insert_with(VectorClock::new)
+        .tick(SERVER_NODE);
+}
+
+/// Compare the two copies, pick the sync mode and build the state to keep.
+pub fn reconcile(
+    server: &PersistedAMASState,
+    device: &PersistedAMASState,
+    device_id: &str,
+    thresholds: &DivergenceThresholds,
+    now_ms: i64,
+) -> (PersistedAMASState, ReconciliationReport) {
+    let divergence = measure(server, device);
+    let exceeded = divergence.exceeded(thresholds);
+    let causality = match (&device.sync_clock, &server.sync_clock) {
+        (Some(d), Some(s)) => Some(d.compare(s)),
+        _ => None,
+    };
+    let mode = match causality {
+        Some(Causality::Concurrent) => SyncMode::FullMerge,
+        Some(_) => SyncMode::Incremental,
+        None if exceeded.is_empty() => SyncMode::Incremental,
+        None => SyncMode::FullMerge,
+    };
+
+    let device_wins = match causality {
+        Some(c) => c == Causality::After,
+        None => device.last_updated > server.last_updated,
+    };
+    let (mut result, resolution) = match mode {
+        SyncMode::Incremental if device_wins => (device.clone(), "device"),
+        SyncMode::Incremental => (server.clone(), "server"),
+        SyncMode::FullMerge => (full_merge(server, device), "merged"),
+    };
+    result.user_id = server.user_id.clone();
+    result.last_updated = now_ms;
+    result.sync_clock = match (&server.sync_clock, &device.sync_clock) {
+        (Some(s), Some(d)) => Some(s.merged(d)),
+        (s, d) => s.clone().or_else(|| d.clone()),
+    };
+    if mode == SyncMode::FullMerge {
+        // The merged copy is a new write neither side has seen.
+        stamp_server_update(&mut result);
+    }
+
+    let ige_diff = diff_thompson(
+        &Components::of(server).ige.arm_posteriors(),
+        &Components::of(&result).ige.arm_posteriors(),
+        DIFF_TOP_ARMS,
+    );
+    let diffs: Vec<SnapshotDiff> = [ige_diff]
+        .into_iter()
+        .filter(|d| !d.is_unchanged())
+        .collect();
+    for diff in &diffs {
+        tracing::info!(
+            user_id = %server.user_id,
+            device_id,
+            resolution,
+            "model sync overwrites server snapshot: {}",
+            diff.summary()
+        );
+    }
+
+    let report = ReconciliationReport {
+        device_id: device_id.to_string(),
+        mode,
+        divergence,
+        thresholds: thresholds.clone(),
+        exceeded,
+        resolution: resolution.to_string(),
+        server_interactions: server.interaction_count,
+        device_interactions: device.interaction_count,
+        result_interactions: result.interaction_count,
+        created_at: now_ms,
+        diffs,
+        causality,
+    };
+    (result, report)
+}
+
+/// Start from the newer copy and fold in the other copy's bandit statistics
+/// and ability estimate.
+fn full_merge(server: &PersistedAMASState, device: &PersistedAMASState) -> PersistedAMASState {
+    let (newer, older) = if device.last_updated > server.last_updated {
+        (device, server)
+    } else {
+        (server, device)
+    };
+    let mut merged = newer.clone();
+    let mut ours = Components::of(newer);
+    let theirs = Components::of(older);
+
+    ours.ige.merge_from(&theirs.ige);
+    ours.swd.merge_from(&theirs.swd);
+    let air = merge_ability(&ours.air, &theirs.air);
+
+    merged.bandit_model = Some(BanditModel {
+        thompson_params: serde_json::to_value(&ours.ige).ok(),
+        linucb_state: serde_json::to_value(&ours.swd).ok(),
+        ..newer
+            .bandit_model
+            .clone()
+            .or_else(|| older.bandit_model.clone())
+            .unwrap_or_default()
+    });
+
+    let mut algorithm_states = older
+        .algorithm_states
+        .as_ref()
+        .and_then(|v| v.as_object())
+        .cloned()
+        .unwrap_or_default();
+    if let Some(newer_states) = newer.algorithm_states.as_ref().and_then(|v| v.as_object()) {
+        algorithm_states.extend(newer_states.clone());
+    }
+    if let Ok(v) = serde_json::to_value(&air) {
+        algorithm_states.insert(AIR_STATE_KEY.to_string(), v);
+    }
+    merged.algorithm_states = Some(serde_json::Value::Object(algorithm_states));
+
+    merged.interaction_count = server.interaction_count.max(device.interaction_count);
+    merged.cold_start_state = merged
+        .cold_start_state
+        .or_else(|| older.cold_start_state.clone());
+    merged.mastery_history = merged
+        .mastery_history
+        .or_else(|| older.mastery_history.clone());
+    merged
+}
+
+/// Precision-weighted average of two ability estimates.
+fn merge_ability(a: &AirUserState, b: &AirUserState) -> AirUserState {
+    let wa = a.fisher_info_sum.max(0.0);
+    let wb = b.fisher_info_sum.max(0.0);
+    let theta = if wa + wb > 0.0 {
+        (a.theta * wa + b.theta * wb) / (wa + wb)
+    } else {
+        (a.theta + b.theta) / 2.0
+    };
+    AirUserState {
+        theta,
+        fisher_info_sum: wa.max(wb),
+        response_count: a.response_count.max(b.response_count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amas::types::{StrategyParams, UserState};
+
+    fn state_with(ige: &IgeModel, theta: f64, count: i32, updated: i64) -> PersistedAMASState {
+        PersistedAMASState {
+            user_id: "u".into(),
+            user_state: UserState::default(),
+            bandit_model: Some(BanditModel {
+                thompson_params: serde_json::to_value(ige).ok(),
+                linucb_state: None,
+                last_action_idx: None,
+                feature_layout: None,
+            }),
+            current_strategy: StrategyParams::default(),
+            cold_start_state: None,
+            interaction_count: count,
+            last_updated: updated,
+            mastery_history: None,
+            ensemble_performance: None,
+            algorithm_states: Some(serde_json::json!({
+                "air_user": { "theta": theta, "fisher_info_sum": 4.0, "response_count": count }
+            })),
+            sync_clock: None,
+        }
+    }
+
+    #[test]
+    fn close_copies_sync_incrementally() {
+        let mut ige = IgeModel::new();
+        ige.update("a", 1.0, None);
+        let server = state_with(&ige, 0.1, 10, 1_000);
+        let device = state_with(&ige, 0.2, 12, 2_000);
+
+        let (result, report) = reconcile(
+            &server,
+            &device,
+            "phone",
+            &DivergenceThresholds::default(),
+            3_000,
+        );
+        assert_eq!(report.mode, SyncMode::Incremental);
+        assert_eq!(report.resolution, "device");
+        assert_eq!(result.interaction_count, 12);
+        assert_eq!(result.last_updated, 3_000);
+        assert!(report.diffs.is_empty());
+    }
+
+    #[test]
+    fn diverged_copies_are_fully_merged() {
+        let mut server_ige = IgeModel::new();
+        let mut device_ige = IgeModel::new();
+        for _ in 0..30 {
+            server_ige.update("a", 1.0, None);
+            device_ige.update("b", 0.0, None);
+        }
+        let server = state_with(&server_ige, 1.5, 30, 2_000);
+        let device = state_with(&device_ige, -0.5, 30, 1_000);
+
+        let (result, report) = reconcile(
+            &server,
+            &device,
+            "phone",
+            &DivergenceThresholds::default(),
+            3_000,
+        );
+        assert_eq!(report.mode, SyncMode::FullMerge);
+        assert!(report.exceeded.contains(&"igeKl".to_string()));
+        assert!(report.exceeded.contains(&"abilityDelta".to_string()));
+
+        let merged = Components::of(&result);
+        assert_eq!(merged.ige.global_stats().len(), 2);
+        assert!((merged.air.theta - 0.5).abs() < 1e-9);
+        assert_eq!(report.diffs.len(), 1);
+        assert_eq!(report.diffs[0].model, "thompson");
+        assert_eq!(report.diffs[0].added_arms, ["b"]);
+    }
+
+    #[test]
+    fn clocks_override_skewed_timestamps() {
+        let mut ige = IgeModel::new();
+        ige.update("a", 1.0, None);
+        let mut server = state_with(&ige, 0.1, 10, 1_000);
+        stamp_server_update(&mut server);
+        stamp_server_update(&mut server);
+
+        // Behind the server, but its wall clock runs ahead.
+        let mut device = state_with(&ige, 0.2, 12, 9_000);
+        let mut clock = VectorClock::new();
+        clock.tick(SERVER_NODE);
+        device.sync_clock = Some(clock.clone());
+        let thresholds = DivergenceThresholds::default();
+
+        let (result, report) = reconcile(&server, &device, "phone", &thresholds, 10_000);
+        assert_eq!(report.causality, Some(Causality::Before));
+        assert_eq!(report.resolution, "server");
+        assert_eq!(result.interaction_count, 10);
+
+        // Writes on both sides are a conflict even when the copies are close.
+        clock.tick("phone");
+        device.sync_clock = Some(clock);
+        let (result, report) = reconcile(&server, &device, "phone", &thresholds, 10_000);
+        assert_eq!(report.causality, Some(Causality::Concurrent));
+        assert_eq!(report.mode, SyncMode::FullMerge);
+        assert_eq!(report.resolution, "merged");
+        let kept = result.sync_clock.unwrap();
+        assert_eq!(
+            kept.compare(server.sync_clock.as_ref().unwrap()),
+            Causality::After
+        );
+        assert_eq!(kept.get("phone"), 1);
+    }
+}
@@ -1,4 +1,6 @@
 use chrono::Timelike;
+use danci_algo::rng::domains;
+use danci_algo::{FeatureLayout, MemoryFootprint, RngFactory};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
@@ -6,7 +8,11 @@ use tokio::sync::RwLock;
 
 use crate::amas::config::AMASConfig;
 use crate::amas::decision::{ColdStartManager, EnsembleDecision};
-use crate::amas::decision::{IgeModel, SwdModel};
+use crate::amas::decision::{
+    ForcedExploration, IgeBatchUpdateItem, IgeBatchUpdateResult, IgeGcConfig, IgeGcReport,
+    IgeModel, SwdModel,
+};
+use crate::amas::divergence::{self, DivergenceThresholds, ReconciliationReport};
 use crate::amas::memory::mdm::compute_quality as mdm_compute_quality;
 use crate::amas::memory::{
     compute_adaptive_mastery_with_history, MasteryContext, MasteryHistory, MdmState, MemoryEngine,
@@ -55,6 +61,21 @@ impl UserModels {
     }
 }
 
+impl UserModels {
+    /// (ige, swd, cold_start) estimated bytes
+    fn footprint_parts(&self) -> (usize, usize, usize) {
+        let cold_start = self
+            .cold_start
+            .as_ref()
+            .map_or(0, |_| std::mem::size_of::<ColdStartManager>());
+        (
+            self.ige.memory_footprint(),
+            self.swd.memory_footprint(),
+            cold_start,
+        )
+    }
+}
+
 fn restore_algorithm_state<T: Default + serde::de::DeserializeOwned>(
     algorithm_states: &Option<serde_json::Value>,
     key: &str,
@@ -66,12 +87,94 @@ fn restore_algorithm_state<T: Default + serde::de::DeserializeOwned>(
         .unwrap_or_default()
 }
 
+/// SWD context features, in vector order. When `swd_context` changes, add a
+/// new layout version here instead of editing this one so stored histories
+/// are detected and migrated rather than compared against the wrong shape.
+const SWD_LAYOUT_V1: [&str; 4] = ["attention", "fatigue", "motivation", "cognitive.mem"];
+
+pub fn swd_feature_layout() -> FeatureLayout {
+    FeatureLayout::new(1, &SWD_LAYOUT_V1)
+}
+
+fn swd_context(state: &UserState) -> Vec<f64> {
+    vec![
+        state.attention,
+        state.fatigue,
+        state.motivation,
+        state.cognitive.mem,
+    ]
+}
+
+/// Restore IGE/SWD models from persisted state
+fn restore_bandit_models(state: &PersistedAMASState) -> (IgeModel, SwdModel) {
+    let ige: IgeModel = state
+        .bandit_model
+        .as_ref()
+        .and_then(|b| b.thompson_params.as_ref())
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+
+    let swd: SwdModel = state
+        .bandit_model
+        .as_ref()
+        .and_then(|b| b.linucb_state.as_ref())
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+
+    let stored_layout = state.bandit_model.as_ref().map(|b| {
+        b.feature_layout
+            .clone()
+            .unwrap_or_else(|| FeatureLayout::new(1, &SWD_LAYOUT_V1).fingerprint())
+    });
+    let current_layout = swd_feature_layout().fingerprint();
+    match stored_layout {
+        Some(stored) if stored != current_layout => {
+            // SWD history holds raw context vectors; there is no prior to pad
+            // them with, so a layout change resets the history explicitly.
+            tracing::warn!(
+                user_id = %state.user_id,
+                from = %stored,
+                to = %current_layout,
+                strategy = "reset",
+                "SWD feature layout changed, migrating model state"
+            );
+            (ige, SwdModel::default())
+        }
+        _ => (ige, swd),
+    }
+}
+
+/// Uniform draw for IGE forced exploration. With a configured seed each
+/// (user, selection) pair gets its own stream, so a session replays exactly.
+fn exploration_draw(config: &AMASConfig, user_id: &str, selection_index: u64) -> f64 {
+    match config.rng_seed {
+        Some(seed) => RngFactory::new(seed)
+            .substream(domains::BANDIT_EXPLORATION, user_id, selection_index)
+            .next_f64(),
+        None => rand::random::<f64>(),
+    }
+}
+
+fn ige_context_key(state: &UserState) -> String {
+    format!(
+        "{}:{}",
+        (state.attention * 10.0).floor() as i32,
+        (state.fatigue * 10.0).floor() as i32
+    )
+}
+
+/// Lock discipline: each engine lock is taken in its own scope and released
+/// before the next one is acquired, and no guard is held across a persistence
+/// or database await. If two locks ever have to be held together they must be
+/// taken in field order: `config` -> `ensemble` -> `user_states` -> `user_models`.
+/// `tests/amas_engine_stress_tests.rs` hammers the public API concurrently to
+/// catch regressions.
 pub struct AMASEngine {
     config: Arc<RwLock<AMASConfig>>,
     persistence: Option<Arc<AMASPersistence>>,
     ensemble: Arc<RwLock<EnsembleDecision>>,
-    user_models: Arc<RwLock<HashMap<String, UserModels>>>,
     user_states: Arc<RwLock<HashMap<String, PersistedAMASState>>>,
+    user_models: Arc<RwLock<HashMap<String, UserModels>>>,
     monitor: Option<Arc<AMASMonitor>>,
     db_proxy: Option<Arc<DatabaseProxy>>,
 }
@@ -86,13 +189,14 @@ impl AMASEngine {
             .map(|proxy| Arc::new(AMASMonitor::new(Arc::clone(proxy))));
         let ensemble =
             EnsembleDecision::with_config(config.feature_flags.clone(), config.ensemble.clone());
+        crate::amas::version::update(&config);
 
         Self {
             config: Arc::new(RwLock::new(config)),
             persistence,
             ensemble: Arc::new(RwLock::new(ensemble)),
-            user_models: Arc::new(RwLock::new(HashMap::new())),
             user_states: Arc::new(RwLock::new(HashMap::new())),
+            user_models: Arc::new(RwLock::new(HashMap::new())),
             monitor,
             db_proxy,
         }
@@ -105,6 +209,7 @@ impl AMASEngine {
             new_config.ensemble.clone(),
         );
 
+        crate::amas::version::update(&new_config);
         {
             let mut config = self.config.write().await;
             *config = new_config;
@@ -126,6 +231,7 @@ impl AMASEngine {
         {
             let mut config = self.config.write().await;
             config.feature_flags = flags.clone();
+            crate::amas::version::update(&config);
         }
         {
             let mut ensemble = self.ensemble.write().await;
@@ -160,7 +266,7 @@ impl AMASEngine {
         let mut auc_state_to_save: Option<AucState> = None;
         let cold_start_result = if let Some(ref mut cs) = models.cold_start {
             if !cs.is_complete() {
-                let accuracy = if event.is_correct { 1.0 } else { 0.0 };
+                let accuracy = event.credit();
                 let signals = crate::amas::decision::coldstart::ColdStartSignals {
                     attention: new_user_state.attention,
                     motivation: new_user_state.motivation,
@@ -212,6 +318,12 @@ impl AMASEngine {
             .clone()
             .unwrap_or(state.current_strategy.clone());
 
+        let mut forced_exploration: Option<ForcedExplorationRecord> = None;
+        let mut exploration: Option<ExplorationRecord> = None;
+        let ige_exploration = match options.exploration_budget.as_ref() {
+            Some(budget) => config.ige_exploration.scaled(budget),
+            None => config.ige_exploration,
+        };
         let (new_strategy, candidates) = if let Some(ref cs_strategy) = cold_start_result {
             (cs_strategy.clone(), vec![])
         } else if !config.feature_flags.ensemble_enabled {
@@ -221,41 +333,59 @@ impl AMASEngine {
                 self.generate_strategy_candidates(&current_strategy, &new_user_state);
 
             // UMM IGE for strategy exploration
-            let ige_action = {
+            let ige_selection = {
                 let strategy_keys: Vec<String> = strategy_candidates
                     .iter()
                     .map(|s| format!("{:?}:{}", s.difficulty, s.batch_size))
                     .collect();
-                let context_key = Some(format!(
-                    "{}:{}",
-                    (new_user_state.attention * 10.0).floor() as i32,
-                    (new_user_state.fatigue * 10.0).floor() as i32
-                ));
+                let context_key = Some(ige_context_key(&new_user_state));
+                if config.ige_exploration != ForcedExploration::Off {
+                    exploration = Some(ExplorationRecord {
+                        mode: ige_exploration.mode().to_string(),
+                        scale: options.exploration_budget.as_ref().map_or(1.0, |b| b.scale),
+                        intended_share: ige_exploration.intended_share(strategy_keys.len()),
+                        propensity: None,
+                    });
+                }
                 track_algorithm!(
                     AlgorithmId::Ige,
-                    models
-                        .ige
-                        .select_action(&strategy_keys, context_key.as_deref())
-                        .and_then(|key| {
-                            strategy_candidates
-                                .iter()
-                                .find(|s| format!("{:?}:{}", s.difficulty, s.batch_size) == key)
-                                .cloned()
-                        })
+                    models.ige.select_with_exploration(
+                        &strategy_keys,
+                        context_key.as_deref(),
+                        &ige_exploration,
+                        exploration_draw(&config, user_id, models.ige.selection_count() + 1),
+                    )
                 )
             };
+            let ige_action = ige_selection.as_ref().and_then(|sel| {
+                strategy_candidates
+                    .iter()
+                    .find(|s| format!("{:?}:{}", s.difficulty, s.batch_size) == sel.strategy)
+                    .cloned()
+            });
+            if let Some(sel) = ige_selection.as_ref().filter(|sel| sel.forced) {
+                tracing::info!(
+                    user_id = %user_id,
+                    mode = ige_exploration.mode(),
+                    strategy = %sel.strategy,
+                    greedy = %sel.greedy,
+                    selection_index = sel.selection_index,
+                    "IGE forced exploration"
+                );
+                forced_exploration = Some(ForcedExplorationRecord {
+                    mode: ige_exploration.mode().to_string(),
+                    strategy: sel.strategy.clone(),
+                    greedy_strategy: sel.greedy.clone(),
+                    selection_index: sel.selection_index,
+                });
+            }
             let ige_confidence = ige_action.as_ref().map(|a| {
                 let key = format!("{:?}:{}", a.difficulty, a.batch_size);
                 models.ige.get_confidence(&key, None)
             });
 
             // UMM SWD for strategy decision
-            let swd_context_vec: Vec<f64> = vec![
-                new_user_state.attention,
-                new_user_state.fatigue,
-                new_user_state.motivation,
-                new_user_state.cognitive.mem,
-            ];
+            let swd_context_vec = swd_context(&new_user_state);
             let swd_action = {
                 let strategy_keys: Vec<String> = strategy_candidates
                     .iter()
@@ -281,7 +411,7 @@ impl AMASEngine {
             let swd_recommendation = models.swd.recommend_additional_count(&swd_context_vec);
 
             let ensemble = self.ensemble.read().await;
-            let (raw_strategy, candidates) = track_algorithm!(
+            let (mut raw_strategy, candidates) = track_algorithm!(
                 AlgorithmId::Heuristic,
                 ensemble.decide(
                     &new_user_state,
@@ -294,6 +424,17 @@ impl AMASEngine {
                 )
             );
 
+            // A forced pick has to be applied, otherwise the arm is never
+            // actually tried; the safety post-filter still runs on it. The
+            // same goes for a randomized pick that happens to match the
+            // greedy arm, or its logged propensity would not hold.
+            let randomized = ige_selection.as_ref().is_some_and(|sel| sel.randomized);
+            if forced_exploration.is_some() || randomized {
+                if let Some(ref forced) = ige_action {
+                    raw_strategy = forced.clone();
+                }
+            }
+
             let session_info =
                 options
                     .total_sessions
@@ -305,11 +446,51 @@ impl AMASEngine {
                 ensemble.post_filter(raw_strategy, &new_user_state, session_info.as_ref());
             filtered_strategy.swd_recommendation = swd_recommendation;
 
+            // The uniform draw picks a candidate, the estimators score its
+            // difficulty: P(difficulty) = candidates sharing it / candidates.
+            // A pick the post-filter moved off that difficulty is no longer
+            // randomized and logs no propensity.
+            if let (true, Some(picked), Some(record)) =
+                (randomized, ige_action.as_ref(), exploration.as_mut())
+            {
+                if picked.difficulty == filtered_strategy.difficulty {
+                    let same = strategy_candidates
+                        .iter()
+                        .filter(|s| s.difficulty == picked.difficulty)
+                        .count();
+                    record.propensity = Some(same as f64 / strategy_candidates.len() as f64);
+                }
+            }
+
             (filtered_strategy, candidates)
         };
 
         let reward = self.compute_reward(&event, &new_user_state, &options, &config);
 
+        // Two-part reward: count the immediate half now, correct it once the
+        // follow-up review shows whether the word was retained.
+        let reward_attribution = if config.feature_flags.amas_delayed_reward_enabled
+            && cold_start_result.is_none()
+            && !candidates.is_empty()
+            && !options.skip_update.unwrap_or(false)
+        {
+            let strategy_key = format!("{:?}:{}", new_strategy.difficulty, new_strategy.batch_size);
+            let context_key = ige_context_key(&new_user_state);
+            let immediate = (reward.value.clamp(-1.0, 1.0) + 1.0) / 2.0;
+            models
+                .ige
+                .update(&strategy_key, immediate, Some(&context_key));
+            Some(RewardAttribution {
+                decision_id: uuid::Uuid::new_v4().to_string(),
+                strategy: strategy_key,
+                context_key: Some(context_key),
+                immediate_reward: immediate,
+                decided_at: chrono::Utc::now().timestamp_millis(),
+            })
+        } else {
+            None
+        };
+
         if cold_start_result.is_none() {
             {
                 let mut ensemble = self.ensemble.write().await;
@@ -371,6 +552,8 @@ impl AMASEngine {
         // Load AIR user state for potential use in word mastery calculation
         let mut air_user_state_to_save: Option<AirUserState> = None;
 
+        let language = options.language_params.clone().unwrap_or_default();
+
         // Calculate interval using UMM (MDM + vocabulary specialization)
         let word_mastery_decision = event.word_id.as_ref().map(|wid| {
             let quality = mdm_compute_quality(
@@ -385,11 +568,17 @@ impl AMASEngine {
                 let mut air_user: AirUserState =
                     restore_algorithm_state(&state.algorithm_states, "air_user");
 
+                let default_beta = options.calibrated_beta.unwrap_or(0.0);
                 let (init_alpha, init_beta) = options
                     .word_state
                     .as_ref()
-                    .map(|ws| (ws.air_alpha.unwrap_or(1.0), ws.air_beta.unwrap_or(0.0)))
-                    .unwrap_or((1.0, 0.0));
+                    .map(|ws| {
+                        (
+                            ws.air_alpha.unwrap_or(1.0),
+                            ws.air_beta.unwrap_or(default_beta),
+                        )
+                    })
+                    .unwrap_or((1.0, default_beta));
 
                 let mut air_item = AirItemParams {
                     alpha: init_alpha,
@@ -502,13 +691,16 @@ impl AMASEngine {
                     prev_mastery: prev_retrievability,
                     new_mastery: new_retrievability,
                     prev_interval: ws.scheduled_days,
-                    new_interval: new_interval * new_strategy.interval_scale,
+                    new_interval: new_interval
+                        * new_strategy.interval_scale
+                        * language.interval_factor(),
                     quality: if event.is_correct { 3 } else { 1 },
                     stability: mdm.strength,
                     difficulty: 1.0 - mdm.consolidation,
                     retrievability: new_retrievability,
                     // Guess veto: if user marked as guess and answered correctly, deny mastery
-                    is_mastered: mastery_result.is_mastered
+                    is_mastered: language
+                        .is_mastered(mastery_result.score, mastery_result.threshold)
                         && !(event.is_guess && event.is_correct),
                     lapses: if event.is_correct {
                         ws.lapses
@@ -577,13 +769,16 @@ impl AMASEngine {
                     prev_mastery: 0.0,
                     new_mastery: new_retrievability,
                     prev_interval: 0.0,
-                    new_interval: new_interval * new_strategy.interval_scale,
+                    new_interval: new_interval
+                        * new_strategy.interval_scale
+                        * language.interval_factor(),
                     quality: if event.is_correct { 3 } else { 1 },
                     stability: mdm.strength,
                     difficulty: 1.0 - mdm.consolidation,
                     retrievability: new_retrievability,
                     // Guess veto: if user marked as guess and answered correctly, deny mastery
-                    is_mastered: mastery_result.is_mastered
+                    is_mastered: language
+                        .is_mastered(mastery_result.score, mastery_result.threshold)
                         && !(event.is_guess && event.is_correct),
                     lapses: if event.is_correct { 0 } else { 1 },
                     reps: 1,
@@ -634,15 +829,30 @@ impl AMASEngine {
         state.current_strategy = new_strategy.clone();
         state.interaction_count += 1;
         state.last_updated = chrono::Utc::now().timestamp_millis();
+        divergence::stamp_server_update(&mut state);
 
         if let Some(ref cs) = models.cold_start {
             state.cold_start_state = Some(cs.state().clone());
         }
 
+        if let Some(report) = models
+            .ige
+            .maybe_gc(&IgeGcConfig::default(), state.last_updated)
+            .filter(|r| r.pruned_entries > 0)
+        {
+            tracing::debug!(
+                user_id = %user_id,
+                pruned_entries = report.pruned_entries,
+                pruned_contexts = report.pruned_contexts,
+                "Pruned stale IGE context statistics"
+            );
+        }
+
         // Store IGE/SWD model state for persistence
         state.bandit_model = Some(crate::amas::types::BanditModel {
             thompson_params: serde_json::to_value(&models.ige).ok(),
             linucb_state: serde_json::to_value(&models.swd).ok(),
+            feature_layout: Some(swd_feature_layout().fingerprint()),
             last_action_idx: None,
         });
 
@@ -752,6 +962,9 @@ impl AMASEngine {
             objective_evaluation: Some(objective_evaluation),
             multi_objective_adjusted: None,
             algorithm_weights,
+            reward_attribution,
+            forced_exploration,
+            exploration,
         };
 
         // Record monitoring event
@@ -765,6 +978,135 @@ impl AMASEngine {
         Ok(result)
     }
 
+    /// Apply a finished session's IGE outcomes under a single model-map lock.
+    pub async fn batch_update_ige(
+        &self,
+        user_id: &str,
+        items: &[IgeBatchUpdateItem],
+    ) -> Vec<IgeBatchUpdateResult> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let (results, state) = self
+            .update_bandit_models(user_id, now, |models| {
+                (models.ige.batch_update(items), true)
+            })
+            .await;
+        if let Some(state) = state {
+            self.save_state(user_id, &state).await;
+        }
+        results
+    }
+
+    /// Prune stale IGE context statistics now and persist the smaller model.
+    pub async fn gc_ige(&self, user_id: &str, config: &IgeGcConfig) -> IgeGcReport {
+        let now = chrono::Utc::now().timestamp_millis();
+        let (report, state) = self
+            .update_bandit_models(user_id, now, |models| (models.ige.gc(config, now), true))
+            .await;
+        if let Some(state) = state {
+            self.save_state(user_id, &state).await;
+        }
+        report
+    }
+
+    /// Mutate a user's cached bandit models and write them back into the
+    /// cached state while holding `user_states` -> `user_models`, so two
+    /// concurrent updates for one user serialize on the latest state instead
+    /// of each writing back its own stale copy. `update` returns its result
+    /// and whether it changed anything; the updated state, if any, is returned
+    /// for the caller to persist after the locks are released.
+    async fn update_bandit_models<R>(
+        &self,
+        user_id: &str,
+        now: i64,
+        update: impl FnOnce(&mut UserModels) -> (R, bool),
+    ) -> (R, Option<PersistedAMASState>) {
+        let loaded = self.load_or_init_state(user_id).await;
+        let config = self.config.read().await.clone();
+
+        let mut states = self.user_states.write().await;
+        let state = states.entry(user_id.to_string()).or_insert(loaded);
+        let mut model_map = self.user_models.write().await;
+        let models = model_map.entry(user_id.to_string()).or_insert_with(|| {
+            let (ige, swd) = restore_bandit_models(state);
+            self.create_models_from_state(state, &config, ige, swd, state.cold_start_state.clone())
+        });
+        let (result, changed) = update(models);
+        if !changed {
+            return (result, None);
+        }
+        state.bandit_model = Some(crate::amas::types::BanditModel {
+            thompson_params: serde_json::to_value(&models.ige).ok(),
+            linucb_state: serde_json::to_value(&models.swd).ok(),
+            feature_layout: Some(swd_feature_layout().fingerprint()),
+            last_action_idx: state.bandit_model.as_ref().and_then(|b| b.last_action_idx),
+        });
+        state.last_updated = now;
+        divergence::stamp_server_update(state);
+        (result, Some(state.clone()))
+    }
+
+    async fn save_state(&self, user_id: &str, state: &PersistedAMASState) {
+        if let Some(ref persistence) = self.persistence {
+            if let Err(e) = persistence.save_state(state).await {
+                tracing::warn!(error = %e, user_id = %user_id, "Failed to save AMAS state");
+            }
+        }
+    }
+
+    /// Reconcile a device's offline copy of the user's state with the server
+    /// copy and make the result current.
+    pub async fn sync_device_state(
+        &self,
+        user_id: &str,
+        device_id: &str,
+        device_state: &PersistedAMASState,
+        thresholds: &DivergenceThresholds,
+    ) -> (PersistedAMASState, ReconciliationReport) {
+        let server_state = self.load_or_init_state(user_id).await;
+        let now = chrono::Utc::now().timestamp_millis();
+        let (state, report) =
+            divergence::reconcile(&server_state, device_state, device_id, thresholds, now);
+
+        // Cached models were built from the old state; rebuild them lazily.
+        self.user_models.write().await.remove(user_id);
+        {
+            let mut states = self.user_states.write().await;
+            states.insert(user_id.to_string(), state.clone());
+        }
+        if let Some(ref persistence) = self.persistence {
+            if let Err(e) = persistence.save_state(&state).await {
+                tracing::warn!(error = %e, user_id = %user_id, "Failed to save AMAS state");
+            }
+        }
+
+        (state, report)
+    }
+
+    /// Apply the delayed half of a two-part reward recorded at decision time.
+    pub async fn apply_delayed_reward(
+        &self,
+        user_id: &str,
+        attribution: &RewardAttribution,
+        delayed_reward: f64,
+    ) -> bool {
+        let now = chrono::Utc::now().timestamp_millis();
+        let (applied, state) = self
+            .update_bandit_models(user_id, now, |models| {
+                let applied = models.ige.apply_delayed_correction(
+                    &attribution.strategy,
+                    attribution.context_key.as_deref(),
+                    attribution.immediate_reward,
+                    delayed_reward,
+                );
+                (applied, applied)
+            })
+            .await;
+        if let Some(state) = state {
+            self.save_state(user_id, &state).await;
+        }
+        applied
+    }
+
     pub async fn get_user_state(&self, user_id: &str) -> Option<UserState> {
         {
             let states = self.user_states.read().await;
@@ -839,6 +1181,59 @@ impl AMASEngine {
         (states_count, models_count)
     }
 
+    /// Estimated memory of cached per-user states and models, with the
+    /// `top_n` largest users. Takes the two cache locks one after the other.
+    pub async fn memory_report(&self, top_n: usize) -> ModelMemoryReport {
+        let mut per_user: HashMap<String, UserMemoryUsage> = HashMap::new();
+        let mut report = ModelMemoryReport::default();
+
+        {
+            let states = self.user_states.read().await;
+            report.cached_states = states.len();
+            for (user_id, state) in states.iter() {
+                let bytes = state.memory_footprint();
+                report.state_bytes += bytes;
+                per_user.entry(user_id.clone()).or_default().state_bytes = bytes;
+            }
+        }
+        {
+            let models = self.user_models.read().await;
+            report.cached_models = models.len();
+            for (user_id, model) in models.iter() {
+                let (ige, swd, cold_start) = model.footprint_parts();
+                report.ige_bytes += ige;
+                report.swd_bytes += swd;
+                report.cold_start_bytes += cold_start;
+                let usage = per_user.entry(user_id.clone()).or_default();
+                usage.ige_bytes = ige;
+                usage.swd_bytes = swd;
+                usage.cold_start_bytes = cold_start;
+            }
+        }
+
+        report.total_bytes =
+            report.state_bytes + report.ige_bytes + report.swd_bytes + report.cold_start_bytes;
+
+        let mut users: Vec<UserMemoryUsage> = per_user
+            .into_iter()
+            .map(|(user_id, mut usage)| {
+                usage.total_bytes =
+                    usage.state_bytes + usage.ige_bytes + usage.swd_bytes + usage.cold_start_bytes;
+                usage.user_id = user_id;
+                usage
+            })
+            .collect();
+        users.sort_by(|a, b| {
+            b.total_bytes
+                .cmp(&a.total_bytes)
+                .then_with(|| a.user_id.cmp(&b.user_id))
+        });
+        users.truncate(top_n);
+        report.top_users = users;
+
+        report
+    }
+
     async fn load_or_init_state(&self, user_id: &str) -> PersistedAMASState {
         {
             let states = self.user_states.read().await;
@@ -899,6 +1294,7 @@ impl AMASEngine {
             mastery_history: None,
             ensemble_performance: None,
             algorithm_states: None,
+            sync_clock: None,
         };
 
         let mut states = self.user_states.write().await;
@@ -926,20 +1322,7 @@ impl AMASEngine {
             }
         }
 
-        // Restore IGE/SWD models from persisted state
-        let ige: IgeModel = state
-            .bandit_model
-            .as_ref()
-            .and_then(|b| b.thompson_params.as_ref())
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
-
-        let swd: SwdModel = state
-            .bandit_model
-            .as_ref()
-            .and_then(|b| b.linucb_state.as_ref())
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
+        let (ige, swd) = restore_bandit_models(state);
 
         let models = self.create_models_from_state(
             state,
@@ -999,7 +1382,7 @@ impl AMASEngine {
         let values = vec![
             rt_norm,
             dwell_norm,
-            if event.is_correct { 1.0 } else { 0.0 },
+            event.credit(),
             retry_norm,
             state.attention,
             state.fused_fatigue.unwrap_or(state.fatigue),
@@ -1052,7 +1435,7 @@ impl AMASEngine {
                 &mut adf_state,
                 &AdfFeatures {
                     rt_norm,
-                    accuracy: if event.is_correct { 1.0 } else { 0.0 },
+                    accuracy: event.credit(),
                     pause_count: event.pause_count as f64,
                     switch_count: event.switch_count as f64,
                     focus_loss: event
@@ -1075,7 +1458,7 @@ impl AMASEngine {
             tfm.update(
                 &mut tfm_state,
                 &CognitiveFatigueInput {
-                    error_rate_trend: if event.is_correct { -0.05 } else { 0.1 },
+                    error_rate_trend: 0.1 - 0.15 * event.credit(),
                     rt_increase_rate: rt_norm,
                     repeat_errors: event.retry_count,
                 },
@@ -1094,10 +1477,9 @@ impl AMASEngine {
         let fatigue = tfm_output.total;
 
         // BCP: Bayesian Cognitive Profiling
-        let recent_accuracy =
-            options
-                .recent_accuracy
-                .unwrap_or(if event.is_correct { 0.8 } else { 0.6 });
+        let recent_accuracy = options
+            .recent_accuracy
+            .unwrap_or(0.6 + 0.2 * event.credit());
         let error_variance = recent_accuracy * (1.0 - recent_accuracy);
 
         let bcp = BayesianCognitiveProfiler::default();
@@ -1107,7 +1489,7 @@ impl AMASEngine {
             bcp.update(
                 &mut bcp_state,
                 &BcpObservation {
-                    accuracy: if event.is_correct { 1.0 } else { 0.0 },
+                    accuracy: event.credit(),
                     speed: (1.0 - rt_norm).clamp(0.0, 1.0),
                     consistency: 1.0 - error_variance,
                 }
@@ -1211,6 +1593,7 @@ impl AMASEngine {
             .map(|h| MsmtReviewEvent {
                 timestamp_hours: now_hours - (h.seconds_ago as f64 / 3600.0),
                 is_correct: h.is_correct.unwrap_or(true),
+                credit: h.correctness,
             })
             .collect();
 
@@ -1244,7 +1627,7 @@ impl AMASEngine {
         _options: &ProcessOptions,
         config: &AMASConfig,
     ) -> Reward {
-        let accuracy_score = if event.is_correct { 1.0 } else { 0.0 };
+        let accuracy_score = event.credit();
 
         let speed_score = 1.0
             - (event.response_time as f64 / config.perception.max_response_time as f64).min(1.0);
@@ -1260,7 +1643,9 @@ impl AMASEngine {
 
         let reward_value = (reward_value * 2.0 - 1.0).clamp(-1.0, 1.0);
 
-        let reason = if event.is_correct {
+        let reason = if accuracy_score > 0.0 && accuracy_score < 1.0 {
+            "部分正确"
+        } else if event.is_correct {
             if speed_score > 0.7 {
                 "正确且快速回答"
             } else {
@@ -5,11 +5,13 @@
 //! - MSMT (Multi-Scale Memory Trace) - multi-scale exponential traces
 //! - R-Target (Recall Target) - dynamic retention target
 //! - Adaptive Mastery - personalized mastery decision
+//! - Passage - scheduling and credit assignment for multi-word passages
 //! - MemoryEngine - unified entry point for memory computations
 
 pub mod adaptive_mastery;
 pub mod mdm;
 pub mod msmt;
+pub mod passage;
 pub mod r_target;
 
 mod engine;
@@ -21,4 +23,8 @@ pub use adaptive_mastery::{
 pub use engine::{MemoryEngine, ShadowResult};
 pub use mdm::{compute_quality, MdmState};
 pub use msmt::{MsmtModel, ReviewEvent};
+pub use passage::{
+    assign_credit, member_credit, member_outcome, reschedule_member, schedule_passage,
+    MemberActivation, PassageMember, PassageSchedule, PassageScheduleConfig, WordCredit,
+};
 pub use r_target::RTargetCalculator;
@@ -9,6 +9,7 @@
 //! - max_history = 100
 //! - correct_weight = 1.0
 //! - incorrect_weight = 0.2
+//! - partial credit c interpolates: 0.2 + 0.8·c
 
 use serde::{Deserialize, Serialize};
 
@@ -25,6 +26,19 @@ const INCORRECT_WEIGHT: f64 = 0.2;
 pub struct ReviewEvent {
     pub timestamp_hours: f64,
     pub is_correct: bool,
+    /// Partial credit in [0, 1]; overrides `is_correct` when present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit: Option<f64>,
+}
+
+impl ReviewEvent {
+    fn weight(&self) -> f64 {
+        match self.credit.filter(|c| c.is_finite()) {
+            Some(c) => INCORRECT_WEIGHT + (CORRECT_WEIGHT - INCORRECT_WEIGHT) * c.clamp(0.0, 1.0),
+            None if self.is_correct => CORRECT_WEIGHT,
+            None => INCORRECT_WEIGHT,
+        }
+    }
 }
 
 pub struct MsmtModel;
@@ -33,12 +47,7 @@ impl MsmtModel {
     fn compute_trace(events: &[ReviewEvent], now_hours: f64, tau: f64) -> f64 {
         events.iter().fold(0.0, |acc, e| {
             let delta = (now_hours - e.timestamp_hours).max(0.0);
-            let weight = if e.is_correct {
-                CORRECT_WEIGHT
-            } else {
-                INCORRECT_WEIGHT
-            };
-            acc + weight * (-delta / tau).exp()
+            acc + e.weight() * (-delta / tau).exp()
         })
     }
 
@@ -81,6 +90,7 @@ mod tests {
         let events = vec![ReviewEvent {
             timestamp_hours: 0.0,
             is_correct: true,
+            credit: None,
         }];
         let p = MsmtModel::predict_recall(&events, 0.0);
         assert!(p > 0.5);
@@ -91,6 +101,7 @@ mod tests {
         let events = vec![ReviewEvent {
             timestamp_hours: 0.0,
             is_correct: true,
+            credit: None,
         }];
         let p0 = MsmtModel::predict_recall(&events, 0.0);
         let p24 = MsmtModel::predict_recall(&events, 24.0);
@@ -104,13 +115,40 @@ mod tests {
         let correct = vec![ReviewEvent {
             timestamp_hours: 0.0,
             is_correct: true,
+            credit: None,
         }];
         let incorrect = vec![ReviewEvent {
             timestamp_hours: 0.0,
             is_correct: false,
+            credit: None,
         }];
         let pc = MsmtModel::predict_recall(&correct, 0.0);
         let pi = MsmtModel::predict_recall(&incorrect, 0.0);
         assert!(pc > pi);
     }
+
+    #[test]
+    fn test_partial_credit_lies_between() {
+        let partial = vec![ReviewEvent {
+            timestamp_hours: 0.0,
+            is_correct: false,
+            credit: Some(0.6),
+        }];
+        let pp = MsmtModel::predict_recall(&partial, 0.0);
+        let pc = MsmtModel::predict_recall(
+            &[ReviewEvent {
+                credit: Some(1.0),
+                ..partial[0].clone()
+            }],
+            0.0,
+        );
+        let pi = MsmtModel::predict_recall(
+            &[ReviewEvent {
+                credit: Some(0.0),
+                ..partial[0].clone()
+            }],
+            0.0,
+        );
+        assert!(pi < pp && pp < pc);
+    }
 }
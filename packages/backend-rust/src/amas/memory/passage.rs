@@ -0,0 +1,276 @@
+//! Passage scheduling - composite items made of several target words
+//!
+//! A reading passage is shown as one item but memory is still tracked per
+//! word: each member's recall comes from its own MSMT trace, and the passage
+//! is due as soon as any member is due within `tolerance_hours`. This pulls
+//! slightly-early members forward instead of showing the same passage again
+//! a few hours later.
+//!
+//! One answer to the passage produces one reward. Credit assignment splits it
+//! across the members in proportion to how uncertain each word was
+//! (`1 - recall`), so the word the learner was least likely to know gets the
+//! most credit for a correct answer and the most blame for a wrong one.
+//! Rewards use the AMAS range [-1, 1].
+
+use serde::{Deserialize, Serialize};
+
+use super::mdm::MdmState;
+use super::msmt::{MsmtModel, ReviewEvent};
+
+/// Members below this share still receive some credit.
+const MIN_CREDIT_WEIGHT: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassageMember {
+    pub word_id: String,
+    /// Review history with timestamps relative to now (past = negative).
+    pub history: Vec<ReviewEvent>,
+    /// Hours until the stored next review (negative when overdue).
+    pub next_review_in_hours: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassageScheduleConfig {
+    /// Members due within this many hours make the passage due now.
+    pub tolerance_hours: f64,
+    /// Members without a stored next review are due below this recall.
+    pub recall_threshold: f64,
+}
+
+impl Default for PassageScheduleConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_hours: 12.0,
+            recall_threshold: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberActivation {
+    pub word_id: String,
+    pub recall: f64,
+    pub next_review_in_hours: Option<f64>,
+    pub due: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassageSchedule {
+    pub due: bool,
+    /// Earliest member review, used to order due passages.
+    pub next_review_in_hours: Option<f64>,
+    pub members: Vec<MemberActivation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordCredit {
+    pub word_id: String,
+    /// Share of the passage reward; shares sum to 1.
+    pub weight: f64,
+    pub reward: f64,
+}
+
+pub fn member_activation(
+    member: &PassageMember,
+    config: &PassageScheduleConfig,
+) -> MemberActivation {
+    let recall = if member.history.is_empty() {
+        0.0
+    } else {
+        MsmtModel::predict_recall(&member.history, 0.0)
+    };
+    let due = match member.next_review_in_hours {
+        Some(hours) => hours <= config.tolerance_hours,
+        None => recall < config.recall_threshold,
+    };
+    MemberActivation {
+        word_id: member.word_id.clone(),
+        recall,
+        next_review_in_hours: member.next_review_in_hours,
+        due,
+    }
+}
+
+pub fn schedule_passage(
+    members: &[PassageMember],
+    config: &PassageScheduleConfig,
+) -> PassageSchedule {
+    let members: Vec<MemberActivation> = members
+        .iter()
+        .map(|m| member_activation(m, config))
+        .collect();
+    let next_review_in_hours = members
+        .iter()
+        .filter_map(|m| m.next_review_in_hours)
+        .min_by(f64::total_cmp);
+    PassageSchedule {
+        due: members.iter().any(|m| m.due),
+        next_review_in_hours,
+        members,
+    }
+}
+
+/// Splits one passage reward across its members by `1 - recall`.
+pub fn assign_credit(members: &[MemberActivation], reward: f64) -> Vec<WordCredit> {
+    let raw: Vec<f64> = members
+        .iter()
+        .map(|m| (1.0 - m.recall.clamp(0.0, 1.0)).max(MIN_CREDIT_WEIGHT))
+        .collect();
+    let total: f64 = raw.iter().sum();
+    members
+        .iter()
+        .zip(raw)
+        .map(|(m, w)| {
+            let weight = if total > 0.0 { w / total } else { 0.0 };
+            WordCredit {
+                word_id: m.word_id.clone(),
+                weight,
+                reward: reward * weight,
+            }
+        })
+        .collect()
+}
+
+/// Review outcome to record for a member: a positive reward counts as a
+/// correct review for every word, a negative one only as a lapse for the
+/// words carrying at least an even share of the blame.
+pub fn member_outcome(credit: &WordCredit, member_count: usize, reward: f64) -> Option<bool> {
+    if reward >= 0.0 {
+        return Some(true);
+    }
+    let even_share = 1.0 / member_count.max(1) as f64;
+    (credit.weight + 1e-9 >= even_share).then_some(false)
+}
+
+/// Partial credit in [0, 1] recorded for a credited member: the passage
+/// reward mapped onto [0, 1] and scaled by how much of an even share the word
+/// carried, so a word the passage barely exercised stays close to neutral.
+pub fn member_credit(credit: &WordCredit, member_count: usize, reward: f64) -> f64 {
+    let share = (credit.weight * member_count.max(1) as f64).min(1.0);
+    (0.5 + 0.5 * reward.clamp(-1.0, 1.0) * share).clamp(0.0, 1.0)
+}
+
+/// Advance a member's MDM state with its credit as review quality and return
+/// the next interval in days, as the single-word scheduler does.
+pub fn reschedule_member(mdm: &mut MdmState, credit: f64, r_target: f64, now_ts: i64) -> f64 {
+    mdm.update(credit.clamp(0.0, 1.0), now_ts);
+    mdm.interval_for_target(r_target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(word_id: &str, hours_ago: &[f64], next: Option<f64>) -> PassageMember {
+        PassageMember {
+            word_id: word_id.to_string(),
+            history: hours_ago
+                .iter()
+                .map(|h| ReviewEvent {
+                    timestamp_hours: -h,
+                    is_correct: true,
+                    credit: None,
+                })
+                .collect(),
+            next_review_in_hours: next,
+        }
+    }
+
+    #[test]
+    fn test_due_when_any_member_within_tolerance() {
+        let config = PassageScheduleConfig::default();
+        let not_due = schedule_passage(
+            &[
+                member("a", &[1.0], Some(48.0)),
+                member("b", &[2.0], Some(30.0)),
+            ],
+            &config,
+        );
+        assert!(!not_due.due);
+        assert_eq!(not_due.next_review_in_hours, Some(30.0));
+
+        let due = schedule_passage(
+            &[
+                member("a", &[1.0], Some(48.0)),
+                member("b", &[2.0], Some(6.0)),
+            ],
+            &config,
+        );
+        assert!(due.due);
+        assert!(!due.members[0].due);
+        assert!(due.members[1].due);
+    }
+
+    #[test]
+    fn test_members_without_schedule_use_recall() {
+        let config = PassageScheduleConfig::default();
+        let fresh = schedule_passage(&[member("a", &[0.5, 2.0], None)], &config);
+        assert!(!fresh.due);
+        let unseen = schedule_passage(&[member("a", &[], None)], &config);
+        assert!(unseen.due);
+    }
+
+    #[test]
+    fn test_credit_favours_uncertain_words_and_sums_to_reward() {
+        let config = PassageScheduleConfig::default();
+        let schedule = schedule_passage(
+            &[
+                member("known", &[0.5, 1.0, 3.0], Some(48.0)),
+                member("shaky", &[200.0], Some(-2.0)),
+            ],
+            &config,
+        );
+        let credits = assign_credit(&schedule.members, 0.8);
+        assert!(credits[1].weight > credits[0].weight);
+        let total: f64 = credits.iter().map(|c| c.reward).sum();
+        assert!((total - 0.8).abs() < 1e-9);
+        assert!(credits.iter().all(|c| c.weight >= 0.0));
+    }
+
+    #[test]
+    fn test_wrong_answer_only_blames_uncertain_words() {
+        let config = PassageScheduleConfig::default();
+        let schedule = schedule_passage(
+            &[
+                member("known", &[0.5, 1.0, 3.0], Some(48.0)),
+                member("shaky", &[200.0], Some(-2.0)),
+            ],
+            &config,
+        );
+        let credits = assign_credit(&schedule.members, -1.0);
+        assert_eq!(member_outcome(&credits[0], 2, -1.0), None);
+        assert_eq!(member_outcome(&credits[1], 2, -1.0), Some(false));
+        assert_eq!(member_outcome(&credits[0], 2, 1.0), Some(true));
+    }
+
+    #[test]
+    fn test_member_credit_and_reschedule() {
+        let credits = [
+            WordCredit {
+                word_id: "heavy".to_string(),
+                weight: 0.8,
+                reward: 0.8,
+            },
+            WordCredit {
+                word_id: "light".to_string(),
+                weight: 0.2,
+                reward: 0.2,
+            },
+        ];
+        assert_eq!(member_credit(&credits[0], 2, 1.0), 1.0);
+        assert!((member_credit(&credits[1], 2, 1.0) - 0.7).abs() < 1e-9);
+        assert_eq!(member_credit(&credits[0], 2, -1.0), 0.0);
+
+        let mut passed = MdmState::default();
+        let mut failed = MdmState::default();
+        let long = reschedule_member(&mut passed, 1.0, 0.9, 1_000);
+        let short = reschedule_member(&mut failed, 0.0, 0.9, 1_000);
+        assert!(long > short);
+        assert_eq!(passed.last_review_ts, 1_000);
+    }
+}
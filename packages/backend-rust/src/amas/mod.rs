@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
+pub mod cohort_stats;
 pub mod config;
 pub mod decision;
+pub mod divergence;
 pub mod engine;
 pub mod memory;
 pub mod metrics;
@@ -10,6 +12,7 @@ pub mod modeling;
 pub mod monitoring;
 pub mod persistence;
 pub mod types;
+pub mod version;
 pub mod vocabulary;
 
 pub use config::AMASConfig;
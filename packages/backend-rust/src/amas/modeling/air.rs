@@ -78,6 +78,22 @@ pub struct AirUpdateResult {
     pub item_beta: f64,
 }
 
+/// Answers to one item from learners sharing an ability estimate.
+#[derive(Debug, Clone, Copy)]
+pub struct AbilityGroup {
+    pub theta: f64,
+    pub attempts: f64,
+    pub correct: f64,
+}
+
+/// Population estimate of an item's difficulty.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ItemCalibration {
+    pub beta: f64,
+    pub se: f64,
+    pub attempts: f64,
+}
+
 pub struct AdaptiveItemResponse {
     config: AirConfig,
 }
@@ -138,6 +154,46 @@ impl AdaptiveItemResponse {
         }
     }
 
+    /// MAP estimate of an item's difficulty from many learners' answers.
+    ///
+    /// Abilities are held fixed and `alpha = 1` (Rasch), so a high error rate
+    /// among strong learners yields a harder item than the same error rate
+    /// among weak ones. The normal prior around `prior_beta` keeps sparsely
+    /// answered items near their metadata difficulty.
+    pub fn calibrate_beta(
+        groups: &[AbilityGroup],
+        prior_beta: f64,
+        prior_sd: f64,
+    ) -> Option<ItemCalibration> {
+        let attempts: f64 = groups.iter().map(|g| g.attempts).sum();
+        if attempts <= 0.0 {
+            return None;
+        }
+        let prior_precision = 1.0 / prior_sd.max(1e-3).powi(2);
+        let mut beta = prior_beta.clamp(-3.0, 3.0);
+        let mut information = prior_precision;
+        for _ in 0..50 {
+            let mut gradient = -(beta - prior_beta) * prior_precision;
+            information = prior_precision;
+            for g in groups {
+                let p = Self::probability(g.theta, beta, 1.0);
+                gradient += g.attempts * p - g.correct;
+                information += g.attempts * p * (1.0 - p);
+            }
+            let step = gradient / information;
+            beta = (beta + step).clamp(-3.0, 3.0);
+            if step.abs() < 1e-6 {
+                break;
+            }
+        }
+
+        Some(ItemCalibration {
+            beta,
+            se: 1.0 / information.sqrt(),
+            attempts,
+        })
+    }
+
     pub fn confidence(fisher_info_sum: f64) -> f64 {
         if fisher_info_sum <= 0.0 {
             return 0.0;
@@ -257,6 +313,41 @@ mod tests {
         assert!((item_hard.beta - 1.0).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_calibrate_beta_follows_error_rate() {
+        let group = |correct| AbilityGroup {
+            theta: 0.0,
+            attempts: 100.0,
+            correct,
+        };
+        let easy = AdaptiveItemResponse::calibrate_beta(&[group(90.0)], 0.0, 1.0).unwrap();
+        let hard = AdaptiveItemResponse::calibrate_beta(&[group(10.0)], 0.0, 1.0).unwrap();
+        assert!(easy.beta < -1.5 && hard.beta > 1.5);
+        assert!(easy.se < 0.5);
+        assert!(AdaptiveItemResponse::calibrate_beta(&[], 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_calibrate_beta_adjusts_for_ability() {
+        let at = |theta| AbilityGroup {
+            theta,
+            attempts: 50.0,
+            correct: 25.0,
+        };
+        let strong = AdaptiveItemResponse::calibrate_beta(&[at(1.5)], 0.0, 1.0).unwrap();
+        let weak = AdaptiveItemResponse::calibrate_beta(&[at(-1.5)], 0.0, 1.0).unwrap();
+        assert!(strong.beta > 1.0 && weak.beta < -1.0);
+
+        // A few answers barely move the item away from its prior.
+        let sparse = AbilityGroup {
+            theta: 0.0,
+            attempts: 2.0,
+            correct: 0.0,
+        };
+        let prior_bound = AdaptiveItemResponse::calibrate_beta(&[sparse], -1.0, 0.5).unwrap();
+        assert!(prior_bound.beta < 0.0);
+    }
+
     #[test]
     fn test_roundtrip_serialization() {
         let user = AirUserState {
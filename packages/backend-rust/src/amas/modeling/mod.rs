@@ -10,7 +10,10 @@ pub mod tfm;
 pub mod vark;
 
 pub use adf::{AdfConfig, AdfFeatures, AdfState, AttentionDynamicsFilter};
-pub use air::{AdaptiveItemResponse, AirConfig, AirItemParams, AirResponse, AirUserState};
+pub use air::{
+    AbilityGroup, AdaptiveItemResponse, AirConfig, AirItemParams, AirResponse, AirUserState,
+    ItemCalibration,
+};
 pub use auc::{ActiveUserClassifier, AucConfig, AucOutput, AucState, ProbeResponse, UserType};
 pub use bcp::{BayesianCognitiveProfiler, BcpConfig, BcpObservation, BcpOutput, BcpState};
 pub use mds::{MdsConfig, MdsEvent, MotivationDynamics};
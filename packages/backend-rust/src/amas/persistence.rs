@@ -86,6 +86,12 @@ impl AMASPersistence {
 
         let algorithm_states: Option<serde_json::Value> = user_state_row.algorithm_states.clone();
 
+        let sync_clock = get_amas_user_model(&self.db_proxy, user_id, "sync_clock")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|m| serde_json::from_value(m.parameters).ok());
+
         Some(PersistedAMASState {
             user_id: user_id.to_string(),
             user_state,
@@ -97,6 +103,7 @@ impl AMASPersistence {
             mastery_history,
             ensemble_performance,
             algorithm_states,
+            sync_clock,
         })
     }
 
@@ -195,6 +202,21 @@ impl AMASPersistence {
                 .map_err(|e| e.to_string())?;
         }
 
+        if let Some(ref clock) = state.sync_clock {
+            let model = AmasUserModel {
+                id: format!("{}:sync_clock", state.user_id),
+                user_id: state.user_id.clone(),
+                model_type: "sync_clock".to_string(),
+                parameters: serde_json::to_value(clock).unwrap_or_default(),
+                version: 1,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                updated_at: chrono::Utc::now().to_rfc3339(),
+            };
+            insert_amas_user_model_tx(&mut tx, &model)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
         self.save_strategy_snapshot_tx(&mut tx, state).await?;
 
         let count_model = AmasUserModel {
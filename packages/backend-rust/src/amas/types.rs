@@ -503,6 +503,20 @@ pub struct RawEvent {
     pub device_type: Option<String>,
     #[serde(default)]
     pub is_guess: bool,
+    /// Fractional correctness in [0, 1] for partially correct answers
+    /// (e.g. cloze blanks with the right stem); `is_correct` is used when absent.
+    #[serde(default)]
+    pub correctness: Option<f64>,
+}
+
+impl RawEvent {
+    /// Correctness used for reward shaping and model updates.
+    pub fn credit(&self) -> f64 {
+        self.correctness
+            .filter(|c| c.is_finite())
+            .map(|c| c.clamp(0.0, 1.0))
+            .unwrap_or(if self.is_correct { 1.0 } else { 0.0 })
+    }
 }
 
 impl Default for RawEvent {
@@ -525,6 +539,7 @@ impl Default for RawEvent {
             is_quit: false,
             device_type: None,
             is_guess: false,
+            correctness: None,
         }
     }
 }
@@ -728,6 +743,54 @@ pub struct ProcessResult {
     pub objective_evaluation: Option<ObjectiveEvaluation>,
     pub multi_objective_adjusted: Option<bool>,
     pub algorithm_weights: Option<AlgorithmWeights>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward_attribution: Option<RewardAttribution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forced_exploration: Option<ForcedExplorationRecord>,
+    /// Exploration policy in effect for this decision after the session's
+    /// budget was applied; `None` when IGE did not select.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exploration: Option<ExplorationRecord>,
+}
+
+/// Strategy chosen by the forced-exploration policy instead of IGE's own
+/// pick. Analytics should leave these decisions out of strategy comparisons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForcedExplorationRecord {
+    pub mode: String,
+    pub strategy: String,
+    pub greedy_strategy: String,
+    pub selection_index: u64,
+}
+
+/// Exploration budget applied to one IGE selection, logged with the
+/// decision so analytics can compare intended and realised exploration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplorationRecord {
+    pub mode: String,
+    /// Budget scale in [0, 1]; 1 when the session had no budget.
+    pub scale: f64,
+    /// Share of selections the scaled policy hands to exploration.
+    pub intended_share: f64,
+    /// Probability that the uniform draw picked the logged difficulty. Set
+    /// only for randomized picks that survived the safety post-filter; the
+    /// off-policy estimators use these decisions and nothing else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub propensity: Option<f64>,
+}
+
+/// Immediate half of a two-part reward, kept until the follow-up review
+/// outcome arrives and the delayed correction can be applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardAttribution {
+    pub decision_id: String,
+    pub strategy: String,
+    pub context_key: Option<String>,
+    pub immediate_reward: f64,
+    pub decided_at: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -771,6 +834,9 @@ pub struct SessionStats {
 pub struct WordReviewHistory {
     pub seconds_ago: i64,
     pub is_correct: Option<bool>,
+    /// Partial credit of the review, when it was not simply right or wrong.
+    #[serde(default)]
+    pub correctness: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -849,6 +915,16 @@ pub struct ProcessOptions {
     pub recent_word_ids: Option<Vec<String>>,
     #[serde(default)]
     pub context_history: Option<Vec<ContextEntryInput>>,
+    /// Parameters for the word's language pair; `None` uses the global defaults.
+    #[serde(default)]
+    pub language_params: Option<danci_algo::LanguageParams>,
+    /// Population-calibrated IRT difficulty of the word, used as the AIR item
+    /// difficulty until the learner has an estimate of their own.
+    #[serde(default)]
+    pub calibrated_beta: Option<f64>,
+    /// Session exploration budget; scales the forced-exploration policy.
+    #[serde(default)]
+    pub exploration_budget: Option<danci_algo::ExplorationBudget>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -886,6 +962,10 @@ pub struct BanditModel {
     pub thompson_params: Option<serde_json::Value>,
     pub linucb_state: Option<serde_json::Value>,
     pub last_action_idx: Option<usize>,
+    /// Fingerprint of the SWD context layout `linucb_state` was built with.
+    /// Missing on snapshots written before fingerprints were recorded.
+    #[serde(default)]
+    pub feature_layout: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -904,4 +984,61 @@ pub struct PersistedAMASState {
     pub ensemble_performance: Option<crate::amas::decision::ensemble::PerformanceTracker>,
     #[serde(default)]
     pub algorithm_states: Option<serde_json::Value>,
+    /// Writes seen per replica (device ids and `server`); missing on copies
+    /// from clients that predate conflict detection.
+    #[serde(default)]
+    pub sync_clock: Option<danci_algo::VectorClock>,
+}
+
+/// Estimated memory held by one user in the engine caches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMemoryUsage {
+    pub user_id: String,
+    pub total_bytes: usize,
+    pub state_bytes: usize,
+    pub ige_bytes: usize,
+    pub swd_bytes: usize,
+    pub cold_start_bytes: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelMemoryReport {
+    pub cached_states: usize,
+    pub cached_models: usize,
+    pub total_bytes: usize,
+    pub state_bytes: usize,
+    pub ige_bytes: usize,
+    pub swd_bytes: usize,
+    pub cold_start_bytes: usize,
+    /// Largest users first
+    pub top_users: Vec<UserMemoryUsage>,
+}
+
+impl danci_algo::MemoryFootprint for PersistedAMASState {
+    fn memory_footprint(&self) -> usize {
+        use danci_algo::footprint::{hashmap_table_bytes, json_heap_bytes};
+        use std::mem::size_of;
+
+        let json = |v: &Option<serde_json::Value>| v.as_ref().map_or(0, json_heap_bytes);
+        let bandit = self
+            .bandit_model
+            .as_ref()
+            .map_or(0, |m| json(&m.thompson_params) + json(&m.linucb_state));
+        let mastery = self.mastery_history.as_ref().map_or(0, |h| {
+            h.attempts.capacity() * size_of::<crate::amas::memory::MasteryAttempt>()
+        });
+        let ensemble = self.ensemble_performance.as_ref().map_or(0, |p| {
+            hashmap_table_bytes(&p.algorithms)
+                + p.algorithms.keys().map(|k| k.capacity()).sum::<usize>()
+        });
+
+        size_of::<Self>()
+            + self.user_id.capacity()
+            + bandit
+            + mastery
+            + ensemble
+            + json(&self.algorithm_states)
+    }
 }
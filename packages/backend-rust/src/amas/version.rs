@@ -0,0 +1,45 @@
+//! Algorithm build in effect for this process: the `danci-algo` version and
+//! git hash plus a fingerprint of the active AMAS config.
+//!
+//! The engine refreshes it whenever its config changes; model snapshots and
+//! decision records are stamped with it on write.
+
+use std::sync::RwLock;
+
+use danci_algo::build_info::AlgorithmBuild;
+
+use crate::amas::config::AMASConfig;
+
+static CURRENT: RwLock<Option<AlgorithmBuild>> = RwLock::new(None);
+
+pub fn update(config: &AMASConfig) {
+    let build = AlgorithmBuild::for_config(config);
+    if let Ok(mut current) = CURRENT.write() {
+        *current = Some(build);
+    }
+}
+
+/// Falls back to the default config's fingerprint before any engine exists.
+pub fn current() -> AlgorithmBuild {
+    CURRENT
+        .read()
+        .ok()
+        .and_then(|current| current.clone())
+        .unwrap_or_else(|| AlgorithmBuild::for_config(&AMASConfig::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_tracks_config() {
+        let default = AlgorithmBuild::for_config(&AMASConfig::default());
+        let mut changed = AMASConfig::default();
+        changed.feature_flags.ensemble_enabled = !changed.feature_flags.ensemble_enabled;
+        let changed = AlgorithmBuild::for_config(&changed);
+
+        assert_eq!(default.version_label(), changed.version_label());
+        assert_ne!(default.config_fingerprint, changed.config_fingerprint);
+    }
+}
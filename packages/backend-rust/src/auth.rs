@@ -104,6 +104,13 @@ pub async fn verify_request_token_cached(
     Ok(user)
 }
 
+/// User ID of a correctly signed, unexpired token without the session lookup.
+/// Only for attributing requests (e.g. usage counters), never for access control.
+pub fn token_user_id(token: &str) -> Option<String> {
+    let secret = std::env::var("JWT_SECRET").ok()?;
+    verify_jwt_hs256(token, &secret).ok().map(|c| c.user_id)
+}
+
 #[derive(Debug, Clone)]
 struct JwtClaims {
     user_id: String,
@@ -1,4 +1,5 @@
 mod event_bus;
+mod pagination;
 mod redis_event_bridge;
 
 pub use event_bus::{
@@ -6,4 +7,5 @@ pub use event_bus::{
     ForgettingRiskPayload, LearningEvent, RewardDistributedPayload, SessionEndedPayload,
     SessionStartedPayload, StrategyAdjustedPayload, UserStateUpdatedPayload, WordMasteredPayload,
 };
+pub use pagination::{PageInfo, Pagination, QueryParamRejection, SortField, SortFilter, SortOrder};
 pub use redis_event_bridge::{RedisEventBridge, RedisEventError};
@@ -0,0 +1,293 @@
+//! Shared query-string conventions for list endpoints.
+//!
+//! [`Pagination`] reads `page` and `pageSize`, with `limit` and `offset`
+//! accepted as aliases for endpoints that predate the convention. Sizes are
+//! clamped into `1..=MAX`; values that are not integers are rejected.
+//!
+//! [`SortFilter`] reads `sortBy` and `sortOrder`. Each route names its
+//! sortable columns through a [`SortField`] type, so an unknown `sortBy` is
+//! rejected instead of silently falling back or reaching SQL.
+
+use std::collections::HashMap;
+
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use axum::http::{StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+use crate::response::json_error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryParamRejection {
+    pub param: &'static str,
+    pub message: String,
+}
+
+impl IntoResponse for QueryParamRejection {
+    fn into_response(self) -> Response {
+        json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            format!("{}: {}", self.param, self.message),
+        )
+        .into_response()
+    }
+}
+
+fn query_params(uri: &Uri) -> HashMap<String, String> {
+    Query::<HashMap<String, String>>::try_from_uri(uri)
+        .map(|Query(params)| params)
+        .unwrap_or_default()
+}
+
+fn parse_int(
+    params: &HashMap<String, String>,
+    param: &'static str,
+) -> Result<Option<i64>, QueryParamRejection> {
+    match params.get(param).map(|v| v.trim()) {
+        None | Some("") => Ok(None),
+        Some(raw) => raw
+            .parse::<i64>()
+            .map(Some)
+            .map_err(|_| QueryParamRejection {
+                param,
+                message: "必须是整数".to_string(),
+            }),
+    }
+}
+
+/// Page window for a list endpoint with a default and maximum page size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination<const DEFAULT: i64 = 20, const MAX: i64 = 100> {
+    pub page: i64,
+    pub page_size: i64,
+    pub offset: i64,
+    /// Size the client asked for before clamping.
+    pub requested_page_size: Option<i64>,
+}
+
+impl<const DEFAULT: i64, const MAX: i64> Pagination<DEFAULT, MAX> {
+    pub fn from_uri(uri: &Uri) -> Result<Self, QueryParamRejection> {
+        let params = query_params(uri);
+        let requested_page_size = match parse_int(&params, "pageSize")? {
+            Some(size) => Some(size),
+            None => parse_int(&params, "limit")?,
+        };
+        let page_size = requested_page_size.unwrap_or(DEFAULT).clamp(1, MAX);
+
+        let (page, offset) = match parse_int(&params, "page")? {
+            Some(page) => {
+                let page = page.max(1);
+                (page, (page - 1).saturating_mul(page_size))
+            }
+            None => {
+                let offset = parse_int(&params, "offset")?.unwrap_or(0).max(0);
+                (offset / page_size + 1, offset)
+            }
+        };
+
+        Ok(Self {
+            page,
+            page_size,
+            offset,
+            requested_page_size,
+        })
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.page_size
+    }
+
+    /// Whether the requested size, if any, was already within `1..=MAX`.
+    pub fn within_bounds(&self) -> bool {
+        self.requested_page_size
+            .is_none_or(|size| (1..=MAX).contains(&size))
+    }
+
+    pub fn total_pages(&self, total: i64) -> i64 {
+        (total.max(0) + self.page_size - 1) / self.page_size
+    }
+
+    pub fn info(&self, total: i64) -> PageInfo {
+        PageInfo {
+            page: self.page,
+            page_size: self.page_size,
+            total,
+            total_pages: self.total_pages(total),
+        }
+    }
+}
+
+#[async_trait]
+impl<S, const DEFAULT: i64, const MAX: i64> FromRequestParts<S> for Pagination<DEFAULT, MAX>
+where
+    S: Send + Sync,
+{
+    type Rejection = QueryParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_uri(&parts.uri)
+    }
+}
+
+/// Pagination block returned next to a page of results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub page: i64,
+    pub page_size: i64,
+    pub total: i64,
+    pub total_pages: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Sortable columns of one list endpoint.
+pub trait SortField: Copy + Send + Sync + 'static {
+    /// Accepted `sortBy` values; matching ignores case and underscores.
+    const FIELDS: &'static [(&'static str, Self)];
+    const DEFAULT: Self;
+    const DEFAULT_ORDER: SortOrder = SortOrder::Desc;
+
+    fn parse(raw: &str) -> Option<Self> {
+        let wanted: String = raw
+            .trim()
+            .chars()
+            .filter(|c| *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        Self::FIELDS
+            .iter()
+            .find(|(name, _)| name.to_ascii_lowercase() == wanted)
+            .map(|(_, field)| *field)
+    }
+}
+
+/// Validated `sortBy` / `sortOrder` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortFilter<F: SortField> {
+    pub field: F,
+    pub order: SortOrder,
+}
+
+impl<F: SortField> SortFilter<F> {
+    pub fn from_uri(uri: &Uri) -> Result<Self, QueryParamRejection> {
+        let params = query_params(uri);
+        let field = match params.get("sortBy").map(|v| v.trim()) {
+            None | Some("") => F::DEFAULT,
+            Some(raw) => F::parse(raw).ok_or_else(|| QueryParamRejection {
+                param: "sortBy",
+                message: format!(
+                    "可选值为 {}",
+                    F::FIELDS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })?,
+        };
+        let order = match params
+            .get("sortOrder")
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") => F::DEFAULT_ORDER,
+            Some("asc") => SortOrder::Asc,
+            Some("desc") => SortOrder::Desc,
+            Some(_) => {
+                return Err(QueryParamRejection {
+                    param: "sortOrder",
+                    message: "可选值为 asc, desc".to_string(),
+                })
+            }
+        };
+        Ok(Self { field, order })
+    }
+}
+
+#[async_trait]
+impl<S, F> FromRequestParts<S> for SortFilter<F>
+where
+    S: Send + Sync,
+    F: SortField,
+{
+    type Rejection = QueryParamRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_uri(&parts.uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Field {
+        CreatedAt,
+        Name,
+    }
+
+    impl SortField for Field {
+        const FIELDS: &'static [(&'static str, Self)] =
+            &[("createdAt", Field::CreatedAt), ("name", Field::Name)];
+        const DEFAULT: Self = Field::CreatedAt;
+    }
+
+    fn uri(query: &str) -> Uri {
+        format!("/items?{query}").parse().unwrap()
+    }
+
+    #[test]
+    fn test_pagination_defaults_bounds_and_aliases() {
+        let p = Pagination::<20, 100>::from_uri(&uri("")).unwrap();
+        assert_eq!((p.page, p.page_size, p.offset), (1, 20, 0));
+
+        let p = Pagination::<20, 100>::from_uri(&uri("page=3&pageSize=500")).unwrap();
+        assert_eq!((p.page, p.page_size, p.offset), (3, 100, 200));
+        assert!(!p.within_bounds());
+
+        let p = Pagination::<20, 100>::from_uri(&uri("limit=10&offset=25")).unwrap();
+        assert_eq!((p.page, p.page_size, p.offset), (3, 10, 25));
+        assert!(p.within_bounds());
+
+        let p = Pagination::<20, 100>::from_uri(&uri("page=0&pageSize=0")).unwrap();
+        assert_eq!((p.page, p.page_size, p.offset), (1, 1, 0));
+        assert_eq!(p.info(5).total_pages, 5);
+
+        let err = Pagination::<20, 100>::from_uri(&uri("page=abc")).unwrap_err();
+        assert_eq!(err.param, "page");
+    }
+
+    #[test]
+    fn test_sort_filter_whitelist() {
+        let s = SortFilter::<Field>::from_uri(&uri("")).unwrap();
+        assert_eq!((s.field, s.order), (Field::CreatedAt, SortOrder::Desc));
+
+        let s = SortFilter::<Field>::from_uri(&uri("sortBy=created_at&sortOrder=ASC")).unwrap();
+        assert_eq!((s.field, s.order), (Field::CreatedAt, SortOrder::Asc));
+
+        let err = SortFilter::<Field>::from_uri(&uri("sortBy=password")).unwrap_err();
+        assert_eq!(err.param, "sortBy");
+        assert!(err.message.contains("createdAt, name"));
+
+        let err = SortFilter::<Field>::from_uri(&uri("sortBy=name&sortOrder=up")).unwrap_err();
+        assert_eq!(err.param, "sortOrder");
+    }
+}
@@ -6,6 +6,9 @@ use thiserror::Error;
 pub struct DbConfig {
     pub primary_url: String,
     pub health_check: HealthCheckConfig,
+    pub pool: PoolConfig,
+    pub schema_drift: SchemaDriftConfig,
+    pub replicas: ReplicaConfig,
 }
 
 impl DbConfig {
@@ -17,6 +20,9 @@ impl DbConfig {
         Ok(Self {
             primary_url,
             health_check: HealthCheckConfig::from_env(),
+            pool: PoolConfig::from_env(),
+            schema_drift: SchemaDriftConfig::from_env(),
+            replicas: ReplicaConfig::from_env(),
         })
     }
 }
@@ -48,6 +54,97 @@ impl HealthCheckConfig {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    /// Smoothed acquire latency above which reads are offloaded; zero disables.
+    pub offload_latency: Duration,
+    pub offload_cooldown: Duration,
+}
+
+impl PoolConfig {
+    fn from_env() -> Self {
+        let max_connections = env_u32("DB_POOL_MAX_CONNECTIONS", 10).max(1);
+        let min_connections = env_u32("DB_POOL_MIN_CONNECTIONS", 0).min(max_connections);
+        let acquire_timeout_ms = env_u64("DB_POOL_ACQUIRE_TIMEOUT_MS", 5000);
+        let offload_latency_ms = env_u64("DB_READ_OFFLOAD_LATENCY_MS", 200);
+        let offload_cooldown_ms = env_u64("DB_READ_OFFLOAD_COOLDOWN_MS", 30000);
+
+        Self {
+            max_connections,
+            min_connections,
+            acquire_timeout: Duration::from_millis(acquire_timeout_ms),
+            offload_latency: Duration::from_millis(offload_latency_ms),
+            offload_cooldown: Duration::from_millis(offload_cooldown_ms),
+        }
+    }
+
+    pub fn read_offload_enabled(&self) -> bool {
+        !self.offload_latency.is_zero()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SchemaDriftConfig {
+    /// Time between drift checks after the one at startup; zero checks only
+    /// at startup.
+    pub interval: Duration,
+    /// Refuse dual-write to tables the last check found drifted.
+    pub block_dual_write: bool,
+}
+
+impl SchemaDriftConfig {
+    fn from_env() -> Self {
+        let interval_ms = env_u64("DB_SCHEMA_DRIFT_INTERVAL_MS", 3_600_000);
+        let block_dual_write = std::env::var("DB_SCHEMA_DRIFT_BLOCK_DUAL_WRITE")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        Self {
+            interval: Duration::from_millis(interval_ms),
+            block_dual_write,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicaConfig {
+    /// Connection URLs of the read replicas; empty sends every read to the
+    /// primary.
+    pub urls: Vec<String>,
+    pub max_connections: u32,
+    /// Replication lag a read tolerates unless the caller asks for less or
+    /// more.
+    pub max_staleness: Duration,
+}
+
+impl ReplicaConfig {
+    fn from_env() -> Self {
+        let urls = std::env::var("DATABASE_REPLICA_URLS")
+            .map(|value| parse_url_list(&value))
+            .unwrap_or_default();
+        let max_connections = env_u32("DB_REPLICA_POOL_MAX_CONNECTIONS", 10).max(1);
+        let max_staleness_ms = env_u64("DB_REPLICA_MAX_STALENESS_MS", 5000);
+
+        Self {
+            urls,
+            max_connections,
+            max_staleness: Duration::from_millis(max_staleness_ms),
+        }
+    }
+}
+
+fn parse_url_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 #[derive(Debug, Error)]
 pub enum DbConfigError {
     #[error("Missing required env var: {key}")]
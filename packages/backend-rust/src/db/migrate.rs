@@ -234,6 +234,158 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrationError> {
             "052_close_duplicate_active_sessions",
             include_str!("../../sql/052_close_duplicate_active_sessions.sql"),
         ),
+        (
+            "053_reward_attribution",
+            include_str!("../../sql/053_reward_attribution.sql"),
+        ),
+        (
+            "054_user_policy_rules",
+            include_str!("../../sql/054_user_policy_rules.sql"),
+        ),
+        (
+            "055_word_search_trigram",
+            include_str!("../../sql/055_word_search_trigram.sql"),
+        ),
+        ("056_webhooks", include_str!("../../sql/056_webhooks.sql")),
+        (
+            "057_feature_flags",
+            include_str!("../../sql/057_feature_flags.sql"),
+        ),
+        (
+            "058_decision_forced_exploration",
+            include_str!("../../sql/058_decision_forced_exploration.sql"),
+        ),
+        (
+            "059_idempotency_keys",
+            include_str!("../../sql/059_idempotency_keys.sql"),
+        ),
+        (
+            "060_vacation_periods",
+            include_str!("../../sql/060_vacation_periods.sql"),
+        ),
+        (
+            "061_simulation_jobs",
+            include_str!("../../sql/061_simulation_jobs.sql"),
+        ),
+        (
+            "062_user_daily_stats",
+            include_str!("../../sql/062_user_daily_stats.sql"),
+        ),
+        (
+            "063_language_codes",
+            include_str!("../../sql/063_language_codes.sql"),
+        ),
+        (
+            "064_user_data_keys",
+            include_str!("../../sql/064_user_data_keys.sql"),
+        ),
+        (
+            "065_algorithm_config_drafts",
+            include_str!("../../sql/065_algorithm_config_drafts.sql"),
+        ),
+        (
+            "066_progress_report_jobs",
+            include_str!("../../sql/066_progress_report_jobs.sql"),
+        ),
+        ("067_passages", include_str!("../../sql/067_passages.sql")),
+        (
+            "068_word_relations",
+            include_str!("../../sql/068_word_relations.sql"),
+        ),
+        (
+            "069_word_difficulty_calibration",
+            include_str!("../../sql/069_word_difficulty_calibration.sql"),
+        ),
+        (
+            "070_amas_sync_reports",
+            include_str!("../../sql/070_amas_sync_reports.sql"),
+        ),
+        (
+            "071_notification_delivery",
+            include_str!("../../sql/071_notification_delivery.sql"),
+        ),
+        (
+            "072_request_ids",
+            include_str!("../../sql/072_request_ids.sql"),
+        ),
+        (
+            "073_session_reward_summaries",
+            include_str!("../../sql/073_session_reward_summaries.sql"),
+        ),
+        (
+            "074_wordbook_licenses",
+            include_str!("../../sql/074_wordbook_licenses.sql"),
+        ),
+        ("075_api_usage", include_str!("../../sql/075_api_usage.sql")),
+        (
+            "076_hint_policies",
+            include_str!("../../sql/076_hint_policies.sql"),
+        ),
+        (
+            "077_scim_provisioning",
+            include_str!("../../sql/077_scim_provisioning.sql"),
+        ),
+        (
+            "078_placement_tests",
+            include_str!("../../sql/078_placement_tests.sql"),
+        ),
+        (
+            "079_word_state_sync_clocks",
+            include_str!("../../sql/079_word_state_sync_clocks.sql"),
+        ),
+        ("080_due_queue", include_str!("../../sql/080_due_queue.sql")),
+        (
+            "081_algorithm_version",
+            include_str!("../../sql/081_algorithm_version.sql"),
+        ),
+        (
+            "082_wordbook_content_version",
+            include_str!("../../sql/082_wordbook_content_version.sql"),
+        ),
+        (
+            "083_session_objective",
+            include_str!("../../sql/083_session_objective.sql"),
+        ),
+        (
+            "084_user_timeline",
+            include_str!("../../sql/084_user_timeline.sql"),
+        ),
+        (
+            "085_experiment_guardrails",
+            include_str!("../../sql/085_experiment_guardrails.sql"),
+        ),
+        (
+            "086_language_frequency_priors",
+            include_str!("../../sql/086_language_frequency_priors.sql"),
+        ),
+        (
+            "087_decision_exploration_budget",
+            include_str!("../../sql/087_decision_exploration_budget.sql"),
+        ),
+        (
+            "088_backfill_word_book_progress",
+            include_str!("../../sql/088_backfill_word_book_progress.sql"),
+        ),
+        (
+            "089_review_trace_credit",
+            include_str!("../../sql/089_review_trace_credit.sql"),
+        ),
+        (
+            "090_decision_propensity",
+            include_str!("../../sql/090_decision_propensity.sql"),
+        ),
+        (
+            "091_feature_flag_version",
+            include_str!("../../sql/091_feature_flag_version.sql"),
+        ),
+        (
+            "092_maintenance_state",
+            include_str!("../../sql/092_maintenance_state.sql"),
+        ),
+        (
+            "093_session_selection",
+            include_str!("../../sql/093_session_selection.sql"),
+        ),
     ];
 
     let mut applied_count = 0;
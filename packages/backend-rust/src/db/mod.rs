@@ -1,32 +1,51 @@
 pub mod config;
 pub mod migrate;
 pub mod operations;
+pub mod pending_writes;
+pub mod pool_metrics;
+pub mod replica;
+pub mod schema_drift;
+pub mod schema_registry;
+pub mod snapshot_crypto;
 pub mod sqlite_primary;
 pub mod sqlite_schema;
 pub mod state_machine;
 
 mod health_monitor;
 
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
+use sqlx::pool::PoolConnection;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use thiserror::Error;
 use tokio::sync::RwLock;
 
 use crate::db::config::{DbConfig, DbConfigError};
 use crate::db::health_monitor::{HealthCheckResult, HealthCheckSnapshot, HealthTracker};
+use crate::db::pending_writes::{EnqueueOutcome, SqlitePendingWriteStore, WritePriority};
+use crate::db::pool_metrics::{PoolMonitor, PoolStats};
+use crate::db::replica::{ReplicaSet, ReplicaStatus};
+use crate::db::schema_drift::{SchemaDriftMonitor, SchemaDriftReport};
+use crate::db::schema_registry::{SchemaRegistry, SchemaRegistryError};
 
 use crate::db::state_machine::{DatabaseState, DatabaseStateMachine};
 
+/// Queued writes replayed per health-monitor tick after a recovery.
+const PENDING_FLUSH_BATCH: u32 = 200;
+
 #[derive(Clone)]
 pub struct DatabaseProxy {
     config: DbConfig,
     pool: PgPool,
     health: Arc<RwLock<HealthTracker>>,
     state_machine: Arc<RwLock<DatabaseStateMachine>>,
+    pool_monitor: Arc<PoolMonitor>,
+    schema_drift: Arc<SchemaDriftMonitor>,
+    replicas: Arc<ReplicaSet>,
 }
 
 impl DatabaseProxy {
@@ -34,8 +53,9 @@ impl DatabaseProxy {
         let config = DbConfig::from_env()?;
 
         let pool = PgPoolOptions::new()
-            .max_connections(10)
-            .acquire_timeout(Duration::from_secs(5))
+            .max_connections(config.pool.max_connections)
+            .min_connections(config.pool.min_connections)
+            .acquire_timeout(config.pool.acquire_timeout)
             .connect(&config.primary_url)
             .await
             .map_err(DbInitError::Sqlx)?;
@@ -45,16 +65,30 @@ impl DatabaseProxy {
             .await
             .map_err(DbInitError::Migration)?;
 
+        let schema_drift = Arc::new(SchemaDriftMonitor::new(
+            SchemaRegistry::load()?,
+            config.schema_drift.block_dual_write,
+        ));
+
+        let replicas = Arc::new(ReplicaSet::from_config(
+            &config.replicas,
+            &config.health_check,
+        ));
+
         let proxy = Arc::new(Self {
             health: Arc::new(RwLock::new(HealthTracker::new(config.health_check.clone()))),
             state_machine: Arc::new(RwLock::new(DatabaseStateMachine::new(
                 DatabaseState::Normal,
             ))),
+            pool_monitor: Arc::new(PoolMonitor::new(config.pool.clone())),
+            schema_drift,
+            replicas,
             config,
             pool,
         });
 
         proxy.start_health_monitor();
+        proxy.start_schema_drift_monitor();
 
         Ok(proxy)
     }
@@ -88,20 +122,173 @@ impl DatabaseProxy {
         None
     }
 
+    /// Checks out a primary connection through the pool monitor, so acquire
+    /// latency and waiters reflect real traffic. The repository layer
+    /// (`db::operations`) runs its queries on these connections.
+    pub async fn acquire(&self) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        self.pool_monitor.acquire(&self.pool).await
+    }
+
+    /// Starts a primary transaction on an instrumented connection.
+    pub async fn begin(&self) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+        Transaction::begin(self.acquire().await?).await
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool_monitor
+            .stats(&self.pool, chrono::Utc::now().timestamp_millis())
+    }
+
+    /// Fallback pool for a read-only query while the primary pool is
+    /// congested; `None` means the read should go to Postgres as usual.
+    pub async fn read_offload_pool(&self) -> Option<sqlx::SqlitePool> {
+        if !self
+            .pool_monitor
+            .read_offload_active(chrono::Utc::now().timestamp_millis())
+        {
+            return None;
+        }
+        if self.state_machine.read().await.state() != DatabaseState::Normal {
+            return None;
+        }
+        let pool = self.fallback_pool().await?;
+        self.pool_monitor.note_read_offloaded();
+        Some(pool)
+    }
+
+    /// Runs a read-only query on a read replica lagging at most the
+    /// configured `DB_REPLICA_MAX_STALENESS_MS`, or on the primary when no
+    /// replica qualifies or the replica fails. `query` may run twice, so it
+    /// must not write.
+    pub async fn read<T, F, Fut>(&self, query: F) -> Result<T, sqlx::Error>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        self.read_within(self.replicas.max_staleness(), query).await
+    }
+
+    /// Like [`read`](Self::read) with an explicit staleness tolerance.
+    pub async fn read_within<T, F, Fut>(
+        &self,
+        max_staleness: Duration,
+        query: F,
+    ) -> Result<T, sqlx::Error>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        self.replicas.read(&self.pool, max_staleness, query).await
+    }
+
+    pub fn replica_status(&self) -> Vec<ReplicaStatus> {
+        self.replicas.status()
+    }
+
+    /// Latest schema drift check, `None` until the startup check finishes.
+    pub fn schema_drift_report(&self) -> Option<SchemaDriftReport> {
+        self.schema_drift.latest()
+    }
+
+    /// Compares the live schemas with the registry now.
+    pub async fn check_schema_drift(&self) -> Result<SchemaDriftReport, sqlx::Error> {
+        let fallback = self.fallback_pool().await;
+        self.schema_drift.check(&self.pool, fallback.as_ref()).await
+    }
+
+    pub fn dual_write_blocked(&self, table: &str) -> bool {
+        self.schema_drift.blocks_dual_write(table)
+    }
+
     pub fn sqlite_enabled(&self) -> bool {
         false
     }
 
+    /// Writes are no longer mirrored; while the primary is degraded they are
+    /// queued in the SQLite fallback and replayed once it recovers.
     #[deprecated(note = "Dual-write removed - use direct sqlx queries")]
     #[allow(deprecated)]
     pub async fn write_operation(
         &self,
-        _state: DatabaseState,
-        _op: WriteOperation,
+        state: DatabaseState,
+        op: WriteOperation,
     ) -> Result<WriteResult, DbMutationError> {
+        let table = op.table();
+        if self.dual_write_blocked(table) {
+            return Err(DbMutationError::SchemaDrift {
+                table: table.to_string(),
+            });
+        }
+        if matches!(state, DatabaseState::Degraded | DatabaseState::Unavailable) {
+            let fallback = self
+                .fallback_pool()
+                .await
+                .ok_or(DbMutationError::Unavailable)?;
+            return Self::defer_write(fallback, &op).await;
+        }
         Err(DbMutationError::NotSupported)
     }
 
+    #[allow(deprecated)]
+    async fn defer_write(
+        fallback: sqlx::SqlitePool,
+        op: &WriteOperation,
+    ) -> Result<WriteResult, DbMutationError> {
+        let store = SqlitePendingWriteStore::new(fallback);
+        store.ensure_schema().await?;
+        let data = serde_json::to_value(op).map_err(|e| DbMutationError::Custom(e.to_string()))?;
+        match store.save(op.operation_id(), op.priority(), &data).await? {
+            EnqueueOutcome::Shed => Err(DbMutationError::Unavailable),
+            EnqueueOutcome::Queued | EnqueueOutcome::QueuedWithEviction(_) => Ok(WriteResult {
+                written_to: "pending",
+                async_fallback_pending: true,
+            }),
+        }
+    }
+
+    /// Replays one batch of writes queued while the primary was degraded.
+    /// Stops at the first failure; the rest is retried on the next tick.
+    #[allow(deprecated)]
+    async fn flush_pending_writes(&self) {
+        if self.state_machine.read().await.state() != DatabaseState::Normal {
+            return;
+        }
+        let Some(fallback) = self.fallback_pool().await else {
+            return;
+        };
+        let store = SqlitePendingWriteStore::new(fallback);
+        let batch = match store.next_batch(PENDING_FLUSH_BATCH).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to read pending writes");
+                return;
+            }
+        };
+        for write in batch {
+            let Ok(op) = serde_json::from_value::<WriteOperation>(write.operation_data.clone())
+            else {
+                tracing::warn!(
+                    operation_id = %write.operation_id,
+                    "Dropping unreadable pending write"
+                );
+                let _ = store.remove(&write.operation_id).await;
+                continue;
+            };
+            if let Err(e) = pending_writes::replay(&self.pool, &op).await {
+                tracing::warn!(
+                    error = %e,
+                    operation_id = %write.operation_id,
+                    "Pending write replay failed"
+                );
+                return;
+            }
+            if let Err(e) = store.mark_flushed(&write).await {
+                tracing::warn!(error = %e, "Failed to remove flushed pending write");
+                return;
+            }
+        }
+    }
+
     pub async fn delete_session_by_token_hash(&self, token_hash: &str) -> Result<(), sqlx::Error> {
         sqlx::query(r#"DELETE FROM "sessions" WHERE "token" = $1"#)
             .bind(token_hash)
@@ -125,6 +312,23 @@ impl DatabaseProxy {
 }
 
 impl DatabaseProxy {
+    /// Checks for schema drift at startup, then every configured interval.
+    fn start_schema_drift_monitor(self: &Arc<Self>) {
+        let proxy = Arc::clone(self);
+        tokio::spawn(async move {
+            let interval = proxy.config.schema_drift.interval;
+            loop {
+                if let Err(e) = proxy.check_schema_drift().await {
+                    tracing::warn!(error = %e, "Schema drift check failed");
+                }
+                if interval.is_zero() {
+                    break;
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
     fn start_health_monitor(self: &Arc<Self>) {
         let proxy = Arc::clone(self);
         tokio::spawn(async move {
@@ -142,6 +346,13 @@ impl DatabaseProxy {
                 let mut tracker = self.health.write().await;
                 tracker.process(result);
             }
+            self.sample_pool().await;
+            self.flush_pending_writes().await;
+            if !self.replicas.is_empty() {
+                self.replicas
+                    .check_health(self.config.health_check.timeout)
+                    .await;
+            }
 
             let elapsed = start.elapsed();
             if elapsed < interval {
@@ -150,6 +361,15 @@ impl DatabaseProxy {
         }
     }
 
+    /// Times one acquire so latency is tracked even while the repository
+    /// layer is idle, then updates the read-offload window.
+    async fn sample_pool(&self) {
+        let _ = self.pool_monitor.acquire(&self.pool).await;
+        let state = self.state_machine.read().await.state();
+        self.pool_monitor
+            .evaluate(state, chrono::Utc::now().timestamp_millis());
+    }
+
     async fn check_health(&self) -> HealthCheckResult {
         let timeout = self.config.health_check.timeout;
         let pool = self.pool.clone();
@@ -173,6 +393,8 @@ pub enum DbInitError {
     Sqlx(#[from] sqlx::Error),
     #[error(transparent)]
     Migration(#[from] migrate::MigrationError),
+    #[error(transparent)]
+    SchemaRegistry(#[from] SchemaRegistryError),
 }
 
 #[derive(Debug, Error)]
@@ -181,6 +403,8 @@ pub enum DbMutationError {
     NotSupported,
     #[error("Service unavailable")]
     Unavailable,
+    #[error("Dual-write blocked: table {table} has drifted from the schema registry")]
+    SchemaDrift { table: String },
     #[error(transparent)]
     Sqlx(#[from] sqlx::Error),
     #[error("{0}")]
@@ -224,6 +448,42 @@ pub enum WriteOperation {
     },
 }
 
+#[allow(deprecated)]
+impl WriteOperation {
+    pub fn table(&self) -> &str {
+        match self {
+            WriteOperation::Insert { table, .. }
+            | WriteOperation::Update { table, .. }
+            | WriteOperation::Delete { table, .. }
+            | WriteOperation::Upsert { table, .. } => table,
+        }
+    }
+
+    pub fn operation_id(&self) -> &str {
+        match self {
+            WriteOperation::Insert { operation_id, .. }
+            | WriteOperation::Update { operation_id, .. }
+            | WriteOperation::Delete { operation_id, .. }
+            | WriteOperation::Upsert { operation_id, .. } => operation_id,
+        }
+    }
+
+    /// Queue class: writes flagged critical, otherwise by table.
+    pub fn priority(&self) -> WritePriority {
+        let critical = match self {
+            WriteOperation::Insert { critical, .. }
+            | WriteOperation::Update { critical, .. }
+            | WriteOperation::Delete { critical, .. }
+            | WriteOperation::Upsert { critical, .. } => critical.unwrap_or(false),
+        };
+        if critical {
+            WritePriority::Critical
+        } else {
+            WritePriority::for_table(self.table())
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[deprecated(note = "Dual-write removed")]
 pub struct WriteResult {
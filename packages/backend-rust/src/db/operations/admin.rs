@@ -50,7 +50,7 @@ pub async fn create_admin_user(
     .bind(password_hash)
     .bind(username)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(AdminUser {
@@ -77,7 +77,7 @@ pub async fn find_admin_by_email(
         "#,
     )
     .bind(email)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(row.map(|r| {
@@ -118,7 +118,7 @@ pub async fn find_admin_by_id(
         "#,
     )
     .bind(admin_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(row.map(|r| {
@@ -149,7 +149,7 @@ pub async fn update_admin_last_login(
     sqlx::query(r#"UPDATE "admin_users" SET "lastLoginAt" = $2 WHERE "id" = $1"#)
         .bind(admin_id)
         .bind(Utc::now().naive_utc())
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(())
 }
@@ -172,7 +172,7 @@ pub async fn create_admin_session(
     .bind(token_hash)
     .bind(expires_at)
     .bind(Utc::now().naive_utc())
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -183,7 +183,7 @@ pub async fn delete_admin_session(
 ) -> Result<bool, sqlx::Error> {
     let result = sqlx::query(r#"DELETE FROM "admin_sessions" WHERE "token" = $1"#)
         .bind(token_hash)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(result.rows_affected() > 0)
 }
@@ -203,7 +203,7 @@ pub async fn verify_admin_session(
         "#,
     )
     .bind(token_hash)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     let Some(row) = row else {
@@ -242,7 +242,7 @@ pub async fn delete_all_admin_sessions(
 ) -> Result<u64, sqlx::Error> {
     let result = sqlx::query(r#"DELETE FROM "admin_sessions" WHERE "adminId" = $1"#)
         .bind(admin_id)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(result.rows_affected())
 }
@@ -47,7 +47,7 @@ pub async fn upsert_algorithm_metrics_daily(
     .bind(total_latency_us_delta as i64)
     .bind(error_count_delta as i64)
     .bind(last_called_at.map(|dt| dt.naive_utc()))
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(())
@@ -83,7 +83,7 @@ pub async fn list_algorithm_metrics_daily(
 
     qb.push(r#" ORDER BY "day" DESC, "algorithmId" ASC"#);
 
-    let rows = qb.build().fetch_all(proxy.pool()).await?;
+    let rows = qb.build().fetch_all(&mut *proxy.acquire().await?).await?;
     let mut out = Vec::with_capacity(rows.len());
     for row in rows {
         let algorithm_id: String = row.try_get("algorithmId")?;
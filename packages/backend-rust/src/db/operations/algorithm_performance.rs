@@ -24,7 +24,7 @@ pub async fn upsert_algorithm_performance(
     .bind(perf.ema_reward)
     .bind(perf.sample_count as i64)
     .bind(perf.trust_score)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -41,7 +41,7 @@ pub async fn load_algorithm_performance(
     "#,
     )
     .bind(user_id)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
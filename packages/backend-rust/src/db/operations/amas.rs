@@ -1,7 +1,10 @@
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use danci_algo::build_info::AlgorithmBuild;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
+use crate::amas::version;
+use crate::db::snapshot_crypto::{self, SnapshotCryptoError};
 use crate::db::DatabaseProxy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +59,23 @@ pub struct DecisionRecord {
     pub is_simulation: bool,
     pub emotion_label: Option<String>,
     pub flow_score: Option<f64>,
+    /// Chosen by IGE forced exploration rather than on merit.
+    #[serde(default)]
+    pub is_forced_exploration: bool,
+    /// Session exploration budget scale; `None` when exploration was off.
+    #[serde(default)]
+    pub exploration_scale: Option<f64>,
+    /// Share of selections the budgeted policy meant to explore.
+    #[serde(default)]
+    pub intended_exploration_share: Option<f64>,
+    /// Probability of the logged difficulty under IGE's uniform exploration
+    /// draw; `None` unless the decision was randomized.
+    #[serde(default)]
+    pub propensity: Option<f64>,
+    /// Algorithm build that made the decision; stamped with the running
+    /// build on insert when left `None`.
+    #[serde(default)]
+    pub algorithm: Option<AlgorithmBuild>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,7 +133,7 @@ pub async fn get_amas_user_state(
         "#,
     )
     .bind(user_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.map(|r| map_amas_user_state(&r)))
 }
@@ -164,7 +184,7 @@ pub async fn upsert_amas_user_state(
     .bind(&user_state.algorithm_states)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -176,45 +196,29 @@ pub async fn get_amas_user_model(
 ) -> Result<Option<AmasUserModel>, sqlx::Error> {
     let row = sqlx::query(
         r#"
-        SELECT * FROM "amas_user_models"
-        WHERE "userId" = $1 AND "modelType" = $2
-        ORDER BY "version" DESC
+        SELECT m.*, k."wrappedKey", k."masterKeyId", k."keyVersion"
+        FROM "amas_user_models" m
+        LEFT JOIN "user_data_keys" k ON k."userId" = m."userId"
+        WHERE m."userId" = $1 AND m."modelType" = $2
+        ORDER BY m."version" DESC
         LIMIT 1
         "#,
     )
     .bind(user_id)
     .bind(model_type)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
-    Ok(row.map(|r| map_amas_user_model(&r)))
+    row.map(|r| map_amas_user_model(&r)).transpose()
 }
 
+/// Upserts a model snapshot, encrypting its parameters when snapshot keys are configured.
 pub async fn insert_amas_user_model(
     proxy: &DatabaseProxy,
     model: &AmasUserModel,
 ) -> Result<(), sqlx::Error> {
-    let now = Utc::now().naive_utc();
-    sqlx::query(
-        r#"
-        INSERT INTO "amas_user_models" (
-            "id", "userId", "modelType", "parameters", "version", "createdAt", "updatedAt"
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
-        ON CONFLICT ("userId", "modelType") DO UPDATE SET
-            "parameters" = EXCLUDED."parameters",
-            "version" = EXCLUDED."version",
-            "updatedAt" = EXCLUDED."updatedAt"
-        "#,
-    )
-    .bind(&model.id)
-    .bind(&model.user_id)
-    .bind(&model.model_type)
-    .bind(&model.parameters)
-    .bind(model.version)
-    .bind(now)
-    .bind(now)
-    .execute(proxy.pool())
-    .await?;
-    Ok(())
+    let mut tx = proxy.begin().await?;
+    insert_amas_user_model_tx(&mut tx, model).await?;
+    tx.commit().await
 }
 
 pub async fn upsert_amas_user_state_tx(
@@ -272,23 +276,47 @@ pub async fn insert_amas_user_model_tx(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     model: &AmasUserModel,
 ) -> Result<(), sqlx::Error> {
+    let provider = snapshot_crypto::init_master_keys().map_err(SnapshotCryptoError::into_encode)?;
+    let parameters = match provider {
+        Some(provider) => {
+            let key = snapshot_crypto::data_key_for_write(tx, provider, &model.user_id)
+                .await
+                .map_err(SnapshotCryptoError::into_encode)?;
+            snapshot_crypto::encrypt_parameters(
+                &key,
+                &model.user_id,
+                &model.model_type,
+                &model.parameters,
+            )
+            .map_err(SnapshotCryptoError::into_encode)?
+        }
+        None => model.parameters.clone(),
+    };
     let now = Utc::now().naive_utc();
+    let build = version::current();
     sqlx::query(
         r#"
         INSERT INTO "amas_user_models" (
-            "id", "userId", "modelType", "parameters", "version", "createdAt", "updatedAt"
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "id", "userId", "modelType", "parameters", "version",
+            "algorithmVersion", "gitHash", "configFingerprint", "createdAt", "updatedAt"
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         ON CONFLICT ("userId", "modelType") DO UPDATE SET
             "parameters" = EXCLUDED."parameters",
             "version" = EXCLUDED."version",
+            "algorithmVersion" = EXCLUDED."algorithmVersion",
+            "gitHash" = EXCLUDED."gitHash",
+            "configFingerprint" = EXCLUDED."configFingerprint",
             "updatedAt" = EXCLUDED."updatedAt"
         "#,
     )
     .bind(&model.id)
     .bind(&model.user_id)
     .bind(&model.model_type)
-    .bind(&model.parameters)
+    .bind(&parameters)
     .bind(model.version)
+    .bind(&build.crate_version)
+    .bind(&build.git_hash)
+    .bind(&build.config_fingerprint)
     .bind(now)
     .bind(now)
     .execute(&mut **tx)
@@ -303,9 +331,11 @@ pub async fn get_amas_user_model_tx(
 ) -> Result<Option<AmasUserModel>, sqlx::Error> {
     let row = sqlx::query(
         r#"
-        SELECT * FROM "amas_user_models"
-        WHERE "userId" = $1 AND "modelType" = $2
-        ORDER BY "version" DESC
+        SELECT m.*, k."wrappedKey", k."masterKeyId", k."keyVersion"
+        FROM "amas_user_models" m
+        LEFT JOIN "user_data_keys" k ON k."userId" = m."userId"
+        WHERE m."userId" = $1 AND m."modelType" = $2
+        ORDER BY m."version" DESC
         LIMIT 1
         "#,
     )
@@ -313,7 +343,54 @@ pub async fn get_amas_user_model_tx(
     .bind(model_type)
     .fetch_optional(&mut **tx)
     .await?;
-    Ok(row.map(|r| map_amas_user_model(&r)))
+    row.map(|r| map_amas_user_model(&r)).transpose()
+}
+
+/// Restricts a scan of `amas_user_models` to a cohort of users.
+#[derive(Debug, Clone, Default)]
+pub struct UserModelScanFilter {
+    pub user_ids: Option<Vec<String>>,
+    pub registered_from: Option<NaiveDateTime>,
+    pub registered_to: Option<NaiveDateTime>,
+    /// Only models saved at or after this time.
+    pub updated_since: Option<NaiveDateTime>,
+}
+
+/// One page of a model type across users, ordered by user id. Pass the last
+/// user id of the previous page as `after_user_id`.
+pub async fn list_amas_user_models_page(
+    proxy: &DatabaseProxy,
+    model_type: &str,
+    filter: &UserModelScanFilter,
+    after_user_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<AmasUserModel>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT m.*, k."wrappedKey", k."masterKeyId", k."keyVersion"
+        FROM "amas_user_models" m
+        JOIN "users" u ON u."id" = m."userId"
+        LEFT JOIN "user_data_keys" k ON k."userId" = m."userId"
+        WHERE m."modelType" = $1
+          AND ($2::text IS NULL OR m."userId" > $2)
+          AND ($3::text[] IS NULL OR m."userId" = ANY($3))
+          AND ($4::timestamp IS NULL OR u."createdAt" >= $4)
+          AND ($5::timestamp IS NULL OR u."createdAt" < $5)
+          AND ($6::timestamp IS NULL OR m."updatedAt" >= $6)
+        ORDER BY m."userId"
+        LIMIT $7
+        "#,
+    )
+    .bind(model_type)
+    .bind(after_user_id)
+    .bind(filter.user_ids.as_deref())
+    .bind(filter.registered_from)
+    .bind(filter.registered_to)
+    .bind(filter.updated_since)
+    .bind(limit)
+    .fetch_all(&mut *proxy.acquire().await?)
+    .await?;
+    rows.iter().map(map_amas_user_model).collect()
 }
 
 pub async fn insert_decision_record(
@@ -321,14 +398,18 @@ pub async fn insert_decision_record(
     record: &DecisionRecord,
 ) -> Result<(), sqlx::Error> {
     let now = Utc::now().naive_utc();
+    let build = record.algorithm.clone().unwrap_or_else(version::current);
     sqlx::query(
         r#"
         INSERT INTO "decision_records" (
             "id", "decisionId", "answerRecordId", "sessionId", "decisionSource",
             "coldstartPhase", "weightsSnapshot", "memberVotes", "selectedAction",
             "confidence", "reward", "traceVersion", "totalDurationMs",
-            "isSimulation", "emotionLabel", "flowScore", "createdAt", "updatedAt"
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $17)
+            "isSimulation", "emotionLabel", "flowScore", "isForcedExploration",
+            "explorationScale", "intendedExplorationShare", "propensity",
+            "algorithmVersion", "gitHash", "configFingerprint",
+            "createdAt", "updatedAt"
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $24)
         "#,
     )
     .bind(&record.id)
@@ -347,12 +428,150 @@ pub async fn insert_decision_record(
     .bind(record.is_simulation)
     .bind(&record.emotion_label)
     .bind(record.flow_score)
+    .bind(record.is_forced_exploration)
+    .bind(record.exploration_scale)
+    .bind(record.intended_exploration_share)
+    .bind(record.propensity)
+    .bind(&build.crate_version)
+    .bind(&build.git_hash)
+    .bind(&build.config_fingerprint)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
 
+/// Decision counts and outcomes for one algorithm build.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmVersionBreakdown {
+    /// `None` groups decisions recorded before builds were stamped.
+    pub algorithm: Option<AlgorithmBuild>,
+    pub decisions: i64,
+    pub users: i64,
+    pub avg_confidence: Option<f64>,
+    pub avg_reward: Option<f64>,
+    pub forced_exploration_rate: f64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// Non-simulated decisions since `since`, grouped by algorithm build,
+/// most recently seen first.
+pub async fn decision_breakdown_by_algorithm_version(
+    proxy: &DatabaseProxy,
+    since: NaiveDateTime,
+) -> Result<Vec<AlgorithmVersionBreakdown>, sqlx::Error> {
+    let rows = proxy
+        .read(|pool| async move {
+            sqlx::query(
+                r#"
+                SELECT dr."algorithmVersion", dr."gitHash", dr."configFingerprint",
+                       COUNT(*)::bigint AS "decisions",
+                       COUNT(DISTINCT ls."userId")::bigint AS "users",
+                       AVG(dr."confidence") AS "avgConfidence",
+                       AVG(dr."reward") AS "avgReward",
+                       AVG(CASE WHEN dr."isForcedExploration" THEN 1.0 ELSE 0.0 END)::float8 AS "forcedRate",
+                       MIN(dr."timestamp") AS "firstSeen",
+                       MAX(dr."timestamp") AS "lastSeen"
+                FROM "decision_records" dr
+                LEFT JOIN "learning_sessions" ls ON ls."id" = dr."sessionId"
+                WHERE dr."timestamp" >= $1 AND COALESCE(dr."isSimulation", false) = false
+                GROUP BY dr."algorithmVersion", dr."gitHash", dr."configFingerprint"
+                ORDER BY MAX(dr."timestamp") DESC
+                "#,
+            )
+            .bind(since)
+            .fetch_all(&pool)
+            .await
+        })
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let first_seen: NaiveDateTime = row.try_get("firstSeen").unwrap_or(since);
+            let last_seen: NaiveDateTime = row.try_get("lastSeen").unwrap_or(since);
+            AlgorithmVersionBreakdown {
+                algorithm: map_algorithm_build(row),
+                decisions: row.try_get("decisions").unwrap_or(0),
+                users: row.try_get("users").unwrap_or(0),
+                avg_confidence: row.try_get("avgConfidence").ok().flatten(),
+                avg_reward: row.try_get("avgReward").ok().flatten(),
+                forced_exploration_rate: row.try_get("forcedRate").unwrap_or(0.0),
+                first_seen: format_naive_iso(first_seen),
+                last_seen: format_naive_iso(last_seen),
+            }
+        })
+        .collect())
+}
+
+/// Scale bands the exploration-share report groups decisions into.
+const EXPLORATION_SCALE_BANDS: i32 = 5;
+
+/// Intended vs realised exploration for decisions in one budget scale band.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplorationShareBand {
+    pub scale_from: f64,
+    pub scale_to: f64,
+    pub decisions: i64,
+    pub sessions: i64,
+    pub avg_scale: f64,
+    /// Mean share the budgeted policy meant to explore.
+    pub intended_share: f64,
+    /// Share of decisions that were actually forced.
+    pub forced_rate: f64,
+}
+
+/// Non-simulated decisions since `since` that ran under an exploration
+/// policy, grouped into equal-width bands of budget scale.
+pub async fn exploration_share_breakdown(
+    proxy: &DatabaseProxy,
+    since: NaiveDateTime,
+) -> Result<Vec<ExplorationShareBand>, sqlx::Error> {
+    let rows = proxy
+        .read(|pool| async move {
+            sqlx::query(
+                r#"
+                SELECT LEAST(FLOOR(dr."explorationScale" * $2), $2 - 1)::int AS "band",
+                       COUNT(*)::bigint AS "decisions",
+                       COUNT(DISTINCT dr."sessionId")::bigint AS "sessions",
+                       AVG(dr."explorationScale")::float8 AS "avgScale",
+                       AVG(COALESCE(dr."intendedExplorationShare", 0))::float8 AS "intendedShare",
+                       AVG(CASE WHEN dr."isForcedExploration" THEN 1.0 ELSE 0.0 END)::float8 AS "forcedRate"
+                FROM "decision_records" dr
+                WHERE dr."timestamp" >= $1 AND COALESCE(dr."isSimulation", false) = false
+                  AND dr."explorationScale" IS NOT NULL
+                GROUP BY 1
+                ORDER BY 1
+                "#,
+            )
+            .bind(since)
+            .bind(EXPLORATION_SCALE_BANDS)
+            .fetch_all(&pool)
+            .await
+        })
+        .await?;
+
+    let width = 1.0 / f64::from(EXPLORATION_SCALE_BANDS);
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let band: i32 = row.try_get("band").unwrap_or(0);
+            ExplorationShareBand {
+                scale_from: f64::from(band) * width,
+                scale_to: f64::from(band + 1) * width,
+                decisions: row.try_get("decisions").unwrap_or(0),
+                sessions: row.try_get("sessions").unwrap_or(0),
+                avg_scale: row.try_get("avgScale").unwrap_or(0.0),
+                intended_share: row.try_get("intendedShare").unwrap_or(0.0),
+                forced_rate: row.try_get("forcedRate").unwrap_or(0.0),
+            }
+        })
+        .collect())
+}
+
 pub async fn get_recent_decision_records(
     proxy: &DatabaseProxy,
     session_id: &str,
@@ -368,7 +587,7 @@ pub async fn get_recent_decision_records(
     )
     .bind(session_id)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_decision_record).collect())
 }
@@ -386,7 +605,7 @@ pub async fn get_global_recent_decisions(
         "#,
     )
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_decision_record).collect())
 }
@@ -399,7 +618,7 @@ pub async fn get_today_decision_count(proxy: &DatabaseProxy) -> Result<i64, sqlx
         AND "isSimulation" = false
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.0)
 }
@@ -416,7 +635,7 @@ pub async fn get_algorithm_distribution(
         GROUP BY "decisionSource"
         "#,
     )
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     let total: i64 = rows.iter().map(|(_, c)| c).sum();
@@ -444,7 +663,7 @@ pub async fn get_decision_by_id(
         "#,
     )
     .bind(decision_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.as_ref().map(map_decision_record))
 }
@@ -466,7 +685,7 @@ pub async fn insert_feature_vector(
     .bind(&fv.vector)
     .bind(&fv.labels)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -504,7 +723,7 @@ pub async fn insert_decision_insight(
     .bind(triggers)
     .bind(feature_vector_hash)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -547,7 +766,7 @@ pub async fn insert_pipeline_stage(
     .bind(metadata)
     .bind(error_message)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -580,22 +799,31 @@ fn map_amas_user_state(row: &sqlx::postgres::PgRow) -> AmasUserState {
     }
 }
 
-fn map_amas_user_model(row: &sqlx::postgres::PgRow) -> AmasUserModel {
+fn map_amas_user_model(row: &sqlx::postgres::PgRow) -> Result<AmasUserModel, sqlx::Error> {
     let created_at: NaiveDateTime = row
         .try_get("createdAt")
         .unwrap_or_else(|_| Utc::now().naive_utc());
     let updated_at: NaiveDateTime = row
         .try_get("updatedAt")
         .unwrap_or_else(|_| Utc::now().naive_utc());
-    AmasUserModel {
+    let user_id: String = row.try_get("userId").unwrap_or_default();
+    let model_type: String = row.try_get("modelType").unwrap_or_default();
+    let parameters = snapshot_crypto::decrypt_row_parameters(
+        row,
+        &user_id,
+        &model_type,
+        row.try_get("parameters").unwrap_or(serde_json::Value::Null),
+    )
+    .map_err(SnapshotCryptoError::into_decode)?;
+    Ok(AmasUserModel {
         id: row.try_get("id").unwrap_or_default(),
-        user_id: row.try_get("userId").unwrap_or_default(),
-        model_type: row.try_get("modelType").unwrap_or_default(),
-        parameters: row.try_get("parameters").unwrap_or(serde_json::Value::Null),
+        user_id,
+        model_type,
+        parameters,
         version: row.try_get("version").unwrap_or(1),
         created_at: format_naive_iso(created_at),
         updated_at: format_naive_iso(updated_at),
-    }
+    })
 }
 
 fn map_decision_record(row: &sqlx::postgres::PgRow) -> DecisionRecord {
@@ -618,9 +846,26 @@ fn map_decision_record(row: &sqlx::postgres::PgRow) -> DecisionRecord {
         is_simulation: row.try_get("isSimulation").unwrap_or(false),
         emotion_label: row.try_get("emotionLabel").ok(),
         flow_score: row.try_get("flowScore").ok(),
+        is_forced_exploration: row.try_get("isForcedExploration").unwrap_or(false),
+        exploration_scale: row.try_get("explorationScale").ok().flatten(),
+        intended_exploration_share: row.try_get("intendedExplorationShare").ok().flatten(),
+        propensity: row.try_get("propensity").ok().flatten(),
+        algorithm: map_algorithm_build(row),
     }
 }
 
+fn map_algorithm_build(row: &sqlx::postgres::PgRow) -> Option<AlgorithmBuild> {
+    Some(AlgorithmBuild {
+        crate_version: row
+            .try_get::<Option<String>, _>("algorithmVersion")
+            .ok()??,
+        git_hash: row.try_get::<Option<String>, _>("gitHash").ok()??,
+        config_fingerprint: row
+            .try_get::<Option<String>, _>("configFingerprint")
+            .ok()??,
+    })
+}
+
 fn format_naive_iso(value: NaiveDateTime) -> String {
     DateTime::<Utc>::from_naive_utc_and_offset(value, Utc)
         .to_rfc3339_opts(SecondsFormat::Millis, true)
@@ -67,7 +67,7 @@ pub async fn upsert_user_behavior_insight(
     .bind(user_count)
     .bind(data_points)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(id.to_string())
 }
@@ -99,7 +99,7 @@ pub async fn insert_alert_root_cause_analysis(
     .bind(related_metrics)
     .bind(confidence)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(id.to_string())
 }
@@ -128,7 +128,7 @@ pub async fn update_alert_root_cause_resolved(
     .bind(now)
     .bind(resolution)
     .bind(uuid)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -78,7 +78,7 @@ pub async fn create_broadcast(
     .bind(params.expires_at)
     .bind(params.target_count)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(Broadcast {
@@ -106,7 +106,7 @@ pub async fn update_broadcast_delivered_count(
     sqlx::query(r#"UPDATE "broadcasts" SET "deliveredCount" = $2 WHERE "id" = $1"#)
         .bind(broadcast_id)
         .bind(delivered_count)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(())
 }
@@ -150,21 +150,23 @@ pub async fn list_broadcasts(
     let total: i64 = if let Some(ref status) = params.status {
         sqlx::query_scalar(&count_sql)
             .bind(status)
-            .fetch_one(proxy.pool())
+            .fetch_one(&mut *proxy.acquire().await?)
             .await?
     } else {
         sqlx::query_scalar(&count_sql)
-            .fetch_one(proxy.pool())
+            .fetch_one(&mut *proxy.acquire().await?)
             .await?
     };
 
     let rows = if let Some(ref status) = params.status {
         sqlx::query(&data_sql)
             .bind(status)
-            .fetch_all(proxy.pool())
+            .fetch_all(&mut *proxy.acquire().await?)
             .await?
     } else {
-        sqlx::query(&data_sql).fetch_all(proxy.pool()).await?
+        sqlx::query(&data_sql)
+            .fetch_all(&mut *proxy.acquire().await?)
+            .await?
     };
 
     let broadcasts = rows
@@ -202,7 +204,7 @@ pub async fn get_broadcast_by_id(
 ) -> Result<Option<Broadcast>, sqlx::Error> {
     let row = sqlx::query(r#"SELECT "id", "adminId", "title", "content", "target"::text as "target", "targetFilter", "priority"::text as "priority", "persistent", "expiresAt", "status"::text as "status", "targetCount", "deliveredCount", "createdAt" FROM "broadcasts" WHERE "id" = $1"#)
         .bind(id)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *proxy.acquire().await?)
         .await?;
 
     Ok(row.map(|row| {
@@ -251,7 +253,7 @@ pub async fn create_audit_log(
     .bind(action)
     .bind(&details)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(BroadcastAuditLog {
@@ -282,7 +284,7 @@ pub async fn list_audit_logs(
         .bind(bid)
         .bind(limit)
         .bind(offset)
-        .fetch_all(proxy.pool())
+        .fetch_all(&mut *proxy.acquire().await?)
         .await?
     } else {
         sqlx::query(
@@ -294,7 +296,7 @@ pub async fn list_audit_logs(
         )
         .bind(limit)
         .bind(offset)
-        .fetch_all(proxy.pool())
+        .fetch_all(&mut *proxy.acquire().await?)
         .await?
     };
 
@@ -348,7 +350,7 @@ pub async fn insert_broadcast_notifications(
         .bind(priority)
         .bind(broadcast_id)
         .bind(now)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await;
 
         if result.is_ok() {
@@ -31,7 +31,7 @@ pub async fn get_all_clusters(
     )
     .bind(model)
     .bind(dim)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -62,7 +62,7 @@ pub async fn get_cluster_by_id(
         "#,
     )
     .bind(cluster_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(row.map(|r| ClusterRow {
@@ -105,7 +105,7 @@ pub async fn upsert_cluster(
     .bind(avg_cohesion)
     .bind(model)
     .bind(dim)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(id)
@@ -124,7 +124,7 @@ pub async fn delete_all_clusters(
     )
     .bind(model)
     .bind(dim)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(result.rows_affected())
@@ -161,7 +161,7 @@ pub async fn find_centroid_word(
         "#,
     )
     .bind(word_ids)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(row.map(|r| r.get("wordId")))
@@ -189,7 +189,7 @@ pub async fn calc_cluster_cohesion(
         "#,
     )
     .bind(word_ids)
-    .fetch_one(proxy.pool())
+    .fetch_one(&mut *proxy.acquire().await?)
     .await?;
 
     let avg_distance: Option<f64> = row.get("avg_distance");
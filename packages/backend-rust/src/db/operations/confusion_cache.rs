@@ -55,7 +55,7 @@ pub async fn insert_confusion_pair(
     .bind(cluster_id)
     .bind(distance)
     .bind(model)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(id)
@@ -69,7 +69,7 @@ pub async fn delete_by_word_id(proxy: &DatabaseProxy, word_id: &str) -> Result<u
         "#,
     )
     .bind(word_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(result.rows_affected())
@@ -95,7 +95,7 @@ pub async fn find_by_cluster(
     .bind(threshold)
     .bind(limit)
     .bind(offset)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -132,7 +132,7 @@ pub async fn find_by_wordbook(
     .bind(threshold)
     .bind(limit)
     .bind(offset)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -164,7 +164,7 @@ pub async fn get_cluster_confusion_counts(
         "#,
     )
     .bind(threshold)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -186,7 +186,7 @@ pub async fn get_cache_stats(
         FROM "confusion_pairs_cache"
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(&mut *proxy.acquire().await?)
     .await?;
 
     let total: i64 = row.get("total");
@@ -196,7 +196,7 @@ pub async fn get_cache_stats(
 
 pub async fn clear_all(proxy: &DatabaseProxy) -> Result<u64, sqlx::Error> {
     let result = sqlx::query(r#"DELETE FROM "confusion_pairs_cache""#)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(result.rows_affected())
 }
@@ -259,7 +259,7 @@ pub async fn find_confusable_words_batch(
     q = q.bind(threshold);
     q = q.bind(per_word_limit as i64);
 
-    let rows = q.fetch_all(proxy.pool()).await?;
+    let rows = q.fetch_all(&mut *proxy.acquire().await?).await?;
 
     let mut result: HashMap<String, Vec<(String, f64)>> = HashMap::new();
     for row in rows {
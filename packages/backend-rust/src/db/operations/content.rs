@@ -17,6 +17,10 @@ pub struct WordBook {
     pub user_id: Option<String>,
     pub is_public: bool,
     pub word_count: i64,
+    #[serde(default = "default_language_code")]
+    pub language_code: String,
+    #[serde(default = "default_gloss_language")]
+    pub gloss_language: String,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -33,10 +37,21 @@ pub struct Word {
     pub audio_url: Option<String>,
     pub difficulty: Option<f64>,
     pub frequency: Option<i32>,
+    /// Falls back to the book's language when unset.
+    #[serde(default)]
+    pub language_code: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+fn default_language_code() -> String {
+    danci_algo::language::DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_gloss_language() -> String {
+    danci_algo::language::DEFAULT_GLOSS_LANGUAGE.to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StudyPlan {
@@ -72,7 +87,7 @@ pub async fn get_word_book(
 ) -> Result<Option<WordBook>, sqlx::Error> {
     let row = sqlx::query(r#"SELECT * FROM "word_books" WHERE "id" = $1 LIMIT 1"#)
         .bind(word_book_id)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *proxy.acquire().await?)
         .await?;
     Ok(row.map(|r| map_word_book(&r)))
 }
@@ -89,7 +104,7 @@ pub async fn get_user_word_books(
         "#,
     )
     .bind(user_id)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_word_book).collect())
 }
@@ -112,7 +127,7 @@ pub async fn get_system_word_books_cached(
     let rows = sqlx::query(
         r#"SELECT * FROM "word_books" WHERE "type"::text = 'SYSTEM' ORDER BY "createdAt" DESC"#,
     )
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     let books: Vec<WordBook> = rows.iter().map(map_word_book).collect();
 
@@ -129,8 +144,8 @@ pub async fn insert_word_book(proxy: &DatabaseProxy, book: &WordBook) -> Result<
         r#"
         INSERT INTO "word_books" (
             "id", "name", "description", "coverImage", "type", "userId",
-            "isPublic", "wordCount", "createdAt", "updatedAt"
-        ) VALUES ($1, $2, $3, $4, $5::"WordBookType", $6, $7, $8, $9, $10)
+            "isPublic", "wordCount", "languageCode", "glossLanguage", "createdAt", "updatedAt"
+        ) VALUES ($1, $2, $3, $4, $5::"WordBookType", $6, $7, $8, $9, $10, $11, $12)
         "#,
     )
     .bind(&book.id)
@@ -141,9 +156,11 @@ pub async fn insert_word_book(proxy: &DatabaseProxy, book: &WordBook) -> Result<
     .bind(&book.user_id)
     .bind(book.is_public)
     .bind(book.word_count as i32)
+    .bind(&book.language_code)
+    .bind(&book.gloss_language)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -162,7 +179,7 @@ pub async fn update_word_book(proxy: &DatabaseProxy, book: &WordBook) -> Result<
     .bind(&book.cover_image)
     .bind(now)
     .bind(&book.id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -173,7 +190,7 @@ pub async fn delete_word_book(
 ) -> Result<(), sqlx::Error> {
     sqlx::query(r#"DELETE FROM "word_books" WHERE "id" = $1"#)
         .bind(word_book_id)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(())
 }
@@ -196,7 +213,7 @@ pub async fn get_word_cached(
 
     let row = sqlx::query(r#"SELECT * FROM "words" WHERE "id" = $1 LIMIT 1"#)
         .bind(word_id)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *proxy.acquire().await?)
         .await?;
 
     let word = row.map(|r| map_word(&r));
@@ -228,7 +245,7 @@ pub async fn get_words_by_book(
     .bind(word_book_id)
     .bind(limit_val)
     .bind(offset_val)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_word).collect())
 }
@@ -254,7 +271,7 @@ pub async fn get_words_by_ids(
     for id in word_ids {
         q = q.bind(id);
     }
-    let rows = q.fetch_all(proxy.pool()).await?;
+    let rows = q.fetch_all(&mut *proxy.acquire().await?).await?;
     Ok(rows.iter().map(map_word).collect())
 }
 
@@ -277,7 +294,7 @@ pub async fn insert_word(proxy: &DatabaseProxy, word: &Word) -> Result<(), sqlx:
     .bind(&word.audio_url)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -285,7 +302,7 @@ pub async fn insert_word(proxy: &DatabaseProxy, word: &Word) -> Result<(), sqlx:
 pub async fn delete_word(proxy: &DatabaseProxy, word_id: &str) -> Result<(), sqlx::Error> {
     sqlx::query(r#"DELETE FROM "words" WHERE "id" = $1"#)
         .bind(word_id)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(())
 }
@@ -297,7 +314,7 @@ pub async fn count_words_in_book(
     let result: Option<i64> =
         sqlx::query_scalar(r#"SELECT COUNT(*) FROM "words" WHERE "wordBookId" = $1"#)
             .bind(word_book_id)
-            .fetch_one(proxy.pool())
+            .fetch_one(&mut *proxy.acquire().await?)
             .await?;
     Ok(result.unwrap_or(0))
 }
@@ -312,7 +329,7 @@ pub async fn get_study_plan(
     )
     .bind(user_id)
     .bind(word_book_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.map(|r| map_study_plan(&r)))
 }
@@ -325,7 +342,7 @@ pub async fn get_active_study_plan(
         r#"SELECT * FROM "study_plans" WHERE "userId" = $1 AND "isActive" = true LIMIT 1"#,
     )
     .bind(user_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.map(|r| map_study_plan(&r)))
 }
@@ -366,7 +383,7 @@ pub async fn upsert_study_plan(proxy: &DatabaseProxy, plan: &StudyPlan) -> Resul
     .bind(plan.is_active)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -381,7 +398,7 @@ pub async fn get_user_word_book_progress(
     )
     .bind(user_id)
     .bind(word_book_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.map(|r| map_progress(&r)))
 }
@@ -420,7 +437,7 @@ pub async fn upsert_user_word_book_progress(
     .bind(last_study)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -441,6 +458,12 @@ fn map_word_book(row: &sqlx::postgres::PgRow) -> WordBook {
         user_id: row.try_get("userId").ok().flatten(),
         is_public: row.try_get("isPublic").unwrap_or(false),
         word_count: row.try_get("wordCount").unwrap_or(0),
+        language_code: row
+            .try_get("languageCode")
+            .unwrap_or_else(|_| default_language_code()),
+        gloss_language: row
+            .try_get("glossLanguage")
+            .unwrap_or_else(|_| default_gloss_language()),
         created_at: format_naive_iso(created_at),
         updated_at: format_naive_iso(updated_at),
     }
@@ -463,6 +486,7 @@ fn map_word(row: &sqlx::postgres::PgRow) -> Word {
         audio_url: row.try_get("audioUrl").ok().flatten(),
         difficulty: row.try_get("difficulty").ok(),
         frequency: row.try_get("frequency").ok(),
+        language_code: row.try_get("languageCode").ok().flatten(),
         created_at: format_naive_iso(created_at),
         updated_at: format_naive_iso(updated_at),
     }
@@ -52,7 +52,7 @@ pub async fn upsert_word_embedding(
     .bind(dim)
     .bind(&embedding_str)
     .bind(content_hash)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -72,7 +72,7 @@ pub async fn select_words_missing_embeddings(
         "#,
     )
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     let mut items = Vec::new();
@@ -118,7 +118,7 @@ pub async fn semantic_search_words(
         .bind(&embedding_str)
         .bind(wb_id)
         .bind(limit)
-        .fetch_all(proxy.pool())
+        .fetch_all(&mut *proxy.acquire().await?)
         .await?
     } else {
         sqlx::query(
@@ -132,7 +132,7 @@ pub async fn semantic_search_words(
         )
         .bind(&embedding_str)
         .bind(limit)
-        .fetch_all(proxy.pool())
+        .fetch_all(&mut *proxy.acquire().await?)
         .await?
     };
 
@@ -159,7 +159,7 @@ pub async fn get_word_embedding(
         "#,
     )
     .bind(word_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     match row {
@@ -188,7 +188,7 @@ pub async fn filter_word_ids_with_embeddings(
         "#,
     )
     .bind(word_ids)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows.into_iter().map(|r| r.get("wordId")).collect())
@@ -215,7 +215,7 @@ pub async fn find_similar_words(
     )
     .bind(word_id)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -236,7 +236,7 @@ pub async fn get_embedding_stats(proxy: &DatabaseProxy) -> Result<(i64, i64), sq
             (SELECT COUNT(*) FROM "words") as total_count
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(&mut *proxy.acquire().await?)
     .await?;
 
     let embedded_count: i64 = row.get("embedded_count");
@@ -284,7 +284,7 @@ pub async fn find_confusion_pairs_paged(
         .bind(threshold)
         .bind(limit)
         .bind(offset)
-        .fetch_all(proxy.pool())
+        .fetch_all(&mut *proxy.acquire().await?)
         .await?
     } else {
         sqlx::query(
@@ -305,7 +305,7 @@ pub async fn find_confusion_pairs_paged(
         .bind(threshold)
         .bind(limit)
         .bind(offset)
-        .fetch_all(proxy.pool())
+        .fetch_all(&mut *proxy.acquire().await?)
         .await?
     };
 
@@ -342,7 +342,7 @@ pub async fn get_recent_wrong_word_ids(
     .bind(user_id)
     .bind(cutoff_ms)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows.into_iter().map(|r| r.get("wordId")).collect())
@@ -370,7 +370,7 @@ pub async fn calculate_average_embedding_distance(
         "#,
     )
     .bind(word_ids)
-    .fetch_one(proxy.pool())
+    .fetch_one(&mut *proxy.acquire().await?)
     .await?;
 
     let avg_distance: Option<f64> = rows.get("avg_distance");
@@ -107,7 +107,7 @@ pub async fn get_word_learning_state(
     )
     .bind(user_id)
     .bind(word_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.map(|r| map_word_learning_state(&r)))
 }
@@ -127,7 +127,7 @@ pub async fn get_user_word_learning_states(
     )
     .bind(user_id)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_word_learning_state).collect())
 }
@@ -149,7 +149,7 @@ pub async fn get_due_words_for_review(
     .bind(user_id)
     .bind(now)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_word_learning_state).collect())
 }
@@ -224,7 +224,7 @@ pub async fn upsert_word_learning_state(
     .bind(wls.elapsed_days)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -260,7 +260,7 @@ pub async fn insert_answer_record(
     .bind(&record.answer_given)
     .bind(timestamp)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -280,7 +280,7 @@ pub async fn get_recent_answer_records(
     )
     .bind(user_id)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows.iter().map(map_answer_record).collect())
 }
@@ -308,7 +308,7 @@ pub async fn insert_word_review_trace(
     .bind(trace.after_interval)
     .bind(trace.quality)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
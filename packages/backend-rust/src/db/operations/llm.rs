@@ -80,7 +80,7 @@ pub async fn insert_suggestion_effect_tracking(
     .bind(new_value)
     .bind(now)
     .bind(metrics_before_apply)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(id)
 }
@@ -110,7 +110,7 @@ pub async fn update_suggestion_effect(
     .bind(effect_analysis)
     .bind(now)
     .bind(tracking_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -142,7 +142,7 @@ pub async fn insert_word_content_variant(
     .bind(confidence)
     .bind(task_id)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(id)
 }
@@ -172,7 +172,7 @@ pub async fn update_word_content_variant_status(
     .bind(approved_by)
     .bind(approved_at)
     .bind(variant_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -200,7 +200,7 @@ pub async fn insert_llm_analysis_task(
     .bind(input)
     .bind(created_by)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(id)
 }
@@ -221,7 +221,7 @@ pub async fn update_llm_task_started(
     )
     .bind(now)
     .bind(task_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -248,7 +248,7 @@ pub async fn update_llm_task_completed(
     .bind(tokens_used)
     .bind(now)
     .bind(task_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -271,7 +271,7 @@ pub async fn update_llm_task_failed(
     .bind(error)
     .bind(now)
     .bind(task_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
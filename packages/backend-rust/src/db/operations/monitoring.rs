@@ -74,7 +74,7 @@ pub async fn get_monitoring_overview(
         "#,
     )
     .bind(today)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     let latest_report: Option<(f64, String)> = sqlx::query_as(
@@ -85,7 +85,7 @@ pub async fn get_monitoring_overview(
         LIMIT 1
         "#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     let stats_24h: Option<(i64, i64, Option<f64>, Option<f64>)> = sqlx::query_as(
@@ -99,7 +99,7 @@ pub async fn get_monitoring_overview(
         WHERE "periodStart" >= NOW() - INTERVAL '24 hours'
         "#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
 
     let (total_events_today, _) = daily.unwrap_or((0, 0));
@@ -139,7 +139,7 @@ pub async fn get_aggregates_15m(
         "#,
     )
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -181,7 +181,7 @@ pub async fn get_aggregates_daily(
         "#,
     )
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -251,7 +251,7 @@ pub async fn get_health_reports(
         "#,
     )
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(rows
@@ -320,7 +320,7 @@ pub async fn insert_15m_aggregate(
     .bind(constraints_satisfied_rate)
     .bind(alert_level)
     .bind(alert_reasons)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(id.to_string())
@@ -362,7 +362,7 @@ pub async fn insert_daily_aggregate(
     .bind(cold_start_funnel)
     .bind(warn_periods)
     .bind(critical_periods)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(id.to_string())
@@ -398,7 +398,7 @@ pub async fn insert_health_report(
     .bind(recommendations)
     .bind(input_snapshot)
     .bind(tokens_used)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
 
     Ok(id.to_string())
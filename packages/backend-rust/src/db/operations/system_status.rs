@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
+use sqlx::{PgPool, Row};
 
 use crate::db::DatabaseProxy;
 
@@ -135,6 +135,12 @@ pub struct MemoryStatusReal {
 }
 
 pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatusReal, sqlx::Error> {
+    proxy
+        .read(|pool| async move { select_pipeline_status(&pool).await })
+        .await
+}
+
+async fn select_pipeline_status(pool: &PgPool) -> Result<PipelineStatusReal, sqlx::Error> {
     let raw_count_row = sqlx::query(
         r#"
         SELECT COUNT(*) AS raw_count
@@ -142,7 +148,7 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
         WHERE "timestamp" >= NOW() - INTERVAL '24 hours'
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let raw_events: i64 = raw_count_row.try_get("raw_count").unwrap_or(0);
@@ -155,7 +161,7 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
           AND "createdAt" >= NOW() - INTERVAL '24 hours'
         "#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(pool)
     .await?;
 
     let decision_count: i64 = decision_count_row
@@ -173,7 +179,7 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
         WHERE "timestamp" >= NOW() - INTERVAL '1 hour'
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let realtime_events: i64 = realtime_row.try_get("event_count").unwrap_or(0);
@@ -201,7 +207,7 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
               AND "totalDurationMs" IS NOT NULL
             "#,
         )
-        .fetch_optional(proxy.pool())
+        .fetch_optional(pool)
         .await?;
 
         let fallback_latency: f64 = decision_latency_row
@@ -222,7 +228,7 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
         WHERE "periodStart" >= NOW() - INTERVAL '1 hour'
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let events_15m: i64 = agg_row.try_get("events_15m").unwrap_or(0);
@@ -272,7 +278,7 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
         WHERE "timestamp" >= NOW() - INTERVAL '24 hours'
         "#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(pool)
     .await?;
 
     let (optimization_count, optimization_last_run) = optimization_row
@@ -389,6 +395,12 @@ pub async fn get_pipeline_status(proxy: &DatabaseProxy) -> Result<PipelineStatus
 pub async fn get_algorithm_status(
     proxy: &DatabaseProxy,
 ) -> Result<AlgorithmStatusReal, sqlx::Error> {
+    proxy
+        .read(|pool| async move { select_algorithm_status(&pool).await })
+        .await
+}
+
+async fn select_algorithm_status(pool: &PgPool) -> Result<AlgorithmStatusReal, sqlx::Error> {
     let stats_row = sqlx::query(
         r#"
         SELECT
@@ -403,7 +415,7 @@ pub async fn get_algorithm_status(
           AND "createdAt" >= NOW() - INTERVAL '24 hours'
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let total_calls: i64 = stats_row.try_get("total_calls").unwrap_or(0);
@@ -427,7 +439,7 @@ pub async fn get_algorithm_status(
         LIMIT 1
         "#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(pool)
     .await?;
 
     let weights: std::collections::HashMap<String, f64> = weights_row
@@ -489,6 +501,14 @@ pub async fn get_algorithm_status(
 
 pub async fn get_user_state_status(
     proxy: &DatabaseProxy,
+) -> Result<(UserStateDistributions, Vec<RecentInference>), sqlx::Error> {
+    proxy
+        .read(|pool| async move { select_user_state_status(&pool).await })
+        .await
+}
+
+async fn select_user_state_status(
+    pool: &PgPool,
 ) -> Result<(UserStateDistributions, Vec<RecentInference>), sqlx::Error> {
     let dist_row = sqlx::query(
         r#"
@@ -515,7 +535,7 @@ pub async fn get_user_state_status(
         FROM "amas_user_states"
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let total: i64 = dist_row.try_get("total").unwrap_or(1).max(1);
@@ -595,7 +615,7 @@ pub async fn get_user_state_status(
         LIMIT 8
         "#,
     )
-    .fetch_all(proxy.pool())
+    .fetch_all(pool)
     .await?;
 
     let recent: Vec<RecentInference> = recent_rows
@@ -620,6 +640,12 @@ pub async fn get_user_state_status(
 }
 
 pub async fn get_memory_status(proxy: &DatabaseProxy) -> Result<MemoryStatusReal, sqlx::Error> {
+    proxy
+        .read(|pool| async move { select_memory_status(&pool).await })
+        .await
+}
+
+async fn select_memory_status(pool: &PgPool) -> Result<MemoryStatusReal, sqlx::Error> {
     let row = sqlx::query(
         r#"
         SELECT
@@ -636,7 +662,7 @@ pub async fn get_memory_status(proxy: &DatabaseProxy) -> Result<MemoryStatusReal
         FROM "word_learning_states"
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let total: i64 = row.try_get("total").unwrap_or(1).max(1);
@@ -684,7 +710,7 @@ pub async fn get_memory_status(proxy: &DatabaseProxy) -> Result<MemoryStatusReal
         FROM "word_learning_states"
         "#,
     )
-    .fetch_one(proxy.pool())
+    .fetch_one(pool)
     .await?;
 
     let reviewed_today: i64 = consolidation_row.try_get("reviewed_today").unwrap_or(0);
@@ -706,10 +732,13 @@ pub async fn get_memory_status(proxy: &DatabaseProxy) -> Result<MemoryStatusReal
 }
 
 pub async fn has_monitoring_data(proxy: &DatabaseProxy) -> bool {
+    let Ok(mut conn) = proxy.acquire().await else {
+        return false;
+    };
     let aggregates: Option<(i32,)> = sqlx::query_as(
         r#"SELECT 1 FROM "amas_monitoring_aggregates_15m" WHERE "periodStart" >= NOW() - INTERVAL '24 hours' LIMIT 1"#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *conn)
     .await
     .ok()
     .flatten();
@@ -721,7 +750,7 @@ pub async fn has_monitoring_data(proxy: &DatabaseProxy) -> bool {
     let events: Option<(i32,)> = sqlx::query_as(
         r#"SELECT 1 FROM "amas_monitoring_events" WHERE "timestamp" >= NOW() - INTERVAL '24 hours' LIMIT 1"#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *conn)
     .await
     .ok()
     .flatten();
@@ -733,7 +762,7 @@ pub async fn has_monitoring_data(proxy: &DatabaseProxy) -> bool {
     let decisions: Option<(i32,)> = sqlx::query_as(
         r#"SELECT 1 FROM "decision_records" WHERE "isSimulation" = false AND "createdAt" >= NOW() - INTERVAL '24 hours' LIMIT 1"#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *conn)
     .await
     .ok()
     .flatten();
@@ -742,10 +771,13 @@ pub async fn has_monitoring_data(proxy: &DatabaseProxy) -> bool {
 }
 
 pub async fn has_decision_data(proxy: &DatabaseProxy) -> bool {
+    let Ok(mut conn) = proxy.acquire().await else {
+        return false;
+    };
     let result: Option<(i64,)> = sqlx::query_as(
         r#"SELECT COUNT(*) FROM "decision_records" WHERE "isSimulation" = false AND "createdAt" >= NOW() - INTERVAL '24 hours'"#,
     )
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *conn)
     .await
     .ok()
     .flatten();
@@ -754,8 +786,11 @@ pub async fn has_decision_data(proxy: &DatabaseProxy) -> bool {
 }
 
 pub async fn has_user_state_data(proxy: &DatabaseProxy) -> bool {
+    let Ok(mut conn) = proxy.acquire().await else {
+        return false;
+    };
     let result: Option<(i64,)> = sqlx::query_as(r#"SELECT COUNT(*) FROM "amas_user_states""#)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *conn)
         .await
         .ok()
         .flatten();
@@ -764,8 +799,11 @@ pub async fn has_user_state_data(proxy: &DatabaseProxy) -> bool {
 }
 
 pub async fn has_learning_state_data(proxy: &DatabaseProxy) -> bool {
+    let Ok(mut conn) = proxy.acquire().await else {
+        return false;
+    };
     let result: Option<(i64,)> = sqlx::query_as(r#"SELECT COUNT(*) FROM "word_learning_states""#)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *conn)
         .await
         .ok()
         .flatten();
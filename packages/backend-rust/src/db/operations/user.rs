@@ -60,7 +60,7 @@ pub async fn get_user_learning_profile(
 ) -> Result<Option<UserLearningProfile>, sqlx::Error> {
     let row = sqlx::query(r#"SELECT * FROM "user_learning_profiles" WHERE "userId" = $1 LIMIT 1"#)
         .bind(user_id)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *proxy.acquire().await?)
         .await?;
     Ok(row.map(|r| {
         let created_at: NaiveDateTime = r
@@ -115,7 +115,7 @@ pub async fn upsert_user_learning_profile(
     .bind(profile.recovery_rate)
     .bind(now)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -137,7 +137,7 @@ pub async fn insert_user_state_history(
     .bind(&history.state_snapshot)
     .bind(&history.trigger_event)
     .bind(now)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -157,7 +157,7 @@ pub async fn get_recent_user_state_history(
     )
     .bind(user_id)
     .bind(limit)
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
     Ok(rows
         .iter()
@@ -255,7 +255,7 @@ pub async fn upsert_user_interaction_stats_with_vark(
     .bind(vark.total_audio_interactions)
     .bind(vark.total_reading_ms)
     .bind(vark.total_writing_actions)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(())
 }
@@ -277,7 +277,7 @@ pub async fn create_password_reset_token(
     .bind(user_id)
     .bind(token)
     .bind(expires_at)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(id)
 }
@@ -294,7 +294,7 @@ pub async fn get_valid_password_reset_token(
         LIMIT 100
         "#,
     )
-    .fetch_all(proxy.pool())
+    .fetch_all(&mut *proxy.acquire().await?)
     .await?;
 
     for r in rows {
@@ -325,7 +325,7 @@ pub async fn mark_password_reset_token_used(
 ) -> Result<(), sqlx::Error> {
     sqlx::query(r#"UPDATE "password_reset_tokens" SET "used" = TRUE WHERE "id" = $1"#)
         .bind(token_id)
-        .execute(proxy.pool())
+        .execute(&mut *proxy.acquire().await?)
         .await?;
     Ok(())
 }
@@ -338,7 +338,7 @@ pub async fn invalidate_user_reset_tokens(
         r#"UPDATE "password_reset_tokens" SET "used" = TRUE WHERE "userId" = $1 AND "used" = FALSE"#,
     )
     .bind(user_id)
-    .execute(proxy.pool())
+    .execute(&mut *proxy.acquire().await?)
     .await?;
     Ok(result.rows_affected())
 }
@@ -351,7 +351,7 @@ pub async fn get_last_reset_request_time(
         r#"SELECT "createdAt" FROM "password_reset_tokens" WHERE "userId" = $1 ORDER BY "createdAt" DESC LIMIT 1"#,
     )
     .bind(user_id)
-    .fetch_optional(proxy.pool())
+    .fetch_optional(&mut *proxy.acquire().await?)
     .await?;
     Ok(row.and_then(|r| r.try_get("createdAt").ok()))
 }
@@ -362,7 +362,7 @@ pub async fn get_user_id_by_email(
 ) -> Result<Option<String>, sqlx::Error> {
     let row = sqlx::query(r#"SELECT "id" FROM "users" WHERE "email" = $1 LIMIT 1"#)
         .bind(email)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *proxy.acquire().await?)
         .await?;
     Ok(row.and_then(|r| r.try_get("id").ok()))
 }
@@ -373,7 +373,7 @@ pub async fn get_user_interaction_stats(
 ) -> Result<Option<UserInteractionStats>, sqlx::Error> {
     let row = sqlx::query(r#"SELECT * FROM "user_interaction_stats" WHERE "userId" = $1 LIMIT 1"#)
         .bind(user_id)
-        .fetch_optional(proxy.pool())
+        .fetch_optional(&mut *proxy.acquire().await?)
         .await?;
     Ok(row.map(|r| {
         let created_at: NaiveDateTime = r
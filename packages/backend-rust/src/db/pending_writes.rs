@@ -1,35 +1,351 @@
-use sqlx::sqlite::SqliteRow;
-use sqlx::{Row, SqlitePool};
+//! SQLite queue of writes deferred while Postgres is unavailable.
+//!
+//! `DatabaseProxy::write_operation` queues here while the primary is
+//! degraded, and the health monitor replays the queue once it is back.
+//!
+//! Writes carry a priority class. Flushing drains critical writes (auth
+//! sessions, accounts) before normal user data and telemetry last. Each
+//! class has its own size quota, and a write to a full class evicts that
+//! class's oldest write, so the queue never outgrows the quotas' sum. Once
+//! the whole queue passes the pressure threshold, new telemetry is shed and
+//! a critical write evicts the oldest telemetry or normal write instead.
+//! Counters for every outcome are exported as Prometheus metrics.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnection, SqliteRow};
+use sqlx::{PgPool, Row, SqlitePool};
+
+use crate::db::schema_registry::is_valid_identifier;
+#[allow(deprecated)]
+use crate::db::{DbMutationError, WriteOperation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WritePriority {
+    Critical = 0,
+    Normal = 1,
+    Telemetry = 2,
+}
+
+impl WritePriority {
+    pub const ALL: [WritePriority; 3] = [
+        WritePriority::Critical,
+        WritePriority::Normal,
+        WritePriority::Telemetry,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WritePriority::Critical => "critical",
+            WritePriority::Normal => "normal",
+            WritePriority::Telemetry => "telemetry",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "critical" => Some(WritePriority::Critical),
+            "normal" => Some(WritePriority::Normal),
+            "telemetry" => Some(WritePriority::Telemetry),
+            _ => None,
+        }
+    }
+
+    /// Default class of a write to `table`.
+    pub fn for_table(table: &str) -> Self {
+        const CRITICAL: &[&str] = &["sessions", "users", "refresh_tokens", "user_data_keys"];
+        const TELEMETRY_PREFIXES: &[&str] = &[
+            "algorithm_",
+            "amas_decision",
+            "feature_vectors",
+            "visual_fatigue",
+            "decision_",
+            "pipeline_",
+        ];
+        if CRITICAL.contains(&table) {
+            WritePriority::Critical
+        } else if table.ends_with("_logs")
+            || TELEMETRY_PREFIXES.iter().any(|p| table.starts_with(p))
+        {
+            WritePriority::Telemetry
+        } else {
+            WritePriority::Normal
+        }
+    }
+}
+
+/// Per-class size limits.
+#[derive(Debug, Clone, Copy)]
+pub struct QueueQuotas {
+    pub critical: u64,
+    pub normal: u64,
+    pub telemetry: u64,
+    /// Above this many queued writes in total, telemetry is shed outright.
+    pub pressure_threshold: u64,
+}
+
+impl Default for QueueQuotas {
+    fn default() -> Self {
+        Self {
+            critical: 50_000,
+            normal: 200_000,
+            telemetry: 20_000,
+            pressure_threshold: 150_000,
+        }
+    }
+}
+
+impl QueueQuotas {
+    fn limit(&self, priority: WritePriority) -> u64 {
+        match priority {
+            WritePriority::Critical => self.critical,
+            WritePriority::Normal => self.normal,
+            WritePriority::Telemetry => self.telemetry,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnqueueOutcome {
+    Queued,
+    /// Queued after dropping the oldest write of the given class.
+    QueuedWithEviction(WritePriority),
+    Shed,
+}
 
 #[derive(Debug, Clone)]
 pub struct PendingWrite {
     pub operation_id: String,
+    pub priority: WritePriority,
     pub operation_data: serde_json::Value,
     pub created_at: Option<String>,
 }
 
+#[derive(Default)]
+struct ClassCounters {
+    queued: AtomicU64,
+    shed: AtomicU64,
+    evicted: AtomicU64,
+    flushed: AtomicU64,
+}
+
+static COUNTERS: [ClassCounters; 3] = [
+    ClassCounters {
+        queued: AtomicU64::new(0),
+        shed: AtomicU64::new(0),
+        evicted: AtomicU64::new(0),
+        flushed: AtomicU64::new(0),
+    },
+    ClassCounters {
+        queued: AtomicU64::new(0),
+        shed: AtomicU64::new(0),
+        evicted: AtomicU64::new(0),
+        flushed: AtomicU64::new(0),
+    },
+    ClassCounters {
+        queued: AtomicU64::new(0),
+        shed: AtomicU64::new(0),
+        evicted: AtomicU64::new(0),
+        flushed: AtomicU64::new(0),
+    },
+];
+
+fn counters(priority: WritePriority) -> &'static ClassCounters {
+    &COUNTERS[priority as usize]
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMetrics {
+    pub priority: WritePriority,
+    pub queued: u64,
+    pub shed: u64,
+    pub evicted: u64,
+    pub flushed: u64,
+}
+
+pub fn metrics() -> Vec<ClassMetrics> {
+    WritePriority::ALL
+        .iter()
+        .map(|&priority| {
+            let c = counters(priority);
+            ClassMetrics {
+                priority,
+                queued: c.queued.load(Ordering::Relaxed),
+                shed: c.shed.load(Ordering::Relaxed),
+                evicted: c.evicted.load(Ordering::Relaxed),
+                flushed: c.flushed.load(Ordering::Relaxed),
+            }
+        })
+        .collect()
+}
+
+/// Queue counters in Prometheus text format.
+pub fn prometheus_metrics() -> String {
+    let mut out = String::from(
+        "# HELP pending_writes_total Deferred writes by priority class and outcome\n\
+         # TYPE pending_writes_total counter\n",
+    );
+    for m in metrics() {
+        for (outcome, value) in [
+            ("queued", m.queued),
+            ("shed", m.shed),
+            ("evicted", m.evicted),
+            ("flushed", m.flushed),
+        ] {
+            out.push_str(&format!(
+                "pending_writes_total{{class=\"{}\",outcome=\"{outcome}\"}} {value}\n",
+                m.priority.as_str()
+            ));
+        }
+    }
+    out
+}
+
 #[derive(Clone)]
 pub struct SqlitePendingWriteStore {
     pool: SqlitePool,
+    quotas: QueueQuotas,
 }
 
 impl SqlitePendingWriteStore {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self::with_quotas(pool, QueueQuotas::default())
+    }
+
+    pub fn with_quotas(pool: SqlitePool, quotas: QueueQuotas) -> Self {
+        Self { pool, quotas }
     }
 
-    pub async fn save(&self, operation_id: &str, operation_data: &serde_json::Value) -> Result<(), sqlx::Error> {
+    /// Adds the priority column to queues created before it existed.
+    pub async fn ensure_schema(&self) -> Result<(), sqlx::Error> {
+        let has_priority: i64 = sqlx::query_scalar(
+            r#"SELECT COUNT(*) FROM pragma_table_info('_pending_writes') WHERE "name" = 'priority'"#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if has_priority == 0 {
+            sqlx::query(
+                r#"ALTER TABLE "_pending_writes" ADD COLUMN "priority" TEXT NOT NULL DEFAULT 'normal'"#,
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        sqlx::query(
+            r#"CREATE INDEX IF NOT EXISTS "idx_pending_writes_priority" ON "_pending_writes" ("priority", "created_at")"#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Queues a write, applying the class quotas. The quota check and the
+    /// insert run in one `BEGIN IMMEDIATE` transaction, so concurrent saves
+    /// cannot both pass a check against the same count.
+    pub async fn save(
+        &self,
+        operation_id: &str,
+        priority: WritePriority,
+        operation_data: &serde_json::Value,
+    ) -> Result<EnqueueOutcome, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+        let result = self
+            .save_locked(&mut conn, operation_id, priority, operation_data)
+            .await;
+        let end = if result.is_ok() { "COMMIT" } else { "ROLLBACK" };
+        sqlx::query(end).execute(&mut *conn).await?;
+
+        let outcome = result?;
+        let c = counters(priority);
+        match outcome {
+            EnqueueOutcome::Shed => {
+                c.shed.fetch_add(1, Ordering::Relaxed);
+            }
+            EnqueueOutcome::QueuedWithEviction(victim) => {
+                counters(victim).evicted.fetch_add(1, Ordering::Relaxed);
+                c.queued.fetch_add(1, Ordering::Relaxed);
+            }
+            EnqueueOutcome::Queued => {
+                c.queued.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(outcome)
+    }
+
+    async fn save_locked(
+        &self,
+        conn: &mut SqliteConnection,
+        operation_id: &str,
+        priority: WritePriority,
+        operation_data: &serde_json::Value,
+    ) -> Result<EnqueueOutcome, sqlx::Error> {
+        let counts = Self::counts_on(&mut *conn).await?;
+        let total: u64 = counts.iter().sum();
+        let class_full = counts[priority as usize] >= self.quotas.limit(priority);
+        let under_pressure = total >= self.quotas.pressure_threshold;
+
+        let mut outcome = EnqueueOutcome::Queued;
+        if priority == WritePriority::Telemetry && under_pressure && !class_full {
+            return Ok(EnqueueOutcome::Shed);
+        }
+        if class_full {
+            if !Self::evict_oldest(&mut *conn, priority).await? {
+                // A zero quota leaves nothing to evict.
+                return Ok(EnqueueOutcome::Shed);
+            }
+            outcome = EnqueueOutcome::QueuedWithEviction(priority);
+        } else if priority == WritePriority::Critical && under_pressure {
+            for victim in [WritePriority::Telemetry, WritePriority::Normal] {
+                if counts[victim as usize] > 0 && Self::evict_oldest(&mut *conn, victim).await? {
+                    outcome = EnqueueOutcome::QueuedWithEviction(victim);
+                    break;
+                }
+            }
+        }
+
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO "_pending_writes" ("operation_id", "operation_data", "created_at")
-            VALUES (?, ?, CURRENT_TIMESTAMP)
+            INSERT OR REPLACE INTO "_pending_writes" ("operation_id", "operation_data", "priority", "created_at")
+            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
             "#,
         )
         .bind(operation_id)
         .bind(operation_data.to_string())
-        .execute(&self.pool)
+        .bind(priority.as_str())
+        .execute(&mut *conn)
         .await?;
+        Ok(outcome)
+    }
 
+    async fn evict_oldest(
+        conn: &mut SqliteConnection,
+        priority: WritePriority,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM "_pending_writes" WHERE "operation_id" = (
+                SELECT "operation_id" FROM "_pending_writes"
+                WHERE "priority" = ?
+                ORDER BY "created_at" ASC, "rowid" ASC
+                LIMIT 1
+            )
+            "#,
+        )
+        .bind(priority.as_str())
+        .execute(conn)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes a write after it has been replayed against Postgres.
+    pub async fn mark_flushed(&self, write: &PendingWrite) -> Result<(), sqlx::Error> {
+        self.remove(&write.operation_id).await?;
+        counters(write.priority)
+            .flushed
+            .fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
 
@@ -41,25 +357,51 @@ impl SqlitePendingWriteStore {
         Ok(())
     }
 
-    pub async fn get_all(&self) -> Result<Vec<PendingWrite>, sqlx::Error> {
+    /// Next writes to flush: critical first, then normal, then telemetry,
+    /// each oldest first.
+    pub async fn next_batch(&self, limit: u32) -> Result<Vec<PendingWrite>, sqlx::Error> {
         let rows = sqlx::query(
             r#"
-            SELECT "operation_id", "operation_data", "created_at"
+            SELECT "operation_id", "operation_data", "priority", "created_at"
             FROM "_pending_writes"
-            ORDER BY "created_at" ASC
+            ORDER BY CASE "priority" WHEN 'critical' THEN 0 WHEN 'normal' THEN 1 ELSE 2 END,
+                     "created_at" ASC, "rowid" ASC
+            LIMIT ?
             "#,
         )
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows.into_iter().filter_map(map_pending_row).collect())
     }
 
+    /// Queued writes per class, indexed by `WritePriority as usize`.
+    pub async fn counts(&self) -> Result<[u64; 3], sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        Self::counts_on(&mut conn).await
+    }
+
+    async fn counts_on(conn: &mut SqliteConnection) -> Result<[u64; 3], sqlx::Error> {
+        let rows = sqlx::query(
+            r#"SELECT "priority", COUNT(*) AS "count" FROM "_pending_writes" GROUP BY "priority""#,
+        )
+        .fetch_all(conn)
+        .await?;
+
+        let mut counts = [0u64; 3];
+        for row in rows {
+            let priority: String = row.try_get("priority")?;
+            let count: i64 = row.try_get("count")?;
+            if let Some(priority) = WritePriority::parse(&priority) {
+                counts[priority as usize] = count.max(0) as u64;
+            }
+        }
+        Ok(counts)
+    }
+
     pub async fn count(&self) -> Result<u64, sqlx::Error> {
-        let count: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM "_pending_writes""#)
-            .fetch_one(&self.pool)
-            .await?;
-        Ok(count.max(0) as u64)
+        Ok(self.counts().await?.iter().sum())
     }
 
     pub async fn clear(&self) -> Result<(), sqlx::Error> {
@@ -70,16 +412,337 @@ impl SqlitePendingWriteStore {
     }
 }
 
+/// Statement replaying a queued write on the primary, with its JSON
+/// parameters. Values are typed by the target table's row type through
+/// `jsonb_populate_record`, so no per-column mapping is needed.
+#[allow(deprecated)]
+fn replay_statement(
+    operation: &WriteOperation,
+) -> Result<(String, Vec<serde_json::Value>), DbMutationError> {
+    let record = |table: &str, param: usize| {
+        format!(r#"jsonb_populate_record(NULL::"{table}", ${param}::jsonb)"#)
+    };
+    match operation {
+        WriteOperation::Insert { table, data, .. } => {
+            let table = identifier(table)?;
+            let columns = column_list(data)?;
+            let sql = format!(
+                r#"INSERT INTO "{table}" ({columns}) SELECT {columns} FROM {}"#,
+                record(table, 1)
+            );
+            Ok((sql, vec![serde_json::Value::Object(data.clone())]))
+        }
+        WriteOperation::Update {
+            table,
+            r#where,
+            data,
+            ..
+        } => {
+            let table = identifier(table)?;
+            let columns = column_list(data)?;
+            let keys = column_list(r#where)?;
+            let sql = format!(
+                r#"UPDATE "{table}" SET ({columns}) = (SELECT {columns} FROM {}) WHERE ({keys}) = (SELECT {keys} FROM {})"#,
+                record(table, 1),
+                record(table, 2)
+            );
+            Ok((
+                sql,
+                vec![
+                    serde_json::Value::Object(data.clone()),
+                    serde_json::Value::Object(r#where.clone()),
+                ],
+            ))
+        }
+        WriteOperation::Delete { table, r#where, .. } => {
+            let table = identifier(table)?;
+            let keys = column_list(r#where)?;
+            let sql = format!(
+                r#"DELETE FROM "{table}" WHERE ({keys}) = (SELECT {keys} FROM {})"#,
+                record(table, 1)
+            );
+            Ok((sql, vec![serde_json::Value::Object(r#where.clone())]))
+        }
+        WriteOperation::Upsert {
+            table,
+            r#where,
+            create,
+            update,
+            ..
+        } => {
+            let table = identifier(table)?;
+            let columns = column_list(create)?;
+            let keys = column_list(r#where)?;
+            let on_conflict = if update.is_empty() {
+                "DO NOTHING".to_string()
+            } else {
+                let updated = column_list(update)?;
+                format!(
+                    "DO UPDATE SET ({updated}) = (SELECT {updated} FROM {})",
+                    record(table, 2)
+                )
+            };
+            let sql = format!(
+                r#"INSERT INTO "{table}" ({columns}) SELECT {columns} FROM {} ON CONFLICT ({keys}) {on_conflict}"#,
+                record(table, 1)
+            );
+            Ok((
+                sql,
+                vec![
+                    serde_json::Value::Object(create.clone()),
+                    serde_json::Value::Object(update.clone()),
+                ],
+            ))
+        }
+    }
+}
+
+fn identifier(name: &str) -> Result<&str, DbMutationError> {
+    if is_valid_identifier(name) {
+        Ok(name)
+    } else {
+        Err(DbMutationError::Custom(format!(
+            "invalid identifier: {name}"
+        )))
+    }
+}
+
+fn column_list(
+    values: &serde_json::Map<String, serde_json::Value>,
+) -> Result<String, DbMutationError> {
+    if values.is_empty() {
+        return Err(DbMutationError::Custom(
+            "write operation has no columns".to_string(),
+        ));
+    }
+    let columns: Vec<String> = values
+        .keys()
+        .map(|c| identifier(c).map(|c| format!(r#""{c}""#)))
+        .collect::<Result<_, _>>()?;
+    Ok(columns.join(", "))
+}
+
+/// Runs a queued write against the primary.
+#[allow(deprecated)]
+pub async fn replay(pool: &PgPool, operation: &WriteOperation) -> Result<(), DbMutationError> {
+    let (sql, params) = replay_statement(operation)?;
+    let mut query = sqlx::query(&sql);
+    for param in params {
+        query = query.bind(param);
+    }
+    query.execute(pool).await?;
+    Ok(())
+}
+
 fn map_pending_row(row: SqliteRow) -> Option<PendingWrite> {
     let operation_id: String = row.try_get("operation_id").ok()?;
     let raw: String = row.try_get("operation_data").ok()?;
     let operation_data: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    let priority = row
+        .try_get::<String, _>("priority")
+        .ok()
+        .and_then(|p| WritePriority::parse(&p))
+        .unwrap_or(WritePriority::Normal);
     let created_at: Option<String> = row.try_get("created_at").ok();
 
     Some(PendingWrite {
         operation_id,
+        priority,
         operation_data,
         created_at,
     })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn store(quotas: QueueQuotas) -> SqlitePendingWriteStore {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"CREATE TABLE "_pending_writes" (
+                "operation_id" TEXT PRIMARY KEY,
+                "operation_data" TEXT NOT NULL,
+                "created_at" TEXT DEFAULT CURRENT_TIMESTAMP
+            )"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let store = SqlitePendingWriteStore::with_quotas(pool, quotas);
+        store.ensure_schema().await.unwrap();
+        store
+    }
+
+    #[test]
+    fn tables_map_to_classes() {
+        assert_eq!(
+            WritePriority::for_table("sessions"),
+            WritePriority::Critical
+        );
+        assert_eq!(
+            WritePriority::for_table("answer_records"),
+            WritePriority::Normal
+        );
+        assert_eq!(
+            WritePriority::for_table("algorithm_metrics_daily"),
+            WritePriority::Telemetry
+        );
+        assert_eq!(
+            WritePriority::for_table("audit_logs"),
+            WritePriority::Telemetry
+        );
+    }
+
+    #[tokio::test]
+    async fn flushes_by_class_then_age() {
+        let store = store(QueueQuotas::default()).await;
+        let data = serde_json::json!({});
+        store
+            .save("t1", WritePriority::Telemetry, &data)
+            .await
+            .unwrap();
+        store
+            .save("n1", WritePriority::Normal, &data)
+            .await
+            .unwrap();
+        store
+            .save("c1", WritePriority::Critical, &data)
+            .await
+            .unwrap();
+        store
+            .save("n2", WritePriority::Normal, &data)
+            .await
+            .unwrap();
+
+        let order: Vec<String> = store
+            .next_batch(10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|w| w.operation_id)
+            .collect();
+        assert_eq!(order, ["c1", "n1", "n2", "t1"]);
+    }
+
+    #[tokio::test]
+    async fn sheds_telemetry_and_evicts_within_full_classes() {
+        let store = store(QueueQuotas {
+            critical: 1,
+            normal: 1,
+            telemetry: 2,
+            pressure_threshold: 3,
+        })
+        .await;
+        let data = serde_json::json!({});
+        let save = |id: &'static str, priority| {
+            let store = store.clone();
+            let data = data.clone();
+            async move { store.save(id, priority, &data).await.unwrap() }
+        };
+
+        assert_eq!(
+            save("n1", WritePriority::Normal).await,
+            EnqueueOutcome::Queued
+        );
+        // A full class replaces its own oldest write.
+        assert_eq!(
+            save("n2", WritePriority::Normal).await,
+            EnqueueOutcome::QueuedWithEviction(WritePriority::Normal)
+        );
+        assert_eq!(
+            save("t1", WritePriority::Telemetry).await,
+            EnqueueOutcome::Queued
+        );
+        assert_eq!(
+            save("c1", WritePriority::Critical).await,
+            EnqueueOutcome::Queued
+        );
+        // Three queued: the queue is under pressure, telemetry is shed.
+        assert_eq!(
+            save("t2", WritePriority::Telemetry).await,
+            EnqueueOutcome::Shed
+        );
+        // Critical quota is full: the oldest critical write makes room.
+        assert_eq!(
+            save("c2", WritePriority::Critical).await,
+            EnqueueOutcome::QueuedWithEviction(WritePriority::Critical)
+        );
+        assert_eq!(store.counts().await.unwrap(), [1, 1, 1]);
+        let ids: Vec<String> = store
+            .next_batch(10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|w| w.operation_id)
+            .collect();
+        assert_eq!(ids, ["c2", "n2", "t1"]);
+
+        let batch = store.next_batch(1).await.unwrap();
+        store.mark_flushed(&batch[0]).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 2);
+        assert!(prometheus_metrics().contains("class=\"normal\",outcome=\"evicted\""));
+        assert!(prometheus_metrics().contains("class=\"telemetry\",outcome=\"shed\""));
+    }
+
+    #[tokio::test]
+    async fn critical_under_pressure_evicts_lower_classes() {
+        let store = store(QueueQuotas {
+            critical: 5,
+            normal: 5,
+            telemetry: 5,
+            pressure_threshold: 2,
+        })
+        .await;
+        let data = serde_json::json!({});
+        store
+            .save("n1", WritePriority::Normal, &data)
+            .await
+            .unwrap();
+        store
+            .save("t1", WritePriority::Telemetry, &data)
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .save("c1", WritePriority::Critical, &data)
+                .await
+                .unwrap(),
+            EnqueueOutcome::QueuedWithEviction(WritePriority::Telemetry)
+        );
+        assert_eq!(store.counts().await.unwrap(), [1, 1, 0]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn replay_statements_type_values_by_row() {
+        let row = |value: serde_json::Value| value.as_object().unwrap().clone();
+        let (sql, params) = replay_statement(&WriteOperation::Update {
+            table: "notifications".to_string(),
+            r#where: row(serde_json::json!({"id": "n1"})),
+            data: row(serde_json::json!({"isRead": true})),
+            operation_id: "op".to_string(),
+            timestamp_ms: None,
+            critical: None,
+        })
+        .unwrap();
+        assert_eq!(
+            sql,
+            r#"UPDATE "notifications" SET ("isRead") = (SELECT "isRead" FROM jsonb_populate_record(NULL::"notifications", $1::jsonb)) WHERE ("id") = (SELECT "id" FROM jsonb_populate_record(NULL::"notifications", $2::jsonb))"#
+        );
+        assert_eq!(params[1], serde_json::json!({"id": "n1"}));
+
+        let injected = replay_statement(&WriteOperation::Delete {
+            table: "users\"; DROP TABLE x; --".to_string(),
+            r#where: row(serde_json::json!({"id": "u1"})),
+            operation_id: "op".to_string(),
+            timestamp_ms: None,
+            critical: None,
+        });
+        assert!(injected.is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! Postgres connection pool observability and read offloading.
+//!
+//! sqlx reports pool size and idle connections; acquire latency and the
+//! number of waiting callers are measured here around `PoolMonitor::acquire`.
+//! Real traffic goes through it via `DatabaseProxy::acquire` / `begin`, which
+//! the repository layer (`db::operations`) uses, and the health monitor
+//! samples one acquire per tick so the numbers stay fresh while idle.
+//!
+//! When the smoothed acquire latency crosses the configured threshold while
+//! the database is in `Normal` state, read-only paths that have a SQLite
+//! implementation (word search, data export) are offloaded to the fallback
+//! for a cooldown window. The window is re-armed as long as latency stays
+//! high and lapses on its own once the pool recovers.
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Instant;
+
+use serde::Serialize;
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+
+use crate::db::config::PoolConfig;
+use crate::db::state_machine::DatabaseState;
+
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    pub max_connections: u32,
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub waiters: u64,
+    pub acquire_total: u64,
+    pub acquire_timeouts: u64,
+    pub acquire_latency_ms: f64,
+    pub acquire_latency_max_ms: f64,
+    pub read_offload_active: bool,
+    pub reads_offloaded: u64,
+}
+
+#[derive(Debug)]
+pub struct PoolMonitor {
+    config: PoolConfig,
+    waiters: AtomicU64,
+    acquire_total: AtomicU64,
+    acquire_timeouts: AtomicU64,
+    latency_ewma_bits: AtomicU64,
+    latency_max_bits: AtomicU64,
+    offload_until_ms: AtomicI64,
+    reads_offloaded: AtomicU64,
+}
+
+impl PoolMonitor {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            waiters: AtomicU64::new(0),
+            acquire_total: AtomicU64::new(0),
+            acquire_timeouts: AtomicU64::new(0),
+            latency_ewma_bits: AtomicU64::new(0f64.to_bits()),
+            latency_max_bits: AtomicU64::new(0f64.to_bits()),
+            offload_until_ms: AtomicI64::new(0),
+            reads_offloaded: AtomicU64::new(0),
+        }
+    }
+
+    /// Acquires a connection, recording wait time and the waiter gauge.
+    pub async fn acquire(&self, pool: &PgPool) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        let started = Instant::now();
+        let result = {
+            let _waiting = WaiterGuard::new(&self.waiters);
+            pool.acquire().await
+        };
+
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        self.acquire_total.fetch_add(1, Ordering::Relaxed);
+        if matches!(result, Err(sqlx::Error::PoolTimedOut)) {
+            self.acquire_timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+        self.record_latency(elapsed_ms);
+        result
+    }
+
+    fn record_latency(&self, ms: f64) {
+        let _ = self
+            .latency_ewma_bits
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                let prev = f64::from_bits(bits);
+                let next = if self.acquire_total.load(Ordering::Relaxed) <= 1 {
+                    ms
+                } else {
+                    prev + LATENCY_EWMA_ALPHA * (ms - prev)
+                };
+                Some(next.to_bits())
+            });
+        let _ = self
+            .latency_max_bits
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                (ms > f64::from_bits(bits)).then_some(ms.to_bits())
+            });
+    }
+
+    pub fn acquire_latency_ms(&self) -> f64 {
+        f64::from_bits(self.latency_ewma_bits.load(Ordering::Relaxed))
+    }
+
+    /// Re-evaluates the offload window; called after each pool sample.
+    pub fn evaluate(&self, state: DatabaseState, now_ms: i64) {
+        if should_offload(state, self.acquire_latency_ms(), &self.config) {
+            let until = now_ms + self.config.offload_cooldown.as_millis() as i64;
+            self.offload_until_ms.fetch_max(until, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether read-only queries should currently prefer the fallback.
+    pub fn read_offload_active(&self, now_ms: i64) -> bool {
+        self.config.read_offload_enabled() && self.offload_until_ms.load(Ordering::Relaxed) > now_ms
+    }
+
+    pub fn note_read_offloaded(&self) {
+        self.reads_offloaded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self, pool: &PgPool, now_ms: i64) -> PoolStats {
+        let size = pool.size();
+        let idle = (pool.num_idle() as u32).min(size);
+        PoolStats {
+            max_connections: self.config.max_connections,
+            size,
+            idle,
+            in_use: size - idle,
+            waiters: self.waiters.load(Ordering::Relaxed),
+            acquire_total: self.acquire_total.load(Ordering::Relaxed),
+            acquire_timeouts: self.acquire_timeouts.load(Ordering::Relaxed),
+            acquire_latency_ms: self.acquire_latency_ms(),
+            acquire_latency_max_ms: f64::from_bits(self.latency_max_bits.load(Ordering::Relaxed)),
+            read_offload_active: self.read_offload_active(now_ms),
+            reads_offloaded: self.reads_offloaded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Keeps the waiter gauge correct when an acquire future is dropped.
+struct WaiterGuard<'a>(&'a AtomicU64);
+
+impl<'a> WaiterGuard<'a> {
+    fn new(waiters: &'a AtomicU64) -> Self {
+        waiters.fetch_add(1, Ordering::Relaxed);
+        Self(waiters)
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Offload only from a healthy primary that is merely congested; in
+/// `Degraded`/`Unavailable` the state machine already routes reads.
+pub fn should_offload(state: DatabaseState, latency_ms: f64, config: &PoolConfig) -> bool {
+    config.read_offload_enabled()
+        && state == DatabaseState::Normal
+        && latency_ms > config.offload_latency.as_secs_f64() * 1000.0
+}
+
+/// Pool gauges and counters in Prometheus text format.
+pub fn prometheus_metrics(stats: &PoolStats) -> String {
+    let mut out = String::new();
+    for (name, help, kind, value) in [
+        (
+            "db_pool_max_connections",
+            "Configured Postgres pool size",
+            "gauge",
+            stats.max_connections as f64,
+        ),
+        (
+            "db_pool_connections",
+            "Open Postgres connections",
+            "gauge",
+            stats.size as f64,
+        ),
+        (
+            "db_pool_connections_in_use",
+            "Postgres connections checked out",
+            "gauge",
+            stats.in_use as f64,
+        ),
+        (
+            "db_pool_waiters",
+            "Callers waiting to acquire a connection",
+            "gauge",
+            stats.waiters as f64,
+        ),
+        (
+            "db_pool_acquire_latency_ms",
+            "Smoothed connection acquire latency",
+            "gauge",
+            stats.acquire_latency_ms,
+        ),
+        (
+            "db_pool_acquire_timeouts_total",
+            "Connection acquires that timed out",
+            "counter",
+            stats.acquire_timeouts as f64,
+        ),
+        (
+            "db_pool_read_offload_active",
+            "Whether reads are offloaded to the fallback",
+            "gauge",
+            if stats.read_offload_active { 1.0 } else { 0.0 },
+        ),
+        (
+            "db_pool_reads_offloaded_total",
+            "Read-only queries routed to the fallback",
+            "counter",
+            stats.reads_offloaded as f64,
+        ),
+    ] {
+        out.push_str(&format!(
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config() -> PoolConfig {
+        PoolConfig {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(5),
+            offload_latency: Duration::from_millis(200),
+            offload_cooldown: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn offload_only_when_normal_and_slow() {
+        let config = config();
+        assert!(should_offload(DatabaseState::Normal, 250.0, &config));
+        assert!(!should_offload(DatabaseState::Normal, 150.0, &config));
+        assert!(!should_offload(DatabaseState::Degraded, 250.0, &config));
+
+        let disabled = PoolConfig {
+            offload_latency: Duration::ZERO,
+            ..config
+        };
+        assert!(!should_offload(DatabaseState::Normal, 10_000.0, &disabled));
+    }
+
+    #[test]
+    fn offload_window_lapses_after_cooldown() {
+        let monitor = PoolMonitor::new(config());
+        monitor.acquire_total.store(1, Ordering::Relaxed);
+        monitor.record_latency(500.0);
+        monitor.evaluate(DatabaseState::Normal, 1_000);
+        assert!(monitor.read_offload_active(1_000));
+        assert!(monitor.read_offload_active(30_999));
+        assert!(!monitor.read_offload_active(31_000));
+    }
+}
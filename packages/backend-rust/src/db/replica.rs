@@ -0,0 +1,290 @@
+//! Read replicas for heavy read-only queries.
+//!
+//! Replica pools are configured with `DATABASE_REPLICA_URLS` and created
+//! lazily, so an unreachable replica never blocks startup. The health
+//! monitor checks each replica on every tick, alongside the primary, and
+//! records its replication lag. A read names the lag it tolerates; replicas
+//! that are unhealthy, have unknown lag or lag more than that are skipped in
+//! round-robin order. When none qualifies, or the chosen replica fails with a
+//! connection-level error, the read runs on the primary instead. Such a
+//! failure marks the replica unhealthy until the next successful check.
+
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgPool, Row};
+
+use crate::db::config::{HealthCheckConfig, ReplicaConfig};
+use crate::db::health_monitor::{HealthCheckResult, HealthTracker};
+
+/// Replication lag in milliseconds; zero on a caught-up standby or when the
+/// URL points at a primary.
+const LAG_SQL: &str = r#"
+SELECT CASE
+    WHEN NOT pg_is_in_recovery() THEN 0
+    WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+    ELSE COALESCE(EXTRACT(EPOCH FROM (now() - pg_last_xact_replay_timestamp())) * 1000, 0)
+END::float8 AS "lagMs"
+"#;
+
+/// Marks lag that has not been measured yet or whose check failed.
+const LAG_UNKNOWN: u64 = u64::MAX;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaStatus {
+    pub name: String,
+    /// `host:port`, without credentials.
+    pub host: String,
+    pub healthy: bool,
+    pub lag_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub size: u32,
+    pub idle: u32,
+    pub reads: u64,
+    /// Reads that failed here and were retried on the primary.
+    pub fallbacks: u64,
+}
+
+struct ReplicaPool {
+    name: String,
+    host: String,
+    pool: PgPool,
+    health: Mutex<HealthTracker>,
+    lag_ms: AtomicU64,
+    reads: AtomicU64,
+    fallbacks: AtomicU64,
+}
+
+impl ReplicaPool {
+    fn usable(&self, max_staleness: Duration) -> bool {
+        let healthy = self
+            .health
+            .lock()
+            .map(|tracker| tracker.snapshot().healthy)
+            .unwrap_or(false);
+        is_usable(healthy, self.lag(), max_staleness)
+    }
+
+    fn lag(&self) -> Option<u64> {
+        match self.lag_ms.load(Ordering::Relaxed) {
+            LAG_UNKNOWN => None,
+            lag => Some(lag),
+        }
+    }
+
+    fn record(&self, result: HealthCheckResult) {
+        if let Ok(mut tracker) = self.health.lock() {
+            tracker.process(result);
+        }
+    }
+
+    fn status(&self) -> ReplicaStatus {
+        let snapshot = self.health.lock().map(|tracker| tracker.snapshot()).ok();
+        let size = self.pool.size();
+        ReplicaStatus {
+            name: self.name.clone(),
+            host: self.host.clone(),
+            healthy: snapshot.as_ref().is_some_and(|s| s.healthy),
+            lag_ms: self.lag(),
+            consecutive_failures: snapshot.as_ref().map_or(0, |s| s.consecutive_failures),
+            last_error: snapshot.and_then(|s| s.error),
+            size,
+            idle: (self.pool.num_idle() as u32).min(size),
+            reads: self.reads.load(Ordering::Relaxed),
+            fallbacks: self.fallbacks.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub struct ReplicaSet {
+    replicas: Vec<ReplicaPool>,
+    cursor: AtomicUsize,
+    max_staleness: Duration,
+}
+
+impl ReplicaSet {
+    /// Builds the configured pools; URLs that fail to parse are skipped with
+    /// a warning.
+    pub fn from_config(config: &ReplicaConfig, health: &HealthCheckConfig) -> Self {
+        let replicas = config
+            .urls
+            .iter()
+            .enumerate()
+            .filter_map(|(index, url)| {
+                let options = match PgConnectOptions::from_str(url) {
+                    Ok(options) => options,
+                    Err(err) => {
+                        tracing::warn!(index, error = %err, "Invalid replica URL, skipping");
+                        return None;
+                    }
+                };
+                let host = format!("{}:{}", options.get_host(), options.get_port());
+                let pool = PgPoolOptions::new()
+                    .max_connections(config.max_connections)
+                    .acquire_timeout(health.timeout)
+                    .connect_lazy_with(options);
+                tracing::info!(index, host = %host, "Read replica configured");
+                Some(ReplicaPool {
+                    name: format!("replica-{index}"),
+                    host,
+                    pool,
+                    health: Mutex::new(HealthTracker::new(health.clone())),
+                    lag_ms: AtomicU64::new(LAG_UNKNOWN),
+                    reads: AtomicU64::new(0),
+                    fallbacks: AtomicU64::new(0),
+                })
+            })
+            .collect();
+
+        Self {
+            replicas,
+            cursor: AtomicUsize::new(0),
+            max_staleness: config.max_staleness,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replicas.is_empty()
+    }
+
+    pub fn max_staleness(&self) -> Duration {
+        self.max_staleness
+    }
+
+    /// Next usable replica in round-robin order.
+    fn pick(&self, max_staleness: Duration) -> Option<&ReplicaPool> {
+        let count = self.replicas.len();
+        if count == 0 {
+            return None;
+        }
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed);
+        (0..count)
+            .map(|offset| &self.replicas[(start + offset) % count])
+            .find(|replica| replica.usable(max_staleness))
+    }
+
+    /// Runs `query` on a replica within `max_staleness`, falling back to
+    /// `primary` when none is usable or the replica fails to serve it.
+    pub async fn read<T, F, Fut>(
+        &self,
+        primary: &PgPool,
+        max_staleness: Duration,
+        query: F,
+    ) -> Result<T, sqlx::Error>
+    where
+        F: Fn(PgPool) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        if let Some(replica) = self.pick(max_staleness) {
+            match query(replica.pool.clone()).await {
+                Ok(value) => {
+                    replica.reads.fetch_add(1, Ordering::Relaxed);
+                    return Ok(value);
+                }
+                Err(err) if should_fall_back(&err) => {
+                    tracing::warn!(
+                        replica = %replica.name,
+                        error = %err,
+                        "Replica read failed, retrying on primary"
+                    );
+                    replica.fallbacks.fetch_add(1, Ordering::Relaxed);
+                    replica.record(HealthCheckResult::unhealthy(err.to_string()));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        query(primary.clone()).await
+    }
+
+    /// Checks every replica once and records its health and lag.
+    pub async fn check_health(&self, timeout: Duration) {
+        for replica in &self.replicas {
+            let started = std::time::Instant::now();
+            let result =
+                tokio::time::timeout(timeout, sqlx::query(LAG_SQL).fetch_one(&replica.pool)).await;
+            let outcome = match result {
+                Ok(Ok(row)) => {
+                    let lag: f64 = row.try_get("lagMs").unwrap_or(0.0);
+                    replica
+                        .lag_ms
+                        .store(lag.max(0.0).round() as u64, Ordering::Relaxed);
+                    HealthCheckResult::healthy(started.elapsed())
+                }
+                Ok(Err(err)) => {
+                    replica.lag_ms.store(LAG_UNKNOWN, Ordering::Relaxed);
+                    HealthCheckResult::unhealthy(err.to_string())
+                }
+                Err(_) => {
+                    replica.lag_ms.store(LAG_UNKNOWN, Ordering::Relaxed);
+                    HealthCheckResult::unhealthy("timeout".to_string())
+                }
+            };
+            if !outcome.healthy {
+                tracing::warn!(
+                    replica = %replica.name,
+                    error = outcome.error.as_deref().unwrap_or(""),
+                    "Replica health check failed"
+                );
+            }
+            replica.record(outcome);
+        }
+    }
+
+    pub fn status(&self) -> Vec<ReplicaStatus> {
+        self.replicas.iter().map(ReplicaPool::status).collect()
+    }
+}
+
+fn is_usable(healthy: bool, lag_ms: Option<u64>, max_staleness: Duration) -> bool {
+    healthy && lag_ms.is_some_and(|lag| u128::from(lag) <= max_staleness.as_millis())
+}
+
+/// Errors that say nothing about the query itself: the replica is down,
+/// saturated, or cancelled the read over a replication conflict
+/// (SQLSTATE 40001). Retrying a read on the primary is safe for these.
+fn should_fall_back(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::Protocol(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(db) => db.code().as_deref() == Some("40001"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usable_requires_health_and_known_lag_within_tolerance() {
+        let tolerance = Duration::from_secs(5);
+        assert!(is_usable(true, Some(0), tolerance));
+        assert!(is_usable(true, Some(5_000), tolerance));
+        assert!(!is_usable(true, Some(5_001), tolerance));
+        assert!(!is_usable(true, None, tolerance));
+        assert!(!is_usable(false, Some(0), tolerance));
+    }
+
+    #[test]
+    fn falls_back_only_on_connection_level_errors() {
+        assert!(should_fall_back(&sqlx::Error::PoolTimedOut));
+        assert!(should_fall_back(&sqlx::Error::Io(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!should_fall_back(&sqlx::Error::RowNotFound));
+        assert!(!should_fall_back(&sqlx::Error::ColumnNotFound(
+            "x".to_string()
+        )));
+    }
+}
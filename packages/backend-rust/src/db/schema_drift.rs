@@ -0,0 +1,489 @@
+//! Drift between the Prisma schema registry and the live databases.
+//!
+//! The registry (`sql/schema_registry.json`) describes the tables the write
+//! paths expect, but hotfixes applied by hand can leave a live database with
+//! a column missing or retyped. The monitor introspects Postgres
+//! (`information_schema.columns`) and, when present, the SQLite fallback
+//! (`pragma_table_info`), and compares every registry table against them:
+//! missing tables, missing columns and columns whose type no longer fits the
+//! Prisma type. Columns the registry does not know about are ignored. The
+//! registry keeps Prisma field names, not `@map`ped column names, so a field
+//! missing under its own name is looked up again in snake_case.
+//!
+//! Types are compared by family rather than by exact name, so `varchar` and
+//! `text` both satisfy `String` and a Postgres enum does too; SQLite columns
+//! are compared by type affinity. Every table with a finding is blocked for
+//! dual-write until a later check comes back clean.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row, SqlitePool};
+
+use crate::db::schema_registry::{FieldSchema, SchemaRegistry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DbBackend {
+    Postgres,
+    Sqlite,
+}
+
+impl DbBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DbBackend::Postgres => "postgres",
+            DbBackend::Sqlite => "sqlite",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DriftKind {
+    MissingTable,
+    MissingColumn,
+    TypeMismatch,
+}
+
+impl DriftKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftKind::MissingTable => "missing_table",
+            DriftKind::MissingColumn => "missing_column",
+            DriftKind::TypeMismatch => "type_mismatch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftFinding {
+    pub backend: DbBackend,
+    pub table: String,
+    /// `None` for a missing table.
+    pub column: Option<String>,
+    pub kind: DriftKind,
+    /// Prisma type the registry expects.
+    pub expected: Option<String>,
+    /// Type found in the live database.
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaDriftReport {
+    pub checked_at: DateTime<Utc>,
+    pub backends: Vec<DbBackend>,
+    pub tables_checked: usize,
+    pub findings: Vec<DriftFinding>,
+    /// Tables with at least one finding; dual-write refuses them.
+    pub blocked_tables: Vec<String>,
+}
+
+/// Type of a live column. `element_type` is set for Postgres arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveColumn {
+    pub data_type: String,
+    pub element_type: Option<String>,
+}
+
+/// Live columns by table, then by column name.
+pub type LiveSchema = HashMap<String, HashMap<String, LiveColumn>>;
+
+pub struct SchemaDriftMonitor {
+    registry: SchemaRegistry,
+    block_dual_write: bool,
+    latest: RwLock<Option<SchemaDriftReport>>,
+}
+
+impl SchemaDriftMonitor {
+    pub fn new(registry: SchemaRegistry, block_dual_write: bool) -> Self {
+        Self {
+            registry,
+            block_dual_write,
+            latest: RwLock::new(None),
+        }
+    }
+
+    /// Result of the most recent check, `None` before the first one.
+    pub fn latest(&self) -> Option<SchemaDriftReport> {
+        self.latest.read().ok().and_then(|latest| latest.clone())
+    }
+
+    /// Whether dual-write to `table` is refused because the last check
+    /// found it drifted.
+    pub fn blocks_dual_write(&self, table: &str) -> bool {
+        if !self.block_dual_write {
+            return false;
+        }
+        self.latest.read().is_ok_and(|latest| {
+            latest
+                .as_ref()
+                .is_some_and(|report| report.blocked_tables.iter().any(|t| t == table))
+        })
+    }
+
+    /// Introspects the databases, logs findings not seen by the previous
+    /// check (and drift that has cleared), and keeps the report.
+    pub async fn check(
+        &self,
+        pg: &PgPool,
+        sqlite: Option<&SqlitePool>,
+    ) -> Result<SchemaDriftReport, sqlx::Error> {
+        let mut backends = vec![DbBackend::Postgres];
+        let mut findings = compare(&self.registry, DbBackend::Postgres, &pg_columns(pg).await?);
+        if let Some(sqlite) = sqlite {
+            backends.push(DbBackend::Sqlite);
+            findings.extend(compare(
+                &self.registry,
+                DbBackend::Sqlite,
+                &sqlite_columns(sqlite).await?,
+            ));
+        }
+        let blocked_tables: BTreeSet<String> = findings.iter().map(|f| f.table.clone()).collect();
+        let report = SchemaDriftReport {
+            checked_at: Utc::now(),
+            backends,
+            tables_checked: self.registry.tables().len(),
+            findings,
+            blocked_tables: blocked_tables.into_iter().collect(),
+        };
+
+        let previous: BTreeSet<DriftFinding> = self
+            .latest()
+            .map(|r| r.findings.into_iter().collect())
+            .unwrap_or_default();
+        for finding in report.findings.iter().filter(|f| !previous.contains(f)) {
+            tracing::warn!(
+                backend = finding.backend.as_str(),
+                table = %finding.table,
+                column = finding.column.as_deref().unwrap_or(""),
+                kind = finding.kind.as_str(),
+                expected = finding.expected.as_deref().unwrap_or(""),
+                actual = finding.actual.as_deref().unwrap_or(""),
+                "Schema drift detected"
+            );
+        }
+        let resolved = previous
+            .iter()
+            .filter(|f| !report.findings.contains(f))
+            .count();
+        if resolved > 0 {
+            tracing::info!(resolved, "Schema drift resolved");
+        }
+        if !report.blocked_tables.is_empty() && self.block_dual_write {
+            tracing::warn!(
+                tables = ?report.blocked_tables,
+                "Dual-write blocked for drifted tables"
+            );
+        }
+
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = Some(report.clone());
+        }
+        Ok(report)
+    }
+}
+
+/// Findings for every registry table against one backend's live schema.
+pub fn compare(
+    registry: &SchemaRegistry,
+    backend: DbBackend,
+    live: &LiveSchema,
+) -> Vec<DriftFinding> {
+    let mut findings = Vec::new();
+    for table in registry.tables() {
+        let Some(columns) = live.get(&table.table_name) else {
+            findings.push(DriftFinding {
+                backend,
+                table: table.table_name.clone(),
+                column: None,
+                kind: DriftKind::MissingTable,
+                expected: None,
+                actual: None,
+            });
+            continue;
+        };
+        for field in &table.fields {
+            let column = columns
+                .get(&field.name)
+                .or_else(|| columns.get(&snake_case(&field.name)));
+            let (kind, actual) = match column {
+                None => (DriftKind::MissingColumn, None),
+                Some(column) if !type_matches(backend, field, column) => {
+                    (DriftKind::TypeMismatch, Some(describe(column)))
+                }
+                Some(_) => continue,
+            };
+            findings.push(DriftFinding {
+                backend,
+                table: table.table_name.clone(),
+                column: Some(field.name.clone()),
+                kind,
+                expected: Some(field.prisma_type.clone()),
+                actual,
+            });
+        }
+    }
+    findings
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for ch in name.chars() {
+        if ch.is_ascii_uppercase() {
+            out.push('_');
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn describe(column: &LiveColumn) -> String {
+    match &column.element_type {
+        Some(element) => format!("{element}[]"),
+        None => column.data_type.clone(),
+    }
+}
+
+fn type_matches(backend: DbBackend, field: &FieldSchema, column: &LiveColumn) -> bool {
+    let scalar = field.prisma_type.trim_end_matches("[]");
+    let is_array = field.is_array || field.prisma_type.ends_with("[]");
+    match backend {
+        DbBackend::Postgres => match (&column.element_type, is_array) {
+            (Some(element), true) => pg_scalar_matches(scalar, element),
+            (None, false) => pg_scalar_matches(scalar, &column.data_type),
+            _ => false,
+        },
+        // Arrays are stored as JSON text.
+        DbBackend::Sqlite => {
+            let expected = if is_array {
+                "TEXT"
+            } else {
+                match scalar {
+                    "Int" | "BigInt" | "Boolean" => "INTEGER",
+                    "Float" | "Decimal" => "REAL",
+                    "Bytes" => "BLOB",
+                    _ => "TEXT",
+                }
+            };
+            let actual = sqlite_affinity(&column.data_type);
+            actual == expected || (actual == "NUMERIC" && matches!(expected, "INTEGER" | "REAL"))
+        }
+    }
+}
+
+/// Whether a Postgres type (a `data_type`, or an array's element `udt_name`)
+/// can hold a Prisma scalar. Unknown Prisma types are not judged.
+fn pg_scalar_matches(prisma_type: &str, pg_type: &str) -> bool {
+    let pg_type = pg_type.to_ascii_lowercase();
+    match prisma_type {
+        "String" => matches!(
+            pg_type.as_str(),
+            "text"
+                | "character varying"
+                | "varchar"
+                | "character"
+                | "bpchar"
+                | "uuid"
+                | "citext"
+                | "user-defined"
+        ),
+        "Int" | "BigInt" => matches!(
+            pg_type.as_str(),
+            "smallint" | "integer" | "bigint" | "int2" | "int4" | "int8"
+        ),
+        "Float" | "Decimal" => matches!(
+            pg_type.as_str(),
+            "real" | "double precision" | "numeric" | "float4" | "float8"
+        ),
+        "Boolean" => matches!(pg_type.as_str(), "boolean" | "bool"),
+        "DateTime" => pg_type.starts_with("timestamp") || pg_type == "date",
+        "Json" => matches!(pg_type.as_str(), "json" | "jsonb"),
+        "Bytes" => pg_type == "bytea",
+        _ => true,
+    }
+}
+
+/// SQLite's affinity rules for a declared column type.
+fn sqlite_affinity(declared: &str) -> &'static str {
+    let declared = declared.to_ascii_uppercase();
+    if declared.contains("INT") {
+        "INTEGER"
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|t| declared.contains(t))
+    {
+        "TEXT"
+    } else if declared.is_empty() || declared.contains("BLOB") {
+        "BLOB"
+    } else if ["REAL", "FLOA", "DOUB"]
+        .iter()
+        .any(|t| declared.contains(t))
+    {
+        "REAL"
+    } else {
+        "NUMERIC"
+    }
+}
+
+async fn pg_columns(pool: &PgPool) -> Result<LiveSchema, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "table_name"::text AS "table", "column_name"::text AS "column",
+               "data_type"::text AS "dataType", "udt_name"::text AS "udtName"
+        FROM information_schema.columns
+        WHERE "table_schema" = current_schema()
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut live = LiveSchema::new();
+    for row in rows {
+        let data_type: String = row.try_get("dataType")?;
+        let udt_name: String = row.try_get("udtName")?;
+        let element_type =
+            (data_type == "ARRAY").then(|| udt_name.trim_start_matches('_').to_string());
+        live.entry(row.try_get("table")?).or_default().insert(
+            row.try_get("column")?,
+            LiveColumn {
+                data_type,
+                element_type,
+            },
+        );
+    }
+    Ok(live)
+}
+
+async fn sqlite_columns(pool: &SqlitePool) -> Result<LiveSchema, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT m."name" AS "table", p."name" AS "column", p."type" AS "dataType"
+        FROM "sqlite_master" m JOIN pragma_table_info(m."name") p
+        WHERE m."type" = 'table'
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut live = LiveSchema::new();
+    for row in rows {
+        live.entry(row.try_get("table")?).or_default().insert(
+            row.try_get("column")?,
+            LiveColumn {
+                data_type: row.try_get("dataType")?,
+                element_type: None,
+            },
+        );
+    }
+    Ok(live)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite_schema::{split_sql_statements, SQLITE_FALLBACK_SCHEMA_SQL};
+
+    fn live_users(columns: &[(&str, &str, Option<&str>)]) -> LiveSchema {
+        let mut live = LiveSchema::new();
+        let table = live.entry("users".to_string()).or_default();
+        for (name, data_type, element) in columns {
+            table.insert(
+                name.to_string(),
+                LiveColumn {
+                    data_type: data_type.to_string(),
+                    element_type: element.map(str::to_string),
+                },
+            );
+        }
+        live
+    }
+
+    fn users_findings(live: &LiveSchema) -> Vec<DriftFinding> {
+        let registry = SchemaRegistry::load().unwrap();
+        compare(&registry, DbBackend::Postgres, live)
+            .into_iter()
+            .filter(|f| f.table == "users")
+            .collect()
+    }
+
+    #[test]
+    fn reports_missing_tables_columns_and_type_mismatches() {
+        let registry = SchemaRegistry::load().unwrap();
+        let users = registry.get_by_table_name("users").unwrap();
+        let mut columns: Vec<(&str, &str, Option<&str>)> = users
+            .fields
+            .iter()
+            .map(|f| {
+                let pg = match f.prisma_type.as_str() {
+                    "Int" => "integer",
+                    "Boolean" => "boolean",
+                    "DateTime" => "timestamp without time zone",
+                    "Json" => "jsonb",
+                    "Float" => "double precision",
+                    _ => "text",
+                };
+                (f.name.as_str(), pg, None)
+            })
+            .collect();
+        assert!(users_findings(&live_users(&columns)).is_empty());
+
+        columns.retain(|(name, _, _)| *name != "email");
+        let created_at = columns
+            .iter_mut()
+            .find(|(name, _, _)| *name == "createdAt")
+            .unwrap();
+        created_at.1 = "text";
+        let findings = users_findings(&live_users(&columns));
+        assert_eq!(findings.len(), 2);
+        assert!(findings
+            .iter()
+            .any(|f| f.kind == DriftKind::MissingColumn && f.column.as_deref() == Some("email")));
+        assert!(findings.iter().any(|f| f.kind == DriftKind::TypeMismatch
+            && f.column.as_deref() == Some("createdAt")
+            && f.actual.as_deref() == Some("text")));
+
+        let findings = users_findings(&LiveSchema::new());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, DriftKind::MissingTable);
+    }
+
+    #[test]
+    fn type_families_tolerate_equivalent_types() {
+        assert!(pg_scalar_matches("String", "character varying"));
+        assert!(pg_scalar_matches("String", "USER-DEFINED"));
+        assert!(pg_scalar_matches("DateTime", "timestamp with time zone"));
+        assert!(pg_scalar_matches("Int", "int4"));
+        assert!(!pg_scalar_matches("Int", "text"));
+        assert!(!pg_scalar_matches("Json", "text"));
+
+        assert_eq!(sqlite_affinity("VARCHAR(64)"), "TEXT");
+        assert_eq!(sqlite_affinity("BIGINT"), "INTEGER");
+        assert_eq!(sqlite_affinity("DOUBLE"), "REAL");
+        assert_eq!(sqlite_affinity("DATETIME"), "NUMERIC");
+        assert_eq!(snake_case("featureVectorHash"), "feature_vector_hash");
+    }
+
+    #[tokio::test]
+    async fn sqlite_fallback_schema_matches_registry() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for statement in split_sql_statements(SQLITE_FALLBACK_SCHEMA_SQL) {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+        let monitor = SchemaDriftMonitor::new(SchemaRegistry::load().unwrap(), true);
+        let live = sqlite_columns(&pool).await.unwrap();
+        let findings = compare(&monitor.registry, DbBackend::Sqlite, &live);
+        assert!(findings.is_empty(), "{findings:#?}");
+        assert!(!monitor.blocks_dual_write("users"));
+    }
+}
@@ -97,4 +97,3 @@ pub enum SchemaRegistryError {
     #[error("failed to parse schema_registry.json: {0}")]
     Parse(#[source] serde_json::Error),
 }
-
@@ -0,0 +1,737 @@
+//! Envelope encryption for AMAS model snapshots at rest.
+//!
+//! Every user gets a random AES-256-GCM data key. It is stored in
+//! `user_data_keys` wrapped by a master key, which a `MasterKeyProvider`
+//! supplies: `MODEL_SNAPSHOT_KEYS` in the environment, or a KMS-backed
+//! provider. Snapshot parameters are sealed with the data key, with the user id,
+//! model type and data key version as associated data so a blob cannot be
+//! replayed onto another row. Rows written before encryption was enabled stay
+//! readable as plaintext.
+//!
+//! Rotation comes in two forms. Rotating the master key only rewraps the data
+//! keys. Rotating a user's data key re-encrypts that user's snapshots in one
+//! transaction.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, OnceLock};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+/// Marks an encrypted `parameters` value.
+pub const ENVELOPE_MARKER: &str = "$enc";
+const ENVELOPE_FORMAT: i64 = 1;
+const ALGORITHM: &str = "A256GCM";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const KEY_CACHE_CAPACITY: usize = 10_000;
+const REWRAP_BATCH: i64 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotCryptoError {
+    #[error("snapshot encryption is not configured")]
+    NotConfigured,
+    #[error("unknown master key: {0}")]
+    UnknownMasterKey(String),
+    #[error("malformed envelope: {0}")]
+    Malformed(&'static str),
+    #[error("data key version {found} does not match current version {current}")]
+    StaleKeyVersion { found: i64, current: i32 },
+    #[error("decryption failed")]
+    Decrypt,
+    #[error("encryption failed")]
+    Encrypt,
+    #[error("invalid master key configuration: {0}")]
+    Config(String),
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+}
+
+impl SnapshotCryptoError {
+    pub(crate) fn into_decode(self) -> sqlx::Error {
+        match self {
+            SnapshotCryptoError::Sql(e) => e,
+            other => sqlx::Error::Decode(Box::new(other)),
+        }
+    }
+
+    pub(crate) fn into_encode(self) -> sqlx::Error {
+        match self {
+            SnapshotCryptoError::Sql(e) => e,
+            other => sqlx::Error::Io(std::io::Error::other(other)),
+        }
+    }
+}
+
+/// Wraps and unwraps data keys with a master key.
+///
+/// Implementations backed by a KMS should wrap through the KMS API and never
+/// hold the master key material in process.
+pub trait MasterKeyProvider: Send + Sync {
+    /// Master key new data keys are wrapped with.
+    fn active_key_id(&self) -> &str;
+    fn wrap(
+        &self,
+        key_id: &str,
+        data_key: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, SnapshotCryptoError>;
+    fn unwrap(
+        &self,
+        key_id: &str,
+        wrapped: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, SnapshotCryptoError>;
+}
+
+/// Master keys from `MODEL_SNAPSHOT_KEYS`: comma-separated `id:base64key`
+/// entries, the first being active. Older entries stay listed until a rewrap
+/// has moved every data key off them.
+pub struct EnvMasterKeys {
+    active: String,
+    keys: HashMap<String, [u8; KEY_LEN]>,
+}
+
+impl EnvMasterKeys {
+    pub fn parse(spec: &str) -> Result<Self, SnapshotCryptoError> {
+        let mut active = None;
+        let mut keys = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry
+                .split_once(':')
+                .ok_or_else(|| SnapshotCryptoError::Config("expected id:base64key".into()))?;
+            let bytes = B64
+                .decode(encoded.trim())
+                .map_err(|_| SnapshotCryptoError::Config(format!("key {id} is not base64")))?;
+            let key: [u8; KEY_LEN] = bytes
+                .try_into()
+                .map_err(|_| SnapshotCryptoError::Config(format!("key {id} must be 32 bytes")))?;
+            let id = id.trim().to_string();
+            if keys.insert(id.clone(), key).is_some() {
+                return Err(SnapshotCryptoError::Config(format!(
+                    "duplicate key id {id}"
+                )));
+            }
+            active.get_or_insert(id);
+        }
+        let active = active.ok_or_else(|| SnapshotCryptoError::Config("no keys".into()))?;
+        Ok(Self { active, keys })
+    }
+
+    fn cipher(&self, key_id: &str) -> Result<Aes256Gcm, SnapshotCryptoError> {
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| SnapshotCryptoError::UnknownMasterKey(key_id.to_string()))?;
+        Aes256Gcm::new_from_slice(key).map_err(|_| SnapshotCryptoError::Encrypt)
+    }
+}
+
+impl MasterKeyProvider for EnvMasterKeys {
+    fn active_key_id(&self) -> &str {
+        &self.active
+    }
+
+    fn wrap(
+        &self,
+        key_id: &str,
+        data_key: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, SnapshotCryptoError> {
+        let (nonce, ciphertext) = seal(&self.cipher(key_id)?, data_key, aad)?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    fn unwrap(
+        &self,
+        key_id: &str,
+        wrapped: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, SnapshotCryptoError> {
+        if wrapped.len() <= NONCE_LEN {
+            return Err(SnapshotCryptoError::Malformed("wrapped key too short"));
+        }
+        let (nonce, ciphertext) = wrapped.split_at(NONCE_LEN);
+        open(&self.cipher(key_id)?, nonce, ciphertext, aad)
+    }
+}
+
+/// Process-wide provider; `None` when `MODEL_SNAPSHOT_KEYS` is unset.
+///
+/// A set but invalid value is an error rather than `None`: silently falling
+/// back to plaintext would write unencrypted snapshots on a deployment that
+/// asked for encryption. The server calls this at startup and refuses to run.
+pub fn init_master_keys() -> Result<Option<&'static dyn MasterKeyProvider>, SnapshotCryptoError> {
+    static PROVIDER: OnceLock<Result<Option<EnvMasterKeys>, String>> = OnceLock::new();
+    let loaded = PROVIDER.get_or_init(|| match std::env::var("MODEL_SNAPSHOT_KEYS") {
+        Ok(spec) => EnvMasterKeys::parse(&spec)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(_) => Ok(None),
+    });
+    match loaded {
+        Ok(keys) => Ok(keys.as_ref().map(|keys| keys as &dyn MasterKeyProvider)),
+        Err(e) => Err(SnapshotCryptoError::Config(e.clone())),
+    }
+}
+
+/// Provider for reads and admin tools; an invalid configuration reads as
+/// unconfigured. Writers use [`init_master_keys`].
+pub fn master_keys() -> Option<&'static dyn MasterKeyProvider> {
+    init_master_keys().ok().flatten()
+}
+
+fn seal(
+    cipher: &Aes256Gcm,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<([u8; NONCE_LEN], Vec<u8>), SnapshotCryptoError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| SnapshotCryptoError::Encrypt)?;
+    Ok((nonce.into(), ciphertext))
+}
+
+fn open(
+    cipher: &Aes256Gcm,
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, SnapshotCryptoError> {
+    if nonce.len() != NONCE_LEN {
+        return Err(SnapshotCryptoError::Malformed("bad nonce length"));
+    }
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| SnapshotCryptoError::Decrypt)
+}
+
+/// An unwrapped per-user data key.
+#[derive(Clone)]
+pub struct DataKey {
+    pub version: i32,
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataKey")
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DataKey {
+    fn from_bytes(version: i32, bytes: &[u8]) -> Result<Self, SnapshotCryptoError> {
+        let cipher = Aes256Gcm::new_from_slice(bytes).map_err(|_| SnapshotCryptoError::Decrypt)?;
+        Ok(Self { version, cipher })
+    }
+}
+
+/// Stored form of a user's data key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrappedDataKey {
+    pub wrapped_key: String,
+    pub master_key_id: String,
+    pub key_version: i32,
+}
+
+impl WrappedDataKey {
+    /// Reads the `user_data_keys` columns when present (null on a LEFT JOIN miss).
+    pub fn from_row(row: &sqlx::postgres::PgRow) -> Option<Self> {
+        Some(Self {
+            wrapped_key: row.try_get::<Option<String>, _>("wrappedKey").ok()??,
+            master_key_id: row.try_get::<Option<String>, _>("masterKeyId").ok()??,
+            key_version: row.try_get::<Option<i32>, _>("keyVersion").ok()??,
+        })
+    }
+}
+
+fn wrap_aad(user_id: &str, master_key_id: &str, version: i32) -> Vec<u8> {
+    format!("udk\0{user_id}\0{master_key_id}\0{version}").into_bytes()
+}
+
+fn blob_aad(user_id: &str, model_type: &str, version: i32) -> Vec<u8> {
+    format!("snapshot\0{user_id}\0{model_type}\0{version}").into_bytes()
+}
+
+static KEY_CACHE: LazyLock<Mutex<HashMap<(String, String), DataKey>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Unwraps a stored data key, memoizing on the wrapped bytes so a rotated key
+/// never hits a stale entry.
+pub fn unwrap_data_key(
+    provider: &dyn MasterKeyProvider,
+    user_id: &str,
+    stored: &WrappedDataKey,
+) -> Result<DataKey, SnapshotCryptoError> {
+    let cache_key = (user_id.to_string(), stored.wrapped_key.clone());
+    if let Some(key) = KEY_CACHE.lock().get(&cache_key) {
+        return Ok(key.clone());
+    }
+    let wrapped = B64
+        .decode(&stored.wrapped_key)
+        .map_err(|_| SnapshotCryptoError::Malformed("wrapped key is not base64"))?;
+    let bytes = provider.unwrap(
+        &stored.master_key_id,
+        &wrapped,
+        &wrap_aad(user_id, &stored.master_key_id, stored.key_version),
+    )?;
+    let key = DataKey::from_bytes(stored.key_version, &bytes)?;
+
+    let mut cache = KEY_CACHE.lock();
+    if cache.len() >= KEY_CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(cache_key, key.clone());
+    Ok(key)
+}
+
+fn new_data_key(
+    provider: &dyn MasterKeyProvider,
+    user_id: &str,
+    version: i32,
+) -> Result<(DataKey, WrappedDataKey), SnapshotCryptoError> {
+    let bytes = Aes256Gcm::generate_key(OsRng);
+    let master_key_id = provider.active_key_id().to_string();
+    let wrapped = provider.wrap(
+        &master_key_id,
+        &bytes,
+        &wrap_aad(user_id, &master_key_id, version),
+    )?;
+    Ok((
+        DataKey::from_bytes(version, &bytes)?,
+        WrappedDataKey {
+            wrapped_key: B64.encode(wrapped),
+            master_key_id,
+            key_version: version,
+        },
+    ))
+}
+
+pub fn is_envelope(value: &Value) -> bool {
+    value.get(ENVELOPE_MARKER).is_some()
+}
+
+pub fn encrypt_parameters(
+    key: &DataKey,
+    user_id: &str,
+    model_type: &str,
+    parameters: &Value,
+) -> Result<Value, SnapshotCryptoError> {
+    let plaintext = serde_json::to_vec(parameters).map_err(|_| SnapshotCryptoError::Encrypt)?;
+    let (nonce, ciphertext) = seal(
+        &key.cipher,
+        &plaintext,
+        &blob_aad(user_id, model_type, key.version),
+    )?;
+    Ok(serde_json::json!({
+        ENVELOPE_MARKER: ENVELOPE_FORMAT,
+        "alg": ALGORITHM,
+        "kv": key.version,
+        "nonce": B64.encode(nonce),
+        "ct": B64.encode(ciphertext),
+    }))
+}
+
+/// Returns plaintext parameters unchanged; opens envelopes with `key`.
+pub fn decrypt_parameters(
+    key: Option<&DataKey>,
+    user_id: &str,
+    model_type: &str,
+    parameters: Value,
+) -> Result<Value, SnapshotCryptoError> {
+    if !is_envelope(&parameters) {
+        return Ok(parameters);
+    }
+    let key = key.ok_or(SnapshotCryptoError::NotConfigured)?;
+    if parameters.get(ENVELOPE_MARKER).and_then(Value::as_i64) != Some(ENVELOPE_FORMAT)
+        || parameters.get("alg").and_then(Value::as_str) != Some(ALGORITHM)
+    {
+        return Err(SnapshotCryptoError::Malformed("unsupported envelope"));
+    }
+    let version = parameters
+        .get("kv")
+        .and_then(Value::as_i64)
+        .ok_or(SnapshotCryptoError::Malformed("missing key version"))?;
+    if version != key.version as i64 {
+        return Err(SnapshotCryptoError::StaleKeyVersion {
+            found: version,
+            current: key.version,
+        });
+    }
+    let field = |name: &'static str| {
+        parameters
+            .get(name)
+            .and_then(Value::as_str)
+            .and_then(|s| B64.decode(s).ok())
+            .ok_or(SnapshotCryptoError::Malformed(name))
+    };
+    let plaintext = open(
+        &key.cipher,
+        &field("nonce")?,
+        &field("ct")?,
+        &blob_aad(user_id, model_type, key.version),
+    )?;
+    serde_json::from_slice(&plaintext).map_err(|_| SnapshotCryptoError::Malformed("plaintext"))
+}
+
+/// Decrypts parameters read together with the user's key columns.
+pub fn decrypt_row_parameters(
+    row: &sqlx::postgres::PgRow,
+    user_id: &str,
+    model_type: &str,
+    parameters: Value,
+) -> Result<Value, SnapshotCryptoError> {
+    if !is_envelope(&parameters) {
+        return Ok(parameters);
+    }
+    let provider = master_keys().ok_or(SnapshotCryptoError::NotConfigured)?;
+    let stored =
+        WrappedDataKey::from_row(row).ok_or(SnapshotCryptoError::Malformed("data key missing"))?;
+    let key = unwrap_data_key(provider, user_id, &stored)?;
+    decrypt_parameters(Some(&key), user_id, model_type, parameters)
+}
+
+/// Data key for writing, created on first use. The row is share-locked so a
+/// concurrent data key rotation cannot interleave with the write.
+pub async fn data_key_for_write(
+    tx: &mut Transaction<'_, Postgres>,
+    provider: &dyn MasterKeyProvider,
+    user_id: &str,
+) -> Result<DataKey, SnapshotCryptoError> {
+    if let Some(stored) = select_data_key(tx, user_id, "FOR SHARE").await? {
+        return unwrap_data_key(provider, user_id, &stored);
+    }
+    let (_, fresh) = new_data_key(provider, user_id, 1)?;
+    sqlx::query(
+        r#"
+        INSERT INTO "user_data_keys" ("userId", "wrappedKey", "masterKeyId", "keyVersion")
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT ("userId") DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(&fresh.wrapped_key)
+    .bind(&fresh.master_key_id)
+    .bind(fresh.key_version)
+    .execute(&mut **tx)
+    .await?;
+    // Re-read: a concurrent writer may have won the insert.
+    let stored = select_data_key(tx, user_id, "FOR SHARE")
+        .await?
+        .ok_or(SnapshotCryptoError::Malformed("data key missing"))?;
+    unwrap_data_key(provider, user_id, &stored)
+}
+
+async fn select_data_key(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    lock: &str,
+) -> Result<Option<WrappedDataKey>, sqlx::Error> {
+    let sql = format!(
+        r#"SELECT "wrappedKey", "masterKeyId", "keyVersion" FROM "user_data_keys" WHERE "userId" = $1 {lock}"#
+    );
+    let row = sqlx::query(&sql)
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+    Ok(row.as_ref().and_then(WrappedDataKey::from_row))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterKeyUsage {
+    pub master_key_id: String,
+    pub data_keys: i64,
+}
+
+/// Data keys per master key, for checking that a rewrap has finished before
+/// an old master key is retired.
+pub async fn master_key_usage(pool: &PgPool) -> Result<Vec<MasterKeyUsage>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "masterKeyId", COUNT(*) AS "count"
+        FROM "user_data_keys"
+        GROUP BY "masterKeyId"
+        ORDER BY "masterKeyId"
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| MasterKeyUsage {
+            master_key_id: row.get("masterKeyId"),
+            data_keys: row.get("count"),
+        })
+        .collect())
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewrapReport {
+    pub active_key_id: String,
+    pub rewrapped: u64,
+    pub failed: u64,
+}
+
+/// Rewraps every data key not yet under the active master key. Snapshot
+/// blobs are untouched.
+pub async fn rewrap_data_keys(
+    pool: &PgPool,
+    provider: &dyn MasterKeyProvider,
+) -> Result<RewrapReport, SnapshotCryptoError> {
+    let active = provider.active_key_id().to_string();
+    let mut report = RewrapReport {
+        active_key_id: active.clone(),
+        ..Default::default()
+    };
+    let mut after = String::new();
+    loop {
+        let rows = sqlx::query(
+            r#"
+            SELECT "userId", "wrappedKey", "masterKeyId", "keyVersion"
+            FROM "user_data_keys"
+            WHERE "masterKeyId" <> $1 AND "userId" > $2
+            ORDER BY "userId"
+            LIMIT $3
+            "#,
+        )
+        .bind(&active)
+        .bind(&after)
+        .bind(REWRAP_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else { break };
+        after = last.get("userId");
+
+        for row in &rows {
+            let user_id: String = row.get("userId");
+            let Some(stored) = WrappedDataKey::from_row(row) else {
+                continue;
+            };
+            match rewrap_one(provider, &user_id, &stored, &active) {
+                Ok(rewrapped) => {
+                    // Only replace the exact key we read, in case it was rotated meanwhile.
+                    let result = sqlx::query(
+                        r#"
+                        UPDATE "user_data_keys"
+                        SET "wrappedKey" = $1, "masterKeyId" = $2, "rotatedAt" = NOW()
+                        WHERE "userId" = $3 AND "wrappedKey" = $4
+                        "#,
+                    )
+                    .bind(&rewrapped)
+                    .bind(&active)
+                    .bind(&user_id)
+                    .bind(&stored.wrapped_key)
+                    .execute(pool)
+                    .await?;
+                    report.rewrapped += result.rows_affected();
+                }
+                Err(e) => {
+                    tracing::warn!(user_id = %user_id, error = %e, "data key rewrap failed");
+                    report.failed += 1;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn rewrap_one(
+    provider: &dyn MasterKeyProvider,
+    user_id: &str,
+    stored: &WrappedDataKey,
+    active: &str,
+) -> Result<String, SnapshotCryptoError> {
+    let wrapped = B64
+        .decode(&stored.wrapped_key)
+        .map_err(|_| SnapshotCryptoError::Malformed("wrapped key is not base64"))?;
+    let bytes = provider.unwrap(
+        &stored.master_key_id,
+        &wrapped,
+        &wrap_aad(user_id, &stored.master_key_id, stored.key_version),
+    )?;
+    let rewrapped = provider.wrap(
+        active,
+        &bytes,
+        &wrap_aad(user_id, active, stored.key_version),
+    )?;
+    Ok(B64.encode(rewrapped))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserKeyRotation {
+    pub user_id: String,
+    pub key_version: i32,
+    pub reencrypted: u64,
+}
+
+/// Replaces a user's data key and re-encrypts all of their snapshots
+/// (including plaintext rows written before encryption was enabled).
+pub async fn rotate_user_data_key(
+    pool: &PgPool,
+    provider: &dyn MasterKeyProvider,
+    user_id: &str,
+) -> Result<UserKeyRotation, SnapshotCryptoError> {
+    let mut tx = pool.begin().await?;
+    let current = match select_data_key(&mut tx, user_id, "FOR UPDATE").await? {
+        Some(stored) => Some(unwrap_data_key(provider, user_id, &stored)?),
+        None => None,
+    };
+    let next_version = current.as_ref().map_or(1, |k| k.version + 1);
+    let (next, stored) = new_data_key(provider, user_id, next_version)?;
+
+    let rows = sqlx::query(
+        r#"SELECT "id", "modelType", "parameters" FROM "amas_user_models" WHERE "userId" = $1 FOR UPDATE"#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut reencrypted = 0;
+    for row in rows {
+        let id: String = row.get("id");
+        let model_type: String = row.get("modelType");
+        let parameters: Value = row.get("parameters");
+        let plain = decrypt_parameters(current.as_ref(), user_id, &model_type, parameters)?;
+        let sealed = encrypt_parameters(&next, user_id, &model_type, &plain)?;
+        sqlx::query(r#"UPDATE "amas_user_models" SET "parameters" = $1 WHERE "id" = $2"#)
+            .bind(&sealed)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        reencrypted += 1;
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO "user_data_keys" ("userId", "wrappedKey", "masterKeyId", "keyVersion")
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT ("userId") DO UPDATE SET
+            "wrappedKey" = EXCLUDED."wrappedKey",
+            "masterKeyId" = EXCLUDED."masterKeyId",
+            "keyVersion" = EXCLUDED."keyVersion",
+            "rotatedAt" = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(&stored.wrapped_key)
+    .bind(&stored.master_key_id)
+    .bind(stored.key_version)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(UserKeyRotation {
+        user_id: user_id.to_string(),
+        key_version: next_version,
+        reencrypted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() -> EnvMasterKeys {
+        let k1 = B64.encode([1u8; 32]);
+        let k2 = B64.encode([2u8; 32]);
+        EnvMasterKeys::parse(&format!("k2:{k2}, k1:{k1}")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_master_keys() {
+        let keys = provider();
+        assert_eq!(keys.active_key_id(), "k2");
+        assert!(EnvMasterKeys::parse("").is_err());
+        assert!(EnvMasterKeys::parse("k1:not-base64!").is_err());
+        assert!(EnvMasterKeys::parse(&format!("k1:{}", B64.encode([0u8; 16]))).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_and_binding() {
+        let keys = provider();
+        let (key, stored) = new_data_key(&keys, "u1", 1).unwrap();
+        let params = serde_json::json!({ "A": [1.0, 0.0, 0.0, 1.0], "d": 2 });
+
+        let sealed = encrypt_parameters(&key, "u1", "bandit", &params).unwrap();
+        assert!(is_envelope(&sealed));
+        assert!(!sealed.to_string().contains("\"A\""));
+
+        let unwrapped = unwrap_data_key(&keys, "u1", &stored).unwrap();
+        let opened = decrypt_parameters(Some(&unwrapped), "u1", "bandit", sealed.clone()).unwrap();
+        assert_eq!(opened, params);
+
+        // Bound to the row it was written for.
+        assert!(decrypt_parameters(Some(&unwrapped), "u1", "strategy", sealed.clone()).is_err());
+        assert!(decrypt_parameters(Some(&unwrapped), "u2", "bandit", sealed).is_err());
+        // Wrapped key is bound to its user.
+        assert!(unwrap_data_key(&keys, "u2", &stored).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_passthrough_and_stale_version() {
+        let plain = serde_json::json!({ "count": 3 });
+        assert_eq!(
+            decrypt_parameters(None, "u1", "interaction_count", plain.clone()).unwrap(),
+            plain
+        );
+
+        let keys = provider();
+        let (v1, _) = new_data_key(&keys, "u1", 1).unwrap();
+        let (v2, _) = new_data_key(&keys, "u1", 2).unwrap();
+        let sealed = encrypt_parameters(&v1, "u1", "bandit", &plain).unwrap();
+        assert!(matches!(
+            decrypt_parameters(Some(&v2), "u1", "bandit", sealed.clone()),
+            Err(SnapshotCryptoError::StaleKeyVersion {
+                found: 1,
+                current: 2
+            })
+        ));
+        assert!(matches!(
+            decrypt_parameters(None, "u1", "bandit", sealed),
+            Err(SnapshotCryptoError::NotConfigured)
+        ));
+    }
+
+    #[test]
+    fn test_master_rewrap_keeps_data_key() {
+        let keys = provider();
+        let old = EnvMasterKeys::parse(&format!("k1:{}", B64.encode([1u8; 32]))).unwrap();
+        let (key, stored) = new_data_key(&old, "u1", 1).unwrap();
+        assert_eq!(stored.master_key_id, "k1");
+        let sealed = encrypt_parameters(&key, "u1", "bandit", &serde_json::json!([1])).unwrap();
+
+        let rewrapped = WrappedDataKey {
+            wrapped_key: rewrap_one(&keys, "u1", &stored, "k2").unwrap(),
+            master_key_id: "k2".to_string(),
+            key_version: 1,
+        };
+        let key = unwrap_data_key(&keys, "u1", &rewrapped).unwrap();
+        assert_eq!(
+            decrypt_parameters(Some(&key), "u1", "bandit", sealed).unwrap(),
+            serde_json::json!([1])
+        );
+    }
+}
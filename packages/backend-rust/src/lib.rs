@@ -38,5 +38,8 @@ pub async fn create_app() -> axum::Router {
 
     routes::router(state)
         .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(
+            middleware::request_id::request_id_middleware,
+        ))
         .layer(CorsLayer::permissive())
 }
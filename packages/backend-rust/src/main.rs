@@ -8,8 +8,9 @@ use danci_backend_rust::cache::RedisCache;
 use danci_backend_rust::config::Config;
 use danci_backend_rust::db;
 use danci_backend_rust::logging;
+use danci_backend_rust::middleware::request_id::{request_id_middleware, REQUEST_ID_HEADER};
 use danci_backend_rust::routes;
-use danci_backend_rust::services::quality_service;
+use danci_backend_rust::services::{progress_report, quality_service, simulation};
 use danci_backend_rust::state::AppState;
 use danci_backend_rust::workers::WorkerManager;
 
@@ -21,6 +22,7 @@ async fn main() {
         match cmd.as_str() {
             "seed-admin" => return run_seed_admin().await,
             "migrate-admins" => return run_migrate_admins().await,
+            "seed-dev" => return run_seed_dev().await,
             _ => {}
         }
     }
@@ -29,6 +31,8 @@ async fn main() {
 
     let _file_log_guard = logging::init_tracing(&config.log_level);
 
+    db::snapshot_crypto::init_master_keys().expect("invalid MODEL_SNAPSHOT_KEYS");
+
     let db_proxy = match db::DatabaseProxy::from_env().await {
         Ok(proxy) => Some(proxy),
         Err(err) => {
@@ -39,6 +43,8 @@ async fn main() {
 
     if let Some(ref proxy) = db_proxy {
         quality_service::cleanup_stale_tasks(proxy).await;
+        simulation::fail_interrupted_jobs(proxy).await;
+        progress_report::fail_interrupted_jobs(proxy).await;
         if let Err(err) =
             danci_backend_rust::amas::metrics_persistence::restore_registry_from_db(proxy.as_ref())
                 .await
@@ -84,6 +90,10 @@ async fn main() {
         None
     };
 
+    if let Some(ref proxy) = db_proxy {
+        danci_backend_rust::middleware::maintenance::spawn_poller(Arc::clone(proxy));
+    }
+
     let state = AppState::new(db_proxy, amas_engine, cache);
 
     let cors = match std::env::var("CORS_ORIGIN") {
@@ -102,7 +112,16 @@ async fn main() {
                     Method::DELETE,
                     Method::OPTIONS,
                 ])
-                .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT])
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::AUTHORIZATION,
+                    header::ACCEPT,
+                    header::HeaderName::from_static(
+                        danci_backend_rust::middleware::idempotency::IDEMPOTENCY_KEY_HEADER,
+                    ),
+                    header::HeaderName::from_static(REQUEST_ID_HEADER),
+                ])
+                .expose_headers([header::HeaderName::from_static(REQUEST_ID_HEADER)])
                 .allow_credentials(true)
         }
         _ => {
@@ -113,6 +132,7 @@ async fn main() {
 
     let app = routes::router(state)
         .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(request_id_middleware))
         .layer(cors);
 
     let addr = config.bind_addr();
@@ -244,6 +264,50 @@ async fn run_seed_admin() {
     }
 }
 
+async fn run_seed_dev() {
+    use danci_backend_rust::seed::{seed_fixtures, SeedFixtures};
+
+    let args: Vec<String> = std::env::args().collect();
+    let fixtures_path = args
+        .iter()
+        .position(|arg| arg == "--fixtures")
+        .and_then(|i| args.get(i + 1).cloned());
+
+    let fixtures = match fixtures_path {
+        Some(path) => SeedFixtures::from_file(&path),
+        None => SeedFixtures::from_env(),
+    };
+    let fixtures = match fixtures {
+        Ok(fixtures) => fixtures,
+        Err(e) => {
+            eprintln!("Failed to load fixtures: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let proxy = match db::DatabaseProxy::from_env().await {
+        Ok(proxy) => proxy,
+        Err(e) => {
+            eprintln!("Failed to connect to database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match seed_fixtures(proxy.as_ref(), &fixtures).await {
+        Ok(report) => println!(
+            "SEEDED users={} wordBooks={} words={} answerRecords={}",
+            report.users_created,
+            report.word_books_created,
+            report.words_created,
+            report.answer_records_created
+        ),
+        Err(e) => {
+            eprintln!("Failed to seed fixtures: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 async fn run_migrate_admins() {
     use sqlx::postgres::PgPoolOptions;
     use sqlx::Row;
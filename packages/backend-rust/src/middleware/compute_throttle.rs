@@ -0,0 +1,493 @@
+//! Per-user throttling of compute-heavy endpoints.
+//!
+//! AMAS decisions and batch evaluations cost far more than an ordinary
+//! request, and a few users hammering them can starve everyone else. Each
+//! such request needs a slot: at most `perUserConcurrency` per user and
+//! `globalConcurrency` across the instance. Once global use reaches
+//! `shedRatio` of its cap, every user is held to a single slot, so the
+//! remaining capacity is shared out instead of going to whoever asks most.
+//!
+//! A request without a free slot waits in its user's queue (at most
+//! `maxQueuedPerUser` deep) until one frees up or `queueDeadlineMs` passes.
+//! A full queue or a missed deadline gets a 429 with `Retry-After` and, for
+//! AMAS decisions, the static default strategy as a fallback, so the client
+//! can keep the session going. The limits can be changed at runtime through
+//! the admin ops endpoint; unauthenticated requests are left to the handler.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header::RETRY_AFTER, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::amas::types::StrategyParams;
+use crate::middleware::request_id;
+
+const THROTTLED_MESSAGE: &str = "计算资源繁忙，请稍后再试";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComputeClass {
+    Decision,
+    Batch,
+}
+
+/// Compute-heavy endpoint the request targets, if any.
+fn classify(method: &Method, path: &str) -> Option<ComputeClass> {
+    if *method != Method::POST {
+        return None;
+    }
+    match path.trim_end_matches('/') {
+        "/api/amas/process" => Some(ComputeClass::Decision),
+        "/api/amas/batch-process" | "/api/word-mastery/batch" => Some(ComputeClass::Batch),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleConfig {
+    pub enabled: bool,
+    pub per_user_concurrency: u32,
+    pub global_concurrency: u32,
+    /// Share of `global_concurrency` in use beyond which users get one slot.
+    pub shed_ratio: f64,
+    pub queue_deadline_ms: u64,
+    pub max_queued_per_user: u32,
+    pub retry_after_secs: u64,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            per_user_concurrency: 2,
+            global_concurrency: 32,
+            shed_ratio: 0.75,
+            queue_deadline_ms: 2_000,
+            max_queued_per_user: 4,
+            retry_after_secs: 2,
+        }
+    }
+}
+
+impl ThrottleConfig {
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let env = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            enabled: env("COMPUTE_THROTTLE_ENABLED")
+                .map(|v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "off"))
+                .unwrap_or(defaults.enabled),
+            per_user_concurrency: env("COMPUTE_THROTTLE_PER_USER")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.per_user_concurrency),
+            global_concurrency: env("COMPUTE_THROTTLE_GLOBAL")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.global_concurrency),
+            ..defaults
+        }
+    }
+
+    /// Field errors; empty when the config is usable.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.per_user_concurrency == 0 {
+            errors.push("perUserConcurrency 必须大于 0".to_string());
+        }
+        if self.global_concurrency == 0 {
+            errors.push("globalConcurrency 必须大于 0".to_string());
+        }
+        if !(self.shed_ratio > 0.0 && self.shed_ratio <= 1.0) {
+            errors.push("shedRatio 必须在 (0, 1] 之间".to_string());
+        }
+        if self.queue_deadline_ms > 60_000 {
+            errors.push("queueDeadlineMs 不能超过 60000".to_string());
+        }
+        if self.retry_after_secs == 0 {
+            errors.push("retryAfterSecs 必须大于 0".to_string());
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleStats {
+    pub in_flight: u32,
+    pub queued: u32,
+    /// Users holding at least one slot.
+    pub active_users: usize,
+    /// Whether users are currently held to a single slot.
+    pub shedding: bool,
+    pub admitted: u64,
+    /// Admitted after waiting in the queue; included in `admitted`.
+    pub admitted_after_wait: u64,
+    pub rejected_queue_full: u64,
+    pub rejected_deadline: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleStatus {
+    pub config: ThrottleConfig,
+    pub stats: ThrottleStats,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    QueueFull,
+    Deadline,
+}
+
+#[derive(Default)]
+struct State {
+    config: ThrottleConfig,
+    in_flight: HashMap<String, u32>,
+    queued: HashMap<String, u32>,
+    global_in_flight: u32,
+    stats: ThrottleStats,
+}
+
+impl State {
+    fn shedding(&self) -> bool {
+        let cap = self.config.global_concurrency.max(1);
+        f64::from(self.global_in_flight) >= self.config.shed_ratio * f64::from(cap)
+    }
+
+    fn user_cap(&self) -> u32 {
+        if self.shedding() {
+            1
+        } else {
+            self.config.per_user_concurrency.max(1)
+        }
+    }
+
+    fn can_admit(&self, user_id: &str) -> bool {
+        self.global_in_flight < self.config.global_concurrency.max(1)
+            && self.in_flight.get(user_id).copied().unwrap_or(0) < self.user_cap()
+    }
+
+    fn admit(&mut self, user_id: &str) {
+        *self.in_flight.entry(user_id.to_string()).or_default() += 1;
+        self.global_in_flight += 1;
+        self.stats.admitted += 1;
+    }
+
+    fn release(&mut self, user_id: &str) {
+        if let Some(count) = self.in_flight.get_mut(user_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.in_flight.remove(user_id);
+            }
+        }
+        self.global_in_flight = self.global_in_flight.saturating_sub(1);
+    }
+
+    fn dequeue(&mut self, user_id: &str) {
+        if let Some(count) = self.queued.get_mut(user_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.queued.remove(user_id);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Throttle {
+    state: Mutex<State>,
+    /// Woken whenever a slot frees up or the limits change.
+    released: Notify,
+}
+
+/// Holds a slot until dropped.
+struct Permit<'a> {
+    throttle: &'a Throttle,
+    user_id: String,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.throttle.lock().release(&self.user_id);
+        self.throttle.released.notify_waiters();
+    }
+}
+
+/// Keeps the queue count right when a waiting request is cancelled.
+struct Queued<'a> {
+    throttle: &'a Throttle,
+    user_id: &'a str,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.throttle.lock().dequeue(self.user_id);
+    }
+}
+
+impl Throttle {
+    fn with_config(config: ThrottleConfig) -> Self {
+        Self {
+            state: Mutex::new(State {
+                config,
+                ..Default::default()
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    async fn acquire(&self, user_id: &str) -> Result<Permit<'_>, Rejection> {
+        let deadline = Instant::now() + Duration::from_millis(self.lock().config.queue_deadline_ms);
+        let mut queued: Option<Queued<'_>> = None;
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            {
+                let mut state = self.lock();
+                if state.can_admit(user_id) {
+                    state.admit(user_id);
+                    if queued.is_some() {
+                        state.stats.admitted_after_wait += 1;
+                    }
+                    drop(state);
+                    drop(queued);
+                    return Ok(Permit {
+                        throttle: self,
+                        user_id: user_id.to_string(),
+                    });
+                }
+                if queued.is_none() {
+                    let depth = state.queued.get(user_id).copied().unwrap_or(0);
+                    if depth >= state.config.max_queued_per_user {
+                        state.stats.rejected_queue_full += 1;
+                        return Err(Rejection::QueueFull);
+                    }
+                    *state.queued.entry(user_id.to_string()).or_default() += 1;
+                    queued = Some(Queued {
+                        throttle: self,
+                        user_id,
+                    });
+                }
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                self.lock().stats.rejected_deadline += 1;
+                return Err(Rejection::Deadline);
+            }
+        }
+    }
+
+    fn status(&self) -> ThrottleStatus {
+        let state = self.lock();
+        ThrottleStatus {
+            config: state.config,
+            stats: ThrottleStats {
+                in_flight: state.global_in_flight,
+                queued: state.queued.values().sum(),
+                active_users: state.in_flight.len(),
+                shedding: state.shedding(),
+                ..state.stats.clone()
+            },
+        }
+    }
+
+    fn configure(&self, config: ThrottleConfig) -> ThrottleStatus {
+        self.lock().config = config;
+        // Raised limits may let queued requests in right away.
+        self.released.notify_waiters();
+        self.status()
+    }
+}
+
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+fn throttle() -> &'static Throttle {
+    THROTTLE.get_or_init(|| Throttle::with_config(ThrottleConfig::from_env()))
+}
+
+pub fn status() -> ThrottleStatus {
+    throttle().status()
+}
+
+/// Replaces the limits; the caller validates `config` first.
+pub fn configure(config: ThrottleConfig, changed_by: &str) -> ThrottleStatus {
+    tracing::info!(?config, changed_by, "compute throttle reconfigured");
+    throttle().configure(config)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fallback {
+    strategy: StrategyParams,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ThrottledResponse {
+    success: bool,
+    error: &'static str,
+    code: &'static str,
+    retry_after: u64,
+    class: ComputeClass,
+    /// Static recommendation the client can use instead of waiting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fallback: Option<Fallback>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+fn throttled(class: ComputeClass, path: &str, retry_after: u64) -> Response {
+    let fallback = path.starts_with("/api/amas/").then(|| Fallback {
+        strategy: StrategyParams::default(),
+    });
+    let body = ThrottledResponse {
+        success: false,
+        error: THROTTLED_MESSAGE,
+        code: "COMPUTE_THROTTLED",
+        retry_after,
+        class,
+        fallback,
+        request_id: request_id::current(),
+    };
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+pub async fn compute_throttle_middleware(req: Request<Body>, next: Next) -> Response {
+    let Some(class) = classify(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+    let throttle = throttle();
+    let (enabled, retry_after) = {
+        let state = throttle.lock();
+        (state.config.enabled, state.config.retry_after_secs)
+    };
+    if !enabled {
+        return next.run(req).await;
+    }
+    let Some(user_id) =
+        crate::auth::extract_token(req.headers()).and_then(|t| crate::auth::token_user_id(&t))
+    else {
+        return next.run(req).await;
+    };
+
+    match throttle.acquire(&user_id).await {
+        Ok(_permit) => next.run(req).await,
+        Err(rejection) => {
+            tracing::debug!(%user_id, ?class, ?rejection, "compute request throttled");
+            throttled(class, req.uri().path(), retry_after)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(per_user: u32, global: u32) -> ThrottleConfig {
+        ThrottleConfig {
+            per_user_concurrency: per_user,
+            global_concurrency: global,
+            shed_ratio: 1.0,
+            queue_deadline_ms: 50,
+            max_queued_per_user: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_only_compute_endpoints_are_classified() {
+        assert_eq!(
+            classify(&Method::POST, "/api/amas/process"),
+            Some(ComputeClass::Decision)
+        );
+        assert_eq!(
+            classify(&Method::POST, "/api/word-mastery/batch"),
+            Some(ComputeClass::Batch)
+        );
+        assert_eq!(classify(&Method::GET, "/api/amas/process"), None);
+        assert_eq!(classify(&Method::POST, "/api/records"), None);
+    }
+
+    #[tokio::test]
+    async fn test_queue_full_and_deadline_reject() {
+        let throttle = Throttle::with_config(config(1, 8));
+        let _held = throttle.acquire("u1").await.unwrap();
+
+        let waiting = throttle.acquire("u1");
+        let full = async {
+            tokio::task::yield_now().await;
+            throttle.acquire("u1").await.err()
+        };
+        let (waited, full) = tokio::join!(waiting, full);
+        assert_eq!(waited.err(), Some(Rejection::Deadline));
+        assert_eq!(full, Some(Rejection::QueueFull));
+
+        // Other users are not affected by u1's queue.
+        assert!(throttle.acquire("u2").await.is_ok());
+        let stats = throttle.status().stats;
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.rejected_deadline, 1);
+        assert_eq!(stats.rejected_queue_full, 1);
+    }
+
+    #[tokio::test]
+    async fn test_released_slot_admits_waiter() {
+        let throttle = Throttle::with_config(config(1, 8));
+        let held = throttle.acquire("u1").await.unwrap();
+        let waiter = throttle.acquire("u1");
+        let release = async {
+            tokio::task::yield_now().await;
+            drop(held);
+        };
+        let (admitted, _) = tokio::join!(waiter, release);
+        assert!(admitted.is_ok());
+        assert_eq!(throttle.status().stats.admitted_after_wait, 1);
+    }
+
+    #[test]
+    fn test_load_sheds_users_to_one_slot() {
+        let mut state = State {
+            config: ThrottleConfig {
+                shed_ratio: 0.5,
+                ..config(3, 4)
+            },
+            ..Default::default()
+        };
+        state.admit("u1");
+        assert!(state.can_admit("u1"));
+        state.admit("u2");
+        assert!(state.shedding());
+        assert!(!state.can_admit("u1"));
+        assert!(state.can_admit("u3"));
+        assert!(!ThrottleConfig {
+            shed_ratio: 0.0,
+            ..Default::default()
+        }
+        .validate()
+        .is_empty());
+    }
+}
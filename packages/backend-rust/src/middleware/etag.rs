@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::{header, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+use crate::response::json_error;
+
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+/// Validators older than this are re-checked against the handler even without
+/// an invalidation, bounding staleness from writes outside the HTTP hooks.
+const VALIDATOR_TTL: Duration = Duration::from_secs(60);
+const MAX_VALIDATORS: usize = 10_000;
+const MAX_SCOPES: usize = 10_000;
+
+/// Groups of cacheable GET endpoints, invalidated together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheGroup {
+    Wordbooks = 0,
+    Words = 1,
+    Statistics = 2,
+}
+
+const WORDBOOK_PREFIXES: &[&str] = &["/api/wordbooks"];
+const WORD_PREFIXES: &[&str] = &["/api/words", "/api/v1/words"];
+const STATISTICS_PATHS: &[&str] = &[
+    "/api/users/me/statistics",
+    "/api/v1/users/me/statistics",
+    "/api/records/statistics",
+    "/api/records/statistics/enhanced",
+    "/api/v1/learning/statistics",
+];
+
+/// Mutations under these prefixes change word or wordbook content, or who may
+/// see it, for every user.
+const CONTENT_MUTATION_PREFIXES: &[&str] = &[
+    "/api/words",
+    "/api/v1/words",
+    "/api/wordbooks",
+    "/api/wordbook-center",
+    "/api/admin/wordbooks",
+    "/api/admin/licenses",
+];
+/// Mutations under these prefixes change the caller's learning progress, which
+/// also shows up in their learned-word lists and wordbook progress.
+const LEARNING_MUTATION_PREFIXES: &[&str] = &[
+    "/api/records",
+    "/api/learning",
+    "/api/v1/learning",
+    "/api/learning-sessions",
+    "/api/v1/sessions",
+    "/api/word-states",
+    "/api/word-mastery",
+    "/api/amas",
+];
+
+/// Bumped by mutations that affect every user.
+static GENERATIONS: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+/// Bumped by mutations that only affect the caller, per user.
+static SCOPED_GENERATIONS: OnceLock<Mutex<HashMap<String, [u64; 3]>>> = OnceLock::new();
+static VALIDATORS: OnceLock<Mutex<HashMap<String, Validator>>> = OnceLock::new();
+
+/// A validator is current while neither its group's global generation nor its
+/// user's generation has moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Generation {
+    global: u64,
+    scoped: u64,
+}
+
+struct Validator {
+    etag: String,
+    generation: Generation,
+    stored_at: Instant,
+}
+
+/// Who a successful mutation makes validators stale for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reach {
+    Everyone,
+    Caller,
+}
+
+fn has_prefix(path: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+fn cache_group(path: &str) -> Option<CacheGroup> {
+    let path = path.strip_suffix('/').unwrap_or(path);
+    if STATISTICS_PATHS.contains(&path) {
+        Some(CacheGroup::Statistics)
+    } else if has_prefix(path, WORDBOOK_PREFIXES) {
+        Some(CacheGroup::Wordbooks)
+    } else if has_prefix(path, WORD_PREFIXES) {
+        Some(CacheGroup::Words)
+    } else {
+        None
+    }
+}
+
+/// Groups whose cached validators a successful mutation on `path` makes
+/// stale, and for whom.
+fn groups_invalidated_by(path: &str) -> (Reach, &'static [CacheGroup]) {
+    const ALL: &[CacheGroup] = &[
+        CacheGroup::Wordbooks,
+        CacheGroup::Words,
+        CacheGroup::Statistics,
+    ];
+    if has_prefix(path, CONTENT_MUTATION_PREFIXES) {
+        (Reach::Everyone, ALL)
+    } else if has_prefix(path, LEARNING_MUTATION_PREFIXES) {
+        (Reach::Caller, ALL)
+    } else if path.starts_with("/api/") {
+        (Reach::Caller, &[CacheGroup::Statistics])
+    } else {
+        (Reach::Caller, &[])
+    }
+}
+
+fn scoped_generations() -> &'static Mutex<HashMap<String, [u64; 3]>> {
+    SCOPED_GENERATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn generation(group: CacheGroup, scope: &str) -> Generation {
+    // Under the lock so a reset of the scoped counters, which bumps the
+    // global ones, is seen whole.
+    let map = scoped_generations().lock();
+    Generation {
+        global: GENERATIONS[group as usize].load(Ordering::Acquire),
+        scoped: map
+            .get(scope)
+            .map_or(0, |generations| generations[group as usize]),
+    }
+}
+
+/// Marks every cached validator of `group` stale. Called automatically after
+/// successful mutations; services that change content outside the HTTP
+/// routes (workers, imports) call it directly.
+pub fn invalidate(group: CacheGroup) {
+    GENERATIONS[group as usize].fetch_add(1, Ordering::AcqRel);
+}
+
+/// Marks the validators of `groups` stale for one user only.
+fn invalidate_scope(scope: &str, groups: &[CacheGroup]) {
+    let mut map = scoped_generations().lock();
+    if map.len() >= MAX_SCOPES && !map.contains_key(scope) {
+        // Restarting the scoped counters could repeat a generation an old
+        // validator holds, so everyone's validators go stale with them.
+        map.clear();
+        for generation in &GENERATIONS {
+            generation.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+    let generations = map.entry(scope.to_string()).or_default();
+    for group in groups {
+        generations[*group as usize] += 1;
+    }
+}
+
+/// Generations are kept per user so one learner's answers do not force every
+/// other learner's requests through the handlers. Unverifiable tokens fall
+/// back to their own hash, which only ever matches themselves.
+fn generation_scope(token: &str) -> String {
+    crate::auth::token_user_id(token).unwrap_or_else(|| crate::auth::hash_token(token))
+}
+
+fn validators() -> &'static Mutex<HashMap<String, Validator>> {
+    VALIDATORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_etag(key: &str, generation: Generation) -> Option<String> {
+    let map = validators().lock();
+    let validator = map.get(key)?;
+    (validator.generation == generation && validator.stored_at.elapsed() < VALIDATOR_TTL)
+        .then(|| validator.etag.clone())
+}
+
+fn remember(key: String, etag: &str, generation: Generation) {
+    let mut map = validators().lock();
+    if map.len() >= MAX_VALIDATORS {
+        map.retain(|_, v| v.stored_at.elapsed() < VALIDATOR_TTL);
+        if map.len() >= MAX_VALIDATORS {
+            map.clear();
+        }
+    }
+    map.insert(
+        key,
+        Validator {
+            etag: etag.to_string(),
+            generation,
+            stored_at: Instant::now(),
+        },
+    );
+}
+
+fn content_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Weak comparison as required for If-None-Match.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(|candidate| candidate.trim().trim_start_matches("W/"))
+        .any(|candidate| candidate == "*" || candidate == etag)
+}
+
+fn not_modified(etag: &str) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    set_cache_headers(&mut response, etag);
+    response
+}
+
+fn set_cache_headers(response: &mut Response, etag: &str) {
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, value);
+    }
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-cache"),
+    );
+}
+
+/// ETag / If-None-Match for the wordbook, word and statistics GET endpoints.
+///
+/// The ETag is a hash of the response body. Validators are remembered per
+/// auth session and URI, so a repeated If-None-Match for an unchanged group is
+/// answered with 304 without running the handler; successful mutations bump
+/// the affected groups' generation, for the caller or for everyone, and force
+/// the next request through.
+pub async fn etag_middleware(req: Request<Body>, next: Next) -> Response {
+    let path = req.uri().path().to_string();
+    if req.method() != Method::GET {
+        let is_mutation = !matches!(*req.method(), Method::HEAD | Method::OPTIONS);
+        let scope = crate::auth::extract_token(req.headers()).map(|t| generation_scope(&t));
+        let response = next.run(req).await;
+        if is_mutation && response.status().is_success() {
+            match groups_invalidated_by(&path) {
+                (Reach::Everyone, groups) => groups.iter().for_each(|group| invalidate(*group)),
+                (Reach::Caller, groups) => {
+                    if let Some(scope) = scope {
+                        invalidate_scope(&scope, groups);
+                    }
+                }
+            }
+        }
+        return response;
+    }
+
+    let Some(group) = cache_group(&path) else {
+        return next.run(req).await;
+    };
+    // Responses depend on the caller; unauthenticated requests are rejected
+    // by the handlers and never cached.
+    let Some(token) = crate::auth::extract_token(req.headers()) else {
+        return next.run(req).await;
+    };
+    let key = format!("{}:{}", crate::auth::hash_token(&token), req.uri());
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let generation = generation(group, &generation_scope(&token));
+
+    if let Some(inm) = if_none_match.as_deref() {
+        if let Some(etag) = cached_etag(&key, generation) {
+            if etag_matches(inm, &etag) {
+                return not_modified(&etag);
+            }
+        }
+    }
+
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to buffer response for ETag");
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response();
+        }
+    };
+    let etag = content_etag(&bytes);
+    remember(key, &etag, generation);
+
+    if if_none_match
+        .as_deref()
+        .is_some_and(|inm| etag_matches(inm, &etag))
+    {
+        return not_modified(&etag);
+    }
+    let mut response = Response::from_parts(parts, Body::from(bytes));
+    set_cache_headers(&mut response, &etag);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_cover_listed_endpoints_only() {
+        assert_eq!(
+            cache_group("/api/wordbooks/system"),
+            Some(CacheGroup::Wordbooks)
+        );
+        assert_eq!(
+            cache_group("/api/wordbooks/abc/words"),
+            Some(CacheGroup::Wordbooks)
+        );
+        assert_eq!(
+            cache_group("/api/v1/words/learned"),
+            Some(CacheGroup::Words)
+        );
+        assert_eq!(
+            cache_group("/api/users/me/statistics"),
+            Some(CacheGroup::Statistics)
+        );
+        assert_eq!(cache_group("/api/word-scores/abc"), None);
+        assert_eq!(cache_group("/api/wordbook-center/books"), None);
+    }
+
+    #[test]
+    fn mutations_invalidate_affected_groups() {
+        let (reach, groups) = groups_invalidated_by("/api/wordbooks/abc/words");
+        assert_eq!((reach, groups.len()), (Reach::Everyone, 3));
+        let (reach, groups) = groups_invalidated_by("/api/admin/licenses/abc");
+        assert_eq!((reach, groups.len()), (Reach::Everyone, 3));
+        let (reach, groups) = groups_invalidated_by("/api/v1/learning/records");
+        assert_eq!((reach, groups.len()), (Reach::Caller, 3));
+        assert_eq!(
+            groups_invalidated_by("/api/users/me/avatar"),
+            (Reach::Caller, &[CacheGroup::Statistics][..])
+        );
+
+        let before = generation(CacheGroup::Words, "global-test");
+        invalidate(CacheGroup::Words);
+        assert_ne!(generation(CacheGroup::Words, "global-test"), before);
+    }
+
+    #[test]
+    fn learning_mutations_only_invalidate_the_caller() {
+        let mine = generation(CacheGroup::Statistics, "scope-test-a");
+        let theirs = generation(CacheGroup::Statistics, "scope-test-b");
+        invalidate_scope("scope-test-a", &[CacheGroup::Statistics]);
+        assert_ne!(generation(CacheGroup::Statistics, "scope-test-a"), mine);
+        assert_eq!(
+            generation(CacheGroup::Statistics, "scope-test-b").scoped,
+            theirs.scoped
+        );
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let etag = content_etag(b"{\"success\":true}");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("W/{etag}"), &etag));
+        assert!(etag_matches(&format!("\"other\", {etag}"), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+        assert_ne!(etag, content_etag(b"{\"success\":false}"));
+    }
+
+    #[test]
+    fn stale_generation_is_not_served() {
+        let key = "scope:/api/words?page=1".to_string();
+        let stored = Generation {
+            global: 7,
+            scoped: 2,
+        };
+        remember(key.clone(), "\"abc\"", stored);
+        assert_eq!(cached_etag(&key, stored).as_deref(), Some("\"abc\""));
+        let bumped = Generation {
+            scoped: 3,
+            ..stored
+        };
+        assert_eq!(cached_etag(&key, bumped), None);
+    }
+}
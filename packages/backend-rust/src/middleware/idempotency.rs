@@ -0,0 +1,195 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{header, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::response::json_error;
+use crate::services::idempotency::{
+    is_valid_key, request_hash, BeginOutcome, IdempotencyStore, StoredResponse,
+};
+use crate::state::AppState;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Creation endpoints, the AMAS answer endpoint, and the sync endpoints the
+/// desktop operation journal replays onto, that honour `Idempotency-Key` on
+/// POST.
+const IDEMPOTENT_PATHS: &[&str] = &[
+    "/api/amas/process",
+    "/api/records",
+    "/api/records/batch",
+    "/api/v1/learning/records",
+    "/api/v1/learning/records/batch",
+    "/api/learning/session",
+    "/api/learning-sessions",
+    "/api/v1/sessions",
+    "/api/wordbooks",
+    "/api/word-states/sync",
+    "/api/evaluation/exposures",
+];
+
+fn is_idempotent_path(path: &str) -> bool {
+    let path = path.strip_suffix('/').unwrap_or(path);
+    IDEMPOTENT_PATHS.contains(&path)
+}
+
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if req.method() != Method::POST || !is_idempotent_path(req.uri().path()) {
+        return next.run(req).await;
+    }
+    let Some(key) = req
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+    else {
+        return next.run(req).await;
+    };
+    if !is_valid_key(&key) {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_IDEMPOTENCY_KEY",
+            "Idempotency-Key 格式无效",
+        )
+        .into_response();
+    }
+    // Keys are scoped to the authenticated user, so a retry after re-login
+    // still replays. Requests that fail authentication go straight to the
+    // handler, which rejects them, and never touch the store.
+    let Some(token) = crate::auth::extract_token(req.headers()) else {
+        return next.run(req).await;
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return next.run(req).await;
+    };
+    let cache = state.cache();
+    let Ok(user) =
+        crate::auth::verify_request_token_cached(proxy.as_ref(), &token, cache.as_deref()).await
+    else {
+        return next.run(req).await;
+    };
+    let scope = user.id;
+    let Some(store) = IdempotencyStore::select(proxy.as_ref()).await else {
+        return next.run(req).await;
+    };
+
+    let (parts, body) = req.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return json_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "PAYLOAD_TOO_LARGE",
+                "请求体过大",
+            )
+            .into_response();
+        }
+    };
+    let fingerprint = request_hash(parts.method.as_str(), parts.uri.path(), &bytes);
+
+    match store.begin(&scope, &key, &fingerprint).await {
+        Ok(BeginOutcome::Started) => {}
+        Ok(BeginOutcome::Replay(stored)) => return replay(stored),
+        Ok(BeginOutcome::InProgress) => {
+            return json_error(
+                StatusCode::CONFLICT,
+                "IDEMPOTENCY_KEY_IN_PROGRESS",
+                "相同请求正在处理中，请稍后重试",
+            )
+            .into_response();
+        }
+        Ok(BeginOutcome::Mismatch) => {
+            return json_error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "IDEMPOTENCY_KEY_REUSED",
+                "Idempotency-Key 已用于其他请求",
+            )
+            .into_response();
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Idempotency store unavailable, processing without key");
+            return next
+                .run(Request::from_parts(parts, Body::from(bytes)))
+                .await;
+        }
+    }
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    // Only successful responses are remembered. Client and server errors
+    // release the key so a corrected or later retry runs the handler again.
+    if !response.status().is_success() {
+        if let Err(e) = store.release(&scope, &key).await {
+            tracing::warn!(error = %e, "Failed to release idempotency key");
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to buffer response for idempotency key");
+            let _ = store.release(&scope, &key).await;
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response();
+        }
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        body: String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    if let Err(e) = store.complete(&scope, &key, &stored).await {
+        tracing::warn!(error = %e, "Failed to store idempotent response");
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    if let Some(value) = stored
+        .content_type
+        .and_then(|ct| HeaderValue::from_str(&ct).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_creation_paths_are_covered() {
+        assert!(is_idempotent_path("/api/records"));
+        assert!(is_idempotent_path("/api/learning-sessions/"));
+        assert!(is_idempotent_path("/api/wordbooks"));
+        assert!(is_idempotent_path("/api/word-states/sync"));
+        assert!(is_idempotent_path("/api/amas/process"));
+        assert!(!is_idempotent_path("/api/wordbooks/abc/words"));
+        assert!(!is_idempotent_path("/api/records/statistics"));
+    }
+}
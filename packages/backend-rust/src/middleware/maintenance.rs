@@ -0,0 +1,302 @@
+//! Read-only maintenance mode.
+//!
+//! While enabled (via `MAINTENANCE_MODE` at startup or the admin endpoint),
+//! mutating `/api` requests are rejected with a 503 carrying `Retry-After`
+//! and a `MAINTENANCE_MODE` payload, so clients can queue the write locally
+//! and replay it later instead of surfacing an error. Reads keep working.
+//! Only the admin toggle and the login endpoints stay writable, so the mode
+//! can be switched off and users can still sign in; registration, SCIM
+//! provisioning and every other write are rejected.
+//!
+//! The state is kept in the `maintenance_state` row. Each instance serves a
+//! local copy and re-reads the row every [`POLL_INTERVAL`], so a switch made
+//! on one instance reaches all of them. The current state is reported by the
+//! health endpoints and pushed to connected clients as a `maintenance-mode`
+//! realtime event whenever an instance sees it change.
+
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header::RETRY_AFTER, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use sqlx::Row;
+
+use crate::db::DatabaseProxy;
+use crate::middleware::request_id;
+
+const DEFAULT_RETRY_AFTER_SECS: u64 = 300;
+const DEFAULT_MESSAGE: &str = "系统维护中，暂时只读，请稍后再试";
+pub const REALTIME_EVENT: &str = "maintenance-mode";
+/// How often each instance re-reads the shared state.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Mutations that stay allowed during maintenance: the admin toggle and
+/// signing in.
+const EXEMPT_PATHS: &[&str] = &[
+    "/api/admin/maintenance",
+    "/api/admin/auth/login",
+    "/api/auth/login",
+    "/api/v1/auth/login",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub message: String,
+    /// Seconds clients should wait before retrying queued writes.
+    pub retry_after_secs: u64,
+    /// RFC 3339 time the mode was last switched on.
+    pub since: Option<String>,
+    /// `env` or the ID of the admin who changed it.
+    pub changed_by: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MaintenanceErrorResponse {
+    success: bool,
+    error: String,
+    code: &'static str,
+    retry_after: u64,
+    maintenance: MaintenanceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+static STATUS: OnceLock<RwLock<MaintenanceStatus>> = OnceLock::new();
+
+fn cell() -> &'static RwLock<MaintenanceStatus> {
+    STATUS.get_or_init(|| RwLock::new(from_env()))
+}
+
+fn from_env() -> MaintenanceStatus {
+    let enabled = std::env::var("MAINTENANCE_MODE")
+        .map(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false);
+    MaintenanceStatus {
+        enabled,
+        message: std::env::var("MAINTENANCE_MESSAGE")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MESSAGE.to_string()),
+        retry_after_secs: std::env::var("MAINTENANCE_RETRY_AFTER_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_RETRY_AFTER_SECS),
+        since: enabled.then(|| chrono::Utc::now().to_rfc3339()),
+        changed_by: enabled.then(|| "env".to_string()),
+    }
+}
+
+pub fn status() -> MaintenanceStatus {
+    match cell().read() {
+        Ok(status) => status.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Replaces the local copy; returns whether `enabled` flipped.
+fn apply(updated: &MaintenanceStatus) -> bool {
+    let mut guard = match cell().write() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let changed = guard.enabled != updated.enabled;
+    *guard = updated.clone();
+    changed
+}
+
+async fn notify_clients(updated: &MaintenanceStatus) {
+    let payload = serde_json::to_value(updated).unwrap_or_default();
+    for user_id in crate::routes::realtime::get_online_user_ids_async().await {
+        crate::routes::realtime::send_event(user_id, None, REALTIME_EVENT, payload.clone());
+    }
+}
+
+fn map_row(row: &sqlx::postgres::PgRow) -> MaintenanceStatus {
+    let since: Option<chrono::NaiveDateTime> = row.try_get("since").ok().flatten();
+    MaintenanceStatus {
+        enabled: row.try_get("enabled").unwrap_or(false),
+        message: row
+            .try_get("message")
+            .unwrap_or_else(|_| DEFAULT_MESSAGE.to_string()),
+        retry_after_secs: row
+            .try_get::<i64, _>("retryAfterSecs")
+            .map(|secs| secs.max(0) as u64)
+            .unwrap_or(DEFAULT_RETRY_AFTER_SECS),
+        since: since.map(|t| t.and_utc().to_rfc3339()),
+        changed_by: row.try_get("changedBy").ok().flatten(),
+    }
+}
+
+/// Writes the shared row. `since` and `changedBy` only move when `enabled`
+/// flips; omitted fields keep their stored (or, on first write, local) value.
+async fn store(
+    proxy: &DatabaseProxy,
+    enabled: bool,
+    message: Option<&str>,
+    retry_after_secs: Option<u64>,
+    changed_by: &str,
+) -> Result<MaintenanceStatus, sqlx::Error> {
+    let local = status();
+    let row = sqlx::query(
+        r#"
+        INSERT INTO "maintenance_state"
+            ("id", "enabled", "message", "retryAfterSecs", "since", "changedBy", "updatedAt")
+        VALUES (TRUE, $1, COALESCE($2, $5), COALESCE($3, $6), CASE WHEN $1 THEN NOW() END, $4, NOW())
+        ON CONFLICT ("id") DO UPDATE SET
+            "message" = COALESCE($2, "maintenance_state"."message"),
+            "retryAfterSecs" = COALESCE($3, "maintenance_state"."retryAfterSecs"),
+            "since" = CASE
+                WHEN EXCLUDED."enabled" = "maintenance_state"."enabled" THEN "maintenance_state"."since"
+                WHEN EXCLUDED."enabled" THEN NOW()
+            END,
+            "changedBy" = CASE
+                WHEN EXCLUDED."enabled" = "maintenance_state"."enabled" THEN "maintenance_state"."changedBy"
+                ELSE EXCLUDED."changedBy"
+            END,
+            "enabled" = EXCLUDED."enabled",
+            "updatedAt" = NOW()
+        RETURNING "enabled", "message", "retryAfterSecs", "since", "changedBy"
+        "#,
+    )
+    .bind(enabled)
+    .bind(message)
+    .bind(retry_after_secs.map(|secs| secs.min(i64::MAX as u64) as i64))
+    .bind(changed_by)
+    .bind(&local.message)
+    .bind(local.retry_after_secs.min(i64::MAX as u64) as i64)
+    .fetch_one(proxy.pool())
+    .await?;
+    Ok(map_row(&row))
+}
+
+/// Switches the mode for every instance and notifies this instance's online
+/// clients when it changed; the others notify theirs on their next poll.
+pub async fn set(
+    proxy: &DatabaseProxy,
+    enabled: bool,
+    message: Option<String>,
+    retry_after_secs: Option<u64>,
+    changed_by: &str,
+) -> Result<MaintenanceStatus, sqlx::Error> {
+    let message = message.filter(|m| !m.trim().is_empty());
+    let updated = store(
+        proxy,
+        enabled,
+        message.as_deref(),
+        retry_after_secs,
+        changed_by,
+    )
+    .await?;
+    if apply(&updated) {
+        tracing::warn!(enabled, changed_by, "maintenance mode switched");
+        notify_clients(&updated).await;
+    }
+    Ok(updated)
+}
+
+/// Keeps the local copy in sync with the shared row. A `MAINTENANCE_MODE`
+/// set at startup is written to the row first, so the other instances
+/// follow it.
+pub fn spawn_poller(proxy: Arc<DatabaseProxy>) {
+    tokio::spawn(async move {
+        let initial = status();
+        if initial.enabled {
+            if let Err(e) = store(proxy.as_ref(), true, None, None, "env").await {
+                tracing::warn!(error = %e, "failed to store maintenance mode from env");
+            }
+        }
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let row = sqlx::query(
+                r#"SELECT "enabled", "message", "retryAfterSecs", "since", "changedBy"
+                   FROM "maintenance_state" WHERE "id""#,
+            )
+            .fetch_optional(proxy.pool())
+            .await;
+            match row {
+                Ok(Some(row)) => {
+                    let updated = map_row(&row);
+                    if apply(&updated) {
+                        tracing::warn!(
+                            enabled = updated.enabled,
+                            changed_by = ?updated.changed_by,
+                            "maintenance mode switched on another instance"
+                        );
+                        notify_clients(&updated).await;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::debug!(error = %e, "failed to poll maintenance mode"),
+            }
+        }
+    });
+}
+
+fn is_mutation(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn is_exempt(path: &str) -> bool {
+    let path = path.strip_suffix('/').unwrap_or(path);
+    EXEMPT_PATHS.contains(&path)
+}
+
+pub async fn maintenance_middleware(req: Request<Body>, next: Next) -> Response {
+    if !is_mutation(req.method()) || is_exempt(req.uri().path()) {
+        return next.run(req).await;
+    }
+    let status = status();
+    if !status.enabled {
+        return next.run(req).await;
+    }
+
+    let retry_after = status.retry_after_secs;
+    let body = MaintenanceErrorResponse {
+        success: false,
+        error: status.message.clone(),
+        code: "MAINTENANCE_MODE",
+        retry_after,
+        maintenance: status,
+        request_id: request_id::current(),
+    };
+    let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_toggle_and_login_stay_writable() {
+        assert!(is_mutation(&Method::POST));
+        assert!(is_mutation(&Method::DELETE));
+        assert!(!is_mutation(&Method::GET));
+        assert!(is_exempt("/api/admin/maintenance"));
+        assert!(is_exempt("/api/admin/auth/login"));
+        assert!(is_exempt("/api/auth/login"));
+        assert!(is_exempt("/api/v1/auth/login/"));
+        assert!(!is_exempt("/api/auth/register"));
+        assert!(!is_exempt("/api/v1/auth/register"));
+        assert!(!is_exempt("/api/admin/users"));
+        assert!(!is_exempt("/scim/v2/Users"));
+        assert!(!is_exempt("/api/records"));
+        assert!(!is_exempt("/api/learning-sessions/abc/end"));
+    }
+}
@@ -1,5 +1,11 @@
 #![allow(dead_code)]
 
 pub mod auth;
+pub mod compute_throttle;
 pub mod csrf;
+pub mod etag;
+pub mod idempotency;
+pub mod maintenance;
 pub mod rate_limit;
+pub mod request_id;
+pub mod usage;
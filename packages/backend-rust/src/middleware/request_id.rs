@@ -0,0 +1,108 @@
+//! Request correlation IDs.
+//!
+//! Every request gets an ID: the caller's `X-Request-Id` when it is
+//! well-formed, otherwise a fresh UUID. The ID is echoed on the response,
+//! recorded on a tracing span wrapping the rest of the stack, and kept in a
+//! task-local so error bodies and job records created while handling the
+//! request can pick it up via [`current`]. Background work spawned from a
+//! request should go through [`spawn`] to keep the ID and span.
+
+use std::future::Future;
+
+use axum::body::Body;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Request ID extension for handlers that prefer extractors.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Accepts IDs made of URL-safe characters so they can be logged and echoed
+/// back without escaping.
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// ID of the request being handled on this task, if any.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Spawns a task that keeps the current request ID and tracing span.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let future = future.instrument(tracing::Span::current());
+    match current() {
+        Some(id) => tokio::spawn(REQUEST_ID.scope(id, future)),
+        None => tokio::spawn(future),
+    }
+}
+
+pub async fn request_id_middleware(mut req: Request<Body>, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| is_valid_request_id(v))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %req.method(),
+        path = %req.uri().path(),
+    );
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(req).instrument(span))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_incoming_ids() {
+        assert!(is_valid_request_id("3f2b9c1e-7a4d-4c1b-9e2f-0a1b2c3d4e5f"));
+        assert!(is_valid_request_id("web:1700000000.42_a"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has space"));
+        assert!(!is_valid_request_id("line\nbreak"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+
+    #[tokio::test]
+    async fn spawned_tasks_keep_the_request_id() {
+        assert_eq!(current(), None);
+        let inner = REQUEST_ID
+            .scope("req-1".to_string(), async {
+                spawn(async { current() }).await.unwrap()
+            })
+            .await;
+        assert_eq!(inner.as_deref(), Some("req-1"));
+    }
+}
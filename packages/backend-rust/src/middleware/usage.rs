@@ -0,0 +1,209 @@
+//! Per-user API usage counters.
+//!
+//! Authenticated `/api` requests are counted per user, UTC day, path class and
+//! status, with request/response bytes and latency. The counters live in
+//! memory and each instance merges its own into `api_usage_daily` at most once
+//! per [`FLUSH_INTERVAL`]; see [`crate::services::api_usage`]. The user is taken
+//! from the token signature alone, without the session lookup, so counting
+//! adds no query to the request path.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::body::{Body, HttpBody};
+use axum::extract::State;
+use axum::http::{header::CONTENT_LENGTH, HeaderMap, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::Utc;
+
+use crate::services::api_usage::{self, UsageCounters, UsageKey};
+use crate::state::AppState;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+/// Distinct keys buffered between flushes; new keys beyond it are dropped.
+const MAX_BUFFERED_KEYS: usize = 50_000;
+const MAX_CLASS_LEN: usize = 32;
+
+struct Buffer {
+    counters: HashMap<UsageKey, UsageCounters>,
+    last_flush: Instant,
+    last_prune: Option<Instant>,
+}
+
+static BUFFER: OnceLock<Mutex<Buffer>> = OnceLock::new();
+
+fn buffer() -> &'static Mutex<Buffer> {
+    BUFFER.get_or_init(|| {
+        Mutex::new(Buffer {
+            counters: HashMap::new(),
+            last_flush: Instant::now(),
+            last_prune: None,
+        })
+    })
+}
+
+/// Coarse route family: the first segment after `/api` (two for `/api/v1`).
+/// Unmatched routes share one class so probing cannot grow the table.
+fn path_class(path: &str, status: StatusCode) -> String {
+    if status == StatusCode::NOT_FOUND {
+        return "unmatched".to_string();
+    }
+    let rest = path.trim_start_matches("/api/");
+    let mut segments = rest.split('/').filter(|s| !s.is_empty());
+    let class = match segments.next() {
+        Some("v1") => match segments.next() {
+            Some(next) => format!("v1/{next}"),
+            None => "v1".to_string(),
+        },
+        Some(first) => first.to_string(),
+        None => return "root".to_string(),
+    };
+    let valid = class.len() <= MAX_CLASS_LEN
+        && class
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '/'));
+    if valid {
+        class
+    } else {
+        "other".to_string()
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<i64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+fn is_counted(path: &str) -> bool {
+    path.starts_with("/api/")
+        && !path.starts_with("/api/admin/")
+        && !path.starts_with("/api/health")
+}
+
+pub async fn usage_middleware(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !is_counted(req.uri().path()) {
+        return next.run(req).await;
+    }
+    let Some(user_id) =
+        crate::auth::extract_token(req.headers()).and_then(|t| crate::auth::token_user_id(&t))
+    else {
+        return next.run(req).await;
+    };
+    let path = req.uri().path().to_string();
+    let request_bytes = content_length(req.headers()).unwrap_or(0);
+    let started = Instant::now();
+
+    let response = next.run(req).await;
+
+    let status = response.status();
+    let response_bytes = content_length(response.headers())
+        .or_else(|| response.body().size_hint().exact().map(|n| n as i64))
+        .unwrap_or(0);
+    let now = Utc::now().naive_utc();
+    let key = UsageKey {
+        user_id,
+        day: now.date(),
+        path_class: path_class(&path, status),
+        status: status.as_u16(),
+    };
+    let counters = UsageCounters {
+        requests: 1,
+        request_bytes,
+        response_bytes,
+        total_latency_ms: started.elapsed().as_millis() as i64,
+        last_seen_at: now,
+    };
+    record(key, counters, &state);
+    response
+}
+
+fn record(key: UsageKey, counters: UsageCounters, state: &AppState) {
+    let (batch, prune) = {
+        let mut buffer = match buffer().lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let len = buffer.counters.len();
+        match buffer.counters.get_mut(&key) {
+            Some(existing) => existing.merge(&counters),
+            None if len < MAX_BUFFERED_KEYS => {
+                buffer.counters.insert(key, counters);
+            }
+            None => {}
+        }
+        if buffer.last_flush.elapsed() < FLUSH_INTERVAL {
+            return;
+        }
+        buffer.last_flush = Instant::now();
+        let prune = buffer
+            .last_prune
+            .is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL);
+        if prune {
+            buffer.last_prune = Some(Instant::now());
+        }
+        let batch: Vec<_> = std::mem::take(&mut buffer.counters).into_iter().collect();
+        (batch, prune)
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let pool = proxy.pool();
+        if let Err(err) = api_usage::flush(pool, &batch).await {
+            tracing::warn!(error = %err, keys = batch.len(), "failed to flush API usage counters");
+        }
+        if prune {
+            if let Err(err) = api_usage::prune(pool).await {
+                tracing::warn!(error = %err, "failed to prune API usage counters");
+            }
+        }
+    });
+}
+
+/// Counters of one user this instance has not flushed yet.
+pub fn pending_for_user(user_id: &str) -> Vec<(UsageKey, UsageCounters)> {
+    let buffer = match buffer().lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    buffer
+        .counters
+        .iter()
+        .filter(|(key, _)| key.user_id == user_id)
+        .map(|(key, counters)| (key.clone(), *counters))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_class_is_bounded() {
+        let ok = StatusCode::OK;
+        assert_eq!(path_class("/api/records/batch", ok), "records");
+        assert_eq!(path_class("/api/v1/learning/records", ok), "v1/learning");
+        assert_eq!(
+            path_class("/api/learning-sessions/abc/end", ok),
+            "learning-sessions"
+        );
+        assert_eq!(
+            path_class("/api/whatever/x", StatusCode::NOT_FOUND),
+            "unmatched"
+        );
+        assert_eq!(path_class("/api/%3Cscript%3E", ok), "other");
+        assert!(is_counted("/api/records"));
+        assert!(!is_counted("/api/admin/users"));
+        assert!(!is_counted("/health"));
+    }
+}
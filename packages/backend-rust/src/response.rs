@@ -5,11 +5,15 @@ use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Serialize;
 
+use crate::middleware::request_id;
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub success: bool,
     pub error: String,
     pub code: String,
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -109,6 +113,7 @@ impl IntoResponse for AppError {
             success: false,
             error: message,
             code: self.code,
+            request_id: request_id::current(),
         };
 
         (self.status, Json(body)).into_response()
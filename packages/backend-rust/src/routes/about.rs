@@ -617,6 +617,14 @@ async fn simulate_batch(
                         is_simulation: false,
                         emotion_label: None,
                         flow_score: None,
+                        is_forced_exploration: result.forced_exploration.is_some(),
+                        exploration_scale: result.exploration.as_ref().map(|e| e.scale),
+                        intended_exploration_share: result
+                            .exploration
+                            .as_ref()
+                            .map(|e| e.intended_share),
+                        propensity: result.exploration.as_ref().and_then(|e| e.propensity),
+                        algorithm: None,
                     };
 
                     if let Err(e) = insert_decision_record(proxy.as_ref(), &record).await {
@@ -1810,7 +1818,8 @@ async fn about_metrics(State(_state): State<AppState>) -> Response {
 async fn about_metrics_prometheus(State(_state): State<AppState>) -> Response {
     let connections = store().connections.load(Ordering::Relaxed) as f64;
     let body = format!(
-        "# HELP about_sse_connections Number of active SSE connections\n# TYPE about_sse_connections gauge\nabout_sse_connections {connections}\n"
+        "# HELP about_sse_connections Number of active SSE connections\n# TYPE about_sse_connections gauge\nabout_sse_connections {connections}\n{}",
+        crate::db::pending_writes::prometheus_metrics()
     );
     let mut response = Response::new(Body::from(body));
     response.headers_mut().insert(
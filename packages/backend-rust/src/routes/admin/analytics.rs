@@ -7,6 +7,10 @@ use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
+use crate::db::operations::amas::{
+    decision_breakdown_by_algorithm_version, exploration_share_breakdown,
+    AlgorithmVersionBreakdown, ExplorationShareBand,
+};
 use crate::db::operations::analytics::{
     insert_alert_root_cause_analysis, update_alert_root_cause_resolved,
     upsert_user_behavior_insight,
@@ -31,6 +35,14 @@ pub fn router() -> Router<AppState> {
             "/alert-root-cause/:id/resolve",
             put(resolve_alert_root_cause),
         )
+        .route(
+            "/decisions/algorithm-versions",
+            get(decisions_by_algorithm_version),
+        )
+        .route(
+            "/decisions/exploration-share",
+            get(decisions_exploration_share),
+        )
 }
 
 #[derive(Debug, Deserialize)]
@@ -423,3 +435,88 @@ async fn resolve_alert_root_cause(
         }
     }
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlgorithmVersionQuery {
+    days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlgorithmVersionReport {
+    current: danci_algo::build_info::AlgorithmBuild,
+    days: i64,
+    versions: Vec<AlgorithmVersionBreakdown>,
+}
+
+async fn decisions_by_algorithm_version(
+    State(state): State<AppState>,
+    Query(query): Query<AlgorithmVersionQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(proxy) = state.db_proxy() else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        ));
+    };
+
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).naive_utc();
+    match decision_breakdown_by_algorithm_version(proxy.as_ref(), since).await {
+        Ok(versions) => Ok(Json(SuccessResponse {
+            success: true,
+            data: AlgorithmVersionReport {
+                current: crate::amas::version::current(),
+                days,
+                versions,
+            },
+        })),
+        Err(e) => {
+            tracing::warn!(error = %e, "decision breakdown by algorithm version failed");
+            Err(json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "DB_ERROR",
+                "查询算法版本决策分布失败",
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExplorationShareReport {
+    days: i64,
+    bands: Vec<ExplorationShareBand>,
+}
+
+async fn decisions_exploration_share(
+    State(state): State<AppState>,
+    Query(query): Query<AlgorithmVersionQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(proxy) = state.db_proxy() else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        ));
+    };
+
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).naive_utc();
+    match exploration_share_breakdown(proxy.as_ref(), since).await {
+        Ok(bands) => Ok(Json(SuccessResponse {
+            success: true,
+            data: ExplorationShareReport { days, bands },
+        })),
+        Err(e) => {
+            tracing::warn!(error = %e, "exploration share breakdown failed");
+            Err(json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "DB_ERROR",
+                "查询探索占比失败",
+            ))
+        }
+    }
+}
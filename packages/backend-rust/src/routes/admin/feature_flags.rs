@@ -0,0 +1,105 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use danci_algo::FlagDefinition;
+use serde::Serialize;
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::feature_flags::{self, FeatureFlagError};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_flags))
+        .route("/:key", put(upsert_flag).delete(delete_flag))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn flag_error(err: FeatureFlagError) -> Response {
+    match err {
+        FeatureFlagError::Invalid(msg) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_FLAG", msg).into_response()
+        }
+        FeatureFlagError::Sql(e) => {
+            tracing::warn!(error = %e, "feature flag query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "FEATURE_FLAG_FAILED",
+                "功能开关操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn list_flags(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match feature_flags::load_flag_set(proxy.as_ref()).await {
+        Ok(flags) => Json(SuccessResponse {
+            success: true,
+            data: flags,
+        })
+        .into_response(),
+        Err(e) => flag_error(e.into()),
+    }
+}
+
+async fn upsert_flag(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Path(key): Path<String>,
+    Json(mut payload): Json<FlagDefinition>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    payload.key = key;
+    if let Err(e) = feature_flags::upsert_flag(proxy.as_ref(), &payload, &admin.id).await {
+        return flag_error(e);
+    }
+    let flags = feature_flags::refresh_flag_set(&state).await;
+    Json(SuccessResponse {
+        success: true,
+        data: flags,
+    })
+    .into_response()
+}
+
+async fn delete_flag(State(state): State<AppState>, Path(key): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match feature_flags::delete_flag(proxy.as_ref(), &key).await {
+        Ok(true) => {
+            feature_flags::refresh_flag_set(&state).await;
+            Json(SuccessResponse {
+                success: true,
+                data: serde_json::json!({ "deleted": true }),
+            })
+            .into_response()
+        }
+        Ok(false) => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "功能开关不存在").into_response()
+        }
+        Err(e) => flag_error(e),
+    }
+}
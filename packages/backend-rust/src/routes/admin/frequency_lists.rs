@@ -0,0 +1,169 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use danci_algo::BootstrapConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::frequency_bootstrap::{self, FrequencyBootstrapError};
+use crate::state::AppState;
+
+/// Word coverage a wordbook needs for its language's list to be considered usable.
+const DEFAULT_MIN_COVERAGE: f64 = 0.6;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_imports))
+        .route("/:language", put(import_list))
+        .route("/:language/coverage", get(wordbook_coverage))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportBody {
+    /// Frequency list as CSV text.
+    csv: String,
+    source: Option<String>,
+    #[serde(default)]
+    config: BootstrapConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoverageQuery {
+    wordbook_id: String,
+    min_coverage: Option<f64>,
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+async fn list_imports(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match frequency_bootstrap::list_imports(proxy.pool()).await {
+        Ok(lists) => Json(SuccessResponse {
+            success: true,
+            data: lists,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::warn!(error = %e, "frequency list query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "FREQUENCY_LIST_FAILED",
+                "读取词频表失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn import_list(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Path(language): Path<String>,
+    Json(payload): Json<ImportBody>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let language = language.trim().to_lowercase();
+    let source = payload
+        .source
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    match frequency_bootstrap::import_list(
+        proxy.pool(),
+        &language,
+        &payload.csv,
+        &payload.config,
+        source,
+        &admin.id,
+    )
+    .await
+    {
+        Ok(summary) => {
+            tracing::info!(
+                language = %summary.language_code,
+                entries = summary.entries,
+                admin_id = %admin.id,
+                "frequency list imported"
+            );
+            Json(SuccessResponse {
+                success: true,
+                data: summary,
+            })
+            .into_response()
+        }
+        Err(FrequencyBootstrapError::Invalid(msg)) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_FREQUENCY_LIST", msg).into_response()
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "frequency list import failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "FREQUENCY_LIST_FAILED",
+                "导入词频表失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn wordbook_coverage(
+    State(state): State<AppState>,
+    Path(language): Path<String>,
+    Query(query): Query<CoverageQuery>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let min_coverage = query
+        .min_coverage
+        .filter(|c| (0.0..=1.0).contains(c))
+        .unwrap_or(DEFAULT_MIN_COVERAGE);
+    match frequency_bootstrap::wordbook_coverage(
+        proxy.pool(),
+        &language.trim().to_lowercase(),
+        query.wordbook_id.trim(),
+        min_coverage,
+    )
+    .await
+    {
+        Ok(report) => Json(SuccessResponse {
+            success: true,
+            data: report,
+        })
+        .into_response(),
+        Err(FrequencyBootstrapError::NotFound) => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "词频表或词书不存在").into_response()
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "frequency coverage check failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "FREQUENCY_LIST_FAILED",
+                "检查词书覆盖率失败",
+            )
+            .into_response()
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use danci_algo::ParamRegistry;
+use serde::Serialize;
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::language_params::{self, LanguageParamsError};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(get_registry).put(update_registry))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+async fn get_registry(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match language_params::registry(proxy.pool()).await {
+        Ok(registry) => Json(SuccessResponse {
+            success: true,
+            data: registry,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::warn!(error = %e, "language params query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "LANGUAGE_PARAMS_FAILED",
+                "读取语言参数失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn update_registry(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<ParamRegistry>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match language_params::save_registry(proxy.pool(), &payload, &admin.id).await {
+        Ok(()) => Json(SuccessResponse {
+            success: true,
+            data: payload,
+        })
+        .into_response(),
+        Err(LanguageParamsError::Invalid(msg)) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_LANGUAGE_PARAMS", msg).into_response()
+        }
+        Err(LanguageParamsError::Sql(e)) => {
+            tracing::warn!(error = %e, "language params update failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "LANGUAGE_PARAMS_FAILED",
+                "保存语言参数失败",
+            )
+            .into_response()
+        }
+    }
+}
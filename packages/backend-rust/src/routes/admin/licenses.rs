@@ -0,0 +1,170 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, put};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::wordbook_licenses::{self, GrantLicenseRequest, LicenseError, LicenseFilter};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_licenses).post(grant_license))
+        .route("/wordbooks/:word_book_id/requirement", put(set_requirement))
+        .route("/:id", delete(revoke_license))
+        .route("/:id/history", get(license_history))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+fn ok<T: Serialize>(data: T) -> Response {
+    Json(SuccessResponse {
+        success: true,
+        data,
+    })
+    .into_response()
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn license_error(err: LicenseError) -> Response {
+    match err {
+        LicenseError::InvalidSeats => json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_SEATS",
+            "席位数必须为正整数",
+        )
+        .into_response(),
+        LicenseError::InvalidExpiry => json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_EXPIRY",
+            "到期时间必须晚于当前时间",
+        )
+        .into_response(),
+        LicenseError::NotFound => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "记录不存在").into_response()
+        }
+        LicenseError::Sql(e) => {
+            tracing::warn!(error = %e, "wordbook license query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "LICENSE_QUERY_FAILED",
+                "词书授权操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn list_licenses(
+    State(state): State<AppState>,
+    Query(filter): Query<LicenseFilter>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match wordbook_licenses::list_licenses(proxy.as_ref(), &filter).await {
+        Ok(licenses) => ok(licenses),
+        Err(e) => license_error(e),
+    }
+}
+
+/// Grants a license, or changes seats / expiry of the organization's active
+/// license for the same wordbook.
+async fn grant_license(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<GrantLicenseRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    if payload.word_book_id.trim().is_empty() || payload.organization_id.trim().is_empty() {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "wordBookId 和 organizationId 不能为空",
+        )
+        .into_response();
+    }
+    match wordbook_licenses::grant_license(proxy.as_ref(), &admin.id, &payload).await {
+        Ok(license) => ok(license),
+        Err(e) => license_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeQuery {
+    reason: Option<String>,
+}
+
+async fn revoke_license(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Path(id): Path<String>,
+    Query(query): Query<RevokeQuery>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match wordbook_licenses::revoke_license(proxy.as_ref(), &admin.id, &id, query.reason.as_deref())
+        .await
+    {
+        Ok(license) => ok(license),
+        Err(e) => license_error(e),
+    }
+}
+
+async fn license_history(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match wordbook_licenses::license_history(proxy.as_ref(), &id).await {
+        Ok(events) => ok(events),
+        Err(e) => license_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequirementRequest {
+    requires_license: bool,
+}
+
+async fn set_requirement(
+    State(state): State<AppState>,
+    Path(word_book_id): Path<String>,
+    Json(payload): Json<RequirementRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match wordbook_licenses::set_requires_license(
+        proxy.as_ref(),
+        &word_book_id,
+        payload.requires_license,
+    )
+    .await
+    {
+        Ok(()) => ok(serde_json::json!({
+            "wordBookId": word_book_id,
+            "requiresLicense": payload.requires_license,
+        })),
+        Err(e) => license_error(e),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::core::{PageInfo, Pagination, SortField, SortFilter};
 use crate::response::json_error;
 use crate::state::AppState;
 use axum::extract::{Path, Query, State};
@@ -17,8 +18,6 @@ struct SuccessResponse<T> {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct LogsQuery {
-    page: Option<i32>,
-    page_size: Option<i32>,
     level: Option<String>,
     module: Option<String>,
     source: Option<String>,
@@ -27,8 +26,20 @@ struct LogsQuery {
     message_pattern: Option<String>,
     start_time: Option<String>,
     end_time: Option<String>,
-    sort_by: Option<String>,
-    sort_order: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LogSortField {
+    Timestamp,
+    Level,
+}
+
+impl SortField for LogSortField {
+    const FIELDS: &'static [(&'static str, Self)] = &[
+        ("timestamp", LogSortField::Timestamp),
+        ("level", LogSortField::Level),
+    ];
+    const DEFAULT: Self = LogSortField::Timestamp;
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,20 +83,11 @@ struct LogEntry {
     timestamp: String,
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct PaginationInfo {
-    page: i32,
-    page_size: i32,
-    total: i64,
-    total_pages: i64,
-}
-
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct LogsListData {
     logs: Vec<LogEntry>,
-    pagination: PaginationInfo,
+    pagination: PageInfo,
 }
 
 #[derive(Serialize)]
@@ -189,24 +191,23 @@ pub fn router() -> Router<AppState> {
         .route("/{id}", get(get_log))
 }
 
-async fn list_logs(State(state): State<AppState>, Query(query): Query<LogsQuery>) -> Response {
+async fn list_logs(
+    State(state): State<AppState>,
+    pagination: Pagination<20, 100>,
+    sort: SortFilter<LogSortField>,
+    Query(query): Query<LogsQuery>,
+) -> Response {
     let Some(proxy) = state.db_proxy() else {
         return json_error(StatusCode::SERVICE_UNAVAILABLE, "DB_ERROR", "数据库不可用")
             .into_response();
     };
     let pg = proxy.pool();
 
-    let page = query.page.unwrap_or(1).max(1);
-    let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * page_size;
-    let sort_by = query.sort_by.as_deref().unwrap_or("timestamp");
-    let sort_order = query.sort_order.as_deref().unwrap_or("desc");
-
-    let order_column = match sort_by {
-        "level" => "\"level\"",
-        _ => "\"timestamp\"",
+    let order_column = match sort.field {
+        LogSortField::Level => "\"level\"",
+        LogSortField::Timestamp => "\"timestamp\"",
     };
-    let order_dir = if sort_order == "asc" { "ASC" } else { "DESC" };
+    let order_dir = sort.order.sql();
 
     // Parse comma-separated levels into a vector
     let levels: Vec<&str> = query
@@ -306,7 +307,7 @@ async fn list_logs(State(state): State<AppState>, Query(query): Query<LogsQuery>
         data_q = data_q.bind(v);
     }
 
-    data_q = data_q.bind(page_size).bind(offset);
+    data_q = data_q.bind(pagination.limit()).bind(pagination.offset);
 
     let total: i64 = count_q.fetch_one(pg).await.unwrap_or(0);
     let rows = match data_q.fetch_all(pg).await {
@@ -322,7 +323,6 @@ async fn list_logs(State(state): State<AppState>, Query(query): Query<LogsQuery>
     };
 
     let logs: Vec<LogEntry> = rows.iter().map(parse_log_entry_pg).collect();
-    let total_pages = ((total as f64) / (page_size as f64)).ceil() as i64;
 
     (
         StatusCode::OK,
@@ -330,12 +330,7 @@ async fn list_logs(State(state): State<AppState>, Query(query): Query<LogsQuery>
             success: true,
             data: LogsListData {
                 logs,
-                pagination: PaginationInfo {
-                    page,
-                    page_size,
-                    total,
-                    total_pages,
-                },
+                pagination: pagination.info(total),
             },
         }),
     )
@@ -0,0 +1,75 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::middleware::maintenance::{self, MaintenanceStatus};
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(get_status).put(set_status))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetMaintenanceRequest {
+    enabled: bool,
+    message: Option<String>,
+    retry_after_secs: Option<u64>,
+}
+
+async fn get_status() -> Json<SuccessResponse<MaintenanceStatus>> {
+    Json(SuccessResponse {
+        success: true,
+        data: maintenance::status(),
+    })
+}
+
+async fn set_status(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<SetMaintenanceRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "DATABASE_UNAVAILABLE",
+            "数据库不可用",
+        )
+        .into_response();
+    };
+    match maintenance::set(
+        proxy.as_ref(),
+        payload.enabled,
+        payload.message,
+        payload.retry_after_secs,
+        &admin.id,
+    )
+    .await
+    {
+        Ok(data) => Json(SuccessResponse {
+            success: true,
+            data,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to store maintenance mode");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "MAINTENANCE_UPDATE_FAILED",
+                "维护模式切换失败",
+            )
+            .into_response()
+        }
+    }
+}
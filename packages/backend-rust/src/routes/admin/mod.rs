@@ -17,16 +17,25 @@ use crate::state::AppState;
 mod analytics;
 mod auth;
 mod broadcast;
+mod feature_flags;
+mod frequency_lists;
+mod language_params;
+mod licenses;
 mod llm;
 mod logs;
+mod maintenance;
 mod monitoring;
 mod ops;
 mod ota;
 mod quality;
+mod reports;
+mod scim;
 pub mod settings;
+mod simulations;
 mod statistics;
 mod users;
 mod version;
+mod webhooks;
 mod wordbooks;
 
 pub use auth::{
@@ -52,6 +61,15 @@ pub fn router() -> Router<AppState> {
         .nest("/analytics", analytics::router())
         .nest("/amas-monitoring", monitoring::router())
         .nest("/settings", settings::router())
+        .nest("/feature-flags", feature_flags::router())
+        .nest("/webhooks", webhooks::router())
+        .nest("/simulations", simulations::router())
+        .nest("/reports", reports::router())
+        .nest("/language-params", language_params::router())
+        .nest("/frequency-lists", frequency_lists::router())
+        .nest("/licenses", licenses::router())
+        .nest("/maintenance", maintenance::router())
+        .nest("/scim", scim::router())
         .route(
             "/statistics",
             axum::routing::get(statistics::get_statistics),
@@ -79,6 +97,63 @@ pub fn router() -> Router<AppState> {
             axum::routing::get(ota::get_update_status),
         )
         .route("/system/restart", axum::routing::post(ota::restart_backend))
+        .route("/system/seed", axum::routing::post(seed_dev_fixtures))
+}
+
+async fn seed_dev_fixtures(State(state): State<AppState>) -> Response {
+    use crate::seed::{seed_fixtures, SeedError, SeedFixtures};
+
+    if !crate::seed::seeding_allowed() {
+        return json_error(
+            StatusCode::FORBIDDEN,
+            "SEED_DISABLED",
+            "未启用示例数据导入（ALLOW_FIXTURE_SEED）",
+        )
+        .into_response();
+    }
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+    let fixtures = match SeedFixtures::from_env() {
+        Ok(fixtures) => fixtures,
+        Err(e) => {
+            tracing::warn!(error = %e, "seed fixtures could not be loaded");
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FIXTURES",
+                "示例数据文件无效",
+            )
+            .into_response();
+        }
+    };
+
+    match seed_fixtures(proxy.as_ref(), &fixtures).await {
+        Ok(report) => Json(SuccessResponse {
+            success: true,
+            data: report,
+        })
+        .into_response(),
+        Err(SeedError::Disabled) => json_error(
+            StatusCode::FORBIDDEN,
+            "SEED_DISABLED",
+            "未启用示例数据导入（ALLOW_FIXTURE_SEED）",
+        )
+        .into_response(),
+        Err(e) => {
+            tracing::warn!(error = %e, "seed fixtures failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "导入示例数据失败",
+            )
+            .into_response()
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,16 +1,21 @@
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Extension;
 use axum::Json;
 use axum::Router;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::amas::cohort_stats::{aggregate_cohort, CohortQuery};
+use crate::amas::divergence::SyncMode;
+use crate::db::operations::amas::UserModelScanFilter;
 use crate::db::operations::monitoring::{
     get_aggregates_15m, get_aggregates_daily, get_health_reports, get_monitoring_overview,
 };
 use crate::response::{json_error, AppError};
 use crate::services::admin_auth::AdminAuthUser;
+use crate::services::model_sync;
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -24,6 +29,8 @@ pub fn router() -> Router<AppState> {
         .route("/overview", axum::routing::get(overview))
         .route("/aggregates", axum::routing::get(aggregates))
         .route("/health-reports", axum::routing::get(health_reports))
+        .route("/arm-stats", axum::routing::get(arm_stats))
+        .route("/users/:user_id/sync-diffs", axum::routing::get(sync_diffs))
 }
 
 async fn overview(
@@ -160,3 +167,165 @@ async fn health_reports(
         }
     }
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArmStatsQuery {
+    /// Comma-separated user ids.
+    user_ids: Option<String>,
+    registered_from: Option<String>,
+    registered_to: Option<String>,
+    /// Only users whose model was saved within this many days.
+    active_days: Option<i64>,
+    context_key: Option<String>,
+    credible_level: Option<f64>,
+    per_user_cap: Option<f64>,
+}
+
+/// Accepts `YYYY-MM-DD` or an RFC 3339 timestamp.
+fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0);
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc).naive_utc())
+}
+
+fn invalid_arm_query(message: &str) -> AppError {
+    json_error(StatusCode::BAD_REQUEST, "INVALID_QUERY", message)
+}
+
+async fn arm_stats(
+    State(state): State<AppState>,
+    Extension(_user): Extension<AdminAuthUser>,
+    Query(query): Query<ArmStatsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(proxy) = state.db_proxy() else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        ));
+    };
+
+    let parse = |value: Option<&String>| match value {
+        Some(v) => parse_time(v)
+            .map(Some)
+            .ok_or_else(|| invalid_arm_query("时间格式无效")),
+        None => Ok(None),
+    };
+    let registered_from = parse(query.registered_from.as_ref())?;
+    let registered_to = parse(query.registered_to.as_ref())?;
+    let updated_since = match query.active_days {
+        Some(days) if (1..=3650).contains(&days) => {
+            Some(Utc::now().naive_utc() - chrono::Duration::days(days))
+        }
+        Some(_) => return Err(invalid_arm_query("activeDays 需在 1-3650 之间")),
+        None => None,
+    };
+    let credible_level = query.credible_level.unwrap_or(0.95);
+    if !(0.5..=0.999).contains(&credible_level) {
+        return Err(invalid_arm_query("credibleLevel 需在 0.5-0.999 之间"));
+    }
+    if query
+        .per_user_cap
+        .is_some_and(|cap| cap.is_nan() || cap < 1.0)
+    {
+        return Err(invalid_arm_query("perUserCap 需不小于 1"));
+    }
+    let user_ids = query.user_ids.as_deref().map(|ids| {
+        ids.split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    });
+
+    let cohort = CohortQuery {
+        filter: UserModelScanFilter {
+            user_ids,
+            registered_from,
+            registered_to,
+            updated_since,
+        },
+        context_key: query.context_key.filter(|key| !key.is_empty()),
+        credible_level,
+        per_user_trial_cap: query.per_user_cap,
+        ..CohortQuery::default()
+    };
+
+    match aggregate_cohort(&proxy, &cohort).await {
+        Ok(stats) => Ok(Json(SuccessResponse {
+            success: true,
+            data: stats,
+        })),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to aggregate cohort arm stats");
+            Err(json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "DB_ERROR",
+                "获取策略统计失败",
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncDiffsQuery {
+    device_id: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncDiffEntry {
+    device_id: String,
+    mode: SyncMode,
+    resolution: String,
+    created_at: i64,
+    diffs: Vec<danci_algo::SnapshotDiff>,
+}
+
+/// What each recent model sync of a user changed in the server snapshot.
+async fn sync_diffs(
+    State(state): State<AppState>,
+    Extension(_user): Extension<AdminAuthUser>,
+    Path(user_id): Path<String>,
+    Query(query): Query<SyncDiffsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(proxy) = state.db_proxy() else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        ));
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    match model_sync::list_reports(proxy.pool(), &user_id, query.device_id.as_deref(), limit).await
+    {
+        Ok(reports) => Ok(Json(SuccessResponse {
+            success: true,
+            data: reports
+                .into_iter()
+                .map(|r| SyncDiffEntry {
+                    device_id: r.device_id,
+                    mode: r.mode,
+                    resolution: r.resolution,
+                    created_at: r.created_at,
+                    diffs: r.diffs,
+                })
+                .collect::<Vec<_>>(),
+        })),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to list model sync diffs");
+            Err(json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "DB_ERROR",
+                "获取模型同步差异失败",
+            ))
+        }
+    }
+}
@@ -2,12 +2,16 @@ use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, patch, post};
-use axum::{Json, Router};
+use axum::{Extension, Json, Router};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
+use crate::db::snapshot_crypto::{self, MasterKeyProvider};
+use crate::middleware::compute_throttle::{self, ThrottleConfig};
 use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::timeline::{self, TimelineError};
 use crate::services::{insight_generator, segment_classifier, weekly_report};
 use crate::state::AppState;
 use crate::workers::clustering;
@@ -107,7 +111,21 @@ pub fn router() -> Router<AppState> {
         .route("/retention", get(get_retention))
         .route("/amas/config/reload", post(reload_amas_config))
         .route("/amas/config", get(get_amas_config))
+        .route("/amas/memory", get(get_amas_memory))
         .route("/clustering/trigger", post(trigger_clustering))
+        .route(
+            "/compute-throttle",
+            get(get_compute_throttle).put(update_compute_throttle),
+        )
+        .route("/timeline/backfill", post(backfill_timeline))
+        .route("/schema-drift", get(get_schema_drift))
+        .route("/schema-drift/check", post(check_schema_drift))
+        .route("/snapshot-keys", get(get_snapshot_keys))
+        .route("/snapshot-keys/rewrap", post(rewrap_snapshot_keys))
+        .route(
+            "/snapshot-keys/users/:id/rotate",
+            post(rotate_user_snapshot_key),
+        )
 }
 
 async fn analyze_alert(
@@ -761,6 +779,24 @@ async fn get_amas_config(State(state): State<AppState>) -> Response {
     .into_response()
 }
 
+#[derive(Debug, Deserialize)]
+struct MemoryQuery {
+    top: Option<usize>,
+}
+
+async fn get_amas_memory(
+    State(state): State<AppState>,
+    Query(query): Query<MemoryQuery>,
+) -> Response {
+    let top = query.top.unwrap_or(10).clamp(1, 100);
+    let report = state.amas_engine().memory_report(top).await;
+    Json(SuccessResponse {
+        success: true,
+        data: report,
+    })
+    .into_response()
+}
+
 async fn trigger_clustering(State(state): State<AppState>) -> Response {
     let Some(proxy) = state.db_proxy() else {
         return json_error(
@@ -788,3 +824,264 @@ async fn trigger_clustering(State(state): State<AppState>) -> Response {
         }
     }
 }
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DB_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn snapshot_keys_not_configured() -> Response {
+    json_error(
+        StatusCode::CONFLICT,
+        "SNAPSHOT_KEYS_NOT_CONFIGURED",
+        "未配置模型快照主密钥",
+    )
+    .into_response()
+}
+
+async fn get_snapshot_keys(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let provider = snapshot_crypto::master_keys();
+    match snapshot_crypto::master_key_usage(proxy.pool()).await {
+        Ok(usage) => Json(SuccessResponse {
+            success: true,
+            data: serde_json::json!({
+                "enabled": provider.is_some(),
+                "activeKeyId": provider.map(MasterKeyProvider::active_key_id),
+                "usage": usage,
+            }),
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Snapshot key usage query failed");
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, "DB_ERROR", "查询失败").into_response()
+        }
+    }
+}
+
+async fn rewrap_snapshot_keys(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let Some(provider) = snapshot_crypto::master_keys() else {
+        return snapshot_keys_not_configured();
+    };
+    match snapshot_crypto::rewrap_data_keys(proxy.pool(), provider).await {
+        Ok(report) => Json(SuccessResponse {
+            success: true,
+            data: report,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Snapshot key rewrap failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "REWRAP_FAILED",
+                format!("主密钥轮换失败: {e}"),
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn rotate_user_snapshot_key(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let Some(provider) = snapshot_crypto::master_keys() else {
+        return snapshot_keys_not_configured();
+    };
+    match snapshot_crypto::rotate_user_data_key(proxy.pool(), provider, &user_id).await {
+        Ok(rotation) => Json(SuccessResponse {
+            success: true,
+            data: rotation,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::error!(user_id = %user_id, error = %e, "User data key rotation failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "ROTATION_FAILED",
+                format!("数据密钥轮换失败: {e}"),
+            )
+            .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateComputeThrottleRequest {
+    enabled: Option<bool>,
+    per_user_concurrency: Option<u32>,
+    global_concurrency: Option<u32>,
+    shed_ratio: Option<f64>,
+    queue_deadline_ms: Option<u64>,
+    max_queued_per_user: Option<u32>,
+    retry_after_secs: Option<u64>,
+}
+
+async fn get_compute_throttle() -> Response {
+    Json(SuccessResponse {
+        success: true,
+        data: compute_throttle::status(),
+    })
+    .into_response()
+}
+
+/// Changes the compute throttle limits of this instance; omitted fields keep
+/// their current value.
+async fn update_compute_throttle(
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<UpdateComputeThrottleRequest>,
+) -> Response {
+    let current = compute_throttle::status().config;
+    let config = ThrottleConfig {
+        enabled: payload.enabled.unwrap_or(current.enabled),
+        per_user_concurrency: payload
+            .per_user_concurrency
+            .unwrap_or(current.per_user_concurrency),
+        global_concurrency: payload
+            .global_concurrency
+            .unwrap_or(current.global_concurrency),
+        shed_ratio: payload.shed_ratio.unwrap_or(current.shed_ratio),
+        queue_deadline_ms: payload
+            .queue_deadline_ms
+            .unwrap_or(current.queue_deadline_ms),
+        max_queued_per_user: payload
+            .max_queued_per_user
+            .unwrap_or(current.max_queued_per_user),
+        retry_after_secs: payload.retry_after_secs.unwrap_or(current.retry_after_secs),
+    };
+    let errors = config.validate();
+    if !errors.is_empty() {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            errors.join("；"),
+        )
+        .into_response();
+    }
+    Json(SuccessResponse {
+        success: true,
+        data: compute_throttle::configure(config, &admin.id),
+    })
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackfillTimelineRequest {
+    /// Backfills every recently active user when omitted.
+    user_id: Option<String>,
+    days: Option<i64>,
+}
+
+/// Rebuilds timeline entries for the last `days` closed days. A single user
+/// is backfilled inline; all users are backfilled in the background.
+/// Result of the last drift check between the schema registry and the live
+/// databases; `data` is null until the startup check has finished.
+async fn get_schema_drift(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    Json(SuccessResponse {
+        success: true,
+        data: proxy.schema_drift_report(),
+    })
+    .into_response()
+}
+
+/// Re-runs the drift check now, e.g. after fixing a drifted table, so its
+/// dual-write block lifts without waiting for the next periodic check.
+async fn check_schema_drift(State(state): State<AppState>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match proxy.check_schema_drift().await {
+        Ok(report) => Json(SuccessResponse {
+            success: true,
+            data: report,
+        })
+        .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Schema drift check failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SCHEMA_DRIFT_CHECK_FAILED",
+                "数据库结构漂移检查失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn backfill_timeline(
+    State(state): State<AppState>,
+    Json(payload): Json<BackfillTimelineRequest>,
+) -> Response {
+    let days = payload.days.unwrap_or(90);
+    if !(1..=timeline::MAX_BACKFILL_DAYS).contains(&days) {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            format!("days 必须在 1 到 {} 之间", timeline::MAX_BACKFILL_DAYS),
+        )
+        .into_response();
+    }
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+
+    let Some(user_id) = payload.user_id else {
+        tokio::spawn(async move {
+            match timeline::backfill_all(proxy.pool(), days).await {
+                Ok(report) => tracing::info!(
+                    users = report.users,
+                    entries = report.entries,
+                    failed = report.failed,
+                    days,
+                    "Timeline backfill finished"
+                ),
+                Err(e) => tracing::error!(error = %e, "Timeline backfill failed"),
+            }
+        });
+        return (
+            StatusCode::ACCEPTED,
+            Json(SuccessResponse {
+                success: true,
+                data: serde_json::json!({ "scheduled": true, "days": days }),
+            }),
+        )
+            .into_response();
+    };
+
+    match timeline::backfill_user(proxy.pool(), &user_id, days).await {
+        Ok(entries) => Json(SuccessResponse {
+            success: true,
+            data: serde_json::json!({ "userId": user_id, "days": days, "entries": entries }),
+        })
+        .into_response(),
+        Err(TimelineError::UserNotFound) => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "用户不存在").into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, user_id = %user_id, "Timeline backfill failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "BACKFILL_FAILED",
+                format!("时间线回填失败: {}", e),
+            )
+            .into_response()
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::progress_report::{self, ReportError, ReportJob, ReportRequest};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs).post(create_job))
+        .route("/:id", get(get_job))
+        .route("/:id/download", get(download))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobView {
+    #[serde(flatten)]
+    job: ReportJob,
+    /// Set once the file is ready.
+    download_url: Option<String>,
+}
+
+impl From<ReportJob> for JobView {
+    fn from(job: ReportJob) -> Self {
+        let download_url =
+            (job.status == "completed").then(|| format!("/api/admin/reports/{}/download", job.id));
+        Self { job, download_url }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    limit: Option<i64>,
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn report_error(err: ReportError) -> Response {
+    match err {
+        ReportError::Invalid(msg) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_REPORT", msg).into_response()
+        }
+        ReportError::Sql(e) => {
+            tracing::warn!(error = %e, "progress report query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "REPORT_FAILED",
+                "报告任务操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn create_job(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<ReportRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match progress_report::create_job(proxy, &admin.id, payload).await {
+        Ok(job) => (
+            StatusCode::ACCEPTED,
+            Json(SuccessResponse {
+                success: true,
+                data: JobView::from(job),
+            }),
+        )
+            .into_response(),
+        Err(e) => report_error(e),
+    }
+}
+
+async fn list_jobs(State(state): State<AppState>, Query(query): Query<ListQuery>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    match progress_report::list_jobs(proxy.as_ref(), limit).await {
+        Ok(jobs) => Json(SuccessResponse {
+            success: true,
+            data: jobs.into_iter().map(JobView::from).collect::<Vec<_>>(),
+        })
+        .into_response(),
+        Err(e) => report_error(e.into()),
+    }
+}
+
+async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match progress_report::get_job(proxy.as_ref(), &id).await {
+        Ok(Some(job)) => Json(SuccessResponse {
+            success: true,
+            data: JobView::from(job),
+        })
+        .into_response(),
+        Ok(None) => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "报告任务不存在").into_response()
+        }
+        Err(e) => report_error(e.into()),
+    }
+}
+
+async fn download(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match progress_report::get_file(proxy.as_ref(), &id).await {
+        Ok(Some(file)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, file.content_type),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", file.file_name),
+                ),
+            ],
+            file.content,
+        )
+            .into_response(),
+        Ok(None) => json_error(
+            StatusCode::NOT_FOUND,
+            "NOT_FOUND",
+            "报告不存在、尚未生成或已过期",
+        )
+        .into_response(),
+        Err(e) => report_error(e.into()),
+    }
+}
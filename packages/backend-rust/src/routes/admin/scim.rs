@@ -0,0 +1,136 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::scim::{self, ScimError, ScimToken};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/tokens", get(list_tokens).post(create_token))
+        .route("/tokens/:id", delete(revoke_token))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+fn ok<T: Serialize>(data: T) -> Response {
+    Json(SuccessResponse {
+        success: true,
+        data,
+    })
+    .into_response()
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn token_error(err: ScimError) -> Response {
+    match err {
+        ScimError::NotFound => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "记录不存在").into_response()
+        }
+        e => {
+            tracing::warn!(error = %e, "scim token query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SCIM_TOKEN_QUERY_FAILED",
+                "SCIM 令牌操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenQuery {
+    organization_id: Option<String>,
+}
+
+async fn list_tokens(State(state): State<AppState>, Query(query): Query<TokenQuery>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match scim::list_tokens(proxy.as_ref(), query.organization_id.as_deref()).await {
+        Ok(tokens) => ok(tokens),
+        Err(e) => token_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateTokenRequest {
+    organization_id: String,
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreatedToken {
+    #[serde(flatten)]
+    token: ScimToken,
+    /// Shown once; only its hash is stored.
+    secret: String,
+}
+
+async fn create_token(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<CreateTokenRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let organization_id = payload.organization_id.trim();
+    if organization_id.is_empty() {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_ORGANIZATION",
+            "organizationId 不能为空",
+        )
+        .into_response();
+    }
+    match scim::create_token(
+        proxy.as_ref(),
+        organization_id,
+        payload.description.as_deref(),
+        &admin.id,
+    )
+    .await
+    {
+        Ok((token, secret)) => (
+            StatusCode::CREATED,
+            Json(SuccessResponse {
+                success: true,
+                data: CreatedToken { token, secret },
+            }),
+        )
+            .into_response(),
+        Err(e) => token_error(e),
+    }
+}
+
+async fn revoke_token(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match scim::revoke_token(proxy.as_ref(), &id).await {
+        Ok(()) => ok(serde_json::json!({ "id": id })),
+        Err(e) => token_error(e),
+    }
+}
@@ -0,0 +1,107 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::simulation::{self, SimulationError, SimulationRequest};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs).post(create_job))
+        .route("/:id", get(get_job))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    limit: Option<i64>,
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn simulation_error(err: SimulationError) -> Response {
+    match err {
+        SimulationError::Invalid(msg) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_SIMULATION", msg).into_response()
+        }
+        SimulationError::Sql(e) => {
+            tracing::warn!(error = %e, "simulation query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SIMULATION_FAILED",
+                "模拟任务操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn create_job(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<SimulationRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match simulation::create_job(proxy, &admin.id, payload).await {
+        Ok(job) => (
+            StatusCode::ACCEPTED,
+            Json(SuccessResponse {
+                success: true,
+                data: job,
+            }),
+        )
+            .into_response(),
+        Err(e) => simulation_error(e),
+    }
+}
+
+async fn list_jobs(State(state): State<AppState>, Query(query): Query<ListQuery>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    match simulation::list_jobs(proxy.as_ref(), limit).await {
+        Ok(jobs) => Json(SuccessResponse {
+            success: true,
+            data: jobs,
+        })
+        .into_response(),
+        Err(e) => simulation_error(e.into()),
+    }
+}
+
+async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match simulation::get_job(proxy.as_ref(), &id).await {
+        Ok(Some(job)) => Json(SuccessResponse {
+            success: true,
+            data: job,
+        })
+        .into_response(),
+        Ok(None) => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "模拟任务不存在").into_response()
+        }
+        Err(e) => simulation_error(e.into()),
+    }
+}
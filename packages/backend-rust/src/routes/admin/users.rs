@@ -5,7 +5,9 @@ use axum::routing::{get, put};
 use axum::{Json, Router};
 use serde::{Deserialize, Serialize};
 
+use crate::core::{Pagination, SortFilter};
 use crate::response::json_error;
+use crate::services::admin::{DecisionSortBy, UserWordsSortBy};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -23,8 +25,6 @@ struct MessageResponse {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ListUsersQuery {
-    page: Option<i64>,
-    page_size: Option<i64>,
     search: Option<String>,
 }
 
@@ -43,10 +43,6 @@ struct UpdateUserRoleRequest {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct UserWordsQuery {
-    page: Option<i64>,
-    page_size: Option<i64>,
-    sort_by: Option<String>,
-    sort_order: Option<String>,
     state: Option<String>,
     search: Option<String>,
     score_range: Option<String>,
@@ -57,14 +53,10 @@ struct UserWordsQuery {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct DecisionsQuery {
-    page: Option<i64>,
-    page_size: Option<i64>,
     start_date: Option<String>,
     end_date: Option<String>,
     decision_source: Option<String>,
     min_confidence: Option<f64>,
-    sort_by: Option<String>,
-    sort_order: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,6 +89,7 @@ pub fn router() -> Router<AppState> {
         )
         .route("/:id/learning-data", get(get_learning_data))
         .route("/:id/statistics", get(get_statistics))
+        .route("/:id/activity", get(get_activity))
         .route("/:id/words", get(get_user_words))
         .route("/:id/words/export", get(export_user_words))
         .route("/:id/decisions", get(get_user_decisions))
@@ -117,6 +110,7 @@ pub fn router() -> Router<AppState> {
 
 async fn list_users(
     State(state): State<AppState>,
+    pagination: Pagination<20, 200>,
     Query(query): Query<ListUsersQuery>,
 ) -> Response {
     let Some(proxy) = state.db_proxy() else {
@@ -128,12 +122,9 @@ async fn list_users(
         .into_response();
     };
 
-    let page = query.page.unwrap_or(1).max(1);
-    let page_size = query.page_size.unwrap_or(20).clamp(1, 200);
-
     let params = crate::services::admin::ListUsersParams {
-        page,
-        page_size,
+        page: pagination.page,
+        page_size: pagination.page_size,
         search: query.search,
     };
 
@@ -338,9 +329,42 @@ async fn get_statistics(State(state): State<AppState>, Path(id): Path<String>) -
     }
 }
 
+/// Last days of the user's API usage with error breakdowns, for debugging
+/// sync problems.
+async fn get_activity(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "DATABASE_UNAVAILABLE",
+            "数据库不可用",
+        )
+        .into_response();
+    };
+
+    let pending = crate::middleware::usage::pending_for_user(&id);
+    match crate::services::api_usage::user_activity(proxy.pool(), &id, pending).await {
+        Ok(data) => Json(SuccessResponse {
+            success: true,
+            data,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, user_id = %id, "failed to load API usage");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "获取用户活动失败",
+            )
+            .into_response()
+        }
+    }
+}
+
 async fn get_user_words(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    pagination: Pagination<20, 200>,
+    sort: SortFilter<UserWordsSortBy>,
     Query(query): Query<UserWordsQuery>,
 ) -> Response {
     let Some(proxy) = state.db_proxy() else {
@@ -353,10 +377,10 @@ async fn get_user_words(
     };
 
     let params = crate::services::admin::UserWordsParams {
-        page: query.page.unwrap_or(1).max(1),
-        page_size: query.page_size.unwrap_or(20).clamp(1, 200),
-        sort_by: crate::services::admin::UserWordsSortBy::from_query(query.sort_by.as_deref()),
-        sort_order: crate::services::admin::SortOrder::from_query(query.sort_order.as_deref()),
+        page: pagination.page,
+        page_size: pagination.page_size,
+        sort_by: sort.field,
+        sort_order: sort.order,
         state: query.state,
         search: query.search,
         score_range: crate::services::admin::ScoreRange::from_query(query.score_range.as_deref()),
@@ -428,6 +452,8 @@ async fn export_user_words(
 async fn get_user_decisions(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    pagination: Pagination<20, 100>,
+    sort: SortFilter<DecisionSortBy>,
     Query(query): Query<DecisionsQuery>,
 ) -> Response {
     let Some(proxy) = state.db_proxy() else {
@@ -440,14 +466,14 @@ async fn get_user_decisions(
     };
 
     let params = crate::services::admin::UserDecisionsParams {
-        page: query.page.unwrap_or(1).max(1),
-        page_size: query.page_size.unwrap_or(20).clamp(1, 100),
+        page: pagination.page,
+        page_size: pagination.page_size,
         start_date: query.start_date,
         end_date: query.end_date,
         decision_source: query.decision_source,
         min_confidence: query.min_confidence,
-        sort_by: crate::services::admin::DecisionSortBy::from_query(query.sort_by.as_deref()),
-        sort_order: crate::services::admin::SortOrder::from_query(query.sort_order.as_deref()),
+        sort_by: sort.field,
+        sort_order: sort.order,
     };
 
     match crate::services::admin::get_user_decisions(proxy.as_ref(), &id, params).await {
@@ -0,0 +1,284 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::response::json_error;
+use crate::services::admin_auth::AdminAuthUser;
+use crate::services::webhooks::{
+    self, CreateEndpointRequest, DeliveryFilter, UpdateEndpointRequest, WebhookError,
+    WebhookEventType,
+};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_endpoints).post(create_endpoint))
+        .route("/event-types", get(list_event_types))
+        .route("/deliveries", get(list_deliveries))
+        .route("/deliveries/:id/retry", post(retry_delivery))
+        .route(
+            "/organizations/:org_id/members",
+            get(list_members).post(add_member),
+        )
+        .route(
+            "/organizations/:org_id/members/:user_id",
+            delete(remove_member),
+        )
+        .route("/:id", put(update_endpoint).delete(delete_endpoint))
+        .route("/:id/rotate-secret", post(rotate_secret))
+}
+
+#[derive(Debug, Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListResponse<T> {
+    success: bool,
+    data: Vec<T>,
+    total: i64,
+    page: i64,
+    page_size: i64,
+}
+
+fn ok<T: Serialize>(data: T) -> Response {
+    Json(SuccessResponse {
+        success: true,
+        data,
+    })
+    .into_response()
+}
+
+fn db_unavailable() -> Response {
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "数据库不可用",
+    )
+    .into_response()
+}
+
+fn webhook_error(err: WebhookError) -> Response {
+    match err {
+        WebhookError::InvalidUrl(url) => json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_URL",
+            format!("无效的回调地址: {url}"),
+        )
+        .into_response(),
+        WebhookError::ForbiddenTarget(url) => json_error(
+            StatusCode::BAD_REQUEST,
+            "FORBIDDEN_TARGET",
+            format!("回调地址必须是公网 HTTPS 地址: {url}"),
+        )
+        .into_response(),
+        WebhookError::UnknownEventType(t) => json_error(
+            StatusCode::BAD_REQUEST,
+            "UNKNOWN_EVENT_TYPE",
+            format!("未知事件类型: {t}"),
+        )
+        .into_response(),
+        WebhookError::NotFound => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "记录不存在").into_response()
+        }
+        WebhookError::Sql(e) => {
+            tracing::warn!(error = %e, "webhook query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "WEBHOOK_QUERY_FAILED",
+                "Webhook 操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn list_event_types() -> Response {
+    ok(WebhookEventType::ALL
+        .iter()
+        .map(|t| t.as_str())
+        .collect::<Vec<_>>())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EndpointQuery {
+    organization_id: Option<String>,
+}
+
+async fn list_endpoints(
+    State(state): State<AppState>,
+    Query(query): Query<EndpointQuery>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::list_endpoints(proxy.as_ref(), query.organization_id.as_deref()).await {
+        Ok(endpoints) => ok(endpoints),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn create_endpoint(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminAuthUser>,
+    Json(payload): Json<CreateEndpointRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    if payload.organization_id.trim().is_empty() {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_ORGANIZATION",
+            "organizationId 不能为空",
+        )
+        .into_response();
+    }
+    match webhooks::create_endpoint(proxy.as_ref(), &admin.id, payload).await {
+        Ok(endpoint) => (
+            StatusCode::CREATED,
+            Json(SuccessResponse {
+                success: true,
+                data: endpoint,
+            }),
+        )
+            .into_response(),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn update_endpoint(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateEndpointRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::update_endpoint(proxy.as_ref(), &id, payload).await {
+        Ok(endpoint) => ok(endpoint),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn delete_endpoint(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::delete_endpoint(proxy.as_ref(), &id).await {
+        Ok(()) => ok(serde_json::json!({ "deleted": true })),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn rotate_secret(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::rotate_secret(proxy.as_ref(), &id).await {
+        Ok(endpoint) => ok(endpoint),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn list_members(State(state): State<AppState>, Path(org_id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::list_members(proxy.as_ref(), &org_id).await {
+        Ok(members) => ok(members),
+        Err(e) => webhook_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddMemberRequest {
+    user_id: String,
+}
+
+async fn add_member(
+    State(state): State<AppState>,
+    Path(org_id): Path<String>,
+    Json(payload): Json<AddMemberRequest>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::add_member(proxy.as_ref(), &org_id, &payload.user_id).await {
+        Ok(()) => ok(serde_json::json!({ "added": true })),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn remove_member(
+    State(state): State<AppState>,
+    Path((org_id, user_id)): Path<(String, String)>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::remove_member(proxy.as_ref(), &org_id, &user_id).await {
+        Ok(()) => ok(serde_json::json!({ "removed": true })),
+        Err(e) => webhook_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveryQuery {
+    page: Option<i64>,
+    page_size: Option<i64>,
+    organization_id: Option<String>,
+    endpoint_id: Option<String>,
+    event_type: Option<String>,
+    status: Option<String>,
+}
+
+async fn list_deliveries(
+    State(state): State<AppState>,
+    Query(query): Query<DeliveryQuery>,
+) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * page_size;
+    let filter = DeliveryFilter {
+        organization_id: query.organization_id,
+        endpoint_id: query.endpoint_id,
+        event_type: query.event_type,
+        status: query.status,
+    };
+
+    match webhooks::list_deliveries(proxy.as_ref(), &filter, page_size, offset).await {
+        Ok((deliveries, total)) => Json(ListResponse {
+            success: true,
+            data: deliveries,
+            total,
+            page,
+            page_size,
+        })
+        .into_response(),
+        Err(e) => webhook_error(e),
+    }
+}
+
+async fn retry_delivery(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let Some(proxy) = state.db_proxy() else {
+        return db_unavailable();
+    };
+    match webhooks::retry_delivery(proxy.as_ref(), &id).await {
+        Ok(()) => ok(serde_json::json!({ "requeued": true })),
+        Err(e) => webhook_error(e),
+    }
+}
@@ -1,11 +1,13 @@
-use axum::extract::{Path, Query, State};
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use danci_algo::language::{is_valid_language_code, DEFAULT_GLOSS_LANGUAGE, DEFAULT_LANGUAGE};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
+use crate::core::Pagination;
 use crate::response::json_error;
 use crate::state::AppState;
 
@@ -15,13 +17,6 @@ struct SuccessResponse<T> {
     data: T,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ListQuery {
-    page: Option<i32>,
-    page_size: Option<i32>,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct WordBook {
@@ -47,6 +42,8 @@ struct WordBook {
     source_author: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     imported_at: Option<String>,
+    language_code: String,
+    gloss_language: String,
     created_at: String,
     updated_at: String,
 }
@@ -73,6 +70,8 @@ struct CreateWordBookInput {
     name: String,
     description: Option<String>,
     cover_image: Option<String>,
+    language_code: Option<String>,
+    gloss_language: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -128,22 +127,21 @@ pub fn router() -> Router<AppState> {
         .route("/{id}/words/batch", post(batch_add_words))
 }
 
-async fn list_wordbooks(State(state): State<AppState>, Query(query): Query<ListQuery>) -> Response {
+async fn list_wordbooks(
+    State(state): State<AppState>,
+    pagination: Pagination<50, 100>,
+) -> Response {
     let Some(proxy) = state.db_proxy() else {
         return json_error(StatusCode::SERVICE_UNAVAILABLE, "DB_ERROR", "数据库不可用")
             .into_response();
     };
 
-    let page = query.page.unwrap_or(1).max(1);
-    let page_size = query.page_size.unwrap_or(50).clamp(1, 100);
-    let offset = (page - 1) * page_size;
-
     let rows = sqlx::query(
-        r#"SELECT "id","name","description","type"::text,"userId","isPublic","wordCount","coverImage","tags","sourceUrl","sourceVersion","sourceAuthor","importedAt","createdAt","updatedAt"
+        r#"SELECT "id","name","description","type"::text,"userId","isPublic","wordCount","coverImage","tags","sourceUrl","sourceVersion","sourceAuthor","importedAt","languageCode","glossLanguage","createdAt","updatedAt"
            FROM "word_books" WHERE "type" = 'SYSTEM' ORDER BY "createdAt" DESC LIMIT $1 OFFSET $2"#,
     )
-    .bind(page_size)
-    .bind(offset)
+    .bind(pagination.limit())
+    .bind(pagination.offset)
     .fetch_all(proxy.pool())
     .await;
 
@@ -189,6 +187,23 @@ async fn create_wordbook(
         .into_response();
     }
 
+    let language_code = input
+        .language_code
+        .clone()
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let gloss_language = input
+        .gloss_language
+        .clone()
+        .unwrap_or_else(|| DEFAULT_GLOSS_LANGUAGE.to_string());
+    if !is_valid_language_code(&language_code) || !is_valid_language_code(&gloss_language) {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            "语言代码须为2-3位小写字母",
+        )
+        .into_response();
+    }
+
     let Some(proxy) = state.db_proxy() else {
         return json_error(StatusCode::SERVICE_UNAVAILABLE, "DB_ERROR", "数据库不可用")
             .into_response();
@@ -198,13 +213,15 @@ async fn create_wordbook(
     let now_str = now.to_rfc3339();
 
     let result = sqlx::query(
-        r#"INSERT INTO "word_books" ("id","name","description","type","isPublic","wordCount","coverImage","createdAt","updatedAt")
-           VALUES ($1,$2,$3,'SYSTEM'::"WordBookType",true,0,$4,NOW(),NOW())"#,
+        r#"INSERT INTO "word_books" ("id","name","description","type","isPublic","wordCount","coverImage","languageCode","glossLanguage","createdAt","updatedAt")
+           VALUES ($1,$2,$3,'SYSTEM'::"WordBookType",true,0,$4,$5,$6,NOW(),NOW())"#,
     )
     .bind(&id)
     .bind(input.name.trim())
     .bind(input.description.as_ref().map(|d| d.trim()))
     .bind(input.cover_image.as_ref().map(|c| c.trim()))
+    .bind(&language_code)
+    .bind(&gloss_language)
     .execute(proxy.pool())
     .await;
 
@@ -231,6 +248,8 @@ async fn create_wordbook(
         source_version: None,
         source_author: None,
         imported_at: None,
+        language_code,
+        gloss_language,
         created_at: now_str.clone(),
         updated_at: now_str,
     };
@@ -252,7 +271,7 @@ async fn get_wordbook(State(state): State<AppState>, Path(id): Path<String>) ->
     };
 
     let wb_row = sqlx::query(
-        r#"SELECT "id","name","description","type"::text,"userId","isPublic","wordCount","coverImage","tags","sourceUrl","sourceVersion","sourceAuthor","importedAt","createdAt","updatedAt"
+        r#"SELECT "id","name","description","type"::text,"userId","isPublic","wordCount","coverImage","tags","sourceUrl","sourceVersion","sourceAuthor","importedAt","languageCode","glossLanguage","createdAt","updatedAt"
            FROM "word_books" WHERE "id" = $1"#,
     )
     .bind(&id)
@@ -608,6 +627,12 @@ fn parse_wordbook_pg(row: &sqlx::postgres::PgRow) -> WordBook {
         imported_at: imported_at.map(|t| {
             chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(t, chrono::Utc).to_rfc3339()
         }),
+        language_code: row
+            .try_get("languageCode")
+            .unwrap_or_else(|_| DEFAULT_LANGUAGE.to_string()),
+        gloss_language: row
+            .try_get("glossLanguage")
+            .unwrap_or_else(|_| DEFAULT_GLOSS_LANGUAGE.to_string()),
         created_at: chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
             created_at,
             chrono::Utc,
@@ -6,11 +6,14 @@ use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use chrono::{NaiveDateTime, SecondsFormat, Utc};
+use danci_algo::active_learning::{SelectionMode, SessionComposerConfig};
+use danci_algo::SessionObjective;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
 
-use crate::response::json_error;
+use crate::response::{json_error, AppError};
+use crate::services::policy_rules::{self, PolicyRulesError};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -62,6 +65,10 @@ struct AlgorithmConfigDto {
     new_word_ratio_high_accuracy_threshold: f64,
     new_word_ratio_low_accuracy_threshold: f64,
     mastery_thresholds: serde_json::Value,
+    /// Weights and trade-off of the session composer's scoring.
+    session_objective: serde_json::Value,
+    /// Word selection mode of new users' first sessions.
+    session_selection: serde_json::Value,
     is_default: bool,
     created_at: String,
     updated_at: String,
@@ -90,6 +97,16 @@ struct UpdateConfigRequest {
     change_reason: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct PolicyRulesRequest {
+    rules: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct PolicyRulesDto {
+    rules: serde_json::Value,
+}
+
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 struct ResetConfigRequest {
@@ -570,6 +587,131 @@ pub async fn presets(State(state): State<AppState>, req: Request<Body>) -> Respo
     }
 }
 
+pub async fn get_policy_rules(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (proxy, user) = match require_user(&state, req.headers()).await {
+        Ok(value) => value,
+        Err(err) => return err.into_response(),
+    };
+
+    match policy_rules::get_user_policy_rules(proxy.as_ref(), &user.id).await {
+        Ok(set) => Json(SuccessResponse {
+            success: true,
+            data: PolicyRulesDto {
+                rules: serde_json::to_value(&set.rules).unwrap_or_default(),
+            },
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "policy rules lookup failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn update_policy_rules(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (parts, body_bytes) = match split_body(req).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    let (proxy, user) = match require_user(&state, &parts.headers).await {
+        Ok(value) => value,
+        Err(err) => return err.into_response(),
+    };
+    let Ok(payload) = serde_json::from_slice::<PolicyRulesRequest>(&body_bytes) else {
+        return json_error(StatusCode::BAD_REQUEST, "BAD_REQUEST", "无效请求").into_response();
+    };
+
+    match policy_rules::save_user_policy_rules(proxy.as_ref(), &user.id, &payload.rules).await {
+        Ok(set) => Json(SuccessResponse {
+            success: true,
+            data: PolicyRulesDto {
+                rules: serde_json::to_value(&set.rules).unwrap_or_default(),
+            },
+        })
+        .into_response(),
+        Err(PolicyRulesError::Invalid(validation)) => (
+            StatusCode::BAD_REQUEST,
+            Json(ConfigValidationResponse {
+                success: false,
+                message: "策略规则校验失败",
+                errors: validation
+                    .errors
+                    .into_iter()
+                    .chain(validation.conflicts)
+                    .collect(),
+            }),
+        )
+            .into_response(),
+        Err(PolicyRulesError::Sql(err)) => {
+            tracing::warn!(error = %err, "policy rules update failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn validate_policy_rules(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (parts, body_bytes) = match split_body(req).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    if let Err(err) = require_user(&state, &parts.headers).await {
+        return err.into_response();
+    }
+    let Ok(payload) = serde_json::from_slice::<PolicyRulesRequest>(&body_bytes) else {
+        return json_error(StatusCode::BAD_REQUEST, "BAD_REQUEST", "无效请求").into_response();
+    };
+
+    Json(SuccessResponse {
+        success: true,
+        data: policy_rules::validate_rules(&payload.rules),
+    })
+    .into_response()
+}
+
+async fn require_user(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<
+    (
+        std::sync::Arc<crate::db::DatabaseProxy>,
+        crate::auth::AuthUser,
+    ),
+    AppError,
+> {
+    let token = crate::auth::extract_token(headers)
+        .ok_or_else(|| json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌"))?;
+
+    let proxy = state.db_proxy().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+    })?;
+
+    let user = crate::auth::verify_request_token(&proxy, &token)
+        .await
+        .map_err(|_| {
+            json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+        })?;
+
+    Ok((proxy, user))
+}
+
 async fn apply_config_update(
     proxy: &crate::db::DatabaseProxy,
     config_id: &str,
@@ -713,6 +855,14 @@ async fn apply_reset_to_default(
         "masteryThresholds".to_string(),
         default_config.mastery_thresholds.clone(),
     );
+    update.insert(
+        "sessionObjective".to_string(),
+        default_config.session_objective.clone(),
+    );
+    update.insert(
+        "sessionSelection".to_string(),
+        default_config.session_selection.clone(),
+    );
 
     let mut data = filter_update_fields(&update);
     data.insert(
@@ -859,6 +1009,8 @@ async fn update_postgres_config(
     );
     let mastery_thresholds =
         merge_optional(input.mastery_thresholds, &old_config.mastery_thresholds);
+    let session_objective = merge_optional(input.session_objective, &old_config.session_objective);
+    let session_selection = merge_optional(input.session_selection, &old_config.session_selection);
     let updated_at = Utc::now().naive_utc();
 
     let sql = format!(
@@ -889,8 +1041,10 @@ async fn update_postgres_config(
             "newWordRatioHighAccuracyThreshold" = $22,
             "newWordRatioLowAccuracyThreshold" = $23,
             "masteryThresholds" = $24,
-            "updatedAt" = $25
-        WHERE "id" = $26
+            "sessionObjective" = $25,
+            "sessionSelection" = $26,
+            "updatedAt" = $27
+        WHERE "id" = $28
         RETURNING {}
         "#,
         config_select_sql()
@@ -921,6 +1075,8 @@ async fn update_postgres_config(
         .bind(new_word_ratio_high_accuracy_threshold)
         .bind(new_word_ratio_low_accuracy_threshold)
         .bind(mastery_thresholds)
+        .bind(session_objective)
+        .bind(session_selection)
         .bind(updated_at)
         .bind(config_id)
         .fetch_one(pool)
@@ -955,6 +1111,8 @@ struct AlgorithmConfigUpdateInput {
     new_word_ratio_high_accuracy_threshold: Option<f64>,
     new_word_ratio_low_accuracy_threshold: Option<f64>,
     mastery_thresholds: Option<serde_json::Value>,
+    session_objective: Option<serde_json::Value>,
+    session_selection: Option<serde_json::Value>,
     is_default: Option<bool>,
     created_by: Option<Option<String>>,
 }
@@ -1076,6 +1234,8 @@ fn config_select_sql() -> &'static str {
       "newWordRatioHighAccuracyThreshold",
       "newWordRatioLowAccuracyThreshold",
       "masteryThresholds",
+      "sessionObjective",
+      "sessionSelection",
       "isDefault",
       "createdAt",
       "updatedAt",
@@ -1123,6 +1283,12 @@ fn map_postgres_config_row(row: &sqlx::postgres::PgRow) -> AlgorithmConfigDto {
         mastery_thresholds: row
             .try_get("masteryThresholds")
             .unwrap_or(serde_json::Value::Null),
+        session_objective: row
+            .try_get("sessionObjective")
+            .unwrap_or_else(|_| serde_json::json!({})),
+        session_selection: row
+            .try_get("sessionSelection")
+            .unwrap_or_else(|_| serde_json::json!({"mode": "priority"})),
         is_default: row.try_get("isDefault").unwrap_or(false),
         created_at: format_naive_datetime_iso_millis(created_at),
         updated_at: format_naive_datetime_iso_millis(updated_at),
@@ -1189,6 +1355,27 @@ fn validate_config(config: &serde_json::Map<String, serde_json::Value>) -> Vec<S
         }
     }
 
+    if let Some(value) = config.get("sessionObjective") {
+        match serde_json::from_value::<SessionObjective>(value.clone()) {
+            Ok(objective) if value.is_object() => errors.extend(objective.validate()),
+            _ => errors.push("会话目标配置必须是对象".to_string()),
+        }
+    }
+
+    if let Some(value) = config.get("sessionSelection") {
+        match serde_json::from_value::<SelectionMode>(value.clone()) {
+            Ok(selection) => errors.extend(
+                SessionComposerConfig {
+                    selection,
+                    rules: Vec::new(),
+                }
+                .validate()
+                .errors,
+            ),
+            Err(_) => errors.push("会话选词方式配置无效".to_string()),
+        }
+    }
+
     errors
 }
 
@@ -10,9 +10,11 @@ use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 
+use crate::amas::decision::{IgeBatchUpdateItem, IgeGcConfig};
+use crate::amas::divergence::DivergenceThresholds;
 use crate::amas::memory::{MdmState, MemoryEngine};
 use crate::amas::types::{
-    ColdStartPhase, MicroInteractions, ProcessOptions, RawEvent,
+    ColdStartPhase, MicroInteractions, PersistedAMASState, ProcessOptions, RawEvent,
     StrategyParams as AmasStrategyParams,
 };
 use crate::amas::vocabulary::{ConfusionPair, ContextEntry, MorphemeState};
@@ -21,8 +23,16 @@ use crate::db::operations::{
 };
 use crate::response::{json_error, AppError};
 use crate::routes::realtime::send_event;
-use crate::services::delayed_reward::{enqueue_delayed_reward, EnqueueRewardInput};
-use crate::services::learning_state::{upsert_word_state, WordState, WordStateUpdateData};
+use crate::services::book_dedup::record_answer_across_books;
+use crate::services::delayed_reward::{
+    enqueue_delayed_reward, enqueue_retention_attribution, EnqueueRewardInput,
+};
+use crate::services::difficulty_calibration;
+use crate::services::exploration_budget;
+use crate::services::frequency_bootstrap;
+use crate::services::language_params;
+use crate::services::learning_state::{WordState, WordStateUpdateData};
+use crate::services::model_sync;
 use crate::services::record::{create_record, CreateRecordInput};
 use crate::services::state_history::{save_state_snapshot, UserStateSnapshot};
 use crate::state::AppState;
@@ -104,6 +114,10 @@ struct ProcessEventRequest {
     #[serde(default)]
     is_guess: Option<bool>,
     micro_interaction: Option<MicroInteractions>,
+    /// Partial credit in [0, 1], for question types the client scores itself.
+    correctness: Option<f64>,
+    /// Cloze blanks to score server-side; takes precedence over `correctness`.
+    cloze: Option<Vec<danci_algo::ClozeBlank>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -284,6 +298,12 @@ struct BatchProcessResponse {
     final_strategy: StrategyResponse,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IgeBatchUpdateRequest {
+    items: Vec<IgeBatchUpdateItem>,
+}
+
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/process", post(process_event))
@@ -291,6 +311,10 @@ pub fn router() -> Router<AppState> {
         .route("/state", get(get_state))
         .route("/strategy", get(get_strategy))
         .route("/batch-process", post(batch_process))
+        .route("/ige/batch-update", post(ige_batch_update))
+        .route("/ige/gc", post(ige_gc))
+        .route("/sync", post(sync_device_model))
+        .route("/sync/reports", get(get_sync_reports))
         .route("/delayed-rewards", get(get_delayed_rewards))
         .route("/time-preferences", get(get_time_preferences))
         .route("/golden-time", get(get_golden_time))
@@ -334,6 +358,10 @@ async fn process_event(
 
     let word_id = body.word_id.clone();
     let response_time = body.response_time.max(0);
+    let correctness = match body.cloze.clone() {
+        Some(blanks) if !blanks.is_empty() => Some(danci_algo::score_cloze(blanks, None).credit),
+        _ => body.correctness,
+    };
     let switch_count = body.switch_count.unwrap_or(0).max(0);
 
     // Load existing word state for FSRS calculation
@@ -381,6 +409,31 @@ async fn process_event(
     let confusion_pairs = load_confusion_pairs_for_word(proxy.pool(), &word_id).await;
     let recent_word_ids = load_recent_word_ids(proxy.pool(), &user.id, &session_id, 20).await;
     let context_history = load_context_history(proxy.pool(), &user.id, &word_id, 50).await;
+    let language_params = language_params::params_for_word(proxy.pool(), &word_id)
+        .await
+        .ok();
+    let calibrated_beta = match difficulty_calibration::calibrated_beta(proxy.pool(), &word_id)
+        .await
+        .ok()
+        .flatten()
+    {
+        Some(beta) => Some(beta),
+        None => frequency_bootstrap::bootstrap_beta(proxy.pool(), &word_id)
+            .await
+            .ok()
+            .flatten(),
+    };
+
+    let exploration_budget = exploration_budget::for_session(
+        proxy.pool(),
+        &user.id,
+        (!session_id.is_empty()).then_some(session_id.as_str()),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "exploration budget unavailable; exploring unscaled");
+        None
+    });
 
     let raw_event = RawEvent {
         word_id: Some(body.word_id),
@@ -397,6 +450,7 @@ async fn process_event(
         is_quit: body.is_quit,
         is_guess: body.is_guess.unwrap_or(false),
         timestamp: chrono::Utc::now().timestamp_millis(),
+        correctness,
         ..Default::default()
     };
 
@@ -433,6 +487,9 @@ async fn process_event(
         } else {
             Some(context_history.clone())
         },
+        language_params,
+        calibrated_beta,
+        exploration_budget,
         ..Default::default()
     };
 
@@ -566,7 +623,9 @@ async fn process_event(
             air_beta: mastery.air_beta,
         };
 
-        if let Err(e) = upsert_word_state(&proxy, &user.id, &mastery.word_id, update_data).await {
+        if let Err(e) =
+            record_answer_across_books(&proxy, &user.id, &mastery.word_id, update_data).await
+        {
             tracing::warn!(error = %e, "Failed to update word learning state");
         }
     }
@@ -587,6 +646,7 @@ async fn process_event(
         selected_option: None,
         selected_answer: None,
         correct_answer: None,
+        question_type: None,
         is_correct: body.is_correct,
         timestamp_ms: Some(chrono::Utc::now().timestamp_millis()),
         response_time: Some(body.response_time),
@@ -777,6 +837,18 @@ async fn process_event(
             )
             .await;
 
+            if let Some(ref attribution) = result.reward_attribution {
+                let session = (!session_id.is_empty()).then(|| session_id.clone());
+                let _ = enqueue_retention_attribution(
+                    &proxy,
+                    &user.id,
+                    &record.id,
+                    session,
+                    attribution,
+                )
+                .await;
+            }
+
             // Update learning session statistics (only on successful record creation)
             // Use transaction to ensure atomic updates
             if !session_id.is_empty() {
@@ -980,6 +1052,11 @@ async fn process_event(
         is_simulation: false,
         emotion_label: None,
         flow_score: None,
+        is_forced_exploration: result.forced_exploration.is_some(),
+        exploration_scale: result.exploration.as_ref().map(|e| e.scale),
+        intended_exploration_share: result.exploration.as_ref().map(|e| e.intended_share),
+        propensity: result.exploration.as_ref().and_then(|e| e.propensity),
+        algorithm: None,
     };
     if let Err(e) = insert_decision_record(&proxy, &decision_record).await {
         tracing::warn!(error = %e, "Failed to insert decision record");
@@ -1096,6 +1173,12 @@ async fn batch_process(
     let mut final_state = None;
     let mut final_strategy = None;
 
+    let word_ids: Vec<String> = body.events.iter().map(|e| e.word_id.clone()).collect();
+    let language_pairs = language_params::word_language_pairs(proxy.pool(), &word_ids)
+        .await
+        .unwrap_or_default();
+    let registry = language_params::registry(proxy.pool()).await.ok();
+
     for event in &body.events {
         let word_id = event.word_id.clone();
         let raw_event = RawEvent {
@@ -1108,6 +1191,10 @@ async fn batch_process(
 
         let options = ProcessOptions {
             skip_update: Some(false),
+            language_params: registry.as_ref().map(|r| {
+                let pair = language_pairs.get(&word_id).cloned().unwrap_or_default();
+                r.resolve(&pair).clone()
+            }),
             ..Default::default()
         };
 
@@ -1152,7 +1239,8 @@ async fn batch_process(
                     };
 
                     if let Err(e) =
-                        upsert_word_state(&proxy, &user.id, &mastery.word_id, update_data).await
+                        record_answer_across_books(&proxy, &user.id, &mastery.word_id, update_data)
+                            .await
                     {
                         tracing::warn!(error = %e, "Failed to update word learning state in batch");
                     }
@@ -1168,6 +1256,7 @@ async fn batch_process(
                     selected_option: None,
                     selected_answer: None,
                     correct_answer: None,
+                    question_type: None,
                     is_correct: event.is_correct,
                     timestamp_ms: Some(event.timestamp),
                     response_time: Some(event.response_time),
@@ -1208,6 +1297,17 @@ async fn batch_process(
                             },
                         )
                         .await;
+
+                        if let Some(ref attribution) = result.reward_attribution {
+                            let _ = enqueue_retention_attribution(
+                                &proxy,
+                                &user.id,
+                                &record.id,
+                                None,
+                                attribution,
+                            )
+                            .await;
+                        }
                     }
                     Err(e) => {
                         tracing::warn!(error = %e, "Failed to create answer record in batch");
@@ -1236,6 +1336,148 @@ async fn batch_process(
     }))
 }
 
+async fn ige_batch_update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<IgeBatchUpdateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (_, user) = require_user(&state, &headers).await?;
+
+    if body.items.is_empty() {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "更新数组不能为空",
+        ));
+    }
+    if body.items.len() > 100 {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "单次批量更新最多100条",
+        ));
+    }
+
+    let results = state
+        .amas_engine()
+        .batch_update_ige(&user.id, &body.items)
+        .await;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: results,
+    }))
+}
+
+async fn ige_gc(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Option<Json<IgeGcConfig>>,
+) -> Result<impl IntoResponse, AppError> {
+    let (_, user) = require_user(&state, &headers).await?;
+    let config = body.map(|Json(c)| c).unwrap_or_default();
+    if config.max_age_days < 1 || config.max_trials < 0.0 || config.mean_tolerance < 0.0 {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "maxAgeDays 至少为1，maxTrials 和 meanTolerance 不能为负",
+        ));
+    }
+
+    let report = state.amas_engine().gc_ige(&user.id, &config).await;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: report,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceSyncRequest {
+    device_id: String,
+    state: PersistedAMASState,
+    #[serde(default)]
+    thresholds: Option<DivergenceThresholds>,
+}
+
+async fn sync_device_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<DeviceSyncRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let device_id = body.device_id.trim();
+    if device_id.is_empty() || device_id.len() > 128 {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "deviceId 不能为空且不超过128个字符",
+        ));
+    }
+    let thresholds = body.thresholds.unwrap_or_default();
+    if thresholds.ige_kl < 0.0
+        || thresholds.swd_distance < 0.0
+        || thresholds.ability_delta < 0.0
+        || thresholds.cognitive_delta < 0.0
+        || thresholds.interaction_gap < 0
+    {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "分歧阈值不能为负",
+        ));
+    }
+
+    let (merged, report) = state
+        .amas_engine()
+        .sync_device_state(&user.id, device_id, &body.state, &thresholds)
+        .await;
+    if let Err(e) = model_sync::save_report(proxy.pool(), &user.id, &report).await {
+        tracing::warn!(error = %e, user_id = %user.id, "Failed to store sync report");
+    }
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: serde_json::json!({
+            "report": report,
+            "state": merged,
+        }),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncReportsQuery {
+    device_id: Option<String>,
+    limit: Option<i64>,
+}
+
+async fn get_sync_reports(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SyncReportsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let reports =
+        model_sync::list_reports(proxy.pool(), &user.id, query.device_id.as_deref(), limit)
+            .await
+            .map_err(|e| {
+                json_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "QUERY_FAILED",
+                    e.to_string(),
+                )
+            })?;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: reports,
+    }))
+}
+
 async fn get_delayed_rewards(
     State(state): State<AppState>,
     headers: HeaderMap,
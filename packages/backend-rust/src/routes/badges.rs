@@ -4,7 +4,7 @@ use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::{get, post};
 use axum::{Json, Router};
-use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
@@ -737,29 +737,16 @@ async fn compute_consecutive_days(
         return Ok(0);
     }
 
-    let mut unique: HashSet<NaiveDate> = HashSet::new();
-    for d in dates {
-        unique.insert(d);
-    }
-
-    let mut sorted: Vec<NaiveDate> = unique.into_iter().collect();
-    sorted.sort_by(|a, b| b.cmp(a));
-
-    let today = Utc::now().date_naive();
-    if sorted.first().copied() != Some(today) {
-        return Ok(0);
-    }
-
-    let mut consecutive = 1i64;
-    for (i, &date) in sorted.iter().enumerate().skip(1) {
-        let expected = today - Duration::days(i as i64);
-        if date == expected {
-            consecutive += 1;
-        } else {
-            break;
-        }
-    }
-    Ok(consecutive)
+    let unique: HashSet<NaiveDate> = dates.into_iter().collect();
+    let vacations = crate::services::vacation::list_periods(pool, user_id)
+        .await
+        .unwrap_or_default();
+    Ok(crate::services::vacation::consecutive_days(
+        &unique,
+        &vacations,
+        Utc::now().date_naive(),
+        true,
+    ))
 }
 
 fn parse_date_only(raw: &str) -> Option<NaiveDate> {
@@ -454,6 +454,7 @@ fn debug_flags_to_feature_flags(flags: &Map<String, Value>) -> FeatureFlags {
             .and_then(|v| v.as_u64())
             .map(|v| v as u8)
             .unwrap_or(10),
+        amas_delayed_reward_enabled: debug_flag_bool(flags, "enableAmasDelayedReward", false),
     }
 }
 
@@ -7,6 +7,7 @@ use axum::response::IntoResponse;
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use chrono::Utc;
+use danci_algo::experiments::{select_variant_index, ExperimentDefinition, ExperimentVariant};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
@@ -121,6 +122,35 @@ struct UserActiveExperimentDto {
     parameters: serde_json::Value,
 }
 
+/// Upper bound on exposures accepted per report.
+const MAX_EXPOSURES_PER_REPORT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExposureItem {
+    experiment_id: String,
+    variant_id: String,
+    /// Unix ms of the first exposure on the device.
+    exposed_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExposureReportBody {
+    exposures: Vec<ExposureItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExposureResultDto {
+    experiment_id: String,
+    /// Variant the server holds for the caller; `None` when the experiment is
+    /// gone or not running.
+    variant_id: Option<String>,
+    /// Whether the device computed the same variant.
+    consistent: bool,
+}
+
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/causal/observe", post(observe))
@@ -131,6 +161,8 @@ pub fn router() -> Router<AppState> {
         .route("/variant/:experimentId/metric", post(record_variant_metric))
         .route("/active-experiments", get(get_active_experiments))
         .route("/user-experiments", get(get_user_experiments))
+        .route("/experiments/sync", get(sync_experiments))
+        .route("/exposures", post(report_exposures))
 }
 
 fn env_bool(key: &str) -> Option<bool> {
@@ -448,6 +480,79 @@ async fn get_user_experiments(
     }))
 }
 
+/// Running experiments with the caller's existing assignments, so devices can
+/// assign variants offline with the same bucketing.
+async fn sync_experiments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+
+    let primary = proxy.primary_pool().await;
+    let Some(pool) = primary else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "DATABASE_UNAVAILABLE",
+            "数据库不可用",
+        ));
+    };
+    let items = list_experiment_definitions_pg(&pool, &user.id).await?;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: items,
+    }))
+}
+
+/// Records assignments a device made offline. The first assignment wins, so a
+/// server-side assignment made in the meantime is kept and reported back.
+async fn report_exposures(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ExposureReportBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+
+    if payload.exposures.len() > MAX_EXPOSURES_PER_REPORT {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            format!("exposures 数组长度不能超过 {MAX_EXPOSURES_PER_REPORT}"),
+        ));
+    }
+
+    let primary = proxy.primary_pool().await;
+    let Some(pool) = primary else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "DATABASE_UNAVAILABLE",
+            "数据库不可用",
+        ));
+    };
+
+    let mut results = Vec::with_capacity(payload.exposures.len());
+    for exposure in payload.exposures {
+        let experiment_id = exposure.experiment_id.trim();
+        if experiment_id.is_empty() {
+            continue;
+        }
+        record_exposure_pg(&pool, &user.id, experiment_id, &exposure).await;
+        let held = get_user_variant_pg(&pool, &user.id, experiment_id, true)
+            .await?
+            .map(|v| v.variant_id);
+        results.push(ExposureResultDto {
+            experiment_id: experiment_id.to_string(),
+            consistent: held.as_deref() == Some(exposure.variant_id.as_str()),
+            variant_id: held,
+        });
+    }
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: results,
+    }))
+}
+
 fn validate_observation(payload: &ObserveBody) -> Result<(), AppError> {
     if payload.features.len() > MAX_FEATURES_LENGTH {
         return Err(json_error(
@@ -538,6 +643,7 @@ async fn estimate_ate(
             outcome: o.outcome,
             timestamp: Some(o.timestamp_ms as f64),
             user_id: o.user_id,
+            cluster_id: None,
         })
         .collect();
 
@@ -831,7 +937,7 @@ async fn assign_variant_pg(
     }
 
     let variants = sqlx::query(
-        r#"SELECT "id","weight","name","isControl","parameters" FROM "ab_variants" WHERE "experimentId" = $1"#,
+        r#"SELECT "id","weight","name","isControl","parameters" FROM "ab_variants" WHERE "experimentId" = $1 AND "disabledAt" IS NULL ORDER BY "createdAt", "id""#,
     )
     .bind(experiment_id)
     .fetch_all(pool)
@@ -872,31 +978,9 @@ async fn assign_variant_pg(
     get_user_variant_pg(pool, user_id, experiment_id, false).await
 }
 
-fn hash_user_id(user_id: &str) -> u32 {
-    let mut hash: i32 = 0;
-    for ch in user_id.chars() {
-        hash = hash
-            .wrapping_shl(5)
-            .wrapping_sub(hash)
-            .wrapping_add(ch as i32);
-    }
-    hash as u32
-}
-
 fn select_variant_id_by_hash(user_id: &str, variants: &[(String, f64)]) -> Option<String> {
-    if variants.is_empty() {
-        return None;
-    }
-    let hash = hash_user_id(user_id) as f64;
-    let normalized = hash / 0xffffffffu32 as f64;
-    let mut cumulative = 0.0;
-    for (id, weight) in variants {
-        cumulative += *weight;
-        if normalized <= cumulative {
-            return Some(id.clone());
-        }
-    }
-    Some(variants[variants.len() - 1].0.clone())
+    let weights: Vec<f64> = variants.iter().map(|(_, weight)| *weight).collect();
+    select_variant_index(user_id, &weights).map(|idx| variants[idx].0.clone())
 }
 
 async fn list_active_experiments_pg(
@@ -925,6 +1009,99 @@ async fn list_active_experiments_pg(
         .collect())
 }
 
+async fn list_experiment_definitions_pg(
+    pool: &sqlx::PgPool,
+    user_id: &str,
+) -> Result<Vec<ExperimentDefinition>, AppError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          e."id" as "experimentId",
+          e."name" as "experimentName",
+          v."id" as "variantId",
+          v."name" as "variantName",
+          v."weight" as "weight",
+          v."isControl" as "isControl",
+          v."parameters" as "parameters",
+          a."variantId" as "assignedVariantId"
+        FROM "ab_experiments" e
+        JOIN "ab_variants" v ON v."experimentId" = e."id" AND v."disabledAt" IS NULL
+        LEFT JOIN "ab_user_assignments" a
+          ON a."experimentId" = e."id" AND a."userId" = $1
+        WHERE e."status" = 'RUNNING'
+        ORDER BY e."createdAt" ASC, e."id", v."createdAt", v."id"
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let mut items: Vec<ExperimentDefinition> = Vec::new();
+    for row in rows {
+        let experiment_id = row.try_get::<String, _>("experimentId").unwrap_or_default();
+        let variant_id = row.try_get::<String, _>("variantId").unwrap_or_default();
+        if experiment_id.is_empty() || variant_id.is_empty() {
+            continue;
+        }
+        let variant = ExperimentVariant {
+            id: variant_id,
+            name: row.try_get::<String, _>("variantName").unwrap_or_default(),
+            weight: row.try_get::<f64, _>("weight").unwrap_or(0.0).max(0.0),
+            is_control: row.try_get::<bool, _>("isControl").unwrap_or(false),
+            parameters: row
+                .try_get::<sqlx::types::Json<serde_json::Value>, _>("parameters")
+                .map(|v| v.0)
+                .unwrap_or_else(|_| serde_json::json!({})),
+        };
+        match items.last_mut() {
+            Some(last) if last.id == experiment_id => last.variants.push(variant),
+            _ => items.push(ExperimentDefinition {
+                id: experiment_id,
+                name: row
+                    .try_get::<String, _>("experimentName")
+                    .unwrap_or_default(),
+                variants: vec![variant],
+                assigned_variant_id: row
+                    .try_get::<Option<String>, _>("assignedVariantId")
+                    .unwrap_or(None),
+            }),
+        }
+    }
+    Ok(items)
+}
+
+async fn record_exposure_pg(
+    pool: &sqlx::PgPool,
+    user_id: &str,
+    experiment_id: &str,
+    exposure: &ExposureItem,
+) {
+    let exposed_at = exposure
+        .exposed_at
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .filter(|at| *at <= Utc::now())
+        .unwrap_or_else(Utc::now)
+        .naive_utc();
+    sqlx::query(
+        r#"
+        INSERT INTO "ab_user_assignments" ("userId","experimentId","variantId","assignedAt")
+        SELECT $1, e."id", v."id", $4
+        FROM "ab_experiments" e
+        JOIN "ab_variants" v ON v."experimentId" = e."id"
+        WHERE e."id" = $2 AND v."id" = $3 AND e."status" = 'RUNNING' AND v."disabledAt" IS NULL
+        ON CONFLICT ("userId","experimentId") DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(experiment_id)
+    .bind(exposure.variant_id.trim())
+    .bind(exposed_at)
+    .execute(pool)
+    .await
+    .ok();
+}
+
 async fn list_user_active_experiments_pg(
     pool: &sqlx::PgPool,
     user_id: &str,
@@ -12,6 +12,9 @@ use sqlx::Row;
 use uuid::Uuid;
 
 use crate::response::{json_error, AppError};
+use crate::services::experiment_guardrails::{
+    self, GuardrailConfig, GuardrailDecision, VariantGuardrails,
+};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -64,6 +67,9 @@ struct VariantDto {
     parameters: serde_json::Value,
     created_at: String,
     updated_at: String,
+    /// Set when the guardrail worker disabled the variant.
+    disabled_at: Option<String>,
+    disabled_reason: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -181,6 +187,14 @@ struct ExportQuery {
     format: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GuardrailsDto {
+    config: GuardrailConfig,
+    variants: Vec<VariantGuardrails>,
+    decisions: Vec<GuardrailDecision>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ExportDataDto {
@@ -201,6 +215,7 @@ pub fn router() -> Router<AppState> {
         .route("/:experimentId/stop", post(stop_experiment))
         .route("/:experimentId/metric", post(record_metric))
         .route("/:experimentId/export", get(export_experiment))
+        .route("/:experimentId/guardrails", get(get_experiment_guardrails))
         .fallback(|| async { (StatusCode::NOT_FOUND, Json(serde_json::json!({"success": false, "error": "接口不存在", "code": "NOT_FOUND"}))) })
 }
 
@@ -606,6 +621,48 @@ async fn export_experiment(
     }
 }
 
+async fn get_experiment_guardrails(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(experiment_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, _user) = require_admin_user(&state, &headers).await?;
+
+    let primary = proxy.primary_pool().await;
+    let Some(pool) = primary else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "DATABASE_UNAVAILABLE",
+            "数据库不可用",
+        ));
+    };
+
+    let experiment_id = experiment_id.trim();
+    let internal = |_| {
+        json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "INTERNAL_ERROR",
+            "服务器内部错误",
+        )
+    };
+    let config = GuardrailConfig::from_env();
+    let variants = experiment_guardrails::evaluate_experiment(&pool, experiment_id, &config)
+        .await
+        .map_err(internal)?;
+    let decisions = experiment_guardrails::list_decisions(&pool, experiment_id)
+        .await
+        .map_err(internal)?;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: GuardrailsDto {
+            config,
+            variants,
+            decisions,
+        },
+    }))
+}
+
 async fn record_metric(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -1010,7 +1067,8 @@ async fn fetch_experiment_pg(
     let variants_rows = sqlx::query(
         r#"
         SELECT
-          "id","experimentId","name","weight","isControl","parameters","createdAt","updatedAt"
+          "id","experimentId","name","weight","isControl","parameters","createdAt","updatedAt",
+          "disabledAt","disabledReason"
         FROM "ab_variants"
         WHERE "experimentId" = $1
         ORDER BY "createdAt" ASC
@@ -1092,6 +1150,17 @@ async fn fetch_experiment_pg(
                         .to_rfc3339_opts(SecondsFormat::Millis, true)
                 })
                 .unwrap_or_else(|_| Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+            disabled_at: row
+                .try_get::<Option<NaiveDateTime>, _>("disabledAt")
+                .ok()
+                .flatten()
+                .map(|dt| {
+                    DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)
+                        .to_rfc3339_opts(SecondsFormat::Millis, true)
+                }),
+            disabled_reason: row
+                .try_get::<Option<String>, _>("disabledReason")
+                .unwrap_or(None),
         })
         .collect();
 
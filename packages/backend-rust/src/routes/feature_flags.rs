@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use danci_algo::FlagSet;
+use serde::{Deserialize, Serialize};
+
+use crate::response::{json_error, AppError};
+use crate::services::feature_flags::{current_flag_set, DEVICE_REFRESH_SECS};
+use crate::state::AppState;
+
+#[derive(Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncQuery {
+    /// Version the device already has; definitions are omitted when unchanged.
+    since: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FlagSyncData {
+    version: i64,
+    changed: bool,
+    refresh_after_secs: u64,
+    /// Evaluated values for the caller.
+    values: BTreeMap<String, bool>,
+    /// Definitions scoped to the caller, for offline evaluation on the device.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    definitions: Option<FlagSet>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/sync", get(sync_flags))
+}
+
+async fn sync_flags(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SyncQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (_proxy, user) = require_user(&state, &headers).await?;
+    let flags = current_flag_set(&state).await;
+    let changed = query.since != Some(flags.version);
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: FlagSyncData {
+            version: flags.version,
+            changed,
+            refresh_after_secs: DEVICE_REFRESH_SECS,
+            values: flags.evaluate_all(Some(&user.id)),
+            definitions: changed.then(|| flags.for_user(&user.id)),
+        },
+    }))
+}
+
+async fn require_user(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<
+    (
+        std::sync::Arc<crate::db::DatabaseProxy>,
+        crate::auth::AuthUser,
+    ),
+    AppError,
+> {
+    let token = crate::auth::extract_token(headers)
+        .ok_or_else(|| json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌"))?;
+
+    let proxy = state.db_proxy().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+    })?;
+
+    let user = crate::auth::verify_request_token(proxy.as_ref(), &token)
+        .await
+        .map_err(|_| {
+            json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+        })?;
+
+    Ok((proxy, user))
+}
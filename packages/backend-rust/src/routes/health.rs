@@ -1,12 +1,16 @@
 use axum::body::Body;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::header;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::{Json, Router};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::middleware::maintenance::{self, MaintenanceStatus};
+use crate::response::json_error;
+use crate::services::admin_auth;
+use crate::services::health_check::{self, CheckStatus};
 use crate::state::AppState;
 
 pub fn router() -> Router<AppState> {
@@ -20,7 +24,52 @@ pub fn router() -> Router<AppState> {
         .route("/database", get(database))
 }
 
-async fn root(State(state): State<AppState>) -> Response {
+#[derive(Debug, Default, Deserialize)]
+struct HealthQuery {
+    deep: Option<String>,
+}
+
+fn extract_admin_token(headers: &HeaderMap) -> Option<String> {
+    let auth_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())?;
+    auth_header.strip_prefix("Bearer ").map(|s| s.to_string())
+}
+
+/// The deep report lists every dependency and its latency, so it is only
+/// served to admins.
+async fn is_admin(state: &AppState, headers: &HeaderMap) -> bool {
+    let (Some(token), Some(proxy)) = (extract_admin_token(headers), state.db_proxy()) else {
+        return false;
+    };
+    admin_auth::verify_admin_token(proxy.as_ref(), &token)
+        .await
+        .is_ok()
+}
+
+async fn root(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HealthQuery>,
+) -> Response {
+    if matches!(query.deep.as_deref(), Some("true" | "1")) {
+        if !is_admin(&state, &headers).await {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "深度健康检查需要管理员令牌",
+            )
+            .into_response();
+        }
+        let report = health_check::cached(&state).await;
+        let status_code = if report.status == CheckStatus::Fail {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        };
+        return (status_code, Json(report)).into_response();
+    }
+
     let db_status = database_check(&state).await;
     let ok = matches!(db_status, DbCheckStatus::Connected { .. });
 
@@ -28,6 +77,7 @@ async fn root(State(state): State<AppState>) -> Response {
         database: if ok { "connected" } else { "disconnected" },
         timestamp: now_iso(),
         status: if ok { "ok" } else { "degraded" },
+        maintenance: maintenance::status(),
     };
 
     let status_code = if ok {
@@ -131,6 +181,7 @@ async fn ready(State(state): State<AppState>) -> Response {
             memory_usage: Some(memory_usage.heap_used),
             memory_limit: Some(memory_limit),
         }),
+        maintenance: maintenance::status(),
     };
 
     let status_code = match status {
@@ -202,6 +253,11 @@ async fn metrics(State(state): State<AppState>) -> Response {
         },
         database: MetricsDatabase {
             slow_query_total: 0,
+            pool: state.db_proxy().map(|proxy| proxy.pool_stats()),
+            replicas: state
+                .db_proxy()
+                .map(|proxy| proxy.replica_status())
+                .unwrap_or_default(),
         },
         alerts: MetricsAlerts {
             active_count: active_alerts.len() as u64,
@@ -334,6 +390,25 @@ async fn metrics_prometheus(State(state): State<AppState>) -> Response {
         None,
     );
 
+    lines.push(
+        "# HELP danci_algo_events_total Algorithm health events since process start".to_string(),
+    );
+    lines.push("# TYPE danci_algo_events_total counter".to_string());
+    for event in danci_algo::event_counts() {
+        lines.push(format!(
+            "danci_algo_events_total{{event=\"{}\",module=\"{}\",severity=\"{}\"}} {}",
+            event.event, event.module, event.severity, event.count
+        ));
+    }
+
+    if let Some(proxy) = state.db_proxy() {
+        lines.push(
+            crate::db::pool_metrics::prometheus_metrics(&proxy.pool_stats())
+                .trim_end()
+                .to_string(),
+        );
+    }
+
     let body = lines.join("\n");
     let mut response = Response::new(Body::from(body));
     response.headers_mut().insert(
@@ -356,6 +431,7 @@ async fn database(State(state): State<AppState>) -> impl IntoResponse {
             },
             fallback: None,
             hot_standby_enabled: false,
+            replicas: Vec::new(),
         });
         return (StatusCode::OK, Json(response));
     };
@@ -372,6 +448,7 @@ async fn database(State(state): State<AppState>) -> impl IntoResponse {
         },
         fallback: None,
         hot_standby_enabled: false,
+        replicas: proxy.replica_status(),
     });
 
     (StatusCode::OK, Json(response))
@@ -585,6 +662,8 @@ struct DatabaseStatusStandaloneResponse {
     fallback: Option<FallbackStatus>,
     #[serde(rename = "hotStandbyEnabled")]
     hot_standby_enabled: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    replicas: Vec<crate::db::replica::ReplicaStatus>,
 }
 
 #[derive(Serialize)]
@@ -650,6 +729,7 @@ struct CompatHealthResponse {
     database: &'static str,
     timestamp: String,
     status: &'static str,
+    maintenance: MaintenanceStatus,
 }
 
 #[derive(Serialize)]
@@ -689,6 +769,7 @@ struct ReadinessResponse {
     checks: ReadinessChecks,
     #[serde(skip_serializing_if = "Option::is_none")]
     details: Option<ReadinessDetails>,
+    maintenance: MaintenanceStatus,
 }
 
 #[derive(Serialize)]
@@ -790,6 +871,10 @@ struct MetricsHttpDuration {
 struct MetricsDatabase {
     #[serde(rename = "slowQueryTotal")]
     slow_query_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<crate::db::pool_metrics::PoolStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    replicas: Vec<crate::db::replica::ReplicaStatus>,
 }
 
 #[derive(Serialize)]
@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::response::{json_error, AppError};
+use crate::services::knowledge_graph::{self, GraphError, RelationType, MAX_DEPTH};
+use crate::state::AppState;
+
+const DEFAULT_NEIGHBOR_LIMIT: usize = 20;
+const MAX_CLUSTER_WORDS: usize = 500;
+
+#[derive(Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NeighborsQuery {
+    /// Comma-separated relation types; all types when absent.
+    types: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PathQuery {
+    from: String,
+    to: String,
+    max_depth: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClustersBody {
+    word_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EdgeBody {
+    source_word_id: String,
+    target_word_id: String,
+    relation_type: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PathResponse {
+    found: bool,
+    path: Vec<knowledge_graph::PathStep>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/words/:word_id/neighbors", get(get_neighbors))
+        .route("/path", get(get_path))
+        .route("/clusters", post(get_clusters))
+        .route("/edges", post(add_edge))
+        .route("/edges/:id", delete(delete_edge))
+}
+
+fn parse_types(raw: Option<&str>) -> Result<Vec<RelationType>, AppError> {
+    let Some(raw) = raw else {
+        return Ok(Vec::new());
+    };
+    raw.split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| {
+            RelationType::parse(s).ok_or_else(|| {
+                json_error(
+                    StatusCode::BAD_REQUEST,
+                    "INVALID_RELATION_TYPE",
+                    format!("未知的关系类型: {}", s.trim()),
+                )
+            })
+        })
+        .collect()
+}
+
+async fn get_neighbors(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(word_id): Path<String>,
+    Query(query): Query<NeighborsQuery>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let types = parse_types(query.types.as_deref())?;
+
+    let graph = match knowledge_graph::load_neighborhood(
+        proxy.pool(),
+        &user.id,
+        std::slice::from_ref(&word_id),
+        1,
+    )
+    .await
+    {
+        Ok(graph) => graph,
+        Err(e) => return Ok(graph_error(e.into())),
+    };
+    let mut neighbors = graph.neighbors(&word_id, &types);
+    neighbors.truncate(query.limit.unwrap_or(DEFAULT_NEIGHBOR_LIMIT).clamp(1, 100));
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: neighbors,
+    })
+    .into_response())
+}
+
+async fn get_path(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PathQuery>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let max_depth = query.max_depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+
+    let graph = match knowledge_graph::load_neighborhood(
+        proxy.pool(),
+        &user.id,
+        std::slice::from_ref(&query.from),
+        max_depth,
+    )
+    .await
+    {
+        Ok(graph) => graph,
+        Err(e) => return Ok(graph_error(e.into())),
+    };
+    let path = graph.shortest_path(&query.from, &query.to, max_depth);
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: PathResponse {
+            found: path.is_some(),
+            path: path.unwrap_or_default(),
+        },
+    })
+    .into_response())
+}
+
+async fn get_clusters(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ClustersBody>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    if body.word_ids.is_empty() || body.word_ids.len() > MAX_CLUSTER_WORDS {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_WORD_IDS",
+            format!("wordIds 数量必须在 1 到 {MAX_CLUSTER_WORDS} 之间"),
+        ));
+    }
+
+    let graph =
+        match knowledge_graph::load_neighborhood(proxy.pool(), &user.id, &body.word_ids, 1).await {
+            Ok(graph) => graph,
+            Err(e) => return Ok(graph_error(e.into())),
+        };
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        data: graph.clusters(&body.word_ids),
+    })
+    .into_response())
+}
+
+async fn add_edge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<EdgeBody>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let relation_type = RelationType::parse(&body.relation_type).ok_or_else(|| {
+        json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_RELATION_TYPE",
+            "relationType 必须是 synonym、antonym、same_root 或 confused",
+        )
+    })?;
+
+    match knowledge_graph::add_user_edge(
+        proxy.as_ref(),
+        &user.id,
+        &body.source_word_id,
+        &body.target_word_id,
+        relation_type,
+    )
+    .await
+    {
+        Ok(edge) => Ok((
+            StatusCode::CREATED,
+            Json(SuccessResponse {
+                success: true,
+                data: edge,
+            }),
+        )
+            .into_response()),
+        Err(e) => Ok(graph_error(e)),
+    }
+}
+
+async fn delete_edge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    match knowledge_graph::delete_user_edge(proxy.as_ref(), &user.id, &id).await {
+        Ok(()) => Ok(Json(SuccessResponse {
+            success: true,
+            data: serde_json::json!({ "deleted": true }),
+        })
+        .into_response()),
+        Err(e) => Ok(graph_error(e)),
+    }
+}
+
+fn graph_error(err: GraphError) -> Response {
+    match err {
+        GraphError::NotFound => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "关系不存在").into_response()
+        }
+        GraphError::Invalid(msg) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_EDGE", msg).into_response()
+        }
+        GraphError::Sql(e) => {
+            tracing::warn!(error = %e, "knowledge graph query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "KNOWLEDGE_GRAPH_FAILED",
+                "知识图谱查询失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn require_user(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(Arc<crate::db::DatabaseProxy>, crate::auth::AuthUser), AppError> {
+    let token = crate::auth::extract_token(headers)
+        .ok_or_else(|| json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌"))?;
+
+    let proxy = state.db_proxy().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+    })?;
+
+    let user = crate::auth::verify_request_token(proxy.as_ref(), &token)
+        .await
+        .map_err(|_| {
+            json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+        })?;
+
+    Ok((proxy, user))
+}
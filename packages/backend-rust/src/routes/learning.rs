@@ -4,12 +4,14 @@ use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use bytes::Bytes;
+use danci_algo::HintContext;
 use serde::{Deserialize, Serialize};
 
 use crate::response::json_error;
 use crate::services::mastery_learning::{
     self, AdjustWordsInput, GetNextWordsInput, RecentPerformance, SessionError, UserState,
 };
+use crate::services::{hint_policy, time_limit};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -26,6 +28,10 @@ struct NextWordsRequest {
     session_id: String,
     #[serde(default)]
     count: Option<i64>,
+    /// Session objective trade-off in [0, 1]; 0 favours retention, 1 a
+    /// quicker, easier session.
+    #[serde(default)]
+    tradeoff: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -128,6 +134,14 @@ async fn study_words_inner(state: AppState, req: Request<Body>, v1: bool) -> Res
         }
     }
 
+    let tradeoff = match get_query_param(query, "tradeoff") {
+        None => None,
+        Some(raw) => match raw.parse::<f64>() {
+            Ok(t) if (0.0..=1.0).contains(&t) => Some(t),
+            _ => return invalid_tradeoff(v1),
+        },
+    };
+
     let Some(proxy) = state.db_proxy() else {
         return json_error(
             StatusCode::SERVICE_UNAVAILABLE,
@@ -154,6 +168,7 @@ async fn study_words_inner(state: AppState, req: Request<Body>, v1: bool) -> Res
         proxy.as_ref(),
         &auth_user.id,
         target_count,
+        tradeoff,
         Some(amas_engine.as_ref()),
     )
     .await
@@ -232,6 +247,10 @@ async fn next_words_inner(state: AppState, req: Request<Body>, v1: bool) -> Resp
         }
     }
 
+    if payload.tradeoff.is_some_and(|t| !(0.0..=1.0).contains(&t)) {
+        return invalid_tradeoff(v1);
+    }
+
     let Some(proxy) = state.db_proxy() else {
         return json_error(
             StatusCode::SERVICE_UNAVAILABLE,
@@ -258,6 +277,7 @@ async fn next_words_inner(state: AppState, req: Request<Body>, v1: bool) -> Resp
         mastered_word_ids: payload.mastered_word_ids,
         session_id: payload.session_id,
         count: payload.count,
+        tradeoff: payload.tradeoff,
     };
 
     let amas_engine = state.amas_engine();
@@ -676,6 +696,242 @@ pub async fn session_progress(State(state): State<AppState>, req: Request<Body>)
     }
 }
 
+/// Upper bound on `wordIds` per time-limit or hint-level request.
+const MAX_TIME_LIMIT_WORDS: usize = 100;
+
+pub async fn time_limits(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let query = req.uri().query().unwrap_or("");
+    let word_ids: Vec<String> = get_query_param(query, "wordIds")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect();
+    if word_ids.is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "BAD_REQUEST", "wordIds 不能为空")
+            .into_response();
+    }
+    if word_ids.len() > MAX_TIME_LIMIT_WORDS {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "wordIds 不能超过 100 个",
+        )
+        .into_response();
+    }
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    match time_limit::recommend_for_words(proxy.pool(), &auth_user.id, &word_ids).await {
+        Ok(data) => Json(SuccessResponse {
+            success: true,
+            data,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "time limit recommendation failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn hint_levels(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let query = req.uri().query().unwrap_or("");
+    let word_ids: Vec<String> = get_query_param(query, "wordIds")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect();
+    if word_ids.is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "BAD_REQUEST", "wordIds 不能为空")
+            .into_response();
+    }
+    if word_ids.len() > MAX_TIME_LIMIT_WORDS {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "wordIds 不能超过 100 个",
+        )
+        .into_response();
+    }
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    match hint_policy::recommend_for_words(proxy.pool(), &auth_user.id, &word_ids).await {
+        Ok(data) => Json(SuccessResponse {
+            success: true,
+            data,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "hint level recommendation failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HintFeedbackRequest {
+    /// Context returned with the hint level, echoed back unchanged.
+    context: HintContext,
+    level: u32,
+    correct: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HintFeedbackResponse {
+    reward: f64,
+}
+
+pub async fn hint_feedback(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (parts, body_bytes) = match split_body(req).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+
+    let token = crate::auth::extract_token(&parts.headers);
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let payload: HintFeedbackRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(value) => value,
+        Err(_) => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR",
+                "请求参数不合法",
+            )
+            .into_response()
+        }
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    match hint_policy::record_feedback(
+        proxy.pool(),
+        &auth_user.id,
+        payload.context,
+        payload.level,
+        payload.correct,
+    )
+    .await
+    {
+        Ok(reward) => Json(SuccessResponse {
+            success: true,
+            data: HintFeedbackResponse { reward },
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "hint feedback update failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+fn invalid_tradeoff(v1: bool) -> Response {
+    json_error(
+        StatusCode::BAD_REQUEST,
+        if v1 {
+            "INVALID_TRADEOFF"
+        } else {
+            "BAD_REQUEST"
+        },
+        "tradeoff 必须是 0 到 1 之间的数",
+    )
+    .into_response()
+}
+
 async fn split_body(req: Request<Body>) -> Result<(axum::http::request::Parts, Bytes), Response> {
     let (parts, body) = req.into_parts();
     let body_bytes = match axum::body::to_bytes(body, 1024 * 1024).await {
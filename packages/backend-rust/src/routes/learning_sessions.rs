@@ -4,10 +4,12 @@ use axum::response::IntoResponse;
 use axum::routing::{get, post, put};
 use axum::Json;
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use danci_algo::CounterfactualSummary;
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 
 use crate::response::{json_error, AppError};
+use crate::services::session_reward;
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -107,6 +109,16 @@ struct SessionDetail {
     answer_records: Vec<SessionAnswerRecord>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EndedSession {
+    #[serde(flatten)]
+    stats: SessionStats,
+    /// Adaptive schedule vs a uniform-random one on this session's decisions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reward_summary: Option<CounterfactualSummary>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct SessionAnswerRecord {
@@ -298,6 +310,20 @@ async fn end_session(
     set_session_ended_at(proxy.as_ref(), &session_id).await?;
     let stats = get_session_stats_internal(proxy.as_ref(), &session_id).await?;
 
+    let reward_summary = match session_reward::summarize_session(
+        proxy.as_ref(),
+        &session_id,
+        &user.id,
+    )
+    .await
+    {
+        Ok(summary) => summary,
+        Err(err) => {
+            tracing::warn!(error = %err, session_id = %session_id, "session reward summary failed");
+            None
+        }
+    };
+
     let payload = serde_json::json!({
         "sessionId": session_id,
         "userId": user.id,
@@ -309,7 +335,10 @@ async fn end_session(
 
     Ok(Json(SuccessResponse {
         success: true,
-        data: stats,
+        data: EndedSession {
+            stats,
+            reward_summary,
+        },
     }))
 }
 
@@ -8,6 +8,7 @@ use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 
+use crate::middleware::request_id::{is_valid_request_id, REQUEST_ID_HEADER};
 use crate::response::json_error;
 use crate::state::AppState;
 
@@ -43,6 +44,9 @@ struct LogEntry {
     context: Option<HashMap<String, serde_json::Value>>,
     #[serde(default)]
     err: Option<ErrorInfo>,
+    /// Request ID of the API call this entry relates to.
+    #[serde(default, rename = "requestId")]
+    request_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -126,6 +130,11 @@ pub async fn ingest(
                 return true;
             }
         }
+        if let Some(request_id) = entry.request_id.as_deref() {
+            if !is_valid_request_id(request_id) {
+                return true;
+            }
+        }
         if let Some(err) = entry.err.as_ref() {
             if err.message.len() > 5_000 || err.name.len() > 200 {
                 return true;
@@ -154,6 +163,15 @@ pub async fn ingest(
         .and_then(|value| value.to_str().ok())
         .unwrap_or("unknown");
 
+    // Entries without their own request ID fall back to the one the client
+    // sent with the batch, if any.
+    let batch_request_id = parts
+        .headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(str::to_string);
+
     let client_ip = addr.ip().to_string();
     let user_id = user.as_ref().map(|u| u.id.as_str()).unwrap_or("anonymous");
     let username = user
@@ -162,6 +180,10 @@ pub async fn ingest(
         .unwrap_or("anonymous");
 
     for entry in payload.logs {
+        let request_id = entry
+            .request_id
+            .clone()
+            .or_else(|| batch_request_id.clone());
         let mut ctx = serde_json::Map::new();
         ctx.insert(
             "clientIp".to_string(),
@@ -224,13 +246,22 @@ pub async fn ingest(
         }
 
         let ctx_str = serde_json::Value::Object(ctx.clone()).to_string();
+        let client_request_id = request_id.as_deref().unwrap_or("");
         match entry.level {
-            LogLevel::Trace => tracing::trace!(context = %ctx_str, "{}", entry.msg),
-            LogLevel::Debug => tracing::debug!(context = %ctx_str, "{}", entry.msg),
-            LogLevel::Info => tracing::info!(context = %ctx_str, "{}", entry.msg),
-            LogLevel::Warn => tracing::warn!(context = %ctx_str, "{}", entry.msg),
+            LogLevel::Trace => {
+                tracing::trace!(client_request_id, context = %ctx_str, "{}", entry.msg)
+            }
+            LogLevel::Debug => {
+                tracing::debug!(client_request_id, context = %ctx_str, "{}", entry.msg)
+            }
+            LogLevel::Info => {
+                tracing::info!(client_request_id, context = %ctx_str, "{}", entry.msg)
+            }
+            LogLevel::Warn => {
+                tracing::warn!(client_request_id, context = %ctx_str, "{}", entry.msg)
+            }
             LogLevel::Error | LogLevel::Fatal => {
-                tracing::error!(context = %ctx_str, "{}", entry.msg)
+                tracing::error!(client_request_id, context = %ctx_str, "{}", entry.msg)
             }
         }
 
@@ -252,8 +283,8 @@ pub async fn ingest(
                 .unwrap_or_else(|_| chrono::Utc::now());
 
             if let Err(e) = sqlx::query(
-                r#"INSERT INTO "system_logs" ("id", "level", "message", "module", "source", "context", "error", "userId", "clientIp", "userAgent", "app", "env", "timestamp")
-                   VALUES ($1, $2::"LogLevel", $3, $4, 'FRONTEND'::"LogSource", $5, $6, $7, $8, $9, $10, $11, $12)"#
+                r#"INSERT INTO "system_logs" ("id", "level", "message", "module", "source", "context", "error", "userId", "clientIp", "userAgent", "app", "env", "timestamp", "requestId")
+                   VALUES ($1, $2::"LogLevel", $3, $4, 'FRONTEND'::"LogSource", $5, $6, $7, $8, $9, $10, $11, $12, $13)"#
             )
             .bind(&log_id)
             .bind(level_str)
@@ -267,6 +298,7 @@ pub async fn ingest(
             .bind(&entry.app)
             .bind(&entry.env)
             .bind(timestamp)
+            .bind(&request_id)
             .execute(pool)
             .await
             {
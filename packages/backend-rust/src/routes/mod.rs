@@ -9,8 +9,10 @@ mod emergency;
 mod etymology;
 mod evaluation;
 mod experiments;
+mod feature_flags;
 mod habit_profile;
 mod health;
+mod knowledge_graph;
 mod learning;
 mod learning_objectives;
 mod learning_sessions;
@@ -18,12 +20,16 @@ mod llm_advisor;
 mod logs;
 pub mod notifications;
 mod optimization;
+mod passages;
+mod placement;
 mod plan;
 mod preferences;
 pub mod realtime;
 mod records;
+mod scim;
 mod semantic;
 mod study_config;
+mod timeline;
 mod tracking;
 mod users;
 mod v1_auth;
@@ -43,8 +49,13 @@ use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post, put};
 use axum::Router;
 
+use crate::middleware::compute_throttle::compute_throttle_middleware;
 use crate::middleware::csrf::{csrf_token_middleware, csrf_validation_middleware};
+use crate::middleware::etag::etag_middleware;
+use crate::middleware::idempotency::idempotency_middleware;
+use crate::middleware::maintenance::maintenance_middleware;
 use crate::middleware::rate_limit::{api_rate_limit_middleware, auth_rate_limit_middleware};
+use crate::middleware::usage::usage_middleware;
 use crate::response::json_error;
 use crate::state::AppState;
 
@@ -118,6 +129,10 @@ pub fn router(state: AppState) -> Router {
             "/api/users/me/avatar",
             post(users::upload_avatar).fallback(fallback_handler),
         )
+        .route(
+            "/api/users/me/export",
+            get(users::export_data).fallback(fallback_handler),
+        )
         .route(
             "/api/users/profile/reward",
             get(users::reward_profile)
@@ -164,6 +179,13 @@ pub fn router(state: AppState) -> Router {
             "/api/preferences/reset",
             post(preferences::reset_preferences).fallback(fallback_handler),
         )
+        .route(
+            "/api/preferences/vacation",
+            get(preferences::vacation_status)
+                .put(preferences::enable_vacation)
+                .delete(preferences::disable_vacation)
+                .fallback(fallback_handler),
+        )
         .route(
             "/api/preferences/quiet-hours/check",
             get(preferences::quiet_hours_check).fallback(fallback_handler),
@@ -184,6 +206,16 @@ pub fn router(state: AppState) -> Router {
             "/api/algorithm-config/presets",
             get(algorithm_config::presets).fallback(fallback_handler),
         )
+        .route(
+            "/api/algorithm-config/policy-rules",
+            get(algorithm_config::get_policy_rules)
+                .put(algorithm_config::update_policy_rules)
+                .fallback(fallback_handler),
+        )
+        .route(
+            "/api/algorithm-config/policy-rules/validate",
+            post(algorithm_config::validate_policy_rules).fallback(fallback_handler),
+        )
         .route(
             "/api/algorithm-config/:id",
             put(algorithm_config::update_config).fallback(fallback_handler),
@@ -213,6 +245,22 @@ pub fn router(state: AppState) -> Router {
             "/api/notifications/batch",
             axum::routing::delete(notifications::batch_delete).fallback(fallback_handler),
         )
+        .route(
+            "/api/notifications/channels",
+            get(notifications::channel_preferences).fallback(fallback_handler),
+        )
+        .route(
+            "/api/notifications/channels/:channel",
+            put(notifications::update_channel_preference).fallback(fallback_handler),
+        )
+        .route(
+            "/api/notifications/deliveries",
+            get(notifications::deliveries).fallback(fallback_handler),
+        )
+        .route(
+            "/api/notifications/:id/deliveries",
+            get(notifications::deliveries).fallback(fallback_handler),
+        )
         .route(
             "/api/notifications/:id/read",
             put(notifications::mark_read).fallback(fallback_handler),
@@ -264,6 +312,10 @@ pub fn router(state: AppState) -> Router {
             "/api/words/learned",
             get(words::learned_words).fallback(fallback_handler),
         )
+        .route(
+            "/api/words/example-sentences",
+            get(words::example_sentences).fallback(fallback_handler),
+        )
         .route(
             "/api/words/batch",
             post(words::batch_create).fallback(fallback_handler),
@@ -308,6 +360,10 @@ pub fn router(state: AppState) -> Router {
                 .post(wordbooks::add_word_to_wordbook)
                 .fallback(fallback_handler),
         )
+        .route(
+            "/api/wordbooks/:id/changes",
+            get(wordbooks::get_wordbook_changes).fallback(fallback_handler),
+        )
         .route(
             "/api/wordbooks/:id/words/batch",
             post(wordbooks::batch_add_words_to_wordbook).fallback(fallback_handler),
@@ -428,6 +484,14 @@ pub fn router(state: AppState) -> Router {
                 "/api/records/statistics/enhanced",
                 get(records::enhanced_statistics).fallback(fallback_handler),
             )
+            .route(
+                "/api/records/statistics/learning-curve",
+                get(records::learning_curve).fallback(fallback_handler),
+            )
+            .route(
+                "/api/records/statistics/what-if",
+                get(records::what_if).fallback(fallback_handler),
+            )
             .route(
                 "/api/v1/learning/records",
                 get(records::v1_list_learning_records)
@@ -470,6 +534,18 @@ pub fn router(state: AppState) -> Router {
                 "/api/learning/adjust-words",
                 post(learning::adjust_words).fallback(fallback_handler),
             )
+            .route(
+                "/api/learning/time-limits",
+                get(learning::time_limits).fallback(fallback_handler),
+            )
+            .route(
+                "/api/learning/hint-levels",
+                get(learning::hint_levels).fallback(fallback_handler),
+            )
+            .route(
+                "/api/learning/hint-feedback",
+                post(learning::hint_feedback).fallback(fallback_handler),
+            )
             .route(
                 "/api/v1/learning/study-words",
                 get(learning::v1_study_words).fallback(fallback_handler),
@@ -486,10 +562,22 @@ pub fn router(state: AppState) -> Router {
                 "/api/word-states/batch",
                 post(word_states::batch_get).fallback(fallback_handler),
             )
+            .route(
+                "/api/word-states/sync",
+                post(word_states::sync).fallback(fallback_handler),
+            )
             .route(
                 "/api/word-states/due/list",
                 get(word_states::due_list).fallback(fallback_handler),
             )
+            .route(
+                "/api/word-states/due/queue",
+                get(word_states::due_queue).fallback(fallback_handler),
+            )
+            .route(
+                "/api/word-states/due/rescue-plan",
+                get(word_states::rescue_plan).fallback(fallback_handler),
+            )
             .route(
                 "/api/word-states/by-state/:state",
                 get(word_states::by_state).fallback(fallback_handler),
@@ -573,18 +661,24 @@ pub fn router(state: AppState) -> Router {
     app = app.nest("/api/etymology", etymology::routes());
     app = app.nest("/api/evaluation", evaluation::router());
     app = app.nest("/api/experiments", experiments::router());
+    app = app.nest("/api/feature-flags", feature_flags::router());
     app = app.nest("/api/habit-profile", habit_profile::router());
+    app = app.nest("/api/knowledge-graph", knowledge_graph::router());
     app = app.nest("/api/learning-sessions", learning_sessions::router());
     app = app.nest("/api/llm-advisor", llm_advisor::router());
     app = app.nest("/api/optimization", optimization::router());
+    app = app.nest("/api/passages", passages::router());
+    app = app.nest("/api/placement", placement::router());
     app = app.nest("/api/plan", plan::router());
     app = app.nest("/api/realtime", realtime::router());
     app = app.nest("/api/semantic", semantic::router());
+    app = app.nest("/api/timeline", timeline::router());
     app = app.nest("/api/tracking", tracking::router());
     app = app.nest("/api/visual-fatigue", visual_fatigue::router());
     app = app.nest("/api/word-contexts", word_contexts::router());
     app = app.nest("/api/word-mastery", word_mastery::router());
     app = app.nest("/api/wordbook-center", wordbook_center::router());
+    app = app.nest("/scim/v2", scim::router());
 
     let mut health_paths: Vec<String> = Vec::new();
     health_paths.push("/health".to_string());
@@ -599,10 +693,23 @@ pub fn router(state: AppState) -> Router {
         app = app.nest(path.as_str(), health::router());
     }
 
-    app.layer(middleware::from_fn(csrf_validation_middleware))
+    // Layers run bottom-up: idempotency sits inside CSRF validation and the
+    // rate limits, and authenticates the caller before it reserves a key.
+    app.layer(middleware::from_fn(etag_middleware))
+        .layer(middleware::from_fn_with_state(
+            middleware_state.clone(),
+            idempotency_middleware,
+        ))
+        .layer(middleware::from_fn(csrf_validation_middleware))
         .layer(middleware::from_fn(csrf_token_middleware))
+        .layer(middleware::from_fn(compute_throttle_middleware))
         .layer(middleware::from_fn(auth_rate_limit_middleware))
         .layer(middleware::from_fn(api_rate_limit_middleware))
+        .layer(middleware::from_fn(maintenance_middleware))
+        .layer(middleware::from_fn_with_state(
+            middleware_state,
+            usage_middleware,
+        ))
         .fallback(fallback_handler)
         .with_state(state)
 }
@@ -9,7 +9,9 @@ use bytes::Bytes;
 use chrono::{NaiveDateTime, TimeZone, Utc};
 use sqlx::{QueryBuilder, Row};
 
+use crate::core::Pagination;
 use crate::response::json_error;
+use crate::services::notification_delivery as delivery;
 use crate::state::AppState;
 
 #[derive(serde::Serialize)]
@@ -78,12 +80,10 @@ pub async fn list_notifications(State(state): State<AppState>, req: Request<Body
     let status = get_query_param(query_string, "status");
     let notification_type = get_query_param(query_string, "type");
     let priority = get_query_param(query_string, "priority");
-    let limit = get_query_param(query_string, "limit")
-        .and_then(|raw| raw.parse::<i64>().ok())
-        .unwrap_or(50);
-    let offset = get_query_param(query_string, "offset")
-        .and_then(|raw| raw.parse::<i64>().ok())
-        .unwrap_or(0);
+    let pagination = match Pagination::<50, 200>::from_uri(req.uri()) {
+        Ok(pagination) => pagination,
+        Err(rejection) => return rejection.into_response(),
+    };
     let start_date = get_query_param(query_string, "startDate")
         .as_deref()
         .and_then(parse_query_datetime);
@@ -116,8 +116,8 @@ pub async fn list_notifications(State(state): State<AppState>, req: Request<Body
         status,
         notification_type,
         priority,
-        limit: limit.clamp(1, 200),
-        offset: offset.max(0),
+        limit: pagination.limit(),
+        offset: pagination.offset,
         start_date,
         end_date,
     };
@@ -1014,6 +1014,180 @@ async fn mark_many_as_deleted(
     Ok(result.rows_affected())
 }
 
+async fn authenticate(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<
+    (
+        std::sync::Arc<crate::db::DatabaseProxy>,
+        crate::auth::AuthUser,
+    ),
+    Response,
+> {
+    let Some(token) = crate::auth::extract_token(headers) else {
+        return Err(
+            json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌").into_response(),
+        );
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response());
+    };
+    match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => Ok((proxy, user)),
+        Err(_) => Err(json_error(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "认证失败，请重新登录",
+        )
+        .into_response()),
+    }
+}
+
+pub async fn channel_preferences(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (proxy, auth_user) = match authenticate(&state, req.headers()).await {
+        Ok(auth) => auth,
+        Err(response) => return response,
+    };
+
+    match delivery::get_channel_preferences(proxy.pool(), &auth_user.id).await {
+        Ok(preferences) => Json(SuccessResponse {
+            success: true,
+            data: preferences,
+            message: None,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "get notification channel preferences failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn update_channel_preference(
+    State(state): State<AppState>,
+    req: Request<Body>,
+) -> Response {
+    let (proxy, auth_user) = match authenticate(&state, req.headers()).await {
+        Ok(auth) => auth,
+        Err(response) => return response,
+    };
+    let channel = req
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let (_parts, body_bytes) = match split_body(req).await {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+    let update: delivery::ChannelPreferenceUpdate = match serde_json::from_slice(&body_bytes) {
+        Ok(update) => update,
+        Err(_) => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR",
+                "请求参数不合法",
+            )
+            .into_response();
+        }
+    };
+
+    match delivery::set_channel_preference(proxy.pool(), &auth_user.id, &channel, update).await {
+        Ok(preference) => Json(SuccessResponse {
+            success: true,
+            data: preference,
+            message: Some("通知渠道已更新".to_string()),
+        })
+        .into_response(),
+        Err(delivery::DeliveryError::UnknownChannel(_)) => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "通知渠道不存在").into_response()
+        }
+        Err(delivery::DeliveryError::InvalidPreference(reason)) => json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            format!("请求参数不合法: {reason}"),
+        )
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "update notification channel preference failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+/// Delivery status across the user's notifications, or of one notification
+/// when called as `/api/notifications/:id/deliveries`.
+pub async fn deliveries(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (proxy, auth_user) = match authenticate(&state, req.headers()).await {
+        Ok(auth) => auth,
+        Err(response) => return response,
+    };
+
+    let notification_id =
+        if req.uri().path().trim_end_matches('/') == "/api/notifications/deliveries" {
+            None
+        } else {
+            match extract_notification_id(req.uri().path()) {
+                Some(id) => Some(id),
+                None => {
+                    return json_error(
+                        StatusCode::BAD_REQUEST,
+                        "VALIDATION_ERROR",
+                        "请求参数不合法",
+                    )
+                    .into_response();
+                }
+            }
+        };
+    let pagination = match Pagination::<50, 200>::from_uri(req.uri()) {
+        Ok(pagination) => pagination,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    match delivery::list_deliveries(
+        proxy.pool(),
+        &auth_user.id,
+        notification_id.as_deref(),
+        pagination.limit(),
+    )
+    .await
+    {
+        Ok(items) => Json(SuccessResponse {
+            success: true,
+            data: items,
+            message: None,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "list notification deliveries failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
 fn extract_notification_id(path: &str) -> Option<String> {
     let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
     if segments.len() < 3 {
@@ -1185,5 +1359,7 @@ pub async fn create_notification(
     .await
     .map_err(|e| format!("创建通知失败: {e}"))?;
 
+    delivery::enqueue_logged(pool, &id).await;
+
     Ok(id)
 }
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::amas::memory::PassageScheduleConfig;
+use crate::response::{json_error, AppError};
+use crate::services::passages::{self, PassageError};
+use crate::state::AppState;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+#[derive(Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DueQuery {
+    limit: Option<usize>,
+    tolerance_hours: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnswerBody {
+    /// Answer reward in [-1, 1]; falls back to `isCorrect` when absent.
+    reward: Option<f64>,
+    is_correct: Option<bool>,
+    #[serde(default)]
+    response_time: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/due", get(get_due_passages))
+        .route("/:id/answer", post(submit_answer))
+}
+
+async fn get_due_passages(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DueQuery>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+
+    let mut config = PassageScheduleConfig::default();
+    if let Some(tolerance) = query.tolerance_hours {
+        if !tolerance.is_finite() || !(0.0..=168.0).contains(&tolerance) {
+            return Err(json_error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_TOLERANCE",
+                "toleranceHours 必须在 0 到 168 之间",
+            ));
+        }
+        config.tolerance_hours = tolerance;
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    match passages::due_passages(proxy.as_ref(), &user.id, &config, limit).await {
+        Ok(data) => Ok(Json(SuccessResponse {
+            success: true,
+            data,
+        })
+        .into_response()),
+        Err(e) => Ok(passage_error(e)),
+    }
+}
+
+async fn submit_answer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<AnswerBody>,
+) -> Result<Response, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+
+    let reward = match (body.reward, body.is_correct) {
+        (Some(reward), _) => reward,
+        (None, Some(true)) => 1.0,
+        (None, Some(false)) => -1.0,
+        (None, None) => {
+            return Err(json_error(
+                StatusCode::BAD_REQUEST,
+                "INVALID_ANSWER",
+                "需要提供 reward 或 isCorrect",
+            ))
+        }
+    };
+
+    match passages::record_answer(proxy.as_ref(), &user.id, &id, reward, body.response_time).await {
+        Ok(data) => Ok(Json(SuccessResponse {
+            success: true,
+            data,
+        })
+        .into_response()),
+        Err(e) => Ok(passage_error(e)),
+    }
+}
+
+fn passage_error(err: PassageError) -> Response {
+    match err {
+        PassageError::NotFound => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "短文不存在").into_response()
+        }
+        PassageError::Invalid(msg) => {
+            json_error(StatusCode::BAD_REQUEST, "INVALID_ANSWER", msg).into_response()
+        }
+        PassageError::Sql(e) => {
+            tracing::warn!(error = %e, "passage query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "PASSAGE_FAILED",
+                "短文复习操作失败",
+            )
+            .into_response()
+        }
+    }
+}
+
+async fn require_user(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(Arc<crate::db::DatabaseProxy>, crate::auth::AuthUser), AppError> {
+    let token = crate::auth::extract_token(headers)
+        .ok_or_else(|| json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌"))?;
+
+    let proxy = state.db_proxy().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+    })?;
+
+    let user = crate::auth::verify_request_token(proxy.as_ref(), &token)
+        .await
+        .map_err(|_| {
+            json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+        })?;
+
+    Ok((proxy, user))
+}
@@ -0,0 +1,185 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::response::{json_error, AppError};
+use crate::services::placement::{self, PlacementError, SubmittedAnswer};
+use crate::state::AppState;
+
+/// Offline submissions larger than this are rejected.
+const MAX_SUBMITTED_ANSWERS: usize = 100;
+
+#[derive(Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+fn ok<T: Serialize>(data: T) -> impl IntoResponse {
+    Json(SuccessResponse {
+        success: true,
+        data,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnswerRequest {
+    word_id: String,
+    correct: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitRequest {
+    answers: Vec<SubmittedAnswer>,
+}
+
+pub fn router() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/items", get(get_items))
+        .route("/start", post(start))
+        .route("/:id/answer", post(answer))
+        .route("/submit", post(submit))
+        .route("/result", get(get_result))
+}
+
+fn placement_error(err: PlacementError) -> AppError {
+    match err {
+        PlacementError::NotFound => {
+            json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "分级测试不存在")
+        }
+        PlacementError::Completed => json_error(
+            StatusCode::CONFLICT,
+            "PLACEMENT_COMPLETED",
+            "分级测试已结束",
+        ),
+        PlacementError::UnknownWord => json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_WORD",
+            "该单词不属于本次分级测试",
+        ),
+        PlacementError::NoItems => json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "NO_CALIBRATED_WORDS",
+            "暂无可用于分级测试的单词",
+        ),
+        e => {
+            tracing::warn!(error = %e, "placement query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "PLACEMENT_FAILED",
+                "分级测试操作失败",
+            )
+        }
+    }
+}
+
+/// Calibration set for tests taken offline.
+async fn get_items(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, _user) = require_user(&state, &headers).await?;
+    let set = placement::calibration_set(proxy.as_ref())
+        .await
+        .map_err(placement_error)?;
+    Ok(ok(set))
+}
+
+async fn start(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let view = placement::start(proxy.as_ref(), &user.id)
+        .await
+        .map_err(placement_error)?;
+    Ok(ok(view))
+}
+
+async fn answer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(payload): Json<AnswerRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let view = placement::answer(
+        proxy.as_ref(),
+        &user.id,
+        &id,
+        &payload.word_id,
+        payload.correct,
+    )
+    .await
+    .map_err(placement_error)?;
+    Ok(ok(view))
+}
+
+async fn submit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SubmitRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    if payload.answers.is_empty() || payload.answers.len() > MAX_SUBMITTED_ANSWERS {
+        return Err(json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_ANSWERS",
+            "作答记录数量无效",
+        ));
+    }
+    let result = placement::submit(proxy.as_ref(), &user.id, &payload.answers)
+        .await
+        .map_err(placement_error)?;
+    Ok(ok(result))
+}
+
+async fn get_result(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let result = placement::latest_result(proxy.as_ref(), &user.id)
+        .await
+        .map_err(placement_error)?;
+    Ok(ok(result))
+}
+
+async fn require_user(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<
+    (
+        std::sync::Arc<crate::db::DatabaseProxy>,
+        crate::auth::AuthUser,
+    ),
+    AppError,
+> {
+    let token = crate::auth::extract_token(headers)
+        .ok_or_else(|| json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌"))?;
+
+    let proxy = state.db_proxy().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+    })?;
+
+    let user = crate::auth::verify_request_token(&proxy, &token)
+        .await
+        .map_err(|_| {
+            json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+        })?;
+
+    Ok((proxy, user))
+}
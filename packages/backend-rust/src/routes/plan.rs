@@ -11,6 +11,7 @@ use sqlx::{PgPool, QueryBuilder, Row};
 
 use crate::response::{json_error, AppError};
 use crate::services::study_config;
+use crate::services::wordbook_licenses;
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -787,9 +788,8 @@ async fn select_wordbooks(
         }
         sep.push_unseparated(")");
     }
-    qb.push(r#" AND ("type" = 'SYSTEM' OR "userId" = "#);
-    qb.push_bind(user_id);
-    qb.push(")");
+    qb.push(" AND ");
+    wordbook_licenses::push_accessible_wordbook(&mut qb, r#""word_books"."#, user_id);
 
     let rows = qb
         .build()
@@ -1,15 +1,19 @@
+use std::sync::Arc;
+
 use axum::body::Body;
 use axum::extract::State;
 use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use bytes::Bytes;
-use chrono::{Timelike, Utc};
+use chrono::{NaiveDate, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::db::DatabaseProxy;
 use crate::response::json_error;
+use crate::services::vacation::{self, VacationError, VacationStatus};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -26,6 +30,8 @@ struct PreferencesResponse {
     learning: LearningPreferences,
     notification: NotificationPreferences,
     ui: UiPreferences,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vacation: Option<VacationStatus>,
     updated_at: String,
 }
 
@@ -151,6 +157,7 @@ impl PreferencesRow {
                 sound_enabled: self.sound_enabled,
                 animation_enabled: self.animation_enabled,
             },
+            vacation: None,
             updated_at: self.updated_at.clone(),
         }
     }
@@ -228,9 +235,16 @@ pub async fn get_preferences(State(state): State<AppState>, req: Request<Body>)
         }
     };
 
+    let mut data = row.to_grouped();
+    let today = Utc::now().date_naive();
+    match vacation::current_period(proxy.pool(), &auth_user.id, today).await {
+        Ok(period) => data.vacation = Some(VacationStatus::from_period(period, today)),
+        Err(err) => tracing::warn!(error = %err, "select vacation failed"),
+    }
+
     Json(SuccessResponse {
         success: true,
-        data: row.to_grouped(),
+        data,
         message: None,
     })
     .into_response()
@@ -401,6 +415,143 @@ pub async fn quiet_hours_check(State(state): State<AppState>, req: Request<Body>
     .into_response()
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnableVacationDto {
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    ramp_up_days: Option<i32>,
+}
+
+async fn authenticate(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<(Arc<DatabaseProxy>, crate::auth::AuthUser), Response> {
+    let Some(token) = crate::auth::extract_token(headers) else {
+        return Err(
+            json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌").into_response(),
+        );
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return Err(json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response());
+    };
+    match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => Ok((proxy, user)),
+        Err(_) => Err(json_error(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "认证失败，请重新登录",
+        )
+        .into_response()),
+    }
+}
+
+pub async fn vacation_status(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (proxy, auth_user) = match authenticate(&state, req.headers()).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    let today = Utc::now().date_naive();
+    match vacation::current_period(proxy.pool(), &auth_user.id, today).await {
+        Ok(period) => Json(SuccessResponse {
+            success: true,
+            data: VacationStatus::from_period(period, today),
+            message: None,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "select vacation failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn enable_vacation(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (parts, body_bytes) = match split_body(req).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    let (proxy, auth_user) = match authenticate(&state, &parts.headers).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    let dto: EnableVacationDto = match serde_json::from_slice(&body_bytes) {
+        Ok(value) => value,
+        Err(_) => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR",
+                "请求参数不合法",
+            )
+            .into_response();
+        }
+    };
+
+    let ramp_up_days = dto.ramp_up_days.unwrap_or(vacation::DEFAULT_RAMP_UP_DAYS);
+    match vacation::enable(
+        proxy.pool(),
+        &auth_user.id,
+        dto.start_date,
+        dto.end_date,
+        ramp_up_days,
+    )
+    .await
+    {
+        Ok(period) => Json(SuccessResponse {
+            success: true,
+            data: VacationStatus::from_period(Some(period), Utc::now().date_naive()),
+            message: Some("假期模式已开启".to_string()),
+        })
+        .into_response(),
+        Err(VacationError::Invalid(msg)) => {
+            json_error(StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg).into_response()
+        }
+        Err(VacationError::Sql(err)) => {
+            tracing::warn!(error = %err, "enable vacation failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn disable_vacation(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (proxy, auth_user) = match authenticate(&state, req.headers()).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    match vacation::disable(proxy.pool(), &auth_user.id).await {
+        Ok(period) => Json(SuccessResponse {
+            success: true,
+            data: VacationStatus::from_period(period, Utc::now().date_naive()),
+            message: Some("假期模式已关闭".to_string()),
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "disable vacation failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum PreferencesPart {
     Learning,
@@ -569,5 +569,6 @@ async fn send_test_event(
         success: true,
         error: "测试事件已发送".to_string(),
         code: "OK".to_string(),
+        request_id: None,
     }))
 }
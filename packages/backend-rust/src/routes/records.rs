@@ -6,8 +6,10 @@ use axum::Json;
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
+use crate::core::Pagination;
 use crate::response::json_error;
 use crate::services::record::{self, CreateRecordInput, PaginationOptions, RecordError};
+use crate::services::{learning_curve, what_if};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -33,6 +35,9 @@ struct CreateRecordRequest {
     selected_answer: Option<String>,
     #[serde(default)]
     correct_answer: Option<String>,
+    /// e.g. `typing`, `spelling`, `multiple_choice`
+    #[serde(default)]
+    question_type: Option<String>,
     is_correct: bool,
     #[serde(default)]
     timestamp: Option<i64>,
@@ -95,9 +100,10 @@ async fn list_records_inner(
             .into_response();
     };
 
-    let query = req.uri().query().unwrap_or("");
-    let page = get_query_param(query, "page").and_then(|v| v.parse::<i64>().ok());
-    let page_size = get_query_param(query, "pageSize").and_then(|v| v.parse::<i64>().ok());
+    let pagination = match Pagination::<50, 100>::from_uri(req.uri()) {
+        Ok(pagination) => pagination,
+        Err(rejection) => return rejection.into_response(),
+    };
 
     let Some(proxy) = state.db_proxy() else {
         return json_error(
@@ -120,7 +126,10 @@ async fn list_records_inner(
         }
     };
 
-    let options = PaginationOptions { page, page_size };
+    let options = PaginationOptions {
+        page: Some(pagination.page),
+        page_size: Some(pagination.page_size),
+    };
     let result = match session_id {
         Some(session_id) => {
             record::get_records_by_session_id(proxy.as_ref(), &auth_user.id, session_id, options)
@@ -214,6 +223,7 @@ async fn create_record_inner(state: AppState, req: Request<Body>) -> Response {
         selected_option: payload.selected_option,
         selected_answer: payload.selected_answer,
         correct_answer: payload.correct_answer,
+        question_type: payload.question_type,
         is_correct: payload.is_correct,
         timestamp_ms: payload.timestamp,
         response_time: payload.response_time,
@@ -362,6 +372,7 @@ async fn batch_create_records_inner(state: AppState, req: Request<Body>) -> Resp
             selected_option: record.selected_option,
             selected_answer: record.selected_answer,
             correct_answer: record.correct_answer,
+            question_type: record.question_type,
             is_correct: record.is_correct,
             timestamp_ms: record.timestamp,
             response_time: record.response_time,
@@ -529,6 +540,113 @@ pub async fn enhanced_statistics(State(state): State<AppState>, req: Request<Bod
     }
 }
 
+/// `GET /api/records/statistics/learning-curve?wordBookId=&budgets=30,60`
+pub async fn learning_curve(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    let query = req.uri().query().unwrap_or("");
+    let word_book_id = get_query_param(query, "wordBookId").filter(|id| !id.is_empty());
+    let budgets = learning_curve::parse_budgets(get_query_param(query, "budgets").as_deref());
+    match learning_curve::learning_curve(
+        proxy.pool(),
+        &auth_user.id,
+        word_book_id.as_deref(),
+        budgets,
+    )
+    .await
+    {
+        Ok(report) => Json(SuccessResponse {
+            success: true,
+            data: report,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "learning curve query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+/// Projected mastery and retention at 30/90 days for alternative daily
+/// study times, e.g. `?minutes=20,30`.
+pub async fn what_if(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    let query = req.uri().query().unwrap_or("");
+    let minutes = what_if::parse_minutes(get_query_param(query, "minutes").as_deref());
+    match what_if::what_if(proxy.as_ref(), &auth_user.id, minutes).await {
+        Ok(report) => Json(SuccessResponse {
+            success: true,
+            data: report,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "what-if projection failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
 async fn split_body(req: Request<Body>) -> Result<(axum::http::request::Parts, Bytes), Response> {
     let (parts, body) = req.into_parts();
     let body_bytes = match axum::body::to_bytes(body, 1024 * 1024).await {
@@ -0,0 +1,256 @@
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::services::scim::{self, PatchRequest, ScimError, ScimUserInput, ERROR_SCHEMA};
+use crate::state::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/ServiceProviderConfig", get(service_provider_config))
+        .route("/Users", get(list_users).post(create_user))
+        .route(
+            "/Users/:id",
+            get(get_user).put(replace_user).patch(patch_user),
+        )
+}
+
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+fn scim_json<T: Serialize>(status: StatusCode, body: &T) -> Response {
+    let bytes = serde_json::to_vec(body).unwrap_or_default();
+    (
+        status,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(SCIM_CONTENT_TYPE),
+        )],
+        bytes,
+    )
+        .into_response()
+}
+
+fn scim_error(err: ScimError) -> Response {
+    if let ScimError::Sql(e) = &err {
+        tracing::warn!(error = %e, "scim query failed");
+    }
+    let status = StatusCode::from_u16(err.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    scim_json(status, &err.to_body())
+}
+
+fn plain_error(status: StatusCode, detail: &str) -> Response {
+    scim_json(
+        status,
+        &serde_json::json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": status.as_u16().to_string(),
+            "detail": detail,
+        }),
+    )
+}
+
+/// Organization of the bearer token, or the error response to send.
+async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<String, Response> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+    let Some(token) = token else {
+        return Err(plain_error(
+            StatusCode::UNAUTHORIZED,
+            "missing bearer token",
+        ));
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return Err(plain_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database unavailable",
+        ));
+    };
+    match scim::authenticate(proxy.as_ref(), token).await {
+        Ok(Some(org_id)) => Ok(org_id),
+        Ok(None) => Err(plain_error(StatusCode::UNAUTHORIZED, "invalid token")),
+        Err(e) => Err(scim_error(e)),
+    }
+}
+
+/// A deactivated user loses every session, including cached ones.
+async fn revoke_sessions_if_inactive(state: &AppState, user: &scim::ScimUser) {
+    if user.active {
+        return;
+    }
+    let Some(proxy) = state.db_proxy() else {
+        return;
+    };
+    match proxy.delete_all_user_sessions(&user.id).await {
+        Ok(token_hashes) => {
+            if let Some(cache) = state.cache() {
+                for token_hash in token_hashes {
+                    cache
+                        .delete(&crate::cache::keys::session_key(&token_hash))
+                        .await;
+                }
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, user_id = %user.id, "scim session revoke failed"),
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(body: &Bytes) -> Result<T, Response> {
+    serde_json::from_slice(body).map_err(|e| {
+        scim_error(ScimError::InvalidValue(format!(
+            "invalid request body: {e}"
+        )))
+    })
+}
+
+async fn service_provider_config() -> Response {
+    scim_json(StatusCode::OK, &scim::service_provider_config())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListQuery {
+    filter: Option<String>,
+    start_index: Option<i64>,
+    count: Option<i64>,
+}
+
+async fn list_users(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let org_id = match authenticate(&state, &headers).await {
+        Ok(org_id) => org_id,
+        Err(res) => return res,
+    };
+    let user_name = match query.filter.as_deref().map(scim::parse_filter).transpose() {
+        Ok(user_name) => user_name,
+        Err(e) => return scim_error(e),
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return plain_error(StatusCode::SERVICE_UNAVAILABLE, "database unavailable");
+    };
+    match scim::list_users(
+        proxy.as_ref(),
+        &org_id,
+        user_name.as_deref(),
+        query.start_index.unwrap_or(1),
+        query.count.unwrap_or(scim::MAX_RESULTS),
+    )
+    .await
+    {
+        Ok(list) => scim_json(StatusCode::OK, &list),
+        Err(e) => scim_error(e),
+    }
+}
+
+async fn get_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let org_id = match authenticate(&state, &headers).await {
+        Ok(org_id) => org_id,
+        Err(res) => return res,
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return plain_error(StatusCode::SERVICE_UNAVAILABLE, "database unavailable");
+    };
+    match scim::get_user(proxy.as_ref(), &org_id, &id).await {
+        Ok(user) => scim_json(StatusCode::OK, &user),
+        Err(e) => scim_error(e),
+    }
+}
+
+async fn create_user(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    let org_id = match authenticate(&state, &headers).await {
+        Ok(org_id) => org_id,
+        Err(res) => return res,
+    };
+    let fields = match parse_body::<ScimUserInput>(&body).map(ScimUserInput::into_fields) {
+        Ok(Ok(fields)) => fields,
+        Ok(Err(e)) => return scim_error(e),
+        Err(res) => return res,
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return plain_error(StatusCode::SERVICE_UNAVAILABLE, "database unavailable");
+    };
+    match scim::create_user(proxy.as_ref(), &org_id, &fields).await {
+        Ok(user) => {
+            let mut res = scim_json(StatusCode::CREATED, &user);
+            if let Ok(location) = HeaderValue::from_str(&user.meta.location) {
+                res.headers_mut().insert(header::LOCATION, location);
+            }
+            res
+        }
+        Err(e) => scim_error(e),
+    }
+}
+
+async fn replace_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let org_id = match authenticate(&state, &headers).await {
+        Ok(org_id) => org_id,
+        Err(res) => return res,
+    };
+    let fields = match parse_body::<ScimUserInput>(&body).map(ScimUserInput::into_fields) {
+        Ok(Ok(fields)) => fields,
+        Ok(Err(e)) => return scim_error(e),
+        Err(res) => return res,
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return plain_error(StatusCode::SERVICE_UNAVAILABLE, "database unavailable");
+    };
+    match scim::update_user(proxy.as_ref(), &org_id, &id, &fields).await {
+        Ok(user) => {
+            revoke_sessions_if_inactive(&state, &user).await;
+            scim_json(StatusCode::OK, &user)
+        }
+        Err(e) => scim_error(e),
+    }
+}
+
+async fn patch_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let org_id = match authenticate(&state, &headers).await {
+        Ok(org_id) => org_id,
+        Err(res) => return res,
+    };
+    let patch = match parse_body::<PatchRequest>(&body) {
+        Ok(patch) => patch,
+        Err(res) => return res,
+    };
+    let Some(proxy) = state.db_proxy() else {
+        return plain_error(StatusCode::SERVICE_UNAVAILABLE, "database unavailable");
+    };
+    let result = async {
+        let current = scim::load_fields(proxy.as_ref(), &org_id, &id).await?;
+        let fields = scim::apply_patch(current, &patch.operations)?;
+        scim::update_user(proxy.as_ref(), &org_id, &id, &fields).await
+    }
+    .await;
+    match result {
+        Ok(user) => {
+            revoke_sessions_if_inactive(&state, &user).await;
+            scim_json(StatusCode::OK, &user)
+        }
+        Err(e) => scim_error(e),
+    }
+}
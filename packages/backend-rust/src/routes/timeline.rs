@@ -0,0 +1,145 @@
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::response::{json_error, AppError};
+use crate::services::timeline::{self, TimelineError};
+use crate::state::AppState;
+
+#[derive(Serialize)]
+struct SuccessResponse<T> {
+    success: bool,
+    data: T,
+}
+
+fn ok<T: Serialize>(data: T) -> impl IntoResponse {
+    Json(SuccessResponse {
+        success: true,
+        data,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimelineQuery {
+    /// Only entries strictly before this local date (`YYYY-MM-DD`).
+    before: Option<NaiveDate>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimezoneRequest {
+    timezone: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TimezoneData {
+    timezone: String,
+}
+
+pub fn router() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/", get(get_timeline))
+        .route("/timezone", get(get_timezone).put(set_timezone))
+}
+
+fn timeline_error(err: TimelineError) -> AppError {
+    match err {
+        TimelineError::InvalidTimezone(_) => json_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_TIMEZONE",
+            "无效的时区，请使用 IANA 时区名（如 Asia/Shanghai）",
+        ),
+        TimelineError::UserNotFound => json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "用户不存在"),
+        e => {
+            tracing::warn!(error = %e, "timeline query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "TIMELINE_FAILED",
+                "学习时间线查询失败",
+            )
+        }
+    }
+}
+
+/// Rolled-up days, newest first, paged with `nextBefore`.
+async fn get_timeline(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TimelineQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(timeline::DEFAULT_PAGE_SIZE);
+    let page = timeline::page(proxy.pool(), &user.id, query.before, limit)
+        .await
+        .map_err(|e| timeline_error(e.into()))?;
+    Ok(ok(page))
+}
+
+async fn get_timezone(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let timezone = timeline::timezone(proxy.pool(), &user.id)
+        .await
+        .map_err(|e| timeline_error(e.into()))?;
+    Ok(ok(TimezoneData { timezone }))
+}
+
+/// Sets the zone whose midnights close the user's timeline days; clients
+/// report it whenever the device zone changes.
+async fn set_timezone(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<TimezoneRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let (proxy, user) = require_user(&state, &headers).await?;
+    let timezone = payload.timezone.trim();
+    timeline::set_timezone(proxy.pool(), &user.id, timezone)
+        .await
+        .map_err(timeline_error)?;
+    Ok(ok(TimezoneData {
+        timezone: timezone.to_string(),
+    }))
+}
+
+async fn require_user(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<
+    (
+        std::sync::Arc<crate::db::DatabaseProxy>,
+        crate::auth::AuthUser,
+    ),
+    AppError,
+> {
+    let token = crate::auth::extract_token(headers)
+        .ok_or_else(|| json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌"))?;
+
+    let proxy = state.db_proxy().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+    })?;
+
+    let user = crate::auth::verify_request_token(&proxy, &token)
+        .await
+        .map_err(|_| {
+            json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+        })?;
+
+    Ok((proxy, user))
+}
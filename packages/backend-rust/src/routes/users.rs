@@ -9,9 +9,11 @@ use sqlx::Row;
 
 use crate::cache::keys::{user_profile_key, USER_PROFILE_TTL};
 use crate::response::json_error;
+use crate::services::data_export;
 use crate::services::user_profile::{
     compute_chronotype, compute_learning_style, CognitiveProfileResponse,
 };
+use crate::services::wordbook_licenses;
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -356,6 +358,53 @@ pub async fn statistics(State(state): State<AppState>, req: Request<Body>) -> Re
     .into_response()
 }
 
+/// Streams everything the user owns as NDJSON (right of access).
+pub async fn export_data(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    let source = data_export::ExportSource::select(proxy.as_ref()).await;
+    let filename = format!(
+        "attachment; filename=\"user-data-{}.ndjson\"",
+        Utc::now().format("%Y%m%d")
+    );
+    (
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                data_export::CONTENT_TYPE.to_string(),
+            ),
+            (axum::http::header::CONTENT_DISPOSITION, filename),
+        ],
+        Body::from_stream(data_export::export_user_data(source, auth_user.id)),
+    )
+        .into_response()
+}
+
 pub async fn v1_me_profile(State(state): State<AppState>, req: Request<Body>) -> Response {
     let token = crate::auth::extract_token(req.headers());
     let Some(token) = token else {
@@ -1045,13 +1094,10 @@ async fn select_user_statistics(
     user_id: &str,
 ) -> Result<StatisticsResponse, sqlx::Error> {
     let pool = proxy.pool();
-    let word_books: Vec<String> = sqlx::query_scalar(
-        r#"
-        SELECT "id"
-        FROM "word_books"
-        WHERE ("type"::text = 'SYSTEM') OR (("type"::text = 'USER') AND "userId" = $1)
-        "#,
-    )
+    let word_books: Vec<String> = sqlx::query_scalar(&format!(
+        r#"SELECT "id" FROM "word_books" WHERE {}"#,
+        wordbook_licenses::accessible_wordbook_sql(r#""word_books"."#, "$1")
+    ))
     .bind(user_id)
     .fetch_all(pool)
     .await
@@ -688,6 +688,11 @@ pub async fn login(State(state): State<AppState>, req: Request<Body>) -> Respons
         return json_error(StatusCode::FORBIDDEN, "ACCOUNT_BANNED", "账号已被封禁").into_response();
     }
 
+    if user.deactivated {
+        return json_error(StatusCode::FORBIDDEN, "ACCOUNT_DEACTIVATED", "账号已被停用")
+            .into_response();
+    }
+
     let (token, expires_at) = match crate::auth::sign_jwt_for_user(&user.id) {
         Ok(value) => value,
         Err(err) => {
@@ -820,6 +825,8 @@ struct LoginUserRow {
     role: String,
     created_at: String,
     password_hash: String,
+    /// 由组织通过 SCIM 停用
+    deactivated: bool,
 }
 
 async fn select_user_id_by_email(
@@ -866,7 +873,10 @@ async fn select_user_for_login(
           "username",
           "role"::text as "role",
           "createdAt",
-          "passwordHash"
+          "passwordHash",
+          EXISTS (
+            SELECT 1 FROM "scim_users" s WHERE s."userId" = "users"."id" AND NOT s."active"
+          ) AS "deactivated"
         FROM "users"
         WHERE "email" = $1
         LIMIT 1
@@ -889,6 +899,7 @@ async fn select_user_for_login(
         role: row.try_get("role")?,
         created_at: crate::auth::format_naive_datetime_iso_millis(created_at),
         password_hash: row.try_get("passwordHash")?,
+        deactivated: row.try_get("deactivated")?,
     }))
 }
 
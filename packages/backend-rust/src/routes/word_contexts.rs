@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 
 use crate::response::{json_error, AppError};
+use crate::services::wordbook_licenses;
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -937,16 +938,17 @@ async fn is_word_accessible_pg(
     user_id: &str,
     word_id: &str,
 ) -> Result<bool, AppError> {
-    let row = sqlx::query_scalar::<_, String>(
+    let row = sqlx::query_scalar::<_, String>(&format!(
         r#"
         SELECT w."id"
         FROM "words" w
         JOIN "word_books" wb ON wb."id" = w."wordBookId"
         WHERE w."id" = $1
-          AND (wb."type" = 'SYSTEM' OR (wb."type" = 'USER' AND wb."userId" = $2))
+          AND {}
         LIMIT 1
         "#,
-    )
+        wordbook_licenses::accessible_wordbook_sql("wb.", "$2")
+    ))
     .bind(word_id)
     .bind(user_id)
     .fetch_optional(pool)
@@ -960,17 +962,18 @@ async fn is_context_accessible_pg(
     user_id: &str,
     context_id: &str,
 ) -> Result<bool, AppError> {
-    let row = sqlx::query_scalar::<_, String>(
+    let row = sqlx::query_scalar::<_, String>(&format!(
         r#"
         SELECT wc."id"
         FROM "word_contexts" wc
         JOIN "words" w ON w."id" = wc."wordId"
         JOIN "word_books" wb ON wb."id" = w."wordBookId"
         WHERE wc."id" = $1
-          AND (wb."type" = 'SYSTEM' OR (wb."type" = 'USER' AND wb."userId" = $2))
+          AND {}
         LIMIT 1
         "#,
-    )
+        wordbook_licenses::accessible_wordbook_sql("wb.", "$2")
+    ))
     .bind(context_id)
     .bind(user_id)
     .fetch_optional(pool)
@@ -1003,9 +1006,8 @@ async fn count_accessible_words_pg(
         }
         separated.push_unseparated(")");
     }
-    qb.push(r#" AND (wb."type" = 'SYSTEM' OR (wb."type" = 'USER' AND wb."userId" = "#)
-        .push_bind(user_id)
-        .push("))");
+    qb.push(" AND ");
+    wordbook_licenses::push_accessible_wordbook(&mut qb, "wb.", user_id);
     let count: i64 = qb
         .build_query_scalar()
         .fetch_one(pool)
@@ -1039,9 +1041,8 @@ async fn count_accessible_contexts_pg(
         }
         separated.push_unseparated(")");
     }
-    qb.push(r#" AND (wb."type" = 'SYSTEM' OR (wb."type" = 'USER' AND wb."userId" = "#)
-        .push_bind(user_id)
-        .push("))");
+    qb.push(" AND ");
+    wordbook_licenses::push_accessible_wordbook(&mut qb, "wb.", user_id);
     let count: i64 = qb
         .build_query_scalar()
         .fetch_one(pool)
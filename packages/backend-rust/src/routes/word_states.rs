@@ -1,13 +1,17 @@
 use axum::body::Body;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use bytes::Bytes;
+use danci_algo::{MasteryBand, RescueConfig};
 use serde::{Deserialize, Serialize};
 
 use crate::response::json_error;
-use crate::services::word_states::{self, WordLearningStateRecord, WordStateError, WordStateStats};
+use crate::services::due_queue;
+use crate::services::word_states::{
+    self, DeviceWordState, WordLearningStateRecord, WordStateError, WordStateStats,
+};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -26,6 +30,16 @@ struct MessageResponse {
 #[serde(rename_all = "camelCase")]
 struct BatchRequest {
     word_ids: Vec<String>,
+    /// Attach `masteryBand` (mastery ± uncertainty) to each state.
+    #[serde(default)]
+    include_band: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncRequest {
+    device_id: String,
+    states: Vec<DeviceWordState>,
 }
 
 #[derive(Serialize)]
@@ -33,6 +47,57 @@ struct BatchRequest {
 struct BatchItem {
     word_id: String,
     state: Option<WordLearningStateRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mastery_band: Option<MasteryBand>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateWithBand {
+    #[serde(flatten)]
+    state: WordLearningStateRecord,
+    mastery_band: Option<MasteryBand>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RescueQuery {
+    session_minutes: Option<f64>,
+    max_session_words: Option<u32>,
+    ramp_days: Option<u32>,
+}
+
+impl RescueQuery {
+    fn into_config(self) -> RescueConfig {
+        let defaults = RescueConfig::default();
+        RescueConfig {
+            session_minutes: self
+                .session_minutes
+                .filter(|m| m.is_finite())
+                .map_or(defaults.session_minutes, |m| m.clamp(1.0, 120.0)),
+            max_session_words: self
+                .max_session_words
+                .map_or(defaults.max_session_words, |n| n.clamp(1, 500)),
+            ramp_days: self
+                .ramp_days
+                .map_or(defaults.ramp_days, |n| n.clamp(1, 30)),
+            ..defaults
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DueQueueQuery {
+    page: Option<i64>,
+    page_size: Option<i64>,
+}
+
+fn include_band(query: Option<&str>) -> bool {
+    query
+        .unwrap_or("")
+        .split('&')
+        .any(|pair| pair == "includeBand=true" || pair == "includeBand=1")
 }
 
 pub async fn batch_get(State(state): State<AppState>, req: Request<Body>) -> Response {
@@ -122,10 +187,20 @@ pub async fn batch_get(State(state): State<AppState>, req: Request<Body>) -> Res
         }
     };
 
+    let mut bands = if payload.include_band {
+        match word_states::mastery_bands(proxy.as_ref(), &auth_user.id, &unique_ids).await {
+            Ok(value) => value,
+            Err(err) => return handle_service_error(err),
+        }
+    } else {
+        Default::default()
+    };
+
     let items: Vec<BatchItem> = unique_ids
         .into_iter()
         .map(|id| BatchItem {
             state: map.get(id.as_str()).cloned(),
+            mastery_band: bands.remove(&id),
             word_id: id,
         })
         .collect();
@@ -137,6 +212,95 @@ pub async fn batch_get(State(state): State<AppState>, req: Request<Body>) -> Res
     .into_response()
 }
 
+/// Vector-clock sync of a device's learning states; see
+/// `word_states::sync_word_states`.
+pub async fn sync(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (parts, body_bytes) = match split_body(req).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+
+    let token = crate::auth::extract_token(&parts.headers);
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let payload: SyncRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(value) => value,
+        Err(_) => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR",
+                "请求参数不合法",
+            )
+            .into_response()
+        }
+    };
+
+    let device_id = payload.device_id.trim();
+    if device_id.is_empty() || device_id.len() > 128 {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "deviceId 不能为空且不超过128个字符",
+        )
+        .into_response();
+    }
+    if payload.states.iter().any(|s| s.word_id.trim().is_empty()) {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "states must contain only non-empty wordIds",
+        )
+        .into_response();
+    }
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    match word_states::sync_word_states(proxy.as_ref(), &auth_user.id, &payload.states).await {
+        Ok(items) => {
+            let merged = items
+                .iter()
+                .filter(|i| i.resolution == word_states::SyncResolution::Merged)
+                .count();
+            if merged > 0 {
+                tracing::info!(
+                    user_id = %auth_user.id,
+                    device_id,
+                    merged,
+                    "word state sync merged concurrent edits"
+                );
+            }
+            Json(SuccessResponse {
+                success: true,
+                data: items,
+            })
+            .into_response()
+        }
+        Err(err) => handle_service_error(err),
+    }
+}
+
 pub async fn due_list(State(state): State<AppState>, req: Request<Body>) -> Response {
     let token = crate::auth::extract_token(req.headers());
     let Some(token) = token else {
@@ -183,6 +347,121 @@ pub async fn due_list(State(state): State<AppState>, req: Request<Body>) -> Resp
     }
 }
 
+/// Paginated server-side queue of words due today, most urgent first.
+pub async fn due_queue(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let Ok(Query(query)) = Query::<DueQueueQuery>::try_from_uri(req.uri()) else {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            "请求参数不合法",
+        )
+        .into_response();
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    let page = query.page.unwrap_or(1);
+    let page_size = query.page_size.unwrap_or(due_queue::DEFAULT_PAGE_SIZE);
+    match due_queue::page(proxy.pool(), &auth_user.id, page, page_size).await {
+        Ok(page) => Json(SuccessResponse {
+            success: true,
+            data: page,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "word state due queue failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
+/// Overdue words ranked by recall lost per minute of review: a bounded
+/// catch-up session plus the rest spread over the following days.
+pub async fn rescue_plan(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let Ok(Query(query)) = Query::<RescueQuery>::try_from_uri(req.uri()) else {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            "请求参数不合法",
+        )
+        .into_response();
+    };
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    match word_states::rescue_plan(proxy.as_ref(), &auth_user.id, Some(query.into_config())).await {
+        Ok(plan) => Json(SuccessResponse {
+            success: true,
+            data: plan,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "word state rescue plan failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
 pub async fn stats_overview(State(state): State<AppState>, req: Request<Body>) -> Response {
     let token = crate::auth::extract_token(req.headers());
     let Some(token) = token else {
@@ -304,6 +583,7 @@ pub async fn get_one(State(state): State<AppState>, req: Request<Body>) -> Respo
         return json_error(StatusCode::BAD_REQUEST, "BAD_REQUEST", "wordId is required")
             .into_response();
     }
+    let with_band = include_band(req.uri().query());
 
     let Some(proxy) = state.db_proxy() else {
         return json_error(
@@ -326,7 +606,24 @@ pub async fn get_one(State(state): State<AppState>, req: Request<Body>) -> Respo
         }
     };
 
-    match word_states::get_word_state(proxy.as_ref(), &auth_user.id, &word_id).await {
+    let state_row = word_states::get_word_state(proxy.as_ref(), &auth_user.id, &word_id).await;
+    match state_row {
+        Ok(Some(state_row)) if with_band => {
+            let ids = [word_id.clone()];
+            let mastery_band =
+                match word_states::mastery_bands(proxy.as_ref(), &auth_user.id, &ids).await {
+                    Ok(mut bands) => bands.remove(&word_id),
+                    Err(err) => return handle_service_error(err),
+                };
+            Json(SuccessResponse {
+                success: true,
+                data: StateWithBand {
+                    state: state_row,
+                    mastery_band,
+                },
+            })
+            .into_response()
+        }
         Ok(state_row) => Json(SuccessResponse {
             success: true,
             data: state_row,
@@ -12,6 +12,7 @@ use std::sync::Arc;
 use crate::auth::AuthUser;
 use crate::db::DatabaseProxy;
 use crate::response::json_error;
+use crate::services::wordbook_licenses;
 use crate::state::AppState;
 
 const DEFAULT_CENTER_URL: &str = "https://cdn.jsdelivr.net/gh/Heartcoolman/wordbook-center@main";
@@ -1608,12 +1609,13 @@ async fn check_updates(State(state): State<AppState>, req: Request<Body>) -> Res
 
     let base_url = center_url.trim_end_matches('/');
 
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
         r#"SELECT "id", "name", "sourceUrl", "sourceVersion"
            FROM "word_books"
            WHERE "sourceUrl" IS NOT NULL AND "sourceUrl" <> ''
-             AND ("type" = 'SYSTEM' OR ("type" = 'USER' AND "userId" = $1))"#,
-    )
+             AND {}"#,
+        wordbook_licenses::listed_wordbook_sql(r#""word_books"."#, "$1")
+    ))
     .bind(&user.id)
     .fetch_all(proxy.pool())
     .await;
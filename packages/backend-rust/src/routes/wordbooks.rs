@@ -4,10 +4,13 @@ use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use danci_algo::language::{is_valid_language_code, DEFAULT_GLOSS_LANGUAGE, DEFAULT_LANGUAGE};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
 use crate::response::json_error;
+use crate::services::wordbook_deltas;
+use crate::services::wordbook_licenses::{self, LicenseAccess};
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -35,6 +38,7 @@ struct WordBookResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     user_id: Option<String>,
     is_public: bool,
+    requires_license: bool,
     word_count: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     tags: Option<Vec<String>>,
@@ -46,6 +50,10 @@ struct WordBookResponse {
     source_author: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     imported_at: Option<String>,
+    language_code: String,
+    gloss_language: String,
+    /// Bumped on every content change of the book's words.
+    content_version: i64,
     created_at: String,
     updated_at: String,
 }
@@ -71,6 +79,8 @@ struct CreateWordBookRequest {
     name: Option<String>,
     description: Option<String>,
     cover_image: Option<String>,
+    language_code: Option<String>,
+    gloss_language: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -135,12 +145,18 @@ pub async fn list_user_wordbooks(State(state): State<AppState>, req: Request<Bod
 }
 
 pub async fn list_system_wordbooks(State(state): State<AppState>, req: Request<Body>) -> Response {
-    let (proxy, _user_id, _req) = match authenticate(&state, req).await {
+    let (proxy, user_id, _req) = match authenticate(&state, req).await {
         Ok(value) => value,
         Err(res) => return res,
     };
 
-    let books = match select_word_books(proxy.as_ref(), None, WordBookSelection::SystemOnly).await {
+    let books = match select_word_books(
+        proxy.as_ref(),
+        Some(&user_id),
+        WordBookSelection::SystemOnly,
+    )
+    .await
+    {
         Ok(books) => books,
         Err(err) => {
             tracing::warn!(error = %err, "system wordbooks query failed");
@@ -265,6 +281,21 @@ pub async fn create_wordbook(State(state): State<AppState>, req: Request<Body>)
         None => None,
     };
 
+    let language_code = payload
+        .language_code
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let gloss_language = payload
+        .gloss_language
+        .unwrap_or_else(|| DEFAULT_GLOSS_LANGUAGE.to_string());
+    if !is_valid_language_code(&language_code) || !is_valid_language_code(&gloss_language) {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "语言代码须为2-3位小写字母",
+        )
+        .into_response();
+    }
+
     let token = crate::auth::extract_token(&parts.headers);
     let Some(token) = token else {
         return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
@@ -302,6 +333,8 @@ pub async fn create_wordbook(State(state): State<AppState>, req: Request<Body>)
         &name,
         description.as_deref(),
         cover_image.as_deref(),
+        &language_code,
+        &gloss_language,
         &now_iso,
     )
     .await
@@ -327,12 +360,16 @@ pub async fn create_wordbook(State(state): State<AppState>, req: Request<Body>)
                 r#type: "USER".to_string(),
                 user_id: Some(auth_user.id),
                 is_public: false,
+                requires_license: false,
                 word_count: 0,
                 tags: None,
                 source_url: None,
                 source_version: None,
                 source_author: None,
                 imported_at: None,
+                language_code,
+                gloss_language,
+                content_version: 0,
                 created_at: now_iso.clone(),
                 updated_at: now_iso,
             },
@@ -365,6 +402,9 @@ pub async fn get_wordbook(State(state): State<AppState>, req: Request<Body>) ->
                 return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "无权访问此词书")
                     .into_response();
             }
+            if let Err(res) = enforce_license(proxy.as_ref(), &book, &user_id).await {
+                return res;
+            }
             Json(SuccessResponse {
                 success: true,
                 data: book,
@@ -654,6 +694,9 @@ pub async fn get_wordbook_words(State(state): State<AppState>, req: Request<Body
         return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "无权访问此词书")
             .into_response();
     }
+    if let Err(res) = enforce_license(proxy.as_ref(), &book, &user_id).await {
+        return res;
+    }
 
     let words = if fetch_all {
         match select_all_words_in_word_book(proxy.as_ref(), &word_book_id).await {
@@ -692,6 +735,82 @@ pub async fn get_wordbook_words(State(state): State<AppState>, req: Request<Body
     .into_response()
 }
 
+/// `GET /api/wordbooks/:id/changes?since=N&limit=M`: word changes after
+/// content version `since`, for clients updating a downloaded book.
+pub async fn get_wordbook_changes(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let (proxy, user_id, req) = match authenticate(&state, req).await {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+
+    let path = req.uri().path();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.len() < 3 {
+        return json_error(StatusCode::BAD_REQUEST, "BAD_REQUEST", "请求参数不合法")
+            .into_response();
+    }
+    let word_book_id = segments[2].to_string();
+
+    let query_string = req.uri().query().unwrap_or("");
+    let since = match get_query_param(query_string, "since").map(str::parse::<i64>) {
+        None => 0,
+        Some(Ok(since)) if since >= 0 => since,
+        Some(_) => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                "VALIDATION_ERROR",
+                "since 必须是非负整数",
+            )
+            .into_response()
+        }
+    };
+    let limit = get_query_param(query_string, "limit")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(wordbook_deltas::DEFAULT_LIMIT);
+
+    let book = match select_word_book_by_id(proxy.as_ref(), &word_book_id).await {
+        Ok(Some(book)) => book,
+        Ok(None) => {
+            return json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "词书不存在").into_response()
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "wordbook lookup failed");
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response();
+        }
+    };
+
+    if book.r#type == "USER" && book.user_id.as_deref() != Some(&user_id) {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "无权访问此词书")
+            .into_response();
+    }
+    if let Err(res) = enforce_license(proxy.as_ref(), &book, &user_id).await {
+        return res;
+    }
+
+    match wordbook_deltas::changes_since(proxy.pool(), &word_book_id, since, limit).await {
+        Ok(Some(changes)) => Json(SuccessResponse {
+            success: true,
+            data: changes,
+        })
+        .into_response(),
+        Ok(None) => json_error(StatusCode::NOT_FOUND, "NOT_FOUND", "词书不存在").into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "wordbook changes query failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
 pub async fn add_word_to_wordbook(State(state): State<AppState>, req: Request<Body>) -> Response {
     let (parts, body_bytes) = match split_body(req).await {
         Ok(value) => value,
@@ -1116,58 +1235,104 @@ enum WordBookSelection {
     SystemAndUser,
 }
 
+/// Opening a license-gated wordbook needs a license held by one of the
+/// user's organizations; the first visit takes a seat.
+async fn enforce_license(
+    proxy: &crate::db::DatabaseProxy,
+    book: &WordBookResponse,
+    user_id: &str,
+) -> Result<(), Response> {
+    if !book.requires_license {
+        return Ok(());
+    }
+    match wordbook_licenses::check_access(proxy, &book.id, user_id).await {
+        Ok(LicenseAccess::SeatsExhausted) => Err(json_error(
+            StatusCode::FORBIDDEN,
+            "LICENSE_SEATS_EXHAUSTED",
+            "机构授权席位已满",
+        )
+        .into_response()),
+        Ok(access) if !access.is_allowed() => Err(json_error(
+            StatusCode::FORBIDDEN,
+            "LICENSE_REQUIRED",
+            "该词书需要机构授权",
+        )
+        .into_response()),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            tracing::warn!(error = %err, "wordbook license check failed");
+            Err(json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response())
+        }
+    }
+}
+
 async fn select_word_books(
     proxy: &crate::db::DatabaseProxy,
     user_id: Option<&str>,
     selection: WordBookSelection,
 ) -> Result<Vec<WordBookResponse>, sqlx::Error> {
     let pool = proxy.pool();
+    let access = wordbook_licenses::listed_wordbook_sql("wb.", "$1");
     let (query, binds_user) = match selection {
         WordBookSelection::UserOnly => (
             r#"
             SELECT wb."id", wb."name", wb."description", wb."coverImage",
-                   wb."type"::text as "type", wb."userId", wb."isPublic",
+                   wb."type"::text as "type", wb."userId", wb."isPublic", wb."requiresLicense",
                    wb."tags", wb."sourceUrl", wb."sourceVersion", wb."sourceAuthor", wb."importedAt",
+                   wb."languageCode", wb."glossLanguage", wb."contentVersion",
                    COUNT(w."id") as "wordCount", wb."createdAt", wb."updatedAt"
             FROM "word_books" wb
             LEFT JOIN "words" w ON w."wordBookId" = wb."id"
             WHERE wb."type"::text = 'USER' AND wb."userId" = $1
             GROUP BY wb."id"
             ORDER BY wb."createdAt" DESC
-            "#,
+            "#
+            .to_string(),
             true,
         ),
         WordBookSelection::SystemOnly => (
-            r#"
+            format!(
+                r#"
             SELECT wb."id", wb."name", wb."description", wb."coverImage",
-                   wb."type"::text as "type", wb."userId", wb."isPublic",
+                   wb."type"::text as "type", wb."userId", wb."isPublic", wb."requiresLicense",
                    wb."tags", wb."sourceUrl", wb."sourceVersion", wb."sourceAuthor", wb."importedAt",
+                   wb."languageCode", wb."glossLanguage", wb."contentVersion",
                    COUNT(w."id") as "wordCount", wb."createdAt", wb."updatedAt"
             FROM "word_books" wb
             LEFT JOIN "words" w ON w."wordBookId" = wb."id"
             WHERE wb."type"::text = 'SYSTEM'
+              AND {access}
             GROUP BY wb."id"
             ORDER BY wb."createdAt" DESC
-            "#,
-            false,
+            "#
+            ),
+            true,
         ),
         WordBookSelection::SystemAndUser => (
-            r#"
+            format!(
+                r#"
             SELECT wb."id", wb."name", wb."description", wb."coverImage",
-                   wb."type"::text as "type", wb."userId", wb."isPublic",
+                   wb."type"::text as "type", wb."userId", wb."isPublic", wb."requiresLicense",
                    wb."tags", wb."sourceUrl", wb."sourceVersion", wb."sourceAuthor", wb."importedAt",
+                   wb."languageCode", wb."glossLanguage", wb."contentVersion",
                    COUNT(w."id") as "wordCount", wb."createdAt", wb."updatedAt"
             FROM "word_books" wb
             LEFT JOIN "words" w ON w."wordBookId" = wb."id"
-            WHERE (wb."type"::text = 'SYSTEM') OR (wb."type"::text = 'USER' AND wb."userId" = $1)
+            WHERE {access}
             GROUP BY wb."id"
             ORDER BY wb."type"::text ASC, wb."createdAt" DESC
-            "#,
+            "#
+            ),
             true,
         ),
     };
 
-    let mut query = sqlx::query(query);
+    let mut query = sqlx::query(&query);
     if binds_user {
         query = query.bind(user_id.unwrap_or_default());
     }
@@ -1186,8 +1351,9 @@ async fn select_word_book_by_id(
     let row = sqlx::query(
         r#"
         SELECT wb."id", wb."name", wb."description", wb."coverImage",
-               wb."type"::text as "type", wb."userId", wb."isPublic",
+               wb."type"::text as "type", wb."userId", wb."isPublic", wb."requiresLicense",
                wb."tags", wb."sourceUrl", wb."sourceVersion", wb."sourceAuthor", wb."importedAt",
+               wb."languageCode", wb."glossLanguage", wb."contentVersion",
                COUNT(w."id") as "wordCount", wb."createdAt", wb."updatedAt"
         FROM "word_books" wb
         LEFT JOIN "words" w ON w."wordBookId" = wb."id"
@@ -1308,6 +1474,8 @@ async fn insert_word_book(
     name: &str,
     description: Option<&str>,
     cover_image: Option<&str>,
+    language_code: &str,
+    gloss_language: &str,
     _now_iso: &str,
 ) -> Result<(), sqlx::Error> {
     let pool = proxy.pool();
@@ -1315,8 +1483,9 @@ async fn insert_word_book(
     sqlx::query(
         r#"
         INSERT INTO "word_books"
-          ("id","name","description","type","userId","isPublic","wordCount","coverImage","createdAt","updatedAt")
-        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+          ("id","name","description","type","userId","isPublic","wordCount","coverImage",
+           "languageCode","glossLanguage","createdAt","updatedAt")
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12)
         "#,
     )
     .bind(id)
@@ -1327,6 +1496,8 @@ async fn insert_word_book(
     .bind(false)
     .bind(0_i64)
     .bind(cover_image)
+    .bind(language_code)
+    .bind(gloss_language)
     .bind(now)
     .bind(now)
     .execute(pool)
@@ -1474,6 +1645,7 @@ fn map_postgres_word_book_row(row: &sqlx::postgres::PgRow) -> WordBookResponse {
         r#type: row.try_get("type").unwrap_or_else(|_| "SYSTEM".to_string()),
         user_id: row.try_get::<Option<String>, _>("userId").ok().flatten(),
         is_public: row.try_get::<bool, _>("isPublic").unwrap_or(false),
+        requires_license: row.try_get::<bool, _>("requiresLicense").unwrap_or(false),
         word_count: row.try_get::<i64, _>("wordCount").unwrap_or(0),
         tags: row.try_get::<Option<Vec<String>>, _>("tags").ok().flatten(),
         source_url: row.try_get::<Option<String>, _>("sourceUrl").ok().flatten(),
@@ -1486,6 +1658,13 @@ fn map_postgres_word_book_row(row: &sqlx::postgres::PgRow) -> WordBookResponse {
             .ok()
             .flatten(),
         imported_at: imported_at.map(format_naive_iso),
+        language_code: row
+            .try_get("languageCode")
+            .unwrap_or_else(|_| DEFAULT_LANGUAGE.to_string()),
+        gloss_language: row
+            .try_get("glossLanguage")
+            .unwrap_or_else(|_| DEFAULT_GLOSS_LANGUAGE.to_string()),
+        content_version: row.try_get::<i64, _>("contentVersion").unwrap_or(0),
         created_at: format_naive_iso(created_at),
         updated_at: format_naive_iso(updated_at),
     }
@@ -9,7 +9,11 @@ use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{QueryBuilder, Row};
 
+use crate::core::Pagination;
 use crate::response::json_error;
+use crate::services::example_sentences::{self, MAX_WORDS_PER_REQUEST};
+use crate::services::word_search::{self, MatchField, SearchEngine};
+use crate::services::wordbook_licenses;
 use crate::state::AppState;
 
 #[derive(Serialize)]
@@ -39,6 +43,17 @@ struct WordResponse {
     updated_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     word_book: Option<WordBookSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_match: Option<SearchMatch>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchMatch {
+    engine: SearchEngine,
+    score: f64,
+    field: MatchField,
+    snippet: String,
 }
 
 #[derive(Serialize)]
@@ -151,6 +166,71 @@ pub async fn list_words(State(state): State<AppState>, req: Request<Body>) -> Re
     .into_response()
 }
 
+/// Best example sentence per word for the current user, e.g.
+/// `?wordIds=a,b,c`. Words without examples are omitted.
+pub async fn example_sentences(State(state): State<AppState>, req: Request<Body>) -> Response {
+    let token = crate::auth::extract_token(req.headers());
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "未提供认证令牌")
+            .into_response();
+    };
+
+    let query_string = req.uri().query().unwrap_or("");
+    let mut word_ids: Vec<String> = get_query_param(query_string, "wordIds")
+        .unwrap_or_default()
+        .split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    word_ids.dedup();
+    if word_ids.is_empty() || word_ids.len() > MAX_WORDS_PER_REQUEST {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            "VALIDATION_ERROR",
+            "wordIds 必须包含 1-50 个单词ID",
+        )
+        .into_response();
+    }
+
+    let Some(proxy) = state.db_proxy() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
+            "服务不可用",
+        )
+        .into_response();
+    };
+
+    let auth_user = match crate::auth::verify_request_token(proxy.as_ref(), &token).await {
+        Ok(user) => user,
+        Err(_) => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHORIZED",
+                "认证失败，请重新登录",
+            )
+            .into_response();
+        }
+    };
+
+    match example_sentences::best_sentences(proxy.pool(), &auth_user.id, &word_ids).await {
+        Ok(choices) => Json(SuccessResponse {
+            success: true,
+            data: choices,
+        })
+        .into_response(),
+        Err(err) => {
+            tracing::warn!(error = %err, "example sentence selection failed");
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "服务器内部错误",
+            )
+            .into_response()
+        }
+    }
+}
+
 pub async fn learned_words(State(state): State<AppState>, req: Request<Body>) -> Response {
     let token = crate::auth::extract_token(req.headers());
     let Some(token) = token else {
@@ -249,6 +329,28 @@ pub async fn get_word_by_id(State(state): State<AppState>, req: Request<Body>) -
                 return json_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "无权访问此单词")
                     .into_response();
             }
+            match wordbook_licenses::can_read_book(proxy.pool(), &word.word_book_id, &auth_user.id)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    return json_error(
+                        StatusCode::FORBIDDEN,
+                        "LICENSE_REQUIRED",
+                        "该词书需要机构授权",
+                    )
+                    .into_response()
+                }
+                Err(err) => {
+                    tracing::warn!(error = %err, "word license check failed");
+                    return json_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "INTERNAL_ERROR",
+                        "服务器内部错误",
+                    )
+                    .into_response();
+                }
+            }
 
             Json(SuccessResponse {
                 success: true,
@@ -286,15 +388,17 @@ async fn search_words_inner(state: AppState, req: Request<Body>, strict: bool) -
 
     let query_string = req.uri().query().unwrap_or("");
     let q = get_query_param(query_string, "q").unwrap_or_default();
-    let limit_raw = get_query_param(query_string, "limit").unwrap_or_default();
+    let pagination = match Pagination::<20, 100>::from_uri(req.uri()) {
+        Ok(pagination) => pagination,
+        Err(rejection) => return rejection.into_response(),
+    };
 
     if strict && q.trim().is_empty() {
         return json_error(StatusCode::BAD_REQUEST, "EMPTY_QUERY", "搜索关键词不能为空")
             .into_response();
     }
 
-    let limit = limit_raw.parse::<i64>().ok().unwrap_or(20);
-    if strict && !(1..=100).contains(&limit) {
+    if strict && !pagination.within_bounds() {
         return json_error(
             StatusCode::BAD_REQUEST,
             "INVALID_LIMIT",
@@ -324,18 +428,19 @@ async fn search_words_inner(state: AppState, req: Request<Body>, strict: bool) -
         }
     };
 
-    let words = match select_search_words(proxy.as_ref(), &auth_user.id, &q, limit).await {
-        Ok(words) => words,
-        Err(err) => {
-            tracing::warn!(error = %err, "search words failed");
-            return json_error(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "INTERNAL_ERROR",
-                "服务器内部错误",
-            )
-            .into_response();
-        }
-    };
+    let words =
+        match select_search_words(proxy.as_ref(), &auth_user.id, &q, pagination.limit()).await {
+            Ok(words) => words,
+            Err(err) => {
+                tracing::warn!(error = %err, "search words failed");
+                return json_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "服务器内部错误",
+                )
+                .into_response();
+            }
+        };
 
     Json(SuccessResponse {
         success: true,
@@ -475,6 +580,7 @@ pub async fn create_word(State(state): State<AppState>, req: Request<Body>) -> R
                 created_at: now_iso.clone(),
                 updated_at: now_iso,
                 word_book: None,
+                search_match: None,
             },
         }),
     )
@@ -560,6 +666,8 @@ pub async fn batch_create(State(state): State<AppState>, req: Request<Body>) ->
 
     let mut created = Vec::with_capacity(payload.words.len());
     let mut touched_word_books: HashMap<String, ()> = HashMap::new();
+    let mut seen_entries: std::collections::HashSet<(String, String)> =
+        std::collections::HashSet::new();
 
     for word in payload.words {
         let spelling = word.spelling.trim().to_string();
@@ -585,6 +693,15 @@ pub async fn batch_create(State(state): State<AppState>, req: Request<Body>) ->
             .filter(|id| !id.trim().is_empty())
             .unwrap_or_else(|| default_word_book_id.clone());
 
+        // "run" and "runs" in one import land on the same entry; keep the first.
+        let entry_key = (
+            word_book_id.clone(),
+            crate::services::lemmatizer::dedup_key(&spelling),
+        );
+        if !seen_entries.insert(entry_key) {
+            continue;
+        }
+
         let word_id = uuid::Uuid::new_v4().to_string();
         if let Err(err) = insert_word(
             proxy.as_ref(),
@@ -620,6 +737,7 @@ pub async fn batch_create(State(state): State<AppState>, req: Request<Body>) ->
             created_at: now_iso.clone(),
             updated_at: now_iso.clone(),
             word_book: None,
+            search_match: None,
         });
     }
 
@@ -1017,18 +1135,23 @@ async fn select_selected_word_book_ids(
     proxy: &crate::db::DatabaseProxy,
     user_id: &str,
 ) -> Result<Vec<String>, sqlx::Error> {
+    // A selection saved while a license was valid must stop yielding words
+    // once it lapses.
     let pool = proxy.pool();
-    let row = sqlx::query(
-        r#"SELECT "selectedWordBookIds" FROM "user_study_configs" WHERE "userId" = $1 LIMIT 1"#,
-    )
+    sqlx::query_scalar(&format!(
+        r#"
+        SELECT wb."id"
+        FROM "user_study_configs" c
+        CROSS JOIN LATERAL unnest(c."selectedWordBookIds") WITH ORDINALITY AS sel("id", "pos")
+        JOIN "word_books" wb ON wb."id" = sel."id"
+        WHERE c."userId" = $1 AND {}
+        ORDER BY sel."pos"
+        "#,
+        wordbook_licenses::accessible_wordbook_sql("wb.", "$1")
+    ))
     .bind(user_id)
-    .fetch_optional(pool)
-    .await?;
-    let Some(row) = row else {
-        return Ok(Vec::new());
-    };
-    let ids: Vec<String> = row.try_get("selectedWordBookIds")?;
-    Ok(ids)
+    .fetch_all(pool)
+    .await
 }
 
 async fn select_words_by_word_books(
@@ -1150,92 +1273,50 @@ async fn select_search_words(
         return Ok(Vec::new());
     }
 
-    let search_term = query.trim().to_string();
-    let lower_term = search_term.to_lowercase();
-    let escaped = escape_like(&lower_term);
-    let pattern = format!("%{}%", escaped);
-    let prefix_pattern = format!("{}%", escaped);
-
-    let pool = proxy.pool();
-    let ids: Vec<String> = sqlx::query_scalar(
-        r#"
-        SELECT w."id"
-        FROM "words" w
-        JOIN "word_books" wb ON wb."id" = w."wordBookId"
-        WHERE (wb."type"::text = 'SYSTEM' OR (wb."type"::text = 'USER' AND wb."userId" = $1))
-          AND (
-            lower(w."spelling") LIKE $2 ESCAPE '\\'
-            OR EXISTS (
-              SELECT 1 FROM unnest(w."meanings") m
-              WHERE lower(m) LIKE $2 ESCAPE '\\'
-            )
-          )
-        ORDER BY
-          CASE
-            WHEN lower(w."spelling") = $3 THEN 0
-            WHEN lower(w."spelling") LIKE $4 ESCAPE '\\' THEN 1
-            ELSE 2
-          END,
-          w."spelling" ASC
-        LIMIT $5
-        "#,
-    )
-    .bind(user_id)
-    .bind(&pattern)
-    .bind(&lower_term)
-    .bind(&prefix_pattern)
-    .bind(limit)
-    .fetch_all(pool)
-    .await?;
+    let lemmas = search_lemmas(&query.trim().to_lowercase());
+    let results = word_search::search_words(proxy, user_id, query, &lemmas, limit).await?;
+    let engine = results.engine;
 
-    if ids.is_empty() {
-        return Ok(Vec::new());
-    }
+    Ok(results
+        .hits
+        .into_iter()
+        .map(|hit| WordResponse {
+            id: hit.id,
+            word_book_id: hit.word_book_id,
+            spelling: hit.spelling,
+            phonetic: hit.phonetic,
+            meanings: hit.meanings,
+            examples: hit.examples,
+            audio_url: hit.audio_url,
+            created_at: hit.created_at,
+            updated_at: hit.updated_at,
+            word_book: hit.word_book.map(|wb| WordBookSummary {
+                id: wb.id,
+                name: wb.name,
+                r#type: wb.book_type,
+            }),
+            search_match: Some(SearchMatch {
+                engine,
+                score: hit.score,
+                field: hit.matched_field,
+                snippet: hit.snippet,
+            }),
+        })
+        .collect())
+}
 
-    let mut by_id: HashMap<String, WordResponse> = HashMap::new();
-    let mut qb = QueryBuilder::<sqlx::Postgres>::new(
-        r#"
-        SELECT
-          w."id",
-          w."spelling",
-          w."phonetic",
-          w."meanings",
-          w."examples",
-          w."audioUrl",
-          w."wordBookId",
-          w."createdAt",
-          w."updatedAt",
-          wb."id" as "wbId",
-          wb."name" as "wbName",
-          wb."type"::text as "wbType"
-        FROM "words" w
-        JOIN "word_books" wb ON wb."id" = w."wordBookId"
-        WHERE w."id" IN (
-        "#,
-    );
-    let mut separated = qb.separated(", ");
-    for id in &ids {
-        separated.push_bind(id);
-    }
-    separated.push_unseparated(")");
+/// Dictionary forms of a single-word query, so "ran" also finds "run".
+fn search_lemmas(term: &str) -> Vec<String> {
+    use crate::services::lemmatizer::{english, Lemmatizer};
 
-    let rows = qb.build().fetch_all(pool).await?;
-    for row in rows {
-        let mut word = map_postgres_word_row(&row);
-        let wb_id: String = row.try_get("wbId").unwrap_or_default();
-        if !wb_id.is_empty() {
-            let wb_name: String = row.try_get("wbName").unwrap_or_default();
-            let wb_type: String = row.try_get("wbType").unwrap_or_default();
-            word.word_book = Some(WordBookSummary {
-                id: wb_id,
-                name: wb_name,
-                r#type: wb_type,
-            });
-        }
-        by_id.insert(word.id.clone(), word);
+    if term.split_whitespace().count() != 1 {
+        return Vec::new();
     }
-
-    Ok(ids.into_iter().filter_map(|id| by_id.remove(&id)).collect())
+    english()
+        .candidates(term)
+        .into_iter()
+        .filter(|lemma| lemma != term)
+        .collect()
 }
 
 async fn ensure_default_user_word_book(
@@ -1466,6 +1547,7 @@ fn map_postgres_word_row(row: &sqlx::postgres::PgRow) -> WordResponse {
         created_at: format_naive_iso(created_at),
         updated_at: format_naive_iso(updated_at),
         word_book: None,
+        search_match: None,
     }
 }
 
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
 
@@ -82,3 +83,392 @@ pub async fn seed_test_users(proxy: &DatabaseProxy) {
         }
     }
 }
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFixture {
+    pub email: String,
+    pub username: String,
+    pub password: String,
+    #[serde(default = "default_fixture_role")]
+    pub role: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordFixture {
+    pub spelling: String,
+    #[serde(default)]
+    pub phonetic: String,
+    pub meanings: Vec<String>,
+    #[serde(default)]
+    pub examples: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordBookFixture {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub words: Vec<WordFixture>,
+}
+
+/// Dev/local fixture set. Loaded from `SEED_FIXTURES_PATH` (JSON) when set,
+/// otherwise the built-in sample data is used.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedFixtures {
+    #[serde(default)]
+    pub users: Vec<UserFixture>,
+    #[serde(default)]
+    pub word_books: Vec<WordBookFixture>,
+    /// Sample answer records generated per USER-role account.
+    #[serde(default)]
+    pub sample_records_per_user: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedReport {
+    pub users_created: usize,
+    pub word_books_created: usize,
+    pub words_created: usize,
+    pub answer_records_created: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SeedError {
+    #[error("seeding is disabled; set ALLOW_FIXTURE_SEED=true to enable it")]
+    Disabled,
+    #[error("fixture file error: {0}")]
+    Fixtures(String),
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+}
+
+fn default_fixture_role() -> String {
+    "USER".to_string()
+}
+
+impl Default for SeedFixtures {
+    fn default() -> Self {
+        let words = [
+            ("run", "/rʌn/", "v. 跑；运行", "She runs every morning."),
+            (
+                "abandon",
+                "/əˈbændən/",
+                "v. 放弃；抛弃",
+                "They had to abandon the car.",
+            ),
+            (
+                "benefit",
+                "/ˈbenɪfɪt/",
+                "n. 好处；利益",
+                "The new law will benefit everyone.",
+            ),
+            (
+                "capture",
+                "/ˈkæptʃə(r)/",
+                "v. 捕获；夺取",
+                "The photo captures the moment.",
+            ),
+            (
+                "decline",
+                "/dɪˈklaɪn/",
+                "v. 下降；拒绝",
+                "Sales declined last year.",
+            ),
+            (
+                "efficient",
+                "/ɪˈfɪʃnt/",
+                "adj. 高效的",
+                "This is an efficient method.",
+            ),
+            (
+                "fragile",
+                "/ˈfrædʒaɪl/",
+                "adj. 易碎的；脆弱的",
+                "The glass is fragile.",
+            ),
+            (
+                "genuine",
+                "/ˈdʒenjuɪn/",
+                "adj. 真正的；真诚的",
+                "It is a genuine leather bag.",
+            ),
+            (
+                "hesitate",
+                "/ˈhezɪteɪt/",
+                "v. 犹豫",
+                "Don't hesitate to ask.",
+            ),
+            (
+                "inevitable",
+                "/ɪnˈevɪtəbl/",
+                "adj. 不可避免的",
+                "Change is inevitable.",
+            ),
+        ]
+        .into_iter()
+        .map(|(spelling, phonetic, meaning, example)| WordFixture {
+            spelling: spelling.to_string(),
+            phonetic: phonetic.to_string(),
+            meanings: vec![meaning.to_string()],
+            examples: vec![example.to_string()],
+        })
+        .collect();
+
+        Self {
+            users: TEST_USERS
+                .iter()
+                .map(|user| UserFixture {
+                    email: user.email.to_string(),
+                    username: user.username.to_string(),
+                    password: user.password.to_string(),
+                    role: user.role.to_string(),
+                })
+                .collect(),
+            word_books: vec![WordBookFixture {
+                name: "开发示例词书".to_string(),
+                description: Some("本地开发环境示例数据".to_string()),
+                words,
+            }],
+            sample_records_per_user: 20,
+        }
+    }
+}
+
+impl SeedFixtures {
+    pub fn from_env() -> Result<Self, SeedError> {
+        match std::env::var("SEED_FIXTURES_PATH") {
+            Ok(path) if !path.trim().is_empty() => Self::from_file(path.trim()),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, SeedError> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|err| SeedError::Fixtures(format!("{path}: {err}")))?;
+        serde_json::from_str(&raw).map_err(|err| SeedError::Fixtures(format!("{path}: {err}")))
+    }
+}
+
+/// Fixture seeding creates well-known credentials, so it is an explicit
+/// opt-in (`ALLOW_FIXTURE_SEED=true`) rather than inferred from `NODE_ENV`,
+/// which production deployments do not necessarily set.
+pub fn seeding_allowed() -> bool {
+    opt_in(std::env::var("ALLOW_FIXTURE_SEED").ok().as_deref())
+}
+
+fn opt_in(value: Option<&str>) -> bool {
+    value.is_some_and(|v| v == "true" || v == "1")
+}
+
+/// Insert the fixture set. Every row gets a deterministic id derived from its
+/// natural key, so running the seeder repeatedly only fills in what is missing.
+pub async fn seed_fixtures(
+    proxy: &DatabaseProxy,
+    fixtures: &SeedFixtures,
+) -> Result<SeedReport, SeedError> {
+    if !seeding_allowed() {
+        return Err(SeedError::Disabled);
+    }
+
+    let pool = proxy.pool();
+    let now = chrono::Utc::now().naive_utc();
+    let mut report = SeedReport::default();
+    let mut learner_ids = Vec::new();
+
+    for user in &fixtures.users {
+        let existing: Option<String> =
+            sqlx::query_scalar(r#"SELECT "id" FROM "users" WHERE "email" = $1"#)
+                .bind(&user.email)
+                .fetch_optional(pool)
+                .await?;
+        let user_id = match existing {
+            Some(id) => id,
+            None => {
+                let password_hash = bcrypt::hash(&user.password, 10)
+                    .map_err(|err| SeedError::Fixtures(err.to_string()))?;
+                let user_id = fixture_id(&["user", &user.email]);
+                sqlx::query(
+                    r#"
+                    INSERT INTO "users" ("id", "email", "passwordHash", "username", "role", "updatedAt")
+                    VALUES ($1, $2, $3, $4, $5::"UserRole", $6)
+                    "#,
+                )
+                .bind(&user_id)
+                .bind(&user.email)
+                .bind(&password_hash)
+                .bind(&user.username)
+                .bind(&user.role)
+                .bind(now)
+                .execute(pool)
+                .await?;
+                report.users_created += 1;
+                user_id
+            }
+        };
+        if user.role == "USER" {
+            learner_ids.push(user_id);
+        }
+    }
+
+    let mut word_ids = Vec::new();
+    for book in &fixtures.word_books {
+        let book_id = fixture_id(&["word_book", &book.name]);
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO "word_books"
+              ("id","name","description","type","isPublic","wordCount","createdAt","updatedAt")
+            VALUES ($1,$2,$3,'SYSTEM'::"WordBookType",true,0,$4,$4)
+            ON CONFLICT ("id") DO NOTHING
+            "#,
+        )
+        .bind(&book_id)
+        .bind(&book.name)
+        .bind(&book.description)
+        .bind(now)
+        .execute(pool)
+        .await?;
+        report.word_books_created += inserted.rows_affected() as usize;
+
+        for word in &book.words {
+            // A word already in the book (seeded earlier or added by hand) keeps
+            // its own id; sample records must point at the row that exists.
+            let row = sqlx::query(
+                r#"
+                WITH inserted AS (
+                  INSERT INTO "words"
+                    ("id","spelling","phonetic","meanings","examples","wordBookId","createdAt","updatedAt")
+                  VALUES ($1,$2,$3,$4,$5,$6,$7,$7)
+                  ON CONFLICT ("wordBookId","spelling") DO NOTHING
+                  RETURNING "id"
+                )
+                SELECT "id", true AS "created" FROM inserted
+                UNION ALL
+                SELECT "id", false AS "created" FROM "words"
+                WHERE "wordBookId" = $6 AND "spelling" = $2
+                  AND NOT EXISTS (SELECT 1 FROM inserted)
+                "#,
+            )
+            .bind(fixture_id(&["word", &book.name, &word.spelling]))
+            .bind(&word.spelling)
+            .bind(&word.phonetic)
+            .bind(&word.meanings)
+            .bind(&word.examples)
+            .bind(&book_id)
+            .bind(now)
+            .fetch_one(pool)
+            .await?;
+            let word_id: String = row.try_get("id")?;
+            if row.try_get::<bool, _>("created")? {
+                report.words_created += 1;
+            }
+            word_ids.push((word_id, word.meanings.first().cloned().unwrap_or_default()));
+        }
+
+        sqlx::query(
+            r#"UPDATE "word_books" SET "wordCount" = (SELECT COUNT(*) FROM "words" WHERE "wordBookId" = $1) WHERE "id" = $1"#,
+        )
+        .bind(&book_id)
+        .execute(pool)
+        .await?;
+    }
+
+    if !word_ids.is_empty() {
+        for user_id in &learner_ids {
+            for i in 0..fixtures.sample_records_per_user {
+                let (word_id, meaning) = &word_ids[i % word_ids.len()];
+                let record_id = fixture_id(&["answer_record", user_id, &i.to_string()]);
+                let is_correct = i % 4 != 3;
+                let timestamp = now - chrono::Duration::hours((i as i64 + 1) * 6);
+                let selected = if is_correct { meaning.as_str() } else { "" };
+                let inserted = sqlx::query(
+                    r#"
+                    INSERT INTO "answer_records"
+                      ("id","userId","wordId","selectedAnswer","correctAnswer","isCorrect","timestamp","responseTime","dwellTime")
+                    SELECT $1,$2,$3,$4,$5,$6,$7,$8,$9
+                    WHERE NOT EXISTS (SELECT 1 FROM "answer_records" WHERE "id" = $1)
+                    "#,
+                )
+                .bind(&record_id)
+                .bind(user_id)
+                .bind(word_id)
+                .bind(selected)
+                .bind(meaning)
+                .bind(is_correct)
+                .bind(timestamp)
+                .bind(1500 + (i as i32 % 5) * 700)
+                .bind(3000 + (i as i32 % 7) * 500)
+                .execute(pool)
+                .await?;
+                report.answer_records_created += inserted.rows_affected() as usize;
+            }
+        }
+    }
+
+    tracing::info!(
+        users = report.users_created,
+        word_books = report.word_books_created,
+        words = report.words_created,
+        answer_records = report.answer_records_created,
+        "dev fixtures seeded"
+    );
+    Ok(report)
+}
+
+/// Stable UUID (version 4 layout) from a fixture's natural key.
+fn fixture_id(parts: &[&str]) -> String {
+    fn fnv1a(seed: u64, parts: &[&str]) -> u64 {
+        let mut hash = seed;
+        for part in parts {
+            for byte in part.bytes().chain(std::iter::once(0xff)) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+    let high = fnv1a(0xcbf29ce484222325, parts);
+    let low = fnv1a(0x84222325cbf29ce4, parts);
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&high.to_be_bytes());
+    bytes[8..].copy_from_slice(&low.to_be_bytes());
+    uuid::Builder::from_random_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixture_ids_are_stable_uuids() {
+        let a = fixture_id(&["word", "book", "run"]);
+        assert_eq!(a, fixture_id(&["word", "book", "run"]));
+        assert_ne!(a, fixture_id(&["word", "bookr", "un"]));
+        assert!(Uuid::parse_str(&a).is_ok());
+    }
+
+    #[test]
+    fn seeding_requires_explicit_opt_in() {
+        assert!(!opt_in(None));
+        assert!(!opt_in(Some("")));
+        assert!(!opt_in(Some("yes please")));
+        assert!(opt_in(Some("true")));
+        assert!(opt_in(Some("1")));
+    }
+
+    #[test]
+    fn fixtures_parse_with_defaults() {
+        let raw = r#"{"users":[{"email":"a@b.c","username":"a","password":"x"}],"wordBooks":[{"name":"b","words":[{"spelling":"run","meanings":["跑"]}]}]}"#;
+        let fixtures: SeedFixtures = serde_json::from_str(raw).unwrap();
+        assert_eq!(fixtures.users[0].role, "USER");
+        assert_eq!(fixtures.word_books[0].words[0].phonetic, "");
+        assert_eq!(fixtures.sample_records_per_user, 0);
+        assert!(!SeedFixtures::default().word_books.is_empty());
+    }
+}
@@ -4,7 +4,13 @@ use chrono::{NaiveDateTime, Utc};
 use serde::Serialize;
 use sqlx::{QueryBuilder, Row};
 
+use crate::core::SortField;
+pub use crate::core::SortOrder;
 use crate::db::DatabaseProxy;
+use crate::services::stats_rollup::{self, StatsFreshness};
+
+/// Window for counting a user as active on the dashboard.
+const ACTIVE_USER_DAYS: i64 = 7;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AdminError {
@@ -150,7 +156,7 @@ pub struct UserDetailedStatistics {
 pub struct UserWordsParams {
     pub page: i64,
     pub page_size: i64,
-    pub sort_by: Option<UserWordsSortBy>,
+    pub sort_by: UserWordsSortBy,
     pub sort_order: SortOrder,
     pub state: Option<String>,
     pub search: Option<String>,
@@ -159,28 +165,6 @@ pub struct UserWordsParams {
     pub min_accuracy: Option<f64>,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum SortOrder {
-    Asc,
-    Desc,
-}
-
-impl SortOrder {
-    pub fn from_query(value: Option<&str>) -> Self {
-        match value.unwrap_or("desc").trim().to_ascii_lowercase().as_str() {
-            "asc" => SortOrder::Asc,
-            _ => SortOrder::Desc,
-        }
-    }
-
-    fn sql(self) -> &'static str {
-        match self {
-            SortOrder::Asc => "ASC",
-            SortOrder::Desc => "DESC",
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub enum UserWordsSortBy {
     UpdatedAt,
@@ -192,20 +176,17 @@ pub enum UserWordsSortBy {
     Accuracy,
 }
 
-impl UserWordsSortBy {
-    pub fn from_query(value: Option<&str>) -> Option<Self> {
-        let raw = value?.trim().to_ascii_lowercase();
-        match raw.as_str() {
-            "reviewcount" | "review_count" => Some(UserWordsSortBy::ReviewCount),
-            "lastreview" | "last_review" => Some(UserWordsSortBy::LastReview),
-            "spelling" => Some(UserWordsSortBy::Spelling),
-            "masterylevel" | "mastery_level" => Some(UserWordsSortBy::MasteryLevel),
-            "score" => Some(UserWordsSortBy::Score),
-            "accuracy" => Some(UserWordsSortBy::Accuracy),
-            "updatedat" | "updated_at" => Some(UserWordsSortBy::UpdatedAt),
-            _ => None,
-        }
-    }
+impl SortField for UserWordsSortBy {
+    const FIELDS: &'static [(&'static str, Self)] = &[
+        ("updatedAt", UserWordsSortBy::UpdatedAt),
+        ("reviewCount", UserWordsSortBy::ReviewCount),
+        ("lastReview", UserWordsSortBy::LastReview),
+        ("spelling", UserWordsSortBy::Spelling),
+        ("masteryLevel", UserWordsSortBy::MasteryLevel),
+        ("score", UserWordsSortBy::Score),
+        ("accuracy", UserWordsSortBy::Accuracy),
+    ];
+    const DEFAULT: Self = UserWordsSortBy::UpdatedAt;
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -276,15 +257,13 @@ pub enum DecisionSortBy {
     Duration,
 }
 
-impl DecisionSortBy {
-    pub fn from_query(value: Option<&str>) -> Self {
-        let raw = value.unwrap_or("timestamp").trim().to_ascii_lowercase();
-        match raw.as_str() {
-            "confidence" => DecisionSortBy::Confidence,
-            "duration" => DecisionSortBy::Duration,
-            _ => DecisionSortBy::Timestamp,
-        }
-    }
+impl SortField for DecisionSortBy {
+    const FIELDS: &'static [(&'static str, Self)] = &[
+        ("timestamp", DecisionSortBy::Timestamp),
+        ("confidence", DecisionSortBy::Confidence),
+        ("duration", DecisionSortBy::Duration),
+    ];
+    const DEFAULT: Self = DecisionSortBy::Timestamp;
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -339,6 +318,7 @@ pub struct SystemStatistics {
     pub user_word_books: i64,
     pub total_words: i64,
     pub total_records: i64,
+    pub freshness: StatsFreshness,
 }
 
 pub async fn list_users(
@@ -571,9 +551,11 @@ pub async fn get_user_decisions(
     })
 }
 
+/// Served from a read replica when one is within the staleness tolerance.
 pub async fn get_system_statistics(proxy: &DatabaseProxy) -> Result<SystemStatistics, AdminError> {
-    let pool = proxy.pool();
-    select_system_statistics_pg(pool).await
+    Ok(proxy
+        .read(|pool| async move { select_system_statistics_pg(&pool).await })
+        .await?)
 }
 
 fn normalize_search(search: Option<&str>) -> Option<String> {
@@ -964,8 +946,7 @@ async fn select_user_words_pg(
     limit: i64,
     offset: i64,
 ) -> Result<Vec<UserWordItem>, AdminError> {
-    let sort_by = params.sort_by.unwrap_or(UserWordsSortBy::UpdatedAt);
-    let order_by = match sort_by {
+    let order_by = match params.sort_by {
         UserWordsSortBy::ReviewCount => r#"wls."reviewCount""#,
         UserWordsSortBy::LastReview => r#"wls."lastReviewDate""#,
         UserWordsSortBy::Spelling => r#"w."spelling""#,
@@ -1061,7 +1042,7 @@ fn map_user_word_pg(row: sqlx::postgres::PgRow) -> UserWordItem {
     }
 }
 
-async fn select_system_statistics_pg(pool: &sqlx::PgPool) -> Result<SystemStatistics, AdminError> {
+async fn select_system_statistics_pg(pool: &sqlx::PgPool) -> Result<SystemStatistics, sqlx::Error> {
     let row = sqlx::query(
         r#"
         SELECT
@@ -1069,39 +1050,55 @@ async fn select_system_statistics_pg(pool: &sqlx::PgPool) -> Result<SystemStatis
           (SELECT COUNT(*) FROM "word_books") as "totalWordBooks",
           (SELECT COUNT(*) FROM "word_books" WHERE "type" = 'SYSTEM') as "systemWordBooks",
           (SELECT COUNT(*) FROM "word_books" WHERE "type" = 'USER') as "userWordBooks",
-          (SELECT COUNT(*) FROM "words") as "totalWords",
-          (SELECT COUNT(*) FROM "answer_records") as "totalRecords"
+          (SELECT COUNT(*) FROM "words") as "totalWords"
         "#,
     )
     .fetch_one(pool)
     .await?;
 
-    let total_users = row.try_get::<i64, _>("totalUsers").unwrap_or(0);
-    let seven_days_ago = Utc::now() - chrono::Duration::days(7);
+    let watermark = stats_rollup::load_watermark(pool, stats_rollup::USER_DAILY_STATS).await?;
+    let (totals, freshness) = match watermark {
+        Some(watermark) => (
+            stats_rollup::system_totals(pool, ACTIVE_USER_DAYS).await?,
+            watermark.freshness(Utc::now().naive_utc()),
+        ),
+        None => (
+            select_live_record_totals_pg(pool).await?,
+            StatsFreshness::live(),
+        ),
+    };
+
+    Ok(SystemStatistics {
+        total_users: row.try_get::<i64, _>("totalUsers").unwrap_or(0),
+        active_users: totals.active_users,
+        total_word_books: row.try_get::<i64, _>("totalWordBooks").unwrap_or(0),
+        system_word_books: row.try_get::<i64, _>("systemWordBooks").unwrap_or(0),
+        user_word_books: row.try_get::<i64, _>("userWordBooks").unwrap_or(0),
+        total_words: row.try_get::<i64, _>("totalWords").unwrap_or(0),
+        total_records: totals.total_records,
+        freshness,
+    })
+}
 
-    let active_users_row = sqlx::query(
+async fn select_live_record_totals_pg(
+    pool: &sqlx::PgPool,
+) -> Result<stats_rollup::SystemTotals, sqlx::Error> {
+    let since = Utc::now() - chrono::Duration::days(ACTIVE_USER_DAYS);
+    let row = sqlx::query(
         r#"
-        SELECT COUNT(DISTINCT "userId") as "activeUsers"
+        SELECT
+          COUNT(*) as "totalRecords",
+          COUNT(DISTINCT "userId") FILTER (WHERE "timestamp" >= $1) as "activeUsers"
         FROM "answer_records"
-        WHERE "timestamp" >= $1
         "#,
     )
-    .bind(seven_days_ago.naive_utc())
+    .bind(since.naive_utc())
     .fetch_one(pool)
     .await?;
 
-    let active_users = active_users_row
-        .try_get::<i64, _>("activeUsers")
-        .unwrap_or(0);
-
-    Ok(SystemStatistics {
-        total_users,
-        active_users,
-        total_word_books: row.try_get::<i64, _>("totalWordBooks").unwrap_or(0),
-        system_word_books: row.try_get::<i64, _>("systemWordBooks").unwrap_or(0),
-        user_word_books: row.try_get::<i64, _>("userWordBooks").unwrap_or(0),
-        total_words: row.try_get::<i64, _>("totalWords").unwrap_or(0),
+    Ok(stats_rollup::SystemTotals {
         total_records: row.try_get::<i64, _>("totalRecords").unwrap_or(0),
+        active_users: row.try_get::<i64, _>("activeUsers").unwrap_or(0),
     })
 }
 
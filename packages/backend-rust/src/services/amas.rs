@@ -45,10 +45,10 @@ pub fn map_difficulty_level(level: &str) -> DifficultyRange {
     }
 }
 
+/// Difficulty under the default (English) weights; see
+/// `language_params::DifficultyScorer` for per-language scoring.
 pub fn compute_new_word_difficulty(spelling: &str, meaning_count: usize) -> f64 {
-    let length_factor = (spelling.chars().count() as f64 / 15.0).min(1.0);
-    let meaning_factor = (meaning_count as f64 / 5.0).min(1.0);
-    length_factor * 0.6 + meaning_factor * 0.4
+    danci_algo::DifficultyWeights::default().score(spelling, meaning_count)
 }
 
 // ========== Service types ==========
@@ -743,5 +743,12 @@ pub async fn reset_user(proxy: &DatabaseProxy, user_id: &str) -> Result<(), Stri
         .await
         .map_err(|e| format!("写入失败: {e}"))?;
 
+    // The data key goes with the snapshots; a fresh one is issued on next save.
+    sqlx::query(r#"DELETE FROM "user_data_keys" WHERE "userId" = $1"#)
+        .bind(user_id)
+        .execute(proxy.pool())
+        .await
+        .map_err(|e| format!("写入失败: {e}"))?;
+
     Ok(())
 }
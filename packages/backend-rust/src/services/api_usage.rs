@@ -0,0 +1,376 @@
+//! Per-user API usage counters.
+//!
+//! `middleware::usage` accumulates requests per user, UTC day, path class and
+//! status in memory and merges them into `api_usage_daily` about once a
+//! minute. Support reads the last days back through the admin activity
+//! endpoint, with counters not yet flushed by this instance merged in.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+/// Days kept in `api_usage_daily`.
+pub const RETENTION_DAYS: i64 = 30;
+/// Days covered by the activity report.
+pub const ACTIVITY_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UsageKey {
+    pub user_id: String,
+    pub day: NaiveDate,
+    pub path_class: String,
+    pub status: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UsageCounters {
+    pub requests: i64,
+    pub request_bytes: i64,
+    pub response_bytes: i64,
+    pub total_latency_ms: i64,
+    pub last_seen_at: NaiveDateTime,
+}
+
+impl UsageCounters {
+    pub fn merge(&mut self, other: &UsageCounters) {
+        self.requests += other.requests;
+        self.request_bytes += other.request_bytes;
+        self.response_bytes += other.response_bytes;
+        self.total_latency_ms += other.total_latency_ms;
+        self.last_seen_at = self.last_seen_at.max(other.last_seen_at);
+    }
+}
+
+/// Adds the counters to the stored rows in one statement.
+pub async fn flush(
+    pool: &PgPool,
+    entries: &[(UsageKey, UsageCounters)],
+) -> Result<(), sqlx::Error> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut user_ids = Vec::with_capacity(entries.len());
+    let mut days = Vec::with_capacity(entries.len());
+    let mut classes = Vec::with_capacity(entries.len());
+    let mut statuses = Vec::with_capacity(entries.len());
+    let mut requests = Vec::with_capacity(entries.len());
+    let mut request_bytes = Vec::with_capacity(entries.len());
+    let mut response_bytes = Vec::with_capacity(entries.len());
+    let mut latency = Vec::with_capacity(entries.len());
+    let mut last_seen = Vec::with_capacity(entries.len());
+    for (key, counters) in entries {
+        user_ids.push(key.user_id.clone());
+        days.push(key.day);
+        classes.push(key.path_class.clone());
+        statuses.push(i32::from(key.status));
+        requests.push(counters.requests);
+        request_bytes.push(counters.request_bytes);
+        response_bytes.push(counters.response_bytes);
+        latency.push(counters.total_latency_ms);
+        last_seen.push(counters.last_seen_at);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO "api_usage_daily"
+            ("userId","day","pathClass","status","requests","requestBytes","responseBytes","totalLatencyMs","lastSeenAt")
+        SELECT * FROM UNNEST(
+            $1::text[], $2::date[], $3::text[], $4::int[],
+            $5::bigint[], $6::bigint[], $7::bigint[], $8::bigint[], $9::timestamp[]
+        )
+        ON CONFLICT ("userId","day","pathClass","status") DO UPDATE SET
+            "requests" = "api_usage_daily"."requests" + EXCLUDED."requests",
+            "requestBytes" = "api_usage_daily"."requestBytes" + EXCLUDED."requestBytes",
+            "responseBytes" = "api_usage_daily"."responseBytes" + EXCLUDED."responseBytes",
+            "totalLatencyMs" = "api_usage_daily"."totalLatencyMs" + EXCLUDED."totalLatencyMs",
+            "lastSeenAt" = GREATEST("api_usage_daily"."lastSeenAt", EXCLUDED."lastSeenAt")
+        "#,
+    )
+    .bind(&user_ids)
+    .bind(&days)
+    .bind(&classes)
+    .bind(&statuses)
+    .bind(&requests)
+    .bind(&request_bytes)
+    .bind(&response_bytes)
+    .bind(&latency)
+    .bind(&last_seen)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Drops rows older than [`RETENTION_DAYS`].
+pub async fn prune(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let cutoff = Utc::now().date_naive() - Duration::days(RETENTION_DAYS);
+    let result = sqlx::query(r#"DELETE FROM "api_usage_daily" WHERE "day" < $1"#)
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub requests: i64,
+    pub errors: i64,
+    pub client_errors: i64,
+    pub server_errors: i64,
+    pub request_bytes: i64,
+    pub response_bytes: i64,
+    pub avg_latency_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyUsage {
+    pub date: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathClassUsage {
+    pub path_class: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorBreakdown {
+    pub path_class: String,
+    pub status: u16,
+    pub count: i64,
+    pub last_seen_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserActivity {
+    pub user_id: String,
+    pub from: String,
+    pub to: String,
+    pub last_seen_at: Option<String>,
+    pub totals: UsageTotals,
+    /// One entry per day of the window, oldest first, including idle days.
+    pub days: Vec<DailyUsage>,
+    /// Busiest path classes first.
+    pub path_classes: Vec<PathClassUsage>,
+    /// Failing path class / status pairs, most frequent first.
+    pub errors: Vec<ErrorBreakdown>,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    totals: UsageTotals,
+    latency_ms: i64,
+}
+
+impl Accumulator {
+    fn add(&mut self, status: u16, counters: &UsageCounters) {
+        let t = &mut self.totals;
+        t.requests += counters.requests;
+        if status >= 400 {
+            t.errors += counters.requests;
+        }
+        if (400..500).contains(&status) {
+            t.client_errors += counters.requests;
+        }
+        if status >= 500 {
+            t.server_errors += counters.requests;
+        }
+        t.request_bytes += counters.request_bytes;
+        t.response_bytes += counters.response_bytes;
+        self.latency_ms += counters.total_latency_ms;
+    }
+
+    fn finish(mut self) -> UsageTotals {
+        if self.totals.requests > 0 {
+            self.totals.avg_latency_ms = self.latency_ms as f64 / self.totals.requests as f64;
+        }
+        self.totals
+    }
+}
+
+/// Builds the report for `from..=to` out of stored and unflushed counters.
+pub fn summarize(
+    user_id: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    entries: &[(UsageKey, UsageCounters)],
+) -> UserActivity {
+    let mut totals = Accumulator::default();
+    let mut by_day: BTreeMap<NaiveDate, Accumulator> = BTreeMap::new();
+    let mut by_class: HashMap<&str, Accumulator> = HashMap::new();
+    let mut errors: HashMap<(&str, u16), (i64, NaiveDateTime)> = HashMap::new();
+    let mut last_seen: Option<NaiveDateTime> = None;
+
+    for (key, counters) in entries {
+        if key.user_id != user_id || key.day < from || key.day > to {
+            continue;
+        }
+        totals.add(key.status, counters);
+        by_day.entry(key.day).or_default().add(key.status, counters);
+        by_class
+            .entry(key.path_class.as_str())
+            .or_default()
+            .add(key.status, counters);
+        if key.status >= 400 {
+            let entry = errors
+                .entry((key.path_class.as_str(), key.status))
+                .or_insert((0, counters.last_seen_at));
+            entry.0 += counters.requests;
+            entry.1 = entry.1.max(counters.last_seen_at);
+        }
+        last_seen = Some(last_seen.map_or(counters.last_seen_at, |t| t.max(counters.last_seen_at)));
+    }
+
+    let days = from
+        .iter_days()
+        .take_while(|day| *day <= to)
+        .map(|day| DailyUsage {
+            date: day.to_string(),
+            totals: by_day
+                .remove(&day)
+                .map(Accumulator::finish)
+                .unwrap_or_default(),
+        })
+        .collect();
+    let mut path_classes: Vec<PathClassUsage> = by_class
+        .into_iter()
+        .map(|(class, acc)| PathClassUsage {
+            path_class: class.to_string(),
+            totals: acc.finish(),
+        })
+        .collect();
+    path_classes.sort_by(|a, b| {
+        b.totals
+            .requests
+            .cmp(&a.totals.requests)
+            .then_with(|| a.path_class.cmp(&b.path_class))
+    });
+    let mut errors: Vec<ErrorBreakdown> = errors
+        .into_iter()
+        .map(|((class, status), (count, seen))| ErrorBreakdown {
+            path_class: class.to_string(),
+            status,
+            count,
+            last_seen_at: seen.and_utc().to_rfc3339(),
+        })
+        .collect();
+    errors.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.path_class.cmp(&b.path_class))
+            .then_with(|| a.status.cmp(&b.status))
+    });
+
+    UserActivity {
+        user_id: user_id.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        last_seen_at: last_seen.map(|t| t.and_utc().to_rfc3339()),
+        totals: totals.finish(),
+        days,
+        path_classes,
+        errors,
+    }
+}
+
+/// The last [`ACTIVITY_DAYS`] days of a user's usage; `pending` are counters
+/// not yet flushed.
+pub async fn user_activity(
+    pool: &PgPool,
+    user_id: &str,
+    pending: Vec<(UsageKey, UsageCounters)>,
+) -> Result<UserActivity, sqlx::Error> {
+    let to = Utc::now().date_naive();
+    let from = to - Duration::days(ACTIVITY_DAYS - 1);
+    let rows = sqlx::query(
+        r#"
+        SELECT "day","pathClass","status","requests","requestBytes","responseBytes","totalLatencyMs","lastSeenAt"
+        FROM "api_usage_daily"
+        WHERE "userId" = $1 AND "day" >= $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .fetch_all(pool)
+    .await?;
+
+    let mut entries = pending;
+    for row in rows {
+        entries.push((
+            UsageKey {
+                user_id: user_id.to_string(),
+                day: row.try_get("day")?,
+                path_class: row.try_get("pathClass")?,
+                status: u16::try_from(row.try_get::<i32, _>("status")?).unwrap_or(0),
+            },
+            UsageCounters {
+                requests: row.try_get("requests")?,
+                request_bytes: row.try_get("requestBytes")?,
+                response_bytes: row.try_get("responseBytes")?,
+                total_latency_ms: row.try_get("totalLatencyMs")?,
+                last_seen_at: row.try_get("lastSeenAt")?,
+            },
+        ));
+    }
+    Ok(summarize(user_id, from, to, &entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(day: NaiveDate, class: &str, status: u16, requests: i64) -> (UsageKey, UsageCounters) {
+        (
+            UsageKey {
+                user_id: "u1".to_string(),
+                day,
+                path_class: class.to_string(),
+                status,
+            },
+            UsageCounters {
+                requests,
+                request_bytes: requests * 10,
+                response_bytes: requests * 100,
+                total_latency_ms: requests * 20,
+                last_seen_at: day.and_hms_opt(12, 0, 0).unwrap(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_summarize_breaks_down_errors_and_fills_idle_days() {
+        let to = NaiveDate::from_ymd_opt(2025, 3, 7).unwrap();
+        let from = to - Duration::days(6);
+        let entries = vec![
+            entry(to, "records", 200, 8),
+            entry(to, "records", 409, 2),
+            entry(to - Duration::days(2), "sync", 500, 3),
+            entry(to - Duration::days(2), "records", 409, 1),
+            // outside the window
+            entry(from - Duration::days(1), "records", 500, 50),
+        ];
+        let activity = summarize("u1", from, to, &entries);
+
+        assert_eq!(activity.days.len(), 7);
+        assert_eq!(activity.days[6].totals.requests, 10);
+        assert_eq!(activity.days[0].totals.requests, 0);
+        assert_eq!(activity.totals.requests, 14);
+        assert_eq!(activity.totals.client_errors, 3);
+        assert_eq!(activity.totals.server_errors, 3);
+        assert_eq!(activity.totals.response_bytes, 1400);
+        assert!((activity.totals.avg_latency_ms - 20.0).abs() < 1e-9);
+        assert_eq!(activity.path_classes[0].path_class, "records");
+        assert_eq!(activity.errors[0].status, 409);
+        assert_eq!(activity.errors[0].count, 3);
+        assert_eq!(activity.errors.len(), 2);
+    }
+}
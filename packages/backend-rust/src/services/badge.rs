@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 
 use crate::db::DatabaseProxy;
+use crate::services::vacation;
+use crate::services::webhooks::{self, WebhookEventType};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -194,6 +196,23 @@ pub async fn check_and_award_badges(
             .await
             .map_err(|e| format!("写入失败: {e}"))?;
 
+            if BadgeConditionType::parse(&badge.condition.condition_type)
+                == BadgeConditionType::Streak
+            {
+                webhooks::emit_logged(
+                    proxy,
+                    user_id,
+                    WebhookEventType::StreakMilestone,
+                    serde_json::json!({
+                        "days": badge.condition.value as i64,
+                        "currentStreak": stats.consecutive_days,
+                        "badgeId": badge.id,
+                        "tier": badge.tier,
+                    }),
+                )
+                .await;
+            }
+
             new_badges.push(NewBadgeResult {
                 badge: UserBadge {
                     id: user_badge_id,
@@ -547,48 +566,30 @@ fn get_current_value_for_condition(condition: &BadgeCondition, stats: &UserStats
 
 async fn calculate_consecutive_days(pool: &PgPool, user_id: &str) -> Result<i64, String> {
     let rows = sqlx::query(
-        r#"SELECT DISTINCT DATE("timestamp") as d FROM "answer_records" WHERE "userId" = $1 ORDER BY d DESC"#,
+        r#"SELECT DISTINCT DATE("timestamp") as d FROM "answer_records" WHERE "userId" = $1"#,
     )
     .bind(user_id)
     .fetch_all(pool)
     .await
     .map_err(|e| format!("查询失败: {e}"))?;
 
-    let dates: Vec<String> = rows
+    let dates: HashSet<chrono::NaiveDate> = rows
         .iter()
-        .filter_map(|r| {
-            r.try_get::<chrono::NaiveDate, _>("d")
-                .ok()
-                .map(|d| d.to_string())
-        })
+        .filter_map(|r| r.try_get::<chrono::NaiveDate, _>("d").ok())
         .collect();
-
     if dates.is_empty() {
         return Ok(0);
     }
 
-    let today = Utc::now().date_naive().to_string();
-    let yesterday = (Utc::now() - Duration::days(1)).date_naive().to_string();
-
-    if dates[0] != today && dates[0] != yesterday {
-        return Ok(0);
-    }
-
-    let mut consecutive = 1i64;
-    for i in 1..dates.len() {
-        let current = chrono::NaiveDate::parse_from_str(&dates[i - 1], "%Y-%m-%d").ok();
-        let prev = chrono::NaiveDate::parse_from_str(&dates[i], "%Y-%m-%d").ok();
-
-        if let (Some(c), Some(p)) = (current, prev) {
-            if (c - p).num_days() == 1 {
-                consecutive += 1;
-            } else {
-                break;
-            }
-        }
-    }
-
-    Ok(consecutive)
+    let vacations = vacation::list_periods(pool, user_id)
+        .await
+        .map_err(|e| format!("查询失败: {e}"))?;
+    Ok(vacation::consecutive_days(
+        &dates,
+        &vacations,
+        Utc::now().date_naive(),
+        false,
+    ))
 }
 
 async fn calculate_total_words_learned(pool: &PgPool, user_id: &str) -> Result<i64, String> {
@@ -0,0 +1,337 @@
+//! Cross-book deduplication of the review queue.
+//!
+//! The same headword imported into two word books gets two `words` rows, and
+//! each row has its own learning state. Sessions are composed on a canonical
+//! key so the word is asked once, and the answer is written back to every
+//! copy in the user's selected books so each book gets credit for it.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sqlx::Row;
+
+use crate::db::DatabaseProxy;
+use crate::services::learning_state::{upsert_word_state, WordState, WordStateUpdateData};
+use crate::services::webhooks::{self, WebhookEventType};
+
+/// Canonical key for a headword: trimmed, lowercased, inner whitespace collapsed.
+pub fn canonical_word_key(spelling: &str) -> String {
+    spelling
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Keep the first item per canonical key, folding later duplicates into it
+/// with `merge`. Callers sort by preference first.
+pub fn dedup_by_canonical<T>(
+    items: Vec<T>,
+    spelling: impl Fn(&T) -> &str,
+    mut merge: impl FnMut(&mut T, T),
+) -> Vec<T> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut out: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        let key = canonical_word_key(spelling(&item));
+        match index.get(&key) {
+            Some(&pos) => merge(&mut out[pos], item),
+            None => {
+                index.insert(key, out.len());
+                out.push(item);
+            }
+        }
+    }
+    out
+}
+
+/// One copy of a word in a selected book, with its state before the answer.
+#[derive(Debug, Clone)]
+pub struct BookCopy {
+    pub word_id: String,
+    pub word_book_id: String,
+    pub previous: Option<WordState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookCredit {
+    pub word_book_id: String,
+    pub learned_delta: i32,
+    pub mastered_delta: i32,
+}
+
+/// Progress counter changes per book after every copy moved to `new_state`.
+/// A book holding several copies of the word is credited once.
+pub fn book_credits(copies: &[BookCopy], new_state: WordState) -> Vec<BookCredit> {
+    let mut by_book: Vec<(String, Option<WordState>)> = Vec::new();
+    for copy in copies {
+        match by_book.iter_mut().find(|(id, _)| *id == copy.word_book_id) {
+            // Use the most advanced previous state so the book isn't credited twice.
+            Some((_, prev)) => {
+                if progress_rank(copy.previous) > progress_rank(*prev) {
+                    *prev = copy.previous;
+                }
+            }
+            None => by_book.push((copy.word_book_id.clone(), copy.previous)),
+        }
+    }
+
+    by_book
+        .into_iter()
+        .map(|(word_book_id, previous)| {
+            let was_learned = matches!(previous, Some(s) if s != WordState::New);
+            let was_mastered = previous == Some(WordState::Mastered);
+            let is_learned = new_state != WordState::New;
+            let is_mastered = new_state == WordState::Mastered;
+            BookCredit {
+                word_book_id,
+                learned_delta: i32::from(is_learned && !was_learned),
+                mastered_delta: i32::from(is_mastered) - i32::from(was_mastered),
+            }
+        })
+        .collect()
+}
+
+fn progress_rank(state: Option<WordState>) -> u8 {
+    match state {
+        None => 0,
+        Some(WordState::New) => 1,
+        Some(WordState::Learning) => 2,
+        Some(WordState::Reviewing) => 3,
+        Some(WordState::Mastered) => 4,
+    }
+}
+
+/// Copies of `word_id` (including itself) in the user's selected word books.
+pub async fn select_book_copies(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    word_id: &str,
+) -> Result<Vec<BookCopy>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT w."id", w."wordBookId", wls."state"::text AS "state"
+        FROM "words" src
+        JOIN "words" w
+          ON lower(btrim(w."spelling")) = lower(btrim(src."spelling"))
+        LEFT JOIN "word_learning_states" wls
+          ON wls."wordId" = w."id" AND wls."userId" = $1
+        WHERE src."id" = $2
+          AND (
+            w."id" = src."id"
+            OR w."wordBookId" = ANY(
+              SELECT unnest("selectedWordBookIds") FROM "user_study_configs" WHERE "userId" = $1
+            )
+          )
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_id)
+    .fetch_all(proxy.pool())
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| BookCopy {
+            word_id: row.try_get("id").unwrap_or_default(),
+            word_book_id: row.try_get("wordBookId").unwrap_or_default(),
+            previous: row
+                .try_get::<Option<String>, _>("state")
+                .ok()
+                .flatten()
+                .map(|s| WordState::parse(&s)),
+        })
+        .collect())
+}
+
+/// The answered word alone, used when the cross-book lookup fails so its
+/// own book is still credited.
+async fn select_own_copy(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    word_id: &str,
+) -> Result<Vec<BookCopy>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT w."wordBookId", wls."state"::text AS "state"
+        FROM "words" w
+        LEFT JOIN "word_learning_states" wls
+          ON wls."wordId" = w."id" AND wls."userId" = $1
+        WHERE w."id" = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_id)
+    .fetch_optional(proxy.pool())
+    .await?;
+    Ok(row
+        .map(|row| BookCopy {
+            word_id: word_id.to_string(),
+            word_book_id: row.try_get("wordBookId").unwrap_or_default(),
+            previous: row
+                .try_get::<Option<String>, _>("state")
+                .ok()
+                .flatten()
+                .map(|s| WordState::parse(&s)),
+        })
+        .into_iter()
+        .collect())
+}
+
+/// Write an answer's state update to every copy of the word and credit each
+/// book's progress counters.
+pub async fn record_answer_across_books(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    word_id: &str,
+    data: WordStateUpdateData,
+) -> Result<(), String> {
+    let copies = match select_book_copies(proxy, user_id, word_id).await {
+        Ok(copies) => copies,
+        Err(e) => {
+            tracing::warn!(error = %e, word_id = %word_id, "Failed to load word copies across books");
+            select_own_copy(proxy, user_id, word_id)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+    };
+    let new_state = data.state;
+
+    for copy in copies.iter().filter(|c| c.word_id != word_id) {
+        if let Err(e) = upsert_word_state(proxy, user_id, &copy.word_id, data.clone()).await {
+            tracing::warn!(error = %e, word_id = %copy.word_id, "Failed to mirror word state to book copy");
+        }
+    }
+    upsert_word_state(proxy, user_id, word_id, data).await?;
+
+    if let Some(new_state) = new_state {
+        for credit in book_credits(&copies, new_state) {
+            if let Err(e) = apply_book_credit(proxy, user_id, &credit).await {
+                tracing::warn!(error = %e, word_book_id = %credit.word_book_id, "Failed to update word book progress");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add a credit to the book's counters in one statement, so concurrent
+/// answers cannot overwrite each other's increments.
+async fn apply_book_credit(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    credit: &BookCredit,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let row = sqlx::query(
+        r#"
+        INSERT INTO "user_word_book_progress" (
+            "id", "userId", "wordBookId", "learnedCount", "masteredCount",
+            "totalWords", "lastStudyAt", "createdAt", "updatedAt"
+        )
+        SELECT $1, $2, $3, GREATEST($4, 0), GREATEST($5, 0),
+               (SELECT COUNT(*)::int FROM "words" WHERE "wordBookId" = $3), $6, $6, $6
+        ON CONFLICT ("userId", "wordBookId") DO UPDATE SET
+            "learnedCount" = GREATEST("user_word_book_progress"."learnedCount" + $4, 0),
+            "masteredCount" = GREATEST("user_word_book_progress"."masteredCount" + $5, 0),
+            "lastStudyAt" = $6,
+            "updatedAt" = $6
+        RETURNING "masteredCount", "totalWords"
+        "#,
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(&credit.word_book_id)
+    .bind(credit.learned_delta)
+    .bind(credit.mastered_delta)
+    .bind(now)
+    .fetch_one(proxy.pool())
+    .await?;
+    let mastered_count: i32 = row.try_get("masteredCount")?;
+    let total_words: i32 = row.try_get("totalWords")?;
+
+    let was_complete = total_words > 0 && mastered_count - credit.mastered_delta >= total_words;
+    if !was_complete && total_words > 0 && mastered_count >= total_words {
+        webhooks::emit_logged(
+            proxy,
+            user_id,
+            WebhookEventType::WordbookCompleted,
+            serde_json::json!({
+                "wordBookId": credit.word_book_id,
+                "totalWords": total_words,
+                "masteredCount": mastered_count,
+            }),
+        )
+        .await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn copy(word_id: &str, book: &str, previous: Option<WordState>) -> BookCopy {
+        BookCopy {
+            word_id: word_id.to_string(),
+            word_book_id: book.to_string(),
+            previous,
+        }
+    }
+
+    #[test]
+    fn canonical_key_ignores_case_and_spacing() {
+        assert_eq!(canonical_word_key("  Take  Off "), "take off");
+        assert_eq!(canonical_word_key("Apple"), canonical_word_key("apple"));
+        assert_ne!(canonical_word_key("apple"), canonical_word_key("apples"));
+    }
+
+    #[test]
+    fn double_book_word_is_queued_once_with_merged_priority() {
+        let due: Vec<(&str, &str, f64)> = vec![
+            ("w-cet4", "Abandon", 40.0),
+            ("w-ielts", "abandon ", 55.0),
+            ("w-x", "zeal", 10.0),
+        ];
+        let merged = dedup_by_canonical(due, |d| d.1, |kept, dup| kept.2 = kept.2.max(dup.2));
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].0, "w-cet4");
+        assert_eq!(merged[0].2, 55.0);
+        assert_eq!(merged[1].0, "w-x");
+    }
+
+    #[test]
+    fn answer_credits_both_books() {
+        let copies = vec![
+            copy("w-cet4", "cet4", Some(WordState::Reviewing)),
+            copy("w-ielts", "ielts", None),
+        ];
+        let credits = book_credits(&copies, WordState::Mastered);
+        assert_eq!(
+            credits,
+            vec![
+                BookCredit {
+                    word_book_id: "cet4".to_string(),
+                    learned_delta: 0,
+                    mastered_delta: 1,
+                },
+                BookCredit {
+                    word_book_id: "ielts".to_string(),
+                    learned_delta: 1,
+                    mastered_delta: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lapse_removes_mastered_credit_and_duplicate_copies_count_once() {
+        let copies = vec![
+            copy("a", "cet4", Some(WordState::Mastered)),
+            copy("b", "cet4", Some(WordState::Learning)),
+        ];
+        let credits = book_credits(&copies, WordState::Learning);
+        assert_eq!(credits.len(), 1);
+        assert_eq!(credits[0].learned_delta, 0);
+        assert_eq!(credits[0].mastered_delta, -1);
+    }
+}
@@ -4,6 +4,7 @@ use thiserror::Error;
 use crate::db::operations::broadcast::{self, Broadcast, CreateBroadcastParams};
 use crate::db::DatabaseProxy;
 use crate::routes::realtime;
+use crate::services::notification_delivery;
 
 #[derive(Debug, Error)]
 pub enum BroadcastError {
@@ -129,6 +130,12 @@ pub async fn create_broadcast(
         .await
         .unwrap_or(0);
 
+        if let Err(e) =
+            notification_delivery::enqueue_for_broadcast(proxy.pool(), &broadcast_record.id).await
+        {
+            tracing::warn!(error = %e, broadcast_id = %broadcast_record.id, "Failed to queue broadcast deliveries");
+        }
+
         broadcast::update_broadcast_delivered_count(proxy, &broadcast_record.id, persisted as i32)
             .await
             .ok();
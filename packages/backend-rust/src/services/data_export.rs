@@ -0,0 +1,441 @@
+//! Right-of-access export of everything a user owns, streamed as NDJSON.
+//!
+//! Tables are written one after another. Each starts with a `table` line,
+//! followed by one `row` line per row. A closing `manifest` line carries the
+//! row count of every table and whether the export finished, so a truncated
+//! download can be told apart from a complete one.
+//!
+//! Rows pass through a bounded channel. When the client reads slowly the
+//! writer waits on the channel and stops pulling from the database cursor,
+//! so memory stays flat regardless of how much data the user has.
+
+use std::convert::Infallible;
+
+use bytes::Bytes;
+use chrono::Utc;
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::sqlite::{SqliteRow, SqliteValueRef};
+use sqlx::{Column, PgPool, Row, SqlitePool, TypeInfo, ValueRef};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::db::snapshot_crypto;
+use crate::db::state_machine::DatabaseState;
+use crate::db::DatabaseProxy;
+
+/// Encoded lines buffered between the database and the client.
+const CHANNEL_CAPACITY: usize = 64;
+
+pub const CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Where the export reads from.
+#[derive(Clone)]
+pub enum ExportSource {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+impl ExportSource {
+    /// Postgres normally; the SQLite fallback while the primary is degraded
+    /// or its pool is congested (the export only reads).
+    pub async fn select(proxy: &DatabaseProxy) -> Self {
+        let state = proxy.state_machine().read().await.state();
+        if matches!(state, DatabaseState::Degraded | DatabaseState::Unavailable) {
+            if let Some(pool) = proxy.fallback_pool().await {
+                return Self::Sqlite(pool);
+            }
+        }
+        if let Some(pool) = proxy.read_offload_pool().await {
+            return Self::Sqlite(pool);
+        }
+        Self::Postgres(proxy.pool().clone())
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Postgres(_) => "postgres",
+            Self::Sqlite(_) => "sqlite",
+        }
+    }
+}
+
+struct ExportTable {
+    name: &'static str,
+    /// Selects one `to_jsonb` column named `data`; `$1` is the user id.
+    postgres: &'static str,
+    /// Selects the table's columns; `?` is the user id.
+    sqlite: &'static str,
+    /// Rows carry encrypted model parameters plus the user's key columns.
+    encrypted_parameters: bool,
+}
+
+const EXPORT_TABLES: &[ExportTable] = &[
+    ExportTable {
+        name: "word_books",
+        postgres: r#"SELECT to_jsonb(t) AS "data" FROM "word_books" t WHERE t."userId" = $1"#,
+        sqlite: r#"SELECT * FROM "word_books" WHERE "userId" = ?"#,
+        encrypted_parameters: false,
+    },
+    ExportTable {
+        name: "words",
+        postgres: r#"SELECT to_jsonb(t) AS "data" FROM "words" t
+                     JOIN "word_books" b ON b."id" = t."wordBookId"
+                     WHERE b."userId" = $1"#,
+        sqlite: r#"SELECT t.* FROM "words" t
+                   JOIN "word_books" b ON b."id" = t."wordBookId"
+                   WHERE b."userId" = ?"#,
+        encrypted_parameters: false,
+    },
+    ExportTable {
+        name: "word_learning_states",
+        postgres: r#"SELECT to_jsonb(t) AS "data" FROM "word_learning_states" t WHERE t."userId" = $1"#,
+        sqlite: r#"SELECT * FROM "word_learning_states" WHERE "userId" = ?"#,
+        encrypted_parameters: false,
+    },
+    ExportTable {
+        name: "answer_records",
+        postgres: r#"SELECT to_jsonb(t) AS "data" FROM "answer_records" t WHERE t."userId" = $1"#,
+        sqlite: r#"SELECT * FROM "answer_records" WHERE "userId" = ?"#,
+        encrypted_parameters: false,
+    },
+    ExportTable {
+        name: "notifications",
+        postgres: r#"SELECT to_jsonb(t) AS "data" FROM "notifications" t WHERE t."userId" = $1"#,
+        sqlite: r#"SELECT * FROM "notifications" WHERE "userId" = ?"#,
+        encrypted_parameters: false,
+    },
+    ExportTable {
+        name: "amas_user_models",
+        postgres: r#"SELECT to_jsonb(t) AS "data", t."userId", t."modelType", t."parameters",
+                            k."wrappedKey", k."masterKeyId", k."keyVersion"
+                     FROM "amas_user_models" t
+                     LEFT JOIN "user_data_keys" k ON k."userId" = t."userId"
+                     WHERE t."userId" = $1"#,
+        sqlite: r#"SELECT * FROM "amas_user_models" WHERE "userId" = ?"#,
+        encrypted_parameters: true,
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableCount {
+    pub table: String,
+    pub rows: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportManifest {
+    pub user_id: String,
+    pub source: String,
+    pub exported_at: String,
+    pub tables: Vec<TableCount>,
+    /// False when a table failed; rows already written are still valid.
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ExportLine<'a> {
+    Table { table: &'a str },
+    Row { table: &'a str, data: Value },
+    Error { table: &'a str, message: String },
+    Manifest(ExportManifest),
+}
+
+impl ExportLine<'_> {
+    fn encode(&self) -> Bytes {
+        let mut line = serde_json::to_vec(self).unwrap_or_default();
+        line.push(b'\n');
+        Bytes::from(line)
+    }
+}
+
+/// Client went away; the export stops without writing more.
+struct Disconnected;
+
+struct Writer {
+    tx: mpsc::Sender<Bytes>,
+}
+
+impl Writer {
+    async fn send(&self, line: ExportLine<'_>) -> Result<(), Disconnected> {
+        self.tx.send(line.encode()).await.map_err(|_| Disconnected)
+    }
+}
+
+/// NDJSON body of the user's export. The rows are read by a background task
+/// that runs only as fast as the returned stream is consumed.
+pub fn export_user_data(
+    source: ExportSource,
+    user_id: String,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        if write_export(&source, &user_id, Writer { tx })
+            .await
+            .is_err()
+        {
+            tracing::info!(user_id = %user_id, "data export aborted by client");
+        }
+    });
+    ReceiverStream::new(rx).map(Ok)
+}
+
+async fn write_export(
+    source: &ExportSource,
+    user_id: &str,
+    writer: Writer,
+) -> Result<(), Disconnected> {
+    let mut tables = Vec::with_capacity(EXPORT_TABLES.len());
+    let mut complete = true;
+
+    for table in EXPORT_TABLES {
+        writer.send(ExportLine::Table { table: table.name }).await?;
+        let (rows, error) = match source {
+            ExportSource::Postgres(pool) => {
+                write_postgres_table(pool, table, user_id, &writer).await?
+            }
+            ExportSource::Sqlite(pool) => write_sqlite_table(pool, table, user_id, &writer).await?,
+        };
+        tables.push(TableCount {
+            table: table.name.to_string(),
+            rows,
+        });
+        if let Some(err) = error {
+            tracing::warn!(error = %err, table = table.name, "data export table failed");
+            complete = false;
+            writer
+                .send(ExportLine::Error {
+                    table: table.name,
+                    message: "导出该数据表失败".to_string(),
+                })
+                .await?;
+            break;
+        }
+    }
+
+    writer
+        .send(ExportLine::Manifest(ExportManifest {
+            user_id: user_id.to_string(),
+            source: source.name().to_string(),
+            exported_at: Utc::now().to_rfc3339(),
+            tables,
+            complete,
+        }))
+        .await
+}
+
+/// Writes the table's rows; returns how many were written and the database
+/// error that ended the table early, if any.
+async fn write_postgres_table(
+    pool: &PgPool,
+    table: &ExportTable,
+    user_id: &str,
+    writer: &Writer,
+) -> Result<(u64, Option<sqlx::Error>), Disconnected> {
+    let mut rows = sqlx::query(table.postgres).bind(user_id).fetch(pool);
+    let mut count = 0;
+    loop {
+        let row = match rows.try_next().await {
+            Ok(Some(row)) => row,
+            Ok(None) => return Ok((count, None)),
+            Err(err) => return Ok((count, Some(err))),
+        };
+        let mut data = match row.try_get::<sqlx::types::Json<Value>, _>("data") {
+            Ok(data) => data.0,
+            Err(err) => return Ok((count, Some(err))),
+        };
+        if table.encrypted_parameters {
+            decrypt_parameters(&row, &mut data);
+        }
+        writer
+            .send(ExportLine::Row {
+                table: table.name,
+                data,
+            })
+            .await?;
+        count += 1;
+    }
+}
+
+/// Replaces an encrypted `parameters` envelope with its plaintext. Left as is
+/// when the key is unavailable, since the export must not fail on it.
+fn decrypt_parameters(row: &sqlx::postgres::PgRow, data: &mut Value) {
+    let user_id: String = row.try_get("userId").unwrap_or_default();
+    let model_type: String = row.try_get("modelType").unwrap_or_default();
+    let parameters: Value = row.try_get("parameters").unwrap_or(Value::Null);
+    match snapshot_crypto::decrypt_row_parameters(row, &user_id, &model_type, parameters) {
+        Ok(plain) => {
+            if let Some(obj) = data.as_object_mut() {
+                obj.insert("parameters".to_string(), plain);
+            }
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, model_type, "data export kept encrypted parameters");
+        }
+    }
+}
+
+async fn write_sqlite_table(
+    pool: &SqlitePool,
+    table: &ExportTable,
+    user_id: &str,
+    writer: &Writer,
+) -> Result<(u64, Option<sqlx::Error>), Disconnected> {
+    let mut rows = sqlx::query(table.sqlite).bind(user_id).fetch(pool);
+    let mut count = 0;
+    loop {
+        let row = match rows.try_next().await {
+            Ok(Some(row)) => row,
+            Ok(None) => return Ok((count, None)),
+            Err(err) => return Ok((count, Some(err))),
+        };
+        writer
+            .send(ExportLine::Row {
+                table: table.name,
+                data: Value::Object(sqlite_row_to_json(&row)),
+            })
+            .await?;
+        count += 1;
+    }
+}
+
+fn sqlite_row_to_json(row: &SqliteRow) -> Map<String, Value> {
+    let mut map = Map::new();
+    for column in row.columns() {
+        let name = column.name();
+        let Ok(raw) = row.try_get_raw(name) else {
+            continue;
+        };
+        let raw: SqliteValueRef<'_> = raw;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => row
+                    .try_get::<i64, _>(name)
+                    .map(Value::from)
+                    .unwrap_or(Value::Null),
+                "REAL" => row
+                    .try_get::<f64, _>(name)
+                    .map(Value::from)
+                    .unwrap_or(Value::Null),
+                "BLOB" => row
+                    .try_get::<Vec<u8>, _>(name)
+                    .map(|bytes| Value::String(hex::encode(bytes)))
+                    .unwrap_or(Value::Null),
+                _ => row
+                    .try_get::<String, _>(name)
+                    .map(Value::String)
+                    .unwrap_or(Value::Null),
+            }
+        };
+        map.insert(name.to_string(), value);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sqlite_fixture() -> SqlitePool {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for sql in [
+            r#"CREATE TABLE "word_books" ("id" TEXT PRIMARY KEY, "name" TEXT, "userId" TEXT)"#,
+            r#"CREATE TABLE "words" ("id" TEXT PRIMARY KEY, "spelling" TEXT, "wordBookId" TEXT)"#,
+            r#"CREATE TABLE "word_learning_states" ("id" TEXT PRIMARY KEY, "userId" TEXT, "stability" REAL)"#,
+            r#"CREATE TABLE "answer_records" ("id" TEXT PRIMARY KEY, "userId" TEXT, "isCorrect" INTEGER)"#,
+            r#"CREATE TABLE "notifications" ("id" TEXT PRIMARY KEY, "userId" TEXT, "readAt" TEXT)"#,
+            r#"CREATE TABLE "amas_user_models" ("id" TEXT PRIMARY KEY, "userId" TEXT, "modelData" TEXT)"#,
+            r#"INSERT INTO "word_books" VALUES ('b1', 'mine', 'u1'), ('b2', 'other', 'u2')"#,
+            r#"INSERT INTO "words" VALUES ('w1', 'apple', 'b1'), ('w2', 'pear', 'b2')"#,
+            r#"INSERT INTO "word_learning_states" VALUES ('s1', 'u1', 2.5)"#,
+            r#"INSERT INTO "answer_records" VALUES ('r1', 'u1', 1), ('r2', 'u1', 0), ('r3', 'u2', 1)"#,
+            r#"INSERT INTO "notifications" VALUES ('n1', 'u1', NULL)"#,
+            r#"INSERT INTO "amas_user_models" VALUES ('m1', 'u1', '{}')"#,
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    async fn collect(source: ExportSource, user_id: &str) -> Vec<Value> {
+        let chunks: Vec<_> = export_user_data(source, user_id.to_string())
+            .collect()
+            .await;
+        chunks
+            .into_iter()
+            .map(|chunk| {
+                let chunk = chunk.unwrap();
+                assert_eq!(chunk.last(), Some(&b'\n'));
+                serde_json::from_slice(&chunk).unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_export_is_chunked_by_table_with_manifest() {
+        let lines = collect(ExportSource::Sqlite(sqlite_fixture().await), "u1").await;
+
+        let headers: Vec<&str> = lines
+            .iter()
+            .filter(|l| l["type"] == "table")
+            .map(|l| l["table"].as_str().unwrap())
+            .collect();
+        let names: Vec<&str> = EXPORT_TABLES.iter().map(|t| t.name).collect();
+        assert_eq!(headers, names);
+
+        let words: Vec<&Value> = lines
+            .iter()
+            .filter(|l| l["type"] == "row" && l["table"] == "words")
+            .collect();
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0]["data"]["spelling"], "apple");
+        let state = lines
+            .iter()
+            .find(|l| l["table"] == "word_learning_states" && l["type"] == "row")
+            .unwrap();
+        assert_eq!(state["data"]["stability"], 2.5);
+        let notification = lines
+            .iter()
+            .find(|l| l["table"] == "notifications" && l["type"] == "row")
+            .unwrap();
+        assert!(notification["data"]["readAt"].is_null());
+
+        let manifest = lines.last().unwrap();
+        assert_eq!(manifest["type"], "manifest");
+        assert_eq!(manifest["complete"], true);
+        assert_eq!(manifest["source"], "sqlite");
+        let answers = manifest["tables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["table"] == "answer_records")
+            .unwrap();
+        assert_eq!(answers["rows"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_table_marks_manifest_incomplete() {
+        let pool = sqlite_fixture().await;
+        sqlx::query(r#"DROP TABLE "answer_records""#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let lines = collect(ExportSource::Sqlite(pool), "u1").await;
+
+        let error = lines.iter().find(|l| l["type"] == "error").unwrap();
+        assert_eq!(error["table"], "answer_records");
+        let manifest = lines.last().unwrap();
+        assert_eq!(manifest["complete"], false);
+        assert_eq!(manifest["tables"].as_array().unwrap().len(), 4);
+    }
+}
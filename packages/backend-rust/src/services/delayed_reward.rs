@@ -71,6 +71,9 @@ pub struct QueueStats {
     pub oldest_pending_ts: Option<i64>,
 }
 
+/// Follow-up window for the delayed half of a two-part reward.
+pub const RETENTION_DELAY_HOURS: i64 = 24;
+
 const MAX_RETRY: i32 = 5;
 const BATCH_SIZE: i32 = 50;
 const PROCESSING_TIMEOUT_SECS: i64 = 300;
@@ -112,6 +115,41 @@ pub async fn enqueue_delayed_reward(
         .ok_or_else(|| "创建失败".to_string())
 }
 
+/// Store the immediate half of a two-part reward so the worker can apply the
+/// retention correction once the word is reviewed again.
+pub async fn enqueue_retention_attribution(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    answer_record_id: &str,
+    session_id: Option<String>,
+    attribution: &crate::amas::types::RewardAttribution,
+) -> Result<(), String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let due = Utc::now() + Duration::hours(RETENTION_DELAY_HOURS);
+    let attribution_json =
+        serde_json::to_value(attribution).map_err(|e| format!("序列化失败: {e}"))?;
+
+    sqlx::query(
+        r#"INSERT INTO "reward_queue" ("id","userId","answerRecordId","sessionId","reward","dueTs","status","idempotencyKey","kind","decisionId","attribution","createdAt","updatedAt")
+           VALUES ($1,$2,$3,$4,$5,$6,'PENDING'::"RewardStatus",$7,'RETENTION',$8,$9,NOW(),NOW())
+           ON CONFLICT ("idempotencyKey") DO NOTHING"#,
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(answer_record_id)
+    .bind(session_id)
+    .bind(attribution.immediate_reward)
+    .bind(due.naive_utc())
+    .bind(format!("retention:{}", attribution.decision_id))
+    .bind(&attribution.decision_id)
+    .bind(attribution_json)
+    .execute(proxy.pool())
+    .await
+    .map_err(|e| format!("写入失败: {e}"))?;
+
+    Ok(())
+}
+
 async fn get_by_idempotency_key(
     proxy: &DatabaseProxy,
     key: &str,
@@ -0,0 +1,202 @@
+//! Word difficulty calibrated from population answer data.
+//!
+//! Each word's IRT difficulty is estimated from every learner's answers,
+//! holding their ability (`users.airTheta`) fixed, so a word missed mostly
+//! by beginners ends up easier than one missed by advanced learners. The
+//! estimate starts from the word's Elo-derived difficulty (or its
+//! frequency-list prior while the Elo is untouched) and only moves away from
+//! it as answers accumulate. Results are written to `words` and
+//! read by the new-word scheduler and as the AIR item prior.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::amas::modeling::{AbilityGroup, AdaptiveItemResponse};
+use crate::services::frequency_bootstrap;
+
+/// Answers older than this no longer reflect the word's current use.
+const WINDOW_DAYS: i64 = 180;
+/// Words need this many answers from this many learners to be calibrated.
+const MIN_ATTEMPTS: i64 = 30;
+const MIN_LEARNERS: i64 = 5;
+/// Standard deviation of the prior around the metadata difficulty.
+const PRIOR_SD: f64 = 1.0;
+const BATCH_SIZE: i64 = 500;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationReport {
+    pub words_calibrated: usize,
+}
+
+/// Maps an IRT difficulty onto the [0, 1] scale of the static difficulty
+/// score: the probability that a learner of average ability misses the word.
+pub fn difficulty_from_beta(beta: f64) -> f64 {
+    1.0 - AdaptiveItemResponse::probability(0.0, beta, 1.0)
+}
+
+/// Recalibrates every word with enough recent answers.
+pub async fn calibrate_all(pool: &PgPool) -> Result<CalibrationReport, sqlx::Error> {
+    let mut report = CalibrationReport::default();
+    let mut after = String::new();
+
+    loop {
+        let word_ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT "wordId"
+            FROM "answer_records"
+            WHERE "timestamp" >= NOW() - make_interval(days => $1::int)
+              AND "wordId" > $2
+            GROUP BY "wordId"
+            HAVING COUNT(*) >= $3 AND COUNT(DISTINCT "userId") >= $4
+            ORDER BY "wordId"
+            LIMIT $5
+            "#,
+        )
+        .bind(WINDOW_DAYS as i32)
+        .bind(&after)
+        .bind(MIN_ATTEMPTS)
+        .bind(MIN_LEARNERS)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+        let Some(last) = word_ids.last().cloned() else {
+            break;
+        };
+        report.words_calibrated += calibrate_words(pool, &word_ids).await?;
+        after = last;
+    }
+
+    Ok(report)
+}
+
+async fn calibrate_words(pool: &PgPool, word_ids: &[String]) -> Result<usize, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT ar."wordId",
+               COALESCE(u."airTheta", 0) AS "theta",
+               COUNT(*)::float8 AS "attempts",
+               COUNT(*) FILTER (WHERE ar."isCorrect")::float8 AS "correct",
+               ({prior_beta})::float8 AS "priorBeta"
+        FROM "answer_records" ar
+        JOIN "users" u ON u."id" = ar."userId"
+        JOIN "words" w ON w."id" = ar."wordId"
+        {prior_join}
+        WHERE ar."wordId" = ANY($1)
+          AND ar."timestamp" >= NOW() - make_interval(days => $2::int)
+        GROUP BY ar."wordId", ar."userId", u."airTheta",
+                 w."difficultyElo", w."eloGamesPlayed", fp."beta"
+        "#,
+        prior_beta = frequency_bootstrap::PRIOR_BETA_SQL,
+        prior_join = frequency_bootstrap::PRIOR_JOIN,
+    ))
+    .bind(word_ids)
+    .bind(WINDOW_DAYS as i32)
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: HashMap<String, (f64, Vec<AbilityGroup>)> = HashMap::new();
+    for row in &rows {
+        let word_id: String = row.try_get("wordId")?;
+        let prior: f64 = row.try_get("priorBeta").unwrap_or(0.0);
+        groups
+            .entry(word_id)
+            .or_insert_with(|| (prior, Vec::new()))
+            .1
+            .push(AbilityGroup {
+                theta: row.try_get("theta")?,
+                attempts: row.try_get("attempts")?,
+                correct: row.try_get("correct")?,
+            });
+    }
+
+    let mut ids = Vec::with_capacity(groups.len());
+    let mut betas = Vec::with_capacity(groups.len());
+    let mut difficulties = Vec::with_capacity(groups.len());
+    let mut attempts = Vec::with_capacity(groups.len());
+    for (word_id, (prior, word_groups)) in groups {
+        let Some(calibration) = AdaptiveItemResponse::calibrate_beta(&word_groups, prior, PRIOR_SD)
+        else {
+            continue;
+        };
+        ids.push(word_id);
+        betas.push(calibration.beta);
+        difficulties.push(difficulty_from_beta(calibration.beta));
+        attempts.push(calibration.attempts as i32);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE "words" w
+        SET "calibratedBeta" = c."beta",
+            "calibratedDifficulty" = c."difficulty",
+            "calibrationAttempts" = c."attempts",
+            "calibratedAt" = NOW()
+        FROM UNNEST($1::text[], $2::float8[], $3::float8[], $4::int[])
+            AS c("id", "beta", "difficulty", "attempts")
+        WHERE w."id" = c."id"
+        "#,
+    )
+    .bind(&ids)
+    .bind(&betas)
+    .bind(&difficulties)
+    .bind(&attempts)
+    .execute(pool)
+    .await?;
+
+    Ok(ids.len())
+}
+
+/// Calibrated [0, 1] difficulties of the given words; uncalibrated words are
+/// absent.
+pub async fn calibrated_difficulties(
+    pool: &PgPool,
+    word_ids: &[String],
+) -> Result<HashMap<String, f64>, sqlx::Error> {
+    if word_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query(
+        r#"SELECT "id", "calibratedDifficulty" FROM "words"
+           WHERE "id" = ANY($1) AND "calibratedDifficulty" IS NOT NULL"#,
+    )
+    .bind(word_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some((
+                row.try_get("id").ok()?,
+                row.try_get("calibratedDifficulty").ok()?,
+            ))
+        })
+        .collect())
+}
+
+/// Calibrated IRT difficulty of one word, if it has been calibrated.
+pub async fn calibrated_beta(pool: &PgPool, word_id: &str) -> Result<Option<f64>, sqlx::Error> {
+    let beta: Option<Option<f64>> =
+        sqlx::query_scalar(r#"SELECT "calibratedBeta" FROM "words" WHERE "id" = $1"#)
+            .bind(word_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(beta.flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulty_scale_is_monotonic_and_centred() {
+        assert!((difficulty_from_beta(0.0) - 0.5).abs() < 1e-9);
+        assert!(difficulty_from_beta(-2.0) < 0.2);
+        assert!(difficulty_from_beta(2.0) > 0.8);
+        assert!(difficulty_from_beta(1.0) > difficulty_from_beta(0.5));
+    }
+}
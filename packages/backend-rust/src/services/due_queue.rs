@@ -0,0 +1,404 @@
+//! Materialized "due today" queue.
+//!
+//! Clients used to pull every learning state to work out what is due. The
+//! queue keeps, per user, the words due before the end of the current UTC
+//! day in `due_queue`, ranked by `danci_algo::rank_by_priority` (recall lost
+//! per minute of review if the word waits another day; new words rank 0).
+//! State mutations refresh the touched words through [`invalidate`], a
+//! nightly worker rebuilds every materialized queue for the new day, and a
+//! queue whose horizon has passed is rebuilt when it is read.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, NaiveTime, Utc};
+use danci_algo::{rank_by_priority, RescueCandidate, RescueConfig};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::auth::format_naive_datetime_iso_millis;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 200;
+/// Per-answer time cap for review time estimates, as in the rescue plan.
+const MAX_ANSWER_MS: i64 = 60_000;
+/// Queues rebuilt per batch by the nightly refresh.
+const REFRESH_BATCH: i64 = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DueQueueItem {
+    pub word_id: String,
+    pub state: String,
+    pub due_at: Option<String>,
+    pub priority: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DueQueuePage {
+    pub items: Vec<DueQueueItem>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+    /// Words due before this time are in the queue.
+    pub horizon: String,
+    pub refreshed_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshReport {
+    pub users: u64,
+    pub failed: u64,
+    pub entries: u64,
+}
+
+struct Entry {
+    word_id: String,
+    state: String,
+    due_at: Option<NaiveDateTime>,
+    priority: f64,
+}
+
+/// Start of the next UTC day.
+pub fn horizon_at(now: NaiveDateTime) -> NaiveDateTime {
+    (now.date() + Duration::days(1)).and_time(NaiveTime::MIN)
+}
+
+/// Due entries of the user, limited to `word_ids` when given.
+async fn compute_entries(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: Option<&[String]>,
+    now: NaiveDateTime,
+    horizon: NaiveDateTime,
+) -> Result<Vec<Entry>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "wordId","state"::text AS "state","nextReviewDate","halfLife","lastReviewDate"
+        FROM "word_learning_states"
+        WHERE "userId" = $1
+          AND ($3::text[] IS NULL OR "wordId" = ANY($3))
+          AND (
+            (
+              "nextReviewDate" < $2
+              AND "state"::text IN ('LEARNING','REVIEWING')
+            )
+            OR (
+              "state"::text = 'NEW'
+              AND ("nextReviewDate" IS NULL OR "nextReviewDate" < $2)
+            )
+          )
+        "#,
+    )
+    .bind(user_id)
+    .bind(horizon)
+    .bind(word_ids.map(|ids| ids.to_vec()))
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let due_ids: Vec<String> = rows
+        .iter()
+        .filter_map(|row| row.try_get("wordId").ok())
+        .collect();
+    let seconds: HashMap<String, f64> = sqlx::query(
+        r#"
+        SELECT "wordId", AVG(LEAST("responseTime", $3))::float8 / 1000.0 AS "seconds"
+        FROM "answer_records"
+        WHERE "userId" = $1 AND "wordId" = ANY($2) AND "responseTime" > 0
+        GROUP BY "wordId"
+        "#,
+    )
+    .bind(user_id)
+    .bind(&due_ids)
+    .bind(MAX_ANSWER_MS)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .filter_map(|row| Some((row.try_get("wordId").ok()?, row.try_get("seconds").ok()?)))
+    .collect();
+
+    let mut entries = HashMap::with_capacity(rows.len());
+    let mut candidates = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let Ok(word_id) = row.try_get::<String, _>("wordId") else {
+            continue;
+        };
+        let state: String = row.try_get("state").unwrap_or_else(|_| "NEW".to_string());
+        let days = row
+            .try_get::<Option<NaiveDateTime>, _>("lastReviewDate")
+            .ok()
+            .flatten()
+            .filter(|_| state != "NEW")
+            .map(|at| (now - at).num_seconds().max(0) as f64 / 86_400.0);
+        let half_life: f64 = row.try_get::<f64, _>("halfLife").unwrap_or(1.0).max(0.1);
+        let (recall, recall_tomorrow) = match days {
+            Some(days) => (
+                (-days / half_life).exp2(),
+                (-(days + 1.0) / half_life).exp2(),
+            ),
+            None => (0.0, 0.0),
+        };
+        candidates.push(RescueCandidate {
+            seconds: seconds.get(&word_id).copied(),
+            word_id: word_id.clone(),
+            recall,
+            recall_tomorrow,
+        });
+        entries.insert(
+            word_id.clone(),
+            Entry {
+                word_id,
+                state,
+                due_at: row.try_get("nextReviewDate").ok().flatten(),
+                priority: 0.0,
+            },
+        );
+    }
+
+    Ok(rank_by_priority(candidates, &RescueConfig::default())
+        .into_iter()
+        .filter_map(|item| {
+            let mut entry = entries.remove(&item.word_id)?;
+            entry.priority = item.priority;
+            Some(entry)
+        })
+        .collect())
+}
+
+async fn insert_entries(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &str,
+    entries: &[Entry],
+) -> Result<(), sqlx::Error> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let word_ids: Vec<&str> = entries.iter().map(|e| e.word_id.as_str()).collect();
+    let states: Vec<&str> = entries.iter().map(|e| e.state.as_str()).collect();
+    let due_at: Vec<Option<NaiveDateTime>> = entries.iter().map(|e| e.due_at).collect();
+    let priorities: Vec<f64> = entries.iter().map(|e| e.priority).collect();
+    sqlx::query(
+        r#"
+        INSERT INTO "due_queue" ("userId","wordId","state","dueAt","priority")
+        SELECT $1, t.* FROM UNNEST($2::text[], $3::text[], $4::timestamp[], $5::float8[]) AS t
+        ON CONFLICT ("userId","wordId") DO UPDATE SET
+          "state" = EXCLUDED."state",
+          "dueAt" = EXCLUDED."dueAt",
+          "priority" = EXCLUDED."priority"
+        "#,
+    )
+    .bind(user_id)
+    .bind(&word_ids)
+    .bind(&states)
+    .bind(&due_at)
+    .bind(&priorities)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Rebuilds the user's queue for the current day; returns the entry count.
+pub async fn refresh_user(pool: &PgPool, user_id: &str) -> Result<u64, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let horizon = horizon_at(now);
+    let entries = compute_entries(pool, user_id, None, now, horizon).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(r#"DELETE FROM "due_queue" WHERE "userId" = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    insert_entries(&mut tx, user_id, &entries).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO "due_queue_users" ("userId","horizon","refreshedAt")
+        VALUES ($1, $2, $3)
+        ON CONFLICT ("userId") DO UPDATE SET
+          "horizon" = EXCLUDED."horizon",
+          "refreshedAt" = EXCLUDED."refreshedAt"
+        "#,
+    )
+    .bind(user_id)
+    .bind(horizon)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(entries.len() as u64)
+}
+
+/// Recomputes the entries of `word_ids` in a current queue. Users without a
+/// queue for today are left alone; theirs is rebuilt on the next read.
+pub async fn refresh_words(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    if word_ids.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now().naive_utc();
+    let horizon: Option<NaiveDateTime> =
+        sqlx::query_scalar(r#"SELECT "horizon" FROM "due_queue_users" WHERE "userId" = $1"#)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    let Some(horizon) = horizon.filter(|h| *h == horizon_at(now)) else {
+        return Ok(());
+    };
+    let entries = compute_entries(pool, user_id, Some(word_ids), now, horizon).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(r#"DELETE FROM "due_queue" WHERE "userId" = $1 AND "wordId" = ANY($2)"#)
+        .bind(user_id)
+        .bind(word_ids)
+        .execute(&mut *tx)
+        .await?;
+    insert_entries(&mut tx, user_id, &entries).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Hook for learning-state mutations. A failed refresh must not fail the
+/// mutation, so the queue is dropped instead and rebuilt on the next read.
+pub async fn invalidate(pool: &PgPool, user_id: &str, word_ids: &[String]) {
+    let Err(err) = refresh_words(pool, user_id, word_ids).await else {
+        return;
+    };
+    tracing::warn!(error = %err, user_id, "due queue refresh failed, dropping queue");
+    if let Err(err) = sqlx::query(r#"DELETE FROM "due_queue_users" WHERE "userId" = $1"#)
+        .bind(user_id)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(error = %err, user_id, "due queue invalidation failed");
+    }
+}
+
+/// One page of the user's queue, most urgent first. Builds the queue if it
+/// is missing or from an earlier day.
+pub async fn page(
+    pool: &PgPool,
+    user_id: &str,
+    page: i64,
+    page_size: i64,
+) -> Result<DueQueuePage, sqlx::Error> {
+    let page = page.max(1);
+    let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+    let now = Utc::now().naive_utc();
+    let horizon = horizon_at(now);
+
+    let current =
+        sqlx::query(r#"SELECT "horizon","refreshedAt" FROM "due_queue_users" WHERE "userId" = $1"#)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .filter(|row| row.try_get::<NaiveDateTime, _>("horizon").ok() == Some(horizon));
+    let refreshed_at = match current {
+        Some(row) => row.try_get("refreshedAt")?,
+        None => {
+            refresh_user(pool, user_id).await?;
+            now
+        }
+    };
+
+    let total: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM "due_queue" WHERE "userId" = $1"#)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    let rows = sqlx::query(
+        r#"
+        SELECT "wordId","state","dueAt","priority"
+        FROM "due_queue"
+        WHERE "userId" = $1
+        ORDER BY "priority" DESC, "dueAt" ASC NULLS FIRST, "wordId"
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(page_size)
+    .bind((page - 1) * page_size)
+    .fetch_all(pool)
+    .await?;
+
+    let items = rows
+        .iter()
+        .map(|row| {
+            Ok(DueQueueItem {
+                word_id: row.try_get("wordId")?,
+                state: row.try_get("state")?,
+                due_at: row
+                    .try_get::<Option<NaiveDateTime>, _>("dueAt")?
+                    .map(format_naive_datetime_iso_millis),
+                priority: row.try_get("priority")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+    Ok(DueQueuePage {
+        items,
+        total,
+        page,
+        page_size,
+        horizon: format_naive_datetime_iso_millis(horizon),
+        refreshed_at: format_naive_datetime_iso_millis(refreshed_at),
+    })
+}
+
+/// Nightly rebuild of every materialized queue for the new day. A failing
+/// user is logged and skipped.
+pub async fn refresh_all(pool: &PgPool) -> Result<RefreshReport, sqlx::Error> {
+    let mut report = RefreshReport::default();
+    let mut after = String::new();
+    loop {
+        let users: Vec<String> = sqlx::query_scalar(
+            r#"SELECT "userId" FROM "due_queue_users" WHERE "userId" > $1
+               ORDER BY "userId" LIMIT $2"#,
+        )
+        .bind(&after)
+        .bind(REFRESH_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = users.last() else {
+            return Ok(report);
+        };
+        after = last.clone();
+        for user_id in &users {
+            match refresh_user(pool, user_id).await {
+                Ok(entries) => {
+                    report.users += 1;
+                    report.entries += entries;
+                }
+                Err(err) => {
+                    report.failed += 1;
+                    tracing::warn!(error = %err, user_id = %user_id, "due queue rebuild failed");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn horizon_is_next_utc_midnight() {
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2026, 3, 31)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        let midnight = NaiveDate::from_ymd_opt(2026, 4, 1)
+            .unwrap()
+            .and_time(NaiveTime::MIN);
+        assert_eq!(horizon_at(at(0, 0)), midnight);
+        assert_eq!(horizon_at(at(23, 59)), midnight);
+    }
+}
@@ -149,6 +149,10 @@ impl EmbeddingProvider {
         &self.config.model
     }
 
+    pub fn api_endpoint(&self) -> &str {
+        &self.config.api_endpoint
+    }
+
     pub fn dimension(&self) -> usize {
         self.config.dimension
     }
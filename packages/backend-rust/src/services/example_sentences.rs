@@ -0,0 +1,358 @@
+//! Picks the example sentence best suited to a learner.
+//!
+//! Examples are stored as free text on `words.examples`, often with the
+//! Chinese translation appended ("Change is inevitable. 变化不可避免。").
+//! Each example is split into its English sentence and translation, then
+//! scored on three signals:
+//!
+//! - length: sentences around `IDEAL_TOKENS` words read best; very short
+//!   ones give little context and long ones bury the target word;
+//! - coverage: share of the other words the learner already knows (their
+//!   mastered words plus common function words) — comprehensible input;
+//! - frequency: mean corpus frequency of the sentence's words, so a
+//!   sentence built from everyday vocabulary beats one full of rare words.
+//!
+//! Selections are cached per user and word for `CACHE_TTL`.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use tokio::sync::RwLock;
+
+use crate::services::wordbook_licenses;
+
+/// `word_learning_states.masteryLevel` at or above which a word counts as known.
+const MASTERED_LEVEL: i32 = 4;
+const IDEAL_TOKENS: f64 = 12.0;
+/// Frequency assumed for words missing from `word_frequency`.
+const DEFAULT_FREQUENCY: f64 = 0.5;
+const LENGTH_WEIGHT: f64 = 0.25;
+const COVERAGE_WEIGHT: f64 = 0.5;
+const FREQUENCY_WEIGHT: f64 = 0.25;
+pub const MAX_WORDS_PER_REQUEST: usize = 50;
+
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+const CACHE_CAPACITY: usize = 20_000;
+
+const FUNCTION_WORDS: &[&str] = &[
+    "a", "an", "the", "and", "or", "but", "if", "so", "of", "to", "in", "on", "at", "by", "for",
+    "with", "from", "as", "is", "are", "was", "were", "be", "been", "am", "do", "does", "did",
+    "have", "has", "had", "will", "would", "can", "could", "should", "may", "might", "must", "not",
+    "no", "i", "you", "he", "she", "it", "we", "they", "me", "him", "her", "us", "them", "my",
+    "your", "his", "its", "our", "their", "this", "that", "these", "those", "there", "here",
+    "what", "who", "which", "when", "where", "how", "why", "very", "too", "all", "some", "any",
+    "don't", "it's", "i'm",
+];
+
+static CACHE: RwLock<Option<HashMap<(String, String), (SentenceChoice, Instant)>>> =
+    RwLock::const_new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BilingualSentence {
+    pub sentence: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentenceScore {
+    pub total: f64,
+    pub length: f64,
+    pub coverage: f64,
+    pub frequency: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentenceChoice {
+    pub word_id: String,
+    #[serde(flatten)]
+    pub example: BilingualSentence,
+    pub example_index: usize,
+    pub score: SentenceScore,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// Splits an example into its English sentence and appended translation.
+pub fn split_bilingual(example: &str) -> BilingualSentence {
+    let trimmed = example.trim();
+    let Some(at) = trimmed.find(is_cjk) else {
+        return BilingualSentence {
+            sentence: trimmed.to_string(),
+            translation: None,
+        };
+    };
+    let separators: &[char] = &['|', '/', '-', '—', ' ', '\t', '\n'];
+    let sentence = trimmed[..at].trim_end_matches(separators).to_string();
+    let translation = trimmed[at..].trim().to_string();
+    if sentence.is_empty() {
+        return BilingualSentence {
+            sentence: translation,
+            translation: None,
+        };
+    }
+    BilingualSentence {
+        sentence,
+        translation: (!translation.is_empty()).then_some(translation),
+    }
+}
+
+pub fn tokenize(sentence: &str) -> Vec<String> {
+    sentence
+        .split(|c: char| !(c.is_ascii_alphabetic() || c == '\''))
+        .map(|t| t.trim_matches('\'').to_ascii_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Scores one sentence for a learner; `target` is excluded from coverage.
+pub fn score_sentence(
+    tokens: &[String],
+    target: &str,
+    known: &HashSet<String>,
+    frequency: &HashMap<String, f64>,
+) -> SentenceScore {
+    if tokens.is_empty() {
+        return SentenceScore {
+            total: 0.0,
+            length: 0.0,
+            coverage: 0.0,
+            frequency: 0.0,
+        };
+    }
+    let length = (1.0 - (tokens.len() as f64 - IDEAL_TOKENS).abs() / IDEAL_TOKENS).max(0.0);
+
+    let target = target.to_ascii_lowercase();
+    let context: Vec<&String> = tokens.iter().filter(|t| **t != target).collect();
+    let coverage = if context.is_empty() {
+        0.0
+    } else {
+        context
+            .iter()
+            .filter(|t| known.contains(t.as_str()) || FUNCTION_WORDS.contains(&t.as_str()))
+            .count() as f64
+            / context.len() as f64
+    };
+
+    let frequency = tokens
+        .iter()
+        .map(|t| {
+            if FUNCTION_WORDS.contains(&t.as_str()) {
+                1.0
+            } else {
+                frequency.get(t).copied().unwrap_or(DEFAULT_FREQUENCY)
+            }
+        })
+        .sum::<f64>()
+        / tokens.len() as f64;
+
+    SentenceScore {
+        total: LENGTH_WEIGHT * length + COVERAGE_WEIGHT * coverage + FREQUENCY_WEIGHT * frequency,
+        length,
+        coverage,
+        frequency,
+    }
+}
+
+/// Best example for the target word; `None` when it has no usable example.
+pub fn best_sentence(
+    word_id: &str,
+    spelling: &str,
+    examples: &[String],
+    known: &HashSet<String>,
+    frequency: &HashMap<String, f64>,
+) -> Option<SentenceChoice> {
+    examples
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            let example = split_bilingual(raw);
+            let score = score_sentence(&tokenize(&example.sentence), spelling, known, frequency);
+            SentenceChoice {
+                word_id: word_id.to_string(),
+                example,
+                example_index: index,
+                score,
+            }
+        })
+        .filter(|choice| !choice.example.sentence.is_empty())
+        .max_by(|a, b| a.score.total.total_cmp(&b.score.total))
+}
+
+async fn known_words(pool: &PgPool, user_id: &str) -> Result<HashSet<String>, sqlx::Error> {
+    let rows = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT DISTINCT LOWER(w."spelling")
+        FROM "word_learning_states" s
+        JOIN "words" w ON w."id" = s."wordId"
+        WHERE s."userId" = $1 AND s."masteryLevel" >= $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(MASTERED_LEVEL)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+async fn token_frequencies(
+    pool: &PgPool,
+    tokens: &[String],
+) -> Result<HashMap<String, f64>, sqlx::Error> {
+    if tokens.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT LOWER(w."spelling") AS "token", MAX(f.frequency_score) AS "score"
+        FROM "words" w
+        JOIN "word_frequency" f ON f.word_id = w."id"
+        WHERE LOWER(w."spelling") = ANY($1)
+        GROUP BY LOWER(w."spelling")
+        "#,
+    )
+    .bind(tokens)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let token: String = row.try_get("token").ok()?;
+            let score: f64 = row.try_get("score").ok()?;
+            Some((token, score.clamp(0.0, 1.0)))
+        })
+        .collect())
+}
+
+/// Best sentence per requested word, in request order. Unknown words and
+/// words without examples are omitted.
+pub async fn best_sentences(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<Vec<SentenceChoice>, sqlx::Error> {
+    let mut cached = HashMap::new();
+    if let Some(cache) = CACHE.read().await.as_ref() {
+        for word_id in word_ids {
+            if let Some((choice, at)) = cache.get(&(user_id.to_string(), word_id.clone())) {
+                if at.elapsed() < CACHE_TTL {
+                    cached.insert(word_id.clone(), choice.clone());
+                }
+            }
+        }
+    }
+    let missing: Vec<String> = word_ids
+        .iter()
+        .filter(|id| !cached.contains_key(*id))
+        .cloned()
+        .collect();
+
+    if !missing.is_empty() {
+        // Words from books the user cannot read are treated as unknown.
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT w."id", w."spelling", w."examples"
+            FROM "words" w
+            JOIN "word_books" wb ON wb."id" = w."wordBookId"
+            WHERE w."id" = ANY($1) AND {}
+            "#,
+            wordbook_licenses::accessible_wordbook_sql("wb.", "$2")
+        ))
+        .bind(&missing)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+        let words: Vec<(String, String, Vec<String>)> = rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.get("id"),
+                    row.get("spelling"),
+                    row.try_get("examples").unwrap_or_default(),
+                )
+            })
+            .collect();
+
+        let known = known_words(pool, user_id).await?;
+        let mut tokens: Vec<String> = words
+            .iter()
+            .flat_map(|(_, _, examples)| examples.iter())
+            .flat_map(|e| tokenize(&split_bilingual(e).sentence))
+            .filter(|t| !FUNCTION_WORDS.contains(&t.as_str()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        tokens.sort();
+        let frequency = token_frequencies(pool, &tokens).await?;
+
+        let fresh: Vec<SentenceChoice> = words
+            .iter()
+            .filter_map(|(id, spelling, examples)| {
+                best_sentence(id, spelling, examples, &known, &frequency)
+            })
+            .collect();
+
+        let mut guard = CACHE.write().await;
+        let cache = guard.get_or_insert_with(HashMap::new);
+        if cache.len() + fresh.len() > CACHE_CAPACITY {
+            cache.retain(|_, (_, at)| at.elapsed() < CACHE_TTL);
+            if cache.len() + fresh.len() > CACHE_CAPACITY {
+                cache.clear();
+            }
+        }
+        let now = Instant::now();
+        for choice in fresh {
+            cache.insert(
+                (user_id.to_string(), choice.word_id.clone()),
+                (choice.clone(), now),
+            );
+            cached.insert(choice.word_id.clone(), choice);
+        }
+    }
+
+    Ok(word_ids.iter().filter_map(|id| cached.remove(id)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_appended_translation() {
+        let s = split_bilingual("Change is inevitable. 变化不可避免。");
+        assert_eq!(s.sentence, "Change is inevitable.");
+        assert_eq!(s.translation.as_deref(), Some("变化不可避免。"));
+
+        let s = split_bilingual("Don't hesitate to ask. | 有问题尽管问。");
+        assert_eq!(s.sentence, "Don't hesitate to ask.");
+
+        let s = split_bilingual("Change is inevitable.");
+        assert_eq!(s.translation, None);
+    }
+
+    #[test]
+    fn prefers_comprehensible_sentence() {
+        let known: HashSet<String> = ["change", "people", "always", "world"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let examples = vec![
+            "Inevitable.".to_string(),
+            "The ineluctable vicissitudes of epistemology render change inevitable. 认识论……"
+                .to_string(),
+            "People know that change in the world is always inevitable. 人们知道变化总是不可避免的。"
+                .to_string(),
+        ];
+        let best = best_sentence("w1", "inevitable", &examples, &known, &HashMap::new()).unwrap();
+        assert_eq!(best.example_index, 2);
+        assert!(best.example.translation.is_some());
+        assert!(best.score.coverage > 0.8);
+    }
+}
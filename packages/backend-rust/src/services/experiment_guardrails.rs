@@ -0,0 +1,686 @@
+//! Guardrail monitoring for running experiments.
+//!
+//! Every enabled treatment variant is compared with the experiment's
+//! control on rolling-window guardrail metrics: per-learner answer accuracy
+//! and average session length. Learners are the unit of analysis, so a few
+//! heavy users cannot make a variant look significant on their own.
+//!
+//! Degradation is tested with a mixture sequential probability ratio test
+//! (mSPRT) on the difference of means, which stays valid however often the
+//! worker looks at the data. A variant whose metric dropped by at least the
+//! configured relative margin with a likelihood ratio above `1 / alpha` is
+//! disabled: it is no longer assigned or synced, its learners are moved
+//! back to the control, and the decision is recorded in
+//! `ab_guardrail_decisions` together with the statistics that triggered it.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailMetric {
+    /// Share of correct answers.
+    Accuracy,
+    /// Length of finished learning sessions, in minutes.
+    SessionMinutes,
+}
+
+impl GuardrailMetric {
+    pub const ALL: [GuardrailMetric; 2] = [Self::Accuracy, Self::SessionMinutes];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accuracy => "accuracy",
+            Self::SessionMinutes => "session_minutes",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Accuracy => "正确率",
+            Self::SessionMinutes => "会话时长",
+        }
+    }
+
+    /// Per-variant mean and variance of the per-learner metric; `$1` is the
+    /// experiment id, `$2` the window in days. Learners moved to the control
+    /// by an earlier kill are left out of both groups.
+    fn stats_sql(&self) -> &'static str {
+        match self {
+            Self::Accuracy => {
+                r#"
+                WITH per_user AS (
+                  SELECT a."variantId",
+                         AVG(CASE WHEN r."isCorrect" THEN 1.0 ELSE 0.0 END)::float8 AS "value"
+                  FROM "ab_user_assignments" a
+                  JOIN "answer_records" r
+                    ON r."userId" = a."userId"
+                   AND r."timestamp" >= GREATEST(a."assignedAt", NOW() - make_interval(days => $2::int))
+                  WHERE a."experimentId" = $1 AND a."guardrailFromVariantId" IS NULL
+                  GROUP BY a."variantId", a."userId"
+                )
+                SELECT "variantId"::text AS "variantId",
+                       COUNT(*)::bigint AS "users",
+                       AVG("value")::float8 AS "mean",
+                       COALESCE(VAR_SAMP("value"), 0)::float8 AS "variance"
+                FROM per_user
+                GROUP BY "variantId"
+                "#
+            }
+            Self::SessionMinutes => {
+                r#"
+                WITH per_user AS (
+                  SELECT a."variantId",
+                         AVG(EXTRACT(EPOCH FROM (s."endedAt" - s."startedAt")) / 60.0)::float8 AS "value"
+                  FROM "ab_user_assignments" a
+                  JOIN "learning_sessions" s
+                    ON s."userId" = a."userId"
+                   AND s."endedAt" IS NOT NULL
+                   AND s."startedAt" >= GREATEST(a."assignedAt", NOW() - make_interval(days => $2::int))
+                  WHERE a."experimentId" = $1 AND a."guardrailFromVariantId" IS NULL
+                  GROUP BY a."variantId", a."userId"
+                )
+                SELECT "variantId"::text AS "variantId",
+                       COUNT(*)::bigint AS "users",
+                       AVG("value")::float8 AS "mean",
+                       COALESCE(VAR_SAMP("value"), 0)::float8 AS "variance"
+                FROM per_user
+                GROUP BY "variantId"
+                "#
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardrailConfig {
+    /// Rolling window the metrics are computed over.
+    pub window_days: i64,
+    /// Significance level of the sequential test.
+    pub alpha: f64,
+    /// Learners each group needs in the window before a variant can be killed.
+    pub min_users: i64,
+    /// Smallest relative drop against the control that counts as degradation;
+    /// also scales the mSPRT mixing distribution.
+    pub min_relative_drop: f64,
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        Self {
+            window_days: 7,
+            alpha: 0.01,
+            min_users: 30,
+            min_relative_drop: 0.05,
+        }
+    }
+}
+
+impl GuardrailConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env_f64 = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok());
+        let env_i64 = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<i64>().ok());
+        Self {
+            window_days: env_i64("EXPERIMENT_GUARDRAIL_WINDOW_DAYS")
+                .unwrap_or(defaults.window_days)
+                .clamp(1, 90),
+            alpha: env_f64("EXPERIMENT_GUARDRAIL_ALPHA")
+                .filter(|a| *a > 0.0 && *a < 1.0)
+                .unwrap_or(defaults.alpha),
+            min_users: env_i64("EXPERIMENT_GUARDRAIL_MIN_USERS")
+                .unwrap_or(defaults.min_users)
+                .max(2),
+            min_relative_drop: env_f64("EXPERIMENT_GUARDRAIL_MIN_DROP")
+                .filter(|d| *d > 0.0 && *d < 1.0)
+                .unwrap_or(defaults.min_relative_drop),
+        }
+    }
+}
+
+/// Window statistics of one group: learners, mean and sample variance of
+/// the per-learner metric.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmStats {
+    pub users: i64,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardrailCheck {
+    pub metric: GuardrailMetric,
+    pub variant: ArmStats,
+    pub control: ArmStats,
+    /// `(variant - control) / control`; negative when the variant is worse.
+    pub relative_change: f64,
+    pub log_likelihood_ratio: f64,
+    /// `ln(1 / alpha)`.
+    pub threshold: f64,
+    pub breached: bool,
+}
+
+/// Sequential test of the variant against the control on one metric.
+///
+/// The statistic is the normal mSPRT likelihood ratio with a `N(0, tau^2)`
+/// mixture over the difference of means, `tau` being the minimum relative
+/// drop times the control mean:
+/// `ln Λ = ½ ln(V / (V + τ²)) + θ² τ² / (2 V (V + τ²))`,
+/// where `θ` is the observed difference and `V` its variance. Rejecting at
+/// `Λ ≥ 1 / alpha` bounds the false kill rate by `alpha` under continuous
+/// monitoring.
+pub fn check(
+    metric: GuardrailMetric,
+    control: ArmStats,
+    variant: ArmStats,
+    config: &GuardrailConfig,
+) -> GuardrailCheck {
+    let threshold = (1.0 / config.alpha).ln();
+    let diff = variant.mean - control.mean;
+    let relative_change = if control.mean.abs() > f64::EPSILON {
+        diff / control.mean.abs()
+    } else {
+        0.0
+    };
+
+    let enough = control.users >= config.min_users && variant.users >= config.min_users;
+    let tau2 = (config.min_relative_drop * control.mean.abs()).powi(2);
+    let log_likelihood_ratio = if enough && tau2 > 0.0 {
+        let v = (variant.variance.max(0.0) / variant.users as f64
+            + control.variance.max(0.0) / control.users as f64)
+            .max(1e-12);
+        0.5 * (v / (v + tau2)).ln() + diff * diff * tau2 / (2.0 * v * (v + tau2))
+    } else {
+        0.0
+    };
+
+    GuardrailCheck {
+        metric,
+        variant,
+        control,
+        relative_change,
+        log_likelihood_ratio,
+        threshold,
+        breached: enough
+            && diff < 0.0
+            && relative_change <= -config.min_relative_drop
+            && log_likelihood_ratio >= threshold,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantGuardrails {
+    pub variant_id: String,
+    pub variant_name: String,
+    pub disabled_at: Option<String>,
+    pub disabled_reason: Option<String>,
+    pub checks: Vec<GuardrailCheck>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardrailDecision {
+    pub id: String,
+    pub experiment_id: String,
+    pub experiment_name: String,
+    pub variant_id: String,
+    pub variant_name: String,
+    pub control_variant_id: String,
+    pub metric: String,
+    pub window_days: i64,
+    pub variant_users: i64,
+    pub control_users: i64,
+    pub variant_mean: f64,
+    pub control_mean: f64,
+    pub relative_change: f64,
+    pub log_likelihood_ratio: f64,
+    pub threshold: f64,
+    /// Learners moved from the variant back to the control.
+    pub reassigned_users: i64,
+    pub reason: String,
+    /// Every guardrail check of the variant at the time of the decision.
+    pub metrics: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Debug, Default)]
+pub struct GuardrailReport {
+    pub experiments: usize,
+    pub variants_checked: usize,
+    pub decisions: Vec<GuardrailDecision>,
+}
+
+struct VariantRow {
+    id: String,
+    name: String,
+    is_control: bool,
+    disabled_at: Option<NaiveDateTime>,
+    disabled_reason: Option<String>,
+}
+
+struct ExperimentRow {
+    id: String,
+    name: String,
+    variants: Vec<VariantRow>,
+}
+
+/// Checks every running experiment and disables degraded variants.
+pub async fn run_guardrails(
+    pool: &PgPool,
+    config: &GuardrailConfig,
+) -> Result<GuardrailReport, sqlx::Error> {
+    let mut report = GuardrailReport::default();
+
+    for experiment in load_experiments(pool, None).await? {
+        let Some(control) = experiment.variants.iter().find(|v| v.is_control) else {
+            continue;
+        };
+        report.experiments += 1;
+        let stats = load_stats(pool, &experiment.id, config).await?;
+
+        for variant in &experiment.variants {
+            if variant.is_control || variant.disabled_at.is_some() {
+                continue;
+            }
+            report.variants_checked += 1;
+            let checks = variant_checks(&stats, &control.id, &variant.id, config);
+            let Some(trigger) = checks
+                .iter()
+                .filter(|c| c.breached)
+                .max_by(|a, b| a.log_likelihood_ratio.total_cmp(&b.log_likelihood_ratio))
+            else {
+                continue;
+            };
+            if let Some(decision) = disable_variant(
+                pool,
+                &experiment,
+                &control.id,
+                variant,
+                trigger,
+                &checks,
+                config,
+            )
+            .await?
+            {
+                report.decisions.push(decision);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Current guardrail state of one experiment's treatment variants.
+pub async fn evaluate_experiment(
+    pool: &PgPool,
+    experiment_id: &str,
+    config: &GuardrailConfig,
+) -> Result<Vec<VariantGuardrails>, sqlx::Error> {
+    let Some(experiment) = load_experiments(pool, Some(experiment_id))
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(Vec::new());
+    };
+    let Some(control) = experiment.variants.iter().find(|v| v.is_control) else {
+        return Ok(Vec::new());
+    };
+    let stats = load_stats(pool, &experiment.id, config).await?;
+
+    Ok(experiment
+        .variants
+        .iter()
+        .filter(|v| !v.is_control)
+        .map(|v| VariantGuardrails {
+            variant_id: v.id.clone(),
+            variant_name: v.name.clone(),
+            disabled_at: v.disabled_at.map(format_timestamp),
+            disabled_reason: v.disabled_reason.clone(),
+            checks: variant_checks(&stats, &control.id, &v.id, config),
+        })
+        .collect())
+}
+
+pub async fn list_decisions(
+    pool: &PgPool,
+    experiment_id: &str,
+) -> Result<Vec<GuardrailDecision>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "id","experimentId","experimentName","variantId","variantName","controlVariantId",
+               "metric","windowDays","variantUsers","controlUsers","variantMean","controlMean",
+               "relativeChange","logLikelihoodRatio","threshold","reassignedUsers","reason","metrics","createdAt"
+        FROM "ab_guardrail_decisions"
+        WHERE "experimentId" = $1
+        ORDER BY "createdAt" DESC
+        "#,
+    )
+    .bind(experiment_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(GuardrailDecision {
+                id: row.try_get("id")?,
+                experiment_id: row.try_get("experimentId")?,
+                experiment_name: row.try_get("experimentName")?,
+                variant_id: row.try_get("variantId")?,
+                variant_name: row.try_get("variantName")?,
+                control_variant_id: row.try_get("controlVariantId")?,
+                metric: row.try_get("metric")?,
+                window_days: row.try_get::<i32, _>("windowDays")? as i64,
+                variant_users: row.try_get::<i32, _>("variantUsers")? as i64,
+                control_users: row.try_get::<i32, _>("controlUsers")? as i64,
+                variant_mean: row.try_get("variantMean")?,
+                control_mean: row.try_get("controlMean")?,
+                relative_change: row.try_get("relativeChange")?,
+                log_likelihood_ratio: row.try_get("logLikelihoodRatio")?,
+                threshold: row.try_get("threshold")?,
+                reassigned_users: row.try_get::<i32, _>("reassignedUsers")? as i64,
+                reason: row.try_get("reason")?,
+                metrics: row
+                    .try_get::<sqlx::types::Json<serde_json::Value>, _>("metrics")?
+                    .0,
+                created_at: format_timestamp(row.try_get("createdAt")?),
+            })
+        })
+        .collect()
+}
+
+type MetricStats = HashMap<(GuardrailMetric, String), ArmStats>;
+
+fn variant_checks(
+    stats: &MetricStats,
+    control_id: &str,
+    variant_id: &str,
+    config: &GuardrailConfig,
+) -> Vec<GuardrailCheck> {
+    GuardrailMetric::ALL
+        .iter()
+        .map(|&metric| {
+            let arm = |id: &str| {
+                stats
+                    .get(&(metric, id.to_string()))
+                    .copied()
+                    .unwrap_or_default()
+            };
+            check(metric, arm(control_id), arm(variant_id), config)
+        })
+        .collect()
+}
+
+async fn load_experiments(
+    pool: &PgPool,
+    experiment_id: Option<&str>,
+) -> Result<Vec<ExperimentRow>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT e."id" AS "experimentId", e."name" AS "experimentName",
+               v."id" AS "variantId", v."name" AS "variantName",
+               COALESCE(v."isControl", false) AS "isControl",
+               v."disabledAt", v."disabledReason"
+        FROM "ab_experiments" e
+        JOIN "ab_variants" v ON v."experimentId" = e."id"
+        WHERE ($1::text IS NULL AND e."status" = 'RUNNING') OR e."id" = $1
+        ORDER BY e."id", v."createdAt", v."id"
+        "#,
+    )
+    .bind(experiment_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut experiments: Vec<ExperimentRow> = Vec::new();
+    for row in rows {
+        let experiment_id: String = row.try_get("experimentId")?;
+        let variant = VariantRow {
+            id: row.try_get("variantId")?,
+            name: row.try_get("variantName")?,
+            is_control: row.try_get("isControl")?,
+            disabled_at: row.try_get("disabledAt")?,
+            disabled_reason: row.try_get("disabledReason")?,
+        };
+        match experiments.last_mut() {
+            Some(last) if last.id == experiment_id => last.variants.push(variant),
+            _ => experiments.push(ExperimentRow {
+                id: experiment_id,
+                name: row.try_get("experimentName")?,
+                variants: vec![variant],
+            }),
+        }
+    }
+    Ok(experiments)
+}
+
+async fn load_stats(
+    pool: &PgPool,
+    experiment_id: &str,
+    config: &GuardrailConfig,
+) -> Result<MetricStats, sqlx::Error> {
+    let mut stats = HashMap::new();
+    for metric in GuardrailMetric::ALL {
+        let rows = sqlx::query(metric.stats_sql())
+            .bind(experiment_id)
+            .bind(config.window_days as i32)
+            .fetch_all(pool)
+            .await?;
+        for row in rows {
+            stats.insert(
+                (metric, row.try_get::<String, _>("variantId")?),
+                ArmStats {
+                    users: row.try_get("users")?,
+                    mean: row.try_get("mean")?,
+                    variance: row.try_get("variance")?,
+                },
+            );
+        }
+    }
+    Ok(stats)
+}
+
+/// Disables the variant, moves its learners to the control and records the
+/// decision, all in one transaction. Returns `None` when the variant was
+/// already disabled.
+async fn disable_variant(
+    pool: &PgPool,
+    experiment: &ExperimentRow,
+    control_id: &str,
+    variant: &VariantRow,
+    trigger: &GuardrailCheck,
+    checks: &[GuardrailCheck],
+    config: &GuardrailConfig,
+) -> Result<Option<GuardrailDecision>, sqlx::Error> {
+    let reason = format!(
+        "护栏指标「{}」相对对照组下降 {:.1}%（序贯检验 ln Λ = {:.2} ≥ {:.2}）",
+        trigger.metric.label(),
+        -trigger.relative_change * 100.0,
+        trigger.log_likelihood_ratio,
+        trigger.threshold
+    );
+
+    let mut tx = pool.begin().await?;
+
+    let disabled = sqlx::query(
+        r#"
+        UPDATE "ab_variants" SET "disabledAt" = NOW(), "disabledReason" = $2
+        WHERE "id" = $1 AND "disabledAt" IS NULL
+        "#,
+    )
+    .bind(&variant.id)
+    .bind(&reason)
+    .execute(&mut *tx)
+    .await?;
+    if disabled.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(None);
+    }
+
+    let reassigned = sqlx::query(
+        r#"
+        UPDATE "ab_user_assignments" SET "variantId" = $3, "guardrailFromVariantId" = $2
+        WHERE "experimentId" = $1 AND "variantId" = $2
+        "#,
+    )
+    .bind(&experiment.id)
+    .bind(&variant.id)
+    .bind(control_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected() as i64;
+
+    let metrics = serde_json::to_value(checks).unwrap_or_else(|_| serde_json::json!([]));
+    let id = uuid::Uuid::new_v4().to_string();
+    let created_at: NaiveDateTime = sqlx::query_scalar(
+        r#"
+        INSERT INTO "ab_guardrail_decisions" (
+            "id","experimentId","experimentName","variantId","variantName","controlVariantId",
+            "metric","windowDays","variantUsers","controlUsers","variantMean","controlMean",
+            "relativeChange","logLikelihoodRatio","threshold","reassignedUsers","reason","metrics","createdAt"
+        ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,NOW())
+        RETURNING "createdAt"
+        "#,
+    )
+    .bind(&id)
+    .bind(&experiment.id)
+    .bind(&experiment.name)
+    .bind(&variant.id)
+    .bind(&variant.name)
+    .bind(control_id)
+    .bind(trigger.metric.as_str())
+    .bind(config.window_days as i32)
+    .bind(trigger.variant.users as i32)
+    .bind(trigger.control.users as i32)
+    .bind(trigger.variant.mean)
+    .bind(trigger.control.mean)
+    .bind(trigger.relative_change)
+    .bind(trigger.log_likelihood_ratio)
+    .bind(trigger.threshold)
+    .bind(reassigned as i32)
+    .bind(&reason)
+    .bind(sqlx::types::Json(&metrics))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(GuardrailDecision {
+        id,
+        experiment_id: experiment.id.clone(),
+        experiment_name: experiment.name.clone(),
+        variant_id: variant.id.clone(),
+        variant_name: variant.name.clone(),
+        control_variant_id: control_id.to_string(),
+        metric: trigger.metric.as_str().to_string(),
+        window_days: config.window_days,
+        variant_users: trigger.variant.users,
+        control_users: trigger.control.users,
+        variant_mean: trigger.variant.mean,
+        control_mean: trigger.control.mean,
+        relative_change: trigger.relative_change,
+        log_likelihood_ratio: trigger.log_likelihood_ratio,
+        threshold: trigger.threshold,
+        reassigned_users: reassigned,
+        reason,
+        metrics,
+        created_at: format_timestamp(created_at),
+    }))
+}
+
+fn format_timestamp(at: NaiveDateTime) -> String {
+    DateTime::<Utc>::from_naive_utc_and_offset(at, Utc).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arm(users: i64, mean: f64, sd: f64) -> ArmStats {
+        ArmStats {
+            users,
+            mean,
+            variance: sd * sd,
+        }
+    }
+
+    #[test]
+    fn clear_degradation_is_breached() {
+        let config = GuardrailConfig::default();
+        let result = check(
+            GuardrailMetric::Accuracy,
+            arm(400, 0.80, 0.1),
+            arm(400, 0.70, 0.1),
+            &config,
+        );
+        assert!(result.relative_change < -0.1);
+        assert!(result.log_likelihood_ratio > result.threshold);
+        assert!(result.breached);
+    }
+
+    #[test]
+    fn improvement_is_never_breached() {
+        let config = GuardrailConfig::default();
+        let result = check(
+            GuardrailMetric::SessionMinutes,
+            arm(400, 10.0, 2.0),
+            arm(400, 13.0, 2.0),
+            &config,
+        );
+        assert!(result.log_likelihood_ratio > result.threshold);
+        assert!(!result.breached);
+    }
+
+    #[test]
+    fn drop_below_margin_or_too_few_users_is_not_breached() {
+        let config = GuardrailConfig::default();
+        // 显著但只下降 2%，小于最小劣化幅度
+        let small = check(
+            GuardrailMetric::Accuracy,
+            arm(20_000, 0.80, 0.1),
+            arm(20_000, 0.784, 0.1),
+            &config,
+        );
+        assert!(!small.breached);
+
+        let few = check(
+            GuardrailMetric::Accuracy,
+            arm(10, 0.80, 0.1),
+            arm(10, 0.40, 0.1),
+            &config,
+        );
+        assert_eq!(few.log_likelihood_ratio, 0.0);
+        assert!(!few.breached);
+    }
+
+    #[test]
+    fn evidence_grows_with_sample_size() {
+        let config = GuardrailConfig::default();
+        let llr = |users| {
+            check(
+                GuardrailMetric::Accuracy,
+                arm(users, 0.80, 0.15),
+                arm(users, 0.74, 0.15),
+                &config,
+            )
+            .log_likelihood_ratio
+        };
+        assert!(llr(40) < llr(200));
+        assert!(llr(200) < llr(1000));
+        assert!(
+            !check(
+                GuardrailMetric::Accuracy,
+                arm(40, 0.80, 0.15),
+                arm(40, 0.74, 0.15),
+                &config
+            )
+            .breached
+        );
+    }
+}
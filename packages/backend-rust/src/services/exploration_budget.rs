@@ -0,0 +1,64 @@
+//! Per-session exploration budget for AMAS strategy selection.
+//!
+//! Forcing exploratory strategies into a three-minute session wastes most of
+//! it, while a new user's preferences are still worth probing. The budget
+//! scales IGE forced exploration by the user's expected session length (the
+//! habit profile's median session minutes) and tenure (finished sessions
+//! before the current one); see `danci_algo::exploration`. Every decision
+//! records the applied scale and the intended exploration share next to
+//! `isForcedExploration`, so `/admin/analytics/decisions/exploration-share`
+//! can check the realised share against the intent.
+//!
+//! `AMAS_EXPLORATION_BUDGET=off` disables the budget and leaves the
+//! configured policy unscaled.
+
+use danci_algo::{ExplorationBudget, ExplorationBudgetConfig};
+use sqlx::{PgPool, Row};
+
+/// Budget parameters, or `None` when the budget is switched off.
+pub fn config_from_env() -> Option<ExplorationBudgetConfig> {
+    match std::env::var("AMAS_EXPLORATION_BUDGET") {
+        Ok(value) if value.trim().eq_ignore_ascii_case("off") => None,
+        _ => Some(ExplorationBudgetConfig::default()),
+    }
+}
+
+/// Budget for a decision in `session_id`. Users without a habit profile
+/// get the unscaled session factor; their tenure still counts.
+pub async fn for_session(
+    pool: &PgPool,
+    user_id: &str,
+    session_id: Option<&str>,
+) -> Result<Option<ExplorationBudget>, sqlx::Error> {
+    let Some(config) = config_from_env() else {
+        return Ok(None);
+    };
+    let row = sqlx::query(
+        r#"
+        SELECT
+          (SELECT ("rhythmPref"->>'sessionMedianMinutes')::float8
+             FROM "habit_profiles" WHERE "userId" = $1) AS "medianMinutes",
+          (SELECT COUNT(*) FROM "learning_sessions"
+             WHERE "userId" = $1 AND "endedAt" IS NOT NULL
+               AND "id" IS DISTINCT FROM $2)::bigint AS "finishedSessions"
+        "#,
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .fetch_one(pool)
+    .await?;
+
+    let median_minutes: Option<f64> = row.try_get("medianMinutes").ok().flatten();
+    let finished: i64 = row.try_get("finishedSessions").unwrap_or(0);
+    let tenure = u32::try_from(finished.max(0)).unwrap_or(u32::MAX);
+    let budget = danci_algo::exploration_budget(Some(config), median_minutes, tenure);
+    tracing::debug!(
+        user_id = %user_id,
+        session_id = session_id.unwrap_or(""),
+        expected_minutes = ?budget.expected_session_minutes,
+        tenure_sessions = budget.tenure_sessions,
+        scale = budget.scale,
+        "exploration budget"
+    );
+    Ok(Some(budget))
+}
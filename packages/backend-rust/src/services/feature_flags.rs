@@ -0,0 +1,177 @@
+//! Shared feature flags.
+//!
+//! Definitions live in the `feature_flags` table; evaluation (percentage
+//! rollout by hashed user id, per-user overrides) is `danci_algo::flags`, the
+//! same code the desktop app runs against its synced copy.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use danci_algo::{FlagDefinition, FlagSet};
+use sqlx::Row;
+
+use crate::db::DatabaseProxy;
+use crate::state::AppState;
+
+/// How long an instance serves cached definitions before re-reading them.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+/// Suggested polling interval for devices.
+pub const DEVICE_REFRESH_SECS: u64 = 300;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeatureFlagError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("invalid flag: {0}")]
+    Invalid(String),
+}
+
+pub fn validate_definition(def: &FlagDefinition) -> Result<(), FeatureFlagError> {
+    let key_ok = !def.key.is_empty()
+        && def.key.len() <= 64
+        && def
+            .key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.');
+    if !key_ok {
+        return Err(FeatureFlagError::Invalid(format!(
+            "key must be 1-64 chars of [a-z0-9_.]: {}",
+            def.key
+        )));
+    }
+    if !(0.0..=100.0).contains(&def.rollout_percent) {
+        return Err(FeatureFlagError::Invalid(format!(
+            "rolloutPercent out of range: {}",
+            def.rollout_percent
+        )));
+    }
+    Ok(())
+}
+
+/// Definitions with the set version, a counter bumped by a trigger on every
+/// write to `feature_flags`, deletes included (migration 091).
+pub async fn load_flag_set(proxy: &DatabaseProxy) -> Result<FlagSet, sqlx::Error> {
+    // Version first: a write landing in between then only makes devices
+    // fetch the same definitions again, never miss a change.
+    let version: i64 =
+        sqlx::query_scalar(r#"SELECT "version" FROM "feature_flag_version" WHERE "id""#)
+            .fetch_optional(proxy.pool())
+            .await?
+            .unwrap_or(0);
+    let rows = sqlx::query(
+        r#"SELECT "key","description","enabled","rolloutPercent","overrides"
+           FROM "feature_flags" ORDER BY "key""#,
+    )
+    .fetch_all(proxy.pool())
+    .await?;
+
+    let flags = rows
+        .iter()
+        .map(|row| {
+            let overrides: HashMap<String, bool> = row
+                .try_get::<serde_json::Value, _>("overrides")
+                .ok()
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default();
+            FlagDefinition {
+                key: row.try_get("key").unwrap_or_default(),
+                description: row.try_get("description").ok().flatten(),
+                enabled: row.try_get("enabled").unwrap_or(false),
+                rollout_percent: row.try_get("rolloutPercent").unwrap_or(100.0),
+                overrides,
+            }
+        })
+        .collect();
+
+    Ok(FlagSet::new(version, flags))
+}
+
+pub async fn upsert_flag(
+    proxy: &DatabaseProxy,
+    def: &FlagDefinition,
+    admin_id: &str,
+) -> Result<(), FeatureFlagError> {
+    validate_definition(def)?;
+    let overrides = serde_json::to_value(&def.overrides).unwrap_or_default();
+    sqlx::query(
+        r#"INSERT INTO "feature_flags"
+             ("key","description","enabled","rolloutPercent","overrides","updatedBy","updatedAt")
+           VALUES ($1,$2,$3,$4,$5,$6,$7)
+           ON CONFLICT ("key") DO UPDATE SET
+             "description" = EXCLUDED."description",
+             "enabled" = EXCLUDED."enabled",
+             "rolloutPercent" = EXCLUDED."rolloutPercent",
+             "overrides" = EXCLUDED."overrides",
+             "updatedBy" = EXCLUDED."updatedBy",
+             "updatedAt" = EXCLUDED."updatedAt""#,
+    )
+    .bind(&def.key)
+    .bind(&def.description)
+    .bind(def.enabled)
+    .bind(def.rollout_percent)
+    .bind(overrides)
+    .bind(admin_id)
+    .bind(Utc::now().naive_utc())
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_flag(proxy: &DatabaseProxy, key: &str) -> Result<bool, FeatureFlagError> {
+    let result = sqlx::query(r#"DELETE FROM "feature_flags" WHERE "key" = $1"#)
+        .bind(key)
+        .execute(proxy.pool())
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Definitions for this instance, re-read from the database once the cache
+/// is older than [`CACHE_TTL`]. Falls back to the stale copy on errors.
+pub async fn current_flag_set(state: &AppState) -> FlagSet {
+    let runtime = state.runtime();
+    let cached = runtime.cached_flag_set().await;
+    if let Some((ref flags, loaded_at)) = cached {
+        if loaded_at.elapsed() < CACHE_TTL {
+            return flags.clone();
+        }
+    }
+
+    let Some(proxy) = state.db_proxy() else {
+        return cached.map(|(flags, _)| flags).unwrap_or_default();
+    };
+    match load_flag_set(proxy.as_ref()).await {
+        Ok(flags) => {
+            runtime.set_flag_set(flags.clone()).await;
+            flags
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load feature flags, serving cached copy");
+            cached.map(|(flags, _)| flags).unwrap_or_default()
+        }
+    }
+}
+
+/// Drop the instance cache so the next read sees an admin change immediately.
+pub async fn refresh_flag_set(state: &AppState) -> FlagSet {
+    if let Some(proxy) = state.db_proxy() {
+        if let Ok(flags) = load_flag_set(proxy.as_ref()).await {
+            state.runtime().set_flag_set(flags.clone()).await;
+            return flags;
+        }
+    }
+    current_flag_set(state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_key_and_rollout() {
+        assert!(validate_definition(&FlagDefinition::new("desktop.sync_v2", true, 50.0)).is_ok());
+        assert!(validate_definition(&FlagDefinition::new("Bad Key", true, 50.0)).is_err());
+        assert!(validate_definition(&FlagDefinition::new("", true, 50.0)).is_err());
+        assert!(validate_definition(&FlagDefinition::new("ok", true, 101.0)).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! Difficulty bootstrap for new languages from a frequency list.
+//!
+//! Admins import a frequency list per language; danci-algo turns it into a
+//! difficulty, difficulty band, IRT beta prior and selection prior for each
+//! word form, stored in `language_frequency_priors`. Words are matched on
+//! their language and lower-cased spelling. The priors only stand in until
+//! real data exists: the new-word scorer prefers a population-calibrated
+//! difficulty, and the IRT paths prefer a calibrated beta or a played Elo.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use danci_algo::frequency::{
+    self, BootstrapConfig, BootstrapTable, CoverageReport, FrequencyPrior,
+};
+use danci_algo::language::is_valid_language_code;
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+/// Largest frequency list accepted in one import.
+pub const MAX_ENTRIES: usize = 200_000;
+/// Skipped line numbers echoed back in an import summary.
+const SKIPPED_SAMPLE: usize = 20;
+const INSERT_CHUNK: usize = 5_000;
+
+/// Prior IRT difficulty of a word that has no calibrated beta: the Elo
+/// difficulty once the word has played, otherwise its frequency prior,
+/// otherwise the default Elo. `w` is the `words` row and `fp` a LEFT JOIN of
+/// `language_frequency_priors` on [`PRIOR_JOIN`].
+pub const PRIOR_BETA_SQL: &str = r#"CASE
+    WHEN COALESCE(w."eloGamesPlayed", 0) = 0 AND fp."beta" IS NOT NULL THEN fp."beta"
+    ELSE GREATEST(-3.0, LEAST(3.0, (COALESCE(w."difficultyElo", 1200.0) - 1200.0) / 400.0))
+END"#;
+
+/// Joins a word `w` to its frequency prior `fp`.
+pub const PRIOR_JOIN: &str = r#"LEFT JOIN "language_frequency_priors" fp
+    ON fp."languageCode" = COALESCE(w."languageCode", 'en')
+   AND fp."word" = LOWER(TRIM(w."spelling"))"#;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrequencyBootstrapError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("not found")]
+    NotFound,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub language_code: String,
+    pub entries: usize,
+    pub from_counts: bool,
+    pub band_counts: Vec<u32>,
+    pub duplicates: u32,
+    pub skipped_count: usize,
+    /// First skipped line numbers, 1-based.
+    pub skipped_lines: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyListInfo {
+    pub language_code: String,
+    pub source: Option<String>,
+    pub entries: i64,
+    pub from_counts: bool,
+    pub config: serde_json::Value,
+    pub band_counts: serde_json::Value,
+    pub imported_by: Option<String>,
+    pub imported_at: String,
+}
+
+/// Parses and bootstraps a frequency list, replacing the language's priors.
+pub async fn import_list(
+    pool: &PgPool,
+    language: &str,
+    csv: &str,
+    config: &BootstrapConfig,
+    source: Option<&str>,
+    admin_id: &str,
+) -> Result<ImportSummary, FrequencyBootstrapError> {
+    if !is_valid_language_code(language) {
+        return Err(FrequencyBootstrapError::Invalid(format!(
+            "无效的语言代码: {language}"
+        )));
+    }
+    config
+        .validate()
+        .map_err(|e| FrequencyBootstrapError::Invalid(format!("参数无效: {e}")))?;
+    let list = frequency::parse_frequency_csv(csv)
+        .map_err(|_| FrequencyBootstrapError::Invalid("词频表中没有可用的行".to_string()))?;
+    if list.entries.len() > MAX_ENTRIES {
+        return Err(FrequencyBootstrapError::Invalid(format!(
+            "词频表最多 {MAX_ENTRIES} 个词"
+        )));
+    }
+    let table = frequency::bootstrap_priors(&list, config);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO "language_frequency_lists"
+            ("languageCode","source","entries","fromCounts","config","bandCounts","importedBy","importedAt")
+        VALUES ($1,$2,$3,$4,$5,$6,$7,NOW())
+        ON CONFLICT ("languageCode") DO UPDATE SET
+            "source" = EXCLUDED."source",
+            "entries" = EXCLUDED."entries",
+            "fromCounts" = EXCLUDED."fromCounts",
+            "config" = EXCLUDED."config",
+            "bandCounts" = EXCLUDED."bandCounts",
+            "importedBy" = EXCLUDED."importedBy",
+            "importedAt" = EXCLUDED."importedAt"
+        "#,
+    )
+    .bind(language)
+    .bind(source)
+    .bind(table.priors.len() as i32)
+    .bind(table.from_counts)
+    .bind(sqlx::types::Json(config))
+    .bind(sqlx::types::Json(&table.band_counts))
+    .bind(admin_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(r#"DELETE FROM "language_frequency_priors" WHERE "languageCode" = $1"#)
+        .bind(language)
+        .execute(&mut *tx)
+        .await?;
+    for chunk in table.priors.chunks(INSERT_CHUNK) {
+        insert_priors(&mut tx, language, chunk).await?;
+    }
+    tx.commit().await?;
+
+    Ok(ImportSummary {
+        language_code: language.to_string(),
+        entries: table.priors.len(),
+        from_counts: table.from_counts,
+        band_counts: table.band_counts,
+        duplicates: list.duplicates,
+        skipped_count: list.skipped_lines.len(),
+        skipped_lines: list
+            .skipped_lines
+            .into_iter()
+            .take(SKIPPED_SAMPLE)
+            .collect(),
+    })
+}
+
+async fn insert_priors(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    language: &str,
+    priors: &[FrequencyPrior],
+) -> Result<(), sqlx::Error> {
+    let words: Vec<&str> = priors.iter().map(|p| p.word.as_str()).collect();
+    let ranks: Vec<i32> = priors.iter().map(|p| p.rank as i32).collect();
+    let zipfs: Vec<f64> = priors.iter().map(|p| p.zipf).collect();
+    let difficulties: Vec<f64> = priors.iter().map(|p| p.difficulty).collect();
+    let bands: Vec<i32> = priors.iter().map(|p| p.band as i32).collect();
+    let betas: Vec<f64> = priors.iter().map(|p| p.beta).collect();
+    let selection: Vec<f64> = priors.iter().map(|p| p.selection_prior).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO "language_frequency_priors"
+            ("languageCode","word","rank","zipf","difficulty","band","beta","selectionPrior")
+        SELECT $1, p."word", p."rank", p."zipf", p."difficulty", p."band", p."beta", p."selectionPrior"
+        FROM UNNEST($2::text[], $3::int[], $4::float8[], $5::float8[], $6::int[], $7::float8[], $8::float8[])
+            AS p("word", "rank", "zipf", "difficulty", "band", "beta", "selectionPrior")
+        "#,
+    )
+    .bind(language)
+    .bind(&words)
+    .bind(&ranks)
+    .bind(&zipfs)
+    .bind(&difficulties)
+    .bind(&bands)
+    .bind(&betas)
+    .bind(&selection)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_imports(pool: &PgPool) -> Result<Vec<FrequencyListInfo>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "languageCode","source","entries","fromCounts","config","bandCounts","importedBy","importedAt"
+        FROM "language_frequency_lists"
+        ORDER BY "languageCode"
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(FrequencyListInfo {
+                language_code: row.try_get("languageCode")?,
+                source: row.try_get("source")?,
+                entries: row.try_get::<i32, _>("entries")? as i64,
+                from_counts: row.try_get("fromCounts")?,
+                config: row.try_get("config")?,
+                band_counts: row.try_get("bandCounts")?,
+                imported_by: row.try_get("importedBy")?,
+                imported_at: format_timestamp(row.try_get("importedAt")?),
+            })
+        })
+        .collect()
+}
+
+/// Coverage of a wordbook's words by the language's frequency list.
+pub async fn wordbook_coverage(
+    pool: &PgPool,
+    language: &str,
+    wordbook_id: &str,
+    min_word_coverage: f64,
+) -> Result<CoverageReport, FrequencyBootstrapError> {
+    let list = sqlx::query(
+        r#"SELECT "bandCounts","fromCounts" FROM "language_frequency_lists" WHERE "languageCode" = $1"#,
+    )
+    .bind(language)
+    .fetch_optional(pool)
+    .await?;
+    let Some(list) = list else {
+        return Err(FrequencyBootstrapError::NotFound);
+    };
+    let book_exists: Option<String> =
+        sqlx::query_scalar(r#"SELECT "id" FROM "word_books" WHERE "id" = $1"#)
+            .bind(wordbook_id)
+            .fetch_optional(pool)
+            .await?;
+    if book_exists.is_none() {
+        return Err(FrequencyBootstrapError::NotFound);
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT "word","rank","zipf","difficulty","band","beta","selectionPrior"
+        FROM "language_frequency_priors"
+        WHERE "languageCode" = $1
+        "#,
+    )
+    .bind(language)
+    .fetch_all(pool)
+    .await?;
+    let priors = rows
+        .iter()
+        .map(|row| {
+            Ok(FrequencyPrior {
+                word: row.try_get("word")?,
+                rank: row.try_get::<i32, _>("rank")? as u32,
+                zipf: row.try_get("zipf")?,
+                difficulty: row.try_get("difficulty")?,
+                band: row.try_get::<i32, _>("band")? as u32,
+                beta: row.try_get("beta")?,
+                selection_prior: row.try_get("selectionPrior")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+    let table = BootstrapTable {
+        priors,
+        band_counts: serde_json::from_value(list.try_get("bandCounts")?).unwrap_or_default(),
+        from_counts: list.try_get("fromCounts")?,
+    };
+
+    let words: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT "spelling" FROM "words"
+        WHERE "wordBookId" = $1 AND "deletedAt" IS NULL
+        ORDER BY "createdAt", "id"
+        "#,
+    )
+    .bind(wordbook_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(frequency::coverage(&table, &words, min_word_coverage))
+}
+
+/// Bootstrapped [0, 1] difficulties of the given words; words without a
+/// frequency prior are absent.
+pub async fn bootstrapped_difficulties(
+    pool: &PgPool,
+    word_ids: &[String],
+) -> Result<HashMap<String, f64>, sqlx::Error> {
+    if word_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query(&format!(
+        r#"SELECT w."id", fp."difficulty" FROM "words" w {PRIOR_JOIN}
+           WHERE w."id" = ANY($1) AND fp."difficulty" IS NOT NULL"#
+    ))
+    .bind(word_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| Some((row.try_get("id").ok()?, row.try_get("difficulty").ok()?)))
+        .collect())
+}
+
+/// Frequency beta of a word whose Elo difficulty has never been updated.
+pub async fn bootstrap_beta(pool: &PgPool, word_id: &str) -> Result<Option<f64>, sqlx::Error> {
+    let beta: Option<Option<f64>> = sqlx::query_scalar(&format!(
+        r#"SELECT fp."beta" FROM "words" w {PRIOR_JOIN}
+           WHERE w."id" = $1 AND COALESCE(w."eloGamesPlayed", 0) = 0"#
+    ))
+    .bind(word_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(beta.flatten())
+}
+
+fn format_timestamp(at: NaiveDateTime) -> String {
+    DateTime::<Utc>::from_naive_utc_and_offset(at, Utc).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -0,0 +1,376 @@
+//! Deep health check: probes every dependency and grades each against
+//! pass/warn/fail thresholds.
+//!
+//! Only critical checks (the primary database and, when configured, the
+//! SQLite fallback) can fail the instance; everything else degrades the
+//! overall status to `warn` so a load balancer keeps routing to it.
+//!
+//! Probes hit every dependency, so the report is cached for
+//! [`REPORT_CACHE_TTL`] and concurrent callers share one run.
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::db::pending_writes::SqlitePendingWriteStore;
+use crate::services::embedding_provider::EmbeddingProvider;
+use crate::services::llm_provider::{self, LLMProvider};
+use crate::state::AppState;
+use crate::workers;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Not configured on this instance; ignored in the overall status.
+    Skip,
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Values at or above `warn` warn, values at or above `fail` fail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    pub warn: u64,
+    pub fail: u64,
+}
+
+impl Thresholds {
+    fn from_env(prefix: &str, warn: u64, fail: u64) -> Self {
+        let read = |suffix: &str, default: u64| {
+            std::env::var(format!("{prefix}_{suffix}"))
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            warn: read("WARN", warn),
+            fail: read("FAIL", fail),
+        }
+    }
+
+    pub fn classify(&self, value: u64) -> CheckStatus {
+        if value >= self.fail {
+            CheckStatus::Fail
+        } else if value >= self.warn {
+            CheckStatus::Warn
+        } else {
+            CheckStatus::Pass
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Backlog size or age in seconds, depending on the check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub observed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl HealthCheck {
+    fn new(name: impl Into<String>, status: CheckStatus, critical: bool) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            critical,
+            latency_ms: None,
+            observed: None,
+            message: None,
+        }
+    }
+
+    fn skipped(name: impl Into<String>, message: &str) -> Self {
+        Self::new(name, CheckStatus::Skip, false).with_message(message)
+    }
+
+    fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepHealthReport {
+    pub status: CheckStatus,
+    pub timestamp: String,
+    pub duration_ms: u64,
+    pub checks: Vec<HealthCheck>,
+}
+
+/// Worst status among the checks, with non-critical failures capped at `warn`.
+pub fn overall_status(checks: &[HealthCheck]) -> CheckStatus {
+    checks
+        .iter()
+        .map(|c| match c.status {
+            CheckStatus::Fail if !c.critical => CheckStatus::Warn,
+            CheckStatus::Skip => CheckStatus::Pass,
+            other => other,
+        })
+        .max()
+        .unwrap_or(CheckStatus::Pass)
+}
+
+fn probe_timeout() -> Duration {
+    Duration::from_millis(
+        std::env::var("HEALTH_DEEP_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(2000),
+    )
+}
+
+/// Runs a probe with the deep-check timeout and grades its latency.
+async fn timed<F, E>(name: &str, critical: bool, thresholds: Thresholds, probe: F) -> HealthCheck
+where
+    F: Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(probe_timeout(), probe).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let mut check = match result {
+        Ok(Ok(())) => HealthCheck::new(name, thresholds.classify(latency_ms), critical),
+        Ok(Err(e)) => {
+            HealthCheck::new(name, CheckStatus::Fail, critical).with_message(e.to_string())
+        }
+        Err(_) => HealthCheck::new(name, CheckStatus::Fail, critical).with_message("timeout"),
+    };
+    check.latency_ms = Some(latency_ms);
+    check
+}
+
+async fn postgres_check(state: &AppState) -> HealthCheck {
+    let Some(proxy) = state.db_proxy() else {
+        return HealthCheck::new("postgres", CheckStatus::Fail, true)
+            .with_message("database not initialized");
+    };
+    let thresholds = Thresholds::from_env("HEALTH_POSTGRES_LATENCY_MS", 200, 1000);
+    timed("postgres", true, thresholds, async {
+        sqlx::query("SELECT 1")
+            .execute(proxy.pool())
+            .await
+            .map(|_| ())
+    })
+    .await
+}
+
+async fn sqlite_checks(state: &AppState) -> Vec<HealthCheck> {
+    let pool = match state.db_proxy() {
+        Some(proxy) => proxy.fallback_pool().await,
+        None => None,
+    };
+    let Some(pool) = pool else {
+        return vec![
+            HealthCheck::skipped("sqlite", "no SQLite fallback configured"),
+            HealthCheck::skipped("syncBacklog", "no SQLite fallback configured"),
+        ];
+    };
+
+    let thresholds = Thresholds::from_env("HEALTH_SQLITE_LATENCY_MS", 50, 500);
+    let sqlite = timed("sqlite", true, thresholds, async {
+        sqlx::query("SELECT 1").execute(&pool).await.map(|_| ())
+    })
+    .await;
+
+    let thresholds = Thresholds::from_env("HEALTH_SYNC_BACKLOG", 1000, 10_000);
+    let backlog = match SqlitePendingWriteStore::new(pool).count().await {
+        Ok(count) => {
+            let mut check = HealthCheck::new("syncBacklog", thresholds.classify(count), false);
+            check.observed = Some(count);
+            check
+        }
+        Err(e) => {
+            HealthCheck::new("syncBacklog", CheckStatus::Fail, false).with_message(e.to_string())
+        }
+    };
+
+    vec![sqlite, backlog]
+}
+
+async fn redis_checks(state: &AppState) -> Vec<HealthCheck> {
+    let redis = match state.cache_raw() {
+        None => HealthCheck::skipped("redis", "REDIS_URL not configured"),
+        Some(_) if !state.runtime().is_redis_enabled() => {
+            HealthCheck::new("redis", CheckStatus::Warn, false).with_message("disabled at runtime")
+        }
+        Some(cache) => {
+            let thresholds = Thresholds::from_env("HEALTH_REDIS_LATENCY_MS", 50, 500);
+            timed("redis", false, thresholds, async {
+                if cache.is_connected().await {
+                    Ok(())
+                } else {
+                    Err("PING failed")
+                }
+            })
+            .await
+        }
+    };
+    // Writes go straight to a single Postgres primary, so no instance holds
+    // a fencing lock.
+    let fencing = HealthCheck::skipped("fencing", "standalone mode, no fencing lock");
+    vec![redis, fencing]
+}
+
+fn worker_checks() -> Vec<HealthCheck> {
+    if !workers::is_worker_leader() {
+        return vec![HealthCheck::skipped(
+            "workers",
+            "workers run on the leader instance",
+        )];
+    }
+
+    // Ages are graded in multiples of the schedule's interval.
+    let multiples = Thresholds::from_env("HEALTH_WORKER_STALE_INTERVALS", 2, 4);
+    workers::ticks::snapshot()
+        .into_iter()
+        .map(|tick| {
+            let name = format!("worker:{}", tick.name);
+            let age = tick.age.as_secs();
+            let mut check = match tick.expected_interval {
+                Some(interval) => {
+                    let interval = interval.as_secs().max(1);
+                    let status = if age > interval * multiples.fail {
+                        CheckStatus::Fail
+                    } else if age > interval * multiples.warn {
+                        CheckStatus::Warn
+                    } else {
+                        CheckStatus::Pass
+                    };
+                    HealthCheck::new(name, status, false)
+                }
+                None => HealthCheck::new(name, CheckStatus::Pass, false)
+                    .with_message(format!("unrecognized schedule {}", tick.schedule)),
+            };
+            if !tick.ticked && check.message.is_none() {
+                check.message = Some("not run since startup".to_string());
+            }
+            check.observed = Some(age);
+            check
+        })
+        .collect()
+}
+
+/// Any HTTP response counts as reachable; only transport errors fail.
+async fn endpoint_check(name: &str, endpoint: &str) -> HealthCheck {
+    let client = reqwest::Client::builder()
+        .timeout(probe_timeout())
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new());
+    let thresholds = Thresholds::from_env("HEALTH_PROVIDER_LATENCY_MS", 1000, 5000);
+    timed(name, false, thresholds, async {
+        client.head(endpoint).send().await.map(|_| ())
+    })
+    .await
+}
+
+async fn provider_checks(state: &AppState) -> Vec<HealthCheck> {
+    let llm = LLMProvider::from_env();
+    let llm_check =
+        if !llm_provider::is_llm_runtime_enabled() || llm_provider::is_llm_runtime_mock() {
+            HealthCheck::skipped("llmProvider", "disabled or mocked at runtime")
+        } else if !llm.is_available() {
+            HealthCheck::skipped("llmProvider", "not configured")
+        } else {
+            endpoint_check("llmProvider", llm.api_endpoint()).await
+        };
+
+    let embedder = match state.db_proxy() {
+        Some(proxy) => EmbeddingProvider::from_db(&proxy).await,
+        None => EmbeddingProvider::from_env(),
+    };
+    let embedding_check = if embedder.is_available() {
+        endpoint_check("embeddingProvider", embedder.api_endpoint()).await
+    } else {
+        HealthCheck::skipped("embeddingProvider", "not configured")
+    };
+
+    vec![llm_check, embedding_check]
+}
+
+/// How long a deep report is served before the probes run again.
+pub const REPORT_CACHE_TTL: Duration = Duration::from_secs(5);
+
+static REPORT_CACHE: OnceLock<Mutex<Option<(Instant, DeepHealthReport)>>> = OnceLock::new();
+
+/// Latest report if it is younger than [`REPORT_CACHE_TTL`], otherwise a
+/// fresh run. The lock is held across the run, so a burst of requests
+/// probes the dependencies once.
+pub async fn cached(state: &AppState) -> DeepHealthReport {
+    let mut cache = REPORT_CACHE.get_or_init(|| Mutex::new(None)).lock().await;
+    if let Some((at, report)) = cache.as_ref() {
+        if at.elapsed() < REPORT_CACHE_TTL {
+            return report.clone();
+        }
+    }
+    let report = run(state).await;
+    *cache = Some((Instant::now(), report.clone()));
+    report
+}
+
+pub async fn run(state: &AppState) -> DeepHealthReport {
+    let started = Instant::now();
+    let (postgres, sqlite, redis, providers) = tokio::join!(
+        postgres_check(state),
+        sqlite_checks(state),
+        redis_checks(state),
+        provider_checks(state),
+    );
+
+    let mut checks = vec![postgres];
+    checks.extend(sqlite);
+    checks.extend(redis);
+    checks.extend(worker_checks());
+    checks.extend(providers);
+
+    DeepHealthReport {
+        status: overall_status(&checks),
+        timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        duration_ms: started.elapsed().as_millis() as u64,
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds_classify() {
+        let t = Thresholds {
+            warn: 100,
+            fail: 500,
+        };
+        assert_eq!(t.classify(0), CheckStatus::Pass);
+        assert_eq!(t.classify(100), CheckStatus::Warn);
+        assert_eq!(t.classify(499), CheckStatus::Warn);
+        assert_eq!(t.classify(500), CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_only_critical_failures_fail_overall() {
+        let mut checks = vec![
+            HealthCheck::new("postgres", CheckStatus::Pass, true),
+            HealthCheck::skipped("sqlite", "n/a"),
+            HealthCheck::new("llmProvider", CheckStatus::Fail, false),
+        ];
+        assert_eq!(overall_status(&checks), CheckStatus::Warn);
+
+        checks[0].status = CheckStatus::Fail;
+        assert_eq!(overall_status(&checks), CheckStatus::Fail);
+
+        let skipped = vec![HealthCheck::skipped("redis", "n/a")];
+        assert_eq!(overall_status(&skipped), CheckStatus::Pass);
+    }
+}
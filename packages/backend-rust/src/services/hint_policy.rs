@@ -0,0 +1,147 @@
+//! Per-question hint levels chosen by the danci-algo hint policy.
+//!
+//! Each user has one Thompson sampler over hint levels, conditioned on the
+//! word's difficulty band and recall bucket. The client echoes the context
+//! it was given when it reports how the question went, so feedback lands on
+//! the arm that was actually sampled even if the word's recall moved since.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use danci_algo::rng::{domains, RngFactory};
+use danci_algo::{HintChoice, HintContext, HintPolicy};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::services::{difficulty_calibration, time_limit};
+
+/// Difficulty assumed for words without a calibrated value.
+const DEFAULT_DIFFICULTY: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordHint {
+    pub word_id: String,
+    #[serde(flatten)]
+    pub choice: HintChoice,
+}
+
+pub async fn load_policy(pool: &PgPool, user_id: &str) -> Result<HintPolicy, sqlx::Error> {
+    let policy: Option<serde_json::Value> =
+        sqlx::query_scalar(r#"SELECT "policy" FROM "user_hint_policies" WHERE "userId" = $1"#)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(policy
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default())
+}
+
+/// Samples a hint level for each `(word_id, difficulty, recall)`.
+pub fn choose(policy: &HintPolicy, words: &[(String, f64, f64)]) -> Vec<WordHint> {
+    let mut rng = RngFactory::new(rand::random()).stream(domains::HINT_POLICY);
+    words
+        .iter()
+        .map(|(word_id, difficulty, recall)| {
+            let context = HintContext::from_scores(*difficulty, *recall, &policy.config);
+            WordHint {
+                word_id: word_id.clone(),
+                choice: policy.select(context, &mut rng),
+            }
+        })
+        .collect()
+}
+
+/// Hint levels for the given words, in the order given. Difficulty is the
+/// calibrated one and recall the same estimate the time limits use.
+pub async fn recommend_for_words(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<Vec<WordHint>, sqlx::Error> {
+    if word_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (policy, difficulties, limits) = tokio::try_join!(
+        load_policy(pool, user_id),
+        difficulty_calibration::calibrated_difficulties(pool, word_ids),
+        time_limit::recommend_for_words(pool, user_id, word_ids),
+    )?;
+    let recalls: HashMap<&str, f64> = limits
+        .items
+        .iter()
+        .map(|item| (item.word_id.as_str(), item.recall_probability))
+        .collect();
+    let words: Vec<(String, f64, f64)> = word_ids
+        .iter()
+        .filter_map(|id| {
+            let recall = *recalls.get(id.as_str())?;
+            let difficulty = difficulties.get(id).copied().unwrap_or(DEFAULT_DIFFICULTY);
+            Some((id.clone(), difficulty, recall))
+        })
+        .collect();
+    Ok(choose(&policy, &words))
+}
+
+/// Records how a question with the given hint level went; returns the reward
+/// credited to the policy.
+pub async fn record_feedback(
+    pool: &PgPool,
+    user_id: &str,
+    context: HintContext,
+    level: u32,
+    correct: bool,
+) -> Result<f64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    // Seed the row first so the first two reports of a user also serialize.
+    sqlx::query(
+        r#"INSERT INTO "user_hint_policies" ("userId", "policy") VALUES ($1, $2)
+           ON CONFLICT ("userId") DO NOTHING"#,
+    )
+    .bind(user_id)
+    .bind(serde_json::to_value(HintPolicy::default()).unwrap_or_default())
+    .execute(&mut *tx)
+    .await?;
+    let row =
+        sqlx::query(r#"SELECT "policy" FROM "user_hint_policies" WHERE "userId" = $1 FOR UPDATE"#)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let mut policy: HintPolicy = row
+        .and_then(|r| r.try_get::<serde_json::Value, _>("policy").ok())
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    let reward = policy.update(context, level, correct);
+    sqlx::query(
+        r#"UPDATE "user_hint_policies" SET "policy" = $2, "updatedAt" = $3 WHERE "userId" = $1"#,
+    )
+    .bind(user_id)
+    .bind(serde_json::to_value(&policy).unwrap_or_default())
+    .bind(Utc::now().naive_utc())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(reward)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_keeps_order_and_buckets() {
+        let policy = HintPolicy::default();
+        let hints = choose(
+            &policy,
+            &[
+                ("hard".to_string(), 0.95, 0.05),
+                ("easy".to_string(), 0.05, 0.95),
+            ],
+        );
+        assert_eq!(hints[0].word_id, "hard");
+        assert_eq!(hints[0].choice.context.difficulty_band, 2);
+        assert_eq!(hints[1].choice.context.recall_bucket, 3);
+        assert!(hints.iter().all(|h| h.choice.level < 3));
+    }
+}
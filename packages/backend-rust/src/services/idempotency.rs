@@ -0,0 +1,351 @@
+//! Idempotency keys for mutation endpoints.
+//!
+//! A client-supplied `Idempotency-Key` is stored per user together with a
+//! hash of the request and, once the handler succeeds, the response.
+//! Retries with the same key replay the stored response instead of running
+//! the mutation again. Rows live in Postgres, or in the SQLite fallback when
+//! the primary is degraded.
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row, SqlitePool};
+
+use crate::db::state_machine::DatabaseState;
+use crate::db::DatabaseProxy;
+
+/// How long a key is remembered.
+pub const KEY_TTL_MS: i64 = 24 * 3600 * 1000;
+/// An unfinished reservation older than this is assumed abandoned (crash,
+/// dropped connection) and may be taken over by a retry.
+pub const IN_PROGRESS_TIMEOUT_MS: i64 = 60 * 1000;
+/// Longest accepted key.
+pub const MAX_KEY_LEN: usize = 255;
+
+const SQLITE_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS "idempotency_keys" (
+  "scope" TEXT NOT NULL,
+  "key" TEXT NOT NULL,
+  "requestHash" TEXT NOT NULL,
+  "status" TEXT NOT NULL DEFAULT 'IN_PROGRESS',
+  "responseStatus" INTEGER,
+  "contentType" TEXT,
+  "responseBody" TEXT,
+  "responseHash" TEXT,
+  "createdAt" INTEGER NOT NULL,
+  "expiresAt" INTEGER NOT NULL,
+  PRIMARY KEY ("scope", "key")
+)"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BeginOutcome {
+    /// Key reserved; run the handler and call [`IdempotencyStore::complete`].
+    Started,
+    /// Same request already finished; replay this response.
+    Replay(StoredResponse),
+    /// Same key is still being processed by another request.
+    InProgress,
+    /// Key was already used for a different request.
+    Mismatch,
+}
+
+pub enum IdempotencyStore {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+pub fn sha256_hex(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Fingerprint of a request; a key may only be reused for the same one.
+pub fn request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    sha256_hex(&[method.as_bytes(), b"\n", path.as_bytes(), b"\n", body])
+}
+
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+impl IdempotencyStore {
+    /// Postgres normally; the SQLite fallback while the primary is degraded.
+    pub async fn select(proxy: &DatabaseProxy) -> Option<Self> {
+        let state = proxy.state_machine().read().await.state();
+        match state {
+            DatabaseState::Degraded | DatabaseState::Unavailable => {
+                proxy.fallback_pool().await.map(Self::Sqlite)
+            }
+            _ => match proxy.primary_pool().await {
+                Some(pool) => Some(Self::Postgres(pool)),
+                None => proxy.fallback_pool().await.map(Self::Sqlite),
+            },
+        }
+    }
+
+    pub async fn begin(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<BeginOutcome, sqlx::Error> {
+        let now = Utc::now().timestamp_millis();
+        if self.try_reserve(scope, key, request_hash, now).await? {
+            return Ok(BeginOutcome::Started);
+        }
+
+        let Some(row) = self.load(scope, key).await? else {
+            // Deleted between the insert and the read; treat as a fresh key.
+            return Ok(if self.try_reserve(scope, key, request_hash, now).await? {
+                BeginOutcome::Started
+            } else {
+                BeginOutcome::InProgress
+            });
+        };
+
+        let abandoned = row.response.is_none() && row.created_at + IN_PROGRESS_TIMEOUT_MS < now;
+        if row.expires_at < now || abandoned {
+            self.release(scope, key).await?;
+            return Ok(if self.try_reserve(scope, key, request_hash, now).await? {
+                BeginOutcome::Started
+            } else {
+                BeginOutcome::InProgress
+            });
+        }
+        if row.request_hash != request_hash {
+            return Ok(BeginOutcome::Mismatch);
+        }
+        Ok(match row.response {
+            Some(response) => BeginOutcome::Replay(response),
+            None => BeginOutcome::InProgress,
+        })
+    }
+
+    pub async fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), sqlx::Error> {
+        let response_hash = sha256_hex(&[response.body.as_bytes()]);
+        let status = i32::from(response.status);
+        let sql = r#"UPDATE "idempotency_keys"
+           SET "status" = 'COMPLETED', "responseStatus" = $3, "contentType" = $4,
+               "responseBody" = $5, "responseHash" = $6
+           WHERE "scope" = $1 AND "key" = $2"#;
+        match self {
+            Self::Postgres(pool) => {
+                sqlx::query(sql)
+                    .bind(scope)
+                    .bind(key)
+                    .bind(status)
+                    .bind(&response.content_type)
+                    .bind(&response.body)
+                    .bind(&response_hash)
+                    .execute(pool)
+                    .await?;
+            }
+            Self::Sqlite(pool) => {
+                sqlx::query(sql)
+                    .bind(scope)
+                    .bind(key)
+                    .bind(status)
+                    .bind(&response.content_type)
+                    .bind(&response.body)
+                    .bind(&response_hash)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget a reservation so the client can retry (server errors, panics).
+    pub async fn release(&self, scope: &str, key: &str) -> Result<(), sqlx::Error> {
+        let sql = r#"DELETE FROM "idempotency_keys" WHERE "scope" = $1 AND "key" = $2"#;
+        match self {
+            Self::Postgres(pool) => {
+                sqlx::query(sql).bind(scope).bind(key).execute(pool).await?;
+            }
+            Self::Sqlite(pool) => {
+                sqlx::query(sql).bind(scope).bind(key).execute(pool).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn purge_expired(&self) -> Result<u64, sqlx::Error> {
+        let now = Utc::now().timestamp_millis();
+        let sql = r#"DELETE FROM "idempotency_keys" WHERE "expiresAt" < $1"#;
+        Ok(match self {
+            Self::Postgres(pool) => sqlx::query(sql)
+                .bind(now)
+                .execute(pool)
+                .await?
+                .rows_affected(),
+            Self::Sqlite(pool) => {
+                ensure_sqlite_table(pool).await?;
+                sqlx::query(sql)
+                    .bind(now)
+                    .execute(pool)
+                    .await?
+                    .rows_affected()
+            }
+        })
+    }
+
+    async fn try_reserve(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+        now: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let sql = r#"INSERT INTO "idempotency_keys"
+             ("scope", "key", "requestHash", "status", "createdAt", "expiresAt")
+           VALUES ($1, $2, $3, 'IN_PROGRESS', $4, $5)
+           ON CONFLICT ("scope", "key") DO NOTHING"#;
+        let expires_at = now + KEY_TTL_MS;
+        let inserted = match self {
+            Self::Postgres(pool) => sqlx::query(sql)
+                .bind(scope)
+                .bind(key)
+                .bind(request_hash)
+                .bind(now)
+                .bind(expires_at)
+                .execute(pool)
+                .await?
+                .rows_affected(),
+            Self::Sqlite(pool) => {
+                ensure_sqlite_table(pool).await?;
+                sqlx::query(sql)
+                    .bind(scope)
+                    .bind(key)
+                    .bind(request_hash)
+                    .bind(now)
+                    .bind(expires_at)
+                    .execute(pool)
+                    .await?
+                    .rows_affected()
+            }
+        };
+        Ok(inserted > 0)
+    }
+
+    async fn load(&self, scope: &str, key: &str) -> Result<Option<KeyRow>, sqlx::Error> {
+        let sql = r#"SELECT "requestHash", "status", "responseStatus", "contentType",
+                  "responseBody", "createdAt", "expiresAt"
+           FROM "idempotency_keys" WHERE "scope" = $1 AND "key" = $2"#;
+        Ok(match self {
+            Self::Postgres(pool) => sqlx::query(sql)
+                .bind(scope)
+                .bind(key)
+                .fetch_optional(pool)
+                .await?
+                .map(|row| KeyRow {
+                    request_hash: row.try_get("requestHash").unwrap_or_default(),
+                    created_at: row.try_get("createdAt").unwrap_or(0),
+                    expires_at: row.try_get("expiresAt").unwrap_or(0),
+                    response: stored_response(
+                        row.try_get("status").unwrap_or_default(),
+                        row.try_get("responseStatus").ok().flatten(),
+                        row.try_get("contentType").ok().flatten(),
+                        row.try_get("responseBody").ok().flatten(),
+                    ),
+                }),
+            Self::Sqlite(pool) => sqlx::query(sql)
+                .bind(scope)
+                .bind(key)
+                .fetch_optional(pool)
+                .await?
+                .map(|row| KeyRow {
+                    request_hash: row.try_get("requestHash").unwrap_or_default(),
+                    created_at: row.try_get("createdAt").unwrap_or(0),
+                    expires_at: row.try_get("expiresAt").unwrap_or(0),
+                    response: stored_response(
+                        row.try_get("status").unwrap_or_default(),
+                        row.try_get("responseStatus").ok().flatten(),
+                        row.try_get("contentType").ok().flatten(),
+                        row.try_get("responseBody").ok().flatten(),
+                    ),
+                }),
+        })
+    }
+}
+
+struct KeyRow {
+    request_hash: String,
+    created_at: i64,
+    expires_at: i64,
+    response: Option<StoredResponse>,
+}
+
+fn stored_response(
+    status: String,
+    response_status: Option<i32>,
+    content_type: Option<String>,
+    body: Option<String>,
+) -> Option<StoredResponse> {
+    if status != "COMPLETED" {
+        return None;
+    }
+    Some(StoredResponse {
+        status: response_status.and_then(|s| u16::try_from(s).ok())?,
+        content_type,
+        body: body.unwrap_or_default(),
+    })
+}
+
+/// The fallback schema is only applied to fresh SQLite files, so the table is
+/// created on first use for databases that predate it.
+async fn ensure_sqlite_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(SQLITE_TABLE_SQL).execute(pool).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_hash_covers_method_path_and_body() {
+        let base = request_hash("POST", "/api/records", b"{\"a\":1}");
+        assert_eq!(base, request_hash("POST", "/api/records", b"{\"a\":1}"));
+        assert_ne!(base, request_hash("POST", "/api/records", b"{\"a\":2}"));
+        assert_ne!(base, request_hash("POST", "/api/wordbooks", b"{\"a\":1}"));
+        assert_ne!(base, request_hash("PUT", "/api/records", b"{\"a\":1}"));
+    }
+
+    #[test]
+    fn validates_keys() {
+        assert!(is_valid_key("0b6f5c1e-7d7c-4b8c-9a7e-3f0f1b2c3d4e"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("has space"));
+        assert!(!is_valid_key(&"k".repeat(MAX_KEY_LEN + 1)));
+    }
+
+    #[test]
+    fn only_completed_rows_replay() {
+        assert_eq!(
+            stored_response("IN_PROGRESS".into(), Some(201), None, Some("{}".into())),
+            None
+        );
+        let replay = stored_response(
+            "COMPLETED".into(),
+            Some(201),
+            Some("application/json".into()),
+            Some("{}".into()),
+        )
+        .unwrap();
+        assert_eq!(replay.status, 201);
+    }
+}
@@ -0,0 +1,494 @@
+//! Knowledge graph of word relationships.
+//!
+//! Edges live in `word_relations` and are undirected (stored with the smaller
+//! word id first). Global edges have no `userId`; a user can add personal
+//! edges, typically words they keep confusing, which only appear in their own
+//! graph. `same_root` and `confused` edges are derived from the morpheme
+//! tables and the embedding confusion cache by `rebuild_derived_edges`.
+//!
+//! Graph queries load the neighbourhood of the seed words hop by hop and run
+//! on the in-memory `WordGraph`. The scheduler passes each batch's clusters
+//! to the session composer as a separation constraint, so related words are
+//! contrasted within a session instead of shown back to back.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::db::DatabaseProxy;
+
+pub const MAX_DEPTH: usize = 4;
+/// Neighbourhood loads stop expanding past this many words.
+const MAX_NODES: usize = 2000;
+/// Confusion-cache pairs closer than this become `confused` edges.
+const CONFUSION_DISTANCE_THRESHOLD: f64 = 0.15;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GraphError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("edge not found")]
+    NotFound,
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationType {
+    Synonym,
+    Antonym,
+    SameRoot,
+    Confused,
+}
+
+impl RelationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Synonym => "synonym",
+            Self::Antonym => "antonym",
+            Self::SameRoot => "same_root",
+            Self::Confused => "confused",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "synonym" => Some(Self::Synonym),
+            "antonym" => Some(Self::Antonym),
+            "same_root" | "same-root" => Some(Self::SameRoot),
+            "confused" | "commonly_confused" | "commonly-confused" => Some(Self::Confused),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordEdge {
+    pub id: String,
+    pub source_word_id: String,
+    pub target_word_id: String,
+    pub relation_type: RelationType,
+    pub weight: f64,
+    /// Set for personal edges.
+    pub user_id: Option<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Neighbor {
+    pub word_id: String,
+    pub relation_type: RelationType,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathStep {
+    pub word_id: String,
+    /// Relation used to reach this word; `None` for the start word.
+    pub via: Option<RelationType>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WordGraph {
+    adjacency: HashMap<String, Vec<Neighbor>>,
+}
+
+impl WordGraph {
+    pub fn from_edges<'a>(edges: impl IntoIterator<Item = &'a WordEdge>) -> Self {
+        let mut graph = Self::default();
+        for edge in edges {
+            graph.add_edge(edge);
+        }
+        graph
+    }
+
+    fn add_edge(&mut self, edge: &WordEdge) {
+        for (from, to) in [
+            (&edge.source_word_id, &edge.target_word_id),
+            (&edge.target_word_id, &edge.source_word_id),
+        ] {
+            let neighbors = self.adjacency.entry(from.clone()).or_default();
+            // Global and personal edges of the same type collapse into one.
+            match neighbors
+                .iter_mut()
+                .find(|n| n.word_id == *to && n.relation_type == edge.relation_type)
+            {
+                Some(existing) => existing.weight = existing.weight.max(edge.weight),
+                None => neighbors.push(Neighbor {
+                    word_id: to.clone(),
+                    relation_type: edge.relation_type,
+                    weight: edge.weight,
+                }),
+            }
+        }
+    }
+
+    /// Direct neighbours, strongest first, optionally filtered by type.
+    pub fn neighbors(&self, word_id: &str, types: &[RelationType]) -> Vec<Neighbor> {
+        let mut out: Vec<Neighbor> = self
+            .adjacency
+            .get(word_id)
+            .into_iter()
+            .flatten()
+            .filter(|n| types.is_empty() || types.contains(&n.relation_type))
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+        out
+    }
+
+    /// Fewest-hop path between two words, or `None` beyond `max_depth` hops.
+    pub fn shortest_path(&self, from: &str, to: &str, max_depth: usize) -> Option<Vec<PathStep>> {
+        if from == to {
+            return Some(vec![PathStep {
+                word_id: from.to_string(),
+                via: None,
+            }]);
+        }
+        let mut parents: HashMap<&str, (&str, RelationType)> = HashMap::new();
+        let mut queue = VecDeque::from([(from, 0usize)]);
+        let mut seen = HashSet::from([from]);
+        while let Some((word, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            for n in self.adjacency.get(word).into_iter().flatten() {
+                if !seen.insert(n.word_id.as_str()) {
+                    continue;
+                }
+                parents.insert(n.word_id.as_str(), (word, n.relation_type));
+                if n.word_id == to {
+                    let mut path = vec![PathStep {
+                        word_id: to.to_string(),
+                        via: Some(n.relation_type),
+                    }];
+                    let mut current = word;
+                    while current != from {
+                        let (parent, via) = parents[current];
+                        path.push(PathStep {
+                            word_id: current.to_string(),
+                            via: Some(via),
+                        });
+                        current = parent;
+                    }
+                    path.push(PathStep {
+                        word_id: from.to_string(),
+                        via: None,
+                    });
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back((n.word_id.as_str(), depth + 1));
+            }
+        }
+        None
+    }
+
+    /// Connected components among `word_ids`, using only edges between them.
+    /// Largest cluster first; singletons are included.
+    pub fn clusters(&self, word_ids: &[String]) -> Vec<Vec<String>> {
+        let members: HashSet<&str> = word_ids.iter().map(String::as_str).collect();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut clusters = Vec::new();
+        for start in word_ids {
+            if !seen.insert(start.as_str()) {
+                continue;
+            }
+            let mut cluster = vec![start.clone()];
+            let mut stack = vec![start.as_str()];
+            while let Some(word) = stack.pop() {
+                for n in self.adjacency.get(word).into_iter().flatten() {
+                    let id = n.word_id.as_str();
+                    if members.contains(id) && seen.insert(id) {
+                        cluster.push(n.word_id.clone());
+                        stack.push(id);
+                    }
+                }
+            }
+            clusters.push(cluster);
+        }
+        clusters.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        clusters
+    }
+
+    /// Each word mapped to the other words of its cluster, for use as a
+    /// separation constraint by the session composer.
+    pub fn cluster_partners(&self, word_ids: &[String]) -> HashMap<String, Vec<String>> {
+        let mut partners = HashMap::new();
+        for cluster in self.clusters(word_ids) {
+            if cluster.len() < 2 {
+                continue;
+            }
+            for id in &cluster {
+                let others = cluster.iter().filter(|o| *o != id).cloned().collect();
+                partners.insert(id.clone(), others);
+            }
+        }
+        partners
+    }
+}
+
+fn map_edge(row: &sqlx::postgres::PgRow) -> Option<WordEdge> {
+    Some(WordEdge {
+        id: row.try_get("id").ok()?,
+        source_word_id: row.try_get("sourceWordId").ok()?,
+        target_word_id: row.try_get("targetWordId").ok()?,
+        relation_type: RelationType::parse(&row.try_get::<String, _>("relationType").ok()?)?,
+        weight: row.try_get("weight").unwrap_or(1.0),
+        user_id: row.try_get("userId").ok().flatten(),
+        source: row.try_get("source").unwrap_or_default(),
+    })
+}
+
+/// Global and the user's own edges within `depth` hops of the seed words.
+pub async fn load_neighborhood(
+    pool: &PgPool,
+    user_id: &str,
+    seeds: &[String],
+    depth: usize,
+) -> Result<WordGraph, sqlx::Error> {
+    let mut visited: HashSet<String> = seeds.iter().cloned().collect();
+    let mut frontier: Vec<String> = seeds.to_vec();
+    let mut edges: HashMap<String, WordEdge> = HashMap::new();
+
+    for _ in 0..depth.min(MAX_DEPTH) {
+        if frontier.is_empty() || visited.len() >= MAX_NODES {
+            break;
+        }
+        let rows = sqlx::query(
+            r#"
+            SELECT "id", "sourceWordId", "targetWordId", "relationType", "weight", "userId", "source"
+            FROM "word_relations"
+            WHERE ("userId" IS NULL OR "userId" = $1)
+              AND ("sourceWordId" = ANY($2) OR "targetWordId" = ANY($2))
+            "#,
+        )
+        .bind(user_id)
+        .bind(&frontier)
+        .fetch_all(pool)
+        .await?;
+
+        let mut next = Vec::new();
+        for edge in rows.iter().filter_map(map_edge) {
+            for word in [&edge.source_word_id, &edge.target_word_id] {
+                if visited.len() < MAX_NODES && visited.insert(word.clone()) {
+                    next.push(word.clone());
+                }
+            }
+            edges.insert(edge.id.clone(), edge);
+        }
+        frontier = next;
+    }
+
+    Ok(WordGraph::from_edges(edges.values()))
+}
+
+/// Adds a personal edge for the user. Returns the stored edge.
+pub async fn add_user_edge(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    word_a: &str,
+    word_b: &str,
+    relation_type: RelationType,
+) -> Result<WordEdge, GraphError> {
+    if word_a == word_b {
+        return Err(GraphError::Invalid("cannot relate a word to itself".into()));
+    }
+    let (source, target) = if word_a < word_b {
+        (word_a, word_b)
+    } else {
+        (word_b, word_a)
+    };
+    let row = sqlx::query(
+        r#"
+        INSERT INTO "word_relations"
+            ("id", "sourceWordId", "targetWordId", "relationType", "weight", "userId", "source")
+        SELECT $1, $2, $3, $4, 1.0, $5, 'user'
+        WHERE EXISTS (SELECT 1 FROM "words" WHERE "id" = $2)
+          AND EXISTS (SELECT 1 FROM "words" WHERE "id" = $3)
+        ON CONFLICT ("sourceWordId", "targetWordId", "relationType", (COALESCE("userId", '')))
+        DO UPDATE SET "weight" = EXCLUDED."weight"
+        RETURNING "id", "sourceWordId", "targetWordId", "relationType", "weight", "userId", "source"
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(source)
+    .bind(target)
+    .bind(relation_type.as_str())
+    .bind(user_id)
+    .fetch_optional(proxy.pool())
+    .await?;
+    row.as_ref()
+        .and_then(map_edge)
+        .ok_or_else(|| GraphError::Invalid("word not found".into()))
+}
+
+/// Deletes one of the user's personal edges.
+pub async fn delete_user_edge(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    edge_id: &str,
+) -> Result<(), GraphError> {
+    let result = sqlx::query(r#"DELETE FROM "word_relations" WHERE "id" = $1 AND "userId" = $2"#)
+        .bind(edge_id)
+        .bind(user_id)
+        .execute(proxy.pool())
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(GraphError::NotFound);
+    }
+    Ok(())
+}
+
+/// Regenerates the global `same_root` and `confused` edges from the morpheme
+/// links and the confusion-pair cache. Manually curated edges are untouched.
+pub async fn rebuild_derived_edges(proxy: &DatabaseProxy) -> Result<u64, sqlx::Error> {
+    let mut tx = proxy.pool().begin().await?;
+    sqlx::query(
+        r#"DELETE FROM "word_relations" WHERE "userId" IS NULL AND "source" IN ('morpheme', 'confusion_cache')"#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let same_root = sqlx::query(
+        r#"
+        INSERT INTO "word_relations"
+            ("id", "sourceWordId", "targetWordId", "relationType", "weight", "source")
+        SELECT gen_random_uuid()::text, a."wordId", b."wordId", 'same_root',
+               MAX(LEAST(a."confidence", b."confidence")), 'morpheme'
+        FROM "word_morphemes" a
+        JOIN "word_morphemes" b
+          ON a."morphemeId" = b."morphemeId" AND a."wordId" < b."wordId"
+        WHERE a."role" = 'root' AND b."role" = 'root'
+        GROUP BY a."wordId", b."wordId"
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let confused = sqlx::query(
+        r#"
+        INSERT INTO "word_relations"
+            ("id", "sourceWordId", "targetWordId", "relationType", "weight", "source")
+        SELECT gen_random_uuid()::text,
+               LEAST("word1Id", "word2Id"), GREATEST("word1Id", "word2Id"), 'confused',
+               MAX(1.0 - "distance"), 'confusion_cache'
+        FROM "confusion_pairs_cache"
+        WHERE "distance" < $1 AND "word1Id" <> "word2Id"
+        GROUP BY LEAST("word1Id", "word2Id"), GREATEST("word1Id", "word2Id")
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(CONFUSION_DISTANCE_THRESHOLD)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+    Ok(same_root + confused)
+}
+
+pub fn interleave_enabled() -> bool {
+    std::env::var("KNOWLEDGE_GRAPH_INTERLEAVE")
+        .map(|v| {
+            !matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "0" | "false" | "off"
+            )
+        })
+        .unwrap_or(true)
+}
+
+/// Scheduler hook: related words in a study batch, to be kept apart by the
+/// session composer alongside confusable pairs so they are interleaved
+/// without overriding the composed order. Empty when the hook is disabled or
+/// the graph cannot be loaded.
+pub async fn batch_cluster_partners(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    word_ids: &[String],
+) -> HashMap<String, Vec<String>> {
+    if word_ids.len() < 3 || !interleave_enabled() {
+        return HashMap::new();
+    }
+    match load_neighborhood(proxy.pool(), user_id, word_ids, 1).await {
+        Ok(graph) => graph.cluster_partners(word_ids),
+        Err(e) => {
+            tracing::debug!(error = %e, "knowledge graph unavailable, no interleaving constraint");
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(a: &str, b: &str, relation_type: RelationType) -> WordEdge {
+        WordEdge {
+            id: format!("{a}-{b}"),
+            source_word_id: a.to_string(),
+            target_word_id: b.to_string(),
+            relation_type,
+            weight: 1.0,
+            user_id: None,
+            source: "manual".to_string(),
+        }
+    }
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn sample() -> WordGraph {
+        WordGraph::from_edges(&[
+            edge("big", "large", RelationType::Synonym),
+            edge("big", "small", RelationType::Antonym),
+            edge("small", "tiny", RelationType::Synonym),
+            edge("affect", "effect", RelationType::Confused),
+        ])
+    }
+
+    #[test]
+    fn test_neighbors_are_undirected_and_filtered() {
+        let graph = sample();
+        assert_eq!(graph.neighbors("large", &[]).len(), 1);
+        assert_eq!(graph.neighbors("big", &[]).len(), 2);
+        let synonyms = graph.neighbors("big", &[RelationType::Synonym]);
+        assert_eq!(synonyms.len(), 1);
+        assert_eq!(synonyms[0].word_id, "large");
+    }
+
+    #[test]
+    fn test_shortest_path_respects_depth() {
+        let graph = sample();
+        let path = graph.shortest_path("large", "tiny", 4).expect("path");
+        let words: Vec<&str> = path.iter().map(|s| s.word_id.as_str()).collect();
+        assert_eq!(words, ["large", "big", "small", "tiny"]);
+        assert_eq!(path[2].via, Some(RelationType::Antonym));
+        assert!(graph.shortest_path("large", "tiny", 2).is_none());
+        assert!(graph.shortest_path("large", "effect", 4).is_none());
+    }
+
+    #[test]
+    fn test_clusters_and_partners_group_related_words() {
+        let graph = sample();
+        let batch = ids(&["big", "large", "affect", "effect", "tiny", "cat"]);
+        let clusters = graph.clusters(&batch);
+        assert_eq!(clusters[0], ids(&["big", "large"]));
+        assert_eq!(clusters.len(), 4);
+
+        let partners = graph.cluster_partners(&batch);
+        assert_eq!(partners["big"], ids(&["large"]));
+        assert_eq!(partners["large"], ids(&["big"]));
+        assert!(!partners.contains_key("cat"));
+    }
+}
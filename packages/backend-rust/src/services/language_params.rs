@@ -0,0 +1,259 @@
+//! Per-language algorithm parameters.
+//!
+//! The danci-algo `ParamRegistry` is stored as JSON on the `language_params`
+//! row of `algorithm_configs` and cached in-process. Words carry their own
+//! language code and books their gloss language; together they pick the
+//! registry entry used by the scheduler and the new-word difficulty features.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use danci_algo::{LanguagePair, LanguageParams, ParamRegistry};
+use sqlx::{PgPool, Row};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::services::{difficulty_calibration, frequency_bootstrap};
+
+const CONFIG_NAME: &str = "language_params";
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+static CACHE: RwLock<Option<(ParamRegistry, Instant)>> = RwLock::const_new(None);
+
+#[derive(Debug, thiserror::Error)]
+pub enum LanguageParamsError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Current registry; the built-in one until an admin saves overrides.
+pub async fn registry(pool: &PgPool) -> Result<ParamRegistry, sqlx::Error> {
+    if let Some((registry, cached_at)) = CACHE.read().await.as_ref() {
+        if cached_at.elapsed() < CACHE_TTL {
+            return Ok(registry.clone());
+        }
+    }
+    let registry = load_registry(pool).await?;
+    *CACHE.write().await = Some((registry.clone(), Instant::now()));
+    Ok(registry)
+}
+
+async fn load_registry(pool: &PgPool) -> Result<ParamRegistry, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT "masteryThresholds" FROM "algorithm_configs" WHERE "name" = $1 LIMIT 1"#,
+    )
+    .bind(CONFIG_NAME)
+    .fetch_optional(pool)
+    .await?;
+    let registry = row
+        .and_then(|r| r.try_get::<serde_json::Value, _>("masteryThresholds").ok())
+        .and_then(|json| json.get("languageParams").cloned())
+        .and_then(|value| serde_json::from_value::<ParamRegistry>(value).ok())
+        .filter(|registry| registry.validate().is_ok())
+        .unwrap_or_else(ParamRegistry::builtin);
+    Ok(registry)
+}
+
+/// Validates and stores a new registry, recording the change in `config_history`.
+pub async fn save_registry(
+    pool: &PgPool,
+    registry: &ParamRegistry,
+    changed_by: &str,
+) -> Result<(), LanguageParamsError> {
+    registry.validate().map_err(LanguageParamsError::Invalid)?;
+    let previous = load_registry(pool).await?;
+    let json = serde_json::json!({ "languageParams": registry });
+
+    let mut tx = pool.begin().await?;
+    let existing: Option<String> =
+        sqlx::query_scalar(r#"SELECT "id" FROM "algorithm_configs" WHERE "name" = $1 LIMIT 1"#)
+            .bind(CONFIG_NAME)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let config_id = match existing {
+        Some(id) => {
+            sqlx::query(
+                r#"UPDATE "algorithm_configs" SET "masteryThresholds" = $1, "updatedAt" = NOW() WHERE "id" = $2"#,
+            )
+            .bind(&json)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+            id
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                r#"
+                INSERT INTO "algorithm_configs" (
+                    "id", "name", "description", "masteryThresholds",
+                    "isDefault", "createdBy", "createdAt", "updatedAt"
+                ) VALUES ($1, $2, '按语言对的算法参数', $3, false, $4, NOW(), NOW())
+                "#,
+            )
+            .bind(&id)
+            .bind(CONFIG_NAME)
+            .bind(&json)
+            .bind(changed_by)
+            .execute(&mut *tx)
+            .await?;
+            id
+        }
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO "config_history" (
+            "id", "configId", "changedBy", "changeReason", "previousValue", "newValue", "timestamp"
+        ) VALUES ($1, $2, $3, '更新语言参数', $4, $5, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&config_id)
+    .bind(changed_by)
+    .bind(serde_json::json!({ "configType": CONFIG_NAME, "languageParams": previous }))
+    .bind(serde_json::json!({ "configType": CONFIG_NAME, "languageParams": registry }))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    *CACHE.write().await = Some((registry.clone(), Instant::now()));
+    Ok(())
+}
+
+/// Language pair of each word (word language plus its book's gloss language).
+pub async fn word_language_pairs(
+    pool: &PgPool,
+    word_ids: &[String],
+) -> Result<HashMap<String, LanguagePair>, sqlx::Error> {
+    if word_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT w."id",
+               COALESCE(w."languageCode", wb."languageCode") AS "languageCode",
+               wb."glossLanguage"
+        FROM "words" w
+        JOIN "word_books" wb ON wb."id" = w."wordBookId"
+        WHERE w."id" = ANY($1)
+        "#,
+    )
+    .bind(word_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let pair = pair_from_codes(
+                row.try_get("languageCode").ok(),
+                row.try_get("glossLanguage").ok(),
+            );
+            (row.get("id"), pair)
+        })
+        .collect())
+}
+
+/// Parameters for a single word; unknown words get the registry defaults.
+pub async fn params_for_word(pool: &PgPool, word_id: &str) -> Result<LanguageParams, sqlx::Error> {
+    let pairs = word_language_pairs(pool, &[word_id.to_string()]).await?;
+    let registry = registry(pool).await?;
+    let pair = pairs.get(word_id).cloned().unwrap_or_default();
+    Ok(registry.resolve(&pair).clone())
+}
+
+/// New-word difficulty: the population-calibrated value when the word has
+/// one, then the frequency-list prior of its language, otherwise scored with
+/// the word's language-pair weights.
+#[derive(Debug, Clone, Default)]
+pub struct DifficultyScorer {
+    registry: ParamRegistry,
+    pairs: HashMap<String, LanguagePair>,
+    calibrated: HashMap<String, f64>,
+    bootstrapped: HashMap<String, f64>,
+}
+
+impl DifficultyScorer {
+    pub async fn load(pool: &PgPool, word_ids: &[String]) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            registry: registry(pool).await?,
+            pairs: word_language_pairs(pool, word_ids).await?,
+            calibrated: difficulty_calibration::calibrated_difficulties(pool, word_ids).await?,
+            bootstrapped: frequency_bootstrap::bootstrapped_difficulties(pool, word_ids).await?,
+        })
+    }
+
+    pub fn score(&self, word_id: &str, spelling: &str, meaning_count: usize) -> f64 {
+        if let Some(&calibrated) = self.calibrated.get(word_id) {
+            return calibrated;
+        }
+        if let Some(&bootstrapped) = self.bootstrapped.get(word_id) {
+            return bootstrapped;
+        }
+        let pair = self.pairs.get(word_id).cloned().unwrap_or_default();
+        self.registry
+            .resolve(&pair)
+            .difficulty
+            .score(spelling, meaning_count)
+    }
+}
+
+pub fn pair_from_codes(language: Option<String>, gloss: Option<String>) -> LanguagePair {
+    let default = LanguagePair::default();
+    LanguagePair {
+        source: language.unwrap_or(default.source),
+        target: gloss.unwrap_or(default.target),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scorer_uses_word_language() {
+        let mut scorer = DifficultyScorer::default();
+        scorer
+            .pairs
+            .insert("w-ja".to_string(), LanguagePair::new("ja", "zh"));
+        let en = scorer.score("w-en", "一期一会", 2);
+        let ja = scorer.score("w-ja", "一期一会", 2);
+        assert!(ja > en);
+        assert_eq!(
+            scorer.score("w-en", "apple", 3),
+            crate::services::amas::compute_new_word_difficulty("apple", 3)
+        );
+    }
+
+    #[test]
+    fn test_scorer_prefers_calibrated_difficulty() {
+        let mut scorer = DifficultyScorer::default();
+        scorer.calibrated.insert("w-cal".to_string(), 0.9);
+        assert_eq!(scorer.score("w-cal", "apple", 3), 0.9);
+        assert_eq!(
+            scorer.score("w-other", "apple", 3),
+            crate::services::amas::compute_new_word_difficulty("apple", 3)
+        );
+    }
+
+    #[test]
+    fn test_scorer_falls_back_to_frequency_prior() {
+        let mut scorer = DifficultyScorer::default();
+        scorer.bootstrapped.insert("w-new".to_string(), 0.2);
+        scorer.bootstrapped.insert("w-cal".to_string(), 0.2);
+        scorer.calibrated.insert("w-cal".to_string(), 0.7);
+        assert_eq!(scorer.score("w-new", "一期一会", 2), 0.2);
+        assert_eq!(scorer.score("w-cal", "一期一会", 2), 0.7);
+    }
+
+    #[test]
+    fn test_pair_from_codes_defaults() {
+        assert_eq!(pair_from_codes(None, None), LanguagePair::default());
+        assert_eq!(
+            pair_from_codes(Some("ja".into()), None),
+            LanguagePair::new("ja", "zh")
+        );
+    }
+}
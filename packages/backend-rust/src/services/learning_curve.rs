@@ -0,0 +1,142 @@
+//! Learning-curve estimates for the statistics page.
+//!
+//! Study time and newly mastered words are aggregated per day from
+//! `answer_records` (optionally restricted to one wordbook), accumulated into
+//! (minutes, mastered) points and fitted with `danci_algo::curve`.
+
+use chrono::NaiveDate;
+use danci_algo::curve::{cumulative_points, fit_learning_curve};
+use danci_algo::{CurvePoint, LearningCurve};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+/// `masteryLevelAfter` at which a word counts as mastered.
+pub const MASTERED_LEVEL: i32 = 4;
+/// Per-answer time cap, so an answer left open while idle does not count as study.
+pub const MAX_ANSWER_MS: i64 = 60_000;
+pub const DEFAULT_BUDGETS: [f64; 4] = [30.0, 60.0, 120.0, 300.0];
+pub const MAX_BUDGETS: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyProgress {
+    pub date: String,
+    pub minutes: f64,
+    pub mastered: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearningCurveReport {
+    pub word_book_id: Option<String>,
+    pub days: Vec<DailyProgress>,
+    /// `None` until there are enough study days to fit a curve.
+    pub curve: Option<LearningCurve>,
+}
+
+pub async fn daily_progress(
+    pool: &PgPool,
+    user_id: &str,
+    word_book_id: Option<&str>,
+) -> Result<Vec<DailyProgress>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        WITH recs AS (
+            SELECT ar."wordId", ar."timestamp", ar."masteryLevelAfter",
+                   LEAST(COALESCE(ar."dwellTime", ar."responseTime", 0), $3)::BIGINT AS "ms"
+            FROM "answer_records" ar
+            JOIN "words" w ON w."id" = ar."wordId"
+            WHERE ar."userId" = $1 AND ($2::TEXT IS NULL OR w."wordBookId" = $2)
+        ),
+        daily AS (
+            SELECT DATE(recs."timestamp") AS "day", SUM(recs."ms")::BIGINT AS "ms"
+            FROM recs
+            GROUP BY 1
+        ),
+        first_mastered AS (
+            SELECT DATE(MIN(recs."timestamp")) AS "day"
+            FROM recs
+            WHERE recs."masteryLevelAfter" >= $4
+            GROUP BY recs."wordId"
+        )
+        SELECT d."day", d."ms", COUNT(f."day")::BIGINT AS "mastered"
+        FROM daily d
+        LEFT JOIN first_mastered f ON f."day" = d."day"
+        GROUP BY d."day", d."ms"
+        ORDER BY d."day" ASC
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_book_id)
+    .bind(MAX_ANSWER_MS)
+    .bind(MASTERED_LEVEL)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let day: NaiveDate = row.try_get("day").unwrap_or_default();
+            let ms: i64 = row.try_get("ms").unwrap_or(0);
+            DailyProgress {
+                date: day.to_string(),
+                minutes: ms as f64 / 60_000.0,
+                mastered: row.try_get("mastered").unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+pub fn curve_points(days: &[DailyProgress]) -> Vec<CurvePoint> {
+    let increments: Vec<(f64, f64)> = days
+        .iter()
+        .map(|d| (d.minutes, d.mastered as f64))
+        .collect();
+    cumulative_points(&increments)
+}
+
+pub async fn learning_curve(
+    pool: &PgPool,
+    user_id: &str,
+    word_book_id: Option<&str>,
+    budgets: Vec<f64>,
+) -> Result<LearningCurveReport, sqlx::Error> {
+    let days = daily_progress(pool, user_id, word_book_id).await?;
+    let points = curve_points(&days);
+    let curve = tokio::task::spawn_blocking(move || fit_learning_curve(points, budgets, None))
+        .await
+        .unwrap_or_default();
+    Ok(LearningCurveReport {
+        word_book_id: word_book_id.map(str::to_string),
+        days,
+        curve,
+    })
+}
+
+/// Parses a comma-separated list of extra study minutes.
+pub fn parse_budgets(raw: Option<&str>) -> Vec<f64> {
+    let parsed: Vec<f64> = raw
+        .unwrap_or("")
+        .split(',')
+        .filter_map(|s| s.trim().parse::<f64>().ok())
+        .filter(|m| m.is_finite() && *m >= 0.0)
+        .take(MAX_BUDGETS)
+        .collect();
+    if parsed.is_empty() {
+        DEFAULT_BUDGETS.to_vec()
+    } else {
+        parsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budgets_fall_back_to_defaults() {
+        assert_eq!(parse_budgets(None), DEFAULT_BUDGETS.to_vec());
+        assert_eq!(parse_budgets(Some("x,-5")), DEFAULT_BUDGETS.to_vec());
+        assert_eq!(parse_budgets(Some("15, 45")), vec![15.0, 45.0]);
+    }
+}
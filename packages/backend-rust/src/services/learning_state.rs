@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 
 use crate::db::DatabaseProxy;
+use crate::services::due_queue;
 
 // ========== Types ==========
 
@@ -575,6 +576,7 @@ pub async fn upsert_word_state(
     .bind(data.air_alpha).bind(data.air_beta)
     .execute(pool).await.map_err(|e| format!("写入失败: {e}"))?;
 
+    due_queue::invalidate(pool, user_id, &[word_id.to_string()]).await;
     Ok(())
 }
 
@@ -0,0 +1,507 @@
+//! Lightweight lemmatization used to fold inflected forms ("ran", "running")
+//! onto their dictionary entry ("run") for answer matching, duplicate
+//! detection and search.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub trait Lemmatizer: Send + Sync {
+    fn language(&self) -> &'static str;
+
+    /// Possible lemmas for `word`, most likely first. Always non-empty for a
+    /// non-empty input and always contains the normalized word itself.
+    fn candidates(&self, word: &str) -> Vec<String>;
+
+    fn lemmatize(&self, word: &str) -> String {
+        self.candidates(word).into_iter().next().unwrap_or_default()
+    }
+
+    fn same_lemma(&self, a: &str, b: &str) -> bool {
+        let left = self.candidates(a);
+        if left.is_empty() {
+            return false;
+        }
+        let right = self.candidates(b);
+        left.iter().any(|c| right.contains(c))
+    }
+}
+
+/// Fallback for languages without rules: only case and whitespace are folded.
+pub struct IdentityLemmatizer;
+
+impl Lemmatizer for IdentityLemmatizer {
+    fn language(&self) -> &'static str {
+        "und"
+    }
+
+    fn candidates(&self, word: &str) -> Vec<String> {
+        let normalized = normalize(word);
+        if normalized.is_empty() {
+            Vec::new()
+        } else {
+            vec![normalized]
+        }
+    }
+}
+
+pub struct EnglishLemmatizer {
+    irregular: HashMap<&'static str, &'static str>,
+}
+
+const IRREGULAR_FORMS: &[(&str, &str)] = &[
+    // verbs
+    ("am", "be"),
+    ("is", "be"),
+    ("are", "be"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("being", "be"),
+    ("doing", "do"),
+    ("going", "go"),
+    ("has", "have"),
+    ("had", "have"),
+    ("does", "do"),
+    ("did", "do"),
+    ("done", "do"),
+    ("went", "go"),
+    ("gone", "go"),
+    ("goes", "go"),
+    ("ran", "run"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("broke", "break"),
+    ("broken", "break"),
+    ("brought", "bring"),
+    ("built", "build"),
+    ("bought", "buy"),
+    ("caught", "catch"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("came", "come"),
+    ("drew", "draw"),
+    ("drawn", "draw"),
+    ("drank", "drink"),
+    ("drunk", "drink"),
+    ("drove", "drive"),
+    ("driven", "drive"),
+    ("ate", "eat"),
+    ("eaten", "eat"),
+    ("fell", "fall"),
+    ("fallen", "fall"),
+    ("felt", "feel"),
+    ("fought", "fight"),
+    ("found", "find"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("forgot", "forget"),
+    ("forgotten", "forget"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("heard", "hear"),
+    ("held", "hold"),
+    ("kept", "keep"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("led", "lead"),
+    ("left", "leave"),
+    ("lent", "lend"),
+    ("lost", "lose"),
+    ("made", "make"),
+    ("meant", "mean"),
+    ("met", "meet"),
+    ("paid", "pay"),
+    ("rode", "ride"),
+    ("ridden", "ride"),
+    ("rang", "ring"),
+    ("rung", "ring"),
+    ("rose", "rise"),
+    ("risen", "rise"),
+    ("said", "say"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("sold", "sell"),
+    ("sent", "send"),
+    ("shook", "shake"),
+    ("shaken", "shake"),
+    ("shot", "shoot"),
+    ("sang", "sing"),
+    ("sung", "sing"),
+    ("sat", "sit"),
+    ("slept", "sleep"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("spent", "spend"),
+    ("stood", "stand"),
+    ("stole", "steal"),
+    ("stolen", "steal"),
+    ("swam", "swim"),
+    ("swum", "swim"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("taught", "teach"),
+    ("tore", "tear"),
+    ("torn", "tear"),
+    ("told", "tell"),
+    ("thought", "think"),
+    ("threw", "throw"),
+    ("thrown", "throw"),
+    ("understood", "understand"),
+    ("woke", "wake"),
+    ("woken", "wake"),
+    ("wore", "wear"),
+    ("worn", "wear"),
+    ("won", "win"),
+    ("wrote", "write"),
+    ("written", "write"),
+    // nouns
+    ("men", "man"),
+    ("women", "woman"),
+    ("children", "child"),
+    ("people", "person"),
+    ("feet", "foot"),
+    ("teeth", "tooth"),
+    ("geese", "goose"),
+    ("mice", "mouse"),
+    ("oxen", "ox"),
+    ("lives", "life"),
+    ("knives", "knife"),
+    ("wives", "wife"),
+    ("leaves", "leaf"),
+    ("halves", "half"),
+    ("wolves", "wolf"),
+    ("shelves", "shelf"),
+    ("thieves", "thief"),
+    ("data", "datum"),
+    ("criteria", "criterion"),
+    ("phenomena", "phenomenon"),
+    ("analyses", "analysis"),
+    ("crises", "crisis"),
+    // adjectives
+    ("better", "good"),
+    ("best", "good"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+    ("more", "much"),
+    ("most", "much"),
+    ("less", "little"),
+    ("least", "little"),
+];
+
+/// Words whose endings look inflected but are dictionary entries themselves.
+const INVARIANT_FORMS: &[&str] = &[
+    "morning",
+    "evening",
+    "ceiling",
+    "during",
+    "nothing",
+    "something",
+    "anything",
+    "everything",
+    "news",
+    "series",
+    "species",
+    "always",
+    "perhaps",
+    "hundred",
+    "sacred",
+    "naked",
+    "wicked",
+];
+
+impl EnglishLemmatizer {
+    pub fn new() -> Self {
+        let mut irregular: HashMap<&'static str, &'static str> =
+            IRREGULAR_FORMS.iter().copied().collect();
+        for word in INVARIANT_FORMS {
+            irregular.insert(word, word);
+        }
+        Self { irregular }
+    }
+
+    fn rule_candidates(word: &str, out: &mut Vec<String>) {
+        let len = word.len();
+
+        if len > 4 && word.ends_with("ies") {
+            out.push(format!("{}y", &word[..len - 3]));
+        } else if word.ends_with("sses") || word.ends_with("zzes") {
+            out.push(word[..len - 2].to_string());
+        } else if len > 4
+            && (word.ends_with("xes")
+                || word.ends_with("ches")
+                || word.ends_with("shes")
+                || (len > 5 && word.ends_with("oes")))
+        {
+            out.push(word[..len - 2].to_string());
+            out.push(word[..len - 1].to_string());
+        } else if len > 3
+            && word.ends_with('s')
+            && !word.ends_with("ss")
+            && !word.ends_with("us")
+            && !word.ends_with("is")
+        {
+            out.push(word[..len - 1].to_string());
+        }
+
+        if len > 4 && word.ends_with("ied") {
+            out.push(format!("{}y", &word[..len - 3]));
+        } else if word.ends_with("eed") {
+            if len > 5 {
+                out.push(word[..len - 1].to_string());
+            }
+        } else if let Some(stem) = word.strip_suffix("ed") {
+            push_verb_stem(stem, out);
+        }
+
+        if len == 5 && word.ends_with("ying") {
+            out.push(format!("{}ie", &word[..1]));
+        } else if let Some(stem) = word.strip_suffix("ing") {
+            push_verb_stem(stem, out);
+        }
+    }
+}
+
+impl EnglishLemmatizer {
+    /// Lemmas reachable through suffix rules alone. Words listed in the
+    /// irregular or invariant tables only map to themselves.
+    fn regular_candidates(&self, word: &str) -> Vec<String> {
+        let normalized = normalize(word);
+        if normalized.is_empty() {
+            return Vec::new();
+        }
+        if self.irregular.contains_key(normalized.as_str())
+            || !normalized.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return vec![normalized];
+        }
+        let mut out = Vec::new();
+        Self::rule_candidates(&normalized, &mut out);
+        out.retain(|c| !self.irregular.contains_key(c.as_str()));
+        out.push(normalized);
+        out
+    }
+
+    /// Like [`Lemmatizer::same_lemma`], but only through regular inflection.
+    pub fn same_regular_lemma(&self, a: &str, b: &str) -> bool {
+        let left = self.regular_candidates(a);
+        if left.is_empty() {
+            return false;
+        }
+        let right = self.regular_candidates(b);
+        left.iter().any(|c| right.contains(c))
+    }
+}
+
+impl Default for EnglishLemmatizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lemmatizer for EnglishLemmatizer {
+    fn language(&self) -> &'static str {
+        "en"
+    }
+
+    fn candidates(&self, word: &str) -> Vec<String> {
+        let normalized = normalize(word);
+        if normalized.is_empty() {
+            return Vec::new();
+        }
+        if let Some(base) = self.irregular.get(normalized.as_str()) {
+            if *base == normalized {
+                return vec![normalized];
+            }
+            return vec![base.to_string(), normalized];
+        }
+        if !normalized.chars().all(|c| c.is_ascii_alphabetic()) {
+            return vec![normalized];
+        }
+
+        let mut out = Vec::new();
+        Self::rule_candidates(&normalized, &mut out);
+        out.push(normalized);
+        let mut seen = std::collections::HashSet::new();
+        out.retain(|c| seen.insert(c.clone()));
+        out
+    }
+}
+
+/// Stems without a vowel ("sing", "bed", "string") are left alone; doubled
+/// final consonants are undone ("running" -> "run") and a silent "e" is
+/// offered as an alternative ("making" -> "make").
+fn push_verb_stem(stem: &str, out: &mut Vec<String>) {
+    if stem.len() < 2 || !stem.chars().any(is_vowel_like) {
+        return;
+    }
+    if stem.len() == 2 {
+        out.push(format!("{stem}e"));
+        return;
+    }
+    let bytes = stem.as_bytes();
+    let last = bytes[bytes.len() - 1];
+    let prev = bytes[bytes.len() - 2];
+    if last == prev && !matches!(last, b'l' | b's' | b'z' | b'e' | b'o') {
+        out.push(stem[..stem.len() - 1].to_string());
+        return;
+    }
+    if stem.ends_with('e') {
+        out.push(stem.to_string());
+        return;
+    }
+    let silent_e = format!("{stem}e");
+    if prefers_silent_e(stem) {
+        out.push(silent_e);
+        out.push(stem.to_string());
+    } else {
+        out.push(stem.to_string());
+        out.push(silent_e);
+    }
+}
+
+fn prefers_silent_e(stem: &str) -> bool {
+    if stem.ends_with('v') || stem.ends_with('c') {
+        return true;
+    }
+    if stem.len() >= 5 && (stem.ends_with("at") || stem.ends_with("iz") || stem.ends_with("bl")) {
+        return true;
+    }
+    // Single vowel between consonants at the end of a short stem ("mak", "hop").
+    let chars: Vec<char> = stem.chars().collect();
+    let n = chars.len();
+    n <= 4
+        && !is_vowel_like(chars[n - 1])
+        && !matches!(chars[n - 1], 'w' | 'x' | 'y')
+        && is_vowel_like(chars[n - 2])
+        && (n < 3 || !is_vowel_like(chars[n - 3]))
+}
+
+fn is_vowel_like(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+fn normalize(word: &str) -> String {
+    word.trim().to_lowercase()
+}
+
+static ENGLISH: OnceLock<EnglishLemmatizer> = OnceLock::new();
+static IDENTITY: IdentityLemmatizer = IdentityLemmatizer;
+
+pub fn english() -> &'static EnglishLemmatizer {
+    ENGLISH.get_or_init(EnglishLemmatizer::new)
+}
+
+/// Resolve a lemmatizer by BCP-47 language tag; unknown languages fall back
+/// to [`IdentityLemmatizer`].
+pub fn lemmatizer_for(language: &str) -> &'static dyn Lemmatizer {
+    let primary = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match primary.as_str() {
+        "en" => english(),
+        _ => &IDENTITY,
+    }
+}
+
+/// Compare a learner answer against the expected one, tolerating case,
+/// surrounding/duplicated whitespace and regular English inflection token by
+/// token. Irregular forms ("ran", "better", "is") are distinct words for
+/// grading purposes and must be typed exactly.
+pub fn answers_match(selected: &str, correct: &str) -> bool {
+    let selected_tokens: Vec<&str> = selected.split_whitespace().collect();
+    let correct_tokens: Vec<&str> = correct.split_whitespace().collect();
+    if selected_tokens.is_empty() || selected_tokens.len() != correct_tokens.len() {
+        return false;
+    }
+    let lemmatizer = english();
+    selected_tokens
+        .iter()
+        .zip(correct_tokens.iter())
+        .all(|(a, b)| lemmatizer.same_regular_lemma(a, b))
+}
+
+/// Key used to detect duplicate entries such as "run" and "runs".
+pub fn dedup_key(spelling: &str) -> String {
+    spelling
+        .split_whitespace()
+        .map(|token| english().lemmatize(token))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_forms_share_lemma() {
+        let en = english();
+        assert_eq!(en.lemmatize("ran"), "run");
+        assert_eq!(en.lemmatize("running"), "run");
+        assert_eq!(en.lemmatize("runs"), "run");
+        assert!(en.same_lemma("RAN", "run"));
+        assert!(en.same_lemma("running", "ran"));
+    }
+
+    #[test]
+    fn test_regular_rules() {
+        let en = english();
+        assert_eq!(en.lemmatize("studies"), "study");
+        assert_eq!(en.lemmatize("boxes"), "box");
+        assert_eq!(en.lemmatize("classes"), "class");
+        assert_eq!(en.lemmatize("making"), "make");
+        assert_eq!(en.lemmatize("eating"), "eat");
+        assert_eq!(en.lemmatize("stopped"), "stop");
+        assert_eq!(en.lemmatize("lying"), "lie");
+        assert!(en.same_lemma("used", "use"));
+        assert!(en.same_lemma("agreed", "agree"));
+        assert_eq!(en.lemmatize("need"), "need");
+        assert!(en.same_lemma("children", "child"));
+    }
+
+    #[test]
+    fn test_non_inflected_words_are_stable() {
+        let en = english();
+        for word in ["sing", "bring", "string", "bus", "analysis", "glass", "bed"] {
+            assert_eq!(en.lemmatize(word), word);
+        }
+        assert!(!en.same_lemma("sing", "s"));
+        assert!(!en.same_lemma("run", "rain"));
+    }
+
+    #[test]
+    fn test_answers_match_and_dedup_key() {
+        assert!(answers_match("  Runs ", "run"));
+        assert!(answers_match("studied", "study"));
+        assert!(answers_match("looking up", "look  up"));
+        for (wrong, expected) in [
+            ("better", "good"),
+            ("is", "are"),
+            ("left", "leave"),
+            ("saw", "see"),
+            ("more", "much"),
+            ("ran", "run"),
+            ("gave up", "give up"),
+        ] {
+            assert!(!answers_match(wrong, expected), "{wrong} vs {expected}");
+        }
+        assert!(!answers_match("give", "give up"));
+        assert!(!answers_match("", ""));
+        assert!(answers_match("跑", "跑"));
+        assert_eq!(dedup_key("Looking  forward"), "look forward");
+    }
+
+    #[test]
+    fn test_lemmatizer_for_language() {
+        assert_eq!(lemmatizer_for("en-US").language(), "en");
+        let fallback = lemmatizer_for("fr");
+        assert_eq!(fallback.language(), "und");
+        assert_eq!(fallback.lemmatize(" Mangeons "), "mangeons");
+    }
+}
@@ -138,6 +138,10 @@ impl LLMProvider {
             && !self.config.api_endpoint.trim().is_empty()
     }
 
+    pub fn api_endpoint(&self) -> &str {
+        &self.config.api_endpoint
+    }
+
     pub async fn chat(&self, messages: &[ChatMessage]) -> Result<ChatResponse, LLMError> {
         if !is_llm_runtime_enabled() {
             return Err(LLMError::NotConfigured("LLM runtime disabled"));
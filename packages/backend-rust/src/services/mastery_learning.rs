@@ -1,6 +1,8 @@
 use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, NaiveDateTime, Utc};
+use danci_algo::active_learning::{SelectionMode, SessionComposerConfig};
+use danci_algo::{HintChoice, SessionObjective};
 use serde::{Deserialize, Serialize};
 use sqlx::{QueryBuilder, Row};
 
@@ -11,10 +13,19 @@ use crate::db::operations::confusion_cache::find_confusable_words_batch;
 use crate::db::operations::content::get_words_by_ids;
 use crate::db::operations::get_amas_user_model;
 use crate::db::DatabaseProxy;
-use crate::services::amas::{
-    compute_new_word_difficulty, map_difficulty_level, DifficultyRange, StrategyParams,
+use crate::services::amas::{map_difficulty_level, DifficultyRange, StrategyParams};
+use crate::services::book_dedup::dedup_by_canonical;
+use crate::services::hint_policy;
+use crate::services::knowledge_graph;
+use crate::services::language_params::DifficultyScorer;
+use crate::services::policy_rules::{
+    apply_to_words as apply_policy_rules, batch_confusion_pairs, get_user_policy_rules,
+    load_early_sessions, load_session_objective, load_session_progress, load_session_selection,
+    SessionScoring,
 };
 use crate::services::study_config::{get_or_create_user_study_config, UserStudyConfig};
+use crate::services::time_limit::{self, TimeLimit};
+use crate::services::webhooks::{self, WebhookError, WebhookEventType};
 
 fn convert_amas_strategy(s: AmasStrategyParams) -> StrategyParams {
     StrategyParams {
@@ -100,6 +111,11 @@ pub struct LearningWord {
     pub difficulty: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub distractors: Option<Distractors>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<TimeLimit>,
+    /// Hint level sampled by the user's hint policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<HintChoice>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -178,6 +194,8 @@ pub struct GetNextWordsInput {
     pub mastered_word_ids: Vec<String>,
     pub session_id: String,
     pub count: Option<i64>,
+    /// Overrides the configured session objective trade-off.
+    pub tradeoff: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -223,6 +241,7 @@ pub async fn get_words_for_mastery_mode(
     proxy: &DatabaseProxy,
     user_id: &str,
     target_count: Option<i64>,
+    tradeoff: Option<f64>,
     amas_engine: Option<&AMASEngine>,
 ) -> Result<MasteryStudyWordsResponse, sqlx::Error> {
     let start = std::time::Instant::now();
@@ -271,6 +290,10 @@ pub async fn get_words_for_mastery_mode(
         effective_batch_size(None, &strategy).min(usize::try_from(target.max(1)).unwrap_or(20));
     let words =
         fetch_words_with_strategy(proxy, user_id, fetch_count, &strategy, &[], &config).await?;
+    let fatigue = user_state
+        .as_ref()
+        .map(|s| s.fused_fatigue.unwrap_or(s.fatigue));
+    let words = arrange_batch(proxy, user_id, None, words, fatigue, tradeoff).await?;
 
     tracing::info!(
         user_id = %user_id,
@@ -324,6 +347,22 @@ pub async fn get_next_words(
     let words =
         fetch_words_with_strategy(proxy, user_id, batch_size, &strategy, &exclude_ids, &config)
             .await?;
+    let fatigue = match amas_engine {
+        Some(engine) => engine
+            .get_user_state(user_id)
+            .await
+            .map(|s| s.fused_fatigue.unwrap_or(s.fatigue)),
+        None => None,
+    };
+    let words = arrange_batch(
+        proxy,
+        user_id,
+        Some(&input.session_id),
+        words,
+        fatigue,
+        input.tradeoff,
+    )
+    .await?;
     let reason = explain_word_selection(&strategy, &words);
 
     Ok(NextWordsResponse {
@@ -435,12 +474,32 @@ pub async fn sync_session_progress(
     total_questions: i64,
     context_shifts: Option<i64>,
 ) -> Result<(), SessionError> {
-    let exists = select_learning_session(proxy, session_id, user_id).await?;
-    if exists.is_none() {
+    // The row lock makes concurrent progress reports see each other's counts,
+    // so the completion webhook is queued exactly once and only if the update
+    // that crossed the target commits.
+    let mut tx = proxy.pool().begin().await?;
+    let previous = sqlx::query(
+        r#"
+        SELECT "targetMasteryCount", COALESCE("actualMasteryCount", 0) AS "actualMasteryCount"
+        FROM "learning_sessions"
+        WHERE "id" = $1 AND "userId" = $2
+        FOR UPDATE
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(previous) = previous else {
         return Err(SessionError::NotFound);
-    }
+    };
+    let target = previous
+        .try_get::<i32, _>("targetMasteryCount")
+        .unwrap_or(0) as i64;
+    let previous_mastery = previous
+        .try_get::<i32, _>("actualMasteryCount")
+        .unwrap_or(0) as i64;
 
-    let pool = proxy.pool();
     sqlx::query(
         r#"
         UPDATE "learning_sessions"
@@ -457,9 +516,35 @@ pub async fn sync_session_progress(
     .bind(Utc::now().naive_utc())
     .bind(session_id)
     .bind(user_id)
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
 
+    if target > 0 && previous_mastery < target && actual_mastery_count >= target {
+        let queued = webhooks::emit_with(
+            &mut *tx,
+            user_id,
+            WebhookEventType::AssignmentCompleted,
+            serde_json::json!({
+                "sessionId": session_id,
+                "targetMasteryCount": target,
+                "actualMasteryCount": actual_mastery_count,
+                "totalQuestions": total_questions,
+            }),
+        )
+        .await;
+        match queued {
+            Ok(n) if n > 0 => {
+                tracing::debug!(user_id = %user_id, deliveries = n, "Webhook event queued")
+            }
+            Ok(_) => {}
+            Err(WebhookError::Sql(e)) => return Err(SessionError::Sql(e)),
+            Err(e) => {
+                tracing::warn!(error = %e, user_id = %user_id, "Failed to queue webhook event")
+            }
+        }
+    }
+
+    tx.commit().await?;
     Ok(())
 }
 
@@ -611,6 +696,16 @@ async fn fetch_words_with_strategy(
         }
     });
 
+    // 同一个词出现在多个词书中时只排一次，合并各副本的优先级与难度
+    let due_words = dedup_by_canonical(
+        due_words,
+        |w| w.word.spelling.as_str(),
+        |kept, dup| {
+            kept.priority = kept.priority.max(dup.priority);
+            kept.difficulty = kept.difficulty.max(dup.difficulty);
+        },
+    );
+
     let review_count = ((count as f64) * (1.0 - strategy.new_ratio)).ceil() as usize;
     let new_count = count.saturating_sub(review_count);
 
@@ -645,6 +740,7 @@ async fn fetch_words_with_strategy(
         "fetch_words_with_strategy: new words loaded"
     );
 
+    let new_word_ids: Vec<String> = new_words.iter().map(|w| w.id.clone()).collect();
     let mut out: Vec<LearningWord> = Vec::new();
     for w in review_words {
         out.push(word_to_learning_word(w.word, false, w.difficulty, None));
@@ -653,6 +749,33 @@ async fn fetch_words_with_strategy(
         out.push(w);
     }
 
+    // 新词可能与复习词或其他词书中的新词重复；去重后补足数量，
+    // 补来的词仍可能重名，最多补三轮
+    let mut out = dedup_by_canonical(out, |w| w.spelling.as_str(), |_, _| {});
+    combined_exclude.extend(new_word_ids);
+    for _ in 0..3 {
+        let shortfall = count.saturating_sub(out.len());
+        if shortfall == 0 {
+            break;
+        }
+        combined_exclude.extend(out.iter().map(|w| w.id.clone()));
+        let extra = fetch_new_words_in_range(
+            proxy,
+            user_id,
+            shortfall,
+            difficulty_range,
+            &combined_exclude,
+            config,
+        )
+        .await?;
+        if extra.is_empty() {
+            break;
+        }
+        combined_exclude.extend(extra.iter().map(|w| w.id.clone()));
+        out.extend(extra);
+        out = dedup_by_canonical(out, |w| w.spelling.as_str(), |_, _| {});
+    }
+
     // Fetch distractor pools in parallel
     let out_ids: Vec<String> = out.iter().map(|w| w.id.clone()).collect();
     let mut all_exclude: Vec<String> = exclude_ids.to_vec();
@@ -680,7 +803,24 @@ async fn get_due_words_with_priority(
     exclude_ids: &[String],
 ) -> Result<Vec<DueWord>, sqlx::Error> {
     let now_ms = Utc::now().timestamp_millis();
-    let word_states = select_due_word_states(proxy, user_id, now_ms, exclude_ids).await?;
+    let mut word_states = select_due_word_states(proxy, user_id, now_ms, exclude_ids).await?;
+
+    // 假期刚结束：积压的复习按词分摊到恢复期各天，而不是回来第一天全部到期
+    let today = Utc::now().date_naive();
+    match crate::services::vacation::ramp_up_period(proxy.pool(), user_id, today).await {
+        Ok(Some(vacation)) => word_states.retain(|s| {
+            let due = s
+                .next_review_ms
+                .and_then(DateTime::<Utc>::from_timestamp_millis)
+                .map(|dt| dt.date_naive())
+                .unwrap_or(today);
+            vacation.release_date(&s.word_id, due) <= today
+        }),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!(user_id = %user_id, error = %e, "Failed to load vacation ramp-up, skipping");
+        }
+    }
     if word_states.is_empty() {
         return Ok(Vec::new());
     }
@@ -808,10 +948,18 @@ async fn fetch_new_words_in_range(
         "fetch_new_words_in_range: candidates fetched"
     );
 
+    let candidate_ids: Vec<String> = candidates.iter().map(|w| w.id.clone()).collect();
+    let scorer = DifficultyScorer::load(proxy.pool(), &candidate_ids)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "fetch_new_words_in_range: language params unavailable");
+            DifficultyScorer::default()
+        });
+
     let with_diff: Vec<LearningWord> = candidates
         .drain(..)
         .map(|w| {
-            let difficulty = compute_new_word_difficulty(&w.spelling, w.meanings.len());
+            let difficulty = scorer.score(&w.id, &w.spelling, w.meanings.len());
             LearningWord {
                 id: w.id,
                 spelling: w.spelling,
@@ -822,6 +970,8 @@ async fn fetch_new_words_in_range(
                 is_new: true,
                 difficulty,
                 distractors: None,
+                time_limit: None,
+                hint: None,
             }
         })
         .collect();
@@ -899,6 +1049,8 @@ async fn fetch_words_in_difficulty_range(
                 is_new,
                 difficulty,
                 distractors: None,
+                time_limit: None,
+                hint: None,
             }
         })
         .filter(|w| w.difficulty >= range.min && w.difficulty <= range.max)
@@ -1260,6 +1412,153 @@ fn word_to_learning_word(
         is_new,
         difficulty,
         distractors,
+        time_limit: None,
+        hint: None,
+    }
+}
+
+/// Orders a fetched batch and fills in per-word extras: candidates are
+/// ranked by the session objective (`tradeoff` overrides the configured
+/// one), or by information gain during a new user's first sessions,
+/// composed under the user's policy rules with confusable words and
+/// knowledge-graph neighbours kept apart, and given time limits and hints.
+/// Inside a running session its answers so far count towards the rules.
+async fn arrange_batch(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    session_id: Option<&str>,
+    mut words: Vec<LearningWord>,
+    fatigue: Option<f64>,
+    tradeoff: Option<f64>,
+) -> Result<Vec<LearningWord>, sqlx::Error> {
+    let policy = get_user_policy_rules(proxy, user_id).await?;
+    let selection = load_session_selection(proxy).await.unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "session selection unavailable; using priority");
+        SelectionMode::default()
+    });
+    let early = load_early_sessions(proxy, user_id, selection)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "ability estimate unavailable; skipping information gain");
+            None
+        });
+    let progress = match session_id.filter(|id| !id.is_empty()) {
+        Some(id) => match load_session_progress(proxy, user_id, id).await {
+            Ok(progress) => Some(progress),
+            Err(e) => {
+                tracing::warn!(error = %e, "session progress unavailable; composing the batch alone");
+                None
+            }
+        },
+        None => None,
+    };
+    let composer = SessionComposerConfig {
+        selection,
+        rules: policy.rules,
+    };
+    let mut separate = batch_confusion_pairs(proxy, &words)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "confusion pairs unavailable; ordering without them");
+            HashMap::new()
+        });
+    // Related words are interleaved by the composer, not reordered after it.
+    let ids: Vec<String> = words.iter().map(|w| w.id.clone()).collect();
+    for (id, related) in knowledge_graph::batch_cluster_partners(proxy, user_id, &ids).await {
+        let partners = separate.entry(id).or_default();
+        for word_id in related {
+            if !partners.contains(&word_id) {
+                partners.push(word_id);
+            }
+        }
+    }
+    let recalls = attach_time_limits(proxy, user_id, &mut words).await;
+    let recall_by_id: HashMap<String, f64> = words
+        .iter()
+        .map(|w| w.id.clone())
+        .zip(recalls.iter().copied())
+        .collect();
+    let objective = load_session_objective(proxy).await.unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "session objective unavailable; using defaults");
+        SessionObjective::default()
+    });
+    let scoring = SessionScoring {
+        objective: match tradeoff {
+            Some(t) => objective.with_tradeoff(t),
+            None => objective,
+        },
+        fatigue: fatigue.unwrap_or(0.0),
+        recalls: &recall_by_id,
+    };
+    let mut words = apply_policy_rules(
+        &composer,
+        words,
+        &separate,
+        Some(&scoring),
+        early.as_ref(),
+        progress.as_ref(),
+    );
+    let recalls: Vec<f64> = if recall_by_id.is_empty() {
+        Vec::new()
+    } else {
+        words
+            .iter()
+            .map(|w| recall_by_id.get(&w.id).copied().unwrap_or(0.5))
+            .collect()
+    };
+    attach_hints(proxy, user_id, &mut words, &recalls).await;
+    Ok(words)
+}
+
+/// Fills in adaptive time limits and returns the recall estimate of each
+/// word; a failure only leaves them unset and returns no estimates.
+async fn attach_time_limits(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    words: &mut [LearningWord],
+) -> Vec<f64> {
+    let word_ids: Vec<String> = words.iter().map(|w| w.id.clone()).collect();
+    match time_limit::recommend_for_words(proxy.pool(), user_id, &word_ids).await {
+        Ok(recommendation) => words
+            .iter_mut()
+            .zip(recommendation.items)
+            .map(|(word, item)| {
+                word.time_limit = Some(item.limit);
+                item.recall_probability
+            })
+            .collect(),
+        Err(err) => {
+            tracing::warn!(error = %err, user_id = %user_id, "time limit lookup failed");
+            Vec::new()
+        }
+    }
+}
+
+/// Samples a hint level per word from the user's hint policy; words without
+/// a recall estimate get none.
+async fn attach_hints(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    words: &mut [LearningWord],
+    recalls: &[f64],
+) {
+    if recalls.is_empty() {
+        return;
+    }
+    let policy = match hint_policy::load_policy(proxy.pool(), user_id).await {
+        Ok(policy) => policy,
+        Err(err) => {
+            tracing::warn!(error = %err, user_id = %user_id, "hint policy lookup failed");
+            return;
+        }
+    };
+    let inputs: Vec<(String, f64, f64)> = words
+        .iter()
+        .zip(recalls)
+        .map(|(w, recall)| (w.id.clone(), w.difficulty, *recall))
+        .collect();
+    for (word, hint) in words.iter_mut().zip(hint_policy::choose(&policy, &inputs)) {
+        word.hint = Some(hint.choice);
     }
 }
 
@@ -1675,6 +1974,8 @@ async fn fetch_distractor_pool_words(
             is_new: false,
             difficulty: 0.5,
             distractors: None,
+            time_limit: None,
+            hint: None,
         })
         .collect())
 }
@@ -1779,6 +2080,8 @@ async fn fetch_semantic_distractor_pool(
                     is_new: false,
                     difficulty: w.difficulty.unwrap_or(0.5),
                     distractors: None,
+                    time_limit: None,
+                    hint: None,
                 });
             }
         }
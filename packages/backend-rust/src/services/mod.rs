@@ -6,30 +6,66 @@ pub mod alert_engine;
 pub mod alerts;
 pub mod amas;
 pub mod amas_config;
+pub mod api_usage;
 pub mod badge;
+pub mod book_dedup;
 pub mod broadcast;
+pub mod data_export;
 pub mod delayed_reward;
+pub mod difficulty_calibration;
+pub mod due_queue;
 pub mod elo;
 pub mod email_provider;
 pub mod embedding_provider;
 pub mod etymology;
 pub mod evaluation;
+pub mod example_sentences;
 pub mod experiment;
+pub mod experiment_guardrails;
 pub mod explainability;
+pub mod exploration_budget;
+pub mod feature_flags;
+pub mod frequency_bootstrap;
 pub mod habit_profile;
+pub mod health_check;
+pub mod hint_policy;
+pub mod idempotency;
 pub mod insight_generator;
+pub mod knowledge_graph;
+pub mod language_params;
+pub mod learning_curve;
 pub mod learning_state;
 pub mod learning_time;
+pub mod lemmatizer;
 pub mod llm_provider;
 pub mod mastery_learning;
+pub mod model_sync;
+pub mod notification_delivery;
+pub mod passages;
+pub mod placement;
+pub mod policy_rules;
+pub mod progress_report;
+pub mod push_provider;
 pub mod quality_service;
 pub mod record;
+pub mod scim;
 pub mod segment_classifier;
+pub mod session_reward;
+pub mod simulation;
 pub mod state_history;
+pub mod stats_rollup;
 pub mod study_config;
+pub mod time_limit;
+pub mod timeline;
 pub mod trend_analysis;
 pub mod user_profile;
+pub mod vacation;
+pub mod webhooks;
 pub mod weekly_report;
+pub mod what_if;
 pub mod word_scores;
+pub mod word_search;
 pub mod word_states;
+pub mod wordbook_deltas;
+pub mod wordbook_licenses;
 pub mod zpd;
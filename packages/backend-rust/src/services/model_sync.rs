@@ -0,0 +1,51 @@
+//! Stored reconciliation reports of cross-device model syncs.
+
+use sqlx::{PgPool, Row};
+
+use crate::amas::divergence::ReconciliationReport;
+
+pub async fn save_report(
+    pool: &PgPool,
+    user_id: &str,
+    report: &ReconciliationReport,
+) -> Result<String, sqlx::Error> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"INSERT INTO "amas_sync_reports" ("id", "userId", "deviceId", "mode", "report")
+           VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(&report.device_id)
+    .bind(report.mode.as_str())
+    .bind(serde_json::to_value(report).unwrap_or_default())
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+/// Most recent reports first, optionally for one device.
+pub async fn list_reports(
+    pool: &PgPool,
+    user_id: &str,
+    device_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<ReconciliationReport>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"SELECT "report" FROM "amas_sync_reports"
+           WHERE "userId" = $1 AND ($2::text IS NULL OR "deviceId" = $2)
+           ORDER BY "createdAt" DESC
+           LIMIT $3"#,
+    )
+    .bind(user_id)
+    .bind(device_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| row.try_get::<serde_json::Value, _>("report").ok())
+        .filter_map(|v| serde_json::from_value(v).ok())
+        .collect())
+}
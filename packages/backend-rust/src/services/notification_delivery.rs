@@ -0,0 +1,519 @@
+//! Out-of-app delivery of notifications by email and push.
+//!
+//! Creating a notification enqueues one `notification_deliveries` row per
+//! channel the user has enabled for its type. The message is rendered at
+//! enqueue time from `notification_templates` (type-specific first, then
+//! `DEFAULT`) so a later template edit does not change what a retry sends.
+//! The notification delivery worker drains the queue with retries.
+//!
+//! Templates use a small handlebars-style syntax: `{{path}}` inserts a value
+//! (HTML-escaped for email), `{{{path}}}` inserts it raw and
+//! `{{#if path}}...{{/if}}` keeps its body only when the value is truthy.
+//! Paths are dot-separated keys into the template variables.
+
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use thiserror::Error;
+
+use crate::services::push_provider::PushPlatform;
+
+pub const DEFAULT_TEMPLATE_KEY: &str = "DEFAULT";
+const MAX_ADDRESS_LEN: usize = 512;
+
+#[derive(Debug, Error)]
+pub enum DeliveryError {
+    #[error("unknown channel: {0}")]
+    UnknownChannel(String),
+    #[error("invalid channel preference: {0}")]
+    InvalidPreference(&'static str),
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryChannel {
+    Email,
+    Push,
+}
+
+impl DeliveryChannel {
+    pub const ALL: [DeliveryChannel; 2] = [Self::Email, Self::Push];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Push => "push",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "email" => Some(Self::Email),
+            "push" => Some(Self::Push),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelPreference {
+    pub channel: DeliveryChannel,
+    pub enabled: bool,
+    /// Email override (the account email is used when empty) or push device token.
+    pub address: Option<String>,
+    /// Push platform; unused for email.
+    pub platform: Option<PushPlatform>,
+    /// Notification types delivered on this channel; empty means all.
+    pub types: Vec<String>,
+}
+
+impl ChannelPreference {
+    fn disabled(channel: DeliveryChannel) -> Self {
+        Self {
+            channel,
+            enabled: false,
+            address: None,
+            platform: None,
+            types: Vec::new(),
+        }
+    }
+
+    fn accepts(&self, notification_type: &str) -> bool {
+        self.enabled && (self.types.is_empty() || self.types.iter().any(|t| t == notification_type))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelPreferenceUpdate {
+    pub enabled: bool,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub platform: Option<PushPlatform>,
+    #[serde(default)]
+    pub types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationDelivery {
+    pub id: String,
+    pub notification_id: String,
+    pub channel: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<String>,
+    pub created_at: String,
+}
+
+/// Render a handlebars-style template against `vars`.
+pub fn render_template(template: &str, vars: &serde_json::Value, escape_html: bool) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+
+        if let Some(inner) = after.strip_prefix("{{{") {
+            let Some(end) = inner.find("}}}") else {
+                out.push_str(after);
+                return out;
+            };
+            out.push_str(&value_text(lookup(vars, inner[..end].trim())));
+            rest = &inner[end + 3..];
+            continue;
+        }
+
+        let inner = &after[2..];
+        let Some(end) = inner.find("}}") else {
+            out.push_str(after);
+            return out;
+        };
+        let tag = inner[..end].trim();
+        let tail = &inner[end + 2..];
+
+        if let Some(path) = tag.strip_prefix("#if ") {
+            let (body, remaining) = match tail.find("{{/if}}") {
+                Some(close) => (&tail[..close], &tail[close + "{{/if}}".len()..]),
+                None => (tail, ""),
+            };
+            if is_truthy(lookup(vars, path.trim())) {
+                out.push_str(&render_template(body, vars, escape_html));
+            }
+            rest = remaining;
+            continue;
+        }
+
+        let text = value_text(lookup(vars, tag));
+        if escape_html {
+            out.push_str(&escape(&text));
+        } else {
+            out.push_str(&text);
+        }
+        rest = tail;
+    }
+    out.push_str(rest);
+    out
+}
+
+fn lookup<'a>(vars: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(vars, |value, key| value.get(key))
+}
+
+fn value_text(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn is_truthy(value: Option<&serde_json::Value>) -> bool {
+    match value {
+        None | Some(serde_json::Value::Null) => false,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(serde_json::Value::String(s)) => !s.is_empty(),
+        Some(serde_json::Value::Number(n)) => n.as_f64().is_some_and(|v| v != 0.0),
+        Some(serde_json::Value::Array(a)) => !a.is_empty(),
+        Some(serde_json::Value::Object(o)) => !o.is_empty(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// One entry per channel; channels the user never configured come back disabled.
+pub async fn get_channel_preferences(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Vec<ChannelPreference>, DeliveryError> {
+    let rows = sqlx::query(
+        r#"SELECT "channel", "enabled", "address", "platform", "types"
+           FROM "notification_channel_preferences" WHERE "userId" = $1"#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(DeliveryChannel::ALL
+        .iter()
+        .map(|&channel| {
+            rows.iter()
+                .find(|r| {
+                    r.try_get::<String, _>("channel").ok().as_deref() == Some(channel.as_str())
+                })
+                .map(|r| ChannelPreference {
+                    channel,
+                    enabled: r.try_get("enabled").unwrap_or(false),
+                    address: r.try_get("address").ok().flatten(),
+                    platform: r
+                        .try_get::<Option<String>, _>("platform")
+                        .ok()
+                        .flatten()
+                        .and_then(|p| PushPlatform::parse(&p)),
+                    types: r.try_get("types").unwrap_or_default(),
+                })
+                .unwrap_or_else(|| ChannelPreference::disabled(channel))
+        })
+        .collect())
+}
+
+pub async fn set_channel_preference(
+    pool: &PgPool,
+    user_id: &str,
+    channel: &str,
+    update: ChannelPreferenceUpdate,
+) -> Result<ChannelPreference, DeliveryError> {
+    let channel = DeliveryChannel::parse(channel)
+        .ok_or_else(|| DeliveryError::UnknownChannel(channel.to_string()))?;
+    let address = update
+        .address
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty());
+    if address.as_ref().is_some_and(|a| a.len() > MAX_ADDRESS_LEN) {
+        return Err(DeliveryError::InvalidPreference("address too long"));
+    }
+    match channel {
+        DeliveryChannel::Email => {
+            if address.as_ref().is_some_and(|a| !a.contains('@')) {
+                return Err(DeliveryError::InvalidPreference("invalid email address"));
+            }
+        }
+        DeliveryChannel::Push => {
+            if update.enabled && (address.is_none() || update.platform.is_none()) {
+                return Err(DeliveryError::InvalidPreference(
+                    "push needs a device token and platform",
+                ));
+            }
+        }
+    }
+    let platform = match channel {
+        DeliveryChannel::Push => update.platform,
+        DeliveryChannel::Email => None,
+    };
+
+    sqlx::query(
+        r#"INSERT INTO "notification_channel_preferences"
+             ("userId", "channel", "enabled", "address", "platform", "types", "updatedAt")
+           VALUES ($1, $2, $3, $4, $5, $6, NOW())
+           ON CONFLICT ("userId", "channel") DO UPDATE SET
+             "enabled" = EXCLUDED."enabled",
+             "address" = EXCLUDED."address",
+             "platform" = EXCLUDED."platform",
+             "types" = EXCLUDED."types",
+             "updatedAt" = NOW()"#,
+    )
+    .bind(user_id)
+    .bind(channel.as_str())
+    .bind(update.enabled)
+    .bind(&address)
+    .bind(platform.map(|p| p.as_str()))
+    .bind(&update.types)
+    .execute(pool)
+    .await?;
+
+    Ok(ChannelPreference {
+        channel,
+        enabled: update.enabled,
+        address,
+        platform,
+        types: update.types,
+    })
+}
+
+/// Queue deliveries for one notification. Returns the number queued.
+pub async fn enqueue_for_notification(
+    pool: &PgPool,
+    notification_id: &str,
+) -> Result<u64, DeliveryError> {
+    let rows = sqlx::query(
+        r#"SELECT n."id", n."userId", n."type"::text AS "type", n."title", n."content",
+                  n."priority"::text AS "priority", n."metadata", u."email", u."username"
+           FROM "notifications" n JOIN "users" u ON u."id" = n."userId"
+           WHERE n."id" = $1"#,
+    )
+    .bind(notification_id)
+    .fetch_all(pool)
+    .await?;
+    enqueue_rows(pool, &rows).await
+}
+
+/// Queue deliveries for every notification of an admin broadcast.
+pub async fn enqueue_for_broadcast(
+    pool: &PgPool,
+    broadcast_id: &str,
+) -> Result<u64, DeliveryError> {
+    let rows = sqlx::query(
+        r#"SELECT n."id", n."userId", n."type"::text AS "type", n."title", n."content",
+                  n."priority"::text AS "priority", n."metadata", u."email", u."username"
+           FROM "notifications" n JOIN "users" u ON u."id" = n."userId"
+           WHERE n."broadcastId" = $1"#,
+    )
+    .bind(broadcast_id)
+    .fetch_all(pool)
+    .await?;
+    enqueue_rows(pool, &rows).await
+}
+
+/// Fire-and-forget wrapper for notification producers.
+pub async fn enqueue_logged(pool: &PgPool, notification_id: &str) {
+    match enqueue_for_notification(pool, notification_id).await {
+        Ok(0) => {}
+        Ok(n) => {
+            tracing::debug!(notification_id = %notification_id, deliveries = n, "Notification deliveries queued")
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, notification_id = %notification_id, "Failed to queue notification deliveries")
+        }
+    }
+}
+
+async fn enqueue_rows(pool: &PgPool, rows: &[sqlx::postgres::PgRow]) -> Result<u64, DeliveryError> {
+    let mut queued = 0;
+    for row in rows {
+        let notification_id: String = row.try_get("id")?;
+        let user_id: String = row.try_get("userId")?;
+        let notification_type: String = row.try_get("type")?;
+        let account_email: Option<String> = row.try_get("email").ok();
+
+        for pref in get_channel_preferences(pool, &user_id).await? {
+            if !pref.accepts(&notification_type) {
+                continue;
+            }
+            let recipient = match pref.channel {
+                DeliveryChannel::Email => pref.address.clone().or_else(|| account_email.clone()),
+                DeliveryChannel::Push => pref.address.clone(),
+            };
+            let Some(recipient) = recipient else {
+                continue;
+            };
+
+            let vars = serde_json::json!({
+                "title": row.try_get::<String, _>("title").unwrap_or_default(),
+                "content": row.try_get::<String, _>("content").unwrap_or_default(),
+                "type": notification_type,
+                "priority": row.try_get::<String, _>("priority").unwrap_or_default(),
+                "metadata": row.try_get::<Option<serde_json::Value>, _>("metadata").ok().flatten(),
+                "user": { "username": row.try_get::<String, _>("username").unwrap_or_default() },
+            });
+            let (subject, body) = render_for(pool, &notification_type, pref.channel, &vars).await?;
+
+            let now = Utc::now().naive_utc();
+            let result = sqlx::query(
+                r#"INSERT INTO "notification_deliveries"
+                     ("id", "notificationId", "userId", "channel", "platform", "recipient", "subject", "body",
+                      "status", "attempts", "nextAttemptAt", "createdAt", "updatedAt")
+                   VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'PENDING', 0, $9, $9, $9)
+                   ON CONFLICT ("notificationId", "channel") DO NOTHING"#,
+            )
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(&notification_id)
+            .bind(&user_id)
+            .bind(pref.channel.as_str())
+            .bind(pref.platform.map(|p| p.as_str()))
+            .bind(&recipient)
+            .bind(&subject)
+            .bind(&body)
+            .bind(now)
+            .execute(pool)
+            .await?;
+            queued += result.rows_affected();
+        }
+    }
+    Ok(queued)
+}
+
+async fn render_for(
+    pool: &PgPool,
+    notification_type: &str,
+    channel: DeliveryChannel,
+    vars: &serde_json::Value,
+) -> Result<(String, String), DeliveryError> {
+    let template = sqlx::query(
+        r#"SELECT "subject", "body" FROM "notification_templates"
+           WHERE "channel" = $1 AND "key" IN ($2, $3)
+           ORDER BY ("key" = $3) ASC
+           LIMIT 1"#,
+    )
+    .bind(channel.as_str())
+    .bind(notification_type)
+    .bind(DEFAULT_TEMPLATE_KEY)
+    .fetch_optional(pool)
+    .await?;
+
+    let (subject, body) = match template {
+        Some(row) => (
+            row.try_get::<String, _>("subject")?,
+            row.try_get::<String, _>("body")?,
+        ),
+        None => ("{{title}}".to_string(), "{{content}}".to_string()),
+    };
+    let escape_body = channel == DeliveryChannel::Email;
+    Ok((
+        render_template(&subject, vars, false),
+        render_template(&body, vars, escape_body),
+    ))
+}
+
+/// Delivery status of the user's notifications, newest first.
+pub async fn list_deliveries(
+    pool: &PgPool,
+    user_id: &str,
+    notification_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<NotificationDelivery>, DeliveryError> {
+    let rows = sqlx::query(
+        r#"SELECT "id", "notificationId", "channel", "status", "attempts", "nextAttemptAt",
+                  "lastError", "deliveredAt", "createdAt"
+           FROM "notification_deliveries"
+           WHERE "userId" = $1 AND ($2::text IS NULL OR "notificationId" = $2)
+           ORDER BY "createdAt" DESC
+           LIMIT $3"#,
+    )
+    .bind(user_id)
+    .bind(notification_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now().naive_utc();
+    Ok(rows
+        .iter()
+        .map(|row| NotificationDelivery {
+            id: row.try_get("id").unwrap_or_default(),
+            notification_id: row.try_get("notificationId").unwrap_or_default(),
+            channel: row.try_get("channel").unwrap_or_default(),
+            status: row.try_get("status").unwrap_or_default(),
+            attempts: row.try_get("attempts").unwrap_or(0),
+            next_attempt_at: format_ts(row.try_get("nextAttemptAt").unwrap_or(now)),
+            last_error: row.try_get("lastError").ok().flatten(),
+            delivered_at: row
+                .try_get::<Option<NaiveDateTime>, _>("deliveredAt")
+                .ok()
+                .flatten()
+                .map(format_ts),
+            created_at: format_ts(row.try_get("createdAt").unwrap_or(now)),
+        })
+        .collect())
+}
+
+fn format_ts(ts: NaiveDateTime) -> String {
+    crate::auth::format_naive_datetime_iso_millis(ts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_values_sections_and_escaping() {
+        let vars = serde_json::json!({
+            "title": "<复习>",
+            "user": { "username": "amy" },
+            "metadata": { "count": 3 },
+            "empty": "",
+        });
+        assert_eq!(
+            render_template("Hi {{ user.username }}: {{title}} {{{title}}}", &vars, true),
+            "Hi amy: &lt;复习&gt; <复习>"
+        );
+        assert_eq!(
+            render_template("{{#if metadata.count}}{{metadata.count}} words{{/if}}{{#if empty}}x{{/if}}{{missing}}", &vars, false),
+            "3 words"
+        );
+        assert_eq!(
+            render_template("broken {{title", &vars, false),
+            "broken {{title"
+        );
+    }
+
+    #[test]
+    fn channel_preference_filters_types() {
+        let mut pref = ChannelPreference::disabled(DeliveryChannel::Email);
+        assert!(!pref.accepts("SYSTEM"));
+        pref.enabled = true;
+        assert!(pref.accepts("SYSTEM"));
+        pref.types = vec!["REMINDER".into()];
+        assert!(!pref.accepts("SYSTEM"));
+        assert!(pref.accepts("REMINDER"));
+    }
+}
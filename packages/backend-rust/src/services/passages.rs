@@ -0,0 +1,342 @@
+//! Reading passages scheduled as composite items.
+//!
+//! Members of a passage keep their own `word_learning_states` row and review
+//! trace; `amas::memory::passage` decides when the passage as a whole is due
+//! and how an answer is split across its words. Each credited member gets a
+//! regular `word_review_traces` row carrying its partial credit, and its
+//! `nextReviewDate` is advanced by the same MDM scheduler single words use,
+//! so an answered passage is not due again until one of its words is.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+use crate::amas::memory::{
+    assign_credit, member_credit, member_outcome, reschedule_member, schedule_passage, MdmState,
+    MemberActivation, PassageMember, PassageScheduleConfig, ReviewEvent, WordCredit,
+};
+use crate::db::DatabaseProxy;
+
+/// Most recent reviews per word fed into the MSMT recall estimate.
+const TRACE_LIMIT_PER_WORD: i64 = 20;
+/// Candidate passages considered per due-list request.
+const MAX_CANDIDATES: i64 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PassageError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("passage not found")]
+    NotFound,
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuePassage {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub next_review_in_hours: Option<f64>,
+    pub members: Vec<MemberActivation>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberResult {
+    #[serde(flatten)]
+    pub credit: WordCredit,
+    /// Outcome written to the review trace; `None` leaves the word untouched.
+    pub recorded: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassageAnswerResult {
+    pub passage_id: String,
+    pub reward: f64,
+    pub members: Vec<MemberResult>,
+}
+
+/// Due passages among those sharing at least one word the user is learning,
+/// most overdue first.
+pub async fn due_passages(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    config: &PassageScheduleConfig,
+    limit: usize,
+) -> Result<Vec<DuePassage>, PassageError> {
+    let pool = proxy.pool();
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT p."id", p."title", p."content", p."createdAt"
+        FROM "passages" p
+        JOIN "passage_words" pw ON pw."passageId" = p."id"
+        JOIN "word_learning_states" wls
+          ON wls."wordId" = pw."wordId" AND wls."userId" = $1
+        ORDER BY p."createdAt" DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(MAX_CANDIDATES)
+    .fetch_all(pool)
+    .await?;
+
+    let ids: Vec<String> = rows.iter().map(|r| r.get("id")).collect();
+    let mut members = load_members(pool, user_id, &ids).await?;
+
+    let mut due: Vec<DuePassage> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let id: String = row.get("id");
+            let schedule = schedule_passage(&members.remove(&id)?, config);
+            schedule.due.then(|| DuePassage {
+                id,
+                title: row.get("title"),
+                content: row.get("content"),
+                next_review_in_hours: schedule.next_review_in_hours,
+                members: schedule.members,
+            })
+        })
+        .collect();
+    // Passages due only by low recall have no stored review time; treat them as due now.
+    due.sort_by(|a, b| {
+        let a = a.next_review_in_hours.unwrap_or(0.0);
+        let b = b.next_review_in_hours.unwrap_or(0.0);
+        a.total_cmp(&b)
+    });
+    due.truncate(limit);
+    Ok(due)
+}
+
+/// Records one answer to a passage. `reward` is in [-1, 1].
+pub async fn record_answer(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    passage_id: &str,
+    reward: f64,
+    response_time_ms: i64,
+) -> Result<PassageAnswerResult, PassageError> {
+    if !reward.is_finite() || !(-1.0..=1.0).contains(&reward) {
+        return Err(PassageError::Invalid(
+            "reward must be between -1 and 1".to_string(),
+        ));
+    }
+    let pool = proxy.pool();
+    let members = load_members(pool, user_id, &[passage_id.to_string()])
+        .await?
+        .remove(passage_id)
+        .ok_or(PassageError::NotFound)?;
+
+    let schedule = schedule_passage(&members, &PassageScheduleConfig::default());
+    let credits = assign_credit(&schedule.members, reward);
+    let member_count = credits.len();
+    let now = Utc::now().naive_utc();
+
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(member_count);
+    for credit in credits {
+        let recorded = member_outcome(&credit, member_count, reward);
+        if let Some(is_correct) = recorded {
+            let partial = member_credit(&credit, member_count, reward);
+            // Reading time is split in proportion to each word's share.
+            let response_time = (response_time_ms.max(0) as f64 * credit.weight).round() as i32;
+            sqlx::query(
+                r#"INSERT INTO "word_review_traces" ("id","userId","wordId","isCorrect","credit","responseTime","timestamp","createdAt")
+                   VALUES ($1,$2,$3,$4,$5,$6,$7,$7)"#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(&credit.word_id)
+            .bind(is_correct)
+            .bind(partial)
+            .bind(response_time)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            reschedule_word(&mut tx, user_id, &credit.word_id, partial, now).await?;
+        }
+        results.push(MemberResult { credit, recorded });
+    }
+    tx.commit().await?;
+
+    Ok(PassageAnswerResult {
+        passage_id: passage_id.to_string(),
+        reward,
+        members: results,
+    })
+}
+
+/// Move a credited member's `nextReviewDate` forward with the MDM scheduler
+/// so an answered passage stops being due. Words without a learning state
+/// are left for the single-word flow to introduce.
+async fn reschedule_word(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    word_id: &str,
+    credit: f64,
+    now: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT "amasStrength", "amasConsolidation", "amasLastReviewTs",
+               "stability", "difficulty", "desiredRetention"
+        FROM "word_learning_states"
+        WHERE "userId" = $1 AND "wordId" = $2
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+    let Some(row) = row else {
+        return Ok(());
+    };
+
+    let strength: Option<f64> = row.try_get("amasStrength").ok().flatten();
+    let consolidation: Option<f64> = row.try_get("amasConsolidation").ok().flatten();
+    let mut mdm = match (strength, consolidation) {
+        (Some(strength), Some(consolidation)) => MdmState {
+            strength,
+            consolidation,
+            last_review_ts: row
+                .try_get::<Option<i64>, _>("amasLastReviewTs")
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+        },
+        _ => MdmState::from_fsrs(
+            row.try_get::<Option<f64>, _>("stability")
+                .ok()
+                .flatten()
+                .unwrap_or(1.0),
+            row.try_get::<Option<f64>, _>("difficulty")
+                .ok()
+                .flatten()
+                .unwrap_or(0.3),
+        ),
+    };
+    let desired_retention: f64 = row
+        .try_get::<Option<f64>, _>("desiredRetention")
+        .ok()
+        .flatten()
+        .unwrap_or(0.9);
+    let now_ts = now.and_utc().timestamp_millis();
+    let interval_days = reschedule_member(&mut mdm, credit, desired_retention, now_ts);
+    let next_review = now + chrono::Duration::seconds((interval_days * 86_400.0).round() as i64);
+
+    sqlx::query(
+        r#"
+        UPDATE "word_learning_states"
+        SET "nextReviewDate" = $3, "lastReviewDate" = $4, "scheduledDays" = $5,
+            "amasStrength" = $6, "amasConsolidation" = $7, "amasLastReviewTs" = $8,
+            "reviewCount" = "reviewCount" + 1, "updatedAt" = $4
+        WHERE "userId" = $1 AND "wordId" = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_id)
+    .bind(next_review)
+    .bind(now)
+    .bind(interval_days)
+    .bind(mdm.strength)
+    .bind(mdm.consolidation)
+    .bind(now_ts)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Members of each passage with their review history relative to now.
+async fn load_members(
+    pool: &PgPool,
+    user_id: &str,
+    passage_ids: &[String],
+) -> Result<HashMap<String, Vec<PassageMember>>, sqlx::Error> {
+    if passage_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT pw."passageId", pw."wordId", wls."nextReviewDate"
+        FROM "passage_words" pw
+        LEFT JOIN "word_learning_states" wls
+          ON wls."wordId" = pw."wordId" AND wls."userId" = $1
+        WHERE pw."passageId" = ANY($2)
+        ORDER BY pw."passageId", pw."position"
+        "#,
+    )
+    .bind(user_id)
+    .bind(passage_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let word_ids: Vec<String> = rows.iter().map(|r| r.get("wordId")).collect();
+    let history = load_history(pool, user_id, &word_ids).await?;
+
+    let now = Utc::now().naive_utc();
+    let mut members: HashMap<String, Vec<PassageMember>> = HashMap::new();
+    for row in rows {
+        let word_id: String = row.get("wordId");
+        let next_review: Option<NaiveDateTime> = row.try_get("nextReviewDate").ok().flatten();
+        members
+            .entry(row.get("passageId"))
+            .or_default()
+            .push(PassageMember {
+                history: history.get(&word_id).cloned().unwrap_or_default(),
+                next_review_in_hours: next_review.map(|ts| hours_between(now, ts)),
+                word_id,
+            });
+    }
+    Ok(members)
+}
+
+async fn load_history(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<HashMap<String, Vec<ReviewEvent>>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "wordId", "timestamp", "isCorrect", "credit"
+        FROM (
+            SELECT "wordId", "timestamp", "isCorrect", "credit",
+                   ROW_NUMBER() OVER (PARTITION BY "wordId" ORDER BY "timestamp" DESC) AS rn
+            FROM "word_review_traces"
+            WHERE "userId" = $1 AND "wordId" = ANY($2)
+        ) t
+        WHERE rn <= $3
+        ORDER BY "wordId", "timestamp" ASC
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_ids)
+    .bind(TRACE_LIMIT_PER_WORD)
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now().naive_utc();
+    let mut history: HashMap<String, Vec<ReviewEvent>> = HashMap::new();
+    for row in rows {
+        let ts: NaiveDateTime = row.get("timestamp");
+        history
+            .entry(row.get("wordId"))
+            .or_default()
+            .push(ReviewEvent {
+                timestamp_hours: hours_between(now, ts),
+                is_correct: row.get("isCorrect"),
+                credit: row.try_get("credit").ok().flatten(),
+            });
+    }
+    Ok(history)
+}
+
+fn hours_between(now: NaiveDateTime, ts: NaiveDateTime) -> f64 {
+    (ts - now).num_seconds() as f64 / 3600.0
+}
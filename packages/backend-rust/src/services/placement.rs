@@ -0,0 +1,336 @@
+//! Placement test for new learners.
+//!
+//! A short adaptive test over a calibration word set spread across the IRT
+//! difficulty scale. Item selection and the stopping rule live in
+//! `danci_algo::placement`; this module stores sessions in
+//! `placement_tests`, builds the calibration set from `words`, and writes
+//! the final ability estimate to `users.airTheta` so the AIR model starts
+//! from it instead of zero. Tests taken offline are submitted as a list of
+//! answers and replayed against the server's own difficulties.
+
+use danci_algo::{
+    select_calibration_set, PlacementConfig, PlacementItem, PlacementResult, PlacementSession,
+    PlacementStep,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseProxy;
+use crate::services::frequency_bootstrap;
+
+/// Words in one calibration set.
+pub const CALIBRATION_SET_SIZE: usize = 60;
+/// Candidate words considered when building the set.
+const CANDIDATE_LIMIT: i64 = 2000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlacementError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("invalid session state: {0}")]
+    State(#[from] serde_json::Error),
+    #[error("placement test not found")]
+    NotFound,
+    #[error("placement test already completed")]
+    Completed,
+    #[error("word is not part of this test")]
+    UnknownWord,
+    #[error("no calibrated words available")]
+    NoItems,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementView {
+    pub test_id: String,
+    pub items_answered: u32,
+    pub theta: f64,
+    pub se: f64,
+    #[serde(flatten)]
+    pub step: PlacementStep,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationSet {
+    pub items: Vec<PlacementItem>,
+    pub config: PlacementConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmittedAnswer {
+    pub word_id: String,
+    pub correct: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredResult {
+    pub test_id: String,
+    pub source: String,
+    pub completed_at: String,
+    #[serde(flatten)]
+    pub result: PlacementResult,
+}
+
+fn view(test_id: String, session: &PlacementSession, items: &[PlacementItem]) -> PlacementView {
+    PlacementView {
+        test_id,
+        items_answered: session.items_answered(),
+        theta: session.theta,
+        se: session.se,
+        step: session.step(items),
+    }
+}
+
+/// Calibration set drawn from words with a calibrated, Elo-derived or
+/// frequency-list difficulty, preferring words with the most calibration
+/// evidence.
+pub async fn calibration_set(proxy: &DatabaseProxy) -> Result<CalibrationSet, PlacementError> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT w."id",
+               COALESCE(w."calibratedBeta", {prior_beta})::float8 AS "beta"
+        FROM "words" w
+        {prior_join}
+        WHERE w."calibratedBeta" IS NOT NULL OR w."difficultyElo" IS NOT NULL OR fp."beta" IS NOT NULL
+        ORDER BY COALESCE(w."calibrationAttempts", 0) DESC, w."id"
+        LIMIT $1
+        "#,
+        prior_beta = frequency_bootstrap::PRIOR_BETA_SQL,
+        prior_join = frequency_bootstrap::PRIOR_JOIN,
+    ))
+    .bind(CANDIDATE_LIMIT)
+    .fetch_all(proxy.pool())
+    .await?;
+
+    let candidates: Vec<PlacementItem> = rows
+        .iter()
+        .filter_map(|row| {
+            Some(PlacementItem {
+                word_id: row.try_get("id").ok()?,
+                beta: row.try_get::<f64, _>("beta").ok()?,
+            })
+        })
+        .collect();
+    Ok(CalibrationSet {
+        items: select_calibration_set(&candidates, CALIBRATION_SET_SIZE),
+        config: PlacementConfig::default(),
+    })
+}
+
+/// Starts a new test, abandoning any test the user left unfinished.
+pub async fn start(proxy: &DatabaseProxy, user_id: &str) -> Result<PlacementView, PlacementError> {
+    let set = calibration_set(proxy).await?;
+    if set.items.is_empty() {
+        return Err(PlacementError::NoItems);
+    }
+    let session = PlacementSession::new(set.config);
+    let test_id = Uuid::new_v4().to_string();
+
+    let mut tx = proxy.pool().begin().await?;
+    sqlx::query(
+        r#"UPDATE "placement_tests" SET "status" = 'abandoned'
+           WHERE "userId" = $1 AND "status" = 'active'"#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO "placement_tests" ("id", "userId", "session", "items")
+           VALUES ($1, $2, $3, $4)"#,
+    )
+    .bind(&test_id)
+    .bind(user_id)
+    .bind(Json(&session))
+    .bind(Json(&set.items))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(view(test_id, &session, &set.items))
+}
+
+/// Records one answer and returns the next item or the final result.
+pub async fn answer(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    test_id: &str,
+    word_id: &str,
+    correct: bool,
+) -> Result<PlacementView, PlacementError> {
+    let mut tx = proxy.pool().begin().await?;
+    let row = sqlx::query(
+        r#"SELECT "status", "session", "items" FROM "placement_tests"
+           WHERE "id" = $1 AND "userId" = $2
+           FOR UPDATE"#,
+    )
+    .bind(test_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(PlacementError::NotFound)?;
+
+    let status: String = row.try_get("status")?;
+    if status != "active" {
+        return Err(PlacementError::Completed);
+    }
+    let Json(mut session): Json<PlacementSession> = row.try_get("session")?;
+    let Json(items): Json<Vec<PlacementItem>> = row.try_get("items")?;
+    let item = items
+        .iter()
+        .find(|i| i.word_id == word_id)
+        .ok_or(PlacementError::UnknownWord)?;
+    session.record(item, correct);
+
+    let step = session.step(&items);
+    match &step {
+        PlacementStep::Next { .. } => {
+            sqlx::query(r#"UPDATE "placement_tests" SET "session" = $2 WHERE "id" = $1"#)
+                .bind(test_id)
+                .bind(Json(&session))
+                .execute(&mut *tx)
+                .await?;
+        }
+        PlacementStep::Done { result } => {
+            sqlx::query(
+                r#"UPDATE "placement_tests"
+                   SET "session" = $2, "result" = $3, "status" = 'completed', "completedAt" = NOW()
+                   WHERE "id" = $1"#,
+            )
+            .bind(test_id)
+            .bind(Json(&session))
+            .bind(Json(result))
+            .execute(&mut *tx)
+            .await?;
+            apply_ability(&mut tx, user_id, result.theta).await?;
+        }
+    }
+    tx.commit().await?;
+
+    Ok(PlacementView {
+        test_id: test_id.to_string(),
+        items_answered: session.items_answered(),
+        theta: session.theta,
+        se: session.se,
+        step,
+    })
+}
+
+/// Stores a test taken offline. Answers are replayed with the server's
+/// difficulties; words it does not know are skipped.
+pub async fn submit(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    answers: &[SubmittedAnswer],
+) -> Result<StoredResult, PlacementError> {
+    let word_ids: Vec<String> = answers.iter().map(|a| a.word_id.clone()).collect();
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT w."id",
+               COALESCE(w."calibratedBeta", {prior_beta})::float8 AS "beta"
+        FROM "words" w
+        {prior_join}
+        WHERE w."id" = ANY($1)
+          AND (w."calibratedBeta" IS NOT NULL OR w."difficultyElo" IS NOT NULL OR fp."beta" IS NOT NULL)
+        "#,
+        prior_beta = frequency_bootstrap::PRIOR_BETA_SQL,
+        prior_join = frequency_bootstrap::PRIOR_JOIN,
+    ))
+    .bind(&word_ids)
+    .fetch_all(proxy.pool())
+    .await?;
+    let items: Vec<PlacementItem> = rows
+        .iter()
+        .filter_map(|row| {
+            Some(PlacementItem {
+                word_id: row.try_get("id").ok()?,
+                beta: row.try_get::<f64, _>("beta").ok()?,
+            })
+        })
+        .collect();
+
+    let mut session = PlacementSession::new(PlacementConfig::default());
+    for answer in answers {
+        if let Some(item) = items.iter().find(|i| i.word_id == answer.word_id) {
+            session.record(item, answer.correct);
+        }
+    }
+    if session.items_answered() == 0 {
+        return Err(PlacementError::NoItems);
+    }
+    let result = session.result();
+    let test_id = Uuid::new_v4().to_string();
+
+    let mut tx = proxy.pool().begin().await?;
+    let completed_at: chrono::NaiveDateTime = sqlx::query_scalar(
+        r#"INSERT INTO "placement_tests"
+               ("id", "userId", "status", "session", "items", "result", "source", "completedAt")
+           VALUES ($1, $2, 'completed', $3, $4, $5, 'offline', NOW())
+           RETURNING "completedAt""#,
+    )
+    .bind(&test_id)
+    .bind(user_id)
+    .bind(Json(&session))
+    .bind(Json(&items))
+    .bind(Json(&result))
+    .fetch_one(&mut *tx)
+    .await?;
+    apply_ability(&mut tx, user_id, result.theta).await?;
+    tx.commit().await?;
+
+    Ok(StoredResult {
+        test_id,
+        source: "offline".to_string(),
+        completed_at: crate::auth::format_naive_datetime_iso_millis(completed_at),
+        result,
+    })
+}
+
+/// Most recent completed test of the user.
+pub async fn latest_result(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+) -> Result<Option<StoredResult>, PlacementError> {
+    let row = sqlx::query(
+        r#"SELECT "id", "source", "result", "completedAt" FROM "placement_tests"
+           WHERE "userId" = $1 AND "status" = 'completed' AND "result" IS NOT NULL
+           ORDER BY "completedAt" DESC
+           LIMIT 1"#,
+    )
+    .bind(user_id)
+    .fetch_optional(proxy.pool())
+    .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let Json(result): Json<PlacementResult> = row.try_get("result")?;
+    let completed_at: chrono::NaiveDateTime = row.try_get("completedAt")?;
+    Ok(Some(StoredResult {
+        test_id: row.try_get("id")?,
+        source: row.try_get("source")?,
+        completed_at: crate::auth::format_naive_datetime_iso_millis(completed_at),
+        result,
+    }))
+}
+
+/// Seeds the AIR ability; only learners without AIR history are touched so a
+/// retaken test never overrides an estimate built from real answers.
+async fn apply_ability(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &str,
+    theta: f64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "users" SET "airTheta" = $2
+           WHERE "id" = $1 AND COALESCE("airResponseCount", 0) = 0"#,
+    )
+    .bind(user_id)
+    .bind(theta.clamp(-3.0, 3.0))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,517 @@
+//! Per-user pedagogy constraints evaluated by the danci-algo session composer.
+//!
+//! Before the constraints are applied, candidates can be ranked by the
+//! composer's multi-objective score (retention gain vs time cost vs
+//! frustration risk). Its weights and trade-off live in the active
+//! algorithm config's `sessionObjective`. For a new user's first sessions
+//! the config's `sessionSelection` can instead rank them by how much each
+//! answer narrows the ability estimate.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use danci_algo::active_learning::{AbilityPosterior, SelectionMode, SessionComposerConfig};
+use danci_algo::{
+    PlacementResult, PolicyRule, PolicyRuleSet, PolicyValidation, SessionItem, SessionObjective,
+};
+use sqlx::types::Json;
+use sqlx::Row;
+
+use crate::db::operations::confusion_cache::find_confusable_words_batch;
+use crate::db::DatabaseProxy;
+use crate::services::mastery_learning::LearningWord;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyRulesError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("invalid policy rules")]
+    Invalid(PolicyValidation),
+}
+
+/// Validates raw JSON rules without persisting them.
+pub fn validate_rules(rules: &serde_json::Value) -> PolicyValidation {
+    danci_algo::validate_policy_rules(rules.to_string())
+}
+
+pub async fn get_user_policy_rules(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+) -> Result<PolicyRuleSet, sqlx::Error> {
+    let row = sqlx::query(r#"SELECT "rules" FROM "user_policy_rules" WHERE "userId" = $1"#)
+        .bind(user_id)
+        .fetch_optional(proxy.pool())
+        .await?;
+
+    let rules = row
+        .and_then(|r| r.try_get::<serde_json::Value, _>("rules").ok())
+        .and_then(|v| PolicyRuleSet::from_json(&v.to_string()).ok())
+        .unwrap_or_default();
+    Ok(rules)
+}
+
+/// Stores the user's rules after validation; rules with errors or conflicts are rejected.
+pub async fn save_user_policy_rules(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    rules: &serde_json::Value,
+) -> Result<PolicyRuleSet, PolicyRulesError> {
+    let validation = validate_rules(rules);
+    if !validation.valid {
+        return Err(PolicyRulesError::Invalid(validation));
+    }
+    let set = PolicyRuleSet::from_json(&rules.to_string()).map_err(|e| {
+        PolicyRulesError::Invalid(PolicyValidation {
+            valid: false,
+            errors: vec![e],
+            conflicts: Vec::new(),
+        })
+    })?;
+
+    let canonical: serde_json::Value =
+        serde_json::from_str(&set.to_json()).unwrap_or_else(|_| serde_json::json!([]));
+    sqlx::query(
+        r#"
+        INSERT INTO "user_policy_rules" ("userId", "rules", "updatedAt")
+        VALUES ($1, $2, $3)
+        ON CONFLICT ("userId") DO UPDATE SET
+          "rules" = EXCLUDED."rules",
+          "updatedAt" = EXCLUDED."updatedAt"
+        "#,
+    )
+    .bind(user_id)
+    .bind(canonical)
+    .bind(Utc::now().naive_utc())
+    .execute(proxy.pool())
+    .await?;
+
+    Ok(set)
+}
+
+/// Pairs closer than this in `confusion_pairs_cache` are kept apart in a batch.
+const CONFUSION_DISTANCE_THRESHOLD: f64 = 0.5;
+const CONFUSION_PER_WORD_LIMIT: usize = 10;
+
+/// Confusable partners of each word that are in the same batch.
+pub async fn batch_confusion_pairs(
+    proxy: &DatabaseProxy,
+    words: &[LearningWord],
+) -> Result<HashMap<String, Vec<String>>, sqlx::Error> {
+    if words.len() < 2 {
+        return Ok(HashMap::new());
+    }
+    let ids: Vec<String> = words.iter().map(|w| w.id.clone()).collect();
+    let pairs = find_confusable_words_batch(
+        proxy,
+        &ids,
+        CONFUSION_DISTANCE_THRESHOLD,
+        CONFUSION_PER_WORD_LIMIT,
+    )
+    .await?;
+    Ok(pairs
+        .into_iter()
+        .filter_map(|(id, partners)| {
+            let partners: Vec<String> = partners
+                .into_iter()
+                .map(|(partner, _)| partner)
+                .filter(|partner| ids.contains(partner))
+                .collect();
+            (!partners.is_empty()).then_some((id, partners))
+        })
+        .collect())
+}
+
+/// Objective the composer ranks candidates by, with the inputs it needs.
+pub struct SessionScoring<'a> {
+    pub objective: SessionObjective,
+    /// Current fatigue in [0, 1].
+    pub fatigue: f64,
+    /// Recall probability by word id; words missing here are scored by
+    /// difficulty.
+    pub recalls: &'a HashMap<String, f64>,
+}
+
+/// Scoring weights of the active algorithm config. Missing or invalid
+/// settings fall back to the defaults.
+pub async fn load_session_objective(
+    proxy: &DatabaseProxy,
+) -> Result<SessionObjective, sqlx::Error> {
+    let value: Option<serde_json::Value> = sqlx::query_scalar(
+        r#"
+        SELECT "sessionObjective" FROM "algorithm_configs"
+        ORDER BY "isDefault" DESC, "createdAt" ASC
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(proxy.pool())
+    .await?;
+    let objective = value
+        .and_then(|v| serde_json::from_value::<SessionObjective>(v).ok())
+        .unwrap_or_default();
+    if objective.validate().is_empty() {
+        Ok(objective)
+    } else {
+        tracing::warn!("invalid session objective in algorithm config; using defaults");
+        Ok(SessionObjective::default())
+    }
+}
+
+/// Word selection mode of the active algorithm config; a missing or invalid
+/// setting keeps plain priority.
+pub async fn load_session_selection(proxy: &DatabaseProxy) -> Result<SelectionMode, sqlx::Error> {
+    let value: Option<serde_json::Value> = sqlx::query_scalar(
+        r#"
+        SELECT "sessionSelection" FROM "algorithm_configs"
+        ORDER BY "isDefault" DESC, "createdAt" ASC
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(proxy.pool())
+    .await?;
+    let Some(value) = value else {
+        return Ok(SelectionMode::default());
+    };
+    match serde_json::from_value::<SelectionMode>(value) {
+        Ok(SelectionMode::InformationGain { sessions: 0 }) | Err(_) => {
+            tracing::warn!("invalid session selection in algorithm config; using priority");
+            Ok(SelectionMode::default())
+        }
+        Ok(selection) => Ok(selection),
+    }
+}
+
+/// Where a user stands for information-gain selection.
+pub struct EarlySessions {
+    pub sessions_completed: u32,
+    pub posterior: AbilityPosterior,
+}
+
+/// Session count and ability posterior of a user who is still inside the
+/// information-gain window of `selection`; `None` once they are past it.
+///
+/// The posterior is centred on the latest placement estimate with its
+/// standard error, falling back to the AIR ability with unit spread.
+pub async fn load_early_sessions(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    selection: SelectionMode,
+) -> Result<Option<EarlySessions>, sqlx::Error> {
+    let SelectionMode::InformationGain { sessions } = selection else {
+        return Ok(None);
+    };
+    let row = sqlx::query(
+        r#"
+        SELECT
+          (SELECT COUNT(*) FROM "learning_sessions"
+           WHERE "userId" = $1 AND "endedAt" IS NOT NULL
+             AND COALESCE("totalQuestions", 0) > 0) AS "sessions",
+          (SELECT "result" FROM "placement_tests"
+           WHERE "userId" = $1 AND "status" = 'completed' AND "result" IS NOT NULL
+           ORDER BY "completedAt" DESC LIMIT 1) AS "placement",
+          (SELECT "airTheta" FROM "users" WHERE "id" = $1) AS "theta"
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(proxy.pool())
+    .await?;
+
+    let completed = row.try_get::<i64, _>("sessions")?.clamp(0, u32::MAX as i64) as u32;
+    if completed >= sessions {
+        return Ok(None);
+    }
+    let placement = row
+        .try_get::<Option<Json<PlacementResult>>, _>("placement")
+        .ok()
+        .flatten();
+    let posterior = match placement {
+        Some(Json(result)) => AbilityPosterior::new(result.theta, result.se),
+        None => {
+            let theta = row.try_get::<Option<f64>, _>("theta").ok().flatten();
+            AbilityPosterior::new(theta.unwrap_or(0.0), 1.0)
+        }
+    };
+    Ok(Some(EarlySessions {
+        sessions_completed: completed,
+        posterior,
+    }))
+}
+
+/// What a session in progress has already done, from its answer records.
+#[derive(Debug, Default)]
+pub struct SessionProgress {
+    /// Words the session answered that the user had never answered before.
+    pub new_introduced: u32,
+    /// Words whose latest answer in the session was wrong.
+    pub failed: HashSet<String>,
+}
+
+pub async fn load_session_progress(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    session_id: &str,
+) -> Result<SessionProgress, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT ON (ar."wordId")
+          ar."wordId",
+          ar."isCorrect",
+          NOT EXISTS (
+            SELECT 1 FROM "answer_records" p
+            WHERE p."userId" = $1 AND p."wordId" = ar."wordId"
+              AND p."sessionId" IS DISTINCT FROM $2
+              AND p."timestamp" < ar."timestamp"
+          ) AS "isNew"
+        FROM "answer_records" ar
+        WHERE ar."userId" = $1 AND ar."sessionId" = $2
+        ORDER BY ar."wordId", ar."timestamp" DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .fetch_all(proxy.pool())
+    .await?;
+
+    let mut progress = SessionProgress::default();
+    for row in rows {
+        let word_id: String = row.try_get("wordId")?;
+        if row.try_get::<bool, _>("isNew")? {
+            progress.new_introduced += 1;
+        }
+        if !row.try_get::<bool, _>("isCorrect")? {
+            progress.failed.insert(word_id);
+        }
+    }
+    Ok(progress)
+}
+
+/// `composer` with its per-session new-word cap lowered by the new words
+/// the session has already introduced.
+fn remaining_for_session(
+    composer: &SessionComposerConfig,
+    introduced: u32,
+) -> SessionComposerConfig {
+    let mut composer = composer.clone();
+    for rule in &mut composer.rules {
+        if let PolicyRule::MaxNewPerSession { limit } = rule {
+            *limit = limit.saturating_sub(introduced);
+        }
+    }
+    composer
+}
+
+/// Reorders a word batch so it satisfies the user's rules and keeps
+/// confusable words apart; words over a per-session new-word cap are dropped
+/// from the batch. With `early`, a user still inside the information-gain
+/// window gets the most informative words first; otherwise, with `scoring`,
+/// the batch is first ranked by the objective. `progress` carries the
+/// session's failed words and counts its earlier new words against the cap.
+pub fn apply_to_words(
+    composer: &SessionComposerConfig,
+    words: Vec<LearningWord>,
+    confusable: &HashMap<String, Vec<String>>,
+    scoring: Option<&SessionScoring<'_>>,
+    early: Option<&EarlySessions>,
+    progress: Option<&SessionProgress>,
+) -> Vec<LearningWord> {
+    let early = early.filter(|e| composer.information_gain_active(e.sessions_completed));
+    if (composer.rules.is_empty() && confusable.is_empty() && scoring.is_none() && early.is_none())
+        || words.is_empty()
+    {
+        return words;
+    }
+    let composer = match progress {
+        Some(p) => remaining_for_session(composer, p.new_introduced),
+        None => composer.clone(),
+    };
+
+    let candidates: Vec<SessionItem> = words
+        .iter()
+        .map(|w| SessionItem {
+            word_id: w.id.clone(),
+            is_new: w.is_new,
+            failed: progress.is_some_and(|p| p.failed.contains(&w.id)),
+            difficulty: Some(w.difficulty),
+            confusable_with: confusable.get(&w.id).cloned(),
+            recall_probability: scoring.and_then(|s| s.recalls.get(&w.id).copied()),
+        })
+        .collect();
+    let composed = match (early, scoring) {
+        (Some(e), _) => composer.compose(&candidates, e.sessions_completed, &e.posterior),
+        (None, Some(s)) => {
+            composer
+                .rule_set()
+                .compose_with_objective(&candidates, &s.objective, s.fatigue)
+        }
+        (None, None) => composer.rule_set().compose(&candidates),
+    };
+
+    let mut remaining = words;
+    let mut ordered = Vec::with_capacity(remaining.len());
+    for item in composed.items {
+        if let Some(idx) = remaining.iter().position(|w| w.id == item.word_id) {
+            ordered.push(remaining.swap_remove(idx));
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(id: &str, is_new: bool) -> LearningWord {
+        LearningWord {
+            id: id.to_string(),
+            spelling: id.to_string(),
+            phonetic: String::new(),
+            meanings: Vec::new(),
+            examples: Vec::new(),
+            audio_url: None,
+            is_new,
+            difficulty: 0.5,
+            distractors: None,
+            time_limit: None,
+            hint: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_to_words_limits_new_word_runs() {
+        let composer = SessionComposerConfig::from_json(
+            r#"{"rules":[{"type":"maxConsecutiveNew","limit":1},{"type":"maxNewPerSession","limit":2}]}"#,
+        )
+        .unwrap();
+        let words = vec![
+            word("n1", true),
+            word("n2", true),
+            word("n3", true),
+            word("r1", false),
+        ];
+        let ids: Vec<String> = apply_to_words(&composer, words, &HashMap::new(), None, None, None)
+            .into_iter()
+            .map(|w| w.id)
+            .collect();
+        assert_eq!(ids, vec!["n1", "r1", "n2"]);
+    }
+
+    #[test]
+    fn test_apply_to_words_counts_new_words_across_the_session() {
+        let composer = SessionComposerConfig::from_json(
+            r#"{"rules":[{"type":"maxNewPerSession","limit":2}]}"#,
+        )
+        .unwrap();
+        let words = vec![word("n1", true), word("n2", true), word("r1", false)];
+        let progress = SessionProgress {
+            new_introduced: 1,
+            failed: HashSet::new(),
+        };
+        let ids: Vec<String> = apply_to_words(
+            &composer,
+            words,
+            &HashMap::new(),
+            None,
+            None,
+            Some(&progress),
+        )
+        .into_iter()
+        .map(|w| w.id)
+        .collect();
+        assert_eq!(ids, vec!["n1", "r1"]);
+    }
+
+    #[test]
+    fn test_apply_to_words_separates_confusable_pairs() {
+        let words = vec![
+            word("affect", false),
+            word("effect", false),
+            word("r1", false),
+            word("r2", false),
+        ];
+        let confusable = HashMap::from([("affect".to_string(), vec!["effect".to_string()])]);
+        let ids: Vec<String> = apply_to_words(
+            &SessionComposerConfig::default(),
+            words,
+            &confusable,
+            None,
+            None,
+            None,
+        )
+        .into_iter()
+        .map(|w| w.id)
+        .collect();
+        assert_eq!(ids.len(), 4);
+        let pos = |id: &str| ids.iter().position(|w| w == id).unwrap();
+        assert!(pos("affect").abs_diff(pos("effect")) > 1);
+    }
+
+    #[test]
+    fn test_apply_to_words_ranks_by_objective_tradeoff() {
+        let words = vec![word("fresh", false), word("fading", false)];
+        let recalls = HashMap::from([("fresh".to_string(), 0.95), ("fading".to_string(), 0.2)]);
+        let order = |tradeoff: f64| -> Vec<String> {
+            let scoring = SessionScoring {
+                objective: SessionObjective::default().with_tradeoff(tradeoff),
+                fatigue: 0.5,
+                recalls: &recalls,
+            };
+            apply_to_words(
+                &SessionComposerConfig::default(),
+                words.clone(),
+                &HashMap::new(),
+                Some(&scoring),
+                None,
+                None,
+            )
+            .into_iter()
+            .map(|w| w.id)
+            .collect()
+        };
+        assert_eq!(order(0.0), vec!["fading", "fresh"]);
+        assert_eq!(order(1.0), vec!["fresh", "fading"]);
+    }
+
+    #[test]
+    fn test_apply_to_words_prefers_informative_words_in_early_sessions() {
+        let composer = SessionComposerConfig::from_json(
+            r#"{"selection":{"mode":"informationGain","sessions":2}}"#,
+        )
+        .unwrap();
+        let words = vec![
+            LearningWord {
+                difficulty: 0.02,
+                ..word("trivial", true)
+            },
+            word("matched", true),
+        ];
+        let order = |sessions_completed: u32| -> Vec<String> {
+            let early = EarlySessions {
+                sessions_completed,
+                posterior: AbilityPosterior::default(),
+            };
+            apply_to_words(
+                &composer,
+                words.clone(),
+                &HashMap::new(),
+                None,
+                Some(&early),
+                None,
+            )
+            .into_iter()
+            .map(|w| w.id)
+            .collect()
+        };
+        assert_eq!(order(0), vec!["matched", "trivial"]);
+        assert_eq!(order(2), vec!["trivial", "matched"]);
+    }
+
+    #[test]
+    fn test_validate_rules_reports_conflicts() {
+        let rules = serde_json::json!([
+            {"type": "reviewFailedWithin", "within": 2},
+            {"type": "minRepeatGap", "gap": 5}
+        ]);
+        let validation = validate_rules(&rules);
+        assert!(!validation.valid);
+        assert_eq!(validation.conflicts.len(), 1);
+    }
+}
@@ -0,0 +1,864 @@
+//! Downloadable learning progress reports.
+//!
+//! A report covers either one student or a roster of students (a class) over
+//! the last `days` days: daily accuracy from `user_daily_stats`, mastered word
+//! counts from `word_learning_states` and time on task (dwell time, falling
+//! back to response time). Reports render to CSV or PDF; the PDF uses the
+//! built-in Helvetica font, so non-ASCII names are replaced by their user id.
+//!
+//! Generation runs in the background and the file is kept in
+//! `progress_report_jobs` until `expiresAt`, mirroring the simulation jobs.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseProxy;
+use crate::middleware::request_id;
+
+pub const DEFAULT_DAYS: u32 = 30;
+pub const MAX_DAYS: u32 = 365;
+pub const MAX_STUDENTS: usize = 500;
+/// Generated files stay downloadable this long.
+const RETENTION_DAYS: i64 = 7;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReportError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Pdf,
+}
+
+impl ReportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "text/csv; charset=utf-8",
+            ReportFormat::Pdf => "application/pdf",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRequest {
+    /// One id produces a student report, several a class report.
+    pub user_ids: Vec<String>,
+    /// Class name shown in the report header.
+    #[serde(default)]
+    pub label: Option<String>,
+    pub format: ReportFormat,
+    #[serde(default)]
+    pub days: Option<u32>,
+}
+
+impl ReportRequest {
+    fn scope(&self) -> &'static str {
+        if self.user_ids.len() == 1 {
+            "student"
+        } else {
+            "class"
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ReportError> {
+        if self.user_ids.is_empty() {
+            return Err(ReportError::Invalid("至少需要一个学生".into()));
+        }
+        if self.user_ids.len() > MAX_STUDENTS {
+            return Err(ReportError::Invalid(format!(
+                "单个报告最多包含 {MAX_STUDENTS} 名学生"
+            )));
+        }
+        let unique: HashSet<&String> = self.user_ids.iter().collect();
+        if unique.len() != self.user_ids.len() {
+            return Err(ReportError::Invalid("学生列表中有重复的用户".into()));
+        }
+        let days = self.days.unwrap_or(DEFAULT_DAYS);
+        if !(1..=MAX_DAYS).contains(&days) {
+            return Err(ReportError::Invalid(format!("days 需在 1-{MAX_DAYS} 之间")));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyProgress {
+    pub date: NaiveDate,
+    pub answers: i64,
+    pub correct: i64,
+    pub time_on_task_ms: i64,
+}
+
+impl DailyProgress {
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.answers > 0).then(|| self.correct as f64 / self.answers as f64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StudentProgress {
+    pub user_id: String,
+    pub username: String,
+    pub mastered_words: i64,
+    /// Words that reached MASTERED inside the report period.
+    pub newly_mastered: i64,
+    pub daily: Vec<DailyProgress>,
+}
+
+impl StudentProgress {
+    pub fn answers(&self) -> i64 {
+        self.daily.iter().map(|d| d.answers).sum()
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        let answers = self.answers();
+        let correct: i64 = self.daily.iter().map(|d| d.correct).sum();
+        (answers > 0).then(|| correct as f64 / answers as f64)
+    }
+
+    pub fn time_on_task_ms(&self) -> i64 {
+        self.daily.iter().map(|d| d.time_on_task_ms).sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressReport {
+    pub label: Option<String>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub students: Vec<StudentProgress>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportJob {
+    pub id: String,
+    pub scope: String,
+    pub format: String,
+    pub status: String,
+    pub request: serde_json::Value,
+    pub file_name: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub created_by: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub expires_at: String,
+}
+
+pub struct ReportFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+pub async fn load_report(
+    proxy: &DatabaseProxy,
+    request: &ReportRequest,
+) -> Result<ProgressReport, ReportError> {
+    let pool = proxy.pool();
+    let to = Utc::now().date_naive();
+    let from = to - Duration::days(i64::from(request.days.unwrap_or(DEFAULT_DAYS)) - 1);
+
+    let users = sqlx::query(r#"SELECT "id", "username" FROM "users" WHERE "id" = ANY($1)"#)
+        .bind(&request.user_ids)
+        .fetch_all(pool)
+        .await?;
+    let mut names: HashMap<String, String> = users
+        .into_iter()
+        .map(|row| {
+            (
+                row.try_get("id").unwrap_or_default(),
+                row.try_get("username").unwrap_or_default(),
+            )
+        })
+        .collect();
+    if names.len() != request.user_ids.len() {
+        let missing: Vec<&str> = request
+            .user_ids
+            .iter()
+            .filter(|id| !names.contains_key(*id))
+            .map(String::as_str)
+            .collect();
+        return Err(ReportError::Invalid(format!(
+            "用户不存在: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let daily_rows = sqlx::query(
+        r#"SELECT "userId", "date", "answerCount", "correctCount",
+                  GREATEST("totalDwellTimeMs", "totalResponseTimeMs") AS "timeOnTaskMs"
+           FROM "user_daily_stats"
+           WHERE "userId" = ANY($1) AND "date" >= $2
+           ORDER BY "date" ASC"#,
+    )
+    .bind(&request.user_ids)
+    .bind(from)
+    .fetch_all(pool)
+    .await?;
+    let mut daily: HashMap<String, Vec<DailyProgress>> = HashMap::new();
+    for row in daily_rows {
+        let user_id: String = row.try_get("userId").unwrap_or_default();
+        daily.entry(user_id).or_default().push(DailyProgress {
+            date: row.try_get("date").unwrap_or(from),
+            answers: i64::from(row.try_get::<i32, _>("answerCount").unwrap_or(0)),
+            correct: i64::from(row.try_get::<i32, _>("correctCount").unwrap_or(0)),
+            time_on_task_ms: row.try_get("timeOnTaskMs").unwrap_or(0),
+        });
+    }
+
+    let mastery_rows = sqlx::query(
+        r#"SELECT "userId",
+                  COUNT(*) FILTER (WHERE "state" = 'MASTERED') AS "mastered",
+                  COUNT(*) FILTER (WHERE "state" = 'MASTERED' AND "updatedAt" >= $2) AS "newlyMastered"
+           FROM "word_learning_states"
+           WHERE "userId" = ANY($1)
+           GROUP BY "userId""#,
+    )
+    .bind(&request.user_ids)
+    .bind(from.and_hms_opt(0, 0, 0).unwrap_or_default())
+    .fetch_all(pool)
+    .await?;
+    let mut mastery: HashMap<String, (i64, i64)> = HashMap::new();
+    for row in mastery_rows {
+        mastery.insert(
+            row.try_get("userId").unwrap_or_default(),
+            (
+                row.try_get("mastered").unwrap_or(0),
+                row.try_get("newlyMastered").unwrap_or(0),
+            ),
+        );
+    }
+
+    let students = request
+        .user_ids
+        .iter()
+        .map(|id| {
+            let (mastered_words, newly_mastered) = mastery.get(id).copied().unwrap_or_default();
+            StudentProgress {
+                user_id: id.clone(),
+                username: names.remove(id).unwrap_or_default(),
+                mastered_words,
+                newly_mastered,
+                daily: daily.remove(id).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(ProgressReport {
+        label: request.label.clone(),
+        from,
+        to,
+        students,
+    })
+}
+
+fn minutes(ms: i64) -> f64 {
+    (ms as f64 / 60_000.0 * 10.0).round() / 10.0
+}
+
+fn percent(accuracy: Option<f64>) -> String {
+    accuracy
+        .map(|a| format!("{:.1}", a * 100.0))
+        .unwrap_or_default()
+}
+
+/// Student reports list one row per day; class reports one row per student.
+pub fn render_csv(report: &ProgressReport) -> Result<Vec<u8>, ReportError> {
+    let csv_error = |e: csv::Error| ReportError::Invalid(format!("CSV 生成失败: {e}"));
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if let [student] = report.students.as_slice() {
+        writer
+            .write_record([
+                "userId",
+                "username",
+                "date",
+                "answers",
+                "correct",
+                "accuracyPercent",
+                "timeOnTaskMinutes",
+            ])
+            .map_err(csv_error)?;
+        for day in &student.daily {
+            writer
+                .write_record([
+                    student.user_id.clone(),
+                    student.username.clone(),
+                    day.date.to_string(),
+                    day.answers.to_string(),
+                    day.correct.to_string(),
+                    percent(day.accuracy()),
+                    minutes(day.time_on_task_ms).to_string(),
+                ])
+                .map_err(csv_error)?;
+        }
+    } else {
+        writer
+            .write_record([
+                "userId",
+                "username",
+                "answers",
+                "accuracyPercent",
+                "masteredWords",
+                "newlyMastered",
+                "timeOnTaskMinutes",
+                "activeDays",
+            ])
+            .map_err(csv_error)?;
+        for student in &report.students {
+            writer
+                .write_record([
+                    student.user_id.clone(),
+                    student.username.clone(),
+                    student.answers().to_string(),
+                    percent(student.accuracy()),
+                    student.mastered_words.to_string(),
+                    student.newly_mastered.to_string(),
+                    minutes(student.time_on_task_ms()).to_string(),
+                    student
+                        .daily
+                        .iter()
+                        .filter(|d| d.answers > 0)
+                        .count()
+                        .to_string(),
+                ])
+                .map_err(csv_error)?;
+        }
+    }
+    writer
+        .into_inner()
+        .map_err(|e| ReportError::Invalid(format!("CSV 生成失败: {e}")))
+}
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const LINE_HEIGHT: f32 = 14.0;
+const FONT: Name<'static> = Name(b"F1");
+const FONT_BOLD: Name<'static> = Name(b"F2");
+
+/// Top-down text layout that starts a new page when the current one is full.
+struct PdfLayout {
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl PdfLayout {
+    fn new() -> Self {
+        Self {
+            pages: vec![Content::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn ensure_space(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(Content::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn current(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    fn text_at(&mut self, x: f32, text: &str, size: f32, bold: bool) {
+        let y = self.y;
+        let encoded = pdf_text(text);
+        self.current()
+            .begin_text()
+            .set_font(if bold { FONT_BOLD } else { FONT }, size)
+            .next_line(x, y)
+            .show(Str(&encoded))
+            .end_text();
+    }
+
+    fn line(&mut self, text: &str, size: f32, bold: bool) {
+        self.ensure_space(size + 4.0);
+        self.y -= size + 4.0;
+        self.text_at(MARGIN, text, size, bold);
+    }
+
+    /// One table row; `columns` are x offsets from the left margin.
+    fn row(&mut self, columns: &[f32], cells: &[String], bold: bool) {
+        self.ensure_space(LINE_HEIGHT);
+        self.y -= LINE_HEIGHT;
+        for (x, cell) in columns.iter().zip(cells) {
+            self.text_at(MARGIN + x, cell, 9.0, bold);
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Accuracy line chart, 0-100% on the y axis, one point per active day.
+    fn accuracy_chart(&mut self, report: &ProgressReport, daily: &[DailyProgress]) {
+        let height = 120.0;
+        let width = PAGE_WIDTH - 2.0 * MARGIN;
+        self.ensure_space(height + 10.0);
+        self.y -= height + 10.0;
+        let (x0, y0) = (MARGIN, self.y);
+        let span = ((report.to - report.from).num_days().max(1)) as f32;
+
+        let content = self.current();
+        content
+            .set_stroke_rgb(0.6, 0.6, 0.6)
+            .set_line_width(0.5)
+            .rect(x0, y0, width, height)
+            .stroke();
+        let points: Vec<(f32, f32)> = daily
+            .iter()
+            .filter_map(|d| {
+                let accuracy = d.accuracy()? as f32;
+                let offset = (d.date - report.from).num_days() as f32 / span;
+                Some((x0 + offset * width, y0 + accuracy * height))
+            })
+            .collect();
+        if let Some((first, rest)) = points.split_first() {
+            content
+                .set_stroke_rgb(0.12, 0.39, 0.78)
+                .set_line_width(1.5)
+                .move_to(first.0, first.1);
+            for (x, y) in rest {
+                content.line_to(*x, *y);
+            }
+            content.stroke();
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let font_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let first_page = 5;
+        let page_ids: Vec<Ref> = (0..self.pages.len())
+            .map(|i| Ref::new(first_page + 2 * i as i32))
+            .collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        pdf.type1_font(font_id).base_font(Name(b"Helvetica"));
+        pdf.type1_font(bold_id).base_font(Name(b"Helvetica-Bold"));
+        for (page_id, content) in page_ids.iter().zip(self.pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                .parent(tree_id)
+                .contents(content_id);
+            let mut resources = page.resources();
+            resources
+                .fonts()
+                .pair(FONT, font_id)
+                .pair(FONT_BOLD, bold_id);
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+        pdf.finish()
+    }
+}
+
+/// The base-14 fonts only cover Latin-1; anything else becomes `?`.
+fn pdf_text(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c as u8
+            } else {
+                b'?'
+            }
+        })
+        .collect()
+}
+
+fn display_name(student: &StudentProgress) -> String {
+    if student.username.is_ascii() && !student.username.is_empty() {
+        student.username.clone()
+    } else {
+        student.user_id.clone()
+    }
+}
+
+pub fn render_pdf(report: &ProgressReport) -> Vec<u8> {
+    let mut layout = PdfLayout::new();
+    let title = match (&report.label, report.students.as_slice()) {
+        (_, [student]) => format!("Progress report: {}", display_name(student)),
+        (Some(label), _) if label.is_ascii() => format!("Class progress report: {label}"),
+        _ => "Class progress report".to_string(),
+    };
+    layout.line(&title, 18.0, true);
+    layout.line(
+        &format!("Period: {} to {}", report.from, report.to),
+        10.0,
+        false,
+    );
+    layout.gap(10.0);
+
+    if let [student] = report.students.as_slice() {
+        for text in [
+            format!("Answers: {}", student.answers()),
+            format!("Accuracy: {}%", percent(student.accuracy())),
+            format!(
+                "Mastered words: {} ({} new in period)",
+                student.mastered_words, student.newly_mastered
+            ),
+            format!("Time on task: {} min", minutes(student.time_on_task_ms())),
+        ] {
+            layout.line(&text, 11.0, false);
+        }
+        layout.gap(10.0);
+        layout.line("Daily accuracy", 12.0, true);
+        layout.accuracy_chart(report, &student.daily);
+        layout.gap(10.0);
+
+        let columns = [0.0, 110.0, 190.0, 270.0, 360.0];
+        let header = ["Date", "Answers", "Correct", "Accuracy %", "Minutes"];
+        layout.row(&columns, &header.map(String::from), true);
+        for day in &student.daily {
+            layout.row(
+                &columns,
+                &[
+                    day.date.to_string(),
+                    day.answers.to_string(),
+                    day.correct.to_string(),
+                    percent(day.accuracy()),
+                    minutes(day.time_on_task_ms).to_string(),
+                ],
+                false,
+            );
+        }
+    } else {
+        let columns = [0.0, 160.0, 220.0, 290.0, 360.0, 430.0];
+        let header = [
+            "Student",
+            "Answers",
+            "Accuracy %",
+            "Mastered",
+            "New",
+            "Minutes",
+        ];
+        layout.row(&columns, &header.map(String::from), true);
+        for student in &report.students {
+            layout.row(
+                &columns,
+                &[
+                    display_name(student),
+                    student.answers().to_string(),
+                    percent(student.accuracy()),
+                    student.mastered_words.to_string(),
+                    student.newly_mastered.to_string(),
+                    minutes(student.time_on_task_ms()).to_string(),
+                ],
+                false,
+            );
+        }
+    }
+    layout.finish()
+}
+
+/// Validates the request, records a queued job and renders it in the background.
+pub async fn create_job(
+    proxy: Arc<DatabaseProxy>,
+    created_by: &str,
+    request: ReportRequest,
+) -> Result<ReportJob, ReportError> {
+    request.validate()?;
+    sqlx::query(r#"DELETE FROM "progress_report_jobs" WHERE "expiresAt" < NOW()"#)
+        .execute(proxy.pool())
+        .await?;
+
+    let id = Uuid::new_v4().to_string();
+    let expires_at = Utc::now().naive_utc() + Duration::days(RETENTION_DAYS);
+    let request_json = serde_json::to_value(&request).unwrap_or(serde_json::Value::Null);
+    sqlx::query(
+        r#"INSERT INTO "progress_report_jobs" ("id","scope","format","request","createdBy","expiresAt","requestId")
+           VALUES ($1,$2,$3,$4,$5,$6,$7)"#,
+    )
+    .bind(&id)
+    .bind(request.scope())
+    .bind(request.format.extension())
+    .bind(&request_json)
+    .bind(created_by)
+    .bind(expires_at)
+    .bind(request_id::current())
+    .execute(proxy.pool())
+    .await?;
+
+    let job_id = id.clone();
+    let job_proxy = Arc::clone(&proxy);
+    request_id::spawn(async move {
+        if let Err(e) = run_job(&job_proxy, &job_id, request).await {
+            tracing::warn!(job_id = %job_id, error = %e, "progress report job failed");
+            let _ = mark_failed(&job_proxy, &job_id, &e.to_string()).await;
+        }
+    });
+
+    get_job(proxy.as_ref(), &id)
+        .await?
+        .ok_or_else(|| ReportError::Invalid("任务创建失败".into()))
+}
+
+async fn run_job(
+    proxy: &DatabaseProxy,
+    id: &str,
+    request: ReportRequest,
+) -> Result<(), ReportError> {
+    sqlx::query(r#"UPDATE "progress_report_jobs" SET "status" = 'running' WHERE "id" = $1"#)
+        .bind(id)
+        .execute(proxy.pool())
+        .await?;
+
+    let report = load_report(proxy, &request).await?;
+    let format = request.format;
+    let content = tokio::task::spawn_blocking(move || match format {
+        ReportFormat::Csv => render_csv(&report),
+        ReportFormat::Pdf => Ok(render_pdf(&report)),
+    })
+    .await
+    .map_err(|e| ReportError::Invalid(format!("报告生成失败: {e}")))??;
+
+    let file_name = format!(
+        "progress-{}-{}.{}",
+        request.scope(),
+        Utc::now().format("%Y%m%d"),
+        format.extension()
+    );
+    sqlx::query(
+        r#"UPDATE "progress_report_jobs"
+           SET "status" = 'completed', "fileName" = $2, "contentType" = $3, "content" = $4,
+               "completedAt" = NOW()
+           WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(&file_name)
+    .bind(format.content_type())
+    .bind(content)
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+async fn mark_failed(proxy: &DatabaseProxy, id: &str, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "progress_report_jobs"
+           SET "status" = 'failed', "error" = $2, "completedAt" = NOW()
+           WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(error)
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+/// Reports render in this process only, so a job still queued or running at
+/// startup was cut off by a restart; it is marked failed so pollers stop
+/// waiting on it.
+pub async fn fail_interrupted_jobs(proxy: &DatabaseProxy) {
+    match sqlx::query(
+        r#"UPDATE "progress_report_jobs"
+           SET "status" = 'failed', "error" = '服务重启，任务已中断', "completedAt" = NOW()
+           WHERE "status" IN ('queued', 'running')"#,
+    )
+    .execute(proxy.pool())
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            tracing::info!(
+                count = result.rows_affected(),
+                "marked interrupted progress report jobs failed"
+            );
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "failed to clean up interrupted progress report jobs"),
+    }
+}
+
+const JOB_COLUMNS: &str = r#""id","scope","format","status","request","fileName",OCTET_LENGTH("content")::BIGINT AS "sizeBytes","error","createdBy","requestId","createdAt","completedAt","expiresAt""#;
+
+pub async fn get_job(proxy: &DatabaseProxy, id: &str) -> Result<Option<ReportJob>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        r#"SELECT {JOB_COLUMNS} FROM "progress_report_jobs" WHERE "id" = $1"#
+    ))
+    .bind(id)
+    .fetch_optional(proxy.pool())
+    .await?;
+    Ok(row.map(map_job_row))
+}
+
+pub async fn list_jobs(proxy: &DatabaseProxy, limit: i64) -> Result<Vec<ReportJob>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        r#"SELECT {JOB_COLUMNS} FROM "progress_report_jobs" ORDER BY "createdAt" DESC LIMIT $1"#
+    ))
+    .bind(limit)
+    .fetch_all(proxy.pool())
+    .await?;
+    Ok(rows.into_iter().map(map_job_row).collect())
+}
+
+/// The finished file, or `None` when the job is missing, unfinished or expired.
+pub async fn get_file(proxy: &DatabaseProxy, id: &str) -> Result<Option<ReportFile>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT "fileName","contentType","content" FROM "progress_report_jobs"
+           WHERE "id" = $1 AND "status" = 'completed' AND "expiresAt" > NOW()"#,
+    )
+    .bind(id)
+    .fetch_optional(proxy.pool())
+    .await?;
+    Ok(row.and_then(|row| {
+        Some(ReportFile {
+            file_name: row.try_get("fileName").ok()?,
+            content_type: row.try_get("contentType").ok()?,
+            content: row.try_get("content").ok()?,
+        })
+    }))
+}
+
+fn format_ts(ts: NaiveDateTime) -> String {
+    ts.and_utc().to_rfc3339()
+}
+
+fn map_job_row(row: sqlx::postgres::PgRow) -> ReportJob {
+    ReportJob {
+        id: row.try_get("id").unwrap_or_default(),
+        scope: row.try_get("scope").unwrap_or_default(),
+        format: row.try_get("format").unwrap_or_default(),
+        status: row.try_get("status").unwrap_or_default(),
+        request: row.try_get("request").unwrap_or(serde_json::Value::Null),
+        file_name: row.try_get("fileName").ok().flatten(),
+        size_bytes: row.try_get("sizeBytes").ok().flatten(),
+        error: row.try_get("error").ok().flatten(),
+        created_by: row.try_get("createdBy").ok().flatten(),
+        request_id: row.try_get("requestId").ok().flatten(),
+        created_at: row
+            .try_get::<NaiveDateTime, _>("createdAt")
+            .map(format_ts)
+            .unwrap_or_else(|_| Utc::now().to_rfc3339()),
+        completed_at: row
+            .try_get::<Option<NaiveDateTime>, _>("completedAt")
+            .ok()
+            .flatten()
+            .map(format_ts),
+        expires_at: row
+            .try_get::<NaiveDateTime, _>("expiresAt")
+            .map(format_ts)
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn student(id: &str, name: &str, days: usize) -> StudentProgress {
+        let from = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+        StudentProgress {
+            user_id: id.to_string(),
+            username: name.to_string(),
+            mastered_words: 120,
+            newly_mastered: 15,
+            daily: (0..days)
+                .map(|i| DailyProgress {
+                    date: from + Duration::days(i as i64),
+                    answers: 20,
+                    correct: 10 + (i as i64 % 10),
+                    time_on_task_ms: 600_000,
+                })
+                .collect(),
+        }
+    }
+
+    fn report(students: Vec<StudentProgress>) -> ProgressReport {
+        ProgressReport {
+            label: Some("Class 3A".into()),
+            from: NaiveDate::from_ymd_opt(2026, 9, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2026, 9, 30).unwrap(),
+            students,
+        }
+    }
+
+    #[test]
+    fn csv_has_daily_rows_for_a_student_and_summary_rows_for_a_class() {
+        let single =
+            String::from_utf8(render_csv(&report(vec![student("u1", "amy", 3)])).unwrap()).unwrap();
+        assert_eq!(single.lines().count(), 4);
+        assert!(single
+            .lines()
+            .nth(1)
+            .unwrap()
+            .contains("2026-09-01,20,10,50.0,10"));
+
+        let class = String::from_utf8(
+            render_csv(&report(vec![
+                student("u1", "amy", 3),
+                student("u2", "张三", 0),
+            ]))
+            .unwrap(),
+        )
+        .unwrap();
+        let rows: Vec<&str> = class.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[1].starts_with("u1,amy,60,55.0,120,15,30,3"));
+        assert!(rows[2].starts_with("u2,张三,0,,120,15,0,0"));
+    }
+
+    #[test]
+    fn pdf_paginates_long_reports() {
+        let short = render_pdf(&report(vec![student("u1", "amy", 5)]));
+        assert!(short.starts_with(b"%PDF-"));
+        assert!(String::from_utf8_lossy(&short).contains("/Count 1"));
+
+        let class: Vec<StudentProgress> = (0..120)
+            .map(|i| student(&format!("u{i}"), "student", 2))
+            .collect();
+        let long = render_pdf(&report(class));
+        let text = String::from_utf8_lossy(&long);
+        assert!(text.contains("/Count 3"), "expected three pages");
+    }
+
+    #[test]
+    fn validate_bounds() {
+        let mut request = ReportRequest {
+            user_ids: vec![],
+            label: None,
+            format: ReportFormat::Pdf,
+            days: None,
+        };
+        assert!(request.validate().is_err());
+        request.user_ids = vec!["u1".into()];
+        assert!(request.validate().is_ok());
+        assert_eq!(request.scope(), "student");
+        request.days = Some(MAX_DAYS + 1);
+        assert!(request.validate().is_err());
+    }
+}
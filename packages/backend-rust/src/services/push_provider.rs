@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushPlatform {
+    Fcm,
+    Apns,
+}
+
+impl PushPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fcm => "fcm",
+            Self::Apns => "apns",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "fcm" => Some(Self::Fcm),
+            "apns" => Some(Self::Apns),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushProviderType {
+    Mock,
+    None,
+}
+
+#[derive(Debug, Error)]
+pub enum PushError {
+    #[error("push not configured: {0}")]
+    NotConfigured(&'static str),
+}
+
+/// Push delivery adapter. FCM and APNs are stubs until credentials are
+/// provisioned: only the mock provider reports success, so queued pushes
+/// stay visible as failed deliveries instead of being silently dropped.
+#[derive(Debug, Clone)]
+pub struct PushService {
+    provider: PushProviderType,
+}
+
+impl PushService {
+    pub fn from_env() -> Self {
+        let provider = match std::env::var("PUSH_PROVIDER").ok().as_deref() {
+            Some("mock") => PushProviderType::Mock,
+            _ => PushProviderType::None,
+        };
+        Self { provider }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.provider == PushProviderType::Mock
+    }
+
+    pub async fn send_push(
+        &self,
+        platform: PushPlatform,
+        device_token: &str,
+        title: &str,
+        body: &str,
+    ) -> Result<(), PushError> {
+        match self.provider {
+            PushProviderType::Mock => {
+                tracing::debug!(
+                    platform = platform.as_str(),
+                    token_len = device_token.len(),
+                    title_len = title.len(),
+                    body_len = body.len(),
+                    "Mock push sent"
+                );
+                Ok(())
+            }
+            PushProviderType::None => Err(PushError::NotConfigured(match platform {
+                PushPlatform::Fcm => "FCM",
+                PushPlatform::Apns => "APNS",
+            })),
+        }
+    }
+}
@@ -7,6 +7,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::db::DatabaseProxy;
+use crate::services::lemmatizer::Lemmatizer;
 use crate::services::llm_provider::{ChatMessage, LLMProvider};
 
 const DEFAULT_CONCURRENCY: usize = 1;
@@ -1289,18 +1290,21 @@ fn example_contains_word_form(example: &str, word: &str) -> bool {
         return !target.is_empty() && lowered.contains(&target);
     };
 
+    let lemmatizer = crate::services::lemmatizer::english();
+    let matches = |token: &str| forms.contains(token) || lemmatizer.same_lemma(token, word);
+
     let mut token = String::new();
     for ch in example.chars() {
         if ch.is_ascii_alphabetic() {
             token.push(ch.to_ascii_lowercase());
         } else if !token.is_empty() {
-            if forms.contains(&token) {
+            if matches(&token) {
                 return true;
             }
             token.clear();
         }
     }
-    !token.is_empty() && forms.contains(&token)
+    !token.is_empty() && matches(&token)
 }
 
 fn build_word_forms(word: &str) -> Option<std::collections::HashSet<String>> {
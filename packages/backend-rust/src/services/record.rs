@@ -1,11 +1,13 @@
 use std::collections::HashSet;
 
-use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
 use serde::Serialize;
 use sqlx::{QueryBuilder, Row};
 use uuid::Uuid;
 
 use crate::db::DatabaseProxy;
+use crate::services::stats_rollup::{self, StatsFreshness};
+use crate::services::wordbook_licenses;
 
 const MAX_BATCH_SIZE: usize = 1000;
 const TIMESTAMP_PAST_LIMIT_MS: i64 = 24 * 60 * 60 * 1000;
@@ -17,6 +19,7 @@ pub struct CreateRecordInput {
     pub selected_option: Option<String>,
     pub selected_answer: Option<String>,
     pub correct_answer: Option<String>,
+    pub question_type: Option<String>,
     pub is_correct: bool,
     pub timestamp_ms: Option<i64>,
     pub response_time: Option<i64>,
@@ -167,6 +170,7 @@ pub struct EnhancedStudyStatistics {
     pub daily_accuracy: Vec<DailyAccuracyItem>,
     pub weekday_heat: Vec<i32>,
     pub mastery_distribution: Vec<MasteryLevelCount>,
+    pub freshness: StatsFreshness,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -224,9 +228,10 @@ pub enum RecordError {
 pub async fn create_record(
     proxy: &DatabaseProxy,
     user_id: &str,
-    input: CreateRecordInput,
+    mut input: CreateRecordInput,
 ) -> Result<AnswerRecord, RecordError> {
     validate_record_input(&input)?;
+    apply_answer_matching(&mut input);
 
     if let Some(session_id) = input.session_id.as_deref() {
         ensure_learning_session_exists(proxy, session_id, user_id).await?;
@@ -374,7 +379,8 @@ pub async fn batch_create_records(
     let mut resolved: Vec<ResolvedRecord> = Vec::with_capacity(records.len());
     let now_ms = Utc::now().timestamp_millis();
 
-    for record in records {
+    for mut record in records {
+        apply_answer_matching(&mut record);
         let ts_ms = match record.timestamp_ms {
             Some(ts) => validate_timestamp_ms(ts)?,
             None => now_ms,
@@ -662,6 +668,31 @@ fn resolve_selected_answer(input: &CreateRecordInput) -> String {
         .to_string()
 }
 
+/// Question types where the learner types the answer; choice questions are
+/// graded by the client and never overridden.
+const TYPED_QUESTION_TYPES: &[&str] = &["typing", "spelling", "dictation", "fill_blank"];
+
+/// Accept regular inflections of the expected answer ("runs" for "run") on
+/// typed questions.
+fn apply_answer_matching(input: &mut CreateRecordInput) {
+    if input.is_correct {
+        return;
+    }
+    let typed = input.question_type.as_deref().is_some_and(|kind| {
+        TYPED_QUESTION_TYPES.contains(&kind.trim().to_ascii_lowercase().as_str())
+    });
+    if !typed {
+        return;
+    }
+    let Some(correct) = input.correct_answer.as_deref() else {
+        return;
+    };
+    let selected = resolve_selected_answer(input);
+    if crate::services::lemmatizer::answers_match(&selected, correct) {
+        input.is_correct = true;
+    }
+}
+
 fn validate_record_input(input: &CreateRecordInput) -> Result<(), RecordError> {
     if Uuid::parse_str(&input.word_id).is_err() {
         return Err(RecordError::Validation("无效的单词ID".to_string()));
@@ -836,11 +867,8 @@ async fn select_accessible_word_ids(
         }
         sep.push_unseparated(")");
     }
-    qb.push(
-        " AND (wb.\"type\"::text = 'SYSTEM' OR (wb.\"type\"::text = 'USER' AND wb.\"userId\" = ",
-    );
-    qb.push_bind(user_id);
-    qb.push("))");
+    qb.push(" AND ");
+    wordbook_licenses::push_accessible_wordbook(&mut qb, "wb.", user_id);
     let rows = qb.build().fetch_all(pool).await?;
     Ok(rows
         .into_iter()
@@ -1037,13 +1065,10 @@ async fn select_accessible_word_book_ids(
     user_id: &str,
 ) -> Result<Vec<String>, sqlx::Error> {
     let pool = proxy.pool();
-    let rows = sqlx::query(
-        r#"
-        SELECT "id"
-        FROM "word_books"
-        WHERE "type"::text = 'SYSTEM' OR ("type"::text = 'USER' AND "userId" = $1)
-        "#,
-    )
+    let rows = sqlx::query(&format!(
+        r#"SELECT "id" FROM "word_books" WHERE {}"#,
+        wordbook_licenses::accessible_wordbook_sql(r#""word_books"."#, "$1")
+    ))
     .bind(user_id)
     .fetch_all(pool)
     .await?;
@@ -1207,6 +1232,51 @@ async fn select_learning_session_owner(
 pub async fn get_enhanced_statistics(
     proxy: &DatabaseProxy,
     user_id: &str,
+) -> Result<EnhancedStudyStatistics, sqlx::Error> {
+    let pool = proxy.pool();
+    let Some(watermark) =
+        stats_rollup::load_watermark(pool, stats_rollup::USER_DAILY_STATS).await?
+    else {
+        return get_enhanced_statistics_live(proxy, user_id).await;
+    };
+
+    let (total_words, recent_records, daily, mastery_distribution) = tokio::try_join!(
+        count_accessible_words(proxy, user_id),
+        select_recent_records_with_date(proxy, user_id, None),
+        stats_rollup::list_user_daily_stats(pool, user_id),
+        calculate_mastery_distribution(proxy, user_id),
+    )?;
+    let now = Utc::now();
+    let summary = stats_rollup::summarize(&daily, now.date_naive());
+    let study_dates: HashSet<NaiveDate> = summary.study_dates.iter().copied().collect();
+    let consecutive_days = consecutive_days_from_dates(proxy, user_id, &study_dates).await?;
+
+    Ok(EnhancedStudyStatistics {
+        total_words,
+        total_records: summary.total_records,
+        correct_rate: summary.correct_rate(),
+        recent_records,
+        study_days: summary.study_dates.len() as i64,
+        consecutive_days,
+        daily_accuracy: summary
+            .daily_accuracy
+            .iter()
+            .map(|(date, accuracy)| DailyAccuracyItem {
+                date: date.to_string(),
+                accuracy: *accuracy,
+            })
+            .collect(),
+        weekday_heat: summary.weekday_heat,
+        mastery_distribution,
+        freshness: watermark.freshness(now.naive_utc()),
+    })
+}
+
+/// Computes everything from `answer_records` directly; used until the
+/// rollup has been populated for the first time.
+async fn get_enhanced_statistics_live(
+    proxy: &DatabaseProxy,
+    user_id: &str,
 ) -> Result<EnhancedStudyStatistics, sqlx::Error> {
     let (base, study_days, consecutive_days, daily_accuracy, weekday_heat, mastery_distribution) =
         tokio::try_join!(
@@ -1228,9 +1298,15 @@ pub async fn get_enhanced_statistics(
         daily_accuracy,
         weekday_heat,
         mastery_distribution,
+        freshness: StatsFreshness::live(),
     })
 }
 
+async fn count_accessible_words(proxy: &DatabaseProxy, user_id: &str) -> Result<i64, sqlx::Error> {
+    let word_book_ids = select_accessible_word_book_ids(proxy, user_id).await?;
+    count_words_in_word_books(proxy, &word_book_ids).await
+}
+
 async fn calculate_study_days(proxy: &DatabaseProxy, user_id: &str) -> Result<i64, sqlx::Error> {
     let pool = proxy.pool();
     let row = sqlx::query(
@@ -1247,35 +1323,34 @@ async fn calculate_consecutive_days(
     user_id: &str,
 ) -> Result<i64, sqlx::Error> {
     let pool = proxy.pool();
-    let row = sqlx::query(
-        r#"
-        WITH study_dates AS (
-            SELECT DISTINCT DATE("timestamp") as study_date
-            FROM "answer_records"
-            WHERE "userId" = $1
-        ),
-        date_gaps AS (
-            SELECT study_date,
-                   study_date - (ROW_NUMBER() OVER (ORDER BY study_date))::int as grp
-            FROM study_dates
-        ),
-        streaks AS (
-            SELECT grp, COUNT(*) as streak_len, MAX(study_date) as last_date
-            FROM date_gaps
-            GROUP BY grp
-        )
-        SELECT COALESCE(
-            (SELECT streak_len FROM streaks
-             WHERE last_date >= CURRENT_DATE - INTERVAL '1 day'
-             ORDER BY last_date DESC LIMIT 1),
-            0
-        )::bigint as "consecutive"
-        "#,
+    let rows = sqlx::query(
+        r#"SELECT DISTINCT DATE("timestamp") as "studyDate" FROM "answer_records" WHERE "userId" = $1"#,
     )
     .bind(user_id)
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await?;
-    Ok(row.try_get::<i64, _>("consecutive").unwrap_or(0))
+    let dates: HashSet<NaiveDate> = rows
+        .iter()
+        .filter_map(|row| row.try_get::<NaiveDate, _>("studyDate").ok())
+        .collect();
+    consecutive_days_from_dates(proxy, user_id, &dates).await
+}
+
+async fn consecutive_days_from_dates(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    dates: &HashSet<NaiveDate>,
+) -> Result<i64, sqlx::Error> {
+    if dates.is_empty() {
+        return Ok(0);
+    }
+    let vacations = crate::services::vacation::list_periods(proxy.pool(), user_id).await?;
+    Ok(crate::services::vacation::consecutive_days(
+        dates,
+        &vacations,
+        Utc::now().date_naive(),
+        false,
+    ))
 }
 
 async fn calculate_daily_accuracy(
@@ -0,0 +1,883 @@
+//! SCIM v2 user provisioning (a subset of RFC 7643/7644) for organizations.
+//!
+//! An organization's identity provider authenticates with a bearer token
+//! issued by an admin; only the SHA-256 of the token is stored. Users created
+//! through SCIM are ordinary rows in `users`, linked to the organization in
+//! `scim_users` and added to `webhook_organization_members` so licenses and
+//! webhooks see them. A token only ever sees users of its own organization.
+//!
+//! Supported: `Users` create, read, list with `filter=userName eq "..."`,
+//! replace (PUT) and PATCH of `userName`, `displayName`, `externalId` and
+//! `active`. `userName` maps to the account email and `displayName` to the
+//! username. Deactivating a user blocks login and drops all of their
+//! sessions; there is no hard delete.
+
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseProxy;
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const LIST_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+pub const PATCH_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+pub const SERVICE_PROVIDER_SCHEMA: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+
+/// Upper bound for `count` on list requests.
+pub const MAX_RESULTS: i64 = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScimError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("password hash failed")]
+    Hash,
+    #[error("{0}")]
+    InvalidValue(String),
+    #[error("{0}")]
+    InvalidFilter(String),
+    #[error("{0}")]
+    InvalidPath(String),
+    #[error("userName is already taken")]
+    Uniqueness,
+    #[error("resource not found")]
+    NotFound,
+}
+
+impl ScimError {
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::Sql(_) | Self::Hash => 500,
+            Self::InvalidValue(_) | Self::InvalidFilter(_) | Self::InvalidPath(_) => 400,
+            Self::Uniqueness => 409,
+            Self::NotFound => 404,
+        }
+    }
+
+    /// `scimType` of the error response, where RFC 7644 defines one.
+    pub fn scim_type(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidValue(_) => Some("invalidValue"),
+            Self::InvalidFilter(_) => Some("invalidFilter"),
+            Self::InvalidPath(_) => Some("invalidPath"),
+            Self::Uniqueness => Some("uniqueness"),
+            _ => None,
+        }
+    }
+
+    /// SCIM error body; internal failures do not leak details.
+    pub fn to_body(&self) -> Value {
+        let detail = match self {
+            Self::Sql(_) | Self::Hash => "internal error".to_string(),
+            other => other.to_string(),
+        };
+        let mut body = serde_json::json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": self.status().to_string(),
+            "detail": detail,
+        });
+        if let Some(scim_type) = self.scim_type() {
+            body["scimType"] = Value::from(scim_type);
+        }
+        body
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScimEmail {
+    pub value: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: &'static str,
+    pub created: String,
+    pub last_modified: String,
+    pub location: String,
+}
+
+/// A user as returned to the identity provider.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    pub schemas: Vec<&'static str>,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub user_name: String,
+    pub display_name: String,
+    pub emails: Vec<ScimEmail>,
+    pub active: bool,
+    pub meta: ScimMeta,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScimListResponse {
+    pub schemas: Vec<&'static str>,
+    #[serde(rename = "totalResults")]
+    pub total_results: i64,
+    #[serde(rename = "startIndex")]
+    pub start_index: i64,
+    #[serde(rename = "itemsPerPage")]
+    pub items_per_page: i64,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimUser>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimName {
+    pub formatted: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+}
+
+/// Body of POST and PUT; attributes outside the supported subset are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserInput {
+    pub user_name: Option<String>,
+    pub external_id: Option<String>,
+    pub display_name: Option<String>,
+    pub name: Option<ScimName>,
+    pub active: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatchRequest {
+    #[serde(rename = "Operations", alias = "operations")]
+    pub operations: Vec<PatchOperation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatchOperation {
+    pub op: String,
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Value,
+}
+
+/// The writable part of a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserFields {
+    pub user_name: String,
+    pub display_name: String,
+    pub external_id: Option<String>,
+    pub active: bool,
+}
+
+impl ScimUserInput {
+    /// Full resource for create/replace: `userName` is required, a missing
+    /// display name falls back to `name`, then to the local part of the email.
+    pub fn into_fields(self) -> Result<UserFields, ScimError> {
+        let user_name = self
+            .user_name
+            .map(|u| u.trim().to_string())
+            .ok_or_else(|| ScimError::InvalidValue("userName is required".to_string()))?;
+        validate_user_name(&user_name)?;
+        let display_name = self
+            .display_name
+            .filter(|n| !n.trim().is_empty())
+            .or_else(|| self.name.as_ref().and_then(ScimName::display))
+            .unwrap_or_else(|| local_part(&user_name).to_string());
+        let active = match self.active {
+            None => true,
+            Some(v) => parse_bool(&v)?,
+        };
+        Ok(UserFields {
+            user_name,
+            display_name: display_name.trim().to_string(),
+            external_id: self.external_id.filter(|e| !e.is_empty()),
+            active,
+        })
+    }
+}
+
+impl ScimName {
+    fn display(&self) -> Option<String> {
+        if let Some(formatted) = self.formatted.as_ref().filter(|f| !f.trim().is_empty()) {
+            return Some(formatted.clone());
+        }
+        let parts: Vec<&str> = [&self.given_name, &self.family_name]
+            .into_iter()
+            .filter_map(|p| p.as_deref())
+            .filter(|p| !p.trim().is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+fn local_part(email: &str) -> &str {
+    email.split('@').next().unwrap_or(email)
+}
+
+/// `userName` becomes the login email, so it has to look like one.
+pub fn validate_user_name(user_name: &str) -> Result<(), ScimError> {
+    let valid = user_name.len() <= 254
+        && !user_name.chars().any(char::is_whitespace)
+        && user_name
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ScimError::InvalidValue(
+            "userName must be an email address".to_string(),
+        ))
+    }
+}
+
+/// Some providers send booleans as the strings "True"/"False".
+fn parse_bool(value: &Value) -> Result<bool, ScimError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        Value::String(s) if s.eq_ignore_ascii_case("true") => Ok(true),
+        Value::String(s) if s.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(ScimError::InvalidValue(
+            "active must be a boolean".to_string(),
+        )),
+    }
+}
+
+fn parse_string(value: &Value, attr: &str) -> Result<String, ScimError> {
+    value
+        .as_str()
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| ScimError::InvalidValue(format!("{attr} must be a string")))
+}
+
+/// Parses the only supported filter, `userName eq "value"`. Attribute names
+/// and operators are case-insensitive, as RFC 7644 requires.
+pub fn parse_filter(filter: &str) -> Result<String, ScimError> {
+    let invalid = || ScimError::InvalidFilter(format!("unsupported filter: {filter}"));
+    let filter = filter.trim();
+    let (attr, rest) = filter.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let (op, value) = rest
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    if !attr.eq_ignore_ascii_case("userName") || !op.eq_ignore_ascii_case("eq") {
+        return Err(invalid());
+    }
+    let value = value.trim();
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push(chars.next().ok_or_else(invalid)?),
+            '"' => return Err(invalid()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Applies PATCH operations in order. `add` and `replace` take a supported
+/// path, or no path and an object of attributes; `remove` only clears
+/// `externalId`.
+pub fn apply_patch(
+    mut fields: UserFields,
+    ops: &[PatchOperation],
+) -> Result<UserFields, ScimError> {
+    for op in ops {
+        let kind = op.op.to_ascii_lowercase();
+        match (kind.as_str(), op.path.as_deref()) {
+            ("add" | "replace", Some(path)) => set_attribute(&mut fields, path, &op.value)?,
+            ("add" | "replace", None) => {
+                let Value::Object(attrs) = &op.value else {
+                    return Err(ScimError::InvalidValue(
+                        "value must be an object when path is omitted".to_string(),
+                    ));
+                };
+                for (path, value) in attrs {
+                    set_attribute(&mut fields, path, value)?;
+                }
+            }
+            ("remove", Some(path)) if path.eq_ignore_ascii_case("externalId") => {
+                fields.external_id = None;
+            }
+            ("remove", _) => {
+                return Err(ScimError::InvalidPath(format!(
+                    "cannot remove {}",
+                    op.path.as_deref().unwrap_or("<none>")
+                )))
+            }
+            _ => {
+                return Err(ScimError::InvalidValue(format!(
+                    "unsupported op: {}",
+                    op.op
+                )))
+            }
+        }
+    }
+    Ok(fields)
+}
+
+fn set_attribute(fields: &mut UserFields, path: &str, value: &Value) -> Result<(), ScimError> {
+    match path.to_ascii_lowercase().as_str() {
+        "username" => {
+            let user_name = parse_string(value, "userName")?;
+            validate_user_name(&user_name)?;
+            fields.user_name = user_name;
+        }
+        "displayname" | "name.formatted" => {
+            let name = parse_string(value, path)?;
+            if name.is_empty() {
+                return Err(ScimError::InvalidValue(format!("{path} must not be empty")));
+            }
+            fields.display_name = name;
+        }
+        "externalid" => {
+            let external_id = parse_string(value, "externalId")?;
+            fields.external_id = (!external_id.is_empty()).then_some(external_id);
+        }
+        "active" => fields.active = parse_bool(value)?,
+        _ => return Err(ScimError::InvalidPath(format!("unsupported path: {path}"))),
+    }
+    Ok(())
+}
+
+fn format_ts(ts: NaiveDateTime) -> String {
+    crate::auth::format_naive_datetime_iso_millis(ts)
+}
+
+const USER_COLUMNS: &str = r#"u."id", u."email", u."username", s."externalId", s."active",
+    u."createdAt", GREATEST(u."updatedAt", s."updatedAt") AS "lastModified""#;
+
+fn map_user(row: &sqlx::postgres::PgRow) -> ScimUser {
+    let now = Utc::now().naive_utc();
+    let id: String = row.try_get("id").unwrap_or_default();
+    let email: String = row.try_get("email").unwrap_or_default();
+    ScimUser {
+        schemas: vec![USER_SCHEMA],
+        external_id: row.try_get("externalId").ok().flatten(),
+        display_name: row.try_get("username").unwrap_or_default(),
+        emails: vec![ScimEmail {
+            value: email.clone(),
+            kind: "work",
+            primary: true,
+        }],
+        user_name: email,
+        active: row.try_get("active").unwrap_or(true),
+        meta: ScimMeta {
+            resource_type: "User",
+            created: format_ts(row.try_get("createdAt").unwrap_or(now)),
+            last_modified: format_ts(row.try_get("lastModified").unwrap_or(now)),
+            location: format!("/scim/v2/Users/{id}"),
+        },
+        id,
+    }
+}
+
+/// Organization of an active token; records its use.
+pub async fn authenticate(proxy: &DatabaseProxy, token: &str) -> Result<Option<String>, ScimError> {
+    let org: Option<String> = sqlx::query_scalar(
+        r#"UPDATE "scim_tokens" SET "lastUsedAt" = NOW()
+           WHERE "tokenHash" = $1 AND "revokedAt" IS NULL
+           RETURNING "organizationId""#,
+    )
+    .bind(crate::auth::hash_token(token))
+    .fetch_optional(proxy.pool())
+    .await?;
+    Ok(org)
+}
+
+pub async fn get_user(
+    proxy: &DatabaseProxy,
+    org_id: &str,
+    id: &str,
+) -> Result<ScimUser, ScimError> {
+    let sql = format!(
+        r#"SELECT {USER_COLUMNS} FROM "scim_users" s JOIN "users" u ON u."id" = s."userId"
+           WHERE s."organizationId" = $1 AND s."userId" = $2"#
+    );
+    sqlx::query(&sql)
+        .bind(org_id)
+        .bind(id)
+        .fetch_optional(proxy.pool())
+        .await?
+        .as_ref()
+        .map(map_user)
+        .ok_or(ScimError::NotFound)
+}
+
+/// `start_index` is 1-based as in SCIM; `count` is clamped to `MAX_RESULTS`.
+pub async fn list_users(
+    proxy: &DatabaseProxy,
+    org_id: &str,
+    user_name: Option<&str>,
+    start_index: i64,
+    count: i64,
+) -> Result<ScimListResponse, ScimError> {
+    let start_index = start_index.max(1);
+    let count = count.clamp(0, MAX_RESULTS);
+    let total: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM "scim_users" s JOIN "users" u ON u."id" = s."userId"
+           WHERE s."organizationId" = $1 AND ($2::text IS NULL OR LOWER(u."email") = LOWER($2))"#,
+    )
+    .bind(org_id)
+    .bind(user_name)
+    .fetch_one(proxy.pool())
+    .await?;
+    let sql = format!(
+        r#"SELECT {USER_COLUMNS} FROM "scim_users" s JOIN "users" u ON u."id" = s."userId"
+           WHERE s."organizationId" = $1 AND ($2::text IS NULL OR LOWER(u."email") = LOWER($2))
+           ORDER BY s."createdAt", u."id"
+           LIMIT $3 OFFSET $4"#
+    );
+    let rows = sqlx::query(&sql)
+        .bind(org_id)
+        .bind(user_name)
+        .bind(count)
+        .bind(start_index - 1)
+        .fetch_all(proxy.pool())
+        .await?;
+    let resources: Vec<ScimUser> = rows.iter().map(map_user).collect();
+    Ok(ScimListResponse {
+        schemas: vec![LIST_SCHEMA],
+        total_results: total,
+        start_index,
+        items_per_page: resources.len() as i64,
+        resources,
+    })
+}
+
+async fn email_taken(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    email: &str,
+    except_user: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let existing: Option<String> = sqlx::query_scalar(
+        r#"SELECT "id" FROM "users"
+           WHERE LOWER("email") = LOWER($1) AND ($2::text IS NULL OR "id" <> $2)
+           LIMIT 1"#,
+    )
+    .bind(email)
+    .bind(except_user)
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(existing.is_some())
+}
+
+async fn external_id_taken(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    org_id: &str,
+    external_id: Option<&str>,
+    except_user: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let Some(external_id) = external_id else {
+        return Ok(false);
+    };
+    let existing: Option<String> = sqlx::query_scalar(
+        r#"SELECT "userId" FROM "scim_users"
+           WHERE "organizationId" = $1 AND "externalId" = $2
+             AND ($3::text IS NULL OR "userId" <> $3)"#,
+    )
+    .bind(org_id)
+    .bind(external_id)
+    .bind(except_user)
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(existing.is_some())
+}
+
+/// Creates a learner account. It has an unusable random password; the user
+/// sets one through the password reset flow.
+pub async fn create_user(
+    proxy: &DatabaseProxy,
+    org_id: &str,
+    fields: &UserFields,
+) -> Result<ScimUser, ScimError> {
+    let password_hash =
+        bcrypt::hash(Uuid::new_v4().to_string(), 10).map_err(|_| ScimError::Hash)?;
+    let user_id = Uuid::new_v4().to_string();
+    let now = Utc::now().naive_utc();
+
+    let mut tx = proxy.pool().begin().await?;
+    if email_taken(&mut tx, &fields.user_name, None).await?
+        || external_id_taken(&mut tx, org_id, fields.external_id.as_deref(), None).await?
+    {
+        return Err(ScimError::Uniqueness);
+    }
+    sqlx::query(
+        r#"INSERT INTO "users" ("id", "email", "passwordHash", "username", "updatedAt")
+           VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(&user_id)
+    .bind(&fields.user_name)
+    .bind(&password_hash)
+    .bind(&fields.display_name)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO "scim_users" ("userId", "organizationId", "externalId", "active", "updatedAt")
+           VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(&user_id)
+    .bind(org_id)
+    .bind(fields.external_id.as_deref())
+    .bind(fields.active)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO "webhook_organization_members" ("organizationId", "userId")
+           VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+    )
+    .bind(org_id)
+    .bind(&user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    get_user(proxy, org_id, &user_id).await
+}
+
+/// Current writable fields of an organization's user.
+pub async fn load_fields(
+    proxy: &DatabaseProxy,
+    org_id: &str,
+    id: &str,
+) -> Result<UserFields, ScimError> {
+    let user = get_user(proxy, org_id, id).await?;
+    Ok(UserFields {
+        user_name: user.user_name,
+        display_name: user.display_name,
+        external_id: user.external_id,
+        active: user.active,
+    })
+}
+
+/// Writes the fields of PUT or PATCH. Callers revoke the sessions of a
+/// deactivated user.
+pub async fn update_user(
+    proxy: &DatabaseProxy,
+    org_id: &str,
+    id: &str,
+    fields: &UserFields,
+) -> Result<ScimUser, ScimError> {
+    let now = Utc::now().naive_utc();
+    let mut tx = proxy.pool().begin().await?;
+    let owned: Option<String> = sqlx::query_scalar(
+        r#"SELECT "userId" FROM "scim_users"
+           WHERE "organizationId" = $1 AND "userId" = $2 FOR UPDATE"#,
+    )
+    .bind(org_id)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if owned.is_none() {
+        return Err(ScimError::NotFound);
+    }
+    if email_taken(&mut tx, &fields.user_name, Some(id)).await?
+        || external_id_taken(&mut tx, org_id, fields.external_id.as_deref(), Some(id)).await?
+    {
+        return Err(ScimError::Uniqueness);
+    }
+    sqlx::query(
+        r#"UPDATE "users" SET "email" = $2, "username" = $3, "updatedAt" = $4 WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(&fields.user_name)
+    .bind(&fields.display_name)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"UPDATE "scim_users" SET "externalId" = $2, "active" = $3, "updatedAt" = $4
+           WHERE "userId" = $1"#,
+    )
+    .bind(id)
+    .bind(fields.external_id.as_deref())
+    .bind(fields.active)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    get_user(proxy, org_id, id).await
+}
+
+/// Static description of the supported subset.
+pub fn service_provider_config() -> Value {
+    serde_json::json!({
+        "schemas": [SERVICE_PROVIDER_SCHEMA],
+        "patch": { "supported": true },
+        "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+        "filter": { "supported": true, "maxResults": MAX_RESULTS },
+        "changePassword": { "supported": false },
+        "sort": { "supported": false },
+        "etag": { "supported": false },
+        "authenticationSchemes": [{
+            "type": "oauthbearertoken",
+            "name": "Bearer token",
+            "description": "Organization token issued under /api/admin/scim/tokens",
+            "primary": true,
+        }],
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimToken {
+    pub id: String,
+    pub organization_id: String,
+    pub description: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+}
+
+fn map_token(row: &sqlx::postgres::PgRow) -> ScimToken {
+    let now = Utc::now().naive_utc();
+    ScimToken {
+        id: row.try_get("id").unwrap_or_default(),
+        organization_id: row.try_get("organizationId").unwrap_or_default(),
+        description: row.try_get("description").ok().flatten(),
+        created_by: row.try_get("createdBy").ok().flatten(),
+        created_at: format_ts(row.try_get("createdAt").unwrap_or(now)),
+        last_used_at: row
+            .try_get::<Option<NaiveDateTime>, _>("lastUsedAt")
+            .ok()
+            .flatten()
+            .map(format_ts),
+        revoked_at: row
+            .try_get::<Option<NaiveDateTime>, _>("revokedAt")
+            .ok()
+            .flatten()
+            .map(format_ts),
+    }
+}
+
+/// Issues a token; the plaintext is returned only here.
+pub async fn create_token(
+    proxy: &DatabaseProxy,
+    org_id: &str,
+    description: Option<&str>,
+    admin_id: &str,
+) -> Result<(ScimToken, String), ScimError> {
+    let secret = format!(
+        "scim_{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let row = sqlx::query(
+        r#"INSERT INTO "scim_tokens" ("id", "organizationId", "tokenHash", "description", "createdBy")
+           VALUES ($1, $2, $3, $4, $5)
+           RETURNING "id", "organizationId", "description", "createdBy", "createdAt",
+                     "lastUsedAt", "revokedAt""#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(org_id)
+    .bind(crate::auth::hash_token(&secret))
+    .bind(description)
+    .bind(admin_id)
+    .fetch_one(proxy.pool())
+    .await?;
+    Ok((map_token(&row), secret))
+}
+
+pub async fn list_tokens(
+    proxy: &DatabaseProxy,
+    org_id: Option<&str>,
+) -> Result<Vec<ScimToken>, ScimError> {
+    let rows = sqlx::query(
+        r#"SELECT "id", "organizationId", "description", "createdBy", "createdAt",
+                  "lastUsedAt", "revokedAt"
+           FROM "scim_tokens"
+           WHERE ($1::text IS NULL OR "organizationId" = $1)
+           ORDER BY "createdAt" DESC"#,
+    )
+    .bind(org_id)
+    .fetch_all(proxy.pool())
+    .await?;
+    Ok(rows.iter().map(map_token).collect())
+}
+
+pub async fn revoke_token(proxy: &DatabaseProxy, id: &str) -> Result<(), ScimError> {
+    let result = sqlx::query(
+        r#"UPDATE "scim_tokens" SET "revokedAt" = NOW() WHERE "id" = $1 AND "revokedAt" IS NULL"#,
+    )
+    .bind(id)
+    .execute(proxy.pool())
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ScimError::NotFound);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields() -> UserFields {
+        UserFields {
+            user_name: "ada@example.com".to_string(),
+            display_name: "Ada".to_string(),
+            external_id: Some("ext-1".to_string()),
+            active: true,
+        }
+    }
+
+    fn ops(value: Value) -> Vec<PatchOperation> {
+        serde_json::from_value::<PatchRequest>(value)
+            .unwrap()
+            .operations
+    }
+
+    #[test]
+    fn test_filter_user_name_eq() {
+        assert_eq!(
+            parse_filter(r#"userName eq "ada@example.com""#).unwrap(),
+            "ada@example.com"
+        );
+        assert_eq!(
+            parse_filter(r#"USERNAME Eq "a\"b@x.io""#).unwrap(),
+            r#"a"b@x.io"#
+        );
+        for bad in [
+            r#"displayName eq "Ada""#,
+            r#"userName co "ada""#,
+            r#"userName eq ada"#,
+            r#"userName eq "a" or userName eq "b""#,
+            "userName",
+        ] {
+            let err = parse_filter(bad).unwrap_err();
+            assert_eq!(err.scim_type(), Some("invalidFilter"), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_input_defaults_and_validation() {
+        let input: ScimUserInput = serde_json::from_value(json!({
+            "schemas": [USER_SCHEMA],
+            "userName": "grace@example.com",
+            "name": { "givenName": "Grace", "familyName": "Hopper" },
+            "active": "False",
+            "title": "ignored",
+        }))
+        .unwrap();
+        let f = input.into_fields().unwrap();
+        assert_eq!(f.display_name, "Grace Hopper");
+        assert!(!f.active);
+        assert_eq!(f.external_id, None);
+
+        let bare = ScimUserInput {
+            user_name: Some("bob@example.com".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(bare.into_fields().unwrap().display_name, "bob");
+
+        let missing = ScimUserInput::default().into_fields().unwrap_err();
+        assert_eq!(missing.status(), 400);
+        let bad = ScimUserInput {
+            user_name: Some("not an email".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            bad.into_fields().unwrap_err().scim_type(),
+            Some("invalidValue")
+        );
+    }
+
+    #[test]
+    fn test_patch_with_and_without_path() {
+        let patched = apply_patch(
+            fields(),
+            &ops(json!({
+                "schemas": [PATCH_SCHEMA],
+                "Operations": [
+                    { "op": "Replace", "path": "active", "value": "False" },
+                    { "op": "replace", "value": { "displayName": "Ada L.", "userName": "ada@corp.io" } },
+                    { "op": "remove", "path": "externalId" },
+                ],
+            })),
+        )
+        .unwrap();
+        assert_eq!(
+            patched,
+            UserFields {
+                user_name: "ada@corp.io".to_string(),
+                display_name: "Ada L.".to_string(),
+                external_id: None,
+                active: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_patch_rejects_unsupported() {
+        let cases = [
+            (
+                json!([{ "op": "replace", "path": "emails[type eq \"work\"].value", "value": "x" }]),
+                "invalidPath",
+            ),
+            (
+                json!([{ "op": "remove", "path": "userName" }]),
+                "invalidPath",
+            ),
+            (
+                json!([{ "op": "replace", "path": "active", "value": 3 }]),
+                "invalidValue",
+            ),
+            (
+                json!([{ "op": "move", "path": "active", "value": true }]),
+                "invalidValue",
+            ),
+        ];
+        for (operations, expected) in cases {
+            let err = apply_patch(fields(), &ops(json!({ "Operations": operations }))).unwrap_err();
+            assert_eq!(err.scim_type(), Some(expected), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_error_body_and_resource_shape() {
+        let body = ScimError::Uniqueness.to_body();
+        assert_eq!(body["schemas"][0], ERROR_SCHEMA);
+        assert_eq!(body["status"], "409");
+        assert_eq!(body["scimType"], "uniqueness");
+        assert!(ScimError::Hash.to_body().get("scimType").is_none());
+
+        let user = ScimUser {
+            schemas: vec![USER_SCHEMA],
+            id: "u1".to_string(),
+            external_id: None,
+            user_name: "ada@example.com".to_string(),
+            display_name: "Ada".to_string(),
+            emails: vec![ScimEmail {
+                value: "ada@example.com".to_string(),
+                kind: "work",
+                primary: true,
+            }],
+            active: true,
+            meta: ScimMeta {
+                resource_type: "User",
+                created: "2026-01-01T00:00:00.000Z".to_string(),
+                last_modified: "2026-01-01T00:00:00.000Z".to_string(),
+                location: "/scim/v2/Users/u1".to_string(),
+            },
+        };
+        let v = serde_json::to_value(&user).unwrap();
+        assert_eq!(v["userName"], "ada@example.com");
+        assert_eq!(v["emails"][0]["type"], "work");
+        assert_eq!(v["meta"]["resourceType"], "User");
+        assert!(v.get("externalId").is_none());
+    }
+}
@@ -0,0 +1,122 @@
+//! Counterfactual reward summary for finished learning sessions.
+//!
+//! The session's logged AMAS decisions are compared with a schedule that
+//! picks a strategy difficulty uniformly at random, using the doubly robust
+//! estimate from `danci_algo::uniform_baseline_summary`. The result backs the
+//! "this session earned ~X% more than a random schedule" line on the session
+//! end screen and is kept in `session_reward_summaries` for analytics.
+//!
+//! Only decisions randomized by IGE's exploration draw are used, because
+//! only they carry a logged propensity; a greedy pick says nothing about
+//! what a random schedule would have earned. Sessions with too few of them
+//! get no summary rather than an uplift the data cannot support.
+
+use danci_algo::{uniform_baseline_summary, BetaPrior, CounterfactualSummary, LoggedDecision};
+use sqlx::Row;
+
+use crate::db::DatabaseProxy;
+
+/// Arms are the strategy difficulty levels, as in offline tuning.
+const ACTIONS: [&str; 3] = ["easy", "mid", "hard"];
+/// Fewer randomized, rewarded decisions than this give no summary.
+const MIN_DECISIONS: usize = 5;
+
+fn logged_decision(selected_action: &str, reward: f64, propensity: f64) -> Option<LoggedDecision> {
+    let strategy: serde_json::Value = serde_json::from_str(selected_action).ok()?;
+    let difficulty = strategy.get("difficulty")?.as_str()?;
+    let action = ACTIONS.iter().position(|a| *a == difficulty)? as u32;
+    Some(LoggedDecision {
+        context: Vec::new(),
+        action,
+        // Stored rewards are in [-1, 1]; the estimator expects [0, 1].
+        reward: (reward.clamp(-1.0, 1.0) + 1.0) / 2.0,
+        propensity: Some(propensity),
+    })
+}
+
+/// Computes and stores the summary of a session. Returns `None` when the
+/// session has too few randomized, rewarded decisions.
+pub async fn summarize_session(
+    proxy: &DatabaseProxy,
+    session_id: &str,
+    user_id: &str,
+) -> Result<Option<CounterfactualSummary>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "selectedAction", "reward", "propensity"
+        FROM "decision_records"
+        WHERE "sessionId" = $1
+          AND "reward" IS NOT NULL
+          AND "propensity" > 0
+          AND COALESCE("isSimulation", false) = false
+        ORDER BY "timestamp" ASC
+        "#,
+    )
+    .bind(session_id)
+    .fetch_all(proxy.pool())
+    .await?;
+
+    let log: Vec<LoggedDecision> = rows
+        .iter()
+        .filter_map(|row| {
+            let action: String = row.try_get("selectedAction").ok()?;
+            let reward: f64 = row.try_get("reward").ok()?;
+            let propensity: f64 = row.try_get("propensity").ok()?;
+            logged_decision(&action, reward, propensity)
+        })
+        .collect();
+    if log.len() < MIN_DECISIONS {
+        return Ok(None);
+    }
+    let Ok(summary) = uniform_baseline_summary(&log, ACTIONS.len() as u32, BetaPrior::default())
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO "session_reward_summaries" (
+            "sessionId", "userId", "decisions", "executedValue", "baselineValue",
+            "baselineStdError", "uplift", "upliftStdError", "relativeUplift"
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT ("sessionId") DO UPDATE SET
+            "decisions" = EXCLUDED."decisions",
+            "executedValue" = EXCLUDED."executedValue",
+            "baselineValue" = EXCLUDED."baselineValue",
+            "baselineStdError" = EXCLUDED."baselineStdError",
+            "uplift" = EXCLUDED."uplift",
+            "upliftStdError" = EXCLUDED."upliftStdError",
+            "relativeUplift" = EXCLUDED."relativeUplift",
+            "createdAt" = NOW()
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(summary.decisions as i32)
+    .bind(summary.executed_value)
+    .bind(summary.baseline_value)
+    .bind(summary.baseline_std_error)
+    .bind(summary.uplift)
+    .bind(summary.uplift_std_error)
+    .bind(summary.relative_uplift)
+    .execute(proxy.pool())
+    .await?;
+
+    Ok(Some(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_selected_strategy_to_arm() {
+        let decision =
+            logged_decision(r#"{"difficulty":"hard","batch_size":8}"#, 0.5, 0.4).unwrap();
+        assert_eq!(decision.action, 2);
+        assert!((decision.reward - 0.75).abs() < 1e-12);
+        assert_eq!(decision.propensity, Some(0.4));
+        assert!(logged_decision(r#"{"difficulty":"extreme"}"#, 0.5, 0.4).is_none());
+        assert!(logged_decision("not json", 0.5, 0.4).is_none());
+    }
+}
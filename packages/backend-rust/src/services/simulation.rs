@@ -0,0 +1,503 @@
+//! Admin simulation sandbox.
+//!
+//! Runs the danci-algo `sim` harness with a proposed scheduling config against
+//! synthetic cohorts and compares it with the active algorithm config. Jobs are
+//! stored in `simulation_jobs` and run in the background; the admin UI polls
+//! them for progress and the final retention / review-load deltas.
+
+use std::sync::Arc;
+
+use chrono::{NaiveDateTime, Utc};
+use danci_algo::sim::{compare_metrics, run_cohort, SchedulerConfig};
+use danci_algo::{CohortSpec, InterferenceSpec, SimComparison, SimMetrics};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::db::DatabaseProxy;
+use crate::middleware::request_id;
+
+pub const DEFAULT_LEARNERS: u32 = 200;
+pub const MAX_LEARNERS: u32 = 2000;
+pub const DEFAULT_DAYS: u32 = 60;
+pub const MAX_DAYS: u32 = 365;
+
+/// Simulations are CPU bound; cap how many run at once.
+static RUNNING_JOBS: Semaphore = Semaphore::const_new(2);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimulationError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Fields of the algorithm config the simulator understands; omitted fields
+/// keep the active config's value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedConfig {
+    pub review_intervals: Option<Vec<u32>>,
+    pub new_word_ratio_default: Option<f64>,
+    pub consecutive_correct_threshold: Option<u32>,
+    /// Ordering cost for planning confusable words on the same day; the
+    /// active config has none.
+    pub interference_penalty: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationRequest {
+    pub config: ProposedConfig,
+    /// Subset of the standard cohorts (`casual`, `regular`, `intensive`).
+    #[serde(default)]
+    pub cohorts: Option<Vec<String>>,
+    #[serde(default)]
+    pub learners_per_cohort: Option<u32>,
+    #[serde(default)]
+    pub days: Option<u32>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Confusable-pair model applied to every selected cohort.
+    #[serde(default)]
+    pub interference: Option<InterferenceSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationSummary {
+    /// Mean retention change across cohorts, in percentage points.
+    pub retention_delta: f64,
+    /// Mean relative change in reviews per learner-day.
+    pub review_load_delta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub seed: u64,
+    pub baseline_config: SchedulerConfig,
+    pub proposed_config: SchedulerConfig,
+    pub cohorts: Vec<SimComparison>,
+    pub summary: SimulationSummary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationJob {
+    pub id: String,
+    pub status: String,
+    pub progress: f64,
+    pub completed_units: i32,
+    pub total_units: i32,
+    pub request: serde_json::Value,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_by: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// Scheduling fields of the active algorithm config, or the built-in
+/// defaults when none is stored.
+pub async fn load_baseline(proxy: &DatabaseProxy) -> Result<SchedulerConfig, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT "reviewIntervals","newWordRatioDefault","consecutiveCorrectThreshold"
+           FROM "algorithm_configs" ORDER BY "isDefault" DESC, "createdAt" ASC LIMIT 1"#,
+    )
+    .fetch_optional(proxy.pool())
+    .await?;
+    let defaults = SchedulerConfig {
+        review_intervals: vec![1, 3, 7, 15, 30],
+        new_word_ratio: 0.3,
+        mastery_streak: 5,
+        interference_penalty: 0.0,
+    };
+    let Some(row) = row else {
+        return Ok(defaults);
+    };
+    let intervals: Vec<i32> = row.try_get("reviewIntervals").unwrap_or_default();
+    let intervals: Vec<u32> = intervals
+        .into_iter()
+        .filter_map(|d| u32::try_from(d).ok())
+        .filter(|d| *d > 0)
+        .collect();
+    Ok(SchedulerConfig {
+        review_intervals: if intervals.is_empty() {
+            defaults.review_intervals
+        } else {
+            intervals
+        },
+        new_word_ratio: row
+            .try_get("newWordRatioDefault")
+            .unwrap_or(defaults.new_word_ratio),
+        mastery_streak: row
+            .try_get::<i32, _>("consecutiveCorrectThreshold")
+            .ok()
+            .and_then(|v| u32::try_from(v).ok())
+            .unwrap_or(defaults.mastery_streak),
+        interference_penalty: defaults.interference_penalty,
+    })
+}
+
+pub fn merge_config(baseline: &SchedulerConfig, proposed: &ProposedConfig) -> SchedulerConfig {
+    SchedulerConfig {
+        review_intervals: proposed
+            .review_intervals
+            .clone()
+            .unwrap_or_else(|| baseline.review_intervals.clone()),
+        new_word_ratio: proposed
+            .new_word_ratio_default
+            .unwrap_or(baseline.new_word_ratio),
+        mastery_streak: proposed
+            .consecutive_correct_threshold
+            .unwrap_or(baseline.mastery_streak),
+        interference_penalty: proposed
+            .interference_penalty
+            .unwrap_or(baseline.interference_penalty),
+    }
+}
+
+pub fn select_cohorts(request: &SimulationRequest) -> Result<Vec<CohortSpec>, SimulationError> {
+    let learners = request.learners_per_cohort.unwrap_or(DEFAULT_LEARNERS);
+    let days = request.days.unwrap_or(DEFAULT_DAYS);
+    if !(1..=MAX_LEARNERS).contains(&learners) {
+        return Err(SimulationError::Invalid(format!(
+            "learnersPerCohort 需在 1-{MAX_LEARNERS} 之间"
+        )));
+    }
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(SimulationError::Invalid(format!(
+            "days 需在 1-{MAX_DAYS} 之间"
+        )));
+    }
+    if let Some(spec) = request.interference {
+        if !(0.0..=1.0).contains(&spec.pair_rate) || !(0.0..=1.0).contains(&spec.penalty) {
+            return Err(SimulationError::Invalid(
+                "interference.pairRate 与 interference.penalty 需在 0-1 之间".into(),
+            ));
+        }
+    }
+    let standard: Vec<CohortSpec> = CohortSpec::standard(learners, days)
+        .into_iter()
+        .map(|cohort| CohortSpec {
+            interference: request.interference,
+            ..cohort
+        })
+        .collect();
+    let Some(names) = &request.cohorts else {
+        return Ok(standard);
+    };
+    let mut selected = Vec::new();
+    for name in names {
+        match standard.iter().find(|c| &c.name == name) {
+            Some(cohort) => selected.push(cohort.clone()),
+            None => {
+                return Err(SimulationError::Invalid(format!("未知的模拟队列: {name}")));
+            }
+        }
+    }
+    if selected.is_empty() {
+        return Err(SimulationError::Invalid("至少需要一个模拟队列".into()));
+    }
+    Ok(selected)
+}
+
+/// Validates the request, records a queued job and starts it in the background.
+pub async fn create_job(
+    proxy: Arc<DatabaseProxy>,
+    created_by: &str,
+    request: SimulationRequest,
+) -> Result<SimulationJob, SimulationError> {
+    let cohorts = select_cohorts(&request)?;
+    let baseline = load_baseline(proxy.as_ref()).await?;
+    let proposed = merge_config(&baseline, &request.config);
+    proposed.validate().map_err(SimulationError::Invalid)?;
+    let seed = request.seed.unwrap_or_else(rand::random);
+
+    let id = Uuid::new_v4().to_string();
+    // Each cohort runs once per config.
+    let total_units = (cohorts.len() * 2) as i32;
+    let request_json = serde_json::to_value(&request).unwrap_or(serde_json::Value::Null);
+    sqlx::query(
+        r#"INSERT INTO "simulation_jobs" ("id","status","totalUnits","request","createdBy","requestId")
+           VALUES ($1,'queued',$2,$3,$4,$5)"#,
+    )
+    .bind(&id)
+    .bind(total_units)
+    .bind(&request_json)
+    .bind(created_by)
+    .bind(request_id::current())
+    .execute(proxy.pool())
+    .await?;
+
+    let job_id = id.clone();
+    let job_proxy = Arc::clone(&proxy);
+    request_id::spawn(async move {
+        if let Err(e) = run_job(&job_proxy, &job_id, baseline, proposed, cohorts, seed).await {
+            tracing::warn!(job_id = %job_id, error = %e, "simulation job failed");
+            let _ = mark_failed(&job_proxy, &job_id, &e.to_string()).await;
+        }
+    });
+
+    get_job(proxy.as_ref(), &id)
+        .await?
+        .ok_or_else(|| SimulationError::Invalid("任务创建失败".into()))
+}
+
+async fn run_job(
+    proxy: &DatabaseProxy,
+    id: &str,
+    baseline: SchedulerConfig,
+    proposed: SchedulerConfig,
+    cohorts: Vec<CohortSpec>,
+    seed: u64,
+) -> Result<(), SimulationError> {
+    let _permit = RUNNING_JOBS
+        .acquire()
+        .await
+        .map_err(|e| SimulationError::Invalid(e.to_string()))?;
+    sqlx::query(
+        r#"UPDATE "simulation_jobs" SET "status" = 'running', "updatedAt" = NOW() WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .execute(proxy.pool())
+    .await?;
+
+    let mut completed = 0;
+    let mut comparisons = Vec::with_capacity(cohorts.len());
+    for cohort in cohorts {
+        let mut metrics: Vec<SimMetrics> = Vec::with_capacity(2);
+        for config in [&baseline, &proposed] {
+            let config = config.clone();
+            let spec = cohort.clone();
+            let result = tokio::task::spawn_blocking(move || run_cohort(&config, &spec, seed))
+                .await
+                .map_err(|e| SimulationError::Invalid(format!("模拟执行失败: {e}")))?;
+            metrics.push(result);
+            completed += 1;
+            sqlx::query(
+                r#"UPDATE "simulation_jobs" SET "completedUnits" = $2, "updatedAt" = NOW() WHERE "id" = $1"#,
+            )
+            .bind(id)
+            .bind(completed)
+            .execute(proxy.pool())
+            .await?;
+        }
+        let proposed_metrics = metrics.pop().unwrap_or_default();
+        let baseline_metrics = metrics.pop().unwrap_or_default();
+        comparisons.push(compare_metrics(
+            &cohort.name,
+            baseline_metrics,
+            proposed_metrics,
+        ));
+    }
+
+    let result = SimulationResult {
+        seed,
+        summary: summarize(&comparisons),
+        baseline_config: baseline,
+        proposed_config: proposed,
+        cohorts: comparisons,
+    };
+    sqlx::query(
+        r#"UPDATE "simulation_jobs"
+           SET "status" = 'completed', "result" = $2, "updatedAt" = NOW(), "completedAt" = NOW()
+           WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(serde_json::to_value(&result).unwrap_or(serde_json::Value::Null))
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+fn summarize(comparisons: &[SimComparison]) -> SimulationSummary {
+    let n = comparisons.len().max(1) as f64;
+    SimulationSummary {
+        retention_delta: comparisons.iter().map(|c| c.retention_delta).sum::<f64>() / n,
+        review_load_delta: comparisons.iter().map(|c| c.review_load_delta).sum::<f64>() / n,
+    }
+}
+
+async fn mark_failed(proxy: &DatabaseProxy, id: &str, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "simulation_jobs"
+           SET "status" = 'failed', "error" = $2, "updatedAt" = NOW(), "completedAt" = NOW()
+           WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(error)
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+/// Jobs run in this process only, so a job still queued or running at
+/// startup was cut off by a restart; it is marked failed so pollers stop
+/// waiting on it.
+pub async fn fail_interrupted_jobs(proxy: &DatabaseProxy) {
+    match sqlx::query(
+        r#"UPDATE "simulation_jobs"
+           SET "status" = 'failed', "error" = '服务重启，任务已中断', "updatedAt" = NOW(), "completedAt" = NOW()
+           WHERE "status" IN ('queued', 'running')"#,
+    )
+    .execute(proxy.pool())
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            tracing::info!(
+                count = result.rows_affected(),
+                "marked interrupted simulation jobs failed"
+            );
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "failed to clean up interrupted simulation jobs"),
+    }
+}
+
+pub async fn get_job(
+    proxy: &DatabaseProxy,
+    id: &str,
+) -> Result<Option<SimulationJob>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        r#"SELECT {JOB_COLUMNS} FROM "simulation_jobs" WHERE "id" = $1"#
+    ))
+    .bind(id)
+    .fetch_optional(proxy.pool())
+    .await?;
+    Ok(row.map(map_job_row))
+}
+
+pub async fn list_jobs(
+    proxy: &DatabaseProxy,
+    limit: i64,
+) -> Result<Vec<SimulationJob>, sqlx::Error> {
+    let rows = sqlx::query(&format!(
+        r#"SELECT {JOB_COLUMNS} FROM "simulation_jobs" ORDER BY "createdAt" DESC LIMIT $1"#
+    ))
+    .bind(limit)
+    .fetch_all(proxy.pool())
+    .await?;
+    Ok(rows.into_iter().map(map_job_row).collect())
+}
+
+const JOB_COLUMNS: &str = r#""id","status","completedUnits","totalUnits","request","result","error","createdBy","requestId","createdAt","updatedAt","completedAt""#;
+
+fn format_ts(ts: NaiveDateTime) -> String {
+    ts.and_utc().to_rfc3339()
+}
+
+fn map_job_row(row: sqlx::postgres::PgRow) -> SimulationJob {
+    let completed_units: i32 = row.try_get("completedUnits").unwrap_or(0);
+    let total_units: i32 = row.try_get("totalUnits").unwrap_or(0);
+    SimulationJob {
+        id: row.try_get("id").unwrap_or_default(),
+        status: row.try_get("status").unwrap_or_default(),
+        progress: if total_units > 0 {
+            f64::from(completed_units) / f64::from(total_units)
+        } else {
+            0.0
+        },
+        completed_units,
+        total_units,
+        request: row.try_get("request").unwrap_or(serde_json::Value::Null),
+        result: row.try_get("result").ok().flatten(),
+        error: row.try_get("error").ok().flatten(),
+        created_by: row.try_get("createdBy").ok().flatten(),
+        request_id: row.try_get("requestId").ok().flatten(),
+        created_at: row
+            .try_get::<NaiveDateTime, _>("createdAt")
+            .map(format_ts)
+            .unwrap_or_else(|_| Utc::now().to_rfc3339()),
+        updated_at: row
+            .try_get::<NaiveDateTime, _>("updatedAt")
+            .map(format_ts)
+            .unwrap_or_else(|_| Utc::now().to_rfc3339()),
+        completed_at: row
+            .try_get::<Option<NaiveDateTime>, _>("completedAt")
+            .ok()
+            .flatten()
+            .map(format_ts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(cohorts: Option<Vec<&str>>) -> SimulationRequest {
+        SimulationRequest {
+            config: ProposedConfig::default(),
+            cohorts: cohorts.map(|c| c.into_iter().map(String::from).collect()),
+            learners_per_cohort: Some(10),
+            days: Some(20),
+            seed: Some(1),
+            interference: None,
+        }
+    }
+
+    #[test]
+    fn merge_keeps_unspecified_fields() {
+        let baseline = SchedulerConfig {
+            review_intervals: vec![1, 3, 7],
+            new_word_ratio: 0.3,
+            mastery_streak: 5,
+            interference_penalty: 0.0,
+        };
+        let merged = merge_config(
+            &baseline,
+            &ProposedConfig {
+                review_intervals: Some(vec![1, 2, 4]),
+                ..Default::default()
+            },
+        );
+        assert_eq!(merged.review_intervals, vec![1, 2, 4]);
+        assert_eq!(merged.new_word_ratio, 0.3);
+        assert_eq!(merged.mastery_streak, 5);
+    }
+
+    #[test]
+    fn selects_named_cohorts() {
+        assert_eq!(select_cohorts(&request(None)).unwrap().len(), 3);
+        let picked = select_cohorts(&request(Some(vec!["casual"]))).unwrap();
+        assert_eq!(picked[0].name, "casual");
+        assert!(select_cohorts(&request(Some(vec!["nope"]))).is_err());
+        assert!(select_cohorts(&request(Some(vec![]))).is_err());
+
+        let mut too_long = request(None);
+        too_long.days = Some(MAX_DAYS + 1);
+        assert!(select_cohorts(&too_long).is_err());
+
+        let mut paired = request(Some(vec!["regular"]));
+        paired.interference = Some(InterferenceSpec {
+            pair_rate: 0.5,
+            penalty: 0.4,
+        });
+        let picked = select_cohorts(&paired).unwrap();
+        assert_eq!(picked[0].interference, paired.interference);
+        paired.interference = Some(InterferenceSpec {
+            pair_rate: 1.5,
+            penalty: 0.4,
+        });
+        assert!(select_cohorts(&paired).is_err());
+    }
+
+    #[test]
+    fn summary_averages_cohorts() {
+        let cmp = |r, l| SimComparison {
+            cohort: "c".into(),
+            baseline: SimMetrics::default(),
+            proposed: SimMetrics::default(),
+            retention_delta: r,
+            review_load_delta: l,
+        };
+        let summary = summarize(&[cmp(2.0, 0.1), cmp(4.0, 0.3)]);
+        assert!((summary.retention_delta - 3.0).abs() < 1e-9);
+        assert!((summary.review_load_delta - 0.2).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,359 @@
+//! Incrementally maintained statistics rollups.
+//!
+//! `user_daily_stats` holds one row per user and UTC day with answer counts
+//! and time totals. A refresh recomputes only the (user, day) pairs touched
+//! by answer records ingested since the last watermark, so the statistics
+//! endpoints can read a handful of daily rows instead of scanning every
+//! answer record on each request.
+
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use tokio::sync::Mutex;
+
+use crate::auth::format_naive_datetime_iso_millis;
+
+pub const USER_DAILY_STATS: &str = "user_daily_stats";
+
+/// Records are stamped with their transaction's start time, so one that
+/// commits after a refresh can carry an `ingestedAt` older than the
+/// watermark. Each refresh re-reads this much history to pick those up.
+const WATERMARK_OVERLAP_SECS: i64 = 300;
+/// Rollups not refreshed within this window are reported as stale.
+const STALE_AFTER_SECS: i64 = 600;
+/// Days covered by the daily accuracy series.
+const DAILY_ACCURACY_DAYS: i64 = 14;
+
+static REFRESH_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsSource {
+    Rollup,
+    Live,
+}
+
+/// How current the figures in a statistics response are.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsFreshness {
+    pub source: StatsSource,
+    /// Latest ingestion time covered by the rollup.
+    pub watermark: Option<String>,
+    pub last_refreshed_at: Option<String>,
+    pub stale: bool,
+}
+
+impl StatsFreshness {
+    pub fn live() -> Self {
+        Self {
+            source: StatsSource::Live,
+            watermark: None,
+            last_refreshed_at: None,
+            stale: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watermark {
+    pub watermark: NaiveDateTime,
+    pub last_run_at: NaiveDateTime,
+}
+
+impl Watermark {
+    pub fn freshness(&self, now: NaiveDateTime) -> StatsFreshness {
+        StatsFreshness {
+            source: StatsSource::Rollup,
+            watermark: Some(format_naive_datetime_iso_millis(self.watermark)),
+            last_refreshed_at: Some(format_naive_datetime_iso_millis(self.last_run_at)),
+            stale: now - self.last_run_at > Duration::seconds(STALE_AFTER_SECS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyStat {
+    pub date: NaiveDate,
+    pub answer_count: i64,
+    pub correct_count: i64,
+    pub total_response_time_ms: i64,
+    pub total_dwell_time_ms: i64,
+}
+
+/// Per-user figures derived from daily rollup rows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserSummary {
+    pub total_records: i64,
+    pub correct_records: i64,
+    pub study_dates: BTreeSet<NaiveDate>,
+    /// `(date, accuracy)` for active days in the trailing window, oldest first.
+    pub daily_accuracy: Vec<(NaiveDate, f64)>,
+    /// Answer counts indexed by weekday, Sunday first.
+    pub weekday_heat: Vec<i32>,
+}
+
+impl UserSummary {
+    pub fn correct_rate(&self) -> f64 {
+        if self.total_records > 0 {
+            self.correct_records as f64 / self.total_records as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+pub fn summarize(rows: &[DailyStat], today: NaiveDate) -> UserSummary {
+    let accuracy_from = today - Duration::days(DAILY_ACCURACY_DAYS);
+    let mut summary = UserSummary {
+        weekday_heat: vec![0; 7],
+        ..Default::default()
+    };
+    for row in rows.iter().filter(|row| row.answer_count > 0) {
+        summary.total_records += row.answer_count;
+        summary.correct_records += row.correct_count;
+        summary.study_dates.insert(row.date);
+        summary.weekday_heat[row.date.weekday().num_days_from_sunday() as usize] +=
+            row.answer_count as i32;
+        if row.date >= accuracy_from {
+            let accuracy = row.correct_count as f64 / row.answer_count as f64;
+            summary.daily_accuracy.push((row.date, accuracy));
+        }
+    }
+    summary.daily_accuracy.sort_by_key(|(date, _)| *date);
+    summary
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshReport {
+    pub skipped: bool,
+    pub rows_upserted: u64,
+    pub watermark: Option<NaiveDateTime>,
+}
+
+pub async fn load_watermark(pool: &PgPool, name: &str) -> Result<Option<Watermark>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT "watermark", "lastRunAt" FROM "stats_refresh_watermarks" WHERE "name" = $1"#,
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| Watermark {
+        watermark: row.get("watermark"),
+        last_run_at: row.get("lastRunAt"),
+    }))
+}
+
+/// Brings `user_daily_stats` up to date. The first run (no watermark yet)
+/// rebuilds every row; later runs only touch pairs with new records.
+pub async fn refresh_user_daily_stats(pool: &PgPool) -> Result<RefreshReport, sqlx::Error> {
+    let Ok(_guard) = REFRESH_LOCK.try_lock() else {
+        return Ok(RefreshReport {
+            skipped: true,
+            ..Default::default()
+        });
+    };
+
+    let previous = load_watermark(pool, USER_DAILY_STATS).await?;
+    let since = previous.map(|w| w.watermark - Duration::seconds(WATERMARK_OVERLAP_SECS));
+
+    let mut tx = pool.begin().await?;
+    let upto: Option<NaiveDateTime> =
+        sqlx::query_scalar(r#"SELECT MAX("ingestedAt") FROM "answer_records""#)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    let mut rows_upserted = 0;
+    if let Some(upto) = upto {
+        rows_upserted = sqlx::query(
+            r#"
+            WITH "touched" AS (
+                SELECT DISTINCT "userId", DATE("timestamp") AS "date"
+                FROM "answer_records"
+                WHERE ($1::timestamp IS NULL OR "ingestedAt" > $1) AND "ingestedAt" <= $2
+            )
+            INSERT INTO "user_daily_stats"
+                ("userId", "date", "answerCount", "correctCount",
+                 "totalResponseTimeMs", "totalDwellTimeMs", "updatedAt")
+            SELECT
+                ar."userId",
+                t."date",
+                COUNT(*)::int,
+                COUNT(*) FILTER (WHERE ar."isCorrect")::int,
+                COALESCE(SUM(ar."responseTime"), 0)::bigint,
+                COALESCE(SUM(ar."dwellTime"), 0)::bigint,
+                NOW()
+            FROM "touched" t
+            JOIN "answer_records" ar
+              ON ar."userId" = t."userId"
+             AND ar."timestamp" >= t."date"
+             AND ar."timestamp" < t."date" + 1
+            GROUP BY ar."userId", t."date"
+            ON CONFLICT ("userId", "date") DO UPDATE SET
+                "answerCount" = EXCLUDED."answerCount",
+                "correctCount" = EXCLUDED."correctCount",
+                "totalResponseTimeMs" = EXCLUDED."totalResponseTimeMs",
+                "totalDwellTimeMs" = EXCLUDED."totalDwellTimeMs",
+                "updatedAt" = EXCLUDED."updatedAt"
+            "#,
+        )
+        .bind(since)
+        .bind(upto)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    // Never move the watermark backwards, and start it at "now" on an empty
+    // table so the rollup is marked as initialised.
+    let watermark = match (upto, previous) {
+        (Some(upto), Some(prev)) => upto.max(prev.watermark),
+        (Some(upto), None) => upto,
+        (None, Some(prev)) => prev.watermark,
+        (None, None) => Utc::now().naive_utc(),
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO "stats_refresh_watermarks" ("name", "watermark", "lastRunAt", "rowsProcessed")
+        VALUES ($1, $2, NOW(), $3)
+        ON CONFLICT ("name") DO UPDATE SET
+            "watermark" = EXCLUDED."watermark",
+            "lastRunAt" = EXCLUDED."lastRunAt",
+            "rowsProcessed" = "stats_refresh_watermarks"."rowsProcessed" + EXCLUDED."rowsProcessed"
+        "#,
+    )
+    .bind(USER_DAILY_STATS)
+    .bind(watermark)
+    .bind(rows_upserted as i64)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(RefreshReport {
+        skipped: false,
+        rows_upserted,
+        watermark: Some(watermark),
+    })
+}
+
+pub async fn list_user_daily_stats(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Vec<DailyStat>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "date", "answerCount", "correctCount", "totalResponseTimeMs", "totalDwellTimeMs"
+        FROM "user_daily_stats"
+        WHERE "userId" = $1
+        ORDER BY "date" ASC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| DailyStat {
+            date: row.get("date"),
+            answer_count: i64::from(row.get::<i32, _>("answerCount")),
+            correct_count: i64::from(row.get::<i32, _>("correctCount")),
+            total_response_time_ms: row.get("totalResponseTimeMs"),
+            total_dwell_time_ms: row.get("totalDwellTimeMs"),
+        })
+        .collect())
+}
+
+/// System-wide totals read from the rollup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemTotals {
+    pub total_records: i64,
+    pub active_users: i64,
+}
+
+/// `active_users` counts users with answers on any of the last `active_days`
+/// UTC days, today included.
+pub async fn system_totals(pool: &PgPool, active_days: i64) -> Result<SystemTotals, sqlx::Error> {
+    let active_from = Utc::now().date_naive() - Duration::days(active_days);
+    let row = sqlx::query(
+        r#"
+        SELECT
+            COALESCE(SUM("answerCount"), 0)::bigint AS "totalRecords",
+            COUNT(DISTINCT "userId") FILTER (WHERE "date" >= $1) AS "activeUsers"
+        FROM "user_daily_stats"
+        "#,
+    )
+    .bind(active_from)
+    .fetch_one(pool)
+    .await?;
+    Ok(SystemTotals {
+        total_records: row.try_get("totalRecords").unwrap_or(0),
+        active_users: row.try_get("activeUsers").unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn stat(date: &str, answers: i64, correct: i64) -> DailyStat {
+        DailyStat {
+            date: day(date),
+            answer_count: answers,
+            correct_count: correct,
+            total_response_time_ms: 0,
+            total_dwell_time_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_summarize_totals_and_heat() {
+        // 2026-10-11 is a Sunday.
+        let rows = vec![
+            stat("2026-10-11", 10, 5),
+            stat("2026-10-12", 4, 4),
+            stat("2026-10-18", 6, 3),
+            stat("2026-10-13", 0, 0),
+        ];
+        let summary = summarize(&rows, day("2026-10-18"));
+        assert_eq!(summary.total_records, 20);
+        assert_eq!(summary.correct_records, 12);
+        assert_eq!(summary.study_dates.len(), 3);
+        assert_eq!(summary.weekday_heat, vec![16, 4, 0, 0, 0, 0, 0]);
+        assert!((summary.correct_rate() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_summarize_accuracy_window() {
+        let rows = vec![
+            stat("2026-10-20", 4, 2),
+            stat("2026-09-01", 10, 10),
+            stat("2026-10-06", 2, 2),
+        ];
+        let summary = summarize(&rows, day("2026-10-20"));
+        assert_eq!(
+            summary.daily_accuracy,
+            vec![(day("2026-10-06"), 1.0), (day("2026-10-20"), 0.5)]
+        );
+        assert_eq!(summarize(&[], day("2026-10-20")).correct_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_freshness_staleness() {
+        let at = day("2026-10-17").and_hms_opt(12, 0, 0).unwrap();
+        let wm = Watermark {
+            watermark: at,
+            last_run_at: at,
+        };
+        assert!(!wm.freshness(at + Duration::minutes(5)).stale);
+        let stale = wm.freshness(at + Duration::minutes(30));
+        assert!(stale.stale);
+        assert_eq!(stale.source, StatsSource::Rollup);
+    }
+}
@@ -1,14 +1,17 @@
 use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use danci_algo::{mastery_progress, MasteryProgress};
 use serde::Serialize;
 use sqlx::{QueryBuilder, Row};
 
 use crate::amas::types::StrategyParams as AmasStrategyParams;
 use crate::amas::AMASEngine;
 use crate::db::DatabaseProxy;
-use crate::services::amas::{compute_new_word_difficulty, map_difficulty_level, StrategyParams};
+use crate::services::amas::{map_difficulty_level, StrategyParams};
+use crate::services::language_params::DifficultyScorer;
 use crate::services::mastery_learning::load_user_strategy;
+use crate::services::wordbook_licenses;
 
 fn convert_amas_strategy(s: AmasStrategyParams) -> StrategyParams {
     StrategyParams {
@@ -65,6 +68,8 @@ pub struct StudyProgress {
     pub total_studied: i64,
     pub correct_rate: i64,
     pub weekly_trend: Vec<i64>,
+    /// Expected mastery of the selected books, accounting for forgetting.
+    pub mastery: MasteryProgress,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -335,7 +340,11 @@ pub async fn get_today_words(
             candidate_count,
         )
         .await?;
-        choose_new_words(candidates, range, actual_new)
+        let candidate_ids: Vec<String> = candidates.iter().map(|w| w.id.clone()).collect();
+        let scorer = DifficultyScorer::load(proxy.pool(), &candidate_ids)
+            .await
+            .unwrap_or_default();
+        choose_new_words(candidates, &scorer, range, actual_new)
     };
 
     let mut words: Vec<StudyWord> = Vec::new();
@@ -376,6 +385,7 @@ pub async fn get_study_progress(
         total_studied: 0,
         correct_rate: 0,
         weekly_trend: vec![0, 0, 0, 0, 0, 0, 0],
+        mastery: mastery_progress(Vec::new(), 0.0, None),
     };
 
     if config.selected_word_book_ids.is_empty() {
@@ -392,6 +402,8 @@ pub async fn get_study_progress(
         compute_progress_counts(proxy, user_id, &accessible_ids).await?;
 
     let weekly_trend = compute_weekly_trend(proxy, user_id, &accessible_ids).await?;
+    let daily_pace = weekly_trend.iter().sum::<i64>() as f64 / weekly_trend.len() as f64;
+    let recalls = select_recall_probabilities(proxy, user_id, &accessible_ids).await?;
 
     Ok(StudyProgress {
         today_studied,
@@ -399,6 +411,7 @@ pub async fn get_study_progress(
         total_studied,
         correct_rate,
         weekly_trend,
+        mastery: mastery_progress(recalls, daily_pace, None),
     })
 }
 
@@ -574,9 +587,8 @@ async fn select_accessible_word_book_ids(
         separated.push_unseparated(")");
     }
 
-    qb.push(" AND (\"type\"::text = 'SYSTEM' OR (\"type\"::text = 'USER' AND \"userId\" = ");
-    qb.push_bind(user_id);
-    qb.push("))");
+    qb.push(" AND ");
+    wordbook_licenses::push_accessible_wordbook(&mut qb, r#""word_books"."#, user_id);
 
     let rows = qb.build().fetch_all(pool).await?;
     Ok(rows
@@ -752,6 +764,7 @@ async fn select_candidate_new_words(
 
 fn choose_new_words(
     mut candidates: Vec<StudyWordBase>,
+    scorer: &DifficultyScorer,
     range: crate::services::amas::DifficultyRange,
     target: usize,
 ) -> Vec<StudyWordBase> {
@@ -762,7 +775,7 @@ fn choose_new_words(
     let with_difficulty: Vec<(StudyWordBase, f64)> = candidates
         .drain(..)
         .map(|w| {
-            let difficulty = compute_new_word_difficulty(&w.spelling, w.meanings.len());
+            let difficulty = scorer.score(&w.id, &w.spelling, w.meanings.len());
             (w, difficulty)
         })
         .collect();
@@ -906,6 +919,50 @@ async fn compute_progress_counts(
     Ok((today_studied, total_studied, correct_rate))
 }
 
+/// Current recall probability of every word in the books, 2^(−Δt / halfLife)
+/// with Δt in days since the last review; words never reviewed count as 0.
+async fn select_recall_probabilities(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    accessible_word_book_ids: &[String],
+) -> Result<Vec<f64>, sqlx::Error> {
+    let pool = proxy.pool();
+    let mut qb = QueryBuilder::<sqlx::Postgres>::new(
+        r#"
+            SELECT wls."halfLife" as "halfLife", wls."lastReviewDate" as "lastReviewDate"
+            FROM "words" w
+            LEFT JOIN "word_learning_states" wls
+              ON wls."wordId" = w."id" AND wls."userId" = 
+            "#,
+    );
+    qb.push_bind(user_id);
+    qb.push(" WHERE w.\"wordBookId\" IN (");
+    {
+        let mut sep = qb.separated(", ");
+        for id in accessible_word_book_ids {
+            sep.push_bind(id);
+        }
+        sep.push_unseparated(")");
+    }
+    let rows = qb.build().fetch_all(pool).await?;
+
+    let now = Utc::now().naive_utc();
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let half_life: Option<f64> = row.try_get("halfLife").ok().flatten();
+            let last_review: Option<NaiveDateTime> = row.try_get("lastReviewDate").ok().flatten();
+            match (half_life, last_review) {
+                (Some(h), Some(at)) if h > 0.0 => {
+                    let days = (now - at).num_seconds().max(0) as f64 / 86_400.0;
+                    (-days / h).exp2()
+                }
+                _ => 0.0,
+            }
+        })
+        .collect())
+}
+
 async fn compute_weekly_trend(
     proxy: &DatabaseProxy,
     user_id: &str,
@@ -0,0 +1,300 @@
+//! Adaptive per-question time limits.
+//!
+//! The expected answer time for a word follows the ACT-R retrieval latency
+//! law, `T = base + F·e^(-A)`, with the activation `A` recovered from the
+//! word's MSMT recall probability. That prediction is then scaled to the
+//! user: the median of their recent response times sets the speed, and the
+//! p90/p50 spread sets how much slack the recommended limit gets. The hard
+//! cap is the point after which the client should time the question out.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::amas::memory::{MsmtModel, ReviewEvent};
+
+/// Fixed encoding/reading/motor time on top of retrieval.
+const BASE_LATENCY_MS: f64 = 1500.0;
+/// ACT-R latency factor `F`.
+const LATENCY_FACTOR_MS: f64 = 1200.0;
+/// Logistic noise `s` linking activation to recall probability (threshold 0).
+const ACTIVATION_NOISE: f64 = 0.4;
+/// Recall probability assumed for words without history.
+const UNSEEN_RECALL: f64 = 0.3;
+/// Population median the personal speed factor is measured against.
+const REFERENCE_MEDIAN_MS: f64 = 3000.0;
+/// Below this many samples the user's percentiles are not trusted.
+const MIN_PROFILE_SAMPLES: i64 = 20;
+const PROFILE_WINDOW: i64 = 200;
+/// Response times above this are treated as abandoned questions.
+const MAX_SAMPLE_MS: i64 = 120_000;
+const WORD_HISTORY_LIMIT: i64 = 20;
+const DEFAULT_SLACK: f64 = 2.0;
+const MIN_LIMIT_MS: f64 = 3000.0;
+const MAX_LIMIT_MS: f64 = 30_000.0;
+const MAX_HARD_CAP_MS: f64 = 60_000.0;
+
+/// The user's recent response-time distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseTimeProfile {
+    pub samples: i64,
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+}
+
+impl ResponseTimeProfile {
+    pub fn empty() -> Self {
+        Self {
+            samples: 0,
+            p50_ms: None,
+            p90_ms: None,
+            p95_ms: None,
+        }
+    }
+
+    fn trusted(&self) -> Option<(f64, f64, f64)> {
+        if self.samples < MIN_PROFILE_SAMPLES {
+            return None;
+        }
+        match (self.p50_ms, self.p90_ms, self.p95_ms) {
+            (Some(p50), Some(p90), Some(p95)) if p50 > 0.0 => Some((p50, p90, p95)),
+            _ => None,
+        }
+    }
+
+    /// Multiplier applied to model predictions for this user.
+    pub fn speed_factor(&self) -> f64 {
+        self.trusted()
+            .map(|(p50, _, _)| (p50 / REFERENCE_MEDIAN_MS).clamp(0.5, 3.0))
+            .unwrap_or(1.0)
+    }
+
+    /// Headroom between the expected and the recommended time.
+    pub fn slack(&self) -> f64 {
+        self.trusted()
+            .map(|(p50, p90, _)| (p90 / p50).clamp(1.5, 3.0))
+            .unwrap_or(DEFAULT_SLACK)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeLimit {
+    pub predicted_latency_ms: i64,
+    pub recommended_ms: i64,
+    pub hard_cap_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordTimeLimit {
+    pub word_id: String,
+    pub recall_probability: f64,
+    #[serde(flatten)]
+    pub limit: TimeLimit,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeLimitRecommendation {
+    pub profile: ResponseTimeProfile,
+    pub items: Vec<WordTimeLimit>,
+}
+
+/// ACT-R retrieval latency for a given recall probability.
+pub fn predicted_latency_ms(recall_probability: f64) -> f64 {
+    let p = recall_probability.clamp(0.02, 0.98);
+    let activation = ACTIVATION_NOISE * (p / (1.0 - p)).ln();
+    BASE_LATENCY_MS + LATENCY_FACTOR_MS * (-activation).exp()
+}
+
+pub fn recommend(recall_probability: f64, profile: &ResponseTimeProfile) -> TimeLimit {
+    let predicted = predicted_latency_ms(recall_probability) * profile.speed_factor();
+    let recommended = (predicted * profile.slack()).clamp(MIN_LIMIT_MS, MAX_LIMIT_MS);
+    let p95 = profile.trusted().map(|(_, _, p95)| p95).unwrap_or(0.0);
+    let hard_cap = (recommended * 2.0)
+        .max(p95)
+        .clamp(recommended, MAX_HARD_CAP_MS);
+    TimeLimit {
+        predicted_latency_ms: predicted.round() as i64,
+        recommended_ms: recommended.round() as i64,
+        hard_cap_ms: hard_cap.round() as i64,
+    }
+}
+
+fn recall_from_history(history: &[(NaiveDateTime, bool)], now: NaiveDateTime) -> f64 {
+    if history.is_empty() {
+        return UNSEEN_RECALL;
+    }
+    let events: Vec<ReviewEvent> = history
+        .iter()
+        .map(|(at, is_correct)| ReviewEvent {
+            timestamp_hours: -((now - *at).num_seconds().max(0) as f64 / 3600.0),
+            is_correct: *is_correct,
+            credit: None,
+        })
+        .collect();
+    MsmtModel::predict_recall(&events, 0.0)
+}
+
+pub async fn load_profile(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<ResponseTimeProfile, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            COUNT(*) AS "samples",
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY "responseTime") AS "p50",
+            percentile_cont(0.9) WITHIN GROUP (ORDER BY "responseTime") AS "p90",
+            percentile_cont(0.95) WITHIN GROUP (ORDER BY "responseTime") AS "p95"
+        FROM (
+            SELECT "responseTime"
+            FROM "answer_records"
+            WHERE "userId" = $1 AND "responseTime" > 0 AND "responseTime" <= $2
+            ORDER BY "timestamp" DESC
+            LIMIT $3
+        ) recent
+        "#,
+    )
+    .bind(user_id)
+    .bind(MAX_SAMPLE_MS)
+    .bind(PROFILE_WINDOW)
+    .fetch_one(pool)
+    .await?;
+    Ok(ResponseTimeProfile {
+        samples: row.try_get("samples").unwrap_or(0),
+        p50_ms: row.try_get("p50").ok().flatten(),
+        p90_ms: row.try_get("p90").ok().flatten(),
+        p95_ms: row.try_get("p95").ok().flatten(),
+    })
+}
+
+async fn load_word_histories(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<HashMap<String, Vec<(NaiveDateTime, bool)>>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT "wordId", "timestamp", "isCorrect"
+        FROM (
+            SELECT "wordId", "timestamp", "isCorrect",
+                   ROW_NUMBER() OVER (PARTITION BY "wordId" ORDER BY "timestamp" DESC) AS "rn"
+            FROM "answer_records"
+            WHERE "userId" = $1 AND "wordId" = ANY($2)
+        ) ranked
+        WHERE "rn" <= $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_ids)
+    .bind(WORD_HISTORY_LIMIT)
+    .fetch_all(pool)
+    .await?;
+
+    let mut histories: HashMap<String, Vec<(NaiveDateTime, bool)>> = HashMap::new();
+    for row in rows {
+        let word_id: String = row.get("wordId");
+        histories
+            .entry(word_id)
+            .or_default()
+            .push((row.get("timestamp"), row.get("isCorrect")));
+    }
+    Ok(histories)
+}
+
+/// Recommended limits for each word, in the order given.
+pub async fn recommend_for_words(
+    pool: &PgPool,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<TimeLimitRecommendation, sqlx::Error> {
+    if word_ids.is_empty() {
+        return Ok(TimeLimitRecommendation {
+            profile: ResponseTimeProfile::empty(),
+            items: Vec::new(),
+        });
+    }
+    let (profile, histories) = tokio::try_join!(
+        load_profile(pool, user_id),
+        load_word_histories(pool, user_id, word_ids),
+    )?;
+    let now = Utc::now().naive_utc();
+    let items = word_ids
+        .iter()
+        .map(|word_id| {
+            let recall = histories
+                .get(word_id)
+                .map(|h| recall_from_history(h, now))
+                .unwrap_or(UNSEEN_RECALL);
+            WordTimeLimit {
+                word_id: word_id.clone(),
+                recall_probability: (recall * 1000.0).round() / 1000.0,
+                limit: recommend(recall, &profile),
+            }
+        })
+        .collect();
+    Ok(TimeLimitRecommendation { profile, items })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(samples: i64, p50: f64, p90: f64, p95: f64) -> ResponseTimeProfile {
+        ResponseTimeProfile {
+            samples,
+            p50_ms: Some(p50),
+            p90_ms: Some(p90),
+            p95_ms: Some(p95),
+        }
+    }
+
+    #[test]
+    fn test_latency_falls_with_recall() {
+        let weak = predicted_latency_ms(0.1);
+        let even = predicted_latency_ms(0.5);
+        let strong = predicted_latency_ms(0.9);
+        assert!(weak > even && even > strong);
+        assert!((even - (BASE_LATENCY_MS + LATENCY_FACTOR_MS)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_slow_readers_get_more_time() {
+        let fast = recommend(0.5, &profile(100, 2000.0, 3500.0, 4000.0));
+        let slow = recommend(0.5, &profile(100, 6000.0, 12000.0, 15000.0));
+        assert!(slow.recommended_ms > fast.recommended_ms);
+        assert!(slow.hard_cap_ms >= slow.recommended_ms);
+    }
+
+    #[test]
+    fn test_thin_profile_uses_defaults() {
+        let thin = recommend(0.5, &profile(5, 9000.0, 20000.0, 30000.0));
+        let none = recommend(0.5, &ResponseTimeProfile::empty());
+        assert_eq!(thin, none);
+        assert_eq!(none.recommended_ms, 5400);
+        assert_eq!(none.hard_cap_ms, 10800);
+    }
+
+    #[test]
+    fn test_limits_are_clamped() {
+        let limit = recommend(0.02, &profile(100, 20000.0, 60000.0, 90000.0));
+        assert_eq!(limit.recommended_ms, MAX_LIMIT_MS as i64);
+        assert_eq!(limit.hard_cap_ms, MAX_HARD_CAP_MS as i64);
+        let quick = recommend(0.98, &profile(100, 800.0, 1000.0, 1100.0));
+        assert_eq!(quick.recommended_ms, MIN_LIMIT_MS as i64);
+    }
+
+    #[test]
+    fn test_unseen_word_recall() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(recall_from_history(&[], now), UNSEEN_RECALL);
+        let recent = recall_from_history(&[(now, true), (now, true)], now);
+        assert!(recent > UNSEEN_RECALL);
+    }
+}
@@ -0,0 +1,784 @@
+//! Day-by-day learning timeline for the journey screen.
+//!
+//! `user_timeline_entries` holds one row per user and *local* day with the
+//! day's answers, new and reviewed words, study minutes, best run of correct
+//! answers, the running streak of study days and the notable events of the
+//! day. A day is rolled up once it has ended in the user's timezone, which
+//! is kept with the user's cursor in `user_timeline_cursors` together with
+//! the last day rolled up. Days are computed by Postgres from the IANA zone
+//! name, so DST transitions are handled there.
+//!
+//! Entries only exist for days with answers. Streaks and "most answers"
+//! records continue from the entries before the rolled-up range, so a
+//! backfill always rebuilds everything from its first day up to the last
+//! closed day.
+
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use tokio::sync::Mutex;
+
+/// Days caught up by a regular rollup; older gaps need a backfill.
+const MAX_CATCHUP_DAYS: i64 = 31;
+pub const MAX_BACKFILL_DAYS: i64 = 366;
+pub const DEFAULT_PAGE_SIZE: i64 = 30;
+pub const MAX_PAGE_SIZE: i64 = 100;
+/// Users rolled up per cursor page.
+const ROLLUP_BATCH: i64 = 500;
+/// Users who studied within this many UTC days get a cursor on the next run.
+const ENROLL_RECENT_DAYS: i64 = 2;
+
+const STREAK_MILESTONES: [i32; 8] = [3, 7, 14, 30, 50, 100, 200, 365];
+const WORDS_MILESTONES: [i64; 7] = [50, 100, 500, 1000, 2000, 5000, 10000];
+/// A day needs at least this many answers, all correct, to count as perfect.
+const PERFECT_DAY_MIN_ANSWERS: i64 = 20;
+
+static ROLLUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, thiserror::Error)]
+pub enum TimelineError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("unknown timezone: {0}")]
+    InvalidTimezone(String),
+    #[error("user not found")]
+    UserNotFound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TimelineEvent {
+    FirstStudyDay,
+    StreakMilestone {
+        days: i32,
+    },
+    /// More answers than on any earlier day.
+    MostAnswers {
+        answers: i64,
+    },
+    PerfectDay {
+        answers: i64,
+    },
+    WordsMilestone {
+        words: i64,
+    },
+    BadgeUnlocked {
+        #[serde(rename = "badgeId")]
+        badge_id: String,
+        name: Option<String>,
+        tier: i32,
+    },
+}
+
+/// Figures of one local day, as aggregated from answer records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayFigures {
+    pub date: NaiveDate,
+    pub answer_count: i64,
+    pub correct_count: i64,
+    pub words_learned: i64,
+    pub words_reviewed: i64,
+    pub study_ms: i64,
+    pub best_correct_streak: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    pub date: NaiveDate,
+    pub answer_count: i64,
+    pub correct_count: i64,
+    pub accuracy: f64,
+    pub words_learned: i64,
+    pub words_reviewed: i64,
+    pub study_minutes: i64,
+    pub best_correct_streak: i64,
+    /// Consecutive study days ending on this day.
+    pub day_streak: i32,
+    pub events: Vec<TimelineEvent>,
+}
+
+/// What the entries before a rolled-up range contribute to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct History {
+    /// Date and streak of the latest earlier entry.
+    pub last: Option<(NaiveDate, i32)>,
+    pub best_answers: i64,
+    pub words_learned: i64,
+    pub active_days: i64,
+}
+
+/// Turns aggregated days (oldest first) into timeline entries. Badges are
+/// attached to their unlock day; one unlocked on a day without answers is
+/// not shown.
+pub fn build_entries(
+    history: History,
+    days: &[DayFigures],
+    badges: &[(NaiveDate, TimelineEvent)],
+) -> Vec<TimelineEntry> {
+    let mut last = history.last;
+    let mut best_answers = history.best_answers;
+    let mut words_learned = history.words_learned;
+    let mut first_day = history.active_days == 0;
+    let mut entries = Vec::with_capacity(days.len());
+
+    for day in days.iter().filter(|day| day.answer_count > 0) {
+        let day_streak = match last {
+            Some((date, streak)) if date + Duration::days(1) == day.date => streak + 1,
+            _ => 1,
+        };
+        let mut events = Vec::new();
+        if first_day {
+            events.push(TimelineEvent::FirstStudyDay);
+            first_day = false;
+        }
+        if STREAK_MILESTONES.contains(&day_streak) {
+            events.push(TimelineEvent::StreakMilestone { days: day_streak });
+        }
+        if best_answers > 0 && day.answer_count > best_answers {
+            events.push(TimelineEvent::MostAnswers {
+                answers: day.answer_count,
+            });
+        }
+        best_answers = best_answers.max(day.answer_count);
+        if day.answer_count >= PERFECT_DAY_MIN_ANSWERS && day.correct_count == day.answer_count {
+            events.push(TimelineEvent::PerfectDay {
+                answers: day.answer_count,
+            });
+        }
+        let words_before = words_learned;
+        words_learned += day.words_learned;
+        events.extend(
+            WORDS_MILESTONES
+                .iter()
+                .filter(|&&m| words_before < m && m <= words_learned)
+                .map(|&words| TimelineEvent::WordsMilestone { words }),
+        );
+        events.extend(
+            badges
+                .iter()
+                .filter(|(date, _)| *date == day.date)
+                .map(|(_, event)| event.clone()),
+        );
+
+        entries.push(TimelineEntry {
+            date: day.date,
+            answer_count: day.answer_count,
+            correct_count: day.correct_count,
+            accuracy: day.correct_count as f64 / day.answer_count as f64,
+            words_learned: day.words_learned,
+            words_reviewed: day.words_reviewed,
+            study_minutes: (day.study_ms + 30_000) / 60_000,
+            best_correct_streak: day.best_correct_streak,
+            day_streak,
+            events,
+        });
+        last = Some((day.date, day_streak));
+    }
+    entries
+}
+
+async fn load_history(
+    pool: &PgPool,
+    user_id: &str,
+    before: NaiveDate,
+) -> Result<History, sqlx::Error> {
+    let last = sqlx::query(
+        r#"SELECT "date", "dayStreak" FROM "user_timeline_entries"
+           WHERE "userId" = $1 AND "date" < $2
+           ORDER BY "date" DESC LIMIT 1"#,
+    )
+    .bind(user_id)
+    .bind(before)
+    .fetch_optional(pool)
+    .await?;
+    let totals = sqlx::query(
+        r#"SELECT COALESCE(MAX("answerCount"), 0)::bigint AS "bestAnswers",
+                  COALESCE(SUM("wordsLearned"), 0)::bigint AS "wordsLearned",
+                  COUNT(*) AS "activeDays"
+           FROM "user_timeline_entries"
+           WHERE "userId" = $1 AND "date" < $2"#,
+    )
+    .bind(user_id)
+    .bind(before)
+    .fetch_one(pool)
+    .await?;
+    Ok(History {
+        last: last.map(|row| (row.get("date"), row.get("dayStreak"))),
+        best_answers: totals.get("bestAnswers"),
+        words_learned: totals.get("wordsLearned"),
+        active_days: totals.get("activeDays"),
+    })
+}
+
+/// Aggregates the user's answers by local day over `[from, to]`. A word is
+/// learned on the local day of its first answer ever and reviewed on every
+/// other day it is answered on.
+async fn load_days(
+    pool: &PgPool,
+    user_id: &str,
+    timezone: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DayFigures>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        WITH "local" AS (
+            SELECT ar."id", ar."wordId", ar."isCorrect", ar."timestamp",
+                   (ar."timestamp" AT TIME ZONE 'UTC' AT TIME ZONE $2)::date AS "day",
+                   COALESCE(ar."dwellTime", ar."responseTime", 0)::bigint AS "ms"
+            FROM "answer_records" ar
+            WHERE ar."userId" = $1
+              AND ar."timestamp" >= ($3::date::timestamp AT TIME ZONE $2) AT TIME ZONE 'UTC'
+              AND ar."timestamp" < (($4::date + 1)::timestamp AT TIME ZONE $2) AT TIME ZONE 'UTC'
+        ),
+        "firsts" AS (
+            SELECT ar."wordId",
+                   (MIN(ar."timestamp") AT TIME ZONE 'UTC' AT TIME ZONE $2)::date AS "firstDay"
+            FROM "answer_records" ar
+            WHERE ar."userId" = $1 AND ar."wordId" IN (SELECT "wordId" FROM "local")
+            GROUP BY ar."wordId"
+        ),
+        "runs" AS (
+            SELECT "day", "isCorrect",
+                   ROW_NUMBER() OVER (PARTITION BY "day" ORDER BY "timestamp", "id")
+                 - ROW_NUMBER() OVER (PARTITION BY "day", "isCorrect" ORDER BY "timestamp", "id")
+                   AS "run"
+            FROM "local"
+        ),
+        "streaks" AS (
+            SELECT "day", MAX("length") AS "best"
+            FROM (
+                SELECT "day", "run", COUNT(*) AS "length"
+                FROM "runs" WHERE "isCorrect"
+                GROUP BY "day", "run"
+            ) r
+            GROUP BY "day"
+        )
+        SELECT
+            l."day",
+            COUNT(*)::bigint AS "answerCount",
+            COUNT(*) FILTER (WHERE l."isCorrect")::bigint AS "correctCount",
+            COUNT(DISTINCT l."wordId") FILTER (WHERE f."firstDay" = l."day")::bigint
+                AS "wordsLearned",
+            COUNT(DISTINCT l."wordId")::bigint AS "wordsAnswered",
+            COALESCE(SUM(l."ms"), 0)::bigint AS "studyMs",
+            COALESCE(MAX(s."best"), 0)::bigint AS "bestCorrectStreak"
+        FROM "local" l
+        JOIN "firsts" f ON f."wordId" = l."wordId"
+        LEFT JOIN "streaks" s ON s."day" = l."day"
+        GROUP BY l."day"
+        ORDER BY l."day"
+        "#,
+    )
+    .bind(user_id)
+    .bind(timezone)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let words_learned: i64 = row.get("wordsLearned");
+            DayFigures {
+                date: row.get("day"),
+                answer_count: row.get("answerCount"),
+                correct_count: row.get("correctCount"),
+                words_learned,
+                words_reviewed: row.get::<i64, _>("wordsAnswered") - words_learned,
+                study_ms: row.get("studyMs"),
+                best_correct_streak: row.get("bestCorrectStreak"),
+            }
+        })
+        .collect())
+}
+
+async fn load_badges(
+    pool: &PgPool,
+    user_id: &str,
+    timezone: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(NaiveDate, TimelineEvent)>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT (ub."unlockedAt" AT TIME ZONE 'UTC' AT TIME ZONE $2)::date AS "day",
+               ub."badgeId", bd."name", COALESCE(ub."tier", 1) AS "tier"
+        FROM "user_badges" ub
+        LEFT JOIN "badge_definitions" bd ON bd."id" = ub."badgeId"
+        WHERE ub."userId" = $1
+          AND ub."unlockedAt" >= ($3::date::timestamp AT TIME ZONE $2) AT TIME ZONE 'UTC'
+          AND ub."unlockedAt" < (($4::date + 1)::timestamp AT TIME ZONE $2) AT TIME ZONE 'UTC'
+        ORDER BY ub."unlockedAt"
+        "#,
+    )
+    .bind(user_id)
+    .bind(timezone)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get("day"),
+                TimelineEvent::BadgeUnlocked {
+                    badge_id: row.get("badgeId"),
+                    name: row.get("name"),
+                    tier: row.get("tier"),
+                },
+            )
+        })
+        .collect())
+}
+
+/// Rebuilds the user's entries for the local days `[from, to]` and moves
+/// the cursor up to `to`. Returns the number of entries written.
+pub async fn roll_up_range(
+    pool: &PgPool,
+    user_id: &str,
+    timezone: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    if from > to {
+        return Ok(0);
+    }
+    let history = load_history(pool, user_id, from).await?;
+    let days = load_days(pool, user_id, timezone, from, to).await?;
+    let badges = load_badges(pool, user_id, timezone, from, to).await?;
+    let entries = build_entries(history, &days, &badges);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"DELETE FROM "user_timeline_entries"
+           WHERE "userId" = $1 AND "date" >= $2 AND "date" <= $3"#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+    for entry in &entries {
+        sqlx::query(
+            r#"
+            INSERT INTO "user_timeline_entries"
+                ("userId", "date", "timezone", "answerCount", "correctCount", "wordsLearned",
+                 "wordsReviewed", "studyMinutes", "bestCorrectStreak", "dayStreak", "events",
+                 "updatedAt")
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW())
+            ON CONFLICT ("userId", "date") DO UPDATE SET
+                "timezone" = EXCLUDED."timezone",
+                "answerCount" = EXCLUDED."answerCount",
+                "correctCount" = EXCLUDED."correctCount",
+                "wordsLearned" = EXCLUDED."wordsLearned",
+                "wordsReviewed" = EXCLUDED."wordsReviewed",
+                "studyMinutes" = EXCLUDED."studyMinutes",
+                "bestCorrectStreak" = EXCLUDED."bestCorrectStreak",
+                "dayStreak" = EXCLUDED."dayStreak",
+                "events" = EXCLUDED."events",
+                "updatedAt" = EXCLUDED."updatedAt"
+            "#,
+        )
+        .bind(user_id)
+        .bind(entry.date)
+        .bind(timezone)
+        .bind(entry.answer_count as i32)
+        .bind(entry.correct_count as i32)
+        .bind(entry.words_learned as i32)
+        .bind(entry.words_reviewed as i32)
+        .bind(entry.study_minutes as i32)
+        .bind(entry.best_correct_streak as i32)
+        .bind(entry.day_streak)
+        .bind(sqlx::types::Json(&entry.events))
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO "user_timeline_cursors" ("userId", "timezone", "rolledThrough", "updatedAt")
+        VALUES ($1, $2, $3, NOW())
+        ON CONFLICT ("userId") DO UPDATE SET
+            "rolledThrough" = GREATEST(
+                COALESCE("user_timeline_cursors"."rolledThrough", EXCLUDED."rolledThrough"),
+                EXCLUDED."rolledThrough"
+            ),
+            "updatedAt" = EXCLUDED."updatedAt"
+        "#,
+    )
+    .bind(user_id)
+    .bind(timezone)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(entries.len() as u64)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollupReport {
+    pub skipped: bool,
+    pub users: u64,
+    pub entries: u64,
+    pub failed: u64,
+}
+
+/// First day of a rollup that resumes after `rolled_through` and ends on
+/// `closed_day`, capped at the catch-up window.
+fn rollup_start(rolled_through: Option<NaiveDate>, closed_day: NaiveDate) -> NaiveDate {
+    let earliest = closed_day - Duration::days(MAX_CATCHUP_DAYS - 1);
+    match rolled_through {
+        Some(day) => (day + Duration::days(1)).max(earliest),
+        None => earliest,
+    }
+}
+
+/// Rolls up every day that has ended in its user's timezone since the
+/// user's cursor. Recently active users without a cursor get one in UTC.
+/// A failing user is logged and skipped.
+pub async fn roll_up_closed_days(pool: &PgPool) -> Result<RollupReport, sqlx::Error> {
+    let Ok(_guard) = ROLLUP_LOCK.try_lock() else {
+        return Ok(RollupReport {
+            skipped: true,
+            ..Default::default()
+        });
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO "user_timeline_cursors" ("userId")
+        SELECT DISTINCT "userId" FROM "user_daily_stats"
+        WHERE "date" >= CURRENT_DATE - $1::int
+        ON CONFLICT ("userId") DO NOTHING
+        "#,
+    )
+    .bind(ENROLL_RECENT_DAYS as i32)
+    .execute(pool)
+    .await?;
+
+    let mut report = RollupReport::default();
+    let mut after = String::new();
+    loop {
+        let rows = sqlx::query(
+            r#"
+            SELECT "userId", "timezone", "rolledThrough", "closedDay"
+            FROM (
+                SELECT "userId", "timezone", "rolledThrough",
+                       (NOW() AT TIME ZONE "timezone")::date - 1 AS "closedDay"
+                FROM "user_timeline_cursors"
+                WHERE "userId" > $1
+            ) c
+            WHERE "rolledThrough" IS NULL OR "rolledThrough" < "closedDay"
+            ORDER BY "userId"
+            LIMIT $2
+            "#,
+        )
+        .bind(&after)
+        .bind(ROLLUP_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else {
+            return Ok(report);
+        };
+        after = last.get("userId");
+        for row in &rows {
+            let user_id: String = row.get("userId");
+            let timezone: String = row.get("timezone");
+            let closed_day: NaiveDate = row.get("closedDay");
+            let from = rollup_start(row.get("rolledThrough"), closed_day);
+            match roll_up_range(pool, &user_id, &timezone, from, closed_day).await {
+                Ok(entries) => {
+                    report.users += 1;
+                    report.entries += entries;
+                }
+                Err(err) => {
+                    report.failed += 1;
+                    tracing::warn!(error = %err, user_id = %user_id, "timeline rollup failed");
+                }
+            }
+        }
+    }
+}
+
+/// Rebuilds the user's last `days` closed days.
+pub async fn backfill_user(pool: &PgPool, user_id: &str, days: i64) -> Result<u64, TimelineError> {
+    let row = sqlx::query(
+        r#"
+        SELECT COALESCE(c."timezone", 'UTC') AS "timezone",
+               (NOW() AT TIME ZONE COALESCE(c."timezone", 'UTC'))::date - 1 AS "closedDay"
+        FROM "users" u
+        LEFT JOIN "user_timeline_cursors" c ON c."userId" = u."id"
+        WHERE u."id" = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(TimelineError::UserNotFound)?;
+    let timezone: String = row.get("timezone");
+    let closed_day: NaiveDate = row.get("closedDay");
+    let from = closed_day - Duration::days(days.clamp(1, MAX_BACKFILL_DAYS) - 1);
+    Ok(roll_up_range(pool, user_id, &timezone, from, closed_day).await?)
+}
+
+/// Backfills every user who studied within the last `days` UTC days.
+pub async fn backfill_all(pool: &PgPool, days: i64) -> Result<RollupReport, sqlx::Error> {
+    let days = days.clamp(1, MAX_BACKFILL_DAYS);
+    let mut report = RollupReport::default();
+    let mut after = String::new();
+    loop {
+        let users: Vec<String> = sqlx::query_scalar(
+            r#"SELECT DISTINCT "userId" FROM "user_daily_stats"
+               WHERE "date" >= CURRENT_DATE - $1::int AND "userId" > $2
+               ORDER BY "userId" LIMIT $3"#,
+        )
+        .bind(days as i32)
+        .bind(&after)
+        .bind(ROLLUP_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = users.last() else {
+            return Ok(report);
+        };
+        after = last.clone();
+        for user_id in &users {
+            match backfill_user(pool, user_id, days).await {
+                Ok(entries) => {
+                    report.users += 1;
+                    report.entries += entries;
+                }
+                Err(err) => {
+                    report.failed += 1;
+                    tracing::warn!(error = %err, user_id = %user_id, "timeline backfill failed");
+                }
+            }
+        }
+    }
+}
+
+/// The user's timeline timezone, `UTC` until one is set.
+pub async fn timezone(pool: &PgPool, user_id: &str) -> Result<String, sqlx::Error> {
+    let timezone: Option<String> =
+        sqlx::query_scalar(r#"SELECT "timezone" FROM "user_timeline_cursors" WHERE "userId" = $1"#)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(timezone.unwrap_or_else(|| "UTC".to_string()))
+}
+
+/// Sets the IANA timezone whose midnights close the user's days. Entries
+/// already rolled up keep the days of the zone they were computed in.
+pub async fn set_timezone(
+    pool: &PgPool,
+    user_id: &str,
+    timezone: &str,
+) -> Result<(), TimelineError> {
+    let known: bool =
+        sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)"#)
+            .bind(timezone)
+            .fetch_one(pool)
+            .await?;
+    if !known {
+        return Err(TimelineError::InvalidTimezone(timezone.to_string()));
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO "user_timeline_cursors" ("userId", "timezone", "updatedAt")
+        VALUES ($1, $2, NOW())
+        ON CONFLICT ("userId") DO UPDATE SET
+            "timezone" = EXCLUDED."timezone",
+            "updatedAt" = EXCLUDED."updatedAt"
+        "#,
+    )
+    .bind(user_id)
+    .bind(timezone)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelinePage {
+    pub timezone: String,
+    /// Newest first.
+    pub entries: Vec<TimelineEntry>,
+    /// Pass as `before` to get the next, older page.
+    pub next_before: Option<NaiveDate>,
+    pub has_more: bool,
+}
+
+/// Entries strictly before `before` (or the newest ones), newest first.
+pub async fn page(
+    pool: &PgPool,
+    user_id: &str,
+    before: Option<NaiveDate>,
+    limit: i64,
+) -> Result<TimelinePage, sqlx::Error> {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let rows = sqlx::query(
+        r#"
+        SELECT "date", "answerCount", "correctCount", "wordsLearned", "wordsReviewed",
+               "studyMinutes", "bestCorrectStreak", "dayStreak", "events"
+        FROM "user_timeline_entries"
+        WHERE "userId" = $1 AND ($2::date IS NULL OR "date" < $2)
+        ORDER BY "date" DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(before)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+    let has_more = rows.len() as i64 > limit;
+    let entries: Vec<TimelineEntry> = rows
+        .into_iter()
+        .take(limit as usize)
+        .map(|row| {
+            let answer_count = i64::from(row.get::<i32, _>("answerCount"));
+            let correct_count = i64::from(row.get::<i32, _>("correctCount"));
+            let events: sqlx::types::Json<Vec<TimelineEvent>> = row
+                .try_get("events")
+                .unwrap_or(sqlx::types::Json(Vec::new()));
+            TimelineEntry {
+                date: row.get("date"),
+                answer_count,
+                correct_count,
+                accuracy: if answer_count > 0 {
+                    correct_count as f64 / answer_count as f64
+                } else {
+                    0.0
+                },
+                words_learned: i64::from(row.get::<i32, _>("wordsLearned")),
+                words_reviewed: i64::from(row.get::<i32, _>("wordsReviewed")),
+                study_minutes: i64::from(row.get::<i32, _>("studyMinutes")),
+                best_correct_streak: i64::from(row.get::<i32, _>("bestCorrectStreak")),
+                day_streak: row.get("dayStreak"),
+                events: events.0,
+            }
+        })
+        .collect();
+    Ok(TimelinePage {
+        timezone: timezone(pool, user_id).await?,
+        next_before: if has_more {
+            entries.last().map(|entry| entry.date)
+        } else {
+            None
+        },
+        has_more,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn figures(date: &str, answers: i64, correct: i64, learned: i64) -> DayFigures {
+        DayFigures {
+            date: day(date),
+            answer_count: answers,
+            correct_count: correct,
+            words_learned: learned,
+            words_reviewed: 0,
+            study_ms: 95_000,
+            best_correct_streak: correct,
+        }
+    }
+
+    #[test]
+    fn test_streak_continues_from_history_and_breaks_on_gap() {
+        let history = History {
+            last: Some((day("2026-10-10"), 6)),
+            best_answers: 100,
+            words_learned: 10,
+            active_days: 6,
+        };
+        let days = [
+            figures("2026-10-11", 10, 5, 0),
+            figures("2026-10-12", 10, 5, 0),
+            figures("2026-10-14", 10, 5, 0),
+        ];
+        let entries = build_entries(history, &days, &[]);
+        let streaks: Vec<i32> = entries.iter().map(|e| e.day_streak).collect();
+        assert_eq!(streaks, vec![7, 8, 1]);
+        assert_eq!(
+            entries[0].events,
+            vec![TimelineEvent::StreakMilestone { days: 7 }]
+        );
+        assert!(entries[1].events.is_empty());
+        assert_eq!(entries[0].study_minutes, 2);
+        assert!((entries[0].accuracy - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_first_day_records_and_milestones() {
+        let days = [
+            figures("2026-10-01", 30, 30, 60),
+            figures("2026-10-02", 40, 20, 50),
+            figures("2026-10-03", 0, 0, 0),
+        ];
+        let badges = [(
+            day("2026-10-02"),
+            TimelineEvent::BadgeUnlocked {
+                badge_id: "b1".to_string(),
+                name: Some("Starter".to_string()),
+                tier: 1,
+            },
+        )];
+        let entries = build_entries(History::default(), &days, &badges);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].events,
+            vec![
+                TimelineEvent::FirstStudyDay,
+                TimelineEvent::PerfectDay { answers: 30 },
+                TimelineEvent::WordsMilestone { words: 50 },
+            ]
+        );
+        assert_eq!(
+            entries[1].events,
+            vec![
+                TimelineEvent::MostAnswers { answers: 40 },
+                TimelineEvent::WordsMilestone { words: 100 },
+                badges[0].1.clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rollup_start_is_capped() {
+        let closed = day("2026-10-16");
+        assert_eq!(
+            rollup_start(Some(day("2026-10-14")), closed),
+            day("2026-10-15")
+        );
+        assert_eq!(rollup_start(None, closed), day("2026-09-16"));
+        assert_eq!(
+            rollup_start(Some(day("2026-01-01")), closed),
+            day("2026-09-16")
+        );
+    }
+
+    #[test]
+    fn test_event_json_shape() {
+        let json = serde_json::to_value(TimelineEvent::BadgeUnlocked {
+            badge_id: "b1".to_string(),
+            name: None,
+            tier: 2,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "badgeUnlocked", "badgeId": "b1", "name": null, "tier": 2})
+        );
+    }
+}
@@ -9,6 +9,7 @@ use crate::amas::modeling::vark::{
     VarkLabels,
 };
 use crate::db::DatabaseProxy;
+use crate::services::wordbook_licenses;
 
 // ========== Types ==========
 
@@ -293,10 +294,14 @@ pub async fn get_password_hash(pool: &PgPool, user_id: &str) -> Result<Option<St
 }
 
 pub async fn get_user_statistics(pool: &PgPool, user_id: &str) -> Result<UserStatistics, String> {
-    let word_books: Vec<String> = sqlx::query_scalar(
-        r#"SELECT "id" FROM "word_books" WHERE ("type"::text = 'SYSTEM') OR (("type"::text = 'USER') AND "userId" = $1)"#,
-    )
-    .bind(user_id).fetch_all(pool).await.unwrap_or_default();
+    let word_books: Vec<String> = sqlx::query_scalar(&format!(
+        r#"SELECT "id" FROM "word_books" WHERE {}"#,
+        wordbook_licenses::accessible_wordbook_sql(r#""word_books"."#, "$1")
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
 
     let total_words = count_words_pg(pool, &word_books).await.unwrap_or(0);
     let total_records: i64 =
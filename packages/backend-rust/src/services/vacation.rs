@@ -0,0 +1,341 @@
+//! Vacation mode (streak freeze).
+//!
+//! A vacation is a date range during which missed days do not break the
+//! study streak. Reviews that fall due before the user returns are not all
+//! dumped on the first day back: each backlog word is assigned a stable
+//! release day within the ramp-up window following the vacation.
+
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+/// Longest single vacation.
+pub const MAX_VACATION_DAYS: i64 = 90;
+pub const DEFAULT_RAMP_UP_DAYS: i32 = 3;
+pub const MAX_RAMP_UP_DAYS: i32 = 14;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VacationError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("{0}")]
+    Invalid(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VacationPeriod {
+    pub id: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub ramp_up_days: i32,
+}
+
+impl VacationPeriod {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && date <= self.end_date
+    }
+
+    /// First day back from vacation.
+    pub fn return_date(&self) -> NaiveDate {
+        self.end_date + Duration::days(1)
+    }
+
+    /// Last day of the ramp-up window.
+    pub fn ramp_up_until(&self) -> NaiveDate {
+        self.end_date + Duration::days(i64::from(self.ramp_up_days.max(1)))
+    }
+
+    pub fn in_ramp_up(&self, date: NaiveDate) -> bool {
+        self.return_date() <= date && date <= self.ramp_up_until()
+    }
+
+    /// Day on which a review that fell due on `due` is released. Only reviews
+    /// due before the return date count as backlog; the rest are untouched.
+    pub fn release_date(&self, word_id: &str, due: NaiveDate) -> NaiveDate {
+        if due >= self.return_date() {
+            return due;
+        }
+        let window = self.ramp_up_days.max(1) as u64;
+        let slot = danci_algo::hash::fnv1a_64(&[self.id.as_bytes(), word_id.as_bytes()]) % window;
+        self.return_date() + Duration::days(slot as i64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VacationStatus {
+    pub on_vacation: bool,
+    pub in_ramp_up: bool,
+    /// Active, upcoming or ramping-up vacation.
+    pub current: Option<VacationPeriod>,
+    pub ramp_up_until: Option<NaiveDate>,
+}
+
+impl VacationStatus {
+    pub fn from_period(period: Option<VacationPeriod>, today: NaiveDate) -> Self {
+        Self {
+            on_vacation: period.as_ref().is_some_and(|p| p.contains(today)),
+            in_ramp_up: period.as_ref().is_some_and(|p| p.in_ramp_up(today)),
+            ramp_up_until: period.as_ref().map(VacationPeriod::ramp_up_until),
+            current: period,
+        }
+    }
+}
+
+pub fn is_frozen(periods: &[VacationPeriod], date: NaiveDate) -> bool {
+    periods.iter().any(|p| p.contains(date))
+}
+
+/// Consecutive study days ending today, where vacation days neither count
+/// nor break the streak. Unless `require_today`, a streak that last
+/// extended to yesterday is still alive.
+pub fn consecutive_days(
+    study_dates: &HashSet<NaiveDate>,
+    periods: &[VacationPeriod],
+    today: NaiveDate,
+    require_today: bool,
+) -> i64 {
+    let Some(earliest) = study_dates.iter().min().copied() else {
+        return 0;
+    };
+    if !require_today && !study_dates.contains(&today) && !is_frozen(periods, today) {
+        return consecutive_days(study_dates, periods, today - Duration::days(1), true);
+    }
+
+    let mut streak = 0;
+    let mut day = today;
+    while day >= earliest {
+        if study_dates.contains(&day) {
+            streak += 1;
+        } else if !is_frozen(periods, day) {
+            break;
+        }
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+pub fn validate_range(
+    start: NaiveDate,
+    end: NaiveDate,
+    ramp_up_days: i32,
+    today: NaiveDate,
+) -> Result<(), VacationError> {
+    if end < start {
+        return Err(VacationError::Invalid("结束日期不能早于开始日期"));
+    }
+    if start < today {
+        return Err(VacationError::Invalid("开始日期不能早于今天"));
+    }
+    if (end - start).num_days() + 1 > MAX_VACATION_DAYS {
+        return Err(VacationError::Invalid("假期最长 90 天"));
+    }
+    if !(1..=MAX_RAMP_UP_DAYS).contains(&ramp_up_days) {
+        return Err(VacationError::Invalid("恢复期天数需在 1-14 之间"));
+    }
+    Ok(())
+}
+
+pub async fn list_periods(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Vec<VacationPeriod>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"SELECT "id","startDate","endDate","rampUpDays" FROM "vacation_periods"
+           WHERE "userId" = $1 ORDER BY "startDate" ASC"#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().filter_map(map_period).collect())
+}
+
+/// The latest vacation that is active, upcoming or still ramping up as of `today`.
+pub async fn current_period(
+    pool: &PgPool,
+    user_id: &str,
+    today: NaiveDate,
+) -> Result<Option<VacationPeriod>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT "id","startDate","endDate","rampUpDays" FROM "vacation_periods"
+           WHERE "userId" = $1 AND "endDate" + "rampUpDays" >= $2
+           ORDER BY "startDate" DESC LIMIT 1"#,
+    )
+    .bind(user_id)
+    .bind(today)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(map_period))
+}
+
+/// The vacation whose ramp-up window contains `today`, if any.
+pub async fn ramp_up_period(
+    pool: &PgPool,
+    user_id: &str,
+    today: NaiveDate,
+) -> Result<Option<VacationPeriod>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT "id","startDate","endDate","rampUpDays" FROM "vacation_periods"
+           WHERE "userId" = $1 AND "endDate" < $2 AND "endDate" + "rampUpDays" >= $2
+           ORDER BY "endDate" DESC LIMIT 1"#,
+    )
+    .bind(user_id)
+    .bind(today)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(map_period))
+}
+
+/// Schedules a vacation, replacing any vacation that has not ended yet.
+pub async fn enable(
+    pool: &PgPool,
+    user_id: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    ramp_up_days: i32,
+) -> Result<VacationPeriod, VacationError> {
+    let today = Utc::now().date_naive();
+    validate_range(start, end, ramp_up_days, today)?;
+
+    let mut tx = pool.begin().await?;
+    // An active vacation keeps its elapsed days frozen; only the rest is replaced.
+    sqlx::query(r#"DELETE FROM "vacation_periods" WHERE "userId" = $1 AND "startDate" >= $2"#)
+        .bind(user_id)
+        .bind(today)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"UPDATE "vacation_periods" SET "endDate" = $2, "updatedAt" = NOW()
+           WHERE "userId" = $1 AND "endDate" >= $2"#,
+    )
+    .bind(user_id)
+    .bind(today - Duration::days(1))
+    .execute(&mut *tx)
+    .await?;
+
+    let period = VacationPeriod {
+        id: Uuid::new_v4().to_string(),
+        start_date: start,
+        end_date: end,
+        ramp_up_days,
+    };
+    sqlx::query(
+        r#"INSERT INTO "vacation_periods" ("id","userId","startDate","endDate","rampUpDays")
+           VALUES ($1,$2,$3,$4,$5)"#,
+    )
+    .bind(&period.id)
+    .bind(user_id)
+    .bind(period.start_date)
+    .bind(period.end_date)
+    .bind(period.ramp_up_days)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(period)
+}
+
+/// Ends the current vacation early (yesterday becomes its last day) or
+/// cancels an upcoming one. Returns the vacation as it stands afterwards;
+/// the ramp-up window still applies from today.
+pub async fn disable(pool: &PgPool, user_id: &str) -> Result<Option<VacationPeriod>, sqlx::Error> {
+    let today = Utc::now().date_naive();
+    let mut tx = pool.begin().await?;
+    sqlx::query(r#"DELETE FROM "vacation_periods" WHERE "userId" = $1 AND "startDate" >= $2"#)
+        .bind(user_id)
+        .bind(today)
+        .execute(&mut *tx)
+        .await?;
+    let row = sqlx::query(
+        r#"UPDATE "vacation_periods" SET "endDate" = $2, "updatedAt" = NOW()
+           WHERE "userId" = $1 AND "endDate" >= $2
+           RETURNING "id","startDate","endDate","rampUpDays""#,
+    )
+    .bind(user_id)
+    .bind(today - Duration::days(1))
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(row.and_then(map_period))
+}
+
+fn map_period(row: sqlx::postgres::PgRow) -> Option<VacationPeriod> {
+    Some(VacationPeriod {
+        id: row.try_get("id").ok()?,
+        start_date: row.try_get("startDate").ok()?,
+        end_date: row.try_get("endDate").ok()?,
+        ramp_up_days: row.try_get("rampUpDays").unwrap_or(DEFAULT_RAMP_UP_DAYS),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn period(start: u32, end: u32, ramp: i32) -> VacationPeriod {
+        VacationPeriod {
+            id: "v1".into(),
+            start_date: d(start),
+            end_date: d(end),
+            ramp_up_days: ramp,
+        }
+    }
+
+    #[test]
+    fn frozen_days_bridge_the_streak() {
+        let studied: HashSet<NaiveDate> = [1, 2, 3, 8, 9].into_iter().map(d).collect();
+        assert_eq!(consecutive_days(&studied, &[], d(9), false), 2);
+        let periods = [period(4, 7, 3)];
+        assert_eq!(consecutive_days(&studied, &periods, d(9), false), 5);
+    }
+
+    #[test]
+    fn yesterday_grace_only_without_require_today() {
+        let studied: HashSet<NaiveDate> = [7, 8].into_iter().map(d).collect();
+        assert_eq!(consecutive_days(&studied, &[], d(9), false), 2);
+        assert_eq!(consecutive_days(&studied, &[], d(9), true), 0);
+        assert_eq!(consecutive_days(&studied, &[], d(10), false), 0);
+        // Still on vacation today: the streak is preserved.
+        assert_eq!(
+            consecutive_days(&studied, &[period(9, 12, 3)], d(11), true),
+            2
+        );
+    }
+
+    #[test]
+    fn backlog_is_spread_over_ramp_up_window() {
+        let p = period(1, 10, 4);
+        assert_eq!(p.return_date(), d(11));
+        assert_eq!(p.ramp_up_until(), d(14));
+        assert!(p.in_ramp_up(d(14)) && !p.in_ramp_up(d(15)));
+
+        let mut per_day = [0usize; 4];
+        for i in 0..400 {
+            let release = p.release_date(&format!("w{i}"), d(5));
+            assert!(p.in_ramp_up(release));
+            per_day[(release - d(11)).num_days() as usize] += 1;
+        }
+        assert!(per_day.iter().all(|&n| n > 50), "{per_day:?}");
+        // Stable per word, and words due after the return are untouched.
+        assert_eq!(p.release_date("w1", d(5)), p.release_date("w1", d(2)));
+        assert_eq!(p.release_date("w1", d(12)), d(12));
+    }
+
+    #[test]
+    fn validates_ranges() {
+        let today = d(5);
+        assert!(validate_range(d(5), d(9), 3, today).is_ok());
+        assert!(validate_range(d(9), d(5), 3, today).is_err());
+        assert!(validate_range(d(4), d(9), 3, today).is_err());
+        assert!(validate_range(d(5), d(9), 0, today).is_err());
+        let far = today + Duration::days(MAX_VACATION_DAYS);
+        assert!(validate_range(today, far, 3, today).is_err());
+    }
+}
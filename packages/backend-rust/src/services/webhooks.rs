@@ -0,0 +1,651 @@
+//! Webhooks for external integrations.
+//!
+//! Partners register endpoints per organization; users are attached to an
+//! organization through `webhook_organization_members`. Emitting an event
+//! only enqueues a delivery row per subscribed endpoint — the webhook
+//! delivery worker signs and sends them with retries.
+//!
+//! Endpoint URLs are admin-supplied and fetched by the server, so they must be
+//! public HTTPS targets: the host is resolved and every address checked
+//! against loopback, private, link-local and unique-local ranges both when
+//! the endpoint is saved and again right before each send, and the worker
+//! pins the connection to the checked addresses and never follows redirects.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use chrono::{NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::{QueryBuilder, Row};
+
+use crate::db::DatabaseProxy;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    #[serde(rename = "assignment.completed")]
+    AssignmentCompleted,
+    #[serde(rename = "streak.milestone")]
+    StreakMilestone,
+    #[serde(rename = "wordbook.completed")]
+    WordbookCompleted,
+}
+
+impl WebhookEventType {
+    pub const ALL: [WebhookEventType; 3] = [
+        Self::AssignmentCompleted,
+        Self::StreakMilestone,
+        Self::WordbookCompleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AssignmentCompleted => "assignment.completed",
+            Self::StreakMilestone => "streak.milestone",
+            Self::WordbookCompleted => "wordbook.completed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("sql error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+    #[error("url resolves to a non-public address: {0}")]
+    ForbiddenTarget(String),
+    #[error("unknown event type: {0}")]
+    UnknownEventType(String),
+    #[error("not found")]
+    NotFound,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEndpoint {
+    pub id: String,
+    pub organization_id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Returned once on creation / rotation; the secret is never listed again.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEndpointWithSecret {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateEndpointRequest {
+    pub organization_id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateEndpointRequest {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub description: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub endpoint_id: String,
+    pub event_type: String,
+    pub user_id: Option<String>,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: String,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryFilter {
+    pub organization_id: Option<String>,
+    pub endpoint_id: Option<String>,
+    pub event_type: Option<String>,
+    pub status: Option<String>,
+}
+
+/// `sha256=<hex>` over `"{timestamp}.{body}"`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac accepts any key");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+fn validate_url(url: &str) -> Result<reqwest::Url, WebhookError> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| WebhookError::InvalidUrl(url.to_string()))?;
+    if parsed.scheme() != "https" || !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(WebhookError::InvalidUrl(url.to_string()));
+    }
+    let Some(host) = parsed.host_str().map(strip_brackets) else {
+        return Err(WebhookError::InvalidUrl(url.to_string()));
+    };
+    match host.parse::<IpAddr>() {
+        Ok(ip) if is_forbidden_ip(ip) => Err(WebhookError::ForbiddenTarget(url.to_string())),
+        _ => Ok(parsed),
+    }
+}
+
+fn strip_brackets(host: &str) -> &str {
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Addresses a webhook must never reach: loopback, private, link-local
+/// (including cloud metadata at 169.254.169.254), carrier-grade NAT,
+/// unique-local, multicast and unspecified.
+pub fn is_forbidden_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_forbidden_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_forbidden_v4(v4),
+            None => is_forbidden_v6(ip),
+        },
+    }
+}
+
+fn is_forbidden_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_forbidden_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+}
+
+/// Validates `url` and resolves its host; fails if any resolved address is
+/// not public. The returned addresses are the ones the caller should connect
+/// to, so a later DNS answer cannot swap in an internal target.
+pub async fn resolve_target(url: &str) -> Result<(reqwest::Url, Vec<SocketAddr>), WebhookError> {
+    let parsed = validate_url(url)?;
+    let host = parsed
+        .host_str()
+        .map(strip_brackets)
+        .ok_or_else(|| WebhookError::InvalidUrl(url.to_string()))?
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|_| WebhookError::InvalidUrl(url.to_string()))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(WebhookError::InvalidUrl(url.to_string()));
+    }
+    if addrs.iter().any(|addr| is_forbidden_ip(addr.ip())) {
+        return Err(WebhookError::ForbiddenTarget(url.to_string()));
+    }
+    Ok((parsed, addrs))
+}
+
+fn validate_event_types(types: &[String]) -> Result<(), WebhookError> {
+    for t in types {
+        if WebhookEventType::parse(t).is_none() {
+            return Err(WebhookError::UnknownEventType(t.clone()));
+        }
+    }
+    Ok(())
+}
+
+fn format_ts(ts: NaiveDateTime) -> String {
+    crate::auth::format_naive_datetime_iso_millis(ts)
+}
+
+fn map_endpoint(row: &sqlx::postgres::PgRow) -> WebhookEndpoint {
+    let now = Utc::now().naive_utc();
+    WebhookEndpoint {
+        id: row.try_get("id").unwrap_or_default(),
+        organization_id: row.try_get("organizationId").unwrap_or_default(),
+        url: row.try_get("url").unwrap_or_default(),
+        event_types: row.try_get("eventTypes").unwrap_or_default(),
+        description: row.try_get("description").ok().flatten(),
+        is_active: row.try_get("isActive").unwrap_or(true),
+        created_by: row.try_get("createdBy").ok().flatten(),
+        created_at: format_ts(row.try_get("createdAt").unwrap_or(now)),
+        updated_at: format_ts(row.try_get("updatedAt").unwrap_or(now)),
+    }
+}
+
+fn map_delivery(row: &sqlx::postgres::PgRow) -> WebhookDelivery {
+    let now = Utc::now().naive_utc();
+    WebhookDelivery {
+        id: row.try_get("id").unwrap_or_default(),
+        endpoint_id: row.try_get("endpointId").unwrap_or_default(),
+        event_type: row.try_get("eventType").unwrap_or_default(),
+        user_id: row.try_get("userId").ok().flatten(),
+        payload: row.try_get("payload").unwrap_or(serde_json::Value::Null),
+        status: row.try_get("status").unwrap_or_default(),
+        attempts: row.try_get("attempts").unwrap_or(0),
+        next_attempt_at: format_ts(row.try_get("nextAttemptAt").unwrap_or(now)),
+        last_status_code: row.try_get("lastStatusCode").ok().flatten(),
+        last_error: row.try_get("lastError").ok().flatten(),
+        delivered_at: row
+            .try_get::<Option<NaiveDateTime>, _>("deliveredAt")
+            .ok()
+            .flatten()
+            .map(format_ts),
+        created_at: format_ts(row.try_get("createdAt").unwrap_or(now)),
+    }
+}
+
+const ENDPOINT_COLUMNS: &str = r#""id","organizationId","url","eventTypes","description","isActive","createdBy","createdAt","updatedAt""#;
+
+pub async fn list_endpoints(
+    proxy: &DatabaseProxy,
+    organization_id: Option<&str>,
+) -> Result<Vec<WebhookEndpoint>, WebhookError> {
+    let rows = sqlx::query(&format!(
+        r#"SELECT {ENDPOINT_COLUMNS} FROM "webhook_endpoints"
+           WHERE ($1::text IS NULL OR "organizationId" = $1)
+           ORDER BY "createdAt" DESC"#
+    ))
+    .bind(organization_id)
+    .fetch_all(proxy.pool())
+    .await?;
+    Ok(rows.iter().map(map_endpoint).collect())
+}
+
+pub async fn create_endpoint(
+    proxy: &DatabaseProxy,
+    admin_id: &str,
+    req: CreateEndpointRequest,
+) -> Result<WebhookEndpointWithSecret, WebhookError> {
+    resolve_target(&req.url).await?;
+    validate_event_types(&req.event_types)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let secret = generate_secret();
+    let now = Utc::now().naive_utc();
+    let row = sqlx::query(&format!(
+        r#"INSERT INTO "webhook_endpoints"
+             ("id","organizationId","url","secret","eventTypes","description","isActive","createdBy","createdAt","updatedAt")
+           VALUES ($1,$2,$3,$4,$5,$6,TRUE,$7,$8,$8)
+           RETURNING {ENDPOINT_COLUMNS}"#
+    ))
+    .bind(&id)
+    .bind(req.organization_id.trim())
+    .bind(&req.url)
+    .bind(&secret)
+    .bind(&req.event_types)
+    .bind(&req.description)
+    .bind(admin_id)
+    .bind(now)
+    .fetch_one(proxy.pool())
+    .await?;
+
+    Ok(WebhookEndpointWithSecret {
+        endpoint: map_endpoint(&row),
+        secret,
+    })
+}
+
+pub async fn update_endpoint(
+    proxy: &DatabaseProxy,
+    id: &str,
+    req: UpdateEndpointRequest,
+) -> Result<WebhookEndpoint, WebhookError> {
+    if let Some(ref url) = req.url {
+        resolve_target(url).await?;
+    }
+    if let Some(ref types) = req.event_types {
+        validate_event_types(types)?;
+    }
+
+    let row = sqlx::query(&format!(
+        r#"UPDATE "webhook_endpoints" SET
+             "url" = COALESCE($2, "url"),
+             "eventTypes" = COALESCE($3, "eventTypes"),
+             "description" = COALESCE($4, "description"),
+             "isActive" = COALESCE($5, "isActive"),
+             "updatedAt" = $6
+           WHERE "id" = $1
+           RETURNING {ENDPOINT_COLUMNS}"#
+    ))
+    .bind(id)
+    .bind(&req.url)
+    .bind(&req.event_types)
+    .bind(&req.description)
+    .bind(req.is_active)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(proxy.pool())
+    .await?;
+
+    row.as_ref().map(map_endpoint).ok_or(WebhookError::NotFound)
+}
+
+pub async fn rotate_secret(
+    proxy: &DatabaseProxy,
+    id: &str,
+) -> Result<WebhookEndpointWithSecret, WebhookError> {
+    let secret = generate_secret();
+    let row = sqlx::query(&format!(
+        r#"UPDATE "webhook_endpoints" SET "secret" = $2, "updatedAt" = $3
+           WHERE "id" = $1
+           RETURNING {ENDPOINT_COLUMNS}"#
+    ))
+    .bind(id)
+    .bind(&secret)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(proxy.pool())
+    .await?;
+
+    let endpoint = row
+        .as_ref()
+        .map(map_endpoint)
+        .ok_or(WebhookError::NotFound)?;
+    Ok(WebhookEndpointWithSecret { endpoint, secret })
+}
+
+pub async fn delete_endpoint(proxy: &DatabaseProxy, id: &str) -> Result<(), WebhookError> {
+    let result = sqlx::query(r#"DELETE FROM "webhook_endpoints" WHERE "id" = $1"#)
+        .bind(id)
+        .execute(proxy.pool())
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(WebhookError::NotFound);
+    }
+    Ok(())
+}
+
+pub async fn list_members(
+    proxy: &DatabaseProxy,
+    organization_id: &str,
+) -> Result<Vec<String>, WebhookError> {
+    let rows = sqlx::query(
+        r#"SELECT "userId" FROM "webhook_organization_members"
+           WHERE "organizationId" = $1 ORDER BY "createdAt""#,
+    )
+    .bind(organization_id)
+    .fetch_all(proxy.pool())
+    .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| r.try_get::<String, _>("userId").ok())
+        .collect())
+}
+
+pub async fn add_member(
+    proxy: &DatabaseProxy,
+    organization_id: &str,
+    user_id: &str,
+) -> Result<(), WebhookError> {
+    sqlx::query(
+        r#"INSERT INTO "webhook_organization_members" ("organizationId","userId")
+           VALUES ($1,$2) ON CONFLICT DO NOTHING"#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_member(
+    proxy: &DatabaseProxy,
+    organization_id: &str,
+    user_id: &str,
+) -> Result<(), WebhookError> {
+    sqlx::query(
+        r#"DELETE FROM "webhook_organization_members"
+           WHERE "organizationId" = $1 AND "userId" = $2"#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(proxy.pool())
+    .await?;
+    Ok(())
+}
+
+pub async fn list_deliveries(
+    proxy: &DatabaseProxy,
+    filter: &DeliveryFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<WebhookDelivery>, i64), WebhookError> {
+    fn push_filters<'a>(qb: &mut QueryBuilder<'a, sqlx::Postgres>, filter: &'a DeliveryFilter) {
+        qb.push(" WHERE 1=1");
+        if let Some(ref org) = filter.organization_id {
+            qb.push(r#" AND e."organizationId" = "#).push_bind(org);
+        }
+        if let Some(ref endpoint) = filter.endpoint_id {
+            qb.push(r#" AND d."endpointId" = "#).push_bind(endpoint);
+        }
+        if let Some(ref event_type) = filter.event_type {
+            qb.push(r#" AND d."eventType" = "#).push_bind(event_type);
+        }
+        if let Some(ref status) = filter.status {
+            qb.push(r#" AND d."status" = "#).push_bind(status);
+        }
+    }
+
+    let mut qb = QueryBuilder::<sqlx::Postgres>::new(
+        r#"SELECT d.* FROM "webhook_deliveries" d
+           JOIN "webhook_endpoints" e ON e."id" = d."endpointId""#,
+    );
+    push_filters(&mut qb, filter);
+    qb.push(r#" ORDER BY d."createdAt" DESC LIMIT "#)
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let rows = qb.build().fetch_all(proxy.pool()).await?;
+
+    let mut count_qb = QueryBuilder::<sqlx::Postgres>::new(
+        r#"SELECT COUNT(*) AS "count" FROM "webhook_deliveries" d
+           JOIN "webhook_endpoints" e ON e."id" = d."endpointId""#,
+    );
+    push_filters(&mut count_qb, filter);
+    let total: i64 = count_qb
+        .build()
+        .fetch_one(proxy.pool())
+        .await?
+        .try_get("count")
+        .unwrap_or(0);
+
+    Ok((rows.iter().map(map_delivery).collect(), total))
+}
+
+/// Requeue a delivery immediately (admin retry of a FAILED delivery).
+pub async fn retry_delivery(proxy: &DatabaseProxy, id: &str) -> Result<(), WebhookError> {
+    let result = sqlx::query(
+        r#"UPDATE "webhook_deliveries"
+           SET "status" = 'PENDING', "nextAttemptAt" = $2, "updatedAt" = $2
+           WHERE "id" = $1 AND "status" <> 'SENDING'"#,
+    )
+    .bind(id)
+    .bind(Utc::now().naive_utc())
+    .execute(proxy.pool())
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(WebhookError::NotFound);
+    }
+    Ok(())
+}
+
+/// Enqueue `event` for every active endpoint subscribed to it in the user's
+/// organizations. Returns the number of deliveries queued.
+pub async fn emit(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    event: WebhookEventType,
+    data: serde_json::Value,
+) -> Result<u64, WebhookError> {
+    emit_with(proxy.pool(), user_id, event, data).await
+}
+
+/// Same as `emit`, on a caller-provided executor, so the delivery rows can
+/// commit or roll back together with the state change that triggered them.
+pub async fn emit_with<'e, E>(
+    executor: E,
+    user_id: &str,
+    event: WebhookEventType,
+    data: serde_json::Value,
+) -> Result<u64, WebhookError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let now = Utc::now();
+    let payload = serde_json::json!({
+        "type": event.as_str(),
+        "userId": user_id,
+        "occurredAt": now.to_rfc3339(),
+        "data": data,
+    });
+
+    let result = sqlx::query(
+        r#"INSERT INTO "webhook_deliveries"
+             ("id","endpointId","eventType","userId","payload","status","attempts","nextAttemptAt","createdAt","updatedAt")
+           SELECT gen_random_uuid()::text, e."id", $2, $1, $3, 'PENDING', 0, $4, $4, $4
+           FROM "webhook_endpoints" e
+           JOIN "webhook_organization_members" m ON m."organizationId" = e."organizationId"
+           WHERE m."userId" = $1 AND e."isActive" AND $2 = ANY(e."eventTypes")"#,
+    )
+    .bind(user_id)
+    .bind(event.as_str())
+    .bind(&payload)
+    .bind(now.naive_utc())
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Fire-and-forget wrapper for call sites on the learning path.
+pub async fn emit_logged(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    event: WebhookEventType,
+    data: serde_json::Value,
+) {
+    match emit(proxy, user_id, event, data).await {
+        Ok(0) => {}
+        Ok(n) => {
+            tracing::debug!(user_id = %user_id, event = event.as_str(), deliveries = n, "Webhook event queued")
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, user_id = %user_id, event = event.as_str(), "Failed to queue webhook event")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_types_round_trip() {
+        for t in WebhookEventType::ALL {
+            assert_eq!(WebhookEventType::parse(t.as_str()), Some(t));
+            assert_eq!(serde_json::to_value(t).unwrap(), t.as_str());
+        }
+        assert_eq!(WebhookEventType::parse("badge.unlocked"), None);
+    }
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        let sig = sign_payload("whsec_test", 1_700_000_000, br#"{"a":1}"#);
+        assert!(sig.starts_with("sha256="));
+        assert_eq!(sig.len(), "sha256=".len() + 64);
+        assert_eq!(
+            sig,
+            sign_payload("whsec_test", 1_700_000_000, br#"{"a":1}"#)
+        );
+        assert_ne!(
+            sig,
+            sign_payload("whsec_test", 1_700_000_001, br#"{"a":1}"#)
+        );
+        assert_ne!(
+            sig,
+            sign_payload("whsec_other", 1_700_000_000, br#"{"a":1}"#)
+        );
+    }
+
+    #[test]
+    fn rejects_non_https_urls() {
+        assert!(validate_url("https://partner.example.com/hook").is_ok());
+        assert!(validate_url("http://partner.example.com/hook").is_err());
+        assert!(validate_url("ftp://partner.example.com").is_err());
+        assert!(validate_url("https://user:pw@partner.example.com").is_err());
+        assert!(validate_url("not a url").is_err());
+    }
+
+    #[test]
+    fn rejects_internal_ip_literals() {
+        for url in [
+            "https://127.0.0.1/hook",
+            "https://10.1.2.3/hook",
+            "https://192.168.0.10/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/hook",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:10.0.0.1]/hook",
+        ] {
+            assert!(
+                matches!(validate_url(url), Err(WebhookError::ForbiddenTarget(_))),
+                "{url}"
+            );
+        }
+        assert!(validate_url("https://93.184.216.34/hook").is_ok());
+        assert!(!is_forbidden_ip("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn resolved_loopback_hosts_are_rejected() {
+        assert!(matches!(
+            resolve_target("https://localhost/hook").await,
+            Err(WebhookError::ForbiddenTarget(_))
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+//! "What if I studied N minutes a day?" projections.
+//!
+//! The learner's current words are bucketed into review stages by mastery
+//! level and handed to `danci_algo::sim::project_learner` together with the
+//! active scheduling config. Study minutes become a daily answer budget
+//! through the learner's own pace over the last `HABIT_WINDOW_DAYS`; each
+//! scenario is simulated `RUNS` times with common random numbers, so the
+//! scenarios differ only by budget.
+
+use danci_algo::sim::project_learner;
+use danci_algo::{HorizonProjection, LearnerProfile, SchedulerConfig};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+use crate::db::DatabaseProxy;
+use crate::services::learning_curve::{MASTERED_LEVEL, MAX_ANSWER_MS};
+use crate::services::simulation;
+
+pub const HORIZONS: [u32; 2] = [30, 90];
+pub const MAX_SCENARIOS: usize = 5;
+pub const MAX_DAILY_MINUTES: f64 = 240.0;
+const HABIT_WINDOW_DAYS: i32 = 30;
+const RUNS: u32 = 100;
+const SEED: u64 = 0x5eed;
+/// Answers per minute assumed until the learner has a minute of study.
+const DEFAULT_ANSWERS_PER_MINUTE: f64 = 3.0;
+/// Extra minutes compared against the current habit when none are requested.
+const DEFAULT_EXTRA_MINUTES: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StudyHabit {
+    /// Average study minutes per calendar day over the window.
+    pub daily_minutes: f64,
+    pub answers_per_minute: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhatIfScenario {
+    pub daily_minutes: f64,
+    pub daily_answers: u32,
+    pub projections: Vec<HorizonProjection>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhatIfReport {
+    pub habit: StudyHabit,
+    pub profile: LearnerProfile,
+    /// The current habit first, then each requested budget.
+    pub scenarios: Vec<WhatIfScenario>,
+}
+
+async fn study_habit(pool: &PgPool, user_id: &str) -> Result<StudyHabit, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT COUNT(*)::BIGINT AS "answers",
+               COALESCE(SUM(LEAST(COALESCE("dwellTime", "responseTime", 0), $2)), 0)::BIGINT AS "ms"
+        FROM "answer_records"
+        WHERE "userId" = $1 AND "timestamp" >= NOW() - make_interval(days => $3)
+        "#,
+    )
+    .bind(user_id)
+    .bind(MAX_ANSWER_MS)
+    .bind(HABIT_WINDOW_DAYS)
+    .fetch_one(pool)
+    .await?;
+    let answers: i64 = row.try_get("answers").unwrap_or(0);
+    let minutes = row.try_get::<i64, _>("ms").unwrap_or(0) as f64 / 60_000.0;
+    Ok(StudyHabit {
+        daily_minutes: minutes / f64::from(HABIT_WINDOW_DAYS),
+        answers_per_minute: if minutes >= 1.0 {
+            answers as f64 / minutes
+        } else {
+            DEFAULT_ANSWERS_PER_MINUTE
+        },
+    })
+}
+
+/// Current words bucketed by review stage; NEW words are not started yet.
+async fn learner_profile(
+    pool: &PgPool,
+    user_id: &str,
+    stages: usize,
+) -> Result<LearnerProfile, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT ("state" = 'MASTERED' OR COALESCE("masteryLevel", 0) >= $2) AS "mastered",
+               LEAST(GREATEST(COALESCE("masteryLevel", 0), 0), $2 - 1) AS "level",
+               COUNT(*)::BIGINT AS "count"
+        FROM "word_learning_states"
+        WHERE "userId" = $1 AND "state" <> 'NEW'
+        GROUP BY 1, 2
+        "#,
+    )
+    .bind(user_id)
+    .bind(MASTERED_LEVEL)
+    .fetch_all(pool)
+    .await?;
+
+    let mut stage_counts = vec![0u32; stages.max(1)];
+    let mut mastered = 0u32;
+    for row in rows {
+        let count = u32::try_from(row.try_get::<i64, _>("count").unwrap_or(0)).unwrap_or(u32::MAX);
+        if row.try_get::<bool, _>("mastered").unwrap_or(false) {
+            mastered = mastered.saturating_add(count);
+            continue;
+        }
+        let level = row.try_get::<i32, _>("level").unwrap_or(0).max(0) as usize;
+        let stage = level.min(stage_counts.len() - 1);
+        stage_counts[stage] = stage_counts[stage].saturating_add(count);
+    }
+
+    // Memory parameters of the simulator's "regular" cohort.
+    Ok(LearnerProfile {
+        stage_counts,
+        mastered,
+        initial_half_life: 1.0,
+        growth: 2.3,
+        lapse_factor: 0.5,
+        ability_spread: 0.25,
+    })
+}
+
+/// Parses a comma-separated list of daily study minutes.
+pub fn parse_minutes(raw: Option<&str>) -> Vec<f64> {
+    raw.unwrap_or("")
+        .split(',')
+        .filter_map(|s| s.trim().parse::<f64>().ok())
+        .filter(|m| m.is_finite() && *m > 0.0 && *m <= MAX_DAILY_MINUTES)
+        .take(MAX_SCENARIOS)
+        .collect()
+}
+
+fn run_scenarios(
+    config: &SchedulerConfig,
+    profile: &LearnerProfile,
+    habit: &StudyHabit,
+    minutes: &[f64],
+) -> Vec<WhatIfScenario> {
+    minutes
+        .iter()
+        .map(|&daily_minutes| {
+            let daily_answers = (daily_minutes * habit.answers_per_minute).round() as u32;
+            WhatIfScenario {
+                daily_minutes,
+                daily_answers,
+                projections: project_learner(config, profile, daily_answers, &HORIZONS, RUNS, SEED),
+            }
+        })
+        .collect()
+}
+
+pub async fn what_if(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    requested_minutes: Vec<f64>,
+) -> Result<WhatIfReport, sqlx::Error> {
+    let config = simulation::load_baseline(proxy).await?;
+    let habit = study_habit(proxy.pool(), user_id).await?;
+    let profile = learner_profile(proxy.pool(), user_id, config.review_intervals.len()).await?;
+
+    let current = (habit.daily_minutes * 10.0).round() / 10.0;
+    let mut minutes = vec![current];
+    if requested_minutes.is_empty() {
+        minutes.push(current + DEFAULT_EXTRA_MINUTES);
+    } else {
+        minutes.extend(requested_minutes);
+    }
+
+    let scenarios = {
+        let (profile, habit) = (profile.clone(), habit.clone());
+        tokio::task::spawn_blocking(move || run_scenarios(&config, &profile, &habit, &minutes))
+            .await
+            .unwrap_or_default()
+    };
+
+    Ok(WhatIfReport {
+        habit,
+        profile,
+        scenarios,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minutes_are_bounded() {
+        assert!(parse_minutes(None).is_empty());
+        assert_eq!(parse_minutes(Some("20, x, -5, 500, 45")), vec![20.0, 45.0]);
+        assert_eq!(parse_minutes(Some("1,2,3,4,5,6,7")).len(), MAX_SCENARIOS);
+    }
+}
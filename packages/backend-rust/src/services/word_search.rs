@@ -0,0 +1,605 @@
+//! Word search across the primary and fallback stores.
+//!
+//! Postgres uses pg_trgm similarity when the extension is installed and falls
+//! back to LIKE matching otherwise; the SQLite fallback uses an FTS5 index.
+//! Every hit carries a score and an HTML-escaped snippet with matches wrapped
+//! in `<mark>`.
+//!
+//! Whether pg_trgm is installed and whether the FTS5 index is current are
+//! checked at most once per [`CHECK_TTL`].
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, QueryBuilder, Row, Sqlite, SqlitePool};
+
+use crate::db::state_machine::DatabaseState;
+use crate::db::DatabaseProxy;
+use crate::services::wordbook_licenses;
+
+pub const HIGHLIGHT_OPEN: &str = "<mark>";
+pub const HIGHLIGHT_CLOSE: &str = "</mark>";
+/// Characters kept on each side of a match inside a meaning snippet.
+const SNIPPET_CONTEXT_CHARS: usize = 16;
+/// How long the pg_trgm probe and the FTS5 signature check are trusted.
+const CHECK_TTL: Duration = Duration::from_secs(60);
+/// Match delimiters SQLite emits around FTS5 hits; the text is escaped
+/// before they are turned into `<mark>` tags.
+const FTS_OPEN: char = '\u{2}';
+const FTS_CLOSE: char = '\u{3}';
+
+static PG_TRGM_AVAILABLE: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+/// Last successful FTS5 signature check, per database file.
+static FTS_CHECKED: OnceLock<Mutex<HashMap<PathBuf, Instant>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEngine {
+    PgTrigram,
+    PgLike,
+    SqliteFts5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchField {
+    Spelling,
+    Meaning,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchWordBook {
+    pub id: String,
+    pub name: String,
+    pub book_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub id: String,
+    pub word_book_id: String,
+    pub spelling: String,
+    pub phonetic: String,
+    pub meanings: Vec<String>,
+    pub examples: Vec<String>,
+    pub audio_url: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub word_book: Option<SearchWordBook>,
+    pub score: f64,
+    pub matched_field: MatchField,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub engine: SearchEngine,
+    pub hits: Vec<SearchHit>,
+}
+
+/// Picks the search implementation for the current database state.
+pub async fn select_engine(proxy: &DatabaseProxy) -> SearchEngine {
+    let state = proxy.state_machine().read().await.state();
+    if matches!(state, DatabaseState::Degraded | DatabaseState::Unavailable)
+        && proxy.fallback_pool().await.is_some()
+    {
+        return SearchEngine::SqliteFts5;
+    }
+    if proxy.read_offload_pool().await.is_some() {
+        return SearchEngine::SqliteFts5;
+    }
+    if pg_trgm_available(proxy.pool()).await {
+        SearchEngine::PgTrigram
+    } else {
+        SearchEngine::PgLike
+    }
+}
+
+pub async fn search_words(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    query: &str,
+    lemmas: &[String],
+    limit: i64,
+) -> Result<SearchResults, sqlx::Error> {
+    let engine = select_engine(proxy).await;
+    let term = query.trim().to_lowercase();
+    if term.is_empty() {
+        return Ok(SearchResults {
+            engine,
+            hits: Vec::new(),
+        });
+    }
+
+    let hits = match engine {
+        SearchEngine::SqliteFts5 => match proxy.fallback_pool().await {
+            Some(pool) => {
+                // The mirror has no license tables; ask Postgres which books
+                // are gated, and fall back to the user's own books when it
+                // cannot answer.
+                let denied = wordbook_licenses::denied_book_ids(proxy.pool(), user_id)
+                    .await
+                    .ok();
+                search_sqlite_fts(&pool, user_id, &term, limit, denied.as_deref()).await?
+            }
+            None => Vec::new(),
+        },
+        SearchEngine::PgTrigram => {
+            search_postgres(proxy.pool(), user_id, &term, lemmas, limit, true).await?
+        }
+        SearchEngine::PgLike => {
+            search_postgres(proxy.pool(), user_id, &term, lemmas, limit, false).await?
+        }
+    };
+
+    Ok(SearchResults { engine, hits })
+}
+
+async fn pg_trgm_available(pool: &PgPool) -> bool {
+    if let Ok(cached) = PG_TRGM_AVAILABLE.lock() {
+        if let Some((at, available)) = *cached {
+            if at.elapsed() < CHECK_TTL {
+                return available;
+            }
+        }
+    }
+
+    let available = sqlx::query_scalar::<_, bool>(
+        r#"SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm')"#,
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+    if let Ok(mut cached) = PG_TRGM_AVAILABLE.lock() {
+        *cached = Some((Instant::now(), available));
+    }
+    available
+}
+
+async fn search_postgres(
+    pool: &PgPool,
+    user_id: &str,
+    term: &str,
+    lemmas: &[String],
+    limit: i64,
+    trigram: bool,
+) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let escaped = escape_like(term);
+    let pattern = format!("%{escaped}%");
+    let prefix_pattern = format!("{escaped}%");
+
+    let (fuzzy_filter, score) = if trigram {
+        (
+            r#"OR lower(w."spelling") % $3
+            OR $3 <% lower(words_meanings_text(w."meanings"))"#,
+            r#"GREATEST(
+              similarity(lower(w."spelling"), $3),
+              word_similarity($3, lower(words_meanings_text(w."meanings"))) * 0.8
+            )"#,
+        )
+    } else {
+        ("", "0.0::float8")
+    };
+
+    let access = wordbook_licenses::accessible_wordbook_sql("wb.", "$1");
+    let sql = format!(
+        r#"
+        SELECT
+          w."id", w."spelling", w."phonetic", w."meanings", w."examples", w."audioUrl",
+          w."wordBookId", w."createdAt", w."updatedAt",
+          wb."id" AS "wbId", wb."name" AS "wbName", wb."type"::text AS "wbType",
+          CASE
+            WHEN lower(w."spelling") = $3 THEN 0
+            WHEN lower(w."spelling") = ANY($6) THEN 1
+            WHEN lower(w."spelling") LIKE $4 ESCAPE '\' THEN 2
+            ELSE 3
+          END AS "matchRank",
+          ({score})::float8 AS "fuzzyScore"
+        FROM "words" w
+        JOIN "word_books" wb ON wb."id" = w."wordBookId"
+        WHERE {access}
+          AND (
+            lower(w."spelling") LIKE $2 ESCAPE '\'
+            OR lower(w."spelling") = ANY($6)
+            OR EXISTS (
+              SELECT 1 FROM unnest(w."meanings") m
+              WHERE lower(m) LIKE $2 ESCAPE '\'
+            )
+            {fuzzy_filter}
+          )
+        ORDER BY "matchRank", "fuzzyScore" DESC, w."spelling" ASC
+        LIMIT $5
+        "#
+    );
+
+    let rows = sqlx::query(&sql)
+        .bind(user_id)
+        .bind(&pattern)
+        .bind(term)
+        .bind(&prefix_pattern)
+        .bind(limit)
+        .bind(lemmas)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let spelling: String = row.try_get("spelling").unwrap_or_default();
+            let meanings: Vec<String> = row.try_get("meanings").unwrap_or_default();
+            let rank: i32 = row.try_get("matchRank").unwrap_or(3);
+            let fuzzy: f64 = row.try_get("fuzzyScore").unwrap_or(0.0);
+            let (matched_field, snippet) = build_snippet(&spelling, &meanings, term);
+            let created_at: NaiveDateTime = row
+                .try_get("createdAt")
+                .unwrap_or_else(|_| Utc::now().naive_utc());
+            let updated_at: NaiveDateTime = row
+                .try_get("updatedAt")
+                .unwrap_or_else(|_| Utc::now().naive_utc());
+
+            SearchHit {
+                id: row.try_get("id").unwrap_or_default(),
+                word_book_id: row.try_get("wordBookId").unwrap_or_default(),
+                phonetic: row.try_get("phonetic").unwrap_or_default(),
+                examples: row.try_get("examples").unwrap_or_default(),
+                audio_url: row.try_get::<Option<String>, _>("audioUrl").ok().flatten(),
+                created_at: format_naive_iso(created_at),
+                updated_at: format_naive_iso(updated_at),
+                word_book: Some(SearchWordBook {
+                    id: row.try_get("wbId").unwrap_or_default(),
+                    name: row.try_get("wbName").unwrap_or_default(),
+                    book_type: row.try_get("wbType").unwrap_or_default(),
+                }),
+                score: rank_score(rank, fuzzy),
+                matched_field,
+                snippet,
+                spelling,
+                meanings,
+            }
+        })
+        .collect())
+}
+
+/// Creates the FTS5 index for the SQLite fallback and rebuilds it when the
+/// word table changed since the last build.
+pub async fn ensure_sqlite_fts(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS "words_fts" USING fts5(
+          "spelling", "meanings",
+          content='words', content_rowid='rowid',
+          tokenize='unicode61 remove_diacritics 2'
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let signature: String = sqlx::query_scalar(
+        r#"SELECT COUNT(*) || ':' || COALESCE(MAX("updatedAt"), '') FROM "words""#,
+    )
+    .fetch_one(pool)
+    .await?;
+    let indexed: Option<String> = sqlx::query_scalar(
+        r#"SELECT "value" FROM "_db_metadata" WHERE "key" = 'words_fts_signature'"#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if indexed.as_deref() != Some(signature.as_str()) {
+        sqlx::query(r#"INSERT INTO "words_fts"("words_fts") VALUES ('rebuild')"#)
+            .execute(pool)
+            .await?;
+        sqlx::query(
+            r#"INSERT OR REPLACE INTO "_db_metadata" ("key", "value") VALUES ('words_fts_signature', $1)"#,
+        )
+        .bind(&signature)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Runs [`ensure_sqlite_fts`] when this database was not checked within
+/// [`CHECK_TTL`].
+async fn ensure_sqlite_fts_fresh(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let path = pool
+        .connect_options()
+        .as_ref()
+        .clone()
+        .get_filename()
+        .into_owned();
+    let checked = FTS_CHECKED.get_or_init(|| Mutex::new(HashMap::new()));
+    let fresh = checked
+        .lock()
+        .ok()
+        .and_then(|map| map.get(&path).map(|at| at.elapsed() < CHECK_TTL))
+        .unwrap_or(false);
+    if fresh {
+        return Ok(());
+    }
+
+    ensure_sqlite_fts(pool).await?;
+    if let Ok(mut map) = checked.lock() {
+        map.insert(path, Instant::now());
+    }
+    Ok(())
+}
+
+pub async fn search_sqlite_fts(
+    pool: &SqlitePool,
+    user_id: &str,
+    term: &str,
+    limit: i64,
+    denied_books: Option<&[String]>,
+) -> Result<Vec<SearchHit>, sqlx::Error> {
+    ensure_sqlite_fts_fresh(pool).await?;
+
+    let Some(match_expr) = fts5_match_expression(term) else {
+        return Ok(Vec::new());
+    };
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        r#"
+        SELECT
+          w."id", w."spelling", w."phonetic", w."meanings", w."examples", w."audioUrl",
+          w."wordBookId", w."createdAt", w."updatedAt",
+          wb."id" AS "wbId", wb."name" AS "wbName", wb."type" AS "wbType",
+          bm25("words_fts", 10.0, 1.0) AS "rank",
+          highlight("words_fts", 0, char(2), char(3)) AS "spellingHighlight",
+          snippet("words_fts", 1, char(2), char(3), '…', 12) AS "meaningSnippet"
+        FROM "words_fts"
+        JOIN "words" w ON w.rowid = "words_fts".rowid
+        JOIN "word_books" wb ON wb."id" = w."wordBookId"
+        WHERE "words_fts" MATCH "#,
+    );
+    qb.push_bind(&match_expr);
+    qb.push(r#" AND ((upper(wb."type") = 'USER' AND wb."userId" = "#);
+    qb.push_bind(user_id);
+    qb.push(")");
+    if let Some(denied) = denied_books {
+        qb.push(r#" OR (upper(wb."type") = 'SYSTEM'"#);
+        if !denied.is_empty() {
+            qb.push(r#" AND wb."id" NOT IN ("#);
+            let mut ids = qb.separated(", ");
+            for id in denied {
+                ids.push_bind(id);
+            }
+            ids.push_unseparated(")");
+        }
+        qb.push(")");
+    }
+    qb.push(r#") ORDER BY "rank" LIMIT "#);
+    qb.push_bind(limit);
+    let rows = qb.build().fetch_all(pool).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let spelling_highlight: String = row.try_get("spellingHighlight").unwrap_or_default();
+            let (matched_field, snippet) = if spelling_highlight.contains(FTS_OPEN) {
+                (MatchField::Spelling, mark_fts_matches(&spelling_highlight))
+            } else {
+                let meaning_snippet: String = row.try_get("meaningSnippet").unwrap_or_default();
+                (MatchField::Meaning, mark_fts_matches(&meaning_snippet))
+            };
+            let rank: f64 = row.try_get("rank").unwrap_or(0.0);
+
+            SearchHit {
+                id: row.try_get("id").unwrap_or_default(),
+                word_book_id: row.try_get("wordBookId").unwrap_or_default(),
+                spelling: row.try_get("spelling").unwrap_or_default(),
+                phonetic: row.try_get("phonetic").unwrap_or_default(),
+                meanings: parse_json_list(row.try_get("meanings").ok()),
+                examples: parse_json_list(row.try_get("examples").ok()),
+                audio_url: row.try_get::<Option<String>, _>("audioUrl").ok().flatten(),
+                created_at: row.try_get("createdAt").unwrap_or_default(),
+                updated_at: row.try_get("updatedAt").unwrap_or_default(),
+                word_book: Some(SearchWordBook {
+                    id: row.try_get("wbId").unwrap_or_default(),
+                    name: row.try_get("wbName").unwrap_or_default(),
+                    book_type: row.try_get("wbType").unwrap_or_default(),
+                }),
+                // bm25 is negative, smaller is better
+                score: 1.0 - 1.0 / (1.0 + rank.abs()),
+                matched_field,
+                snippet,
+            }
+        })
+        .collect())
+}
+
+/// Quotes each token as an FTS5 prefix phrase so user input cannot inject
+/// query syntax.
+fn fts5_match_expression(term: &str) -> Option<String> {
+    let tokens: Vec<String> = term
+        .split_whitespace()
+        .map(|token| format!("\"{}\"*", token.replace('"', "\"\"")))
+        .collect();
+    (!tokens.is_empty()).then(|| tokens.join(" "))
+}
+
+fn rank_score(rank: i32, fuzzy: f64) -> f64 {
+    let base: f64 = match rank {
+        0 => 1.0,
+        1 => 0.9,
+        2 => 0.75,
+        _ => 0.0,
+    };
+    base.max(fuzzy.clamp(0.0, 1.0) * 0.7)
+}
+
+/// Snippet for a Postgres hit: the spelling when it contains the term,
+/// otherwise the first meaning that does, otherwise the bare spelling
+/// (a fuzzy or lemma match with nothing literal to mark).
+fn build_snippet(spelling: &str, meanings: &[String], term: &str) -> (MatchField, String) {
+    if let Some(marked) = highlight(spelling, term, usize::MAX) {
+        return (MatchField::Spelling, marked);
+    }
+    for meaning in meanings {
+        if let Some(marked) = highlight(meaning, term, SNIPPET_CONTEXT_CHARS) {
+            return (MatchField::Meaning, marked);
+        }
+    }
+    (MatchField::Spelling, escape_html(spelling))
+}
+
+/// Escapes an FTS5 highlight and turns its match delimiters into `<mark>`.
+fn mark_fts_matches(raw: &str) -> String {
+    escape_html(raw)
+        .replace(FTS_OPEN, HIGHLIGHT_OPEN)
+        .replace(FTS_CLOSE, HIGHLIGHT_CLOSE)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    push_escaped(&mut out, text.chars());
+    out
+}
+
+fn push_escaped(out: &mut String, chars: impl IntoIterator<Item = char>) {
+    for c in chars {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Wraps every case-insensitive occurrence of `term` in `<mark>` and trims
+/// the text to `context` characters around the first match. The text is
+/// HTML-escaped; only the `<mark>` tags are markup.
+pub fn highlight(text: &str, term: &str, context: usize) -> Option<String> {
+    let term_chars: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    if term_chars.is_empty() {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + term_chars.len() <= lowered.len() {
+        if lowered[i..i + term_chars.len()] == term_chars[..] {
+            matches.push(i);
+            i += term_chars.len();
+        } else {
+            i += 1;
+        }
+    }
+    let first = *matches.first()?;
+
+    let start = first.saturating_sub(context);
+    let end = first
+        .saturating_add(term_chars.len())
+        .saturating_add(context)
+        .min(chars.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut pos = start;
+    for &m in matches
+        .iter()
+        .filter(|&&m| m >= start && m + term_chars.len() <= end)
+    {
+        push_escaped(&mut out, chars[pos..m].iter().copied());
+        out.push_str(HIGHLIGHT_OPEN);
+        push_escaped(&mut out, chars[m..m + term_chars.len()].iter().copied());
+        out.push_str(HIGHLIGHT_CLOSE);
+        pos = m + term_chars.len();
+    }
+    push_escaped(&mut out, chars[pos..end].iter().copied());
+    if end < chars.len() {
+        out.push('…');
+    }
+    Some(out)
+}
+
+fn parse_json_list(raw: Option<String>) -> Vec<String> {
+    raw.and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn escape_like(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for ch in input.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+fn format_naive_iso(value: NaiveDateTime) -> String {
+    DateTime::<Utc>::from_naive_utc_and_offset(value, Utc)
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_marks_all_matches() {
+        assert_eq!(
+            highlight("Banana", "an", usize::MAX).as_deref(),
+            Some("B<mark>an</mark><mark>an</mark>a")
+        );
+        assert_eq!(highlight("apple", "x", usize::MAX), None);
+    }
+
+    #[test]
+    fn test_highlight_trims_long_meanings() {
+        let text = "这是一个很长很长的释义，其中包含关键词苹果，后面还有很多很多的内容";
+        let snippet = highlight(text, "苹果", 4).unwrap();
+        assert_eq!(snippet, "…含关键词<mark>苹果</mark>，后面还…");
+    }
+
+    #[test]
+    fn test_highlight_escapes_text_around_matches() {
+        assert_eq!(
+            highlight("<b>a&b</b>", "a&b", usize::MAX).as_deref(),
+            Some("&lt;b&gt;<mark>a&amp;b</mark>&lt;/b&gt;")
+        );
+        assert_eq!(
+            mark_fts_matches("x<\u{2}y\u{3}>"),
+            "x&lt;<mark>y</mark>&gt;"
+        );
+    }
+
+    #[test]
+    fn test_build_snippet_prefers_spelling() {
+        let meanings = vec!["n. 苹果".to_string()];
+        let (field, snippet) = build_snippet("Apple", &meanings, "app");
+        assert_eq!(field, MatchField::Spelling);
+        assert_eq!(snippet, "<mark>App</mark>le");
+
+        let (field, snippet) = build_snippet("apple", &meanings, "苹果");
+        assert_eq!(field, MatchField::Meaning);
+        assert_eq!(snippet, "n. <mark>苹果</mark>");
+    }
+
+    #[test]
+    fn test_fts5_match_expression_quotes_tokens() {
+        assert_eq!(
+            fts5_match_expression(r#"run "fast"#).as_deref(),
+            Some(r#""run"* """fast"*"#)
+        );
+        assert_eq!(fts5_match_expression("   "), None);
+    }
+}
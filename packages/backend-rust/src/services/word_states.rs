@@ -1,15 +1,30 @@
 use std::collections::{HashMap, HashSet};
 
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
-use serde::Serialize;
+use danci_algo::{
+    mastery_band, plan_rescue, Causality, MasteryBand, RescueCandidate, RescueConfig, RescuePlan,
+    ReviewEvent, VectorClock,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::{QueryBuilder, Row};
 use uuid::Uuid;
 
 use crate::db::DatabaseProxy;
+use crate::services::due_queue;
 
 const MAX_BATCH_SIZE: usize = 500;
 const TIMESTAMP_PAST_LIMIT_MS: i64 = 365 * 24 * 60 * 60 * 1000;
 const TIMESTAMP_FUTURE_LIMIT_MS: i64 = 60 * 60 * 1000;
+/// Highest `masteryLevel`; mastery bands work on level / this.
+const MAX_MASTERY_LEVEL: f64 = 5.0;
+/// Most recent answers per word fed into the mastery band.
+const BAND_TRACE_LIMIT: i64 = 30;
+/// Per-answer time cap for rescue review estimates, so idle answers do not
+/// make a word look slow.
+const MAX_ANSWER_MS: i64 = 60_000;
+/// Clock entry for writes made on the server.
+const SERVER_NODE: &str = "server";
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -101,6 +116,73 @@ pub async fn batch_get_word_states(
     Ok(out)
 }
 
+/// Mastery ± uncertainty for each word with a learning state, from the
+/// mastery level and the word's most recent answers.
+pub async fn mastery_bands(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    word_ids: &[String],
+) -> Result<HashMap<String, MasteryBand>, WordStateError> {
+    if word_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let pool = proxy.pool();
+    let levels: HashMap<String, i64> = sqlx::query(
+        r#"SELECT "wordId", "masteryLevel" FROM "word_learning_states"
+           WHERE "userId" = $1 AND "wordId" = ANY($2)"#,
+    )
+    .bind(user_id)
+    .bind(word_ids)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        (
+            row.get::<String, _>("wordId"),
+            row.try_get::<i32, _>("masteryLevel").unwrap_or(0) as i64,
+        )
+    })
+    .collect();
+
+    let rows = sqlx::query(
+        r#"
+        SELECT "wordId", "isCorrect",
+               EXTRACT(EPOCH FROM (NOW() - "timestamp"))::DOUBLE PRECISION / 86400.0 AS "ageDays"
+        FROM (
+            SELECT "wordId", "isCorrect", "timestamp",
+                   ROW_NUMBER() OVER (PARTITION BY "wordId" ORDER BY "timestamp" DESC) AS "rn"
+            FROM "answer_records"
+            WHERE "userId" = $1 AND "wordId" = ANY($2)
+        ) recent
+        WHERE "rn" <= $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(word_ids)
+    .bind(BAND_TRACE_LIMIT)
+    .fetch_all(pool)
+    .await?;
+    let mut events: HashMap<String, Vec<ReviewEvent>> = HashMap::new();
+    for row in &rows {
+        events
+            .entry(row.get("wordId"))
+            .or_default()
+            .push(ReviewEvent {
+                age_days: row.try_get::<f64, _>("ageDays").unwrap_or(0.0).max(0.0),
+                correct: row.try_get("isCorrect").unwrap_or(false),
+            });
+    }
+
+    Ok(levels
+        .into_iter()
+        .map(|(word_id, level)| {
+            let trace = events.remove(&word_id).unwrap_or_default();
+            let band = mastery_band(level as f64 / MAX_MASTERY_LEVEL, trace, None);
+            (word_id, band)
+        })
+        .collect())
+}
+
 pub async fn get_word_state(
     proxy: &DatabaseProxy,
     user_id: &str,
@@ -204,6 +286,7 @@ pub async fn upsert_word_state(
     );
 
     let row = qb.build().fetch_one(pool).await?;
+    due_queue::invalidate(pool, user_id, &[word_id.to_string()]).await;
     Ok(map_pg_row(&row))
 }
 
@@ -230,6 +313,7 @@ pub async fn delete_word_state(
         return Err(WordStateError::NotFound("学习状态不存在".to_string()));
     }
 
+    due_queue::invalidate(pool, user_id, &[word_id.to_string()]).await;
     Ok(())
 }
 
@@ -269,6 +353,79 @@ pub async fn list_due_words(
     Ok(rows.iter().map(map_pg_row).collect())
 }
 
+/// Rescue review plan for a user returning after a break. Overdue words are
+/// the `LEARNING`/`REVIEWING` states past `nextReviewDate`; recall follows
+/// 2^(−Δt / halfLife) and the review time is the word's mean response time.
+pub async fn rescue_plan(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    config: Option<RescueConfig>,
+) -> Result<RescuePlan, sqlx::Error> {
+    let pool = proxy.pool();
+    let now = Utc::now().naive_utc();
+    let rows = sqlx::query(
+        r#"
+        SELECT "wordId","halfLife","lastReviewDate"
+        FROM "word_learning_states"
+        WHERE "userId" = $1
+          AND "nextReviewDate" <= $2
+          AND "state"::text IN ('LEARNING','REVIEWING')
+        "#,
+    )
+    .bind(user_id)
+    .bind(now)
+    .fetch_all(pool)
+    .await?;
+
+    let word_ids: Vec<String> = rows
+        .iter()
+        .filter_map(|row| row.try_get("wordId").ok())
+        .collect();
+    let seconds: HashMap<String, f64> = sqlx::query(
+        r#"
+        SELECT "wordId", AVG(LEAST("responseTime", $3))::float8 / 1000.0 AS "seconds"
+        FROM "answer_records"
+        WHERE "userId" = $1 AND "wordId" = ANY($2) AND "responseTime" > 0
+        GROUP BY "wordId"
+        "#,
+    )
+    .bind(user_id)
+    .bind(&word_ids)
+    .bind(MAX_ANSWER_MS)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .filter_map(|row| Some((row.try_get("wordId").ok()?, row.try_get("seconds").ok()?)))
+    .collect();
+
+    let candidates = rows
+        .iter()
+        .filter_map(|row| {
+            let word_id: String = row.try_get("wordId").ok()?;
+            let half_life: f64 = row.try_get::<f64, _>("halfLife").unwrap_or(1.0).max(0.1);
+            let days = row
+                .try_get::<Option<NaiveDateTime>, _>("lastReviewDate")
+                .ok()
+                .flatten()
+                .map(|at| (now - at).num_seconds().max(0) as f64 / 86_400.0);
+            let (recall, recall_tomorrow) = match days {
+                Some(days) => (
+                    (-days / half_life).exp2(),
+                    (-(days + 1.0) / half_life).exp2(),
+                ),
+                None => (0.0, 0.0),
+            };
+            Some(RescueCandidate {
+                seconds: seconds.get(&word_id).copied(),
+                word_id,
+                recall,
+                recall_tomorrow,
+            })
+        })
+        .collect();
+    Ok(plan_rescue(candidates, config))
+}
+
 pub async fn list_words_by_state(
     proxy: &DatabaseProxy,
     user_id: &str,
@@ -693,6 +850,201 @@ pub async fn batch_update_states(
     Ok(results)
 }
 
+/// A device's copy of one learning state, with the vector clock of the
+/// writes it has seen.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceWordState {
+    pub word_id: String,
+    /// Absent when the device only wants the server copy.
+    pub state: Option<String>,
+    pub mastery_level: Option<i64>,
+    #[serde(default)]
+    pub clock: VectorClock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncResolution {
+    Server,
+    Device,
+    Merged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedWordState {
+    pub word_id: String,
+    pub state: Option<WordLearningStateRecord>,
+    pub clock: VectorClock,
+    pub resolution: SyncResolution,
+}
+
+fn state_rank(state: &str) -> u8 {
+    match state {
+        "LEARNING" => 1,
+        "REVIEWING" => 2,
+        "MASTERED" => 3,
+        _ => 0,
+    }
+}
+
+/// Merge of two concurrently edited states: progress made on either copy is
+/// kept, so the later stage and the higher mastery level win.
+pub fn merge_word_state<'a>(a: (&'a str, i64), b: (&'a str, i64)) -> (&'a str, i64) {
+    let state = if state_rank(b.0) > state_rank(a.0) {
+        b.0
+    } else {
+        a.0
+    };
+    (state, a.1.max(b.1))
+}
+
+/// Reconciles device copies with the server by vector clock. A copy that has
+/// seen every write of the other wins; copies with writes the other has not
+/// seen are merged. Server writes made since the clock was last aligned
+/// count as one `server` write.
+pub async fn sync_word_states(
+    proxy: &DatabaseProxy,
+    user_id: &str,
+    items: &[DeviceWordState],
+) -> Result<Vec<SyncedWordState>, WordStateError> {
+    if items.len() > MAX_BATCH_SIZE {
+        return Err(WordStateError::Validation(format!(
+            "states array exceeds maximum size of {MAX_BATCH_SIZE}"
+        )));
+    }
+
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let device = match (item.state.as_deref(), item.mastery_level) {
+            (Some(state), Some(level)) => {
+                let state = normalize_state_param(state).ok_or_else(|| {
+                    WordStateError::Validation(format!("invalid state for {}", item.word_id))
+                })?;
+                Some((state, level.clamp(0, MAX_MASTERY_LEVEL as i64)))
+            }
+            _ => None,
+        };
+
+        let mut tx = proxy.pool().begin().await?;
+        let row = sqlx::query(
+            r#"SELECT "state"::text AS "state", "masteryLevel", "syncClock", "clockedAt", "updatedAt"
+               FROM "word_learning_states"
+               WHERE "userId" = $1 AND "wordId" = $2
+               FOR UPDATE"#,
+        )
+        .bind(user_id)
+        .bind(&item.word_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let (resolution, write, clock) = match row {
+            None => match device {
+                None => {
+                    results.push(SyncedWordState {
+                        word_id: item.word_id.clone(),
+                        state: None,
+                        clock: item.clock.clone(),
+                        resolution: SyncResolution::Server,
+                    });
+                    continue;
+                }
+                Some(d) => {
+                    ensure_word_access(proxy, user_id, &item.word_id).await?;
+                    sqlx::query(
+                        r#"INSERT INTO "word_learning_states"
+                             ("id", "userId", "wordId", "state", "masteryLevel", "updatedAt")
+                           VALUES ($1, $2, $3, $4::"WordState", $5, NOW())"#,
+                    )
+                    .bind(Uuid::new_v4().to_string())
+                    .bind(user_id)
+                    .bind(&item.word_id)
+                    .bind(d.0)
+                    .bind(d.1 as i32)
+                    .execute(&mut *tx)
+                    .await?;
+                    (SyncResolution::Device, None, item.clock.clone())
+                }
+            },
+            Some(row) => {
+                let mut server_clock = row
+                    .try_get::<Option<Json<VectorClock>>, _>("syncClock")?
+                    .map(|c| c.0)
+                    .unwrap_or_default();
+                let clocked_at: Option<NaiveDateTime> = row.try_get("clockedAt")?;
+                let updated_at: NaiveDateTime = row.try_get("updatedAt")?;
+                if clocked_at.is_none_or(|c| updated_at > c) {
+                    server_clock.tick(SERVER_NODE);
+                }
+                let server_state: String = row.try_get("state")?;
+                let server_level = row.try_get::<i32, _>("masteryLevel")? as i64;
+
+                match device.map(|d| (d, item.clock.compare(&server_clock))) {
+                    None | Some((_, Causality::Equal | Causality::Before)) => {
+                        (SyncResolution::Server, None, server_clock)
+                    }
+                    Some((d, Causality::After)) => {
+                        (SyncResolution::Device, Some(d), item.clock.clone())
+                    }
+                    Some((d, Causality::Concurrent)) => {
+                        let (state, level) = merge_word_state((&server_state, server_level), d);
+                        let mut clock = server_clock.merged(&item.clock);
+                        clock.tick(SERVER_NODE);
+                        (
+                            SyncResolution::Merged,
+                            Some((normalize_state_param(state).unwrap_or("NEW"), level)),
+                            clock,
+                        )
+                    }
+                }
+            }
+        };
+
+        if let Some((state, level)) = write {
+            sqlx::query(
+                r#"UPDATE "word_learning_states"
+                   SET "state" = $3::"WordState", "masteryLevel" = $4, "updatedAt" = NOW()
+                   WHERE "userId" = $1 AND "wordId" = $2"#,
+            )
+            .bind(user_id)
+            .bind(&item.word_id)
+            .bind(state)
+            .bind(level as i32)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let row = sqlx::query(
+            r#"UPDATE "word_learning_states"
+               SET "syncClock" = $3, "clockedAt" = "updatedAt"
+               WHERE "userId" = $1 AND "wordId" = $2
+               RETURNING "id","userId","wordId","state"::text as "state","masteryLevel","easeFactor",
+                 "reviewCount","lastReviewDate","nextReviewDate","currentInterval",
+                 "consecutiveCorrect","consecutiveWrong","halfLife","version","createdAt","updatedAt""#,
+        )
+        .bind(user_id)
+        .bind(&item.word_id)
+        .bind(Json(&clock))
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        results.push(SyncedWordState {
+            word_id: item.word_id.clone(),
+            state: Some(map_pg_row(&row)),
+            clock,
+            resolution,
+        });
+    }
+    let changed: Vec<String> = results
+        .iter()
+        .filter(|r| r.resolution != SyncResolution::Server)
+        .map(|r| r.word_id.clone())
+        .collect();
+    due_queue::invalidate(proxy.pool(), user_id, &changed).await;
+    Ok(results)
+}
+
 pub fn validate_word_state_update_payload(
     raw: &serde_json::Map<String, serde_json::Value>,
 ) -> Result<WordStateUpdate, WordStateError> {
@@ -816,3 +1168,24 @@ pub fn validate_word_state_update_payload(
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_keeps_progress_from_either_copy() {
+        assert_eq!(
+            merge_word_state(("REVIEWING", 2), ("LEARNING", 4)),
+            ("REVIEWING", 4)
+        );
+        assert_eq!(
+            merge_word_state(("NEW", 0), ("MASTERED", 5)),
+            ("MASTERED", 5)
+        );
+        assert_eq!(
+            merge_word_state(("LEARNING", 1), ("LEARNING", 1)),
+            ("LEARNING", 1)
+        );
+    }
+}
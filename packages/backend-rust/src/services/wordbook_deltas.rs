@@ -0,0 +1,313 @@
+//! Word deltas of a wordbook since a content version.
+//!
+//! Every content change of a word (insert, edit, soft delete, move to another
+//! book, hard delete) bumps the book's `contentVersion` and stamps the new
+//! version on the word; hard deletes and moves leave a row in
+//! `word_tombstones` instead (triggers in migration 082). A client that
+//! downloaded the book at version `v` asks for the changes after `v` and
+//! applies them, rather than downloading the whole book again.
+//!
+//! Changes come back in version order, at most `limit` per page. When a page
+//! is cut short, `next_since` is the version to ask from next.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+pub const DEFAULT_LIMIT: i64 = 500;
+pub const MAX_LIMIT: i64 = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedWord {
+    pub id: String,
+    pub spelling: String,
+    pub phonetic: String,
+    pub meanings: Vec<String>,
+    pub examples: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_url: Option<String>,
+    pub content_version: i64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedWord {
+    pub id: String,
+    pub content_version: i64,
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordbookChanges {
+    pub word_book_id: String,
+    /// Current version of the book.
+    pub content_version: i64,
+    pub since: i64,
+    pub words: Vec<ChangedWord>,
+    pub deleted: Vec<DeletedWord>,
+    /// Version the client is up to date with after applying this page.
+    pub next_since: i64,
+    pub has_more: bool,
+}
+
+enum Change {
+    Upsert(ChangedWord),
+    Delete(DeletedWord),
+}
+
+impl Change {
+    fn version(&self) -> i64 {
+        match self {
+            Change::Upsert(w) => w.content_version,
+            Change::Delete(d) => d.content_version,
+        }
+    }
+}
+
+/// Merges both change lists (each sorted by version) into one page of at
+/// most `limit` changes. Changes sharing a version are never split across
+/// pages, so a page may exceed `limit` by the size of the last version.
+fn merge_page(
+    words: Vec<ChangedWord>,
+    deleted: Vec<DeletedWord>,
+    since: i64,
+    limit: usize,
+    current: i64,
+) -> (Vec<ChangedWord>, Vec<DeletedWord>, i64, bool) {
+    let mut changes: Vec<Change> = words
+        .into_iter()
+        .map(Change::Upsert)
+        .chain(deleted.into_iter().map(Change::Delete))
+        .collect();
+    changes.sort_by_key(Change::version);
+
+    let mut cut = changes.len();
+    if changes.len() > limit {
+        let boundary = changes[limit - 1].version();
+        cut = changes
+            .iter()
+            .position(|c| c.version() > boundary)
+            .unwrap_or(changes.len());
+    }
+    let has_more = cut < changes.len();
+    let next_since = if has_more {
+        changes[cut - 1].version()
+    } else {
+        current.max(since)
+    };
+
+    let mut upserts = Vec::new();
+    let mut deletes = Vec::new();
+    for change in changes.into_iter().take(cut) {
+        match change {
+            Change::Upsert(w) => upserts.push(w),
+            Change::Delete(d) => deletes.push(d),
+        }
+    }
+    (upserts, deletes, next_since, has_more)
+}
+
+fn format_iso(value: NaiveDateTime) -> String {
+    DateTime::<Utc>::from_naive_utc_and_offset(value, Utc)
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Changes of `word_book_id` after version `since`; `None` when the book does
+/// not exist. Soft-deleted words are reported as deleted.
+pub async fn changes_since(
+    pool: &PgPool,
+    word_book_id: &str,
+    since: i64,
+    limit: i64,
+) -> Result<Option<WordbookChanges>, sqlx::Error> {
+    let current: Option<i64> =
+        sqlx::query_scalar(r#"SELECT "contentVersion" FROM "word_books" WHERE "id" = $1"#)
+            .bind(word_book_id)
+            .fetch_optional(pool)
+            .await?;
+    let Some(current) = current else {
+        return Ok(None);
+    };
+    let limit = limit.clamp(1, MAX_LIMIT);
+
+    // One extra row per list tells whether the page was cut short.
+    let rows = sqlx::query(
+        r#"
+        SELECT "id", "spelling", "phonetic", "meanings", "examples", "audioUrl",
+               "contentVersion", "updatedAt", "deletedAt"
+        FROM "words"
+        WHERE "wordBookId" = $1 AND "contentVersion" > $2
+        ORDER BY "contentVersion", "id"
+        LIMIT $3
+        "#,
+    )
+    .bind(word_book_id)
+    .bind(since)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+    let tombstones = sqlx::query(
+        r#"
+        SELECT "wordId", "contentVersion", "deletedAt"
+        FROM "word_tombstones"
+        WHERE "wordBookId" = $1 AND "contentVersion" > $2
+        ORDER BY "contentVersion", "wordId"
+        LIMIT $3
+        "#,
+    )
+    .bind(word_book_id)
+    .bind(since)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    let mut words = Vec::new();
+    let mut deleted = Vec::new();
+    for row in &rows {
+        let content_version: i64 = row.try_get("contentVersion")?;
+        let updated_at: NaiveDateTime = row.try_get("updatedAt")?;
+        if let Some(deleted_at) = row.try_get::<Option<NaiveDateTime>, _>("deletedAt")? {
+            deleted.push(DeletedWord {
+                id: row.try_get("id")?,
+                content_version,
+                deleted_at: format_iso(deleted_at),
+            });
+            continue;
+        }
+        words.push(ChangedWord {
+            id: row.try_get("id")?,
+            spelling: row.try_get("spelling")?,
+            phonetic: row.try_get("phonetic")?,
+            meanings: row.try_get("meanings")?,
+            examples: row.try_get("examples")?,
+            audio_url: row.try_get("audioUrl")?,
+            content_version,
+            updated_at: format_iso(updated_at),
+        });
+    }
+    for row in &tombstones {
+        deleted.push(DeletedWord {
+            id: row.try_get("wordId")?,
+            content_version: row.try_get("contentVersion")?,
+            deleted_at: format_iso(row.try_get("deletedAt")?),
+        });
+    }
+    deleted.sort_by_key(|d| d.content_version);
+
+    // Both queries stop at limit + 1 rows, so versions past the shorter
+    // list's last row may be missing from it; cap the page below that point.
+    let mut horizon = i64::MAX;
+    if rows.len() as i64 > limit {
+        horizon = horizon.min(rows.last().map_or(i64::MAX, |r| {
+            r.try_get("contentVersion").unwrap_or(i64::MAX)
+        }));
+    }
+    if tombstones.len() as i64 > limit {
+        horizon = horizon.min(tombstones.last().map_or(i64::MAX, |r| {
+            r.try_get("contentVersion").unwrap_or(i64::MAX)
+        }));
+    }
+    let truncated = horizon != i64::MAX;
+    words.retain(|w| w.content_version < horizon);
+    deleted.retain(|d| d.content_version < horizon);
+
+    let (words, deleted, mut next_since, mut has_more) =
+        merge_page(words, deleted, since, limit as usize, current);
+    if truncated && !has_more {
+        // Everything below the horizon fit; resume from just below it.
+        has_more = true;
+        next_since = horizon - 1;
+    }
+
+    Ok(Some(WordbookChanges {
+        word_book_id: word_book_id.to_string(),
+        content_version: current,
+        since,
+        words,
+        deleted,
+        next_since,
+        has_more,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(id: &str, version: i64) -> ChangedWord {
+        ChangedWord {
+            id: id.to_string(),
+            spelling: id.to_string(),
+            phonetic: String::new(),
+            meanings: vec![],
+            examples: vec![],
+            audio_url: None,
+            content_version: version,
+            updated_at: String::new(),
+        }
+    }
+
+    fn tombstone(id: &str, version: i64) -> DeletedWord {
+        DeletedWord {
+            id: id.to_string(),
+            content_version: version,
+            deleted_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn merge_page_returns_everything_when_under_limit() {
+        let (words, deleted, next, more) = merge_page(
+            vec![word("a", 3), word("b", 5)],
+            vec![tombstone("c", 4)],
+            2,
+            10,
+            5,
+        );
+        assert_eq!(words.len(), 2);
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(next, 5);
+        assert!(!more);
+    }
+
+    #[test]
+    fn merge_page_cuts_in_version_order() {
+        let (words, deleted, next, more) = merge_page(
+            vec![word("a", 1), word("b", 4)],
+            vec![tombstone("c", 2), tombstone("d", 3)],
+            0,
+            2,
+            4,
+        );
+        assert_eq!(words.iter().map(|w| &w.id[..]).collect::<Vec<_>>(), ["a"]);
+        assert_eq!(deleted.iter().map(|d| &d.id[..]).collect::<Vec<_>>(), ["c"]);
+        assert_eq!(next, 2);
+        assert!(more);
+    }
+
+    #[test]
+    fn merge_page_keeps_a_version_together() {
+        let (words, deleted, next, more) = merge_page(
+            vec![word("a", 1), word("b", 2)],
+            vec![tombstone("c", 2), tombstone("d", 3)],
+            0,
+            2,
+            3,
+        );
+        assert_eq!(words.len(), 2);
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(next, 2);
+        assert!(more);
+    }
+
+    #[test]
+    fn merge_page_without_changes_reports_current_version() {
+        let (words, deleted, next, more) = merge_page(vec![], vec![], 7, 10, 9);
+        assert!(words.is_empty() && deleted.is_empty());
+        assert_eq!(next, 9);
+        assert!(!more);
+    }
+}
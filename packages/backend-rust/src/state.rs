@@ -2,6 +2,7 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 
+use danci_algo::FlagSet;
 use tokio::sync::RwLock;
 
 use crate::amas::config::FeatureFlags;
@@ -19,6 +20,7 @@ pub struct RuntimeConfig {
     pub db_slow_enabled: AtomicBool,
     pub db_slow_delay_ms: AtomicU64,
     amas_flags: RwLock<FeatureFlags>,
+    flag_set: RwLock<Option<(FlagSet, Instant)>>,
 }
 
 impl RuntimeConfig {
@@ -30,6 +32,7 @@ impl RuntimeConfig {
             db_slow_enabled: AtomicBool::new(false),
             db_slow_delay_ms: AtomicU64::new(0),
             amas_flags: RwLock::new(FeatureFlags::default()),
+            flag_set: RwLock::new(None),
         }
     }
 
@@ -62,6 +65,16 @@ impl RuntimeConfig {
         *guard = flags;
     }
 
+    /// Cached shared feature-flag definitions and when they were loaded.
+    pub async fn cached_flag_set(&self) -> Option<(FlagSet, Instant)> {
+        self.flag_set.read().await.clone()
+    }
+
+    pub async fn set_flag_set(&self, flags: FlagSet) {
+        let mut guard = self.flag_set.write().await;
+        *guard = Some((flags, Instant::now()));
+    }
+
     pub async fn maybe_db_delay(&self) {
         if self.is_db_slow_enabled() {
             let delay = self.db_slow_delay();
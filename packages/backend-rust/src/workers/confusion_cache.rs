@@ -66,6 +66,11 @@ pub async fn rebuild_confusion_cache(
     }
 
     info!(total = total_saved, "Confusion cache rebuild completed");
+
+    match crate::services::knowledge_graph::rebuild_derived_edges(&db).await {
+        Ok(edges) => info!(edges, "Knowledge graph derived edges rebuilt"),
+        Err(e) => warn!(error = %e, "Knowledge graph edge rebuild failed"),
+    }
     Ok(())
 }
 
@@ -5,11 +5,18 @@ use chrono::Utc;
 use sqlx::{PgPool, Row};
 use tracing::{debug, error, info, warn};
 
+use crate::amas::engine::AMASEngine;
+use crate::amas::types::RewardAttribution;
 use crate::db::DatabaseProxy;
 
 const BATCH_SIZE: i64 = 50;
 const MAX_RETRY: i32 = 3;
 const PROCESSING_TIMEOUT_SECS: i64 = 300;
+/// A review counts as the retention follow-up only after this gap.
+const RETENTION_MIN_GAP_HOURS: i64 = 12;
+/// Give up waiting for a follow-up review after this long; the immediate
+/// reward then stands on its own.
+const RETENTION_MAX_WAIT_DAYS: i64 = 7;
 
 #[derive(Debug)]
 struct RewardTask {
@@ -19,9 +26,20 @@ struct RewardTask {
     reward: f64,
     due_ts: chrono::DateTime<Utc>,
     last_error: Option<String>,
+    kind: String,
+    attribution: Option<RewardAttribution>,
 }
 
-pub async fn process_pending_rewards(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+enum RetentionOutcome {
+    Applied,
+    Waiting,
+    Expired,
+}
+
+pub async fn process_pending_rewards(
+    db: Arc<DatabaseProxy>,
+    engine: Arc<AMASEngine>,
+) -> Result<(), super::WorkerError> {
     let start = Instant::now();
     debug!("Starting delayed reward processing cycle");
 
@@ -41,7 +59,12 @@ pub async fn process_pending_rewards(db: Arc<DatabaseProxy>) -> Result<(), super
     let mut failure_count = 0;
 
     for task in tasks {
-        match process_single_task_atomic(pool, &task).await {
+        let result = if task.kind == "RETENTION" {
+            process_retention_task(pool, &engine, &task).await
+        } else {
+            process_single_task_atomic(pool, &task).await
+        };
+        match result {
             Ok(()) => {
                 success_count += 1;
             }
@@ -109,7 +132,8 @@ async fn claim_pending_tasks(pool: &PgPool) -> Result<Vec<RewardTask>, super::Wo
         SET status = 'PROCESSING'::"RewardStatus", "updatedAt" = $1
         FROM claimed
         WHERE rq.id = claimed.id
-        RETURNING rq.id, rq."userId", rq."answerRecordId", rq.reward, rq."dueTs", rq."lastError"
+        RETURNING rq.id, rq."userId", rq."answerRecordId", rq.reward, rq."dueTs", rq."lastError",
+                  rq."kind", rq."attribution"
         "#,
     )
     .bind(now)
@@ -135,6 +159,14 @@ async fn claim_pending_tasks(pool: &PgPool) -> Result<Vec<RewardTask>, super::Wo
                         reward,
                         due_ts,
                         last_error: row.try_get("lastError").ok(),
+                        kind: row
+                            .try_get::<String, _>("kind")
+                            .unwrap_or_else(|_| "IMMEDIATE".to_string()),
+                        attribution: row
+                            .try_get::<Option<serde_json::Value>, _>("attribution")
+                            .ok()
+                            .flatten()
+                            .and_then(|v| serde_json::from_value(v).ok()),
                     })
                 }
                 _ => {
@@ -209,6 +241,97 @@ async fn process_single_task_atomic(
     Ok(())
 }
 
+/// Delayed half of a two-part reward: the first review of the same word at
+/// least `RETENTION_MIN_GAP_HOURS` after the decision decides the outcome.
+async fn process_retention_task(
+    pool: &PgPool,
+    engine: &AMASEngine,
+    task: &RewardTask,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let attribution = task
+        .attribution
+        .as_ref()
+        .ok_or("retention task without attribution")?;
+    let answer_record_id = task
+        .answer_record_id
+        .as_deref()
+        .ok_or("retention task without answer record")?;
+
+    let outcome = match find_follow_up_outcome(pool, &task.user_id, answer_record_id).await? {
+        Some(is_correct) => {
+            let delayed = if is_correct { 1.0 } else { 0.0 };
+            if engine
+                .apply_delayed_reward(&task.user_id, attribution, delayed)
+                .await
+            {
+                RetentionOutcome::Applied
+            } else {
+                RetentionOutcome::Expired
+            }
+        }
+        None => {
+            let waited_ms = Utc::now().timestamp_millis() - attribution.decided_at;
+            if waited_ms < RETENTION_MAX_WAIT_DAYS * 24 * 3600 * 1000 {
+                RetentionOutcome::Waiting
+            } else {
+                RetentionOutcome::Expired
+            }
+        }
+    };
+
+    match outcome {
+        RetentionOutcome::Waiting => {
+            let next_due = (Utc::now()
+                + chrono::Duration::hours(crate::services::delayed_reward::RETENTION_DELAY_HOURS))
+            .naive_utc();
+            sqlx::query(r#"UPDATE "reward_queue" SET status = 'PENDING'::"RewardStatus", "dueTs" = $1, "updatedAt" = NOW() WHERE id = $2"#)
+                .bind(next_due)
+                .bind(&task.id)
+                .execute(pool)
+                .await?;
+            debug!(task_id = %task.id, "No follow-up review yet, retention reward rescheduled");
+        }
+        RetentionOutcome::Applied | RetentionOutcome::Expired => {
+            let note = matches!(outcome, RetentionOutcome::Expired).then_some("expired");
+            sqlx::query(r#"UPDATE "reward_queue" SET status = 'DONE'::"RewardStatus", "lastError" = $1, "updatedAt" = NOW() WHERE id = $2"#)
+                .bind(note)
+                .bind(&task.id)
+                .execute(pool)
+                .await?;
+            debug!(task_id = %task.id, decision_id = %attribution.decision_id, expired = note.is_some(), "Retention reward resolved");
+        }
+    }
+
+    Ok(())
+}
+
+async fn find_follow_up_outcome(
+    pool: &PgPool,
+    user_id: &str,
+    answer_record_id: &str,
+) -> Result<Option<bool>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT next."isCorrect"
+        FROM "answer_records" ar
+        JOIN "answer_records" next
+          ON next."userId" = ar."userId"
+         AND next."wordId" = ar."wordId"
+         AND next."timestamp" >= ar."timestamp" + make_interval(hours => $3)
+        WHERE ar.id = $1 AND ar."userId" = $2
+        ORDER BY next."timestamp" ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(answer_record_id)
+    .bind(user_id)
+    .bind(RETENTION_MIN_GAP_HOURS as i32)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.and_then(|r| r.try_get::<bool, _>("isCorrect").ok()))
+}
+
 async fn handle_task_failure(
     pool: &PgPool,
     task: &RewardTask,
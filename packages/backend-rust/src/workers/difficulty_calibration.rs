@@ -0,0 +1,15 @@
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::db::DatabaseProxy;
+use crate::services::difficulty_calibration;
+
+pub async fn recalibrate(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let report = difficulty_calibration::calibrate_all(db.pool()).await?;
+    info!(
+        words = report.words_calibrated,
+        "Word difficulty calibration completed"
+    );
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::db::DatabaseProxy;
+use crate::services::due_queue;
+
+pub async fn rebuild_due_queues(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let report = due_queue::refresh_all(db.pool()).await?;
+    if report.failed > 0 {
+        warn!(
+            users = report.users,
+            failed = report.failed,
+            "Due queue rebuild finished with failures"
+        );
+    } else {
+        info!(
+            users = report.users,
+            entries = report.entries,
+            "Due queues rebuilt"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use tracing::{debug, info, warn};
+
+use crate::db::DatabaseProxy;
+use crate::routes::notifications::{create_notification, CreateNotificationInput};
+use crate::services::alerts::{alert_monitoring_service, AlertEvent, AlertSeverity, AlertStatus};
+use crate::services::experiment_guardrails::{self, GuardrailConfig, GuardrailDecision};
+
+pub async fn check_guardrails(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let config = GuardrailConfig::from_env();
+    let report = experiment_guardrails::run_guardrails(db.pool(), &config).await?;
+
+    if report.decisions.is_empty() {
+        debug!(
+            experiments = report.experiments,
+            variants = report.variants_checked,
+            "Experiment guardrails passed"
+        );
+        return Ok(());
+    }
+
+    for decision in &report.decisions {
+        warn!(
+            experiment_id = %decision.experiment_id,
+            variant_id = %decision.variant_id,
+            metric = %decision.metric,
+            relative_change = decision.relative_change,
+            reassigned_users = decision.reassigned_users,
+            "Experiment variant disabled by guardrail"
+        );
+        notify_admins(&db, decision).await;
+    }
+    info!(
+        experiments = report.experiments,
+        variants = report.variants_checked,
+        disabled = report.decisions.len(),
+        "Experiment guardrail check completed"
+    );
+    Ok(())
+}
+
+async fn notify_admins(db: &DatabaseProxy, decision: &GuardrailDecision) {
+    let message = format!(
+        "实验「{}」的变体「{}」已被自动停用：{}，{} 名用户已改回对照组",
+        decision.experiment_name, decision.variant_name, decision.reason, decision.reassigned_users
+    );
+
+    alert_monitoring_service().fire_alert(AlertEvent {
+        id: format!("alert_experiment_guardrail_{}", decision.id),
+        rule_id: format!("experiment_guardrail:{}", decision.variant_id),
+        rule_name: "Experiment Guardrail".to_string(),
+        metric: decision.metric.clone(),
+        severity: AlertSeverity::Critical,
+        status: AlertStatus::Firing,
+        message: message.clone(),
+        value: decision.relative_change,
+        threshold: None,
+        triggered_at: Utc::now().to_rfc3339(),
+        resolved_at: None,
+    });
+
+    let admins: Vec<String> =
+        match sqlx::query_scalar(r#"SELECT "id" FROM "users" WHERE "role"::text = 'ADMIN'"#)
+            .fetch_all(db.pool())
+            .await
+        {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!(error = %e, "Failed to load admins for guardrail notification");
+                return;
+            }
+        };
+
+    for admin_id in admins {
+        if let Err(e) = create_notification(
+            db,
+            CreateNotificationInput {
+                user_id: admin_id,
+                notification_type: "ALERT".to_string(),
+                title: "实验变体已被护栏停用".to_string(),
+                content: message.clone(),
+                priority: "HIGH".to_string(),
+                metadata: serde_json::to_value(decision).ok(),
+            },
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to create guardrail notification");
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Monthly offline tuning of the bandit hyperparameters.
+//!
+//! For each activity cohort the logged AMAS decisions of the last
+//! `TUNING_WINDOW_DAYS` are replayed through `danci_algo::tune_hyperparameters`
+//! and the best alpha / lambda / Beta prior is stored as a PENDING row in
+//! `algorithm_config_drafts`. Nothing is applied automatically; an admin
+//! reviews the draft and its uplift estimate first.
+//!
+//! Only decisions randomized by IGE's uniform exploration draw are replayed:
+//! they carry a logged `propensity`, so matched decisions are importance
+//! weighted and the estimate is unbiased. Greedy picks depend on the model
+//! that made them and would bias the comparison, so they are left out along
+//! with simulated decisions.
+
+use std::sync::Arc;
+
+use danci_algo::{tune_hyperparameters, LoggedDecision, TuningConfig, TuningGrid, TuningResult};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::DatabaseProxy;
+
+const TUNING_WINDOW_DAYS: i32 = 90;
+const MAX_DECISIONS_PER_COHORT: i64 = 50_000;
+const MIN_DECISIONS_PER_COHORT: usize = 200;
+
+/// Cohorts by decisions per user inside the window, matching the simulator's
+/// `casual` / `regular` / `intensive` cohorts.
+const COHORTS: [(&str, i64, i64); 3] = [
+    ("casual", 0, 50),
+    ("regular", 50, 300),
+    ("intensive", 300, i64::MAX),
+];
+
+/// Arms are the strategy difficulty levels.
+const ACTIONS: [&str; 3] = ["easy", "mid", "hard"];
+
+pub async fn run_monthly_tuning(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let pool = db.pool();
+    let base_config_id: Option<String> = sqlx::query_scalar(
+        r#"SELECT "id" FROM "algorithm_configs" WHERE "isDefault" = true LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    for (cohort, min_decisions, max_decisions) in COHORTS {
+        let log = load_cohort_log(pool, min_decisions, max_decisions).await?;
+        if log.len() < MIN_DECISIONS_PER_COHORT {
+            info!(
+                cohort,
+                decisions = log.len(),
+                "Not enough logged decisions for tuning, skipping cohort"
+            );
+            continue;
+        }
+
+        let decisions = log.len();
+        let result = tokio::task::spawn_blocking(move || {
+            tune_hyperparameters(
+                &log,
+                ACTIONS.len() as u32,
+                &TuningGrid::default(),
+                &TuningConfig::default(),
+            )
+        })
+        .await
+        .map_err(|e| super::WorkerError::Custom(e.to_string()))?;
+
+        match result {
+            Ok(result) => {
+                insert_draft(pool, cohort, base_config_id.as_deref(), &result).await?;
+                info!(
+                    cohort,
+                    decisions,
+                    alpha = result.best.candidate.alpha,
+                    lambda = result.best.candidate.lambda,
+                    uplift = result.best.uplift,
+                    "Hyperparameter tuning draft written"
+                );
+            }
+            Err(e) => warn!(cohort, error = %e, "Hyperparameter tuning produced no candidate"),
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_cohort_log(
+    pool: &PgPool,
+    min_decisions: i64,
+    max_decisions: i64,
+) -> Result<Vec<LoggedDecision>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        WITH logged AS (
+            SELECT di."user_id", dr."timestamp", dr."selectedAction", dr."reward",
+                   dr."propensity", di."state_snapshot"
+            FROM "decision_records" dr
+            JOIN "decision_insights" di ON di."decision_id" = dr."decisionId"
+            WHERE dr."timestamp" >= NOW() - make_interval(days => $1)
+              AND dr."reward" IS NOT NULL
+              AND COALESCE(dr."isSimulation", false) = false
+              AND dr."propensity" > 0
+        ),
+        activity AS (
+            SELECT "user_id", COUNT(*) AS "decisions" FROM logged GROUP BY "user_id"
+        )
+        SELECT l."selectedAction", l."reward", l."propensity", l."state_snapshot"
+        FROM logged l
+        JOIN activity a ON a."user_id" = l."user_id"
+        WHERE a."decisions" >= $2 AND a."decisions" < $3
+        ORDER BY l."timestamp" DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(TUNING_WINDOW_DAYS)
+    .bind(min_decisions)
+    .bind(max_decisions)
+    .bind(MAX_DECISIONS_PER_COHORT)
+    .fetch_all(pool)
+    .await?;
+
+    // Newest rows were kept by the LIMIT; replay needs chronological order.
+    let mut log: Vec<LoggedDecision> = rows
+        .iter()
+        .filter_map(|row| {
+            let action: String = row.try_get("selectedAction").ok()?;
+            let reward: f64 = row.try_get("reward").ok()?;
+            let propensity: f64 = row.try_get("propensity").ok()?;
+            let state: serde_json::Value = row.try_get("state_snapshot").ok()?;
+            logged_decision(&action, reward, propensity, &state)
+        })
+        .collect();
+    log.reverse();
+    Ok(log)
+}
+
+fn logged_decision(
+    selected_action: &str,
+    reward: f64,
+    propensity: f64,
+    state: &serde_json::Value,
+) -> Option<LoggedDecision> {
+    let strategy: serde_json::Value = serde_json::from_str(selected_action).ok()?;
+    let difficulty = strategy.get("difficulty")?.as_str()?;
+    let action = ACTIONS.iter().position(|a| *a == difficulty)? as u32;
+    let feature = |key: &str| state.get(key).and_then(|v| v.as_f64()).unwrap_or(0.5);
+    Some(LoggedDecision {
+        context: vec![
+            1.0,
+            feature("attention"),
+            feature("fatigue"),
+            feature("motivation"),
+        ],
+        action,
+        // Stored rewards are in [-1, 1]; the tuner expects [0, 1].
+        reward: (reward.clamp(-1.0, 1.0) + 1.0) / 2.0,
+        propensity: Some(propensity),
+    })
+}
+
+async fn insert_draft(
+    pool: &PgPool,
+    cohort: &str,
+    base_config_id: Option<&str>,
+    result: &TuningResult,
+) -> Result<(), sqlx::Error> {
+    let best = &result.best;
+    let parameters = json!({
+        "alpha": best.candidate.alpha,
+        "lambda": best.candidate.lambda,
+        "priorAlpha": best.candidate.prior.alpha,
+        "priorBeta": best.candidate.prior.beta,
+    });
+    let metrics = json!({
+        "value": best.value,
+        "stdError": best.std_error,
+        "uplift": best.uplift,
+        "upliftStdError": best.uplift_std_error,
+        "matches": best.matches,
+        "baselineValue": result.baseline_value,
+        "baselineStdError": result.baseline_std_error,
+        "trainingSize": result.training_size,
+        "holdoutSize": result.holdout_size,
+        "candidatesEvaluated": result.candidates.len(),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO "algorithm_config_drafts"
+            ("id", "cohort", "source", "baseConfigId", "parameters", "metrics")
+        VALUES ($1, $2, 'auto_tuning', $3, $4, $5)
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(cohort)
+    .bind(base_config_id)
+    .bind(parameters)
+    .bind(metrics)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logged_decision_maps_strategy_and_reward() {
+        let state = json!({"attention": 0.8, "fatigue": 0.2});
+        let decision =
+            logged_decision(r#"{"difficulty":"hard","batch_size":8}"#, 0.5, 0.25, &state)
+                .expect("decision");
+        assert_eq!(decision.action, 2);
+        assert_eq!(decision.context, vec![1.0, 0.8, 0.2, 0.5]);
+        assert!((decision.reward - 0.75).abs() < 1e-12);
+        assert_eq!(decision.propensity, Some(0.25));
+
+        assert!(logged_decision(r#"{"difficulty":"extreme"}"#, 0.0, 0.5, &state).is_none());
+        assert!(logged_decision("not json", 0.0, 0.5, &state).is_none());
+    }
+}
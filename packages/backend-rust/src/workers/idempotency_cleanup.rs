@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use tracing::{debug, info};
+
+use crate::db::DatabaseProxy;
+use crate::services::idempotency::IdempotencyStore;
+
+pub async fn purge_expired_keys(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let Some(store) = IdempotencyStore::select(db.as_ref()).await else {
+        debug!("No store available for idempotency key cleanup");
+        return Ok(());
+    };
+    let purged = store.purge_expired().await?;
+    if purged > 0 {
+        info!(purged, "Expired idempotency keys purged");
+    }
+    Ok(())
+}
@@ -6,13 +6,23 @@ mod amas_health_analyzer;
 pub mod clustering;
 pub mod confusion_cache;
 mod delayed_reward;
+mod difficulty_calibration;
+mod due_queue;
 mod embedding_worker;
 mod etymology;
+mod experiment_guardrail;
 mod forgetting_alert;
+mod hyperparameter_tuning;
+mod idempotency_cleanup;
 mod llm_advisor;
 mod log_export;
+mod notification_delivery;
 mod optimization;
 mod session_cleanup;
+mod stats_rollup;
+pub mod ticks;
+mod timeline;
+mod webhook_delivery;
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -94,22 +104,62 @@ impl WorkerManager {
             .map(|v| v != "false" && v != "0")
             .unwrap_or(true);
 
+        let enable_webhook_delivery = std::env::var("ENABLE_WEBHOOK_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_notification_delivery = std::env::var("ENABLE_NOTIFICATION_DELIVERY_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_idempotency_cleanup = std::env::var("ENABLE_IDEMPOTENCY_CLEANUP_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_stats_rollup = std::env::var("ENABLE_STATS_ROLLUP_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_timeline = std::env::var("ENABLE_TIMELINE_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_difficulty_calibration = std::env::var("ENABLE_DIFFICULTY_CALIBRATION_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_due_queue = std::env::var("ENABLE_DUE_QUEUE_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
         let enable_etymology = std::env::var("ENABLE_ETYMOLOGY_WORKER")
             .map(|v| v == "true" || v == "1")
             .unwrap_or(false);
 
+        let enable_hyperparameter_tuning = std::env::var("ENABLE_HYPERPARAMETER_TUNING_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let enable_experiment_guardrail = std::env::var("ENABLE_EXPERIMENT_GUARDRAIL_WORKER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
         let scheduler = self.scheduler.lock().await;
 
         if enable_delayed_reward {
             let db = Arc::clone(&self.db_proxy);
+            let amas = Arc::clone(&self.amas_engine);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("delayed_reward", "0 * * * * *");
             let job = Job::new_async("0 * * * * *", move |_uuid, _lock| {
                 let db = Arc::clone(&db);
+                let amas = Arc::clone(&amas);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("delayed_reward");
                     tokio::select! {
                         _ = rx.recv() => {},
-                        result = delayed_reward::process_pending_rewards(db) => {
+                        result = delayed_reward::process_pending_rewards(db, amas) => {
                             if let Err(e) = result {
                                 error!(error = %e, "Delayed reward worker error");
                             }
@@ -127,10 +177,12 @@ impl WorkerManager {
                 .unwrap_or_else(|_| "0 0 3 * * *".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("optimization", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("optimization");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = optimization::run_optimization_cycle(db) => {
@@ -151,10 +203,12 @@ impl WorkerManager {
                 std::env::var("LLM_ADVISOR_SCHEDULE").unwrap_or_else(|_| "0 0 4 * * 0".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("llm_advisor", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("llm_advisor");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = llm_advisor::run_weekly_analysis(db) => {
@@ -175,10 +229,12 @@ impl WorkerManager {
                 .unwrap_or_else(|_| "0 0 2 * * *".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("forgetting_alert", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("forgetting_alert");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = forgetting_alert::scan_forgetting_risks(db) => {
@@ -199,10 +255,12 @@ impl WorkerManager {
                 std::env::var("ETYMOLOGY_SCHEDULE").unwrap_or_else(|_| "0 30 3 * * *".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("etymology", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("etymology");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = etymology::run_etymology_analysis(db) => {
@@ -218,6 +276,235 @@ impl WorkerManager {
             info!(schedule = %schedule, "Etymology worker scheduled");
         }
 
+        if enable_hyperparameter_tuning {
+            let schedule = std::env::var("HYPERPARAMETER_TUNING_SCHEDULE")
+                .unwrap_or_else(|_| "0 0 5 1 * *".to_string());
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("hyperparameter_tuning", &schedule);
+            let job = Job::new_async(&schedule, move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("hyperparameter_tuning");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = hyperparameter_tuning::run_monthly_tuning(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Hyperparameter tuning worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!(schedule = %schedule, "Hyperparameter tuning worker scheduled");
+        }
+
+        if enable_webhook_delivery {
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("webhook_delivery", "30 * * * * *");
+            let job = Job::new_async("30 * * * * *", move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("webhook_delivery");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = webhook_delivery::process_pending_deliveries(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Webhook delivery worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!("Webhook delivery worker scheduled (every minute)");
+        }
+
+        if enable_notification_delivery {
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("notification_delivery", "45 * * * * *");
+            let job = Job::new_async("45 * * * * *", move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("notification_delivery");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = notification_delivery::process_pending_deliveries(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Notification delivery worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!("Notification delivery worker scheduled (every minute)");
+        }
+
+        if enable_idempotency_cleanup {
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("idempotency_cleanup", "0 15 * * * *");
+            let job = Job::new_async("0 15 * * * *", move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("idempotency_cleanup");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = idempotency_cleanup::purge_expired_keys(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Idempotency cleanup worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!("Idempotency cleanup worker scheduled (hourly)");
+        }
+
+        if enable_stats_rollup {
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("stats_rollup", "30 * * * * *");
+            let job = Job::new_async("30 * * * * *", move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("stats_rollup");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = stats_rollup::refresh_rollups(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Stats rollup worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!("Stats rollup worker scheduled (every minute)");
+        }
+
+        // Timeline rollup - every 15 minutes, so days close soon after local
+        // midnight in zones with :30 and :45 offsets too
+        if enable_timeline {
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("timeline", "0 1/15 * * * *");
+            let job = Job::new_async("0 1/15 * * * *", move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("timeline");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = timeline::roll_up_timelines(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Timeline rollup worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!("Timeline rollup worker scheduled (every 15 minutes)");
+        }
+
+        // Due queue rebuild - just after UTC midnight, when every queue expires
+        if enable_due_queue {
+            let schedule =
+                std::env::var("DUE_QUEUE_SCHEDULE").unwrap_or_else(|_| "0 5 0 * * *".to_string());
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("due_queue", &schedule);
+            let job = Job::new_async(&schedule, move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("due_queue");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = due_queue::rebuild_due_queues(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Due queue worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!(schedule = %schedule, "Due queue worker scheduled");
+        }
+
+        // Word difficulty calibration - daily at 04:30 by default
+        if enable_difficulty_calibration {
+            let schedule = std::env::var("DIFFICULTY_CALIBRATION_SCHEDULE")
+                .unwrap_or_else(|_| "0 30 4 * * *".to_string());
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("difficulty_calibration", &schedule);
+            let job = Job::new_async(&schedule, move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("difficulty_calibration");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = difficulty_calibration::recalibrate(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Difficulty calibration worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!(schedule = %schedule, "Difficulty calibration worker scheduled");
+        }
+
+        // Experiment guardrails - every 30 minutes by default
+        if enable_experiment_guardrail {
+            let schedule = std::env::var("EXPERIMENT_GUARDRAIL_SCHEDULE")
+                .unwrap_or_else(|_| "0 */30 * * * *".to_string());
+            let db = Arc::clone(&self.db_proxy);
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("experiment_guardrail", &schedule);
+            let job = Job::new_async(&schedule, move |_uuid, _lock| {
+                let db = Arc::clone(&db);
+                let mut rx = shutdown_rx.resubscribe();
+                Box::pin(async move {
+                    ticks::record("experiment_guardrail");
+                    tokio::select! {
+                        _ = rx.recv() => {},
+                        result = experiment_guardrail::check_guardrails(db) => {
+                            if let Err(e) = result {
+                                error!(error = %e, "Experiment guardrail worker error");
+                            }
+                        }
+                    }
+                })
+            })
+            .map_err(WorkerError::Scheduler)?;
+            scheduler.add(job).await.map_err(WorkerError::Scheduler)?;
+            info!(schedule = %schedule, "Experiment guardrail worker scheduled");
+        }
+
         // AMAS cache cleanup - runs every 10 minutes
         {
             let amas = Arc::clone(&self.amas_engine);
@@ -226,10 +513,12 @@ impl WorkerManager {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(30 * 60 * 1000); // 30 minutes default
+            ticks::register("amas_cache_cleanup", "0 */10 * * * *");
             let job = Job::new_async("0 */10 * * * *", move |_uuid, _lock| {
                 let amas = Arc::clone(&amas);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("amas_cache_cleanup");
                     tokio::select! {
                         _ = rx.recv() => {},
                         _ = async {
@@ -255,10 +544,12 @@ impl WorkerManager {
         if enable_amas_monitoring {
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("amas_aggregation_15min", "0 */15 * * * *");
             let job = Job::new_async("0 */15 * * * *", move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("amas_aggregation_15min");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = amas_aggregation::aggregate_15min(db) => {
@@ -278,10 +569,12 @@ impl WorkerManager {
         if enable_amas_monitoring {
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("amas_aggregation_daily", "0 0 1 * * *");
             let job = Job::new_async("0 0 1 * * *", move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("amas_aggregation_daily");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = amas_aggregation::aggregate_daily(db) => {
@@ -307,10 +600,12 @@ impl WorkerManager {
                 .unwrap_or_else(|_| "0 0 5 * * 1".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("amas_health_analyzer", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("amas_health_analyzer");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = amas_health_analyzer::run_weekly_health_analysis(db) => {
@@ -334,11 +629,13 @@ impl WorkerManager {
                 std::env::var("LOG_EXPORT_DIR").unwrap_or_else(|_| "./logs/exports".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("log_export", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let export_dir = export_dir.clone();
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("log_export");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = log_export::export_system_logs(db, &export_dir) => {
@@ -361,11 +658,13 @@ impl WorkerManager {
                 crate::amas::metrics_persistence::AlgorithmMetricsPersistor::new(),
             ));
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("algorithm_metrics", "0 */5 * * * *");
             let job = Job::new_async("0 */5 * * * *", move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let persistor = Arc::clone(&persistor);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("algorithm_metrics");
                     tokio::select! {
                         _ = rx.recv() => {},
                         _ = algorithm_metrics::flush_metrics(db, persistor) => {}
@@ -387,10 +686,12 @@ impl WorkerManager {
                 std::env::var("EMBEDDING_SCHEDULE").unwrap_or_else(|_| "0 */5 * * * *".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("embedding", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("embedding");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = embedding_worker::process_pending_embeddings(db) => {
@@ -416,10 +717,12 @@ impl WorkerManager {
                 std::env::var("CLUSTERING_SCHEDULE").unwrap_or_else(|_| "0 0 4 * * 0".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("clustering", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("clustering");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = clustering::run_clustering_cycle(db) => {
@@ -445,10 +748,12 @@ impl WorkerManager {
                 .unwrap_or_else(|_| "0 0 5 * * 0".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("confusion_cache", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("confusion_cache");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = confusion_cache::rebuild_confusion_cache(db) => {
@@ -474,10 +779,12 @@ impl WorkerManager {
                 .unwrap_or_else(|_| "0 0 6 * * 1".to_string());
             let db = Arc::clone(&self.db_proxy);
             let shutdown_rx = self.shutdown_tx.subscribe();
+            ticks::register("weekly_report", &schedule);
             let job = Job::new_async(&schedule, move |_uuid, _lock| {
                 let db = Arc::clone(&db);
                 let mut rx = shutdown_rx.resubscribe();
                 Box::pin(async move {
+                    ticks::record("weekly_report");
                     tokio::select! {
                         _ = rx.recv() => {},
                         result = crate::services::weekly_report::generate_report(&db) => {
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Utc;
+use sqlx::{PgPool, Row};
+use tracing::{debug, info, warn};
+
+use crate::db::DatabaseProxy;
+use crate::services::email_provider::EmailService;
+use crate::services::notification_delivery::DeliveryChannel;
+use crate::services::push_provider::{PushPlatform, PushService};
+
+const BATCH_SIZE: i64 = 50;
+const MAX_ATTEMPTS: i32 = 6;
+const SENDING_TIMEOUT_SECS: i64 = 300;
+const BASE_BACKOFF_SECS: i64 = 60;
+const MAX_BACKOFF_SECS: i64 = 6 * 3600;
+const MAX_ERROR_LEN: usize = 500;
+
+struct DeliveryTask {
+    id: String,
+    channel: String,
+    platform: Option<String>,
+    recipient: String,
+    subject: String,
+    body: String,
+    attempts: i32,
+}
+
+/// Exponential backoff before attempt `attempts + 1`.
+fn backoff_secs(attempts: i32) -> i64 {
+    let exp = attempts.clamp(0, 20) as u32;
+    BASE_BACKOFF_SECS
+        .saturating_mul(1_i64 << exp)
+        .min(MAX_BACKOFF_SECS)
+}
+
+pub async fn process_pending_deliveries(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let start = Instant::now();
+    let pool = db.pool();
+
+    recover_stuck_deliveries(pool).await?;
+
+    let tasks = claim_due_deliveries(pool).await?;
+    if tasks.is_empty() {
+        debug!("No pending notification deliveries");
+        return Ok(());
+    }
+
+    let email = EmailService::from_env();
+    let push = PushService::from_env();
+
+    let mut sent = 0;
+    let mut failed = 0;
+    for task in tasks {
+        match send(&email, &push, &task).await {
+            Ok(()) => {
+                mark_sent(pool, &task.id).await?;
+                sent += 1;
+            }
+            Err(error) => {
+                warn!(delivery_id = %task.id, channel = %task.channel, attempts = task.attempts + 1, error = %error, "Notification delivery failed");
+                mark_failed(pool, &task, &error).await?;
+                failed += 1;
+            }
+        }
+    }
+
+    info!(
+        sent,
+        failed,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Notification delivery cycle completed"
+    );
+    Ok(())
+}
+
+async fn send(email: &EmailService, push: &PushService, task: &DeliveryTask) -> Result<(), String> {
+    match DeliveryChannel::parse(&task.channel) {
+        Some(DeliveryChannel::Email) => email
+            .send_email(&task.recipient, &task.subject, &task.body)
+            .await
+            .map_err(|e| e.to_string()),
+        Some(DeliveryChannel::Push) => {
+            let platform = task
+                .platform
+                .as_deref()
+                .and_then(PushPlatform::parse)
+                .ok_or_else(|| "missing push platform".to_string())?;
+            push.send_push(platform, &task.recipient, &task.subject, &task.body)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        None => Err(format!("unknown channel: {}", task.channel)),
+    }
+}
+
+async fn recover_stuck_deliveries(pool: &PgPool) -> Result<(), super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    let cutoff = now - chrono::Duration::seconds(SENDING_TIMEOUT_SECS);
+    let result = sqlx::query(
+        r#"UPDATE "notification_deliveries"
+           SET "status" = 'PENDING', "updatedAt" = $1
+           WHERE "status" = 'SENDING' AND "updatedAt" < $2"#,
+    )
+    .bind(now)
+    .bind(cutoff)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() > 0 {
+        warn!(
+            count = result.rows_affected(),
+            "Recovered stuck notification deliveries"
+        );
+    }
+    Ok(())
+}
+
+async fn claim_due_deliveries(pool: &PgPool) -> Result<Vec<DeliveryTask>, super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    let rows = sqlx::query(
+        r#"
+        WITH claimed AS (
+            SELECT "id" FROM "notification_deliveries"
+            WHERE "status" = 'PENDING' AND "nextAttemptAt" <= $1
+            ORDER BY "nextAttemptAt" ASC
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        UPDATE "notification_deliveries" d
+        SET "status" = 'SENDING', "updatedAt" = $1
+        FROM claimed
+        WHERE d."id" = claimed."id"
+        RETURNING d."id", d."channel", d."platform", d."recipient", d."subject", d."body", d."attempts"
+        "#,
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| DeliveryTask {
+            id: row.try_get("id").unwrap_or_default(),
+            channel: row.try_get("channel").unwrap_or_default(),
+            platform: row.try_get("platform").ok().flatten(),
+            recipient: row.try_get("recipient").unwrap_or_default(),
+            subject: row.try_get("subject").unwrap_or_default(),
+            body: row.try_get("body").unwrap_or_default(),
+            attempts: row.try_get("attempts").unwrap_or(0),
+        })
+        .collect())
+}
+
+async fn mark_sent(pool: &PgPool, id: &str) -> Result<(), super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    sqlx::query(
+        r#"UPDATE "notification_deliveries"
+           SET "status" = 'SENT', "attempts" = "attempts" + 1, "lastError" = NULL,
+               "deliveredAt" = $2, "updatedAt" = $2
+           WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn mark_failed(
+    pool: &PgPool,
+    task: &DeliveryTask,
+    error: &str,
+) -> Result<(), super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    let attempts = task.attempts + 1;
+    let (next_status, next_attempt) = if attempts >= MAX_ATTEMPTS {
+        ("FAILED", now)
+    } else {
+        (
+            "PENDING",
+            now + chrono::Duration::seconds(backoff_secs(task.attempts)),
+        )
+    };
+    let error: String = error.chars().take(MAX_ERROR_LEN).collect();
+
+    sqlx::query(
+        r#"UPDATE "notification_deliveries"
+           SET "status" = $2, "attempts" = $3, "nextAttemptAt" = $4,
+               "lastError" = $5, "updatedAt" = $6
+           WHERE "id" = $1"#,
+    )
+    .bind(&task.id)
+    .bind(next_status)
+    .bind(attempts)
+    .bind(next_attempt)
+    .bind(error)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_secs(0), 60);
+        assert_eq!(backoff_secs(3), 480);
+        assert_eq!(backoff_secs(40), MAX_BACKOFF_SECS);
+    }
+}
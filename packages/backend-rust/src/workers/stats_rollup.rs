@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use tracing::{debug, info};
+
+use crate::db::DatabaseProxy;
+use crate::services::stats_rollup;
+
+pub async fn refresh_rollups(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let report = stats_rollup::refresh_user_daily_stats(db.pool()).await?;
+    if report.skipped {
+        debug!("Previous stats rollup refresh still running, skipping");
+    } else if report.rows_upserted > 0 {
+        info!(
+            rows = report.rows_upserted,
+            watermark = ?report.watermark,
+            "Statistics rollup refreshed"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Last-run bookkeeping for scheduled workers, read by the deep health check.
+//!
+//! Each job is registered with its cron expression when it is scheduled and
+//! records a tick whenever it fires. A worker that has not fired yet counts
+//! its age from registration.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+struct TickEntry {
+    schedule: String,
+    expected_interval: Option<Duration>,
+    registered_at: Instant,
+    last_tick: Option<Instant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkerTick {
+    pub name: &'static str,
+    pub schedule: String,
+    /// Gap between two consecutive fire times of the schedule.
+    pub expected_interval: Option<Duration>,
+    /// Time since the last tick, or since registration if it never fired.
+    pub age: Duration,
+    pub ticked: bool,
+}
+
+fn registry() -> &'static Mutex<HashMap<&'static str, TickEntry>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<&'static str, TickEntry>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn register(name: &'static str, schedule: &str) {
+    let entry = TickEntry {
+        schedule: schedule.to_string(),
+        expected_interval: expected_interval(schedule),
+        registered_at: Instant::now(),
+        last_tick: None,
+    };
+    if let Ok(mut map) = registry().lock() {
+        map.insert(name, entry);
+    }
+}
+
+pub fn record(name: &'static str) {
+    if let Ok(mut map) = registry().lock() {
+        if let Some(entry) = map.get_mut(name) {
+            entry.last_tick = Some(Instant::now());
+        }
+    }
+}
+
+pub fn snapshot() -> Vec<WorkerTick> {
+    let Ok(map) = registry().lock() else {
+        return Vec::new();
+    };
+    let mut ticks: Vec<WorkerTick> = map
+        .iter()
+        .map(|(name, entry)| WorkerTick {
+            name,
+            schedule: entry.schedule.clone(),
+            expected_interval: entry.expected_interval,
+            age: entry.last_tick.unwrap_or(entry.registered_at).elapsed(),
+            ticked: entry.last_tick.is_some(),
+        })
+        .collect();
+    ticks.sort_by_key(|t| t.name);
+    ticks
+}
+
+fn expected_interval(schedule: &str) -> Option<Duration> {
+    let schedule = cron::Schedule::from_str(schedule).ok()?;
+    let mut upcoming = schedule.upcoming(chrono::Utc);
+    let first = upcoming.next()?;
+    let second = upcoming.next()?;
+    (second - first).to_std().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_interval_from_cron() {
+        assert_eq!(
+            expected_interval("0 * * * * *"),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            expected_interval("0 */15 * * * *"),
+            Some(Duration::from_secs(15 * 60))
+        );
+        assert_eq!(
+            expected_interval("0 5 0 * * *"),
+            Some(Duration::from_secs(24 * 3600))
+        );
+        assert_eq!(expected_interval("not a schedule"), None);
+    }
+
+    #[test]
+    fn test_record_updates_registered_worker() {
+        register("ticks_test_worker", "0 * * * * *");
+        record("ticks_test_unknown");
+        let before = snapshot()
+            .into_iter()
+            .find(|t| t.name == "ticks_test_worker")
+            .unwrap();
+        assert!(!before.ticked);
+
+        record("ticks_test_worker");
+        let after = snapshot()
+            .into_iter()
+            .find(|t| t.name == "ticks_test_worker")
+            .unwrap();
+        assert!(after.ticked);
+        assert!(after.age <= before.age + Duration::from_secs(1));
+        assert!(snapshot().iter().all(|t| t.name != "ticks_test_unknown"));
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use tracing::{debug, info, warn};
+
+use crate::db::DatabaseProxy;
+use crate::services::timeline;
+
+pub async fn roll_up_timelines(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let report = timeline::roll_up_closed_days(db.pool()).await?;
+    if report.skipped {
+        debug!("Previous timeline rollup still running, skipping");
+    } else if report.failed > 0 {
+        warn!(
+            users = report.users,
+            failed = report.failed,
+            "Timeline rollup finished with failures"
+        );
+    } else if report.users > 0 {
+        info!(
+            users = report.users,
+            entries = report.entries,
+            "Timeline days rolled up"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use sqlx::{PgPool, Row};
+use tracing::{debug, info, warn};
+
+use crate::db::DatabaseProxy;
+use crate::services::webhooks::{
+    resolve_target, sign_payload, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+
+const BATCH_SIZE: i64 = 50;
+const MAX_ATTEMPTS: i32 = 8;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Bounds one attempt including target resolution.
+const SEND_TIMEOUT_SECS: u64 = 30;
+/// No new send starts after this much of a cycle has passed; the rest of the
+/// batch goes back to PENDING. A cycle therefore ends well before
+/// `SENDING_TIMEOUT_SECS`, so recovery never re-sends a delivery in flight.
+const CYCLE_BUDGET_SECS: u64 = 120;
+const SENDING_TIMEOUT_SECS: i64 = 300;
+const _: () = assert!(
+    REQUEST_TIMEOUT_SECS < SEND_TIMEOUT_SECS
+        && ((CYCLE_BUDGET_SECS + SEND_TIMEOUT_SECS) as i64) < SENDING_TIMEOUT_SECS
+);
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 6 * 3600;
+const MAX_ERROR_LEN: usize = 500;
+
+struct DeliveryTask {
+    id: String,
+    event_type: String,
+    payload: serde_json::Value,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+/// Set while a cycle runs so a slow cycle is not overlapped by the next tick.
+static CYCLE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+struct CycleGuard;
+
+impl CycleGuard {
+    fn acquire() -> Option<Self> {
+        CYCLE_RUNNING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self)
+    }
+}
+
+impl Drop for CycleGuard {
+    fn drop(&mut self) {
+        CYCLE_RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// Exponential backoff before attempt `attempts + 1`.
+fn backoff_secs(attempts: i32) -> i64 {
+    let exp = attempts.clamp(0, 20) as u32;
+    BASE_BACKOFF_SECS
+        .saturating_mul(1_i64 << exp)
+        .min(MAX_BACKOFF_SECS)
+}
+
+pub async fn process_pending_deliveries(db: Arc<DatabaseProxy>) -> Result<(), super::WorkerError> {
+    let Some(_cycle) = CycleGuard::acquire() else {
+        debug!("Previous webhook delivery cycle still running");
+        return Ok(());
+    };
+    let start = Instant::now();
+    let budget = Duration::from_secs(CYCLE_BUDGET_SECS);
+    let pool = db.pool();
+
+    recover_stuck_deliveries(pool).await?;
+
+    let tasks = claim_due_deliveries(pool).await?;
+    if tasks.is_empty() {
+        debug!("No pending webhook deliveries");
+        return Ok(());
+    }
+
+    let mut delivered = 0;
+    let mut failed = 0;
+    let mut deferred = 0;
+    for task in tasks {
+        if start.elapsed() >= budget {
+            release(pool, &task.id).await?;
+            deferred += 1;
+            continue;
+        }
+        // Recovery measures from the start of this attempt, not the claim.
+        if !touch_sending(pool, &task.id).await? {
+            continue;
+        }
+        let outcome = tokio::time::timeout(Duration::from_secs(SEND_TIMEOUT_SECS), send(&task))
+            .await
+            .unwrap_or_else(|_| Err((None, "delivery timed out".to_string())));
+        match outcome {
+            Ok(status) => {
+                mark_delivered(pool, &task.id, status).await?;
+                delivered += 1;
+            }
+            Err((status, error)) => {
+                warn!(delivery_id = %task.id, attempts = task.attempts + 1, error = %error, "Webhook delivery failed");
+                mark_failed(pool, &task, status, &error).await?;
+                failed += 1;
+            }
+        }
+    }
+
+    info!(
+        delivered,
+        failed,
+        deferred,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Webhook delivery cycle completed"
+    );
+    Ok(())
+}
+
+/// Client that connects only to the addresses checked by `resolve_target`
+/// and treats redirects as failures.
+fn pinned_client(host: &str, addrs: &[std::net::SocketAddr]) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(host, addrs)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+async fn send(task: &DeliveryTask) -> Result<i32, (Option<i32>, String)> {
+    // Re-checked on every attempt: DNS may have changed since registration.
+    let (url, addrs) = resolve_target(&task.url)
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+    let client =
+        pinned_client(url.host_str().unwrap_or_default(), &addrs).map_err(|e| (None, e))?;
+
+    let body = serde_json::to_vec(&task.payload).map_err(|e| (None, e.to_string()))?;
+    let timestamp = Utc::now().timestamp();
+    let signature = sign_payload(&task.secret, timestamp, &body);
+
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(EVENT_HEADER, &task.event_type)
+        .header(DELIVERY_HEADER, &task.id)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = i32::from(response.status().as_u16());
+    if response.status().is_success() {
+        Ok(status)
+    } else {
+        Err((Some(status), format!("HTTP {status}")))
+    }
+}
+
+async fn recover_stuck_deliveries(pool: &PgPool) -> Result<(), super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    let cutoff = now - chrono::Duration::seconds(SENDING_TIMEOUT_SECS);
+    let result = sqlx::query(
+        r#"UPDATE "webhook_deliveries"
+           SET "status" = 'PENDING', "updatedAt" = $1
+           WHERE "status" = 'SENDING' AND "updatedAt" < $2"#,
+    )
+    .bind(now)
+    .bind(cutoff)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() > 0 {
+        warn!(
+            count = result.rows_affected(),
+            "Recovered stuck webhook deliveries"
+        );
+    }
+    Ok(())
+}
+
+async fn claim_due_deliveries(pool: &PgPool) -> Result<Vec<DeliveryTask>, super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    let rows = sqlx::query(
+        r#"
+        WITH claimed AS (
+            SELECT d."id" FROM "webhook_deliveries" d
+            JOIN "webhook_endpoints" e ON e."id" = d."endpointId"
+            WHERE d."status" = 'PENDING' AND d."nextAttemptAt" <= $1 AND e."isActive"
+            ORDER BY d."nextAttemptAt" ASC
+            LIMIT $2
+            FOR UPDATE OF d SKIP LOCKED
+        )
+        UPDATE "webhook_deliveries" d
+        SET "status" = 'SENDING', "updatedAt" = $1
+        FROM claimed, "webhook_endpoints" e
+        WHERE d."id" = claimed."id" AND e."id" = d."endpointId"
+        RETURNING d."id", d."eventType", d."payload", d."attempts", e."url", e."secret"
+        "#,
+    )
+    .bind(now)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| DeliveryTask {
+            id: row.try_get("id").unwrap_or_default(),
+            event_type: row.try_get("eventType").unwrap_or_default(),
+            payload: row.try_get("payload").unwrap_or(serde_json::Value::Null),
+            attempts: row.try_get("attempts").unwrap_or(0),
+            url: row.try_get("url").unwrap_or_default(),
+            secret: row.try_get("secret").unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Stamps a claimed delivery just before sending; false if it is no longer
+/// claimed.
+async fn touch_sending(pool: &PgPool, id: &str) -> Result<bool, super::WorkerError> {
+    let result = sqlx::query(
+        r#"UPDATE "webhook_deliveries" SET "updatedAt" = $2
+           WHERE "id" = $1 AND "status" = 'SENDING'"#,
+    )
+    .bind(id)
+    .bind(Utc::now().naive_utc())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns a claimed delivery that was not attempted this cycle.
+async fn release(pool: &PgPool, id: &str) -> Result<(), super::WorkerError> {
+    sqlx::query(
+        r#"UPDATE "webhook_deliveries" SET "status" = 'PENDING', "updatedAt" = $2
+           WHERE "id" = $1 AND "status" = 'SENDING'"#,
+    )
+    .bind(id)
+    .bind(Utc::now().naive_utc())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn mark_delivered(pool: &PgPool, id: &str, status: i32) -> Result<(), super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    sqlx::query(
+        r#"UPDATE "webhook_deliveries"
+           SET "status" = 'SUCCEEDED', "attempts" = "attempts" + 1, "lastStatusCode" = $2,
+               "lastError" = NULL, "deliveredAt" = $3, "updatedAt" = $3
+           WHERE "id" = $1"#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn mark_failed(
+    pool: &PgPool,
+    task: &DeliveryTask,
+    status: Option<i32>,
+    error: &str,
+) -> Result<(), super::WorkerError> {
+    let now = Utc::now().naive_utc();
+    let attempts = task.attempts + 1;
+    let (next_status, next_attempt) = if attempts >= MAX_ATTEMPTS {
+        ("FAILED", now)
+    } else {
+        (
+            "PENDING",
+            now + chrono::Duration::seconds(backoff_secs(task.attempts)),
+        )
+    };
+    let error: String = error.chars().take(MAX_ERROR_LEN).collect();
+
+    sqlx::query(
+        r#"UPDATE "webhook_deliveries"
+           SET "status" = $2, "attempts" = $3, "nextAttemptAt" = $4,
+               "lastStatusCode" = $5, "lastError" = $6, "updatedAt" = $7
+           WHERE "id" = $1"#,
+    )
+    .bind(&task.id)
+    .bind(next_status)
+    .bind(attempts)
+    .bind(next_attempt)
+    .bind(status)
+    .bind(error)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_secs(0), 30);
+        assert_eq!(backoff_secs(1), 60);
+        assert_eq!(backoff_secs(4), 480);
+        assert_eq!(backoff_secs(7), 3840);
+        assert_eq!(backoff_secs(40), MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn overlapping_cycles_are_refused() {
+        let first = CycleGuard::acquire().expect("no cycle running");
+        assert!(CycleGuard::acquire().is_none());
+        drop(first);
+        assert!(CycleGuard::acquire().is_some());
+    }
+}
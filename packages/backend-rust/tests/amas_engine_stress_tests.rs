@@ -0,0 +1,208 @@
+//! Concurrency stress tests for AMASEngine lock handling.
+//!
+//! Many tasks interleave event processing, batched IGE updates, cache
+//! invalidation and config reloads on a multi-threaded runtime. A lock-order
+//! inversion shows up as a timeout instead of a hung test run.
+//!
+//! Scale with `AMAS_STRESS_ITERATIONS` (default 20 per task).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use danci_backend_rust::amas::config::AMASConfig;
+use danci_backend_rust::amas::decision::IgeBatchUpdateItem;
+use danci_backend_rust::amas::engine::AMASEngine;
+use danci_backend_rust::amas::types::{ProcessOptions, RawEvent};
+
+const TASKS: usize = 8;
+const USERS: usize = 3;
+const DEADLOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn iterations() -> usize {
+    std::env::var("AMAS_STRESS_ITERATIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20)
+}
+
+fn event(i: usize) -> RawEvent {
+    RawEvent {
+        is_correct: !i.is_multiple_of(3),
+        response_time: 1500 + (i as i64 % 7) * 400,
+        dwell_time: Some(2500),
+        retry_count: (i % 2) as i32,
+        hint_used: false,
+        paused_time_ms: None,
+        word_id: Some(format!("word_{}", i % 5)),
+        question_type: Some("definition".to_string()),
+        confidence: Some(0.7),
+        pause_count: 0,
+        switch_count: 0,
+        focus_loss_duration: None,
+        interaction_density: Some(0.6),
+        timestamp: 1700000000000 + i as i64 * 1000,
+        is_quit: false,
+        device_type: Some("desktop".to_string()),
+        is_guess: false,
+        correctness: None,
+    }
+}
+
+fn batch_items(i: usize) -> Vec<IgeBatchUpdateItem> {
+    (0..4)
+        .map(|k| IgeBatchUpdateItem {
+            strategy: format!("strategy_{}", (i + k) % 3),
+            success: (i + k).is_multiple_of(2),
+            reward: None,
+            context_key: Some(format!("ctx_{}", k % 2)),
+        })
+        .collect()
+}
+
+async fn run_with_deadline<F: std::future::Future<Output = ()>>(name: &str, fut: F) {
+    if tokio::time::timeout(DEADLOCK_TIMEOUT, fut).await.is_err() {
+        panic!("{name}: engine operations did not finish in {DEADLOCK_TIMEOUT:?}, likely deadlock");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stress_interleaved_engine_operations() {
+    let engine = Arc::new(AMASEngine::new(AMASConfig::default(), None));
+    let iterations = iterations();
+
+    run_with_deadline("interleaved", async {
+        let mut handles = Vec::new();
+        for task in 0..TASKS {
+            let engine = Arc::clone(&engine);
+            handles.push(tokio::spawn(async move {
+                for i in 0..iterations {
+                    let user_id = format!("stress_user_{}", (task + i) % USERS);
+                    match (task + i) % 6 {
+                        0 | 1 => {
+                            engine
+                                .process_event(&user_id, event(i), ProcessOptions::default())
+                                .await
+                                .expect("process_event should succeed");
+                        }
+                        2 => {
+                            let results = engine.batch_update_ige(&user_id, &batch_items(i)).await;
+                            assert_eq!(results.len(), 4);
+                        }
+                        3 => {
+                            let _ = engine.get_user_state(&user_id).await;
+                            let _ = engine.get_current_strategy(&user_id).await;
+                        }
+                        4 => engine.invalidate_cache(&user_id).await,
+                        _ => {
+                            let _ = engine.get_cache_stats().await;
+                            let _ = engine.cleanup_stale_users(i64::MAX).await;
+                        }
+                    }
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.expect("stress task panicked");
+        }
+    })
+    .await;
+
+    let (states, models) = engine.get_cache_stats().await;
+    assert!(states <= USERS && models <= USERS);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stress_config_reload_during_processing() {
+    let engine = Arc::new(AMASEngine::new(AMASConfig::default(), None));
+    let iterations = iterations();
+
+    run_with_deadline("config reload", async {
+        let reloader = {
+            let engine = Arc::clone(&engine);
+            tokio::spawn(async move {
+                for _ in 0..iterations {
+                    engine.reload_config().await.expect("reload should succeed");
+                    let _ = engine.get_config().await;
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+
+        let mut workers = Vec::new();
+        for task in 0..TASKS / 2 {
+            let engine = Arc::clone(&engine);
+            workers.push(tokio::spawn(async move {
+                let user_id = format!("reload_user_{task}");
+                for i in 0..iterations {
+                    engine
+                        .process_event(&user_id, event(i), ProcessOptions::default())
+                        .await
+                        .expect("process_event should succeed");
+                }
+                engine
+                    .get_user_state(&user_id)
+                    .await
+                    .expect("state should be cached")
+            }));
+        }
+
+        reloader.await.expect("reloader panicked");
+        for worker in workers {
+            let state = worker.await.expect("worker panicked");
+            assert!(state.attention.is_finite());
+        }
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stress_same_user_batch_updates_are_not_lost() {
+    let engine = Arc::new(AMASEngine::new(AMASConfig::default(), None));
+    let iterations = iterations();
+
+    run_with_deadline("same-user batches", async {
+        let mut handles = Vec::new();
+        for _ in 0..TASKS {
+            let engine = Arc::clone(&engine);
+            handles.push(tokio::spawn(async move {
+                for _ in 0..iterations {
+                    let item = IgeBatchUpdateItem {
+                        strategy: "shared".to_string(),
+                        success: true,
+                        reward: None,
+                        context_key: None,
+                    };
+                    engine.batch_update_ige("shared_user", &[item]).await;
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.expect("batch task panicked");
+        }
+    })
+    .await;
+
+    let final_result = engine
+        .batch_update_ige(
+            "shared_user",
+            &[IgeBatchUpdateItem {
+                strategy: "shared".to_string(),
+                success: false,
+                reward: None,
+                context_key: None,
+            }],
+        )
+        .await;
+    assert!(final_result[0].applied);
+    // n successes followed by one failure give a mean of n / (n + 1); any
+    // lost update shifts the trial count and so the mean.
+    let n = (TASKS * iterations) as f64;
+    assert!(
+        (final_result[0].mean - n / (n + 1.0)).abs() < 1e-12,
+        "mean {} != {}/{}",
+        final_result[0].mean,
+        n,
+        n + 1.0
+    );
+}
@@ -59,6 +59,7 @@ fn sample_event() -> RawEvent {
         is_quit: false,
         device_type: Some("desktop".to_string()),
         is_guess: false,
+        correctness: None,
     }
 }
 
@@ -835,3 +836,65 @@ async fn engine_algorithm_states_persist_and_affect_output() {
         final_state.fatigue
     );
 }
+
+#[tokio::test]
+async fn engine_memory_report_ranks_users_by_footprint() {
+    use danci_backend_rust::amas::decision::IgeBatchUpdateItem;
+
+    let engine = AMASEngine::new(AMASConfig::default(), None);
+    let empty = engine.memory_report(10).await;
+    assert_eq!(empty.total_bytes, 0);
+    assert!(empty.top_users.is_empty());
+
+    for user in ["user_small", "user_large"] {
+        engine
+            .process_event(user, sample_event(), ProcessOptions::default())
+            .await
+            .expect("process_event should succeed");
+    }
+    let items: Vec<IgeBatchUpdateItem> = (0..40)
+        .map(|i| IgeBatchUpdateItem {
+            strategy: format!("strategy_{i}"),
+            success: i % 2 == 0,
+            reward: None,
+            context_key: Some(format!("ctx_{i}")),
+        })
+        .collect();
+    engine.batch_update_ige("user_large", &items).await;
+
+    let report = engine.memory_report(1).await;
+    assert_eq!(report.cached_states, 2);
+    assert_eq!(report.cached_models, 2);
+    assert_eq!(
+        report.total_bytes,
+        report.state_bytes + report.ige_bytes + report.swd_bytes + report.cold_start_bytes
+    );
+    assert_eq!(report.top_users.len(), 1);
+    assert_eq!(report.top_users[0].user_id, "user_large");
+    assert!(report.top_users[0].ige_bytes > 0);
+}
+
+#[tokio::test]
+async fn engine_partial_credit_reward_lies_between_wrong_and_right() {
+    let engine = AMASEngine::new(AMASConfig::default(), None);
+    let mut rewards = Vec::new();
+    for (user, correctness) in [
+        ("user_wrong", 0.0),
+        ("user_partial", 0.6),
+        ("user_right", 1.0),
+    ] {
+        let event = RawEvent {
+            is_correct: correctness >= 1.0,
+            correctness: Some(correctness),
+            ..sample_event()
+        };
+        let result = engine
+            .process_event(user, event, ProcessOptions::default())
+            .await
+            .expect("process_event should succeed");
+        rewards.push(result.reward);
+    }
+    assert!(rewards[0].value < rewards[1].value);
+    assert!(rewards[1].value < rewards[2].value);
+    assert_eq!(rewards[1].reason, "部分正确");
+}
@@ -159,6 +159,7 @@ fn arb_persisted_amas_state() -> impl Strategy<Value = PersistedAMASState> {
                     mastery_history,
                     ensemble_performance,
                     algorithm_states: None,
+                    sync_clock: None,
                 }
             },
         )
@@ -278,6 +279,7 @@ proptest! {
             mastery_history,
             ensemble_performance,
             algorithm_states: None,
+            sync_clock: None,
         };
 
         let json = serde_json::to_value(&state).unwrap();
@@ -382,6 +384,7 @@ fn persisted_state_with_all_none_fields() {
         mastery_history: None,
         ensemble_performance: None,
         algorithm_states: None,
+        sync_clock: None,
     };
 
     let json = serde_json::to_value(&state).unwrap();
@@ -23,6 +23,41 @@ async fn test_health_root() {
     );
 }
 
+#[tokio::test]
+async fn test_health_deep_requires_admin_token() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health?deep=true")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_health_deep_reports_structured_checks() {
+    use danci_backend_rust::services::health_check;
+    use danci_backend_rust::state::AppState;
+
+    let engine = AppState::create_amas_engine(None);
+    let state = AppState::new(None, engine, None);
+
+    // No database in the test app: the critical Postgres check fails.
+    let report = serde_json::to_value(health_check::cached(&state).await).unwrap();
+    assert_eq!(report["status"], "fail");
+    let checks = report["checks"].as_array().unwrap();
+    let postgres = checks.iter().find(|c| c["name"] == "postgres").unwrap();
+    assert_eq!(postgres["status"], "fail");
+    assert_eq!(postgres["critical"], true);
+    assert!(checks.iter().any(|c| c["name"] == "redis"));
+}
+
 #[tokio::test]
 async fn test_health_live() {
     let app = common::create_test_app().await;
@@ -124,3 +159,91 @@ async fn test_404_not_found() {
 
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
+
+#[tokio::test]
+async fn test_scim_service_provider_config() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/scim/v2/ServiceProviderConfig")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/scim+json");
+    let body = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    let config: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(config["patch"]["supported"], true);
+    assert_eq!(config["bulk"]["supported"], false);
+}
+
+#[tokio::test]
+async fn test_scim_users_require_bearer_token() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/scim/v2/Users")
+                .header("content-type", "application/scim+json")
+                .body(Body::from(r#"{"userName":"ada@example.com"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        error["schemas"][0],
+        "urn:ietf:params:scim:api:messages:2.0:Error"
+    );
+    assert_eq!(error["status"], "401");
+}
+
+#[tokio::test]
+async fn test_placement_result_requires_auth() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/placement/result")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_due_queue_requires_auth() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/word-states/due/queue?page=1&pageSize=20")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -369,6 +369,89 @@ async fn test_sqlite_word_progress_workflow() {
     pool.close().await;
 }
 
+#[tokio::test]
+async fn test_sqlite_fts_word_search() {
+    use danci_backend_rust::services::word_search::{
+        ensure_sqlite_fts, search_sqlite_fts, MatchField,
+    };
+
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let db_path = temp_dir.path().join("test.db");
+
+    let pool = create_test_sqlite_pool(db_path)
+        .await
+        .expect("failed to create pool");
+
+    run_migrations(&pool).await.expect("migration failed");
+
+    sqlx::query(
+        r#"
+        INSERT INTO "word_books" ("id", "name", "description", "type", "isPublic")
+        VALUES ('wb-test', 'Test Book', 'Test', 'system', 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("failed to insert wordbook");
+
+    sqlx::query(
+        r#"
+        INSERT INTO "words" ("id", "spelling", "phonetic", "meanings", "examples", "wordBookId")
+        VALUES
+          ('word-1', 'hello', '', '["你好"]', '[]', 'wb-test'),
+          ('word-2', 'help', '', '["帮助"]', '[]', 'wb-test'),
+          ('word-3', 'world', '', '["世界"]', '[]', 'wb-test')
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("failed to insert words");
+
+    let hits = search_sqlite_fts(&pool, "1", "hel", 10, Some(&[]))
+        .await
+        .expect("fts search failed");
+    let mut ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["word-1", "word-2"]);
+    assert!(hits.iter().all(|h| h.matched_field == MatchField::Spelling));
+    assert!(hits.iter().all(|h| h.snippet.contains("<mark>hel")));
+
+    // New rows are picked up by the next signature check; searches only
+    // repeat it once a minute, so run it directly
+    sqlx::query(
+        r#"
+        INSERT INTO "words" ("id", "spelling", "phonetic", "meanings", "examples", "wordBookId")
+        VALUES ('word-4', 'globe', '', '["世界 地球"]', '[]', 'wb-test')
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("failed to insert word");
+    ensure_sqlite_fts(&pool).await.expect("fts rebuild failed");
+
+    let hits = search_sqlite_fts(&pool, "1", "地球", 10, Some(&[]))
+        .await
+        .expect("fts search failed");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, "word-4");
+    assert_eq!(hits[0].matched_field, MatchField::Meaning);
+    assert_eq!(hits[0].meanings, vec!["世界 地球".to_string()]);
+
+    // Licensed books are hidden, and without license data only the user's
+    // own books are searched
+    let denied = vec!["wb-test".to_string()];
+    let hits = search_sqlite_fts(&pool, "1", "hel", 10, Some(&denied))
+        .await
+        .expect("fts search failed");
+    assert!(hits.is_empty());
+    let hits = search_sqlite_fts(&pool, "1", "hel", 10, None)
+        .await
+        .expect("fts search failed");
+    assert!(hits.is_empty());
+
+    pool.close().await;
+}
+
 #[tokio::test]
 async fn test_sqlite_learning_session_workflow() {
     let temp_dir = TempDir::new().expect("failed to create temp dir");
@@ -0,0 +1,39 @@
+//! LinUCB 数值漂移浸泡测试
+//!
+//! cargo run --release --no-default-features --features soak --example linucb_soak -- \
+//!     [updates] [dimension] [check_every] [seed]
+
+use danci_native::linucb::soak::{run_soak, SoakConfig};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg = |i: usize| args.get(i).and_then(|v| v.parse::<u64>().ok());
+    let defaults = SoakConfig::default();
+    let config = SoakConfig {
+        updates: arg(0).unwrap_or(defaults.updates),
+        dimension: arg(1).map_or(defaults.dimension, |d| d as usize),
+        check_every: arg(2).unwrap_or(defaults.check_every),
+        seed: arg(3).unwrap_or(defaults.seed),
+        ..defaults
+    };
+
+    let started = std::time::Instant::now();
+    let report = run_soak(&config);
+    for sample in &report.samples {
+        println!(
+            "{:>10}  recon={:.3e}  theta={:.3e}  cond={:.3e}",
+            sample.update, sample.reconstruction_drift, sample.theta_drift, sample.condition_number
+        );
+    }
+    println!(
+        "updates={} max_recon={:.3e} max_theta={:.3e} flagged={} elapsed={:.1?}",
+        report.updates,
+        report.max_reconstruction_drift,
+        report.max_theta_drift,
+        report.flagged_checks,
+        started.elapsed()
+    );
+    if report.flagged_checks > 0 {
+        std::process::exit(1);
+    }
+}
@@ -18,11 +18,22 @@ export declare class CausalInferenceNative {
    * 公式: tau = (1/n) * sum[ mu1(X) - mu0(X) + T(Y-mu1(X))/e(X) - (1-T)(Y-mu0(X))/(1-e(X)) ]
    */
   estimateAte(observations: Array<CausalObservation>): CausalEstimate;
-  /** Bootstrap 标准误估计（使用 Rayon 并行化） */
+  /**
+   * Bootstrap 标准误估计（常规模式下使用 Rayon 并行化，节能模式下串行且限制重采样次数）
+   *
+   * 簇模式下按 cluster_id（缺省为 user_id）有放回抽取整簇，
+   * 避免同一用户的多条观测被当作独立样本而低估方差
+   */
   bootstrapSe(
     observations: Array<CausalObservation>,
     nBootstrap?: number | undefined | null,
   ): number;
+  /**
+   * 时变处理分析（序贯可忽略性）
+   * 稳定化权重: sw_it = prod_{k<=t} P(A_k | A_{k-1}) / P(A_k | L_k, A_{k-1})
+   * 各时间段效应为稳定化权重下处理组与对照组结果的加权均值之差
+   */
+  estimateTimeVarying(observations: Array<SessionObservation>): LongitudinalEstimate;
   /** 诊断倾向得分分布 */
   diagnosePropensity(observations: Array<CausalObservation>): PropensityDiagnostics;
   /** 获取倾向得分（自动添加截距项） */
@@ -82,6 +93,38 @@ export interface BanditModel {
   updateCount: number;
 }
 
+/** 带布局信息的模型快照 */
+export interface BanditSnapshot {
+  model: BanditModel;
+  /** 旧快照没有指纹，视为未知布局 */
+  layoutFingerprint?: string;
+  /** 保存时的完整布局，供按特征名迁移使用 */
+  layout?: FeatureLayout;
+}
+
+/** 模糊匹配结果 */
+export interface BlankMatch {
+  /** 1 − 编辑距离 / 较长串长度 */
+  similarity: number;
+  /** 词干一致，仅后缀不同 */
+  stemMatch: boolean;
+  exact: boolean;
+}
+
+export interface BlankScore {
+  matched: BlankMatch;
+  credit: number;
+  weight: number;
+}
+
+/** Bootstrap 重采样单位 */
+export declare const enum BootstrapMode {
+  /** 逐条观测独立重采样 */
+  Observation = 0,
+  /** 按簇（通常为用户）有放回重采样，簇内观测整体保留 */
+  Cluster = 1,
+}
+
 /** 因果效应估计结果 */
 export interface CausalEstimate {
   /** 平均处理效应 */
@@ -116,6 +159,8 @@ export interface CausalInferenceConfig {
   maxIterations?: number;
   /** 收敛阈值 */
   convergenceThreshold?: number;
+  /** Bootstrap 重采样单位（默认逐条观测） */
+  bootstrapMode?: BootstrapMode;
 }
 
 /** 因果观测数据 */
@@ -130,6 +175,91 @@ export interface CausalObservation {
   timestamp?: number;
   /** 用户ID（可选） */
   userId?: string;
+  /** 聚类ID（可选，簇 Bootstrap 使用；为空时退回 userId） */
+  clusterId?: string;
+}
+
+/** 一个空的作答 */
+export interface ClozeBlank {
+  expected: string;
+  answer: string;
+  /** 是否为本题考查的目标词 */
+  isTarget: boolean;
+  /** 显式权重；缺省时按是否目标词取值 */
+  weight?: number;
+}
+
+export interface ClozeScore {
+  /** 整题得分 [0, 1] */
+  credit: number;
+  blanks: Array<BlankScore>;
+}
+
+/** 评分参数 */
+export interface ClozeScoringConfig {
+  /** 低于此相似度不给分 */
+  minSimilarity: number;
+  /** 词干正确时的最低得分 */
+  stemCredit: number;
+  /** 非完全正确时的得分上限 */
+  maxPartial: number;
+  /** 目标词所在空的默认权重（其他空为 1） */
+  targetWeight: number;
+}
+
+/** 编排结果 */
+export interface ComposedSession {
+  items: Array<SessionItem>;
+  /** 因 maxNewPerSession 被移出本次会话的词 */
+  droppedWordIds: Array<string>;
+  violations: Array<PolicyViolation>;
+}
+
+/** 当前计算模式对应的预算 */
+export interface ComputeBudget {
+  mode: ComputeMode;
+  /** 单次批量计算的最大条数 */
+  maxBatchSize: number;
+  /** 是否允许 Rayon 并行 */
+  parallel: boolean;
+  /** 是否推迟非紧急的重算（如最优间隔刷新） */
+  deferNonUrgent: boolean;
+  /** Bootstrap 重采样次数上限 */
+  maxBootstrap: number;
+}
+
+/** 计算模式（低电量时切换到 Eco） */
+export declare const enum ComputeMode {
+  Normal = 0,
+  Eco = 1,
+}
+
+/** 带区间的估计值 */
+export interface CurveEstimate {
+  value: number;
+  lower: number;
+  upper: number;
+}
+
+/** 在当前累计时长之上再学习 `extra_minutes` 后的预测 */
+export interface CurveForecast {
+  extraMinutes: number;
+  /** 预计累计掌握词数 */
+  totalMastered: CurveEstimate;
+  /** 相对当前拟合值新增的掌握词数 */
+  newMastered: CurveEstimate;
+}
+
+/** 曲线形式 */
+export declare const enum CurveModel {
+  Exponential = 0,
+  Power = 1,
+}
+
+/** 一个观测点：截至某时刻的累计学习时长与累计掌握词数 */
+export interface CurvePoint {
+  minutes: number;
+  mastered: number;
 }
 
 /** DiagnosticResult 结构体 - 诊断结果 */
@@ -140,6 +270,9 @@ export interface DiagnosticResult {
   conditionNumber: number;
   minDiagonal: number;
   maxDiagonal: number;
+  /** ||L·Lᵀ - A||_F / ||A||_F */
+  reconstructionDrift: number;
+  hasDrift: boolean;
   message: string;
 }
 
@@ -152,7 +285,78 @@ export declare const enum Difficulty {
   Usage = 4,
 }
 
+/** 特征布局（有序特征名 + 构造器版本） */
+export interface FeatureLayout {
+  version: number;
+  names: Array<string>;
+}
+
+/** 一次选择的结果 */
+export interface HintChoice {
+  level: number;
+  context: HintContext;
+  /** 所选等级的后验均值（期望奖励） */
+  expectedReward: number;
+}
+
+/** 选择提示等级时的上下文 */
+export interface HintContext {
+  difficultyBand: number;
+  recallBucket: number;
+}
+
+/** 策略参数 */
+export interface HintPolicyConfig {
+  /** 提示等级数（含“不提示”） */
+  levels: number;
+  /** 难度档数，难度取值 [0, 1] */
+  difficultyBands: number;
+  /** 回忆概率桶数 */
+  recallBuckets: number;
+  /** 最强提示下答对的奖励折扣 */
+  overusePenalty: number;
+}
+
+/** `set_model` 的处理结果，调用方负责记录日志 */
+export interface LayoutCheck {
+  migrated: boolean;
+  /** 未迁移时为 "none" */
+  strategy: string;
+  fromFingerprint?: string;
+  toFingerprint: string;
+}
+
 /** LinUCBContext 结构体 */
+/** 拟合结果 */
+export interface LearningCurve {
+  model: CurveModel;
+  /** 指数模型为 A（渐近上限），幂律为 a */
+  scale: number;
+  /** 指数模型为 k（每分钟），幂律为 b */
+  shape: number;
+  rSquared: number;
+  pointsUsed: number;
+  totalMinutes: number;
+  totalMastered: number;
+  /** 当前时刻的边际掌握速度（词/小时） */
+  ratePerHour: CurveEstimate;
+  /** 全程平均掌握速度（词/小时） */
+  averageRatePerHour: number;
+  /** 指数模型的渐近上限；幂律无上限时为空 */
+  plateau?: number;
+  confidence: number;
+  forecasts: Array<CurveForecast>;
+}
+
+/** 拟合参数 */
+export interface LearningCurveConfig {
+  /** 置信水平（0.9 表示 5%–95% 分位数区间） */
+  confidence: number;
+  /** 自助法重采样次数（受计算预算上限约束） */
+  bootstrapSamples: number;
+  seed: number;
+}
+
 export interface LinUcbContext {
   timeOfDay: number;
   dayOfWeek: number;
@@ -160,6 +364,108 @@ export interface LinUcbContext {
   fatigueFactor?: number;
 }
 
+/** 时变处理分析结果（序贯可忽略性假设下的 MSM 估计） */
+export interface LongitudinalEstimate {
+  periods: Array<PeriodEffect>;
+  /** 按样本量加权的各时间段效应均值 */
+  pooledEffect: number;
+  pooledStandardError: number;
+  unitCount: number;
+  sampleSize: number;
+}
+
+export interface MasteryBand {
+  mastery: number;
+  lower: number;
+  upper: number;
+  /** ACT-R 回忆概率 */
+  recallProbability: number;
+  /** 回忆概率估计的标准差 */
+  predictionSd: number;
+  volatility: number;
+}
+
+/** 区间参数 */
+export interface MasteryBandConfig {
+  /** ACT-R 衰减率 d */
+  decay: number;
+  /** 回忆阈值 τ */
+  threshold: number;
+  /** 激活噪声 s */
+  noise: number;
+  /** Beta 先验的等效观测数 n₀ */
+  priorObservations: number;
+  /** 计算波动时使用的最近作答数 */
+  volatilityWindow: number;
+  /** 波动对半宽的权重 w */
+  volatilityWeight: number;
+  /** 半宽的标准差倍数 z */
+  z: number;
+  /** 另一方向复习对本方向激活的迁移系数 k，取值 [0, 1] */
+  transfer: number;
+}
+
+export interface MasteryProgress {
+  totalWords: number;
+  /** 期望掌握词数 Σpᵢ */
+  expectedMastered: number;
+  /** 期望掌握比例（百分比） */
+  percentComplete: number;
+  targetPercent: number;
+  /** 每日学习词数 */
+  dailyPace: number;
+  /** 达到目标的预计天数；已达到为 0，无法估计时为空 */
+  etaDays?: number;
+}
+
+/** 单个条目的评分 */
+export interface ObjectiveScore {
+  wordId: string;
+  retentionGain: number;
+  timeCost: number;
+  frustrationRisk: number;
+  score: number;
+}
+
+/** 单时间段处理效应 */
+export interface PeriodEffect {
+  period: number;
+  /** 加权（Hajek）处理效应 */
+  effect: number;
+  standardError: number;
+  confidenceIntervalLower: number;
+  confidenceIntervalUpper: number;
+  pValue: number;
+  treatedCount: number;
+  controlCount: number;
+  weights: WeightDiagnostics;
+}
+
+/** 规则校验结果 */
+export interface PolicyValidation {
+  valid: boolean;
+  /** 单条规则自身的错误（解析失败、参数越界） */
+  errors: Array<string>;
+  /** 规则之间的冲突 */
+  conflicts: Array<string>;
+}
+
+/** 违反的约束 */
+export interface PolicyViolation {
+  rule: string;
+  position: number;
+  wordId: string;
+  message: string;
+}
+
+/** 进度参数 */
+export interface ProgressConfig {
+  /** 学习一次后词的回忆概率 θ */
+  recallThreshold: number;
+  /** 目标期望掌握比例 [0, 1] */
+  target: number;
+}
+
 /** 倾向得分诊断 */
 export interface PropensityDiagnostics {
   /** 均值 */
@@ -178,6 +484,105 @@ export interface PropensityDiagnostics {
   auc: number;
 }
 
+export interface RampDay {
+  /** 距今天数，从 1 开始 */
+  day: number;
+  wordIds: Array<string>;
+  minutes: number;
+}
+
+/** 一个到期词 */
+export interface RescueCandidate {
+  wordId: string;
+  /** 当前回忆概率 p₀ */
+  recall: number;
+  /** 推迟一天后的回忆概率 p₁ */
+  recallTomorrow: number;
+  /** 预计复习秒数；为空时用 `default_seconds` */
+  seconds?: number;
+}
+
+/** 规划参数 */
+export interface RescueConfig {
+  /** 补救会话的分钟上限 */
+  sessionMinutes: number;
+  /** 补救会话的词数上限 */
+  maxSessionWords: number;
+  /** 剩余词平摊的天数 */
+  rampDays: number;
+  /** 未给出时长时每个词的预计复习秒数 */
+  defaultSeconds: number;
+}
+
+export interface RescueItem {
+  wordId: string;
+  /** 推迟一天的期望记忆损失 */
+  expectedLoss: number;
+  minutes: number;
+  /** 每分钟挽回的损失 */
+  priority: number;
+}
+
+export interface RescuePlan {
+  overdueWords: number;
+  /** 按优先级排列的补救会话 */
+  session: Array<RescueItem>;
+  sessionMinutes: number;
+  /** 补救会话挽回的期望损失 */
+  lossAvoided: number;
+  ramp: Array<RampDay>;
+}
+
+/** 一次复习 */
+export interface ReviewEvent {
+  /** 距今天数 */
+  ageDays: number;
+  correct: boolean;
+}
+
+/** 会话候选条目 */
+export interface SessionItem {
+  wordId: string;
+  isNew: boolean;
+  /** 本会话中已答错，需要再次复习 */
+  failed: boolean;
+  /** 难度 [0, 1]；缺省时不参与难度连续约束 */
+  difficulty?: number | undefined | null;
+  /** 与之易混的词 id；同一会话中相距越近，干扰代价越高 */
+  confusableWith?: Array<string> | undefined | null;
+  /** 当前回忆概率 [0, 1]；供多目标评分估计收益与失败概率 */
+  recallProbability?: number | undefined | null;
+}
+
+/** 目标权重与权衡参数 */
+export interface SessionObjective {
+  retentionWeight: number;
+  timeWeight: number;
+  frustrationWeight: number;
+  /** 帕累托权衡 [0, 1]：0 偏向记忆收益，1 偏向省时与少挫败 */
+  tradeoff: number;
+  /** 新词的预计作答秒数 */
+  newWordSeconds: number;
+  /** 复习词的预计作答秒数 */
+  reviewSeconds: number;
+  /** 新词的记忆收益 [0, 1] */
+  newWordGain: number;
+}
+
+/** 纵向（逐会话）观测数据，处理状态可随时间变化 */
+export interface SessionObservation {
+  /** 个体ID（同一用户的会话按 period 排序构成处理历史） */
+  unitId: string;
+  /** 时间段编号（会话序号或天序号） */
+  period: number;
+  /** 该时间段开始时的协变量 */
+  features: Array<number>;
+  /** 该时间段的处理标记 (0 或 1) */
+  treatment: number;
+  /** 该时间段的结果值 */
+  outcome: number;
+}
+
 /** UCBStats 结构体 - UCB 统计信息 */
 export interface UcbStats {
   theta: Array<number>;
@@ -194,3 +599,97 @@ export interface UserState {
   totalInteractions: number;
   averageResponseTime: number;
 }
+
+/** 单时间段稳定化 IP 权重分布诊断 */
+export interface WeightDiagnostics {
+  mean: number;
+  std: number;
+  min: number;
+  max: number;
+  /** Kish 有效样本量 */
+  effectiveSampleSize: number;
+  /** 触及截断上限的权重数 */
+  truncatedCount: number;
+}
+
+/** 按 JSON 规则编排会话；规则无效时原样返回候选队列 */
+export declare function composeSession(
+  rulesJson: string,
+  candidates: Array<SessionItem>,
+): ComposedSession;
+
+/** 按 JSON 规则与多目标权重编排会话；规则或权重无效时原样返回候选队列 */
+export declare function composeSessionWithObjective(
+  rulesJson: string,
+  candidates: Array<SessionItem>,
+  objective: SessionObjective,
+  fatigue: number,
+): ComposedSession;
+
+/** 拟合学习曲线并给出各预算下的预测；有效点不足或数据无增长时返回 `None` */
+export declare function fitLearningCurve(
+  points: Array<CurvePoint>,
+  extraMinutes: Array<number>,
+  config?: LearningCurveConfig | undefined | null,
+): LearningCurve | null;
+
+/** 比较标准答案与作答（忽略大小写与多余空白） */
+export declare function matchBlank(expected: string, answer: string): BlankMatch;
+
+/** 整题评分；没有空时得 0 */
+export declare function scoreCloze(
+  blanks: Array<ClozeBlank>,
+  config?: ClozeScoringConfig | undefined | null,
+): ClozeScore;
+
+/** 掌握度区间；`events` 可为任意顺序，波动按 `age_days` 从旧到新计算 */
+export declare function masteryBand(
+  mastery: number,
+  events: Array<ReviewEvent>,
+  config?: MasteryBandConfig | undefined | null,
+): MasteryBand;
+
+/** 由各词回忆概率与每日学习词数估计掌握进度；非有限的概率按 0 处理 */
+export declare function masteryProgress(
+  recalls: Array<number>,
+  dailyPace: number,
+  config?: ProgressConfig | undefined | null,
+): MasteryProgress;
+
+/** 为候选条目逐个评分 */
+export declare function scoreSessionItems(
+  items: Array<SessionItem>,
+  objective: SessionObjective,
+  fatigue: number,
+): Array<ObjectiveScore>;
+
+/** 对到期词排序，装入补救会话，其余平摊到之后的若干天 */
+export declare function planRescue(
+  candidates: Array<RescueCandidate>,
+  config?: RescueConfig | undefined | null,
+): RescuePlan;
+
+/** 为一道题选择提示等级；策略 JSON 无效时使用空策略 */
+export declare function selectHintLevel(
+  policyJson: string,
+  difficulty: number,
+  recall: number,
+  seed: number,
+): HintChoice;
+
+/** 记录作答结果，返回更新后的策略 JSON */
+export declare function updateHintPolicy(
+  policyJson: string,
+  context: HintContext,
+  level: number,
+  correct: boolean,
+): string;
+
+/** 读取当前计算预算 */
+export declare function getComputeBudget(): ComputeBudget;
+
+/** 运行时切换计算模式，返回新的预算 */
+export declare function setComputeMode(mode: ComputeMode): ComputeBudget;
+
+/** 校验 JSON 规则 */
+export declare function validatePolicyRules(rulesJson: string): PolicyValidation;
@@ -752,5 +752,20 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding;
+module.exports.BootstrapMode = nativeBinding.BootstrapMode;
 module.exports.CausalInferenceNative = nativeBinding.CausalInferenceNative;
+module.exports.ComputeMode = nativeBinding.ComputeMode;
+module.exports.CurveModel = nativeBinding.CurveModel;
 module.exports.Difficulty = nativeBinding.Difficulty;
+module.exports.composeSession = nativeBinding.composeSession;
+module.exports.fitLearningCurve = nativeBinding.fitLearningCurve;
+module.exports.getComputeBudget = nativeBinding.getComputeBudget;
+module.exports.masteryBand = nativeBinding.masteryBand;
+module.exports.masteryProgress = nativeBinding.masteryProgress;
+module.exports.planRescue = nativeBinding.planRescue;
+module.exports.selectHintLevel = nativeBinding.selectHintLevel;
+module.exports.updateHintPolicy = nativeBinding.updateHintPolicy;
+module.exports.matchBlank = nativeBinding.matchBlank;
+module.exports.scoreCloze = nativeBinding.scoreCloze;
+module.exports.setComputeMode = nativeBinding.setComputeMode;
+module.exports.validatePolicyRules = nativeBinding.validatePolicyRules;
@@ -0,0 +1,291 @@
+//! 会话内自适应批量大小
+//!
+//! 策略给出的 `batch_size` 只是会话开始时的基准。每批结束后按滚动正确率、
+//! 反应时漂移与疲劳度计算误差，用带限幅的 PI 控制器调整下一批的大小：
+//! 答得好且不累时逐步加量，正确率下滑、变慢或疲劳时缩小批次。
+//!
+//! 控制器无内部可变状态，积分项通过 `BatchControllerState` 在调用之间传递，
+//! 便于 UI 在两批之间直接调用。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::policy::{compose_session, ComposedSession, SessionItem};
+
+/// 控制器参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BatchControllerConfig {
+    pub min_size: u32,
+    pub max_size: u32,
+    /// 期望的滚动正确率
+    pub target_accuracy: f64,
+    /// 比例增益（每单位误差对应的词数）
+    pub kp: f64,
+    /// 积分增益
+    pub ki: f64,
+    /// 反应时变慢（漂移为正）的惩罚权重
+    pub latency_weight: f64,
+    /// 疲劳度惩罚权重
+    pub fatigue_weight: f64,
+    /// 积分项绝对值上限
+    pub integral_limit: f64,
+}
+
+impl Default for BatchControllerConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 3,
+            max_size: 20,
+            target_accuracy: 0.8,
+            kp: 10.0,
+            ki: 4.0,
+            latency_weight: 0.5,
+            fatigue_weight: 0.4,
+            integral_limit: 2.0,
+        }
+    }
+}
+
+/// 一批结束时的观测
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSignals {
+    /// 最近若干题的正确率 [0, 1]
+    pub rolling_accuracy: f64,
+    /// 最近反应时相对会话基线的变化比例（0.2 表示慢了 20%）
+    pub latency_drift: f64,
+    /// 疲劳度 [0, 1]
+    pub fatigue: f64,
+}
+
+/// 跨批次传递的控制器状态
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchControllerState {
+    /// 会话开始时策略给出的批量大小
+    pub base_size: u32,
+    pub integral: f64,
+    pub last_size: u32,
+}
+
+impl BatchControllerState {
+    pub fn new(base_size: u32) -> Self {
+        Self {
+            base_size,
+            integral: 0.0,
+            last_size: base_size,
+        }
+    }
+}
+
+/// 控制器输出
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSizeDecision {
+    pub size: u32,
+    /// 本次误差（正值表示可以加量）
+    pub error: f64,
+    /// 输出被上下限截断
+    pub saturated: bool,
+    pub state: BatchControllerState,
+}
+
+fn clamp_unit(v: f64) -> f64 {
+    if v.is_finite() {
+        v.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+impl BatchControllerConfig {
+    fn bounds(&self) -> (f64, f64) {
+        let min = self.min_size.max(1) as f64;
+        (min, (self.max_size as f64).max(min))
+    }
+
+    /// 误差：正确率偏差减去变慢与疲劳的惩罚
+    pub fn error(&self, signals: &BatchSignals) -> f64 {
+        let drift = if signals.latency_drift.is_finite() {
+            signals.latency_drift.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        clamp_unit(signals.rolling_accuracy)
+            - self.target_accuracy
+            - self.latency_weight * drift
+            - self.fatigue_weight * clamp_unit(signals.fatigue)
+    }
+
+    /// 计算下一批的大小
+    pub fn next(&self, state: &BatchControllerState, signals: &BatchSignals) -> BatchSizeDecision {
+        let (min, max) = self.bounds();
+        let error = self.error(signals);
+        let base = (state.base_size as f64).clamp(min, max);
+
+        let integral = (state.integral + error).clamp(-self.integral_limit, self.integral_limit);
+        let raw = base + self.kp * error + self.ki * integral;
+        let saturated = raw < min || raw > max;
+        // 条件积分抗饱和：输出已截断且误差继续推向同一方向时不累积
+        let integral = if (raw > max && error > 0.0) || (raw < min && error < 0.0) {
+            state.integral
+        } else {
+            integral
+        };
+        let size = raw.clamp(min, max).round() as u32;
+
+        BatchSizeDecision {
+            size,
+            error,
+            saturated,
+            state: BatchControllerState {
+                base_size: state.base_size,
+                integral,
+                last_size: size,
+            },
+        }
+    }
+}
+
+/// 调整后的下一批及其编排结果
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdaptiveBatch {
+    pub decision: BatchSizeDecision,
+    pub session: ComposedSession,
+    /// 编排后超出本批大小、留待下一批的词
+    pub deferred_word_ids: Vec<String>,
+}
+
+/// 计算下一批大小（无状态入口，`state` 缺省时以 `base_size` 开始）
+#[cfg_attr(feature = "napi", napi)]
+pub fn next_batch_size(
+    config: Option<BatchControllerConfig>,
+    state: Option<BatchControllerState>,
+    base_size: u32,
+    signals: BatchSignals,
+) -> BatchSizeDecision {
+    let state = state.unwrap_or_else(|| BatchControllerState::new(base_size));
+    config.unwrap_or_default().next(&state, &signals)
+}
+
+/// 按策略规则编排候选，并截取为控制器给出的批量大小
+#[cfg_attr(feature = "napi", napi)]
+pub fn compose_adaptive_batch(
+    rules_json: String,
+    candidates: Vec<SessionItem>,
+    config: Option<BatchControllerConfig>,
+    state: Option<BatchControllerState>,
+    base_size: u32,
+    signals: BatchSignals,
+) -> AdaptiveBatch {
+    let decision = next_batch_size(config, state, base_size, signals);
+    let mut session = compose_session(rules_json, candidates);
+    let size = decision.size as usize;
+    let mut deferred_word_ids = Vec::new();
+    if session.items.len() > size {
+        for item in session.items.split_off(size) {
+            if !deferred_word_ids.contains(&item.word_id) {
+                deferred_word_ids.push(item.word_id);
+            }
+        }
+        // 违规位置只保留本批内的
+        session.violations.retain(|v| (v.position as usize) < size);
+    }
+    AdaptiveBatch {
+        decision,
+        session,
+        deferred_word_ids,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals(accuracy: f64, drift: f64, fatigue: f64) -> BatchSignals {
+        BatchSignals {
+            rolling_accuracy: accuracy,
+            latency_drift: drift,
+            fatigue,
+        }
+    }
+
+    #[test]
+    fn test_on_target_keeps_base_size() {
+        let decision = next_batch_size(None, None, 8, signals(0.8, 0.0, 0.0));
+        assert_eq!(decision.size, 8);
+        assert!(!decision.saturated);
+    }
+
+    #[test]
+    fn test_grows_when_doing_well_and_shrinks_when_tired() {
+        let config = BatchControllerConfig::default();
+        let mut state = BatchControllerState::new(8);
+        let mut sizes = Vec::new();
+        for _ in 0..3 {
+            let d = config.next(&state, &signals(0.95, 0.0, 0.0));
+            sizes.push(d.size);
+            state = d.state;
+        }
+        assert!(sizes.windows(2).all(|w| w[1] >= w[0]));
+        assert!(sizes[0] > 8);
+
+        let tired = config.next(&state, &signals(0.7, 0.4, 0.8));
+        assert!(tired.size < *sizes.last().unwrap());
+    }
+
+    #[test]
+    fn test_bounds_and_anti_windup() {
+        let config = BatchControllerConfig::default();
+        let mut state = BatchControllerState::new(10);
+        for _ in 0..20 {
+            let d = config.next(&state, &signals(0.0, 1.0, 1.0));
+            assert_eq!(d.size, config.min_size);
+            assert!(d.saturated);
+            state = d.state;
+        }
+        // 积分未无限累积：恢复后很快回升
+        assert!(state.integral.abs() <= config.integral_limit);
+        let recovered = config.next(&state, &signals(1.0, 0.0, 0.0));
+        assert!(recovered.size > config.min_size);
+    }
+
+    #[test]
+    fn test_invalid_signals_are_neutralized() {
+        let d = next_batch_size(None, None, 8, signals(f64::NAN, f64::INFINITY, -3.0));
+        assert!(d.error.is_finite());
+        assert!((3..=20).contains(&d.size));
+    }
+
+    #[test]
+    fn test_compose_adaptive_batch_truncates() {
+        let candidates: Vec<SessionItem> = (0..12)
+            .map(|i| SessionItem {
+                word_id: format!("w{i}"),
+                is_new: i % 2 == 0,
+                failed: false,
+                difficulty: None,
+                confusable_with: None,
+                recall_probability: None,
+            })
+            .collect();
+        let batch = compose_adaptive_batch(
+            r#"[{"type":"maxConsecutiveNew","limit":2}]"#.to_string(),
+            candidates,
+            None,
+            None,
+            5,
+            signals(0.8, 0.0, 0.0),
+        );
+        assert_eq!(batch.session.items.len(), 5);
+        assert_eq!(batch.deferred_word_ids.len(), 7);
+    }
+}
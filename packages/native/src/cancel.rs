@@ -0,0 +1,219 @@
+//! 长时间批量计算的协作式取消
+//!
+//! 批量接口接收一个 `CancellationToken`，在分块之间检查取消标志：已开始的块总会
+//! 算完，之后立即返回已完成的部分结果并标记 `cancelled`。结果中的 `completed`
+//! 即下一次的起始偏移，调用方用剩余输入再次调用即可续算。
+//!
+//! 块内按 `ComputeBudget` 决定是否用 Rayon 并行，块大小取预算的批量上限，
+//! 节能模式下块更小，取消响应也更快。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::compute::{get_compute_budget, ComputeBudget};
+use crate::language::LanguageParams;
+
+/// 可在线程间共享的取消标志；克隆共享同一标志
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// 可能被取消的批量计算结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancellableBatch<T> {
+    /// 与输入前 `completed` 项一一对应
+    pub items: Vec<T>,
+    /// 已完成的输入数（续算时的起始偏移）
+    pub completed: u32,
+    pub total: u32,
+    pub cancelled: bool,
+}
+
+/// 按块处理输入，每块之前检查取消标志
+pub fn map_chunked<I, T, F>(
+    inputs: &[I],
+    budget: &ComputeBudget,
+    token: &CancellationToken,
+    f: F,
+) -> CancellableBatch<T>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I) -> T + Sync,
+{
+    let chunk_size = (budget.max_batch_size as usize).max(1);
+    let mut items = Vec::with_capacity(inputs.len());
+    let mut cancelled = false;
+    for chunk in inputs.chunks(chunk_size) {
+        if token.is_cancelled() {
+            cancelled = true;
+            break;
+        }
+        if budget.parallel {
+            items.par_extend(chunk.par_iter().map(&f));
+        } else {
+            items.extend(chunk.iter().map(&f));
+        }
+    }
+    CancellableBatch {
+        completed: items.len() as u32,
+        total: inputs.len() as u32,
+        items,
+        cancelled,
+    }
+}
+
+/// 单词的复习间隔输入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalInput {
+    pub word_id: String,
+    /// 记忆半衰期（天）
+    pub half_life_days: f64,
+    /// 期望复习时的记忆保持率，缺省 0.9
+    pub target_retention: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewInterval {
+    pub word_id: String,
+    pub interval_days: f64,
+}
+
+/// 复习间隔下限（天）
+const MIN_INTERVAL_DAYS: f64 = 0.01;
+/// 复习间隔上限（天）
+const MAX_INTERVAL_DAYS: f64 = 365.0;
+const DEFAULT_TARGET_RETENTION: f64 = 0.9;
+
+/// 保持率按 2^(-t/h) 衰减，降到目标保持率所需的时间，再按语言参数缩放
+pub fn review_interval(input: &IntervalInput, params: &LanguageParams) -> ReviewInterval {
+    let half_life = if input.half_life_days.is_finite() {
+        input.half_life_days.max(0.0)
+    } else {
+        0.0
+    };
+    let retention = input
+        .target_retention
+        .filter(|r| r.is_finite())
+        .unwrap_or(DEFAULT_TARGET_RETENTION)
+        .clamp(0.5, 0.99);
+    let interval = half_life * (1.0 / retention).log2() * params.interval_factor();
+    ReviewInterval {
+        word_id: input.word_id.clone(),
+        interval_days: interval.clamp(MIN_INTERVAL_DAYS, MAX_INTERVAL_DAYS),
+    }
+}
+
+/// 批量计算复习间隔，可通过 `token` 中途取消
+pub fn review_intervals_batch(
+    inputs: &[IntervalInput],
+    params: &LanguageParams,
+    token: &CancellationToken,
+) -> CancellableBatch<ReviewInterval> {
+    map_chunked(inputs, &get_compute_budget(), token, |input| {
+        review_interval(input, params)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::ComputeMode;
+
+    fn inputs(n: usize) -> Vec<IntervalInput> {
+        (0..n)
+            .map(|i| IntervalInput {
+                word_id: format!("w{i}"),
+                half_life_days: 1.0 + i as f64 % 30.0,
+                target_retention: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_interval_scales_with_half_life_and_language() {
+        let input = IntervalInput {
+            word_id: "w".into(),
+            half_life_days: 10.0,
+            target_retention: Some(0.5),
+        };
+        let base = review_interval(&input, &LanguageParams::default());
+        assert!((base.interval_days - 10.0).abs() < 1e-9);
+
+        let faster = LanguageParams {
+            decay_rate: 2.0,
+            ..Default::default()
+        };
+        assert!((review_interval(&input, &faster).interval_days - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_completes_without_cancellation() {
+        let batch = review_intervals_batch(
+            &inputs(1000),
+            &LanguageParams::default(),
+            &CancellationToken::new(),
+        );
+        assert!(!batch.cancelled);
+        assert_eq!(batch.completed, 1000);
+        assert_eq!(batch.items[999].word_id, "w999");
+    }
+
+    #[test]
+    fn test_cancel_returns_partial_result_that_can_resume() {
+        let data = inputs(1000);
+        let budget = ComputeBudget::for_mode(ComputeMode::Eco);
+        let token = CancellationToken::new();
+        let items_seen = std::sync::atomic::AtomicUsize::new(0);
+        let partial = map_chunked(&data, &budget, &token, |input| {
+            // 第二块开始时取消：第二块照常算完，第三块之前停止
+            if items_seen.fetch_add(1, Ordering::Relaxed) == budget.max_batch_size as usize {
+                token.cancel();
+            }
+            review_interval(input, &LanguageParams::default())
+        });
+        assert!(partial.cancelled);
+        assert_eq!(partial.completed, 2 * budget.max_batch_size);
+        assert_eq!(partial.items.len(), partial.completed as usize);
+
+        let rest = map_chunked(
+            &data[partial.completed as usize..],
+            &budget,
+            &CancellationToken::new(),
+            |input| review_interval(input, &LanguageParams::default()),
+        );
+        assert_eq!(partial.completed + rest.completed, 1000);
+        assert_eq!(rest.items[0].word_id, format!("w{}", partial.completed));
+    }
+
+    #[test]
+    fn test_already_cancelled_token_does_no_work() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let batch = review_intervals_batch(&inputs(10), &LanguageParams::default(), &token);
+        assert!(batch.cancelled);
+        assert_eq!(batch.completed, 0);
+        assert_eq!(batch.total, 10);
+    }
+}
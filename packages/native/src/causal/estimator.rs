@@ -2,9 +2,10 @@ use super::*;
 #[cfg(feature = "napi")]
 use napi_derive::napi;
 use rand::prelude::*;
-use rand::SeedableRng;
-use rand_chacha::ChaCha8Rng;
 use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::rng::{domains, RngFactory};
 
 /// 数值稳定性常量
 const EPSILON: f64 = 1e-10;
@@ -33,6 +34,7 @@ pub struct CausalInferenceNative {
     regularization: f64,
     max_iterations: u32,
     convergence_threshold: f64,
+    bootstrap_mode: BootstrapMode,
 }
 
 #[cfg_attr(feature = "napi", napi)]
@@ -54,6 +56,7 @@ impl CausalInferenceNative {
             regularization: config.regularization.unwrap_or(0.01),
             max_iterations: config.max_iterations.unwrap_or(1000),
             convergence_threshold: config.convergence_threshold.unwrap_or(1e-6),
+            bootstrap_mode: config.bootstrap_mode.unwrap_or_default(),
         }
     }
 
@@ -64,54 +67,13 @@ impl CausalInferenceNative {
             return;
         }
 
-        let n = observations.len();
-        let d = self.feature_dim + 1; // 维度+1用于截距项
-        let mut weights = vec![0.0; d];
-
-        let mut prev_loss = f64::INFINITY;
-
-        // 梯度下降
-        for _iter in 0..self.max_iterations {
-            let mut gradients = vec![0.0; d];
-            let mut loss = 0.0;
-
-            for obs in &observations {
-                // 添加截距项（特征末尾加1）
-                let features_with_bias = Self::add_bias(&obs.features);
-                let logit = Self::dot_product(&features_with_bias, &weights);
-                let pred = Self::sigmoid(logit);
-
-                let treatment = obs.treatment as f64;
-                // 交叉熵损失
-                loss += -treatment * (pred + EPSILON).ln()
-                    - (1.0 - treatment) * (1.0 - pred + EPSILON).ln();
-
-                // 梯度计算
-                let error = pred - treatment;
-                for j in 0..d {
-                    gradients[j] += error * features_with_bias[j];
-                }
-            }
-
-            // 添加L2正则化（不对截距项正则化）
-            for j in 0..(d - 1) {
-                loss += (self.regularization / 2.0) * weights[j] * weights[j];
-                gradients[j] += self.regularization * weights[j];
-            }
-
-            // 更新权重
-            for j in 0..d {
-                weights[j] -= self.learning_rate * gradients[j] / n as f64;
-            }
-
-            // 检查收敛
-            if (prev_loss - loss).abs() < self.convergence_threshold {
-                break;
-            }
-            prev_loss = loss;
-        }
+        // 添加截距项（特征末尾加1）
+        let rows: Vec<(Vec<f64>, f64)> = observations
+            .iter()
+            .map(|obs| (Self::add_bias(&obs.features), obs.treatment as f64))
+            .collect();
 
-        self.propensity_weights = weights;
+        self.propensity_weights = self.fit_logistic(&rows, self.feature_dim + 1);
     }
 
     /// 训练结果模型（Ridge回归 + Cholesky分解）
@@ -231,64 +193,91 @@ impl CausalInferenceNative {
         }
     }
 
-    /// Bootstrap 标准误估计（使用 Rayon 并行化）
+    /// Bootstrap 标准误估计（常规模式下使用 Rayon 并行化，节能模式下串行且限制重采样次数）
+    ///
+    /// 簇模式下按 cluster_id（缺省为 user_id）有放回抽取整簇，
+    /// 避免同一用户的多条观测被当作独立样本而低估方差
     #[cfg_attr(feature = "napi", napi)]
     pub fn bootstrap_se(
         &self,
         observations: Vec<CausalObservation>,
         n_bootstrap: Option<u32>,
     ) -> f64 {
-        let n_bootstrap = n_bootstrap.unwrap_or(100) as usize;
+        let budget = crate::compute::get_compute_budget();
+        let n_bootstrap = n_bootstrap.unwrap_or(100).min(budget.max_bootstrap) as usize;
         let n = observations.len();
 
         if n < 10 || !self.fitted {
             return 0.0;
         }
 
-        // 使用 Rayon 并行计算
-        let estimates: Vec<f64> = (0..n_bootstrap)
-            .into_par_iter()
-            .filter_map(|seed| {
-                // 每个线程使用不同的种子
-                let mut rng = ChaCha8Rng::seed_from_u64(seed as u64);
+        let clusters = match self.bootstrap_mode {
+            BootstrapMode::Observation => Vec::new(),
+            BootstrapMode::Cluster => Self::cluster_indices(&observations),
+        };
+        if self.bootstrap_mode == BootstrapMode::Cluster && clusters.len() < 2 {
+            return 0.0;
+        }
+
+        let factory = RngFactory::new(0);
+        let resample = |index: usize| {
+            // 每次重采样使用独立子流，并行与串行的结果一致
+            let mut rng = factory.substream(domains::CAUSAL_BOOTSTRAP, "resample", index as u64);
 
-                // 重采样
-                let sample: Vec<CausalObservation> = (0..n)
+            // 重采样
+            let sample: Vec<CausalObservation> = match self.bootstrap_mode {
+                BootstrapMode::Observation => (0..n)
                     .map(|_| {
                         let idx = rng.gen_range(0..n);
                         observations[idx].clone()
                     })
-                    .collect();
+                    .collect(),
+                BootstrapMode::Cluster => (0..clusters.len())
+                    .flat_map(|_| {
+                        let members = &clusters[rng.gen_range(0..clusters.len())];
+                        members.iter().map(|&idx| observations[idx].clone())
+                    })
+                    .collect(),
+            };
 
-                // 检查重采样后的数据是否有效
-                let treatment_obs: Vec<_> = sample.iter().filter(|o| o.treatment == 1).collect();
-                let control_obs: Vec<_> = sample.iter().filter(|o| o.treatment == 0).collect();
+            // 检查重采样后的数据是否有效
+            let treatment_obs: Vec<_> = sample.iter().filter(|o| o.treatment == 1).collect();
+            let control_obs: Vec<_> = sample.iter().filter(|o| o.treatment == 0).collect();
 
-                if treatment_obs.len() < 3 || control_obs.len() < 3 {
-                    return None;
-                }
+            if treatment_obs.len() < 3 || control_obs.len() < 3 {
+                return None;
+            }
 
-                // 在重采样数据上计算ATE
-                let mut temp_estimator = CausalInferenceNative::new(
-                    self.feature_dim as u32,
-                    Some(CausalInferenceConfig {
-                        propensity_min: Some(self.propensity_min),
-                        propensity_max: Some(self.propensity_max),
-                        learning_rate: Some(self.learning_rate),
-                        regularization: Some(self.regularization),
-                        max_iterations: Some(self.max_iterations),
-                        convergence_threshold: Some(self.convergence_threshold),
-                    }),
-                );
-                temp_estimator.fit(sample.clone());
-
-                if temp_estimator.fitted {
-                    Some(temp_estimator.estimate_ate(sample).ate)
-                } else {
-                    None
-                }
-            })
-            .collect();
+            // 在重采样数据上计算ATE
+            let mut temp_estimator = CausalInferenceNative::new(
+                self.feature_dim as u32,
+                Some(CausalInferenceConfig {
+                    propensity_min: Some(self.propensity_min),
+                    propensity_max: Some(self.propensity_max),
+                    learning_rate: Some(self.learning_rate),
+                    regularization: Some(self.regularization),
+                    max_iterations: Some(self.max_iterations),
+                    convergence_threshold: Some(self.convergence_threshold),
+                    bootstrap_mode: Some(self.bootstrap_mode),
+                }),
+            );
+            temp_estimator.fit(sample.clone());
+
+            if temp_estimator.fitted {
+                Some(temp_estimator.estimate_ate(sample).ate)
+            } else {
+                None
+            }
+        };
+
+        let estimates: Vec<f64> = if budget.parallel {
+            (0..n_bootstrap)
+                .into_par_iter()
+                .filter_map(resample)
+                .collect()
+        } else {
+            (0..n_bootstrap).filter_map(resample).collect()
+        };
 
         if estimates.len() < 10 {
             return 0.0;
@@ -297,6 +286,161 @@ impl CausalInferenceNative {
         Self::variance(&estimates).sqrt()
     }
 
+    /// 时变处理分析（序贯可忽略性）
+    /// 稳定化权重: sw_it = prod_{k<=t} P(A_k | A_{k-1}) / P(A_k | L_k, A_{k-1})
+    /// 各时间段效应为稳定化权重下处理组与对照组结果的加权均值之差
+    #[cfg_attr(feature = "napi", napi)]
+    pub fn estimate_time_varying(
+        &self,
+        observations: Vec<SessionObservation>,
+    ) -> LongitudinalEstimate {
+        let mut histories: BTreeMap<&str, Vec<&SessionObservation>> = BTreeMap::new();
+        for obs in &observations {
+            histories.entry(obs.unit_id.as_str()).or_default().push(obs);
+        }
+        for history in histories.values_mut() {
+            history.sort_by_key(|o| o.period);
+        }
+
+        let empty = LongitudinalEstimate {
+            periods: Vec::new(),
+            pooled_effect: 0.0,
+            pooled_standard_error: 0.0,
+            unit_count: histories.len() as u32,
+            sample_size: observations.len() as u32,
+        };
+        let treated = observations.iter().filter(|o| o.treatment == 1).count();
+        if observations.len() < 10 || treated == 0 || treated == observations.len() {
+            return empty;
+        }
+
+        // 分母模型: 协变量 + 上期处理 + 截距
+        let d = self.feature_dim + 2;
+        let mut rows = Vec::with_capacity(observations.len());
+        // 分子模型: 按 (period, 上期处理) 的经验处理率
+        let mut numerator_counts: BTreeMap<(u32, u8), (f64, f64)> = BTreeMap::new();
+        for history in histories.values() {
+            let mut prev = 0u8;
+            for obs in history {
+                let mut x = obs.features.clone();
+                x.resize(self.feature_dim, 0.0);
+                x.push(prev as f64);
+                x.push(1.0);
+                rows.push((x, obs.treatment as f64));
+                let entry = numerator_counts.entry((obs.period, prev)).or_default();
+                entry.0 += obs.treatment as f64;
+                entry.1 += 1.0;
+                prev = obs.treatment;
+            }
+        }
+        let denominator_weights = self.fit_logistic(&rows, d);
+
+        // 逐个体累乘稳定化权重，按时间段归组
+        let mut by_period: BTreeMap<u32, Vec<(f64, u8, f64, bool)>> = BTreeMap::new();
+        let mut row_idx = 0;
+        for history in histories.values() {
+            let mut prev = 0u8;
+            let mut cumulative = 1.0;
+            for obs in history {
+                let den = Self::sigmoid(Self::dot_product(&rows[row_idx].0, &denominator_weights))
+                    .clamp(self.propensity_min, self.propensity_max);
+                let (count_treated, count_total) = numerator_counts[&(obs.period, prev)];
+                let num =
+                    (count_treated / count_total).clamp(self.propensity_min, self.propensity_max);
+                cumulative *= if obs.treatment == 1 {
+                    num / den
+                } else {
+                    (1.0 - num) / (1.0 - den)
+                };
+                let truncated = !(1.0 / MAX_WEIGHT..=MAX_WEIGHT).contains(&cumulative);
+                cumulative = cumulative.clamp(1.0 / MAX_WEIGHT, MAX_WEIGHT);
+                by_period.entry(obs.period).or_default().push((
+                    cumulative,
+                    obs.treatment,
+                    obs.outcome,
+                    truncated,
+                ));
+                prev = obs.treatment;
+                row_idx += 1;
+            }
+        }
+
+        let periods: Vec<PeriodEffect> = by_period
+            .into_iter()
+            .map(|(period, samples)| {
+                let treated: Vec<(f64, f64)> = samples
+                    .iter()
+                    .filter(|s| s.1 == 1)
+                    .map(|s| (s.0, s.2))
+                    .collect();
+                let control: Vec<(f64, f64)> = samples
+                    .iter()
+                    .filter(|s| s.1 == 0)
+                    .map(|s| (s.0, s.2))
+                    .collect();
+                let weights: Vec<f64> = samples.iter().map(|s| s.0).collect();
+                let truncated_count = samples.iter().filter(|s| s.3).count() as u32;
+
+                let (effect, se) = match (
+                    Self::weighted_moments(&treated),
+                    Self::weighted_moments(&control),
+                ) {
+                    (Some((m1, v1, n1)), Some((m0, v0, n0))) => {
+                        (m1 - m0, (v1 / n1 + v0 / n0).sqrt())
+                    }
+                    _ => (0.0, 0.0),
+                };
+                let p_value = if se > 0.0 {
+                    2.0 * (1.0 - Self::normal_cdf(effect.abs() / se))
+                } else {
+                    1.0
+                };
+
+                PeriodEffect {
+                    period,
+                    effect,
+                    standard_error: se,
+                    confidence_interval_lower: effect - Z_95 * se,
+                    confidence_interval_upper: effect + Z_95 * se,
+                    p_value,
+                    treated_count: treated.len() as u32,
+                    control_count: control.len() as u32,
+                    weights: Self::weight_diagnostics(&weights, truncated_count),
+                }
+            })
+            .collect();
+
+        let identified: Vec<&PeriodEffect> = periods
+            .iter()
+            .filter(|p| p.treated_count > 0 && p.control_count > 0)
+            .collect();
+        let total: f64 = identified
+            .iter()
+            .map(|p| (p.treated_count + p.control_count) as f64)
+            .sum();
+        if total == 0.0 {
+            return LongitudinalEstimate { periods, ..empty };
+        }
+        let pooled_effect = identified
+            .iter()
+            .map(|p| (p.treated_count + p.control_count) as f64 / total * p.effect)
+            .sum();
+        let pooled_standard_error = identified
+            .iter()
+            .map(|p| {
+                ((p.treated_count + p.control_count) as f64 / total * p.standard_error).powi(2)
+            })
+            .sum::<f64>()
+            .sqrt();
+
+        LongitudinalEstimate {
+            periods,
+            pooled_effect,
+            pooled_standard_error,
+            ..empty
+        }
+    }
+
     /// 诊断倾向得分分布
     #[cfg_attr(feature = "napi", napi)]
     pub fn diagnose_propensity(
@@ -405,6 +549,88 @@ impl CausalInferenceNative {
 
 // 私有实现方法
 impl CausalInferenceNative {
+    /// 逻辑回归（梯度下降 + L2正则化，最后一维为截距项不参与正则化）
+    fn fit_logistic(&self, rows: &[(Vec<f64>, f64)], d: usize) -> Vec<f64> {
+        let n = rows.len();
+        let mut weights = vec![0.0; d];
+
+        let mut prev_loss = f64::INFINITY;
+
+        // 梯度下降
+        for _iter in 0..self.max_iterations {
+            let mut gradients = vec![0.0; d];
+            let mut loss = 0.0;
+
+            for (features_with_bias, treatment) in rows {
+                let logit = Self::dot_product(features_with_bias, &weights);
+                let pred = Self::sigmoid(logit);
+
+                // 交叉熵损失
+                loss += -treatment * (pred + EPSILON).ln()
+                    - (1.0 - treatment) * (1.0 - pred + EPSILON).ln();
+
+                // 梯度计算
+                let error = pred - treatment;
+                for j in 0..d {
+                    gradients[j] += error * features_with_bias[j];
+                }
+            }
+
+            // 添加L2正则化（不对截距项正则化）
+            for j in 0..(d - 1) {
+                loss += (self.regularization / 2.0) * weights[j] * weights[j];
+                gradients[j] += self.regularization * weights[j];
+            }
+
+            // 更新权重
+            for j in 0..d {
+                weights[j] -= self.learning_rate * gradients[j] / n as f64;
+            }
+
+            // 检查收敛
+            if (prev_loss - loss).abs() < self.convergence_threshold {
+                break;
+            }
+            prev_loss = loss;
+        }
+
+        weights
+    }
+
+    /// 稳定化权重分布诊断
+    fn weight_diagnostics(weights: &[f64], truncated_count: u32) -> WeightDiagnostics {
+        let sum: f64 = weights.iter().sum();
+        let sum_sq: f64 = weights.iter().map(|w| w * w).sum();
+        WeightDiagnostics {
+            mean: Self::mean(weights),
+            std: Self::variance(weights).sqrt(),
+            min: weights.iter().copied().fold(f64::INFINITY, f64::min),
+            max: weights.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            effective_sample_size: if sum_sq > 0.0 {
+                sum * sum / sum_sq
+            } else {
+                0.0
+            },
+            truncated_count,
+        }
+    }
+
+    /// 加权均值、加权方差与有效样本量 (Hajek)
+    fn weighted_moments(samples: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+        let sum_w: f64 = samples.iter().map(|(w, _)| w).sum();
+        if sum_w <= EPSILON {
+            return None;
+        }
+        let sum_w_sq: f64 = samples.iter().map(|(w, _)| w * w).sum();
+        let mean = samples.iter().map(|(w, y)| w * y).sum::<f64>() / sum_w;
+        let var = samples
+            .iter()
+            .map(|(w, y)| w * (y - mean).powi(2))
+            .sum::<f64>()
+            / sum_w;
+        Some((mean, var, sum_w * sum_w / sum_w_sq))
+    }
+
     /// 添加截距项（在特征末尾加1）
     fn add_bias(features: &[f64]) -> Vec<f64> {
         let mut result = features.to_vec();
@@ -481,6 +707,19 @@ impl CausalInferenceNative {
         0.5 * (1.0 + sign * y)
     }
 
+    /// 按簇键分组的观测下标；无簇键的观测各自成簇
+    fn cluster_indices(observations: &[CausalObservation]) -> Vec<Vec<usize>> {
+        let mut keyed: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        let mut singletons = Vec::new();
+        for (idx, obs) in observations.iter().enumerate() {
+            match obs.cluster_key() {
+                Some(key) => keyed.entry(key).or_default().push(idx),
+                None => singletons.push(vec![idx]),
+            }
+        }
+        keyed.into_values().chain(singletons).collect()
+    }
+
     /// 拟合线性回归（OLS with Ridge，自动添加截距项）
     fn fit_linear_regression(&self, data: &[CausalObservation]) -> Vec<f64> {
         let n = data.len();
@@ -623,6 +862,7 @@ impl CausalInferenceNative {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand_chacha::ChaCha8Rng;
 
     /// 创建测试观测数据
     fn create_test_observations(n: usize, seed: u64) -> Vec<CausalObservation> {
@@ -656,12 +896,78 @@ mod tests {
                 outcome: outcome.clamp(-1.0, 1.0),
                 timestamp: None,
                 user_id: None,
+                cluster_id: None,
             });
         }
 
         observations
     }
 
+    /// 创建时变处理测试数据（协变量受上期处理影响，真实效应 = 0.3）
+    fn create_session_observations(
+        units: usize,
+        periods: u32,
+        seed: u64,
+    ) -> Vec<SessionObservation> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut observations = Vec::new();
+
+        for unit in 0..units {
+            let mut prev = 0u8;
+            for period in 0..periods {
+                let x: f64 = rng.gen_range(-1.0..1.0) + 0.3 * prev as f64;
+                let p = 1.0 / (1.0 + (-(0.8 * x + 0.5 * prev as f64 - 0.3)).exp());
+                let treatment = if rng.gen::<f64>() < p { 1 } else { 0 };
+                let outcome = 0.4 * x + 0.3 * treatment as f64 + rng.gen_range(-0.1..0.1);
+                observations.push(SessionObservation {
+                    unit_id: format!("u{unit}"),
+                    period,
+                    features: vec![x],
+                    treatment,
+                    outcome,
+                });
+                prev = treatment;
+            }
+        }
+
+        observations
+    }
+
+    #[test]
+    fn test_estimate_time_varying() {
+        let observations = create_session_observations(200, 3, 7);
+        let estimator = CausalInferenceNative::new(1, None);
+
+        let estimate = estimator.estimate_time_varying(observations);
+
+        assert_eq!(estimate.unit_count, 200);
+        assert_eq!(estimate.sample_size, 600);
+        assert_eq!(estimate.periods.len(), 3);
+        assert!((estimate.pooled_effect - 0.3).abs() < 0.15);
+        for period in &estimate.periods {
+            assert!(period.treated_count > 0 && period.control_count > 0);
+            assert!(period.weights.min >= 1.0 / MAX_WEIGHT);
+            assert!(period.weights.max <= MAX_WEIGHT);
+            // 稳定化权重均值应接近 1
+            assert!((period.weights.mean - 1.0).abs() < 0.5);
+            assert!(period.weights.effective_sample_size > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_estimate_time_varying_without_variation() {
+        let observations: Vec<SessionObservation> = create_session_observations(10, 2, 3)
+            .into_iter()
+            .map(|o| SessionObservation { treatment: 1, ..o })
+            .collect();
+        let estimator = CausalInferenceNative::new(1, None);
+
+        let estimate = estimator.estimate_time_varying(observations);
+
+        assert!(estimate.periods.is_empty());
+        assert_eq!(estimate.pooled_effect, 0.0);
+    }
+
     #[test]
     fn test_new_estimator() {
         let estimator = CausalInferenceNative::new(3, None);
@@ -825,10 +1131,10 @@ mod tests {
 
         estimator.fit(observations);
 
-        let score = estimator.get_propensity_score(&vec![0.5, 0.5]);
+        let score = estimator.get_propensity_score(&[0.5, 0.5]);
 
         // 分数应该在配置的范围内
-        assert!(score >= 0.05 && score <= 0.95);
+        assert!((0.05..=0.95).contains(&score));
     }
 
     #[test]
@@ -838,8 +1144,8 @@ mod tests {
 
         estimator.fit(observations);
 
-        let outcome_treatment = estimator.predict_outcome(&vec![0.5, 0.5], 1);
-        let outcome_control = estimator.predict_outcome(&vec![0.5, 0.5], 0);
+        let outcome_treatment = estimator.predict_outcome(&[0.5, 0.5], 1);
+        let outcome_control = estimator.predict_outcome(&[0.5, 0.5], 0);
 
         // 处理组的预测应该与对照组不同
         assert!((outcome_treatment - outcome_control).abs() > EPSILON);
@@ -857,6 +1163,70 @@ mod tests {
         assert!(se >= 0.0);
     }
 
+    #[test]
+    fn test_cluster_bootstrap_widens_se_for_clustered_outcomes() {
+        // 20 个用户各 10 条观测，处理按用户分配，结果含较大的用户级随机效应
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut observations = Vec::new();
+        for user in 0..20 {
+            let user_effect: f64 = rng.gen_range(-0.5..0.5);
+            let treatment = u8::from(user % 2 == 0);
+            for _ in 0..10 {
+                let x1: f64 = rng.gen_range(-1.0..1.0);
+                let outcome = user_effect
+                    + 0.2 * x1
+                    + if treatment == 1 { 0.3 } else { 0.0 }
+                    + rng.gen_range(-0.05..0.05);
+                observations.push(CausalObservation {
+                    features: vec![x1],
+                    treatment,
+                    outcome,
+                    timestamp: None,
+                    user_id: Some(format!("u{user}")),
+                    cluster_id: None,
+                });
+            }
+        }
+
+        let se_for = |mode| {
+            let mut estimator = CausalInferenceNative::new(
+                1,
+                Some(CausalInferenceConfig {
+                    bootstrap_mode: Some(mode),
+                    ..Default::default()
+                }),
+            );
+            estimator.fit(observations.clone());
+            estimator.bootstrap_se(observations.clone(), Some(100))
+        };
+        let naive = se_for(BootstrapMode::Observation);
+        let clustered = se_for(BootstrapMode::Cluster);
+        assert!(naive > 0.0);
+        assert!(clustered > naive, "clustered {clustered} <= naive {naive}");
+    }
+
+    #[test]
+    fn test_cluster_indices_keep_observations_together() {
+        let obs = |user: Option<&str>, cluster: Option<&str>| CausalObservation {
+            features: vec![0.0],
+            treatment: 0,
+            outcome: 0.0,
+            timestamp: None,
+            user_id: user.map(String::from),
+            cluster_id: cluster.map(String::from),
+        };
+        let observations = vec![
+            obs(Some("a"), None),
+            obs(Some("b"), None),
+            obs(Some("a"), None),
+            obs(Some("b"), Some("a")),
+            obs(None, None),
+        ];
+        let clusters = CausalInferenceNative::cluster_indices(&observations);
+        // cluster_id "a" 与 user_id "a" 是不同的簇
+        assert_eq!(clusters, vec![vec![3], vec![0, 2], vec![1], vec![4]]);
+    }
+
     #[test]
     fn test_diagnose_propensity() {
         let observations = create_test_observations(100, 42);
@@ -960,6 +1330,7 @@ mod tests {
             regularization: Some(0.1),
             max_iterations: Some(500),
             convergence_threshold: Some(1e-5),
+            bootstrap_mode: Some(BootstrapMode::Cluster),
         };
 
         let estimator = CausalInferenceNative::new(2, Some(config));
@@ -970,5 +1341,6 @@ mod tests {
         assert_eq!(estimator.regularization, 0.1);
         assert_eq!(estimator.max_iterations, 500);
         assert_eq!(estimator.convergence_threshold, 1e-5);
+        assert_eq!(estimator.bootstrap_mode, BootstrapMode::Cluster);
     }
 }
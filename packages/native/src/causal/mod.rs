@@ -16,6 +16,21 @@ pub struct CausalObservation {
     pub timestamp: Option<f64>,
     /// 用户ID（可选）
     pub user_id: Option<String>,
+    /// 聚类ID（可选，簇 Bootstrap 使用；为空时退回 user_id）
+    pub cluster_id: Option<String>,
+}
+
+impl CausalObservation {
+    /// 簇 Bootstrap 的分组键；cluster_id 与 user_id 均为空时返回 None（单独成簇）
+    ///
+    /// 键带来源前缀，同名的 cluster_id 与 user_id 不会落入同一簇
+    pub fn cluster_key(&self) -> Option<String> {
+        match (&self.cluster_id, &self.user_id) {
+            (Some(cluster), _) => Some(format!("cluster:{cluster}")),
+            (None, Some(user)) => Some(format!("user:{user}")),
+            (None, None) => None,
+        }
+    }
 }
 
 /// 因果效应估计结果
@@ -60,6 +75,75 @@ pub struct PropensityDiagnostics {
     pub auc: f64,
 }
 
+/// 纵向（逐会话）观测数据，处理状态可随时间变化
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Clone, Debug)]
+pub struct SessionObservation {
+    /// 个体ID（同一用户的会话按 period 排序构成处理历史）
+    pub unit_id: String,
+    /// 时间段编号（会话序号或天序号）
+    pub period: u32,
+    /// 该时间段开始时的协变量
+    pub features: Vec<f64>,
+    /// 该时间段的处理标记 (0 或 1)
+    pub treatment: u8,
+    /// 该时间段的结果值
+    pub outcome: f64,
+}
+
+/// 单时间段稳定化 IP 权重分布诊断
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Clone, Debug)]
+pub struct WeightDiagnostics {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Kish 有效样本量
+    pub effective_sample_size: f64,
+    /// 触及截断上限的权重数
+    pub truncated_count: u32,
+}
+
+/// 单时间段处理效应
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Clone, Debug)]
+pub struct PeriodEffect {
+    pub period: u32,
+    /// 加权（Hajek）处理效应
+    pub effect: f64,
+    pub standard_error: f64,
+    pub confidence_interval_lower: f64,
+    pub confidence_interval_upper: f64,
+    pub p_value: f64,
+    pub treated_count: u32,
+    pub control_count: u32,
+    pub weights: WeightDiagnostics,
+}
+
+/// 时变处理分析结果（序贯可忽略性假设下的 MSM 估计）
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Clone, Debug)]
+pub struct LongitudinalEstimate {
+    pub periods: Vec<PeriodEffect>,
+    /// 按样本量加权的各时间段效应均值
+    pub pooled_effect: f64,
+    pub pooled_standard_error: f64,
+    pub unit_count: u32,
+    pub sample_size: u32,
+}
+
+/// Bootstrap 重采样单位
+#[cfg_attr(feature = "napi", napi)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BootstrapMode {
+    /// 逐条观测独立重采样
+    #[default]
+    Observation,
+    /// 按簇（通常为用户）有放回重采样，簇内观测整体保留
+    Cluster,
+}
+
 /// 因果推断配置
 #[cfg_attr(feature = "napi", napi(object))]
 #[derive(Clone, Debug)]
@@ -76,6 +160,8 @@ pub struct CausalInferenceConfig {
     pub max_iterations: Option<u32>,
     /// 收敛阈值
     pub convergence_threshold: Option<f64>,
+    /// Bootstrap 重采样单位（默认逐条观测）
+    pub bootstrap_mode: Option<BootstrapMode>,
 }
 
 impl Default for CausalInferenceConfig {
@@ -87,6 +173,7 @@ impl Default for CausalInferenceConfig {
             regularization: Some(0.01),
             max_iterations: Some(1000),
             convergence_threshold: Some(1e-6),
+            bootstrap_mode: Some(BootstrapMode::Observation),
         }
     }
 }
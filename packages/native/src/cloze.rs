@@ -0,0 +1,242 @@
+//! 完形填空（挖空）题的部分得分
+//!
+//! 每个空先做模糊匹配（归一化编辑距离 + 词干判断），再换算为 [0, 1] 的得分：
+//! 完全正确得 1；词干正确、只错了后缀（如 "running" 写成 "runing"、"runs"）
+//! 至少得 `stem_credit`；其余按相似度线性给分，部分得分不超过 `max_partial`。
+//! 整题得分为各空得分的加权平均，目标词所在的空默认权重更高。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// 词干至少包含的字符数
+const MIN_STEM_CHARS: usize = 3;
+/// 词干判断允许的最长差异后缀
+const MAX_SUFFIX_CHARS: usize = 4;
+
+/// 评分参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClozeScoringConfig {
+    /// 低于此相似度不给分
+    pub min_similarity: f64,
+    /// 词干正确时的最低得分
+    pub stem_credit: f64,
+    /// 非完全正确时的得分上限
+    pub max_partial: f64,
+    /// 目标词所在空的默认权重（其他空为 1）
+    pub target_weight: f64,
+}
+
+impl Default for ClozeScoringConfig {
+    fn default() -> Self {
+        Self {
+            min_similarity: 0.5,
+            stem_credit: 0.6,
+            max_partial: 0.8,
+            target_weight: 2.0,
+        }
+    }
+}
+
+/// 一个空的作答
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClozeBlank {
+    pub expected: String,
+    pub answer: String,
+    /// 是否为本题考查的目标词
+    #[serde(default)]
+    pub is_target: bool,
+    /// 显式权重；缺省时按是否目标词取值
+    #[serde(default)]
+    pub weight: Option<f64>,
+}
+
+/// 模糊匹配结果
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlankMatch {
+    /// 1 − 编辑距离 / 较长串长度
+    pub similarity: f64,
+    /// 词干一致，仅后缀不同
+    pub stem_match: bool,
+    pub exact: bool,
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlankScore {
+    pub matched: BlankMatch,
+    pub credit: f64,
+    pub weight: f64,
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClozeScore {
+    /// 整题得分 [0, 1]
+    pub credit: f64,
+    pub blanks: Vec<BlankScore>,
+}
+
+fn normalize(s: &str) -> Vec<char> {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .collect()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// 比较标准答案与作答（忽略大小写与多余空白）
+#[cfg_attr(feature = "napi", napi)]
+pub fn match_blank(expected: String, answer: String) -> BlankMatch {
+    let (e, a) = (normalize(&expected), normalize(&answer));
+    if e == a {
+        return BlankMatch {
+            similarity: 1.0,
+            stem_match: !e.is_empty(),
+            exact: !e.is_empty(),
+        };
+    }
+    let longest = e.len().max(a.len());
+    let similarity = if longest == 0 {
+        0.0
+    } else {
+        1.0 - levenshtein(&e, &a) as f64 / longest as f64
+    };
+    let prefix = e.iter().zip(&a).take_while(|(x, y)| x == y).count();
+    let stem_match = prefix >= MIN_STEM_CHARS
+        && e.len() - prefix <= MAX_SUFFIX_CHARS
+        && a.len() - prefix <= MAX_SUFFIX_CHARS;
+    BlankMatch {
+        similarity,
+        stem_match,
+        exact: false,
+    }
+}
+
+/// 由匹配结果换算单个空的得分
+pub fn blank_credit(matched: &BlankMatch, config: &ClozeScoringConfig) -> f64 {
+    if matched.exact {
+        return 1.0;
+    }
+    let max_partial = config.max_partial.clamp(0.0, 1.0);
+    let min = config.min_similarity.clamp(0.0, 0.99);
+    let by_similarity = if matched.similarity >= min {
+        max_partial * (matched.similarity - min) / (1.0 - min)
+    } else {
+        0.0
+    };
+    let credit = if matched.stem_match {
+        by_similarity.max(config.stem_credit)
+    } else {
+        by_similarity
+    };
+    credit.clamp(0.0, max_partial)
+}
+
+/// 整题评分；没有空时得 0
+#[cfg_attr(feature = "napi", napi)]
+pub fn score_cloze(blanks: Vec<ClozeBlank>, config: Option<ClozeScoringConfig>) -> ClozeScore {
+    let config = config.unwrap_or_default();
+    let blanks: Vec<BlankScore> = blanks
+        .into_iter()
+        .map(|blank| {
+            let default_weight = if blank.is_target {
+                config.target_weight
+            } else {
+                1.0
+            };
+            let weight = blank
+                .weight
+                .filter(|w| w.is_finite() && *w >= 0.0)
+                .unwrap_or(default_weight);
+            let matched = match_blank(blank.expected, blank.answer);
+            BlankScore {
+                credit: blank_credit(&matched, &config),
+                matched,
+                weight,
+            }
+        })
+        .collect();
+    let total_weight: f64 = blanks.iter().map(|b| b.weight).sum();
+    let credit = if total_weight > 0.0 {
+        blanks.iter().map(|b| b.credit * b.weight).sum::<f64>() / total_weight
+    } else {
+        0.0
+    };
+    ClozeScore {
+        credit: credit.clamp(0.0, 1.0),
+        blanks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank(expected: &str, answer: &str, is_target: bool) -> ClozeBlank {
+        ClozeBlank {
+            expected: expected.into(),
+            answer: answer.into(),
+            is_target,
+            weight: None,
+        }
+    }
+
+    #[test]
+    fn test_exact_and_normalized_answers_score_full() {
+        let m = match_blank("Take off".into(), "  take   OFF ".into());
+        assert!(m.exact);
+        assert_eq!(blank_credit(&m, &ClozeScoringConfig::default()), 1.0);
+    }
+
+    #[test]
+    fn test_wrong_suffix_gets_stem_credit() {
+        let config = ClozeScoringConfig::default();
+        let m = match_blank("running".into(), "runs".into());
+        assert!(m.stem_match);
+        let credit = blank_credit(&m, &config);
+        assert!(credit >= config.stem_credit && credit < 1.0);
+
+        let unrelated = match_blank("running".into(), "apple".into());
+        assert!(!unrelated.stem_match);
+        assert_eq!(blank_credit(&unrelated, &config), 0.0);
+    }
+
+    #[test]
+    fn test_target_blank_weighs_more() {
+        let target_right = score_cloze(
+            vec![blank("abandon", "abandon", true), blank("the", "a", false)],
+            None,
+        );
+        let target_wrong = score_cloze(
+            vec![blank("abandon", "xyz", true), blank("the", "the", false)],
+            None,
+        );
+        assert!((target_right.credit - 2.0 / 3.0).abs() < 1e-9);
+        assert!((target_wrong.credit - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(score_cloze(vec![], None).credit, 0.0);
+    }
+}
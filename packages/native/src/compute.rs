@@ -0,0 +1,154 @@
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 常规模式下批量计算的上限
+pub const NORMAL_MAX_BATCH_SIZE: u32 = 256;
+/// 节能模式下批量计算的上限
+pub const ECO_MAX_BATCH_SIZE: u32 = 32;
+/// 节能模式下 Bootstrap 重采样次数上限
+pub const ECO_MAX_BOOTSTRAP: u32 = 30;
+
+/// 计算模式（低电量时切换到 Eco）
+#[cfg_attr(feature = "napi", napi)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeMode {
+    Normal,
+    Eco,
+}
+
+impl ComputeMode {
+    pub fn try_from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "normal" => Some(ComputeMode::Normal),
+            "eco" => Some(ComputeMode::Eco),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComputeMode::Normal => "normal",
+            ComputeMode::Eco => "eco",
+        }
+    }
+}
+
+/// 当前计算模式对应的预算
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputeBudget {
+    pub mode: ComputeMode,
+    /// 单次批量计算的最大条数
+    pub max_batch_size: u32,
+    /// 是否允许 Rayon 并行
+    pub parallel: bool,
+    /// 是否推迟非紧急的重算（如最优间隔刷新）
+    pub defer_non_urgent: bool,
+    /// Bootstrap 重采样次数上限
+    pub max_bootstrap: u32,
+}
+
+impl ComputeBudget {
+    pub fn for_mode(mode: ComputeMode) -> Self {
+        match mode {
+            ComputeMode::Normal => Self {
+                mode,
+                max_batch_size: NORMAL_MAX_BATCH_SIZE,
+                parallel: true,
+                defer_non_urgent: false,
+                max_bootstrap: u32::MAX,
+            },
+            ComputeMode::Eco => Self {
+                mode,
+                max_batch_size: ECO_MAX_BATCH_SIZE,
+                parallel: false,
+                defer_non_urgent: true,
+                max_bootstrap: ECO_MAX_BOOTSTRAP,
+            },
+        }
+    }
+
+    /// 将请求的批量大小限制在预算内
+    pub fn clamp_batch(&self, requested: usize) -> usize {
+        requested.min(self.max_batch_size as usize)
+    }
+
+    /// 是否应执行该重算；紧急任务始终执行
+    pub fn should_run(&self, urgent: bool) -> bool {
+        urgent || !self.defer_non_urgent
+    }
+}
+
+static COMPUTE_MODE: AtomicU8 = AtomicU8::new(0);
+
+fn mode_to_u8(mode: ComputeMode) -> u8 {
+    match mode {
+        ComputeMode::Normal => 0,
+        ComputeMode::Eco => 1,
+    }
+}
+
+/// 读取当前（进程级）计算模式
+pub fn current_mode() -> ComputeMode {
+    match COMPUTE_MODE.load(Ordering::Relaxed) {
+        1 => ComputeMode::Eco,
+        _ => ComputeMode::Normal,
+    }
+}
+
+/// 读取当前计算预算
+#[cfg_attr(feature = "napi", napi)]
+pub fn get_compute_budget() -> ComputeBudget {
+    ComputeBudget::for_mode(current_mode())
+}
+
+/// 运行时切换计算模式，返回新的预算
+#[cfg_attr(feature = "napi", napi)]
+pub fn set_compute_mode(mode: ComputeMode) -> ComputeBudget {
+    COMPUTE_MODE.store(mode_to_u8(mode), Ordering::Relaxed);
+    ComputeBudget::for_mode(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_for_modes() {
+        let normal = ComputeBudget::for_mode(ComputeMode::Normal);
+        assert!(normal.parallel);
+        assert!(normal.should_run(false));
+        assert_eq!(normal.clamp_batch(1000), NORMAL_MAX_BATCH_SIZE as usize);
+
+        let eco = ComputeBudget::for_mode(ComputeMode::Eco);
+        assert!(!eco.parallel);
+        assert!(!eco.should_run(false));
+        assert!(eco.should_run(true));
+        assert_eq!(eco.clamp_batch(1000), ECO_MAX_BATCH_SIZE as usize);
+        assert_eq!(eco.clamp_batch(5), 5);
+    }
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!(ComputeMode::try_from_str("ECO"), Some(ComputeMode::Eco));
+        assert_eq!(
+            ComputeMode::try_from_str("normal"),
+            Some(ComputeMode::Normal)
+        );
+        assert_eq!(ComputeMode::try_from_str("turbo"), None);
+        assert_eq!(ComputeMode::Eco.as_str(), "eco");
+    }
+
+    #[test]
+    fn test_set_and_get_mode() {
+        let budget = set_compute_mode(ComputeMode::Eco);
+        assert_eq!(budget.mode, ComputeMode::Eco);
+        assert_eq!(get_compute_budget().mode, ComputeMode::Eco);
+        set_compute_mode(ComputeMode::Normal);
+        assert_eq!(current_mode(), ComputeMode::Normal);
+    }
+}
@@ -0,0 +1,432 @@
+//! 学习曲线估计
+//!
+//! 把累计掌握词数对累计学习时长（分钟）拟合为饱和曲线，用于"你每小时大约能
+//! 有效掌握 N 个新词"这类展示，并预测再投入若干学习时长后的掌握数。
+//!
+//! 候选模型（各两个参数）：
+//! - 指数饱和 `y = A·(1 − e^(−k·t))`，A 为渐近上限
+//! - 幂律 `y = a·t^b`（0 < b ≤ 1），增长逐渐放缓但不设上限
+//!
+//! 两者对形状参数（k 或 b）做一维剖面搜索，尺度参数取闭式最小二乘解，
+//! 按残差平方和取较优者。置信带由残差自助法得到，随机流固定种子可重放。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::rng::{domains, RngFactory};
+
+/// 拟合所需的最少观测点数
+pub const MIN_CURVE_POINTS: usize = 3;
+
+const GRID_STEPS: usize = 120;
+const REFINE_STEPS: usize = 40;
+
+/// 一个观测点：截至某时刻的累计学习时长与累计掌握词数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurvePoint {
+    pub minutes: f64,
+    pub mastered: f64,
+}
+
+/// 拟合参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LearningCurveConfig {
+    /// 置信水平（0.9 表示 5%–95% 分位数区间）
+    pub confidence: f64,
+    /// 自助法重采样次数（受计算预算上限约束）
+    pub bootstrap_samples: u32,
+    pub seed: u32,
+}
+
+impl Default for LearningCurveConfig {
+    fn default() -> Self {
+        Self {
+            confidence: 0.9,
+            bootstrap_samples: 200,
+            seed: 0,
+        }
+    }
+}
+
+/// 曲线形式
+#[cfg_attr(feature = "napi", napi)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CurveModel {
+    Exponential,
+    Power,
+}
+
+impl CurveModel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CurveModel::Exponential => "exponential",
+            CurveModel::Power => "power",
+        }
+    }
+
+    fn shape_range(&self, max_minutes: f64) -> (f64, f64) {
+        match self {
+            // 时间常数从数据跨度的 1/20 到 100 倍
+            CurveModel::Exponential => (0.01 / max_minutes, 20.0 / max_minutes),
+            CurveModel::Power => (0.05, 1.0),
+        }
+    }
+
+    fn basis(&self, shape: f64, t: f64) -> f64 {
+        match self {
+            CurveModel::Exponential => 1.0 - (-shape * t).exp(),
+            CurveModel::Power => t.powf(shape),
+        }
+    }
+
+    fn slope(&self, scale: f64, shape: f64, t: f64) -> f64 {
+        match self {
+            CurveModel::Exponential => scale * shape * (-shape * t).exp(),
+            CurveModel::Power if t > 0.0 => scale * shape * t.powf(shape - 1.0),
+            CurveModel::Power => 0.0,
+        }
+    }
+}
+
+/// 带区间的估计值
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurveEstimate {
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// 在当前累计时长之上再学习 `extra_minutes` 后的预测
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurveForecast {
+    pub extra_minutes: f64,
+    /// 预计累计掌握词数
+    pub total_mastered: CurveEstimate,
+    /// 相对当前拟合值新增的掌握词数
+    pub new_mastered: CurveEstimate,
+}
+
+/// 拟合结果
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearningCurve {
+    pub model: CurveModel,
+    /// 指数模型为 A（渐近上限），幂律为 a
+    pub scale: f64,
+    /// 指数模型为 k（每分钟），幂律为 b
+    pub shape: f64,
+    pub r_squared: f64,
+    pub points_used: u32,
+    pub total_minutes: f64,
+    pub total_mastered: f64,
+    /// 当前时刻的边际掌握速度（词/小时）
+    pub rate_per_hour: CurveEstimate,
+    /// 全程平均掌握速度（词/小时）
+    pub average_rate_per_hour: f64,
+    /// 指数模型的渐近上限；幂律无上限时为空
+    pub plateau: Option<f64>,
+    pub confidence: f64,
+    pub forecasts: Vec<CurveForecast>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fit {
+    model: CurveModel,
+    scale: f64,
+    shape: f64,
+    sse: f64,
+}
+
+impl Fit {
+    fn predict(&self, t: f64) -> f64 {
+        self.scale * self.model.basis(self.shape, t)
+    }
+
+    fn rate_per_hour(&self, t: f64) -> f64 {
+        self.model.slope(self.scale, self.shape, t) * 60.0
+    }
+}
+
+/// 固定形状参数时的闭式尺度解及残差平方和
+fn profile(model: CurveModel, shape: f64, t: &[f64], y: &[f64]) -> (f64, f64) {
+    let (mut fy, mut ff) = (0.0, 0.0);
+    for (&ti, &yi) in t.iter().zip(y) {
+        let f = model.basis(shape, ti);
+        fy += f * yi;
+        ff += f * f;
+    }
+    let scale = if ff > 0.0 { (fy / ff).max(0.0) } else { 0.0 };
+    let sse = t
+        .iter()
+        .zip(y)
+        .map(|(&ti, &yi)| (yi - scale * model.basis(shape, ti)).powi(2))
+        .sum();
+    (scale, sse)
+}
+
+/// 对数网格粗搜索后在最优格点邻域内细化
+fn fit_model(model: CurveModel, t: &[f64], y: &[f64]) -> Option<Fit> {
+    let max_t = t.iter().copied().fold(0.0_f64, f64::max);
+    if max_t <= 0.0 {
+        return None;
+    }
+    let (lo, hi) = model.shape_range(max_t);
+    let (ln_lo, ln_hi) = (lo.ln(), hi.ln());
+    let step = (ln_hi - ln_lo) / GRID_STEPS as f64;
+
+    let consider = |best: &mut Option<Fit>, shape: f64| {
+        let (scale, sse) = profile(model, shape, t, y);
+        if sse.is_finite() && best.is_none_or(|b| sse < b.sse) {
+            *best = Some(Fit {
+                model,
+                scale,
+                shape,
+                sse,
+            });
+        }
+    };
+    let mut best = None;
+    for i in 0..=GRID_STEPS {
+        consider(&mut best, (ln_lo + step * i as f64).exp());
+    }
+    let center = best?.shape.ln();
+    for i in 0..=REFINE_STEPS {
+        let offset = step * (2.0 * i as f64 / REFINE_STEPS as f64 - 1.0);
+        consider(&mut best, (center + offset).clamp(ln_lo, ln_hi).exp());
+    }
+    best
+}
+
+fn fit_best(t: &[f64], y: &[f64]) -> Option<Fit> {
+    let exp = fit_model(CurveModel::Exponential, t, y);
+    let pow = fit_model(CurveModel::Power, t, y);
+    match (exp, pow) {
+        (Some(e), Some(p)) => Some(if p.sse < e.sse { p } else { e }),
+        (e, p) => e.or(p),
+    }
+}
+
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (i, frac) = (pos.floor() as usize, pos.fract());
+    let next = sorted[(i + 1).min(sorted.len() - 1)];
+    sorted[i] + (next - sorted[i]) * frac
+}
+
+fn estimate(value: f64, mut samples: Vec<f64>, confidence: f64) -> CurveEstimate {
+    samples.retain(|v| v.is_finite());
+    if samples.is_empty() {
+        return CurveEstimate {
+            value,
+            lower: value,
+            upper: value,
+        };
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let tail = (1.0 - confidence) / 2.0;
+    CurveEstimate {
+        value,
+        lower: quantile(&samples, tail).min(value),
+        upper: quantile(&samples, 1.0 - tail).max(value),
+    }
+}
+
+/// 拟合学习曲线并给出各预算下的预测；有效点不足或数据无增长时返回 `None`
+#[cfg_attr(feature = "napi", napi)]
+pub fn fit_learning_curve(
+    points: Vec<CurvePoint>,
+    extra_minutes: Vec<f64>,
+    config: Option<LearningCurveConfig>,
+) -> Option<LearningCurve> {
+    let config = config.unwrap_or_default();
+    let confidence = if config.confidence.is_finite() {
+        config.confidence.clamp(0.5, 0.99)
+    } else {
+        0.9
+    };
+
+    let mut points: Vec<CurvePoint> = points
+        .into_iter()
+        .filter(|p| p.minutes.is_finite() && p.mastered.is_finite() && p.minutes > 0.0)
+        .map(|p| CurvePoint {
+            minutes: p.minutes,
+            mastered: p.mastered.max(0.0),
+        })
+        .collect();
+    points.sort_by(|a, b| a.minutes.total_cmp(&b.minutes));
+    if points.len() < MIN_CURVE_POINTS {
+        return None;
+    }
+    let t: Vec<f64> = points.iter().map(|p| p.minutes).collect();
+    let y: Vec<f64> = points.iter().map(|p| p.mastered).collect();
+    let total_minutes = *t.last()?;
+    let total_mastered = *y.last()?;
+    if total_mastered <= 0.0 {
+        return None;
+    }
+
+    let fit = fit_best(&t, &y)?;
+    let fitted: Vec<f64> = t.iter().map(|&ti| fit.predict(ti)).collect();
+    let residuals: Vec<f64> = y.iter().zip(&fitted).map(|(yi, fi)| yi - fi).collect();
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let sst: f64 = y.iter().map(|yi| (yi - mean).powi(2)).sum();
+    let r_squared = if sst > 0.0 {
+        (1.0 - fit.sse / sst).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let budgets: Vec<f64> = extra_minutes
+        .into_iter()
+        .filter(|m| m.is_finite() && *m >= 0.0)
+        .collect();
+
+    // 残差自助法：在拟合值上重抽残差后以同一模型重拟合
+    let samples = config
+        .bootstrap_samples
+        .min(crate::compute::get_compute_budget().max_bootstrap) as usize;
+    let mut rng = RngFactory::new(u64::from(config.seed)).stream(domains::CURVE_BOOTSTRAP);
+    let mut rate_samples = Vec::with_capacity(samples);
+    let mut total_samples = vec![Vec::with_capacity(samples); budgets.len()];
+    let mut gain_samples = vec![Vec::with_capacity(samples); budgets.len()];
+    let n = residuals.len();
+    for _ in 0..samples {
+        let resampled: Vec<f64> = fitted
+            .iter()
+            .map(|fi| {
+                let j = ((rng.next_f64() * n as f64) as usize).min(n - 1);
+                (fi + residuals[j]).max(0.0)
+            })
+            .collect();
+        let Some(boot) = fit_model(fit.model, &t, &resampled) else {
+            continue;
+        };
+        rate_samples.push(boot.rate_per_hour(total_minutes));
+        let now = boot.predict(total_minutes);
+        for (i, budget) in budgets.iter().enumerate() {
+            let future = boot.predict(total_minutes + budget);
+            total_samples[i].push(future);
+            gain_samples[i].push(future - now);
+        }
+    }
+
+    let now = fit.predict(total_minutes);
+    let forecasts = budgets
+        .iter()
+        .zip(total_samples.into_iter().zip(gain_samples))
+        .map(|(&budget, (totals, gains))| {
+            let future = fit.predict(total_minutes + budget);
+            CurveForecast {
+                extra_minutes: budget,
+                total_mastered: estimate(future, totals, confidence),
+                new_mastered: estimate(future - now, gains, confidence),
+            }
+        })
+        .collect();
+
+    Some(LearningCurve {
+        model: fit.model,
+        scale: fit.scale,
+        shape: fit.shape,
+        r_squared,
+        points_used: points.len() as u32,
+        total_minutes,
+        total_mastered,
+        rate_per_hour: estimate(fit.rate_per_hour(total_minutes), rate_samples, confidence),
+        average_rate_per_hour: total_mastered / total_minutes * 60.0,
+        plateau: (fit.model == CurveModel::Exponential).then_some(fit.scale),
+        confidence,
+        forecasts,
+    })
+}
+
+/// 把按时间排序的（学习分钟，新掌握词数）增量累加为曲线观测点
+pub fn cumulative_points(increments: &[(f64, f64)]) -> Vec<CurvePoint> {
+    let (mut minutes, mut mastered) = (0.0, 0.0);
+    increments
+        .iter()
+        .map(|&(m, w)| {
+            minutes += m.max(0.0);
+            mastered += w.max(0.0);
+            CurvePoint { minutes, mastered }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic(f: impl Fn(f64) -> f64) -> Vec<CurvePoint> {
+        (1..=20)
+            .map(|i| {
+                let minutes = i as f64 * 30.0;
+                // 确定性的小扰动
+                let noise = if i % 2 == 0 { 1.0 } else { -1.0 };
+                CurvePoint {
+                    minutes,
+                    mastered: f(minutes) + noise,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recovers_exponential_plateau() {
+        let points = synthetic(|t| 300.0 * (1.0 - (-t / 200.0).exp()));
+        let curve = fit_learning_curve(points, vec![0.0, 60.0, 600.0], None).unwrap();
+        assert_eq!(curve.model, CurveModel::Exponential);
+        assert!((curve.plateau.unwrap() - 300.0).abs() < 10.0);
+        assert!(curve.r_squared > 0.99);
+
+        let f = &curve.forecasts;
+        assert!(f[0].new_mastered.value.abs() < 1e-9);
+        assert!(f[1].new_mastered.value > 0.0);
+        assert!(f[2].total_mastered.value <= curve.plateau.unwrap() + 1e-9);
+        for fc in f {
+            assert!(fc.total_mastered.lower <= fc.total_mastered.value);
+            assert!(fc.total_mastered.upper >= fc.total_mastered.value);
+        }
+        // 边际速度低于全程平均（曲线在饱和）
+        assert!(curve.rate_per_hour.value < curve.average_rate_per_hour);
+    }
+
+    #[test]
+    fn test_prefers_power_law_for_unbounded_growth() {
+        let points = synthetic(|t| 4.0 * t.powf(0.7));
+        let curve = fit_learning_curve(points, vec![120.0], None).unwrap();
+        assert_eq!(curve.model, CurveModel::Power);
+        assert!((curve.shape - 0.7).abs() < 0.05);
+        assert!(curve.plateau.is_none());
+    }
+
+    #[test]
+    fn test_same_seed_is_deterministic() {
+        let points = synthetic(|t| 100.0 * (1.0 - (-t / 300.0).exp()));
+        let a = fit_learning_curve(points.clone(), vec![60.0], None).unwrap();
+        let b = fit_learning_curve(points, vec![60.0], None).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_insufficient_data() {
+        let points = cumulative_points(&[(10.0, 2.0), (10.0, 1.0)]);
+        assert!(fit_learning_curve(points, vec![60.0], None).is_none());
+        let flat = cumulative_points(&[(10.0, 0.0), (10.0, 0.0), (10.0, 0.0)]);
+        assert!(fit_learning_curve(flat, vec![], None).is_none());
+    }
+}
@@ -0,0 +1,267 @@
+//! 算法关键事件：计数与节流日志
+//!
+//! 每次发生都累加进程级计数器（始终开启，开销为一次原子加），宿主可用
+//! `event_counts` 读出并展示算法健康状况。启用 `tracing` 特性后，事件同时
+//! 以 `danci_algo::<模块>` 为 target 输出结构化日志，并按事件类型节流：
+//! 同一事件在窗口内只输出一次，下一次输出时带上窗口内被抑制的次数。
+//! 窗口按严重级别放大：warn 为基础窗口，info ×6，debug ×30。
+//! 计数器只依赖 core 原子类型，no_std 构建同样可用（目标需支持 64 位原子操作）。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+/// 基础节流窗口（毫秒）
+pub const DEFAULT_THROTTLE_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warn,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    fn window_factor(self) -> u64 {
+        match self {
+            Severity::Warn => 1,
+            Severity::Info => 6,
+            Severity::Debug => 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlgoEvent {
+    /// 到达周期的完整 Cholesky 重算（立即或分摊完成）
+    PeriodicRecompute,
+    /// L 不健康或 Rank-1 更新失败导致的紧急完整重算
+    EmergencyRecompute,
+    /// 分摊重算回放失败，退回完整重算
+    RefreshReplayFailed,
+    /// 特征向量含非有限值或超出范围，已清理
+    FeatureSanitized,
+    /// 协方差矩阵含无效值、超限值或过小对角线，已修复
+    CovarianceSanitized,
+    /// 状态 JSON 无法解析，退回默认状态
+    StateParseFallback,
+}
+
+impl AlgoEvent {
+    pub const ALL: [AlgoEvent; 6] = [
+        AlgoEvent::PeriodicRecompute,
+        AlgoEvent::EmergencyRecompute,
+        AlgoEvent::RefreshReplayFailed,
+        AlgoEvent::FeatureSanitized,
+        AlgoEvent::CovarianceSanitized,
+        AlgoEvent::StateParseFallback,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlgoEvent::PeriodicRecompute => "periodic_recompute",
+            AlgoEvent::EmergencyRecompute => "emergency_recompute",
+            AlgoEvent::RefreshReplayFailed => "refresh_replay_failed",
+            AlgoEvent::FeatureSanitized => "feature_sanitized",
+            AlgoEvent::CovarianceSanitized => "covariance_sanitized",
+            AlgoEvent::StateParseFallback => "state_parse_fallback",
+        }
+    }
+
+    pub fn module(self) -> &'static str {
+        match self {
+            AlgoEvent::PeriodicRecompute
+            | AlgoEvent::EmergencyRecompute
+            | AlgoEvent::RefreshReplayFailed => "linucb",
+            AlgoEvent::FeatureSanitized | AlgoEvent::CovarianceSanitized => "sanitize",
+            AlgoEvent::StateParseFallback => "state",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            AlgoEvent::PeriodicRecompute => Severity::Debug,
+            AlgoEvent::FeatureSanitized => Severity::Info,
+            AlgoEvent::EmergencyRecompute
+            | AlgoEvent::RefreshReplayFailed
+            | AlgoEvent::CovarianceSanitized
+            | AlgoEvent::StateParseFallback => Severity::Warn,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+const EVENT_COUNT: usize = AlgoEvent::ALL.len();
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const IDLE: Throttle = Throttle::new();
+
+static COUNTS: [AtomicU64; EVENT_COUNT] = [ZERO; EVENT_COUNT];
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+static THROTTLES: [Throttle; EVENT_COUNT] = [IDLE; EVENT_COUNT];
+static THROTTLE_MS: AtomicU64 = AtomicU64::new(DEFAULT_THROTTLE_MS);
+
+/// 单个事件的节流状态
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+struct Throttle {
+    /// 上次输出时刻 + 1（毫秒）；0 表示从未输出
+    last_emit: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+impl Throttle {
+    const fn new() -> Self {
+        Self {
+            last_emit: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// 窗口内首次发生时返回被抑制的次数并清零，否则计入抑制数
+    fn check(&self, window: u64, now: u64) -> Option<u64> {
+        let last = self.last_emit.load(Ordering::Relaxed);
+        let due = last == 0 || now + 1 >= last.saturating_add(window);
+        if due
+            && self
+                .last_emit
+                .compare_exchange(last, now + 1, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            return Some(self.suppressed.swap(0, Ordering::Relaxed));
+        }
+        self.suppressed.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+}
+
+/// 某事件的累计次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventCount {
+    pub event: &'static str,
+    pub module: &'static str,
+    pub severity: &'static str,
+    pub count: u64,
+}
+
+/// 设置基础节流窗口；0 表示不节流
+pub fn set_throttle_ms(ms: u64) {
+    THROTTLE_MS.store(ms, Ordering::Relaxed);
+}
+
+/// 进程启动以来各事件的累计次数
+pub fn event_counts() -> Vec<EventCount> {
+    AlgoEvent::ALL
+        .iter()
+        .map(|&e| EventCount {
+            event: e.as_str(),
+            module: e.module(),
+            severity: e.severity().as_str(),
+            count: COUNTS[e.index()].load(Ordering::Relaxed),
+        })
+        .collect()
+}
+
+/// 记录一次事件；`detail` 只在真正输出日志时求值
+#[inline]
+pub fn record(event: AlgoEvent, detail: impl FnOnce() -> String) {
+    COUNTS[event.index()].fetch_add(1, Ordering::Relaxed);
+    #[cfg(feature = "tracing")]
+    if let Some(suppressed) = throttle(event, now_ms()) {
+        emit(event, suppressed, &detail());
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = detail;
+}
+
+#[cfg(feature = "tracing")]
+fn now_ms() -> u64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+#[cfg(feature = "tracing")]
+fn throttle(event: AlgoEvent, now: u64) -> Option<u64> {
+    let window = THROTTLE_MS.load(Ordering::Relaxed) * event.severity().window_factor();
+    THROTTLES[event.index()].check(window, now)
+}
+
+#[cfg(feature = "tracing")]
+fn emit(event: AlgoEvent, suppressed: u64, detail: &str) {
+    macro_rules! log_event {
+        ($target:literal, $level:ident) => {
+            tracing::event!(
+                target: $target,
+                tracing::Level::$level,
+                event = event.as_str(),
+                suppressed,
+                detail,
+                "algorithm event"
+            )
+        };
+    }
+    match (event.module(), event.severity()) {
+        ("linucb", Severity::Debug) => log_event!("danci_algo::linucb", DEBUG),
+        ("linucb", Severity::Info) => log_event!("danci_algo::linucb", INFO),
+        ("linucb", Severity::Warn) => log_event!("danci_algo::linucb", WARN),
+        ("sanitize", Severity::Debug) => log_event!("danci_algo::sanitize", DEBUG),
+        ("sanitize", Severity::Info) => log_event!("danci_algo::sanitize", INFO),
+        ("sanitize", Severity::Warn) => log_event!("danci_algo::sanitize", WARN),
+        (_, Severity::Debug) => log_event!("danci_algo::state", DEBUG),
+        (_, Severity::Info) => log_event!("danci_algo::state", INFO),
+        (_, Severity::Warn) => log_event!("danci_algo::state", WARN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_accumulate() {
+        let count = |e: AlgoEvent| {
+            event_counts()
+                .into_iter()
+                .find(|c| c.event == e.as_str())
+                .unwrap()
+                .count
+        };
+        let before = count(AlgoEvent::StateParseFallback);
+        record(AlgoEvent::StateParseFallback, || "test".to_string());
+        record(AlgoEvent::StateParseFallback, || "test".to_string());
+        // 其他测试可能并发记录同一事件，只检查下界
+        assert!(count(AlgoEvent::StateParseFallback) >= before + 2);
+        assert_eq!(event_counts().len(), AlgoEvent::ALL.len());
+    }
+
+    #[test]
+    fn test_throttle_window_and_suppressed_count() {
+        let throttle = Throttle::new();
+        let window = DEFAULT_THROTTLE_MS;
+        let base = 1_000_000;
+        assert_eq!(throttle.check(window, base), Some(0));
+        assert_eq!(throttle.check(window, base + 10), None);
+        assert_eq!(throttle.check(window, base + window - 2), None);
+        assert_eq!(throttle.check(window, base + window), Some(2));
+        assert_eq!(throttle.check(window, base + window + 1), None);
+    }
+}
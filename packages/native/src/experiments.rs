@@ -0,0 +1,160 @@
+//! A/B 实验分组
+//!
+//! 后端与桌面端共用同一个确定性分桶：用户 ID 的字符串哈希归一化到 [0, 1]，
+//! 按变体权重累加后落在哪一段就分到哪个变体。桌面端缓存服务端下发的实验定义，
+//! 离线时本地计算分组，联网后上报曝光，结果与服务端分配一致。
+//!
+//! 哈希与早期 JS 实现的 `hash = (hash << 5) - hash + charCode` 保持一致，
+//! 已有的服务端分配依赖它，不能更换。
+
+use serde::{Deserialize, Serialize};
+
+/// 用户 ID 的分桶哈希（32 位环绕运算）
+pub fn assignment_hash(user_id: &str) -> u32 {
+    let mut hash: i32 = 0;
+    for ch in user_id.chars() {
+        hash = hash
+            .wrapping_shl(5)
+            .wrapping_sub(hash)
+            .wrapping_add(ch as i32);
+    }
+    hash as u32
+}
+
+/// 按权重选择变体下标；权重不要求归一化之和为 1，累加不到哈希位置时落到最后一个
+pub fn select_variant_index(user_id: &str, weights: &[f64]) -> Option<usize> {
+    if weights.is_empty() {
+        return None;
+    }
+    let normalized = f64::from(assignment_hash(user_id)) / f64::from(u32::MAX);
+    let mut cumulative = 0.0;
+    for (idx, weight) in weights.iter().enumerate() {
+        cumulative += *weight;
+        if normalized <= cumulative {
+            return Some(idx);
+        }
+    }
+    Some(weights.len() - 1)
+}
+
+/// 实验变体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentVariant {
+    pub id: String,
+    pub name: String,
+    pub weight: f64,
+    #[serde(default)]
+    pub is_control: bool,
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+}
+
+/// 运行中的实验定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentDefinition {
+    pub id: String,
+    pub name: String,
+    pub variants: Vec<ExperimentVariant>,
+    /// 服务端已有的分配；权重调整后哈希结果可能变化，已分配的用户保持原变体
+    #[serde(default)]
+    pub assigned_variant_id: Option<String>,
+}
+
+impl ExperimentDefinition {
+    /// 用户所属变体：优先已有分配，否则按哈希分桶；空 ID 的变体不参与，负权重按 0
+    pub fn assign(&self, user_id: &str) -> Option<&ExperimentVariant> {
+        if let Some(assigned) = self.assigned_variant_id.as_deref() {
+            if let Some(variant) = self.variants.iter().find(|v| v.id == assigned) {
+                return Some(variant);
+            }
+        }
+        let candidates: Vec<&ExperimentVariant> =
+            self.variants.iter().filter(|v| !v.id.is_empty()).collect();
+        let weights: Vec<f64> = candidates.iter().map(|v| v.weight.max(0.0)).collect();
+        select_variant_index(user_id, &weights).map(|idx| candidates[idx])
+    }
+
+    /// 下发给设备的副本：只保留该用户自己的分配
+    pub fn for_user(&self, assigned_variant_id: Option<String>) -> ExperimentDefinition {
+        ExperimentDefinition {
+            assigned_variant_id,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(id: &str, weight: f64) -> ExperimentVariant {
+        ExperimentVariant {
+            id: id.to_string(),
+            name: id.to_string(),
+            weight,
+            is_control: id == "control",
+            parameters: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_hash_matches_js_string_hash() {
+        assert_eq!(assignment_hash(""), 0);
+        assert_eq!(assignment_hash("a"), 97);
+        // "ab" = 97 * 31 + 98
+        assert_eq!(assignment_hash("ab"), 3105);
+        // 溢出后按 i32 环绕再转为无符号
+        let long = "user-0123456789abcdef";
+        let mut expected: i32 = 0;
+        for b in long.bytes() {
+            expected = expected.wrapping_mul(31).wrapping_add(i32::from(b));
+        }
+        assert_eq!(assignment_hash(long), expected as u32);
+    }
+
+    #[test]
+    fn test_assignment_is_deterministic_and_weighted() {
+        let experiment = ExperimentDefinition {
+            id: "exp".to_string(),
+            name: "exp".to_string(),
+            variants: vec![variant("control", 0.5), variant("treatment", 0.5)],
+            assigned_variant_id: None,
+        };
+        let mut treatment = 0;
+        for i in 0..2000 {
+            let user = format!("user-{i}");
+            let first = experiment.assign(&user).unwrap().id.clone();
+            assert_eq!(experiment.assign(&user).unwrap().id, first);
+            if first == "treatment" {
+                treatment += 1;
+            }
+        }
+        assert!(treatment > 0 && treatment < 2000);
+
+        let all_control = ExperimentDefinition {
+            variants: vec![variant("control", 1.0), variant("treatment", 0.0)],
+            ..experiment.clone()
+        };
+        assert_eq!(all_control.assign("anyone").unwrap().id, "control");
+    }
+
+    #[test]
+    fn test_existing_assignment_wins() {
+        let experiment = ExperimentDefinition {
+            id: "exp".to_string(),
+            name: "exp".to_string(),
+            variants: vec![variant("control", 1.0), variant("treatment", 0.0)],
+            assigned_variant_id: Some("treatment".to_string()),
+        };
+        assert_eq!(experiment.assign("u").unwrap().id, "treatment");
+        let stale = experiment.for_user(Some("removed".to_string()));
+        assert_eq!(stale.assign("u").unwrap().id, "control");
+        let empty = ExperimentDefinition {
+            variants: Vec::new(),
+            ..experiment
+        };
+        assert!(empty.assign("u").is_none());
+    }
+}
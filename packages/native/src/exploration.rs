@@ -0,0 +1,226 @@
+//! 按会话长度与用户资历分配探索预算
+//!
+//! 3 分钟的短会话里大量探索只会浪费用户时间；新用户的偏好尚未摸清，比老用户
+//! 更值得探索。探索系数由两部分相乘：
+//!
+//! - 会话系数：预计会话长度不超过 `short_session_minutes` 时取
+//!   `min_session_factor`，达到 `full_session_minutes` 时取 1，中间线性插值；
+//! - 资历系数：`tenure_floor + (1 − tenure_floor) · 0.5^(会话数 / 半衰期)`，
+//!   新用户为 1，随已完成会话数衰减到下限。
+//!
+//! 系数作用于 LinUCB 的 alpha、强制探索概率与轮询间隔，调用方据此缩放各自的
+//! 探索参数，并记录预期探索占比供分析核对。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// 预算参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExplorationBudgetConfig {
+    /// 不超过此长度（分钟）的会话只保留最低探索
+    pub short_session_minutes: f64,
+    /// 达到此长度（分钟）的会话使用完整预算
+    pub full_session_minutes: f64,
+    /// 最短会话的会话系数 [0, 1]
+    pub min_session_factor: f64,
+    /// 资历系数的半衰期（已完成会话数）
+    pub tenure_half_life_sessions: f64,
+    /// 老用户的资历系数下限 [0, 1]
+    pub tenure_floor: f64,
+}
+
+impl Default for ExplorationBudgetConfig {
+    fn default() -> Self {
+        Self {
+            short_session_minutes: 3.0,
+            full_session_minutes: 20.0,
+            min_session_factor: 0.2,
+            tenure_half_life_sessions: 30.0,
+            tenure_floor: 0.5,
+        }
+    }
+}
+
+impl ExplorationBudgetConfig {
+    /// 返回参数错误列表，为空表示合法
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let unit = |v: f64| v.is_finite() && (0.0..=1.0).contains(&v);
+        if !(self.short_session_minutes.is_finite() && self.short_session_minutes >= 0.0) {
+            errors.push("shortSessionMinutes 必须为非负数".to_string());
+        }
+        if !(self.full_session_minutes.is_finite()
+            && self.full_session_minutes > self.short_session_minutes)
+        {
+            errors.push("fullSessionMinutes 必须大于 shortSessionMinutes".to_string());
+        }
+        if !unit(self.min_session_factor) {
+            errors.push("minSessionFactor 必须在 [0, 1] 内".to_string());
+        }
+        if !(self.tenure_half_life_sessions.is_finite() && self.tenure_half_life_sessions > 0.0) {
+            errors.push("tenureHalfLifeSessions 必须为正数".to_string());
+        }
+        if !unit(self.tenure_floor) {
+            errors.push("tenureFloor 必须在 [0, 1] 内".to_string());
+        }
+        errors
+    }
+
+    /// 会话系数；预计长度未知（非有限值）时不做限制
+    pub fn session_factor(&self, expected_minutes: f64) -> f64 {
+        if !expected_minutes.is_finite() {
+            return 1.0;
+        }
+        let span = self.full_session_minutes - self.short_session_minutes;
+        let t = if span > 0.0 {
+            ((expected_minutes - self.short_session_minutes) / span).clamp(0.0, 1.0)
+        } else if expected_minutes >= self.full_session_minutes {
+            1.0
+        } else {
+            0.0
+        };
+        let min = self.min_session_factor.clamp(0.0, 1.0);
+        min + (1.0 - min) * t
+    }
+
+    /// 资历系数
+    pub fn tenure_factor(&self, tenure_sessions: u32) -> f64 {
+        let floor = self.tenure_floor.clamp(0.0, 1.0);
+        if self.tenure_half_life_sessions <= 0.0 || !self.tenure_half_life_sessions.is_finite() {
+            return floor;
+        }
+        let decay = crate::float::powf(
+            0.5,
+            f64::from(tenure_sessions) / self.tenure_half_life_sessions,
+        );
+        floor + (1.0 - floor) * decay
+    }
+}
+
+/// 一次会话的探索预算
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplorationBudget {
+    /// 预计会话长度（分钟），未知时为空
+    pub expected_session_minutes: Option<f64>,
+    pub tenure_sessions: u32,
+    pub session_factor: f64,
+    pub tenure_factor: f64,
+    /// 最终系数 [0, 1] = 会话系数 × 资历系数
+    pub scale: f64,
+}
+
+impl ExplorationBudget {
+    /// 不缩放的预算
+    pub fn full() -> Self {
+        Self {
+            expected_session_minutes: None,
+            tenure_sessions: 0,
+            session_factor: 1.0,
+            tenure_factor: 1.0,
+            scale: 1.0,
+        }
+    }
+
+    /// 缩放 LinUCB 的 alpha
+    pub fn scale_alpha(&self, alpha: f64) -> f64 {
+        alpha * self.scale
+    }
+
+    /// 缩放强制探索概率，结果截断到 [0, 1]
+    pub fn scale_probability(&self, probability: f64) -> f64 {
+        (probability * self.scale).clamp(0.0, 1.0)
+    }
+
+    /// 缩放轮询间隔：每 n 次探索一次变为每 ⌈n / 系数⌉ 次；系数为 0 时关闭探索
+    pub fn scale_interval(&self, every_n: u32) -> Option<u32> {
+        if every_n == 0 || self.scale <= 0.0 {
+            return None;
+        }
+        let scaled = (f64::from(every_n) / self.scale).ceil();
+        Some(if scaled >= f64::from(u32::MAX) {
+            u32::MAX
+        } else {
+            scaled as u32
+        })
+    }
+}
+
+/// 计算预算；预计会话长度缺失时会话系数取 1
+#[cfg_attr(feature = "napi", napi)]
+pub fn exploration_budget(
+    config: Option<ExplorationBudgetConfig>,
+    expected_session_minutes: Option<f64>,
+    tenure_sessions: u32,
+) -> ExplorationBudget {
+    let config = config.unwrap_or_default();
+    let expected_session_minutes = expected_session_minutes.filter(|m| m.is_finite());
+    let session_factor = expected_session_minutes.map_or(1.0, |m| config.session_factor(m));
+    let tenure_factor = config.tenure_factor(tenure_sessions);
+    ExplorationBudget {
+        expected_session_minutes,
+        tenure_sessions,
+        session_factor,
+        tenure_factor,
+        scale: (session_factor * tenure_factor).clamp(0.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_sessions_explore_less() {
+        let short = exploration_budget(None, Some(3.0), 0);
+        let long = exploration_budget(None, Some(25.0), 0);
+        assert!((short.session_factor - 0.2).abs() < 1e-12);
+        assert!((long.scale - 1.0).abs() < 1e-12);
+        let mid = exploration_budget(None, Some(11.5), 0);
+        assert!((mid.session_factor - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tenure_decays_to_floor() {
+        let config = ExplorationBudgetConfig::default();
+        assert!((config.tenure_factor(0) - 1.0).abs() < 1e-12);
+        assert!((config.tenure_factor(30) - 0.75).abs() < 1e-12);
+        assert!(config.tenure_factor(10_000) >= config.tenure_floor);
+        assert!(config.tenure_factor(10_000) < 0.51);
+    }
+
+    #[test]
+    fn test_scaling_helpers() {
+        let budget = exploration_budget(None, Some(11.5), 30);
+        assert!((budget.scale - 0.45).abs() < 1e-12);
+        assert!((budget.scale_alpha(1.0) - 0.45).abs() < 1e-12);
+        assert!((budget.scale_probability(0.1) - 0.045).abs() < 1e-12);
+        assert_eq!(budget.scale_interval(10), Some(23));
+        assert_eq!(ExplorationBudget::full().scale_interval(5), Some(5));
+
+        let off = ExplorationBudget {
+            scale: 0.0,
+            ..ExplorationBudget::full()
+        };
+        assert_eq!(off.scale_interval(5), None);
+        assert_eq!(off.scale_probability(0.3), 0.0);
+    }
+
+    #[test]
+    fn test_unknown_length_and_validation() {
+        let budget = exploration_budget(None, Some(f64::NAN), 0);
+        assert_eq!(budget.scale, 1.0);
+        assert_eq!(budget.expected_session_minutes, None);
+        assert!(ExplorationBudgetConfig::default().validate().is_empty());
+        let bad = ExplorationBudgetConfig {
+            full_session_minutes: 2.0,
+            tenure_floor: 1.5,
+            ..Default::default()
+        };
+        assert_eq!(bad.validate().len(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+//! 功能开关评估
+//!
+//! 后端与桌面端共用同一套定义与评估逻辑：按用户 ID 哈希分桶做百分比灰度，
+//! 支持按用户覆盖。桌面端缓存服务端下发的定义，离线时得到与服务端一致的结果。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::hash::fnv1a_64;
+
+/// 分桶粒度：万分之一
+const BUCKETS: u32 = 10_000;
+
+/// 单个开关定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagDefinition {
+    pub key: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 总开关；关闭时除覆盖外一律为 false
+    pub enabled: bool,
+    /// 灰度比例（0-100，可含小数）
+    #[serde(default = "full_rollout")]
+    pub rollout_percent: f64,
+    /// 用户 ID -> 强制取值，优先级最高
+    #[serde(default)]
+    pub overrides: HashMap<String, bool>,
+}
+
+fn full_rollout() -> f64 {
+    100.0
+}
+
+impl FlagDefinition {
+    pub fn new(key: &str, enabled: bool, rollout_percent: f64) -> Self {
+        Self {
+            key: key.to_string(),
+            description: None,
+            enabled,
+            rollout_percent,
+            overrides: HashMap::new(),
+        }
+    }
+
+    pub fn evaluate(&self, user_id: Option<&str>) -> bool {
+        if let Some(value) = user_id.and_then(|id| self.overrides.get(id)) {
+            return *value;
+        }
+        if !self.enabled {
+            return false;
+        }
+        let percent = self.rollout_percent.clamp(0.0, 100.0);
+        if percent >= 100.0 {
+            return true;
+        }
+        match user_id {
+            Some(id) => f64::from(rollout_bucket(&self.key, id)) < percent * 100.0,
+            // 匿名请求没有稳定分桶，只对全量开关返回 true
+            None => false,
+        }
+    }
+}
+
+/// 用户在某开关上的稳定分桶（0..10000），不同开关彼此独立
+pub fn rollout_bucket(flag_key: &str, user_id: &str) -> u32 {
+    (fnv1a_64(&[flag_key.as_bytes(), b":", user_id.as_bytes()]) % u64::from(BUCKETS)) as u32
+}
+
+/// 一组开关定义及其版本
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagSet {
+    /// 单调递增的版本号，客户端据此判断是否需要刷新
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub flags: Vec<FlagDefinition>,
+}
+
+impl FlagSet {
+    pub fn new(version: i64, flags: Vec<FlagDefinition>) -> Self {
+        Self { version, flags }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&FlagDefinition> {
+        self.flags.iter().find(|f| f.key == key)
+    }
+
+    /// 未定义的开关视为关闭
+    pub fn is_enabled(&self, key: &str, user_id: Option<&str>) -> bool {
+        self.get(key).is_some_and(|f| f.evaluate(user_id))
+    }
+
+    pub fn evaluate_all(&self, user_id: Option<&str>) -> BTreeMap<String, bool> {
+        self.flags
+            .iter()
+            .map(|f| (f.key.clone(), f.evaluate(user_id)))
+            .collect()
+    }
+
+    /// 下发给设备的副本：只保留该用户自己的覆盖，避免泄露其他用户 ID
+    pub fn for_user(&self, user_id: &str) -> FlagSet {
+        FlagSet {
+            version: self.version,
+            flags: self
+                .flags
+                .iter()
+                .map(|f| FlagDefinition {
+                    overrides: f
+                        .overrides
+                        .get(user_id)
+                        .map(|v| HashMap::from([(user_id.to_string(), *v)]))
+                        .unwrap_or_default(),
+                    ..f.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// 用 `newer` 中的定义覆盖同名开关（本地默认值 + 服务端下发）
+    pub fn merged_with(&self, newer: &FlagSet) -> FlagSet {
+        let mut flags = self.flags.clone();
+        for flag in &newer.flags {
+            match flags.iter_mut().find(|f| f.key == flag.key) {
+                Some(existing) => *existing = flag.clone(),
+                None => flags.push(flag.clone()),
+            }
+        }
+        FlagSet {
+            version: self.version.max(newer.version),
+            flags,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollout_bucket_is_stable_and_flag_scoped() {
+        let a = rollout_bucket("new_ui", "user-1");
+        assert_eq!(a, rollout_bucket("new_ui", "user-1"));
+        assert!(a < BUCKETS);
+        let differs = (0..50)
+            .filter(|i| {
+                let id = format!("user-{i}");
+                rollout_bucket("new_ui", &id) != rollout_bucket("other", &id)
+            })
+            .count();
+        assert!(differs > 40);
+    }
+
+    #[test]
+    fn test_percentage_rollout_is_roughly_proportional() {
+        let flag = FlagDefinition::new("beta", true, 25.0);
+        let on = (0..4000)
+            .filter(|i| flag.evaluate(Some(&format!("u{i}"))))
+            .count();
+        assert!((800..1200).contains(&on), "on = {on}");
+        assert!(!flag.evaluate(None));
+    }
+
+    #[test]
+    fn test_override_beats_kill_switch_and_rollout() {
+        let mut flag = FlagDefinition::new("beta", false, 100.0);
+        flag.overrides.insert("vip".to_string(), true);
+        assert!(flag.evaluate(Some("vip")));
+        assert!(!flag.evaluate(Some("someone")));
+
+        let mut flag = FlagDefinition::new("beta", true, 100.0);
+        flag.overrides.insert("opt-out".to_string(), false);
+        assert!(!flag.evaluate(Some("opt-out")));
+        assert!(flag.evaluate(None));
+    }
+
+    #[test]
+    fn test_for_user_strips_other_overrides() {
+        let mut flag = FlagDefinition::new("beta", true, 0.0);
+        flag.overrides.insert("a".to_string(), true);
+        flag.overrides.insert("b".to_string(), true);
+        let set = FlagSet::new(3, vec![flag]);
+        let scoped = set.for_user("a");
+        assert_eq!(scoped.flags[0].overrides.len(), 1);
+        assert_eq!(set.evaluate_all(Some("a")), scoped.evaluate_all(Some("a")));
+        assert!(!set.for_user("c").is_enabled("beta", Some("c")));
+    }
+
+    #[test]
+    fn test_merge_prefers_newer_definitions() {
+        let local = FlagSet::new(
+            0,
+            vec![
+                FlagDefinition::new("a", false, 100.0),
+                FlagDefinition::new("b", true, 100.0),
+            ],
+        );
+        let remote = FlagSet::new(7, vec![FlagDefinition::new("a", true, 100.0)]);
+        let merged = local.merged_with(&remote);
+        assert_eq!(merged.version, 7);
+        assert!(merged.is_enabled("a", None));
+        assert!(merged.is_enabled("b", None));
+        assert!(!merged.is_enabled("missing", None));
+    }
+}
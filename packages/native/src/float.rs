@@ -0,0 +1,63 @@
+//! 浮点数学函数的后端
+//!
+//! `sqrt`、`exp`、`ln`、`powf`、`powi` 不在 core 中。启用 `std` 时直接调用标准库，
+//! 结果与此前逐位一致；no_std + alloc 构建由 `libm` 特性提供纯 Rust 实现，
+//! 结果可能在最后几位有差异。核心数学模块统一经由这里调用。
+
+#[cfg(feature = "std")]
+mod imp {
+    #[inline]
+    pub fn sqrt(x: f64) -> f64 {
+        x.sqrt()
+    }
+
+    #[inline]
+    pub fn exp(x: f64) -> f64 {
+        x.exp()
+    }
+
+    #[inline]
+    pub fn ln(x: f64) -> f64 {
+        x.ln()
+    }
+
+    #[inline]
+    pub fn powf(x: f64, n: f64) -> f64 {
+        x.powf(n)
+    }
+
+    #[inline]
+    pub fn powi(x: f64, n: i32) -> f64 {
+        x.powi(n)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    #[inline]
+    pub fn sqrt(x: f64) -> f64 {
+        libm::sqrt(x)
+    }
+
+    #[inline]
+    pub fn exp(x: f64) -> f64 {
+        libm::exp(x)
+    }
+
+    #[inline]
+    pub fn ln(x: f64) -> f64 {
+        libm::log(x)
+    }
+
+    #[inline]
+    pub fn powf(x: f64, n: f64) -> f64 {
+        libm::pow(x, n)
+    }
+
+    #[inline]
+    pub fn powi(x: f64, n: i32) -> f64 {
+        libm::pow(x, n as f64)
+    }
+}
+
+pub use imp::{exp, ln, powf, powi, sqrt};
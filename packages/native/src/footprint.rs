@@ -0,0 +1,147 @@
+//! 内存占用估算
+//!
+//! 估算值按容量（capacity）计算堆分配，加上结构体自身大小；
+//! 不包含分配器开销，用于运维观测而非精确计量。
+
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use crate::mastery::{MasteryBandConfig, ReviewEvent};
+use crate::types::BanditModel;
+
+/// 可估算内存占用的结构（字节）
+pub trait MemoryFootprint {
+    fn memory_footprint(&self) -> usize;
+}
+
+/// Vec 的堆分配（不含元素内部的堆数据）
+pub fn vec_heap_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+pub fn string_heap_bytes(s: &String) -> usize {
+    s.capacity()
+}
+
+/// HashMap 的表分配估算：每个槽位一个 (K, V) 加 1 字节控制位
+pub fn hashmap_table_bytes<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+/// serde_json::Value 的递归估算
+pub fn json_value_bytes(value: &serde_json::Value) -> usize {
+    use serde_json::Value;
+
+    let own = size_of::<Value>();
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => own,
+        Value::String(s) => own + string_heap_bytes(s),
+        Value::Array(items) => {
+            own + (items.capacity() - items.len()) * size_of::<Value>()
+                + items.iter().map(json_value_bytes).sum::<usize>()
+        }
+        Value::Object(map) => {
+            own + map
+                .iter()
+                .map(|(k, v)| size_of::<String>() + k.capacity() + json_value_bytes(v))
+                .sum::<usize>()
+        }
+    }
+}
+
+/// serde_json::Value 的堆分配（不含 Value 自身，适用于内联在结构体中的字段）
+pub fn json_heap_bytes(value: &serde_json::Value) -> usize {
+    json_value_bytes(value) - size_of::<serde_json::Value>()
+}
+
+impl MemoryFootprint for BanditModel {
+    fn memory_footprint(&self) -> usize {
+        size_of::<Self>()
+            + vec_heap_bytes(&self.a_matrix)
+            + vec_heap_bytes(&self.b)
+            + vec_heap_bytes(&self.l_matrix)
+    }
+}
+
+/// ACT-R 复习轨迹：[`crate::mastery::actr_activation`] 的输入
+impl MemoryFootprint for Vec<ReviewEvent> {
+    fn memory_footprint(&self) -> usize {
+        size_of::<Self>() + vec_heap_bytes(self)
+    }
+}
+
+impl MemoryFootprint for MasteryBandConfig {
+    fn memory_footprint(&self) -> usize {
+        size_of::<Self>()
+    }
+}
+
+impl MemoryFootprint for serde_json::Value {
+    fn memory_footprint(&self) -> usize {
+        json_value_bytes(self)
+    }
+}
+
+impl<T: MemoryFootprint> MemoryFootprint for Option<T> {
+    fn memory_footprint(&self) -> usize {
+        match self {
+            Some(inner) => inner.memory_footprint() + size_of::<Self>() - size_of::<T>(),
+            None => size_of::<Self>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bandit_model_footprint_scales_with_dimension() {
+        let model = |d: usize| BanditModel {
+            a_matrix: vec![0.0; d * d],
+            b: vec![0.0; d],
+            l_matrix: vec![0.0; d * d],
+            lambda: 1.0,
+            alpha: 1.0,
+            d: d as u32,
+            update_count: 0,
+        };
+
+        let small = model(4).memory_footprint();
+        let large = model(22).memory_footprint();
+        assert_eq!(
+            small,
+            size_of::<BanditModel>() + (16 + 4 + 16) * size_of::<f64>()
+        );
+        assert_eq!(large - small, (2 * (484 - 16) + 18) * size_of::<f64>());
+    }
+
+    #[test]
+    fn test_actr_trace_footprint_counts_capacity() {
+        let mut trace: Vec<ReviewEvent> = Vec::with_capacity(8);
+        trace.push(ReviewEvent {
+            age_days: 1.0,
+            correct: true,
+        });
+        assert_eq!(
+            trace.memory_footprint(),
+            size_of::<Vec<ReviewEvent>>() + 8 * size_of::<ReviewEvent>()
+        );
+        assert_eq!(
+            Vec::<ReviewEvent>::new().memory_footprint(),
+            size_of::<Vec<ReviewEvent>>()
+        );
+    }
+
+    #[test]
+    fn test_json_value_bytes_counts_nested_data() {
+        let empty = serde_json::json!({});
+        let nested = serde_json::json!({ "alpha": [1.0, 2.0], "name": "abc" });
+        assert_eq!(json_value_bytes(&empty), size_of::<serde_json::Value>());
+        assert!(json_value_bytes(&nested) > 4 * size_of::<serde_json::Value>());
+        assert_eq!(
+            Some(nested.clone()).memory_footprint(),
+            nested.memory_footprint()
+        );
+    }
+}
@@ -0,0 +1,581 @@
+//! 词频表冷启动难度
+//!
+//! 新语言上线时还没有任何作答数据，难度只能先从语料词频推断：越常见的词越早
+//! 被学习者接触、越容易。本模块读入词频表（CSV），给出每个词的初始难度：
+//!
+//! - 频率用 Zipf 值表示：`log10(每十亿词出现次数)`，常见功能词约 7，罕见词约 1；
+//!   只有排名时按 Zipf 定律 `f(r) ∝ r^(-s)` 由排名估计频率；
+//! - 难度在 `[zipf_hard, zipf_easy]` 区间内随 Zipf 值线性下降，映射到 [0, 1]，
+//!   再等宽切成若干难度段供新词调度使用；
+//! - IRT 难度先验 β 取难度的 logit（Rasch 模型下 θ = 0 的学习者答错的概率即为
+//!   难度），截断到 [-3, 3]；
+//! - 选词先验为该词在词频表中的频率占比，即学会它能覆盖的语料比例。
+//!
+//! 另可检查词书对词频表的覆盖情况，用来判断词频表是否适合该词书。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 覆盖率报告中最多列出的缺失词数
+const MISSING_SAMPLE: usize = 50;
+/// Zipf 值的参照语料规模（每十亿词）
+const ZIPF_SCALE: f64 = 1e9;
+const BETA_LIMIT: f64 = 3.0;
+
+/// 词频表中的一个词
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyEntry {
+    /// 规范化后的词形（去首尾空白、小写）
+    pub word: String,
+    /// 出现次数；表中只有排名时为空
+    pub count: Option<f64>,
+    /// 从 1 开始的频率排名
+    pub rank: u32,
+}
+
+/// 解析后的词频表，按排名升序
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyList {
+    pub entries: Vec<FrequencyEntry>,
+    /// 无法解析而跳过的行号（从 1 开始）
+    pub skipped_lines: Vec<usize>,
+    /// 规范化后重复、已合并的行数
+    pub duplicates: u32,
+}
+
+impl FrequencyList {
+    /// 每个词都带出现次数时按次数计算 Zipf 值，否则按排名估计
+    pub fn has_counts(&self) -> bool {
+        !self.entries.is_empty() && self.entries.iter().all(|e| e.count.is_some())
+    }
+}
+
+/// 词形规范化：去首尾空白并转小写，导入与匹配词书时共用
+pub fn normalize_word(word: &str) -> String {
+    word.trim().to_lowercase()
+}
+
+/// 拆分一行 CSV，支持逗号或制表符分隔、双引号包裹与 `""` 转义
+fn split_fields(line: &str) -> Vec<String> {
+    let delimiter = if line.contains('\t') { '\t' } else { ',' };
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(core::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueColumn {
+    Count(usize),
+    Rank(usize),
+    None,
+}
+
+/// 识别表头：返回 (词列, 数值列)；首行不是表头时返回 None
+fn header_columns(fields: &[String]) -> Option<(usize, ValueColumn)> {
+    let names: Vec<String> = fields.iter().map(|f| f.to_lowercase()).collect();
+    let word = names
+        .iter()
+        .position(|n| matches!(n.as_str(), "word" | "lemma" | "spelling" | "token"))?;
+    let count = names
+        .iter()
+        .position(|n| matches!(n.as_str(), "count" | "frequency" | "freq" | "occurrences"));
+    let rank = names.iter().position(|n| n == "rank");
+    let value = match (count, rank) {
+        (Some(c), _) => ValueColumn::Count(c),
+        (None, Some(r)) => ValueColumn::Rank(r),
+        (None, None) => ValueColumn::None,
+    };
+    Some((word, value))
+}
+
+/// 解析词频表 CSV
+///
+/// 可带表头（词列名为 word/lemma/spelling/token，次数列为 count/frequency/freq/
+/// occurrences，或排名列 rank）；无表头时第一列为词，第二列（若有）为出现次数，
+/// 只有一列时按行序作为排名。空行与 `#` 开头的行忽略；词为空或数值无效的行跳过。
+/// 同一词出现多次时合并：次数相加，排名取最靠前者。
+pub fn parse_frequency_csv(text: &str) -> Result<FrequencyList, String> {
+    let mut columns: Option<(usize, ValueColumn)> = None;
+    let mut skipped_lines = Vec::new();
+    let mut duplicates = 0u32;
+    // (词, 次数, 排名, 行序)
+    let mut rows: Vec<(String, Option<f64>, Option<f64>, usize)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_fields(line);
+        let (word_col, value_col) = match columns {
+            Some(c) => c,
+            None => {
+                if let Some(header) = header_columns(&fields) {
+                    columns = Some(header);
+                    continue;
+                }
+                let c = if fields.len() > 1 {
+                    (0, ValueColumn::Count(1))
+                } else {
+                    (0, ValueColumn::None)
+                };
+                columns = Some(c);
+                c
+            }
+        };
+
+        let word = fields
+            .get(word_col)
+            .map(|w| normalize_word(w))
+            .unwrap_or_default();
+        if word.is_empty() {
+            skipped_lines.push(line_no);
+            continue;
+        }
+        let parse_value = |col: usize| -> Option<f64> {
+            fields
+                .get(col)
+                .and_then(|v| v.replace('_', "").parse::<f64>().ok())
+                .filter(|v| v.is_finite())
+        };
+        let (count, rank) = match value_col {
+            ValueColumn::Count(col) => match parse_value(col).filter(|c| *c >= 0.0) {
+                Some(c) => (Some(c), None),
+                None => {
+                    skipped_lines.push(line_no);
+                    continue;
+                }
+            },
+            ValueColumn::Rank(col) => match parse_value(col).filter(|r| *r >= 1.0) {
+                Some(r) => (None, Some(r)),
+                None => {
+                    skipped_lines.push(line_no);
+                    continue;
+                }
+            },
+            ValueColumn::None => (None, None),
+        };
+
+        match index.get(&word) {
+            Some(&at) => {
+                duplicates += 1;
+                let row = &mut rows[at];
+                row.1 = match (row.1, count) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    (a, b) => a.or(b),
+                };
+                row.2 = match (row.2, rank) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+            None => {
+                index.insert(word.clone(), rows.len());
+                let order = rows.len();
+                rows.push((word, count, rank, order));
+            }
+        }
+    }
+
+    if rows.is_empty() {
+        return Err("frequency list has no usable rows".into());
+    }
+
+    // 次数降序；否则按排名列；都没有时保持行序
+    rows.sort_by(|a, b| match (a.1, b.1, a.2, b.2) {
+        (Some(x), Some(y), _, _) => y.total_cmp(&x).then(a.3.cmp(&b.3)),
+        (_, _, Some(x), Some(y)) => x.total_cmp(&y).then(a.3.cmp(&b.3)),
+        _ => a.3.cmp(&b.3),
+    });
+
+    Ok(FrequencyList {
+        entries: rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, (word, count, _, _))| FrequencyEntry {
+                word,
+                count,
+                rank: i as u32 + 1,
+            })
+            .collect(),
+        skipped_lines,
+        duplicates,
+    })
+}
+
+/// 冷启动参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BootstrapConfig {
+    /// 难度段数
+    pub bands: u32,
+    /// Zipf 值不低于此值的词难度为 0
+    pub zipf_easy: f64,
+    /// Zipf 值不高于此值的词难度为 1
+    pub zipf_hard: f64,
+    /// 只有排名时 Zipf 定律的指数 s
+    pub rank_exponent: f64,
+    /// 源语料总词数；为空时取表内次数之和（截断的词频表会略微高估 Zipf 值）
+    pub corpus_size: Option<f64>,
+}
+
+impl Default for BootstrapConfig {
+    fn default() -> Self {
+        Self {
+            bands: 5,
+            zipf_easy: 6.0,
+            zipf_hard: 2.0,
+            rank_exponent: 1.0,
+            corpus_size: None,
+        }
+    }
+}
+
+impl BootstrapConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=20).contains(&self.bands) {
+            return Err("bands must be within [2, 20]".into());
+        }
+        if !(self.zipf_easy.is_finite() && self.zipf_hard.is_finite())
+            || self.zipf_easy <= self.zipf_hard
+        {
+            return Err("zipfEasy must be greater than zipfHard".into());
+        }
+        if !(0.5..=2.0).contains(&self.rank_exponent) {
+            return Err("rankExponent must be within [0.5, 2]".into());
+        }
+        if self
+            .corpus_size
+            .is_some_and(|n| !(n.is_finite() && n > 0.0))
+        {
+            return Err("corpusSize must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+/// 单个词的冷启动先验
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyPrior {
+    pub word: String,
+    pub rank: u32,
+    pub zipf: f64,
+    /// [0, 1]，与新词难度同一尺度
+    pub difficulty: f64,
+    /// 难度段，0 为最容易
+    pub band: u32,
+    /// IRT 难度先验
+    pub beta: f64,
+    /// 选词先验：该词在词频表中的频率占比，全表之和为 1
+    pub selection_prior: f64,
+}
+
+/// 整张词频表的先验
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapTable {
+    pub priors: Vec<FrequencyPrior>,
+    /// 各难度段的词数
+    pub band_counts: Vec<u32>,
+    /// 是否由出现次数（而非排名）计算
+    pub from_counts: bool,
+}
+
+/// Zipf 值映射到 [0, 1] 难度
+pub fn difficulty_from_zipf(zipf: f64, config: &BootstrapConfig) -> f64 {
+    ((config.zipf_easy - zipf) / (config.zipf_easy - config.zipf_hard)).clamp(0.0, 1.0)
+}
+
+/// 难度映射到 IRT β：Rasch 模型下 θ = 0 时答错概率等于难度，β = logit(难度)
+pub fn beta_from_difficulty(difficulty: f64) -> f64 {
+    let d = difficulty.clamp(1e-6, 1.0 - 1e-6);
+    (d / (1.0 - d)).ln().clamp(-BETA_LIMIT, BETA_LIMIT)
+}
+
+/// 由词频表生成每个词的难度、难度段、β 先验与选词先验
+pub fn bootstrap_priors(list: &FrequencyList, config: &BootstrapConfig) -> BootstrapTable {
+    let from_counts = list.has_counts();
+    // 每个词的相对频率（占参照语料的比例）
+    let frequencies: Vec<f64> = if from_counts {
+        let total = config.corpus_size.unwrap_or_else(|| {
+            list.entries
+                .iter()
+                .map(|e| e.count.unwrap_or(0.0))
+                .sum::<f64>()
+        });
+        list.entries
+            .iter()
+            .map(|e| e.count.unwrap_or(0.0) / total.max(1.0))
+            .collect()
+    } else {
+        let weights: Vec<f64> = list
+            .entries
+            .iter()
+            .map(|e| (e.rank as f64).powf(-config.rank_exponent))
+            .collect();
+        let harmonic: f64 = weights.iter().sum();
+        weights.iter().map(|w| w / harmonic).collect()
+    };
+    let listed_total: f64 = frequencies.iter().sum();
+
+    let bands = config.bands.max(1);
+    let mut band_counts = vec![0u32; bands as usize];
+    let priors = list
+        .entries
+        .iter()
+        .zip(&frequencies)
+        .map(|(entry, &f)| {
+            // 次数为 0 的词按每十亿词出现一次计
+            let zipf = (f * ZIPF_SCALE).max(1.0).log10();
+            let difficulty = difficulty_from_zipf(zipf, config);
+            let band = ((difficulty * bands as f64) as u32).min(bands - 1);
+            band_counts[band as usize] += 1;
+            FrequencyPrior {
+                word: entry.word.clone(),
+                rank: entry.rank,
+                zipf,
+                difficulty,
+                band,
+                beta: beta_from_difficulty(difficulty),
+                selection_prior: if listed_total > 0.0 {
+                    f / listed_total
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+
+    BootstrapTable {
+        priors,
+        band_counts,
+        from_counts,
+    }
+}
+
+/// 某难度段内词书对词频表的覆盖
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandCoverage {
+    pub band: u32,
+    /// 词频表中该段的词数
+    pub listed: u32,
+    /// 其中出现在词书里的词数
+    pub matched: u32,
+}
+
+/// 词书对词频表的覆盖情况
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    /// 词书中规范化后不同的词数
+    pub wordbook_words: u32,
+    /// 在词频表中找到先验的词数
+    pub matched: u32,
+    /// matched / wordbook_words
+    pub word_coverage: f64,
+    /// 匹配词的选词先验之和，即词书覆盖的语料比例
+    pub token_coverage: f64,
+    pub missing_count: u32,
+    /// 部分缺失词（最多 50 个，按词书顺序）
+    pub missing_sample: Vec<String>,
+    pub bands: Vec<BandCoverage>,
+    /// word_coverage 是否达到要求
+    pub sufficient: bool,
+}
+
+/// 检查词书中有多少词能从词频表取得先验
+pub fn coverage(
+    table: &BootstrapTable,
+    words: &[String],
+    min_word_coverage: f64,
+) -> CoverageReport {
+    let by_word: HashMap<&str, &FrequencyPrior> =
+        table.priors.iter().map(|p| (p.word.as_str(), p)).collect();
+    let mut bands: Vec<BandCoverage> = table
+        .band_counts
+        .iter()
+        .enumerate()
+        .map(|(band, &listed)| BandCoverage {
+            band: band as u32,
+            listed,
+            matched: 0,
+        })
+        .collect();
+
+    let mut seen = std::collections::HashSet::new();
+    let mut matched = 0u32;
+    let mut token_coverage = 0.0;
+    let mut missing_count = 0u32;
+    let mut missing_sample = Vec::new();
+    for word in words {
+        let word = normalize_word(word);
+        if word.is_empty() || !seen.insert(word.clone()) {
+            continue;
+        }
+        match by_word.get(word.as_str()) {
+            Some(prior) => {
+                matched += 1;
+                token_coverage += prior.selection_prior;
+                if let Some(band) = bands.get_mut(prior.band as usize) {
+                    band.matched += 1;
+                }
+            }
+            None => {
+                missing_count += 1;
+                if missing_sample.len() < MISSING_SAMPLE {
+                    missing_sample.push(word);
+                }
+            }
+        }
+    }
+
+    let wordbook_words = seen.len() as u32;
+    let word_coverage = if wordbook_words > 0 {
+        matched as f64 / wordbook_words as f64
+    } else {
+        0.0
+    };
+    CoverageReport {
+        wordbook_words,
+        matched,
+        word_coverage,
+        token_coverage: token_coverage.min(1.0),
+        missing_count,
+        missing_sample,
+        bands,
+        sufficient: wordbook_words > 0 && word_coverage >= min_word_coverage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_with_header_sorts_and_merges() {
+        let csv = "\u{feff}Word,Count\nthe,5000\n\"Cat\",120\nzebra,3\ncat,30\n,4\ndog,abc\n";
+        let list = parse_frequency_csv(csv).unwrap();
+        let words: Vec<&str> = list.entries.iter().map(|e| e.word.as_str()).collect();
+        assert_eq!(words, vec!["the", "cat", "zebra"]);
+        assert_eq!(list.entries[1].count, Some(150.0));
+        assert_eq!(list.entries[2].rank, 3);
+        assert_eq!(list.duplicates, 1);
+        assert_eq!(list.skipped_lines, vec![6, 7]);
+        assert!(list.has_counts());
+    }
+
+    #[test]
+    fn test_parse_rank_only_and_headerless() {
+        let ranked = parse_frequency_csv("rank\tlemma\n2\tb\n1\ta\n").unwrap();
+        assert_eq!(ranked.entries[0].word, "a");
+        assert!(!ranked.has_counts());
+
+        let plain = parse_frequency_csv("# comment\nalpha\nbeta\n\ngamma\n").unwrap();
+        assert_eq!(plain.entries.len(), 3);
+        assert_eq!(plain.entries[2].word, "gamma");
+        assert_eq!(plain.entries[2].rank, 3);
+
+        assert!(parse_frequency_csv("word,count\n").is_err());
+    }
+
+    #[test]
+    fn test_priors_follow_frequency() {
+        let csv = "the,60000000\nhouse,400000\nlantern,3000\nxylophone,20\n";
+        let list = parse_frequency_csv(csv).unwrap();
+        let table = bootstrap_priors(
+            &list,
+            &BootstrapConfig {
+                corpus_size: Some(1e9),
+                ..BootstrapConfig::default()
+            },
+        );
+        let p = &table.priors;
+        assert!((p[0].zipf - 7.778).abs() < 0.01);
+        assert_eq!(p[0].difficulty, 0.0);
+        assert_eq!(p[3].difficulty, 1.0);
+        for w in p.windows(2) {
+            assert!(w[0].difficulty <= w[1].difficulty);
+            assert!(w[0].beta <= w[1].beta);
+            assert!(w[0].band <= w[1].band);
+        }
+        assert_eq!(p[3].band, 4);
+        assert_eq!(table.band_counts.iter().sum::<u32>(), 4);
+        let total: f64 = p.iter().map(|x| x.selection_prior).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rank_only_priors_use_zipf_law() {
+        let words: String = (0..20_000).map(|i| format!("w{i}\n")).collect();
+        let list = parse_frequency_csv(&words).unwrap();
+        let table = bootstrap_priors(&list, &BootstrapConfig::default());
+        assert!(!table.from_counts);
+        // 第 1 名约占 1/H(20000) ≈ 9.4%，第 10000 名约为其万分之一
+        assert!((table.priors[0].selection_prior - 0.094).abs() < 0.005);
+        assert!(table.priors[0].difficulty < 0.05);
+        assert!(table.priors[19_999].difficulty > table.priors[999].difficulty);
+    }
+
+    #[test]
+    fn test_beta_matches_rasch_difficulty() {
+        for d in [0.1, 0.5, 0.8] {
+            let beta = beta_from_difficulty(d);
+            let p_correct = 1.0 / (1.0 + beta.exp());
+            assert!((1.0 - p_correct - d).abs() < 1e-9);
+        }
+        assert_eq!(beta_from_difficulty(0.0), -3.0);
+        assert_eq!(beta_from_difficulty(1.0), 3.0);
+    }
+
+    #[test]
+    fn test_coverage_against_wordbook() {
+        let list = parse_frequency_csv("a,900\nb,90\nc,9\nd,1\n").unwrap();
+        let table = bootstrap_priors(&list, &BootstrapConfig::default());
+        let book: Vec<String> = ["A", "a", "b", "missing", " "]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let report = coverage(&table, &book, 0.6);
+        assert_eq!(report.wordbook_words, 3);
+        assert_eq!(report.matched, 2);
+        assert!((report.token_coverage - 0.99).abs() < 1e-9);
+        assert_eq!(report.missing_sample, vec!["missing".to_string()]);
+        assert!(report.sufficient);
+        assert_eq!(
+            report.bands.iter().map(|b| b.matched).sum::<u32>(),
+            report.matched
+        );
+        assert!(!coverage(&table, &book, 0.9).sufficient);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(BootstrapConfig::default().validate().is_ok());
+        let bad = BootstrapConfig {
+            zipf_easy: 2.0,
+            zipf_hard: 3.0,
+            ..BootstrapConfig::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+}
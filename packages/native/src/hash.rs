@@ -0,0 +1,28 @@
+//! 稳定哈希（跨进程、跨平台一致，用于指纹与分桶）
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// FNV-1a 64 位；多段输入之间不插入分隔符，调用方自行拼接
+pub fn fnv1a_64(parts: &[&[u8]]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for part in parts {
+        for &b in *part {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        assert_eq!(fnv1a_64(&[]), FNV_OFFSET);
+        assert_eq!(fnv1a_64(&[b"a"]), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64(&[b"ab", b"c"]), fnv1a_64(&[b"abc"]));
+    }
+}
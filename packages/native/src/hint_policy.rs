@@ -0,0 +1,325 @@
+//! 逐题提示策略
+//!
+//! 提示等级（0 = 不提示 … levels − 1 = 最强提示）由一个独立的小型 Thompson
+//! 采样器决定，与主决策老虎机互不影响：
+//!
+//! - 上下文 = 单词难度档 × 回忆概率桶，每个上下文、每个提示等级一个
+//!   Beta(α, β) 后验；
+//! - 奖励 = 答对(1/0) − 惩罚系数 × level / (levels − 1)，截断到 [0, 1]。
+//!   提示越多，同样答对得到的奖励越低，避免策略一味给足提示；
+//! - 分数奖励按 α += r、β += 1 − r 更新。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::events::{self, AlgoEvent};
+use crate::rng::{domains, RngFactory};
+use crate::tuning::BetaPrior;
+
+/// 策略参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HintPolicyConfig {
+    /// 提示等级数（含“不提示”）
+    pub levels: u32,
+    /// 难度档数，难度取值 [0, 1]
+    pub difficulty_bands: u32,
+    /// 回忆概率桶数
+    pub recall_buckets: u32,
+    /// 最强提示下答对的奖励折扣
+    pub overuse_penalty: f64,
+}
+
+impl Default for HintPolicyConfig {
+    fn default() -> Self {
+        Self {
+            levels: 3,
+            difficulty_bands: 3,
+            recall_buckets: 4,
+            overuse_penalty: 0.5,
+        }
+    }
+}
+
+/// 选择提示等级时的上下文
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HintContext {
+    pub difficulty_band: u32,
+    pub recall_bucket: u32,
+}
+
+impl HintContext {
+    /// 按等宽分档；越界或非有限值取最近的档
+    pub fn from_scores(difficulty: f64, recall: f64, config: &HintPolicyConfig) -> Self {
+        Self {
+            difficulty_band: bucket(difficulty, config.difficulty_bands),
+            recall_bucket: bucket(recall, config.recall_buckets),
+        }
+    }
+
+    fn key(&self) -> String {
+        format!("d{}:r{}", self.difficulty_band, self.recall_bucket)
+    }
+}
+
+fn bucket(value: f64, count: u32) -> u32 {
+    let count = count.max(1);
+    let value = if value.is_finite() { value } else { 0.5 };
+    ((value.clamp(0.0, 1.0) * count as f64) as u32).min(count - 1)
+}
+
+/// 一次选择的结果
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HintChoice {
+    pub level: u32,
+    pub context: HintContext,
+    /// 所选等级的后验均值（期望奖励）
+    pub expected_reward: f64,
+}
+
+/// 提示策略状态，可序列化保存
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HintPolicy {
+    pub config: HintPolicyConfig,
+    /// 上下文键 → 各等级的后验；未见过的上下文使用均匀先验
+    pub arms: BTreeMap<String, Vec<BetaPrior>>,
+}
+
+impl HintPolicy {
+    pub fn new(config: HintPolicyConfig) -> Self {
+        Self {
+            config,
+            arms: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("提示策略解析失败: {e}"))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    fn levels(&self) -> usize {
+        self.config.levels.max(1) as usize
+    }
+
+    /// 上下文下各等级的后验
+    pub fn posteriors(&self, context: &HintContext) -> Vec<BetaPrior> {
+        let mut arms = self.arms.get(&context.key()).cloned().unwrap_or_default();
+        arms.resize(self.levels(), BetaPrior::default());
+        arms
+    }
+
+    /// Thompson 采样：每个等级从后验抽一个值，取最大者
+    pub fn select<R: Rng + ?Sized>(&self, context: HintContext, rng: &mut R) -> HintChoice {
+        let arms = self.posteriors(&context);
+        let (level, _) = arms
+            .iter()
+            .map(|arm| sample_beta(rng, arm.alpha, arm.beta))
+            .enumerate()
+            .fold((0, f64::NEG_INFINITY), |best, (i, v)| {
+                if v > best.1 {
+                    (i, v)
+                } else {
+                    best
+                }
+            });
+        HintChoice {
+            level: level as u32,
+            context,
+            expected_reward: posterior_mean(&arms[level]),
+        }
+    }
+
+    /// 记录一次作答，返回本次奖励；越界等级按最强提示记
+    pub fn update(&mut self, context: HintContext, level: u32, correct: bool) -> f64 {
+        let levels = self.levels();
+        let level = (level as usize).min(levels - 1);
+        let reward = hint_reward(correct, level as u32, &self.config);
+        let arms = self.arms.entry(context.key()).or_default();
+        arms.resize(levels, BetaPrior::default());
+        arms[level].alpha += reward;
+        arms[level].beta += 1.0 - reward;
+        reward
+    }
+}
+
+/// 带提示惩罚的奖励
+pub fn hint_reward(correct: bool, level: u32, config: &HintPolicyConfig) -> f64 {
+    if !correct {
+        return 0.0;
+    }
+    let max_level = config.levels.saturating_sub(1).max(1) as f64;
+    let penalty = config.overuse_penalty.clamp(0.0, 1.0) * (level as f64 / max_level).min(1.0);
+    (1.0 - penalty).clamp(0.0, 1.0)
+}
+
+fn posterior_mean(arm: &BetaPrior) -> f64 {
+    let total = arm.alpha + arm.beta;
+    if total > 0.0 {
+        arm.alpha / total
+    } else {
+        0.5
+    }
+}
+
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Gamma(shape, 1)，Marsaglia–Tsang；shape < 1 时用 U^(1/shape) 提升
+fn sample_gamma<R: Rng + ?Sized>(rng: &mut R, shape: f64) -> f64 {
+    if shape < 1.0 {
+        let u: f64 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+        return sample_gamma(rng, shape + 1.0) * u.powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/// Beta(α, β) 采样；非正参数按 1 处理
+pub fn sample_beta<R: Rng + ?Sized>(rng: &mut R, alpha: f64, beta: f64) -> f64 {
+    let fix = |p: f64| if p.is_finite() && p > 0.0 { p } else { 1.0 };
+    let x = sample_gamma(rng, fix(alpha));
+    let y = sample_gamma(rng, fix(beta));
+    if x + y > 0.0 {
+        x / (x + y)
+    } else {
+        0.5
+    }
+}
+
+fn parse_or_default(policy_json: &str) -> HintPolicy {
+    HintPolicy::from_json(policy_json).unwrap_or_else(|e| {
+        events::record(AlgoEvent::StateParseFallback, || e);
+        HintPolicy::default()
+    })
+}
+
+/// 为一道题选择提示等级；策略 JSON 无效时使用空策略
+#[cfg_attr(feature = "napi", napi)]
+pub fn select_hint_level(
+    policy_json: String,
+    difficulty: f64,
+    recall: f64,
+    seed: u32,
+) -> HintChoice {
+    let policy = parse_or_default(&policy_json);
+    let context = HintContext::from_scores(difficulty, recall, &policy.config);
+    let mut rng = RngFactory::new(seed as u64).stream(domains::HINT_POLICY);
+    policy.select(context, &mut rng)
+}
+
+/// 记录作答结果，返回更新后的策略 JSON
+#[cfg_attr(feature = "napi", napi)]
+pub fn update_hint_policy(
+    policy_json: String,
+    context: HintContext,
+    level: u32,
+    correct: bool,
+) -> String {
+    let mut policy = parse_or_default(&policy_json);
+    policy.update(context, level, correct);
+    policy.to_json()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::RngStream;
+
+    fn rng(seed: u64) -> RngStream {
+        RngFactory::new(seed).stream(domains::HINT_POLICY)
+    }
+
+    #[test]
+    fn test_context_buckets_clamp() {
+        let config = HintPolicyConfig::default();
+        let ctx = HintContext::from_scores(0.0, 1.0, &config);
+        assert_eq!((ctx.difficulty_band, ctx.recall_bucket), (0, 3));
+        let ctx = HintContext::from_scores(1.5, f64::NAN, &config);
+        assert_eq!((ctx.difficulty_band, ctx.recall_bucket), (2, 2));
+    }
+
+    #[test]
+    fn test_reward_penalizes_hints() {
+        let config = HintPolicyConfig::default();
+        assert_eq!(hint_reward(true, 0, &config), 1.0);
+        assert!((hint_reward(true, 1, &config) - 0.75).abs() < 1e-12);
+        assert!((hint_reward(true, 2, &config) - 0.5).abs() < 1e-12);
+        assert_eq!(hint_reward(false, 0, &config), 0.0);
+    }
+
+    #[test]
+    fn test_beta_sample_mean() {
+        let mut r = rng(3);
+        let n = 4000;
+        let mean = (0..n).map(|_| sample_beta(&mut r, 2.0, 6.0)).sum::<f64>() / n as f64;
+        assert!((mean - 0.25).abs() < 0.02, "{mean}");
+        let small = sample_beta(&mut r, 0.3, 0.3);
+        assert!((0.0..=1.0).contains(&small));
+    }
+
+    #[test]
+    fn test_policy_learns_per_context() {
+        let mut policy = HintPolicy::default();
+        let hard = HintContext::from_scores(0.9, 0.1, &policy.config);
+        let easy = HintContext::from_scores(0.1, 0.9, &policy.config);
+        // 难词只有给足提示才答对；易词不提示也答对
+        for _ in 0..200 {
+            for level in 0..3 {
+                policy.update(hard, level, level == 2);
+                policy.update(easy, level, true);
+            }
+        }
+        let mut r = rng(11);
+        let hard_levels: Vec<u32> = (0..50).map(|_| policy.select(hard, &mut r).level).collect();
+        let easy_levels: Vec<u32> = (0..50).map(|_| policy.select(easy, &mut r).level).collect();
+        assert!(hard_levels.iter().filter(|&&l| l == 2).count() > 40);
+        // 同样答对，不提示的奖励更高
+        assert!(easy_levels.iter().filter(|&&l| l == 0).count() > 40);
+    }
+
+    #[test]
+    fn test_json_round_trip_and_select_is_seeded() {
+        let mut policy = HintPolicy::default();
+        let ctx = HintContext::from_scores(0.5, 0.5, &policy.config);
+        policy.update(ctx, 1, true);
+        let json = policy.to_json();
+        assert_eq!(HintPolicy::from_json(&json).unwrap(), policy);
+
+        let a = select_hint_level(json.clone(), 0.5, 0.5, 7);
+        let b = select_hint_level(json.clone(), 0.5, 0.5, 7);
+        assert_eq!(a, b);
+        assert_eq!(a.context, ctx);
+
+        let updated = update_hint_policy(json, ctx, 9, false);
+        let arms = HintPolicy::from_json(&updated).unwrap().posteriors(&ctx);
+        assert_eq!(arms[2].beta, 2.0);
+        assert!(HintPolicy::from_json("not json").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! 按语言对划分的算法参数
+//!
+//! 不同语言的遗忘动态与难度构成不同（例如日语词条更短，但读音与汉字写法需要
+//! 分别记忆，遗忘更快）。参数表以语言对（学习语言-释义语言，如 `ja-zh`）为键，
+//! 查找顺序为：精确语言对 -> 仅学习语言 -> 全局默认。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 默认语言对（英语词汇，中文释义）
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const DEFAULT_GLOSS_LANGUAGE: &str = "zh";
+
+/// 语言代码：2-3 位小写字母（ISO 639）
+pub fn is_valid_language_code(code: &str) -> bool {
+    (2..=3).contains(&code.len()) && code.bytes().all(|b| b.is_ascii_lowercase())
+}
+
+/// 学习语言 -> 释义语言
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguagePair {
+    pub source: String,
+    pub target: String,
+}
+
+impl LanguagePair {
+    pub fn new(source: &str, target: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    /// 解析 `ja-zh` 或仅学习语言 `ja`（释义语言取默认值）
+    pub fn parse(key: &str) -> Result<Self, String> {
+        let (source, target) = key.split_once('-').unwrap_or((key, DEFAULT_GLOSS_LANGUAGE));
+        if !is_valid_language_code(source) || !is_valid_language_code(target) {
+            return Err(format!("invalid language pair: {key}"));
+        }
+        Ok(Self::new(source, target))
+    }
+
+    pub fn key(&self) -> String {
+        format!("{}-{}", self.source, self.target)
+    }
+}
+
+impl Default for LanguagePair {
+    fn default() -> Self {
+        Self::new(DEFAULT_LANGUAGE, DEFAULT_GLOSS_LANGUAGE)
+    }
+}
+
+/// 新词难度特征权重：长度与义项数各自归一化后加权
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultyWeights {
+    pub length_weight: f64,
+    pub meaning_weight: f64,
+    /// 达到最高长度难度的字符数
+    pub length_norm: f64,
+    /// 达到最高义项难度的义项数
+    pub meaning_norm: f64,
+}
+
+impl Default for DifficultyWeights {
+    fn default() -> Self {
+        Self {
+            length_weight: 0.6,
+            meaning_weight: 0.4,
+            length_norm: 15.0,
+            meaning_norm: 5.0,
+        }
+    }
+}
+
+impl DifficultyWeights {
+    pub fn score(&self, spelling: &str, meaning_count: usize) -> f64 {
+        let length = (spelling.chars().count() as f64 / self.length_norm).min(1.0);
+        let meanings = (meaning_count as f64 / self.meaning_norm).min(1.0);
+        (length * self.length_weight + meanings * self.meaning_weight).clamp(0.0, 1.0)
+    }
+}
+
+/// 单个语言对的算法参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageParams {
+    /// 相对基线的遗忘速率（>1 遗忘更快，复习间隔相应缩短）
+    pub decay_rate: f64,
+    /// 自适应掌握阈值的缩放（>1 更难判定为掌握）
+    pub mastery_threshold_scale: f64,
+    pub difficulty: DifficultyWeights,
+}
+
+impl Default for LanguageParams {
+    fn default() -> Self {
+        Self {
+            decay_rate: 1.0,
+            mastery_threshold_scale: 1.0,
+            difficulty: DifficultyWeights::default(),
+        }
+    }
+}
+
+impl LanguageParams {
+    /// 复习间隔缩放系数
+    pub fn interval_factor(&self) -> f64 {
+        1.0 / self.decay_rate
+    }
+
+    /// 按本语言参数重新判定掌握
+    pub fn is_mastered(&self, score: f64, threshold: f64) -> bool {
+        score >= threshold * self.mastery_threshold_scale
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.25..=4.0).contains(&self.decay_rate) {
+            return Err("decayRate must be within [0.25, 4]".into());
+        }
+        if !(0.5..=1.5).contains(&self.mastery_threshold_scale) {
+            return Err("masteryThresholdScale must be within [0.5, 1.5]".into());
+        }
+        let d = &self.difficulty;
+        if d.length_weight < 0.0 || d.meaning_weight < 0.0 {
+            return Err("difficulty weights must be non-negative".into());
+        }
+        if (d.length_weight + d.meaning_weight - 1.0).abs() > 1e-6 {
+            return Err("difficulty weights must sum to 1".into());
+        }
+        if d.length_norm < 1.0 || d.meaning_norm < 1.0 {
+            return Err("difficulty norms must be at least 1".into());
+        }
+        Ok(())
+    }
+}
+
+/// 参数注册表
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamRegistry {
+    pub defaults: LanguageParams,
+    /// 键为语言对（`ja-zh`）或仅学习语言（`ja`）
+    #[serde(default)]
+    pub entries: BTreeMap<String, LanguageParams>,
+}
+
+impl Default for ParamRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ParamRegistry {
+    /// 内置参数：英语沿用全局默认；日语遗忘更快、词条更短、义项更分散
+    pub fn builtin() -> Self {
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            "ja".to_string(),
+            LanguageParams {
+                decay_rate: 1.25,
+                mastery_threshold_scale: 1.1,
+                difficulty: DifficultyWeights {
+                    length_weight: 0.4,
+                    meaning_weight: 0.6,
+                    length_norm: 6.0,
+                    meaning_norm: 4.0,
+                },
+            },
+        );
+        Self {
+            defaults: LanguageParams::default(),
+            entries,
+        }
+    }
+
+    pub fn resolve(&self, pair: &LanguagePair) -> &LanguageParams {
+        self.entries
+            .get(&pair.key())
+            .or_else(|| self.entries.get(&pair.source))
+            .unwrap_or(&self.defaults)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.defaults
+            .validate()
+            .map_err(|e| format!("defaults: {e}"))?;
+        for (key, params) in &self.entries {
+            let valid_key = match key.split_once('-') {
+                Some(_) => LanguagePair::parse(key).is_ok(),
+                None => is_valid_language_code(key),
+            };
+            if !valid_key {
+                return Err(format!("invalid language key: {key}"));
+            }
+            params.validate().map_err(|e| format!("{key}: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_parse() {
+        assert_eq!(
+            LanguagePair::parse("ja-zh").unwrap(),
+            LanguagePair::new("ja", "zh")
+        );
+        assert_eq!(LanguagePair::parse("ja").unwrap().key(), "ja-zh");
+        assert!(LanguagePair::parse("zh-CN").is_err());
+        assert!(LanguagePair::parse("").is_err());
+    }
+
+    #[test]
+    fn test_resolve_falls_back() {
+        let mut registry = ParamRegistry::builtin();
+        let ja_en = LanguageParams {
+            decay_rate: 1.5,
+            ..LanguageParams::default()
+        };
+        registry.entries.insert("ja-en".to_string(), ja_en.clone());
+
+        assert_eq!(registry.resolve(&LanguagePair::new("ja", "en")), &ja_en);
+        assert_eq!(
+            registry.resolve(&LanguagePair::new("ja", "zh")).decay_rate,
+            1.25
+        );
+        assert_eq!(
+            registry.resolve(&LanguagePair::default()),
+            &LanguageParams::default()
+        );
+    }
+
+    #[test]
+    fn test_default_difficulty_matches_legacy_formula() {
+        let weights = DifficultyWeights::default();
+        let legacy = |spelling: &str, meanings: usize| {
+            (spelling.chars().count() as f64 / 15.0).min(1.0) * 0.6
+                + (meanings as f64 / 5.0).min(1.0) * 0.4
+        };
+        for (word, meanings) in [("cat", 1), ("internationalization", 7), ("apple", 3)] {
+            assert!((weights.score(word, meanings) - legacy(word, meanings)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_japanese_words_are_not_all_easy() {
+        let registry = ParamRegistry::builtin();
+        let ja = registry.resolve(&LanguagePair::new("ja", "zh"));
+        let en = registry.resolve(&LanguagePair::default());
+        // 四字熟语在英语参数下会被当作很短的简单词
+        assert!(ja.difficulty.score("一期一会", 2) > en.difficulty.score("一期一会", 2) + 0.2);
+        assert!(ja.interval_factor() < en.interval_factor());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ParamRegistry::builtin().validate().is_ok());
+        let mut registry = ParamRegistry::builtin();
+        registry
+            .entries
+            .insert("Japanese".to_string(), LanguageParams::default());
+        assert!(registry.validate().is_err());
+
+        let mut registry = ParamRegistry::builtin();
+        registry.defaults.decay_rate = 0.0;
+        assert!(registry.validate().is_err());
+    }
+}
@@ -0,0 +1,407 @@
+//! 特征布局指纹与模型迁移
+//!
+//! LinUCB 的 A/b/L 与特征顺序一一对应。特征构造器变化后，旧快照若直接加载会
+//! 产生无意义的预测。快照记录布局指纹，加载时（`set_model`）检测不一致，并按
+//! 显式选择的迁移策略处理：重置、按特征名保留并以先验填充、或从日志回放。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::hash::fnv1a_64;
+use crate::matrix::{
+    cholesky_decompose, cholesky_rank1_update, rank1_update_matrix, vec_add_scaled,
+};
+use crate::types::{BanditModel, MIN_RANK1_DIAG};
+
+/// 特征布局（有序特征名 + 构造器版本）
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureLayout {
+    pub version: u32,
+    pub names: Vec<String>,
+}
+
+impl FeatureLayout {
+    pub fn new(version: u32, names: &[&str]) -> Self {
+        Self {
+            version,
+            names: names.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.names.len()
+    }
+
+    /// 稳定指纹（FNV-1a 64 位，十六进制）
+    pub fn fingerprint(&self) -> String {
+        let version = self.version.to_le_bytes();
+        let mut parts: Vec<&[u8]> = vec![&version];
+        for name in &self.names {
+            parts.push(name.as_bytes());
+            parts.push(&[0]);
+        }
+        format!("{:016x}", fnv1a_64(&parts))
+    }
+}
+
+/// 带布局信息的模型快照
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BanditSnapshot {
+    pub model: BanditModel,
+    /// 旧快照没有指纹，视为未知布局
+    #[serde(default)]
+    pub layout_fingerprint: Option<String>,
+    /// 保存时的完整布局，供按特征名迁移使用
+    #[serde(default)]
+    pub layout: Option<FeatureLayout>,
+}
+
+/// 一条可回放的历史观测（特征已按目标布局构造）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayRecord {
+    pub features: Vec<f64>,
+    pub reward: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    /// 模型维度与声明的布局不一致
+    DimensionMismatch { expected: usize, actual: usize },
+    /// 策略需要旧布局但快照未记录
+    MissingSourceLayout,
+    /// 回放记录维度错误
+    InvalidReplayRecord { index: usize },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::DimensionMismatch { expected, actual } => {
+                write!(f, "模型维度 {actual} 与布局维度 {expected} 不一致")
+            }
+            LayoutError::MissingSourceLayout => write!(f, "快照未记录特征布局，无法按特征名迁移"),
+            LayoutError::InvalidReplayRecord { index } => {
+                write!(f, "第 {index} 条回放记录的特征维度错误")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// 布局变化时的迁移策略
+pub trait LayoutMigration {
+    fn name(&self) -> &'static str;
+
+    fn migrate(
+        &self,
+        snapshot: &BanditSnapshot,
+        target: &FeatureLayout,
+    ) -> Result<BanditModel, LayoutError>;
+}
+
+/// 丢弃旧参数，回到先验
+pub struct ResetMigration;
+
+impl LayoutMigration for ResetMigration {
+    fn name(&self) -> &'static str {
+        "reset"
+    }
+
+    fn migrate(
+        &self,
+        snapshot: &BanditSnapshot,
+        target: &FeatureLayout,
+    ) -> Result<BanditModel, LayoutError> {
+        Ok(prior_model(
+            target.dimension(),
+            snapshot.model.lambda,
+            snapshot.model.alpha,
+        ))
+    }
+}
+
+/// 按特征名保留两种布局共有的 A/b 分量，新特征以 lambda*I 先验填充
+pub struct PadWithPriorMigration;
+
+impl LayoutMigration for PadWithPriorMigration {
+    fn name(&self) -> &'static str {
+        "pad_with_prior"
+    }
+
+    fn migrate(
+        &self,
+        snapshot: &BanditSnapshot,
+        target: &FeatureLayout,
+    ) -> Result<BanditModel, LayoutError> {
+        let source = snapshot
+            .layout
+            .as_ref()
+            .ok_or(LayoutError::MissingSourceLayout)?;
+        let old = &snapshot.model;
+        let old_d = source.dimension();
+        if old.d as usize != old_d || old.a_matrix.len() != old_d * old_d || old.b.len() != old_d {
+            return Err(LayoutError::DimensionMismatch {
+                expected: old_d,
+                actual: old.d as usize,
+            });
+        }
+
+        let mut model = prior_model(target.dimension(), old.lambda, old.alpha);
+        let d = target.dimension();
+        let mapping: Vec<Option<usize>> = target
+            .names
+            .iter()
+            .map(|name| source.names.iter().position(|n| n == name))
+            .collect();
+
+        for (i, oi) in mapping.iter().enumerate() {
+            let Some(oi) = *oi else { continue };
+            model.b[i] = old.b[oi];
+            for (j, oj) in mapping.iter().enumerate() {
+                if let Some(oj) = *oj {
+                    model.a_matrix[i * d + j] = old.a_matrix[oi * old_d + oj];
+                }
+            }
+        }
+        model.l_matrix = cholesky_decompose(&model.a_matrix, d, model.lambda);
+        model.update_count = old.update_count;
+        Ok(model)
+    }
+}
+
+/// 从先验开始回放历史观测（特征需已按目标布局重建）
+pub struct ReplayMigration {
+    pub records: Vec<ReplayRecord>,
+}
+
+impl LayoutMigration for ReplayMigration {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn migrate(
+        &self,
+        snapshot: &BanditSnapshot,
+        target: &FeatureLayout,
+    ) -> Result<BanditModel, LayoutError> {
+        let d = target.dimension();
+        let mut model = prior_model(d, snapshot.model.lambda, snapshot.model.alpha);
+        for (index, record) in self.records.iter().enumerate() {
+            if record.features.len() != d {
+                return Err(LayoutError::InvalidReplayRecord { index });
+            }
+            rank1_update_matrix(&mut model.a_matrix, &record.features, d);
+            vec_add_scaled(&mut model.b, &record.features, record.reward);
+            if !cholesky_rank1_update(&mut model.l_matrix, &record.features, d, MIN_RANK1_DIAG) {
+                model.l_matrix = cholesky_decompose(&model.a_matrix, d, model.lambda);
+            }
+            model.update_count += 1;
+        }
+        Ok(model)
+    }
+}
+
+/// A = lambda*I, b = 0, L = sqrt(lambda)*I
+pub fn prior_model(d: usize, lambda: f64, alpha: f64) -> BanditModel {
+    let mut a_matrix = vec![0.0; d * d];
+    let mut l_matrix = vec![0.0; d * d];
+    for i in 0..d {
+        a_matrix[i * d + i] = lambda;
+        l_matrix[i * d + i] = lambda.sqrt();
+    }
+    BanditModel {
+        a_matrix,
+        b: vec![0.0; d],
+        l_matrix,
+        lambda,
+        alpha,
+        d: d as u32,
+        update_count: 0,
+    }
+}
+
+/// `set_model` 的处理结果，调用方负责记录日志
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutCheck {
+    pub migrated: bool,
+    /// 未迁移时为 "none"
+    pub strategy: String,
+    pub from_fingerprint: Option<String>,
+    pub to_fingerprint: String,
+}
+
+/// 绑定了特征布局的 LinUCB 模型
+pub struct LayoutBoundModel {
+    layout: FeatureLayout,
+    model: BanditModel,
+}
+
+impl LayoutBoundModel {
+    pub fn new(layout: FeatureLayout, lambda: f64, alpha: f64) -> Self {
+        let model = prior_model(layout.dimension(), lambda, alpha);
+        Self { layout, model }
+    }
+
+    pub fn layout(&self) -> &FeatureLayout {
+        &self.layout
+    }
+
+    pub fn model(&self) -> &BanditModel {
+        &self.model
+    }
+
+    pub fn snapshot(&self) -> BanditSnapshot {
+        BanditSnapshot {
+            model: self.model.clone(),
+            layout_fingerprint: Some(self.layout.fingerprint()),
+            layout: Some(self.layout.clone()),
+        }
+    }
+
+    /// 加载快照；布局指纹不一致（或缺失）时使用 `migration` 迁移
+    pub fn set_model(
+        &mut self,
+        snapshot: BanditSnapshot,
+        migration: &dyn LayoutMigration,
+    ) -> Result<LayoutCheck, LayoutError> {
+        let target = self.layout.fingerprint();
+        let matches = snapshot.layout_fingerprint.as_deref() == Some(target.as_str());
+
+        if matches {
+            let d = self.layout.dimension();
+            if snapshot.model.d as usize != d {
+                return Err(LayoutError::DimensionMismatch {
+                    expected: d,
+                    actual: snapshot.model.d as usize,
+                });
+            }
+            self.model = snapshot.model;
+            return Ok(LayoutCheck {
+                migrated: false,
+                strategy: "none".to_string(),
+                from_fingerprint: Some(target.clone()),
+                to_fingerprint: target,
+            });
+        }
+
+        self.model = migration.migrate(&snapshot, &self.layout)?;
+        Ok(LayoutCheck {
+            migrated: true,
+            strategy: migration.name().to_string(),
+            from_fingerprint: snapshot.layout_fingerprint,
+            to_fingerprint: target,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trained(layout: &FeatureLayout) -> BanditSnapshot {
+        let mut bound = LayoutBoundModel::new(layout.clone(), 1.0, 0.5);
+        let d = layout.dimension();
+        let records: Vec<ReplayRecord> = (0..5)
+            .map(|i| ReplayRecord {
+                features: (0..d).map(|j| ((i + j) % 3) as f64 * 0.5).collect(),
+                reward: if i % 2 == 0 { 1.0 } else { 0.0 },
+            })
+            .collect();
+        let snapshot = bound.snapshot();
+        bound.model = ReplayMigration { records }
+            .migrate(&snapshot, layout)
+            .unwrap();
+        bound.snapshot()
+    }
+
+    #[test]
+    fn test_fingerprint_depends_on_order_and_version() {
+        let a = FeatureLayout::new(1, &["attention", "fatigue"]);
+        let b = FeatureLayout::new(1, &["fatigue", "attention"]);
+        let c = FeatureLayout::new(2, &["attention", "fatigue"]);
+        assert_eq!(a.fingerprint(), a.clone().fingerprint());
+        assert_ne!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), c.fingerprint());
+        assert_eq!(a.fingerprint().len(), 16);
+    }
+
+    #[test]
+    fn test_set_model_same_layout_keeps_parameters() {
+        let layout = FeatureLayout::new(1, &["a", "b", "c"]);
+        let snapshot = trained(&layout);
+        let mut bound = LayoutBoundModel::new(layout, 1.0, 0.5);
+        let check = bound.set_model(snapshot.clone(), &ResetMigration).unwrap();
+        assert!(!check.migrated);
+        assert_eq!(bound.model().b, snapshot.model.b);
+    }
+
+    #[test]
+    fn test_set_model_reset_on_layout_change() {
+        let old = FeatureLayout::new(1, &["a", "b", "c"]);
+        let new = FeatureLayout::new(2, &["a", "b", "c", "d"]);
+        let mut bound = LayoutBoundModel::new(new, 1.0, 0.5);
+        let check = bound.set_model(trained(&old), &ResetMigration).unwrap();
+        assert!(check.migrated);
+        assert_eq!(check.strategy, "reset");
+        assert_eq!(bound.model().d, 4);
+        assert!(bound.model().b.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_pad_with_prior_keeps_shared_features() {
+        let old = FeatureLayout::new(1, &["a", "b", "c"]);
+        let new = FeatureLayout::new(2, &["c", "new", "a"]);
+        let snapshot = trained(&old);
+        let mut bound = LayoutBoundModel::new(new, 1.0, 0.5);
+        bound
+            .set_model(snapshot.clone(), &PadWithPriorMigration)
+            .unwrap();
+
+        let m = bound.model();
+        assert_eq!(m.b, vec![snapshot.model.b[2], 0.0, snapshot.model.b[0]]);
+        // A[c][a] 在新布局中位于 (0, 2)
+        assert_eq!(m.a_matrix[2], snapshot.model.a_matrix[2 * 3]);
+        // 新特征只有先验
+        assert_eq!(m.a_matrix[3 + 1], 1.0);
+        assert_eq!(m.a_matrix[1], 0.0);
+    }
+
+    #[test]
+    fn test_pad_with_prior_requires_source_layout() {
+        let old = FeatureLayout::new(1, &["a", "b"]);
+        let mut snapshot = trained(&old);
+        snapshot.layout = None;
+        snapshot.layout_fingerprint = None;
+        let mut bound = LayoutBoundModel::new(FeatureLayout::new(1, &["a", "b"]), 1.0, 0.5);
+        assert_eq!(
+            bound.set_model(snapshot, &PadWithPriorMigration),
+            Err(LayoutError::MissingSourceLayout)
+        );
+    }
+
+    #[test]
+    fn test_replay_rejects_wrong_dimension() {
+        let layout = FeatureLayout::new(1, &["a", "b"]);
+        let snapshot = trained(&layout);
+        let replay = ReplayMigration {
+            records: vec![ReplayRecord {
+                features: vec![1.0],
+                reward: 1.0,
+            }],
+        };
+        assert_eq!(
+            replay.migrate(&snapshot, &layout).unwrap_err(),
+            LayoutError::InvalidReplayRecord { index: 0 }
+        );
+    }
+}
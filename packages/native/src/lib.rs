@@ -1,10 +1,172 @@
 #![deny(clippy::all)]
+// 关闭 `std` 特性时只编译核心数学模块（matrix、sanitize、mastery 的 ACT-R 计算及其
+// 依赖的 types、events），可在 no_std + alloc 环境（如手表端）中使用
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("关闭 `std` 特性时需要启用 `libm` 特性以提供浮点数学函数");
+
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod cancel;
+#[cfg(feature = "std")]
 pub mod causal;
+#[cfg(feature = "std")]
+pub mod cloze;
+#[cfg(feature = "std")]
+pub mod compute;
+#[cfg(feature = "std")]
+pub mod curve;
+pub mod events;
+#[cfg(feature = "std")]
+pub mod experiments;
+#[cfg(feature = "std")]
+pub mod exploration;
+#[cfg(feature = "std")]
+pub mod flags;
+mod float;
+#[cfg(feature = "std")]
+pub mod footprint;
+#[cfg(feature = "std")]
+pub mod frequency;
+#[cfg(feature = "std")]
+pub mod hash;
+#[cfg(feature = "std")]
+pub mod hint_policy;
+#[cfg(feature = "std")]
+pub mod language;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod linucb;
+pub mod mastery;
 pub mod matrix;
+#[cfg(feature = "std")]
+pub mod objective;
+#[cfg(feature = "std")]
+pub mod placement;
+#[cfg(feature = "std")]
+pub mod policy;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod rescue;
+#[cfg(feature = "std")]
+pub mod rng;
 pub mod sanitize;
+#[cfg(feature = "std")]
+pub mod sim;
+#[cfg(feature = "std")]
+pub mod snapshot_diff;
+#[cfg(feature = "std")]
+pub mod tuning;
 pub mod types;
+#[cfg(feature = "std")]
+pub mod vclock;
 
+#[cfg(feature = "std")]
+pub use batch::{
+    compose_adaptive_batch, next_batch_size, AdaptiveBatch, BatchControllerConfig,
+    BatchControllerState, BatchSignals, BatchSizeDecision,
+};
+#[cfg(feature = "std")]
+pub use cancel::{
+    review_intervals_batch, CancellableBatch, CancellationToken, IntervalInput, ReviewInterval,
+};
+#[cfg(feature = "std")]
 pub use causal::estimator::CausalInferenceNative;
-pub use causal::{CausalEstimate, CausalInferenceConfig, CausalObservation, PropensityDiagnostics};
+#[cfg(feature = "std")]
+pub use causal::{
+    BootstrapMode, CausalEstimate, CausalInferenceConfig, CausalObservation, PropensityDiagnostics,
+};
+#[cfg(feature = "std")]
+pub use cloze::{
+    match_blank, score_cloze, BlankMatch, BlankScore, ClozeBlank, ClozeScore, ClozeScoringConfig,
+};
+#[cfg(feature = "std")]
+pub use compute::{get_compute_budget, set_compute_mode, ComputeBudget, ComputeMode};
+#[cfg(feature = "std")]
+pub use curve::{
+    cumulative_points, fit_learning_curve, CurveEstimate, CurveForecast, CurveModel, CurvePoint,
+    LearningCurve, LearningCurveConfig,
+};
+pub use events::{event_counts, AlgoEvent, EventCount, Severity};
+#[cfg(feature = "std")]
+pub use experiments::{
+    assignment_hash, select_variant_index, ExperimentDefinition, ExperimentVariant,
+};
+#[cfg(feature = "std")]
+pub use exploration::{exploration_budget, ExplorationBudget, ExplorationBudgetConfig};
+#[cfg(feature = "std")]
+pub use flags::{FlagDefinition, FlagSet};
+#[cfg(feature = "std")]
+pub use footprint::MemoryFootprint;
+#[cfg(feature = "std")]
+pub use frequency::{
+    bootstrap_priors, parse_frequency_csv, BootstrapConfig, BootstrapTable, CoverageReport,
+    FrequencyList, FrequencyPrior,
+};
+#[cfg(feature = "std")]
+pub use hint_policy::{
+    hint_reward, select_hint_level, update_hint_policy, HintChoice, HintContext, HintPolicy,
+    HintPolicyConfig,
+};
+#[cfg(feature = "std")]
+pub use language::{DifficultyWeights, LanguagePair, LanguageParams, ParamRegistry};
+#[cfg(feature = "std")]
+pub use layout::{
+    BanditSnapshot, FeatureLayout, LayoutBoundModel, LayoutCheck, LayoutError, LayoutMigration,
+    PadWithPriorMigration, ReplayMigration, ResetMigration,
+};
+#[cfg(feature = "std")]
+pub use linucb::discount::discount_for_half_life;
+#[cfg(feature = "std")]
+pub use linucb::features::FeatureBuilder;
+#[cfg(feature = "std")]
+pub use linucb::persist::{validate_model, MODEL_FORMAT_VERSION};
+#[cfg(feature = "std")]
+pub use linucb::topk::{CandidateIndex, TopKSelection};
+#[cfg(feature = "std")]
+pub use linucb::{FeatureVector, LinUCB, LinUCBError, UcbScore};
+pub use mastery::{mastery_band, MasteryBand, MasteryBandConfig, ReviewEvent};
+pub use matrix::sparse::SparseVector;
+#[cfg(feature = "std")]
+pub use objective::{score_session_items, ObjectiveScore, SessionObjective};
+#[cfg(feature = "std")]
+pub use placement::{
+    select_calibration_set, BandMastery, PlacementConfig, PlacementItem, PlacementResponse,
+    PlacementResult, PlacementSession, PlacementStep,
+};
+#[cfg(feature = "std")]
+pub use policy::{
+    compose_session, compose_session_with_objective, interference_cost, validate_policy_rules,
+    ComposedSession, PolicyRule, PolicyRuleSet, PolicyValidation, PolicyViolation, SessionItem,
+};
+#[cfg(feature = "std")]
+pub use progress::{mastery_progress, MasteryProgress, ProgressConfig};
+#[cfg(feature = "std")]
+pub use rescue::{
+    plan_rescue, rank_by_priority, RampDay, RescueCandidate, RescueConfig, RescueItem, RescuePlan,
+};
+#[cfg(feature = "std")]
+pub use rng::{RngFactory, RngStream};
+#[cfg(feature = "std")]
+pub use sim::{
+    CohortSpec, HorizonProjection, InterferenceSpec, LearnerProfile, ProjectionBand,
+    SchedulerConfig, SimComparison, SimMetrics,
+};
+#[cfg(feature = "std")]
+pub use snapshot_diff::{
+    diff_actr, diff_linucb, diff_thompson, ActrSnapshot, ArmChange, ParamDelta, SnapshotDiff,
+};
+#[cfg(feature = "std")]
+pub use tuning::{
+    tune_hyperparameters, uniform_baseline_summary, BetaPrior, CandidateScore,
+    CounterfactualSummary, LoggedDecision, TuningCandidate, TuningConfig, TuningGrid, TuningResult,
+};
 pub use types::*;
+#[cfg(feature = "std")]
+pub use vclock::{Causality, VectorClock};
@@ -0,0 +1,236 @@
+//! 折扣 LinUCB（D-LinUCB）
+//!
+//! 学习者行为并不平稳（假期、考前冲刺），半年前的观测不应与昨天的同权。
+//! 折扣模式下每次更新前先对历史观测按 γ 衰减：
+//!
+//! ```text
+//! A ← γ·(A − λI) + λI,  b ← γ·b
+//! ```
+//!
+//! 正则项 λI 不衰减，保证 A 始终正定；等效窗口约为 1 / (1 − γ) 次观测。
+//! √γ·L 恰好是 γA 的 Cholesky 因子，再以 √((1 − γ)λ)·eᵢ 做 d 次秩一更新
+//! 补回正则项（第 i 次只触及第 i 列之后），无需重新分解 A；更新失败时才完整
+//! 重算。衰减会改变 A，分摊重算的回放不再成立，折扣模式到期时直接重算。
+//! 宿主也可以按真实时间调用 `discount_by`，例如长时间未学习后按天数衰减。
+//!
+//! γ 属于运行配置，不随 `BanditModel` 持久化。
+
+use super::{LinUCB, LinUCBError};
+use crate::events::{self, AlgoEvent};
+use crate::matrix::cholesky_rank1_update_from;
+use crate::types::MIN_RANK1_DIAG;
+
+/// 半衰期为 `half_life` 次观测的折扣因子
+pub fn discount_for_half_life(half_life: f64) -> Option<f64> {
+    if !(half_life.is_finite() && half_life > 0.0) {
+        return None;
+    }
+    Some(crate::float::powf(0.5, 1.0 / half_life))
+}
+
+impl LinUCB {
+    /// 设置每次更新的折扣因子 γ ∈ (0, 1]；`None` 或 1 关闭折扣
+    pub fn set_discount(&mut self, gamma: Option<f64>) -> Result<(), LinUCBError> {
+        match gamma {
+            None => self.discount = None,
+            Some(g) if g.is_finite() && g > 0.0 && g <= 1.0 => {
+                self.discount = (g < 1.0).then_some(g);
+                // 折扣模式无法回放分摊重算；进行中的一轮立即完成，不跳过这个周期
+                if self.refresh.take().is_some() {
+                    self.recompute_now();
+                }
+            }
+            Some(g) => {
+                return Err(LinUCBError::InvalidModel(format!(
+                    "折扣因子 {g} 必须在 (0, 1] 内"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn discount(&self) -> Option<f64> {
+        self.discount
+    }
+
+    /// 将历史观测按权重 `weight` ∈ [0, 1] 衰减并更新 L；越界值会被截断
+    pub fn discount_by(&mut self, weight: f64) {
+        let weight = if weight.is_finite() {
+            weight.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        if weight >= 1.0 {
+            return;
+        }
+        let d = self.dimension();
+        let model = &mut self.model;
+        let lambda = model.lambda;
+        for i in 0..d {
+            for j in 0..d {
+                let prior = if i == j { lambda } else { 0.0 };
+                let a = &mut model.a_matrix[i * d + j];
+                *a = weight * (*a - prior) + prior;
+            }
+        }
+        for v in model.b.iter_mut() {
+            *v *= weight;
+        }
+
+        let scale = crate::float::sqrt(weight);
+        for v in model.l_matrix.iter_mut() {
+            *v *= scale;
+        }
+        let boost = crate::float::sqrt((1.0 - weight) * lambda);
+        let mut e = vec![0.0; d];
+        let restored = (0..d).all(|i| {
+            // 上一次更新改写了 e[i - 1..]，前缀之外重新清零
+            e[i.saturating_sub(1)..].fill(0.0);
+            e[i] = boost;
+            cholesky_rank1_update_from(&mut model.l_matrix, &mut e, d, MIN_RANK1_DIAG, i)
+        });
+        if !restored {
+            events::record(AlgoEvent::EmergencyRecompute, || {
+                format!("discount update failed (weight = {weight}, d = {d})")
+            });
+        }
+        // 进行中的分摊重算基于衰减前的 A，不能继续；和 `set_refresh_budget(None)`
+        // 一样立即完成这一轮，避免跳过周期性的漂移校正
+        if self.refresh.take().is_some() || !restored {
+            self.recompute_now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::prior_model;
+    use crate::linucb::FeatureVector;
+    use crate::matrix::cholesky_decompose;
+    use crate::matrix::sparse::SparseVector;
+
+    fn shifted_theta(discount: Option<f64>) -> Vec<f64> {
+        let mut model = LinUCB::from_model(prior_model(2, 1.0, 0.1));
+        model.set_discount(discount).unwrap();
+        let x = FeatureVector::from(vec![1.0, 0.0]);
+        for step in 0..400 {
+            let reward = if step < 200 { 1.0 } else { 0.0 };
+            model.update_with_feature_vector(&x, reward).unwrap();
+        }
+        model.theta()
+    }
+
+    #[test]
+    fn test_discount_tracks_reward_shift() {
+        let stationary = shifted_theta(None);
+        let discounted = shifted_theta(discount_for_half_life(20.0));
+        assert!((stationary[0] - 0.5).abs() < 0.01);
+        assert!(discounted[0] < 0.01);
+    }
+
+    #[test]
+    fn test_discount_keeps_factor_consistent() {
+        let d = 6;
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        model.set_discount(Some(0.9)).unwrap();
+        for step in 0..50 {
+            let mut x = vec![0.0; d];
+            x[step % d] = 1.0;
+            x[(step + 2) % d] = 0.5;
+            let sparse = SparseVector::from_dense(&x);
+            model
+                .update_with_feature_vector(&FeatureVector::from(sparse), 0.3)
+                .unwrap();
+        }
+        model.discount_by(0.25);
+        let diagnostics = model.diagnose();
+        assert!(diagnostics.is_healthy);
+        for i in 0..d {
+            assert!(model.model().a_matrix[i * d + i] >= 1.0 - 1e-12);
+        }
+        assert!(!model.refresh_pending());
+    }
+
+    #[test]
+    fn test_discount_factor_matches_full_decomposition() {
+        let d = 5;
+        let mut model = LinUCB::from_model(prior_model(d, 2.0, 0.3));
+        for step in 0..30 {
+            let x: Vec<f64> = (0..d)
+                .map(|i| ((step * 7 + i * 3) % 5) as f64 / 4.0)
+                .collect();
+            model
+                .update_with_feature_vector(&FeatureVector::from(x), 0.5)
+                .unwrap();
+        }
+        for weight in [0.95, 0.5, 0.01] {
+            model.discount_by(weight);
+            let a = &model.model().a_matrix;
+            let full = cholesky_decompose(a, d, model.model().lambda);
+            for (got, want) in model.model().l_matrix.iter().zip(&full) {
+                assert!(
+                    (got - want).abs() < 1e-9,
+                    "weight {weight}: {got} vs {want}"
+                );
+            }
+        }
+
+        // 权重为 0 时 √w·L 为零矩阵，秩一补回失败，退回完整重算
+        model.discount_by(0.0);
+        assert!(model.diagnose().is_healthy);
+        for i in 0..d {
+            assert!((model.model().l_matrix[i * d + i] - 2.0f64.sqrt()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_discount_mid_refresh_completes_the_cycle() {
+        use crate::types::CHOLESKY_RECOMPUTE_INTERVAL;
+
+        let d = 8;
+        let train = |model: &mut LinUCB| {
+            // 每次只推进一行，周期点后两次更新时这一轮仍在进行
+            model.set_refresh_budget(Some(1));
+            for step in 0..CHOLESKY_RECOMPUTE_INTERVAL as usize + 2 {
+                let x: Vec<f64> = (0..d).map(|i| ((step * 3 + i) % 5) as f64 * 0.1).collect();
+                model
+                    .update_with_feature_vector(&FeatureVector::from(x), 0.4)
+                    .unwrap();
+            }
+            assert!(model.refresh_pending());
+        };
+        let assert_exact = |model: &LinUCB| {
+            assert!(!model.refresh_pending());
+            let m = model.model();
+            let full = cholesky_decompose(&m.a_matrix, d, m.lambda);
+            for (got, want) in m.l_matrix.iter().zip(&full) {
+                assert!((got - want).abs() < 1e-9, "{got} vs {want}");
+            }
+        };
+
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        train(&mut model);
+        model.discount_by(0.8);
+        assert_exact(&model);
+
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        train(&mut model);
+        model.set_discount(Some(0.9)).unwrap();
+        assert_exact(&model);
+    }
+
+    #[test]
+    fn test_discount_validation() {
+        let mut model = LinUCB::from_model(prior_model(2, 1.0, 0.1));
+        assert!(model.set_discount(Some(0.0)).is_err());
+        assert!(model.set_discount(Some(1.5)).is_err());
+        assert!(model.set_discount(Some(f64::NAN)).is_err());
+        model.set_discount(Some(1.0)).unwrap();
+        assert_eq!(model.discount(), None);
+        model.set_discount(Some(0.95)).unwrap();
+        assert_eq!(model.discount(), Some(0.95));
+        assert_eq!(discount_for_half_life(0.0), None);
+        assert!((discount_for_half_life(1.0).unwrap() - 0.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,196 @@
+//! 可插拔的特征构造
+//!
+//! 默认模型使用 `FEATURE_DIMENSION` 维的 AMAS 特征。下游应用实现
+//! `FeatureBuilder` 即可定义自己的上下文与特征向量，维度在运行时由构造器给出，
+//! 模型缓冲区与清理路径都按该维度处理。
+
+use super::{FeatureVector, LinUCB, LinUCBError, UcbScore};
+use crate::layout::{prior_model, FeatureLayout};
+
+/// 从应用上下文构造特征向量
+pub trait FeatureBuilder {
+    type Context: ?Sized;
+
+    /// 特征维度，构造出的向量必须与之一致
+    fn dimension(&self) -> usize;
+
+    fn build(&self, context: &Self::Context) -> FeatureVector;
+
+    /// 特征布局，用于快照指纹；未声明时为空
+    fn layout(&self) -> Option<FeatureLayout> {
+        None
+    }
+}
+
+impl LinUCB {
+    /// 以 d 维先验（A = λI，b = 0）创建模型
+    pub fn with_dimension(d: usize, alpha: f64, lambda: f64) -> Result<Self, LinUCBError> {
+        if d == 0 || u32::try_from(d).is_err() {
+            return Err(LinUCBError::InvalidModel(format!("维度 {d} 无效")));
+        }
+        if !(lambda.is_finite() && lambda > 0.0) {
+            return Err(LinUCBError::InvalidModel("lambda 必须为正数".to_string()));
+        }
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(LinUCBError::InvalidModel("alpha 必须为非负数".to_string()));
+        }
+        Ok(Self::from_model(prior_model(d, lambda, alpha)))
+    }
+
+    /// 按构造器声明的维度创建模型
+    pub fn for_builder<B: FeatureBuilder>(
+        builder: &B,
+        alpha: f64,
+        lambda: f64,
+    ) -> Result<Self, LinUCBError> {
+        Self::with_dimension(builder.dimension(), alpha, lambda)
+    }
+
+    /// 构造特征并校验构造器、特征与模型三者维度一致
+    fn build_checked<B: FeatureBuilder>(
+        &self,
+        builder: &B,
+        context: &B::Context,
+    ) -> Result<FeatureVector, LinUCBError> {
+        let expected = self.dimension();
+        if builder.dimension() != expected {
+            return Err(LinUCBError::DimensionMismatch {
+                expected,
+                actual: builder.dimension(),
+            });
+        }
+        let x = builder.build(context);
+        self.check_dimension(&x)?;
+        Ok(x)
+    }
+
+    /// 以构造器生成的特征更新模型
+    pub fn update_with<B: FeatureBuilder>(
+        &mut self,
+        builder: &B,
+        context: &B::Context,
+        reward: f64,
+    ) -> Result<(), LinUCBError> {
+        let x = self.build_checked(builder, context)?;
+        self.update_with_feature_vector(&x, reward)
+    }
+
+    /// 在若干上下文中选择 UCB 最大者；候选为空时返回 `None`
+    pub fn select_with<'c, B, I>(
+        &self,
+        builder: &B,
+        contexts: I,
+    ) -> Result<Option<(usize, UcbScore)>, LinUCBError>
+    where
+        B: FeatureBuilder,
+        B::Context: 'c,
+        I: IntoIterator<Item = &'c B::Context>,
+    {
+        let candidates = contexts
+            .into_iter()
+            .map(|context| self.build_checked(builder, context))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.select(&candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4 维：偏置、难度、难度²、是否新词
+    struct DifficultyFeatures;
+
+    struct Card {
+        difficulty: f64,
+        new_word: bool,
+    }
+
+    impl FeatureBuilder for DifficultyFeatures {
+        type Context = Card;
+
+        fn dimension(&self) -> usize {
+            4
+        }
+
+        fn build(&self, card: &Card) -> FeatureVector {
+            FeatureVector::Dense(vec![
+                1.0,
+                card.difficulty,
+                card.difficulty * card.difficulty,
+                if card.new_word { 1.0 } else { 0.0 },
+            ])
+        }
+
+        fn layout(&self) -> Option<FeatureLayout> {
+            Some(FeatureLayout::new(
+                1,
+                &["bias", "difficulty", "difficulty_sq", "new_word"],
+            ))
+        }
+    }
+
+    #[test]
+    fn test_with_dimension_builds_runtime_sized_buffers() {
+        let model = LinUCB::with_dimension(5, 0.3, 2.0).unwrap();
+        assert_eq!(model.dimension(), 5);
+        assert_eq!(model.model().a_matrix.len(), 25);
+        assert_eq!(model.model().b.len(), 5);
+        assert_eq!(model.model().l_matrix.len(), 25);
+        assert!(model.diagnose().is_healthy);
+        assert!(matches!(
+            LinUCB::with_dimension(0, 0.3, 1.0),
+            Err(LinUCBError::InvalidModel(_))
+        ));
+        for (alpha, lambda) in [(0.3, 0.0), (0.3, -1.0), (0.3, f64::NAN), (-0.1, 1.0)] {
+            assert!(matches!(
+                LinUCB::with_dimension(5, alpha, lambda),
+                Err(LinUCBError::InvalidModel(_))
+            ));
+        }
+        assert!(LinUCB::with_dimension(5, 0.0, 1.0).is_ok());
+    }
+
+    #[test]
+    fn test_custom_builder_learns_and_selects() {
+        let builder = DifficultyFeatures;
+        assert_eq!(builder.layout().unwrap().dimension(), builder.dimension());
+        let mut model = LinUCB::for_builder(&builder, 0.1, 1.0).unwrap();
+        let easy = Card {
+            difficulty: 0.2,
+            new_word: false,
+        };
+        let hard = Card {
+            difficulty: 0.9,
+            new_word: true,
+        };
+        for _ in 0..50 {
+            model.update_with(&builder, &easy, 1.0).unwrap();
+            model.update_with(&builder, &hard, 0.0).unwrap();
+        }
+        let (best, _) = model
+            .select_with(&builder, [&hard, &easy])
+            .unwrap()
+            .unwrap();
+        assert_eq!(best, 1);
+        assert!(model.select_with(&builder, []).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_builder_dimension_must_match_model() {
+        let builder = DifficultyFeatures;
+        let mut model = LinUCB::with_dimension(3, 0.1, 1.0).unwrap();
+        let card = Card {
+            difficulty: 0.5,
+            new_word: false,
+        };
+        assert_eq!(
+            model.update_with(&builder, &card, 1.0),
+            Err(LinUCBError::DimensionMismatch {
+                expected: 3,
+                actual: 4
+            })
+        );
+        assert_eq!(model.model().update_count, 0);
+    }
+}
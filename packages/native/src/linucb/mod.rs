@@ -0,0 +1,569 @@
+//! LinUCB 上下文老虎机
+//!
+//! 模型参数沿用 `BanditModel`（A、b 与 A 的 Cholesky 因子 L）。特征可以是稠密
+//! 向量，也可以是稀疏向量（one-hot 展开的高维上下文）；稀疏向量的非零占比
+//! 超过 `SPARSE_DENSITY_THRESHOLD` 时按稠密路径处理，此时稀疏下标的额外开销
+//! 已不划算。
+//!
+//! 周期性的完整 Cholesky 重算默认分摊到后续若干次更新中完成，见 `refresh`。
+//! 模型的 JSON 与二进制持久化见 `persist`；自定义维度与特征构造见 `features`；
+//! 让旧观测随时间衰减的折扣模式见 `discount`。
+
+pub mod discount;
+pub mod features;
+pub mod persist;
+pub mod refresh;
+#[cfg(feature = "soak")]
+pub mod soak;
+pub mod topk;
+
+use std::fmt;
+
+use crate::events::{self, AlgoEvent};
+use crate::layout::prior_model;
+use crate::matrix::sparse::{
+    sparse_cholesky_rank1_update, sparse_dot, sparse_quadratic_form, sparse_rank1_update_matrix,
+    sparse_vec_add_scaled, SparseVector,
+};
+use crate::matrix::{
+    cholesky_decompose, cholesky_rank1_update, compute_quadratic_form, dot_product,
+    rank1_update_matrix, solve_cholesky, vec_add_scaled,
+};
+use crate::sanitize::{diagnose_model, factor_unhealthy, sanitize_feature_vector};
+use crate::types::{
+    BanditModel, DiagnosticResult, CHOLESKY_RECOMPUTE_INTERVAL, FEATURE_DIMENSION, MAX_FEATURE_ABS,
+    MIN_RANK1_DIAG,
+};
+
+use refresh::{CholeskyRefresh, RefreshStep, DEFAULT_REFRESH_OPS};
+
+/// 非零占比超过该值时稀疏向量按稠密处理
+pub const SPARSE_DENSITY_THRESHOLD: f64 = 0.25;
+
+/// 特征向量：稠密或稀疏
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureVector {
+    Dense(Vec<f64>),
+    Sparse(SparseVector),
+}
+
+impl FeatureVector {
+    /// 按非零占比自动选择表示
+    pub fn from_dense_auto(x: Vec<f64>) -> Self {
+        let sparse = SparseVector::from_dense(&x);
+        if sparse.density() <= SPARSE_DENSITY_THRESHOLD {
+            FeatureVector::Sparse(sparse)
+        } else {
+            FeatureVector::Dense(x)
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        match self {
+            FeatureVector::Dense(x) => x.len(),
+            FeatureVector::Sparse(x) => x.dim(),
+        }
+    }
+
+    /// 清理数值并在稀疏表示不划算时转为稠密
+    fn prepared(&self) -> FeatureVector {
+        match self {
+            FeatureVector::Dense(x) => {
+                let mut x = x.clone();
+                sanitize_feature_vector(&mut x);
+                FeatureVector::Dense(x)
+            }
+            FeatureVector::Sparse(x) if x.density() > SPARSE_DENSITY_THRESHOLD => {
+                let mut x = x.to_dense();
+                sanitize_feature_vector(&mut x);
+                FeatureVector::Dense(x)
+            }
+            FeatureVector::Sparse(x) => {
+                let mut x = x.clone();
+                let fixed = x
+                    .values()
+                    .iter()
+                    .filter(|v| !v.is_finite() || v.abs() > MAX_FEATURE_ABS)
+                    .count();
+                if fixed > 0 {
+                    events::record(AlgoEvent::FeatureSanitized, || {
+                        format!("{fixed}/{} sparse feature values fixed", x.nnz())
+                    });
+                }
+                x.map_values(|v| {
+                    if v.is_finite() {
+                        v.clamp(-MAX_FEATURE_ABS, MAX_FEATURE_ABS)
+                    } else {
+                        0.0
+                    }
+                });
+                FeatureVector::Sparse(x)
+            }
+        }
+    }
+}
+
+impl From<Vec<f64>> for FeatureVector {
+    fn from(x: Vec<f64>) -> Self {
+        FeatureVector::Dense(x)
+    }
+}
+
+impl From<SparseVector> for FeatureVector {
+    fn from(x: SparseVector) -> Self {
+        FeatureVector::Sparse(x)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinUCBError {
+    /// 特征维度与模型维度不一致
+    DimensionMismatch { expected: usize, actual: usize },
+    /// 持久化格式版本不受支持
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// 持久化数据无法解析
+    Malformed(String),
+    /// 模型结构或数值不合法
+    InvalidModel(String),
+}
+
+impl fmt::Display for LinUCBError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinUCBError::DimensionMismatch { expected, actual } => {
+                write!(f, "特征维度 {actual} 与模型维度 {expected} 不一致")
+            }
+            LinUCBError::UnsupportedVersion { found, supported } => {
+                write!(f, "模型格式版本 {found} 不受支持（当前为 {supported}）")
+            }
+            LinUCBError::Malformed(msg) => write!(f, "模型数据无法解析: {msg}"),
+            LinUCBError::InvalidModel(msg) => write!(f, "模型不合法: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for LinUCBError {}
+
+/// 单个候选的 UCB 分解
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UcbScore {
+    pub exploitation: f64,
+    pub exploration: f64,
+    pub score: f64,
+}
+
+pub struct LinUCB {
+    model: BanditModel,
+    refresh: Option<CholeskyRefresh>,
+    refresh_ops: Option<usize>,
+    /// 下一次周期重算到期的更新计数
+    next_refresh: u32,
+    /// 折扣因子 γ；`None` 表示平稳模型
+    discount: Option<f64>,
+}
+
+impl LinUCB {
+    /// 默认的 `FEATURE_DIMENSION` 维模型；其他维度见 `with_dimension`
+    pub fn new(alpha: f64, lambda: f64) -> Self {
+        Self::from_model(prior_model(FEATURE_DIMENSION, lambda, alpha))
+    }
+
+    pub fn from_model(model: BanditModel) -> Self {
+        let count = model.update_count;
+        // 分摊重算的进度不随模型持久化。周期点之后 d 次更新内保存的模型，
+        // 那一轮可能还没完成，恢复时直接补一次完整重算
+        let mid_refresh = count >= CHOLESKY_RECOMPUTE_INTERVAL
+            && ((count % CHOLESKY_RECOMPUTE_INTERVAL) as usize) < model.d as usize;
+        let mut linucb = Self {
+            model,
+            refresh: None,
+            refresh_ops: Some(DEFAULT_REFRESH_OPS),
+            next_refresh: next_refresh_after(count),
+            discount: None,
+        };
+        if mid_refresh {
+            linucb.recompute_now();
+        }
+        linucb
+    }
+
+    /// 周期重算每次更新分摊的乘加预算；`None` 表示到期立即完整重算
+    pub fn set_refresh_budget(&mut self, ops: Option<usize>) {
+        self.refresh_ops = ops;
+        if ops.is_none() && self.refresh.take().is_some() {
+            // 进行中的一轮不能丢，否则这个周期就被跳过了
+            self.recompute_now();
+        }
+    }
+
+    /// 是否有进行中的分摊重算
+    pub fn refresh_pending(&self) -> bool {
+        self.refresh.is_some()
+    }
+
+    pub fn model(&self) -> &BanditModel {
+        &self.model
+    }
+
+    pub fn into_model(self) -> BanditModel {
+        self.model
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.model.d as usize
+    }
+
+    /// theta = A^{-1} b；一次选择中所有候选共用
+    pub fn theta(&self) -> Vec<f64> {
+        solve_cholesky(&self.model.l_matrix, &self.model.b, self.dimension())
+    }
+
+    fn check_dimension(&self, x: &FeatureVector) -> Result<(), LinUCBError> {
+        let expected = self.dimension();
+        if x.dim() != expected {
+            return Err(LinUCBError::DimensionMismatch {
+                expected,
+                actual: x.dim(),
+            });
+        }
+        Ok(())
+    }
+
+    /// 健康诊断，含 L·Lᵀ 相对 A 的漂移
+    pub fn diagnose(&self) -> DiagnosticResult {
+        diagnose_model(&self.model.a_matrix, &self.model.l_matrix, self.dimension())
+    }
+
+    /// 以一次观测更新 A、b 与 L
+    pub fn update_with_feature_vector(
+        &mut self,
+        x: &FeatureVector,
+        reward: f64,
+    ) -> Result<(), LinUCBError> {
+        self.check_dimension(x)?;
+        if let Some(gamma) = self.discount {
+            self.discount_by(gamma);
+        }
+        let d = self.dimension();
+        let model = &mut self.model;
+        let x = x.prepared();
+        let rank1_ok = match &x {
+            FeatureVector::Dense(x) => {
+                rank1_update_matrix(&mut model.a_matrix, x, d);
+                vec_add_scaled(&mut model.b, x, reward);
+                cholesky_rank1_update(&mut model.l_matrix, x, d, MIN_RANK1_DIAG)
+            }
+            FeatureVector::Sparse(x) => {
+                sparse_rank1_update_matrix(&mut model.a_matrix, x, d);
+                sparse_vec_add_scaled(&mut model.b, x, reward);
+                sparse_cholesky_rank1_update(&mut model.l_matrix, x, d, MIN_RANK1_DIAG)
+            }
+        };
+        model.update_count += 1;
+        if !rank1_ok || factor_unhealthy(&model.l_matrix, d) {
+            let count = model.update_count;
+            events::record(AlgoEvent::EmergencyRecompute, || {
+                let reason = if rank1_ok {
+                    "unhealthy factor"
+                } else {
+                    "rank-1 update failed"
+                };
+                format!("{reason} at update {count} (d = {d})")
+            });
+            self.refresh = None;
+            self.recompute_now();
+            self.next_refresh = next_refresh_after(count);
+            return Ok(());
+        }
+
+        // 用 >= 而非整除判断：计数跳过周期点（如衰减模式切回）时也不会漏掉
+        let periodic = model.update_count >= self.next_refresh;
+        if self.discount.is_some() {
+            // 衰减改变了 A，分摊重算无法回放，到期时直接重算
+            if periodic {
+                events::record(AlgoEvent::PeriodicRecompute, || {
+                    format!("discounted, d = {d}")
+                });
+                self.next_refresh = next_refresh_after(model.update_count);
+                self.recompute_now();
+            }
+            return Ok(());
+        }
+        let Some(ops) = self.refresh_ops else {
+            if periodic {
+                events::record(AlgoEvent::PeriodicRecompute, || {
+                    format!("immediate, d = {d}")
+                });
+                self.next_refresh = next_refresh_after(model.update_count);
+                self.recompute_now();
+            }
+            return Ok(());
+        };
+        match self.refresh.as_mut() {
+            Some(refresh) => refresh.record(x),
+            None if periodic => {
+                self.next_refresh = next_refresh_after(model.update_count);
+                self.refresh = Some(CholeskyRefresh::start(&model.a_matrix, d, model.lambda));
+            }
+            None => return Ok(()),
+        }
+        let step = self.refresh.as_mut().map(|r| r.advance(ops));
+        match step {
+            Some(RefreshStep::Ready(l)) => {
+                events::record(AlgoEvent::PeriodicRecompute, || {
+                    format!("amortized, d = {d}")
+                });
+                self.model.l_matrix = l;
+                self.refresh = None;
+            }
+            Some(RefreshStep::Failed) => {
+                events::record(AlgoEvent::RefreshReplayFailed, || format!("d = {d}"));
+                self.refresh = None;
+                self.recompute_now();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn recompute_now(&mut self) {
+        let d = self.dimension();
+        let model = &mut self.model;
+        model.l_matrix = cholesky_decompose(&model.a_matrix, d, model.lambda);
+    }
+
+    /// theta^T x + alpha * sqrt(x^T A^{-1} x)
+    pub fn ucb(&self, theta: &[f64], x: &FeatureVector) -> Result<UcbScore, LinUCBError> {
+        self.check_dimension(x)?;
+        Ok(self.ucb_prepared(theta, &x.prepared()))
+    }
+
+    /// 同 `ucb`，特征已清理且维度已校验
+    fn ucb_prepared(&self, theta: &[f64], x: &FeatureVector) -> UcbScore {
+        let d = self.dimension();
+        let l = &self.model.l_matrix;
+        let (exploitation, quadratic) = match x {
+            FeatureVector::Dense(x) => (dot_product(theta, x), compute_quadratic_form(l, x, d)),
+            FeatureVector::Sparse(x) => (sparse_dot(theta, x), sparse_quadratic_form(l, x, d)),
+        };
+        let exploration = self.model.alpha * quadratic.max(0.0).sqrt();
+        UcbScore {
+            exploitation,
+            exploration,
+            score: exploitation + exploration,
+        }
+    }
+
+    /// 选择 UCB 最大的候选；候选为空时返回 `None`
+    pub fn select(
+        &self,
+        candidates: &[FeatureVector],
+    ) -> Result<Option<(usize, UcbScore)>, LinUCBError> {
+        let theta = self.theta();
+        let mut best: Option<(usize, UcbScore)> = None;
+        for (index, x) in candidates.iter().enumerate() {
+            let score = self.ucb(&theta, x)?;
+            if best.is_none_or(|(_, b)| score.score > b.score) {
+                best = Some((index, score));
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// `count` 之后的第一个周期点
+fn next_refresh_after(count: u32) -> u32 {
+    (count / CHOLESKY_RECOMPUTE_INTERVAL + 1).saturating_mul(CHOLESKY_RECOMPUTE_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_hot(d: usize, hot: &[usize]) -> Vec<f64> {
+        let mut x = vec![0.0; d];
+        for &i in hot {
+            x[i] = 1.0;
+        }
+        x
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9, "{x} != {y}");
+        }
+    }
+
+    #[test]
+    fn test_sparse_and_dense_updates_agree() {
+        let d = 40;
+        let mut dense = LinUCB::from_model(prior_model(d, 1.0, 0.5));
+        let mut sparse = LinUCB::from_model(prior_model(d, 1.0, 0.5));
+        for step in 0..30 {
+            let x = one_hot(d, &[step % d, (step * 7 + 3) % d, 39]);
+            let reward = if step % 3 == 0 { 1.0 } else { 0.2 };
+            dense
+                .update_with_feature_vector(&FeatureVector::Dense(x.clone()), reward)
+                .unwrap();
+            sparse
+                .update_with_feature_vector(
+                    &FeatureVector::Sparse(SparseVector::from_dense(&x)),
+                    reward,
+                )
+                .unwrap();
+        }
+        assert_close(&dense.model().a_matrix, &sparse.model().a_matrix);
+        assert_close(&dense.model().b, &sparse.model().b);
+        assert_close(&dense.model().l_matrix, &sparse.model().l_matrix);
+
+        let probe = one_hot(d, &[5, 22]);
+        let theta = dense.theta();
+        let a = dense
+            .ucb(&theta, &FeatureVector::Dense(probe.clone()))
+            .unwrap();
+        let b = sparse
+            .ucb(&theta, &FeatureVector::from_dense_auto(probe))
+            .unwrap();
+        assert!((a.score - b.score).abs() < 1e-9);
+        assert!((a.exploration - b.exploration).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dense_fallback_for_high_density() {
+        let x = FeatureVector::Sparse(SparseVector::from_dense(&[1.0, 0.0, 2.0, 3.0]));
+        assert!(matches!(x.prepared(), FeatureVector::Dense(_)));
+        let auto = FeatureVector::from_dense_auto(one_hot(20, &[3]));
+        assert!(matches!(auto, FeatureVector::Sparse(_)));
+        let auto = FeatureVector::from_dense_auto(vec![1.0; 4]);
+        assert!(matches!(auto, FeatureVector::Dense(_)));
+    }
+
+    #[test]
+    fn test_sparse_values_are_sanitized() {
+        let d = 30;
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.5));
+        let x = SparseVector::new(d, vec![(2, f64::NAN), (4, 1e6)]).unwrap();
+        model
+            .update_with_feature_vector(&FeatureVector::Sparse(x), 1.0)
+            .unwrap();
+        let m = model.model();
+        assert_eq!(m.a_matrix[2 * d + 2], 1.0);
+        assert_eq!(
+            m.a_matrix[4 * d + 4],
+            1.0 + MAX_FEATURE_ABS * MAX_FEATURE_ABS
+        );
+        assert!(m.l_matrix.iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn test_select_prefers_rewarded_feature() {
+        let d = 30;
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.1));
+        for _ in 0..20 {
+            let good = FeatureVector::from_dense_auto(one_hot(d, &[1]));
+            let bad = FeatureVector::from_dense_auto(one_hot(d, &[2]));
+            model.update_with_feature_vector(&good, 1.0).unwrap();
+            model.update_with_feature_vector(&bad, 0.0).unwrap();
+        }
+        let candidates = vec![
+            FeatureVector::from_dense_auto(one_hot(d, &[2])),
+            FeatureVector::from_dense_auto(one_hot(d, &[1])),
+        ];
+        let (index, score) = model.select(&candidates).unwrap().unwrap();
+        assert_eq!(index, 1);
+        assert!(score.exploitation > 0.9);
+        assert_eq!(model.select(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_diagnose_reports_drift() {
+        let d = 10;
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        for step in 0..50 {
+            let x = FeatureVector::from_dense_auto(one_hot(d, &[step % d, 9]));
+            model.update_with_feature_vector(&x, 0.5).unwrap();
+        }
+        let diagnostic = model.diagnose();
+        assert!(diagnostic.is_healthy);
+        assert!(!diagnostic.has_drift, "{diagnostic:?}");
+
+        model.model.l_matrix[d + 1] *= 1.01;
+        assert!(model.diagnose().has_drift);
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        let mut model = LinUCB::new(0.3, 1.0);
+        let x = FeatureVector::Dense(vec![1.0; 3]);
+        assert_eq!(
+            model.update_with_feature_vector(&x, 1.0),
+            Err(LinUCBError::DimensionMismatch {
+                expected: FEATURE_DIMENSION,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_periodic_recompute_is_amortized() {
+        let d = 30;
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        model.set_refresh_budget(Some(200));
+        let interval = CHOLESKY_RECOMPUTE_INTERVAL as usize;
+        let mut pending_steps = 0;
+        for step in 0..interval + 40 {
+            let x = (0..d).map(|i| ((step * 3 + i) % 7) as f64 * 0.1).collect();
+            model
+                .update_with_feature_vector(&FeatureVector::Dense(x), 0.2)
+                .unwrap();
+            if model.refresh_pending() {
+                pending_steps += 1;
+            }
+        }
+        // 到期后分多次完成，且不会拖到下一个周期
+        assert!(pending_steps > 1);
+        assert!(!model.refresh_pending());
+        let expected = cholesky_decompose(&model.model.a_matrix, d, model.model.lambda);
+        for (x, y) in model.model.l_matrix.iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-6, "{x} != {y}");
+        }
+    }
+
+    #[test]
+    fn test_restored_mid_refresh_model_is_recomputed() {
+        let d = 8;
+        let mut trained = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        // 每次只推进一行，周期点后两次更新时这一轮仍在进行
+        trained.set_refresh_budget(Some(1));
+        for step in 0..CHOLESKY_RECOMPUTE_INTERVAL as usize + 2 {
+            let x = (0..d).map(|i| ((step * 3 + i) % 5) as f64 * 0.1).collect();
+            trained
+                .update_with_feature_vector(&FeatureVector::Dense(x), 0.4)
+                .unwrap();
+        }
+        assert!(trained.refresh_pending());
+        let mut saved = trained.into_model();
+        saved.l_matrix[0] *= 1.5;
+
+        let restored = LinUCB::from_model(saved);
+        assert!(!restored.refresh_pending());
+        assert!(restored.diagnose().is_healthy);
+        let expected = cholesky_decompose(&restored.model.a_matrix, d, restored.model.lambda);
+        for (x, y) in restored.model.l_matrix.iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-9, "{x} != {y}");
+        }
+        assert_eq!(restored.next_refresh, 2 * CHOLESKY_RECOMPUTE_INTERVAL);
+    }
+
+    #[test]
+    fn test_unhealthy_factor_recomputes_immediately() {
+        let d = 8;
+        let mut model = LinUCB::from_model(prior_model(d, 1.0, 0.3));
+        model.model.l_matrix[0] = f64::NAN;
+        model
+            .update_with_feature_vector(&FeatureVector::Dense(one_hot(d, &[1])), 1.0)
+            .unwrap();
+        assert!(!model.refresh_pending());
+        assert!(model.diagnose().is_healthy);
+    }
+}
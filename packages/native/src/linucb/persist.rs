@@ -0,0 +1,336 @@
+//! LinUCB 模型的持久化格式
+//!
+//! 除 NAPI 的 `get_model`/`set_model` 外，任意 Rust 宿主都可以直接保存与加载
+//! 模型状态：
+//!
+//! - JSON：`{"version": 1, "model": BanditModel}`；不带 `version` 的裸
+//!   `BanditModel`（旧快照、NAPI 导出）按版本 1 读取；
+//! - 二进制：小端定长布局，`LUCB` 魔数 | 版本 u32 | d u32 | updateCount u32 |
+//!   lambda f64 | alpha f64 | A (d² × f64) | b (d × f64) | L (d² × f64)。
+//!
+//! 加载时校验版本、A/b/L 长度与 d 的一致性、数值有限性，并可要求模型维度与
+//! 调用方的特征维度一致。
+
+use serde::{Deserialize, Serialize};
+
+use super::{LinUCB, LinUCBError};
+use crate::types::BanditModel;
+
+/// 当前持久化格式版本
+pub const MODEL_FORMAT_VERSION: u32 = 1;
+
+const BINARY_MAGIC: [u8; 4] = *b"LUCB";
+/// 魔数、版本、d、updateCount、lambda、alpha
+const BINARY_HEADER_LEN: usize = 4 + 4 + 4 + 4 + 8 + 8;
+
+#[derive(Serialize)]
+struct EnvelopeRef<'a> {
+    version: u32,
+    model: &'a BanditModel,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    version: u32,
+    model: BanditModel,
+}
+
+impl LinUCB {
+    /// 序列化为带版本的 JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&EnvelopeRef {
+            version: MODEL_FORMAT_VERSION,
+            model: self.model(),
+        })
+        .unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// 从 JSON 加载；`expected_dimension` 非空时要求模型维度一致
+    pub fn from_json(json: &str, expected_dimension: Option<usize>) -> Result<Self, LinUCBError> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| LinUCBError::Malformed(format!("JSON 解析失败: {e}")))?;
+        let (version, model) = if value.get("version").is_some() {
+            let envelope: Envelope = serde_json::from_value(value)
+                .map_err(|e| LinUCBError::Malformed(format!("模型结构错误: {e}")))?;
+            (envelope.version, envelope.model)
+        } else {
+            let model: BanditModel = serde_json::from_value(value)
+                .map_err(|e| LinUCBError::Malformed(format!("模型结构错误: {e}")))?;
+            (MODEL_FORMAT_VERSION, model)
+        };
+        check_version(version)?;
+        validate_model(&model, expected_dimension)?;
+        Ok(Self::from_model(model))
+    }
+
+    /// 序列化为紧凑二进制
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let model = self.model();
+        let floats = model.a_matrix.len() + model.b.len() + model.l_matrix.len();
+        let mut out = Vec::with_capacity(BINARY_HEADER_LEN + floats * 8);
+        out.extend_from_slice(&BINARY_MAGIC);
+        out.extend_from_slice(&MODEL_FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&model.d.to_le_bytes());
+        out.extend_from_slice(&model.update_count.to_le_bytes());
+        out.extend_from_slice(&model.lambda.to_le_bytes());
+        out.extend_from_slice(&model.alpha.to_le_bytes());
+        for v in model.a_matrix.iter().chain(&model.b).chain(&model.l_matrix) {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+
+    /// 从二进制加载；`expected_dimension` 非空时要求模型维度一致
+    pub fn from_bytes(
+        bytes: &[u8],
+        expected_dimension: Option<usize>,
+    ) -> Result<Self, LinUCBError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != BINARY_MAGIC {
+            return Err(LinUCBError::Malformed("缺少 LUCB 魔数".to_string()));
+        }
+        check_version(reader.u32()?)?;
+        let d = reader.u32()?;
+        let update_count = reader.u32()?;
+        let lambda = reader.f64()?;
+        let alpha = reader.f64()?;
+
+        let d_usize = d as usize;
+        if let Some(expected) = expected_dimension {
+            if d_usize != expected {
+                return Err(LinUCBError::DimensionMismatch {
+                    expected,
+                    actual: d_usize,
+                });
+            }
+        }
+        // 先按声明的 d 核对总长度，避免按损坏的 d 分配超大缓冲区
+        let square = d_usize
+            .checked_mul(d_usize)
+            .ok_or_else(|| LinUCBError::Malformed(format!("维度 {d} 过大")))?;
+        let expected_len = square
+            .checked_mul(2)
+            .and_then(|n| n.checked_add(d_usize))
+            .and_then(|n| n.checked_mul(8))
+            .and_then(|n| n.checked_add(BINARY_HEADER_LEN))
+            .ok_or_else(|| LinUCBError::Malformed(format!("维度 {d} 过大")))?;
+        if bytes.len() != expected_len {
+            return Err(LinUCBError::Malformed(format!(
+                "数据长度 {} 与维度 {d} 要求的 {expected_len} 不符",
+                bytes.len()
+            )));
+        }
+
+        let model = BanditModel {
+            a_matrix: reader.f64s(square)?,
+            b: reader.f64s(d_usize)?,
+            l_matrix: reader.f64s(square)?,
+            lambda,
+            alpha,
+            d,
+            update_count,
+        };
+        validate_model(&model, expected_dimension)?;
+        Ok(Self::from_model(model))
+    }
+}
+
+fn check_version(version: u32) -> Result<(), LinUCBError> {
+    if version == 0 || version > MODEL_FORMAT_VERSION {
+        return Err(LinUCBError::UnsupportedVersion {
+            found: version,
+            supported: MODEL_FORMAT_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// 校验模型结构与数值
+pub fn validate_model(
+    model: &BanditModel,
+    expected_dimension: Option<usize>,
+) -> Result<(), LinUCBError> {
+    let d = model.d as usize;
+    if let Some(expected) = expected_dimension {
+        if d != expected {
+            return Err(LinUCBError::DimensionMismatch {
+                expected,
+                actual: d,
+            });
+        }
+    }
+    if d == 0 {
+        return Err(LinUCBError::InvalidModel("维度 d 必须为正数".to_string()));
+    }
+    let square = d * d;
+    for (name, len, want) in [
+        ("A", model.a_matrix.len(), square),
+        ("b", model.b.len(), d),
+        ("L", model.l_matrix.len(), square),
+    ] {
+        if len != want {
+            return Err(LinUCBError::InvalidModel(format!(
+                "{name} 长度 {len} 与维度 {d} 要求的 {want} 不符"
+            )));
+        }
+    }
+    if !(model.lambda.is_finite() && model.lambda > 0.0) {
+        return Err(LinUCBError::InvalidModel("lambda 必须为正数".to_string()));
+    }
+    if !model.alpha.is_finite() || model.alpha < 0.0 {
+        return Err(LinUCBError::InvalidModel("alpha 必须为非负数".to_string()));
+    }
+    let all_finite = model
+        .a_matrix
+        .iter()
+        .chain(&model.b)
+        .chain(&model.l_matrix)
+        .all(|v| v.is_finite());
+    if !all_finite {
+        return Err(LinUCBError::InvalidModel(
+            "A、b 或 L 含有非有限值".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], LinUCBError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| LinUCBError::Malformed("数据被截断".to_string()))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, LinUCBError> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn f64(&mut self) -> Result<f64, LinUCBError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(buf))
+    }
+
+    fn f64s(&mut self, n: usize) -> Result<Vec<f64>, LinUCBError> {
+        (0..n).map(|_| self.f64()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linucb::FeatureVector;
+
+    fn trained() -> LinUCB {
+        let mut bandit = LinUCB::from_model(crate::layout::prior_model(3, 1.0, 0.5));
+        for (x, r) in [
+            (vec![1.0, 0.0, 0.5], 1.0),
+            (vec![0.2, 0.8, 0.0], 0.0),
+            (vec![0.0, 0.3, 1.0], 0.7),
+        ] {
+            bandit
+                .update_with_feature_vector(&FeatureVector::from(x), r)
+                .unwrap();
+        }
+        bandit
+    }
+
+    fn assert_same(a: &BanditModel, b: &BanditModel) {
+        assert_eq!(a.d, b.d);
+        assert_eq!(a.update_count, b.update_count);
+        assert_eq!(a.lambda, b.lambda);
+        assert_eq!(a.alpha, b.alpha);
+        assert_eq!(a.a_matrix, b.a_matrix);
+        assert_eq!(a.b, b.b);
+        assert_eq!(a.l_matrix, b.l_matrix);
+    }
+
+    #[test]
+    fn test_json_and_binary_roundtrip() {
+        let bandit = trained();
+        let json = bandit.to_json();
+        assert!(json.starts_with("{\"version\":1,"));
+        let from_json = LinUCB::from_json(&json, Some(3)).unwrap();
+        assert_same(bandit.model(), from_json.model());
+
+        let bytes = bandit.to_bytes();
+        assert_eq!(bytes.len(), BINARY_HEADER_LEN + (9 + 3 + 9) * 8);
+        let from_bytes = LinUCB::from_bytes(&bytes, None).unwrap();
+        assert_same(bandit.model(), from_bytes.model());
+    }
+
+    #[test]
+    fn test_bare_model_json_is_accepted() {
+        let bandit = trained();
+        let bare = serde_json::to_string(bandit.model()).unwrap();
+        let loaded = LinUCB::from_json(&bare, None).unwrap();
+        assert_same(bandit.model(), loaded.model());
+    }
+
+    #[test]
+    fn test_rejects_version_and_dimension_mismatch() {
+        let bandit = trained();
+        let json = bandit
+            .to_json()
+            .replacen("\"version\":1", "\"version\":9", 1);
+        assert!(matches!(
+            LinUCB::from_json(&json, None),
+            Err(LinUCBError::UnsupportedVersion { found: 9, .. })
+        ));
+
+        let mut bytes = bandit.to_bytes();
+        assert!(matches!(
+            LinUCB::from_bytes(&bytes, Some(22)),
+            Err(LinUCBError::DimensionMismatch {
+                expected: 22,
+                actual: 3
+            })
+        ));
+        bytes[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(
+            LinUCB::from_bytes(&bytes, None),
+            Err(LinUCBError::UnsupportedVersion { found: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_corrupt_payloads() {
+        let bandit = trained();
+        let bytes = bandit.to_bytes();
+        assert!(matches!(
+            LinUCB::from_bytes(&bytes[..bytes.len() - 1], None),
+            Err(LinUCBError::Malformed(_))
+        ));
+        let mut huge = bytes.clone();
+        huge[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            LinUCB::from_bytes(&huge, None),
+            Err(LinUCBError::Malformed(_))
+        ));
+
+        let mut model = bandit.model().clone();
+        model.b.pop();
+        assert!(matches!(
+            validate_model(&model, None),
+            Err(LinUCBError::InvalidModel(_))
+        ));
+        let mut model = bandit.model().clone();
+        model.l_matrix[0] = f64::NAN;
+        assert!(matches!(
+            validate_model(&model, None),
+            Err(LinUCBError::InvalidModel(_))
+        ));
+    }
+}
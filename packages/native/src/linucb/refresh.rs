@@ -0,0 +1,145 @@
+//! 分摊式 Cholesky 周期重算
+//!
+//! 每 `CHOLESKY_RECOMPUTE_INTERVAL` 次更新做一次完整 O(d³) 分解会在那一次
+//! 更新上造成明显的延迟尖刺。这里把它拆到后续若干次更新里：
+//!
+//! - 到达周期时对 A 拍快照，开始在影子矩阵上逐行分解，当前 L 继续服务；
+//! - 每次更新只推进不超过 `ops_budget` 次乘加的行数（至少一行）；
+//! - 快照之后的观测记录下来，分解完成时在影子 L 上按 Rank-1 更新回放，
+//!   使结果等价于对当前 A 的完整分解，然后整体替换 L。
+//!
+//! 回放失败或 L 本身已不健康时仍立即完整重算，见 `LinUCB::update_with_feature_vector`。
+
+use crate::matrix::sparse::sparse_cholesky_rank1_update;
+use crate::matrix::{cholesky_rank1_update, cholesky_row, regularized_copy};
+use crate::types::MIN_RANK1_DIAG;
+
+use super::FeatureVector;
+
+/// 每次更新默认分摊的乘加次数；d = 22 时约 4 次更新完成一轮
+pub const DEFAULT_REFRESH_OPS: usize = 512;
+
+/// 推进一步后的状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefreshStep {
+    /// 尚未完成
+    Pending,
+    /// 完成，附带可直接替换的 L
+    Ready(Vec<f64>),
+    /// 回放 Rank-1 更新时数值失败，需要完整重算
+    Failed,
+}
+
+/// 进行中的一轮分摊重算
+#[derive(Debug, Clone)]
+pub struct CholeskyRefresh {
+    work: Vec<f64>,
+    l: Vec<f64>,
+    d: usize,
+    safe_lambda: f64,
+    next_row: usize,
+    pending: Vec<FeatureVector>,
+}
+
+impl CholeskyRefresh {
+    /// 以当前 A 为快照开始一轮重算
+    pub fn start(a: &[f64], d: usize, lambda: f64) -> Self {
+        let (work, safe_lambda) = regularized_copy(a, d, lambda);
+        Self {
+            work,
+            l: vec![0.0; d * d],
+            d,
+            safe_lambda,
+            next_row: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// 已分解的行数
+    pub fn rows_done(&self) -> usize {
+        self.next_row
+    }
+
+    /// 快照之后待回放的观测数
+    pub fn pending_updates(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 记录快照之后的一次观测（需为已清理的特征）
+    pub fn record(&mut self, x: FeatureVector) {
+        self.pending.push(x);
+    }
+
+    /// 在预算内继续分解；第 i 行约需 i²/2 次乘加
+    pub fn advance(&mut self, ops_budget: usize) -> RefreshStep {
+        let d = self.d;
+        let mut spent = 0usize;
+        while self.next_row < d {
+            let i = self.next_row;
+            let cost = (i + 1) * (i + 2) / 2;
+            if spent > 0 && spent + cost > ops_budget {
+                return RefreshStep::Pending;
+            }
+            cholesky_row(&self.work, &mut self.l, d, i, self.safe_lambda);
+            self.next_row += 1;
+            spent += cost;
+        }
+        for x in self.pending.drain(..) {
+            let ok = match &x {
+                FeatureVector::Dense(x) => cholesky_rank1_update(&mut self.l, x, d, MIN_RANK1_DIAG),
+                FeatureVector::Sparse(x) => {
+                    sparse_cholesky_rank1_update(&mut self.l, x, d, MIN_RANK1_DIAG)
+                }
+            };
+            if !ok {
+                return RefreshStep::Failed;
+            }
+        }
+        RefreshStep::Ready(std::mem::take(&mut self.l))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::{cholesky_decompose, rank1_update_matrix};
+
+    #[test]
+    fn test_refresh_with_replay_matches_full_decompose() {
+        let d = 6;
+        let mut a = vec![0.0; d * d];
+        for i in 0..d {
+            a[i * d + i] = 1.0;
+        }
+        let xs: Vec<Vec<f64>> = (0..12)
+            .map(|k| (0..d).map(|i| ((k * 7 + i * 3) % 5) as f64 * 0.3).collect())
+            .collect();
+        for x in &xs[..8] {
+            rank1_update_matrix(&mut a, x, d);
+        }
+
+        let mut refresh = CholeskyRefresh::start(&a, d, 1.0);
+        let mut steps = 0;
+        let l = loop {
+            if let Some(x) = xs.get(8 + steps) {
+                rank1_update_matrix(&mut a, x, d);
+                refresh.record(FeatureVector::Dense(x.clone()));
+            }
+            steps += 1;
+            match refresh.advance(4) {
+                RefreshStep::Pending => assert!(refresh.rows_done() < d),
+                RefreshStep::Ready(l) => break l,
+                RefreshStep::Failed => panic!("replay failed"),
+            }
+        };
+        assert!(
+            steps > 1,
+            "budget should spread the rows over several steps"
+        );
+
+        let expected = cholesky_decompose(&a, d, 1.0);
+        for (x, y) in l.iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-8, "{x} != {y}");
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! 长时间随机更新的浸泡测试（`soak` feature）
+//!
+//! 以固定种子生成数百万次随机更新（稠密与稀疏 one-hot 混合，偶尔夹带被截断的
+//! 极端值），每隔 `check_every` 次比较 L·Lᵀ 与 A、增量 theta 与直接求解的
+//! theta，记录漂移曲线。用法见 `examples/linucb_soak.rs`。
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::layout::prior_model;
+use crate::matrix::sparse::SparseVector;
+use crate::rng::RngFactory;
+use crate::sanitize::theta_drift;
+use crate::types::MAX_FEATURE_ABS;
+
+use super::{FeatureVector, LinUCB};
+
+const SOAK_DOMAIN: &str = "linucb.soak";
+
+#[derive(Debug, Clone)]
+pub struct SoakConfig {
+    pub updates: u64,
+    pub dimension: usize,
+    pub check_every: u64,
+    pub seed: u64,
+    /// 稀疏 one-hot 更新所占比例
+    pub sparse_ratio: f64,
+    /// 含极端值（超出截断范围）的更新所占比例
+    pub outlier_ratio: f64,
+    pub lambda: f64,
+    pub alpha: f64,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            updates: 1_000_000,
+            dimension: 22,
+            check_every: 10_000,
+            seed: 42,
+            sparse_ratio: 0.5,
+            outlier_ratio: 0.01,
+            lambda: 1.0,
+            alpha: 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftSample {
+    pub update: u64,
+    pub reconstruction_drift: f64,
+    pub theta_drift: f64,
+    pub condition_number: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoakReport {
+    pub updates: u64,
+    pub samples: Vec<DriftSample>,
+    pub max_reconstruction_drift: f64,
+    pub max_theta_drift: f64,
+    /// `diagnose()` 报告不健康或漂移的检查次数
+    pub flagged_checks: u64,
+}
+
+fn random_feature(rng: &mut impl Rng, config: &SoakConfig) -> FeatureVector {
+    let d = config.dimension;
+    let scale = if rng.gen_bool(config.outlier_ratio) {
+        MAX_FEATURE_ABS * 4.0
+    } else {
+        1.0
+    };
+    if rng.gen_bool(config.sparse_ratio) {
+        let nnz = rng.gen_range(1..=3.min(d));
+        let mut entries: Vec<(usize, f64)> = Vec::with_capacity(nnz + 1);
+        for _ in 0..nnz {
+            let index = rng.gen_range(0..d);
+            if entries.iter().all(|(i, _)| *i != index) {
+                entries.push((index, scale));
+            }
+        }
+        // 偏置项
+        if entries.iter().all(|(i, _)| *i != d - 1) {
+            entries.push((d - 1, 1.0));
+        }
+        FeatureVector::Sparse(SparseVector::new(d, entries).expect("indices within dimension"))
+    } else {
+        FeatureVector::Dense((0..d).map(|_| rng.gen_range(-1.0..1.0) * scale).collect())
+    }
+}
+
+pub fn run_soak(config: &SoakConfig) -> SoakReport {
+    let mut rng = RngFactory::new(config.seed).stream(SOAK_DOMAIN);
+    let mut linucb = LinUCB::from_model(prior_model(config.dimension, config.lambda, config.alpha));
+    let check_every = config.check_every.max(1);
+    let mut report = SoakReport {
+        updates: config.updates,
+        samples: Vec::new(),
+        max_reconstruction_drift: 0.0,
+        max_theta_drift: 0.0,
+        flagged_checks: 0,
+    };
+
+    for update in 1..=config.updates {
+        let x = random_feature(&mut rng, config);
+        let reward = rng.gen_range(-1.0..1.0);
+        linucb
+            .update_with_feature_vector(&x, reward)
+            .expect("soak features match model dimension");
+
+        // 检查点前移一次：check_every 为重算周期的倍数时，整点检查恰好落在
+        // 周期性完整重算之后，看不到累积的漂移
+        if update % check_every == check_every - 1 || update == config.updates {
+            let diagnostic = linucb.diagnose();
+            let model = linucb.model();
+            let theta = theta_drift(
+                &model.a_matrix,
+                &model.l_matrix,
+                &model.b,
+                config.dimension,
+                model.lambda,
+            );
+            if !diagnostic.is_healthy || diagnostic.has_drift {
+                report.flagged_checks += 1;
+            }
+            report.max_reconstruction_drift = report
+                .max_reconstruction_drift
+                .max(diagnostic.reconstruction_drift);
+            report.max_theta_drift = report.max_theta_drift.max(theta);
+            report.samples.push(DriftSample {
+                update,
+                reconstruction_drift: diagnostic.reconstruction_drift,
+                theta_drift: theta,
+                condition_number: diagnostic.condition_number,
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_soak_stays_within_tolerance() {
+        let report = run_soak(&SoakConfig {
+            updates: 5_000,
+            check_every: 700,
+            ..SoakConfig::default()
+        });
+        assert_eq!(report.samples.len(), 8);
+        assert_eq!(report.samples[0].update, 699);
+        assert_eq!(report.samples.last().unwrap().update, 5_000);
+        assert_eq!(report.flagged_checks, 0, "{report:?}");
+        assert!(report.max_theta_drift < 1e-6);
+    }
+
+    #[test]
+    fn test_soak_is_reproducible() {
+        let config = SoakConfig {
+            updates: 1_000,
+            check_every: 250,
+            ..SoakConfig::default()
+        };
+        let a = run_soak(&config);
+        let b = run_soak(&config);
+        assert_eq!(a.max_reconstruction_drift, b.max_reconstruction_drift);
+        assert_eq!(a.max_theta_drift, b.max_theta_drift);
+    }
+}
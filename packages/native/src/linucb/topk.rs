@@ -0,0 +1,327 @@
+//! 大候选集的近似 top-k 选择
+//!
+//! 完整 UCB 的瓶颈是探索项 ‖L⁻¹x‖ 的前向替换（O(d²)）。对每个候选先算一个
+//! 廉价上界：利用项 θᵀx 精确计算（O(nnz)），探索项用
+//!
+//! ‖L⁻¹x‖ ≤ min(Σ|xⱼ|·‖L⁻¹eⱼ‖, ‖x‖·‖L⁻¹‖_F)
+//!
+//! 其中 L⁻¹ 的列范数每次选择只算一次，候选的 ‖x‖ 缓存在 `CandidateIndex` 中。
+//! 按上界从高到低依次计算完整 UCB，用容量为 k 的小顶堆保留当前最好的 k 个；
+//! 下一个上界不超过第 k 名得分 + `epsilon` 时提前终止。
+//!
+//! `epsilon = 0` 时结果与精确排序一致；`epsilon > 0` 时每个被跳过的候选
+//! 得分都不超过返回的第 k 名得分 + `epsilon`，以此换取更早终止。
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::matrix::solve_triangular_lower;
+
+use super::{FeatureVector, LinUCB, LinUCBError, UcbScore};
+
+/// 上界的相对放宽量，吸收浮点舍入，避免误剪掉与上界恰好相等的候选
+const BOUND_SLACK: f64 = 1e-9;
+
+/// 预处理后的候选集，缓存每个候选的 L2 范数，可在多次选择间复用
+#[derive(Debug, Clone)]
+pub struct CandidateIndex {
+    features: Vec<FeatureVector>,
+    norms: Vec<f64>,
+}
+
+impl CandidateIndex {
+    pub fn new(candidates: &[FeatureVector]) -> Self {
+        let features: Vec<FeatureVector> = candidates.iter().map(|x| x.prepared()).collect();
+        let norms = features.iter().map(l2_norm).collect();
+        Self { features, norms }
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+}
+
+/// 近似 top-k 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopKSelection {
+    /// 按得分降序；下标对应 `CandidateIndex` 中的位置
+    pub ranked: Vec<(usize, UcbScore)>,
+    /// 实际计算完整 UCB 的候选数
+    pub evaluated: usize,
+}
+
+fn l2_norm(x: &FeatureVector) -> f64 {
+    let squares: f64 = match x {
+        FeatureVector::Dense(x) => x.iter().map(|v| v * v).sum(),
+        FeatureVector::Sparse(x) => x.values().iter().map(|v| v * v).sum(),
+    };
+    squares.sqrt()
+}
+
+/// 堆中的候选；得分高者为大，同分时下标小者为大（与 `select` 取第一个最大值一致）
+#[derive(Debug, Clone, Copy)]
+struct Ranked {
+    index: usize,
+    score: UcbScore,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .score
+            .total_cmp(&other.score.score)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl LinUCB {
+    /// L⁻¹ 的各列范数 ‖L⁻¹eⱼ‖ 与 Frobenius 范数
+    fn inverse_factor_norms(&self) -> (Vec<f64>, f64) {
+        let d = self.dimension();
+        let mut unit = vec![0.0; d];
+        let columns: Vec<f64> = (0..d)
+            .map(|j| {
+                unit[j] = 1.0;
+                let z = solve_triangular_lower(&self.model.l_matrix, &unit, d);
+                unit[j] = 0.0;
+                z.iter().map(|v| v * v).sum::<f64>().sqrt()
+            })
+            .collect();
+        let frobenius = columns.iter().map(|c| c * c).sum::<f64>().sqrt();
+        (columns, frobenius)
+    }
+
+    /// 选出 UCB 最高的 k 个候选；`epsilon` 为允许的得分误差（0 为精确）
+    pub fn select_top_k(
+        &self,
+        index: &CandidateIndex,
+        k: usize,
+        epsilon: f64,
+    ) -> Result<TopKSelection, LinUCBError> {
+        for x in &index.features {
+            self.check_dimension(x)?;
+        }
+        if k == 0 || index.is_empty() {
+            return Ok(TopKSelection {
+                ranked: Vec::new(),
+                evaluated: 0,
+            });
+        }
+
+        let theta = self.theta();
+        let (columns, frobenius) = self.inverse_factor_norms();
+        let alpha = self.model.alpha;
+        let mut bounds: Vec<(f64, usize)> = index
+            .features
+            .iter()
+            .zip(&index.norms)
+            .enumerate()
+            .map(|(i, (x, &norm))| {
+                let (exploitation, weighted) = match x {
+                    FeatureVector::Dense(x) => (
+                        x.iter().zip(&theta).map(|(v, t)| v * t).sum::<f64>(),
+                        x.iter()
+                            .zip(&columns)
+                            .map(|(v, c)| v.abs() * c)
+                            .sum::<f64>(),
+                    ),
+                    FeatureVector::Sparse(x) => (
+                        x.iter().map(|(j, v)| v * theta[j]).sum::<f64>(),
+                        x.iter().map(|(j, v)| v.abs() * columns[j]).sum::<f64>(),
+                    ),
+                };
+                let bound = exploitation + alpha * weighted.min(norm * frobenius);
+                (bound + BOUND_SLACK * (1.0 + bound.abs()), i)
+            })
+            .collect();
+        bounds.sort_unstable_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let epsilon = epsilon.max(0.0);
+        let mut heap: BinaryHeap<Reverse<Ranked>> = BinaryHeap::with_capacity(k + 1);
+        let mut evaluated = 0;
+        for (bound, i) in bounds {
+            if heap.len() == k {
+                let kth = heap
+                    .peek()
+                    .map(|Reverse(r)| r.score.score)
+                    .unwrap_or(f64::MIN);
+                if bound <= kth + epsilon {
+                    break;
+                }
+            }
+            let score = self.ucb_prepared(&theta, &index.features[i]);
+            evaluated += 1;
+            heap.push(Reverse(Ranked { index: i, score }));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        let mut ranked: Vec<Ranked> = heap.into_iter().map(|Reverse(r)| r).collect();
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(TopKSelection {
+            ranked: ranked.into_iter().map(|r| (r.index, r.score)).collect(),
+            evaluated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::layout::prior_model;
+    use crate::matrix::sparse::SparseVector;
+    use crate::rng::RngFactory;
+
+    const D: usize = 24;
+
+    fn candidates(n: usize, seed: u64) -> Vec<FeatureVector> {
+        let mut rng = RngFactory::new(seed).stream("linucb.topk");
+        (0..n)
+            .map(|i| {
+                if i % 2 == 0 {
+                    let mut entries = vec![(rng.gen_range(0..D - 1), rng.gen_range(0.2..2.0))];
+                    let second = rng.gen_range(0..D - 1);
+                    if second != entries[0].0 {
+                        entries.push((second, rng.gen_range(0.2..2.0)));
+                    }
+                    entries.push((D - 1, 1.0));
+                    FeatureVector::Sparse(SparseVector::new(D, entries).unwrap())
+                } else {
+                    let scale = rng.gen_range(0.1..1.5);
+                    FeatureVector::Dense((0..D).map(|_| rng.gen_range(-1.0..1.0) * scale).collect())
+                }
+            })
+            .collect()
+    }
+
+    fn trained(seed: u64) -> LinUCB {
+        let mut rng = RngFactory::new(seed).stream("linucb.topk.train");
+        let mut model = LinUCB::from_model(prior_model(D, 1.0, 0.3));
+        let weights: Vec<f64> = (0..D).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        for x in candidates(400, seed + 1) {
+            let reward = match &x {
+                FeatureVector::Dense(x) => x.iter().zip(&weights).map(|(a, b)| a * b).sum(),
+                FeatureVector::Sparse(x) => x.iter().map(|(j, v)| v * weights[j]).sum::<f64>(),
+            } + rng.gen_range(-0.1..0.1);
+            model.update_with_feature_vector(&x, reward).unwrap();
+        }
+        model
+    }
+
+    fn exact_ranking(model: &LinUCB, candidates: &[FeatureVector]) -> Vec<(usize, f64)> {
+        let theta = model.theta();
+        let mut scores: Vec<(usize, f64)> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i, model.ucb(&theta, x).unwrap().score))
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scores
+    }
+
+    #[test]
+    fn test_zero_epsilon_matches_exact_ranking() {
+        let model = trained(7);
+        let pool = candidates(5_000, 11);
+        let index = CandidateIndex::new(&pool);
+        let selection = model.select_top_k(&index, 100, 0.0).unwrap();
+        let exact = exact_ranking(&model, &pool);
+
+        assert_eq!(selection.ranked.len(), 100);
+        for ((i, score), (j, expected)) in selection.ranked.iter().zip(&exact) {
+            assert_eq!(i, j);
+            assert!((score.score - expected).abs() < 1e-9);
+        }
+        assert!(selection.evaluated < pool.len(), "{}", selection.evaluated);
+        assert_eq!(
+            model.select_top_k(&index, 1, 0.0).unwrap().ranked[0].0,
+            model.select(&pool).unwrap().unwrap().0
+        );
+    }
+
+    #[test]
+    fn test_epsilon_bounds_regret_against_exact() {
+        let model = trained(3);
+        let pool = candidates(5_000, 5);
+        let index = CandidateIndex::new(&pool);
+        let exact = exact_ranking(&model, &pool);
+        let k = 100;
+        let epsilon = 0.05;
+
+        let strict = model.select_top_k(&index, k, 0.0).unwrap();
+        let approx = model.select_top_k(&index, k, epsilon).unwrap();
+        assert_eq!(approx.ranked.len(), k);
+        assert!(approx.evaluated <= strict.evaluated);
+
+        // 未返回的候选都不超过第 k 名 + epsilon
+        let kth = approx.ranked.last().unwrap().1.score;
+        let returned: Vec<usize> = approx.ranked.iter().map(|(i, _)| *i).collect();
+        for (i, score) in &exact {
+            if !returned.contains(i) {
+                assert!(*score <= kth + epsilon + 1e-9, "{i}: {score} > {kth}");
+            }
+        }
+        // 与精确 top-k 的得分差（遗憾）不超过 epsilon
+        for ((_, approx), (_, exact)) in approx.ranked.iter().zip(&exact) {
+            assert!(exact - approx.score <= epsilon + 1e-9);
+        }
+        let recall = returned
+            .iter()
+            .filter(|i| exact[..k].iter().any(|(j, _)| j == *i))
+            .count() as f64
+            / k as f64;
+        assert!(recall >= 0.9, "recall {recall}");
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let model = trained(1);
+        let pool = candidates(10, 2);
+        let index = CandidateIndex::new(&pool);
+        assert!(model
+            .select_top_k(&index, 0, 0.0)
+            .unwrap()
+            .ranked
+            .is_empty());
+        let all = model.select_top_k(&index, 50, 0.0).unwrap();
+        assert_eq!(all.ranked.len(), 10);
+        assert_eq!(all.evaluated, 10);
+
+        let empty = CandidateIndex::new(&[]);
+        assert!(model
+            .select_top_k(&empty, 5, 0.0)
+            .unwrap()
+            .ranked
+            .is_empty());
+
+        let wrong = CandidateIndex::new(&[FeatureVector::Dense(vec![1.0; 3])]);
+        assert_eq!(
+            model.select_top_k(&wrong, 1, 0.0),
+            Err(LinUCBError::DimensionMismatch {
+                expected: D,
+                actual: 3
+            })
+        );
+    }
+}
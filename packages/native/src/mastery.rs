@@ -0,0 +1,304 @@
+//! 掌握度置信区间
+//!
+//! 掌握度以单值展示时看不出学习者表现是否稳定。这里把两类不确定性合成一个
+//! 区间，供界面显示“掌握度 ± 不确定度”：
+//!
+//! - ACT-R 预测的不确定性：复习轨迹的激活值 A = ln Σ tⱼ^(−d)（tⱼ 以天计），
+//!   回忆概率 p = σ((A − τ) / s)。轨迹越短，p 越不可信，按 Beta 后验的标准差
+//!   √(p(1 − p) / (n + n₀ + 1)) 计算；
+//! - 近期答题波动：最近 `volatility_window` 次作答的离散度（2√(q(1 − q))）与
+//!   对错翻转率的平均，取值 [0, 1]。
+//!
+//! 区间半宽为 z·√(σ² + (w·volatility)²)，截断到 [0, 1]。
+//!
+//! 识记（L2→L1）与产出（L1→L2）是两张卡片，各有一条复习轨迹。一个方向的
+//! 复习也会部分加强另一方向：关联激活 A = ln(Σ_own tⱼ^(−d) + k·Σ_other tⱼ^(−d))，
+//! k 为跨方向迁移系数 `transfer`。
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::float;
+
+/// 年龄下限（天），避免刚作答的记录激活值发散
+const MIN_AGE_DAYS: f64 = 1e-3;
+
+/// 区间参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MasteryBandConfig {
+    /// ACT-R 衰减率 d
+    pub decay: f64,
+    /// 回忆阈值 τ
+    pub threshold: f64,
+    /// 激活噪声 s
+    pub noise: f64,
+    /// Beta 先验的等效观测数 n₀
+    pub prior_observations: f64,
+    /// 计算波动时使用的最近作答数
+    pub volatility_window: u32,
+    /// 波动对半宽的权重 w
+    pub volatility_weight: f64,
+    /// 半宽的标准差倍数 z
+    pub z: f64,
+    /// 另一方向复习对本方向激活的迁移系数 k，取值 [0, 1]
+    pub transfer: f64,
+}
+
+impl Default for MasteryBandConfig {
+    fn default() -> Self {
+        Self {
+            decay: 0.5,
+            threshold: -0.5,
+            noise: 0.4,
+            prior_observations: 2.0,
+            volatility_window: 10,
+            volatility_weight: 0.3,
+            z: 1.0,
+            transfer: 0.3,
+        }
+    }
+}
+
+/// 一次复习
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewEvent {
+    /// 距今天数
+    pub age_days: f64,
+    pub correct: bool,
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasteryBand {
+    pub mastery: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// ACT-R 回忆概率
+    pub recall_probability: f64,
+    /// 回忆概率估计的标准差
+    pub prediction_sd: f64,
+    pub volatility: f64,
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + float::exp(-x))
+}
+
+/// ACT-R 激活值 ln Σ age^(−d)；没有有效复习记录时为 `None`
+///
+/// 激活只取决于复习轨迹与衰减率，调用方可以按 (轨迹, d) 缓存它，
+/// 再用 [`recall_from_activation`] 换算回忆概率。
+pub fn actr_activation(events: &[ReviewEvent], decay: f64) -> Option<f64> {
+    let strength = trace_strength(events, decay);
+    (strength > 0.0).then(|| float::ln(strength))
+}
+
+/// 轨迹强度 Σ age^(−d)
+fn trace_strength(events: &[ReviewEvent], decay: f64) -> f64 {
+    events
+        .iter()
+        .filter(|e| e.age_days.is_finite())
+        .map(|e| float::powf(e.age_days.max(MIN_AGE_DAYS), -decay))
+        .sum()
+}
+
+/// 关联激活值 ln(Σ_own + k·Σ_other)：`own` 为本方向轨迹，`other` 为另一方向
+/// 轨迹，k 截断到 [0, 1]；两条轨迹都没有有效记录时为 `None`
+pub fn linked_activation(
+    own: &[ReviewEvent],
+    other: &[ReviewEvent],
+    decay: f64,
+    transfer: f64,
+) -> Option<f64> {
+    let transfer = if transfer.is_finite() {
+        transfer.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let strength = trace_strength(own, decay) + transfer * trace_strength(other, decay);
+    (strength > 0.0).then(|| float::ln(strength))
+}
+
+/// 激活值对应的回忆概率
+pub fn recall_from_activation(activation: f64, config: &MasteryBandConfig) -> f64 {
+    let noise = if config.noise > 0.0 {
+        config.noise
+    } else {
+        0.4
+    };
+    sigmoid((activation - config.threshold) / noise)
+}
+
+/// ACT-R 回忆概率；没有复习记录时为 0
+pub fn actr_recall(events: &[ReviewEvent], config: &MasteryBandConfig) -> f64 {
+    actr_activation(events, config.decay).map_or(0.0, |a| recall_from_activation(a, config))
+}
+
+/// 考虑跨方向迁移的 ACT-R 回忆概率；两条轨迹都为空时为 0
+pub fn linked_recall(
+    own: &[ReviewEvent],
+    other: &[ReviewEvent],
+    config: &MasteryBandConfig,
+) -> f64 {
+    linked_activation(own, other, config.decay, config.transfer)
+        .map_or(0.0, |a| recall_from_activation(a, config))
+}
+
+/// 近期作答波动 [0, 1]；`outcomes` 按时间先后排列
+pub fn answer_volatility(outcomes: &[bool], window: usize) -> f64 {
+    let recent = &outcomes[outcomes.len().saturating_sub(window.max(2))..];
+    if recent.len() < 2 {
+        return 0.0;
+    }
+    let q = recent.iter().filter(|c| **c).count() as f64 / recent.len() as f64;
+    let dispersion = 2.0 * float::sqrt(q * (1.0 - q));
+    let flips = recent.windows(2).filter(|w| w[0] != w[1]).count() as f64;
+    let flip_rate = flips / (recent.len() - 1) as f64;
+    (dispersion + flip_rate) / 2.0
+}
+
+/// 掌握度区间；`events` 可为任意顺序，波动按 `age_days` 从旧到新计算
+#[cfg_attr(feature = "napi", napi)]
+pub fn mastery_band(
+    mastery: f64,
+    events: Vec<ReviewEvent>,
+    config: Option<MasteryBandConfig>,
+) -> MasteryBand {
+    let config = config.unwrap_or_default();
+    let mastery = if mastery.is_finite() {
+        mastery.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let mut events = events;
+    events.sort_by(|a, b| b.age_days.total_cmp(&a.age_days));
+
+    let p = actr_recall(&events, &config);
+    let n = events.len() as f64 + config.prior_observations.max(0.0);
+    let prediction_sd = float::sqrt(p * (1.0 - p) / (n + 1.0));
+
+    let outcomes: Vec<bool> = events.iter().map(|e| e.correct).collect();
+    let volatility = answer_volatility(&outcomes, config.volatility_window as usize);
+
+    let spread = config.volatility_weight.max(0.0) * volatility;
+    let half_width =
+        config.z.max(0.0) * float::sqrt(float::powi(prediction_sd, 2) + float::powi(spread, 2));
+    MasteryBand {
+        mastery,
+        lower: (mastery - half_width).max(0.0),
+        upper: (mastery + half_width).min(1.0),
+        recall_probability: p,
+        prediction_sd,
+        volatility,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(outcomes: &[bool]) -> Vec<ReviewEvent> {
+        // 每天一次，最后一个最近
+        outcomes
+            .iter()
+            .enumerate()
+            .map(|(i, &correct)| ReviewEvent {
+                age_days: (outcomes.len() - i) as f64,
+                correct,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_erratic_learner_gets_wider_band() {
+        let steady = mastery_band(0.6, events(&[true; 10]), None);
+        let erratic = mastery_band(
+            0.6,
+            events(&[
+                true, false, true, false, true, false, true, false, true, false,
+            ]),
+            None,
+        );
+        assert_eq!(steady.volatility, 0.0);
+        assert!(erratic.volatility > 0.9);
+        assert!(erratic.upper - erratic.lower > steady.upper - steady.lower);
+        assert!(erratic.lower <= 0.6 && erratic.upper >= 0.6);
+    }
+
+    #[test]
+    fn test_more_reviews_narrow_prediction() {
+        let few = mastery_band(0.5, events(&[true, true]), None);
+        let many = mastery_band(0.5, events(&[true; 30]), None);
+        assert!(many.prediction_sd < few.prediction_sd);
+        assert!(many.recall_probability > few.recall_probability);
+
+        let empty = mastery_band(1.5, Vec::new(), None);
+        assert_eq!(empty.mastery, 1.0);
+        assert_eq!(empty.recall_probability, 0.0);
+        assert_eq!((empty.lower, empty.upper), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_recall_decays_with_age() {
+        let config = MasteryBandConfig::default();
+        let recent = actr_recall(
+            &[ReviewEvent {
+                age_days: 1.0,
+                correct: true,
+            }],
+            &config,
+        );
+        let old = actr_recall(
+            &[ReviewEvent {
+                age_days: 30.0,
+                correct: true,
+            }],
+            &config,
+        );
+        assert!(recent > 0.5 && old < 0.2, "{recent} {old}");
+    }
+
+    #[test]
+    fn test_recall_from_cached_activation_matches_direct() {
+        let config = MasteryBandConfig::default();
+        let trace = events(&[true, false, true, true]);
+        let activation = actr_activation(&trace, config.decay).unwrap();
+        assert_eq!(
+            recall_from_activation(activation, &config),
+            actr_recall(&trace, &config)
+        );
+        assert_eq!(actr_activation(&[], config.decay), None);
+        assert_eq!(actr_recall(&[], &config), 0.0);
+    }
+
+    #[test]
+    fn test_other_direction_transfers_partially() {
+        let config = MasteryBandConfig::default();
+        let trace = events(&[true, true, true]);
+        let own_only = linked_recall(&trace, &[], &config);
+        let other_only = linked_recall(&[], &trace, &config);
+        let both = linked_recall(&trace, &trace, &config);
+        assert_eq!(own_only, actr_recall(&trace, &config));
+        assert!(other_only > 0.0 && other_only < own_only);
+        assert!(both > own_only);
+
+        let isolated = MasteryBandConfig {
+            transfer: 0.0,
+            ..config.clone()
+        };
+        assert_eq!(linked_recall(&[], &trace, &isolated), 0.0);
+        assert_eq!(
+            linked_activation(&trace, &trace, config.decay, 5.0),
+            linked_activation(&trace, &trace, config.decay, 1.0)
+        );
+        assert_eq!(linked_activation(&[], &[], config.decay, 0.3), None);
+    }
+}
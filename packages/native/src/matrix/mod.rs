@@ -1,46 +1,59 @@
+pub mod sparse;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::float;
 use crate::types::{EPSILON, MIN_LAMBDA, MIN_RANK1_DIAG};
 
 /// Cholesky 分解 - 将正定矩阵 A 分解为 L * L^T
 /// 参考: packages/backend/src/amas/learning/math-utils.ts 第 179-235 行
 pub fn cholesky_decompose(a: &[f64], d: usize, lambda: f64) -> Vec<f64> {
-    let safe_lambda = lambda.max(MIN_LAMBDA);
+    let (work, safe_lambda) = regularized_copy(a, d, lambda);
     let mut l = vec![0.0; d * d];
+    for i in 0..d {
+        cholesky_row(&work, &mut l, d, i, safe_lambda);
+    }
+    l
+}
 
-    // 复制 A 到工作矩阵（添加正则化）
+/// 复制 A 并在对角线添加微小正则化，返回工作矩阵与修正后的 λ
+pub(crate) fn regularized_copy(a: &[f64], d: usize, lambda: f64) -> (Vec<f64>, f64) {
+    let safe_lambda = lambda.max(MIN_LAMBDA);
     let mut work = a.to_vec();
     for i in 0..d {
-        work[i * d + i] += safe_lambda * EPSILON; // 微小正则化
+        work[i * d + i] += safe_lambda * EPSILON;
     }
+    (work, safe_lambda)
+}
 
-    for i in 0..d {
-        for j in 0..=i {
-            let mut sum = work[i * d + j];
+/// 计算 Cholesky 因子 L 的第 i 行；要求前 i 行已算出
+pub(crate) fn cholesky_row(work: &[f64], l: &mut [f64], d: usize, i: usize, safe_lambda: f64) {
+    for j in 0..=i {
+        let mut sum = work[i * d + j];
 
-            for k in 0..j {
-                sum -= l[i * d + k] * l[j * d + k];
-            }
+        for k in 0..j {
+            sum -= l[i * d + k] * l[j * d + k];
+        }
 
-            if i == j {
-                // 对角线元素
-                if sum <= 0.0 {
-                    // 数值修复：使用最小值
-                    l[i * d + i] = safe_lambda.sqrt();
-                } else {
-                    l[i * d + i] = sum.sqrt();
-                }
+        if i == j {
+            // 对角线元素
+            if sum <= 0.0 {
+                // 数值修复：使用最小值
+                l[i * d + i] = float::sqrt(safe_lambda);
             } else {
-                // 非对角线元素
-                let diag = l[j * d + j];
-                if diag.abs() > EPSILON {
-                    l[i * d + j] = sum / diag;
-                } else {
-                    l[i * d + j] = 0.0;
-                }
+                l[i * d + i] = float::sqrt(sum);
+            }
+        } else {
+            // 非对角线元素
+            let diag = l[j * d + j];
+            if diag.abs() > EPSILON {
+                l[i * d + j] = sum / diag;
+            } else {
+                l[i * d + j] = 0.0;
             }
         }
     }
-
-    l
 }
 
 /// Cholesky Rank-1 更新 - 使用 Givens 旋转
@@ -49,15 +62,26 @@ pub fn cholesky_decompose(a: &[f64], d: usize, lambda: f64) -> Vec<f64> {
 ///
 /// 返回 true 如果更新成功，false 如果需要完整重算
 pub fn cholesky_rank1_update(l: &mut [f64], x: &[f64], d: usize, min_diag: f64) -> bool {
+    cholesky_rank1_update_from(l, &mut x.to_vec(), d, min_diag, 0)
+}
+
+/// 从第 `start` 列开始的 Givens 旋转；要求 `x_work[..start]` 全为零，
+/// 此时前 `start` 列的旋转是恒等变换，可以直接跳过。`x_work` 会被改写
+pub(crate) fn cholesky_rank1_update_from(
+    l: &mut [f64],
+    x_work: &mut [f64],
+    d: usize,
+    min_diag: f64,
+    start: usize,
+) -> bool {
     let safe_min_diag = min_diag.max(MIN_RANK1_DIAG);
-    let mut x_work = x.to_vec();
 
-    for k in 0..d {
+    for k in start..d {
         let l_kk = l[k * d + k];
         let x_k = x_work[k];
 
         // 计算 Givens 旋转参数
-        let r = (l_kk * l_kk + x_k * x_k).sqrt();
+        let r = float::sqrt(l_kk * l_kk + x_k * x_k);
 
         if r < safe_min_diag {
             // 数值不稳定，需要完整重算
@@ -103,11 +127,21 @@ pub fn solve_cholesky(l: &[f64], b: &[f64], d: usize) -> Vec<f64> {
 
 /// 求解下三角系统 L * x = b (前向替换)
 pub fn solve_triangular_lower(l: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    solve_triangular_lower_from(l, b, n, 0)
+}
+
+/// 前向替换；要求 `b[..start]` 全为零，此时 `x[..start]` 也为零
+pub(crate) fn solve_triangular_lower_from(
+    l: &[f64],
+    b: &[f64],
+    n: usize,
+    start: usize,
+) -> Vec<f64> {
     let mut x = vec![0.0; n];
 
-    for i in 0..n {
+    for i in start..n {
         let mut sum = b[i];
-        for j in 0..i {
+        for j in start..i {
             sum -= l[i * n + j] * x[j];
         }
 
@@ -156,7 +190,7 @@ pub fn compute_quadratic_form(l: &[f64], x: &[f64], d: usize) -> f64 {
 /// 返回 sqrt(x^T * A^{-1} * x)，其中 A^{-1} 通过 Cholesky 分解 L 表示
 /// 这是 UCB 算法中用于计算探索项的关键函数
 pub fn compute_confidence_width(l: &[f64], x: &[f64], d: usize) -> f64 {
-    compute_quadratic_form(l, x, d).sqrt()
+    float::sqrt(compute_quadratic_form(l, x, d))
 }
 
 /// 矩阵向量乘法 (行优先存储)
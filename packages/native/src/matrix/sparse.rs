@@ -0,0 +1,273 @@
+//! 稀疏特征向量与对应的稀疏感知矩阵运算
+//!
+//! one-hot 上下文特征展开后大部分分量为零。A 与 b 的更新只触及非零分量
+//! （O(nnz²) / O(nnz)）。
+//!
+//! Cholesky 秩一更新与二次型不是稀疏算法：L 是稠密下三角，第一次旋转（或
+//! 第一步前向替换）之后工作向量即被填满，复杂度为 O((d − k₀)²)，k₀ 为第一个
+//! 非零下标。这里只跳过前导零对应的恒等部分，收益取决于 k₀ 的位置。
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::cholesky_rank1_update_from;
+use crate::types::EPSILON;
+
+/// 稀疏向量：下标严格递增、值均非零
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseVector {
+    dim: usize,
+    indices: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl SparseVector {
+    /// 由 (下标, 值) 构造；重复下标累加，零值丢弃
+    pub fn new(dim: usize, mut entries: Vec<(usize, f64)>) -> Result<Self, String> {
+        if let Some(&(index, _)) = entries.iter().find(|(i, _)| *i >= dim) {
+            return Err(format!("index {index} out of range for dimension {dim}"));
+        }
+        entries.sort_by_key(|(i, _)| *i);
+        let mut indices = Vec::with_capacity(entries.len());
+        let mut values: Vec<f64> = Vec::with_capacity(entries.len());
+        for (i, v) in entries {
+            if indices.last() == Some(&i) {
+                *values.last_mut().unwrap() += v;
+            } else {
+                indices.push(i);
+                values.push(v);
+            }
+        }
+        let mut vector = Self {
+            dim,
+            indices,
+            values,
+        };
+        vector.drop_zeros();
+        Ok(vector)
+    }
+
+    pub fn from_dense(x: &[f64]) -> Self {
+        let (indices, values) = x
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| v != 0.0)
+            .map(|(i, &v)| (i, v))
+            .unzip();
+        Self {
+            dim: x.len(),
+            indices,
+            values,
+        }
+    }
+
+    pub fn to_dense(&self) -> Vec<f64> {
+        let mut x = vec![0.0; self.dim];
+        for (&i, &v) in self.indices.iter().zip(&self.values) {
+            x[i] = v;
+        }
+        x
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn density(&self) -> f64 {
+        if self.dim == 0 {
+            return 0.0;
+        }
+        self.nnz() as f64 / self.dim as f64
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// 第一个非零下标；零向量返回维度
+    pub fn first_index(&self) -> usize {
+        self.indices.first().copied().unwrap_or(self.dim)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.indices
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+
+    /// 对每个分量就地变换，变换后为零的分量被移除
+    pub fn map_values(&mut self, f: impl Fn(f64) -> f64) {
+        for v in &mut self.values {
+            *v = f(*v);
+        }
+        self.drop_zeros();
+    }
+
+    fn drop_zeros(&mut self) {
+        let mut k = 0;
+        for j in 0..self.indices.len() {
+            if self.values[j] != 0.0 {
+                self.indices[k] = self.indices[j];
+                self.values[k] = self.values[j];
+                k += 1;
+            }
+        }
+        self.indices.truncate(k);
+        self.values.truncate(k);
+    }
+}
+
+/// 外积更新: A += x * x^T，只触及非零分量
+pub fn sparse_rank1_update_matrix(a: &mut [f64], x: &SparseVector, d: usize) {
+    for (i, xi) in x.iter() {
+        for (j, xj) in x.iter() {
+            a[i * d + j] += xi * xj;
+        }
+    }
+}
+
+/// 向量加法: a += scale * x
+pub fn sparse_vec_add_scaled(a: &mut [f64], x: &SparseVector, scale: f64) {
+    for (i, v) in x.iter() {
+        a[i] += scale * v;
+    }
+}
+
+/// 稠密向量与稀疏向量的点积
+pub fn sparse_dot(a: &[f64], x: &SparseVector) -> f64 {
+    x.iter().map(|(i, v)| a[i] * v).sum()
+}
+
+/// Cholesky 秩一更新，从第一个非零下标开始旋转
+///
+/// 旋转会把 x 填满，因此先展开为稠密工作向量；只省去前 k₀ 步恒等旋转。
+pub fn sparse_cholesky_rank1_update(
+    l: &mut [f64],
+    x: &SparseVector,
+    d: usize,
+    min_diag: f64,
+) -> bool {
+    cholesky_rank1_update_from(l, &mut x.to_dense(), d, min_diag, x.first_index())
+}
+
+/// x^T * A^{-1} * x = ||L^{-1} * x||^2，前向替换从第一个非零下标开始
+///
+/// z = L^{-1} x 在 k₀ 之后一般是稠密的，只为后缀 [k₀, d) 分配；右端项按
+/// 下标游标直接读取稀疏分量，不展开 x。
+pub fn sparse_quadratic_form(l: &[f64], x: &SparseVector, d: usize) -> f64 {
+    let start = x.first_index();
+    if start >= d {
+        return 0.0;
+    }
+    let mut z = vec![0.0; d - start];
+    let mut entries = x.iter().peekable();
+    let mut total = 0.0;
+    for i in start..d {
+        let mut sum = match entries.peek() {
+            Some(&(j, v)) if j == i => {
+                entries.next();
+                v
+            }
+            _ => 0.0,
+        };
+        for j in start..i {
+            sum -= l[i * d + j] * z[j - start];
+        }
+        let diag = l[i * d + i];
+        let zi = if diag.abs() > EPSILON {
+            sum / diag
+        } else {
+            0.0
+        };
+        z[i - start] = zi;
+        total += zi * zi;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::{
+        cholesky_decompose, cholesky_rank1_update, compute_quadratic_form, rank1_update_matrix,
+    };
+    use crate::types::MIN_RANK1_DIAG;
+
+    fn spd(d: usize) -> Vec<f64> {
+        let mut a = vec![0.0; d * d];
+        for i in 0..d {
+            a[i * d + i] = 2.0;
+            if i + 1 < d {
+                a[i * d + i + 1] = 0.5;
+                a[(i + 1) * d + i] = 0.5;
+            }
+        }
+        a
+    }
+
+    #[test]
+    fn test_new_merges_and_validates() {
+        let x = SparseVector::new(5, vec![(3, 1.0), (1, 2.0), (3, 0.5), (4, 0.0)]).unwrap();
+        assert_eq!(x.indices(), &[1, 3]);
+        assert_eq!(x.values(), &[2.0, 1.5]);
+        assert_eq!(x.to_dense(), vec![0.0, 2.0, 0.0, 1.5, 0.0]);
+        assert!(SparseVector::new(3, vec![(3, 1.0)]).is_err());
+        assert_eq!(SparseVector::from_dense(&x.to_dense()), x);
+    }
+
+    #[test]
+    fn test_rank1_update_matches_dense() {
+        let d = 6;
+        let dense = vec![0.0, 0.0, 1.0, 0.0, -0.5, 0.0];
+        let x = SparseVector::from_dense(&dense);
+
+        let mut a_dense = spd(d);
+        let mut a_sparse = a_dense.clone();
+        rank1_update_matrix(&mut a_dense, &dense, d);
+        sparse_rank1_update_matrix(&mut a_sparse, &x, d);
+        assert_eq!(a_dense, a_sparse);
+
+        let mut l_dense = cholesky_decompose(&spd(d), d, 1.0);
+        let mut l_sparse = l_dense.clone();
+        assert!(cholesky_rank1_update(
+            &mut l_dense,
+            &dense,
+            d,
+            MIN_RANK1_DIAG
+        ));
+        assert!(sparse_cholesky_rank1_update(
+            &mut l_sparse,
+            &x,
+            d,
+            MIN_RANK1_DIAG
+        ));
+        for (a, b) in l_dense.iter().zip(&l_sparse) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_quadratic_form_matches_dense() {
+        let d = 6;
+        let l = cholesky_decompose(&spd(d), d, 1.0);
+        let dense = vec![0.0, 0.0, 0.0, 1.0, 0.0, 2.0];
+        let x = SparseVector::from_dense(&dense);
+        let expected = compute_quadratic_form(&l, &dense, d);
+        assert!((sparse_quadratic_form(&l, &x, d) - expected).abs() < 1e-12);
+        assert_eq!(
+            sparse_quadratic_form(&l, &SparseVector::from_dense(&[0.0; 6]), d),
+            0.0
+        );
+    }
+}
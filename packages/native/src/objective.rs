@@ -0,0 +1,274 @@
+//! 多目标会话评分：记忆收益、时间成本与挫败风险
+//!
+//! 每个候选条目给出三个分量，均归一到 [0, 1]：
+//!
+//! - 记忆收益：复习词为 1 − 回忆概率（越接近遗忘，此时复习挽回越多），
+//!   新词为 `new_word_gain`；
+//! - 时间成本：新词按 `new_word_seconds`、复习词按 `review_seconds` 估计，
+//!   再乘以 1 + 失败概率（答错需要重来），除以最大可能值；
+//! - 挫败风险：失败概率 × (1 + 疲劳度)，截断到 1。
+//!
+//! 失败概率取 1 − 回忆概率；没有回忆概率时取难度，两者都没有时取 0.5。
+//!
+//! 三个分量按权重标量化为一个分数：
+//!
+//! ```text
+//! score = (1 − t)·w_r·收益 − t·(w_t·时间 + w_f·挫败)
+//! ```
+//!
+//! t 为帕累托权衡参数：0 只看记忆收益（强化训练），1 只看轻松（省时、少受挫），
+//! 中间值沿前沿滑动，调“会话手感”不必改代码。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::policy::SessionItem;
+
+/// 无法估计失败概率时的取值
+const UNKNOWN_FAILURE: f64 = 0.5;
+
+/// 目标权重与权衡参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionObjective {
+    pub retention_weight: f64,
+    pub time_weight: f64,
+    pub frustration_weight: f64,
+    /// 帕累托权衡 [0, 1]：0 偏向记忆收益，1 偏向省时与少挫败
+    pub tradeoff: f64,
+    /// 新词的预计作答秒数
+    pub new_word_seconds: f64,
+    /// 复习词的预计作答秒数
+    pub review_seconds: f64,
+    /// 新词的记忆收益 [0, 1]
+    pub new_word_gain: f64,
+}
+
+impl Default for SessionObjective {
+    fn default() -> Self {
+        Self {
+            retention_weight: 1.0,
+            time_weight: 0.5,
+            frustration_weight: 1.0,
+            tradeoff: 0.3,
+            new_word_seconds: 12.0,
+            review_seconds: 6.0,
+            new_word_gain: 0.5,
+        }
+    }
+}
+
+/// 单个条目的评分
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectiveScore {
+    pub word_id: String,
+    pub retention_gain: f64,
+    pub time_cost: f64,
+    pub frustration_risk: f64,
+    pub score: f64,
+}
+
+impl SessionObjective {
+    /// 参数错误列表；为空表示有效
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (name, value) in [
+            ("retentionWeight", self.retention_weight),
+            ("timeWeight", self.time_weight),
+            ("frustrationWeight", self.frustration_weight),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                errors.push(format!("{name} 必须是非负数"));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.tradeoff) {
+            errors.push("tradeoff 必须在 0 到 1 之间".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.new_word_gain) {
+            errors.push("newWordGain 必须在 0 到 1 之间".to_string());
+        }
+        for (name, value) in [
+            ("newWordSeconds", self.new_word_seconds),
+            ("reviewSeconds", self.review_seconds),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                errors.push(format!("{name} 必须是正数"));
+            }
+        }
+        errors
+    }
+
+    /// 以 `tradeoff` 替换权衡参数（截断到 [0, 1]）
+    pub fn with_tradeoff(self, tradeoff: f64) -> Self {
+        Self {
+            tradeoff: if tradeoff.is_finite() {
+                tradeoff.clamp(0.0, 1.0)
+            } else {
+                self.tradeoff
+            },
+            ..self
+        }
+    }
+
+    /// 给一个条目评分；`fatigue` 为当前疲劳度 [0, 1]
+    pub fn score(&self, item: &SessionItem, fatigue: f64) -> ObjectiveScore {
+        let fatigue = if fatigue.is_finite() {
+            fatigue.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let recall = item
+            .recall_probability
+            .filter(|p| p.is_finite())
+            .map(|p| p.clamp(0.0, 1.0));
+        let failure = recall.map(|p| 1.0 - p).unwrap_or_else(|| {
+            item.difficulty
+                .filter(|d| d.is_finite())
+                .map_or(UNKNOWN_FAILURE, |d| d.clamp(0.0, 1.0))
+        });
+
+        let retention_gain = if item.is_new {
+            self.new_word_gain
+        } else {
+            failure
+        };
+        let seconds = if item.is_new {
+            self.new_word_seconds
+        } else {
+            self.review_seconds
+        };
+        let max_seconds = self.new_word_seconds.max(self.review_seconds).max(1e-9);
+        let time_cost = (seconds * (1.0 + failure) / (2.0 * max_seconds)).clamp(0.0, 1.0);
+        let frustration_risk = (failure * (1.0 + fatigue)).min(1.0);
+
+        let t = self.tradeoff.clamp(0.0, 1.0);
+        let score = (1.0 - t) * self.retention_weight * retention_gain
+            - t * (self.time_weight * time_cost + self.frustration_weight * frustration_risk);
+        ObjectiveScore {
+            word_id: item.word_id.clone(),
+            retention_gain,
+            time_cost,
+            frustration_risk,
+            score,
+        }
+    }
+
+    /// 按分数从高到低排列；同分保持原顺序
+    pub fn rank(&self, items: &[SessionItem], fatigue: f64) -> Vec<SessionItem> {
+        let mut scored: Vec<(f64, &SessionItem)> = items
+            .iter()
+            .map(|item| (self.score(item, fatigue).score, item))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, item)| item.clone()).collect()
+    }
+}
+
+/// 为候选条目逐个评分
+#[cfg_attr(feature = "napi", napi)]
+pub fn score_session_items(
+    items: Vec<SessionItem>,
+    objective: SessionObjective,
+    fatigue: f64,
+) -> Vec<ObjectiveScore> {
+    items
+        .iter()
+        .map(|item| objective.score(item, fatigue))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(id: &str, recall: f64) -> SessionItem {
+        SessionItem {
+            word_id: id.to_string(),
+            is_new: false,
+            failed: false,
+            difficulty: None,
+            confusable_with: None,
+            recall_probability: Some(recall),
+        }
+    }
+
+    fn order(items: &[SessionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.word_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_tradeoff_moves_along_the_front() {
+        let items = vec![
+            review("easy", 0.95),
+            review("mid", 0.6),
+            review("hard", 0.2),
+        ];
+
+        let retention = SessionObjective {
+            tradeoff: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            order(&retention.rank(&items, 0.0)),
+            vec!["hard", "mid", "easy"]
+        );
+
+        let comfort = SessionObjective {
+            tradeoff: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            order(&comfort.rank(&items, 0.0)),
+            vec!["easy", "mid", "hard"]
+        );
+    }
+
+    #[test]
+    fn test_fatigue_raises_frustration_risk() {
+        let objective = SessionObjective::default();
+        let item = review("w", 0.6);
+        let rested = objective.score(&item, 0.0);
+        let tired = objective.score(&item, 1.0);
+        assert!(tired.frustration_risk > rested.frustration_risk);
+        assert!(tired.score < rested.score);
+        assert_eq!(tired.retention_gain, rested.retention_gain);
+    }
+
+    #[test]
+    fn test_components_stay_normalized() {
+        let objective = SessionObjective::default();
+        let new_word = SessionItem {
+            is_new: true,
+            recall_probability: None,
+            difficulty: Some(0.9),
+            ..review("n", 0.0)
+        };
+        for item in [new_word, review("r", 0.0), review("s", 1.0)] {
+            let s = objective.score(&item, 1.0);
+            for v in [s.retention_gain, s.time_cost, s.frustration_risk] {
+                assert!((0.0..=1.0).contains(&v), "{v}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_and_override() {
+        assert!(SessionObjective::default().validate().is_empty());
+        let bad = SessionObjective {
+            tradeoff: 1.5,
+            time_weight: -1.0,
+            review_seconds: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(bad.validate().len(), 3);
+
+        let objective = SessionObjective::default().with_tradeoff(2.0);
+        assert_eq!(objective.tradeoff, 1.0);
+        let objective = objective.with_tradeoff(f64::NAN);
+        assert_eq!(objective.tradeoff, 1.0);
+    }
+}
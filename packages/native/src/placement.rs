@@ -0,0 +1,438 @@
+//! 新用户分级测试
+//!
+//! 在一组已标定难度 β（IRT 尺度，约 [-3, 3]）的词上做自适应测试，估计学习者
+//! 能力 θ，用来初始化掌握度与 ACT-R 参数：
+//!
+//! - 作答模型为 Rasch（1PL）：P(正确 | θ, β) = σ(θ − β)；
+//! - θ 用 EAP 估计：在 [-4, 4] 网格上对正态先验 N(μ₀, σ₀²) 与似然求后验，
+//!   取均值为 θ、标准差为 SE；
+//! - 下一题选未作答词中 Fisher 信息 P(1 − P) 最大者，即 β 最接近 θ 的词；
+//! - 答满 `min_items` 且 SE ≤ `target_se`，或答满 `max_items`，或题库用尽时停止。
+//!
+//! 结果给出各难度段的预期掌握度（后验平均的正确概率）与 ACT-R 参数先验：
+//! 能力越强衰减越慢、回忆阈值越低。会话可序列化，便于服务端与离线端保存进度。
+
+use serde::{Deserialize, Serialize};
+
+use crate::mastery::MasteryBandConfig;
+
+const GRID_MIN: f64 = -4.0;
+const GRID_MAX: f64 = 4.0;
+const GRID_POINTS: usize = 161;
+
+/// θ 每增加 1，ACT-R 衰减率减少的量
+const DECAY_PER_THETA: f64 = 0.05;
+/// θ 每增加 1，回忆阈值降低的量
+const THRESHOLD_PER_THETA: f64 = 0.1;
+const DECAY_MIN: f64 = 0.3;
+const DECAY_MAX: f64 = 0.7;
+
+/// 标定词
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementItem {
+    pub word_id: String,
+    /// IRT 难度
+    pub beta: f64,
+}
+
+/// 测试参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlacementConfig {
+    pub min_items: u32,
+    pub max_items: u32,
+    /// 目标标准误，达到后可提前停止
+    pub target_se: f64,
+    /// 能力先验均值 μ₀
+    pub prior_mean: f64,
+    /// 能力先验标准差 σ₀
+    pub prior_sd: f64,
+    /// 难度分段边界（升序），n 个边界分出 n + 1 段
+    pub band_edges: Vec<f64>,
+}
+
+impl Default for PlacementConfig {
+    fn default() -> Self {
+        Self {
+            min_items: 6,
+            max_items: 20,
+            target_se: 0.4,
+            prior_mean: 0.0,
+            prior_sd: 1.0,
+            band_edges: vec![-1.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// 一次作答
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementResponse {
+    pub word_id: String,
+    pub beta: f64,
+    pub correct: bool,
+}
+
+/// 某难度段的预期掌握度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandMastery {
+    pub band: u32,
+    /// 段下界；最低段为 None
+    pub lower_beta: Option<f64>,
+    /// 段上界；最高段为 None
+    pub upper_beta: Option<f64>,
+    pub mastery: f64,
+}
+
+/// 测试结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementResult {
+    pub theta: f64,
+    pub se: f64,
+    pub items_answered: u32,
+    /// 是否因 SE 达标而停止
+    pub converged: bool,
+    pub bands: Vec<BandMastery>,
+    /// ACT-R 参数先验
+    pub actr: MasteryBandConfig,
+}
+
+/// 推进一步后的状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum PlacementStep {
+    Next { item: PlacementItem },
+    Done { result: PlacementResult },
+}
+
+/// 进行中的分级测试
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementSession {
+    pub config: PlacementConfig,
+    pub responses: Vec<PlacementResponse>,
+    pub theta: f64,
+    pub se: f64,
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn grid() -> impl Iterator<Item = f64> {
+    let step = (GRID_MAX - GRID_MIN) / (GRID_POINTS - 1) as f64;
+    (0..GRID_POINTS).map(move |i| GRID_MIN + step * i as f64)
+}
+
+/// 归一化的后验权重，与 `grid()` 一一对应
+fn posterior(responses: &[PlacementResponse], config: &PlacementConfig) -> Vec<f64> {
+    let sd = if config.prior_sd > 0.0 {
+        config.prior_sd
+    } else {
+        1.0
+    };
+    let log_weights: Vec<f64> = grid()
+        .map(|theta| {
+            let z = (theta - config.prior_mean) / sd;
+            let prior = -0.5 * z * z;
+            let likelihood: f64 = responses
+                .iter()
+                .filter(|r| r.beta.is_finite())
+                .map(|r| {
+                    let p = sigmoid(theta - r.beta).clamp(1e-12, 1.0 - 1e-12);
+                    if r.correct {
+                        p.ln()
+                    } else {
+                        (1.0 - p).ln()
+                    }
+                })
+                .sum();
+            prior + likelihood
+        })
+        .collect();
+    let max = log_weights
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = log_weights.iter().map(|w| (w - max).exp()).collect();
+    let total: f64 = weights.iter().sum();
+    weights.into_iter().map(|w| w / total).collect()
+}
+
+/// EAP 估计，返回 (θ, SE)
+pub fn estimate_ability(responses: &[PlacementResponse], config: &PlacementConfig) -> (f64, f64) {
+    let weights = posterior(responses, config);
+    let mean: f64 = grid().zip(&weights).map(|(t, w)| t * w).sum();
+    let var: f64 = grid()
+        .zip(&weights)
+        .map(|(t, w)| (t - mean).powi(2) * w)
+        .sum();
+    (mean, var.max(0.0).sqrt())
+}
+
+/// 从候选词中按难度均匀抽取 `size` 个作为标定集
+pub fn select_calibration_set(candidates: &[PlacementItem], size: usize) -> Vec<PlacementItem> {
+    let mut sorted: Vec<&PlacementItem> =
+        candidates.iter().filter(|c| c.beta.is_finite()).collect();
+    sorted.sort_by(|a, b| {
+        a.beta
+            .total_cmp(&b.beta)
+            .then_with(|| a.word_id.cmp(&b.word_id))
+    });
+    sorted.dedup_by(|a, b| a.word_id == b.word_id);
+    if size == 0 || sorted.is_empty() {
+        return Vec::new();
+    }
+    if sorted.len() <= size {
+        return sorted.into_iter().cloned().collect();
+    }
+    let last = (sorted.len() - 1) as f64;
+    let mut picked: Vec<PlacementItem> = Vec::with_capacity(size);
+    for k in 0..size {
+        let pos = if size == 1 {
+            last / 2.0
+        } else {
+            last * k as f64 / (size - 1) as f64
+        };
+        let item = sorted[pos.round() as usize];
+        if picked
+            .last()
+            .map(|p| p.word_id != item.word_id)
+            .unwrap_or(true)
+        {
+            picked.push(item.clone());
+        }
+    }
+    picked
+}
+
+impl PlacementSession {
+    pub fn new(config: PlacementConfig) -> Self {
+        let (theta, se) = estimate_ability(&[], &config);
+        Self {
+            config,
+            responses: Vec::new(),
+            theta,
+            se,
+        }
+    }
+
+    pub fn items_answered(&self) -> u32 {
+        self.responses.len() as u32
+    }
+
+    fn converged(&self) -> bool {
+        self.items_answered() >= self.config.min_items && self.se <= self.config.target_se
+    }
+
+    /// 是否满足停止条件（不含题库用尽）
+    pub fn is_finished(&self) -> bool {
+        self.converged() || self.items_answered() >= self.config.max_items
+    }
+
+    fn answered(&self, word_id: &str) -> bool {
+        self.responses.iter().any(|r| r.word_id == word_id)
+    }
+
+    /// 信息量最大的未作答词；已停止或题库用尽时为 None
+    pub fn next_item<'a>(&self, pool: &'a [PlacementItem]) -> Option<&'a PlacementItem> {
+        if self.is_finished() {
+            return None;
+        }
+        pool.iter()
+            .filter(|item| item.beta.is_finite() && !self.answered(&item.word_id))
+            .min_by(|a, b| {
+                (a.beta - self.theta)
+                    .abs()
+                    .total_cmp(&(b.beta - self.theta).abs())
+                    .then_with(|| a.word_id.cmp(&b.word_id))
+            })
+    }
+
+    /// 下一题，或停止时的结果
+    pub fn step(&self, pool: &[PlacementItem]) -> PlacementStep {
+        match self.next_item(pool) {
+            Some(item) => PlacementStep::Next { item: item.clone() },
+            None => PlacementStep::Done {
+                result: self.result(),
+            },
+        }
+    }
+
+    /// 记录一次作答并更新估计；重复作答同一词时忽略并返回 false
+    pub fn record(&mut self, item: &PlacementItem, correct: bool) -> bool {
+        if !item.beta.is_finite() || self.answered(&item.word_id) {
+            return false;
+        }
+        self.responses.push(PlacementResponse {
+            word_id: item.word_id.clone(),
+            beta: item.beta,
+            correct,
+        });
+        let (theta, se) = estimate_ability(&self.responses, &self.config);
+        self.theta = theta;
+        self.se = se;
+        true
+    }
+
+    /// 当前估计下的结果；测试中途调用也有效
+    pub fn result(&self) -> PlacementResult {
+        let weights = posterior(&self.responses, &self.config);
+        let mut edges: Vec<f64> = self
+            .config
+            .band_edges
+            .iter()
+            .copied()
+            .filter(|e| e.is_finite())
+            .collect();
+        edges.sort_by(f64::total_cmp);
+        edges.dedup();
+
+        let bands = (0..=edges.len())
+            .map(|band| {
+                let lower = band.checked_sub(1).map(|i| edges[i]);
+                let upper = edges.get(band).copied();
+                // 开放段取边界外 1 个单位作为代表难度
+                let beta = match (lower, upper) {
+                    (Some(l), Some(u)) => (l + u) / 2.0,
+                    (Some(l), None) => l + 1.0,
+                    (None, Some(u)) => u - 1.0,
+                    (None, None) => 0.0,
+                };
+                let mastery: f64 = grid()
+                    .zip(&weights)
+                    .map(|(t, w)| sigmoid(t - beta) * w)
+                    .sum();
+                BandMastery {
+                    band: band as u32,
+                    lower_beta: lower,
+                    upper_beta: upper,
+                    mastery,
+                }
+            })
+            .collect();
+
+        let base = MasteryBandConfig::default();
+        let actr = MasteryBandConfig {
+            decay: (base.decay - DECAY_PER_THETA * self.theta).clamp(DECAY_MIN, DECAY_MAX),
+            threshold: base.threshold - THRESHOLD_PER_THETA * self.theta,
+            ..base
+        };
+
+        PlacementResult {
+            theta: self.theta,
+            se: self.se,
+            items_answered: self.items_answered(),
+            converged: self.converged(),
+            bands,
+            actr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::RngFactory;
+
+    fn pool() -> Vec<PlacementItem> {
+        (0..40)
+            .map(|i| PlacementItem {
+                word_id: format!("w{i:02}"),
+                beta: -3.0 + 6.0 * i as f64 / 39.0,
+            })
+            .collect()
+    }
+
+    fn run(true_theta: f64, seed: u64) -> PlacementResult {
+        let pool = pool();
+        let mut rng = RngFactory::new(seed).stream("placement.test");
+        let mut session = PlacementSession::new(PlacementConfig::default());
+        loop {
+            match session.step(&pool) {
+                PlacementStep::Next { item } => {
+                    let correct = rng.next_f64() < sigmoid(true_theta - item.beta);
+                    assert!(session.record(&item, correct));
+                }
+                PlacementStep::Done { result } => return result,
+            }
+        }
+    }
+
+    #[test]
+    fn test_prior_only_estimate() {
+        let config = PlacementConfig::default();
+        let (theta, se) = estimate_ability(&[], &config);
+        assert!(theta.abs() < 1e-9);
+        assert!((se - 1.0).abs() < 0.01, "se = {se}");
+    }
+
+    #[test]
+    fn test_first_item_is_most_informative_and_not_repeated() {
+        let pool = pool();
+        let mut session = PlacementSession::new(PlacementConfig::default());
+        let first = session.next_item(&pool).unwrap().clone();
+        assert!(first.beta.abs() < 0.1);
+        assert!(session.record(&first, true));
+        assert!(!session.record(&first, false));
+        assert!(session.theta > 0.0);
+        let second = session.next_item(&pool).unwrap();
+        assert_ne!(second.word_id, first.word_id);
+        assert!(second.beta > first.beta);
+    }
+
+    #[test]
+    fn test_recovers_ability_and_stops() {
+        for (true_theta, seed) in [(-1.5, 1), (0.0, 2), (1.5, 3)] {
+            let result = run(true_theta, seed);
+            assert!(result.items_answered >= 6 && result.items_answered <= 20);
+            assert!(
+                (result.theta - true_theta).abs() < 1.0,
+                "theta {} vs {true_theta}",
+                result.theta
+            );
+            assert!(result.converged || result.items_answered == 20);
+        }
+    }
+
+    #[test]
+    fn test_band_mastery_and_actr_priors() {
+        let strong = run(1.5, 7);
+        let weak = run(-1.5, 7);
+        assert_eq!(strong.bands.len(), 4);
+        assert_eq!(strong.bands[0].lower_beta, None);
+        assert_eq!(strong.bands[3].upper_beta, None);
+        for w in strong.bands.windows(2) {
+            assert!(w[0].mastery > w[1].mastery);
+        }
+        assert!(strong.bands[1].mastery > weak.bands[1].mastery);
+        assert!(strong.actr.decay < weak.actr.decay);
+        assert!(strong.actr.threshold < weak.actr.threshold);
+    }
+
+    #[test]
+    fn test_pool_exhaustion_and_calibration_set() {
+        let set = select_calibration_set(&pool(), 5);
+        assert_eq!(set.len(), 5);
+        assert_eq!(set[0].word_id, "w00");
+        assert_eq!(set[4].word_id, "w39");
+
+        let mut session = PlacementSession::new(PlacementConfig::default());
+        for item in &set {
+            session.record(item, true);
+        }
+        match session.step(&set) {
+            PlacementStep::Done { result } => assert_eq!(result.items_answered, 5),
+            PlacementStep::Next { .. } => panic!("pool should be exhausted"),
+        }
+
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: PlacementSession = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.items_answered(), 5);
+        assert!((restored.theta - session.theta).abs() < 1e-12);
+        assert_eq!(restored.is_finished(), session.is_finished());
+    }
+}
@@ -0,0 +1,931 @@
+//! 教学策略约束 DSL
+//!
+//! 规则以 JSON 数组描述，例如：
+//!
+//! ```json
+//! [
+//!   { "type": "maxConsecutiveNew", "limit": 3 },
+//!   { "type": "reviewFailedWithin", "within": 5 },
+//!   { "type": "maxSameDifficultyRun", "limit": 2, "bandWidth": 0.2 }
+//! ]
+//! ```
+//!
+//! 会话编排器先按优先级贪心排出队列；仍有约束不满足时，再用带步数上限的
+//! 回溯排列器在同一批条目上寻找满足全部顺序约束的排列，并报告无法满足的约束。
+//! 条目带有易混词时，最后在不增加违规的前提下交换位置，降低干扰代价
+//! （每对易混词计 1 / 间距，相邻为 1）。
+//!
+//! 给出 [`SessionObjective`] 时，候选先按多目标分数重新排序（见
+//! [`crate::objective`]），再交给上述流程，约束仍然优先于分数。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::objective::SessionObjective;
+
+/// 单条教学约束
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", deny_unknown_fields)]
+pub enum PolicyRule {
+    /// 连续新词不超过 limit 个
+    MaxConsecutiveNew { limit: u32 },
+    /// 每个会话新词总数不超过 limit 个
+    MaxNewPerSession { limit: u32 },
+    /// 答错的词必须在之后 within 个位置内再次复习
+    ReviewFailedWithin { within: u32 },
+    /// 同一个词两次出现之间至少间隔 gap 个其他条目
+    MinRepeatGap { gap: u32 },
+    /// 同一难度档连续不超过 limit 个（难度按 bandWidth 分档）
+    #[serde(rename_all = "camelCase")]
+    MaxSameDifficultyRun {
+        limit: u32,
+        #[serde(default = "default_band_width")]
+        band_width: f64,
+    },
+}
+
+fn default_band_width() -> f64 {
+    0.2
+}
+
+/// 回溯排列器的最大步数（入栈与回退各计一步），超出后放弃回溯
+const ARRANGE_STEP_BUDGET: usize = 20_000;
+
+/// 降低干扰代价的交换最多扫描几轮
+const SPREAD_PASSES: usize = 4;
+
+impl PolicyRule {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PolicyRule::MaxConsecutiveNew { .. } => "maxConsecutiveNew",
+            PolicyRule::MaxNewPerSession { .. } => "maxNewPerSession",
+            PolicyRule::ReviewFailedWithin { .. } => "reviewFailedWithin",
+            PolicyRule::MinRepeatGap { .. } => "minRepeatGap",
+            PolicyRule::MaxSameDifficultyRun { .. } => "maxSameDifficultyRun",
+        }
+    }
+}
+
+/// 规则集（已解析）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRuleSet {
+    pub rules: Vec<PolicyRule>,
+}
+
+/// 规则校验结果
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyValidation {
+    pub valid: bool,
+    /// 单条规则自身的错误（解析失败、参数越界）
+    pub errors: Vec<String>,
+    /// 规则之间的冲突
+    pub conflicts: Vec<String>,
+}
+
+/// 会话候选条目
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionItem {
+    pub word_id: String,
+    pub is_new: bool,
+    /// 本会话中已答错，需要再次复习
+    pub failed: bool,
+    /// 难度 [0, 1]；缺省时不参与难度连续约束
+    #[serde(default)]
+    pub difficulty: Option<f64>,
+    /// 与之易混的词 id；同一会话中相距越近，干扰代价越高
+    #[serde(default)]
+    pub confusable_with: Option<Vec<String>>,
+    /// 当前回忆概率 [0, 1]；供多目标评分估计收益与失败概率
+    #[serde(default)]
+    pub recall_probability: Option<f64>,
+}
+
+/// 违反的约束
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyViolation {
+    pub rule: String,
+    pub position: u32,
+    pub word_id: String,
+    pub message: String,
+}
+
+/// 编排结果
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComposedSession {
+    pub items: Vec<SessionItem>,
+    /// 因 maxNewPerSession 被移出本次会话的词
+    pub dropped_word_ids: Vec<String>,
+    pub violations: Vec<PolicyViolation>,
+}
+
+impl PolicyRuleSet {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let rules: Vec<PolicyRule> =
+            serde_json::from_str(json).map_err(|e| format!("规则解析失败: {e}"))?;
+        Ok(Self { rules })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.rules).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    fn find<T>(&self, f: impl Fn(&PolicyRule) -> Option<T>) -> Option<T> {
+        self.rules.iter().find_map(f)
+    }
+
+    fn max_consecutive_new(&self) -> Option<u32> {
+        self.find(|r| match r {
+            PolicyRule::MaxConsecutiveNew { limit } => Some(*limit),
+            _ => None,
+        })
+    }
+
+    fn max_new_per_session(&self) -> Option<u32> {
+        self.find(|r| match r {
+            PolicyRule::MaxNewPerSession { limit } => Some(*limit),
+            _ => None,
+        })
+    }
+
+    fn review_failed_within(&self) -> Option<u32> {
+        self.find(|r| match r {
+            PolicyRule::ReviewFailedWithin { within } => Some(*within),
+            _ => None,
+        })
+    }
+
+    fn min_repeat_gap(&self) -> u32 {
+        self.find(|r| match r {
+            PolicyRule::MinRepeatGap { gap } => Some(*gap),
+            _ => None,
+        })
+        .unwrap_or(0)
+    }
+
+    fn max_same_difficulty_run(&self) -> Option<(u32, f64)> {
+        self.find(|r| match r {
+            PolicyRule::MaxSameDifficultyRun { limit, band_width } => Some((*limit, *band_width)),
+            _ => None,
+        })
+    }
+
+    /// 校验参数并检测规则之间的冲突
+    pub fn validate(&self) -> PolicyValidation {
+        let mut errors = Vec::new();
+        let mut conflicts = Vec::new();
+
+        let mut seen: HashMap<&'static str, &PolicyRule> = HashMap::new();
+        for rule in &self.rules {
+            match rule {
+                PolicyRule::MaxConsecutiveNew { limit: 0 } => {
+                    errors.push("maxConsecutiveNew.limit 必须 >= 1".to_string())
+                }
+                PolicyRule::ReviewFailedWithin { within: 0 } => {
+                    errors.push("reviewFailedWithin.within 必须 >= 1".to_string())
+                }
+                PolicyRule::MaxSameDifficultyRun { limit, band_width } => {
+                    if *limit == 0 {
+                        errors.push("maxSameDifficultyRun.limit 必须 >= 1".to_string());
+                    }
+                    if !band_width.is_finite() || *band_width <= 0.0 {
+                        errors.push("maxSameDifficultyRun.bandWidth 必须为正数".to_string());
+                    }
+                }
+                _ => {}
+            }
+            if let Some(prev) = seen.insert(rule.kind(), rule) {
+                if prev != rule {
+                    conflicts.push(format!("{} 重复定义且取值不同", rule.kind()));
+                }
+            }
+        }
+
+        if let (Some(within), gap) = (self.review_failed_within(), self.min_repeat_gap()) {
+            if gap >= within {
+                conflicts.push(format!(
+                    "minRepeatGap({gap}) 不小于 reviewFailedWithin({within})，错词无法按时复习"
+                ));
+            }
+        }
+
+        if let (Some(consecutive), Some(total)) =
+            (self.max_consecutive_new(), self.max_new_per_session())
+        {
+            if total == 0 && consecutive > 0 {
+                conflicts.push("maxNewPerSession 为 0 时 maxConsecutiveNew 不会生效".to_string());
+            }
+        }
+
+        PolicyValidation {
+            valid: errors.is_empty() && conflicts.is_empty(),
+            errors,
+            conflicts,
+        }
+    }
+
+    /// 检查给定序列违反了哪些约束
+    pub fn check(&self, items: &[SessionItem]) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let consecutive_limit = self.max_consecutive_new();
+        let total_limit = self.max_new_per_session();
+        let within = self.review_failed_within();
+        let gap = self.min_repeat_gap() as usize;
+        let difficulty_run = self.max_same_difficulty_run();
+
+        let mut streak = 0u32;
+        let mut new_total = 0u32;
+        let mut band_run: Option<(i64, u32)> = None;
+        let mut last_seen: HashMap<&str, usize> = HashMap::new();
+
+        for (pos, item) in items.iter().enumerate() {
+            let violation = |rule: &str, message: String| PolicyViolation {
+                rule: rule.to_string(),
+                position: pos as u32,
+                word_id: item.word_id.clone(),
+                message,
+            };
+
+            if item.is_new {
+                streak += 1;
+                new_total += 1;
+                if consecutive_limit.is_some_and(|limit| streak > limit) {
+                    violations.push(violation(
+                        "maxConsecutiveNew",
+                        format!("连续新词 {streak} 个"),
+                    ));
+                }
+                if total_limit.is_some_and(|limit| new_total > limit) {
+                    violations.push(violation(
+                        "maxNewPerSession",
+                        format!("新词总数 {new_total}"),
+                    ));
+                }
+            } else {
+                streak = 0;
+            }
+
+            if let Some((limit, width)) = difficulty_run {
+                band_run = match (band_of(item, width), band_run) {
+                    (Some(band), Some((prev, run))) if band == prev => Some((band, run + 1)),
+                    (Some(band), _) => Some((band, 1)),
+                    (None, _) => None,
+                };
+                if let Some((_, run)) = band_run.filter(|(_, run)| *run > limit) {
+                    violations.push(violation(
+                        "maxSameDifficultyRun",
+                        format!("同一难度档连续 {run} 个"),
+                    ));
+                }
+            }
+
+            if let Some(&prev) = last_seen.get(item.word_id.as_str()) {
+                if pos - prev - 1 < gap {
+                    violations.push(violation("minRepeatGap", format!("与位置 {prev} 间隔不足")));
+                }
+            }
+
+            if let Some(within) = within {
+                if item.failed {
+                    let deadline = pos + within as usize;
+                    let repeated = items
+                        .iter()
+                        .enumerate()
+                        .skip(pos + 1)
+                        .take_while(|(p, _)| *p <= deadline)
+                        .any(|(_, next)| next.word_id == item.word_id);
+                    if !repeated {
+                        violations.push(violation(
+                            "reviewFailedWithin",
+                            format!("错词未在 {within} 个位置内复习"),
+                        ));
+                    }
+                }
+            }
+
+            last_seen.insert(item.word_id.as_str(), pos);
+        }
+
+        violations
+    }
+
+    /// 按规则重排候选队列
+    ///
+    /// 尽量保持原有优先级顺序：新词连续过多时插入最早的复习词，答错的词在
+    /// 截止位置前重新入队。无法满足的约束写入 `violations`。
+    pub fn compose(&self, candidates: &[SessionItem]) -> ComposedSession {
+        let consecutive_limit = self.max_consecutive_new();
+        let within = self.review_failed_within();
+        let gap = self.min_repeat_gap() as usize;
+
+        let mut dropped = Vec::new();
+        let mut queue: Vec<SessionItem> = Vec::with_capacity(candidates.len());
+        let mut new_total = 0u32;
+        for item in candidates {
+            if item.is_new && self.max_new_per_session().is_some_and(|l| new_total >= l) {
+                dropped.push(item.word_id.clone());
+                continue;
+            }
+            if item.is_new {
+                new_total += 1;
+            }
+            queue.push(item.clone());
+        }
+
+        let mut out: Vec<SessionItem> = Vec::with_capacity(queue.len());
+        // (复习条目, 最早可放位置, 截止位置)
+        let mut repeats: Vec<(SessionItem, usize, usize)> = Vec::new();
+        let mut streak = 0u32;
+
+        loop {
+            let pos = out.len();
+
+            // 1. 到期的错词复习优先
+            if let Some(idx) = repeats
+                .iter()
+                .position(|(_, earliest, deadline)| *deadline <= pos && *earliest <= pos)
+            {
+                out.push(repeats.remove(idx).0);
+                streak = 0;
+                continue;
+            }
+
+            if queue.is_empty() {
+                // 队列耗尽后补上剩余的错词复习（间隔不足由 check 报告）
+                if repeats.is_empty() {
+                    break;
+                }
+                out.push(repeats.remove(0).0);
+                streak = 0;
+                continue;
+            }
+
+            // 2. 新词连续过多时，插入复习词（队列中的复习或可提前的错词复习）
+            let streak_full = consecutive_limit.is_some_and(|l| streak >= l);
+            let idx = if streak_full && queue[0].is_new {
+                if let Some(idx) = queue.iter().position(|i| !i.is_new) {
+                    idx
+                } else if let Some(ridx) =
+                    repeats.iter().position(|(_, earliest, _)| *earliest <= pos)
+                {
+                    out.push(repeats.remove(ridx).0);
+                    streak = 0;
+                    continue;
+                } else {
+                    0
+                }
+            } else {
+                0
+            };
+
+            let item = queue.remove(idx);
+            if item.is_new {
+                streak += 1;
+            } else {
+                streak = 0;
+            }
+            if item.failed {
+                if let Some(within) = within {
+                    let review = SessionItem {
+                        is_new: false,
+                        failed: false,
+                        ..item.clone()
+                    };
+                    repeats.push((review, pos + gap + 1, pos + within as usize));
+                }
+            }
+            out.push(item);
+        }
+
+        let mut violations = self.check(&out);
+        if !violations.is_empty() {
+            let arranged = self
+                .arrange(&out)
+                .unwrap_or_else(|| self.arrange_greedy(&out));
+            let arranged_violations = self.check(&arranged);
+            if arranged_violations.len() < violations.len() {
+                out = arranged;
+                violations = arranged_violations;
+            }
+        }
+        if out
+            .iter()
+            .any(|i| i.confusable_with.as_ref().is_some_and(|c| !c.is_empty()))
+        {
+            out = self.spread_confusable(out, violations.len());
+        }
+        ComposedSession {
+            items: out,
+            dropped_word_ids: dropped,
+            violations,
+        }
+    }
+
+    /// 先按多目标分数排序候选，再按规则编排；`fatigue` 为当前疲劳度 [0, 1]
+    pub fn compose_with_objective(
+        &self,
+        candidates: &[SessionItem],
+        objective: &SessionObjective,
+        fatigue: f64,
+    ) -> ComposedSession {
+        self.compose(&objective.rank(candidates, fatigue))
+    }
+
+    /// 在给定条目上寻找满足全部顺序约束的排列（回溯）
+    ///
+    /// 每个位置按原顺序（即优先级）尝试剩余条目，因此原顺序本身可行时结果不变。
+    /// 错词的截止约束只在剩余条目中还有同一个词时生效。无解或超出步数上限时
+    /// 返回 `None`。
+    pub fn arrange(&self, items: &[SessionItem]) -> Option<Vec<SessionItem>> {
+        let n = items.len();
+        let mut used = vec![false; n];
+        let mut seq: Vec<usize> = Vec::with_capacity(n);
+        // cursor[d]：第 d 个位置下一次从哪个候选开始尝试
+        let mut cursor: Vec<usize> = vec![0];
+        let mut steps = 0usize;
+
+        while seq.len() < n {
+            steps += 1;
+            if steps > ARRANGE_STEP_BUDGET {
+                return None;
+            }
+            let depth = seq.len();
+            let next = (cursor[depth]..n).find(|&c| !used[c] && self.fits(items, &seq, &used, c));
+            match next {
+                Some(c) => {
+                    cursor[depth] = c + 1;
+                    seq.push(c);
+                    used[c] = true;
+                    cursor.push(0);
+                }
+                None => {
+                    cursor.pop();
+                    let c = seq.pop()?;
+                    used[c] = false;
+                }
+            }
+        }
+
+        Some(seq.into_iter().map(|i| items[i].clone()).collect())
+    }
+
+    /// 交换位置降低干扰代价：从前往后，为每个易混条目找最近的一次能降低代价、
+    /// 且违规数不超过 `max_violations` 的交换，最多扫描 `SPREAD_PASSES` 轮
+    fn spread_confusable(
+        &self,
+        mut items: Vec<SessionItem>,
+        max_violations: usize,
+    ) -> Vec<SessionItem> {
+        let mut cost = interference_cost(&items);
+        for _ in 0..SPREAD_PASSES {
+            let mut improved = false;
+            for i in 0..items.len() {
+                if !(0..items.len()).any(|j| j != i && confusable(&items[i], &items[j])) {
+                    continue;
+                }
+                for j in (0..items.len()).filter(|&j| j != i) {
+                    items.swap(i, j);
+                    let swapped = interference_cost(&items);
+                    if swapped < cost - 1e-9 && self.check(&items).len() <= max_violations {
+                        cost = swapped;
+                        improved = true;
+                        break;
+                    }
+                    items.swap(i, j);
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+        items
+    }
+
+    /// 回溯失败时的退路：每个位置取第一个可行条目，都不可行时取第一个剩余条目
+    fn arrange_greedy(&self, items: &[SessionItem]) -> Vec<SessionItem> {
+        let n = items.len();
+        let mut used = vec![false; n];
+        let mut seq: Vec<usize> = Vec::with_capacity(n);
+        while seq.len() < n {
+            let c = (0..n)
+                .find(|&c| !used[c] && self.fits(items, &seq, &used, c))
+                .or_else(|| (0..n).find(|&c| !used[c]))
+                .unwrap_or_default();
+            seq.push(c);
+            used[c] = true;
+        }
+        seq.into_iter().map(|i| items[i].clone()).collect()
+    }
+
+    /// 把 `items[c]` 接在 `seq` 之后是否仍满足顺序约束
+    fn fits(&self, items: &[SessionItem], seq: &[usize], used: &[bool], c: usize) -> bool {
+        let item = &items[c];
+        let pos = seq.len();
+
+        if let Some(limit) = self.max_consecutive_new() {
+            let streak = seq.iter().rev().take_while(|&&i| items[i].is_new).count();
+            if item.is_new && streak as u32 >= limit {
+                return false;
+            }
+        }
+
+        if let Some((limit, width)) = self.max_same_difficulty_run() {
+            if let Some(band) = band_of(item, width) {
+                let run = seq
+                    .iter()
+                    .rev()
+                    .take_while(|&&i| band_of(&items[i], width) == Some(band))
+                    .count();
+                if run as u32 >= limit {
+                    return false;
+                }
+            }
+        }
+
+        let gap = self.min_repeat_gap() as usize;
+        if let Some(prev) = seq.iter().rposition(|&i| items[i].word_id == item.word_id) {
+            if pos - prev - 1 < gap {
+                return false;
+            }
+        }
+
+        if let Some(within) = self.review_failed_within() {
+            // 最后一个允许的位置上只能放该错词本身
+            let deadline_hit = pos.checked_sub(within as usize).map(|q| &items[seq[q]]);
+            if let Some(failed) = deadline_hit.filter(|f| f.failed && f.word_id != item.word_id) {
+                let repeated = seq[pos - within as usize + 1..]
+                    .iter()
+                    .any(|&i| items[i].word_id == failed.word_id);
+                let pending = (0..items.len())
+                    .any(|j| j != c && !used[j] && items[j].word_id == failed.word_id);
+                if !repeated && pending {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+fn confusable(a: &SessionItem, b: &SessionItem) -> bool {
+    let lists = |x: &SessionItem, y: &SessionItem| {
+        x.confusable_with
+            .as_ref()
+            .is_some_and(|c| c.contains(&y.word_id))
+    };
+    a.word_id != b.word_id && (lists(a, b) || lists(b, a))
+}
+
+/// 会话顺序的干扰代价：每对易混词计 1 / 间距（相邻为 1）
+pub fn interference_cost(items: &[SessionItem]) -> f64 {
+    let mut cost = 0.0;
+    for (i, a) in items.iter().enumerate() {
+        for (gap, b) in items[i + 1..].iter().enumerate() {
+            if confusable(a, b) {
+                cost += 1.0 / (gap + 1) as f64;
+            }
+        }
+    }
+    cost
+}
+
+fn band_of(item: &SessionItem, width: f64) -> Option<i64> {
+    item.difficulty
+        .filter(|d| d.is_finite())
+        .map(|d| (d / width).floor() as i64)
+}
+
+/// 校验 JSON 规则
+#[cfg_attr(feature = "napi", napi)]
+pub fn validate_policy_rules(rules_json: String) -> PolicyValidation {
+    match PolicyRuleSet::from_json(&rules_json) {
+        Ok(set) => set.validate(),
+        Err(e) => PolicyValidation {
+            valid: false,
+            errors: vec![e],
+            conflicts: Vec::new(),
+        },
+    }
+}
+
+/// 按 JSON 规则编排会话；规则无效时原样返回候选队列
+#[cfg_attr(feature = "napi", napi)]
+pub fn compose_session(rules_json: String, candidates: Vec<SessionItem>) -> ComposedSession {
+    match PolicyRuleSet::from_json(&rules_json) {
+        Ok(set) if set.validate().valid => set.compose(&candidates),
+        _ => ComposedSession {
+            items: candidates,
+            dropped_word_ids: Vec::new(),
+            violations: Vec::new(),
+        },
+    }
+}
+
+/// 按 JSON 规则与多目标权重编排会话；规则或权重无效时原样返回候选队列
+#[cfg_attr(feature = "napi", napi)]
+pub fn compose_session_with_objective(
+    rules_json: String,
+    candidates: Vec<SessionItem>,
+    objective: SessionObjective,
+    fatigue: f64,
+) -> ComposedSession {
+    match PolicyRuleSet::from_json(&rules_json) {
+        Ok(set) if set.validate().valid && objective.validate().is_empty() => {
+            set.compose_with_objective(&candidates, &objective, fatigue)
+        }
+        _ => ComposedSession {
+            items: candidates,
+            dropped_word_ids: Vec::new(),
+            violations: Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, is_new: bool, failed: bool) -> SessionItem {
+        SessionItem {
+            word_id: id.to_string(),
+            is_new,
+            failed,
+            difficulty: None,
+            confusable_with: None,
+            recall_probability: None,
+        }
+    }
+
+    fn rated(id: &str, difficulty: f64) -> SessionItem {
+        SessionItem {
+            difficulty: Some(difficulty),
+            ..item(id, false, false)
+        }
+    }
+
+    fn ids(items: &[SessionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.word_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        let set = PolicyRuleSet::from_json(
+            r#"[{"type":"maxConsecutiveNew","limit":3},{"type":"reviewFailedWithin","within":4}]"#,
+        )
+        .unwrap();
+        assert_eq!(set.rules.len(), 2);
+        assert!(set.validate().valid);
+
+        assert!(PolicyRuleSet::from_json(r#"[{"type":"unknownRule"}]"#).is_err());
+
+        let invalid = validate_policy_rules(r#"[{"type":"maxConsecutiveNew","limit":0}]"#.into());
+        assert!(!invalid.valid);
+        assert_eq!(invalid.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_conflict_detection() {
+        let set = PolicyRuleSet {
+            rules: vec![
+                PolicyRule::ReviewFailedWithin { within: 3 },
+                PolicyRule::MinRepeatGap { gap: 3 },
+                PolicyRule::MaxConsecutiveNew { limit: 2 },
+                PolicyRule::MaxConsecutiveNew { limit: 4 },
+            ],
+        };
+        let result = set.validate();
+        assert!(!result.valid);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.conflicts.len(), 2);
+    }
+
+    #[test]
+    fn test_compose_breaks_new_word_streaks() {
+        let set = PolicyRuleSet {
+            rules: vec![PolicyRule::MaxConsecutiveNew { limit: 2 }],
+        };
+        let candidates = vec![
+            item("n1", true, false),
+            item("n2", true, false),
+            item("n3", true, false),
+            item("n4", true, false),
+            item("r1", false, false),
+            item("r2", false, false),
+        ];
+        let composed = set.compose(&candidates);
+        let order: Vec<&str> = composed.items.iter().map(|i| i.word_id.as_str()).collect();
+        assert_eq!(order, vec!["n1", "n2", "r1", "n3", "n4", "r2"]);
+        assert!(composed.violations.is_empty());
+    }
+
+    #[test]
+    fn test_compose_requeues_failed_words() {
+        let set = PolicyRuleSet {
+            rules: vec![
+                PolicyRule::ReviewFailedWithin { within: 3 },
+                PolicyRule::MinRepeatGap { gap: 1 },
+                PolicyRule::MaxNewPerSession { limit: 3 },
+            ],
+        };
+        let candidates = vec![
+            item("f1", false, true),
+            item("a", true, false),
+            item("b", true, false),
+            item("c", true, false),
+            item("d", true, false),
+        ];
+        let composed = set.compose(&candidates);
+        let order: Vec<&str> = composed.items.iter().map(|i| i.word_id.as_str()).collect();
+        assert_eq!(order, vec!["f1", "a", "b", "f1", "c"]);
+        assert_eq!(composed.dropped_word_ids, vec!["d".to_string()]);
+        assert!(composed.violations.is_empty());
+    }
+
+    #[test]
+    fn test_check_reports_unsatisfied_rules() {
+        let set = PolicyRuleSet {
+            rules: vec![
+                PolicyRule::MaxConsecutiveNew { limit: 1 },
+                PolicyRule::ReviewFailedWithin { within: 2 },
+            ],
+        };
+        let sequence = vec![
+            item("x", false, true),
+            item("a", true, false),
+            item("b", true, false),
+            item("c", false, false),
+        ];
+        let violations = set.check(&sequence);
+        let rules: Vec<&str> = violations.iter().map(|v| v.rule.as_str()).collect();
+        assert_eq!(rules, vec!["reviewFailedWithin", "maxConsecutiveNew"]);
+    }
+
+    #[test]
+    fn test_difficulty_runs_are_interleaved() {
+        let set =
+            PolicyRuleSet::from_json(r#"[{"type":"maxSameDifficultyRun","limit":1}]"#).unwrap();
+        assert!(set.validate().valid);
+        let candidates = vec![
+            rated("e1", 0.1),
+            rated("e2", 0.15),
+            rated("e3", 0.05),
+            rated("h1", 0.9),
+            rated("h2", 0.85),
+            rated("m1", 0.5),
+        ];
+        assert_eq!(set.check(&candidates).len(), 3);
+
+        let composed = set.compose(&candidates);
+        assert!(composed.violations.is_empty());
+        assert_eq!(composed.items.len(), candidates.len());
+        // 每个位置取最靠前的可行条目
+        assert_eq!(
+            ids(&composed.items),
+            vec!["e1", "h1", "e2", "h2", "e3", "m1"]
+        );
+    }
+
+    #[test]
+    fn test_failed_word_reappears_after_gap() {
+        let set = PolicyRuleSet {
+            rules: vec![
+                PolicyRule::ReviewFailedWithin { within: 4 },
+                PolicyRule::MinRepeatGap { gap: 2 },
+                PolicyRule::MaxSameDifficultyRun {
+                    limit: 2,
+                    band_width: 0.5,
+                },
+            ],
+        };
+        let candidates = vec![
+            SessionItem {
+                failed: true,
+                ..rated("f", 0.2)
+            },
+            rated("a", 0.1),
+            rated("b", 0.3),
+            rated("c", 0.7),
+            rated("d", 0.9),
+        ];
+        let composed = set.compose(&candidates);
+        assert!(composed.violations.is_empty(), "{:?}", composed.violations);
+
+        let positions: Vec<usize> = composed
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.word_id == "f")
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(positions.len(), 2);
+        let gap = positions[1] - positions[0] - 1;
+        assert!((2..4).contains(&gap));
+    }
+
+    #[test]
+    fn test_arranger_satisfies_constraints_when_possible() {
+        let set = PolicyRuleSet {
+            rules: vec![
+                PolicyRule::MaxConsecutiveNew { limit: 1 },
+                PolicyRule::MaxSameDifficultyRun {
+                    limit: 1,
+                    band_width: 0.5,
+                },
+            ],
+        };
+        // 伪随机实例：新词与复习词、两个难度档各半，总存在满足约束的排列
+        let mut seed = 7u64;
+        for _ in 0..50 {
+            let mut candidates: Vec<SessionItem> = (0..8)
+                .map(|i| SessionItem {
+                    is_new: i % 2 == 0,
+                    difficulty: Some(if i % 4 < 2 { 0.2 } else { 0.8 }),
+                    ..item(&format!("w{i}"), false, false)
+                })
+                .collect();
+            for i in (1..candidates.len()).rev() {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                candidates.swap(i, (seed >> 33) as usize % (i + 1));
+            }
+            let arranged = set.arrange(&candidates).expect("instance is satisfiable");
+            assert!(set.check(&arranged).is_empty());
+            let mut sorted = ids(&arranged);
+            sorted.sort_unstable();
+            let mut expected = ids(&candidates);
+            expected.sort_unstable();
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn test_unsatisfiable_ordering_falls_back() {
+        let set = PolicyRuleSet {
+            rules: vec![PolicyRule::MaxSameDifficultyRun {
+                limit: 1,
+                band_width: 0.2,
+            }],
+        };
+        let candidates = vec![rated("a", 0.1), rated("b", 0.1), rated("c", 0.9)];
+        let composed = set.compose(&candidates);
+        assert_eq!(ids(&composed.items), vec!["a", "c", "b"]);
+        assert!(composed.violations.is_empty());
+
+        let stuck = vec![rated("a", 0.1), rated("b", 0.1), rated("c", 0.1)];
+        assert!(set.arrange(&stuck).is_none());
+        let composed = set.compose(&stuck);
+        assert_eq!(composed.items.len(), 3);
+        assert_eq!(composed.violations.len(), 2);
+        assert!(composed
+            .violations
+            .iter()
+            .all(|v| v.rule == "maxSameDifficultyRun"));
+
+        let invalid = validate_policy_rules(
+            r#"[{"type":"maxSameDifficultyRun","limit":2,"bandWidth":0}]"#.into(),
+        );
+        assert!(!invalid.valid);
+    }
+    #[test]
+    fn test_confusable_words_are_spread_apart() {
+        let confusable = |id: &str, with: &str| SessionItem {
+            confusable_with: Some(vec![with.to_string()]),
+            ..item(id, true, false)
+        };
+        let candidates = vec![
+            confusable("affect", "effect"),
+            item("effect", true, false),
+            item("c", false, false),
+            item("d", false, false),
+            item("e", false, false),
+        ];
+        assert_eq!(interference_cost(&candidates), 1.0);
+
+        let set = PolicyRuleSet {
+            rules: vec![PolicyRule::MaxConsecutiveNew { limit: 2 }],
+        };
+        let composed = set.compose(&candidates);
+        assert!(composed.violations.is_empty());
+        assert_eq!(composed.items.len(), 5);
+        assert!(interference_cost(&composed.items) < 0.5);
+
+        // 交换不能引入新的违规
+        let strict = PolicyRuleSet {
+            rules: vec![PolicyRule::MaxConsecutiveNew { limit: 1 }],
+        };
+        let composed = strict.compose(&candidates);
+        assert!(composed.violations.is_empty());
+        assert!(interference_cost(&composed.items) < 1.0);
+    }
+}
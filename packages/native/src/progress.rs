@@ -0,0 +1,147 @@
+//! 词书掌握进度与预计完成时间
+//!
+//! “已学 / 总数”的进度条不考虑遗忘：学过但快忘光的词也算完成。这里把每个词
+//! 当前的回忆概率 pᵢ（未学过为 0）合成期望掌握比例 Σpᵢ / N，并按学习者近期
+//! 的节奏估计达到目标比例还需要的天数：
+//!
+//! - 每学习一个未达阈值的词，其回忆概率提升到 `recall_threshold`，单词平均
+//!   增益 g 取所有未达阈值词 (θ − pᵢ) 的均值；
+//! - 缺口 = 目标比例 × N − Σpᵢ，预计天数 = 缺口 / (每日学习词数 × g)；
+//! - 节奏为 0，或目标超过所有词都达到阈值时的上限，则没有预计时间。
+//!
+//! 估计忽略学习期间的继续遗忘，偏乐观，仅用于进度展示。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// 进度参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProgressConfig {
+    /// 学习一次后词的回忆概率 θ
+    pub recall_threshold: f64,
+    /// 目标期望掌握比例 [0, 1]
+    pub target: f64,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            recall_threshold: 0.9,
+            target: 0.8,
+        }
+    }
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasteryProgress {
+    pub total_words: u32,
+    /// 期望掌握词数 Σpᵢ
+    pub expected_mastered: f64,
+    /// 期望掌握比例（百分比）
+    pub percent_complete: f64,
+    pub target_percent: f64,
+    /// 每日学习词数
+    pub daily_pace: f64,
+    /// 达到目标的预计天数；已达到为 0，无法估计时为空
+    pub eta_days: Option<f64>,
+}
+
+/// 由各词回忆概率与每日学习词数估计掌握进度；非有限的概率按 0 处理
+#[cfg_attr(feature = "napi", napi)]
+pub fn mastery_progress(
+    recalls: Vec<f64>,
+    daily_pace: f64,
+    config: Option<ProgressConfig>,
+) -> MasteryProgress {
+    let config = config.unwrap_or_default();
+    let threshold = if config.recall_threshold.is_finite() {
+        config.recall_threshold.clamp(0.0, 1.0)
+    } else {
+        0.9
+    };
+    let target = if config.target.is_finite() {
+        config.target.clamp(0.0, 1.0)
+    } else {
+        0.8
+    };
+    let daily_pace = if daily_pace.is_finite() {
+        daily_pace.max(0.0)
+    } else {
+        0.0
+    };
+
+    let recalls: Vec<f64> = recalls
+        .into_iter()
+        .map(|p| {
+            if p.is_finite() {
+                p.clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let n = recalls.len() as f64;
+    let expected: f64 = recalls.iter().sum();
+    let gains: Vec<f64> = recalls
+        .iter()
+        .filter(|p| **p < threshold)
+        .map(|p| threshold - p)
+        .collect();
+    let total_gain: f64 = gains.iter().sum();
+
+    let deficit = target * n - expected;
+    let eta_days = if deficit <= 0.0 {
+        Some(0.0)
+    } else if deficit > total_gain + 1e-9 || daily_pace <= 0.0 {
+        None
+    } else {
+        let mean_gain = total_gain / gains.len() as f64;
+        Some(deficit / (daily_pace * mean_gain))
+    };
+
+    MasteryProgress {
+        total_words: recalls.len() as u32,
+        expected_mastered: expected,
+        percent_complete: if n > 0.0 { expected / n * 100.0 } else { 0.0 },
+        target_percent: target * 100.0,
+        daily_pace,
+        eta_days,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forgotten_words_lower_progress() {
+        let fresh = mastery_progress(vec![0.95; 10], 5.0, None);
+        let fading = mastery_progress(vec![0.3; 10], 5.0, None);
+        assert!((fresh.percent_complete - 95.0).abs() < 1e-9);
+        assert_eq!(fresh.eta_days, Some(0.0));
+        assert!((fading.percent_complete - 30.0).abs() < 1e-9);
+        // 缺口 5，每词增益 0.6，每天 5 词
+        let eta = fading.eta_days.unwrap();
+        assert!((eta - 5.0 / 3.0).abs() < 1e-9, "{eta}");
+    }
+
+    #[test]
+    fn test_no_eta_without_pace_or_reachable_target() {
+        let idle = mastery_progress(vec![0.0; 20], 0.0, None);
+        assert_eq!(idle.eta_days, None);
+        let config = ProgressConfig {
+            recall_threshold: 0.7,
+            target: 0.9,
+        };
+        let capped = mastery_progress(vec![0.0; 20], 10.0, Some(config));
+        assert_eq!(capped.eta_days, None);
+        let empty = mastery_progress(Vec::new(), 3.0, None);
+        assert_eq!(empty.percent_complete, 0.0);
+        assert_eq!(empty.eta_days, Some(0.0));
+    }
+}
@@ -0,0 +1,257 @@
+//! 积压复习的救援模式规划
+//!
+//! 用户隔了很久回来时，到期词可能有上千个，按到期时间逐个复习既做不完，也
+//! 不一定先救最要紧的词。这里按“每分钟复习挽回的记忆损失”排序：
+//!
+//! - 每个词给出当前回忆概率 p₀ 与推迟一天后的回忆概率 p₁，推迟的期望损失为
+//!   max(p₀ − p₁, 0)；已经基本忘光的词推迟也损失不大，排在后面；
+//! - 优先级 = 损失 / 预计复习分钟数；
+//! - 按优先级装入一次有界的补救会话（分钟数与词数上限），其余按优先级顺序
+//!   平摊到之后 `ramp_days` 天，每天的分钟数大致相同。
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// 复习时长下限（秒），避免异常小的时长让优先级发散
+const MIN_SECONDS: f64 = 1.0;
+
+/// 规划参数
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RescueConfig {
+    /// 补救会话的分钟上限
+    pub session_minutes: f64,
+    /// 补救会话的词数上限
+    pub max_session_words: u32,
+    /// 剩余词平摊的天数
+    pub ramp_days: u32,
+    /// 未给出时长时每个词的预计复习秒数
+    pub default_seconds: f64,
+}
+
+impl Default for RescueConfig {
+    fn default() -> Self {
+        Self {
+            session_minutes: 20.0,
+            max_session_words: 100,
+            ramp_days: 7,
+            default_seconds: 12.0,
+        }
+    }
+}
+
+/// 一个到期词
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RescueCandidate {
+    pub word_id: String,
+    /// 当前回忆概率 p₀
+    pub recall: f64,
+    /// 推迟一天后的回忆概率 p₁
+    pub recall_tomorrow: f64,
+    /// 预计复习秒数；为空时用 `default_seconds`
+    pub seconds: Option<f64>,
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RescueItem {
+    pub word_id: String,
+    /// 推迟一天的期望记忆损失
+    pub expected_loss: f64,
+    pub minutes: f64,
+    /// 每分钟挽回的损失
+    pub priority: f64,
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RampDay {
+    /// 距今天数，从 1 开始
+    pub day: u32,
+    pub word_ids: Vec<String>,
+    pub minutes: f64,
+}
+
+#[cfg_attr(feature = "napi", napi(object))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RescuePlan {
+    pub overdue_words: u32,
+    /// 按优先级排列的补救会话
+    pub session: Vec<RescueItem>,
+    pub session_minutes: f64,
+    /// 补救会话挽回的期望损失
+    pub loss_avoided: f64,
+    pub ramp: Vec<RampDay>,
+}
+
+fn finite_or(value: f64, fallback: f64) -> f64 {
+    if value.is_finite() {
+        value
+    } else {
+        fallback
+    }
+}
+
+fn rank(candidates: Vec<RescueCandidate>, default_seconds: f64) -> Vec<RescueItem> {
+    let mut items: Vec<RescueItem> = candidates
+        .into_iter()
+        .map(|c| {
+            let recall = finite_or(c.recall, 0.0).clamp(0.0, 1.0);
+            let tomorrow = finite_or(c.recall_tomorrow, recall).clamp(0.0, 1.0);
+            let seconds = c
+                .seconds
+                .filter(|s| s.is_finite() && *s > 0.0)
+                .unwrap_or(default_seconds)
+                .max(MIN_SECONDS);
+            let expected_loss = (recall - tomorrow).max(0.0);
+            let minutes = seconds / 60.0;
+            RescueItem {
+                word_id: c.word_id,
+                expected_loss,
+                minutes,
+                priority: expected_loss / minutes,
+            }
+        })
+        .collect();
+    // 同优先级按 wordId 排序，保证结果稳定
+    items.sort_by(|a, b| {
+        b.priority
+            .total_cmp(&a.priority)
+            .then_with(|| a.word_id.cmp(&b.word_id))
+    });
+    items
+}
+
+/// 只按每分钟挽回的损失排序，不装会话；供只需要优先级的调用方（如到期队列）
+pub fn rank_by_priority(
+    candidates: Vec<RescueCandidate>,
+    config: &RescueConfig,
+) -> Vec<RescueItem> {
+    rank(
+        candidates,
+        finite_or(config.default_seconds, 12.0).max(MIN_SECONDS),
+    )
+}
+
+/// 对到期词排序，装入补救会话，其余平摊到之后的若干天
+#[cfg_attr(feature = "napi", napi)]
+pub fn plan_rescue(candidates: Vec<RescueCandidate>, config: Option<RescueConfig>) -> RescuePlan {
+    let config = config.unwrap_or_default();
+    let budget = finite_or(config.session_minutes, 20.0).max(0.0);
+    let default_seconds = finite_or(config.default_seconds, 12.0).max(MIN_SECONDS);
+    let overdue_words = candidates.len() as u32;
+
+    let mut session = Vec::new();
+    let mut rest = Vec::new();
+    let mut used = 0.0;
+    for item in rank(candidates, default_seconds) {
+        // 放不下的词不会挡住后面更短的词
+        if session.len() < config.max_session_words as usize && used + item.minutes <= budget {
+            used += item.minutes;
+            session.push(item);
+        } else {
+            rest.push(item);
+        }
+    }
+
+    let ramp_days = config.ramp_days.max(1);
+    let rest_minutes: f64 = rest.iter().map(|item| item.minutes).sum();
+    let per_day = rest_minutes / ramp_days as f64;
+    let mut ramp: Vec<RampDay> = Vec::new();
+    let mut scheduled = 0.0;
+    for item in rest {
+        // 第 d 天结束时累计分钟数不超过 d × per_day，最后一天收尾
+        let day = ((scheduled / per_day).floor() as u32 + 1).min(ramp_days);
+        scheduled += item.minutes;
+        match ramp.last_mut() {
+            Some(last) if last.day == day => {
+                last.word_ids.push(item.word_id);
+                last.minutes += item.minutes;
+            }
+            _ => ramp.push(RampDay {
+                day,
+                word_ids: vec![item.word_id],
+                minutes: item.minutes,
+            }),
+        }
+    }
+
+    RescuePlan {
+        overdue_words,
+        loss_avoided: session.iter().map(|item| item.expected_loss).sum(),
+        session_minutes: used,
+        session,
+        ramp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(id: &str, recall: f64, tomorrow: f64, seconds: Option<f64>) -> RescueCandidate {
+        RescueCandidate {
+            word_id: id.to_string(),
+            recall,
+            recall_tomorrow: tomorrow,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn test_ranks_by_loss_per_minute() {
+        let plan = plan_rescue(
+            vec![
+                candidate("forgotten", 0.05, 0.04, None),
+                candidate("slipping", 0.7, 0.5, None),
+                candidate("slow", 0.7, 0.5, Some(60.0)),
+                candidate("stable", 0.8, 0.78, None),
+            ],
+            None,
+        );
+        let order: Vec<&str> = plan.session.iter().map(|i| i.word_id.as_str()).collect();
+        assert_eq!(order, ["slipping", "slow", "stable", "forgotten"]);
+        assert!((plan.loss_avoided - 0.43).abs() < 1e-9);
+        assert!(plan.ramp.is_empty());
+    }
+
+    #[test]
+    fn test_session_is_bounded_and_rest_spread_evenly() {
+        let candidates: Vec<_> = (0..100)
+            .map(|i| {
+                candidate(
+                    &format!("w{i:03}"),
+                    0.9,
+                    0.9 - i as f64 / 1000.0,
+                    Some(30.0),
+                )
+            })
+            .collect();
+        let config = RescueConfig {
+            session_minutes: 10.0,
+            max_session_words: 15,
+            ramp_days: 4,
+            default_seconds: 12.0,
+        };
+        let plan = plan_rescue(candidates, Some(config));
+        assert_eq!(plan.overdue_words, 100);
+        // 10 分钟只能放 20 个，词数上限更紧
+        assert_eq!(plan.session.len(), 15);
+        assert_eq!(plan.session[0].word_id, "w099");
+
+        let days: Vec<u32> = plan.ramp.iter().map(|d| d.day).collect();
+        assert_eq!(days, [1, 2, 3, 4]);
+        let counts: Vec<usize> = plan.ramp.iter().map(|d| d.word_ids.len()).collect();
+        assert_eq!(counts.iter().sum::<usize>(), 85);
+        assert!(counts.iter().all(|n| (21..=22).contains(n)), "{counts:?}");
+        // 剩余词仍按优先级先后排到前几天
+        assert_eq!(plan.ramp[0].word_ids[0], "w084");
+    }
+}
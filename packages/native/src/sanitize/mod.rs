@@ -1,6 +1,12 @@
+use alloc::format;
+use alloc::string::ToString;
+
+use crate::events::{self, AlgoEvent};
+use crate::float;
+use crate::matrix::{cholesky_decompose, solve_cholesky};
 use crate::types::{
     DiagnosticResult, CHOLESKY_RECOMPUTE_INTERVAL, EPSILON, MAX_COVARIANCE, MAX_FEATURE_ABS,
-    MIN_LAMBDA, MIN_RANK1_DIAG,
+    MAX_RECONSTRUCTION_DRIFT, MIN_LAMBDA, MIN_RANK1_DIAG,
 };
 
 /// 检查数组是否包含无效值 (NaN 或 Inf)
@@ -10,18 +16,27 @@ pub fn has_invalid_values(arr: &[f64]) -> bool {
 
 /// 清理特征向量，确保数值稳定
 pub fn sanitize_feature_vector(x: &mut [f64]) {
+    let mut fixed = 0usize;
     for val in x.iter_mut() {
         if val.is_nan() || val.is_infinite() {
             *val = 0.0;
-        } else {
+            fixed += 1;
+        } else if val.abs() > MAX_FEATURE_ABS {
             *val = (*val).clamp(-MAX_FEATURE_ABS, MAX_FEATURE_ABS);
+            fixed += 1;
         }
     }
+    if fixed > 0 {
+        events::record(AlgoEvent::FeatureSanitized, || {
+            format!("{fixed}/{} feature values fixed", x.len())
+        });
+    }
 }
 
 /// 清理协方差矩阵，确保正定性
 pub fn sanitize_covariance(a: &mut [f64], d: usize, lambda: f64) {
     let safe_lambda = lambda.max(MIN_LAMBDA);
+    let mut fixed = 0usize;
 
     for i in 0..d {
         for j in 0..d {
@@ -31,12 +46,14 @@ pub fn sanitize_covariance(a: &mut [f64], d: usize, lambda: f64) {
             // 处理无效值
             if val.is_nan() || val.is_infinite() {
                 a[idx] = if i == j { safe_lambda } else { 0.0 };
+                fixed += 1;
                 continue;
             }
 
             // 限制最大值
             if val.abs() > MAX_COVARIANCE {
                 a[idx] = val.signum() * MAX_COVARIANCE;
+                fixed += 1;
             }
         }
 
@@ -44,6 +61,7 @@ pub fn sanitize_covariance(a: &mut [f64], d: usize, lambda: f64) {
         let diag_idx = i * d + i;
         if a[diag_idx] < safe_lambda {
             a[diag_idx] = safe_lambda;
+            fixed += 1;
         }
     }
 
@@ -55,6 +73,12 @@ pub fn sanitize_covariance(a: &mut [f64], d: usize, lambda: f64) {
             a[j * d + i] = avg;
         }
     }
+
+    if fixed > 0 {
+        events::record(AlgoEvent::CovarianceSanitized, || {
+            format!("{fixed} covariance entries fixed (d = {d})")
+        });
+    }
 }
 
 /// 判断是否需要完整重新计算 Cholesky 分解
@@ -63,7 +87,11 @@ pub fn needs_full_recompute(update_count: u32, l: &[f64], d: usize) -> bool {
     if update_count.is_multiple_of(CHOLESKY_RECOMPUTE_INTERVAL) {
         return true;
     }
+    factor_unhealthy(l, d)
+}
 
+/// L 已不可用（对角线非有限、过小或条件数过大），必须立即完整重算
+pub fn factor_unhealthy(l: &[f64], d: usize) -> bool {
     // 检查 L 矩阵对角线
     for i in 0..d {
         let diag = l[i * d + i];
@@ -93,6 +121,50 @@ pub fn needs_full_recompute(update_count: u32, l: &[f64], d: usize) -> bool {
     false
 }
 
+/// L·Lᵀ 与 A 的相对 Frobenius 偏差，衡量 rank-1 更新累积的数值漂移；
+/// 含非有限值时返回无穷大
+pub fn reconstruction_drift(a: &[f64], l: &[f64], d: usize) -> f64 {
+    let mut diff_sq = 0.0;
+    let mut norm_sq = 0.0;
+    for i in 0..d {
+        for j in 0..d {
+            let mut llt = 0.0;
+            for k in 0..=i.min(j) {
+                llt += l[i * d + k] * l[j * d + k];
+            }
+            let aij = a[i * d + j];
+            diff_sq += (llt - aij) * (llt - aij);
+            norm_sq += aij * aij;
+        }
+    }
+    let drift = float::sqrt(diff_sq) / float::sqrt(norm_sq).max(EPSILON);
+    if drift.is_finite() {
+        drift
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// 用 L 求得的 theta 与对 A 重新分解后求得的 theta 之间的相对偏差
+pub fn theta_drift(a: &[f64], l: &[f64], b: &[f64], d: usize, lambda: f64) -> f64 {
+    let incremental = solve_cholesky(l, b, d);
+    let direct = solve_cholesky(&cholesky_decompose(a, d, lambda), b, d);
+    let diff = float::sqrt(
+        incremental
+            .iter()
+            .zip(&direct)
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum::<f64>(),
+    );
+    let norm = float::sqrt(direct.iter().map(|y| y * y).sum::<f64>());
+    let drift = diff / norm.max(EPSILON);
+    if drift.is_finite() {
+        drift
+    } else {
+        f64::INFINITY
+    }
+}
+
 /// 诊断模型健康状态
 pub fn diagnose_model(a: &[f64], l: &[f64], d: usize) -> DiagnosticResult {
     let mut has_nan = false;
@@ -127,14 +199,19 @@ pub fn diagnose_model(a: &[f64], l: &[f64], d: usize) -> DiagnosticResult {
 
     // 计算条件数估计
     let condition_number = if min_diagonal > EPSILON {
-        (max_diagonal / min_diagonal).powi(2)
+        float::powi(max_diagonal / min_diagonal, 2)
     } else {
         f64::MAX
     };
 
     let is_healthy = !has_nan && !has_inf && condition_number < 1e12;
 
-    let message = if is_healthy {
+    let drift = reconstruction_drift(a, l, d);
+    let has_drift = drift > MAX_RECONSTRUCTION_DRIFT;
+
+    let message = if is_healthy && has_drift {
+        format!("Model has numerical drift: {:.2e}", drift)
+    } else if is_healthy {
         "Model is healthy".to_string()
     } else if has_nan {
         "Model contains NaN values".to_string()
@@ -159,6 +236,8 @@ pub fn diagnose_model(a: &[f64], l: &[f64], d: usize) -> DiagnosticResult {
         } else {
             max_diagonal
         },
+        reconstruction_drift: drift,
+        has_drift,
         message,
     }
 }
@@ -524,6 +603,29 @@ mod tests {
         assert!(result.condition_number < 1e12); // 极小值，技术上"健康"
     }
 
+    #[test]
+    fn test_reconstruction_drift() {
+        let d = 2;
+        let a = vec![4.0, 2.0, 2.0, 5.0];
+        let l = cholesky_decompose(&a, d, 1.0);
+        assert!(reconstruction_drift(&a, &l, d) < 1e-9);
+        assert!(theta_drift(&a, &l, &[1.0, 2.0], d, 1.0) < 1e-9);
+
+        let mut drifted = l.clone();
+        drifted[2] += 1e-3;
+        assert!(reconstruction_drift(&a, &drifted, d) > MAX_RECONSTRUCTION_DRIFT);
+        assert!(theta_drift(&a, &drifted, &[1.0, 2.0], d, 1.0) > 1e-6);
+
+        let result = diagnose_model(&a, &drifted, d);
+        assert!(result.has_drift);
+        assert!(result.message.contains("drift"));
+        assert!(!diagnose_model(&a, &l, d).has_drift);
+        assert_eq!(
+            reconstruction_drift(&a, &[f64::NAN, 0.0, 0.0, 1.0], d),
+            f64::INFINITY
+        );
+    }
+
     #[test]
     fn test_diagnose_model_1x1() {
         let d = 1;
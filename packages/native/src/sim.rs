@@ -0,0 +1,692 @@
+//! 学习者队列模拟器（sim harness）
+//!
+//! 用合成学习者队列在给定调度配置下逐日模拟学习与复习：每个词的记忆按半衰期
+//! 指数衰减，复习答对则半衰期增长、答错则回落并重置复习阶段。输出期末保持率与
+//! 复习负荷，用于在配置上线前比较两套算法配置的影响。
+//!
+//! 随机数来自 `RngFactory` 的 `sim.learner` 域，按队列名与学习者序号细分，
+//! 同一种子下两套配置面对的是同一批学习者（公共随机数），差异更多来自配置本身。
+//!
+//! 队列可带干扰模型：一部分新词成对出现（形近或义近的易混词），一对词在同一天
+//! 学习或复习时，双方的回忆概率都按 `penalty` 打折，此时答错计为混淆错误。调度
+//! 配置的 `interferencePenalty` 是调度器对"同日安排易混词"估计的代价：复习时
+//! 若该代价大于推迟一天带来的遗忘（回忆概率下降量），就把复习推迟一天；新词的
+//! 易混搭档则推迟到次日引入。
+//!
+//! `project_learner` 则从单个学习者的当前掌握分布出发，预测不同每日学习量下
+//! 的掌握词数与保持率，并给出多次运行的分位区间（"what-if" 预测）。
+
+use serde::{Deserialize, Serialize};
+
+use crate::rng::{domains, RngFactory, RngStream};
+
+/// 半衰期下限（天）
+const MIN_HALF_LIFE: f64 = 0.25;
+
+/// 被模拟的调度配置（对应后端算法配置中与调度相关的字段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerConfig {
+    /// 第 n 次连续答对后的复习间隔（天）
+    pub review_intervals: Vec<u32>,
+    /// 每日学习量中新词的占比
+    pub new_word_ratio: f64,
+    /// 在最后一个间隔阶段连续答对多少次视为掌握（之后不再安排复习）
+    pub mastery_streak: u32,
+    /// 同日安排一对易混词的估计代价（回忆概率单位），0 表示不考虑干扰
+    #[serde(default)]
+    pub interference_penalty: f64,
+}
+
+impl SchedulerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.review_intervals.is_empty() {
+            return Err("reviewIntervals must not be empty".into());
+        }
+        if self.review_intervals.contains(&0) {
+            return Err("reviewIntervals must be positive".into());
+        }
+        if !(0.0..=1.0).contains(&self.new_word_ratio) {
+            return Err("newWordRatio must be within [0, 1]".into());
+        }
+        if self.mastery_streak == 0 {
+            return Err("masteryStreak must be positive".into());
+        }
+        if !(0.0..=1.0).contains(&self.interference_penalty) {
+            return Err("interferencePenalty must be within [0, 1]".into());
+        }
+        Ok(())
+    }
+}
+
+/// 合成学习者队列
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortSpec {
+    pub name: String,
+    pub learners: u32,
+    pub days: u32,
+    /// 每日学习量（词数），其中新词占比由配置决定
+    pub daily_budget: u32,
+    /// 首次学习后的记忆半衰期（天）
+    pub initial_half_life: f64,
+    /// 答对后半衰期的平均增长倍数
+    pub growth: f64,
+    /// 答错后半衰期保留比例
+    pub lapse_factor: f64,
+    /// 学习者能力差异（增长倍数的对数标准差）
+    pub ability_spread: f64,
+    /// 易混词干扰，缺省时词之间互不影响
+    #[serde(default)]
+    pub interference: Option<InterferenceSpec>,
+}
+
+/// 易混词干扰模型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterferenceSpec {
+    /// 新词带一个易混搭档的概率
+    pub pair_rate: f64,
+    /// 同日学习时回忆概率（及首次学习后的半衰期）的折损比例
+    pub penalty: f64,
+}
+
+impl CohortSpec {
+    /// 预置的三类队列：轻度、常规、高强度
+    pub fn standard(learners: u32, days: u32) -> Vec<CohortSpec> {
+        let base = |name: &str, daily_budget, initial_half_life, growth| CohortSpec {
+            name: name.to_string(),
+            learners,
+            days,
+            daily_budget,
+            initial_half_life,
+            growth,
+            lapse_factor: 0.5,
+            ability_spread: 0.25,
+            interference: None,
+        };
+        vec![
+            base("casual", 10, 0.8, 2.0),
+            base("regular", 20, 1.0, 2.3),
+            base("intensive", 40, 1.2, 2.6),
+        ]
+    }
+}
+
+/// 单个队列在单套配置下的结果（均为每名学习者的平均值）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimMetrics {
+    pub learners: u32,
+    pub days: u32,
+    pub words_introduced: f64,
+    pub words_mastered: f64,
+    /// 期末对所有学过词的平均回忆概率
+    pub mean_retention: f64,
+    pub reviews_per_day: f64,
+    /// 按天平均后的复习量峰值
+    pub peak_reviews_per_day: f64,
+    pub review_accuracy: f64,
+    /// 日均混淆错误数：易混搭档同日出现时的答错
+    #[serde(default)]
+    pub confusion_errors_per_day: f64,
+}
+
+/// 同一队列下基线配置与候选配置的对比
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimComparison {
+    pub cohort: String,
+    pub baseline: SimMetrics,
+    pub proposed: SimMetrics,
+    /// 保持率差值（百分点，候选 - 基线）
+    pub retention_delta: f64,
+    /// 日均复习量相对变化（候选 / 基线 - 1）
+    pub review_load_delta: f64,
+}
+
+struct WordSim {
+    half_life: f64,
+    last_review: u32,
+    due: u32,
+    stage: usize,
+    streak: u32,
+    mastered: bool,
+    /// 易混搭档在 `words` 中的下标
+    partner: Option<usize>,
+    /// 已生成但推迟到次日引入
+    pending: bool,
+    /// 最近一次被安排学习的日子
+    planned: Option<u32>,
+    /// 上一次到期时已因干扰推迟过（不连续推迟两次）
+    deferred: bool,
+}
+
+impl WordSim {
+    fn new(half_life: f64, day: u32, first_interval: u32) -> Self {
+        Self {
+            half_life: half_life.max(MIN_HALF_LIFE),
+            last_review: day,
+            due: day + first_interval,
+            stage: 0,
+            streak: 0,
+            mastered: false,
+            partner: None,
+            pending: false,
+            planned: Some(day),
+            deferred: false,
+        }
+    }
+}
+
+fn recall_probability(elapsed: f64, half_life: f64) -> f64 {
+    (-elapsed / half_life).exp2()
+}
+
+/// 标准正态（Box-Muller）
+fn standard_normal(rng: &mut RngStream) -> f64 {
+    let u1 = rng.next_f64().max(f64::MIN_POSITIVE);
+    let u2 = rng.next_f64();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// 复习一个到期词并更新其状态，返回是否答对；`recall_scale` 为干扰折损后的
+/// 回忆概率倍数
+fn review_word(
+    word: &mut WordSim,
+    day: u32,
+    config: &SchedulerConfig,
+    growth: f64,
+    lapse_factor: f64,
+    recall_scale: f64,
+    rng: &mut RngStream,
+) -> bool {
+    let intervals = &config.review_intervals;
+    let elapsed = f64::from(day - word.last_review);
+    let recalled = rng.next_f64() < recall_probability(elapsed, word.half_life) * recall_scale;
+    if recalled {
+        word.half_life *= growth.max(1.0);
+        word.streak += 1;
+        if word.stage + 1 >= intervals.len() && word.streak >= config.mastery_streak {
+            word.mastered = true;
+        }
+        word.stage = (word.stage + 1).min(intervals.len() - 1);
+    } else {
+        word.half_life = (word.half_life * lapse_factor).max(MIN_HALF_LIFE);
+        word.streak = 0;
+        word.stage = 0;
+    }
+    word.last_review = day;
+    word.due = day + intervals[word.stage];
+    word.deferred = false;
+    recalled
+}
+
+fn partner_planned(words: &[WordSim], idx: usize, day: u32) -> bool {
+    words[idx]
+        .partner
+        .is_some_and(|p| words[p].planned == Some(day))
+}
+
+/// 引入当天的新词：先引入昨天推迟的，再生成新的；成对生成时搭档紧随其后，
+/// 调度器考虑干扰时推迟到次日
+fn introduce_words(
+    words: &mut Vec<WordSim>,
+    day: u32,
+    new_per_day: u32,
+    config: &SchedulerConfig,
+    cohort: &CohortSpec,
+    rng: &mut RngStream,
+) {
+    let first_interval = config.review_intervals[0];
+    let mut slots = new_per_day;
+    let mut fresh = Vec::new();
+
+    for word in words.iter_mut().filter(|w| w.pending) {
+        if slots == 0 {
+            break;
+        }
+        *word = WordSim {
+            partner: word.partner,
+            ..WordSim::new(cohort.initial_half_life, day, first_interval)
+        };
+        slots -= 1;
+    }
+
+    while slots > 0 {
+        let idx = words.len() + fresh.len();
+        fresh.push(WordSim::new(cohort.initial_half_life, day, first_interval));
+        slots -= 1;
+        let Some(spec) = cohort.interference else {
+            continue;
+        };
+        if rng.next_f64() < spec.pair_rate {
+            let mut partner = WordSim::new(cohort.initial_half_life, day, first_interval);
+            partner.partner = Some(idx);
+            fresh[idx - words.len()].partner = Some(idx + 1);
+            if config.interference_penalty > 0.0 {
+                partner.pending = true;
+                partner.planned = None;
+            } else if slots > 0 {
+                slots -= 1;
+            } else {
+                partner.pending = true;
+                partner.planned = None;
+            }
+            fresh.push(partner);
+        }
+    }
+    words.extend(fresh);
+}
+
+/// 当天新学的词若搭档也在当天出现，首次学习后的半衰期按干扰折损
+fn apply_encoding_interference(words: &mut [WordSim], day: u32, penalty: f64) {
+    for idx in 0..words.len() {
+        let introduced_today = words[idx].planned == Some(day) && words[idx].last_review == day;
+        if introduced_today && partner_planned(words, idx, day) {
+            words[idx].half_life = (words[idx].half_life * (1.0 - penalty)).max(MIN_HALF_LIFE);
+        }
+    }
+}
+
+/// 安排当天的复习：到期词默认复习；调度器考虑干扰且搭档已在当天安排时，
+/// 若干扰代价大于推迟一天的遗忘代价，则推迟一天
+fn plan_reviews(words: &mut [WordSim], day: u32, config: &SchedulerConfig) -> Vec<usize> {
+    let mut agenda = Vec::new();
+    for idx in 0..words.len() {
+        let word = &words[idx];
+        if word.mastered || word.pending || word.due > day || word.planned == Some(day) {
+            continue;
+        }
+        if config.interference_penalty > 0.0 && !word.deferred && partner_planned(words, idx, day) {
+            let elapsed = f64::from(day - word.last_review);
+            let forgetting = recall_probability(elapsed, word.half_life)
+                - recall_probability(elapsed + 1.0, word.half_life);
+            if config.interference_penalty > forgetting {
+                words[idx].due = day + 1;
+                words[idx].deferred = true;
+                continue;
+            }
+        }
+        words[idx].planned = Some(day);
+        agenda.push(idx);
+    }
+    agenda
+}
+
+/// 模拟单个队列
+pub fn run_cohort(config: &SchedulerConfig, cohort: &CohortSpec, seed: u64) -> SimMetrics {
+    let factory = RngFactory::new(seed);
+    let new_per_day = (f64::from(cohort.daily_budget) * config.new_word_ratio).round() as u32;
+
+    let mut introduced = 0u64;
+    let mut mastered = 0u64;
+    let mut retention_sum = 0.0;
+    let mut retention_words = 0u64;
+    let mut reviews = 0u64;
+    let mut correct = 0u64;
+    let mut confusion_errors = 0u64;
+    let mut daily_reviews = vec![0u64; cohort.days as usize];
+    let penalty = cohort.interference.map_or(0.0, |i| i.penalty);
+
+    for learner in 0..cohort.learners {
+        let mut rng = factory.substream(domains::SIM_LEARNER, &cohort.name, u64::from(learner));
+        let growth = cohort.growth * (cohort.ability_spread * standard_normal(&mut rng)).exp();
+        let mut words: Vec<WordSim> = Vec::new();
+
+        for day in 0..cohort.days {
+            // 新词先定下来，复习安排据此避开易混搭档；新词当天不会到期
+            introduce_words(&mut words, day, new_per_day, config, cohort, &mut rng);
+            let agenda = plan_reviews(&mut words, day, config);
+            if penalty > 0.0 {
+                apply_encoding_interference(&mut words, day, penalty);
+            }
+            for idx in agenda {
+                let interfered = penalty > 0.0 && partner_planned(&words, idx, day);
+                let scale = if interfered { 1.0 - penalty } else { 1.0 };
+                reviews += 1;
+                daily_reviews[day as usize] += 1;
+                let word = &mut words[idx];
+                if review_word(
+                    word,
+                    day,
+                    config,
+                    growth,
+                    cohort.lapse_factor,
+                    scale,
+                    &mut rng,
+                ) {
+                    correct += 1;
+                } else if interfered {
+                    confusion_errors += 1;
+                }
+            }
+        }
+
+        words.retain(|w| !w.pending);
+        introduced += words.len() as u64;
+        for word in &words {
+            mastered += u64::from(word.mastered);
+            let elapsed = f64::from(cohort.days - word.last_review);
+            retention_sum += recall_probability(elapsed, word.half_life);
+            retention_words += 1;
+        }
+    }
+
+    let learners = f64::from(cohort.learners.max(1));
+    let learner_days = learners * f64::from(cohort.days.max(1));
+    SimMetrics {
+        learners: cohort.learners,
+        days: cohort.days,
+        words_introduced: introduced as f64 / learners,
+        words_mastered: mastered as f64 / learners,
+        mean_retention: if retention_words > 0 {
+            retention_sum / retention_words as f64
+        } else {
+            0.0
+        },
+        reviews_per_day: reviews as f64 / learner_days,
+        peak_reviews_per_day: daily_reviews.iter().copied().max().unwrap_or(0) as f64 / learners,
+        review_accuracy: if reviews > 0 {
+            correct as f64 / reviews as f64
+        } else {
+            0.0
+        },
+        confusion_errors_per_day: confusion_errors as f64 / learner_days,
+    }
+}
+
+/// 由两次运行结果构造对比
+pub fn compare_metrics(cohort: &str, baseline: SimMetrics, proposed: SimMetrics) -> SimComparison {
+    let review_load_delta = if baseline.reviews_per_day > 0.0 {
+        proposed.reviews_per_day / baseline.reviews_per_day - 1.0
+    } else {
+        0.0
+    };
+    SimComparison {
+        cohort: cohort.to_string(),
+        retention_delta: (proposed.mean_retention - baseline.mean_retention) * 100.0,
+        review_load_delta,
+        baseline,
+        proposed,
+    }
+}
+
+/// 在同一队列、同一种子下比较两套配置
+pub fn compare_cohort(
+    baseline: &SchedulerConfig,
+    proposed: &SchedulerConfig,
+    cohort: &CohortSpec,
+    seed: u64,
+) -> SimComparison {
+    compare_metrics(
+        &cohort.name,
+        run_cohort(baseline, cohort, seed),
+        run_cohort(proposed, cohort, seed),
+    )
+}
+
+/// 个人预测的起点：当前掌握分布与记忆参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearnerProfile {
+    /// 各复习阶段中在学的词数（下标即阶段，超出配置阶段数的并入最后一阶段）
+    pub stage_counts: Vec<u32>,
+    /// 已掌握（不再安排复习）的词数
+    pub mastered: u32,
+    pub initial_half_life: f64,
+    pub growth: f64,
+    pub lapse_factor: f64,
+    /// 个人增长倍数的不确定度（对数标准差），决定预测区间宽度
+    pub ability_spread: f64,
+}
+
+/// 多次运行的均值与 10%/90% 分位
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectionBand {
+    pub mean: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl ProjectionBand {
+    fn of(mut samples: Vec<f64>) -> Self {
+        if samples.is_empty() {
+            return Self {
+                mean: 0.0,
+                lower: 0.0,
+                upper: 0.0,
+            };
+        }
+        samples.sort_by(f64::total_cmp);
+        let quantile = |q: f64| samples[((samples.len() - 1) as f64 * q).round() as usize];
+        Self {
+            mean: samples.iter().sum::<f64>() / samples.len() as f64,
+            lower: quantile(0.1),
+            upper: quantile(0.9),
+        }
+    }
+}
+
+/// 第 `day` 天结束时的预测
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HorizonProjection {
+    pub day: u32,
+    /// 累计掌握词数（含起点已掌握的词）
+    pub words_mastered: ProjectionBand,
+    /// 对所有学过词的平均回忆概率
+    pub retention: ProjectionBand,
+}
+
+fn seed_words(config: &SchedulerConfig, profile: &LearnerProfile, growth: f64) -> Vec<WordSim> {
+    let intervals = &config.review_intervals;
+    let last = intervals.len() - 1;
+    let base = profile.initial_half_life.max(MIN_HALF_LIFE);
+    let mut words = Vec::new();
+    for (stage, &count) in profile.stage_counts.iter().enumerate() {
+        let stage = stage.min(last);
+        let interval = intervals[stage];
+        for i in 0..count {
+            words.push(WordSim {
+                // 到期日在当前间隔内均匀铺开，避免第一天集中复习
+                due: i % interval,
+                stage,
+                planned: None,
+                ..WordSim::new(base * growth.max(1.0).powi(stage as i32), 0, 0)
+            });
+        }
+    }
+    let mastered_half_life = base
+        * growth
+            .max(1.0)
+            .powi((intervals.len() as u32 + config.mastery_streak) as i32);
+    words.extend((0..profile.mastered).map(|_| WordSim {
+        stage: last,
+        streak: config.mastery_streak,
+        mastered: true,
+        planned: None,
+        ..WordSim::new(mastered_half_life, 0, 0)
+    }));
+    words
+}
+
+/// 从个人当前状态出发，按每日学习量 `daily_budget` 预测各 `horizons` 天后的
+/// 掌握词数与保持率。每次运行重新抽取个人增长倍数；同一种子下不同学习量面对的
+/// 是同一组抽样（公共随机数），因此方案之间的差异来自学习量本身。
+pub fn project_learner(
+    config: &SchedulerConfig,
+    profile: &LearnerProfile,
+    daily_budget: u32,
+    horizons: &[u32],
+    runs: u32,
+    seed: u64,
+) -> Vec<HorizonProjection> {
+    let factory = RngFactory::new(seed);
+    let intervals = &config.review_intervals;
+    let new_per_day = (f64::from(daily_budget) * config.new_word_ratio).round() as u32;
+    let days = horizons.iter().copied().max().unwrap_or(0);
+    let runs = runs.max(1);
+
+    let mut mastered_samples = vec![Vec::with_capacity(runs as usize); horizons.len()];
+    let mut retention_samples = vec![Vec::with_capacity(runs as usize); horizons.len()];
+
+    for run in 0..runs {
+        let mut rng = factory.substream(domains::SIM_LEARNER, "what_if", u64::from(run));
+        let growth = profile.growth * (profile.ability_spread * standard_normal(&mut rng)).exp();
+        let mut words = seed_words(config, profile, growth);
+
+        for day in 0..days {
+            for word in words.iter_mut().filter(|w| !w.mastered && w.due <= day) {
+                review_word(
+                    word,
+                    day,
+                    config,
+                    growth,
+                    profile.lapse_factor,
+                    1.0,
+                    &mut rng,
+                );
+            }
+            for _ in 0..new_per_day {
+                words.push(WordSim::new(profile.initial_half_life, day, intervals[0]));
+            }
+
+            for (i, _) in horizons.iter().enumerate().filter(|(_, &h)| h == day + 1) {
+                let mastered = words.iter().filter(|w| w.mastered).count();
+                let retention = if words.is_empty() {
+                    0.0
+                } else {
+                    words
+                        .iter()
+                        .map(|w| {
+                            recall_probability(f64::from(day + 1 - w.last_review), w.half_life)
+                        })
+                        .sum::<f64>()
+                        / words.len() as f64
+                };
+                mastered_samples[i].push(mastered as f64);
+                retention_samples[i].push(retention);
+            }
+        }
+    }
+
+    horizons
+        .iter()
+        .zip(mastered_samples.into_iter().zip(retention_samples))
+        .map(|(&day, (mastered, retention))| HorizonProjection {
+            day,
+            words_mastered: ProjectionBand::of(mastered),
+            retention: ProjectionBand::of(retention),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(intervals: &[u32]) -> SchedulerConfig {
+        SchedulerConfig {
+            review_intervals: intervals.to_vec(),
+            new_word_ratio: 0.3,
+            mastery_streak: 2,
+            interference_penalty: 0.0,
+        }
+    }
+
+    fn cohort() -> CohortSpec {
+        CohortSpec::standard(30, 40).remove(1)
+    }
+
+    #[test]
+    fn test_same_seed_is_deterministic() {
+        let c = config(&[1, 2, 4, 7, 15]);
+        assert_eq!(run_cohort(&c, &cohort(), 9), run_cohort(&c, &cohort(), 9));
+        assert_ne!(run_cohort(&c, &cohort(), 9), run_cohort(&c, &cohort(), 10));
+    }
+
+    #[test]
+    fn test_shorter_intervals_trade_load_for_retention() {
+        let relaxed = config(&[2, 5, 12, 30]);
+        let tight = config(&[1, 1, 2, 3, 5]);
+        let cmp = compare_cohort(&relaxed, &tight, &cohort(), 42);
+        assert!(cmp.review_load_delta > 0.0, "{cmp:?}");
+        assert!(cmp.retention_delta > 0.0, "{cmp:?}");
+    }
+
+    #[test]
+    fn test_metrics_are_in_range() {
+        let m = run_cohort(&config(&[1, 3, 7]), &cohort(), 1);
+        assert_eq!(m.words_introduced, 240.0);
+        assert!((0.0..=1.0).contains(&m.mean_retention));
+        assert!((0.0..=1.0).contains(&m.review_accuracy));
+        assert!(m.peak_reviews_per_day >= m.reviews_per_day);
+        assert!(m.words_mastered <= m.words_introduced);
+    }
+
+    #[test]
+    fn test_interference_aware_scheduling_reduces_confusion_errors() {
+        let mut spec = cohort();
+        spec.interference = Some(InterferenceSpec {
+            pair_rate: 0.5,
+            penalty: 0.4,
+        });
+        let naive = config(&[1, 3, 7, 15]);
+        let aware = SchedulerConfig {
+            interference_penalty: 0.4,
+            ..naive.clone()
+        };
+
+        let plain = run_cohort(&naive, &cohort(), 7);
+        assert_eq!(plain.confusion_errors_per_day, 0.0);
+
+        let cmp = compare_cohort(&naive, &aware, &spec, 7);
+        assert!(cmp.baseline.confusion_errors_per_day > 0.0, "{cmp:?}");
+        assert!(
+            cmp.proposed.confusion_errors_per_day < cmp.baseline.confusion_errors_per_day * 0.5,
+            "{cmp:?}"
+        );
+        assert_eq!(cmp.baseline.words_introduced, cmp.proposed.words_introduced);
+    }
+
+    fn profile() -> LearnerProfile {
+        LearnerProfile {
+            stage_counts: vec![20, 15, 10, 5],
+            mastered: 30,
+            initial_half_life: 1.0,
+            growth: 2.3,
+            lapse_factor: 0.5,
+            ability_spread: 0.25,
+        }
+    }
+
+    #[test]
+    fn test_more_study_time_projects_more_mastery() {
+        let c = config(&[1, 3, 7, 15]);
+        let low = project_learner(&c, &profile(), 10, &[30, 90], 40, 5);
+        let high = project_learner(&c, &profile(), 30, &[30, 90], 40, 5);
+        assert_eq!(low.len(), 2);
+        assert_eq!(low[1].day, 90);
+        for p in low.iter().chain(&high) {
+            assert!(p.words_mastered.lower <= p.words_mastered.mean);
+            assert!(p.words_mastered.mean <= p.words_mastered.upper);
+            assert!(p.words_mastered.lower >= 30.0);
+            assert!((0.0..=1.0).contains(&p.retention.mean));
+        }
+        assert!(high[1].words_mastered.mean > low[1].words_mastered.mean);
+        assert!(low[1].words_mastered.mean >= low[0].words_mastered.mean);
+        assert_eq!(low, project_learner(&c, &profile(), 10, &[30, 90], 40, 5));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(config(&[1, 2]).validate().is_ok());
+        assert!(config(&[]).validate().is_err());
+        assert!(config(&[1, 0]).validate().is_err());
+        let mut c = config(&[1]);
+        c.new_word_ratio = 1.5;
+        assert!(c.validate().is_err());
+    }
+}
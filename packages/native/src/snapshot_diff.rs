@@ -0,0 +1,387 @@
+//! 算法状态快照差异
+//!
+//! 同步时覆盖一份模型快照之前，需要知道新旧两份差多少，才能判断是否该回滚。
+//! 三类模型都归结为“标量参数 + 按臂的数值向量”再比较：
+//!
+//! - LinUCB：参数 α、λ、更新次数，向量为 θ = A⁻¹b 的各分量（臂名取布局中的
+//!   特征名）；维度或布局指纹不同时只比较参数；
+//! - Thompson：每个臂 Beta(α, β) 的后验均值，参数为臂数与总证据量 Σ(α + β)；
+//! - ACT-R：衰减、阈值、噪声参数，每个词的回忆概率。
+//!
+//! 输出参数变化、向量范数变化以及变化最大的若干个臂。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::layout::BanditSnapshot;
+use crate::linucb::LinUCB;
+use crate::mastery::{actr_recall, MasteryBandConfig, ReviewEvent};
+use crate::tuning::BetaPrior;
+
+/// 参数视为未变化的容差
+const PARAM_TOLERANCE: f64 = 1e-12;
+
+/// 标量参数的变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamDelta {
+    pub name: String,
+    pub before: f64,
+    pub after: f64,
+    pub delta: f64,
+}
+
+/// 单个臂的变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmChange {
+    pub arm: String,
+    pub before: f64,
+    pub after: f64,
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    /// linucb / thompson / actr
+    pub model: String,
+    /// 有变化的标量参数
+    pub param_deltas: Vec<ParamDelta>,
+    /// 两份快照的臂能否逐一对应；为 false 时只比较参数
+    pub comparable: bool,
+    pub norm_before: f64,
+    pub norm_after: f64,
+    /// 共有臂上差向量的 L2 范数
+    pub norm_change: f64,
+    /// norm_change / norm_before；旧范数为 0 时等于 norm_change
+    pub relative_change: f64,
+    /// 按 |change| 从大到小
+    pub top_arms: Vec<ArmChange>,
+    pub added_arms: Vec<String>,
+    pub removed_arms: Vec<String>,
+}
+
+impl SnapshotDiff {
+    /// 没有任何参数或臂发生变化
+    pub fn is_unchanged(&self) -> bool {
+        self.param_deltas.is_empty()
+            && self.norm_change <= PARAM_TOLERANCE
+            && self.added_arms.is_empty()
+            && self.removed_arms.is_empty()
+    }
+
+    /// 单行摘要，供日志使用
+    pub fn summary(&self) -> String {
+        if !self.comparable {
+            return format!(
+                "{}: not comparable, {} param(s) changed",
+                self.model,
+                self.param_deltas.len()
+            );
+        }
+        let mut head = format!("{}: |Δ|={:.4}", self.model, self.norm_change);
+        if self.norm_before > PARAM_TOLERANCE {
+            head.push_str(&format!(" ({:.1}%)", self.relative_change * 100.0));
+        }
+        let mut parts = vec![head];
+        if let Some(top) = self.top_arms.first() {
+            parts.push(format!("top {} {:+.4}", top.arm, top.change));
+        }
+        for p in &self.param_deltas {
+            parts.push(format!("{} {}→{}", p.name, p.before, p.after));
+        }
+        if !self.added_arms.is_empty() || !self.removed_arms.is_empty() {
+            parts.push(format!(
+                "arms +{} -{}",
+                self.added_arms.len(),
+                self.removed_arms.len()
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+/// ACT-R 快照：参数与各词的复习轨迹
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActrSnapshot {
+    #[serde(default)]
+    pub config: MasteryBandConfig,
+    pub traces: BTreeMap<String, Vec<ReviewEvent>>,
+}
+
+fn param_deltas(params: &[(&str, f64, f64)]) -> Vec<ParamDelta> {
+    params
+        .iter()
+        .filter(|(_, before, after)| {
+            !((before - after).abs() <= PARAM_TOLERANCE || (before.is_nan() && after.is_nan()))
+        })
+        .map(|(name, before, after)| ParamDelta {
+            name: name.to_string(),
+            before: *before,
+            after: *after,
+            delta: after - before,
+        })
+        .collect()
+}
+
+fn norm<'a>(values: impl Iterator<Item = &'a f64>) -> f64 {
+    values.map(|v| v * v).sum::<f64>().sqrt()
+}
+
+/// 按臂名对齐两组数值并汇总
+fn compare(
+    model: &str,
+    params: &[(&str, f64, f64)],
+    before: &BTreeMap<String, f64>,
+    after: &BTreeMap<String, f64>,
+    top_k: usize,
+) -> SnapshotDiff {
+    let mut changes: Vec<ArmChange> = before
+        .iter()
+        .filter_map(|(arm, b)| {
+            after.get(arm).map(|a| ArmChange {
+                arm: arm.clone(),
+                before: *b,
+                after: *a,
+                change: a - b,
+            })
+        })
+        .collect();
+    let norm_change = norm(changes.iter().map(|c| &c.change));
+    let norm_before = norm(before.values());
+    changes.sort_by(|x, y| {
+        y.change
+            .abs()
+            .total_cmp(&x.change.abs())
+            .then_with(|| x.arm.cmp(&y.arm))
+    });
+    changes.retain(|c| c.change.abs() > PARAM_TOLERANCE);
+    changes.truncate(top_k);
+
+    SnapshotDiff {
+        model: model.to_string(),
+        param_deltas: param_deltas(params),
+        comparable: true,
+        norm_before,
+        norm_after: norm(after.values()),
+        norm_change,
+        relative_change: if norm_before > PARAM_TOLERANCE {
+            norm_change / norm_before
+        } else {
+            norm_change
+        },
+        top_arms: changes,
+        added_arms: after
+            .keys()
+            .filter(|arm| !before.contains_key(*arm))
+            .cloned()
+            .collect(),
+        removed_arms: before
+            .keys()
+            .filter(|arm| !after.contains_key(*arm))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn theta_by_name(snapshot: &BanditSnapshot) -> BTreeMap<String, f64> {
+    let theta = LinUCB::from_model(snapshot.model.clone()).theta();
+    let names = snapshot
+        .layout
+        .as_ref()
+        .filter(|layout| layout.dimension() == theta.len())
+        .map(|layout| layout.names.clone());
+    theta
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let arm = names
+                .as_ref()
+                .map_or_else(|| format!("θ[{i}]"), |names| names[i].clone());
+            (arm, value)
+        })
+        .collect()
+}
+
+/// LinUCB 快照差异；维度或已知的布局指纹不同时不比较 θ
+pub fn diff_linucb(before: &BanditSnapshot, after: &BanditSnapshot, top_k: usize) -> SnapshotDiff {
+    let (b, a) = (&before.model, &after.model);
+    let params = [
+        ("alpha", b.alpha, a.alpha),
+        ("lambda", b.lambda, a.lambda),
+        ("updateCount", b.update_count as f64, a.update_count as f64),
+        ("d", b.d as f64, a.d as f64),
+    ];
+    let shape_ok = |m: &crate::types::BanditModel| {
+        let d = m.d as usize;
+        d > 0 && m.l_matrix.len() == d * d && m.b.len() == d
+    };
+    let same_layout = match (&before.layout_fingerprint, &after.layout_fingerprint) {
+        (Some(x), Some(y)) => x == y,
+        _ => true,
+    };
+    if !(b.d == a.d && shape_ok(b) && shape_ok(a) && same_layout) {
+        return SnapshotDiff {
+            model: "linucb".to_string(),
+            param_deltas: param_deltas(&params),
+            comparable: false,
+            norm_before: 0.0,
+            norm_after: 0.0,
+            norm_change: 0.0,
+            relative_change: 0.0,
+            top_arms: Vec::new(),
+            added_arms: Vec::new(),
+            removed_arms: Vec::new(),
+        };
+    }
+    compare(
+        "linucb",
+        &params,
+        &theta_by_name(before),
+        &theta_by_name(after),
+        top_k,
+    )
+}
+
+/// Thompson 采样各臂 Beta 后验的差异，按后验均值比较
+pub fn diff_thompson(
+    before: &HashMap<String, BetaPrior>,
+    after: &HashMap<String, BetaPrior>,
+    top_k: usize,
+) -> SnapshotDiff {
+    let means = |arms: &HashMap<String, BetaPrior>| -> BTreeMap<String, f64> {
+        arms.iter()
+            .map(|(arm, p)| {
+                let total = p.alpha + p.beta;
+                let mean = if total > 0.0 { p.alpha / total } else { 0.5 };
+                (arm.clone(), mean)
+            })
+            .collect()
+    };
+    let evidence =
+        |arms: &HashMap<String, BetaPrior>| arms.values().map(|p| p.alpha + p.beta).sum::<f64>();
+    compare(
+        "thompson",
+        &[
+            ("arms", before.len() as f64, after.len() as f64),
+            ("evidence", evidence(before), evidence(after)),
+        ],
+        &means(before),
+        &means(after),
+        top_k,
+    )
+}
+
+/// ACT-R 快照差异，按各词的回忆概率比较
+pub fn diff_actr(before: &ActrSnapshot, after: &ActrSnapshot, top_k: usize) -> SnapshotDiff {
+    let recalls = |s: &ActrSnapshot| -> BTreeMap<String, f64> {
+        s.traces
+            .iter()
+            .map(|(word, events)| (word.clone(), actr_recall(events, &s.config)))
+            .collect()
+    };
+    let (b, a) = (&before.config, &after.config);
+    compare(
+        "actr",
+        &[
+            ("decay", b.decay, a.decay),
+            ("threshold", b.threshold, a.threshold),
+            ("noise", b.noise, a.noise),
+        ],
+        &recalls(before),
+        &recalls(after),
+        top_k,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{prior_model, FeatureLayout};
+    use crate::linucb::FeatureVector;
+
+    fn snapshot(updates: &[(Vec<f64>, f64)]) -> BanditSnapshot {
+        let layout = FeatureLayout::new(1, &["bias", "accuracy", "fatigue"]);
+        let mut model = LinUCB::from_model(prior_model(3, 1.0, 0.3));
+        for (x, reward) in updates {
+            model
+                .update_with_feature_vector(&FeatureVector::Dense(x.clone()), *reward)
+                .unwrap();
+        }
+        BanditSnapshot {
+            model: model.model().clone(),
+            layout_fingerprint: Some(layout.fingerprint()),
+            layout: Some(layout),
+        }
+    }
+
+    #[test]
+    fn test_linucb_diff_names_changed_features() {
+        let before = snapshot(&[(vec![1.0, 0.0, 0.0], 0.5)]);
+        let after = snapshot(&[(vec![1.0, 0.0, 0.0], 0.5), (vec![0.0, 0.0, 1.0], 1.0)]);
+        let diff = diff_linucb(&before, &after, 2);
+        assert!(diff.comparable);
+        assert_eq!(diff.top_arms[0].arm, "fatigue");
+        assert!((diff.top_arms[0].change - 0.5).abs() < 1e-6, "{diff:?}");
+        assert_eq!(diff.top_arms.len(), 1);
+        assert_eq!(diff.param_deltas[0].name, "updateCount");
+        assert!(diff_linucb(&before, &before, 3).is_unchanged());
+
+        let mut other = after.clone();
+        other.layout_fingerprint = Some("different".to_string());
+        let diff = diff_linucb(&before, &other, 3);
+        assert!(!diff.comparable);
+        assert!(diff.summary().contains("not comparable"));
+    }
+
+    #[test]
+    fn test_thompson_diff_reports_arm_changes() {
+        let arm = |alpha, beta| BetaPrior { alpha, beta };
+        let before = HashMap::from([
+            ("easy".to_string(), arm(8.0, 2.0)),
+            ("hard".to_string(), arm(2.0, 2.0)),
+            ("old".to_string(), arm(1.0, 1.0)),
+        ]);
+        let after = HashMap::from([
+            ("easy".to_string(), arm(8.0, 2.0)),
+            ("hard".to_string(), arm(2.0, 6.0)),
+            ("new".to_string(), arm(1.0, 1.0)),
+        ]);
+        let diff = diff_thompson(&before, &after, 5);
+        assert_eq!(diff.top_arms.len(), 1);
+        assert_eq!(diff.top_arms[0].arm, "hard");
+        assert!((diff.top_arms[0].change + 0.25).abs() < 1e-12);
+        assert!((diff.norm_change - 0.25).abs() < 1e-12);
+        assert_eq!(diff.added_arms, ["new"]);
+        assert_eq!(diff.removed_arms, ["old"]);
+        assert_eq!(diff.param_deltas[0].name, "evidence");
+    }
+
+    #[test]
+    fn test_actr_diff_compares_recall() {
+        let trace = |age_days| {
+            vec![ReviewEvent {
+                age_days,
+                correct: true,
+            }]
+        };
+        let before = ActrSnapshot {
+            config: MasteryBandConfig::default(),
+            traces: BTreeMap::from([
+                ("w1".to_string(), trace(1.0)),
+                ("w2".to_string(), trace(1.0)),
+            ]),
+        };
+        let mut after = before.clone();
+        after.traces.insert("w2".to_string(), trace(10.0));
+        after.config.decay = 0.6;
+        let diff = diff_actr(&before, &after, 1);
+        assert_eq!(diff.top_arms.len(), 1);
+        assert_eq!(diff.top_arms[0].arm, "w2");
+        assert!(diff.top_arms[0].change < 0.0);
+        assert_eq!(diff.param_deltas.len(), 1);
+        assert!(diff.summary().starts_with("actr: |Δ|="));
+    }
+}
@@ -0,0 +1,577 @@
+//! 基于留出集的超参数自动调优
+//!
+//! 在已记录的决策日志上离线评估 LinUCB 的 alpha / lambda 与 Beta 先验的参数网格：
+//! 日志按时间顺序切分，前段用于热启动每个候选策略，末段留出集用回放估计
+//! （Li et al. 2011）评估——只保留候选策略选择与日志动作一致的事件，
+//! 有记录倾向分时按 1/p 加权并自归一化。评估期间匹配到的事件也会回灌给策略，
+//! 与线上逐条学习的行为一致。
+//!
+//! 候选策略每个动作的得分为 Beta 后验均值（截距）加上对残差的 LinUCB 估计：
+//! `m_a + θ_aᵀx + alpha·sqrt(xᵀA_a⁻¹x)`，其中 `A_a = λI + Σxxᵀ`，
+//! `b_a = Σ(r - m_a)x`。
+//!
+//! [`uniform_baseline_summary`] 用同一份日志做单次会话的反事实比较：实际执行策略
+//! 的平均奖励与“均匀随机排程”的双稳健（doubly robust）估计之差。
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::matrix::{
+    cholesky_decompose, cholesky_rank1_update, compute_confidence_width, dot_product,
+    solve_cholesky,
+};
+use crate::types::MIN_RANK1_DIAG;
+
+/// 一条已记录的决策
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedDecision {
+    pub context: Vec<f64>,
+    pub action: u32,
+    /// 奖励，约定在 [0, 1]
+    pub reward: f64,
+    /// 日志策略选择该动作的概率；缺省视为均匀加权
+    #[serde(default)]
+    pub propensity: Option<f64>,
+}
+
+/// Beta 先验（Thompson 采样使用的同一组参数）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BetaPrior {
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for BetaPrior {
+    fn default() -> Self {
+        Self {
+            alpha: 1.0,
+            beta: 1.0,
+        }
+    }
+}
+
+/// 待评估的参数网格（取笛卡尔积）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningGrid {
+    pub alphas: Vec<f64>,
+    pub lambdas: Vec<f64>,
+    pub priors: Vec<BetaPrior>,
+}
+
+impl Default for TuningGrid {
+    fn default() -> Self {
+        Self {
+            alphas: vec![0.1, 0.3, 0.5, 1.0, 1.5, 2.0],
+            lambdas: vec![0.1, 0.5, 1.0, 2.0],
+            priors: vec![
+                BetaPrior::default(),
+                BetaPrior {
+                    alpha: 2.0,
+                    beta: 2.0,
+                },
+                BetaPrior {
+                    alpha: 7.0,
+                    beta: 3.0,
+                },
+            ],
+        }
+    }
+}
+
+impl TuningGrid {
+    pub fn candidates(&self) -> Vec<TuningCandidate> {
+        let mut out =
+            Vec::with_capacity(self.alphas.len() * self.lambdas.len() * self.priors.len());
+        for &alpha in &self.alphas {
+            for &lambda in &self.lambdas {
+                for &prior in &self.priors {
+                    out.push(TuningCandidate {
+                        alpha,
+                        lambda,
+                        prior,
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TuningConfig {
+    /// 日志末尾作为留出集的比例
+    pub holdout_fraction: f64,
+    /// 留出集上至少匹配这么多事件，候选才参与比较
+    pub min_matches: u32,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            holdout_fraction: 0.3,
+            min_matches: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningCandidate {
+    pub alpha: f64,
+    pub lambda: f64,
+    pub prior: BetaPrior,
+}
+
+/// 单个候选在留出集上的回放估计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateScore {
+    pub candidate: TuningCandidate,
+    /// 估计的平均奖励
+    pub value: f64,
+    pub std_error: f64,
+    /// 与日志动作一致、计入估计的事件数
+    pub matches: u32,
+    /// 相对日志策略的提升（value - baseline）
+    pub uplift: f64,
+    pub uplift_std_error: f64,
+    pub eligible: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningResult {
+    pub best: CandidateScore,
+    /// 日志策略在留出集上的平均奖励
+    pub baseline_value: f64,
+    pub baseline_std_error: f64,
+    pub training_size: u32,
+    pub holdout_size: u32,
+    /// 全部候选，按 value 降序（不合格的排在最后）
+    pub candidates: Vec<CandidateScore>,
+}
+
+/// 会话内实际策略与均匀随机基线的比较
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterfactualSummary {
+    pub decisions: u32,
+    /// 实际执行策略的平均奖励
+    pub executed_value: f64,
+    pub executed_std_error: f64,
+    /// 均匀随机策略的估计平均奖励
+    pub baseline_value: f64,
+    pub baseline_std_error: f64,
+    /// executed_value - baseline_value（逐决策配对）
+    pub uplift: f64,
+    pub uplift_std_error: f64,
+    /// 相对基线的提升比例；基线为 0 时为 None
+    pub relative_uplift: Option<f64>,
+}
+
+/// 单个动作的在线状态
+struct ArmState {
+    l: Vec<f64>,
+    b: Vec<f64>,
+    successes: f64,
+    trials: f64,
+}
+
+/// 候选参数下的回放策略
+struct ReplayPolicy {
+    candidate: TuningCandidate,
+    d: usize,
+    arms: Vec<ArmState>,
+}
+
+impl ReplayPolicy {
+    fn new(candidate: TuningCandidate, n_actions: usize, d: usize) -> Self {
+        let mut a = vec![0.0; d * d];
+        for i in 0..d {
+            a[i * d + i] = candidate.lambda;
+        }
+        let l = cholesky_decompose(&a, d, candidate.lambda);
+        let arms = (0..n_actions)
+            .map(|_| ArmState {
+                l: l.clone(),
+                b: vec![0.0; d],
+                successes: 0.0,
+                trials: 0.0,
+            })
+            .collect();
+        Self { candidate, d, arms }
+    }
+
+    fn intercept(&self, arm: &ArmState) -> f64 {
+        let prior = self.candidate.prior;
+        (prior.alpha + arm.successes) / (prior.alpha + prior.beta + arm.trials)
+    }
+
+    fn score(&self, arm: &ArmState, x: &[f64]) -> f64 {
+        let theta = solve_cholesky(&arm.l, &arm.b, self.d);
+        self.intercept(arm)
+            + dot_product(&theta, x)
+            + self.candidate.alpha * compute_confidence_width(&arm.l, x, self.d)
+    }
+
+    fn select(&self, x: &[f64]) -> usize {
+        let mut best = 0;
+        let mut best_score = f64::NEG_INFINITY;
+        for (i, arm) in self.arms.iter().enumerate() {
+            let s = self.score(arm, x);
+            if s > best_score {
+                best = i;
+                best_score = s;
+            }
+        }
+        best
+    }
+
+    fn update(&mut self, action: usize, x: &[f64], reward: f64) {
+        let d = self.d;
+        let lambda = self.candidate.lambda;
+        let residual = reward - self.intercept(&self.arms[action]);
+        let arm = &mut self.arms[action];
+        if !cholesky_rank1_update(&mut arm.l, x, d, MIN_RANK1_DIAG) {
+            // 更新失败时从 L·Lᵀ 重建
+            let mut a = vec![0.0; d * d];
+            for i in 0..d {
+                for j in 0..d {
+                    a[i * d + j] = (0..d).map(|k| arm.l[i * d + k] * arm.l[j * d + k]).sum();
+                }
+            }
+            for i in 0..d {
+                for j in 0..d {
+                    a[i * d + j] += x[i] * x[j];
+                }
+            }
+            arm.l = cholesky_decompose(&a, d, lambda);
+        }
+        for (bi, xi) in arm.b.iter_mut().zip(x) {
+            *bi += residual * xi;
+        }
+        arm.successes += reward;
+        arm.trials += 1.0;
+    }
+}
+
+fn clamp_reward(r: f64) -> f64 {
+    if r.is_finite() {
+        r.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// 倾向分权重；缺失或非法时为 1
+fn importance_weight(propensity: Option<f64>) -> f64 {
+    match propensity {
+        Some(p) if p.is_finite() && p > 0.0 => 1.0 / p.min(1.0),
+        _ => 1.0,
+    }
+}
+
+/// 自归一化加权均值及其标准误（按有效样本量）
+fn weighted_mean_se(samples: &[(f64, f64)]) -> (f64, f64) {
+    let sum_w: f64 = samples.iter().map(|(w, _)| w).sum();
+    if sum_w <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let mean = samples.iter().map(|(w, r)| w * r).sum::<f64>() / sum_w;
+    let var = samples
+        .iter()
+        .map(|(w, r)| w * (r - mean).powi(2))
+        .sum::<f64>()
+        / sum_w;
+    let sum_w2: f64 = samples.iter().map(|(w, _)| w * w).sum();
+    let n_eff = sum_w * sum_w / sum_w2;
+    (mean, (var / n_eff).sqrt())
+}
+
+fn evaluate(
+    candidate: TuningCandidate,
+    training: &[LoggedDecision],
+    holdout: &[LoggedDecision],
+    n_actions: usize,
+    d: usize,
+) -> (f64, f64, u32) {
+    let mut policy = ReplayPolicy::new(candidate, n_actions, d);
+    for event in training {
+        policy.update(
+            event.action as usize,
+            &event.context,
+            clamp_reward(event.reward),
+        );
+    }
+
+    let mut samples = Vec::new();
+    for event in holdout {
+        if policy.select(&event.context) != event.action as usize {
+            continue;
+        }
+        let reward = clamp_reward(event.reward);
+        samples.push((importance_weight(event.propensity), reward));
+        policy.update(event.action as usize, &event.context, reward);
+    }
+    let (value, se) = weighted_mean_se(&samples);
+    (value, se, samples.len() as u32)
+}
+
+/// 在留出集上评估参数网格并返回最优配置
+pub fn tune_hyperparameters(
+    log: &[LoggedDecision],
+    n_actions: u32,
+    grid: &TuningGrid,
+    config: &TuningConfig,
+) -> Result<TuningResult, String> {
+    if n_actions == 0 {
+        return Err("n_actions must be positive".to_string());
+    }
+    let d = log
+        .first()
+        .map(|e| e.context.len())
+        .ok_or_else(|| "decision log is empty".to_string())?;
+    if d == 0 {
+        return Err("context must not be empty".to_string());
+    }
+    if let Some(bad) = log
+        .iter()
+        .position(|e| e.context.len() != d || e.action >= n_actions)
+    {
+        return Err(format!("decision {bad} has wrong dimension or action"));
+    }
+    let candidates = grid.candidates();
+    if candidates.is_empty() {
+        return Err("tuning grid is empty".to_string());
+    }
+    if candidates
+        .iter()
+        .any(|c| !(c.alpha.is_finite() && c.alpha >= 0.0 && c.lambda.is_finite() && c.lambda > 0.0))
+    {
+        return Err("grid contains invalid alpha or lambda".to_string());
+    }
+
+    let fraction = if config.holdout_fraction.is_finite() {
+        config.holdout_fraction.clamp(0.05, 0.95)
+    } else {
+        0.3
+    };
+    let holdout_size = ((log.len() as f64 * fraction).round() as usize).clamp(1, log.len());
+    let (training, holdout) = log.split_at(log.len() - holdout_size);
+
+    let baseline: Vec<(f64, f64)> = holdout
+        .iter()
+        .map(|e| (1.0, clamp_reward(e.reward)))
+        .collect();
+    let (baseline_value, baseline_std_error) = weighted_mean_se(&baseline);
+
+    let n_actions = n_actions as usize;
+    let mut scores: Vec<CandidateScore> = candidates
+        .into_par_iter()
+        .map(|candidate| {
+            let (value, std_error, matches) = evaluate(candidate, training, holdout, n_actions, d);
+            CandidateScore {
+                candidate,
+                value,
+                std_error,
+                matches,
+                uplift: value - baseline_value,
+                uplift_std_error: (std_error.powi(2) + baseline_std_error.powi(2)).sqrt(),
+                eligible: matches >= config.min_matches.max(1),
+            }
+        })
+        .collect();
+
+    scores.sort_by(|a, b| {
+        b.eligible
+            .cmp(&a.eligible)
+            .then(b.value.total_cmp(&a.value))
+    });
+    let best = scores
+        .first()
+        .filter(|s| s.eligible)
+        .cloned()
+        .ok_or_else(|| {
+            format!(
+                "no candidate matched at least {} holdout decisions",
+                config.min_matches
+            )
+        })?;
+
+    Ok(TuningResult {
+        best,
+        baseline_value,
+        baseline_std_error,
+        training_size: training.len() as u32,
+        holdout_size: holdout.len() as u32,
+        candidates: scores,
+    })
+}
+
+fn mean_se(values: &[f64]) -> (f64, f64) {
+    let samples: Vec<(f64, f64)> = values.iter().map(|v| (1.0, *v)).collect();
+    weighted_mean_se(&samples)
+}
+
+/// 会话反事实摘要
+///
+/// 基线为每次决策等概率选择 `n_actions` 个动作之一的随机策略，用双稳健估计：
+/// 奖励模型取会话内各动作的 Beta 后验均值 r̂(a)，每条决策贡献
+/// `Σ_a r̂(a)/K + (1/K)/p·(r - r̂(aᵢ))`，其中 p 为记录的倾向分（缺省按确定性
+/// 选择记为 1）。会话内没出现的动作取先验均值。
+pub fn uniform_baseline_summary(
+    log: &[LoggedDecision],
+    n_actions: u32,
+    prior: BetaPrior,
+) -> Result<CounterfactualSummary, String> {
+    if n_actions == 0 {
+        return Err("n_actions must be positive".to_string());
+    }
+    if log.is_empty() {
+        return Err("decision log is empty".to_string());
+    }
+    if let Some(bad) = log.iter().position(|e| e.action >= n_actions) {
+        return Err(format!("decision {bad} has an unknown action"));
+    }
+    let (alpha, beta) = if prior.alpha > 0.0 && prior.beta > 0.0 {
+        (prior.alpha, prior.beta)
+    } else {
+        (1.0, 1.0)
+    };
+
+    let k = n_actions as usize;
+    let mut successes = vec![0.0; k];
+    let mut trials = vec![0.0; k];
+    for event in log {
+        successes[event.action as usize] += clamp_reward(event.reward);
+        trials[event.action as usize] += 1.0;
+    }
+    let model: Vec<f64> = successes
+        .iter()
+        .zip(&trials)
+        .map(|(s, n)| (alpha + s) / (alpha + beta + n))
+        .collect();
+    let direct = model.iter().sum::<f64>() / k as f64;
+
+    let executed: Vec<f64> = log.iter().map(|e| clamp_reward(e.reward)).collect();
+    let baseline: Vec<f64> = log
+        .iter()
+        .zip(&executed)
+        .map(|(event, reward)| {
+            let weight = importance_weight(event.propensity) / k as f64;
+            direct + weight * (reward - model[event.action as usize])
+        })
+        .collect();
+    let differences: Vec<f64> = executed.iter().zip(&baseline).map(|(r, b)| r - b).collect();
+
+    let (executed_value, executed_std_error) = mean_se(&executed);
+    let (baseline_value, baseline_std_error) = mean_se(&baseline);
+    let (uplift, uplift_std_error) = mean_se(&differences);
+    Ok(CounterfactualSummary {
+        decisions: log.len() as u32,
+        executed_value,
+        executed_std_error,
+        baseline_value,
+        baseline_std_error,
+        uplift,
+        uplift_std_error,
+        relative_uplift: (baseline_value > 0.0).then(|| uplift / baseline_value),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::RngFactory;
+
+    /// 两个动作：x[1] > 0.5 时动作 1 更好，否则动作 0；日志策略均匀随机
+    fn synthetic_log(n: usize) -> Vec<LoggedDecision> {
+        let mut rng = RngFactory::new(7).stream("tuning.test");
+        (0..n)
+            .map(|_| {
+                let feature = rng.next_f64();
+                let action = (rng.next_f64() * 2.0) as u32;
+                let good = (feature > 0.5) == (action == 1);
+                let p = if good { 0.85 } else { 0.25 };
+                LoggedDecision {
+                    context: vec![1.0, feature],
+                    action,
+                    reward: if rng.next_f64() < p { 1.0 } else { 0.0 },
+                    propensity: Some(0.5),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tuning_beats_uniform_logging_policy() {
+        let log = synthetic_log(3000);
+        let result =
+            tune_hyperparameters(&log, 2, &TuningGrid::default(), &TuningConfig::default())
+                .unwrap();
+        assert_eq!(result.holdout_size, 900);
+        assert!((result.baseline_value - 0.55).abs() < 0.05);
+        assert!(result.best.eligible);
+        assert!(result.best.uplift > 0.1, "uplift {}", result.best.uplift);
+        assert!(result
+            .candidates
+            .windows(2)
+            .all(|w| !w[1].eligible || w[0].value >= w[1].value));
+    }
+
+    #[test]
+    fn test_no_eligible_candidate_is_an_error() {
+        let log = synthetic_log(40);
+        let config = TuningConfig {
+            holdout_fraction: 0.3,
+            min_matches: 100,
+        };
+        assert!(tune_hyperparameters(&log, 2, &TuningGrid::default(), &config).is_err());
+    }
+
+    #[test]
+    fn test_uniform_baseline_summary() {
+        // 一直选择更好的动作 0（奖励 0.9），动作 1 没出现过，取先验均值 0.5
+        let log: Vec<LoggedDecision> = (0..20)
+            .map(|i| LoggedDecision {
+                context: vec![1.0],
+                action: 0,
+                reward: if i % 10 == 0 { 0.0 } else { 1.0 },
+                propensity: None,
+            })
+            .collect();
+        let summary = uniform_baseline_summary(&log, 2, BetaPrior::default()).unwrap();
+        assert_eq!(summary.decisions, 20);
+        assert!((summary.executed_value - 0.9).abs() < 1e-9);
+        let model = (1.0 + 18.0) / 22.0;
+        let expected_baseline = (model + 0.5) / 2.0 + 0.5 * (0.9 - model);
+        assert!((summary.baseline_value - expected_baseline).abs() < 1e-9);
+        assert!(summary.uplift > 0.15);
+        assert!(summary.relative_uplift.unwrap() > 0.25);
+
+        // 随机日志下实际策略与基线相当
+        let random = synthetic_log(2000);
+        let summary = uniform_baseline_summary(&random, 2, BetaPrior::default()).unwrap();
+        assert!(summary.uplift.abs() < 3.0 * summary.uplift_std_error + 0.01);
+
+        assert!(uniform_baseline_summary(&[], 2, BetaPrior::default()).is_err());
+        assert!(uniform_baseline_summary(&log, 0, BetaPrior::default()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_log() {
+        let mut log = synthetic_log(10);
+        log[3].action = 5;
+        assert!(
+            tune_hyperparameters(&log, 2, &TuningGrid::default(), &TuningConfig::default())
+                .is_err()
+        );
+        assert!(
+            tune_hyperparameters(&[], 2, &TuningGrid::default(), &TuningConfig::default()).is_err()
+        );
+    }
+}
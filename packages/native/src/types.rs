@@ -1,7 +1,13 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str::FromStr;
 #[cfg(feature = "napi")]
 use napi_derive::napi;
+
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+
+use crate::float;
 
 // 常量定义 (与 TS 对齐)
 pub const FEATURE_DIMENSION: usize = 22;
@@ -11,6 +17,8 @@ pub const MAX_COVARIANCE: f64 = 1e9;
 pub const MAX_FEATURE_ABS: f64 = 50.0;
 pub const EPSILON: f64 = 1e-10;
 pub const CHOLESKY_RECOMPUTE_INTERVAL: u32 = 200;
+/// L·Lᵀ 相对 A 的漂移超过该值视为数值漂移
+pub const MAX_RECONSTRUCTION_DRIFT: f64 = 1e-6;
 
 /// BanditModel 结构体 (字段命名与 TS 对齐)
 #[cfg_attr(feature = "napi", napi(object))]
@@ -186,6 +194,11 @@ pub struct DiagnosticResult {
     pub min_diagonal: f64,
     #[serde(rename = "maxDiagonal")]
     pub max_diagonal: f64,
+    /// ||L·Lᵀ - A||_F / ||A||_F
+    #[serde(rename = "reconstructionDrift", default)]
+    pub reconstruction_drift: f64,
+    #[serde(rename = "hasDrift", default)]
+    pub has_drift: bool,
     pub message: String,
 }
 
@@ -193,7 +206,7 @@ impl Default for BanditModel {
     fn default() -> Self {
         let d = FEATURE_DIMENSION;
         let lambda: f64 = 1.0;
-        let sqrt_lambda = lambda.sqrt();
+        let sqrt_lambda = float::sqrt(lambda);
 
         // A = λI
         let mut a_matrix = vec![0.0; d * d];
@@ -329,8 +342,8 @@ mod tests {
         ];
 
         for (name, expected_index) in difficulties {
-            let difficulty =
-                Difficulty::try_from_str(name).expect(&format!("{} should be valid", name));
+            let difficulty = Difficulty::try_from_str(name)
+                .unwrap_or_else(|| panic!("{} should be valid", name));
             assert_eq!(difficulty.to_index(), expected_index);
         }
     }
@@ -338,7 +351,7 @@ mod tests {
     #[test]
     fn test_difficulty_index_uniqueness() {
         // 确保所有难度的索引都是唯一的
-        let all_difficulties = vec![
+        let all_difficulties = [
             Difficulty::Recognition,
             Difficulty::Recall,
             Difficulty::Spelling,
@@ -486,12 +499,14 @@ mod tests {
     #[test]
     fn test_constants() {
         assert_eq!(FEATURE_DIMENSION, 22);
-        assert!(MIN_LAMBDA > 0.0);
-        assert!(MIN_RANK1_DIAG > 0.0);
-        assert!(MAX_COVARIANCE > 0.0);
-        assert!(MAX_FEATURE_ABS > 0.0);
-        assert!(EPSILON > 0.0);
-        assert!(EPSILON < 1e-6);
+        const {
+            assert!(MIN_LAMBDA > 0.0);
+            assert!(MIN_RANK1_DIAG > 0.0);
+            assert!(MAX_COVARIANCE > 0.0);
+            assert!(MAX_FEATURE_ABS > 0.0);
+            assert!(EPSILON > 0.0);
+            assert!(EPSILON < 1e-6);
+        }
     }
 
     // ============ Difficulty 序列化测试 ============
@@ -0,0 +1,136 @@
+//! 向量时钟：多设备同步的写冲突检测
+//!
+//! 按时间戳“后写者胜”在设备时钟偏差时会丢更新。每个可同步的行带一个向量
+//! 时钟（设备 id → 该设备在此行上的写次数），写入时只递增本设备的计数：
+//!
+//! - 一方的每个分量都不小于另一方且至少一个更大：前者包含后者的全部写入，
+//!   直接取前者；
+//! - 各有对方没有的写入：真正的并发冲突，需要交给合并函数；
+//! - 合并后的时钟取逐分量最大值。
+//!
+//! 缺失的分量视为 0，因此空时钟早于任何非空时钟。
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// 两个时钟的先后关系（以 `self` 相对 `other` 表述）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Causality {
+    Equal,
+    /// `self` 早于 `other`，已被其包含
+    Before,
+    /// `self` 晚于 `other`，包含其全部写入
+    After,
+    /// 并发写入，需要合并
+    Concurrent,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VectorClock {
+    counters: BTreeMap<String, u64>,
+}
+
+impl VectorClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    pub fn get(&self, node: &str) -> u64 {
+        self.counters.get(node).copied().unwrap_or(0)
+    }
+
+    /// 记录 `node` 上的一次写入，返回新计数
+    pub fn tick(&mut self, node: &str) -> u64 {
+        let counter = self.counters.entry(node.to_string()).or_insert(0);
+        *counter = counter.saturating_add(1);
+        *counter
+    }
+
+    /// 逐分量取最大值
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (node, &count) in &other.counters {
+            let counter = self.counters.entry(node.clone()).or_insert(0);
+            *counter = (*counter).max(count);
+        }
+    }
+
+    pub fn merged(&self, other: &VectorClock) -> VectorClock {
+        let mut clock = self.clone();
+        clock.merge(other);
+        clock
+    }
+
+    pub fn compare(&self, other: &VectorClock) -> Causality {
+        let mut ordering = Ordering::Equal;
+        let nodes = self.counters.keys().chain(other.counters.keys());
+        for node in nodes {
+            match (self.get(node).cmp(&other.get(node)), ordering) {
+                (Ordering::Equal, _) => {}
+                (o, Ordering::Equal) => ordering = o,
+                (o, current) if o != current => return Causality::Concurrent,
+                _ => {}
+            }
+        }
+        match ordering {
+            Ordering::Equal => Causality::Equal,
+            Ordering::Less => Causality::Before,
+            Ordering::Greater => Causality::After,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(entries: &[(&str, u64)]) -> VectorClock {
+        let mut c = VectorClock::new();
+        for &(node, n) in entries {
+            for _ in 0..n {
+                c.tick(node);
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn test_compare() {
+        let base = clock(&[("a", 2), ("b", 1)]);
+        assert_eq!(base.compare(&base.clone()), Causality::Equal);
+        assert_eq!(VectorClock::new().compare(&base), Causality::Before);
+
+        let mut ahead = base.clone();
+        ahead.tick("a");
+        assert_eq!(ahead.compare(&base), Causality::After);
+        assert_eq!(base.compare(&ahead), Causality::Before);
+
+        let mut other = base.clone();
+        other.tick("c");
+        assert_eq!(ahead.compare(&other), Causality::Concurrent);
+        assert_eq!(other.compare(&ahead), Causality::Concurrent);
+    }
+
+    #[test]
+    fn test_merge_dominates_both_sides() {
+        let a = clock(&[("a", 3), ("b", 1)]);
+        let b = clock(&[("b", 2), ("c", 1)]);
+        let merged = a.merged(&b);
+        assert_eq!(merged.get("a"), 3);
+        assert_eq!(merged.get("b"), 2);
+        assert_eq!(merged.get("c"), 1);
+        assert_eq!(merged.compare(&a), Causality::After);
+        assert_eq!(merged.compare(&b), Causality::After);
+
+        let json = serde_json::to_string(&merged).unwrap();
+        assert_eq!(json, r#"{"a":3,"b":2,"c":1}"#);
+        assert_eq!(serde_json::from_str::<VectorClock>(&json).unwrap(), merged);
+    }
+}
@@ -0,0 +1,371 @@
+//! Memoized ACT-R activations.
+//!
+//! Recall batches (the due list, ACT-R selection, book mastery) load every
+//! word's review trace from SQLite and sum its decayed strengths, even though
+//! most traces have not changed since the previous batch. Activations are
+//! cached per `(wordBookId, wordId, direction)` together with the
+//! fingerprint of the traces they came from: the length and newest timestamp
+//! of the card's own trace and of the opposite direction's (which transfers
+//! into it), the decay rate and the transfer factor. A batch reads all
+//! fingerprints in one query and only loads the traces whose fingerprint
+//! differs from the cached one, so a new answer in either direction
+//! invalidates its card without any event.
+//!
+//! Activation also depends on the time it is evaluated at. An entry is reused
+//! for `max_skew_ms` after it was computed, which by default is one tick of
+//! the algorithm context, so recalls within a tick agree with each other.
+//! Compaction and learning-data syncs can rewrite a trace without changing
+//! its fingerprint and drop the affected entries through the bus.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use danci_native::mastery::{linked_activation, recall_from_activation, MasteryBandConfig};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::events::{AppEvent, EventBus, SyncKind};
+use crate::lock_order::{LockRank, RankedMutex};
+use crate::stats::{self, CardDirection};
+use crate::sync::SyncPhase;
+
+/// Fingerprints are read this many words per query, well under SQLite's
+/// bound-parameter limit.
+const FINGERPRINT_CHUNK: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivationCacheConfig {
+    /// Maximum number of cached activations; 0 disables the cache.
+    pub capacity: usize,
+    /// How long after its evaluation time an activation is reused.
+    pub max_skew_ms: i64,
+}
+
+impl Default for ActivationCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 8192,
+            max_skew_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ActivationCacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Misses on an entry whose trace has changed; included in `misses`.
+    pub stale: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+    /// Hits over lookups, or `None` before the first lookup.
+    pub hit_rate: Option<f64>,
+}
+
+/// Length and newest answer time of one card's trace.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TraceMark {
+    count: i64,
+    last_ms: i64,
+}
+
+/// What an activation was computed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    own: TraceMark,
+    other: TraceMark,
+    decay_bits: u64,
+    transfer_bits: u64,
+}
+
+type Key = (String, String, CardDirection);
+
+struct Entry {
+    fingerprint: Fingerprint,
+    /// `None` when the trace has no usable reviews.
+    activation: Option<f64>,
+    evaluated_at_ms: i64,
+    recency: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    config: ActivationCacheConfig,
+    entries: HashMap<Key, Entry>,
+    /// Recency stamp to key; the first entry is the least recently used.
+    order: BTreeMap<u64, Key>,
+    next_recency: u64,
+    stats: ActivationCacheStats,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &Key) {
+        let recency = self.next_recency;
+        self.next_recency += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.recency);
+            entry.recency = recency;
+            self.order.insert(recency, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.recency);
+        }
+    }
+
+    fn evict_to_capacity(&mut self) {
+        while self.entries.len() > self.config.capacity {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+pub struct ActivationCache {
+    lru: RankedMutex<Lru>,
+}
+
+impl Default for ActivationCache {
+    fn default() -> Self {
+        Self {
+            lru: RankedMutex::with_default(LockRank::ActivationCache),
+        }
+    }
+}
+
+impl ActivationCache {
+    pub fn new(config: ActivationCacheConfig) -> Self {
+        let cache = Self::default();
+        if let Ok(mut lru) = cache.lru.lock() {
+            lru.config = config;
+        }
+        cache
+    }
+
+    fn get(&self, key: &Key, fingerprint: Fingerprint, now_ms: i64) -> Option<Option<f64>> {
+        let mut lru = self.lru.lock().ok()?;
+        let max_skew_ms = lru.config.max_skew_ms;
+        // (trace unchanged, evaluated recently enough)
+        let state = lru.entries.get(key).map(|entry| {
+            (
+                entry.fingerprint == fingerprint,
+                (0..=max_skew_ms).contains(&(now_ms - entry.evaluated_at_ms)),
+            )
+        });
+        match state {
+            Some((true, true)) => {
+                lru.stats.hits += 1;
+                lru.touch(key);
+                lru.entries.get(key).map(|entry| entry.activation)
+            }
+            Some((same_trace, _)) => {
+                lru.stats.misses += 1;
+                if !same_trace {
+                    lru.stats.stale += 1;
+                }
+                lru.remove(key);
+                None
+            }
+            None => {
+                lru.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&self, key: Key, fingerprint: Fingerprint, activation: Option<f64>, now_ms: i64) {
+        let Ok(mut lru) = self.lru.lock() else {
+            return;
+        };
+        if lru.config.capacity == 0 {
+            return;
+        }
+        lru.remove(&key);
+        let recency = lru.next_recency;
+        lru.next_recency += 1;
+        lru.order.insert(recency, key.clone());
+        lru.entries.insert(
+            key,
+            Entry {
+                fingerprint,
+                activation,
+                evaluated_at_ms: now_ms,
+                recency,
+            },
+        );
+        lru.evict_to_capacity();
+    }
+
+    /// Drops one wordbook's activations, or all of them when `word_book_id`
+    /// is `None`.
+    pub fn invalidate(&self, word_book_id: Option<&str>) {
+        let Ok(mut lru) = self.lru.lock() else {
+            return;
+        };
+        lru.stats.invalidations += 1;
+        match word_book_id {
+            None => {
+                lru.entries.clear();
+                lru.order.clear();
+            }
+            Some(book) => {
+                let keys: Vec<Key> = lru
+                    .entries
+                    .keys()
+                    .filter(|(b, _, _)| b == book)
+                    .cloned()
+                    .collect();
+                for key in &keys {
+                    lru.remove(key);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ActivationCacheStats {
+        let Ok(lru) = self.lru.lock() else {
+            return ActivationCacheStats::default();
+        };
+        let lookups = lru.stats.hits + lru.stats.misses;
+        ActivationCacheStats {
+            capacity: lru.config.capacity,
+            entries: lru.entries.len(),
+            hit_rate: (lookups > 0).then(|| lru.stats.hits as f64 / lookups as f64),
+            ..lru.stats.clone()
+        }
+    }
+
+    /// ACT-R recall of each of `word_ids`' cards in `direction` at `now_ms`,
+    /// in request order, with the opposite direction's reviews transferring
+    /// in. Only cards whose traces changed since they were cached, or whose
+    /// cached activation is older than `max_skew_ms`, load their traces from
+    /// SQLite.
+    pub async fn recalls(
+        &self,
+        pool: &SqlitePool,
+        book_id: &str,
+        word_ids: &[String],
+        direction: CardDirection,
+        config: &MasteryBandConfig,
+        now_ms: i64,
+    ) -> Result<Vec<f64>, sqlx::Error> {
+        let marks = trace_marks(pool, book_id, word_ids).await?;
+        let mut out = Vec::with_capacity(word_ids.len());
+        for word_id in word_ids {
+            let mark = |direction: CardDirection| {
+                marks
+                    .get(&(word_id.clone(), direction))
+                    .copied()
+                    .unwrap_or_default()
+            };
+            let fingerprint = Fingerprint {
+                own: mark(direction),
+                other: mark(direction.opposite()),
+                decay_bits: config.decay.to_bits(),
+                transfer_bits: config.transfer.to_bits(),
+            };
+            let key = (book_id.to_string(), word_id.clone(), direction);
+            let activation = match self.get(&key, fingerprint, now_ms) {
+                Some(activation) => activation,
+                None => {
+                    let activation = if fingerprint.own.count == 0 && fingerprint.other.count == 0 {
+                        None
+                    } else {
+                        let own =
+                            stats::review_events(pool, book_id, word_id, direction, now_ms).await?;
+                        let other = stats::review_events(
+                            pool,
+                            book_id,
+                            word_id,
+                            direction.opposite(),
+                            now_ms,
+                        )
+                        .await?;
+                        linked_activation(&own, &other, config.decay, config.transfer)
+                    };
+                    self.insert(key, fingerprint, activation, now_ms);
+                    activation
+                }
+            };
+            out.push(activation.map_or(0.0, |a| recall_from_activation(a, config)));
+        }
+        Ok(out)
+    }
+}
+
+/// Trace length and newest answer time per card, over raw answers and
+/// compacted days alike. Cards without any answer are left out.
+async fn trace_marks(
+    pool: &SqlitePool,
+    book_id: &str,
+    word_ids: &[String],
+) -> Result<HashMap<(String, CardDirection), TraceMark>, sqlx::Error> {
+    let mut out = HashMap::with_capacity(word_ids.len());
+    for chunk in word_ids.chunks(FINGERPRINT_CHUNK) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+        let sql = format!(
+            r#"SELECT "wordId", "direction", SUM("answers") AS "traceCount",
+                      MAX("lastAt") AS "lastAt" FROM (
+                   SELECT "wordId", "direction", COUNT(*) AS "answers",
+                          MAX("timestamp") AS "lastAt"
+                   FROM "answer_records"
+                   WHERE "wordBookId" = ? AND "wordId" IN ({placeholders})
+                   GROUP BY "wordId", "direction"
+                   UNION ALL
+                   SELECT "wordId", "direction", SUM("answers") AS "answers",
+                          MAX("timestampSum" / "answers") AS "lastAt"
+                   FROM "answer_daily_summaries"
+                   WHERE "wordBookId" = ? AND "wordId" IN ({placeholders}) AND "answers" > 0
+                   GROUP BY "wordId", "direction"
+               )
+               GROUP BY "wordId", "direction""#
+        );
+        let mut query = sqlx::query(&sql).bind(book_id);
+        for id in chunk {
+            query = query.bind(id);
+        }
+        query = query.bind(book_id);
+        for id in chunk {
+            query = query.bind(id);
+        }
+        for row in query.fetch_all(pool).await? {
+            let direction: String = row.try_get("direction")?;
+            out.insert(
+                (row.try_get("wordId")?, CardDirection::from_db(&direction)),
+                TraceMark {
+                    count: row.try_get("traceCount")?,
+                    last_ms: row.try_get("lastAt")?,
+                },
+            );
+        }
+    }
+    Ok(out)
+}
+
+/// Registers this module's event handlers. New answers change their word's
+/// fingerprint and need no event; compaction folds answers into daily rows
+/// and pulled syncs may replace them, so those drop the cache.
+pub fn subscribe(bus: &EventBus, cache: Arc<ActivationCache>) {
+    bus.subscribe_with("activation_cache", move |event| match event {
+        AppEvent::CompactionProgress { compacted, .. } if compacted > 0 => cache.invalidate(None),
+        AppEvent::SyncCompleted {
+            kind: SyncKind::LearningData,
+            word_book_id,
+        } => cache.invalidate(word_book_id.as_deref()),
+        AppEvent::SyncProgress {
+            phase: SyncPhase::Pulled,
+            rows,
+            ..
+        } if rows > 0 => cache.invalidate(None),
+        _ => {}
+    });
+}
@@ -0,0 +1,491 @@
+//! Content-addressed store for per-word audio and image assets.
+//!
+//! Files live under `<app data>/assets/<first two hex chars>/<sha256>` and are
+//! tracked in the local `assets` table with a reference count. Each wordbook
+//! references assets through `asset_refs`; syncing a wordbook's manifest
+//! downloads what is missing, re-points changed entries and drops entries no
+//! longer listed. Assets whose count reaches zero are removed by
+//! [`AssetStore::collect_garbage`].
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+use tokio::sync::RwLock;
+
+/// Unreferenced assets are kept this long in case a wordbook is re-downloaded.
+pub const GC_GRACE: Duration = Duration::from_secs(24 * 3600);
+const TMP_SUFFIX: &str = ".tmp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum AssetError {
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("download failed: {0}")]
+    Download(String),
+    #[error("hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: String, actual: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Audio,
+    Image,
+}
+
+impl AssetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetKind::Audio => "audio",
+            AssetKind::Image => "image",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub word_id: String,
+    pub kind: AssetKind,
+    pub url: String,
+    /// Expected SHA-256 (hex). When present, an asset already in the store is
+    /// reused without downloading and downloads are verified against it.
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// Full list of assets a downloaded wordbook needs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AssetManifest {
+    pub word_book_id: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FailedAsset {
+    pub word_id: String,
+    pub kind: AssetKind,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub downloaded: u32,
+    pub reused: u32,
+    pub released: u32,
+    pub failed: Vec<FailedAsset>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    pub removed_assets: u32,
+    pub removed_bytes: u64,
+    pub orphan_files: u32,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone)]
+pub struct AssetStore {
+    root: PathBuf,
+    pool: SqlitePool,
+    /// Shared by a manifest entry from lookup until its reference is
+    /// written, exclusive during garbage collection, so an unreferenced
+    /// asset a sync is about to reuse is never deleted in between.
+    gc_lock: Arc<RwLock<()>>,
+}
+
+impl AssetStore {
+    pub fn new(root: PathBuf, pool: SqlitePool) -> Self {
+        Self {
+            root,
+            pool,
+            gc_lock: Arc::default(),
+        }
+    }
+
+    pub fn path_for(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+
+    /// Whether `hash` is recorded and its file is present.
+    pub async fn contains(&self, hash: &str) -> Result<bool, AssetError> {
+        if !is_hash(hash) {
+            return Ok(false);
+        }
+        let row = sqlx::query(r#"SELECT 1 FROM "assets" WHERE "hash" = ?"#)
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some() && tokio::fs::try_exists(self.path_for(hash)).await?)
+    }
+
+    /// Stores `bytes` under their SHA-256 and returns the hash. The new asset
+    /// starts unreferenced.
+    pub async fn put(&self, bytes: &[u8], mime_type: Option<&str>) -> Result<String, AssetError> {
+        let hash = sha256_hex(bytes);
+        let path = self.path_for(&hash);
+        if !tokio::fs::try_exists(&path).await? {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            // Write then rename so a crash never leaves a truncated file under
+            // a valid hash.
+            let tmp = path.with_file_name(format!("{hash}{TMP_SUFFIX}"));
+            tokio::fs::write(&tmp, bytes).await?;
+            tokio::fs::rename(&tmp, &path).await?;
+        }
+        let now = now_ms();
+        sqlx::query(
+            r#"INSERT INTO "assets" ("hash", "size", "mimeType", "refCount", "createdAt", "lastReferencedAt")
+               VALUES (?, ?, ?, 0, ?, ?)
+               ON CONFLICT ("hash") DO NOTHING"#,
+        )
+        .bind(&hash)
+        .bind(bytes.len() as i64)
+        .bind(mime_type)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(hash)
+    }
+
+    /// Local file for a word's asset, from whichever wordbook references it.
+    pub async fn resolve(
+        &self,
+        word_id: &str,
+        kind: AssetKind,
+    ) -> Result<Option<PathBuf>, AssetError> {
+        let hash: Option<String> = sqlx::query_scalar(
+            r#"SELECT "hash" FROM "asset_refs" WHERE "wordId" = ? AND "kind" = ? LIMIT 1"#,
+        )
+        .bind(word_id)
+        .bind(kind.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(hash) = hash else {
+            return Ok(None);
+        };
+        let path = self.path_for(&hash);
+        Ok(tokio::fs::try_exists(&path).await?.then_some(path))
+    }
+
+    /// Brings a wordbook's references in line with its manifest. Entries that
+    /// fail keep their previous asset, if any, and are reported.
+    pub async fn sync_manifest(&self, manifest: &AssetManifest) -> Result<SyncReport, AssetError> {
+        let mut report = SyncReport::default();
+        let existing = self.load_refs(&manifest.word_book_id).await?;
+        let mut wanted: HashSet<(String, AssetKind)> = HashSet::new();
+
+        for entry in &manifest.entries {
+            wanted.insert((entry.word_id.clone(), entry.kind));
+            let current = existing.get(&(entry.word_id.clone(), entry.kind));
+            let _gc = self.gc_lock.read().await;
+            match self.ensure_entry(entry, current).await {
+                Ok((hash, downloaded)) => {
+                    if downloaded {
+                        report.downloaded += 1;
+                    } else {
+                        report.reused += 1;
+                    }
+                    self.set_ref(&manifest.word_book_id, entry, &hash).await?;
+                }
+                Err(e) => report.failed.push(FailedAsset {
+                    word_id: entry.word_id.clone(),
+                    kind: entry.kind,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        for (word_id, kind) in existing.keys() {
+            if !wanted.contains(&(word_id.clone(), *kind)) {
+                self.drop_ref(&manifest.word_book_id, word_id, *kind)
+                    .await?;
+                report.released += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Drops every reference held by a wordbook (e.g. when it is removed).
+    pub async fn release_wordbook(&self, word_book_id: &str) -> Result<u32, AssetError> {
+        let refs = self.load_refs(word_book_id).await?;
+        for (word_id, kind) in refs.keys() {
+            self.drop_ref(word_book_id, word_id, *kind).await?;
+        }
+        Ok(refs.len() as u32)
+    }
+
+    /// Deletes assets that have been unreferenced for longer than `grace`,
+    /// plus files on disk that the table does not know about.
+    pub async fn collect_garbage(&self, grace: Duration) -> Result<GcReport, AssetError> {
+        let _gc = self.gc_lock.write().await;
+        let mut report = GcReport::default();
+        let cutoff = now_ms() - grace.as_millis() as i64;
+        let rows = sqlx::query(
+            r#"SELECT "hash", "size" FROM "assets" WHERE "refCount" <= 0 AND "lastReferencedAt" < ?"#,
+        )
+        .bind(cutoff)
+        .fetch_all(&self.pool)
+        .await?;
+        for row in rows {
+            let hash: String = row.try_get("hash")?;
+            let size: i64 = row.try_get("size")?;
+            let deleted =
+                sqlx::query(r#"DELETE FROM "assets" WHERE "hash" = ? AND "refCount" <= 0"#)
+                    .bind(&hash)
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+            if deleted == 0 {
+                continue;
+            }
+            remove_if_exists(&self.path_for(&hash)).await?;
+            report.removed_assets += 1;
+            report.removed_bytes += size.max(0) as u64;
+        }
+
+        let known: HashSet<String> = sqlx::query_scalar(r#"SELECT "hash" FROM "assets""#)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .collect();
+        report.orphan_files = self.sweep_orphans(&known, grace).await?;
+        Ok(report)
+    }
+
+    async fn ensure_entry(
+        &self,
+        entry: &ManifestEntry,
+        current: Option<&(String, String)>,
+    ) -> Result<(String, bool), AssetError> {
+        let expected = entry.sha256.as_deref().map(str::to_ascii_lowercase);
+        if let Some(expected) = &expected {
+            if self.contains(expected).await? {
+                return Ok((expected.clone(), false));
+            }
+        } else if let Some((hash, url)) = current {
+            // Without a declared hash, an unchanged URL is trusted to be unchanged content.
+            if url == &entry.url && self.contains(hash).await? {
+                return Ok((hash.clone(), false));
+            }
+        }
+
+        let (bytes, mime_type) = download(&entry.url).await?;
+        if let Some(expected) = expected {
+            let actual = sha256_hex(&bytes);
+            if actual != expected {
+                return Err(AssetError::HashMismatch { expected, actual });
+            }
+        }
+        let hash = self.put(&bytes, mime_type.as_deref()).await?;
+        Ok((hash, true))
+    }
+
+    /// (wordId, kind) -> (hash, sourceUrl) for a wordbook.
+    async fn load_refs(
+        &self,
+        word_book_id: &str,
+    ) -> Result<HashMap<(String, AssetKind), (String, String)>, AssetError> {
+        let rows = sqlx::query(
+            r#"SELECT "wordId", "kind", "hash", "sourceUrl" FROM "asset_refs" WHERE "wordBookId" = ?"#,
+        )
+        .bind(word_book_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut refs = HashMap::new();
+        for row in rows {
+            let kind = match row.try_get::<String, _>("kind")?.as_str() {
+                "audio" => AssetKind::Audio,
+                "image" => AssetKind::Image,
+                _ => continue,
+            };
+            refs.insert(
+                (row.try_get("wordId")?, kind),
+                (row.try_get("hash")?, row.try_get("sourceUrl")?),
+            );
+        }
+        Ok(refs)
+    }
+
+    async fn set_ref(
+        &self,
+        word_book_id: &str,
+        entry: &ManifestEntry,
+        hash: &str,
+    ) -> Result<(), AssetError> {
+        let now = now_ms();
+        let mut tx = self.pool.begin().await?;
+        let previous: Option<String> = sqlx::query_scalar(
+            r#"SELECT "hash" FROM "asset_refs" WHERE "wordBookId" = ? AND "wordId" = ? AND "kind" = ?"#,
+        )
+        .bind(word_book_id)
+        .bind(&entry.word_id)
+        .bind(entry.kind.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"INSERT INTO "asset_refs" ("wordBookId", "wordId", "kind", "hash", "sourceUrl")
+               VALUES (?, ?, ?, ?, ?)
+               ON CONFLICT ("wordBookId", "wordId", "kind")
+               DO UPDATE SET "hash" = excluded."hash", "sourceUrl" = excluded."sourceUrl""#,
+        )
+        .bind(word_book_id)
+        .bind(&entry.word_id)
+        .bind(entry.kind.as_str())
+        .bind(hash)
+        .bind(&entry.url)
+        .execute(&mut *tx)
+        .await?;
+
+        if previous.as_deref() != Some(hash) {
+            sqlx::query(
+                r#"UPDATE "assets" SET "refCount" = "refCount" + 1, "lastReferencedAt" = ? WHERE "hash" = ?"#,
+            )
+            .bind(now)
+            .bind(hash)
+            .execute(&mut *tx)
+            .await?;
+            if let Some(previous) = previous {
+                decrement(&mut tx, &previous, now).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn drop_ref(
+        &self,
+        word_book_id: &str,
+        word_id: &str,
+        kind: AssetKind,
+    ) -> Result<(), AssetError> {
+        let mut tx = self.pool.begin().await?;
+        let previous: Option<String> = sqlx::query_scalar(
+            r#"DELETE FROM "asset_refs" WHERE "wordBookId" = ? AND "wordId" = ? AND "kind" = ?
+               RETURNING "hash""#,
+        )
+        .bind(word_book_id)
+        .bind(word_id)
+        .bind(kind.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(previous) = previous {
+            decrement(&mut tx, &previous, now_ms()).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes files under the store root whose hash is not in `known`.
+    /// Temp files are only removed once older than `grace`, since a download
+    /// may still be writing them.
+    async fn sweep_orphans(
+        &self,
+        known: &HashSet<String>,
+        grace: Duration,
+    ) -> Result<u32, AssetError> {
+        let mut removed = 0;
+        let Ok(mut shards) = tokio::fs::read_dir(&self.root).await else {
+            return Ok(0);
+        };
+        while let Some(shard) = shards.next_entry().await? {
+            if !shard.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut files = tokio::fs::read_dir(shard.path()).await?;
+            while let Some(file) = files.next_entry().await? {
+                let name = file.file_name().to_string_lossy().into_owned();
+                let orphan = if name.ends_with(TMP_SUFFIX) {
+                    let age = file
+                        .metadata()
+                        .await?
+                        .modified()
+                        .ok()
+                        .and_then(|m| m.elapsed().ok())
+                        .unwrap_or_default();
+                    age > grace
+                } else {
+                    !known.contains(&name)
+                };
+                if orphan {
+                    remove_if_exists(&file.path()).await?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+async fn decrement(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    hash: &str,
+    now: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE "assets" SET "refCount" = MAX("refCount" - 1, 0), "lastReferencedAt" = ? WHERE "hash" = ?"#,
+    )
+    .bind(now)
+    .bind(hash)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn remove_if_exists(path: &Path) -> Result<(), std::io::Error> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+async fn download(url: &str) -> Result<(Vec<u8>, Option<String>), AssetError> {
+    let response = tauri_plugin_http::reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AssetError::Download(e.to_string()))?;
+    let mime_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| AssetError::Download(e.to_string()))?;
+    Ok((bytes.to_vec(), mime_type))
+}
@@ -0,0 +1,162 @@
+//! Local decision audit log.
+//!
+//! Each selection (which word to show next, how big the next batch is) is
+//! stored as one compact row in `decision_audit`, so a report like "the app
+//! keeps showing the same word" can be checked against what was actually
+//! chosen. The table is a ring buffer: rows past `CAPACITY` are pruned on
+//! insert, oldest first.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+/// Rows kept in the ring buffer.
+pub const CAPACITY: i64 = 5_000;
+/// Upper bound for one export.
+pub const MAX_EXPORT: i64 = 1_000;
+/// Longest `context` string stored per row.
+const MAX_CONTEXT_LEN: usize = 512;
+
+/// Min / max / mean of the candidate scores and the chosen one's score.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreSummary {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub chosen: Option<f64>,
+}
+
+impl ScoreSummary {
+    pub fn of(scores: &[f64], chosen: Option<f64>) -> Self {
+        let finite: Vec<f64> = scores.iter().copied().filter(|s| s.is_finite()).collect();
+        if finite.is_empty() {
+            return Self {
+                chosen,
+                ..Self::default()
+            };
+        }
+        Self {
+            min: finite.iter().copied().reduce(f64::min),
+            max: finite.iter().copied().reduce(f64::max),
+            mean: Some(finite.iter().sum::<f64>() / finite.len() as f64),
+            chosen,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionRecord {
+    /// Unix ms; filled in when stored if zero.
+    #[serde(default)]
+    pub timestamp: i64,
+    /// What was decided, e.g. `word_selection` or `batch_size`.
+    pub kind: String,
+    pub candidates: i64,
+    /// Chosen word id, or the decided value for numeric decisions.
+    pub chosen: String,
+    #[serde(default)]
+    pub scores: ScoreSummary,
+    /// Free-form detail (session id, algorithm, reason); truncated when stored.
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+/// Stored row, as returned by [`export_recent`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    #[serde(flatten)]
+    pub record: DecisionRecord,
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Appends one record and prunes rows beyond [`CAPACITY`].
+pub async fn record(pool: &SqlitePool, record: &DecisionRecord) -> Result<i64, sqlx::Error> {
+    let timestamp = if record.timestamp > 0 {
+        record.timestamp
+    } else {
+        now_ms()
+    };
+    let context = record
+        .context
+        .as_deref()
+        .map(|c| c.chars().take(MAX_CONTEXT_LEN).collect::<String>());
+
+    let mut tx = pool.begin().await?;
+    let id = sqlx::query(
+        r#"INSERT INTO "decision_audit"
+           ("timestamp", "kind", "candidates", "chosen", "scoreMin", "scoreMax", "scoreMean", "scoreChosen", "context")
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(timestamp)
+    .bind(&record.kind)
+    .bind(record.candidates)
+    .bind(&record.chosen)
+    .bind(record.scores.min)
+    .bind(record.scores.max)
+    .bind(record.scores.mean)
+    .bind(record.scores.chosen)
+    .bind(context)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+    sqlx::query(r#"DELETE FROM "decision_audit" WHERE "id" <= ?"#)
+        .bind(id - CAPACITY)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Records without failing the caller: a selection must not break because
+/// its audit row could not be written.
+pub async fn record_logged(pool: &SqlitePool, record: &DecisionRecord) {
+    if let Err(e) = self::record(pool, record).await {
+        log::warn!("decision audit write failed: {e}");
+    }
+}
+
+/// The `n` most recent records, newest first.
+pub async fn export_recent(pool: &SqlitePool, n: i64) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    sqlx::query(
+        r#"SELECT "id", "timestamp", "kind", "candidates", "chosen",
+                  "scoreMin", "scoreMax", "scoreMean", "scoreChosen", "context"
+           FROM "decision_audit"
+           ORDER BY "id" DESC
+           LIMIT ?"#,
+    )
+    .bind(n.clamp(1, MAX_EXPORT))
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        Ok(AuditEntry {
+            id: row.try_get("id")?,
+            record: DecisionRecord {
+                timestamp: row.try_get("timestamp")?,
+                kind: row.try_get("kind")?,
+                candidates: row.try_get("candidates")?,
+                chosen: row.try_get("chosen")?,
+                scores: ScoreSummary {
+                    min: row.try_get("scoreMin")?,
+                    max: row.try_get("scoreMax")?,
+                    mean: row.try_get("scoreMean")?,
+                    chosen: row.try_get("scoreChosen")?,
+                },
+                context: row.try_get("context")?,
+            },
+        })
+    })
+    .collect()
+}
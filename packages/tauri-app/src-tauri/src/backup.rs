@@ -0,0 +1,181 @@
+//! Crash-consistent snapshots of the local database.
+//!
+//! A snapshot is written with `VACUUM INTO` on one of the pool's own
+//! connections, which copies a single read transaction's view of the whole
+//! database while the app keeps writing. The copy goes to a `.tmp` file that
+//! is checked, fsynced and then renamed into place, so a kill at any point
+//! leaves either the previous file or a complete new one — never a torn copy.
+//!
+//! Rolling backups live under `<app data>/backups/` and only the newest
+//! [`KEEP_BACKUPS`] are kept.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{Connection, SqlitePool};
+
+pub const KEEP_BACKUPS: usize = 3;
+/// A rolling backup is taken on start when the newest one is older than this.
+pub const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+const BACKUP_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "danci-local-";
+const BACKUP_EXT: &str = ".db";
+const TMP_SUFFIX: &str = ".tmp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("snapshot failed integrity check: {0}")]
+    Corrupt(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub path: String,
+    pub size: u64,
+    /// Unix ms.
+    pub created_at: i64,
+}
+
+/// Writes a consistent copy of the database to `dest`, replacing it atomically.
+pub async fn snapshot(pool: &SqlitePool, dest: &Path) -> Result<SnapshotInfo, BackupError> {
+    if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp = tmp_path(dest);
+    // VACUUM INTO refuses to overwrite, so clear a leftover from a killed run.
+    remove_if_exists(&tmp).await?;
+
+    let result = write_verified(pool, &tmp).await;
+    if let Err(e) = result {
+        let _ = remove_if_exists(&tmp).await;
+        return Err(e);
+    }
+
+    tokio::fs::rename(&tmp, dest).await?;
+    sync_dir(dest.parent()).await?;
+
+    let size = tokio::fs::metadata(dest).await?.len();
+    Ok(SnapshotInfo {
+        path: dest.to_string_lossy().into_owned(),
+        size,
+        created_at: now_ms(),
+    })
+}
+
+/// Takes a new rolling backup and prunes all but the newest [`KEEP_BACKUPS`].
+pub async fn rolling_backup(
+    pool: &SqlitePool,
+    data_dir: &Path,
+) -> Result<SnapshotInfo, BackupError> {
+    let dir = data_dir.join(BACKUP_DIR);
+    let dest = dir.join(format!("{BACKUP_PREFIX}{}{BACKUP_EXT}", now_ms()));
+    let info = snapshot(pool, &dest).await?;
+
+    for stale in list_backups(data_dir).await?.into_iter().skip(KEEP_BACKUPS) {
+        remove_if_exists(Path::new(&stale.path)).await?;
+    }
+    Ok(info)
+}
+
+/// Rolling backups, newest first.
+pub async fn list_backups(data_dir: &Path) -> Result<Vec<SnapshotInfo>, BackupError> {
+    let dir = data_dir.join(BACKUP_DIR);
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut backups = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let Some(created_at) = name
+            .to_str()
+            .and_then(|n| n.strip_prefix(BACKUP_PREFIX))
+            .and_then(|n| n.strip_suffix(BACKUP_EXT))
+            .and_then(|ts| ts.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        backups.push(SnapshotInfo {
+            path: entry.path().to_string_lossy().into_owned(),
+            size: entry.metadata().await?.len(),
+            created_at,
+        });
+    }
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// Takes a rolling backup unless a recent enough one already exists.
+pub async fn backup_if_due(
+    pool: &SqlitePool,
+    data_dir: &Path,
+) -> Result<Option<SnapshotInfo>, BackupError> {
+    let newest = list_backups(data_dir).await?.into_iter().next();
+    let interval_ms = BACKUP_INTERVAL.as_millis() as i64;
+    if newest.is_some_and(|b| now_ms() - b.created_at < interval_ms) {
+        return Ok(None);
+    }
+    rolling_backup(pool, data_dir).await.map(Some)
+}
+
+async fn write_verified(pool: &SqlitePool, tmp: &Path) -> Result<(), BackupError> {
+    sqlx::query("VACUUM INTO ?")
+        .bind(tmp.to_string_lossy().into_owned())
+        .execute(pool)
+        .await?;
+
+    let options = SqliteConnectOptions::new().filename(tmp).read_only(true);
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+    let check: String = sqlx::query_scalar("PRAGMA quick_check")
+        .fetch_one(&mut conn)
+        .await?;
+    conn.close().await?;
+    if check != "ok" {
+        return Err(BackupError::Corrupt(check));
+    }
+
+    tokio::fs::File::open(tmp).await?.sync_all().await?;
+    Ok(())
+}
+
+fn tmp_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(TMP_SUFFIX);
+    PathBuf::from(name)
+}
+
+async fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Persists the rename itself. Directories cannot be opened for syncing on
+/// Windows, so there the rename is left to the file system.
+async fn sync_dir(dir: Option<&Path>) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Some(dir) = dir {
+        tokio::fs::File::open(dir).await?.sync_all().await?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,115 @@
+//! Vector clocks of locally written rows, for conflict detection on sync.
+//!
+//! Every local write of a synced row (a word's learning state, a model
+//! snapshot) increments this device's entry in the row's clock. Sync sends
+//! the clock along, and the server compares it with its own: a copy that has
+//! seen every write of the other wins, concurrent copies are merged. Rows
+//! taken from the server adopt the server's clock without a local tick.
+//!
+//! The device id is generated on first use and kept in `device_identity`.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use danci_native::vclock::VectorClock;
+use sqlx::{SqliteConnection, SqlitePool};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockScope {
+    /// Keyed by word id, as on the server.
+    WordState,
+    /// Keyed by model name.
+    ModelSnapshot,
+}
+
+impl ClockScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            ClockScope::WordState => "word_state",
+            ClockScope::ModelSnapshot => "model_snapshot",
+        }
+    }
+}
+
+/// `prefix-` followed by 32 random hex digits.
+pub fn random_id(prefix: &str) -> String {
+    let mut hi = RandomState::new().build_hasher();
+    hi.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0),
+    );
+    let lo = RandomState::new().build_hasher();
+    format!("{prefix}-{:016x}{:016x}", hi.finish(), lo.finish())
+}
+
+/// This installation's id in vector clocks.
+pub async fn device_id(pool: &SqlitePool) -> Result<String, sqlx::Error> {
+    sqlx::query(r#"INSERT OR IGNORE INTO "device_identity" ("id", "deviceId") VALUES (1, ?)"#)
+        .bind(random_id("desktop"))
+        .execute(pool)
+        .await?;
+    sqlx::query_scalar(r#"SELECT "deviceId" FROM "device_identity" WHERE "id" = 1"#)
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn load(
+    conn: &mut SqliteConnection,
+    scope: ClockScope,
+    key: &str,
+) -> Result<VectorClock, sqlx::Error> {
+    let clock: Option<String> =
+        sqlx::query_scalar(r#"SELECT "clock" FROM "sync_clocks" WHERE "scope" = ? AND "key" = ?"#)
+            .bind(scope.as_str())
+            .bind(key)
+            .fetch_optional(&mut *conn)
+            .await?;
+    Ok(clock
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default())
+}
+
+pub async fn store(
+    conn: &mut SqliteConnection,
+    scope: ClockScope,
+    key: &str,
+    clock: &VectorClock,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO "sync_clocks" ("scope", "key", "clock") VALUES (?, ?, ?)
+           ON CONFLICT ("scope", "key") DO UPDATE SET "clock" = excluded."clock""#,
+    )
+    .bind(scope.as_str())
+    .bind(key)
+    .bind(serde_json::to_string(clock).unwrap_or_default())
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Records a local write of the row.
+pub async fn tick(
+    conn: &mut SqliteConnection,
+    scope: ClockScope,
+    key: &str,
+    device: &str,
+) -> Result<VectorClock, sqlx::Error> {
+    let mut clock = load(conn, scope, key).await?;
+    clock.tick(device);
+    store(conn, scope, key, &clock).await?;
+    Ok(clock)
+}
+
+/// Takes in a clock received from the server for the row.
+pub async fn adopt(
+    conn: &mut SqliteConnection,
+    scope: ClockScope,
+    key: &str,
+    remote: &VectorClock,
+) -> Result<VectorClock, sqlx::Error> {
+    let clock = load(conn, scope, key).await?.merged(remote);
+    store(conn, scope, key, &clock).await?;
+    Ok(clock)
+}
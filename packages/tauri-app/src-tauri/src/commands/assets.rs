@@ -0,0 +1,55 @@
+use tauri::State;
+
+use crate::assets::{AssetKind, AssetManifest, AssetStore, GcReport, SyncReport, GC_GRACE};
+use crate::events::{AppEvent, EventBus, SyncKind};
+
+/// Download/re-point the assets listed in a wordbook's manifest.
+#[tauri::command]
+pub async fn sync_wordbook_assets(
+    store: State<'_, AssetStore>,
+    bus: State<'_, EventBus>,
+    manifest: AssetManifest,
+) -> Result<SyncReport, String> {
+    let report = store
+        .sync_manifest(&manifest)
+        .await
+        .map_err(|e| format!("Failed to sync wordbook assets: {e}"))?;
+    bus.publish(AppEvent::SyncCompleted {
+        kind: SyncKind::Assets,
+        word_book_id: Some(manifest.word_book_id),
+    });
+    Ok(report)
+}
+
+/// Local file path of a word's asset, if it has been synced.
+#[tauri::command]
+pub async fn resolve_word_asset(
+    store: State<'_, AssetStore>,
+    word_id: String,
+    kind: AssetKind,
+) -> Result<Option<String>, String> {
+    let path = store
+        .resolve(&word_id, kind)
+        .await
+        .map_err(|e| format!("Failed to resolve asset: {e}"))?;
+    Ok(path.map(|p| p.to_string_lossy().into_owned()))
+}
+
+#[tauri::command]
+pub async fn release_wordbook_assets(
+    store: State<'_, AssetStore>,
+    word_book_id: String,
+) -> Result<u32, String> {
+    store
+        .release_wordbook(&word_book_id)
+        .await
+        .map_err(|e| format!("Failed to release wordbook assets: {e}"))
+}
+
+#[tauri::command]
+pub async fn collect_asset_garbage(store: State<'_, AssetStore>) -> Result<GcReport, String> {
+    store
+        .collect_garbage(GC_GRACE)
+        .await
+        .map_err(|e| format!("Failed to collect asset garbage: {e}"))
+}
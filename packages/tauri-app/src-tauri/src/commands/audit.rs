@@ -0,0 +1,38 @@
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::audit::{self, AuditEntry, DecisionRecord, ScoreSummary};
+
+/// Log a selection made in the webview (e.g. the next word).
+#[tauri::command]
+pub async fn audit_record_decision(
+    pool: State<'_, SqlitePool>,
+    kind: String,
+    candidate_scores: Vec<f64>,
+    chosen: String,
+    chosen_score: Option<f64>,
+    context: Option<String>,
+) -> Result<i64, String> {
+    let record = DecisionRecord {
+        timestamp: 0,
+        kind,
+        candidates: candidate_scores.len() as i64,
+        chosen,
+        scores: ScoreSummary::of(&candidate_scores, chosen_score),
+        context,
+    };
+    audit::record(&pool, &record)
+        .await
+        .map_err(|e| format!("Failed to write decision audit: {e}"))
+}
+
+/// Most recent `n` decisions, newest first, for attaching to a bug report.
+#[tauri::command]
+pub async fn audit_export_recent(
+    pool: State<'_, SqlitePool>,
+    n: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    audit::export_recent(&pool, n.unwrap_or(200))
+        .await
+        .map_err(|e| format!("Failed to read decision audit: {e}"))
+}
@@ -0,0 +1,36 @@
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::backup::{self, SnapshotInfo};
+
+/// Write a consistent copy of the local database to a user-chosen path.
+#[tauri::command]
+pub async fn export_database(
+    pool: State<'_, SqlitePool>,
+    path: String,
+) -> Result<SnapshotInfo, String> {
+    backup::snapshot(&pool, std::path::Path::new(&path))
+        .await
+        .map_err(|e| format!("Failed to export database: {e}"))
+}
+
+#[tauri::command]
+pub async fn create_local_backup<R: Runtime>(
+    app: AppHandle<R>,
+    pool: State<'_, SqlitePool>,
+) -> Result<SnapshotInfo, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    backup::rolling_backup(&pool, &data_dir)
+        .await
+        .map_err(|e| format!("Failed to back up database: {e}"))
+}
+
+#[tauri::command]
+pub async fn list_local_backups<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<SnapshotInfo>, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    backup::list_backups(&data_dir)
+        .await
+        .map_err(|e| format!("Failed to list backups: {e}"))
+}
@@ -0,0 +1,40 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::compaction::{self, CompactionReport, DEFAULT_HORIZON_DAYS, DEFAULT_MAX_ROWS};
+use crate::events::{AppEvent, EventBus};
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Fold answers older than `horizon_days` (at least 30) into daily summaries,
+/// moving at most `max_rows` rows. Progress is emitted as
+/// `compaction-progress` after each batch; call again while `done` is false.
+#[tauri::command]
+pub async fn compact_answer_history(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    horizon_days: Option<i64>,
+    max_rows: Option<i64>,
+) -> Result<CompactionReport, String> {
+    compaction::compact_answer_records(
+        &pool,
+        horizon_days.unwrap_or(DEFAULT_HORIZON_DAYS),
+        max_rows.unwrap_or(DEFAULT_MAX_ROWS).max(0),
+        now_ms(),
+        |compacted, remaining| {
+            bus.publish(AppEvent::CompactionProgress {
+                compacted,
+                remaining,
+            })
+        },
+    )
+    .await
+    .map_err(|e| format!("Failed to compact answer history: {e}"))
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use danci_native::cancel::{
+    self, CancellableBatch, CancellationToken, IntervalInput, ReviewInterval,
+};
+use danci_native::compute::{self, ComputeBudget, ComputeMode};
+use danci_native::language::LanguageParams;
+use tauri::State;
+
+use crate::lock_order::{LockRank, RankedMutex};
+use crate::packed::PackedBatch;
+use crate::validation::{self, StateError, StateValidator};
+
+#[tauri::command]
+pub async fn get_compute_mode() -> Result<ComputeBudget, String> {
+    Ok(compute::get_compute_budget())
+}
+
+#[tauri::command]
+pub async fn set_compute_mode(mode: String) -> Result<ComputeBudget, String> {
+    let mode =
+        ComputeMode::try_from_str(&mode).ok_or_else(|| format!("Unknown compute mode: {mode}"))?;
+    Ok(compute::set_compute_mode(mode))
+}
+
+/// Cancel handles for long batch computations. The UI creates a handle before
+/// starting a computation and cancels it when the user navigates away; the
+/// computation removes its handle when it returns.
+pub struct CancelHandles {
+    next_id: AtomicU64,
+    tokens: RankedMutex<HashMap<u64, CancellationToken>>,
+}
+
+impl Default for CancelHandles {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::default(),
+            tokens: RankedMutex::with_default(LockRank::CancelHandles),
+        }
+    }
+}
+
+impl CancelHandles {
+    fn create(&self) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.insert(id, CancellationToken::new());
+        }
+        id
+    }
+
+    fn token(&self, id: u64) -> Result<CancellationToken, String> {
+        self.tokens
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown cancel handle: {id}"))
+    }
+
+    fn cancel(&self, id: u64) -> bool {
+        match self.tokens.lock() {
+            Ok(tokens) => tokens.get(&id).map(CancellationToken::cancel).is_some(),
+            Err(_) => false,
+        }
+    }
+
+    fn release(&self, id: u64) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.remove(&id);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn create_cancel_handle(handles: State<'_, CancelHandles>) -> Result<u64, String> {
+    Ok(handles.create())
+}
+
+/// Returns false when the handle is unknown or its computation already finished.
+#[tauri::command]
+pub async fn cancel_computation(
+    handles: State<'_, CancelHandles>,
+    handle: u64,
+) -> Result<bool, String> {
+    Ok(handles.cancel(handle))
+}
+
+/// Review intervals for a batch of words. The result holds the intervals for
+/// the first `completed` words; when that is less than `total` (cancelled,
+/// or cut to the compute budget's batch size), call again with the rest to
+/// resume.
+///
+/// `urgent` defaults to true. Background refreshes of intervals already
+/// shown should pass false: in eco mode they are deferred, and the result
+/// is empty with `completed` 0 and `cancelled` false.
+#[tauri::command]
+pub async fn compute_review_intervals(
+    handles: State<'_, CancelHandles>,
+    handle: Option<u64>,
+    words: Vec<IntervalInput>,
+    language_params: Option<LanguageParams>,
+    urgent: Option<bool>,
+) -> Result<CancellableBatch<ReviewInterval>, StateError> {
+    review_intervals(&handles, handle, words, language_params, urgent).await
+}
+
+/// [`compute_review_intervals`] as a [`PackedBatch`]: one `intervalDays`
+/// field per record, record `i` for `words[i]`, and `completed`, `total`
+/// and `cancelled` in the header's `meta`. Deferral and resuming work the
+/// same way.
+#[tauri::command]
+pub async fn compute_review_intervals_packed(
+    handles: State<'_, CancelHandles>,
+    handle: Option<u64>,
+    words: Vec<IntervalInput>,
+    language_params: Option<LanguageParams>,
+    urgent: Option<bool>,
+) -> Result<tauri::ipc::Response, StateError> {
+    let batch = review_intervals(&handles, handle, words, language_params, urgent).await?;
+    let mut packed = PackedBatch::new(
+        &["intervalDays"],
+        batch.items.len(),
+        serde_json::json!({
+            "completed": batch.completed,
+            "total": batch.total,
+            "cancelled": batch.cancelled,
+        }),
+    );
+    for item in &batch.items {
+        packed.push(&[item.interval_days]);
+    }
+    packed.into_response().map_err(StateError::from)
+}
+
+async fn review_intervals(
+    handles: &CancelHandles,
+    handle: Option<u64>,
+    mut words: Vec<IntervalInput>,
+    language_params: Option<LanguageParams>,
+    urgent: Option<bool>,
+) -> Result<CancellableBatch<ReviewInterval>, StateError> {
+    let mut params = language_params.unwrap_or_default();
+    let mut v = StateValidator::default();
+    validation::language_params(&mut v, &mut params);
+    let clamped = v.finish("language parameters")?;
+    validation::report_clamped("compute_review_intervals", &clamped);
+
+    let budget = compute::get_compute_budget();
+    let total = words.len() as u32;
+    if !budget.should_run(urgent.unwrap_or(true)) {
+        if let Some(id) = handle {
+            handles.release(id);
+        }
+        return Ok(CancellableBatch {
+            items: Vec::new(),
+            completed: 0,
+            total,
+            cancelled: false,
+        });
+    }
+    words.truncate(budget.clamp_batch(words.len()));
+
+    let token = match handle {
+        Some(id) => handles.token(id)?,
+        None => CancellationToken::new(),
+    };
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        cancel::review_intervals_batch(&words, &params, &token)
+    })
+    .await
+    .map(|batch| CancellableBatch { total, ..batch })
+    .map_err(|e| StateError::from(e.to_string()));
+    if let Some(id) = handle {
+        handles.release(id);
+    }
+    result
+}
@@ -0,0 +1,410 @@
+//! Context shared by the LinUCB, Thompson and ACT-R commands.
+//!
+//! All three look at the same clock and session: time of day, how long the
+//! session has run, its accuracy and the fatigue derived from them. The
+//! [`ContextBuilder`] assembles that once per tick and hands the cached copy
+//! to every algorithm command, so a selection made by one algorithm and a
+//! score from another within the same tick agree on their inputs. Answers,
+//! learning-data syncs and finished sessions change the session stats and
+//! drop the cache.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use danci_native::mastery::MasteryBandConfig;
+use danci_native::LinUCBContext;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+
+use crate::activation_cache::ActivationCache;
+use crate::events::{AppEvent, EventBus, SyncKind};
+use crate::lock_order::{LockRank, RankedMutex};
+use crate::packed::PackedBatch;
+use crate::stats::CardDirection;
+
+/// Context is rebuilt at most once per tick unless invalidated.
+const TICK_MS: i64 = 30_000;
+/// Answers at the end of the session used for the recent accuracy.
+const RECENT_WINDOW: i64 = 10;
+/// Session length at which the duration part of fatigue saturates.
+const FATIGUE_FULL_MINUTES: f64 = 60.0;
+/// Share of fatigue from session length; the rest comes from the accuracy drop.
+const FATIGUE_DURATION_WEIGHT: f64 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmContext {
+    pub session_id: Option<String>,
+    pub tick: i64,
+    /// Unix ms the context was built for; ACT-R ages reviews against it.
+    pub now_ms: i64,
+    /// Local hour as a fraction of the day, in [0, 1).
+    pub time_of_day: f64,
+    /// Local weekday, 0 = Sunday.
+    pub day_of_week: u32,
+    pub session_minutes: f64,
+    pub answers: u32,
+    pub accuracy: f64,
+    pub recent_accuracy: f64,
+    pub average_response_ms: f64,
+    /// 0–1 from session length and the drop from overall to recent accuracy.
+    pub fatigue: f64,
+}
+
+impl AlgorithmContext {
+    pub fn linucb(&self) -> LinUCBContext {
+        LinUCBContext {
+            time_of_day: self.time_of_day,
+            day_of_week: self.day_of_week,
+            session_duration: self.session_minutes,
+            fatigue_factor: Some(self.fatigue),
+        }
+    }
+
+    pub fn thompson(&self) -> ThompsonContext {
+        let time_bucket = match (self.time_of_day * 24.0) as u32 {
+            5..=11 => "morning",
+            12..=17 => "afternoon",
+            18..=22 => "evening",
+            _ => "night",
+        };
+        let fatigue_bucket = if self.fatigue < 0.33 {
+            "low"
+        } else if self.fatigue < 0.66 {
+            "mid"
+        } else {
+            "high"
+        };
+        ThompsonContext {
+            time_bucket,
+            fatigue_bucket,
+            key: format!("{time_bucket}:{fatigue_bucket}"),
+        }
+    }
+}
+
+/// Discrete context Thompson sampling keeps separate Beta posteriors for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ThompsonContext {
+    pub time_bucket: &'static str,
+    pub fatigue_bucket: &'static str,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WordRecall {
+    pub word_id: String,
+    pub direction: CardDirection,
+    pub recall: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheKey {
+    session_id: Option<String>,
+    utc_offset_minutes: i32,
+    tick: i64,
+}
+
+pub struct ContextBuilder {
+    cached: RankedMutex<Option<(CacheKey, AlgorithmContext)>>,
+}
+
+impl Default for ContextBuilder {
+    fn default() -> Self {
+        Self {
+            cached: RankedMutex::with_default(LockRank::ContextCache),
+        }
+    }
+}
+
+impl ContextBuilder {
+    /// Cached context for this session and tick, building it when missing.
+    pub async fn get(
+        &self,
+        pool: &SqlitePool,
+        session_id: Option<String>,
+        utc_offset_minutes: i32,
+    ) -> Result<AlgorithmContext, sqlx::Error> {
+        let now_ms = now_ms();
+        let key = CacheKey {
+            session_id,
+            utc_offset_minutes,
+            tick: now_ms.div_euclid(TICK_MS),
+        };
+        if let Ok(cached) = self.cached.lock() {
+            if let Some((k, context)) = cached.as_ref() {
+                if *k == key {
+                    return Ok(context.clone());
+                }
+            }
+        }
+        let context = build(pool, &key, now_ms).await?;
+        if let Ok(mut cached) = self.cached.lock() {
+            *cached = Some((key, context.clone()));
+        }
+        Ok(context)
+    }
+
+    pub fn invalidate(&self) {
+        if let Ok(mut cached) = self.cached.lock() {
+            *cached = None;
+        }
+    }
+}
+
+/// Registers this module's event handlers.
+pub fn subscribe(bus: &EventBus, builder: Arc<ContextBuilder>) {
+    bus.subscribe_with("algorithm.context", move |event| match event {
+        AppEvent::AnswersRecorded { .. }
+        | AppEvent::SessionFinished { .. }
+        | AppEvent::SyncProgress { .. }
+        | AppEvent::SyncCompleted {
+            kind: SyncKind::LearningData,
+            ..
+        } => builder.invalidate(),
+        _ => {}
+    });
+}
+
+async fn build(
+    pool: &SqlitePool,
+    key: &CacheKey,
+    now_ms: i64,
+) -> Result<AlgorithmContext, sqlx::Error> {
+    let local_ms = now_ms + i64::from(key.utc_offset_minutes) * 60_000;
+    let day_ms = 24 * 3600 * 1000;
+    let time_of_day = local_ms.rem_euclid(day_ms) as f64 / day_ms as f64;
+    // 1970-01-01 was a Thursday.
+    let day_of_week = (local_ms.div_euclid(day_ms) + 4).rem_euclid(7) as u32;
+
+    let mut context = AlgorithmContext {
+        session_id: key.session_id.clone(),
+        tick: key.tick,
+        now_ms,
+        time_of_day,
+        day_of_week,
+        session_minutes: 0.0,
+        answers: 0,
+        accuracy: 0.0,
+        recent_accuracy: 0.0,
+        average_response_ms: 0.0,
+        fatigue: 0.0,
+    };
+    let Some(session_id) = key.session_id.as_deref() else {
+        return Ok(context);
+    };
+
+    let row = sqlx::query(
+        r#"SELECT COUNT(*) AS "answers",
+                  COALESCE(SUM("isCorrect"), 0) AS "correct",
+                  COALESCE(AVG("responseTime"), 0.0) AS "avgResponse",
+                  MIN("timestamp") AS "startedAt"
+           FROM "answer_records" WHERE "sessionId" = ?"#,
+    )
+    .bind(session_id)
+    .fetch_one(pool)
+    .await?;
+    let answers: i64 = row.try_get("answers")?;
+    if answers == 0 {
+        return Ok(context);
+    }
+    let correct: i64 = row.try_get("correct")?;
+    let started_at: i64 = row.try_get("startedAt")?;
+    let recent: f64 = sqlx::query(
+        r#"SELECT COALESCE(AVG("isCorrect"), 0.0) AS "recent" FROM (
+               SELECT "isCorrect" FROM "answer_records" WHERE "sessionId" = ?
+               ORDER BY "timestamp" DESC LIMIT ?
+           )"#,
+    )
+    .bind(session_id)
+    .bind(RECENT_WINDOW)
+    .fetch_one(pool)
+    .await?
+    .try_get("recent")?;
+
+    context.answers = answers as u32;
+    context.accuracy = correct as f64 / answers as f64;
+    context.recent_accuracy = recent;
+    context.average_response_ms = row.try_get("avgResponse")?;
+    context.session_minutes = (now_ms - started_at).max(0) as f64 / 60_000.0;
+    let duration = (context.session_minutes / FATIGUE_FULL_MINUTES).min(1.0);
+    let accuracy_drop = (context.accuracy - context.recent_accuracy).clamp(0.0, 1.0);
+    context.fatigue =
+        FATIGUE_DURATION_WEIGHT * duration + (1.0 - FATIGUE_DURATION_WEIGHT) * accuracy_drop;
+    Ok(context)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// The shared context of the current tick. `utc_offset_minutes` is the local
+/// offset east of UTC (the negated JS `getTimezoneOffset()`).
+#[tauri::command]
+pub async fn get_algorithm_context(
+    pool: State<'_, SqlitePool>,
+    builder: State<'_, Arc<ContextBuilder>>,
+    session_id: Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<AlgorithmContext, String> {
+    builder
+        .get(&pool, session_id, utc_offset_minutes.unwrap_or(0))
+        .await
+        .map_err(|e| format!("Failed to build algorithm context: {e}"))
+}
+
+/// LinUCB context features for the current tick.
+#[tauri::command]
+pub async fn get_linucb_context(
+    pool: State<'_, SqlitePool>,
+    builder: State<'_, Arc<ContextBuilder>>,
+    session_id: Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<LinUCBContext, String> {
+    get_algorithm_context(pool, builder, session_id, utc_offset_minutes)
+        .await
+        .map(|c| c.linucb())
+}
+
+/// Thompson sampling context bucket for the current tick.
+#[tauri::command]
+pub async fn get_thompson_context(
+    pool: State<'_, SqlitePool>,
+    builder: State<'_, Arc<ContextBuilder>>,
+    session_id: Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<ThompsonContext, String> {
+    get_algorithm_context(pool, builder, session_id, utc_offset_minutes)
+        .await
+        .map(|c| c.thompson())
+}
+
+/// ACT-R recall probability of each word's card in each of `directions`
+/// (recognition only by default) at the context's time, lowest first, i.e.
+/// in the order ACT-R selection would review them.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_actr_recall(
+    pool: State<'_, SqlitePool>,
+    builder: State<'_, Arc<ContextBuilder>>,
+    activations: State<'_, Arc<ActivationCache>>,
+    book_id: String,
+    word_ids: Vec<String>,
+    directions: Option<Vec<CardDirection>>,
+    session_id: Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<WordRecall>, String> {
+    let recalls = recalls_by_direction(
+        &pool,
+        &builder,
+        &activations,
+        &book_id,
+        &word_ids,
+        directions,
+        session_id,
+        utc_offset_minutes,
+    )
+    .await?;
+    let mut out = Vec::with_capacity(word_ids.len() * recalls.len());
+    for (direction, recalls) in recalls {
+        out.extend(
+            word_ids
+                .iter()
+                .zip(recalls)
+                .map(|(word_id, recall)| WordRecall {
+                    word_id: word_id.clone(),
+                    direction,
+                    recall,
+                }),
+        );
+    }
+    out.sort_by(|a, b| a.recall.total_cmp(&b.recall));
+    Ok(out)
+}
+
+/// [`get_actr_recall`] as a [`PackedBatch`], unsorted: one `recall` field
+/// per record, grouped by the directions listed in the header's
+/// `meta.directions` and in `word_ids` order within each, so record
+/// `d * word_ids.len() + i` is `word_ids[i]` in direction `d`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_actr_recall_packed(
+    pool: State<'_, SqlitePool>,
+    builder: State<'_, Arc<ContextBuilder>>,
+    activations: State<'_, Arc<ActivationCache>>,
+    book_id: String,
+    word_ids: Vec<String>,
+    directions: Option<Vec<CardDirection>>,
+    session_id: Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<tauri::ipc::Response, String> {
+    let recalls = recalls_by_direction(
+        &pool,
+        &builder,
+        &activations,
+        &book_id,
+        &word_ids,
+        directions,
+        session_id,
+        utc_offset_minutes,
+    )
+    .await?;
+    let directions: Vec<CardDirection> = recalls.iter().map(|(d, _)| *d).collect();
+    let mut packed = PackedBatch::new(
+        &["recall"],
+        word_ids.len() * directions.len(),
+        serde_json::json!({ "directions": directions }),
+    );
+    for recall in recalls.iter().flat_map(|(_, r)| r) {
+        packed.push(&[*recall]);
+    }
+    packed.into_response()
+}
+
+/// Recalls of `word_ids` in request order for each requested direction
+/// (recognition only by default), directions in [`CardDirection::ALL`] order.
+#[allow(clippy::too_many_arguments)]
+async fn recalls_by_direction(
+    pool: &SqlitePool,
+    builder: &ContextBuilder,
+    activations: &ActivationCache,
+    book_id: &str,
+    word_ids: &[String],
+    directions: Option<Vec<CardDirection>>,
+    session_id: Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<(CardDirection, Vec<f64>)>, String> {
+    let context = builder
+        .get(pool, session_id, utc_offset_minutes.unwrap_or(0))
+        .await
+        .map_err(|e| format!("Failed to build algorithm context: {e}"))?;
+    let directions = directions.unwrap_or_else(|| vec![CardDirection::Recognition]);
+    let mut out = Vec::with_capacity(directions.len());
+    for direction in CardDirection::ALL
+        .into_iter()
+        .filter(|d| directions.contains(d))
+    {
+        let recalls = activations
+            .recalls(
+                pool,
+                book_id,
+                word_ids,
+                direction,
+                &MasteryBandConfig::default(),
+                context.now_ms,
+            )
+            .await
+            .map_err(|e| format!("Failed to load review history: {e}"))?;
+        out.push((direction, recalls));
+    }
+    Ok(out)
+}
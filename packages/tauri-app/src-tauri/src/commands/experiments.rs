@@ -0,0 +1,192 @@
+//! Offline A/B assignment.
+//!
+//! Definitions of running experiments come from
+//! `GET /api/evaluation/experiments/sync` and are cached in the store. The
+//! variant is computed locally with the backend's bucketing
+//! (`danci_native::experiments`), so a device that never reached the server
+//! lands in the same variant the server would have picked. Each first exposure
+//! is queued in SQLite and reported via `POST /api/evaluation/exposures` once
+//! the device is back online, either by the webview or by operation journal
+//! replay during sync, which also drops the queued row.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use danci_native::experiments::ExperimentDefinition;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::events::{AppEvent, EventBus, SyncKind};
+use crate::journal::{self, Operation};
+
+const STORE_PATH: &str = ".danci-store.json";
+const EXPERIMENTS_KEY: &str = "experiments";
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentAssignment {
+    pub experiment_id: String,
+    pub variant_id: String,
+    pub variant_name: String,
+    pub is_control: bool,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PendingExposure {
+    pub experiment_id: String,
+    pub variant_id: String,
+    pub exposed_at: i64,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn load_experiments<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<ExperimentDefinition>, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    Ok(store
+        .get(EXPERIMENTS_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default())
+}
+
+/// Queues the first exposure and journals it for replay in one transaction.
+async fn record_exposure(
+    pool: &SqlitePool,
+    user_id: &str,
+    experiment_id: &str,
+    variant_id: &str,
+) -> Result<(), sqlx::Error> {
+    let exposed_at = now_ms();
+    let mut tx = pool.begin().await?;
+    let inserted = sqlx::query(
+        r#"INSERT OR IGNORE INTO "pending_experiment_exposures"
+           ("userId", "experimentId", "variantId", "exposedAt") VALUES (?, ?, ?, ?)"#,
+    )
+    .bind(user_id)
+    .bind(experiment_id)
+    .bind(variant_id)
+    .bind(exposed_at)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if inserted > 0 {
+        let op = Operation::RecordExposure {
+            user_id: user_id.to_string(),
+            experiment_id: experiment_id.to_string(),
+            variant_id: variant_id.to_string(),
+            exposed_at,
+        };
+        journal::append(&mut tx, &op, exposed_at).await?;
+    }
+    tx.commit().await
+}
+
+/// Persist definitions returned by `GET /api/evaluation/experiments/sync`.
+/// Experiments missing from the list have stopped and are no longer assigned.
+#[tauri::command]
+pub async fn apply_experiment_sync<R: Runtime>(
+    app: AppHandle<R>,
+    bus: State<'_, EventBus>,
+    definitions: Vec<ExperimentDefinition>,
+) -> Result<usize, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(&definitions)
+        .map_err(|e| format!("Failed to serialize experiments: {e}"))?;
+    store.set(EXPERIMENTS_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to persist experiments: {e}"))?;
+    bus.publish(AppEvent::SyncCompleted {
+        kind: SyncKind::Experiments,
+        word_book_id: None,
+    });
+    Ok(definitions.len())
+}
+
+/// The caller's variant in a cached experiment, or `None` when the experiment
+/// is unknown or not running. The first exposure is queued for reporting.
+#[tauri::command]
+pub async fn get_experiment_variant<R: Runtime>(
+    app: AppHandle<R>,
+    pool: State<'_, SqlitePool>,
+    experiment_id: String,
+    user_id: String,
+) -> Result<Option<ExperimentAssignment>, String> {
+    let experiments = load_experiments(&app)?;
+    let Some(experiment) = experiments.iter().find(|e| e.id == experiment_id) else {
+        return Ok(None);
+    };
+    let Some(variant) = experiment.assign(&user_id) else {
+        return Ok(None);
+    };
+
+    record_exposure(&pool, &user_id, &experiment.id, &variant.id)
+        .await
+        .map_err(|e| format!("Failed to record experiment exposure: {e}"))?;
+
+    Ok(Some(ExperimentAssignment {
+        experiment_id: experiment.id.clone(),
+        variant_id: variant.id.clone(),
+        variant_name: variant.name.clone(),
+        is_control: variant.is_control,
+        parameters: variant.parameters.clone(),
+    }))
+}
+
+/// Exposures not yet reported to the server, oldest first.
+#[tauri::command]
+pub async fn list_pending_exposures(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+) -> Result<Vec<PendingExposure>, String> {
+    let rows = sqlx::query(
+        r#"SELECT "experimentId", "variantId", "exposedAt" FROM "pending_experiment_exposures"
+           WHERE "userId" = ? ORDER BY "exposedAt""#,
+    )
+    .bind(&user_id)
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| format!("Failed to load pending exposures: {e}"))?;
+    rows.iter()
+        .map(|row| {
+            Ok(PendingExposure {
+                experiment_id: row.try_get("experimentId")?,
+                variant_id: row.try_get("variantId")?,
+                exposed_at: row.try_get("exposedAt")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| format!("Failed to load pending exposures: {e}"))
+}
+
+/// Drop exposures the server acknowledged.
+#[tauri::command]
+pub async fn ack_experiment_exposures(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+    experiment_ids: Vec<String>,
+) -> Result<u64, String> {
+    let mut removed = 0;
+    for experiment_id in experiment_ids {
+        removed += sqlx::query(
+            r#"DELETE FROM "pending_experiment_exposures"
+               WHERE "userId" = ? AND "experimentId" = ?"#,
+        )
+        .bind(&user_id)
+        .bind(&experiment_id)
+        .execute(pool.inner())
+        .await
+        .map_err(|e| format!("Failed to acknowledge exposures: {e}"))?
+        .rows_affected();
+    }
+    Ok(removed)
+}
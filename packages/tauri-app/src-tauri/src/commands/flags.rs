@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use danci_native::flags::{FlagDefinition, FlagSet};
+use serde::Serialize;
+use tauri::{AppHandle, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::events::{AppEvent, EventBus, SyncKind};
+
+const STORE_PATH: &str = ".danci-store.json";
+const FLAGS_KEY: &str = "feature_flags";
+
+/// Built-in defaults, used until the first successful sync.
+fn default_flags() -> FlagSet {
+    FlagSet::new(
+        0,
+        vec![
+            FlagDefinition::new("desktop.local_compute", true, 100.0),
+            FlagDefinition::new("desktop.telemetry_prompt", false, 100.0),
+        ],
+    )
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagState {
+    /// Version of the last synced definitions (0 = built-in defaults only).
+    pub version: i64,
+    pub values: BTreeMap<String, bool>,
+}
+
+fn load_flags<R: Runtime>(app: &AppHandle<R>) -> Result<FlagSet, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    let synced: Option<FlagSet> = store
+        .get(FLAGS_KEY)
+        .and_then(|value| serde_json::from_value(value).ok());
+    Ok(match synced {
+        Some(synced) => default_flags().merged_with(&synced),
+        None => default_flags(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_feature_flags<R: Runtime>(
+    app: AppHandle<R>,
+    user_id: Option<String>,
+) -> Result<FeatureFlagState, String> {
+    let flags = load_flags(&app)?;
+    Ok(FeatureFlagState {
+        version: flags.version,
+        values: flags.evaluate_all(user_id.as_deref()),
+    })
+}
+
+/// Persist definitions returned by `GET /api/feature-flags/sync`.
+#[tauri::command]
+pub async fn apply_feature_flag_sync<R: Runtime>(
+    app: AppHandle<R>,
+    bus: State<'_, EventBus>,
+    definitions: FlagSet,
+    user_id: Option<String>,
+) -> Result<FeatureFlagState, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(&definitions)
+        .map_err(|e| format!("Failed to serialize feature flags: {e}"))?;
+    store.set(FLAGS_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to persist feature flags: {e}"))?;
+    bus.publish(AppEvent::SyncCompleted {
+        kind: SyncKind::FeatureFlags,
+        word_book_id: None,
+    });
+
+    let flags = default_flags().merged_with(&definitions);
+    Ok(FeatureFlagState {
+        version: flags.version,
+        values: flags.evaluate_all(user_id.as_deref()),
+    })
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::activation_cache::{ActivationCache, ActivationCacheStats};
+use crate::integrity::{self, IntegrityReport};
+use crate::word_cache::{WordCache, WordCacheStats};
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Check learning states against the answer history of one wordbook (or all).
+/// Dry run by default; with `dry_run: false` the proposed repairs are applied
+/// in one transaction.
+#[tauri::command]
+pub async fn check_data_integrity(
+    pool: State<'_, SqlitePool>,
+    book_id: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<IntegrityReport, String> {
+    integrity::run(&pool, book_id.as_deref(), dry_run.unwrap_or(true), now_ms())
+        .await
+        .map_err(|e| format!("Failed to check data integrity: {e}"))
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub database_ok: bool,
+    pub database_latency_ms: u64,
+    pub database_error: Option<String>,
+    pub word_cache: WordCacheStats,
+    pub activation_cache: ActivationCacheStats,
+}
+
+/// Quick health report for the diagnostics screen: a round trip to the
+/// local database plus in-process cache metrics.
+#[tauri::command]
+pub async fn run_self_test(
+    pool: State<'_, SqlitePool>,
+    word_cache: State<'_, Arc<WordCache>>,
+    activation_cache: State<'_, Arc<ActivationCache>>,
+) -> Result<SelfTestReport, String> {
+    let started = Instant::now();
+    let database = sqlx::query("SELECT 1").execute(&*pool).await;
+    Ok(SelfTestReport {
+        database_ok: database.is_ok(),
+        database_latency_ms: started.elapsed().as_millis() as u64,
+        database_error: database.err().map(|e| e.to_string()),
+        word_cache: word_cache.stats(),
+        activation_cache: activation_cache.stats(),
+    })
+}
@@ -0,0 +1,13 @@
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::journal::{self, JournalStatus};
+
+/// Operations not yet replayed onto the server, and when the last replay
+/// happened.
+#[tauri::command]
+pub async fn get_journal_status(pool: State<'_, SqlitePool>) -> Result<JournalStatus, String> {
+    journal::status(&pool)
+        .await
+        .map_err(|e| format!("Failed to read operation journal: {e}"))
+}
@@ -1,6 +1,19 @@
+use danci_native::batch::{
+    self, BatchControllerConfig, BatchControllerState, BatchSignals, BatchSizeDecision,
+};
+use danci_native::compute::{self, ComputeMode};
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::audit::{self, DecisionRecord, ScoreSummary};
+use crate::commands::statistics;
+use crate::events::{AppEvent, EventBus};
+use crate::stats::{LocalAnswer, LocalWordState};
+use crate::validation::{self, StateError, StateValidator};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct LearningWord {
     pub id: String,
     pub word: String,
@@ -9,17 +22,23 @@ pub struct LearningWord {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct LearningSession {
     pub session_id: String,
     pub words: Vec<LearningWord>,
     pub total_count: u32,
     pub completed_count: u32,
+    #[cfg_attr(test, schemars(with = "serde_json::Value"))]
+    pub compute_mode: ComputeMode,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct AnswerResult {
     pub correct: bool,
     pub next_word: Option<LearningWord>,
+    #[cfg_attr(test, schemars(with = "serde_json::Value"))]
+    pub compute_mode: ComputeMode,
 }
 
 #[tauri::command]
@@ -28,11 +47,23 @@ pub async fn get_learning_words() -> Result<LearningSession, String> {
     Err("Not implemented".into())
 }
 
+/// Records one answer, with the word's new learning state when the webview
+/// has computed it, through the same journaled path as
+/// `record_study_progress`.
 #[tauri::command]
-pub async fn submit_answer(word_id: String, answer: String) -> Result<AnswerResult, String> {
-    let _ = (word_id, answer);
-    // TODO: Implement with SQLite backend
-    Err("Not implemented".into())
+pub async fn submit_answer(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    answer: LocalAnswer,
+    state: Option<LocalWordState>,
+) -> Result<AnswerResult, String> {
+    let correct = answer.is_correct;
+    statistics::record_study_progress(pool, bus, vec![answer], state.into_iter().collect()).await?;
+    Ok(AnswerResult {
+        correct,
+        next_word: None,
+        compute_mode: compute::current_mode(),
+    })
 }
 
 #[tauri::command]
@@ -40,3 +71,73 @@ pub async fn get_session() -> Result<LearningSession, String> {
     // TODO: Implement with SQLite backend
     Err("Not implemented".into())
 }
+
+#[tauri::command]
+pub async fn finish_session(
+    bus: State<'_, EventBus>,
+    session_id: String,
+    completed_count: u32,
+) -> Result<(), String> {
+    bus.publish(AppEvent::SessionFinished {
+        session_id,
+        completed_count,
+    });
+    Ok(())
+}
+
+/// Called by the webview after it applies a server model snapshot locally.
+#[tauri::command]
+pub async fn report_model_updated(
+    bus: State<'_, EventBus>,
+    model: String,
+    version: i64,
+) -> Result<(), String> {
+    bus.publish(AppEvent::ModelUpdated { model, version });
+    Ok(())
+}
+
+/// Called by the UI between batches; the returned `state` is passed back on
+/// the next call so the controller's integral term survives across batches.
+/// Out-of-range state is clamped; NaN or inconsistent limits fail the call.
+#[tauri::command]
+pub async fn next_batch_size(
+    pool: State<'_, SqlitePool>,
+    base_size: u32,
+    mut signals: BatchSignals,
+    mut state: Option<BatchControllerState>,
+    config: Option<BatchControllerConfig>,
+) -> Result<BatchSizeDecision, StateError> {
+    let mut v = StateValidator::default();
+    if base_size == 0 {
+        v.reject("baseSize", "must be positive");
+    }
+    let mut config = config.unwrap_or_default();
+    validation::batch_config(&mut v, &mut config);
+    if let Some(state) = state.as_mut() {
+        validation::batch_state(&mut v, state, &config);
+    }
+    validation::batch_signals(&mut v, &mut signals);
+    let clamped = v.finish("batch controller state")?;
+    validation::report_clamped("next_batch_size", &clamped);
+
+    let decision = batch::next_batch_size(Some(config), state, base_size, signals);
+    audit::record_logged(
+        &pool,
+        &DecisionRecord {
+            timestamp: 0,
+            kind: "batch_size".into(),
+            candidates: 1,
+            chosen: decision.size.to_string(),
+            scores: ScoreSummary {
+                chosen: Some(decision.error),
+                ..ScoreSummary::default()
+            },
+            context: Some(format!(
+                "base={} saturated={}",
+                decision.state.base_size, decision.saturated
+            )),
+        },
+    )
+    .await;
+    Ok(decision)
+}
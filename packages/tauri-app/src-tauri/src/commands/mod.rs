@@ -1,4 +1,19 @@
+pub mod assets;
+pub mod audit;
+pub mod backup;
+pub mod compaction;
+pub mod compute;
+pub mod context;
+pub mod experiments;
+pub mod flags;
+pub mod integrity;
+pub mod journal;
 pub mod learning;
+pub mod models;
+pub mod placement;
 pub mod settings;
 pub mod statistics;
+pub mod sync;
+pub mod tts;
 pub mod wordbooks;
+pub mod words;
@@ -0,0 +1,88 @@
+use danci_native::events::{self, EventCount};
+use danci_native::layout::BanditSnapshot;
+use danci_native::snapshot_diff::SnapshotDiff;
+use danci_native::vclock::VectorClock;
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::events::{AppEvent, EventBus};
+use crate::models::{self, LoadedModel, QuarantinedSnapshot};
+
+/// Persist a model snapshot; unhealthy snapshots are refused. Pass the
+/// server's `syncClock` when the snapshot came from a sync. Returns the
+/// difference from the snapshot it replaced.
+#[tauri::command]
+pub async fn save_model_snapshot(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    model: String,
+    version: i64,
+    snapshot: BanditSnapshot,
+    sync_clock: Option<VectorClock>,
+) -> Result<Option<SnapshotDiff>, String> {
+    let diff = models::save_snapshot(&pool, &model, version, &snapshot, sync_clock.as_ref())
+        .await
+        .map_err(|e| format!("Failed to save model snapshot: {e}"))?;
+    if let Some(diff) = diff.as_ref().filter(|d| !d.is_unchanged()) {
+        log::info!("model snapshot {model} v{version}: {}", diff.summary());
+    }
+    bus.publish(AppEvent::ModelUpdated { model, version });
+    Ok(diff)
+}
+
+/// Store the population model that new or quarantined local models start
+/// from; an unhealthy one is refused.
+#[tauri::command]
+pub async fn save_model_prior(
+    pool: State<'_, SqlitePool>,
+    model: String,
+    snapshot: BanditSnapshot,
+) -> Result<(), String> {
+    models::save_population_prior(&pool, &model, &snapshot)
+        .await
+        .map_err(|e| format!("Failed to save model prior: {e}"))
+}
+
+/// Load a model snapshot. A corrupted one is quarantined and a prior model is
+/// returned in its place.
+#[tauri::command]
+pub async fn load_model_snapshot(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    model: String,
+) -> Result<LoadedModel, String> {
+    let loaded = models::load_snapshot(&pool, &model)
+        .await
+        .map_err(|e| format!("Failed to load model snapshot: {e}"))?;
+    if let Some(q) = &loaded.quarantined {
+        log::warn!(
+            "quarantined model snapshot {} v{}: {}",
+            q.model,
+            q.version,
+            q.reason
+        );
+        bus.publish(AppEvent::ModelQuarantined {
+            model,
+            reason: q.reason.clone(),
+        });
+    }
+    Ok(loaded)
+}
+
+/// Most recent quarantined snapshots, newest first, for bug reports.
+#[tauri::command]
+pub async fn list_quarantined_models(
+    pool: State<'_, SqlitePool>,
+    n: Option<i64>,
+) -> Result<Vec<QuarantinedSnapshot>, String> {
+    models::list_quarantined(&pool, n.unwrap_or(50))
+        .await
+        .map_err(|e| format!("Failed to read quarantined models: {e}"))
+}
+
+/// Counts of algorithm health events (emergency recomputes, sanitized
+/// inputs, state fallbacks) since the app started.
+#[tauri::command]
+pub async fn get_algorithm_events() -> Result<Vec<EventCount>, String> {
+    Ok(events::event_counts())
+}
@@ -0,0 +1,224 @@
+//! Offline placement test.
+//!
+//! The calibration set comes from `GET /api/placement/items` while online;
+//! the test itself runs locally with `danci_native::placement`, so a new
+//! learner can finish onboarding without a connection. One session per user
+//! is kept in SQLite. A finished test is reported via
+//! `POST /api/placement/submit`, where the server replays the answers with
+//! its own difficulties.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use danci_native::placement::{
+    PlacementConfig, PlacementItem, PlacementResult, PlacementSession, PlacementStep,
+};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementProgress {
+    pub items_answered: u32,
+    pub theta: f64,
+    pub se: f64,
+    #[serde(flatten)]
+    #[cfg_attr(test, schemars(with = "serde_json::Value"))]
+    pub step: PlacementStep,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementAnswer {
+    pub word_id: String,
+    pub correct: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PendingPlacement {
+    pub answers: Vec<PlacementAnswer>,
+    pub completed_at: i64,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn progress(session: &PlacementSession, items: &[PlacementItem]) -> PlacementProgress {
+    PlacementProgress {
+        items_answered: session.items_answered(),
+        theta: session.theta,
+        se: session.se,
+        step: session.step(items),
+    }
+}
+
+/// Start a test over the cached calibration set, replacing any earlier one.
+#[tauri::command]
+pub async fn start_placement(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+    items: Vec<PlacementItem>,
+    config: Option<PlacementConfig>,
+) -> Result<PlacementProgress, String> {
+    if items.is_empty() {
+        return Err("Calibration set is empty".to_string());
+    }
+    let session = PlacementSession::new(config.unwrap_or_default());
+    let session_json = serde_json::to_string(&session)
+        .map_err(|e| format!("Failed to encode placement session: {e}"))?;
+    let items_json = serde_json::to_string(&items)
+        .map_err(|e| format!("Failed to encode calibration set: {e}"))?;
+    sqlx::query(
+        r#"INSERT OR REPLACE INTO "placement_sessions"
+           ("userId", "session", "items", "result", "startedAt", "completedAt", "submittedAt")
+           VALUES (?, ?, ?, NULL, ?, NULL, NULL)"#,
+    )
+    .bind(&user_id)
+    .bind(&session_json)
+    .bind(&items_json)
+    .bind(now_ms())
+    .execute(pool.inner())
+    .await
+    .map_err(|e| format!("Failed to start placement test: {e}"))?;
+    Ok(progress(&session, &items))
+}
+
+/// Record an answer; returns the next word or, once finished, the result.
+#[tauri::command]
+pub async fn answer_placement(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+    word_id: String,
+    correct: bool,
+) -> Result<PlacementProgress, String> {
+    let row = sqlx::query(
+        r#"SELECT "session", "items", "completedAt" FROM "placement_sessions" WHERE "userId" = ?"#,
+    )
+    .bind(&user_id)
+    .fetch_optional(pool.inner())
+    .await
+    .map_err(|e| format!("Failed to load placement test: {e}"))?
+    .ok_or_else(|| "No placement test in progress".to_string())?;
+    let completed_at: Option<i64> = row.try_get("completedAt").map_err(|e| e.to_string())?;
+    if completed_at.is_some() {
+        return Err("Placement test already finished".to_string());
+    }
+    let session_json: String = row.try_get("session").map_err(|e| e.to_string())?;
+    let items_json: String = row.try_get("items").map_err(|e| e.to_string())?;
+    let mut session: PlacementSession = serde_json::from_str(&session_json)
+        .map_err(|e| format!("Corrupted placement session: {e}"))?;
+    let items: Vec<PlacementItem> =
+        serde_json::from_str(&items_json).map_err(|e| format!("Corrupted calibration set: {e}"))?;
+
+    let item = items
+        .iter()
+        .find(|i| i.word_id == word_id)
+        .ok_or_else(|| format!("Word {word_id} is not part of this placement test"))?;
+    session.record(item, correct);
+
+    let progress = progress(&session, &items);
+    let session_json = serde_json::to_string(&session)
+        .map_err(|e| format!("Failed to encode placement session: {e}"))?;
+    let (result_json, completed_at) = match &progress.step {
+        PlacementStep::Next { .. } => (None, None),
+        PlacementStep::Done { result } => (
+            Some(
+                serde_json::to_string(result)
+                    .map_err(|e| format!("Failed to encode placement result: {e}"))?,
+            ),
+            Some(now_ms()),
+        ),
+    };
+    sqlx::query(
+        r#"UPDATE "placement_sessions" SET "session" = ?, "result" = ?, "completedAt" = ?
+           WHERE "userId" = ?"#,
+    )
+    .bind(&session_json)
+    .bind(&result_json)
+    .bind(completed_at)
+    .bind(&user_id)
+    .execute(pool.inner())
+    .await
+    .map_err(|e| format!("Failed to save placement answer: {e}"))?;
+    Ok(progress)
+}
+
+/// Result of the user's finished test: ability, per-band mastery and ACT-R
+/// priors.
+#[tauri::command]
+pub async fn get_placement_result(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+) -> Result<Option<PlacementResult>, String> {
+    let result_json: Option<Option<String>> =
+        sqlx::query_scalar(r#"SELECT "result" FROM "placement_sessions" WHERE "userId" = ?"#)
+            .bind(&user_id)
+            .fetch_optional(pool.inner())
+            .await
+            .map_err(|e| format!("Failed to load placement result: {e}"))?;
+    result_json
+        .flatten()
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| format!("Corrupted placement result: {e}"))
+}
+
+/// A finished test not yet reported to the server.
+#[tauri::command]
+pub async fn get_pending_placement(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+) -> Result<Option<PendingPlacement>, String> {
+    let row = sqlx::query(
+        r#"SELECT "session", "completedAt" FROM "placement_sessions"
+           WHERE "userId" = ? AND "completedAt" IS NOT NULL AND "submittedAt" IS NULL"#,
+    )
+    .bind(&user_id)
+    .fetch_optional(pool.inner())
+    .await
+    .map_err(|e| format!("Failed to load placement test: {e}"))?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let session_json: String = row.try_get("session").map_err(|e| e.to_string())?;
+    let session: PlacementSession = serde_json::from_str(&session_json)
+        .map_err(|e| format!("Corrupted placement session: {e}"))?;
+    Ok(Some(PendingPlacement {
+        answers: session
+            .responses
+            .into_iter()
+            .map(|r| PlacementAnswer {
+                word_id: r.word_id,
+                correct: r.correct,
+            })
+            .collect(),
+        completed_at: row.try_get("completedAt").map_err(|e| e.to_string())?,
+    }))
+}
+
+/// Mark the finished test as accepted by the server.
+#[tauri::command]
+pub async fn ack_placement_submission(
+    pool: State<'_, SqlitePool>,
+    user_id: String,
+) -> Result<bool, String> {
+    let updated = sqlx::query(
+        r#"UPDATE "placement_sessions" SET "submittedAt" = ?
+           WHERE "userId" = ? AND "completedAt" IS NOT NULL AND "submittedAt" IS NULL"#,
+    )
+    .bind(now_ms())
+    .bind(&user_id)
+    .execute(pool.inner())
+    .await
+    .map_err(|e| format!("Failed to acknowledge placement submission: {e}"))?
+    .rows_affected();
+    Ok(updated > 0)
+}
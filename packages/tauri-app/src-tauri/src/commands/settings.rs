@@ -3,10 +3,11 @@ use tauri::{AppHandle, Manager, Runtime};
 use tauri_plugin_store::StoreExt;
 use tauri_plugin_window_state::AppHandleExt;
 
-const STORE_PATH: &str = ".danci-store.json";
+pub(crate) const STORE_PATH: &str = ".danci-store.json";
 const SETTINGS_KEY: &str = "app_settings";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct AppSettings {
     pub daily_goal: u32,
     pub reminder_enabled: bool,
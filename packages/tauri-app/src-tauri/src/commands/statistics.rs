@@ -1,6 +1,20 @@
+use std::sync::Arc;
+
+use danci_native::progress::MasteryProgress;
+use danci_native::rescue::{RescueConfig, RescuePlan};
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::activation_cache::ActivationCache;
+use crate::events::{AppEvent, EventBus};
+use crate::stats::{
+    self, BookStatistics, CardDirection, LearningCurveReport, LocalAnswer, LocalWordState,
+    WordMastery,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct Statistics {
     pub total_words: u32,
     pub learned_words: u32,
@@ -10,6 +24,7 @@ pub struct Statistics {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct WeeklyReport {
     pub week_start: String,
     pub new_words: u32,
@@ -19,6 +34,7 @@ pub struct WeeklyReport {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct DailyStats {
     pub date: String,
     pub new_words: u32,
@@ -36,3 +52,117 @@ pub async fn get_weekly_report() -> Result<WeeklyReport, String> {
     // TODO: Implement with SQLite backend
     Err("Not implemented".into())
 }
+
+/// Statistics of one wordbook from the local database, in the backend's
+/// statistics shape.
+#[tauri::command]
+pub async fn get_book_statistics(
+    pool: State<'_, SqlitePool>,
+    book_id: String,
+) -> Result<BookStatistics, String> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    stats::book_statistics(&pool, &book_id, now_ms)
+        .await
+        .map_err(|e| format!("Failed to compute book statistics: {e}"))
+}
+
+/// Learning curve of one wordbook (or all books) with forecasts for the
+/// given extra study minutes.
+#[tauri::command]
+pub async fn get_learning_curve(
+    pool: State<'_, SqlitePool>,
+    book_id: Option<String>,
+    budgets: Option<Vec<f64>>,
+) -> Result<LearningCurveReport, String> {
+    let budgets = budgets.unwrap_or_else(|| vec![30.0, 60.0, 120.0, 300.0]);
+    stats::learning_curve(&pool, book_id.as_deref(), budgets)
+        .await
+        .map_err(|e| format!("Failed to compute learning curve: {e}"))
+}
+
+/// Local learning states of words in a wordbook with mastery ± uncertainty,
+/// for one card direction (recognition unless given).
+#[tauri::command]
+pub async fn get_word_mastery(
+    pool: State<'_, SqlitePool>,
+    book_id: String,
+    word_ids: Vec<String>,
+    direction: Option<CardDirection>,
+) -> Result<Vec<WordMastery>, String> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    stats::word_mastery(
+        &pool,
+        &book_id,
+        &word_ids,
+        direction.unwrap_or_default(),
+        now_ms,
+    )
+    .await
+    .map_err(|e| format!("Failed to load word mastery: {e}"))
+}
+
+/// Expected mastery of a wordbook and an ETA to the target at the recent
+/// pace. `total_words` is the book size, so unlearned words count as 0.
+#[tauri::command]
+pub async fn get_mastery_progress(
+    pool: State<'_, SqlitePool>,
+    activations: State<'_, Arc<ActivationCache>>,
+    book_id: String,
+    total_words: Option<u32>,
+) -> Result<MasteryProgress, String> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    stats::book_mastery_progress(&pool, &activations, &book_id, total_words, now_ms)
+        .await
+        .map_err(|e| format!("Failed to compute mastery progress: {e}"))
+}
+
+/// Catch-up plan for a wordbook after a break: a bounded rescue session of
+/// the overdue words losing the most recall per minute, the rest spread over
+/// the following days.
+#[tauri::command]
+pub async fn get_rescue_plan(
+    pool: State<'_, SqlitePool>,
+    book_id: String,
+    config: Option<RescueConfig>,
+) -> Result<RescuePlan, String> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    stats::book_rescue_plan(&pool, &book_id, config, now_ms)
+        .await
+        .map_err(|e| format!("Failed to plan rescue review: {e}"))
+}
+
+/// Mirror answers and learning states so statistics work offline. Both are
+/// journaled and reach the server on the next sync.
+#[tauri::command]
+pub async fn record_study_progress(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    answers: Vec<LocalAnswer>,
+    states: Vec<LocalWordState>,
+) -> Result<(), String> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    stats::record_progress(&pool, &answers, &states, now_ms)
+        .await
+        .map_err(|e| format!("Failed to record study progress: {e}"))?;
+    if !answers.is_empty() {
+        bus.publish(AppEvent::AnswersRecorded {
+            count: answers.len() as u32,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::events::{AppEvent, EventBus, SyncKind};
+use crate::sync::{self, SyncPhase, SyncServer, SyncTable, TableSyncResult};
+
+async fn run(
+    pool: &SqlitePool,
+    bus: &EventBus,
+    server: &SyncServer,
+    tables: &[SyncTable],
+) -> Vec<TableSyncResult> {
+    let progress = |table: SyncTable, phase: SyncPhase, rows: u32, error: Option<&str>| {
+        bus.publish(AppEvent::SyncProgress {
+            table,
+            phase,
+            rows,
+            error: error.map(str::to_string),
+        });
+    };
+    let results = sync::sync_tables(pool, server, tables, &progress).await;
+    bus.publish(AppEvent::SyncCompleted {
+        kind: SyncKind::LearningData,
+        word_book_id: None,
+    });
+    results
+}
+
+/// Sync every table, each resuming from its saved cursor. Progress is
+/// emitted as `sync-progress` events; a failed table does not stop the rest.
+#[tauri::command]
+pub async fn full_sync(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    server: SyncServer,
+) -> Result<Vec<TableSyncResult>, String> {
+    Ok(run(&pool, &bus, &server, &SyncTable::ALL).await)
+}
+
+/// Re-run only the tables whose last sync failed.
+#[tauri::command]
+pub async fn retry_failed_sync(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    server: SyncServer,
+) -> Result<Vec<TableSyncResult>, String> {
+    let tables = sync::failed_tables(&pool)
+        .await
+        .map_err(|e| format!("Failed to read sync status: {e}"))?;
+    Ok(run(&pool, &bus, &server, &tables).await)
+}
@@ -0,0 +1,73 @@
+use tauri::{AppHandle, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+use super::settings::STORE_PATH;
+use crate::tts::{self, Speaker, TtsPreferences, Utterance, VoiceInfo, VoicePreference};
+
+const PREFERENCES_KEY: &str = "tts_preferences";
+
+fn load_preferences<R: Runtime>(app: &AppHandle<R>) -> Result<TtsPreferences, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    match store.get(PREFERENCES_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse TTS preferences: {e}")),
+        None => Ok(TtsPreferences::default()),
+    }
+}
+
+/// Speak one utterance, interrupting whatever is playing.
+#[tauri::command]
+pub async fn tts_speak<R: Runtime>(
+    app: AppHandle<R>,
+    speaker: State<'_, Speaker>,
+    utterance: Utterance,
+) -> Result<(), String> {
+    let resolved = load_preferences(&app)?
+        .resolve(utterance)
+        .map_err(|e| e.to_string())?;
+    speaker
+        .speak(&resolved)
+        .map_err(|e| format!("Failed to speak: {e}"))
+}
+
+#[tauri::command]
+pub async fn tts_stop(speaker: State<'_, Speaker>) -> Result<bool, String> {
+    Ok(speaker.stop())
+}
+
+/// Voices of the platform speech engine with what each supports.
+#[tauri::command]
+pub async fn tts_list_voices() -> Result<Vec<VoiceInfo>, String> {
+    tauri::async_runtime::spawn_blocking(tts::list_voices)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to list voices: {e}"))
+}
+
+#[tauri::command]
+pub async fn tts_get_preferences<R: Runtime>(app: AppHandle<R>) -> Result<TtsPreferences, String> {
+    load_preferences(&app)
+}
+
+/// Set (or, with no preference, clear) the default voice for a language.
+#[tauri::command]
+pub async fn tts_set_language_preference<R: Runtime>(
+    app: AppHandle<R>,
+    language: String,
+    preference: Option<VoicePreference>,
+) -> Result<TtsPreferences, String> {
+    if language.trim().is_empty() {
+        return Err("Language must not be empty".into());
+    }
+    let mut preferences = load_preferences(&app)?;
+    preferences.set(&language, preference);
+
+    let value = serde_json::to_value(&preferences)
+        .map_err(|e| format!("Failed to serialize TTS preferences: {e}"))?;
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    store.set(PREFERENCES_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to persist TTS preferences: {e}"))?;
+    Ok(preferences)
+}
@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::events::{AppEvent, EventBus, SyncKind};
+use crate::sync::SyncServer;
+use crate::wordbook_deltas::{self, WordbookUpdate};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct Wordbook {
     pub id: String,
     pub name: String,
@@ -21,3 +28,23 @@ pub async fn select_wordbook(wordbook_id: String) -> Result<(), String> {
     // TODO: Implement with SQLite backend
     Err("Not implemented".into())
 }
+
+/// Apply the server's word changes since the local copy's version.
+#[tauri::command]
+pub async fn update_wordbook_content(
+    pool: State<'_, SqlitePool>,
+    bus: State<'_, EventBus>,
+    server: SyncServer,
+    word_book_id: String,
+) -> Result<WordbookUpdate, String> {
+    let update = wordbook_deltas::update_wordbook(&pool, &server, &word_book_id)
+        .await
+        .map_err(|e| format!("Failed to update wordbook: {e}"))?;
+    if update.upserted > 0 || update.removed > 0 {
+        bus.publish(AppEvent::SyncCompleted {
+            kind: SyncKind::Assets,
+            word_book_id: Some(word_book_id),
+        });
+    }
+    Ok(update)
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+use super::settings::STORE_PATH;
+use crate::word_cache::{WordCache, WordCacheConfig, WordRow};
+
+const CONFIG_KEY: &str = "word_cache_config";
+
+/// Stored cache configuration, or the default when none is stored or it
+/// cannot be read.
+pub fn load_word_cache_config<R: Runtime>(app: &AppHandle<R>) -> WordCacheConfig {
+    app.store(STORE_PATH)
+        .ok()
+        .and_then(|store| store.get(CONFIG_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Word rows for the question being rendered, served from the word cache
+/// where possible.
+#[tauri::command]
+pub async fn get_words(
+    pool: State<'_, SqlitePool>,
+    cache: State<'_, Arc<WordCache>>,
+    word_book_id: String,
+    word_ids: Vec<String>,
+) -> Result<Vec<WordRow>, String> {
+    cache
+        .get_words(&pool, &word_book_id, &word_ids)
+        .await
+        .map_err(|e| format!("Failed to load words: {e}"))
+}
+
+#[tauri::command]
+pub async fn get_word_cache_config(
+    cache: State<'_, Arc<WordCache>>,
+) -> Result<WordCacheConfig, String> {
+    Ok(cache.config())
+}
+
+/// Applies the new capacity and age bound immediately and keeps them for
+/// the next start.
+#[tauri::command]
+pub async fn set_word_cache_config<R: Runtime>(
+    app: AppHandle<R>,
+    cache: State<'_, Arc<WordCache>>,
+    config: WordCacheConfig,
+) -> Result<(), String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(config)
+        .map_err(|e| format!("Failed to serialize word cache config: {e}"))?;
+    store.set(CONFIG_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to persist word cache config: {e}"))?;
+    cache.configure(config);
+    Ok(())
+}
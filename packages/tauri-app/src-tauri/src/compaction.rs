@@ -0,0 +1,254 @@
+//! Compaction of old answer history.
+//!
+//! `answer_records` gains a row per answer and is never trimmed, which
+//! reaches hundreds of thousands of rows for long-time users. Rows older than
+//! a horizon are folded into `answer_daily_summaries`, one row per card (word
+//! and direction) and UTC day, and then deleted. A summary keeps what the readers in
+//! [`crate::stats`] need: answer and correct counts, study time both raw and
+//! capped per answer (the learning curve caps it), and the sum of timestamps,
+//! so ACT-R can still age each compacted answer at the day's mean time.
+//!
+//! Only rows already pushed to the server are compacted. A run moves at most
+//! `max_rows` rows, in batches that each commit on their own, so it can stop
+//! anywhere and the next run picks up the rest. Merging into an existing
+//! summary is additive, so a day split across runs ends up the same.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::stats::{DAY_MS, MAX_ANSWER_MS, TREND_DAYS};
+use crate::sync;
+
+pub const DEFAULT_HORIZON_DAYS: i64 = 180;
+/// Recent days stay raw: the trend and pace windows read per-answer rows, and
+/// session context needs `sessionId`.
+pub const MIN_HORIZON_DAYS: i64 = TREND_DAYS;
+pub const DEFAULT_MAX_ROWS: i64 = 50_000;
+/// Rows folded per transaction.
+const BATCH: i64 = 2_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionReport {
+    /// Answers before this time (start of a UTC day) were eligible.
+    pub cutoff_ms: i64,
+    pub compacted_rows: i64,
+    /// Eligible rows left for a later run.
+    pub remaining_rows: i64,
+    /// Old rows kept because they have not been pushed to the server yet.
+    pub unsynced_rows: i64,
+    pub summary_rows: i64,
+    pub done: bool,
+}
+
+/// Start of the UTC day `horizon_days` before `now_ms`.
+pub fn cutoff_ms(now_ms: i64, horizon_days: i64) -> i64 {
+    let horizon = horizon_days.max(MIN_HORIZON_DAYS);
+    (now_ms - horizon * DAY_MS).div_euclid(DAY_MS) * DAY_MS
+}
+
+async fn count_old_rows(
+    pool: &SqlitePool,
+    cutoff_ms: i64,
+    pushed_rowid: i64,
+) -> Result<(i64, i64), sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT COALESCE(SUM(rowid <= ?), 0), COALESCE(SUM(rowid > ?), 0)
+           FROM "answer_records" WHERE "timestamp" < ?"#,
+    )
+    .bind(pushed_rowid)
+    .bind(pushed_rowid)
+    .bind(cutoff_ms)
+    .fetch_one(pool)
+    .await
+}
+
+/// Folds up to `max_rows` eligible answers into daily summaries, calling
+/// `on_batch(compacted_so_far, remaining)` after each committed batch.
+pub async fn compact_answer_records<F>(
+    pool: &SqlitePool,
+    horizon_days: i64,
+    max_rows: i64,
+    now_ms: i64,
+    mut on_batch: F,
+) -> Result<CompactionReport, sqlx::Error>
+where
+    F: FnMut(i64, i64),
+{
+    let cutoff_ms = cutoff_ms(now_ms, horizon_days);
+    let pushed_rowid = sync::pushed_answer_rowid(pool).await?;
+    let (mut remaining, _) = count_old_rows(pool, cutoff_ms, pushed_rowid).await?;
+    let mut compacted = 0;
+
+    while remaining > 0 && compacted < max_rows {
+        let limit = BATCH.min(max_rows - compacted);
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            r#"INSERT INTO "answer_daily_summaries"
+                   ("wordBookId", "wordId", "direction", "day", "answers", "correct",
+                    "timeSpent", "cappedTime", "timestampSum")
+               SELECT "wordBookId", "wordId", "direction", "timestamp" / 86400000, COUNT(*),
+                      SUM("isCorrect"),
+                      SUM(COALESCE("dwellTime", "responseTime", 0)),
+                      SUM(MIN(COALESCE("dwellTime", "responseTime", 0), ?)),
+                      SUM("timestamp")
+               FROM "answer_records"
+               WHERE rowid IN (
+                   SELECT rowid FROM "answer_records"
+                   WHERE "timestamp" < ? AND rowid <= ?
+                   ORDER BY rowid LIMIT ?
+               )
+               GROUP BY "wordBookId", "wordId", "direction", "timestamp" / 86400000
+               ON CONFLICT ("wordBookId", "wordId", "direction", "day") DO UPDATE SET
+                   "answers" = "answers" + excluded."answers",
+                   "correct" = "correct" + excluded."correct",
+                   "timeSpent" = "timeSpent" + excluded."timeSpent",
+                   "cappedTime" = "cappedTime" + excluded."cappedTime",
+                   "timestampSum" = "timestampSum" + excluded."timestampSum""#,
+        )
+        .bind(MAX_ANSWER_MS)
+        .bind(cutoff_ms)
+        .bind(pushed_rowid)
+        .bind(limit)
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query(
+            r#"DELETE FROM "answer_records" WHERE rowid IN (
+                   SELECT rowid FROM "answer_records"
+                   WHERE "timestamp" < ? AND rowid <= ?
+                   ORDER BY rowid LIMIT ?
+               )"#,
+        )
+        .bind(cutoff_ms)
+        .bind(pushed_rowid)
+        .bind(limit)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i64;
+        tx.commit().await?;
+
+        if deleted == 0 {
+            break;
+        }
+        compacted += deleted;
+        remaining = (remaining - deleted).max(0);
+        on_batch(compacted, remaining);
+    }
+
+    let (remaining, unsynced) = count_old_rows(pool, cutoff_ms, pushed_rowid).await?;
+    let summary_rows = sqlx::query_scalar(r#"SELECT COUNT(*) FROM "answer_daily_summaries""#)
+        .fetch_one(pool)
+        .await?;
+    Ok(CompactionReport {
+        cutoff_ms,
+        compacted_rows: compacted,
+        remaining_rows: remaining,
+        unsynced_rows: unsynced,
+        summary_rows,
+        done: remaining == 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    const NOW: i64 = 400 * DAY_MS;
+
+    async fn insert_answer(pool: &SqlitePool, id: &str, timestamp: i64, correct: bool) {
+        sqlx::query(
+            r#"INSERT INTO "answer_records"
+                   ("id", "wordId", "wordBookId", "isCorrect", "responseTime", "timestamp")
+               VALUES (?, 'w', 'b', ?, 1000, ?)"#,
+        )
+        .bind(id)
+        .bind(correct)
+        .bind(timestamp)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn set_pushed_rowid(pool: &SqlitePool, rowid: i64) {
+        sqlx::query(
+            r#"INSERT INTO "sync_cursors" ("table", "cursor", "status", "updatedAt")
+               VALUES ('answer_records', ?, 'completed', 0)"#,
+        )
+        .bind(rowid.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn cutoff_keeps_the_trend_window_raw() {
+        assert_eq!(cutoff_ms(NOW, 1), NOW - MIN_HORIZON_DAYS * DAY_MS);
+        assert_eq!(cutoff_ms(NOW + 5, 180), NOW - 180 * DAY_MS);
+    }
+
+    #[tokio::test]
+    async fn only_pushed_old_answers_are_folded() {
+        let pool = db::open_in_memory().await;
+        insert_answer(&pool, "old1", DAY_MS + 10, true).await;
+        insert_answer(&pool, "old2", DAY_MS + 20, false).await;
+        insert_answer(&pool, "old-unpushed", DAY_MS + 30, true).await;
+        insert_answer(&pool, "recent", NOW - DAY_MS, true).await;
+        set_pushed_rowid(&pool, 2).await;
+
+        let mut batches = Vec::new();
+        let report = compact_answer_records(&pool, 180, 1_000, NOW, |done, left| {
+            batches.push((done, left))
+        })
+        .await
+        .unwrap();
+        assert_eq!(report.compacted_rows, 2);
+        assert_eq!(report.unsynced_rows, 1);
+        assert_eq!(report.remaining_rows, 0);
+        assert_eq!(report.summary_rows, 1);
+        assert!(report.done);
+        assert_eq!(batches, vec![(2, 0)]);
+
+        let (answers, correct, timestamp_sum): (i64, i64, i64) = sqlx::query_as(
+            r#"SELECT "answers", "correct", "timestampSum" FROM "answer_daily_summaries""#,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((answers, correct), (2, 1));
+        assert_eq!(timestamp_sum, 2 * DAY_MS + 30);
+
+        let left: Vec<String> =
+            sqlx::query_scalar(r#"SELECT "id" FROM "answer_records" ORDER BY rowid"#)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(left, vec!["old-unpushed", "recent"]);
+    }
+
+    #[tokio::test]
+    async fn a_day_split_across_runs_merges_into_one_summary() {
+        let pool = db::open_in_memory().await;
+        for i in 0..3 {
+            insert_answer(&pool, &format!("a{i}"), DAY_MS + i, true).await;
+        }
+        set_pushed_rowid(&pool, 3).await;
+
+        let first = compact_answer_records(&pool, 180, 2, NOW, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!((first.compacted_rows, first.remaining_rows), (2, 1));
+        assert!(!first.done);
+        let second = compact_answer_records(&pool, 180, 2, NOW, |_, _| {})
+            .await
+            .unwrap();
+        assert!(second.done);
+
+        let answers: i64 = sqlx::query_scalar(r#"SELECT "answers" FROM "answer_daily_summaries""#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(answers, 3);
+    }
+}
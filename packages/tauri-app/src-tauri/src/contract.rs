@@ -0,0 +1,374 @@
+//! Contract tests between command payloads and the frontend types.
+//!
+//! The webview only sees commands through their JSON: argument names
+//! (camelCased by Tauri), result shape and error shape. Renaming a field of a
+//! result struct compiles fine here and breaks the frontend at runtime. Each
+//! command's JSON Schema is generated from its Rust types and compared with
+//! the checked-in copy under `schemas/commands/`, and so are the events
+//! forwarded to the webview (`schemas/events.json`). A shape change fails
+//! the tests until the schemas are regenerated, so it shows up in review
+//! together with the TS types it should come with:
+//!
+//! ```text
+//! UPDATE_CONTRACTS=1 cargo test contract
+//! ```
+//!
+//! Types owned by `danci-native` are left opaque here; their TS shapes are
+//! generated into its `index.d.ts`.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::Schema;
+use serde_json::{json, Map, Value};
+
+use crate::assets::{AssetKind, AssetManifest, GcReport, SyncReport};
+use crate::audit::AuditEntry;
+use crate::backup::SnapshotInfo;
+use crate::commands::context::{AlgorithmContext, ThompsonContext, WordRecall};
+use crate::commands::experiments::{ExperimentAssignment, PendingExposure};
+use crate::commands::flags::FeatureFlagState;
+use crate::commands::integrity::SelfTestReport;
+use crate::commands::learning::{AnswerResult, LearningSession};
+use crate::commands::placement::{PendingPlacement, PlacementProgress};
+use crate::commands::settings::AppSettings;
+use crate::commands::statistics::{Statistics, WeeklyReport};
+use crate::commands::wordbooks::Wordbook;
+use crate::compaction::CompactionReport;
+use crate::events::AppEvent;
+use crate::integrity::IntegrityReport;
+use crate::journal::JournalStatus;
+use crate::models::{LoadedModel, QuarantinedSnapshot};
+use crate::packed::PackedHeader;
+use crate::stats::{
+    BookStatistics, CardDirection, LearningCurveReport, LocalAnswer, LocalWordState, WordMastery,
+};
+use crate::sync::{SyncServer, TableSyncResult};
+use crate::tts::{TtsPreferences, Utterance, VoiceInfo, VoicePreference};
+use crate::validation::StateError;
+use crate::word_cache::{WordCacheConfig, WordRow};
+use crate::wordbook_deltas::WordbookUpdate;
+
+/// A `danci-native` type, opaque to these tests.
+type Native = Value;
+
+/// Raw bytes, an `ArrayBuffer` in the webview; the layout is documented on
+/// [`crate::packed::PackedBatch`] and its header is checked as `PackedHeader`.
+type Packed = PackedHeader;
+
+type Parts = (Vec<(&'static str, Schema)>, Schema, Schema);
+
+/// One entry per registered command, written like its signature without
+/// the injected state: `name(arg: Type, ..) -> Result<Ok, Err>`.
+macro_rules! contracts {
+    ($($command:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> Result<$result:ty, $error:ty>;)*) => {
+        fn contracts() -> Vec<(&'static str, Value)> {
+            vec![$((
+                stringify!($command),
+                contract(stringify!($command), |gen: &mut SchemaGenerator| -> Parts {
+                    (
+                        vec![$((stringify!($arg), gen.subschema_for::<$arg_ty>())),*],
+                        gen.subschema_for::<$result>(),
+                        gen.subschema_for::<$error>(),
+                    )
+                }),
+            )),*]
+        }
+    };
+}
+
+contracts! {
+    sync_wordbook_assets(manifest: AssetManifest) -> Result<SyncReport, String>;
+    resolve_word_asset(word_id: String, kind: AssetKind) -> Result<Option<String>, String>;
+    release_wordbook_assets(word_book_id: String) -> Result<u32, String>;
+    collect_asset_garbage() -> Result<GcReport, String>;
+    audit_record_decision(
+        kind: String,
+        candidate_scores: Vec<f64>,
+        chosen: String,
+        chosen_score: Option<f64>,
+        context: Option<String>,
+    ) -> Result<i64, String>;
+    audit_export_recent(n: Option<i64>) -> Result<Vec<AuditEntry>, String>;
+    export_database(path: String) -> Result<SnapshotInfo, String>;
+    create_local_backup() -> Result<SnapshotInfo, String>;
+    list_local_backups() -> Result<Vec<SnapshotInfo>, String>;
+    compact_answer_history(
+        horizon_days: Option<i64>,
+        max_rows: Option<i64>,
+    ) -> Result<CompactionReport, String>;
+    get_compute_mode() -> Result<Native, String>;
+    set_compute_mode(mode: String) -> Result<Native, String>;
+    create_cancel_handle() -> Result<u64, String>;
+    cancel_computation(handle: u64) -> Result<bool, String>;
+    compute_review_intervals(
+        handle: Option<u64>,
+        words: Vec<Native>,
+        language_params: Option<Native>,
+        urgent: Option<bool>,
+    ) -> Result<Native, StateError>;
+    compute_review_intervals_packed(
+        handle: Option<u64>,
+        words: Vec<Native>,
+        language_params: Option<Native>,
+        urgent: Option<bool>,
+    ) -> Result<Packed, StateError>;
+    get_algorithm_context(
+        session_id: Option<String>,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<AlgorithmContext, String>;
+    get_linucb_context(
+        session_id: Option<String>,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<Native, String>;
+    get_thompson_context(
+        session_id: Option<String>,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<ThompsonContext, String>;
+    get_actr_recall(
+        book_id: String,
+        word_ids: Vec<String>,
+        directions: Option<Vec<CardDirection>>,
+        session_id: Option<String>,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<Vec<WordRecall>, String>;
+    get_actr_recall_packed(
+        book_id: String,
+        word_ids: Vec<String>,
+        directions: Option<Vec<CardDirection>>,
+        session_id: Option<String>,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<Packed, String>;
+    apply_experiment_sync(definitions: Vec<Native>) -> Result<usize, String>;
+    get_experiment_variant(
+        experiment_id: String,
+        user_id: String,
+    ) -> Result<Option<ExperimentAssignment>, String>;
+    list_pending_exposures(user_id: String) -> Result<Vec<PendingExposure>, String>;
+    ack_experiment_exposures(user_id: String, experiment_ids: Vec<String>) -> Result<u64, String>;
+    get_feature_flags(user_id: Option<String>) -> Result<FeatureFlagState, String>;
+    apply_feature_flag_sync(
+        definitions: Native,
+        user_id: Option<String>,
+    ) -> Result<FeatureFlagState, String>;
+    check_data_integrity(
+        book_id: Option<String>,
+        dry_run: Option<bool>,
+    ) -> Result<IntegrityReport, String>;
+    run_self_test() -> Result<SelfTestReport, String>;
+    get_journal_status() -> Result<JournalStatus, String>;
+    get_learning_words() -> Result<LearningSession, String>;
+    submit_answer(
+        answer: LocalAnswer,
+        state: Option<LocalWordState>,
+    ) -> Result<AnswerResult, String>;
+    get_session() -> Result<LearningSession, String>;
+    next_batch_size(
+        base_size: u32,
+        signals: Native,
+        state: Option<Native>,
+        config: Option<Native>,
+    ) -> Result<Native, StateError>;
+    finish_session(session_id: String, completed_count: u32) -> Result<(), String>;
+    report_model_updated(model: String, version: i64) -> Result<(), String>;
+    save_model_snapshot(
+        model: String,
+        version: i64,
+        snapshot: Native,
+        sync_clock: Option<Native>,
+    ) -> Result<Option<Native>, String>;
+    save_model_prior(model: String, snapshot: Native) -> Result<(), String>;
+    load_model_snapshot(model: String) -> Result<LoadedModel, String>;
+    list_quarantined_models(n: Option<i64>) -> Result<Vec<QuarantinedSnapshot>, String>;
+    get_algorithm_events() -> Result<Vec<Native>, String>;
+    start_placement(
+        user_id: String,
+        items: Vec<Native>,
+        config: Option<Native>,
+    ) -> Result<PlacementProgress, String>;
+    answer_placement(
+        user_id: String,
+        word_id: String,
+        correct: bool,
+    ) -> Result<PlacementProgress, String>;
+    get_placement_result(user_id: String) -> Result<Option<Native>, String>;
+    get_pending_placement(user_id: String) -> Result<Option<PendingPlacement>, String>;
+    ack_placement_submission(user_id: String) -> Result<bool, String>;
+    get_statistics() -> Result<Statistics, String>;
+    get_weekly_report() -> Result<WeeklyReport, String>;
+    get_book_statistics(book_id: String) -> Result<BookStatistics, String>;
+    get_learning_curve(
+        book_id: Option<String>,
+        budgets: Option<Vec<f64>>,
+    ) -> Result<LearningCurveReport, String>;
+    get_word_mastery(
+        book_id: String,
+        word_ids: Vec<String>,
+        direction: Option<CardDirection>,
+    ) -> Result<Vec<WordMastery>, String>;
+    get_mastery_progress(book_id: String, total_words: Option<u32>) -> Result<Native, String>;
+    get_rescue_plan(book_id: String, config: Option<Native>) -> Result<Native, String>;
+    record_study_progress(
+        answers: Vec<LocalAnswer>,
+        states: Vec<LocalWordState>,
+    ) -> Result<(), String>;
+    list_wordbooks() -> Result<Vec<Wordbook>, String>;
+    select_wordbook(wordbook_id: String) -> Result<(), String>;
+    update_wordbook_content(
+        server: SyncServer,
+        word_book_id: String,
+    ) -> Result<WordbookUpdate, String>;
+    get_words(word_book_id: String, word_ids: Vec<String>) -> Result<Vec<WordRow>, String>;
+    get_word_cache_config() -> Result<WordCacheConfig, String>;
+    set_word_cache_config(config: WordCacheConfig) -> Result<(), String>;
+    get_settings() -> Result<AppSettings, String>;
+    update_settings(settings: AppSettings) -> Result<(), String>;
+    reset_window_layout() -> Result<(), String>;
+    full_sync(server: SyncServer) -> Result<Vec<TableSyncResult>, String>;
+    retry_failed_sync(server: SyncServer) -> Result<Vec<TableSyncResult>, String>;
+    tts_speak(utterance: Utterance) -> Result<(), String>;
+    tts_stop() -> Result<bool, String>;
+    tts_list_voices() -> Result<Vec<VoiceInfo>, String>;
+    tts_get_preferences() -> Result<TtsPreferences, String>;
+    tts_set_language_preference(
+        language: String,
+        preference: Option<VoicePreference>,
+    ) -> Result<TtsPreferences, String>;
+}
+
+/// Tauri passes command arguments under their camelCased names.
+fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn contract(command: &str, build: impl FnOnce(&mut SchemaGenerator) -> Parts) -> Value {
+    let mut gen = SchemaSettings::draft07().into_generator();
+    let (args, result, error) = build(&mut gen);
+    let args: Map<String, Value> = args
+        .into_iter()
+        .map(|(name, schema)| (camel_case(name), json!(schema)))
+        .collect();
+    json!({
+        "command": command,
+        "args": args,
+        "result": result,
+        "error": error,
+        "definitions": gen.definitions(),
+    })
+}
+
+fn events() -> Value {
+    json!(SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<AppEvent>())
+}
+
+fn schema_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas")
+}
+
+fn updating() -> bool {
+    std::env::var("UPDATE_CONTRACTS").is_ok_and(|v| v == "1")
+}
+
+/// Compares `actual` with the checked-in schema at `path`, or rewrites it
+/// when updating. Formatting differences are ignored.
+fn check(path: &Path, actual: &Value, drift: &mut Vec<String>) {
+    let name = path
+        .strip_prefix(schema_dir())
+        .unwrap_or(path)
+        .display()
+        .to_string();
+    if updating() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let rendered = serde_json::to_string_pretty(actual).unwrap() + "\n";
+        std::fs::write(path, rendered).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+    match expected {
+        None => drift.push(format!("{name} is missing")),
+        Some(expected) if expected != *actual => drift.push(format!("{name} changed")),
+        Some(_) => {}
+    }
+}
+
+/// Commands listed in `generate_handler!`, i.e. what the webview can call.
+fn registered_commands() -> BTreeSet<String> {
+    let source = include_str!("lib.rs");
+    let start = source
+        .find("generate_handler![")
+        .expect("lib.rs registers commands");
+    let list = &source[start + "generate_handler![".len()..];
+    let list = &list[..list.find(']').expect("handler list is closed")];
+    list.split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(|path| path.rsplit("::").next().unwrap_or(path).to_string())
+        .collect()
+}
+
+#[test]
+fn every_registered_command_has_a_contract() {
+    let registered = registered_commands();
+    let covered: BTreeSet<String> = contracts()
+        .into_iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    let missing: Vec<_> = registered.difference(&covered).collect();
+    let unknown: Vec<_> = covered.difference(&registered).collect();
+    assert!(
+        missing.is_empty() && unknown.is_empty(),
+        "contract list out of date; add to `contracts!`: {missing:?}, remove: {unknown:?}"
+    );
+}
+
+#[test]
+fn command_payloads_match_checked_in_schemas() {
+    let commands_dir = schema_dir().join("commands");
+    let mut drift = Vec::new();
+    let mut expected_files = BTreeSet::new();
+    for (command, schema) in contracts() {
+        let path = commands_dir.join(format!("{command}.json"));
+        check(&path, &schema, &mut drift);
+        expected_files.insert(path);
+    }
+    check(&schema_dir().join("events.json"), &events(), &mut drift);
+
+    // Schemas of removed commands.
+    if let Ok(entries) = std::fs::read_dir(&commands_dir) {
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().is_some_and(|ext| ext == "json") && !expected_files.contains(&path)
+            {
+                if updating() {
+                    std::fs::remove_file(&path).unwrap();
+                } else {
+                    drift.push(format!("{} has no command", path.display()));
+                }
+            }
+        }
+    }
+
+    assert!(
+        drift.is_empty(),
+        "command JSON shapes drifted from the checked-in schemas:\n  {}\n\
+         If the change is intended, update the frontend types and regenerate \
+         with `UPDATE_CONTRACTS=1 cargo test contract`.",
+        drift.join("\n  ")
+    );
+}
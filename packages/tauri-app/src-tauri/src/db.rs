@@ -0,0 +1,287 @@
+use std::path::Path;
+
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+const DB_FILE: &str = "danci-local.db";
+
+/// Applied on every start, so each statement must be idempotent.
+const SCHEMA: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "assets" (
+        "hash" TEXT PRIMARY KEY,
+        "size" INTEGER NOT NULL,
+        "mimeType" TEXT,
+        "refCount" INTEGER NOT NULL DEFAULT 0,
+        "createdAt" INTEGER NOT NULL,
+        "lastReferencedAt" INTEGER NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "asset_refs" (
+        "wordBookId" TEXT NOT NULL,
+        "wordId" TEXT NOT NULL,
+        "kind" TEXT NOT NULL,
+        "hash" TEXT NOT NULL REFERENCES "assets"("hash"),
+        "sourceUrl" TEXT NOT NULL,
+        PRIMARY KEY ("wordBookId", "wordId", "kind")
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_asset_refs_word" ON "asset_refs"("wordId", "kind")"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_asset_refs_hash" ON "asset_refs"("hash")"#,
+    r#"CREATE TABLE IF NOT EXISTS "words" (
+        "wordBookId" TEXT NOT NULL,
+        "id" TEXT NOT NULL,
+        "word" TEXT NOT NULL,
+        "phonetic" TEXT,
+        "definition" TEXT NOT NULL,
+        "updatedAt" INTEGER NOT NULL,
+        PRIMARY KEY ("wordBookId", "id")
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "wordbook_versions" (
+        "wordBookId" TEXT PRIMARY KEY,
+        "contentVersion" INTEGER NOT NULL,
+        "updatedAt" INTEGER NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "answer_records" (
+        "id" TEXT PRIMARY KEY,
+        "wordId" TEXT NOT NULL,
+        "wordBookId" TEXT NOT NULL,
+        "isCorrect" INTEGER NOT NULL,
+        "responseTime" INTEGER,
+        "dwellTime" INTEGER,
+        "timestamp" INTEGER NOT NULL,
+        "sessionId" TEXT,
+        "direction" TEXT NOT NULL DEFAULT 'recognition'
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_answer_records_book_time" ON "answer_records"("wordBookId", "timestamp")"#,
+    r#"CREATE TABLE IF NOT EXISTS "answer_daily_summaries" (
+        "wordBookId" TEXT NOT NULL,
+        "wordId" TEXT NOT NULL,
+        "day" INTEGER NOT NULL,
+        "answers" INTEGER NOT NULL,
+        "correct" INTEGER NOT NULL,
+        "timeSpent" INTEGER NOT NULL,
+        "cappedTime" INTEGER NOT NULL,
+        "timestampSum" INTEGER NOT NULL,
+        "direction" TEXT NOT NULL DEFAULT 'recognition',
+        PRIMARY KEY ("wordBookId", "wordId", "direction", "day")
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "word_learning_states" (
+        "wordBookId" TEXT NOT NULL,
+        "wordId" TEXT NOT NULL,
+        "masteryLevel" INTEGER NOT NULL DEFAULT 0,
+        "state" TEXT NOT NULL DEFAULT 'NEW',
+        "updatedAt" INTEGER NOT NULL,
+        "direction" TEXT NOT NULL DEFAULT 'recognition',
+        PRIMARY KEY ("wordBookId", "wordId", "direction")
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_word_learning_states_book_level" ON "word_learning_states"("wordBookId", "masteryLevel")"#,
+    r#"CREATE TABLE IF NOT EXISTS "decision_audit" (
+        "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+        "timestamp" INTEGER NOT NULL,
+        "kind" TEXT NOT NULL,
+        "candidates" INTEGER NOT NULL,
+        "chosen" TEXT NOT NULL,
+        "scoreMin" REAL,
+        "scoreMax" REAL,
+        "scoreMean" REAL,
+        "scoreChosen" REAL,
+        "context" TEXT
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "state_redownloads" (
+        "wordBookId" TEXT NOT NULL,
+        "wordId" TEXT NOT NULL,
+        "reason" TEXT NOT NULL,
+        "markedAt" INTEGER NOT NULL,
+        PRIMARY KEY ("wordBookId", "wordId")
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "sync_cursors" (
+        "table" TEXT PRIMARY KEY,
+        "cursor" TEXT,
+        "status" TEXT NOT NULL,
+        "lastError" TEXT,
+        "updatedAt" INTEGER NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "model_snapshots" (
+        "model" TEXT PRIMARY KEY,
+        "version" INTEGER NOT NULL,
+        "payload" TEXT NOT NULL,
+        "savedAt" INTEGER NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "model_priors" (
+        "model" TEXT PRIMARY KEY,
+        "payload" TEXT NOT NULL,
+        "savedAt" INTEGER NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "quarantined_model_snapshots" (
+        "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+        "model" TEXT NOT NULL,
+        "version" INTEGER NOT NULL,
+        "payload" TEXT NOT NULL,
+        "reason" TEXT NOT NULL,
+        "diagnostics" TEXT,
+        "quarantinedAt" INTEGER NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "pending_experiment_exposures" (
+        "userId" TEXT NOT NULL,
+        "experimentId" TEXT NOT NULL,
+        "variantId" TEXT NOT NULL,
+        "exposedAt" INTEGER NOT NULL,
+        PRIMARY KEY ("userId", "experimentId")
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "device_identity" (
+        "id" INTEGER PRIMARY KEY CHECK ("id" = 1),
+        "deviceId" TEXT NOT NULL
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "sync_clocks" (
+        "scope" TEXT NOT NULL,
+        "key" TEXT NOT NULL,
+        "clock" TEXT NOT NULL,
+        PRIMARY KEY ("scope", "key")
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS "operation_journal" (
+        "seq" INTEGER PRIMARY KEY AUTOINCREMENT,
+        "opId" TEXT NOT NULL UNIQUE,
+        "kind" TEXT NOT NULL,
+        "schemaVersion" INTEGER NOT NULL,
+        "payload" TEXT NOT NULL,
+        "createdAt" INTEGER NOT NULL,
+        "batchId" TEXT,
+        "replayedAt" INTEGER
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_operation_journal_pending" ON "operation_journal"("replayedAt", "seq")"#,
+    r#"CREATE INDEX IF NOT EXISTS "idx_operation_journal_batch" ON "operation_journal"("batchId")"#,
+    r#"CREATE TABLE IF NOT EXISTS "placement_sessions" (
+        "userId" TEXT PRIMARY KEY,
+        "session" TEXT NOT NULL,
+        "items" TEXT NOT NULL,
+        "result" TEXT,
+        "startedAt" INTEGER NOT NULL,
+        "completedAt" INTEGER,
+        "submittedAt" INTEGER
+    )"#,
+];
+
+/// Opens (creating if needed) the desktop app's local database.
+pub async fn open(data_dir: &Path) -> Result<SqlitePool, sqlx::Error> {
+    std::fs::create_dir_all(data_dir)?;
+    let options = SqliteConnectOptions::new()
+        .filename(data_dir.join(DB_FILE))
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await?;
+    init(&pool).await?;
+    Ok(pool)
+}
+
+/// A private in-memory database with the full schema, for tests. It has a
+/// single connection that is never recycled, since every in-memory
+/// connection is a database of its own.
+#[cfg(test)]
+pub async fn open_in_memory() -> SqlitePool {
+    let options = "sqlite::memory:"
+        .parse::<SqliteConnectOptions>()
+        .expect("in-memory database url")
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .expect("open in-memory database");
+    init(&pool).await.expect("apply local schema");
+    pool
+}
+
+async fn init(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    for statement in SCHEMA {
+        sqlx::query(statement).execute(pool).await?;
+    }
+    add_card_directions(pool).await?;
+    // Answers stored before the journal existed are pushed through it too.
+    crate::journal::adopt_unpushed_answers(pool).await?;
+    Ok(())
+}
+
+async fn has_column(pool: &SqlitePool, table: &str, column: &str) -> Result<bool, sqlx::Error> {
+    let found: i64 =
+        sqlx::query_scalar(r#"SELECT COUNT(*) FROM pragma_table_info(?) WHERE "name" = ?"#)
+            .bind(table)
+            .bind(column)
+            .fetch_one(pool)
+            .await?;
+    Ok(found > 0)
+}
+
+/// Upgrades a database created before cards had a direction: every existing
+/// answer, summary and state becomes a recognition card. The summary and
+/// state tables get the direction in their primary key, which SQLite can
+/// only do by copying them into a new table.
+async fn add_card_directions(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    if !has_column(pool, "answer_records", "direction").await? {
+        sqlx::query(
+            r#"ALTER TABLE "answer_records" ADD COLUMN "direction" TEXT NOT NULL DEFAULT 'recognition'"#,
+        )
+        .execute(pool)
+        .await?;
+    }
+    let rebuilds = [
+        (
+            "answer_daily_summaries",
+            r#"CREATE TABLE "answer_daily_summaries_new" (
+                "wordBookId" TEXT NOT NULL,
+                "wordId" TEXT NOT NULL,
+                "day" INTEGER NOT NULL,
+                "answers" INTEGER NOT NULL,
+                "correct" INTEGER NOT NULL,
+                "timeSpent" INTEGER NOT NULL,
+                "cappedTime" INTEGER NOT NULL,
+                "timestampSum" INTEGER NOT NULL,
+                "direction" TEXT NOT NULL DEFAULT 'recognition',
+                PRIMARY KEY ("wordBookId", "wordId", "direction", "day")
+            )"#,
+            r#""wordBookId", "wordId", "day", "answers", "correct", "timeSpent", "cappedTime", "timestampSum""#,
+        ),
+        (
+            "word_learning_states",
+            r#"CREATE TABLE "word_learning_states_new" (
+                "wordBookId" TEXT NOT NULL,
+                "wordId" TEXT NOT NULL,
+                "masteryLevel" INTEGER NOT NULL DEFAULT 0,
+                "state" TEXT NOT NULL DEFAULT 'NEW',
+                "updatedAt" INTEGER NOT NULL,
+                "direction" TEXT NOT NULL DEFAULT 'recognition',
+                PRIMARY KEY ("wordBookId", "wordId", "direction")
+            )"#,
+            r#""wordBookId", "wordId", "masteryLevel", "state", "updatedAt""#,
+        ),
+    ];
+    for (table, create, columns) in rebuilds {
+        if has_column(pool, table, "direction").await? {
+            continue;
+        }
+        let mut tx = pool.begin().await?;
+        sqlx::query(create).execute(&mut *tx).await?;
+        sqlx::query(&format!(
+            r#"INSERT INTO "{table}_new" ({columns}) SELECT {columns} FROM "{table}""#
+        ))
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(r#"DROP TABLE "{table}""#))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(r#"ALTER TABLE "{table}_new" RENAME TO "{table}""#))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+    // Dropped with the old state table.
+    sqlx::query(
+        r#"CREATE INDEX IF NOT EXISTS "idx_word_learning_states_book_level" ON "word_learning_states"("wordBookId", "masteryLevel")"#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
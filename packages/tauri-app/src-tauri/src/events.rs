@@ -0,0 +1,129 @@
+//! In-process event bus between command modules.
+//!
+//! Commands publish an [`AppEvent`] when they change something other modules
+//! may have cached. Modules subscribe at setup through
+//! [`EventBus::subscribe_with`]. Events that the UI also cares about are
+//! forwarded to the webview under the name given by [`AppEvent::webview_name`].
+//!
+//! The bus is a `tokio::sync::broadcast` channel. A subscriber that falls more
+//! than `CAPACITY` events behind skips the missed ones and keeps going, so
+//! handlers must treat events as invalidation hints, not an exact log.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::sync::{SyncPhase, SyncTable};
+
+const CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum SyncKind {
+    Assets,
+    Experiments,
+    FeatureFlags,
+    LearningData,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AppEvent {
+    #[serde(rename_all = "camelCase")]
+    SyncCompleted {
+        kind: SyncKind,
+        word_book_id: Option<String>,
+    },
+    /// One step of a learning-data sync; `rows` is the count moved by this step.
+    #[serde(rename_all = "camelCase")]
+    SyncProgress {
+        table: SyncTable,
+        phase: SyncPhase,
+        rows: u32,
+        error: Option<String>,
+    },
+    /// A locally held model was replaced (e.g. a server snapshot applied).
+    #[serde(rename_all = "camelCase")]
+    ModelUpdated { model: String, version: i64 },
+    /// A stored model snapshot failed its health check on load and was
+    /// replaced by a prior model.
+    #[serde(rename_all = "camelCase")]
+    ModelQuarantined { model: String, reason: String },
+    /// Answers were mirrored into the local database.
+    #[serde(rename_all = "camelCase")]
+    AnswersRecorded { count: u32 },
+    /// A batch of old answers was folded into daily summaries.
+    #[serde(rename_all = "camelCase")]
+    CompactionProgress { compacted: i64, remaining: i64 },
+    #[serde(rename_all = "camelCase")]
+    SessionFinished {
+        session_id: String,
+        completed_count: u32,
+    },
+}
+
+impl AppEvent {
+    /// Webview event name, or `None` for events that stay in the backend.
+    pub fn webview_name(&self) -> Option<&'static str> {
+        match self {
+            AppEvent::SyncCompleted { .. } => Some("sync-completed"),
+            AppEvent::SyncProgress { .. } => Some("sync-progress"),
+            AppEvent::SessionFinished { .. } => Some("session-finished"),
+            AppEvent::ModelUpdated { .. } => None,
+            AppEvent::ModelQuarantined { .. } => Some("model-quarantined"),
+            AppEvent::AnswersRecorded { .. } => None,
+            AppEvent::CompactionProgress { .. } => Some("compaction-progress"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<AppEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    /// Publishing with no subscribers is not an error.
+    pub fn publish(&self, event: AppEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Runs `handler` for every event until the bus is dropped.
+    pub fn subscribe_with<F>(&self, name: &'static str, mut handler: F)
+    where
+        F: FnMut(AppEvent) + Send + 'static,
+    {
+        let mut receiver = self.sender.subscribe();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => handler(event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("event subscriber {name} skipped {skipped} events");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Emits webview-visible events to every window.
+    pub fn forward_to_webview<R: Runtime>(&self, app: AppHandle<R>) {
+        self.subscribe_with("webview", move |event| {
+            if let Some(name) = event.webview_name() {
+                if let Err(e) = app.emit(name, &event) {
+                    log::warn!("failed to forward {name} to webview: {e}");
+                }
+            }
+        });
+    }
+}
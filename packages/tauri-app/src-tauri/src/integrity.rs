@@ -0,0 +1,373 @@
+//! Consistency checks between `word_learning_states` and the answer history.
+//!
+//! A partial sync can leave a learning state for a word the device has no
+//! answers for, or answers for a word without a state. The check pairs every
+//! state with the word's answers (raw rows and compacted summaries) and
+//! proposes one repair per problem:
+//!
+//! - answers but no state, a `NEW` state, or an invalid one: rebuild the state
+//!   from the answers;
+//! - a learned state without any answers: mark the word for re-download. The
+//!   next word-state pull overwrites it from the server, or drops it when the
+//!   server has no state for the word (see [`crate::sync`]).
+//!
+//! Only recognition cards are checked: they are the ones the server knows
+//! about, while production cards live on this device alone.
+//!
+//! Repairs are applied in a single transaction; a dry run only reports them.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::stats::{self, CardDirection};
+
+/// Highest valid `masteryLevel`.
+const MAX_LEVEL: i64 = 5;
+const STATES: [&str; 4] = ["NEW", "LEARNING", "REVIEWING", "MASTERED"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    /// Answers exist but the word has no learning state.
+    AnswersWithoutState,
+    /// The word is past `NEW` but has no answers on this device.
+    StateWithoutAnswers,
+    /// The word has answers but its state is still `NEW`.
+    NewStateWithAnswers,
+    /// Unknown state name or mastery level out of range.
+    InvalidState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum Repair {
+    #[serde(rename_all = "camelCase")]
+    RebuildFromAnswers {
+        mastery_level: i64,
+        state: String,
+        updated_at: i64,
+    },
+    MarkForRedownload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub word_book_id: String,
+    pub word_id: String,
+    pub kind: IssueKind,
+    pub answers: i64,
+    pub mastery_level: Option<i64>,
+    pub state: Option<String>,
+    pub repair: Repair,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub checked_words: usize,
+    /// Words already waiting for re-download, skipped by the check.
+    pub pending_redownloads: usize,
+    pub issues: Vec<IntegrityIssue>,
+    pub dry_run: bool,
+    pub repaired: usize,
+}
+
+#[derive(Default)]
+struct WordRow {
+    state: Option<(i64, String)>,
+    answers: i64,
+}
+
+/// Finds inconsistent words of one wordbook, or of all local books.
+pub async fn check(
+    pool: &SqlitePool,
+    book_id: Option<&str>,
+    now_ms: i64,
+) -> Result<IntegrityReport, sqlx::Error> {
+    let mut words: BTreeMap<(String, String), WordRow> = BTreeMap::new();
+    for row in sqlx::query(
+        r#"SELECT "wordBookId", "wordId", "masteryLevel", "state" FROM "word_learning_states"
+           WHERE "direction" = 'recognition' AND (? IS NULL OR "wordBookId" = ?)"#,
+    )
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    {
+        words
+            .entry((row.try_get("wordBookId")?, row.try_get("wordId")?))
+            .or_default()
+            .state = Some((row.try_get("masteryLevel")?, row.try_get("state")?));
+    }
+    for row in sqlx::query(
+        r#"SELECT "wordBookId", "wordId", SUM("n") AS "answers" FROM (
+               SELECT "wordBookId", "wordId", COUNT(*) AS "n" FROM "answer_records"
+               WHERE "direction" = 'recognition' AND (? IS NULL OR "wordBookId" = ?)
+               GROUP BY "wordBookId", "wordId"
+               UNION ALL
+               SELECT "wordBookId", "wordId", SUM("answers") AS "n" FROM "answer_daily_summaries"
+               WHERE "direction" = 'recognition' AND (? IS NULL OR "wordBookId" = ?)
+               GROUP BY "wordBookId", "wordId"
+           )
+           GROUP BY "wordBookId", "wordId""#,
+    )
+    .bind(book_id)
+    .bind(book_id)
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    {
+        words
+            .entry((row.try_get("wordBookId")?, row.try_get("wordId")?))
+            .or_default()
+            .answers = row.try_get("answers")?;
+    }
+    let pending: HashSet<(String, String)> = sqlx::query_as(
+        r#"SELECT "wordBookId", "wordId" FROM "state_redownloads"
+           WHERE ? IS NULL OR "wordBookId" = ?"#,
+    )
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut issues = Vec::new();
+    for ((word_book_id, word_id), row) in &words {
+        if pending.contains(&(word_book_id.clone(), word_id.clone())) {
+            continue;
+        }
+        let valid = row.state.as_ref().is_none_or(|(level, state)| {
+            (0..=MAX_LEVEL).contains(level) && STATES.contains(&state.as_str())
+        });
+        let kind = match (&row.state, row.answers > 0) {
+            (None, true) => IssueKind::AnswersWithoutState,
+            (Some(_), _) if !valid => IssueKind::InvalidState,
+            (Some((0, state)), false) if state == "NEW" => continue,
+            (Some(_), false) => IssueKind::StateWithoutAnswers,
+            (Some((_, state)), true) if state == "NEW" => IssueKind::NewStateWithAnswers,
+            _ => continue,
+        };
+        let repair = if row.answers > 0 {
+            rebuild(pool, word_book_id, word_id, now_ms).await?
+        } else {
+            Repair::MarkForRedownload
+        };
+        issues.push(IntegrityIssue {
+            word_book_id: word_book_id.clone(),
+            word_id: word_id.clone(),
+            kind,
+            answers: row.answers,
+            mastery_level: row.state.as_ref().map(|(level, _)| *level),
+            state: row.state.as_ref().map(|(_, state)| state.clone()),
+            repair,
+        });
+    }
+
+    Ok(IntegrityReport {
+        checked_words: words.len(),
+        pending_redownloads: pending.len(),
+        issues,
+        dry_run: true,
+        repaired: 0,
+    })
+}
+
+/// State implied by the answers: the level is the run of correct answers at
+/// the end of the trace, capped at the top level.
+async fn rebuild(
+    pool: &SqlitePool,
+    book_id: &str,
+    word_id: &str,
+    now_ms: i64,
+) -> Result<Repair, sqlx::Error> {
+    let events =
+        stats::review_events(pool, book_id, word_id, CardDirection::Recognition, now_ms).await?;
+    let streak = events.iter().take_while(|e| e.correct).count() as i64;
+    let mastery_level = streak.min(MAX_LEVEL);
+    let state = match mastery_level {
+        MAX_LEVEL => "MASTERED",
+        2.. => "REVIEWING",
+        _ => "LEARNING",
+    };
+    let last_answer: Option<i64> = sqlx::query_scalar(
+        r#"SELECT MAX("at") FROM (
+               SELECT MAX("timestamp") AS "at" FROM "answer_records"
+               WHERE "wordBookId" = ? AND "wordId" = ? AND "direction" = 'recognition'
+               UNION ALL
+               SELECT MAX("timestampSum" / "answers") AS "at" FROM "answer_daily_summaries"
+               WHERE "wordBookId" = ? AND "wordId" = ? AND "direction" = 'recognition'
+                 AND "answers" > 0
+           )"#,
+    )
+    .bind(book_id)
+    .bind(word_id)
+    .bind(book_id)
+    .bind(word_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(Repair::RebuildFromAnswers {
+        mastery_level,
+        state: state.to_string(),
+        updated_at: last_answer.unwrap_or(now_ms),
+    })
+}
+
+/// Applies the proposed repairs in one transaction; returns how many were applied.
+pub async fn apply(
+    pool: &SqlitePool,
+    issues: &[IntegrityIssue],
+    now_ms: i64,
+) -> Result<usize, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for issue in issues {
+        match &issue.repair {
+            Repair::RebuildFromAnswers {
+                mastery_level,
+                state,
+                updated_at,
+            } => {
+                sqlx::query(
+                    r#"INSERT INTO "word_learning_states" ("wordBookId", "wordId", "masteryLevel", "state", "updatedAt")
+                       VALUES (?, ?, ?, ?, ?)
+                       ON CONFLICT ("wordBookId", "wordId", "direction") DO UPDATE SET
+                           "masteryLevel" = excluded."masteryLevel",
+                           "state" = excluded."state",
+                           "updatedAt" = excluded."updatedAt""#,
+                )
+                .bind(&issue.word_book_id)
+                .bind(&issue.word_id)
+                .bind(mastery_level)
+                .bind(state)
+                .bind(updated_at)
+                .execute(&mut *tx)
+                .await?;
+            }
+            Repair::MarkForRedownload => {
+                sqlx::query(
+                    r#"INSERT OR REPLACE INTO "state_redownloads" ("wordBookId", "wordId", "reason", "markedAt")
+                       VALUES (?, ?, ?, ?)"#,
+                )
+                .bind(&issue.word_book_id)
+                .bind(&issue.word_id)
+                .bind(serde_json::to_string(&issue.kind).unwrap_or_default())
+                .bind(now_ms)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+    }
+    tx.commit().await?;
+    Ok(issues.len())
+}
+
+/// Checks and, unless `dry_run`, applies the repairs.
+pub async fn run(
+    pool: &SqlitePool,
+    book_id: Option<&str>,
+    dry_run: bool,
+    now_ms: i64,
+) -> Result<IntegrityReport, sqlx::Error> {
+    let mut report = check(pool, book_id, now_ms).await?;
+    if !dry_run {
+        report.repaired = apply(pool, &report.issues, now_ms).await?;
+        report.dry_run = false;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    const NOW: i64 = 1_000_000;
+
+    async fn insert_answer(pool: &SqlitePool, word_id: &str, timestamp: i64) {
+        sqlx::query(
+            r#"INSERT INTO "answer_records" ("id", "wordId", "wordBookId", "isCorrect", "timestamp")
+               VALUES (?, ?, 'b', 1, ?)"#,
+        )
+        .bind(format!("{word_id}-{timestamp}"))
+        .bind(word_id)
+        .bind(timestamp)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_state(pool: &SqlitePool, word_id: &str, level: i64, state: &str) {
+        sqlx::query(
+            r#"INSERT INTO "word_learning_states" ("wordBookId", "wordId", "masteryLevel", "state", "updatedAt")
+               VALUES ('b', ?, ?, ?, 0)"#,
+        )
+        .bind(word_id)
+        .bind(level)
+        .bind(state)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn finds_and_repairs_each_kind_of_mismatch() {
+        let pool = db::open_in_memory().await;
+        insert_answer(&pool, "answered", 100).await;
+        insert_answer(&pool, "answered", 200).await;
+        insert_state(&pool, "learned", 3, "REVIEWING").await;
+        insert_state(&pool, "untouched", 0, "NEW").await;
+        insert_state(&pool, "broken", 9, "LEARNING").await;
+        insert_answer(&pool, "broken", 300).await;
+
+        let report = check(&pool, Some("b"), NOW).await.unwrap();
+        assert_eq!(report.checked_words, 4);
+        let kinds: Vec<(&str, IssueKind)> = report
+            .issues
+            .iter()
+            .map(|i| (i.word_id.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("answered", IssueKind::AnswersWithoutState),
+                ("broken", IssueKind::InvalidState),
+                ("learned", IssueKind::StateWithoutAnswers),
+            ]
+        );
+        assert_eq!(
+            report.issues[0].repair,
+            Repair::RebuildFromAnswers {
+                mastery_level: 2,
+                state: "REVIEWING".to_string(),
+                updated_at: 200,
+            }
+        );
+        assert_eq!(report.issues[2].repair, Repair::MarkForRedownload);
+
+        let repaired = run(&pool, None, false, NOW).await.unwrap();
+        assert_eq!(repaired.repaired, 3);
+        let after = check(&pool, None, NOW).await.unwrap();
+        assert!(after.issues.is_empty());
+        assert_eq!(after.pending_redownloads, 1);
+    }
+
+    #[tokio::test]
+    async fn a_dry_run_changes_nothing() {
+        let pool = db::open_in_memory().await;
+        insert_answer(&pool, "answered", 100).await;
+
+        let report = run(&pool, None, true, NOW).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.repaired, 0);
+        assert_eq!(check(&pool, None, NOW).await.unwrap().issues.len(), 1);
+    }
+}
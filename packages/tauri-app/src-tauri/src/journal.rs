@@ -0,0 +1,379 @@
+//! Append-only journal of local mutations, replayed onto the server in order.
+//!
+//! Every local write that the server must learn about appends a typed
+//! [`Operation`] to `operation_journal` in the same transaction as the write
+//! itself: answers and learning states from [`crate::stats::record_progress`]
+//! and first experiment exposures. Each entry carries an operation id and the
+//! schema version its payload was written with, so entries written by an
+//! older build can still be decoded after an upgrade.
+//!
+//! Replay (see [`crate::sync`]) walks the journal in `seq` order. It claims a
+//! run of consecutive entries of one kind as a batch by stamping them with a
+//! batch id, sends the batch with that id as `Idempotency-Key`, and marks the
+//! entries replayed once the server answered. A batch that failed midway is
+//! resent unchanged under the same key, so the server applies each
+//! operation exactly once. Replayed entries are kept for
+//! [`RETENTION_MS`] and then pruned.
+
+use danci_native::vclock::VectorClock;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+use crate::clocks;
+use crate::stats::{CardDirection, LocalAnswer, LocalWordState, DAY_MS};
+
+/// Version of the payload shape written by this build.
+pub const SCHEMA_VERSION: i64 = 1;
+/// How long replayed entries stay in the journal.
+pub const RETENTION_MS: i64 = 7 * DAY_MS;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Operation {
+    RecordAnswer(LocalAnswer),
+    /// A learning state written locally, with the word's clock after the write.
+    #[serde(rename_all = "camelCase")]
+    SetWordState {
+        state: LocalWordState,
+        clock: VectorClock,
+    },
+    #[serde(rename_all = "camelCase")]
+    RecordExposure {
+        user_id: String,
+        experiment_id: String,
+        variant_id: String,
+        exposed_at: i64,
+    },
+}
+
+impl Operation {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Operation::RecordAnswer(_) => "recordAnswer",
+            Operation::SetWordState { .. } => "setWordState",
+            Operation::RecordExposure { .. } => "recordExposure",
+        }
+    }
+
+    /// Answers keep their own id, so journaling one twice is a no-op; other
+    /// operations get a fresh id.
+    fn op_id(&self) -> String {
+        match self {
+            Operation::RecordAnswer(answer) => format!("answer:{}", answer.id),
+            _ => clocks::random_id("op"),
+        }
+    }
+}
+
+/// Decodes a payload written with `schema_version`.
+pub fn decode(schema_version: i64, payload: &str) -> Result<Operation, String> {
+    match schema_version {
+        1 => serde_json::from_str(payload).map_err(|e| format!("invalid journal payload: {e}")),
+        v if v > SCHEMA_VERSION => Err(format!(
+            "journal entry has schema version {v}, written by a newer app version"
+        )),
+        v => Err(format!("unknown journal schema version {v}")),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub op_id: String,
+    pub schema_version: i64,
+    pub payload: String,
+}
+
+/// Entries claimed for one replay request.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    pub id: String,
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStatus {
+    pub pending: i64,
+    pub oldest_pending_at: Option<i64>,
+    /// Batch claimed by an interrupted replay, resent first next time.
+    pub open_batch: Option<String>,
+    pub last_replayed_at: Option<i64>,
+}
+
+/// Appends `op`; returns `false` when an entry with its id already exists.
+pub async fn append(
+    conn: &mut SqliteConnection,
+    op: &Operation,
+    now_ms: i64,
+) -> Result<bool, sqlx::Error> {
+    let payload = serde_json::to_string(op).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let inserted = sqlx::query(
+        r#"INSERT OR IGNORE INTO "operation_journal"
+               ("opId", "kind", "schemaVersion", "payload", "createdAt")
+           VALUES (?, ?, ?, ?, ?)"#,
+    )
+    .bind(op.op_id())
+    .bind(op.kind())
+    .bind(SCHEMA_VERSION)
+    .bind(payload)
+    .bind(now_ms)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    Ok(inserted > 0)
+}
+
+/// Journals answers that were stored before the journal existed and not yet
+/// pushed (rowid above the `answer_records` sync cursor). Runs on every
+/// open; answers already journaled are skipped by their operation id.
+pub async fn adopt_unpushed_answers(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let pushed: i64 = sqlx::query_scalar::<_, Option<String>>(
+        r#"SELECT "cursor" FROM "sync_cursors" WHERE "table" = 'answer_records'"#,
+    )
+    .fetch_optional(pool)
+    .await?
+    .flatten()
+    .and_then(|c| c.parse().ok())
+    .unwrap_or(0);
+    let rows = sqlx::query(
+        r#"SELECT "id", "wordId", "wordBookId", "isCorrect", "responseTime", "dwellTime",
+                  "timestamp", "sessionId", "direction"
+           FROM "answer_records" WHERE rowid > ? ORDER BY rowid"#,
+    )
+    .bind(pushed)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut adopted = 0;
+    for row in &rows {
+        let answer = LocalAnswer {
+            id: row.try_get("id")?,
+            word_id: row.try_get("wordId")?,
+            word_book_id: row.try_get("wordBookId")?,
+            is_correct: row.try_get::<i64, _>("isCorrect")? != 0,
+            response_time: row.try_get("responseTime")?,
+            dwell_time: row.try_get("dwellTime")?,
+            timestamp: row.try_get("timestamp")?,
+            session_id: row.try_get("sessionId")?,
+            direction: CardDirection::from_db(&row.try_get::<String, _>("direction")?),
+        };
+        let at = answer.timestamp;
+        if append(&mut tx, &Operation::RecordAnswer(answer), at).await? {
+            adopted += 1;
+        }
+    }
+    tx.commit().await?;
+    Ok(adopted)
+}
+
+async fn load_batch(pool: &SqlitePool, batch_id: &str) -> Result<Option<Batch>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"SELECT "opId", "schemaVersion", "payload" FROM "operation_journal"
+           WHERE "batchId" = ? AND "replayedAt" IS NULL ORDER BY "seq""#,
+    )
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    let entries = rows
+        .iter()
+        .map(|row| {
+            Ok(JournalEntry {
+                op_id: row.try_get("opId")?,
+                schema_version: row.try_get("schemaVersion")?,
+                payload: row.try_get("payload")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
+    Ok(Some(Batch {
+        id: batch_id.to_string(),
+        entries,
+    }))
+}
+
+/// The batch to replay next: one claimed by an interrupted replay, or else
+/// up to `max` consecutive unreplayed entries of the same kind, newly
+/// claimed. `None` when everything has been replayed.
+pub async fn next_batch(pool: &SqlitePool, max: i64) -> Result<Option<Batch>, sqlx::Error> {
+    let open: Option<String> = sqlx::query_scalar(
+        r#"SELECT "batchId" FROM "operation_journal"
+           WHERE "replayedAt" IS NULL AND "batchId" IS NOT NULL ORDER BY "seq" LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await?;
+    if let Some(batch_id) = open {
+        return load_batch(pool, &batch_id).await;
+    }
+
+    let rows = sqlx::query(
+        r#"SELECT "seq", "kind" FROM "operation_journal"
+           WHERE "replayedAt" IS NULL ORDER BY "seq" LIMIT ?"#,
+    )
+    .bind(max)
+    .fetch_all(pool)
+    .await?;
+    let Some(first) = rows.first() else {
+        return Ok(None);
+    };
+    let kind: String = first.try_get("kind")?;
+    let first_seq: i64 = first.try_get("seq")?;
+    let mut last_seq = first_seq;
+    for row in &rows {
+        if row.try_get::<String, _>("kind")? != kind {
+            break;
+        }
+        last_seq = row.try_get("seq")?;
+    }
+
+    let batch_id = clocks::random_id("batch");
+    sqlx::query(
+        r#"UPDATE "operation_journal" SET "batchId" = ?
+           WHERE "seq" BETWEEN ? AND ? AND "replayedAt" IS NULL"#,
+    )
+    .bind(&batch_id)
+    .bind(first_seq)
+    .bind(last_seq)
+    .execute(pool)
+    .await?;
+    load_batch(pool, &batch_id).await
+}
+
+pub async fn mark_replayed(
+    conn: &mut SqliteConnection,
+    batch_id: &str,
+    now_ms: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE "operation_journal" SET "replayedAt" = ? WHERE "batchId" = ?"#)
+        .bind(now_ms)
+        .bind(batch_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Drops entries replayed before `before_ms`.
+pub async fn prune(pool: &SqlitePool, before_ms: i64) -> Result<u64, sqlx::Error> {
+    Ok(sqlx::query(
+        r#"DELETE FROM "operation_journal"
+           WHERE "replayedAt" IS NOT NULL AND "replayedAt" < ?"#,
+    )
+    .bind(before_ms)
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
+
+pub async fn status(pool: &SqlitePool) -> Result<JournalStatus, sqlx::Error> {
+    let row = sqlx::query(
+        r#"SELECT
+               COALESCE(SUM("replayedAt" IS NULL), 0) AS "pending",
+               MIN(CASE WHEN "replayedAt" IS NULL THEN "createdAt" END) AS "oldestPendingAt",
+               MAX("replayedAt") AS "lastReplayedAt"
+           FROM "operation_journal""#,
+    )
+    .fetch_one(pool)
+    .await?;
+    let open_batch = sqlx::query_scalar(
+        r#"SELECT "batchId" FROM "operation_journal"
+           WHERE "replayedAt" IS NULL AND "batchId" IS NOT NULL ORDER BY "seq" LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(JournalStatus {
+        pending: row.try_get("pending")?,
+        oldest_pending_at: row.try_get("oldestPendingAt")?,
+        open_batch,
+        last_replayed_at: row.try_get("lastReplayedAt")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn answer(id: &str) -> Operation {
+        Operation::RecordAnswer(LocalAnswer {
+            id: id.to_string(),
+            word_id: "w".to_string(),
+            word_book_id: "b".to_string(),
+            is_correct: true,
+            response_time: Some(1200),
+            dwell_time: None,
+            timestamp: 1,
+            session_id: None,
+            direction: CardDirection::Recognition,
+        })
+    }
+
+    fn exposure() -> Operation {
+        Operation::RecordExposure {
+            user_id: "u".to_string(),
+            experiment_id: "e".to_string(),
+            variant_id: "v".to_string(),
+            exposed_at: 1,
+        }
+    }
+
+    async fn append_all(pool: &SqlitePool, ops: &[Operation]) -> Vec<bool> {
+        let mut conn = pool.acquire().await.unwrap();
+        let mut appended = Vec::new();
+        for op in ops {
+            appended.push(append(&mut conn, op, 10).await.unwrap());
+        }
+        appended
+    }
+
+    #[tokio::test]
+    async fn answers_are_journaled_once() {
+        let pool = db::open_in_memory().await;
+        let appended = append_all(&pool, &[answer("a1"), answer("a1"), exposure()]).await;
+        assert_eq!(appended, vec![true, false, true]);
+        assert_eq!(status(&pool).await.unwrap().pending, 2);
+    }
+
+    #[tokio::test]
+    async fn batches_hold_one_kind_and_are_resent_until_replayed() {
+        let pool = db::open_in_memory().await;
+        append_all(&pool, &[answer("a1"), answer("a2"), exposure()]).await;
+
+        let first = next_batch(&pool, 10).await.unwrap().unwrap();
+        assert_eq!(first.entries.len(), 2);
+        assert!(first.entries.iter().all(|e| e.op_id.starts_with("answer:")));
+        // An interrupted replay gets the same batch and key back.
+        let again = next_batch(&pool, 10).await.unwrap().unwrap();
+        assert_eq!(again.id, first.id);
+        assert_eq!(
+            status(&pool).await.unwrap().open_batch.as_deref(),
+            Some(first.id.as_str())
+        );
+
+        let mut conn = pool.acquire().await.unwrap();
+        mark_replayed(&mut conn, &first.id, 20).await.unwrap();
+        drop(conn);
+        let second = next_batch(&pool, 10).await.unwrap().unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(second.entries.len(), 1);
+        let op = decode(second.entries[0].schema_version, &second.entries[0].payload).unwrap();
+        assert_eq!(op.kind(), "recordExposure");
+
+        assert_eq!(prune(&pool, 21).await.unwrap(), 2);
+        let status = status(&pool).await.unwrap();
+        assert_eq!(status.pending, 1);
+        assert_eq!(status.last_replayed_at, None);
+    }
+
+    #[test]
+    fn newer_schema_versions_are_rejected() {
+        assert!(decode(SCHEMA_VERSION + 1, "{}")
+            .unwrap_err()
+            .contains("newer app version"));
+    }
+}
@@ -1,9 +1,39 @@
+mod activation_cache;
+mod assets;
+mod audit;
+mod backup;
+mod clocks;
 mod commands;
+mod compaction;
+#[cfg(test)]
+mod contract;
+mod db;
+mod events;
+mod integrity;
+mod journal;
+mod lock_order;
+mod models;
+mod packed;
+mod stats;
+#[cfg(test)]
+mod stress_tests;
+mod sync;
+mod tts;
+mod validation;
+mod word_cache;
+mod wordbook_deltas;
+
+use std::sync::Arc;
 
 use tauri::Manager;
 
 pub fn run() {
     tauri::Builder::default()
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .level(log::LevelFilter::Info)
+                .build(),
+        )
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_http::init())
@@ -15,6 +45,44 @@ pub fn run() {
             }
         }))
         .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            let pool = tauri::async_runtime::block_on(db::open(&data_dir))?;
+            let asset_store = assets::AssetStore::new(data_dir.join("assets"), pool.clone());
+            app.manage(pool);
+            app.manage(asset_store.clone());
+
+            let bus = events::EventBus::default();
+            let algorithm_context = Arc::new(commands::context::ContextBuilder::default());
+            commands::context::subscribe(&bus, algorithm_context.clone());
+            let word_cache = Arc::new(word_cache::WordCache::new(
+                commands::words::load_word_cache_config(app.handle()),
+            ));
+            word_cache::subscribe(&bus, word_cache.clone());
+            let activation_cache = Arc::new(activation_cache::ActivationCache::new(
+                activation_cache::ActivationCacheConfig::default(),
+            ));
+            activation_cache::subscribe(&bus, activation_cache.clone());
+            bus.forward_to_webview(app.handle().clone());
+            app.manage(algorithm_context);
+            app.manage(word_cache);
+            app.manage(activation_cache);
+            app.manage(bus);
+            app.manage(commands::compute::CancelHandles::default());
+            app.manage(tts::Speaker::default());
+
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = asset_store.collect_garbage(assets::GC_GRACE).await {
+                    log::error!("asset garbage collection failed: {e}");
+                }
+            });
+
+            let backup_pool = app.state::<sqlx::SqlitePool>().inner().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = backup::backup_if_due(&backup_pool, &data_dir).await {
+                    log::error!("local database backup failed: {e}");
+                }
+            });
+
             // 确保窗口在启动后显示（window-state 插件的备用方案）
             let window = app
                 .get_webview_window("main")
@@ -28,16 +96,76 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            commands::assets::sync_wordbook_assets,
+            commands::assets::resolve_word_asset,
+            commands::assets::release_wordbook_assets,
+            commands::assets::collect_asset_garbage,
+            commands::audit::audit_record_decision,
+            commands::audit::audit_export_recent,
+            commands::backup::export_database,
+            commands::backup::create_local_backup,
+            commands::backup::list_local_backups,
+            commands::compaction::compact_answer_history,
+            commands::compute::get_compute_mode,
+            commands::compute::set_compute_mode,
+            commands::compute::create_cancel_handle,
+            commands::compute::cancel_computation,
+            commands::compute::compute_review_intervals,
+            commands::compute::compute_review_intervals_packed,
+            commands::context::get_algorithm_context,
+            commands::context::get_linucb_context,
+            commands::context::get_thompson_context,
+            commands::context::get_actr_recall,
+            commands::context::get_actr_recall_packed,
+            commands::experiments::apply_experiment_sync,
+            commands::experiments::get_experiment_variant,
+            commands::experiments::list_pending_exposures,
+            commands::experiments::ack_experiment_exposures,
+            commands::flags::get_feature_flags,
+            commands::flags::apply_feature_flag_sync,
+            commands::integrity::check_data_integrity,
+            commands::integrity::run_self_test,
+            commands::journal::get_journal_status,
             commands::learning::get_learning_words,
             commands::learning::submit_answer,
             commands::learning::get_session,
+            commands::learning::next_batch_size,
+            commands::learning::finish_session,
+            commands::learning::report_model_updated,
+            commands::models::save_model_snapshot,
+            commands::models::save_model_prior,
+            commands::models::load_model_snapshot,
+            commands::models::list_quarantined_models,
+            commands::models::get_algorithm_events,
+            commands::placement::start_placement,
+            commands::placement::answer_placement,
+            commands::placement::get_placement_result,
+            commands::placement::get_pending_placement,
+            commands::placement::ack_placement_submission,
             commands::statistics::get_statistics,
             commands::statistics::get_weekly_report,
+            commands::statistics::get_book_statistics,
+            commands::statistics::get_learning_curve,
+            commands::statistics::get_word_mastery,
+            commands::statistics::get_mastery_progress,
+            commands::statistics::get_rescue_plan,
+            commands::statistics::record_study_progress,
             commands::wordbooks::list_wordbooks,
             commands::wordbooks::select_wordbook,
+            commands::wordbooks::update_wordbook_content,
+            commands::words::get_words,
+            commands::words::get_word_cache_config,
+            commands::words::set_word_cache_config,
             commands::settings::get_settings,
             commands::settings::update_settings,
             commands::settings::reset_window_layout,
+            commands::sync::full_sync,
+            commands::sync::retry_failed_sync,
+            commands::tts::tts_speak,
+            commands::tts::tts_stop,
+            commands::tts::tts_list_voices,
+            commands::tts::tts_get_preferences,
+            commands::tts::tts_set_language_preference,
         ])
         .run(tauri::generate_context!())
         .expect("error running Danci");
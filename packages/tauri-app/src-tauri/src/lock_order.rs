@@ -0,0 +1,153 @@
+//! Lock ordering for shared desktop state.
+//!
+//! Async locks come first: the asset store's GC lock is taken before any
+//! SQLite query of the operation it guards, and no pooled connection or
+//! transaction is held while waiting for it. The in-memory caches and
+//! handle tables below use `RankedMutex`, which is only ever held inside
+//! synchronous code (its guard is not `Send`, so a command future holding it
+//! across an await does not compile) and never while waiting on SQLite.
+//!
+//! Ranked mutexes nest only in increasing [`LockRank`]. Debug builds track
+//! the ranks held by the current thread and panic when a lock is taken out
+//! of order, before blocking on it, so a violating change fails the tests
+//! in `stress_tests` instead of deadlocking at runtime.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+
+/// Lower ranks are taken first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockRank {
+    ContextCache,
+    ActivationCache,
+    WordCache,
+    CancelHandles,
+    Speaker,
+}
+
+/// The mutex was poisoned by a panic while held.
+#[derive(Debug)]
+pub struct Poisoned(pub LockRank);
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} lock poisoned", self.0)
+    }
+}
+
+impl std::error::Error for Poisoned {}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static HELD: std::cell::RefCell<Vec<LockRank>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+pub struct RankedMutex<T> {
+    rank: LockRank,
+    inner: Mutex<T>,
+}
+
+impl<T> RankedMutex<T> {
+    pub const fn new(rank: LockRank, value: T) -> Self {
+        Self {
+            rank,
+            inner: Mutex::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> Result<RankedGuard<'_, T>, Poisoned> {
+        #[cfg(debug_assertions)]
+        HELD.with(|held| {
+            if let Some(&top) = held.borrow().iter().max() {
+                assert!(
+                    top < self.rank,
+                    "lock order violation: {:?} taken while holding {top:?}",
+                    self.rank
+                );
+            }
+        });
+        let guard = self.inner.lock().map_err(|_| Poisoned(self.rank))?;
+        #[cfg(debug_assertions)]
+        HELD.with(|held| held.borrow_mut().push(self.rank));
+        Ok(RankedGuard {
+            guard,
+            rank: self.rank,
+        })
+    }
+}
+
+impl<T: Default> RankedMutex<T> {
+    pub fn with_default(rank: LockRank) -> Self {
+        Self::new(rank, T::default())
+    }
+}
+
+pub struct RankedGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    rank: LockRank,
+}
+
+impl<T> Deref for RankedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for RankedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for RankedGuard<'_, T> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(at) = held.iter().rposition(|&rank| rank == self.rank) {
+                held.remove(at);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_in_rank_order_is_allowed() {
+        let outer = RankedMutex::new(LockRank::ActivationCache, 1);
+        let inner = RankedMutex::new(LockRank::WordCache, 2);
+        let a = outer.lock().unwrap();
+        let b = inner.lock().unwrap();
+        assert_eq!(*a + *b, 3);
+        drop(b);
+        drop(a);
+        // Released ranks no longer constrain later locks
+        let _b = inner.lock().unwrap();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "lock order violation")]
+    fn nesting_out_of_rank_order_panics() {
+        let outer = RankedMutex::new(LockRank::WordCache, ());
+        let inner = RankedMutex::new(LockRank::ActivationCache, ());
+        let _a = outer.lock().unwrap();
+        let _b = inner.lock();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "lock order violation")]
+    fn relocking_the_same_rank_panics_instead_of_deadlocking() {
+        let cache = RankedMutex::new(LockRank::WordCache, ());
+        let _a = cache.lock().unwrap();
+        let _b = cache.lock();
+    }
+}
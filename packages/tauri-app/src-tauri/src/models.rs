@@ -0,0 +1,471 @@
+//! Locally persisted LinUCB snapshots with a load-time health guard.
+//!
+//! A crash in the middle of an update has left NaN-filled snapshots on disk
+//! before, and loading one breaks every following session. Snapshots are
+//! therefore checked when saved and again when loaded: shape (`A`, `L` are
+//! d×d, `b` has d entries), finite values, usable `lambda` / `alpha`, and the
+//! native `diagnose` health check. A snapshot failing on load is moved to
+//! `quarantined_model_snapshots` with the reason and diagnostics, and the
+//! caller gets a fresh model instead: the population prior the webview last
+//! stored with [`save_population_prior`], or A = λI, b = 0 without one. The
+//! fresh model keeps the snapshot's dimension when its shape was consistent,
+//! and its λ and α when those are still usable.
+//!
+//! Saving over a stored snapshot returns how far the new one moved from it
+//! (parameter changes, θ norm change, most-changed features), so a sync that
+//! overwrites the model leaves a readable trace.
+//!
+//! Each snapshot carries a vector clock (see [`crate::clocks`]). A local save
+//! ticks this device's entry; a snapshot taken from `/api/amas/sync` adopts
+//! the server's clock instead. The clock is returned on load so the webview
+//! can send it as `syncClock` on the next sync.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use danci_native::layout::{prior_model, BanditSnapshot};
+use danci_native::linucb::LinUCB;
+use danci_native::snapshot_diff::{diff_linucb, SnapshotDiff};
+use danci_native::vclock::VectorClock;
+use danci_native::{BanditModel, DiagnosticResult, FEATURE_DIMENSION};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::clocks::{self, ClockScope};
+
+const DEFAULT_ALPHA: f64 = 0.3;
+const DEFAULT_LAMBDA: f64 = 1.0;
+/// Most-changed features reported when a snapshot is overwritten.
+const DIFF_TOP_ARMS: usize = 5;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModelStoreError {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error("snapshot rejected: {0}")]
+    Unhealthy(String),
+    #[error("snapshot could not be encoded: {0}")]
+    Encode(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum SnapshotSource {
+    Stored,
+    /// Nothing stored yet, or the stored snapshot was quarantined.
+    Prior,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedSnapshot {
+    pub id: i64,
+    pub model: String,
+    pub version: i64,
+    pub reason: String,
+    #[cfg_attr(test, schemars(with = "Option<serde_json::Value>"))]
+    pub diagnostics: Option<DiagnosticResult>,
+    pub quarantined_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedModel {
+    #[cfg_attr(test, schemars(with = "serde_json::Value"))]
+    pub snapshot: BanditSnapshot,
+    /// Version of the stored snapshot; 0 for a prior model.
+    pub version: i64,
+    pub source: SnapshotSource,
+    /// Set when the stored snapshot was quarantined by this load.
+    pub quarantined: Option<QuarantinedSnapshot>,
+    #[cfg_attr(test, schemars(with = "serde_json::Value"))]
+    pub clock: VectorClock,
+}
+
+/// Why a snapshot is unusable, with diagnostics when they could be computed.
+#[derive(Debug, Clone)]
+pub struct SnapshotProblem {
+    pub reason: String,
+    pub diagnostics: Option<DiagnosticResult>,
+}
+
+fn problem(reason: impl Into<String>) -> SnapshotProblem {
+    SnapshotProblem {
+        reason: reason.into(),
+        diagnostics: None,
+    }
+}
+
+/// Shape, finiteness and numerical health of a snapshot.
+pub fn check_snapshot(snapshot: &BanditSnapshot) -> Result<(), SnapshotProblem> {
+    let model = &snapshot.model;
+    let d = model.d as usize;
+    if d == 0 {
+        return Err(problem("dimension is 0"));
+    }
+    if model.a_matrix.len() != d * d || model.l_matrix.len() != d * d || model.b.len() != d {
+        return Err(problem(format!(
+            "expected A and L of {} entries and b of {d}, got {}, {} and {}",
+            d * d,
+            model.a_matrix.len(),
+            model.l_matrix.len(),
+            model.b.len()
+        )));
+    }
+    if !(model.lambda.is_finite() && model.lambda > 0.0) {
+        return Err(problem(format!("lambda {} is not positive", model.lambda)));
+    }
+    if !(model.alpha.is_finite() && model.alpha >= 0.0) {
+        return Err(problem(format!("alpha {} is not usable", model.alpha)));
+    }
+    if model.b.iter().any(|x| !x.is_finite()) {
+        return Err(problem("b contains non-finite values"));
+    }
+
+    // `diagnose` reports drift separately from `is_healthy` and skips
+    // non-positive pivots, so both are checked here as well.
+    let diagnostics = LinUCB::from_model(model.clone()).diagnose();
+    let reason = if !diagnostics.is_healthy || diagnostics.has_drift {
+        diagnostics.message.clone()
+    } else if model.l_matrix.iter().any(|x| !x.is_finite()) {
+        "L contains non-finite values".to_string()
+    } else if (0..d).any(|i| model.l_matrix[i * d + i] <= 0.0) {
+        "L has a non-positive diagonal entry".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(SnapshotProblem {
+        reason,
+        diagnostics: Some(diagnostics),
+    })
+}
+
+/// Stores the population model new or reset local models start from. It
+/// must pass the same health check as a snapshot.
+pub async fn save_population_prior(
+    pool: &SqlitePool,
+    model: &str,
+    prior: &BanditSnapshot,
+) -> Result<(), ModelStoreError> {
+    check_snapshot(prior).map_err(|p| ModelStoreError::Unhealthy(p.reason))?;
+    sqlx::query(
+        r#"INSERT INTO "model_priors" ("model", "payload", "savedAt")
+           VALUES (?, ?, ?)
+           ON CONFLICT ("model") DO UPDATE SET
+               "payload" = excluded."payload",
+               "savedAt" = excluded."savedAt""#,
+    )
+    .bind(model)
+    .bind(serde_json::to_string(prior)?)
+    .bind(now_ms())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The stored population prior, when it still passes the health check.
+async fn population_prior(
+    pool: &SqlitePool,
+    model: &str,
+) -> Result<Option<BanditSnapshot>, sqlx::Error> {
+    let payload: Option<String> =
+        sqlx::query_scalar(r#"SELECT "payload" FROM "model_priors" WHERE "model" = ?"#)
+            .bind(model)
+            .fetch_optional(pool)
+            .await?;
+    Ok(payload
+        .and_then(|p| serde_json::from_str::<BanditSnapshot>(&p).ok())
+        .filter(|prior| check_snapshot(prior).is_ok()))
+}
+
+/// Fresh model for `model`: the population prior when one of the right
+/// dimension is stored, otherwise A = λI, keeping whatever of the rejected
+/// snapshot is still usable.
+async fn prior_snapshot(
+    pool: &SqlitePool,
+    model: &str,
+    rejected: Option<&BanditSnapshot>,
+) -> Result<BanditSnapshot, sqlx::Error> {
+    let (d, lambda, alpha) = prior_parameters(rejected);
+    if let Some(prior) = population_prior(pool, model).await? {
+        if prior.model.d as usize == d {
+            return Ok(BanditSnapshot {
+                model: BanditModel {
+                    update_count: 0,
+                    ..prior.model
+                },
+                ..prior
+            });
+        }
+    }
+    Ok(BanditSnapshot {
+        model: prior_model(d, lambda, alpha),
+        layout_fingerprint: rejected.and_then(|s| s.layout_fingerprint.clone()),
+        layout: rejected.and_then(|s| s.layout.clone()),
+    })
+}
+
+/// Dimension, λ and α for a fresh model replacing `rejected`. Its dimension
+/// is only trusted when A, L and b all agree with it.
+fn prior_parameters(rejected: Option<&BanditSnapshot>) -> (usize, f64, f64) {
+    let usable = |x: f64, min: f64| x.is_finite() && x >= min;
+    let consistent = |m: &BanditModel| {
+        let d = m.d as usize;
+        d > 0 && m.a_matrix.len() == d * d && m.l_matrix.len() == d * d && m.b.len() == d
+    };
+    match rejected {
+        Some(s) => (
+            if consistent(&s.model) {
+                s.model.d as usize
+            } else {
+                FEATURE_DIMENSION
+            },
+            if usable(s.model.lambda, f64::MIN_POSITIVE) {
+                s.model.lambda
+            } else {
+                DEFAULT_LAMBDA
+            },
+            if usable(s.model.alpha, 0.0) {
+                s.model.alpha
+            } else {
+                DEFAULT_ALPHA
+            },
+        ),
+        None => (FEATURE_DIMENSION, DEFAULT_LAMBDA, DEFAULT_ALPHA),
+    }
+}
+
+/// Stores a snapshot after checking it; an unhealthy one is refused. Returns
+/// the difference from the snapshot it replaced, if a readable one was stored.
+/// `sync_clock` is the server's clock when the snapshot came from a sync;
+/// without one the save counts as a local write.
+pub async fn save_snapshot(
+    pool: &SqlitePool,
+    model: &str,
+    version: i64,
+    snapshot: &BanditSnapshot,
+    sync_clock: Option<&VectorClock>,
+) -> Result<Option<SnapshotDiff>, ModelStoreError> {
+    check_snapshot(snapshot).map_err(|p| ModelStoreError::Unhealthy(p.reason))?;
+    let previous: Option<String> =
+        sqlx::query_scalar(r#"SELECT "payload" FROM "model_snapshots" WHERE "model" = ?"#)
+            .bind(model)
+            .fetch_optional(pool)
+            .await?;
+    let diff = previous
+        .and_then(|p| serde_json::from_str::<BanditSnapshot>(&p).ok())
+        .filter(|previous| check_snapshot(previous).is_ok())
+        .map(|previous| diff_linucb(&previous, snapshot, DIFF_TOP_ARMS));
+    let payload = serde_json::to_string(snapshot)?;
+    let device = clocks::device_id(pool).await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"INSERT INTO "model_snapshots" ("model", "version", "payload", "savedAt")
+           VALUES (?, ?, ?, ?)
+           ON CONFLICT ("model") DO UPDATE SET
+               "version" = excluded."version",
+               "payload" = excluded."payload",
+               "savedAt" = excluded."savedAt""#,
+    )
+    .bind(model)
+    .bind(version)
+    .bind(payload)
+    .bind(now_ms())
+    .execute(&mut *tx)
+    .await?;
+    match sync_clock {
+        Some(remote) => clocks::adopt(&mut tx, ClockScope::ModelSnapshot, model, remote).await?,
+        None => clocks::tick(&mut tx, ClockScope::ModelSnapshot, model, &device).await?,
+    };
+    tx.commit().await?;
+    Ok(diff)
+}
+
+/// Loads a snapshot, quarantining it and falling back to a prior model when
+/// it fails the health check.
+pub async fn load_snapshot(pool: &SqlitePool, model: &str) -> Result<LoadedModel, sqlx::Error> {
+    let row =
+        sqlx::query(r#"SELECT "version", "payload" FROM "model_snapshots" WHERE "model" = ?"#)
+            .bind(model)
+            .fetch_optional(pool)
+            .await?;
+    let clock = clocks::load(
+        &mut *pool.acquire().await?,
+        ClockScope::ModelSnapshot,
+        model,
+    )
+    .await?;
+    let Some(row) = row else {
+        return Ok(LoadedModel {
+            snapshot: prior_snapshot(pool, model, None).await?,
+            version: 0,
+            source: SnapshotSource::Prior,
+            quarantined: None,
+            clock,
+        });
+    };
+    let version: i64 = row.try_get("version")?;
+    let payload: String = row.try_get("payload")?;
+
+    // NaN and infinities are stored as JSON null, which fails to decode.
+    let (rejected, problem) = match serde_json::from_str::<BanditSnapshot>(&payload) {
+        Ok(snapshot) => match check_snapshot(&snapshot) {
+            Ok(()) => {
+                return Ok(LoadedModel {
+                    snapshot,
+                    version,
+                    source: SnapshotSource::Stored,
+                    quarantined: None,
+                    clock,
+                })
+            }
+            Err(problem) => (Some(snapshot), problem),
+        },
+        Err(e) => (None, problem(format!("payload does not decode: {e}"))),
+    };
+
+    let diagnostics_json = problem
+        .diagnostics
+        .as_ref()
+        .and_then(|d| serde_json::to_string(d).ok());
+    let quarantined_at = now_ms();
+    let mut tx = pool.begin().await?;
+    let id = sqlx::query(
+        r#"INSERT INTO "quarantined_model_snapshots"
+               ("model", "version", "payload", "reason", "diagnostics", "quarantinedAt")
+           VALUES (?, ?, ?, ?, ?, ?)"#,
+    )
+    .bind(model)
+    .bind(version)
+    .bind(&payload)
+    .bind(&problem.reason)
+    .bind(diagnostics_json)
+    .bind(quarantined_at)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+    sqlx::query(r#"DELETE FROM "model_snapshots" WHERE "model" = ?"#)
+        .bind(model)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(LoadedModel {
+        snapshot: prior_snapshot(pool, model, rejected.as_ref()).await?,
+        version: 0,
+        source: SnapshotSource::Prior,
+        quarantined: Some(QuarantinedSnapshot {
+            id,
+            model: model.to_string(),
+            version,
+            reason: problem.reason,
+            diagnostics: problem.diagnostics,
+            quarantined_at,
+        }),
+        clock,
+    })
+}
+
+/// Most recent quarantined snapshots, newest first (payloads omitted).
+pub async fn list_quarantined(
+    pool: &SqlitePool,
+    limit: i64,
+) -> Result<Vec<QuarantinedSnapshot>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"SELECT "id", "model", "version", "reason", "diagnostics", "quarantinedAt"
+           FROM "quarantined_model_snapshots"
+           ORDER BY "quarantinedAt" DESC, "id" DESC
+           LIMIT ?"#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            let diagnostics: Option<String> = row.try_get("diagnostics")?;
+            Ok(QuarantinedSnapshot {
+                id: row.try_get("id")?,
+                model: row.try_get("model")?,
+                version: row.try_get("version")?,
+                reason: row.try_get("reason")?,
+                diagnostics: diagnostics.and_then(|d| serde_json::from_str(&d).ok()),
+                quarantined_at: row.try_get("quarantinedAt")?,
+            })
+        })
+        .collect()
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn snapshot(model: BanditModel) -> BanditSnapshot {
+        BanditSnapshot {
+            model,
+            layout_fingerprint: None,
+            layout: None,
+        }
+    }
+
+    #[test]
+    fn an_inconsistent_shape_falls_back_to_the_feature_dimension() {
+        let mut model = prior_model(3, 2.0, 0.5);
+        model.b.pop();
+        assert_eq!(
+            prior_parameters(Some(&snapshot(model))),
+            (FEATURE_DIMENSION, 2.0, 0.5)
+        );
+        assert_eq!(
+            prior_parameters(Some(&snapshot(prior_model(3, f64::NAN, 0.5)))),
+            (3, DEFAULT_LAMBDA, 0.5)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_quarantined_snapshot_is_replaced_by_the_population_prior() {
+        let pool = db::open_in_memory().await;
+        let mut population = prior_model(FEATURE_DIMENSION, 2.0, 0.4);
+        population.b[0] = 0.7;
+        population.update_count = 120;
+        save_population_prior(&pool, "linucb", &snapshot(population.clone()))
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"INSERT INTO "model_snapshots" ("model", "version", "payload", "savedAt")
+               VALUES ('linucb', 3, '{"model":null}', 0)"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let loaded = load_snapshot(&pool, "linucb").await.unwrap();
+        assert_eq!(loaded.source, SnapshotSource::Prior);
+        assert_eq!(loaded.quarantined.map(|q| q.version), Some(3));
+        assert_eq!(loaded.snapshot.model.b, population.b);
+        assert_eq!(loaded.snapshot.model.lambda, 2.0);
+        assert_eq!(loaded.snapshot.model.update_count, 0);
+    }
+
+    #[tokio::test]
+    async fn a_prior_of_another_dimension_is_not_used() {
+        let pool = db::open_in_memory().await;
+        save_population_prior(&pool, "linucb", &snapshot(prior_model(3, 2.0, 0.4)))
+            .await
+            .unwrap();
+
+        let loaded = load_snapshot(&pool, "linucb").await.unwrap();
+        assert_eq!(loaded.source, SnapshotSource::Prior);
+        assert_eq!(loaded.snapshot.model.d as usize, FEATURE_DIMENSION);
+        assert_eq!(loaded.snapshot.model.lambda, DEFAULT_LAMBDA);
+    }
+}
@@ -0,0 +1,98 @@
+//! Binary IPC payloads for large batch results.
+//!
+//! A batch of thousands of small records costs tens of milliseconds to
+//! serialize as a JSON array of objects and to parse again in the webview.
+//! The packed variants of the heavy batch commands return the records'
+//! numeric fields as one `f64` buffer behind a small JSON header instead,
+//! sent as raw bytes, which the webview receives as an `ArrayBuffer` and
+//! reads through a `Float64Array` without parsing. Records carry no ids:
+//! they follow the order of the request, as documented on each command.
+
+use serde::Serialize;
+
+/// Bumped whenever the byte layout of [`PackedBatch`] changes.
+pub const PACKED_VERSION: u32 = 1;
+
+/// JSON part of a [`PackedBatch`].
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PackedHeader {
+    pub version: u32,
+    /// Number of records in the buffer.
+    pub count: u32,
+    /// Names of each record's fields, in buffer order.
+    pub fields: Vec<String>,
+    /// Command-specific metadata, e.g. the progress of a cancellable batch.
+    pub meta: serde_json::Value,
+}
+
+/// A batch result packed for IPC.
+///
+/// Byte layout, all integers and floats little-endian:
+///
+/// | offset              | size                       | content                        |
+/// |---------------------|----------------------------|--------------------------------|
+/// | 0                   | 4                          | header length `n` (u32)        |
+/// | 4                   | `n`                        | [`PackedHeader`] as UTF-8 JSON |
+/// | 4 + `n`             | 0–7                        | zero padding                   |
+/// | `ceil((4 + n) / 8) * 8` | `8 * count * fields.len()` | values (f64), record by record |
+///
+/// The padding keeps the values 8-byte aligned, so the webview can view
+/// them in place (`Float64Array` uses the platform byte order, which is
+/// little-endian on every target the app ships for):
+///
+/// ```text
+/// const view = new DataView(buffer);
+/// const n = view.getUint32(0, true);
+/// const header = JSON.parse(new TextDecoder().decode(new Uint8Array(buffer, 4, n)));
+/// const values = new Float64Array(
+///   buffer, Math.ceil((4 + n) / 8) * 8, header.count * header.fields.length);
+/// // field f of record i: values[i * header.fields.length + f]
+/// ```
+#[derive(Debug, Clone)]
+pub struct PackedBatch {
+    pub header: PackedHeader,
+    pub values: Vec<f64>,
+}
+
+impl PackedBatch {
+    pub fn new(fields: &[&str], capacity: usize, meta: serde_json::Value) -> Self {
+        Self {
+            header: PackedHeader {
+                version: PACKED_VERSION,
+                count: 0,
+                fields: fields.iter().map(|f| f.to_string()).collect(),
+                meta,
+            },
+            values: Vec::with_capacity(capacity * fields.len()),
+        }
+    }
+
+    /// Appends one record; `record` holds one value per field.
+    pub fn push(&mut self, record: &[f64]) {
+        debug_assert_eq!(record.len(), self.header.fields.len());
+        self.values.extend_from_slice(record);
+        self.header.count += 1;
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
+        let header = serde_json::to_vec(&self.header)?;
+        let values_at = (4 + header.len()).div_ceil(8) * 8;
+        let mut out = Vec::with_capacity(values_at + self.values.len() * 8);
+        out.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        out.extend_from_slice(&header);
+        out.resize(values_at, 0);
+        for value in &self.values {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(out)
+    }
+
+    /// Raw-bytes command response; arrives as an `ArrayBuffer`.
+    pub fn into_response(self) -> Result<tauri::ipc::Response, String> {
+        self.to_bytes()
+            .map(tauri::ipc::Response::new)
+            .map_err(|e| format!("Failed to pack batch result: {e}"))
+    }
+}
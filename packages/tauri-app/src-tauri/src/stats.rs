@@ -0,0 +1,747 @@
+//! Per-wordbook study statistics computed from the local database.
+//!
+//! The webview mirrors each answer and learning-state change into the local
+//! `answer_records` and `word_learning_states` tables, so the statistics
+//! screen can be filled offline. The result uses the field names of the backend's
+//! enhanced statistics response so the UI can render either. Days are UTC.
+//! Answers older than the compaction horizon live on as per-day rows in
+//! `answer_daily_summaries` (see [`crate::compaction`]); the all-time figures
+//! read both tables, the trend and pace windows only cover raw rows.
+//!
+//! Each word is studied as two cards, recognition and production (see
+//! [`CardDirection`]), with separate answer traces and learning states. Word
+//! counts and mastery figures are those of the recognition cards; answer
+//! counts and study time include both directions.
+
+use std::collections::HashMap;
+
+use danci_native::curve::{cumulative_points, fit_learning_curve};
+use danci_native::mastery::{
+    linked_recall, mastery_band, MasteryBand, MasteryBandConfig, ReviewEvent,
+};
+use danci_native::progress::{mastery_progress, MasteryProgress};
+use danci_native::rescue::{plan_rescue, RescueCandidate, RescueConfig, RescuePlan};
+use danci_native::LearningCurve;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::activation_cache::ActivationCache;
+use crate::clocks::{self, ClockScope};
+use crate::journal::{self, Operation};
+
+pub const DAY_MS: i64 = 24 * 3600 * 1000;
+/// Days covered by the trend series.
+pub const TREND_DAYS: i64 = 30;
+/// Mastery level at which a word counts as mastered for the learning curve.
+const CURVE_MASTERED_LEVEL: i32 = 4;
+/// Per-answer time cap, so an answer left open while idle is not study time.
+pub const MAX_ANSWER_MS: i64 = 60_000;
+/// Highest `masteryLevel`; mastery bands work on level / this.
+const MAX_MASTERY_LEVEL: f64 = 5.0;
+/// Most recent answers per word fed into the mastery band.
+const BAND_TRACE_LIMIT: i64 = 30;
+/// Days of answers averaged into the study pace for the mastery ETA.
+const PACE_DAYS: i64 = 7;
+/// ACT-R recall below which a learned word counts as overdue for rescue review.
+const RESCUE_DUE_RECALL: f64 = 0.7;
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct BookStatistics {
+    pub word_book_id: String,
+    pub total_words: i64,
+    pub total_records: i64,
+    pub correct_rate: f64,
+    pub mastered_words: i64,
+    pub time_spent_ms: i64,
+    pub study_days: i64,
+    pub consecutive_days: i64,
+    pub daily_accuracy: Vec<DailyAccuracyItem>,
+    pub mastery_distribution: Vec<MasteryLevelCount>,
+    pub trend: Vec<DailyTrendItem>,
+    pub freshness: Freshness,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DailyAccuracyItem {
+    pub date: String,
+    pub accuracy: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MasteryLevelCount {
+    pub level: i32,
+    pub count: i64,
+}
+
+/// One day of the trend; days without answers are included with zeros.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DailyTrendItem {
+    pub date: String,
+    pub answers: i64,
+    pub correct: i64,
+    pub time_spent_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Freshness {
+    pub source: &'static str,
+    pub stale: bool,
+}
+
+/// Which way a word is asked. Recognizing a word and producing it are
+/// different skills, so each direction is its own card with its own trace
+/// and learning state; reviews of one direction still count partially
+/// towards the other (`MasteryBandConfig::transfer`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum CardDirection {
+    /// L2 → L1: the word is shown, its meaning recalled. The only direction
+    /// the server knows about.
+    #[default]
+    Recognition,
+    /// L1 → L2: the meaning is shown, the word produced.
+    Production,
+}
+
+impl CardDirection {
+    pub const ALL: [CardDirection; 2] = [CardDirection::Recognition, CardDirection::Production];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CardDirection::Recognition => "recognition",
+            CardDirection::Production => "production",
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            CardDirection::Recognition => CardDirection::Production,
+            CardDirection::Production => CardDirection::Recognition,
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        if value == "production" {
+            CardDirection::Production
+        } else {
+            CardDirection::Recognition
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LocalAnswer {
+    pub id: String,
+    pub word_id: String,
+    pub word_book_id: String,
+    pub is_correct: bool,
+    pub response_time: Option<i64>,
+    pub dwell_time: Option<i64>,
+    /// Unix ms.
+    pub timestamp: i64,
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub direction: CardDirection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LocalWordState {
+    pub word_id: String,
+    pub word_book_id: String,
+    pub mastery_level: i32,
+    /// NEW, LEARNING, REVIEWING or MASTERED.
+    pub state: String,
+    /// Unix ms.
+    pub updated_at: i64,
+    #[serde(default)]
+    pub direction: CardDirection,
+}
+
+/// Stores answers (ignoring ones already stored) and the latest learning
+/// states in one transaction. Every answer written is appended to the
+/// operation journal for replay onto the server. Each recognition state
+/// actually written counts as a local write in the word's sync clock and is
+/// journaled too; production states stay local, as the server keeps one
+/// state per word.
+pub async fn record_progress(
+    pool: &SqlitePool,
+    answers: &[LocalAnswer],
+    states: &[LocalWordState],
+    now_ms: i64,
+) -> Result<(), sqlx::Error> {
+    let device = clocks::device_id(pool).await?;
+    let mut tx = pool.begin().await?;
+    for a in answers {
+        let inserted = sqlx::query(
+            r#"INSERT OR IGNORE INTO "answer_records"
+               ("id", "wordId", "wordBookId", "isCorrect", "responseTime", "dwellTime", "timestamp", "sessionId", "direction")
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(&a.id)
+        .bind(&a.word_id)
+        .bind(&a.word_book_id)
+        .bind(a.is_correct)
+        .bind(a.response_time)
+        .bind(a.dwell_time)
+        .bind(a.timestamp)
+        .bind(&a.session_id)
+        .bind(a.direction.as_str())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted > 0 {
+            journal::append(&mut tx, &Operation::RecordAnswer(a.clone()), now_ms).await?;
+        }
+    }
+    for s in states {
+        let written = sqlx::query(
+            r#"INSERT INTO "word_learning_states" ("wordBookId", "wordId", "direction", "masteryLevel", "state", "updatedAt")
+               VALUES (?, ?, ?, ?, ?, ?)
+               ON CONFLICT ("wordBookId", "wordId", "direction") DO UPDATE SET
+                   "masteryLevel" = excluded."masteryLevel",
+                   "state" = excluded."state",
+                   "updatedAt" = excluded."updatedAt"
+               WHERE excluded."updatedAt" >= "word_learning_states"."updatedAt""#,
+        )
+        .bind(&s.word_book_id)
+        .bind(&s.word_id)
+        .bind(s.direction.as_str())
+        .bind(s.mastery_level)
+        .bind(&s.state)
+        .bind(s.updated_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if written > 0 && s.direction == CardDirection::Recognition {
+            let clock = clocks::tick(&mut tx, ClockScope::WordState, &s.word_id, &device).await?;
+            let op = Operation::SetWordState {
+                state: s.clone(),
+                clock,
+            };
+            journal::append(&mut tx, &op, now_ms).await?;
+        }
+    }
+    tx.commit().await
+}
+
+pub async fn book_statistics(
+    pool: &SqlitePool,
+    book_id: &str,
+    now_ms: i64,
+) -> Result<BookStatistics, sqlx::Error> {
+    let totals = sqlx::query(
+        r#"
+        SELECT COALESCE(SUM("answers"), 0) AS "records",
+               COALESCE(SUM("correct"), 0) AS "correct",
+               COALESCE(SUM("timeSpent"), 0) AS "timeSpent",
+               COUNT(DISTINCT "day") AS "studyDays"
+        FROM (
+            SELECT 1 AS "answers", "isCorrect" AS "correct",
+                   COALESCE("dwellTime", "responseTime", 0) AS "timeSpent",
+                   "timestamp" / 86400000 AS "day"
+            FROM "answer_records" WHERE "wordBookId" = ?
+            UNION ALL
+            SELECT "answers", "correct", "timeSpent", "day"
+            FROM "answer_daily_summaries" WHERE "wordBookId" = ?
+        )
+        "#,
+    )
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_one(pool)
+    .await?;
+    let total_records: i64 = totals.try_get("records")?;
+    let correct: i64 = totals.try_get("correct")?;
+
+    let words = sqlx::query(
+        r#"
+        SELECT COUNT(*) AS "words",
+               COALESCE(SUM(CASE WHEN "state" = 'MASTERED' THEN 1 ELSE 0 END), 0) AS "mastered"
+        FROM "word_learning_states"
+        WHERE "wordBookId" = ? AND "direction" = 'recognition'
+        "#,
+    )
+    .bind(book_id)
+    .fetch_one(pool)
+    .await?;
+
+    let mastery_distribution = sqlx::query(
+        r#"
+        SELECT "masteryLevel" AS "level", COUNT(*) AS "count"
+        FROM "word_learning_states"
+        WHERE "wordBookId" = ? AND "direction" = 'recognition'
+        GROUP BY "masteryLevel"
+        ORDER BY "masteryLevel"
+        "#,
+    )
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        Ok(MasteryLevelCount {
+            level: row.try_get("level")?,
+            count: row.try_get("count")?,
+        })
+    })
+    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+    let today = now_ms.div_euclid(DAY_MS);
+    let first_day = today - TREND_DAYS + 1;
+    let mut by_day: HashMap<i64, (i64, i64, i64)> = HashMap::new();
+    for row in sqlx::query(
+        r#"
+        SELECT "timestamp" / 86400000 AS "day",
+               COUNT(*) AS "answers",
+               COALESCE(SUM("isCorrect"), 0) AS "correct",
+               COALESCE(SUM(COALESCE("dwellTime", "responseTime", 0)), 0) AS "timeSpent"
+        FROM "answer_records"
+        WHERE "wordBookId" = ? AND "timestamp" >= ?
+        GROUP BY "day"
+        "#,
+    )
+    .bind(book_id)
+    .bind(first_day * DAY_MS)
+    .fetch_all(pool)
+    .await?
+    {
+        by_day.insert(
+            row.try_get("day")?,
+            (
+                row.try_get("answers")?,
+                row.try_get("correct")?,
+                row.try_get("timeSpent")?,
+            ),
+        );
+    }
+
+    let trend: Vec<DailyTrendItem> = (first_day..=today)
+        .map(|day| {
+            let (answers, correct, time_spent_ms) = by_day.get(&day).copied().unwrap_or_default();
+            DailyTrendItem {
+                date: format_day(day),
+                answers,
+                correct,
+                time_spent_ms,
+            }
+        })
+        .collect();
+    let daily_accuracy = trend
+        .iter()
+        .filter(|d| d.answers > 0)
+        .map(|d| DailyAccuracyItem {
+            date: d.date.clone(),
+            accuracy: d.correct as f64 / d.answers as f64,
+        })
+        .collect();
+
+    Ok(BookStatistics {
+        word_book_id: book_id.to_string(),
+        total_words: words.try_get("words")?,
+        total_records,
+        correct_rate: if total_records > 0 {
+            correct as f64 / total_records as f64
+        } else {
+            0.0
+        },
+        mastered_words: words.try_get("mastered")?,
+        time_spent_ms: totals.try_get("timeSpent")?,
+        study_days: totals.try_get("studyDays")?,
+        consecutive_days: consecutive_days(pool, book_id, today).await?,
+        daily_accuracy,
+        mastery_distribution,
+        trend,
+        freshness: Freshness {
+            source: "local",
+            stale: false,
+        },
+    })
+}
+
+/// Study minutes and newly mastered words of one day.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DailyProgress {
+    pub date: String,
+    pub minutes: f64,
+    pub mastered: i64,
+}
+
+/// Same shape as the backend's learning-curve statistics.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LearningCurveReport {
+    pub word_book_id: Option<String>,
+    pub days: Vec<DailyProgress>,
+    #[cfg_attr(test, schemars(with = "Option<serde_json::Value>"))]
+    pub curve: Option<LearningCurve>,
+}
+
+/// Fits the mastered-words vs study-time curve of one wordbook, or of all
+/// local books when `book_id` is `None`. Local states only keep their latest
+/// update, so a word counts as mastered on the day its state last changed.
+pub async fn learning_curve(
+    pool: &SqlitePool,
+    book_id: Option<&str>,
+    budgets: Vec<f64>,
+) -> Result<LearningCurveReport, sqlx::Error> {
+    let mut by_day: std::collections::BTreeMap<i64, (i64, i64)> = Default::default();
+    for row in sqlx::query(
+        r#"
+        SELECT "day", COALESCE(SUM("ms"), 0) AS "ms"
+        FROM (
+            SELECT "timestamp" / 86400000 AS "day",
+                   MIN(COALESCE("dwellTime", "responseTime", 0), ?) AS "ms"
+            FROM "answer_records"
+            WHERE ? IS NULL OR "wordBookId" = ?
+            UNION ALL
+            SELECT "day", "cappedTime" AS "ms"
+            FROM "answer_daily_summaries"
+            WHERE ? IS NULL OR "wordBookId" = ?
+        )
+        GROUP BY "day"
+        "#,
+    )
+    .bind(MAX_ANSWER_MS)
+    .bind(book_id)
+    .bind(book_id)
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    {
+        by_day.entry(row.try_get("day")?).or_default().0 = row.try_get("ms")?;
+    }
+    for row in sqlx::query(
+        r#"
+        SELECT "updatedAt" / 86400000 AS "day", COUNT(*) AS "mastered"
+        FROM "word_learning_states"
+        WHERE "masteryLevel" >= ? AND "direction" = 'recognition'
+          AND (? IS NULL OR "wordBookId" = ?)
+        GROUP BY "day"
+        "#,
+    )
+    .bind(CURVE_MASTERED_LEVEL)
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    {
+        by_day.entry(row.try_get("day")?).or_default().1 = row.try_get("mastered")?;
+    }
+
+    let days: Vec<DailyProgress> = by_day
+        .into_iter()
+        .map(|(day, (ms, mastered))| DailyProgress {
+            date: format_day(day),
+            minutes: ms as f64 / 60_000.0,
+            mastered,
+        })
+        .collect();
+    let increments: Vec<(f64, f64)> = days
+        .iter()
+        .map(|d| (d.minutes, d.mastered as f64))
+        .collect();
+    let curve = fit_learning_curve(cumulative_points(&increments), budgets, None);
+
+    Ok(LearningCurveReport {
+        word_book_id: book_id.map(str::to_string),
+        days,
+        curve,
+    })
+}
+
+/// A word's local learning state with its mastery confidence band.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WordMastery {
+    pub word_id: String,
+    pub direction: CardDirection,
+    pub mastery_level: i32,
+    pub state: String,
+    #[cfg_attr(test, schemars(with = "serde_json::Value"))]
+    pub band: MasteryBand,
+}
+
+/// Learning states of the given words' cards in one direction of a wordbook,
+/// each with a mastery band from the card's recent answers. Cards never
+/// studied are omitted.
+pub async fn word_mastery(
+    pool: &SqlitePool,
+    book_id: &str,
+    word_ids: &[String],
+    direction: CardDirection,
+    now_ms: i64,
+) -> Result<Vec<WordMastery>, sqlx::Error> {
+    let mut out = Vec::with_capacity(word_ids.len());
+    for word_id in word_ids {
+        let Some(row) = sqlx::query(
+            r#"SELECT "masteryLevel", "state" FROM "word_learning_states"
+               WHERE "wordBookId" = ? AND "wordId" = ? AND "direction" = ?"#,
+        )
+        .bind(book_id)
+        .bind(word_id)
+        .bind(direction.as_str())
+        .fetch_optional(pool)
+        .await?
+        else {
+            continue;
+        };
+        let mastery_level: i32 = row.try_get("masteryLevel")?;
+
+        let events = review_events(pool, book_id, word_id, direction, now_ms).await?;
+
+        out.push(WordMastery {
+            word_id: word_id.clone(),
+            direction,
+            mastery_level,
+            state: row.try_get("state")?,
+            band: mastery_band(f64::from(mastery_level) / MAX_MASTERY_LEVEL, events, None),
+        });
+    }
+    Ok(out)
+}
+
+/// Most recent answers to one card of a word, newest first, aged relative
+/// to `now_ms`. When the raw rows run out, compacted days fill the rest of
+/// the trace, each answer aged at its day's mean time.
+pub async fn review_events(
+    pool: &SqlitePool,
+    book_id: &str,
+    word_id: &str,
+    direction: CardDirection,
+    now_ms: i64,
+) -> Result<Vec<ReviewEvent>, sqlx::Error> {
+    let mut events = sqlx::query(
+        r#"SELECT "isCorrect", "timestamp" FROM "answer_records"
+           WHERE "wordBookId" = ? AND "wordId" = ? AND "direction" = ?
+           ORDER BY "timestamp" DESC
+           LIMIT ?"#,
+    )
+    .bind(book_id)
+    .bind(word_id)
+    .bind(direction.as_str())
+    .bind(BAND_TRACE_LIMIT)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| {
+        let timestamp: i64 = row.try_get("timestamp")?;
+        Ok(ReviewEvent {
+            age_days: (now_ms - timestamp).max(0) as f64 / DAY_MS as f64,
+            correct: row.try_get::<i64, _>("isCorrect")? != 0,
+        })
+    })
+    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+    let missing = BAND_TRACE_LIMIT - events.len() as i64;
+    if missing <= 0 {
+        return Ok(events);
+    }
+    for row in sqlx::query(
+        r#"SELECT "answers", "correct", "timestampSum" FROM "answer_daily_summaries"
+           WHERE "wordBookId" = ? AND "wordId" = ? AND "direction" = ?
+           ORDER BY "day" DESC
+           LIMIT ?"#,
+    )
+    .bind(book_id)
+    .bind(word_id)
+    .bind(direction.as_str())
+    .bind(missing)
+    .fetch_all(pool)
+    .await?
+    {
+        let answers: i64 = row.try_get("answers")?;
+        let correct: i64 = row.try_get("correct")?;
+        let timestamp_sum: i64 = row.try_get("timestampSum")?;
+        if answers <= 0 {
+            continue;
+        }
+        let age_days = (now_ms - timestamp_sum / answers).max(0) as f64 / DAY_MS as f64;
+        for i in 0..answers {
+            if events.len() as i64 >= BAND_TRACE_LIMIT {
+                return Ok(events);
+            }
+            events.push(ReviewEvent {
+                age_days,
+                correct: i < correct,
+            });
+        }
+    }
+    Ok(events)
+}
+
+/// Expected mastery of a wordbook from each word's ACT-R recall (of its
+/// recognition card), with an ETA at the pace of the last `PACE_DAYS` days.
+/// `total_words` is the book size; words without a local learning state
+/// count as unlearned. Recalls go through `activations`, so only words
+/// answered since the last call reload their trace.
+pub async fn book_mastery_progress(
+    pool: &SqlitePool,
+    activations: &ActivationCache,
+    book_id: &str,
+    total_words: Option<u32>,
+    now_ms: i64,
+) -> Result<MasteryProgress, sqlx::Error> {
+    let word_ids: Vec<String> = sqlx::query_scalar(
+        r#"SELECT "wordId" FROM "word_learning_states"
+               WHERE "wordBookId" = ? AND "direction" = 'recognition'"#,
+    )
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?;
+    let mut recalls = activations
+        .recalls(
+            pool,
+            book_id,
+            &word_ids,
+            CardDirection::Recognition,
+            &MasteryBandConfig::default(),
+            now_ms,
+        )
+        .await?;
+    let unlearned = total_words.map_or(0, |n| (n as usize).saturating_sub(recalls.len()));
+    recalls.extend(std::iter::repeat(0.0).take(unlearned));
+
+    // Distinct words per day, averaged over the window.
+    let studied: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM (
+               SELECT DISTINCT "wordId", "timestamp" / ? AS "day" FROM "answer_records"
+               WHERE "wordBookId" = ? AND "timestamp" >= ?
+           )"#,
+    )
+    .bind(DAY_MS)
+    .bind(book_id)
+    .bind(now_ms - PACE_DAYS * DAY_MS)
+    .fetch_one(pool)
+    .await?;
+    Ok(mastery_progress(
+        recalls,
+        studied as f64 / PACE_DAYS as f64,
+        None,
+    ))
+}
+
+/// Rescue review plan for a wordbook after a long break. There are no due
+/// dates locally, so every learned word whose ACT-R recall (of its
+/// recognition card, helped by production reviews) has dropped below
+/// `RESCUE_DUE_RECALL` is overdue; its loss is the recall it would lose by
+/// waiting another day, and its review time is the word's mean answer time.
+pub async fn book_rescue_plan(
+    pool: &SqlitePool,
+    book_id: &str,
+    config: Option<RescueConfig>,
+    now_ms: i64,
+) -> Result<RescuePlan, sqlx::Error> {
+    let word_ids: Vec<String> = sqlx::query_scalar(
+        r#"SELECT "wordId" FROM "word_learning_states"
+           WHERE "wordBookId" = ? AND "direction" = 'recognition' AND "state" <> 'NEW'"#,
+    )
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?;
+    let seconds: HashMap<String, f64> = sqlx::query_as::<_, (String, f64)>(
+        r#"SELECT "wordId", AVG(MIN("responseTime", ?)) / 1000.0 FROM "answer_records"
+           WHERE "wordBookId" = ? AND "responseTime" > 0
+           GROUP BY "wordId""#,
+    )
+    .bind(MAX_ANSWER_MS)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let band_config = MasteryBandConfig::default();
+    let mut candidates = Vec::new();
+    for word_id in word_ids {
+        let mut own =
+            review_events(pool, book_id, &word_id, CardDirection::Recognition, now_ms).await?;
+        let mut other =
+            review_events(pool, book_id, &word_id, CardDirection::Production, now_ms).await?;
+        let recall = linked_recall(&own, &other, &band_config);
+        if recall >= RESCUE_DUE_RECALL {
+            continue;
+        }
+        for event in own.iter_mut().chain(other.iter_mut()) {
+            event.age_days += 1.0;
+        }
+        candidates.push(RescueCandidate {
+            recall,
+            recall_tomorrow: linked_recall(&own, &other, &band_config),
+            seconds: seconds.get(&word_id).copied(),
+            word_id,
+        });
+    }
+    Ok(plan_rescue(candidates, config))
+}
+
+/// Days in a row with at least one answer, ending today (or yesterday when
+/// nothing has been answered yet today).
+async fn consecutive_days(
+    pool: &SqlitePool,
+    book_id: &str,
+    today: i64,
+) -> Result<i64, sqlx::Error> {
+    let days: Vec<i64> = sqlx::query_scalar(
+        r#"
+        SELECT "timestamp" / 86400000 AS "day"
+        FROM "answer_records"
+        WHERE "wordBookId" = ?
+        UNION
+        SELECT "day" FROM "answer_daily_summaries" WHERE "wordBookId" = ?
+        ORDER BY "day" DESC
+        "#,
+    )
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut expected = match days.first() {
+        Some(&day) if day == today || day == today - 1 => day,
+        _ => return Ok(0),
+    };
+    let mut streak = 0;
+    for day in days {
+        if day != expected {
+            break;
+        }
+        streak += 1;
+        expected -= 1;
+    }
+    Ok(streak)
+}
+
+/// `YYYY-MM-DD` of a day number counted from 1970-01-01.
+fn format_day(day: i64) -> String {
+    // Civil-from-days, proleptic Gregorian calendar.
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
@@ -0,0 +1,302 @@
+//! Concurrency stress tests for shared desktop state.
+//!
+//! Many tasks on a multi-threaded runtime interleave answer recording,
+//! cached recall and word lookups, context rebuilds and cache invalidation
+//! over one SQLite pool, and race manifest syncs against wordbook removal
+//! and garbage collection in the asset store. Debug builds also check the
+//! ranked mutexes in `lock_order`, so an out-of-order nesting panics here;
+//! a lock held across a pool wait shows up as a timeout instead of a hung
+//! test run.
+//!
+//! Scale with `DESKTOP_STRESS_ITERATIONS` (default 20 per task).
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use danci_native::mastery::MasteryBandConfig;
+use sqlx::{Row, SqlitePool};
+
+use crate::activation_cache::{ActivationCache, ActivationCacheConfig};
+use crate::assets::{AssetKind, AssetManifest, AssetStore, ManifestEntry};
+use crate::commands::context::ContextBuilder;
+use crate::db;
+use crate::stats::{self, CardDirection, LocalAnswer, LocalWordState};
+use crate::word_cache::{WordCache, WordCacheConfig};
+
+const TASKS: usize = 8;
+const BOOKS: usize = 3;
+const WORDS: usize = 6;
+const DEADLOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn iterations() -> usize {
+    std::env::var("DESKTOP_STRESS_ITERATIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn book(i: usize) -> String {
+    format!("book_{}", i % BOOKS)
+}
+
+fn word_ids() -> Vec<String> {
+    (0..WORDS).map(|w| format!("word_{w}")).collect()
+}
+
+async fn run_with_deadline<F: std::future::Future<Output = ()>>(name: &str, fut: F) {
+    if tokio::time::timeout(DEADLOCK_TIMEOUT, fut).await.is_err() {
+        panic!("{name}: operations did not finish in {DEADLOCK_TIMEOUT:?}, likely deadlock");
+    }
+}
+
+async fn seed_words(pool: &SqlitePool) {
+    for b in 0..BOOKS {
+        for id in word_ids() {
+            sqlx::query(
+                r#"INSERT INTO "words" ("wordBookId", "id", "word", "definition", "updatedAt")
+                   VALUES (?, ?, ?, '', 0)"#,
+            )
+            .bind(book(b))
+            .bind(&id)
+            .bind(&id)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+}
+
+fn progress(task: usize, i: usize, now: i64) -> (LocalAnswer, LocalWordState) {
+    let word_id = format!("word_{}", (task + i) % WORDS);
+    let direction = if i.is_multiple_of(2) {
+        CardDirection::Recognition
+    } else {
+        CardDirection::Production
+    };
+    let answer = LocalAnswer {
+        id: format!("answer_{task}_{i}"),
+        word_id: word_id.clone(),
+        word_book_id: book(task + i),
+        is_correct: !(task + i).is_multiple_of(3),
+        response_time: Some(1500),
+        dwell_time: None,
+        timestamp: now - 60_000 + i as i64,
+        session_id: Some(format!("session_{}", task % 2)),
+        direction,
+    };
+    let state = LocalWordState {
+        word_id,
+        word_book_id: book(task + i),
+        mastery_level: (i % 5) as i32,
+        state: "LEARNING".to_string(),
+        updated_at: now + i as i64,
+        direction,
+    };
+    (answer, state)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stress_progress_against_caches() {
+    let pool = db::open_in_memory().await;
+    seed_words(&pool).await;
+    let activations = Arc::new(ActivationCache::new(ActivationCacheConfig {
+        capacity: 16,
+        max_skew_ms: 0,
+    }));
+    let words = Arc::new(WordCache::new(WordCacheConfig {
+        capacity: 8,
+        max_age_secs: 600,
+    }));
+    let contexts = Arc::new(ContextBuilder::default());
+    let iterations = iterations();
+
+    run_with_deadline("caches", async {
+        let mut handles = Vec::new();
+        for task in 0..TASKS {
+            let pool = pool.clone();
+            let activations = Arc::clone(&activations);
+            let words = Arc::clone(&words);
+            let contexts = Arc::clone(&contexts);
+            handles.push(tokio::spawn(async move {
+                let ids = word_ids();
+                let config = MasteryBandConfig::default();
+                for i in 0..iterations {
+                    let now = now_ms();
+                    match (task + i) % 6 {
+                        0 => {
+                            let (answer, state) = progress(task, i, now);
+                            stats::record_progress(&pool, &[answer], &[state], now)
+                                .await
+                                .unwrap();
+                        }
+                        1 => {
+                            let recalls = activations
+                                .recalls(
+                                    &pool,
+                                    &book(i),
+                                    &ids,
+                                    CardDirection::Recognition,
+                                    &config,
+                                    now,
+                                )
+                                .await
+                                .unwrap();
+                            assert_eq!(recalls.len(), ids.len());
+                            assert!(recalls.iter().all(|r| (0.0..=1.0).contains(r)));
+                        }
+                        2 => {
+                            let rows = words.get_words(&pool, &book(i), &ids).await.unwrap();
+                            assert_eq!(rows.len(), ids.len());
+                            assert!(rows.iter().all(|row| row.word_book_id == book(i)));
+                        }
+                        3 => {
+                            let session = Some(format!("session_{}", task % 2));
+                            contexts.get(&pool, session, 480).await.unwrap();
+                        }
+                        4 => {
+                            activations.invalidate(Some(&book(i)));
+                            words.invalidate(None);
+                            contexts.invalidate();
+                        }
+                        _ => {
+                            words.configure(WordCacheConfig {
+                                capacity: 4 + i % 8,
+                                max_age_secs: 600,
+                            });
+                            activations.invalidate(None);
+                        }
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    })
+    .await;
+
+    let answers: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM "answer_records""#)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(answers > 0);
+}
+
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("danci-{name}-{}-{}", std::process::id(), now_ms()));
+        Self(path)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn manifest(book_index: usize, hashes: &[String]) -> AssetManifest {
+    AssetManifest {
+        word_book_id: book(book_index),
+        entries: word_ids()
+            .into_iter()
+            .enumerate()
+            .map(|(w, word_id)| ManifestEntry {
+                word_id,
+                kind: AssetKind::Audio,
+                // Nothing listens on the discard port, so an entry whose asset
+                // was collected fails fast instead of downloading.
+                url: format!("http://127.0.0.1:9/{book_index}/{w}.mp3"),
+                sha256: Some(hashes[(book_index + w) % hashes.len()].clone()),
+            })
+            .collect(),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stress_asset_sync_against_gc() {
+    let pool = db::open_in_memory().await;
+    let root = ScratchDir::new("asset-stress");
+    let store = AssetStore::new(root.0.clone(), pool.clone());
+    let mut hashes = Vec::new();
+    for i in 0..WORDS {
+        hashes.push(
+            store
+                .put(format!("clip {i}").as_bytes(), None)
+                .await
+                .unwrap(),
+        );
+    }
+    let hashes = Arc::new(hashes);
+    let iterations = iterations();
+
+    run_with_deadline("assets", async {
+        let mut handles = Vec::new();
+        for task in 0..TASKS {
+            let store = store.clone();
+            let hashes = Arc::clone(&hashes);
+            handles.push(tokio::spawn(async move {
+                for i in 0..iterations {
+                    match (task + i) % 4 {
+                        0 | 1 => {
+                            let report = store.sync_manifest(&manifest(i, &hashes)).await.unwrap();
+                            assert_eq!(report.reused as usize + report.failed.len(), WORDS);
+                        }
+                        2 => {
+                            store.release_wordbook(&book(i)).await.unwrap();
+                        }
+                        _ => {
+                            store.collect_garbage(Duration::ZERO).await.unwrap();
+                        }
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    })
+    .await;
+
+    // Every reference points at a recorded asset whose file survived, and
+    // reference counts match the references actually held.
+    let rows = sqlx::query(
+        r#"SELECT a."hash", a."refCount", COUNT(r."hash") AS "refs"
+           FROM "assets" a LEFT JOIN "asset_refs" r ON r."hash" = a."hash"
+           GROUP BY a."hash""#,
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    for row in rows {
+        let hash: String = row.get("hash");
+        let ref_count: i64 = row.get("refCount");
+        let refs: i64 = row.get("refs");
+        assert_eq!(ref_count, refs, "refCount of {hash}");
+        if refs > 0 {
+            assert!(
+                store.contains(&hash).await.unwrap(),
+                "{hash} missing on disk"
+            );
+        }
+    }
+    let dangling: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM "asset_refs" r
+           WHERE NOT EXISTS (SELECT 1 FROM "assets" a WHERE a."hash" = r."hash")"#,
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(dangling, 0);
+}
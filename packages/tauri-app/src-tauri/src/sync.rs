@@ -0,0 +1,647 @@
+//! Table-by-table sync of local learning data with the server.
+//!
+//! Each table syncs on its own, in chunks, and saves its cursor to
+//! `sync_cursors` after every chunk. A failure therefore loses at most the
+//! chunk in flight, and a retry resumes where that table stopped. A failing
+//! table does not stop the others: the caller gets one [`TableSyncResult`]
+//! per table, and progress is reported as each table starts, moves rows and
+//! finishes.
+//!
+//! - `operation_journal` is push-only: journaled operations (see
+//!   [`crate::journal`]) are replayed in order, one batch of a single kind
+//!   per request, to `POST /api/records/batch`, `POST /api/word-states/sync`
+//!   or `POST /api/evaluation/exposures`, each with the batch id as
+//!   `Idempotency-Key`. Replayed answers advance the `answer_records` cursor
+//!   (rowid), which bounds compaction.
+//! - `word_learning_states` syncs both ways: local words are walked in key
+//!   order and sent with their vector clocks (see [`crate::clocks`]) to
+//!   `POST /api/word-states/sync`, which keeps whichever copy has seen the
+//!   other's writes and merges concurrent ones; the result and its clock are
+//!   stored locally. The cursor is the last synced `[wordBookId, wordId]`
+//!   and is cleared once a pass completes. Words marked by the integrity
+//!   check are sent without their state, so only the server copy is taken;
+//!   they leave `state_redownloads` once refreshed, and if the server has no
+//!   state for them, the local one is dropped. Only recognition cards are
+//!   synced; the server keeps one state per word, so production cards stay
+//!   on this device.
+
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use danci_native::vclock::VectorClock;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use tauri_plugin_http::reqwest::Client;
+
+use crate::clocks::{self, ClockScope};
+use crate::journal::{self, Operation};
+
+const CHUNK: i64 = 100;
+/// `sync_cursors` row holding the highest pushed `answer_records` rowid.
+const ANSWER_CURSOR: &str = "answer_records";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("request failed: {0}")]
+    Http(String),
+    #[error("unexpected response: {0}")]
+    Decode(String),
+}
+
+/// Server address and session token, supplied by the webview.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SyncServer {
+    pub base_url: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTable {
+    OperationJournal,
+    WordLearningStates,
+}
+
+impl SyncTable {
+    /// Sync order: the journal is replayed before states are synced, so the
+    /// server states already reflect the answers in it.
+    pub const ALL: [SyncTable; 2] = [SyncTable::OperationJournal, SyncTable::WordLearningStates];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncTable::OperationJournal => "operation_journal",
+            SyncTable::WordLearningStates => "word_learning_states",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum SyncPhase {
+    Started,
+    Pushed,
+    Pulled,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum TableStatus {
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TableSyncResult {
+    pub table: SyncTable,
+    pub status: TableStatus,
+    pub pushed: u32,
+    pub pulled: u32,
+    pub error: Option<String>,
+}
+
+/// Receives `(table, phase, rows, error)` as the sync advances.
+pub type Progress<'a> = &'a (dyn Fn(SyncTable, SyncPhase, u32, Option<&str>) + Sync);
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+async fn load_cursor(pool: &SqlitePool, name: &str) -> Result<Option<String>, sqlx::Error> {
+    let cursor = sqlx::query_scalar::<_, Option<String>>(
+        r#"SELECT "cursor" FROM "sync_cursors" WHERE "table" = ?"#,
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+    Ok(cursor.flatten())
+}
+
+/// Highest `answer_records` rowid already pushed to the server.
+pub async fn pushed_answer_rowid(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    Ok(load_cursor(pool, ANSWER_CURSOR)
+        .await?
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(0))
+}
+
+/// Moves the answer cursor up to the highest rowid among `ids`.
+async fn advance_answer_cursor(
+    conn: &mut SqliteConnection,
+    ids: &[String],
+) -> Result<(), sqlx::Error> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(r#"SELECT MAX(rowid) FROM "answer_records" WHERE "id" IN ({placeholders})"#);
+    let mut query = sqlx::query_scalar::<_, Option<i64>>(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    let Some(rowid) = query.fetch_one(&mut *conn).await? else {
+        return Ok(());
+    };
+    sqlx::query(
+        r#"INSERT INTO "sync_cursors" ("table", "cursor", "status", "updatedAt")
+           VALUES (?, ?, 'completed', ?)
+           ON CONFLICT ("table") DO UPDATE SET "cursor" = excluded."cursor",
+               "updatedAt" = excluded."updatedAt"
+           WHERE CAST(COALESCE("sync_cursors"."cursor", '0') AS INTEGER) < ?"#,
+    )
+    .bind(ANSWER_CURSOR)
+    .bind(rowid.to_string())
+    .bind(now_ms())
+    .bind(rowid)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn save_cursor(
+    pool: &SqlitePool,
+    table: SyncTable,
+    cursor: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO "sync_cursors" ("table", "cursor", "status", "updatedAt")
+           VALUES (?, ?, 'running', ?)
+           ON CONFLICT ("table") DO UPDATE SET "cursor" = excluded."cursor",
+               "updatedAt" = excluded."updatedAt""#,
+    )
+    .bind(table.as_str())
+    .bind(cursor)
+    .bind(now_ms())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn save_status(
+    pool: &SqlitePool,
+    table: SyncTable,
+    status: TableStatus,
+    error: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let status = match status {
+        TableStatus::Completed => "completed",
+        TableStatus::Failed => "failed",
+    };
+    sqlx::query(
+        r#"INSERT INTO "sync_cursors" ("table", "status", "lastError", "updatedAt")
+           VALUES (?, ?, ?, ?)
+           ON CONFLICT ("table") DO UPDATE SET "status" = excluded."status",
+               "lastError" = excluded."lastError", "updatedAt" = excluded."updatedAt""#,
+    )
+    .bind(table.as_str())
+    .bind(status)
+    .bind(error)
+    .bind(now_ms())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Tables whose last sync failed, in sync order.
+pub async fn failed_tables(pool: &SqlitePool) -> Result<Vec<SyncTable>, sqlx::Error> {
+    let names = sqlx::query_scalar::<_, String>(
+        r#"SELECT "table" FROM "sync_cursors" WHERE "status" = 'failed'"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    let failed: Vec<SyncTable> = names.iter().filter_map(|n| SyncTable::parse(n)).collect();
+    Ok(SyncTable::ALL
+        .into_iter()
+        .filter(|t| failed.contains(t))
+        .collect())
+}
+
+async fn post_json<T: DeserializeOwned>(
+    client: &Client,
+    server: &SyncServer,
+    path: &str,
+    body: &serde_json::Value,
+    idempotency_key: Option<&str>,
+) -> Result<T, SyncError> {
+    let url = format!("{}{path}", server.base_url.trim_end_matches('/'));
+    let payload = serde_json::to_vec(body).map_err(|e| SyncError::Decode(e.to_string()))?;
+    let mut request = client
+        .post(url)
+        .bearer_auth(&server.token)
+        .header("content-type", "application/json");
+    if let Some(key) = idempotency_key {
+        request = request.header("idempotency-key", key);
+    }
+    let bytes = request
+        .body(payload)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| SyncError::Http(e.to_string()))?
+        .bytes()
+        .await
+        .map_err(|e| SyncError::Http(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| SyncError::Decode(e.to_string()))
+}
+
+/// Request for one journal batch: endpoint and body.
+fn replay_request(
+    device_id: &str,
+    ops: Vec<Operation>,
+) -> Result<(&'static str, serde_json::Value), SyncError> {
+    let (mut answers, mut states, mut exposures) = (Vec::new(), Vec::new(), Vec::new());
+    for op in ops {
+        match op {
+            Operation::RecordAnswer(a) => answers.push(serde_json::json!({
+                "wordId": a.word_id,
+                "isCorrect": a.is_correct,
+                "responseTime": a.response_time,
+                "dwellTime": a.dwell_time,
+                "timestamp": a.timestamp,
+                "sessionId": a.session_id,
+            })),
+            Operation::SetWordState { state, clock } => {
+                // The later write of a word carries the later clock.
+                states.retain(|s: &serde_json::Value| s["wordId"] != state.word_id.as_str());
+                states.push(serde_json::json!({
+                    "wordId": state.word_id,
+                    "state": state.state,
+                    "masteryLevel": state.mastery_level,
+                    "clock": clock,
+                }));
+            }
+            Operation::RecordExposure {
+                experiment_id,
+                variant_id,
+                exposed_at,
+                ..
+            } => exposures.push(serde_json::json!({
+                "experimentId": experiment_id,
+                "variantId": variant_id,
+                "exposedAt": exposed_at,
+            })),
+        }
+    }
+    match (answers.is_empty(), states.is_empty(), exposures.is_empty()) {
+        (false, true, true) => Ok((
+            "/api/records/batch",
+            serde_json::json!({ "records": answers }),
+        )),
+        (true, false, true) => Ok((
+            "/api/word-states/sync",
+            serde_json::json!({ "deviceId": device_id, "states": states }),
+        )),
+        (true, true, false) => Ok((
+            "/api/evaluation/exposures",
+            serde_json::json!({ "exposures": exposures }),
+        )),
+        _ => Err(SyncError::Decode(
+            "journal batch mixes operation kinds".into(),
+        )),
+    }
+}
+
+async fn replay_journal(
+    pool: &SqlitePool,
+    client: &Client,
+    server: &SyncServer,
+    progress: Progress<'_>,
+    pushed: &mut u32,
+) -> Result<(), SyncError> {
+    let table = SyncTable::OperationJournal;
+    let device_id = clocks::device_id(pool).await?;
+    while let Some(batch) = journal::next_batch(pool, CHUNK).await? {
+        let ops = batch
+            .entries
+            .iter()
+            .map(|e| {
+                journal::decode(e.schema_version, &e.payload)
+                    .map_err(|err| SyncError::Decode(format!("{}: {err}", e.op_id)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let answer_ids: Vec<String> = ops
+            .iter()
+            .filter_map(|op| match op {
+                Operation::RecordAnswer(a) => Some(a.id.clone()),
+                _ => None,
+            })
+            .collect();
+        let exposures: Vec<(String, String)> = ops
+            .iter()
+            .filter_map(|op| match op {
+                Operation::RecordExposure {
+                    user_id,
+                    experiment_id,
+                    ..
+                } => Some((user_id.clone(), experiment_id.clone())),
+                _ => None,
+            })
+            .collect();
+        let count = ops.len() as u32;
+        let (path, body) = replay_request(&device_id, ops)?;
+        post_json::<serde_json::Value>(client, server, path, &body, Some(&batch.id)).await?;
+
+        let mut tx = pool.begin().await?;
+        journal::mark_replayed(&mut tx, &batch.id, now_ms()).await?;
+        advance_answer_cursor(&mut tx, &answer_ids).await?;
+        for (user_id, experiment_id) in &exposures {
+            sqlx::query(
+                r#"DELETE FROM "pending_experiment_exposures"
+                   WHERE "userId" = ? AND "experimentId" = ?"#,
+            )
+            .bind(user_id)
+            .bind(experiment_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        *pushed += count;
+        progress(table, SyncPhase::Pushed, count, None);
+    }
+    journal::prune(pool, now_ms() - journal::RETENTION_MS).await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct SyncedStates {
+    data: Vec<SyncedState>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedState {
+    word_id: String,
+    state: Option<RemoteState>,
+    clock: VectorClock,
+    resolution: Resolution,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteState {
+    state: String,
+    mastery_level: i64,
+}
+
+#[derive(Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum Resolution {
+    Server,
+    Device,
+    Merged,
+}
+
+async fn sync_word_states(
+    pool: &SqlitePool,
+    client: &Client,
+    server: &SyncServer,
+    progress: Progress<'_>,
+    pushed: &mut u32,
+    pulled: &mut u32,
+) -> Result<(), SyncError> {
+    let table = SyncTable::WordLearningStates;
+    let device_id = clocks::device_id(pool).await?;
+    let (mut book, mut word) = load_cursor(pool, table.as_str())
+        .await?
+        .and_then(|c| serde_json::from_str::<(String, String)>(&c).ok())
+        .unwrap_or_default();
+    loop {
+        let rows = sqlx::query(
+            r#"SELECT s."wordBookId", s."wordId", s."masteryLevel", s."state",
+                      EXISTS (
+                          SELECT 1 FROM "state_redownloads" r
+                          WHERE r."wordBookId" = s."wordBookId" AND r."wordId" = s."wordId"
+                      ) AS "redownload"
+               FROM "word_learning_states" s
+               WHERE s."direction" = 'recognition' AND (s."wordBookId", s."wordId") > (?, ?)
+               ORDER BY s."wordBookId", s."wordId" LIMIT ?"#,
+        )
+        .bind(&book)
+        .bind(&word)
+        .bind(CHUNK)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else {
+            save_cursor(pool, table, None).await?;
+            return Ok(());
+        };
+        let last: (String, String) = (last.get("wordBookId"), last.get("wordId"));
+
+        // A word kept in several books is sent once; the server holds one
+        // state per word.
+        let mut conn = pool.acquire().await?;
+        let mut states = Vec::with_capacity(rows.len());
+        let mut seen = HashSet::new();
+        for row in &rows {
+            let word_id: String = row.get("wordId");
+            if !seen.insert(word_id.clone()) {
+                continue;
+            }
+            let clock = clocks::load(&mut conn, ClockScope::WordState, &word_id).await?;
+            // States doubted by the integrity check are only pulled.
+            let state = if row.get::<bool, _>("redownload") {
+                serde_json::json!({ "wordId": word_id, "clock": clock })
+            } else {
+                serde_json::json!({
+                    "wordId": word_id,
+                    "state": row.get::<String, _>("state"),
+                    "masteryLevel": row.get::<i64, _>("masteryLevel"),
+                    "clock": clock,
+                })
+            };
+            states.push(state);
+        }
+        drop(conn);
+        let remote: SyncedStates = post_json(
+            client,
+            server,
+            "/api/word-states/sync",
+            &serde_json::json!({ "deviceId": device_id, "states": states }),
+            None,
+        )
+        .await?;
+
+        let (mut accepted, mut changed) = (0u32, 0u32);
+        let mut tx = pool.begin().await?;
+        for item in remote.data {
+            clocks::adopt(&mut tx, ClockScope::WordState, &item.word_id, &item.clock).await?;
+            let Some(state) = item.state else {
+                // A state the integrity check doubted and the server does not
+                // have is dropped.
+                sqlx::query(
+                    r#"DELETE FROM "word_learning_states"
+                       WHERE "wordId" = ? AND "direction" = 'recognition' AND EXISTS (
+                           SELECT 1 FROM "state_redownloads" r
+                           WHERE r."wordBookId" = "word_learning_states"."wordBookId"
+                             AND r."wordId" = "word_learning_states"."wordId"
+                       )"#,
+                )
+                .bind(&item.word_id)
+                .execute(&mut *tx)
+                .await?;
+                continue;
+            };
+            if item.resolution == Resolution::Device {
+                accepted += 1;
+                continue;
+            }
+            let result = sqlx::query(
+                r#"UPDATE "word_learning_states"
+                   SET "masteryLevel" = ?, "state" = ?, "updatedAt" = ?
+                   WHERE "wordId" = ? AND "direction" = 'recognition'
+                     AND ("masteryLevel" <> ? OR "state" <> ?)"#,
+            )
+            .bind(state.mastery_level)
+            .bind(&state.state)
+            .bind(now_ms())
+            .bind(&item.word_id)
+            .bind(state.mastery_level)
+            .bind(&state.state)
+            .execute(&mut *tx)
+            .await?;
+            changed += result.rows_affected() as u32;
+        }
+        for row in &rows {
+            sqlx::query(
+                r#"DELETE FROM "state_redownloads" WHERE "wordBookId" = ? AND "wordId" = ?"#,
+            )
+            .bind(row.get::<String, _>("wordBookId"))
+            .bind(row.get::<String, _>("wordId"))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        (book, word) = last;
+        let cursor = serde_json::to_string(&(&book, &word)).unwrap_or_default();
+        save_cursor(pool, table, Some(&cursor)).await?;
+        *pushed += accepted;
+        *pulled += changed;
+        progress(table, SyncPhase::Pushed, accepted, None);
+        progress(table, SyncPhase::Pulled, changed, None);
+    }
+}
+
+/// Syncs `tables` in order, continuing past failures. Rows moved before a
+/// table failed are still counted in its result.
+pub async fn sync_tables(
+    pool: &SqlitePool,
+    server: &SyncServer,
+    tables: &[SyncTable],
+    progress: Progress<'_>,
+) -> Vec<TableSyncResult> {
+    let client = Client::new();
+    let mut results = Vec::with_capacity(tables.len());
+    for &table in tables {
+        progress(table, SyncPhase::Started, 0, None);
+        let (mut pushed, mut pulled) = (0, 0);
+        let outcome = match table {
+            SyncTable::OperationJournal => {
+                replay_journal(pool, &client, server, progress, &mut pushed).await
+            }
+            SyncTable::WordLearningStates => {
+                sync_word_states(pool, &client, server, progress, &mut pushed, &mut pulled).await
+            }
+        };
+        let (status, error) = match outcome {
+            Ok(()) => (TableStatus::Completed, None),
+            Err(e) => (TableStatus::Failed, Some(e.to_string())),
+        };
+        if let Err(e) = save_status(pool, table, status, error.as_deref()).await {
+            log::warn!("failed to record sync status for {}: {e}", table.as_str());
+        }
+        match status {
+            TableStatus::Completed => progress(table, SyncPhase::Completed, pushed + pulled, None),
+            TableStatus::Failed => progress(table, SyncPhase::Failed, 0, error.as_deref()),
+        }
+        results.push(TableSyncResult {
+            table,
+            status,
+            pushed,
+            pulled,
+            error,
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    async fn insert_answers(pool: &SqlitePool, ids: &[&str]) {
+        for (i, id) in ids.iter().enumerate() {
+            sqlx::query(
+                r#"INSERT INTO "answer_records" ("id", "wordId", "wordBookId", "isCorrect", "timestamp")
+                   VALUES (?, 'w', 'b', 1, ?)"#,
+            )
+            .bind(id)
+            .bind(i as i64)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn answer_cursor_only_moves_forward() {
+        let pool = db::open_in_memory().await;
+        insert_answers(&pool, &["a1", "a2", "a3"]).await;
+        assert_eq!(pushed_answer_rowid(&pool).await.unwrap(), 0);
+
+        for (ids, expected) in [
+            (vec!["a2"], 2),
+            (vec!["a1"], 2),
+            (vec!["missing"], 2),
+            (vec!["a1", "a3"], 3),
+        ] {
+            let ids: Vec<String> = ids.into_iter().map(String::from).collect();
+            let mut conn = pool.acquire().await.unwrap();
+            advance_answer_cursor(&mut conn, &ids).await.unwrap();
+            drop(conn);
+            assert_eq!(pushed_answer_rowid(&pool).await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn status_changes_keep_the_saved_cursor() {
+        let pool = db::open_in_memory().await;
+        let table = SyncTable::WordLearningStates;
+        save_cursor(&pool, table, Some(r#"["b","w"]"#))
+            .await
+            .unwrap();
+        save_status(&pool, table, TableStatus::Failed, Some("offline"))
+            .await
+            .unwrap();
+        assert_eq!(failed_tables(&pool).await.unwrap(), vec![table]);
+        assert_eq!(
+            load_cursor(&pool, table.as_str()).await.unwrap().as_deref(),
+            Some(r#"["b","w"]"#)
+        );
+
+        save_status(&pool, table, TableStatus::Completed, None)
+            .await
+            .unwrap();
+        assert!(failed_tables(&pool).await.unwrap().is_empty());
+    }
+}
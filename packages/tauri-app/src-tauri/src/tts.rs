@@ -0,0 +1,435 @@
+//! Text-to-speech through the platform's own speech engine.
+//!
+//! macOS uses `say`, Linux `espeak-ng` (or `espeak`) and Windows the
+//! System.Speech synthesizer via PowerShell. Each utterance may carry its
+//! own voice, rate, pitch and language; whatever it leaves out comes from
+//! the learner's default for that language, then from the engine default.
+//! Rate and pitch are multipliers where 1.0 is the engine's normal speed or
+//! pitch.
+
+use std::collections::HashMap;
+use std::process::{Child, Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::lock_order::{LockRank, RankedMutex};
+
+const MIN_FACTOR: f32 = 0.5;
+const MAX_FACTOR: f32 = 2.0;
+/// Words per minute `say` and `espeak` use at rate 1.0.
+const BASE_WPM: f32 = 175.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TtsError {
+    #[error("no speech engine available on this platform")]
+    Unsupported,
+    #[error("nothing to speak")]
+    EmptyText,
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("speech engine failed: {0}")]
+    Engine(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Say,
+    Espeak,
+    Sapi,
+}
+
+impl Backend {
+    pub fn detect() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Backend::Say)
+        } else if cfg!(target_os = "windows") {
+            Some(Backend::Sapi)
+        } else if cfg!(target_os = "linux") {
+            Some(Backend::Espeak)
+        } else {
+            None
+        }
+    }
+
+    fn supports_pitch(self) -> bool {
+        !matches!(self, Backend::Say)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceInfo {
+    /// Value to pass as an utterance's `voice`.
+    pub id: String,
+    pub name: String,
+    /// BCP 47 tag, e.g. `en-US`, when the engine reports one.
+    pub language: Option<String>,
+    pub backend: Backend,
+    pub supports_rate: bool,
+    pub supports_pitch: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", default)]
+pub struct VoicePreference {
+    pub voice: Option<String>,
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+impl Default for VoicePreference {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            rate: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+/// Per-language defaults, keyed by normalized language tag (`en-us`, `ja`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", default)]
+pub struct TtsPreferences {
+    pub languages: HashMap<String, VoicePreference>,
+}
+
+impl TtsPreferences {
+    /// The default for `language`: the exact tag first, then its primary
+    /// subtag, so an `en` preference also covers `en-GB`.
+    pub fn for_language(&self, language: Option<&str>) -> VoicePreference {
+        let Some(tag) = language.map(normalize_language) else {
+            return VoicePreference::default();
+        };
+        let primary = tag.split('-').next().unwrap_or_default();
+        self.languages
+            .get(&tag)
+            .or_else(|| self.languages.get(primary))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set(&mut self, language: &str, preference: Option<VoicePreference>) {
+        let tag = normalize_language(language);
+        match preference {
+            Some(preference) => {
+                self.languages.insert(tag, preference);
+            }
+            None => {
+                self.languages.remove(&tag);
+            }
+        }
+    }
+
+    pub fn resolve(&self, utterance: Utterance) -> Result<ResolvedUtterance, TtsError> {
+        if utterance.text.trim().is_empty() {
+            return Err(TtsError::EmptyText);
+        }
+        let default = self.for_language(utterance.language.as_deref());
+        Ok(ResolvedUtterance {
+            text: utterance.text,
+            voice: utterance.voice.or(default.voice),
+            rate: utterance
+                .rate
+                .unwrap_or(default.rate)
+                .clamp(MIN_FACTOR, MAX_FACTOR),
+            pitch: utterance
+                .pitch
+                .unwrap_or(default.pitch)
+                .clamp(MIN_FACTOR, MAX_FACTOR),
+            language: utterance.language,
+        })
+    }
+}
+
+/// One request to speak; unset fields fall back to the language default.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Utterance {
+    pub text: String,
+    #[serde(default)]
+    pub voice: Option<String>,
+    #[serde(default)]
+    pub rate: Option<f32>,
+    #[serde(default)]
+    pub pitch: Option<f32>,
+    /// Language hint used to pick a default and, without a voice, a voice.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedUtterance {
+    pub text: String,
+    pub voice: Option<String>,
+    pub rate: f32,
+    pub pitch: f32,
+    pub language: Option<String>,
+}
+
+/// `en_US` / `EN-us` -> `en-us`.
+pub fn normalize_language(tag: &str) -> String {
+    tag.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+/// The playing utterance. A new utterance interrupts the previous one.
+pub struct Speaker {
+    current: RankedMutex<Option<Child>>,
+}
+
+impl Default for Speaker {
+    fn default() -> Self {
+        Self {
+            current: RankedMutex::with_default(LockRank::Speaker),
+        }
+    }
+}
+
+impl Speaker {
+    pub fn speak(&self, utterance: &ResolvedUtterance) -> Result<(), TtsError> {
+        let backend = Backend::detect().ok_or(TtsError::Unsupported)?;
+        let voice = match (&utterance.voice, &utterance.language) {
+            (Some(voice), _) => Some(voice.clone()),
+            (None, Some(language)) => voice_for_language(backend, language),
+            (None, None) => None,
+        };
+        let mut command = speak_command(backend, utterance, voice.as_deref());
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut current = self
+            .current
+            .lock()
+            .map_err(|e| TtsError::Engine(e.to_string()))?;
+        if let Some(mut previous) = current.replace(child) {
+            let _ = previous.kill();
+            let _ = previous.wait();
+        }
+        Ok(())
+    }
+
+    /// Stops the playing utterance; false when nothing was playing.
+    pub fn stop(&self) -> bool {
+        let Ok(mut current) = self.current.lock() else {
+            return false;
+        };
+        match current.take() {
+            Some(mut child) => {
+                let playing = matches!(child.try_wait(), Ok(None));
+                let _ = child.kill();
+                let _ = child.wait();
+                playing
+            }
+            None => false,
+        }
+    }
+}
+
+fn speak_command(backend: Backend, utterance: &ResolvedUtterance, voice: Option<&str>) -> Command {
+    let wpm = (BASE_WPM * utterance.rate).round() as u32;
+    match backend {
+        Backend::Say => {
+            let mut command = Command::new("say");
+            if let Some(voice) = voice {
+                command.args(["-v", voice]);
+            }
+            command.args(["-r", &wpm.to_string(), "--", &utterance.text]);
+            command
+        }
+        Backend::Espeak => {
+            let mut command = Command::new(espeak_binary());
+            if let Some(voice) = voice {
+                command.args(["-v", voice]);
+            }
+            let pitch = (50.0 * utterance.pitch).round().clamp(0.0, 99.0) as u32;
+            command.args([
+                "-s",
+                &wpm.to_string(),
+                "-p",
+                &pitch.to_string(),
+                "--",
+                &utterance.text,
+            ]);
+            command
+        }
+        Backend::Sapi => {
+            // The text travels in an environment variable so it never has
+            // to be quoted for PowerShell.
+            let mut command = powershell(
+                "Add-Type -AssemblyName System.Speech; \
+                 $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                 if ($env:DANCI_TTS_VOICE) { $s.SelectVoice($env:DANCI_TTS_VOICE) }; \
+                 $s.SpeakSsml($env:DANCI_TTS_SSML)",
+            );
+            command
+                .env("DANCI_TTS_VOICE", voice.unwrap_or_default())
+                .env("DANCI_TTS_SSML", ssml(utterance));
+            command
+        }
+    }
+}
+
+fn ssml(utterance: &ResolvedUtterance) -> String {
+    let percent = |factor: f32| ((factor - 1.0) * 100.0).round() as i32;
+    format!(
+        "<speak version=\"1.0\" xmlns=\"http://www.w3.org/2001/10/synthesis\" xml:lang=\"{}\">\
+         <prosody rate=\"{:+}%\" pitch=\"{:+}%\">{}</prosody></speak>",
+        escape_xml(utterance.language.as_deref().unwrap_or("en-US")),
+        percent(utterance.rate),
+        percent(utterance.pitch),
+        escape_xml(&utterance.text)
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn powershell(script: &str) -> Command {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", script]);
+    command
+}
+
+fn espeak_binary() -> &'static str {
+    let has_ng = Command::new("espeak-ng")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok();
+    if has_ng {
+        "espeak-ng"
+    } else {
+        "espeak"
+    }
+}
+
+/// Voices of the platform engine.
+pub fn list_voices() -> Result<Vec<VoiceInfo>, TtsError> {
+    let backend = Backend::detect().ok_or(TtsError::Unsupported)?;
+    let output = match backend {
+        Backend::Say => Command::new("say").args(["-v", "?"]).output()?,
+        Backend::Espeak => Command::new(espeak_binary()).arg("--voices").output()?,
+        Backend::Sapi => powershell(
+            "Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | \
+             ForEach-Object { $_.VoiceInfo.Name + '|' + $_.VoiceInfo.Culture.Name }",
+        )
+        .output()?,
+    };
+    if !output.status.success() {
+        return Err(TtsError::Engine(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let voices = match backend {
+        Backend::Say => parse_say_voices(&stdout),
+        Backend::Espeak => parse_espeak_voices(&stdout),
+        Backend::Sapi => parse_sapi_voices(&stdout),
+    };
+    Ok(voices
+        .into_iter()
+        .map(|(id, name, language)| VoiceInfo {
+            id,
+            name,
+            language,
+            backend,
+            supports_rate: true,
+            supports_pitch: backend.supports_pitch(),
+        })
+        .collect())
+}
+
+/// First voice for `language`, matching the full tag before the primary
+/// subtag. espeak voices are named after their language, so the tag itself
+/// is used there.
+fn voice_for_language(backend: Backend, language: &str) -> Option<String> {
+    if backend == Backend::Espeak {
+        return Some(normalize_language(language));
+    }
+    let tag = normalize_language(language);
+    let primary = tag.split('-').next().unwrap_or_default().to_string();
+    let voices = list_voices().ok()?;
+    let language_of = |v: &VoiceInfo| v.language.as_deref().map(normalize_language);
+    voices
+        .iter()
+        .find(|v| language_of(v).as_deref() == Some(tag.as_str()))
+        .or_else(|| {
+            voices.iter().find(|v| {
+                language_of(v).is_some_and(|l| l.split('-').next() == Some(primary.as_str()))
+            })
+        })
+        .map(|v| v.id.clone())
+}
+
+type ParsedVoice = (String, String, Option<String>);
+
+/// `say -v ?` lines: `Alex                en_US    # Most people recognize me…`.
+fn parse_say_voices(output: &str) -> Vec<ParsedVoice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let head = line.split('#').next()?.trim_end();
+            let (name, locale) = head.rsplit_once(char::is_whitespace)?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| {
+                (
+                    name.to_string(),
+                    name.to_string(),
+                    Some(locale.replace('_', "-")),
+                )
+            })
+        })
+        .collect()
+}
+
+/// `espeak --voices` rows: `Pty Language Age/Gender VoiceName File …`.
+fn parse_espeak_voices(output: &str) -> Vec<ParsedVoice> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let _priority = columns.next()?;
+            let language = columns.next()?;
+            let _age_gender = columns.next()?;
+            let name = columns.next()?;
+            Some((
+                language.to_string(),
+                name.replace('_', " "),
+                Some(language.to_string()),
+            ))
+        })
+        .collect()
+}
+
+/// `Name|Culture` lines printed by the PowerShell listing.
+fn parse_sapi_voices(output: &str) -> Vec<ParsedVoice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, culture) = line.trim().split_once('|')?;
+            let culture = culture.trim();
+            Some((
+                name.to_string(),
+                name.to_string(),
+                (!culture.is_empty()).then(|| culture.to_string()),
+            ))
+        })
+        .collect()
+}
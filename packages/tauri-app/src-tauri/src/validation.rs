@@ -0,0 +1,261 @@
+//! Validation of algorithm state handed to commands by the webview.
+//!
+//! Controller state, configs and per-language parameters round-trip through
+//! the UI, so a stale or hand-edited payload can reach the native algorithms.
+//! Commands run such payloads through a [`StateValidator`] before use:
+//! non-finite numbers and inconsistent settings are rejected, out-of-range
+//! values are clamped and reported, and any rejection fails the command with
+//! a [`StateError::InvalidState`] listing every problem found.
+
+use danci_native::batch::{BatchControllerConfig, BatchControllerState, BatchSignals};
+use danci_native::language::LanguageParams;
+use serde::Serialize;
+
+/// Largest batch the controller may be configured for.
+const MAX_BATCH_SIZE: u32 = 100;
+/// Upper bound for controller gains and penalty weights.
+const MAX_GAIN: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    /// Path of the offending value, e.g. `config.targetAccuracy`.
+    pub field: String,
+    pub message: String,
+    /// Value used instead; absent when the value was rejected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clamped_to: Option<f64>,
+}
+
+/// Error payload of commands that accept algorithm state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(tag = "code", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StateError {
+    InvalidState {
+        message: String,
+        issues: Vec<ValidationIssue>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for StateError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+/// Collects problems for one payload; see the module docs.
+#[derive(Debug, Default)]
+pub struct StateValidator {
+    rejected: Vec<ValidationIssue>,
+    clamped: Vec<ValidationIssue>,
+}
+
+impl StateValidator {
+    pub fn reject(&mut self, field: &str, message: impl Into<String>) {
+        self.rejected.push(ValidationIssue {
+            field: field.to_string(),
+            message: message.into(),
+            clamped_to: None,
+        });
+    }
+
+    /// Rejects NaN and infinities; returns whether the value is usable.
+    pub fn finite(&mut self, field: &str, value: f64) -> bool {
+        if value.is_finite() {
+            true
+        } else {
+            self.reject(field, format!("must be a finite number, got {value}"));
+            false
+        }
+    }
+
+    /// Clamps `value` into `[min, max]`, rejecting non-finite values.
+    pub fn clamp(&mut self, field: &str, value: &mut f64, min: f64, max: f64) {
+        if !self.finite(field, *value) {
+            return;
+        }
+        let clamped = value.clamp(min, max);
+        if clamped != *value {
+            self.clamped.push(ValidationIssue {
+                field: field.to_string(),
+                message: format!("{value} is outside [{min}, {max}]"),
+                clamped_to: Some(clamped),
+            });
+            *value = clamped;
+        }
+    }
+
+    pub fn clamp_u32(&mut self, field: &str, value: &mut u32, min: u32, max: u32) {
+        let clamped = (*value).clamp(min, max);
+        if clamped != *value {
+            self.clamped.push(ValidationIssue {
+                field: field.to_string(),
+                message: format!("{value} is outside [{min}, {max}]"),
+                clamped_to: Some(clamped as f64),
+            });
+            *value = clamped;
+        }
+    }
+
+    /// Fails when anything was rejected; otherwise returns the clamped values
+    /// so the caller can report them.
+    pub fn finish(self, what: &str) -> Result<Vec<ValidationIssue>, StateError> {
+        if self.rejected.is_empty() {
+            return Ok(self.clamped);
+        }
+        let message = format!("invalid {what}: {} problem(s)", self.rejected.len());
+        let mut issues = self.rejected;
+        issues.extend(self.clamped);
+        Err(StateError::InvalidState { message, issues })
+    }
+}
+
+/// Logs clamped values of an accepted payload.
+pub fn report_clamped(command: &str, clamped: &[ValidationIssue]) {
+    for issue in clamped {
+        log::warn!(
+            "{command}: clamped {} ({}) to {}",
+            issue.field,
+            issue.message,
+            issue.clamped_to.unwrap_or_default()
+        );
+    }
+}
+
+pub fn batch_config(v: &mut StateValidator, config: &mut BatchControllerConfig) {
+    v.clamp_u32("config.minSize", &mut config.min_size, 1, MAX_BATCH_SIZE);
+    v.clamp_u32("config.maxSize", &mut config.max_size, 1, MAX_BATCH_SIZE);
+    if config.min_size > config.max_size {
+        v.reject(
+            "config.minSize",
+            format!(
+                "minSize {} exceeds maxSize {}",
+                config.min_size, config.max_size
+            ),
+        );
+    }
+    v.clamp(
+        "config.targetAccuracy",
+        &mut config.target_accuracy,
+        0.0,
+        1.0,
+    );
+    v.clamp("config.kp", &mut config.kp, 0.0, MAX_GAIN);
+    v.clamp("config.ki", &mut config.ki, 0.0, MAX_GAIN);
+    v.clamp(
+        "config.latencyWeight",
+        &mut config.latency_weight,
+        0.0,
+        MAX_GAIN,
+    );
+    v.clamp(
+        "config.fatigueWeight",
+        &mut config.fatigue_weight,
+        0.0,
+        MAX_GAIN,
+    );
+    v.clamp(
+        "config.integralLimit",
+        &mut config.integral_limit,
+        0.0,
+        MAX_GAIN,
+    );
+}
+
+/// `config` should already be validated; the integral and last size are
+/// kept within its limits.
+pub fn batch_state(
+    v: &mut StateValidator,
+    state: &mut BatchControllerState,
+    config: &BatchControllerConfig,
+) {
+    v.clamp_u32("state.baseSize", &mut state.base_size, 1, MAX_BATCH_SIZE);
+    let limit = config.integral_limit.max(0.0);
+    v.clamp("state.integral", &mut state.integral, -limit, limit);
+    if config.min_size <= config.max_size {
+        v.clamp_u32(
+            "state.lastSize",
+            &mut state.last_size,
+            config.min_size,
+            config.max_size,
+        );
+    }
+}
+
+pub fn batch_signals(v: &mut StateValidator, signals: &mut BatchSignals) {
+    v.clamp(
+        "signals.rollingAccuracy",
+        &mut signals.rolling_accuracy,
+        0.0,
+        1.0,
+    );
+    v.clamp(
+        "signals.latencyDrift",
+        &mut signals.latency_drift,
+        -1.0,
+        10.0,
+    );
+    v.clamp("signals.fatigue", &mut signals.fatigue, 0.0, 1.0);
+}
+
+/// Clamps each value into the range `LanguageParams::validate` accepts, then
+/// runs it for the rules clamping cannot fix (difficulty weights summing to 1).
+pub fn language_params(v: &mut StateValidator, params: &mut LanguageParams) {
+    v.clamp(
+        "languageParams.decayRate",
+        &mut params.decay_rate,
+        0.25,
+        4.0,
+    );
+    v.clamp(
+        "languageParams.masteryThresholdScale",
+        &mut params.mastery_threshold_scale,
+        0.5,
+        1.5,
+    );
+    let d = &mut params.difficulty;
+    v.clamp(
+        "languageParams.difficulty.lengthWeight",
+        &mut d.length_weight,
+        0.0,
+        1.0,
+    );
+    v.clamp(
+        "languageParams.difficulty.meaningWeight",
+        &mut d.meaning_weight,
+        0.0,
+        1.0,
+    );
+    v.clamp(
+        "languageParams.difficulty.lengthNorm",
+        &mut d.length_norm,
+        1.0,
+        f64::MAX,
+    );
+    v.clamp(
+        "languageParams.difficulty.meaningNorm",
+        &mut d.meaning_norm,
+        1.0,
+        f64::MAX,
+    );
+    let all_finite = [
+        params.decay_rate,
+        params.mastery_threshold_scale,
+        params.difficulty.length_weight,
+        params.difficulty.meaning_weight,
+        params.difficulty.length_norm,
+        params.difficulty.meaning_norm,
+    ]
+    .iter()
+    .all(|x| x.is_finite());
+    if all_finite {
+        if let Err(message) = params.validate() {
+            v.reject("languageParams", message);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! In-memory LRU over rows of the local `words` table.
+//!
+//! Question rendering looks words up one by one, so rows are cached by
+//! `(wordBookId, id)` instead of going to SQLite every time. Staleness is
+//! bounded twice over: an entry expires `max_age` after it was loaded, and
+//! sync events on the bus drop the affected wordbook's entries (or all of
+//! them when the event names no wordbook).
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::events::{AppEvent, EventBus, SyncKind};
+use crate::lock_order::{LockRank, RankedMutex};
+use crate::sync::SyncPhase;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WordRow {
+    pub id: String,
+    pub word_book_id: String,
+    pub word: String,
+    pub phonetic: Option<String>,
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WordCacheConfig {
+    /// Maximum number of cached rows; 0 disables the cache.
+    pub capacity: usize,
+    /// Rows older than this are reloaded even without a sync event.
+    pub max_age_secs: u64,
+}
+
+impl Default for WordCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 2048,
+            max_age_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WordCacheStats {
+    pub capacity: usize,
+    pub max_age_secs: u64,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Misses caused by an entry outliving `max_age`; included in `misses`.
+    pub expired: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+    /// Hits over lookups, or `None` before the first lookup.
+    pub hit_rate: Option<f64>,
+}
+
+type Key = (String, String);
+
+struct Entry {
+    row: WordRow,
+    loaded_at: Instant,
+    recency: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    config: WordCacheConfig,
+    entries: HashMap<Key, Entry>,
+    /// Recency stamp to key; the first entry is the least recently used.
+    order: BTreeMap<u64, Key>,
+    next_recency: u64,
+    stats: WordCacheStats,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &Key) {
+        let recency = self.next_recency;
+        self.next_recency += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.recency);
+            entry.recency = recency;
+            self.order.insert(recency, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.recency);
+        }
+    }
+
+    fn evict_to_capacity(&mut self) {
+        while self.entries.len() > self.config.capacity {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+pub struct WordCache {
+    lru: RankedMutex<Lru>,
+}
+
+impl Default for WordCache {
+    fn default() -> Self {
+        Self {
+            lru: RankedMutex::with_default(LockRank::WordCache),
+        }
+    }
+}
+
+impl WordCache {
+    pub fn new(config: WordCacheConfig) -> Self {
+        let cache = Self::default();
+        cache.configure(config);
+        cache
+    }
+
+    /// Applies a new capacity and age bound, evicting down to the capacity.
+    pub fn configure(&self, config: WordCacheConfig) {
+        if let Ok(mut lru) = self.lru.lock() {
+            lru.config = config;
+            lru.evict_to_capacity();
+        }
+    }
+
+    pub fn config(&self) -> WordCacheConfig {
+        self.lru.lock().map(|lru| lru.config).unwrap_or_default()
+    }
+
+    pub fn get(&self, word_book_id: &str, word_id: &str) -> Option<WordRow> {
+        let mut lru = self.lru.lock().ok()?;
+        let key = (word_book_id.to_string(), word_id.to_string());
+        let max_age = Duration::from_secs(lru.config.max_age_secs);
+        let fresh = match lru.entries.get(&key) {
+            None => None,
+            Some(entry) => Some(entry.loaded_at.elapsed() <= max_age),
+        };
+        match fresh {
+            Some(true) => {
+                lru.stats.hits += 1;
+                lru.touch(&key);
+                lru.entries.get(&key).map(|entry| entry.row.clone())
+            }
+            Some(false) => {
+                lru.stats.misses += 1;
+                lru.stats.expired += 1;
+                lru.remove(&key);
+                None
+            }
+            None => {
+                lru.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, row: WordRow) {
+        let Ok(mut lru) = self.lru.lock() else {
+            return;
+        };
+        if lru.config.capacity == 0 {
+            return;
+        }
+        let key = (row.word_book_id.clone(), row.id.clone());
+        lru.remove(&key);
+        let recency = lru.next_recency;
+        lru.next_recency += 1;
+        lru.order.insert(recency, key.clone());
+        lru.entries.insert(
+            key,
+            Entry {
+                row,
+                loaded_at: Instant::now(),
+                recency,
+            },
+        );
+        lru.evict_to_capacity();
+    }
+
+    /// Drops one wordbook's rows, or every row when `word_book_id` is `None`.
+    pub fn invalidate(&self, word_book_id: Option<&str>) {
+        let Ok(mut lru) = self.lru.lock() else {
+            return;
+        };
+        lru.stats.invalidations += 1;
+        match word_book_id {
+            None => {
+                lru.entries.clear();
+                lru.order.clear();
+            }
+            Some(book) => {
+                let keys: Vec<Key> = lru
+                    .entries
+                    .keys()
+                    .filter(|(b, _)| b == book)
+                    .cloned()
+                    .collect();
+                for key in &keys {
+                    lru.remove(key);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> WordCacheStats {
+        let Ok(lru) = self.lru.lock() else {
+            return WordCacheStats::default();
+        };
+        let lookups = lru.stats.hits + lru.stats.misses;
+        WordCacheStats {
+            capacity: lru.config.capacity,
+            max_age_secs: lru.config.max_age_secs,
+            entries: lru.entries.len(),
+            hit_rate: (lookups > 0).then(|| lru.stats.hits as f64 / lookups as f64),
+            ..lru.stats.clone()
+        }
+    }
+
+    /// Rows for `word_ids` in request order, loading misses from SQLite in
+    /// one query. Ids with no local row are left out.
+    pub async fn get_words(
+        &self,
+        pool: &SqlitePool,
+        word_book_id: &str,
+        word_ids: &[String],
+    ) -> Result<Vec<WordRow>, sqlx::Error> {
+        let mut found: HashMap<&str, WordRow> = HashMap::new();
+        let mut missing: Vec<&str> = Vec::new();
+        for id in word_ids {
+            match self.get(word_book_id, id) {
+                Some(row) => {
+                    found.insert(id, row);
+                }
+                None => missing.push(id),
+            }
+        }
+
+        if !missing.is_empty() {
+            let placeholders = vec!["?"; missing.len()].join(", ");
+            let sql = format!(
+                r#"SELECT "id", "wordBookId", "word", "phonetic", "definition"
+                   FROM "words" WHERE "wordBookId" = ? AND "id" IN ({placeholders})"#
+            );
+            let mut query = sqlx::query(&sql).bind(word_book_id);
+            for id in &missing {
+                query = query.bind(*id);
+            }
+            for row in query.fetch_all(pool).await? {
+                let row = WordRow {
+                    id: row.try_get("id")?,
+                    word_book_id: row.try_get("wordBookId")?,
+                    word: row.try_get("word")?,
+                    phonetic: row.try_get("phonetic")?,
+                    definition: row.try_get("definition")?,
+                };
+                self.insert(row.clone());
+                if let Some(id) = missing.iter().find(|id| **id == row.id) {
+                    found.insert(*id, row);
+                }
+            }
+        }
+
+        Ok(word_ids
+            .iter()
+            .filter_map(|id| found.remove(id.as_str()))
+            .collect())
+    }
+}
+
+/// Registers this module's event handlers. Word content only changes through
+/// asset and learning-data syncs, so other events leave the cache alone.
+pub fn subscribe(bus: &EventBus, cache: Arc<WordCache>) {
+    bus.subscribe_with("word_cache", move |event| match event {
+        AppEvent::SyncCompleted {
+            kind: SyncKind::Assets | SyncKind::LearningData,
+            word_book_id,
+        } => cache.invalidate(word_book_id.as_deref()),
+        AppEvent::SyncProgress {
+            phase: SyncPhase::Pulled,
+            rows,
+            ..
+        } if rows > 0 => cache.invalidate(None),
+        _ => {}
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn row(book: &str, id: &str) -> WordRow {
+        WordRow {
+            id: id.to_string(),
+            word_book_id: book.to_string(),
+            word: id.to_string(),
+            phonetic: None,
+            definition: format!("{id} def"),
+        }
+    }
+
+    fn cache(capacity: usize) -> WordCache {
+        WordCache::new(WordCacheConfig {
+            capacity,
+            max_age_secs: 600,
+        })
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_row() {
+        let cache = cache(2);
+        cache.insert(row("b", "w1"));
+        cache.insert(row("b", "w2"));
+        assert!(cache.get("b", "w1").is_some());
+        cache.insert(row("b", "w3"));
+
+        assert!(cache.get("b", "w2").is_none());
+        assert!(cache.get("b", "w1").is_some());
+        assert!(cache.get("b", "w3").is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+        assert_eq!((stats.hits, stats.misses), (3, 1));
+    }
+
+    #[test]
+    fn invalidation_is_scoped_to_a_wordbook() {
+        let cache = cache(8);
+        cache.insert(row("a", "w1"));
+        cache.insert(row("b", "w1"));
+        cache.invalidate(Some("a"));
+        assert!(cache.get("a", "w1").is_none());
+        assert!(cache.get("b", "w1").is_some());
+
+        cache.invalidate(None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn shrinking_the_capacity_evicts_at_once() {
+        let cache = cache(4);
+        for id in ["w1", "w2", "w3"] {
+            cache.insert(row("b", id));
+        }
+        cache.configure(WordCacheConfig {
+            capacity: 1,
+            max_age_secs: 600,
+        });
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.get("b", "w3").is_some());
+    }
+
+    #[tokio::test]
+    async fn get_words_loads_misses_in_request_order() {
+        let pool = db::open_in_memory().await;
+        for id in ["w1", "w2"] {
+            sqlx::query(
+                r#"INSERT INTO "words" ("wordBookId", "id", "word", "definition", "updatedAt")
+                   VALUES ('b', ?, ?, '', 0)"#,
+            )
+            .bind(id)
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let cache = cache(8);
+        let ids: Vec<String> = ["w2", "gone", "w1"].map(String::from).to_vec();
+
+        let words = cache.get_words(&pool, "b", &ids).await.unwrap();
+        let order: Vec<&str> = words.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(order, vec!["w2", "w1"]);
+        assert_eq!(cache.stats().misses, 3);
+
+        cache.get_words(&pool, "b", &ids).await.unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 4));
+    }
+}
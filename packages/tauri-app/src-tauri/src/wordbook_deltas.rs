@@ -0,0 +1,197 @@
+//! Incremental updates of downloaded wordbooks.
+//!
+//! The server bumps a wordbook's content version on every word change and
+//! serves the changes after a given version from
+//! `GET /api/wordbooks/:id/changes?since=N`. The version a local copy is at
+//! is kept in `wordbook_versions`; an update asks for the changes after it,
+//! page by page, and applies each page to `words` in one transaction together
+//! with the new version. An interrupted update therefore resumes from the
+//! last applied page, and a book never ends up between two versions. A book
+//! with no recorded version is fetched from version 0, i.e. in full.
+//!
+//! Removed words also lose their `asset_refs` rows, so the next asset garbage
+//! collection can drop media nobody references any more.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri_plugin_http::reqwest::Client;
+
+use crate::sync::{SyncError, SyncServer};
+
+const PAGE_SIZE: i64 = 500;
+/// Separator between meanings in the local `definition` column.
+const MEANING_SEPARATOR: &str = "；";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangedWord {
+    id: String,
+    spelling: String,
+    phonetic: String,
+    meanings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeletedWord {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangesPage {
+    content_version: i64,
+    words: Vec<ChangedWord>,
+    deleted: Vec<DeletedWord>,
+    next_since: i64,
+    has_more: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope<T> {
+    data: T,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WordbookUpdate {
+    pub word_book_id: String,
+    pub from_version: i64,
+    pub to_version: i64,
+    pub upserted: u32,
+    pub removed: u32,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Version the local copy of the book is at; 0 when never updated.
+pub async fn local_version(pool: &SqlitePool, word_book_id: &str) -> Result<i64, sqlx::Error> {
+    Ok(sqlx::query_scalar(
+        r#"SELECT "contentVersion" FROM "wordbook_versions" WHERE "wordBookId" = ?"#,
+    )
+    .bind(word_book_id)
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or(0))
+}
+
+async fn fetch_page(
+    client: &Client,
+    server: &SyncServer,
+    word_book_id: &str,
+    since: i64,
+) -> Result<ChangesPage, SyncError> {
+    let url = format!(
+        "{}/api/wordbooks/{word_book_id}/changes?since={since}&limit={PAGE_SIZE}",
+        server.base_url.trim_end_matches('/')
+    );
+    let bytes = client
+        .get(url)
+        .bearer_auth(&server.token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| SyncError::Http(e.to_string()))?
+        .bytes()
+        .await
+        .map_err(|e| SyncError::Http(e.to_string()))?;
+    serde_json::from_slice::<Envelope<ChangesPage>>(&bytes)
+        .map(|envelope| envelope.data)
+        .map_err(|e| SyncError::Decode(e.to_string()))
+}
+
+async fn apply_page(
+    pool: &SqlitePool,
+    word_book_id: &str,
+    page: &ChangesPage,
+    update: &mut WordbookUpdate,
+) -> Result<(), sqlx::Error> {
+    let now = now_ms();
+    let mut tx = pool.begin().await?;
+    for word in &page.words {
+        sqlx::query(
+            r#"INSERT INTO "words" ("wordBookId", "id", "word", "phonetic", "definition", "updatedAt")
+               VALUES (?, ?, ?, ?, ?, ?)
+               ON CONFLICT ("wordBookId", "id") DO UPDATE SET
+                   "word" = excluded."word",
+                   "phonetic" = excluded."phonetic",
+                   "definition" = excluded."definition",
+                   "updatedAt" = excluded."updatedAt""#,
+        )
+        .bind(word_book_id)
+        .bind(&word.id)
+        .bind(&word.spelling)
+        .bind(Some(word.phonetic.as_str()).filter(|p| !p.is_empty()))
+        .bind(word.meanings.join(MEANING_SEPARATOR))
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        update.upserted += 1;
+    }
+    for word in &page.deleted {
+        let removed = sqlx::query(r#"DELETE FROM "words" WHERE "wordBookId" = ? AND "id" = ?"#)
+            .bind(word_book_id)
+            .bind(&word.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        sqlx::query(r#"DELETE FROM "asset_refs" WHERE "wordBookId" = ? AND "wordId" = ?"#)
+            .bind(word_book_id)
+            .bind(&word.id)
+            .execute(&mut *tx)
+            .await?;
+        update.removed += removed as u32;
+    }
+    sqlx::query(
+        r#"INSERT INTO "wordbook_versions" ("wordBookId", "contentVersion", "updatedAt")
+           VALUES (?, ?, ?)
+           ON CONFLICT ("wordBookId") DO UPDATE
+           SET "contentVersion" = excluded."contentVersion", "updatedAt" = excluded."updatedAt""#,
+    )
+    .bind(word_book_id)
+    .bind(page.next_since)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Brings the local copy of the book up to the server's current version.
+pub async fn update_wordbook(
+    pool: &SqlitePool,
+    server: &SyncServer,
+    word_book_id: &str,
+) -> Result<WordbookUpdate, SyncError> {
+    let client = Client::new();
+    let from_version = local_version(pool, word_book_id).await?;
+    let mut update = WordbookUpdate {
+        word_book_id: word_book_id.to_string(),
+        from_version,
+        to_version: from_version,
+        ..Default::default()
+    };
+    loop {
+        let page = fetch_page(&client, server, word_book_id, update.to_version).await?;
+        if page.next_since < update.to_version {
+            return Err(SyncError::Decode(format!(
+                "server version {} is behind local version {}",
+                page.content_version, update.to_version
+            )));
+        }
+        if page.has_more && page.next_since == update.to_version {
+            return Err(SyncError::Decode("changes page made no progress".into()));
+        }
+        apply_page(pool, word_book_id, &page, &mut update).await?;
+        update.to_version = page.next_since;
+        if !page.has_more {
+            return Ok(update);
+        }
+    }
+}